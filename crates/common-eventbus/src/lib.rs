@@ -78,6 +78,26 @@ impl Event {
         )
     }
 
+    pub fn customer_billing_email_updated(customer_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::CustomerBillingEmailUpdated(TenantEventDataDetails {
+                tenant_id,
+                entity_id: customer_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn credit_limit_exceeded(credit_limit_exceeded_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::CreditLimitExceeded(TenantEventDataDetails {
+                tenant_id,
+                entity_id: credit_limit_exceeded_id,
+            }),
+            None,
+        )
+    }
+
     pub fn organization_created(actor: Uuid, organization_id: Uuid) -> Self {
         Self::new(
             EventData::OrganizationCreated(EventDataDetails {
@@ -107,6 +127,60 @@ impl Event {
         )
     }
 
+    pub fn invoice_delivery_requested(invoice_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::InvoiceDeliveryRequested(TenantEventDataDetails {
+                tenant_id,
+                entity_id: invoice_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn invoice_paid(invoice_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::InvoicePaid(TenantEventDataDetails {
+                tenant_id,
+                entity_id: invoice_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn invoice_payment_recorded(
+        actor: Uuid,
+        invoice_payment_id: Uuid,
+        tenant_id: Uuid,
+    ) -> Self {
+        Self::new(
+            EventData::InvoicePaymentRecorded(TenantEventDataDetails {
+                tenant_id,
+                entity_id: invoice_payment_id,
+            }),
+            Some(actor),
+        )
+    }
+
+    pub fn invoice_payment_reminder(payment_reminder_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::InvoicePaymentReminder(TenantEventDataDetails {
+                tenant_id,
+                entity_id: payment_reminder_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn invoice_stuck(invoice_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::InvoiceStuck(TenantEventDataDetails {
+                tenant_id,
+                entity_id: invoice_id,
+            }),
+            None,
+        )
+    }
+
     pub fn plan_created_draft(actor: Uuid, plan_version_id: Uuid, tenant_id: Uuid) -> Self {
         Self::new(
             EventData::PlanCreatedDraft(TenantEventDataDetails {
@@ -201,6 +275,66 @@ impl Event {
         )
     }
 
+    pub fn subscription_term_updated(actor: Uuid, subscription_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::SubscriptionTermUpdated(TenantEventDataDetails {
+                tenant_id,
+                entity_id: subscription_id,
+            }),
+            Some(actor),
+        )
+    }
+
+    pub fn usage_alert_triggered(usage_alert_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::UsageAlertTriggered(TenantEventDataDetails {
+                tenant_id,
+                entity_id: usage_alert_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn seat_increase_requested(seat_increase_request_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::SeatIncreaseRequested(TenantEventDataDetails {
+                tenant_id,
+                entity_id: seat_increase_request_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn usage_alert_rule_firing(usage_alert_rule_event_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::UsageAlertRuleFiring(TenantEventDataDetails {
+                tenant_id,
+                entity_id: usage_alert_rule_event_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn usage_alert_rule_resolved(usage_alert_rule_event_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::UsageAlertRuleResolved(TenantEventDataDetails {
+                tenant_id,
+                entity_id: usage_alert_rule_event_id,
+            }),
+            None,
+        )
+    }
+
+    pub fn usage_cap_reached(usage_cap_reached_id: Uuid, tenant_id: Uuid) -> Self {
+        Self::new(
+            EventData::UsageCapReached(TenantEventDataDetails {
+                tenant_id,
+                entity_id: usage_cap_reached_id,
+            }),
+            None,
+        )
+    }
+
     pub fn user_created(actor: Option<Uuid>, user_id: Uuid) -> Self {
         Self::new(
             EventData::UserCreated(EventDataDetails { entity_id: user_id }),
@@ -239,9 +373,16 @@ pub enum EventData {
     BillableMetricCreated(TenantEventDataDetails),
     CustomerCreated(TenantEventDataDetails),
     CustomerPatched(TenantEventDataDetails),
+    CustomerBillingEmailUpdated(TenantEventDataDetails),
+    CreditLimitExceeded(TenantEventDataDetails),
     OrganizationCreated(EventDataDetails),
     InvoiceCreated(TenantEventDataDetails),
     InvoiceFinalized(TenantEventDataDetails),
+    InvoiceDeliveryRequested(TenantEventDataDetails),
+    InvoicePaid(TenantEventDataDetails),
+    InvoicePaymentRecorded(TenantEventDataDetails),
+    InvoicePaymentReminder(TenantEventDataDetails),
+    InvoiceStuck(TenantEventDataDetails),
     PlanCreatedDraft(TenantEventDataDetails),
     PlanPublishedVersion(TenantEventDataDetails),
     PlanDiscardedVersion(TenantEventDataDetails),
@@ -249,9 +390,15 @@ pub enum EventData {
     PriceComponentEdited(TenantEventDataDetails),
     PriceComponentRemoved(TenantEventDataDetails),
     ProductFamilyCreated(TenantEventDataDetails),
+    SeatIncreaseRequested(TenantEventDataDetails),
     SubscriptionCreated(TenantEventDataDetails),
     SubscriptionCanceled(TenantEventDataDetails),
+    SubscriptionTermUpdated(TenantEventDataDetails),
     TenantCreated(TenantEventDataDetails),
+    UsageAlertTriggered(TenantEventDataDetails),
+    UsageAlertRuleFiring(TenantEventDataDetails),
+    UsageAlertRuleResolved(TenantEventDataDetails),
+    UsageCapReached(TenantEventDataDetails),
     UserCreated(EventDataDetails),
     UserUpdated(EventDataWithMetadataDetails),
 }
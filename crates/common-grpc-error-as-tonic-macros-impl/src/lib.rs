@@ -3,8 +3,9 @@ extern crate proc_macro;
 use proc_macro::TokenStream;
 
 use quote::quote;
+use syn::parse::ParseStream;
 use syn::punctuated::Punctuated;
-use syn::{parse_macro_input, Attribute, Data, DeriveInput, Meta, Token};
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, LitStr, Meta, Token};
 
 #[proc_macro_derive(ErrorAsTonic, attributes(code))]
 pub fn error_as_tonic_derive(input: TokenStream) -> TokenStream {
@@ -29,6 +30,10 @@ fn error_as_tonic_impl(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStrea
     };
 
     let mut arms = proc_macro2::TokenStream::new();
+    let mut error_code_arms = proc_macro2::TokenStream::new();
+    let mut descriptors = proc_macro2::TokenStream::new();
+
+    let prefix = error_code_prefix(&name.to_string());
 
     for variant in variants {
         let ident = &variant.ident;
@@ -40,23 +45,50 @@ fn error_as_tonic_impl(ast: &DeriveInput) -> syn::Result<proc_macro2::TokenStrea
 
         let code = parse_code_attr(code_attr)?;
 
+        let error_code = format!("{}_{}", prefix, to_screaming_snake_case(&ident.to_string()));
+        let description = parse_error_attr(&variant.attrs);
+
         let arm = quote! {
             #name::#ident { .. } => #code,
         };
 
         arms.extend(arm);
+
+        error_code_arms.extend(quote! {
+            #name::#ident { .. } => #error_code,
+        });
+
+        descriptors.extend(quote! {
+            ::common_grpc_error_as_tonic_macros::ErrorCodeDescriptor {
+                code: #error_code,
+                grpc_code: #code,
+                description: #description,
+            },
+        });
     }
 
     let gen = quote! {
+        impl ::common_grpc_error_as_tonic_macros::ErrorCatalog for #name {
+            const ERROR_CODES: &'static [::common_grpc_error_as_tonic_macros::ErrorCodeDescriptor] = &[
+                #descriptors
+            ];
+
+            fn error_code(&self) -> &'static str {
+                match self {
+                    #error_code_arms
+                }
+            }
+        }
+
         impl From<#name> for ::tonic::Status {
             fn from(error: #name) -> ::tonic::Status {
                 let code = match &error {
                     #arms
                 };
 
-                let metadata = ::common_grpc_error_as_tonic_macros::error_to_metadata(&error);
+                let error_code = ::common_grpc_error_as_tonic_macros::ErrorCatalog::error_code(&error);
 
-                ::tonic::Status::with_metadata(code, error.to_string(), metadata)
+                ::common_grpc_error_as_tonic_macros::error_to_status(code, error_code, error)
             }
         }
     };
@@ -94,3 +126,49 @@ fn parse_code_attr(attr: &Attribute) -> syn::Result<proc_macro2::TokenStream> {
         )),
     }
 }
+
+/// The message template of the `#[error("...")]` attribute, if any
+fn parse_error_attr(attrs: &[Attribute]) -> String {
+    attrs
+        .iter()
+        .find(|attr| attr.path().is_ident("error"))
+        .and_then(|attr| {
+            attr.parse_args_with(|input: ParseStream| {
+                let lit: LitStr = input.parse()?;
+                // the format arguments
+                input.parse::<proc_macro2::TokenStream>()?;
+                Ok(lit.value())
+            })
+            .ok()
+        })
+        .unwrap_or_default()
+}
+
+/// CustomerApiError => CUSTOMER
+fn error_code_prefix(name: &str) -> String {
+    let name = name
+        .strip_suffix("ApiError")
+        .or_else(|| name.strip_suffix("Error"))
+        .unwrap_or(name);
+
+    to_screaming_snake_case(name)
+}
+
+fn to_screaming_snake_case(name: &str) -> String {
+    let mut res = String::new();
+    let chars: Vec<char> = name.chars().collect();
+
+    for (i, c) in chars.iter().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            let prev_lower = chars[i - 1].is_lowercase() || chars[i - 1].is_ascii_digit();
+            let next_lower = chars.get(i + 1).is_some_and(|n| n.is_lowercase());
+
+            if prev_lower || (chars[i - 1].is_uppercase() && next_lower) {
+                res.push('_');
+            }
+        }
+        res.push(c.to_ascii_uppercase());
+    }
+
+    res
+}
@@ -1,7 +1,42 @@
+use std::collections::HashMap;
+
 use tonic::metadata::MetadataMap;
+use tonic_types::{ErrorDetails, StatusExt};
 
 pub static HEADER_SOURCE_DETAILS: &str = "x-md-source-details-bin";
 
+/// Domain of the `ErrorInfo` detail attached to the errors
+pub static ERROR_DOMAIN: &str = "meteroid";
+
+/// A stable error code, returned in the `ErrorInfo` detail of the errors so that the clients don't parse the messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ErrorCodeDescriptor {
+    /// ex: CUSTOMER_NOT_FOUND, derived from the error type and variant names
+    pub code: &'static str,
+    pub grpc_code: tonic::Code,
+    /// the message template of the error
+    pub description: &'static str,
+}
+
+/// The error codes of an error type, implemented by the `ErrorAsTonic` derive
+pub trait ErrorCatalog {
+    const ERROR_CODES: &'static [ErrorCodeDescriptor];
+
+    fn error_code(&self) -> &'static str;
+}
+
+/// The status of an error, with its error code as details
+pub fn error_to_status<T>(grpc_code: tonic::Code, error_code: &str, error: T) -> tonic::Status
+where
+    T: std::error::Error,
+{
+    let metadata = error_to_metadata(&error);
+
+    let details = ErrorDetails::with_error_info(error_code, ERROR_DOMAIN, HashMap::new());
+
+    tonic::Status::with_error_details_and_metadata(grpc_code, error.to_string(), details, metadata)
+}
+
 #[derive(Debug, serde::Serialize, serde::Deserialize)]
 pub struct SourceDetails {
     pub msg: String,
@@ -0,0 +1,134 @@
+use std::fmt;
+use std::str::FromStr;
+
+use anyhow::anyhow;
+
+use crate::GrpcServiceMethod;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ScopeAccess {
+    Read,
+    Write,
+}
+
+/// The access of an api token to a resource, ex: invoices:read, metering:write.
+/// The write access includes the read access
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ApiTokenScope {
+    pub resource: String,
+    pub access: ScopeAccess,
+}
+
+impl FromStr for ApiTokenScope {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (resource, access) = s.split_once(':').ok_or(anyhow!(
+            "Invalid scope {}, expected <resource>:<read|write>",
+            s
+        ))?;
+
+        if resource.is_empty()
+            || !resource
+                .chars()
+                .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        {
+            return Err(anyhow!("Invalid scope resource: {}", resource));
+        }
+
+        let access = match access {
+            "read" => ScopeAccess::Read,
+            "write" => ScopeAccess::Write,
+            _ => return Err(anyhow!("Invalid scope access: {}", access)),
+        };
+
+        Ok(ApiTokenScope {
+            resource: resource.to_string(),
+            access,
+        })
+    }
+}
+
+impl fmt::Display for ApiTokenScope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let access = match self.access {
+            ScopeAccess::Read => "read",
+            ScopeAccess::Write => "write",
+        };
+        write!(f, "{}:{}", self.resource, access)
+    }
+}
+
+/// The resource of a service is the name of its package,
+/// ex: invoices for meteroid.api.invoices.v1.InvoicesService, metering for meteroid.metering.v1.EventsService
+pub fn scope_resource(sm: &GrpcServiceMethod) -> Option<&str> {
+    let mut parts = sm.service.rsplit('.');
+    parts.next()?; // the service
+    parts.next()?; // the version
+    parts.next()
+}
+
+/// Whether the scopes of a token allow the access to the resource. A token without scopes has access to all the resources
+pub fn scopes_allow(scopes: &[ApiTokenScope], resource: &str, write: bool) -> bool {
+    scopes.is_empty()
+        || scopes.iter().any(|scope| {
+            scope.resource == resource && (!write || scope.access == ScopeAccess::Write)
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sm(service: &str, method: &str) -> GrpcServiceMethod {
+        GrpcServiceMethod {
+            service: service.to_string(),
+            method: method.to_string(),
+        }
+    }
+
+    fn scopes(values: &[&str]) -> Vec<ApiTokenScope> {
+        values.iter().map(|s| s.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn test_parse_scope() {
+        let scope: ApiTokenScope = "invoices:read".parse().unwrap();
+        assert_eq!(scope.resource, "invoices");
+        assert_eq!(scope.access, ScopeAccess::Read);
+        assert_eq!(scope.to_string(), "invoices:read");
+
+        assert!("invoices".parse::<ApiTokenScope>().is_err());
+        assert!("invoices:admin".parse::<ApiTokenScope>().is_err());
+        assert!(":write".parse::<ApiTokenScope>().is_err());
+        assert!("Invoices:write".parse::<ApiTokenScope>().is_err());
+    }
+
+    #[test]
+    fn test_scope_resource() {
+        assert_eq!(
+            scope_resource(&sm(
+                "meteroid.api.invoices.v1.InvoicesService",
+                "ListInvoices"
+            )),
+            Some("invoices")
+        );
+        assert_eq!(
+            scope_resource(&sm("meteroid.metering.v1.EventsService", "Ingest")),
+            Some("metering")
+        );
+        assert_eq!(scope_resource(&sm("InvoicesService", "ListInvoices")), None);
+    }
+
+    #[test]
+    fn test_scopes_allow() {
+        assert!(scopes_allow(&[], "invoices", true));
+
+        let scopes = scopes(&["invoices:read", "metering:write"]);
+        assert!(scopes_allow(&scopes, "invoices", false));
+        assert!(!scopes_allow(&scopes, "invoices", true));
+        assert!(scopes_allow(&scopes, "metering", false));
+        assert!(scopes_allow(&scopes, "metering", true));
+        assert!(!scopes_allow(&scopes, "customers", false));
+    }
+}
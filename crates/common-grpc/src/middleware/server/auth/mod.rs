@@ -6,6 +6,7 @@ pub use admin_layer::AdminAuthService;
 use common_config::auth::InternalAuthConfig;
 
 mod admin_layer;
+pub mod api_token_scopes;
 pub mod api_token_validator;
 
 pub fn create_admin(config: &InternalAuthConfig) -> AdminAuthLayer {
@@ -16,6 +17,7 @@ pub trait RequestExt {
     fn actor(&self) -> Result<Uuid, Status>;
     fn tenant(&self) -> Result<Uuid, Status>;
     fn organization(&self) -> Result<Uuid, Status>;
+    fn tenant_role(&self) -> Result<TenantRole, Status>;
 }
 
 impl<T> RequestExt for tonic::Request<T> {
@@ -30,6 +32,10 @@ impl<T> RequestExt for tonic::Request<T> {
     fn organization(&self) -> Result<Uuid, Status> {
         extract_organization(self.extensions().get::<AuthorizedState>())
     }
+
+    fn tenant_role(&self) -> Result<TenantRole, Status> {
+        extract_tenant_role(self.extensions().get::<TenantRole>())
+    }
 }
 
 impl<T> RequestExt for http::Request<T> {
@@ -44,6 +50,10 @@ impl<T> RequestExt for http::Request<T> {
     fn organization(&self) -> Result<Uuid, Status> {
         extract_organization(self.extensions().get::<AuthorizedState>())
     }
+
+    fn tenant_role(&self) -> Result<TenantRole, Status> {
+        extract_tenant_role(self.extensions().get::<TenantRole>())
+    }
 }
 
 pub fn extract_actor(maybe_auth: Option<&AuthorizedState>) -> Result<Uuid, Status> {
@@ -86,6 +96,12 @@ pub fn extract_organization(maybe_auth: Option<&AuthorizedState>) -> Result<Uuid
     Ok(res)
 }
 
+pub fn extract_tenant_role(maybe_role: Option<&TenantRole>) -> Result<TenantRole, Status> {
+    maybe_role.copied().ok_or(Status::unauthenticated(
+        "Missing tenant role in request extensions",
+    ))
+}
+
 pub enum AuthenticatedState {
     ApiKey {
         id: Uuid,
@@ -112,3 +128,11 @@ pub enum AuthorizedState {
         user_id: Uuid,
     },
 }
+
+/// The role of the caller on the tenant, in the extensions of the requests with a tenant authorized state
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TenantRole {
+    Admin,
+    Billing,
+    ReadOnly,
+}
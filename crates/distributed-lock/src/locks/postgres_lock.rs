@@ -59,7 +59,17 @@ pub enum LockKey {
     InvoicingIssue,
     InvoicingFinalize,
     InvoicingPrice,
+    InvoicingUsageThreshold,
+    InvoicingWatchdog,
+    InvoicingPaymentReminders,
+    InvoicingApprovalEscalation,
     CurrencyRates,
+    UsageAlerts,
+    SubscriptionTrials,
+    UsageCaps,
+    ApiTokenExpiry,
+    UsageAlertRules,
+    AccountingExport,
 }
 
 impl LockKey {
@@ -70,7 +80,17 @@ impl LockKey {
             LockKey::InvoicingIssue => 1002,
             LockKey::InvoicingFinalize => 1003,
             LockKey::InvoicingPrice => 1004,
+            LockKey::InvoicingUsageThreshold => 1005,
+            LockKey::InvoicingWatchdog => 1006,
+            LockKey::InvoicingPaymentReminders => 1007,
+            LockKey::InvoicingApprovalEscalation => 1008,
             LockKey::CurrencyRates => 2000,
+            LockKey::UsageAlerts => 2001,
+            LockKey::SubscriptionTrials => 2002,
+            LockKey::UsageCaps => 2003,
+            LockKey::ApiTokenExpiry => 2004,
+            LockKey::UsageAlertRules => 2005,
+            LockKey::AccountingExport => 2006,
         }
     }
 }
@@ -19,6 +19,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
         .compile_protos_with_config(
             config,
             &[
+                "proto/diagnostics.proto",
                 "proto/events.proto",
                 "proto/meters.proto",
                 "proto/queries.proto",
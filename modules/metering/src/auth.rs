@@ -2,12 +2,17 @@ use common_grpc::middleware::common::auth::API_KEY_HEADER;
 use common_grpc::GrpcServiceMethod;
 
 use cached::proc_macro::cached;
+use chrono::{DateTime, NaiveDateTime};
 use common_grpc::middleware::client::LayeredClientService;
+use common_grpc::middleware::server::auth::api_token_scopes::{
+    scope_resource, scopes_allow, ApiTokenScope,
+};
 use common_grpc::middleware::server::auth::api_token_validator::ApiTokenValidator;
 use futures::TryFutureExt;
 use hyper::{HeaderMap, Request, Response, StatusCode};
 use std::future::Future;
 use std::pin::Pin;
+use std::str::FromStr;
 use std::sync::Arc;
 use std::task::{Context, Poll};
 use tonic::body::{empty_body, BoxBody};
@@ -94,14 +99,14 @@ where
         let clone = self.inner.clone();
         let mut inner = std::mem::replace(&mut self.inner, clone);
 
-        let _sm = GrpcServiceMethod::extract(request.uri());
+        let sm = GrpcServiceMethod::extract(request.uri());
 
         let metadata = request.headers().clone();
         let mut internal_client = self.internal_client.clone();
 
         let future = async move {
             let authenticated_state = if metadata.contains_key(API_KEY_HEADER) {
-                validate_api_key(&metadata, &mut internal_client)
+                validate_api_key(&metadata, &mut internal_client, &sm)
                     .await
                     .map_err(|e| BoxError::from(e) as BoxError)
             } else {
@@ -143,6 +148,17 @@ where
     }
 }
 
+#[derive(Clone)]
+struct ResolvedApiKey {
+    organization_id: Uuid,
+    tenant_id: Uuid,
+    scopes: Vec<ApiTokenScope>,
+    expires_at: Option<NaiveDateTime>,
+}
+
+// the calls that read the events, the others writing them
+const READ_METHOD_PREFIXES: [&str; 2] = ["Query", "Get"];
+
 #[cached(
     result = true,
     size = 100,
@@ -154,7 +170,7 @@ async fn validate_api_token_by_id_cached(
     internal_client: &mut InternalServiceClient<LayeredClientService>,
     validator: &ApiTokenValidator,
     api_key_id: &Uuid,
-) -> Result<(Uuid, Uuid), Status> {
+) -> Result<ResolvedApiKey, Status> {
     let res = internal_client
         .clone()
         .resolve_api_key(ResolveApiKeyRequest {
@@ -180,12 +196,34 @@ async fn validate_api_token_by_id_cached(
     let organization_uuid = Uuid::parse_str(&inner.tenant_id)
         .map_err(|_| Status::internal("failed to parse tenant id"))?;
 
-    Ok((organization_uuid, tenant_uuid))
+    let scopes = inner
+        .scopes
+        .iter()
+        .map(|scope| ApiTokenScope::from_str(scope))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Status::permission_denied("Invalid api key scopes"))?;
+
+    let expires_at = inner
+        .expires_at
+        .map(|t| {
+            DateTime::from_timestamp(t.seconds, t.nanos as u32)
+                .map(|d| d.naive_utc())
+                .ok_or(Status::internal("failed to parse api key expiry"))
+        })
+        .transpose()?;
+
+    Ok(ResolvedApiKey {
+        organization_id: organization_uuid,
+        tenant_id: tenant_uuid,
+        scopes,
+        expires_at,
+    })
 }
 
 pub async fn validate_api_key(
     header_map: &HeaderMap,
     internal_client: &mut InternalServiceClient<LayeredClientService>,
+    sm: &GrpcServiceMethod,
 ) -> Result<AuthenticatedState, Status> {
     let api_key = header_map
         .get(API_KEY_HEADER)
@@ -200,12 +238,30 @@ pub async fn validate_api_key(
         Status::permission_denied("Invalid API key format. Failed to extract identifier")
     })?;
 
-    let (organization_id, tenant_id) =
-        validate_api_token_by_id_cached(internal_client, &validator, &id).await?;
+    let resolved = validate_api_token_by_id_cached(internal_client, &validator, &id).await?;
+
+    // the key may have expired since it was cached
+    if resolved
+        .expires_at
+        .is_some_and(|expires_at| expires_at <= chrono::Utc::now().naive_utc())
+    {
+        return Err(Status::permission_denied("API key expired"));
+    }
+
+    let write = !READ_METHOD_PREFIXES
+        .iter()
+        .any(|prefix| sm.method.starts_with(prefix));
+    let resource = scope_resource(sm).unwrap_or_default();
+
+    if !scopes_allow(&resolved.scopes, resource, write) {
+        return Err(Status::permission_denied(
+            "The scopes of this API key do not allow this call",
+        ));
+    }
 
     Ok(AuthenticatedState::ApiKey {
         id,
-        tenant_id,
-        organization_id,
+        tenant_id: resolved.tenant_id,
+        organization_id: resolved.organization_id,
     })
 }
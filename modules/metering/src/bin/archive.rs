@@ -0,0 +1,76 @@
+/*
+
+Archives the expired raw events to the object storage, or restores the archived events of a tenant
+so that the meter migrations re-aggregate them.
+Usage:
+  metering-archive archive
+  metering-archive restore <tenant_id> <from> <to>   (RFC 3339 timestamps)
+
+*/
+
+use std::env;
+
+use chrono::{DateTime, Utc};
+use envconfig::Envconfig;
+
+use common_logging::init::init_regular_logging;
+use metering::config::Config;
+use metering::connectors::clickhouse::ClickhouseConnector;
+use metering::connectors::Connector;
+
+const USAGE: &str =
+    "Usage: metering-archive archive | metering-archive restore <tenant_id> <from> <to>";
+
+fn parse_timestamp(value: Option<String>) -> Result<DateTime<Utc>, Box<dyn std::error::Error>> {
+    let value = value.ok_or(USAGE)?;
+
+    Ok(DateTime::parse_from_rfc3339(&value)
+        .map_err(|e| format!("Invalid timestamp {}: {}", value, e))?
+        .with_timezone(&Utc))
+}
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    dotenvy::dotenv().ok();
+
+    init_regular_logging();
+
+    let config = Config::init_from_env()?;
+
+    let mut args = env::args().skip(1);
+
+    let command = args.next().ok_or(USAGE)?;
+
+    let connector = ClickhouseConnector::init(&config.clickhouse, &config.kafka, vec![]).await?;
+
+    match command.as_str() {
+        "archive" => {
+            log::info!("Archiving the expired events");
+
+            connector.archive_expired_events().await?;
+        }
+        "restore" => {
+            let tenant_id = args.next().ok_or(USAGE)?;
+            let from = parse_timestamp(args.next())?;
+            let to = parse_timestamp(args.next())?;
+
+            if from >= to {
+                return Err("The restored period must end after its start".into());
+            }
+
+            log::info!(
+                "Restoring the archived events of tenant {} from {} to {}",
+                tenant_id,
+                from,
+                to
+            );
+
+            connector
+                .restore_archived_events(&tenant_id, from, to)
+                .await?;
+        }
+        _ => return Err(USAGE.into()),
+    }
+
+    Ok(())
+}
@@ -2,10 +2,17 @@ use cached::once_cell::sync::Lazy;
 
 use quick_cache::sync::Cache;
 use std::sync::Arc;
+use std::time::Instant;
+
+use crate::ingest::schema::TenantEventSchemas;
 
 // type IdentifierCache = Lazy<RwLock<SizedCache<(String, String), String>>>;
 // pub static CUSTOMER_ID_CACHE: IdentifierCache = Lazy::new(|| RwLock::new(SizedCache::with_size(10000)));
 type IdentifierCache = Lazy<Arc<Cache<(String, String), String>>>;
 pub static CUSTOMER_ID_CACHE: IdentifierCache = Lazy::new(|| Arc::new(Cache::new(10000)));
 
+// event schemas by tenant, with the time they were fetched at
+type EventSchemaCache = Lazy<Arc<Cache<String, (Instant, Arc<TenantEventSchemas>)>>>;
+pub static EVENT_SCHEMA_CACHE: EventSchemaCache = Lazy::new(|| Arc::new(Cache::new(10000)));
+
 // TODO add an optional redis on top
@@ -1,6 +1,8 @@
 use common_config::auth::InternalAuthConfig;
 use common_config::common::CommonConfig;
 use envconfig::Envconfig;
+
+use crate::ingest::limits::TruncationPolicy;
 use std::net::SocketAddr;
 
 #[cfg(feature = "kafka")]
@@ -29,6 +31,62 @@ pub struct Config {
 
     #[envconfig(nested)]
     pub internal_auth: InternalAuthConfig,
+
+    #[envconfig(nested)]
+    pub ingest_limits: IngestLimitsConfig,
+
+    #[envconfig(nested)]
+    pub ingest_dedup: IngestDedupConfig,
+
+    #[envconfig(nested)]
+    pub ingest_shedding: IngestSheddingConfig,
+}
+
+#[derive(Envconfig, Clone)]
+pub struct IngestLimitsConfig {
+    #[envconfig(from = "INGEST_MAX_BATCH_SIZE", default = "500")]
+    pub max_batch_size: usize,
+
+    #[envconfig(from = "INGEST_MAX_PROPERTIES_PER_EVENT", default = "100")]
+    pub max_properties_per_event: usize,
+
+    #[envconfig(from = "INGEST_MAX_PROPERTY_KEY_LENGTH", default = "128")]
+    pub max_property_key_length: usize,
+
+    #[envconfig(from = "INGEST_MAX_PROPERTY_VALUE_LENGTH", default = "1024")]
+    pub max_property_value_length: usize,
+
+    #[envconfig(from = "INGEST_VALUE_TRUNCATION", default = "reject")]
+    pub value_truncation: TruncationPolicy, // reject, truncate
+}
+
+#[derive(Envconfig, Clone)]
+pub struct IngestDedupConfig {
+    // events with an id already ingested by the instance within the window are dropped. 0 disables the deduplication
+    #[envconfig(from = "INGEST_DEDUP_WINDOW_SECONDS", default = "3600")]
+    pub window_seconds: u64,
+
+    // event ids tracked per instance, the oldest are forgotten beyond
+    #[envconfig(from = "INGEST_DEDUP_MAX_TRACKED_EVENTS", default = "500000")]
+    pub max_tracked_events: usize,
+}
+
+#[derive(Envconfig, Clone)]
+pub struct IngestSheddingConfig {
+    // the ingestion is refused while the p90 latency of the inserts into the events table is above. Unset is not checked
+    #[envconfig(from = "INGEST_SHEDDING_MAX_INSERT_LATENCY_MS")]
+    pub max_insert_latency_ms: Option<u64>,
+
+    // the ingestion is refused while the lag of the clickhouse consumers on the events topic is above. Unset is not checked
+    #[envconfig(from = "INGEST_SHEDDING_MAX_KAFKA_LAG")]
+    pub max_kafka_lag: Option<i64>,
+
+    // delay suggested to the clients of the refused batches, scaled by the overload
+    #[envconfig(from = "INGEST_SHEDDING_RETRY_AFTER_SECONDS", default = "5")]
+    pub retry_after_seconds: u64,
+
+    #[envconfig(from = "INGEST_SHEDDING_PROBE_INTERVAL_SECONDS", default = "10")]
+    pub probe_interval_seconds: u64,
 }
 
 #[cfg(feature = "kafka")]
@@ -45,6 +103,10 @@ pub struct KafkaConfig {
     #[envconfig(from = "KAFKA_TOPIC", default = "meteroid-events-raw")]
     pub kafka_topic: String,
 
+    // receives the events rejected at ingestion, with the failure reasons. Unset drops them
+    #[envconfig(from = "KAFKA_DEAD_LETTER_TOPIC")]
+    pub kafka_dead_letter_topic: Option<String>,
+
     #[envconfig(from = "KAFKA_PRODUCER_LINGER_MS", default = "20")]
     pub kafka_producer_linger_ms: u32, // Maximum time between producer batches during low traffic
 
@@ -92,4 +154,18 @@ pub struct ClickhouseConfig {
     #[envconfig(from = "CLICKHOUSE_PASSWORD", default = "default")]
     pub password: String,
     // TODO TLS
+
+    // object storage receiving the expired events of the tenants with archival, ex: https://bucket.s3.amazonaws.com/events
+    #[envconfig(from = "CLICKHOUSE_ARCHIVE_S3_URL")]
+    pub archive_s3_url: Option<String>,
+
+    #[envconfig(from = "CLICKHOUSE_ARCHIVE_S3_ACCESS_KEY_ID")]
+    pub archive_s3_access_key_id: Option<String>,
+
+    #[envconfig(from = "CLICKHOUSE_ARCHIVE_S3_SECRET_ACCESS_KEY")]
+    pub archive_s3_secret_access_key: Option<String>,
+
+    // tenants without retention policy get an archival policy of this many days once they have older events. Unset keeps their events
+    #[envconfig(from = "CLICKHOUSE_ARCHIVE_AFTER_DAYS")]
+    pub archive_after_days: Option<u32>,
 }
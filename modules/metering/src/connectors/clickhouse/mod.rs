@@ -1,27 +1,46 @@
 use crate::config::{ClickhouseConfig, KafkaConfig};
 use crate::connectors::errors::ConnectorError;
 use crate::connectors::Connector;
-use crate::domain::{Meter, QueryMeterParams, Usage};
+use crate::domain::{
+    IngestionHealth, InsertError, Meter, MeterViewsDrift, QueryMeterParams, RetentionPolicy,
+    TenantIngestion, Usage,
+};
+use crate::ingest::domain::{
+    BackfillSession, BackfillStatus, BackfilledUsage, MergedBackfill, ProcessedEvent,
+};
 use async_trait::async_trait;
 use chrono::{DateTime, Utc};
-use clickhouse_rs::{Options, Pool};
-use std::collections::HashMap;
+use clickhouse_rs::{ClientHandle, Options, Pool};
+use std::collections::{HashMap, HashSet};
 
 use error_stack::{Result, ResultExt};
 
 use std::str::FromStr;
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use uuid::Uuid;
 
 pub mod extensions;
 pub mod sql;
 
 use crate::connectors::clickhouse::extensions::ConnectorClickhouseExtension;
+use crate::connectors::clickhouse::sql::create_meter::MeterViewKind;
+use crate::connectors::clickhouse::sql::query_meter::RollupRange;
+use crate::connectors::clickhouse::sql::retention::ArchiveStorage;
 use chrono_tz::Tz;
 
+/// The consumer group of the kafka engine table
+pub const KAFKA_CONSUMER_GROUP: &str = "clickhouse";
+
 #[derive(Clone)]
 pub struct ClickhouseConnector {
     pool: Pool,
     extensions: Vec<Arc<dyn ConnectorClickhouseExtension + Send + Sync>>,
+    archive_storage: Option<ArchiveStorage>,
+    // the retention given to the tenants without policy, with archival
+    archive_after_days: Option<u32>,
+    // the daily rollups known to exist, the meters registered before the rollups are queried from their minute view until migrated
+    rollups: Arc<Mutex<HashSet<String>>>,
 }
 
 impl ClickhouseConnector {
@@ -47,10 +66,15 @@ impl ClickhouseConnector {
         let kafka_table_ddl = sql::init::create_kafka_event_table_sql(
             kafka_config.kafka_internal_addr.clone(),
             kafka_config.kafka_topic.clone(),
-            "clickhouse".to_string(),
+            KAFKA_CONSUMER_GROUP.to_string(),
             "JSONEachRow".to_string(),
         );
         let kafka_mv_ddl = sql::init::create_kafka_mv_sql();
+        let backfill_events_ddl = sql::init::create_backfill_events_table_sql();
+        let backfill_sessions_ddl = sql::init::create_backfill_sessions_table_sql();
+        let retention_ddl = sql::init::create_retention_table_sql();
+        let retention_dictionary_ddl = sql::init::create_retention_dictionary_sql();
+        let restored_events_ddl = sql::init::create_restored_events_table_sql();
 
         let mut client = pool.get_handle().await.map_err(|err| {
             ConnectorError::ConnectionError(format!("Failed to connect to Clickhouse : {}", err))
@@ -74,12 +98,83 @@ impl ClickhouseConnector {
             .change_context(ConnectorError::InitError(
                 "Could not create kafka MV".to_string(),
             ))?;
+        client
+            .execute(backfill_events_ddl)
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not create backfill events table".to_string(),
+            ))?;
+        client
+            .execute(backfill_sessions_ddl)
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not create backfill sessions table".to_string(),
+            ))?;
+
+        client
+            .execute(retention_ddl)
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not create retention table".to_string(),
+            ))?;
+        client
+            .execute(retention_dictionary_ddl)
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not create retention dictionary".to_string(),
+            ))?;
+        client
+            .execute(restored_events_ddl)
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not create restored events table".to_string(),
+            ))?;
+
+        let ttl_block = client
+            .query(sql::init::events_table_has_ttl_sql())
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::InitError(
+                "Could not read the event table TTL".to_string(),
+            ))?;
+        let has_ttl: u64 = match ttl_block.rows().next() {
+            Some(row) => row.get("count").change_context(ConnectorError::InitError(
+                "Could not read the event table TTL".to_string(),
+            ))?,
+            None => 0,
+        };
+        if has_ttl == 0 {
+            client
+                .execute(sql::init::modify_events_table_ttl_sql())
+                .await
+                .change_context(ConnectorError::InitError(
+                    "Could not set the event table TTL".to_string(),
+                ))?;
+        }
 
         for ext in &extensions {
             ext.init(&pool).await?;
         }
 
-        Ok(ClickhouseConnector { pool, extensions })
+        let archive_storage = clickhouse_config
+            .archive_s3_url
+            .clone()
+            .map(|url| ArchiveStorage {
+                url,
+                access_key_id: clickhouse_config.archive_s3_access_key_id.clone(),
+                secret_access_key: clickhouse_config.archive_s3_secret_access_key.clone(),
+            });
+
+        Ok(ClickhouseConnector {
+            pool,
+            extensions,
+            archive_after_days: archive_storage
+                .as_ref()
+                .and(clickhouse_config.archive_after_days)
+                .filter(|days| *days > 0),
+            archive_storage,
+            rollups: Arc::new(Mutex::new(HashSet::new())),
+        })
     }
 
     pub async fn execute_ddl(&self, ddl: String) -> Result<(), ConnectorError> {
@@ -97,6 +192,290 @@ impl ClickhouseConnector {
         Ok(())
     }
 
+    fn archive_storage(&self) -> Result<&ArchiveStorage, ConnectorError> {
+        self.archive_storage.as_ref().ok_or_else(|| {
+            ConnectorError::ConfigurationError("No archive storage configured".to_string()).into()
+        })
+    }
+
+    async fn query_retention_policies(
+        &self,
+        sql: String,
+    ) -> Result<Vec<RetentionPolicy>, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql)
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        block
+            .rows()
+            .map(|row| -> Result<RetentionPolicy, ConnectorError> {
+                let archived_until: Option<DateTime<Tz>> = row
+                    .get("archived_until")
+                    .change_context(ConnectorError::QueryError)?;
+
+                Ok(RetentionPolicy {
+                    tenant_id: row
+                        .get("tenant_id")
+                        .change_context(ConnectorError::QueryError)?,
+                    retention_days: row
+                        .get("retention_days")
+                        .change_context(ConnectorError::QueryError)?,
+                    archive: row
+                        .get::<u8, _>("archive")
+                        .change_context(ConnectorError::QueryError)?
+                        == 1,
+                    archived_until: archived_until.map(|until| until.with_timezone(&Utc)),
+                })
+            })
+            .collect()
+    }
+
+    /// Gives the default archival policy to the tenants without policy that have events older than its retention.
+    /// Their archival progress is then kept like the one of any policy
+    async fn apply_default_archival(&self, now: DateTime<Utc>) -> Result<(), ConnectorError> {
+        let Some(retention_days) = self.archive_after_days else {
+            return Ok(());
+        };
+
+        let default_policy = |tenant_id: String| RetentionPolicy {
+            tenant_id,
+            retention_days,
+            archive: true,
+            archived_until: None,
+        };
+
+        let Some(until) = default_policy(String::new()).expires_before(now) else {
+            return Ok(());
+        };
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::retention::list_tenants_without_policy_sql(&until))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let tenant_ids = block
+            .rows()
+            .map(|row| row.get::<String, _>("tenant_id"))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .change_context(ConnectorError::QueryError)?;
+
+        for tenant_id in tenant_ids {
+            log::info!(
+                "Applying the default archival policy to tenant {}",
+                tenant_id
+            );
+
+            client
+                .execute(sql::retention::upsert_policy_sql(&default_policy(
+                    tenant_id,
+                )))
+                .await
+                .change_context(ConnectorError::WriteError)?;
+        }
+
+        Ok(())
+    }
+
+    /// Exports then deletes the events expired since the previous archival of the tenant
+    async fn archive_tenant_events(
+        &self,
+        mut policy: RetentionPolicy,
+        now: DateTime<Utc>,
+    ) -> Result<(), ConnectorError> {
+        let Some(until) = policy.expires_before(now) else {
+            return Ok(());
+        };
+        if policy
+            .archived_until
+            .is_some_and(|archived| archived >= until)
+        {
+            return Ok(());
+        }
+
+        let storage = self.archive_storage()?;
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        client
+            .execute(sql::retention::archive_events_sql(
+                storage,
+                &policy.tenant_id,
+                policy.archived_until.as_ref(),
+                &until,
+            ))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        client
+            .execute(sql::retention::delete_archived_events_sql(
+                &policy.tenant_id,
+                &until,
+            ))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        policy.archived_until = Some(until);
+
+        client
+            .execute(sql::retention::upsert_policy_sql(&policy))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(())
+    }
+
+    async fn has_rollup(
+        &self,
+        client: &mut ClientHandle,
+        namespace: &str,
+        meter_slug: &str,
+    ) -> Result<bool, ConnectorError> {
+        let rollup_name = sql::get_meter_rollup_name(namespace, meter_slug);
+
+        if self
+            .rollups
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .contains(&rollup_name)
+        {
+            return Ok(true);
+        }
+
+        let block = client
+            .query(sql::query_meter::rollup_exists_sql(namespace, meter_slug))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let count: u64 = match block.rows().next() {
+            Some(row) => row
+                .get("count")
+                .change_context(ConnectorError::QueryError)?,
+            None => 0,
+        };
+
+        if count > 0 {
+            self.rollups
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .insert(rollup_name);
+        }
+
+        Ok(count > 0)
+    }
+
+    /// Drops the versions left over by an interrupted migration of the view, and returns whether the view exists
+    async fn drop_interrupted_migrations(
+        client: &mut ClientHandle,
+        meter: &Meter,
+        kind: MeterViewKind,
+    ) -> Result<bool, ConnectorError> {
+        use sql::migrate_meter::*;
+
+        let block = client
+            .query(list_meter_view_versions_sql(
+                kind,
+                &meter.namespace,
+                &meter.meter_slug,
+            ))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let views = block
+            .rows()
+            .map(|row| {
+                row.get::<String, _>("name")
+                    .map(|name| format!("{}.{}", sql::DATABASE, name))
+            })
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .change_context(ConnectorError::QueryError)?;
+
+        let current_view = kind.view_name(&meter.namespace, &meter.meter_slug);
+
+        for view in views.iter().filter(|view| **view != current_view) {
+            client
+                .execute(drop_meter_view_sql(view))
+                .await
+                .change_context(ConnectorError::MigrateError)?;
+        }
+
+        Ok(views.contains(&current_view))
+    }
+
+    async fn migrate_meter_view(
+        client: &mut ClientHandle,
+        meter: &Meter,
+        kind: MeterViewKind,
+    ) -> Result<(), ConnectorError> {
+        use sql::migrate_meter::*;
+
+        let version = Utc::now().timestamp_millis();
+
+        client
+            .execute(create_meter_view_version_sql(meter, kind, version))
+            .await
+            .change_context(ConnectorError::MigrateError)?;
+
+        // the new version captures the events inserted from now on
+        let cutoff = Utc::now();
+
+        let swapped = async {
+            for events_table_name in [
+                sql::init::get_events_table_name(),
+                sql::init::get_restored_events_table_name(),
+            ] {
+                client
+                    .execute(backfill_meter_view_version_sql(
+                        meter,
+                        kind,
+                        version,
+                        &events_table_name,
+                        &cutoff,
+                    ))
+                    .await?;
+            }
+            client
+                .execute(exchange_meter_view_version_sql(meter, kind, version))
+                .await
+        }
+        .await;
+
+        // the new version on failure, else the previous definition that it replaced
+        let dropped = client
+            .execute(drop_meter_view_sql(&get_meter_view_version_name(
+                kind,
+                &meter.namespace,
+                &meter.meter_slug,
+                version,
+            )))
+            .await;
+
+        swapped.change_context(ConnectorError::MigrateError)?;
+        dropped.change_context(ConnectorError::MigrateError)?;
+
+        Ok(())
+    }
+
     fn match_extension(
         &self,
         params: &QueryMeterParams,
@@ -118,14 +497,51 @@ impl Connector for ClickhouseConnector {
             .await
             .change_context(ConnectorError::ResourceUnavailable)?;
 
-        let ddl = sql::create_meter::create_meter_view(
-            meter, true, // TODO consider making this configurable
-        );
+        for kind in MeterViewKind::ALL {
+            let ddl = sql::create_meter::create_meter_view(
+                &meter, kind, true, // TODO consider making this configurable
+            );
 
-        client
-            .execute(ddl)
+            client
+                .execute(ddl)
+                .await
+                .change_context(ConnectorError::RegisterError)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn migrate_meter(&self, meter: Meter) -> Result<(), ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
             .await
-            .change_context(ConnectorError::RegisterError)?;
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let mut existing = vec![];
+        for kind in MeterViewKind::ALL {
+            if Self::drop_interrupted_migrations(&mut client, &meter, kind).await? {
+                existing.push(kind);
+            }
+        }
+
+        if !existing.contains(&MeterViewKind::Minute) {
+            log::info!("No view for meter {}, registering it", meter.meter_slug);
+            return self.register_meter(meter).await;
+        }
+
+        for kind in MeterViewKind::ALL {
+            if existing.contains(&kind) {
+                Self::migrate_meter_view(&mut client, &meter, kind).await?;
+            } else {
+                // a meter registered before the daily rollups
+                client
+                    .execute(sql::create_meter::create_meter_view(&meter, kind, true))
+                    .await
+                    .change_context(ConnectorError::MigrateError)?;
+            }
+        }
 
         Ok(())
     }
@@ -143,8 +559,22 @@ impl Connector for ClickhouseConnector {
             .and_then(|ext| ext.build_query(&params))
         {
             Some(ext) => ext,
-            None => sql::query_meter::query_meter_view_sql(params.clone())
-                .map_err(ConnectorError::InvalidQuery)?,
+            None => {
+                // the closed days from the daily rollup, the current day from the minute view
+                let rollup = match RollupRange::of_query(&params, Utc::now()) {
+                    Some(range)
+                        if self
+                            .has_rollup(&mut client, &params.namespace, &params.meter_slug)
+                            .await? =>
+                    {
+                        Some(range)
+                    }
+                    _ => None,
+                };
+
+                sql::query_meter::query_meter_view_sql(params.clone(), rollup)
+                    .map_err(ConnectorError::InvalidQuery)?
+            }
         };
 
         let block = client
@@ -205,4 +635,487 @@ impl Connector for ClickhouseConnector {
 
         parsed
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn save_backfill(&self, session: &BackfillSession) -> Result<(), ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        client
+            .execute(sql::backfill::upsert_session_sql(session))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<Option<BackfillSession>, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::backfill::select_session_sql(tenant_id, backfill_id))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let session = block
+            .rows()
+            .next()
+            .map(|row| -> Result<BackfillSession, ConnectorError> {
+                let status: String = row
+                    .get("status")
+                    .change_context(ConnectorError::QueryError)?;
+                let created_at: DateTime<Tz> = row
+                    .get("created_at")
+                    .change_context(ConnectorError::QueryError)?;
+                let updated_at: DateTime<Tz> = row
+                    .get("updated_at")
+                    .change_context(ConnectorError::QueryError)?;
+
+                Ok(BackfillSession {
+                    id: *backfill_id,
+                    tenant_id: tenant_id.to_string(),
+                    status: BackfillStatus::parse(&status)
+                        .ok_or(ConnectorError::QueryError)
+                        .attach_printable_lazy(|| format!("Unknown backfill status {}", status))?,
+                    max_events_per_second: row
+                        .get("max_events_per_second")
+                        .change_context(ConnectorError::QueryError)?,
+                    events_staged: row
+                        .get("events_staged")
+                        .change_context(ConnectorError::QueryError)?,
+                    events_failed: row
+                        .get("events_failed")
+                        .change_context(ConnectorError::QueryError)?,
+                    events_merged: row
+                        .get("events_merged")
+                        .change_context(ConnectorError::QueryError)?,
+                    last_batch_sequence: row
+                        .get("last_batch_sequence")
+                        .change_context(ConnectorError::QueryError)?,
+                    created_at: created_at.with_timezone(&Utc),
+                    updated_at: updated_at.with_timezone(&Utc),
+                })
+            })
+            .transpose()?;
+
+        Ok(session)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn stage_backfill_events(
+        &self,
+        backfill_id: &Uuid,
+        events: Vec<ProcessedEvent>,
+    ) -> Result<(), ConnectorError> {
+        let Some(insert) = sql::backfill::insert_staged_events_sql(backfill_id, &events) else {
+            return Ok(());
+        };
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        client
+            .execute(insert)
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn count_staged_backfill_events(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+        event_ids: &[String],
+    ) -> Result<u64, ConnectorError> {
+        let Some(sql) = sql::backfill::count_staged_events_sql(tenant_id, backfill_id, event_ids)
+        else {
+            return Ok(0);
+        };
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql)
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        match block.rows().next() {
+            Some(row) => row.get("count").change_context(ConnectorError::QueryError),
+            None => Ok(0),
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn merge_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<MergedBackfill, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::backfill::count_mergeable_events_sql(
+                tenant_id,
+                backfill_id,
+            ))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let count: u64 = match block.rows().next() {
+            Some(row) => row
+                .get("count")
+                .change_context(ConnectorError::QueryError)?,
+            None => 0,
+        };
+
+        let block = client
+            .query(sql::backfill::mergeable_usage_sql(tenant_id, backfill_id))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let usage = block
+            .rows()
+            .map(|row| -> Result<BackfilledUsage, ConnectorError> {
+                let from: DateTime<Tz> = row
+                    .get("from_timestamp")
+                    .change_context(ConnectorError::QueryError)?;
+                let to: DateTime<Tz> = row
+                    .get("to_timestamp")
+                    .change_context(ConnectorError::QueryError)?;
+
+                Ok(BackfilledUsage {
+                    customer_id: row
+                        .get("customer_id")
+                        .change_context(ConnectorError::QueryError)?,
+                    from: from.naive_utc(),
+                    to: to.naive_utc(),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        client
+            .execute(sql::backfill::merge_staged_events_sql(
+                tenant_id,
+                backfill_id,
+            ))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        client
+            .execute(sql::backfill::delete_staged_events_sql(
+                tenant_id,
+                backfill_id,
+            ))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(MergedBackfill {
+            events_merged: count,
+            usage,
+        })
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn discard_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<(), ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        client
+            .execute(sql::backfill::delete_staged_events_sql(
+                tenant_id,
+                backfill_id,
+            ))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(())
+    }
+
+    async fn purge_tenant_events(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<(), ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        for statement in sql::purge::purge_tenant_events_sql(tenant_id, meter_slugs) {
+            client
+                .execute(statement)
+                .await
+                .change_context(ConnectorError::WriteError)?;
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn check_meter_views(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<MeterViewsDrift, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::meter_views::list_tenant_meter_views_sql(tenant_id))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let views = block
+            .rows()
+            .map(|row| row.get::<String, _>("name"))
+            .collect::<std::result::Result<Vec<_>, _>>()
+            .change_context(ConnectorError::QueryError)?;
+
+        Ok(sql::meter_views::meter_views_drift(
+            tenant_id,
+            meter_slugs,
+            &views,
+        ))
+    }
+    #[tracing::instrument(skip_all)]
+    async fn set_retention_policy(
+        &self,
+        mut policy: RetentionPolicy,
+    ) -> Result<RetentionPolicy, ConnectorError> {
+        if policy.archive {
+            self.archive_storage()?;
+        }
+
+        policy.archived_until = self
+            .find_retention_policy(&policy.tenant_id)
+            .await?
+            .and_then(|existing| existing.archived_until);
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        client
+            .execute(sql::retention::upsert_policy_sql(&policy))
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        client
+            .execute(sql::retention::reload_retention_dictionary_sql())
+            .await
+            .change_context(ConnectorError::WriteError)?;
+
+        Ok(policy)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_retention_policy(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Option<RetentionPolicy>, ConnectorError> {
+        let policies = self
+            .query_retention_policies(sql::retention::select_policy_sql(tenant_id))
+            .await?;
+
+        Ok(policies.into_iter().next())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn archive_expired_events(&self) -> Result<(), ConnectorError> {
+        let now = Utc::now();
+
+        self.apply_default_archival(now).await?;
+
+        let policies = self
+            .query_retention_policies(sql::retention::list_archived_policies_sql())
+            .await?;
+
+        // a failing tenant does not block the others
+        for policy in policies {
+            let tenant_id = policy.tenant_id.clone();
+            if let Err(e) = self.archive_tenant_events(policy, now).await {
+                log::error!(
+                    "Failed to archive the events of tenant {}: {:?}",
+                    tenant_id,
+                    e
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn restore_archived_events(
+        &self,
+        tenant_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<(), ConnectorError> {
+        let storage = self.archive_storage()?;
+
+        // only the archived events, that are not in the events table anymore
+        let Some(to) = self
+            .find_retention_policy(tenant_id)
+            .await?
+            .and_then(|policy| policy.archived_until)
+            .map(|archived_until| archived_until.min(to))
+            .filter(|to| *to > from)
+        else {
+            return Ok(());
+        };
+
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        // restoring the same period again replaces it
+        for statement in [
+            sql::retention::delete_restored_events_sql(tenant_id, &from, &to),
+            sql::retention::restore_events_sql(storage, tenant_id, &from, &to),
+        ] {
+            client
+                .execute(statement)
+                .await
+                .change_context(ConnectorError::WriteError)?;
+        }
+
+        Ok(())
+    }
+    #[tracing::instrument(skip_all)]
+    async fn ingestion_health(
+        &self,
+        tenant_ids: &[String],
+    ) -> Result<IngestionHealth, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::diagnostics::last_events_sql(tenant_ids))
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let tenants = block
+            .rows()
+            .map(|row| -> Result<TenantIngestion, ConnectorError> {
+                let last_event_timestamp: DateTime<Tz> = row
+                    .get("last_event_timestamp")
+                    .change_context(ConnectorError::QueryError)?;
+
+                Ok(TenantIngestion {
+                    tenant_id: row
+                        .get("tenant_id")
+                        .change_context(ConnectorError::QueryError)?,
+                    last_event_timestamp: last_event_timestamp.with_timezone(&Utc),
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let block = client
+            .query(sql::diagnostics::kafka_consumer_errors_sql())
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let insert_errors = block
+            .rows()
+            .map(|row| -> Result<InsertError, ConnectorError> {
+                let time: DateTime<Tz> = row
+                    .get("exception_time")
+                    .change_context(ConnectorError::QueryError)?;
+
+                Ok(InsertError {
+                    time: time.with_timezone(&Utc),
+                    message: row
+                        .get("exception_text")
+                        .change_context(ConnectorError::QueryError)?,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(IngestionHealth {
+            tenants,
+            insert_errors,
+        })
+    }
+
+    async fn insert_latency(&self) -> Result<Option<Duration>, ConnectorError> {
+        let mut client = self
+            .pool
+            .get_handle()
+            .await
+            .change_context(ConnectorError::ResourceUnavailable)?;
+
+        let block = client
+            .query(sql::diagnostics::insert_latency_sql())
+            .fetch_all()
+            .await
+            .change_context(ConnectorError::QueryError)?;
+
+        let Some(row) = block.rows().next() else {
+            return Ok(None);
+        };
+
+        let inserts: u64 = row
+            .get("inserts")
+            .change_context(ConnectorError::QueryError)?;
+        if inserts == 0 {
+            return Ok(None);
+        }
+
+        let latency_ms: f64 = row
+            .get("insert_latency_ms")
+            .change_context(ConnectorError::QueryError)?;
+
+        Ok(Some(Duration::from_millis(latency_ms.max(0.0) as u64)))
+    }
 }
@@ -0,0 +1,254 @@
+use crate::connectors::clickhouse::sql::init::{
+    get_backfill_events_table_name, get_backfill_sessions_table_name, get_events_table_name,
+};
+use crate::connectors::clickhouse::sql::{datetime64_literal, escape_sql_string};
+use crate::ingest::domain::{BackfillSession, ProcessedEvent};
+use uuid::Uuid;
+
+const SESSION_COLUMNS: &str = "tenant_id, backfill_id, status, max_events_per_second, events_staged, events_failed, events_merged, last_batch_sequence, created_at, updated_at";
+
+fn backfill_filter(tenant_id: &str, backfill_id: &Uuid) -> String {
+    format!(
+        "tenant_id = '{}' AND backfill_id = '{}'",
+        escape_sql_string(tenant_id),
+        backfill_id
+    )
+}
+
+pub fn upsert_session_sql(session: &BackfillSession) -> String {
+    format!(
+        "INSERT INTO {} ({}) VALUES ('{}', '{}', '{}', {}, {}, {}, {}, {}, {}, {})",
+        get_backfill_sessions_table_name(),
+        SESSION_COLUMNS,
+        escape_sql_string(&session.tenant_id),
+        session.id,
+        session.status.as_str(),
+        session.max_events_per_second,
+        session.events_staged,
+        session.events_failed,
+        session.events_merged,
+        session
+            .last_batch_sequence
+            .map(|s| s.to_string())
+            .unwrap_or("NULL".to_string()),
+        datetime64_literal(&session.created_at.naive_utc(), 3),
+        datetime64_literal(&session.updated_at.naive_utc(), 3),
+    )
+}
+
+pub fn select_session_sql(tenant_id: &str, backfill_id: &Uuid) -> String {
+    format!(
+        "SELECT {} FROM {} FINAL WHERE {} LIMIT 1",
+        SESSION_COLUMNS,
+        get_backfill_sessions_table_name(),
+        backfill_filter(tenant_id, backfill_id)
+    )
+}
+
+pub fn insert_staged_events_sql(backfill_id: &Uuid, events: &[ProcessedEvent]) -> Option<String> {
+    if events.is_empty() {
+        return None;
+    }
+
+    let values = events
+        .iter()
+        .map(|event| {
+            let properties = event
+                .properties
+                .iter()
+                .flat_map(|(k, v)| [k, v])
+                .map(|s| format!("'{}'", escape_sql_string(s)))
+                .collect::<Vec<_>>()
+                .join(", ");
+
+            format!(
+                "('{}', '{}', '{}', '{}', '{}', {}, map({}))",
+                backfill_id,
+                escape_sql_string(&event.tenant_id),
+                escape_sql_string(&event.event_id),
+                escape_sql_string(&event.event_name),
+                escape_sql_string(&event.customer_id),
+                datetime64_literal(&event.event_timestamp, 9),
+                properties
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(format!(
+        "INSERT INTO {} (backfill_id, tenant_id, event_id, event_name, customer_id, event_timestamp, properties) VALUES {}",
+        get_backfill_events_table_name(),
+        values
+    ))
+}
+
+pub fn count_staged_events_sql(
+    tenant_id: &str,
+    backfill_id: &Uuid,
+    event_ids: &[String],
+) -> Option<String> {
+    if event_ids.is_empty() {
+        return None;
+    }
+
+    let ids = event_ids
+        .iter()
+        .map(|id| format!("'{}'", escape_sql_string(id)))
+        .collect::<Vec<_>>()
+        .join(", ");
+
+    Some(format!(
+        "SELECT count(DISTINCT event_id) AS count FROM {} WHERE {} AND event_id IN ({})",
+        get_backfill_events_table_name(),
+        backfill_filter(tenant_id, backfill_id),
+        ids
+    ))
+}
+
+// staged events not yet present in the events table. The lookup is bounded to the staged time range
+fn new_staged_events_sql(columns: &str, tenant_id: &str, backfill_id: &Uuid) -> String {
+    let staging_table = get_backfill_events_table_name();
+    let filter = backfill_filter(tenant_id, backfill_id);
+
+    format!(
+        "SELECT {columns} FROM {staging_table}
+        WHERE {filter}
+        AND event_id NOT IN (
+            SELECT event_id FROM {events_table}
+            WHERE tenant_id = '{tenant_id}'
+            AND event_timestamp >= (SELECT min(event_timestamp) FROM {staging_table} WHERE {filter})
+            AND event_timestamp <= (SELECT max(event_timestamp) FROM {staging_table} WHERE {filter})
+        )",
+        events_table = get_events_table_name(),
+        tenant_id = escape_sql_string(tenant_id),
+    )
+}
+
+pub fn count_mergeable_events_sql(tenant_id: &str, backfill_id: &Uuid) -> String {
+    new_staged_events_sql("count(DISTINCT event_id) AS count", tenant_id, backfill_id)
+}
+
+/// The time range of the mergeable events of each customer
+pub fn mergeable_usage_sql(tenant_id: &str, backfill_id: &Uuid) -> String {
+    format!(
+        "{} GROUP BY customer_id",
+        new_staged_events_sql(
+            "customer_id, min(event_timestamp) AS from_timestamp, max(event_timestamp) AS to_timestamp",
+            tenant_id,
+            backfill_id
+        )
+    )
+}
+
+pub fn merge_staged_events_sql(tenant_id: &str, backfill_id: &Uuid) -> String {
+    let columns = "tenant_id, event_id, event_name, customer_id, event_timestamp, properties";
+
+    format!(
+        "INSERT INTO {} ({}) {} LIMIT 1 BY event_id",
+        get_events_table_name(),
+        columns,
+        new_staged_events_sql(columns, tenant_id, backfill_id)
+    )
+}
+
+pub fn delete_staged_events_sql(tenant_id: &str, backfill_id: &Uuid) -> String {
+    format!(
+        "ALTER TABLE {} DELETE WHERE {}",
+        get_backfill_events_table_name(),
+        backfill_filter(tenant_id, backfill_id)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    fn clean_sql(sql: &str) -> String {
+        sql.replace("\n", "").replace(" ", "")
+    }
+
+    #[test]
+    fn test_insert_staged_events_escapes_values() {
+        let backfill_id = Uuid::nil();
+        let event = ProcessedEvent {
+            event_id: "evt_1".to_string(),
+            event_name: "api'call".to_string(),
+            customer_id: "cus_1".to_string(),
+            tenant_id: "tenant".to_string(),
+            event_timestamp: chrono::NaiveDate::from_ymd_opt(2022, 3, 1)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap(),
+            properties: HashMap::from([("region".to_string(), "eu\\west".to_string())]),
+        };
+
+        let sql = insert_staged_events_sql(&backfill_id, &[event]).unwrap();
+
+        let expected = r#"
+            INSERT INTO meteroid.raw_backfill_events (backfill_id, tenant_id, event_id, event_name, customer_id, event_timestamp, properties)
+            VALUES ('00000000-0000-0000-0000-000000000000', 'tenant', 'evt_1', 'api\'call', 'cus_1',
+                toDateTime64('2022-03-01 10:00:00.000000000', 9, 'UTC'), map('region', 'eu\\west'))
+        "#;
+
+        assert_eq!(clean_sql(&sql), clean_sql(expected));
+    }
+
+    #[test]
+    fn test_insert_staged_events_empty() {
+        assert_eq!(insert_staged_events_sql(&Uuid::nil(), &[]), None);
+    }
+
+    #[test]
+    fn test_count_staged_events_sql() {
+        let sql = count_staged_events_sql(
+            "tenant",
+            &Uuid::nil(),
+            &["event-1".to_string(), "event'2".to_string()],
+        )
+        .unwrap();
+
+        let expected = r#"
+            SELECT count(DISTINCT event_id) AS count FROM meteroid.raw_backfill_events
+            WHERE tenant_id = 'tenant' AND backfill_id = '00000000-0000-0000-0000-000000000000'
+            AND event_id IN ('event-1', 'event\'2')
+        "#;
+
+        assert_eq!(clean_sql(&sql), clean_sql(expected));
+        assert!(count_staged_events_sql("tenant", &Uuid::nil(), &[]).is_none());
+    }
+
+    #[test]
+    fn test_merge_staged_events_skips_existing() {
+        let sql = merge_staged_events_sql("tenant", &Uuid::nil());
+
+        let filter =
+            "tenant_id = 'tenant' AND backfill_id = '00000000-0000-0000-0000-000000000000'";
+        let expected = format!(
+            r#"
+            INSERT INTO meteroid.raw_events (tenant_id, event_id, event_name, customer_id, event_timestamp, properties)
+            SELECT tenant_id, event_id, event_name, customer_id, event_timestamp, properties FROM meteroid.raw_backfill_events
+            WHERE {filter}
+            AND event_id NOT IN (
+                SELECT event_id FROM meteroid.raw_events
+                WHERE tenant_id = 'tenant'
+                AND event_timestamp >= (SELECT min(event_timestamp) FROM meteroid.raw_backfill_events WHERE {filter})
+                AND event_timestamp <= (SELECT max(event_timestamp) FROM meteroid.raw_backfill_events WHERE {filter})
+            )
+            LIMIT 1 BY event_id
+        "#
+        );
+
+        assert_eq!(clean_sql(&sql), clean_sql(&expected));
+    }
+
+    #[test]
+    fn test_mergeable_usage_sql() {
+        let sql = mergeable_usage_sql("tenant", &Uuid::nil());
+
+        assert!(clean_sql(&sql).starts_with(&clean_sql(
+            "SELECT customer_id, min(event_timestamp) AS from_timestamp, max(event_timestamp) AS to_timestamp FROM meteroid.raw_backfill_events"
+        )));
+        assert!(sql.ends_with(") GROUP BY customer_id"));
+    }
+}
@@ -1,6 +1,9 @@
 use crate::connectors::clickhouse::sql::init::get_events_table_name;
-use crate::connectors::clickhouse::sql::{escape_sql_identifier, get_meter_view_name, Column};
+use crate::connectors::clickhouse::sql::{
+    datetime64_literal, escape_sql_identifier, get_meter_rollup_name, get_meter_view_name, Column,
+};
 use crate::domain::{Meter, MeterAggregation};
+use chrono::{DateTime, Utc};
 
 use std::fmt;
 
@@ -18,21 +21,59 @@ impl fmt::Display for MeterAggregation {
     }
 }
 
-fn create_meter_view_to_select_sql(meter: Meter) -> String {
+/// The views maintained for each meter, with the same columns but different windows
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MeterViewKind {
+    /// the main view, aggregated per minute
+    Minute,
+    /// aggregated per UTC day, to query the closed days of long periods
+    DailyRollup,
+}
+
+impl MeterViewKind {
+    pub const ALL: [MeterViewKind; 2] = [MeterViewKind::Minute, MeterViewKind::DailyRollup];
+
+    pub fn view_name(&self, namespace: &str, meter_slug: &str) -> String {
+        match self {
+            MeterViewKind::Minute => get_meter_view_name(namespace, meter_slug),
+            MeterViewKind::DailyRollup => get_meter_rollup_name(namespace, meter_slug),
+        }
+    }
+
+    fn interval(&self) -> &'static str {
+        match self {
+            MeterViewKind::Minute => "toIntervalMinute(1)",
+            MeterViewKind::DailyRollup => "toIntervalDay(1)",
+        }
+    }
+}
+
+/// Aggregates the events of the meter from the events table. Only the events before `until` when set (used to backfill a view)
+pub(super) fn create_meter_view_to_select_sql(
+    meter: &Meter,
+    kind: MeterViewKind,
+    events_table_name: &str,
+    until: Option<&DateTime<Utc>>,
+) -> String {
     let agg_state_fn = format!("{}State", meter.aggregation);
 
-    // TODO we moved from day to minute aggregation. Not sure if we should keep it like this (or we can make it configurable)
-    // Also we want to make sure that we can group by full day, as it's the main view. Maybe an extra MV ?
     let mut selects = vec![
         "customer_id".to_string(),
-        "tumbleStart(toDateTime(event_timestamp), toIntervalMinute(1)) AS windowstart".to_string(),
-        "tumbleEnd(toDateTime(event_timestamp), toIntervalMinute(1)) AS windowend".to_string(),
+        format!(
+            "tumbleStart(toDateTime(event_timestamp), {}) AS windowstart",
+            kind.interval()
+        ),
+        format!(
+            "tumbleEnd(toDateTime(event_timestamp), {}) AS windowend",
+            kind.interval()
+        ),
     ];
 
     // we rasterize the value property to be an option of non empty string
     let value_property_nes =
         meter
             .value_property
+            .as_ref()
             .and_then(|v| if v.is_empty() { None } else { Some(v) });
 
     match value_property_nes {
@@ -40,7 +81,7 @@ fn create_meter_view_to_select_sql(meter: Meter) -> String {
             selects.push(format!(
                 "{}(cast(properties['{}'], 'Float64')) AS value",
                 agg_state_fn,
-                escape_sql_identifier(&value_property)
+                escape_sql_identifier(value_property)
             ));
         }
         None => {
@@ -58,7 +99,7 @@ fn create_meter_view_to_select_sql(meter: Meter) -> String {
         "windowend".to_string(),
         "customer_id".to_string(),
     ];
-    let mut sorted_group_by = meter.group_by;
+    let mut sorted_group_by = meter.group_by.clone();
     sorted_group_by.sort();
 
     for k in &sorted_group_by {
@@ -68,24 +109,43 @@ fn create_meter_view_to_select_sql(meter: Meter) -> String {
         selects.push(format!("properties['{}'] as {}", column_name, column_name));
     }
 
-    let events_table_name = get_events_table_name();
+    let until_filter = until
+        .map(|until| {
+            format!(
+                " AND {}.event_timestamp < {}",
+                events_table_name,
+                datetime64_literal(&until.naive_utc(), 9)
+            )
+        })
+        .unwrap_or_default();
 
     let query = format!(
-        "SELECT {} FROM {} WHERE {}.tenant_id = '{}' AND {}.event_name = '{}' GROUP BY {}",
+        "SELECT {} FROM {} WHERE {}.tenant_id = '{}' AND {}.event_name = '{}'{} GROUP BY {}",
         selects.join(", "),
         events_table_name,
         events_table_name,
         escape_sql_identifier(&meter.namespace),
         events_table_name,
         escape_sql_identifier(&meter.event_name),
+        until_filter,
         order_by.join(", "), // TODO check
     );
 
     query
 }
 
-pub fn create_meter_view(meter: Meter, populate: bool) -> String {
-    let view_name = get_meter_view_name(&meter.namespace, &meter.meter_slug);
+pub fn create_meter_view(meter: &Meter, kind: MeterViewKind, populate: bool) -> String {
+    let view_name = kind.view_name(&meter.namespace, &meter.meter_slug);
+
+    create_meter_view_named(meter, kind, &view_name, populate)
+}
+
+pub(super) fn create_meter_view_named(
+    meter: &Meter,
+    kind: MeterViewKind,
+    view_name: &str,
+    populate: bool,
+) -> String {
     let mut columns = vec![
         Column {
             name: "customer_id".to_string(),
@@ -138,7 +198,7 @@ pub fn create_meter_view(meter: Meter, populate: bool) -> String {
         sql.push_str("POPULATE\n");
     }
 
-    let select_query = create_meter_view_to_select_sql(meter);
+    let select_query = create_meter_view_to_select_sql(meter, kind, &get_events_table_name(), None);
 
     // Add SELECT statement
     sql.push_str(&format!("AS {}\n", select_query)); // Add your select statement here
@@ -190,8 +250,42 @@ mod tests {
                 GROUP BY windowstart, windowend, customer_id, test_group1, test_group2
         "#;
 
-        let result = create_meter_view(meter, true);
+        let result = create_meter_view(&meter, MeterViewKind::Minute, true);
         // assert equal ignoring whitespace
         assert_eq!(clean_sql(&result), clean_sql(expected));
     }
+
+    #[test]
+    fn test_create_meter_rollup_view() {
+        let meter = Meter {
+            namespace: "test_namespace".to_string(),
+            meter_slug: "test_slug".to_string(),
+            event_name: "test_event".to_string(),
+            aggregation: MeterAggregation::Sum,
+            group_by: vec![],
+            value_property: Some("test_value".to_string()),
+        };
+
+        let expected = r#"
+            CREATE MATERIALIZED VIEW IF NOT EXISTS meteroid.METER_NStestnamespace_Mtestslug_DAILY (
+                customer_id String,
+                windowstart DateTime,
+                windowend DateTime,
+                value AggregateFunction(sum, Float64))
+            ENGINE = AggregatingMergeTree()
+            ORDER BY (windowstart, windowend, customer_id)
+            AS SELECT
+                customer_id,
+                tumbleStart(toDateTime(event_timestamp), toIntervalDay(1)) AS windowstart,
+                tumbleEnd(toDateTime(event_timestamp), toIntervalDay(1)) AS windowend,
+                sumState(cast(properties['test_value'], 'Float64')) AS value
+            FROM meteroid.raw_events
+            WHERE meteroid.raw_events.tenant_id = 'test_namespace'
+                AND meteroid.raw_events.event_name = 'test_event'
+                GROUP BY windowstart, windowend, customer_id
+        "#;
+
+        let result = create_meter_view(&meter, MeterViewKind::DailyRollup, false);
+        assert_eq!(clean_sql(&result), clean_sql(expected));
+    }
 }
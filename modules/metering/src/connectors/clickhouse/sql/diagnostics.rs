@@ -0,0 +1,87 @@
+use crate::connectors::clickhouse::sql::escape_sql_string;
+use crate::connectors::clickhouse::sql::init::{
+    get_events_table_name, get_kafka_events_table_name,
+};
+
+const LAST_EVENTS_WINDOW_DAYS: u32 = 7;
+
+const MAX_INSERT_ERRORS: u32 = 100;
+
+const INSERT_LATENCY_WINDOW_SECONDS: u32 = 60;
+
+/// The most recent event of each tenant over the window, as older partitions are not scanned
+pub fn last_events_sql(tenant_ids: &[String]) -> String {
+    let tenant_filter = if tenant_ids.is_empty() {
+        String::new()
+    } else {
+        format!(
+            " AND tenant_id IN ({})",
+            tenant_ids
+                .iter()
+                .map(|id| format!("'{}'", escape_sql_string(id)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        )
+    };
+
+    format!(
+        "SELECT tenant_id, max(event_timestamp) AS last_event_timestamp FROM {} WHERE event_timestamp >= now() - INTERVAL {} DAY{} GROUP BY tenant_id ORDER BY tenant_id",
+        get_events_table_name(),
+        LAST_EVENTS_WINDOW_DAYS,
+        tenant_filter
+    )
+}
+
+/// The errors of the kafka engine consumers writing the events table. Requires clickhouse 23.8+
+pub fn kafka_consumer_errors_sql() -> String {
+    format!(
+        "SELECT exception_time, exception_text FROM system.kafka_consumers \
+        ARRAY JOIN exceptions.time AS exception_time, exceptions.text AS exception_text \
+        WHERE concat(database, '.', table) = '{}' ORDER BY exception_time DESC LIMIT {}",
+        get_kafka_events_table_name(),
+        MAX_INSERT_ERRORS
+    )
+}
+
+/// The 90th percentile duration of the parts written to the events table over the window. Requires the part log
+pub fn insert_latency_sql() -> String {
+    format!(
+        "SELECT count() AS inserts, quantile(0.9)(duration_ms) AS insert_latency_ms FROM system.part_log \
+        WHERE event_type = 'NewPart' AND concat(database, '.', table) = '{}' AND event_time >= now() - INTERVAL {} SECOND",
+        get_events_table_name(),
+        INSERT_LATENCY_WINDOW_SECONDS
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_last_events_sql() {
+        assert_eq!(
+            last_events_sql(&[]),
+            "SELECT tenant_id, max(event_timestamp) AS last_event_timestamp FROM meteroid.raw_events WHERE event_timestamp >= now() - INTERVAL 7 DAY GROUP BY tenant_id ORDER BY tenant_id"
+        );
+        assert_eq!(
+            last_events_sql(&["t1".to_string(), "t'2".to_string()]),
+            "SELECT tenant_id, max(event_timestamp) AS last_event_timestamp FROM meteroid.raw_events WHERE event_timestamp >= now() - INTERVAL 7 DAY AND tenant_id IN ('t1', 't\\'2') GROUP BY tenant_id ORDER BY tenant_id"
+        );
+    }
+
+    #[test]
+    fn test_kafka_consumer_errors_sql() {
+        assert_eq!(
+            kafka_consumer_errors_sql(),
+            "SELECT exception_time, exception_text FROM system.kafka_consumers ARRAY JOIN exceptions.time AS exception_time, exceptions.text AS exception_text WHERE concat(database, '.', table) = 'meteroid.raw_kafka_events' ORDER BY exception_time DESC LIMIT 100"
+        );
+    }
+
+    #[test]
+    fn test_insert_latency_sql() {
+        assert_eq!(
+            insert_latency_sql(),
+            "SELECT count() AS inserts, quantile(0.9)(duration_ms) AS insert_latency_ms FROM system.part_log WHERE event_type = 'NewPart' AND concat(database, '.', table) = 'meteroid.raw_events' AND event_time >= now() - INTERVAL 60 SECOND"
+        );
+    }
+}
@@ -12,7 +12,7 @@ pub fn get_events_table_name() -> String {
 }
 
 // the streaming ingestion table, temporary storage
-fn get_kafka_events_table_name() -> String {
+pub fn get_kafka_events_table_name() -> String {
     get_table_name("kafka_events")
 }
 
@@ -21,6 +21,33 @@ fn get_kafka_mv_table_name() -> String {
     get_table_name("kafka_events_mv")
 }
 
+// staging area of the backfills, merged into the events table once the backfill is completed
+pub fn get_backfill_events_table_name() -> String {
+    get_table_name("backfill_events")
+}
+
+pub fn get_backfill_sessions_table_name() -> String {
+    get_table_name("backfill_sessions")
+}
+
+// the retention policies of the tenants
+pub fn get_retention_table_name() -> String {
+    get_table_name("events_retention")
+}
+
+// the retention of the tenants without archival, read by the TTL of the events table
+pub fn get_retention_dictionary_name() -> String {
+    get_table_name("events_retention_dict")
+}
+
+// the archived events restored from the object storage, kept apart from the events table so that the meters do not count them twice
+pub fn get_restored_events_table_name() -> String {
+    get_table_name("restored_events")
+}
+
+// the events of the tenants without retention policy are kept (~100 years)
+const NO_RETENTION_DAYS: u32 = 36500;
+
 // data String if we want JSON with path, but to simplify for end user let's use Map<String,String> for now
 // TODO LowCardinality(String) for tenant, event name and for property key as well when available, https://github.com/suharev7/clickhouse-rs/issues/199#issuecomment-1837427136
 const COMMON_COLUMNS: &str = "tenant_id String,
@@ -41,6 +68,26 @@ pub(crate) fn create_events_table_sql() -> String {
         COMMON_COLUMNS
     )
 }
+
+// the TTL is only set once, as modifying it rewrites the existing parts
+pub(crate) fn events_table_has_ttl_sql() -> String {
+    format!(
+        "SELECT count() AS count FROM system.tables WHERE database = '{}' AND name = '{}_events' AND position(engine_full, ' TTL ') > 0",
+        DATABASE, TABLE_PREFIX
+    )
+}
+
+/// The events expire once the retention of their tenant is over. The tenants with archival are excluded from the dictionary,
+/// their events being deleted by the archival instead
+pub(crate) fn modify_events_table_ttl_sql() -> String {
+    format!(
+        "ALTER TABLE {} MODIFY TTL toDate(event_timestamp) + toIntervalDay(dictGetOrDefault('{}', 'retention_days', tuple(tenant_id), toUInt32({})))",
+        get_events_table_name(),
+        get_retention_dictionary_name(),
+        NO_RETENTION_DAYS
+    )
+}
+
 /*
     TODO
     TTL
@@ -83,3 +130,80 @@ pub(crate) fn create_kafka_mv_sql() -> String {
         get_kafka_events_table_name(),
     )
 }
+
+// abandoned staged events are dropped after a while
+pub(crate) fn create_backfill_events_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            backfill_id String,
+            {},
+            staged_at DateTime DEFAULT now()
+        ) ENGINE = MergeTree
+        ORDER BY (tenant_id, backfill_id, event_id)
+        TTL staged_at + INTERVAL 30 DAY",
+        get_backfill_events_table_name(),
+        COMMON_COLUMNS
+    )
+}
+
+pub(crate) fn create_backfill_sessions_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            tenant_id String,
+            backfill_id String,
+            status String,
+            max_events_per_second UInt32,
+            events_staged UInt64,
+            events_failed UInt64,
+            events_merged UInt64,
+            last_batch_sequence Nullable(UInt64),
+            created_at DateTime64(3, 'UTC'),
+            updated_at DateTime64(3, 'UTC')
+        ) ENGINE = ReplacingMergeTree(updated_at)
+        ORDER BY (tenant_id, backfill_id)",
+        get_backfill_sessions_table_name()
+    )
+}
+
+// retention_days 0 keeps the events
+pub(crate) fn create_retention_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            tenant_id String,
+            retention_days UInt32,
+            archive UInt8,
+            archived_until Nullable(DateTime64(9, 'UTC')),
+            updated_at DateTime64(3, 'UTC')
+        ) ENGINE = ReplacingMergeTree(updated_at)
+        ORDER BY tenant_id",
+        get_retention_table_name()
+    )
+}
+
+pub(crate) fn create_retention_dictionary_sql() -> String {
+    format!(
+        "CREATE DICTIONARY IF NOT EXISTS {} (
+            tenant_id String,
+            retention_days UInt32
+        ) PRIMARY KEY tenant_id
+        SOURCE(CLICKHOUSE(QUERY 'SELECT tenant_id, retention_days FROM {} FINAL WHERE retention_days > 0 AND archive = 0'))
+        LAYOUT(COMPLEX_KEY_HASHED())
+        LIFETIME(MIN 60 MAX 300)",
+        get_retention_dictionary_name(),
+        get_retention_table_name()
+    )
+}
+
+// restored events are dropped after a while
+pub(crate) fn create_restored_events_table_sql() -> String {
+    format!(
+        "CREATE TABLE IF NOT EXISTS {} (
+            {},
+            restored_at DateTime DEFAULT now()
+        ) ENGINE = MergeTree
+        ORDER BY (tenant_id, event_timestamp, event_name, customer_id)
+        TTL restored_at + INTERVAL 30 DAY",
+        get_restored_events_table_name(),
+        COMMON_COLUMNS
+    )
+}
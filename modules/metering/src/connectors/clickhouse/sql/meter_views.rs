@@ -0,0 +1,103 @@
+use crate::connectors::clickhouse::sql::create_meter::MeterViewKind;
+use crate::connectors::clickhouse::sql::{
+    encode_identifier, escape_sql_string, DATABASE, METER_TABLE_PREFIX,
+};
+use crate::domain::MeterViewsDrift;
+
+pub fn list_tenant_meter_views_sql(tenant_id: &str) -> String {
+    format!(
+        "SELECT name FROM system.tables WHERE database = '{}' AND startsWith(name, '{}_NS{}_M')",
+        DATABASE,
+        METER_TABLE_PREFIX,
+        escape_sql_string(&encode_identifier(tenant_id))
+    )
+}
+
+/// Compares the meter views of the tenant, as listed in system.tables (without database), to its meters.
+/// A meter is missing if any of its views is, as registering it again creates the missing ones
+pub fn meter_views_drift(
+    tenant_id: &str,
+    meter_slugs: &[String],
+    views: &[String],
+) -> MeterViewsDrift {
+    let views: Vec<String> = views
+        .iter()
+        .map(|view| format!("{}.{}", DATABASE, view))
+        .collect();
+
+    let expected: Vec<Vec<String>> = meter_slugs
+        .iter()
+        .map(|slug| {
+            MeterViewKind::ALL
+                .iter()
+                .map(|kind| kind.view_name(tenant_id, slug))
+                .collect()
+        })
+        .collect();
+
+    MeterViewsDrift {
+        missing_meter_slugs: meter_slugs
+            .iter()
+            .zip(&expected)
+            .filter(|(_, meter_views)| meter_views.iter().any(|view| !views.contains(view)))
+            .map(|(slug, _)| slug.clone())
+            .collect(),
+        orphaned_views: views
+            .into_iter()
+            .filter(|view| !expected.iter().flatten().any(|expected| expected == view))
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TENANT_ID: &str = "018c2c82-3df1-7e84-9e05-6e141d0e751a";
+
+    #[test]
+    fn test_list_tenant_meter_views_sql() {
+        assert_eq!(
+            list_tenant_meter_views_sql(TENANT_ID),
+            "SELECT name FROM system.tables WHERE database = 'meteroid' AND startsWith(name, 'METER_NS018c2c823df17e849e056e141d0e751a_M')"
+        );
+    }
+
+    #[test]
+    fn test_meter_views_drift() {
+        let slugs = vec![
+            "018c2c82-0000-0000-0000-000000000001".to_string(),
+            "018c2c82-0000-0000-0000-000000000002".to_string(),
+        ];
+        let views = vec![
+            "METER_NS018c2c823df17e849e056e141d0e751a_M018c2c82000000000000000000000001"
+                .to_string(),
+            "METER_NS018c2c823df17e849e056e141d0e751a_M018c2c82000000000000000000000001_DAILY"
+                .to_string(),
+            "METER_NS018c2c823df17e849e056e141d0e751a_M018c2c82000000000000000000000003"
+                .to_string(),
+        ];
+
+        assert_eq!(
+            meter_views_drift(TENANT_ID, &slugs, &views),
+            MeterViewsDrift {
+                missing_meter_slugs: vec!["018c2c82-0000-0000-0000-000000000002".to_string()],
+                orphaned_views: vec![
+                    "meteroid.METER_NS018c2c823df17e849e056e141d0e751a_M018c2c82000000000000000000000003"
+                        .to_string()
+                ],
+            }
+        );
+
+        assert_eq!(
+            meter_views_drift(TENANT_ID, &slugs[..1], &views[..2]),
+            MeterViewsDrift::default()
+        );
+
+        // registered before the daily rollups
+        assert_eq!(
+            meter_views_drift(TENANT_ID, &slugs[..1], &views[..1]).missing_meter_slugs,
+            vec!["018c2c82-0000-0000-0000-000000000001".to_string()]
+        );
+    }
+}
@@ -0,0 +1,199 @@
+use crate::connectors::clickhouse::sql::create_meter::{
+    create_meter_view_named, create_meter_view_to_select_sql, MeterViewKind,
+};
+use crate::connectors::clickhouse::sql::{escape_sql_string, DATABASE};
+use crate::domain::Meter;
+use chrono::{DateTime, Utc};
+
+// Migration of a meter view after a change of its aggregation or dimensions :
+// 1. a new version of the view is created, without POPULATE, so that it starts capturing the new events
+// 2. the events prior to its creation are backfilled from raw_events, and from the restored events
+// 3. the new version is atomically exchanged with the current view, that keeps its name for the queries
+// 4. the previous definition, now under the versioned name, is dropped
+//
+// An event ingested during the backfill with a timestamp before the cutoff is aggregated twice (by the view and the backfill).
+// The daily rollup of the meter is migrated the same way.
+
+const VERSION_SEPARATOR: &str = "_V";
+
+/// The version suffix cannot conflict with a meter slug, as the encoded identifiers are alphanumeric
+pub fn get_meter_view_version_name(
+    kind: MeterViewKind,
+    namespace: &str,
+    meter_slug: &str,
+    version: i64,
+) -> String {
+    format!(
+        "{}{}{}",
+        kind.view_name(namespace, meter_slug),
+        VERSION_SEPARATOR,
+        version
+    )
+}
+
+/// The current view and the versions left over by an interrupted migration (names without database)
+pub fn list_meter_view_versions_sql(
+    kind: MeterViewKind,
+    namespace: &str,
+    meter_slug: &str,
+) -> String {
+    let view_name = kind.view_name(namespace, meter_slug);
+    let name = escape_sql_string(
+        view_name
+            .strip_prefix(&format!("{}.", DATABASE))
+            .unwrap_or(&view_name),
+    );
+
+    format!(
+        "SELECT name FROM system.tables WHERE database = '{}' AND (name = '{}' OR startsWith(name, '{}{}'))",
+        DATABASE, name, name, VERSION_SEPARATOR
+    )
+}
+
+pub fn create_meter_view_version_sql(meter: &Meter, kind: MeterViewKind, version: i64) -> String {
+    let view_name = get_meter_view_version_name(kind, &meter.namespace, &meter.meter_slug, version);
+
+    create_meter_view_named(meter, kind, &view_name, false)
+}
+
+/// Aggregates the events of the table before the cutoff into the new version. The columns are explicit, as the select sorts the dimensions
+pub fn backfill_meter_view_version_sql(
+    meter: &Meter,
+    kind: MeterViewKind,
+    version: i64,
+    events_table_name: &str,
+    until: &DateTime<Utc>,
+) -> String {
+    let view_name = get_meter_view_version_name(kind, &meter.namespace, &meter.meter_slug, version);
+
+    let mut columns = vec![
+        "customer_id".to_string(),
+        "windowstart".to_string(),
+        "windowend".to_string(),
+        "value".to_string(),
+    ];
+    let mut sorted_group_by = meter.group_by.clone();
+    sorted_group_by.sort();
+    columns.extend(sorted_group_by);
+
+    format!(
+        "INSERT INTO {} ({}) {}",
+        view_name,
+        columns.join(", "),
+        create_meter_view_to_select_sql(meter, kind, events_table_name, Some(until))
+    )
+}
+
+pub fn exchange_meter_view_version_sql(meter: &Meter, kind: MeterViewKind, version: i64) -> String {
+    format!(
+        "EXCHANGE TABLES {} AND {}",
+        kind.view_name(&meter.namespace, &meter.meter_slug),
+        get_meter_view_version_name(kind, &meter.namespace, &meter.meter_slug, version)
+    )
+}
+
+pub fn drop_meter_view_sql(view_name: &str) -> String {
+    format!("DROP TABLE IF EXISTS {} SYNC", view_name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::connectors::clickhouse::sql::init::get_events_table_name;
+    use crate::domain::MeterAggregation;
+    use chrono::TimeZone;
+
+    fn clean_sql(sql: &str) -> String {
+        sql.replace("\n", "").replace(" ", "")
+    }
+
+    fn meter() -> Meter {
+        Meter {
+            namespace: "test_namespace".to_string(),
+            meter_slug: "test_slug".to_string(),
+            event_name: "test_event".to_string(),
+            aggregation: MeterAggregation::Sum,
+            group_by: vec!["region".to_string(), "model".to_string()],
+            value_property: Some("tokens".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_list_meter_view_versions_sql() {
+        assert_eq!(
+            list_meter_view_versions_sql(MeterViewKind::Minute, "test_namespace", "test_slug"),
+            "SELECT name FROM system.tables WHERE database = 'meteroid' AND (name = 'METER_NStestnamespace_Mtestslug' OR startsWith(name, 'METER_NStestnamespace_Mtestslug_V'))"
+        );
+    }
+
+    #[test]
+    fn test_create_meter_view_version_sql() {
+        let sql = create_meter_view_version_sql(&meter(), MeterViewKind::Minute, 42);
+
+        assert!(sql.starts_with(
+            "CREATE MATERIALIZED VIEW IF NOT EXISTS meteroid.METER_NStestnamespace_Mtestslug_V42 ("
+        ));
+        assert!(!sql.contains("POPULATE"));
+    }
+
+    #[test]
+    fn test_backfill_meter_view_version_sql() {
+        let until = Utc.with_ymd_and_hms(2024, 11, 3, 15, 0, 0).unwrap();
+
+        let expected = r#"
+            INSERT INTO meteroid.METER_NStestnamespace_Mtestslug_V42 (customer_id, windowstart, windowend, value, model, region)
+            SELECT
+                customer_id,
+                tumbleStart(toDateTime(event_timestamp), toIntervalMinute(1)) AS windowstart,
+                tumbleEnd(toDateTime(event_timestamp), toIntervalMinute(1)) AS windowend,
+                sumState(cast(properties['tokens'], 'Float64')) AS value,
+                properties['model'] as model,
+                properties['region'] as region
+            FROM meteroid.raw_events
+            WHERE meteroid.raw_events.tenant_id = 'test_namespace'
+                AND meteroid.raw_events.event_name = 'test_event'
+                AND meteroid.raw_events.event_timestamp < toDateTime64('2024-11-03 15:00:00.000000000', 9, 'UTC')
+                GROUP BY windowstart, windowend, customer_id, model, region
+        "#;
+
+        assert_eq!(
+            clean_sql(&backfill_meter_view_version_sql(
+                &meter(),
+                MeterViewKind::Minute,
+                42,
+                &get_events_table_name(),
+                &until
+            )),
+            clean_sql(expected)
+        );
+    }
+
+    #[test]
+    fn test_exchange_and_drop_sql() {
+        assert_eq!(
+            exchange_meter_view_version_sql(&meter(), MeterViewKind::Minute, 42),
+            "EXCHANGE TABLES meteroid.METER_NStestnamespace_Mtestslug AND meteroid.METER_NStestnamespace_Mtestslug_V42"
+        );
+        assert_eq!(
+            drop_meter_view_sql(&get_meter_view_version_name(
+                MeterViewKind::Minute,
+                "test_namespace",
+                "test_slug",
+                42
+            )),
+            "DROP TABLE IF EXISTS meteroid.METER_NStestnamespace_Mtestslug_V42 SYNC"
+        );
+    }
+
+    #[test]
+    fn test_rollup_versions_sql() {
+        assert_eq!(
+            list_meter_view_versions_sql(MeterViewKind::DailyRollup, "test_namespace", "test_slug"),
+            "SELECT name FROM system.tables WHERE database = 'meteroid' AND (name = 'METER_NStestnamespace_Mtestslug_DAILY' OR startsWith(name, 'METER_NStestnamespace_Mtestslug_DAILY_V'))"
+        );
+        assert_eq!(
+            exchange_meter_view_version_sql(&meter(), MeterViewKind::DailyRollup, 42),
+            "EXCHANGE TABLES meteroid.METER_NStestnamespace_Mtestslug_DAILY AND meteroid.METER_NStestnamespace_Mtestslug_DAILY_V42"
+        );
+    }
+}
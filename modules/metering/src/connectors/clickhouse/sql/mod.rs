@@ -1,7 +1,15 @@
+pub mod backfill;
 pub mod create_meter;
+pub mod diagnostics;
 pub mod init;
+pub mod meter_views;
+pub mod migrate_meter;
+pub mod purge;
 pub mod query_meter;
 pub mod query_raw;
+pub mod retention;
+
+use chrono::NaiveDateTime;
 
 pub const DATABASE: &str = "meteroid"; // TODO config
 
@@ -11,6 +19,18 @@ fn escape_sql_identifier(identifier: &str) -> String {
     identifier.replace("'", "''")
 }
 
+fn escape_sql_string(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('\'', "\\'")
+}
+
+fn datetime64_literal(ts: &NaiveDateTime, precision: u8) -> String {
+    format!(
+        "toDateTime64('{}', {}, 'UTC')",
+        ts.format("%Y-%m-%d %H:%M:%S%.9f"),
+        precision
+    )
+}
+
 fn encode_identifier(identifier: &str) -> String {
     identifier
         .chars()
@@ -34,6 +54,11 @@ pub fn get_meter_view_name(namespace: &str, meter_slug: &str) -> String {
     )
 }
 
+/// The daily rollup of the meter view. The suffix cannot conflict with a meter slug, as the encoded identifiers are alphanumeric
+pub fn get_meter_rollup_name(namespace: &str, meter_slug: &str) -> String {
+    format!("{}_DAILY", get_meter_view_name(namespace, meter_slug))
+}
+
 struct Column {
     name: String,
     col_type: String,
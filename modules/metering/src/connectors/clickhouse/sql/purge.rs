@@ -0,0 +1,66 @@
+use crate::connectors::clickhouse::sql::create_meter::MeterViewKind;
+use crate::connectors::clickhouse::sql::escape_sql_string;
+use crate::connectors::clickhouse::sql::init::{
+    get_backfill_events_table_name, get_events_table_name, get_restored_events_table_name,
+};
+
+/// The meter views are specific to the tenant namespace, so they are emptied as a whole
+pub fn purge_tenant_events_sql(tenant_id: &str, meter_slugs: &[String]) -> Vec<String> {
+    let tenant_filter = format!("tenant_id = '{}'", escape_sql_string(tenant_id));
+
+    let mut statements = vec![
+        format!(
+            "ALTER TABLE {} DELETE WHERE {}",
+            get_events_table_name(),
+            tenant_filter
+        ),
+        format!(
+            "ALTER TABLE {} DELETE WHERE {}",
+            get_backfill_events_table_name(),
+            tenant_filter
+        ),
+        format!(
+            "ALTER TABLE {} DELETE WHERE {}",
+            get_restored_events_table_name(),
+            tenant_filter
+        ),
+    ];
+
+    statements.extend(meter_slugs.iter().flat_map(|slug| {
+        MeterViewKind::ALL.map(|kind| {
+            format!(
+                "TRUNCATE TABLE IF EXISTS {}",
+                kind.view_name(tenant_id, slug)
+            )
+        })
+    }));
+
+    statements
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_purge_tenant_events_sql() {
+        let statements = purge_tenant_events_sql(
+            "018c2c82-3df1-7e84-9e05-6e141d0e751a",
+            &["meter-1".to_string()],
+        );
+
+        assert_eq!(statements.len(), 5);
+        assert_eq!(
+            statements[0],
+            "ALTER TABLE meteroid.raw_events DELETE WHERE tenant_id = '018c2c82-3df1-7e84-9e05-6e141d0e751a'"
+        );
+        assert_eq!(
+            statements[3],
+            "TRUNCATE TABLE IF EXISTS meteroid.METER_NS018c2c823df17e849e056e141d0e751a_Mmeter1"
+        );
+        assert_eq!(
+            statements[4],
+            "TRUNCATE TABLE IF EXISTS meteroid.METER_NS018c2c823df17e849e056e141d0e751a_Mmeter1_DAILY"
+        );
+    }
+}
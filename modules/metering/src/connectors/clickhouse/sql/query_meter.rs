@@ -1,7 +1,72 @@
-use crate::connectors::clickhouse::sql::get_meter_view_name;
+use crate::connectors::clickhouse::sql::{
+    escape_sql_string, get_meter_rollup_name, get_meter_view_name, DATABASE,
+};
 use crate::domain::{MeterAggregation, QueryMeterParams, WindowSize};
+use chrono::{DateTime, Duration, DurationRound, Utc};
 
-pub fn query_meter_view_sql(params: QueryMeterParams) -> Result<String, String> {
+/// The closed days of a query, read from the daily rollup instead of the minute view
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RollupRange {
+    pub from: DateTime<Utc>,
+    pub to: DateTime<Utc>,
+}
+
+impl RollupRange {
+    /// The whole UTC days of the query period before `now`, if at least one and the query windows can be computed from days
+    pub fn of_query(params: &QueryMeterParams, now: DateTime<Utc>) -> Option<RollupRange> {
+        let windows_of_days = match params.window_size {
+            None => true,
+            Some(WindowSize::Day) => params
+                .window_time_zone
+                .as_ref()
+                .map_or(true, |tz| tz == "UTC"),
+            Some(WindowSize::Minute | WindowSize::Hour) => false,
+        };
+
+        if !windows_of_days
+            || matches!(
+                params.aggregation,
+                MeterAggregation::Latest | MeterAggregation::CountDistinct
+            )
+        {
+            return None;
+        }
+
+        let day = Duration::days(1);
+        let today = now.duration_trunc(day).ok()?;
+
+        let from = params.from.duration_trunc(day).ok()?;
+        let from = if from < params.from { from + day } else { from };
+
+        let to = params
+            .to
+            .and_then(|to| to.duration_trunc(day).ok())
+            .map_or(today, |to| to.min(today));
+
+        (from < to).then_some(RollupRange { from, to })
+    }
+}
+
+pub fn rollup_exists_sql(namespace: &str, meter_slug: &str) -> String {
+    let rollup_name = get_meter_rollup_name(namespace, meter_slug);
+
+    format!(
+        "SELECT count() AS count FROM system.tables WHERE database = '{}' AND name = '{}'",
+        DATABASE,
+        escape_sql_string(
+            rollup_name
+                .strip_prefix(&format!("{}.", DATABASE))
+                .unwrap_or(&rollup_name)
+        )
+    )
+}
+
+/// The windows of the query period from the meter view, or when a rollup range is given,
+/// from the daily rollup over that range and from the minute view for the rest of the period (the current day)
+pub fn query_meter_view_sql(
+    params: QueryMeterParams,
+    rollup: Option<RollupRange>,
+) -> Result<String, String> {
     let view_name = get_meter_view_name(&params.namespace, &params.meter_slug);
 
     let mut select_columns = Vec::new();
@@ -118,13 +183,34 @@ pub fn query_meter_view_sql(params: QueryMeterParams) -> Result<String, String>
 
     // Time filter clauses
     // TODO limit & probably make from required
-    where_clauses.push(format!("windowstart >= {}", params.from.timestamp()));
-    if let Some(to) = params.to {
-        where_clauses.push(format!("windowend <= {}", to.timestamp()));
-    }
+    let period_filter = match params.to {
+        Some(to) => format!(
+            "windowstart >= {} AND windowend <= {}",
+            params.from.timestamp(),
+            to.timestamp()
+        ),
+        None => format!("windowstart >= {}", params.from.timestamp()),
+    };
+
+    let source = match rollup {
+        None => {
+            where_clauses.push(period_filter);
+            view_name
+        }
+        Some(rollup) => format!(
+            "(SELECT * FROM {} WHERE windowstart >= {} AND windowend <= {} UNION ALL SELECT * FROM {} WHERE ({}) AND (windowend <= {} OR windowstart >= {}))",
+            get_meter_rollup_name(&params.namespace, &params.meter_slug),
+            rollup.from.timestamp(),
+            rollup.to.timestamp(),
+            view_name,
+            period_filter,
+            rollup.from.timestamp(),
+            rollup.to.timestamp(),
+        ),
+    };
 
     // Constructing the final SQL query
-    let mut sql = format!("SELECT {} FROM {}", select_columns.join(", "), view_name);
+    let mut sql = format!("SELECT {} FROM {}", select_columns.join(", "), source);
     if !where_clauses.is_empty() {
         sql.push_str(&format!(" WHERE {}", where_clauses.join(" AND ")));
     }
@@ -137,3 +223,110 @@ pub fn query_meter_view_sql(params: QueryMeterParams) -> Result<String, String>
 
     Ok(sql)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::Customer;
+    use chrono::TimeZone;
+    use std::collections::HashMap;
+
+    fn params(from: DateTime<Utc>, to: Option<DateTime<Utc>>) -> QueryMeterParams {
+        QueryMeterParams {
+            aggregation: MeterAggregation::Sum,
+            namespace: "test_namespace".to_string(),
+            meter_slug: "test_slug".to_string(),
+            event_name: "test_event".to_string(),
+            customers: vec![Customer {
+                id: "cus_1".to_string(),
+                external_id: "ext_1".to_string(),
+            }],
+            filter_group_by: HashMap::new(),
+            group_by: vec![],
+            window_size: None,
+            window_time_zone: None,
+            from,
+            to,
+        }
+    }
+
+    fn at(day: u32, hour: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 10, day, hour, 0, 0).unwrap()
+    }
+
+    #[test]
+    fn test_rollup_range_of_query() {
+        let now = at(20, 15);
+
+        assert_eq!(
+            RollupRange::of_query(&params(at(1, 0), Some(at(31, 0))), now),
+            Some(RollupRange {
+                from: at(1, 0),
+                to: at(20, 0)
+            })
+        );
+        // the partial days are left to the minute view
+        assert_eq!(
+            RollupRange::of_query(&params(at(1, 6), Some(at(10, 6))), now),
+            Some(RollupRange {
+                from: at(2, 0),
+                to: at(10, 0)
+            })
+        );
+        assert_eq!(RollupRange::of_query(&params(at(20, 0), None), now), None);
+
+        let hourly = QueryMeterParams {
+            window_size: Some(WindowSize::Hour),
+            ..params(at(1, 0), Some(at(31, 0)))
+        };
+        assert_eq!(RollupRange::of_query(&hourly, now), None);
+
+        let other_time_zone = QueryMeterParams {
+            window_size: Some(WindowSize::Day),
+            window_time_zone: Some("Europe/Paris".to_string()),
+            ..params(at(1, 0), Some(at(31, 0)))
+        };
+        assert_eq!(RollupRange::of_query(&other_time_zone, now), None);
+    }
+
+    #[test]
+    fn test_query_meter_view_sql_with_rollup() {
+        let sql = query_meter_view_sql(
+            params(at(1, 0), Some(at(31, 0))),
+            Some(RollupRange {
+                from: at(1, 0),
+                to: at(20, 0),
+            }),
+        )
+        .unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT min(windowstart), max(windowend), sumMerge(value) AS value, customer_id \
+            FROM (SELECT * FROM meteroid.METER_NStestnamespace_Mtestslug_DAILY WHERE windowstart >= 1727740800 AND windowend <= 1729382400 \
+            UNION ALL SELECT * FROM meteroid.METER_NStestnamespace_Mtestslug WHERE (windowstart >= 1727740800 AND windowend <= 1730332800) \
+            AND (windowend <= 1727740800 OR windowstart >= 1729382400)) \
+            WHERE (customer_id = 'cus_1') GROUP BY customer_id"
+        );
+    }
+
+    #[test]
+    fn test_query_meter_view_sql_without_rollup() {
+        let sql = query_meter_view_sql(params(at(1, 0), Some(at(31, 0))), None).unwrap();
+
+        assert_eq!(
+            sql,
+            "SELECT min(windowstart), max(windowend), sumMerge(value) AS value, customer_id \
+            FROM meteroid.METER_NStestnamespace_Mtestslug \
+            WHERE (customer_id = 'cus_1') AND windowstart >= 1727740800 AND windowend <= 1730332800 GROUP BY customer_id"
+        );
+    }
+
+    #[test]
+    fn test_rollup_exists_sql() {
+        assert_eq!(
+            rollup_exists_sql("test_namespace", "test_slug"),
+            "SELECT count() AS count FROM system.tables WHERE database = 'meteroid' AND name = 'METER_NStestnamespace_Mtestslug_DAILY'"
+        );
+    }
+}
@@ -0,0 +1,252 @@
+use crate::connectors::clickhouse::sql::init::{
+    get_events_table_name, get_restored_events_table_name, get_retention_dictionary_name,
+    get_retention_table_name,
+};
+use crate::connectors::clickhouse::sql::{
+    datetime64_literal, encode_identifier, escape_sql_string,
+};
+use crate::domain::RetentionPolicy;
+use chrono::{DateTime, Utc};
+
+const POLICY_COLUMNS: &str = "tenant_id, retention_days, archive, archived_until, updated_at";
+
+const EVENT_COLUMNS: &str =
+    "tenant_id, event_id, event_name, customer_id, event_timestamp, properties";
+
+/// The object storage receiving the archived events, as one Parquet file per tenant and archival
+#[derive(Debug, Clone)]
+pub struct ArchiveStorage {
+    /// ex: https://bucket.s3.amazonaws.com/events
+    pub url: String,
+    pub access_key_id: Option<String>,
+    pub secret_access_key: Option<String>,
+}
+
+impl ArchiveStorage {
+    fn s3_function(&self, path: &str) -> String {
+        let url = format!("{}/{}", self.url.trim_end_matches('/'), path);
+
+        match (&self.access_key_id, &self.secret_access_key) {
+            (Some(key_id), Some(secret)) => format!(
+                "s3('{}', '{}', '{}', 'Parquet')",
+                escape_sql_string(&url),
+                escape_sql_string(key_id),
+                escape_sql_string(secret)
+            ),
+            _ => format!("s3('{}', 'Parquet')", escape_sql_string(&url)),
+        }
+    }
+}
+
+fn tenant_filter(tenant_id: &str) -> String {
+    format!("tenant_id = '{}'", escape_sql_string(tenant_id))
+}
+
+pub fn upsert_policy_sql(policy: &RetentionPolicy) -> String {
+    format!(
+        "INSERT INTO {} ({}) VALUES ('{}', {}, {}, {}, now64(3))",
+        get_retention_table_name(),
+        POLICY_COLUMNS,
+        escape_sql_string(&policy.tenant_id),
+        policy.retention_days,
+        policy.archive as u8,
+        policy
+            .archived_until
+            .map(|until| datetime64_literal(&until.naive_utc(), 9))
+            .unwrap_or("NULL".to_string()),
+    )
+}
+
+pub fn select_policy_sql(tenant_id: &str) -> String {
+    format!(
+        "SELECT {} FROM {} FINAL WHERE {} LIMIT 1",
+        POLICY_COLUMNS,
+        get_retention_table_name(),
+        tenant_filter(tenant_id)
+    )
+}
+
+pub fn list_archived_policies_sql() -> String {
+    format!(
+        "SELECT {} FROM {} FINAL WHERE retention_days > 0 AND archive = 1",
+        POLICY_COLUMNS,
+        get_retention_table_name()
+    )
+}
+
+/// The tenants without retention policy that have events before `until`
+pub fn list_tenants_without_policy_sql(until: &DateTime<Utc>) -> String {
+    format!(
+        "SELECT DISTINCT tenant_id FROM {} WHERE event_timestamp < {} AND tenant_id NOT IN (SELECT tenant_id FROM {} FINAL)",
+        get_events_table_name(),
+        datetime64_literal(&until.naive_utc(), 9),
+        get_retention_table_name()
+    )
+}
+
+/// So that the TTL applies the new policy without waiting for the dictionary lifetime
+pub fn reload_retention_dictionary_sql() -> String {
+    format!(
+        "SYSTEM RELOAD DICTIONARY {}",
+        get_retention_dictionary_name()
+    )
+}
+
+fn archive_path(tenant_id: &str, from: Option<&DateTime<Utc>>, until: &DateTime<Utc>) -> String {
+    format!(
+        "{}/{}_{}.parquet",
+        encode_identifier(tenant_id),
+        from.map(|from| from.timestamp()).unwrap_or(0),
+        until.timestamp()
+    )
+}
+
+/// Exports the events of the tenant between the previous archival and `until`.
+/// An archival that is retried overwrites its file
+pub fn archive_events_sql(
+    storage: &ArchiveStorage,
+    tenant_id: &str,
+    from: Option<&DateTime<Utc>>,
+    until: &DateTime<Utc>,
+) -> String {
+    let from_filter = from
+        .map(|from| {
+            format!(
+                " AND event_timestamp >= {}",
+                datetime64_literal(&from.naive_utc(), 9)
+            )
+        })
+        .unwrap_or_default();
+
+    format!(
+        "INSERT INTO FUNCTION {} SELECT {} FROM {} WHERE {}{} AND event_timestamp < {} SETTINGS s3_truncate_on_insert = 1",
+        storage.s3_function(&archive_path(tenant_id, from, until)),
+        EVENT_COLUMNS,
+        get_events_table_name(),
+        tenant_filter(tenant_id),
+        from_filter,
+        datetime64_literal(&until.naive_utc(), 9)
+    )
+}
+
+pub fn delete_archived_events_sql(tenant_id: &str, until: &DateTime<Utc>) -> String {
+    format!(
+        "ALTER TABLE {} DELETE WHERE {} AND event_timestamp < {}",
+        get_events_table_name(),
+        tenant_filter(tenant_id),
+        datetime64_literal(&until.naive_utc(), 9)
+    )
+}
+
+pub fn delete_restored_events_sql(
+    tenant_id: &str,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> String {
+    format!(
+        "ALTER TABLE {} DELETE WHERE {} AND event_timestamp >= {} AND event_timestamp < {}",
+        get_restored_events_table_name(),
+        tenant_filter(tenant_id),
+        datetime64_literal(&from.naive_utc(), 9),
+        datetime64_literal(&to.naive_utc(), 9)
+    )
+}
+
+/// Imports the archived events of the tenant over the period, to re-aggregate them
+pub fn restore_events_sql(
+    storage: &ArchiveStorage,
+    tenant_id: &str,
+    from: &DateTime<Utc>,
+    to: &DateTime<Utc>,
+) -> String {
+    format!(
+        "INSERT INTO {} ({}) SELECT {} FROM {} WHERE {} AND event_timestamp >= {} AND event_timestamp < {}",
+        get_restored_events_table_name(),
+        EVENT_COLUMNS,
+        EVENT_COLUMNS,
+        storage.s3_function(&format!("{}/*.parquet", encode_identifier(tenant_id))),
+        tenant_filter(tenant_id),
+        datetime64_literal(&from.naive_utc(), 9),
+        datetime64_literal(&to.naive_utc(), 9)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    const TENANT_ID: &str = "018c2c82-3df1-7e84-9e05-6e141d0e751a";
+
+    fn storage() -> ArchiveStorage {
+        ArchiveStorage {
+            url: "https://bucket.s3.amazonaws.com/events/".to_string(),
+            access_key_id: Some("key".to_string()),
+            secret_access_key: Some("secret".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_upsert_policy_sql() {
+        let policy = RetentionPolicy {
+            tenant_id: TENANT_ID.to_string(),
+            retention_days: 90,
+            archive: true,
+            archived_until: None,
+        };
+
+        assert_eq!(
+            upsert_policy_sql(&policy),
+            "INSERT INTO meteroid.raw_events_retention (tenant_id, retention_days, archive, archived_until, updated_at) VALUES ('018c2c82-3df1-7e84-9e05-6e141d0e751a', 90, 1, NULL, now64(3))"
+        );
+    }
+
+    #[test]
+    fn test_archive_events_sql() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let until = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            archive_events_sql(&storage(), TENANT_ID, Some(&from), &until),
+            "INSERT INTO FUNCTION s3('https://bucket.s3.amazonaws.com/events/018c2c823df17e849e056e141d0e751a/1704067200_1706745600.parquet', 'key', 'secret', 'Parquet') \
+            SELECT tenant_id, event_id, event_name, customer_id, event_timestamp, properties FROM meteroid.raw_events \
+            WHERE tenant_id = '018c2c82-3df1-7e84-9e05-6e141d0e751a' \
+            AND event_timestamp >= toDateTime64('2024-01-01 00:00:00.000000000', 9, 'UTC') \
+            AND event_timestamp < toDateTime64('2024-02-01 00:00:00.000000000', 9, 'UTC') \
+            SETTINGS s3_truncate_on_insert = 1"
+        );
+    }
+
+    #[test]
+    fn test_list_tenants_without_policy_sql() {
+        let until = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+
+        assert_eq!(
+            list_tenants_without_policy_sql(&until),
+            "SELECT DISTINCT tenant_id FROM meteroid.raw_events \
+            WHERE event_timestamp < toDateTime64('2024-02-01 00:00:00.000000000', 9, 'UTC') \
+            AND tenant_id NOT IN (SELECT tenant_id FROM meteroid.raw_events_retention FINAL)"
+        );
+    }
+
+    #[test]
+    fn test_restore_events_sql() {
+        let from = Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap();
+        let to = Utc.with_ymd_and_hms(2024, 2, 1, 0, 0, 0).unwrap();
+        let storage = ArchiveStorage {
+            access_key_id: None,
+            secret_access_key: None,
+            ..storage()
+        };
+
+        assert_eq!(
+            restore_events_sql(&storage, TENANT_ID, &from, &to),
+            "INSERT INTO meteroid.raw_restored_events (tenant_id, event_id, event_name, customer_id, event_timestamp, properties) \
+            SELECT tenant_id, event_id, event_name, customer_id, event_timestamp, properties \
+            FROM s3('https://bucket.s3.amazonaws.com/events/018c2c823df17e849e056e141d0e751a/*.parquet', 'Parquet') \
+            WHERE tenant_id = '018c2c82-3df1-7e84-9e05-6e141d0e751a' \
+            AND event_timestamp >= toDateTime64('2024-01-01 00:00:00.000000000', 9, 'UTC') \
+            AND event_timestamp < toDateTime64('2024-02-01 00:00:00.000000000', 9, 'UTC')"
+        );
+    }
+}
@@ -15,9 +15,15 @@ pub enum ConnectorError {
     #[error("Failed to register meter")]
     RegisterError,
 
+    #[error("Failed to migrate meter")]
+    MigrateError,
+
     #[error("Failed to query metering database")]
     QueryError,
 
+    #[error("Failed to write to metering database")]
+    WriteError,
+
     #[error("Invalid query : {0}")]
     InvalidQuery(String),
 }
@@ -3,8 +3,14 @@ mod errors;
 pub mod clickhouse;
 
 use crate::connectors::errors::ConnectorError;
-use crate::domain::{Meter, QueryMeterParams, Usage};
+use crate::domain::{
+    IngestionHealth, Meter, MeterViewsDrift, QueryMeterParams, RetentionPolicy, Usage,
+};
+use crate::ingest::domain::{BackfillSession, MergedBackfill, ProcessedEvent};
+use chrono::{DateTime, Utc};
 use error_stack::Result;
+use std::time::Duration;
+use uuid::Uuid;
 
 use tonic::async_trait;
 
@@ -12,7 +18,93 @@ use tonic::async_trait;
 pub trait Connector {
     async fn register_meter(&self, meter: Meter) -> Result<(), ConnectorError>;
 
+    /// Rebuilds the view of an existing meter after a change of its aggregation or dimensions, from the raw events.
+    /// Registers the meter if it has no view
+    async fn migrate_meter(&self, meter: Meter) -> Result<(), ConnectorError>;
+
     async fn query_meter(&self, params: QueryMeterParams) -> Result<Vec<Usage>, ConnectorError>;
+
+    async fn save_backfill(&self, session: &BackfillSession) -> Result<(), ConnectorError>;
+
+    async fn find_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<Option<BackfillSession>, ConnectorError>;
+
+    async fn stage_backfill_events(
+        &self,
+        backfill_id: &Uuid,
+        events: Vec<ProcessedEvent>,
+    ) -> Result<(), ConnectorError>;
+
+    /// The number of distinct events among `event_ids` already staged for the backfill
+    async fn count_staged_backfill_events(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+        event_ids: &[String],
+    ) -> Result<u64, ConnectorError>;
+
+    /// Merges the staged events into the raw events, skipping the ones already ingested.
+    /// Returns the number of merged events and their time range per customer
+    async fn merge_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<MergedBackfill, ConnectorError>;
+
+    async fn discard_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<(), ConnectorError>;
+
+    /// Deletes the raw and staged events of the tenant, and empties its meters
+    async fn purge_tenant_events(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<(), ConnectorError>;
+
+    /// Compares the meter views of the tenant to the given meters
+    async fn check_meter_views(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<MeterViewsDrift, ConnectorError>;
+
+    /// Replaces the retention of the raw events of the tenant. The archival progress is kept
+    async fn set_retention_policy(
+        &self,
+        policy: RetentionPolicy,
+    ) -> Result<RetentionPolicy, ConnectorError>;
+
+    async fn find_retention_policy(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Option<RetentionPolicy>, ConnectorError>;
+
+    /// Exports the expired events of the tenants with archival to the object storage, then deletes them.
+    /// The tenants without policy are archived after the default retention, if configured
+    async fn archive_expired_events(&self) -> Result<(), ConnectorError>;
+
+    /// Imports the archived events of the tenant over the period, so that the meter migrations re-aggregate them
+    async fn restore_archived_events(
+        &self,
+        tenant_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<(), ConnectorError>;
+
+    /// The latest events of the tenants (all when empty) and the recent insert errors of the storage
+    async fn ingestion_health(
+        &self,
+        tenant_ids: &[String],
+    ) -> Result<IngestionHealth, ConnectorError>;
+
+    /// The 90th percentile duration of the recent inserts into the events table, none without recent insert
+    async fn insert_latency(&self) -> Result<Option<Duration>, ConnectorError>;
 }
 
 pub struct PrintConnector {}
@@ -24,8 +116,143 @@ impl Connector for PrintConnector {
         Ok(())
     }
 
+    async fn migrate_meter(&self, meter: Meter) -> Result<(), ConnectorError> {
+        println!("Migrating meter: {:?}", meter);
+        Ok(())
+    }
+
     async fn query_meter(&self, params: QueryMeterParams) -> Result<Vec<Usage>, ConnectorError> {
         println!("Querying meter: {:?}", params);
         Ok(vec![])
     }
+
+    async fn save_backfill(&self, session: &BackfillSession) -> Result<(), ConnectorError> {
+        println!("Saving backfill: {:?}", session);
+        Ok(())
+    }
+
+    async fn find_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<Option<BackfillSession>, ConnectorError> {
+        println!("Finding backfill: {} / {}", tenant_id, backfill_id);
+        Ok(None)
+    }
+
+    async fn stage_backfill_events(
+        &self,
+        backfill_id: &Uuid,
+        events: Vec<ProcessedEvent>,
+    ) -> Result<(), ConnectorError> {
+        println!(
+            "Staging {} events for backfill {}",
+            events.len(),
+            backfill_id
+        );
+        Ok(())
+    }
+
+    async fn count_staged_backfill_events(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+        event_ids: &[String],
+    ) -> Result<u64, ConnectorError> {
+        println!(
+            "Counting staged events: {} / {} ({} ids)",
+            tenant_id,
+            backfill_id,
+            event_ids.len()
+        );
+        Ok(0)
+    }
+
+    async fn merge_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<MergedBackfill, ConnectorError> {
+        println!("Merging backfill: {} / {}", tenant_id, backfill_id);
+        Ok(MergedBackfill::default())
+    }
+
+    async fn discard_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &Uuid,
+    ) -> Result<(), ConnectorError> {
+        println!("Discarding backfill: {} / {}", tenant_id, backfill_id);
+        Ok(())
+    }
+
+    async fn purge_tenant_events(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<(), ConnectorError> {
+        println!(
+            "Purging events of tenant {} and {} meters",
+            tenant_id,
+            meter_slugs.len()
+        );
+        Ok(())
+    }
+
+    async fn check_meter_views(
+        &self,
+        tenant_id: &str,
+        meter_slugs: &[String],
+    ) -> Result<MeterViewsDrift, ConnectorError> {
+        println!(
+            "Checking the views of {} meters of tenant {}",
+            meter_slugs.len(),
+            tenant_id
+        );
+        Ok(MeterViewsDrift::default())
+    }
+    async fn set_retention_policy(
+        &self,
+        policy: RetentionPolicy,
+    ) -> Result<RetentionPolicy, ConnectorError> {
+        println!("Setting retention policy: {:?}", policy);
+        Ok(policy)
+    }
+
+    async fn find_retention_policy(
+        &self,
+        tenant_id: &str,
+    ) -> Result<Option<RetentionPolicy>, ConnectorError> {
+        println!("Finding retention policy of tenant {}", tenant_id);
+        Ok(None)
+    }
+
+    async fn archive_expired_events(&self) -> Result<(), ConnectorError> {
+        println!("Archiving expired events");
+        Ok(())
+    }
+
+    async fn restore_archived_events(
+        &self,
+        tenant_id: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Result<(), ConnectorError> {
+        println!(
+            "Restoring archived events of tenant {} from {} to {}",
+            tenant_id, from, to
+        );
+        Ok(())
+    }
+    async fn ingestion_health(
+        &self,
+        tenant_ids: &[String],
+    ) -> Result<IngestionHealth, ConnectorError> {
+        println!("Checking the ingestion of {} tenants", tenant_ids.len());
+        Ok(IngestionHealth::default())
+    }
+
+    async fn insert_latency(&self) -> Result<Option<Duration>, ConnectorError> {
+        Ok(None)
+    }
 }
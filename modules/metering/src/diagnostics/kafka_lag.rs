@@ -0,0 +1,90 @@
+use crate::config::KafkaConfig;
+use rdkafka::consumer::{BaseConsumer, Consumer};
+use rdkafka::error::KafkaError;
+use rdkafka::{Offset, TopicPartitionList};
+use std::sync::Arc;
+use std::time::Duration;
+
+const KAFKA_TIMEOUT: Duration = Duration::from_secs(5);
+
+#[derive(Debug, Clone)]
+pub struct PartitionLag {
+    pub partition: i32,
+    pub high_watermark: i64,
+    pub committed_offset: Option<i64>,
+    pub lag: i64,
+}
+
+/// Reads the offsets committed by a consumer group, without joining it
+#[derive(Clone)]
+pub struct KafkaLagProbe {
+    consumer: Arc<BaseConsumer>,
+    pub topic: String,
+}
+
+impl KafkaLagProbe {
+    pub fn new(config: &KafkaConfig, group_id: &str) -> Result<Self, KafkaError> {
+        let consumer: BaseConsumer = config
+            .kafka_connection
+            .to_client_config()
+            .set("group.id", group_id)
+            .set("enable.auto.commit", "false")
+            .create()?;
+
+        Ok(KafkaLagProbe {
+            consumer: Arc::new(consumer),
+            topic: config.kafka_topic.clone(),
+        })
+    }
+
+    pub async fn partition_lags(&self) -> Result<Vec<PartitionLag>, KafkaError> {
+        let probe = self.clone();
+
+        // the rdkafka calls are blocking
+        tokio::task::spawn_blocking(move || probe.partition_lags_blocking())
+            .await
+            .map_err(|_| KafkaError::Canceled)?
+    }
+
+    fn partition_lags_blocking(&self) -> Result<Vec<PartitionLag>, KafkaError> {
+        let metadata = self
+            .consumer
+            .fetch_metadata(Some(&self.topic), KAFKA_TIMEOUT)?;
+
+        let partitions: Vec<i32> = metadata
+            .topics()
+            .iter()
+            .flat_map(|topic| topic.partitions().iter().map(|p| p.id()))
+            .collect();
+
+        let mut assignment = TopicPartitionList::new();
+        for partition in &partitions {
+            assignment.add_partition(&self.topic, *partition);
+        }
+        let committed = self.consumer.committed_offsets(assignment, KAFKA_TIMEOUT)?;
+
+        partitions
+            .into_iter()
+            .map(|partition| {
+                let (low, high) =
+                    self.consumer
+                        .fetch_watermarks(&self.topic, partition, KAFKA_TIMEOUT)?;
+
+                let committed_offset =
+                    committed
+                        .find_partition(&self.topic, partition)
+                        .and_then(|elem| match elem.offset() {
+                            Offset::Offset(offset) => Some(offset),
+                            _ => None,
+                        });
+
+                Ok(PartitionLag {
+                    partition,
+                    high_watermark: high,
+                    committed_offset,
+                    lag: (high - committed_offset.unwrap_or(low)).max(0),
+                })
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,23 @@
+use crate::connectors::Connector;
+use crate::diagnostics::service::DiagnosticsService;
+use crate::ingest::shedding::LoadShedder;
+use metering_grpc::meteroid::metering::v1::diagnostics_service_server::DiagnosticsServiceServer;
+use std::sync::Arc;
+
+#[cfg(feature = "kafka")]
+pub mod kafka_lag;
+pub mod service;
+
+pub fn service(
+    connector: Arc<dyn Connector + Send + Sync>,
+    shedder: Arc<LoadShedder>,
+    #[cfg(feature = "kafka")] kafka_lag_probe: Option<kafka_lag::KafkaLagProbe>,
+) -> DiagnosticsServiceServer<DiagnosticsService> {
+    let inner = DiagnosticsService {
+        connector,
+        shedder,
+        #[cfg(feature = "kafka")]
+        kafka_lag_probe,
+    };
+    DiagnosticsServiceServer::new(inner)
+}
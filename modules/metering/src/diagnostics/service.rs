@@ -0,0 +1,103 @@
+use metering_grpc::meteroid::metering::v1::diagnostics_service_server::DiagnosticsService as DiagnosticsServiceGrpc;
+use std::sync::Arc;
+
+use metering_grpc::meteroid::metering::v1::get_ingestion_health_response as grpc;
+use metering_grpc::meteroid::metering::v1::{
+    GetIngestionHealthRequest, GetIngestionHealthResponse,
+};
+use tonic::{Request, Response, Status};
+
+use crate::connectors::Connector;
+#[cfg(feature = "kafka")]
+use crate::diagnostics::kafka_lag::KafkaLagProbe;
+use crate::ingest::shedding::LoadShedder;
+use crate::utils::datetime_to_timestamp;
+
+#[derive(Clone)]
+pub struct DiagnosticsService {
+    pub connector: Arc<dyn Connector + Send + Sync>,
+    pub shedder: Arc<LoadShedder>,
+    #[cfg(feature = "kafka")]
+    pub kafka_lag_probe: Option<KafkaLagProbe>,
+}
+
+#[tonic::async_trait]
+impl DiagnosticsServiceGrpc for DiagnosticsService {
+    #[tracing::instrument(skip_all)]
+    async fn get_ingestion_health(
+        &self,
+        request: Request<GetIngestionHealthRequest>,
+    ) -> Result<Response<GetIngestionHealthResponse>, Status> {
+        let req = request.into_inner();
+
+        let health = self
+            .connector
+            .ingestion_health(&req.tenant_ids)
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to check ingestion health")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        #[allow(unused_mut)]
+        let mut response = GetIngestionHealthResponse {
+            topic: None,
+            partitions: vec![],
+            total_lag: 0,
+            tenants: health
+                .tenants
+                .into_iter()
+                .map(|t| grpc::TenantIngestion {
+                    tenant_id: t.tenant_id,
+                    last_event_timestamp: Some(datetime_to_timestamp(t.last_event_timestamp)),
+                })
+                .collect(),
+            insert_errors: health
+                .insert_errors
+                .into_iter()
+                .map(|e| grpc::InsertError {
+                    time: Some(datetime_to_timestamp(e.time)),
+                    message: e.message,
+                })
+                .collect(),
+            load_shedding: self.shedder.is_enabled().then(|| {
+                let state = self.shedder.state();
+                grpc::LoadShedding {
+                    shedding: state.shedding,
+                    reason: state.reason,
+                    since: state.since.map(datetime_to_timestamp),
+                    retry_after_seconds: state.retry_after.as_secs(),
+                    insert_latency_ms: state
+                        .pressure
+                        .insert_latency
+                        .map(|latency| latency.as_millis() as u64),
+                    kafka_lag: state.pressure.kafka_lag,
+                }
+            }),
+        };
+
+        #[cfg(feature = "kafka")]
+        if let Some(probe) = &self.kafka_lag_probe {
+            let lags = probe.partition_lags().await.map_err(|e| {
+                Status::internal("Failed to read the kafka consumer lag")
+                    .set_source(Arc::new(e))
+                    .clone()
+            })?;
+
+            response.topic = Some(probe.topic.clone());
+            response.total_lag = lags.iter().map(|l| l.lag).sum();
+            response.partitions = lags
+                .into_iter()
+                .map(|l| grpc::PartitionLag {
+                    partition: l.partition,
+                    high_watermark: l.high_watermark,
+                    committed_offset: l.committed_offset,
+                    lag: l.lag,
+                })
+                .collect();
+        }
+
+        Ok(Response::new(response))
+    }
+}
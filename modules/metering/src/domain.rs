@@ -74,3 +74,55 @@ pub struct Usage {
     pub customer_id: String,
     pub group_by: HashMap<String, Option<String>>,
 }
+
+/// The meters of a tenant without view, and its views matching no meter
+#[derive(Debug, Default, PartialEq, Eq)]
+pub struct MeterViewsDrift {
+    pub missing_meter_slugs: Vec<String>,
+    pub orphaned_views: Vec<String>,
+}
+
+/// How long the raw events of a tenant are kept. The usage aggregated by the meters is kept regardless
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RetentionPolicy {
+    pub tenant_id: String,
+    /// 0 keeps the events
+    pub retention_days: u32,
+    /// the expired events are exported to the object storage before their deletion
+    pub archive: bool,
+    /// the events before that point are archived and deleted
+    pub archived_until: Option<DateTime<Utc>>,
+}
+
+impl RetentionPolicy {
+    /// The point before which the events are expired, at day boundary
+    pub fn expires_before(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        if self.retention_days == 0 {
+            return None;
+        }
+
+        (now.date_naive() - chrono::Days::new(self.retention_days as u64))
+            .and_hms_opt(0, 0, 0)
+            .map(|dt| dt.and_utc())
+    }
+}
+
+/// The most recent event of a tenant
+#[derive(Debug, Clone)]
+pub struct TenantIngestion {
+    pub tenant_id: String,
+    pub last_event_timestamp: DateTime<Utc>,
+}
+
+/// An error of the metering storage while inserting the ingested events
+#[derive(Debug, Clone)]
+pub struct InsertError {
+    pub time: DateTime<Utc>,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct IngestionHealth {
+    pub tenants: Vec<TenantIngestion>,
+    pub insert_errors: Vec<InsertError>,
+}
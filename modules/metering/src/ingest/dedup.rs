@@ -0,0 +1,200 @@
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::config::IngestDedupConfig;
+use crate::ingest::domain::ProcessedEvent;
+
+#[derive(Debug, Default)]
+struct SeenEvents {
+    expirations: HashMap<String, Instant>,
+    // in order of reservation, possibly with the entries of released keys
+    order: VecDeque<(Instant, String)>,
+}
+
+impl SeenEvents {
+    fn evict_expired(&mut self, now: Instant) {
+        while let Some((expires_at, _)) = self.order.front() {
+            if *expires_at > now {
+                break;
+            }
+            self.evict_oldest();
+        }
+    }
+
+    fn evict_oldest(&mut self) {
+        if let Some((expires_at, key)) = self.order.pop_front() {
+            // the key may have been released then reserved again since
+            if self.expirations.get(&key) == Some(&expires_at) {
+                self.expirations.remove(&key);
+            }
+        }
+    }
+}
+
+/// Drops the events whose id was already ingested for the tenant within the window, as sent again by the client retries.
+/// This is tracked per instance, the retries of a client are expected to reach the same instance within the window.
+/// Beyond the capacity, the oldest ids are forgotten first
+#[derive(Debug)]
+pub struct EventDeduplicator {
+    window: Duration,
+    capacity: usize,
+    seen: Mutex<SeenEvents>,
+}
+
+impl From<&IngestDedupConfig> for EventDeduplicator {
+    fn from(config: &IngestDedupConfig) -> Self {
+        EventDeduplicator::new(
+            Duration::from_secs(config.window_seconds),
+            config.max_tracked_events,
+        )
+    }
+}
+
+impl EventDeduplicator {
+    pub fn new(window: Duration, capacity: usize) -> Self {
+        EventDeduplicator {
+            window,
+            capacity,
+            seen: Mutex::new(SeenEvents::default()),
+        }
+    }
+
+    fn is_enabled(&self) -> bool {
+        !self.window.is_zero() && self.capacity > 0
+    }
+
+    /// Splits the events into the events to ingest and the duplicates, reserving the ids of the events to ingest.
+    /// The ids must be released if their events could not be ingested, so that the retries are accepted
+    pub fn reserve(
+        &self,
+        events: Vec<ProcessedEvent>,
+    ) -> (Vec<ProcessedEvent>, Vec<ProcessedEvent>) {
+        self.reserve_at(events, Instant::now())
+    }
+
+    fn reserve_at(
+        &self,
+        events: Vec<ProcessedEvent>,
+        now: Instant,
+    ) -> (Vec<ProcessedEvent>, Vec<ProcessedEvent>) {
+        if !self.is_enabled() {
+            return (events, vec![]);
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        seen.evict_expired(now);
+
+        let expires_at = now + self.window;
+
+        events.into_iter().partition(|event| {
+            let key = event.key();
+            if seen.expirations.contains_key(&key) {
+                return false;
+            }
+            while seen.expirations.len() >= self.capacity {
+                seen.evict_oldest();
+            }
+            seen.expirations.insert(key.clone(), expires_at);
+            seen.order.push_back((expires_at, key));
+            true
+        })
+    }
+
+    /// Releases the keys of the events, as per `ProcessedEvent::key`
+    pub fn release(&self, keys: impl IntoIterator<Item = String>) {
+        if !self.is_enabled() {
+            return;
+        }
+
+        let mut seen = self.seen.lock().unwrap_or_else(|e| e.into_inner());
+        for key in keys {
+            seen.expirations.remove(&key);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn event(event_id: &str) -> ProcessedEvent {
+        ProcessedEvent {
+            event_id: event_id.to_string(),
+            tenant_id: "tenant".to_string(),
+            ..Default::default()
+        }
+    }
+
+    fn ids(events: &[ProcessedEvent]) -> Vec<&str> {
+        events.iter().map(|e| e.event_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_reserve_drops_the_duplicates_within_the_window() {
+        let dedup = EventDeduplicator::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+
+        let (accepted, duplicates) =
+            dedup.reserve_at(vec![event("a"), event("b"), event("a")], now);
+        assert_eq!(ids(&accepted), vec!["a", "b"]);
+        assert_eq!(ids(&duplicates), vec!["a"]);
+
+        let (accepted, duplicates) =
+            dedup.reserve_at(vec![event("b"), event("c")], now + Duration::from_secs(59));
+        assert_eq!(ids(&accepted), vec!["c"]);
+        assert_eq!(ids(&duplicates), vec!["b"]);
+
+        let (accepted, _) = dedup.reserve_at(vec![event("a")], now + Duration::from_secs(60));
+        assert_eq!(ids(&accepted), vec!["a"]);
+    }
+
+    #[test]
+    fn test_reserve_is_per_tenant() {
+        let dedup = EventDeduplicator::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+
+        let other_tenant = ProcessedEvent {
+            tenant_id: "other".to_string(),
+            ..event("a")
+        };
+
+        let (accepted, duplicates) = dedup.reserve_at(vec![event("a"), other_tenant], now);
+        assert_eq!(accepted.len(), 2);
+        assert!(duplicates.is_empty());
+    }
+
+    #[test]
+    fn test_released_events_are_accepted_again() {
+        let dedup = EventDeduplicator::new(Duration::from_secs(60), 100);
+        let now = Instant::now();
+
+        dedup.reserve_at(vec![event("a"), event("b")], now);
+        dedup.release(vec![event("a").key()]);
+
+        let (accepted, duplicates) = dedup.reserve_at(vec![event("a"), event("b")], now);
+        assert_eq!(ids(&accepted), vec!["a"]);
+        assert_eq!(ids(&duplicates), vec!["b"]);
+    }
+
+    #[test]
+    fn test_capacity_forgets_the_oldest() {
+        let dedup = EventDeduplicator::new(Duration::from_secs(60), 2);
+        let now = Instant::now();
+
+        dedup.reserve_at(vec![event("a"), event("b"), event("c")], now);
+
+        let (accepted, duplicates) = dedup.reserve_at(vec![event("a"), event("c")], now);
+        assert_eq!(ids(&accepted), vec!["a"]);
+        assert_eq!(ids(&duplicates), vec!["c"]);
+    }
+
+    #[test]
+    fn test_disabled() {
+        let dedup = EventDeduplicator::new(Duration::ZERO, 100);
+
+        let (accepted, duplicates) = dedup.reserve(vec![event("a"), event("a")]);
+        assert_eq!(accepted.len(), 2);
+        assert!(duplicates.is_empty());
+    }
+}
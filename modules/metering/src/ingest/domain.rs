@@ -1,8 +1,10 @@
-use chrono::NaiveDateTime;
-use std::collections::HashMap;
+use chrono::{DateTime, NaiveDateTime, Utc};
+use std::collections::{BTreeMap, HashMap};
 
-use metering_grpc::meteroid::metering::v1::Event;
+use metering_grpc::meteroid::metering::v1::event::CustomerId;
+use metering_grpc::meteroid::metering::v1::{Event, IngestFailureCode};
 use serde::Serialize;
+use uuid::Uuid;
 
 #[derive(Clone, Default, Debug, Serialize, Eq, PartialEq)]
 pub struct ProcessedEvent {
@@ -20,7 +22,224 @@ impl ProcessedEvent {
     }
 }
 
+/// The range of the event timestamps of a customer ingested into the past, for meteroid to re-rate the invoices of the period
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BackfilledUsage {
+    pub customer_id: String,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+}
+
+impl BackfilledUsage {
+    pub fn from_events<'a>(events: impl IntoIterator<Item = &'a ProcessedEvent>) -> Vec<Self> {
+        let mut ranges: BTreeMap<&str, (NaiveDateTime, NaiveDateTime)> = BTreeMap::new();
+
+        for event in events {
+            let ts = event.event_timestamp;
+            ranges
+                .entry(event.customer_id.as_str())
+                .and_modify(|(from, to)| {
+                    *from = (*from).min(ts);
+                    *to = (*to).max(ts);
+                })
+                .or_insert((ts, ts));
+        }
+
+        ranges
+            .into_iter()
+            .map(|(customer_id, (from, to))| BackfilledUsage {
+                customer_id: customer_id.to_string(),
+                from,
+                to,
+            })
+            .collect()
+    }
+}
+
 pub struct FailedEvent {
     pub event: Event,
+    pub code: IngestFailureCode,
+    pub reason: String,
+    pub violations: Vec<SchemaViolation>,
+}
+
+impl FailedEvent {
+    pub fn new(event: Event, code: IngestFailureCode, reason: String) -> Self {
+        FailedEvent {
+            event,
+            code,
+            reason,
+            violations: vec![],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Eq, PartialEq)]
+#[serde(rename_all = "SCREAMING_SNAKE_CASE")]
+pub enum ViolationKind {
+    MissingProperty,
+    InvalidType,
+    ValueNotAllowed,
+}
+
+/// A property of an event not matching the event schema of a metric
+#[derive(Clone, Debug, Serialize, Eq, PartialEq)]
+pub struct SchemaViolation {
+    pub metric_id: String,
+    pub property: String,
+    pub kind: ViolationKind,
+    pub reason: String,
+}
+
+/// An event rejected at ingestion, forwarded as is for inspection and replay
+#[derive(Clone, Debug, Serialize)]
+pub struct DeadLetter {
+    pub tenant_id: String,
+    pub event_id: String,
+    pub event_name: String,
+    pub customer_id: Option<String>,
+    pub timestamp: String,
+    pub properties: HashMap<String, String>,
+    pub code: String,
     pub reason: String,
+    pub violations: Vec<SchemaViolation>,
+    pub rejected_at: NaiveDateTime,
+}
+
+impl DeadLetter {
+    pub fn new(tenant_id: &str, failed: &FailedEvent, rejected_at: NaiveDateTime) -> Self {
+        let customer_id = failed.event.customer_id.as_ref().map(|id| match id {
+            CustomerId::MeteroidCustomerId(id) => id.clone(),
+            CustomerId::ExternalCustomerId(id) => id.clone(),
+        });
+
+        DeadLetter {
+            tenant_id: tenant_id.to_string(),
+            event_id: failed.event.event_id.clone(),
+            event_name: failed.event.event_name.clone(),
+            customer_id,
+            timestamp: failed.event.timestamp.clone(),
+            properties: failed.event.properties.clone(),
+            code: failed.code.as_str_name().to_string(),
+            reason: failed.reason.clone(),
+            violations: failed.violations.clone(),
+            rejected_at,
+        }
+    }
+
+    pub fn key(&self) -> String {
+        format!("{}:{}", self.tenant_id, self.event_id)
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum BackfillStatus {
+    Open,
+    Merging,
+    Completed,
+    Aborted,
+}
+
+impl BackfillStatus {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            BackfillStatus::Open => "OPEN",
+            BackfillStatus::Merging => "MERGING",
+            BackfillStatus::Completed => "COMPLETED",
+            BackfillStatus::Aborted => "ABORTED",
+        }
+    }
+
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "OPEN" => Some(BackfillStatus::Open),
+            "MERGING" => Some(BackfillStatus::Merging),
+            "COMPLETED" => Some(BackfillStatus::Completed),
+            "ABORTED" => Some(BackfillStatus::Aborted),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct BackfillSession {
+    pub id: Uuid,
+    pub tenant_id: String,
+    pub status: BackfillStatus,
+    pub max_events_per_second: u32,
+    pub events_staged: u64,
+    pub events_failed: u64,
+    pub events_merged: u64,
+    pub last_batch_sequence: Option<u64>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl BackfillSession {
+    pub fn new(tenant_id: String, max_events_per_second: u32) -> Self {
+        let now = Utc::now();
+        BackfillSession {
+            id: Uuid::new_v4(),
+            tenant_id,
+            status: BackfillStatus::Open,
+            max_events_per_second,
+            events_staged: 0,
+            events_failed: 0,
+            events_merged: 0,
+            last_batch_sequence: None,
+            created_at: now,
+            updated_at: now,
+        }
+    }
+
+    pub fn is_batch_acknowledged(&self, batch_sequence: u64) -> bool {
+        self.last_batch_sequence
+            .is_some_and(|last| batch_sequence <= last)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct MergedBackfill {
+    pub events_merged: u64,
+    pub usage: Vec<BackfilledUsage>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn event(customer_id: &str, hour: u32) -> ProcessedEvent {
+        ProcessedEvent {
+            customer_id: customer_id.to_string(),
+            event_timestamp: NaiveDate::from_ymd_opt(2024, 10, 1)
+                .unwrap()
+                .and_hms_opt(hour, 0, 0)
+                .unwrap(),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_backfilled_usage_from_events() {
+        let events = vec![event("b", 5), event("a", 3), event("b", 1), event("b", 9)];
+
+        let usage = BackfilledUsage::from_events(&events);
+
+        assert_eq!(
+            usage,
+            vec![
+                BackfilledUsage {
+                    customer_id: "a".to_string(),
+                    from: event("a", 3).event_timestamp,
+                    to: event("a", 3).event_timestamp,
+                },
+                BackfilledUsage {
+                    customer_id: "b".to_string(),
+                    from: event("b", 1).event_timestamp,
+                    to: event("b", 9).event_timestamp,
+                },
+            ]
+        );
+    }
 }
@@ -1,3 +1,5 @@
+use metering_grpc::meteroid::metering::v1::IngestFailureCode;
+
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
 pub enum IngestError {
     #[error("transient error, please retry")]
@@ -7,3 +9,13 @@ pub enum IngestError {
     #[error("invalid event could not be processed")]
     NonRetryableSinkError,
 }
+
+impl IngestError {
+    pub fn failure_code(&self) -> IngestFailureCode {
+        match self {
+            IngestError::RetryableSinkError => IngestFailureCode::SinkError,
+            IngestError::EventTooBig => IngestFailureCode::EventTooBig,
+            IngestError::NonRetryableSinkError => IngestFailureCode::InvalidEvent,
+        }
+    }
+}
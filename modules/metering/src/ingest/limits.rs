@@ -0,0 +1,201 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+
+use metering_grpc::meteroid::metering::v1::IngestFailureCode;
+
+use crate::config::IngestLimitsConfig;
+
+/// What to do with a property value longer than the limit.
+/// Keys are never truncated, as distinct keys could be merged into the same dimension
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationPolicy {
+    /// the event is rejected
+    Reject,
+    /// the value is truncated to the limit, and the event ingested
+    Truncate,
+}
+
+impl FromStr for TruncationPolicy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "reject" => Ok(TruncationPolicy::Reject),
+            "truncate" => Ok(TruncationPolicy::Truncate),
+            _ => Err(format!("Unknown truncation policy: {}", s)),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LimitViolation {
+    pub code: IngestFailureCode,
+    pub reason: String,
+}
+
+/// Bounds of the ingested events, protecting the storage from unbounded properties
+#[derive(Debug, Clone)]
+pub struct IngestLimits {
+    pub max_batch_size: usize,
+    pub max_properties_per_event: usize,
+    pub max_property_key_length: usize,
+    pub max_property_value_length: usize,
+    pub value_truncation: TruncationPolicy,
+}
+
+impl From<&IngestLimitsConfig> for IngestLimits {
+    fn from(config: &IngestLimitsConfig) -> Self {
+        IngestLimits {
+            max_batch_size: config.max_batch_size,
+            max_properties_per_event: config.max_properties_per_event,
+            max_property_key_length: config.max_property_key_length,
+            max_property_value_length: config.max_property_value_length,
+            value_truncation: config.value_truncation,
+        }
+    }
+}
+
+impl IngestLimits {
+    /// Checks the properties of an event against the limits, truncating the values if allowed by the policy.
+    /// Lengths are in characters
+    pub fn sanitize_properties(
+        &self,
+        properties: &mut HashMap<String, String>,
+    ) -> Result<(), LimitViolation> {
+        if properties.len() > self.max_properties_per_event {
+            return Err(LimitViolation {
+                code: IngestFailureCode::TooManyProperties,
+                reason: format!(
+                    "Too many properties: {} (max {})",
+                    properties.len(),
+                    self.max_properties_per_event
+                ),
+            });
+        }
+
+        if let Some(key) = properties
+            .keys()
+            .find(|k| k.chars().count() > self.max_property_key_length)
+        {
+            return Err(LimitViolation {
+                code: IngestFailureCode::PropertyKeyTooLong,
+                reason: format!(
+                    "Property key {}... exceeds {} characters",
+                    key.chars().take(32).collect::<String>(),
+                    self.max_property_key_length
+                ),
+            });
+        }
+
+        for (key, value) in properties.iter_mut() {
+            if value.chars().count() <= self.max_property_value_length {
+                continue;
+            }
+
+            match self.value_truncation {
+                TruncationPolicy::Reject => {
+                    return Err(LimitViolation {
+                        code: IngestFailureCode::PropertyValueTooLong,
+                        reason: format!(
+                            "Value of property {} exceeds {} characters",
+                            key, self.max_property_value_length
+                        ),
+                    });
+                }
+                TruncationPolicy::Truncate => {
+                    *value = value.chars().take(self.max_property_value_length).collect();
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn limits(value_truncation: TruncationPolicy) -> IngestLimits {
+        IngestLimits {
+            max_batch_size: 10,
+            max_properties_per_event: 2,
+            max_property_key_length: 5,
+            max_property_value_length: 4,
+            value_truncation,
+        }
+    }
+
+    fn properties(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn test_within_limits() {
+        let mut props = properties(&[("model", "gpt"), ("unit", "tok")]);
+
+        assert!(limits(TruncationPolicy::Reject)
+            .sanitize_properties(&mut props)
+            .is_ok());
+        assert_eq!(props, properties(&[("model", "gpt"), ("unit", "tok")]));
+    }
+
+    #[test]
+    fn test_too_many_properties() {
+        let mut props = properties(&[("a", "1"), ("b", "2"), ("c", "3")]);
+
+        let err = limits(TruncationPolicy::Truncate)
+            .sanitize_properties(&mut props)
+            .unwrap_err();
+        assert_eq!(err.code, IngestFailureCode::TooManyProperties);
+    }
+
+    #[test]
+    fn test_key_too_long() {
+        let mut props = properties(&[("region", "eu")]);
+
+        // keys are never truncated
+        let err = limits(TruncationPolicy::Truncate)
+            .sanitize_properties(&mut props)
+            .unwrap_err();
+        assert_eq!(err.code, IngestFailureCode::PropertyKeyTooLong);
+    }
+
+    #[test]
+    fn test_value_too_long() {
+        let mut props = properties(&[("model", "gpt-4o")]);
+
+        let err = limits(TruncationPolicy::Reject)
+            .sanitize_properties(&mut props)
+            .unwrap_err();
+        assert_eq!(err.code, IngestFailureCode::PropertyValueTooLong);
+
+        limits(TruncationPolicy::Truncate)
+            .sanitize_properties(&mut props)
+            .unwrap();
+        assert_eq!(props, properties(&[("model", "gpt-")]));
+
+        // multi-byte characters are counted once
+        let mut props = properties(&[("city", "Zürich")]);
+        limits(TruncationPolicy::Truncate)
+            .sanitize_properties(&mut props)
+            .unwrap();
+        assert_eq!(props, properties(&[("city", "Züri")]));
+    }
+
+    #[test]
+    fn test_parse_truncation_policy() {
+        assert_eq!(
+            TruncationPolicy::from_str("truncate"),
+            Ok(TruncationPolicy::Truncate)
+        );
+        assert_eq!(
+            TruncationPolicy::from_str("REJECT"),
+            Ok(TruncationPolicy::Reject)
+        );
+        assert!(TruncationPolicy::from_str("drop").is_err());
+    }
+}
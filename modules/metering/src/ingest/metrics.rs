@@ -16,3 +16,24 @@ pub(super) static INGESTED_EVENTS_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
         .with_description("Count of event ingested")
         .init()
 });
+
+pub(super) static REJECTED_EVENTS_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    GLOBAL_METER
+        .u64_counter("metering.ingest.rejected_events_total")
+        .with_description("Count of event rejected, by failure code")
+        .init()
+});
+
+pub(super) static DUPLICATE_EVENTS_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    GLOBAL_METER
+        .u64_counter("metering.ingest.duplicate_events_total")
+        .with_description("Count of event dropped as already ingested")
+        .init()
+});
+
+pub(super) static SHED_EVENTS_TOTAL: Lazy<Counter<u64>> = Lazy::new(|| {
+    GLOBAL_METER
+        .u64_counter("metering.ingest.shed_events_total")
+        .with_description("Count of event refused while the storage is under pressure")
+        .init()
+});
@@ -1,10 +1,19 @@
+pub mod dedup;
 pub mod domain;
 mod errors;
+pub mod limits;
 mod metrics;
+pub mod schema;
 pub mod service;
+pub mod shedding;
 pub mod sinks;
+pub mod throttle;
 
+use crate::connectors::Connector;
+use crate::ingest::dedup::EventDeduplicator;
+use crate::ingest::limits::IngestLimits;
 use crate::ingest::service::EventsService;
+use crate::ingest::shedding::LoadShedder;
 use crate::ingest::sinks::Sink;
 
 use common_grpc::middleware::client::LayeredClientService;
@@ -15,7 +24,11 @@ use std::sync::Arc;
 pub fn service(
     internal_client: InternalServiceClient<LayeredClientService>,
     sink: Arc<dyn Sink + Send + Sync>,
+    connector: Arc<dyn Connector + Send + Sync>,
+    limits: IngestLimits,
+    dedup: EventDeduplicator,
+    shedder: Arc<LoadShedder>,
 ) -> EventsServiceServer<EventsService> {
-    let inner = EventsService::new(internal_client, sink);
+    let inner = EventsService::new(internal_client, sink, connector, limits, dedup, shedder);
     EventsServiceServer::new(inner)
 }
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use meteroid_grpc::meteroid::internal::v1::event_property_schema::PropertyType as GrpcPropertyType;
+use meteroid_grpc::meteroid::internal::v1::MetricEventSchema as GrpcMetricEventSchema;
+
+use crate::ingest::domain::{SchemaViolation, ViolationKind};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PropertyType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl PropertyType {
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            PropertyType::String => true,
+            PropertyType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            PropertyType::Integer => value.parse::<i64>().is_ok(),
+            PropertyType::Boolean => value == "true" || value == "false",
+        }
+    }
+
+    fn as_str(&self) -> &'static str {
+        match self {
+            PropertyType::String => "string",
+            PropertyType::Number => "number",
+            PropertyType::Integer => "integer",
+            PropertyType::Boolean => "boolean",
+        }
+    }
+}
+
+impl From<GrpcPropertyType> for PropertyType {
+    fn from(value: GrpcPropertyType) -> Self {
+        match value {
+            GrpcPropertyType::String => PropertyType::String,
+            GrpcPropertyType::Number => PropertyType::Number,
+            GrpcPropertyType::Integer => PropertyType::Integer,
+            GrpcPropertyType::Boolean => PropertyType::Boolean,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct PropertyRule {
+    pub key: String,
+    pub property_type: PropertyType,
+    pub required: bool,
+    /// empty allows any value of the type
+    pub allowed_values: Vec<String>,
+}
+
+impl PropertyRule {
+    fn check(
+        &self,
+        metric_id: &str,
+        properties: &HashMap<String, String>,
+    ) -> Option<SchemaViolation> {
+        let violation = |kind, reason| SchemaViolation {
+            metric_id: metric_id.to_string(),
+            property: self.key.clone(),
+            kind,
+            reason,
+        };
+
+        let Some(value) = properties.get(&self.key) else {
+            return self.required.then(|| {
+                violation(
+                    ViolationKind::MissingProperty,
+                    format!("Missing required property {}", self.key),
+                )
+            });
+        };
+
+        if !self.property_type.accepts(value) {
+            return Some(violation(
+                ViolationKind::InvalidType,
+                format!(
+                    "Property {} must be a {}",
+                    self.key,
+                    self.property_type.as_str()
+                ),
+            ));
+        }
+
+        if !self.allowed_values.is_empty() && !self.allowed_values.contains(value) {
+            return Some(violation(
+                ViolationKind::ValueNotAllowed,
+                format!(
+                    "Value {} of property {} is not one of {}",
+                    value,
+                    self.key,
+                    self.allowed_values.join(", ")
+                ),
+            ));
+        }
+
+        None
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct MetricEventSchema {
+    pub metric_id: String,
+    pub properties: Vec<PropertyRule>,
+}
+
+/// The event schemas of the metrics of a tenant, by event name
+#[derive(Debug, Clone, Default)]
+pub struct TenantEventSchemas {
+    by_event_name: HashMap<String, Vec<MetricEventSchema>>,
+}
+
+impl TenantEventSchemas {
+    pub fn from_grpc(schemas: Vec<GrpcMetricEventSchema>) -> Self {
+        let mut by_event_name: HashMap<String, Vec<MetricEventSchema>> = HashMap::new();
+
+        for schema in schemas {
+            let properties = schema
+                .properties
+                .into_iter()
+                .map(|p| PropertyRule {
+                    property_type: p.property_type().into(),
+                    key: p.key,
+                    required: p.required,
+                    allowed_values: p.allowed_values,
+                })
+                .collect();
+
+            by_event_name
+                .entry(schema.event_name)
+                .or_default()
+                .push(MetricEventSchema {
+                    metric_id: schema.metric_id,
+                    properties,
+                });
+        }
+
+        TenantEventSchemas { by_event_name }
+    }
+
+    /// Checks the properties of an event against the schemas of all the metrics of its name.
+    /// Events without a metric schema are accepted
+    pub fn validate(
+        &self,
+        event_name: &str,
+        properties: &HashMap<String, String>,
+    ) -> Vec<SchemaViolation> {
+        self.by_event_name
+            .get(event_name)
+            .map(|schemas| {
+                schemas
+                    .iter()
+                    .flat_map(|schema| {
+                        schema
+                            .properties
+                            .iter()
+                            .filter_map(|rule| rule.check(&schema.metric_id, properties))
+                    })
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use meteroid_grpc::meteroid::internal::v1::EventPropertySchema;
+
+    fn property(
+        key: &str,
+        property_type: GrpcPropertyType,
+        required: bool,
+        allowed_values: &[&str],
+    ) -> EventPropertySchema {
+        EventPropertySchema {
+            key: key.to_string(),
+            property_type: property_type.into(),
+            required,
+            allowed_values: allowed_values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    fn schemas() -> TenantEventSchemas {
+        TenantEventSchemas::from_grpc(vec![
+            GrpcMetricEventSchema {
+                metric_id: "tokens".to_string(),
+                event_name: "inference".to_string(),
+                properties: vec![
+                    property("tokens", GrpcPropertyType::Integer, true, &[]),
+                    property("model", GrpcPropertyType::String, false, &["gpt-4o", "o1"]),
+                ],
+            },
+            GrpcMetricEventSchema {
+                metric_id: "latency".to_string(),
+                event_name: "inference".to_string(),
+                properties: vec![property("latency", GrpcPropertyType::Number, true, &[])],
+            },
+        ])
+    }
+
+    fn properties(entries: &[(&str, &str)]) -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    fn kinds(violations: &[SchemaViolation]) -> Vec<(&str, &str, ViolationKind)> {
+        violations
+            .iter()
+            .map(|v| (v.metric_id.as_str(), v.property.as_str(), v.kind))
+            .collect()
+    }
+
+    #[test]
+    fn test_valid_event() {
+        let props = properties(&[("tokens", "120"), ("latency", "0.3"), ("extra", "x")]);
+
+        assert!(schemas().validate("inference", &props).is_empty());
+    }
+
+    #[test]
+    fn test_event_without_schema() {
+        assert!(schemas().validate("storage", &properties(&[])).is_empty());
+    }
+
+    #[test]
+    fn test_violations_of_all_metrics() {
+        let props = properties(&[("tokens", "1.5"), ("model", "gpt-3")]);
+
+        let violations = schemas().validate("inference", &props);
+
+        assert_eq!(
+            kinds(&violations),
+            vec![
+                ("tokens", "tokens", ViolationKind::InvalidType),
+                ("tokens", "model", ViolationKind::ValueNotAllowed),
+                ("latency", "latency", ViolationKind::MissingProperty),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_optional_property_may_be_absent() {
+        let props = properties(&[("tokens", "7"), ("latency", "12")]);
+
+        assert!(schemas().validate("inference", &props).is_empty());
+    }
+}
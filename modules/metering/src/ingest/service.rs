@@ -1,62 +1,232 @@
 use chrono::{DateTime, Utc};
 use metering_grpc::meteroid::metering::v1::events_service_server::EventsService as EventsServiceGrpc;
 use opentelemetry::KeyValue;
+use std::collections::HashSet;
 use std::sync::Arc;
+use std::time::{Duration, Instant};
 
-use crate::cache::CUSTOMER_ID_CACHE;
+use crate::cache::{CUSTOMER_ID_CACHE, EVENT_SCHEMA_CACHE};
 use common_grpc::middleware::client::LayeredClientService;
 use metering_grpc::meteroid::metering::v1::event::CustomerId;
-use metering_grpc::meteroid::metering::v1::{Event, IngestFailure, IngestRequest, IngestResponse};
+use metering_grpc::meteroid::metering::v1::schema_violation::Kind as GrpcViolationKind;
+use metering_grpc::meteroid::metering::v1::{
+    backfill_session, AbortBackfillRequest, AbortBackfillResponse,
+    BackfillSession as GrpcBackfillSession, CompleteBackfillRequest, CompleteBackfillResponse,
+    Event, GetBackfillRequest, GetBackfillResponse, IngestBackfillRequest, IngestBackfillResponse,
+    IngestFailure, IngestFailureCode, IngestRequest, IngestResponse,
+    SchemaViolation as GrpcSchemaViolation, StartBackfillRequest, StartBackfillResponse,
+};
 use tonic::{Request, Response, Status};
 use tracing::error;
+use uuid::Uuid;
 
-use crate::ingest::domain::{FailedEvent, ProcessedEvent};
+use crate::connectors::Connector;
+use crate::ingest::dedup::EventDeduplicator;
+use crate::ingest::domain::{
+    BackfillSession, BackfillStatus, BackfilledUsage, DeadLetter, FailedEvent, ProcessedEvent,
+    ViolationKind,
+};
+use crate::ingest::limits::IngestLimits;
+use crate::ingest::metrics::{DUPLICATE_EVENTS_TOTAL, REJECTED_EVENTS_TOTAL, SHED_EVENTS_TOTAL};
+use crate::ingest::schema::TenantEventSchemas;
+use crate::ingest::shedding::LoadShedder;
 use crate::ingest::sinks::Sink;
+use crate::ingest::throttle::BackfillThrottle;
+use crate::utils::datetime_to_timestamp;
 use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::internal::v1::internal_service_client::InternalServiceClient;
-use meteroid_grpc::meteroid::internal::v1::ResolveCustomerExternalIdsRequest;
+use meteroid_grpc::meteroid::internal::v1::{
+    BackfilledUsage as GrpcBackfilledUsage, ListEventSchemasRequest, RerateBackfilledUsageRequest,
+    ResolveCustomerExternalIdsRequest,
+};
+
+const DEFAULT_BACKFILL_EVENTS_PER_SECOND: u32 = 1000;
+const MAX_BACKFILL_EVENTS_PER_SECOND: u32 = 20_000;
+const MAX_BACKFILL_BATCH_SIZE: usize = 5000;
+// delay before a schema change of a metric applies to the ingestion
+const EVENT_SCHEMA_TTL: Duration = Duration::from_secs(60);
 
 #[derive(Clone)]
 pub struct EventsService {
     pub internal_client: InternalServiceClient<LayeredClientService>,
     pub sink: Arc<dyn Sink + Send + Sync>,
+    pub connector: Arc<dyn Connector + Send + Sync>,
+    pub backfill_throttle: Arc<BackfillThrottle>,
+    pub limits: IngestLimits,
+    pub dedup: Arc<EventDeduplicator>,
+    pub shedder: Arc<LoadShedder>,
 }
 
 impl EventsService {
     pub fn new(
         internal_client: InternalServiceClient<LayeredClientService>,
         sink: Arc<dyn Sink + Send + Sync>,
+        connector: Arc<dyn Connector + Send + Sync>,
+        limits: IngestLimits,
+        dedup: EventDeduplicator,
+        shedder: Arc<LoadShedder>,
     ) -> Self {
         EventsService {
             internal_client,
             sink,
+            connector,
+            backfill_throttle: Arc::new(BackfillThrottle::default()),
+            limits,
+            dedup: Arc::new(dedup),
+            shedder,
         }
     }
-}
 
-#[tonic::async_trait]
-impl EventsServiceGrpc for EventsService {
-    #[tracing::instrument(skip(self, request))]
-    async fn ingest(
-        &self,
-        request: Request<IngestRequest>,
-    ) -> Result<Response<IngestResponse>, Status> {
-        let tenant_id = request.tenant()?.to_string();
+    /// Refuses the batch while the storage is under pressure, with the delay to retry after
+    fn shed_load(&self, tenant_id: &str, count: usize) -> Result<(), Status> {
+        self.shedder.check().map_err(|retry_after| {
+            SHED_EVENTS_TOTAL.add(
+                count as u64,
+                &[KeyValue {
+                    key: "tenant_id".into(),
+                    value: tenant_id.to_string().into(),
+                }],
+            );
 
-        let req = request.into_inner();
+            let retry_after = retry_after.as_secs().max(1);
+            let mut status = Status::resource_exhausted(format!(
+                "Ingestion is under pressure, retry in {}s",
+                retry_after
+            ));
+            if let Ok(value) = retry_after.to_string().parse() {
+                status.metadata_mut().insert("retry-after", value);
+            }
+            status
+        })
+    }
 
-        let events = req.events;
+    /// Lets meteroid re-rate the invoices whose period includes the backfilled events.
+    /// The events are ingested regardless, so a failure is only logged
+    async fn notify_backfilled_usage(&self, tenant_id: &str, usage: Vec<BackfilledUsage>) {
+        if usage.is_empty() {
+            return;
+        }
 
-        let allow_backfilling = req.allow_backfilling;
+        let mut client = self.internal_client.clone();
 
-        if events.is_empty() {
-            return Err(Status::invalid_argument("No events provided"));
-        } else if events.len() > 500 {
-            return Err(Status::invalid_argument("Too many events provided"));
+        let res = client
+            .rerate_backfilled_usage(RerateBackfilledUsageRequest {
+                tenant_id: tenant_id.to_string(),
+                usages: usage
+                    .into_iter()
+                    .map(|usage| GrpcBackfilledUsage {
+                        customer_id: usage.customer_id,
+                        from: Some(datetime_to_timestamp(usage.from.and_utc())),
+                        to: Some(datetime_to_timestamp(usage.to.and_utc())),
+                    })
+                    .collect(),
+            })
+            .await;
+
+        if let Err(e) = res {
+            error!(
+                "Unable to notify the backfilled usage of tenant {}: {}",
+                tenant_id, e
+            );
+        }
+    }
+
+    /// The event schemas of the metrics of the tenant. A stale cache entry is used if meteroid cannot be reached
+    async fn event_schemas(&self, tenant_id: &str) -> Result<Arc<TenantEventSchemas>, Status> {
+        let cached = EVENT_SCHEMA_CACHE.get(tenant_id);
+
+        if let Some((fetched_at, schemas)) = &cached {
+            if fetched_at.elapsed() < EVENT_SCHEMA_TTL {
+                return Ok(schemas.clone());
+            }
+        }
+
+        let mut client = self.internal_client.clone();
+
+        let res = client
+            .list_event_schemas(ListEventSchemasRequest {
+                tenant_id: tenant_id.to_string(),
+            })
+            .await;
+
+        match (res, cached) {
+            (Ok(res), _) => {
+                let schemas = Arc::new(TenantEventSchemas::from_grpc(res.into_inner().schemas));
+                EVENT_SCHEMA_CACHE.insert(tenant_id.to_string(), (Instant::now(), schemas.clone()));
+                Ok(schemas)
+            }
+            (Err(e), Some((_, stale))) => {
+                error!(
+                    "Unable to refresh the event schemas of tenant {}: {}",
+                    tenant_id, e
+                );
+                Ok(stale)
+            }
+            (Err(e), None) => Err(Status::internal("Unable to fetch event schemas")
+                .set_source(Arc::new(e))
+                .clone()),
+        }
+    }
+
+    /// Forwards the events rejected at ingestion to the dead-letter destination of the sink.
+    /// The rejections are returned to the client regardless, so a failure is only logged
+    async fn send_dead_letters(&self, tenant_id: &str, failed_events: &[FailedEvent]) {
+        if failed_events.is_empty() {
+            return;
         }
 
+        let now = Utc::now().naive_utc();
+        let records = failed_events
+            .iter()
+            .map(|failed| DeadLetter::new(tenant_id, failed, now))
+            .collect();
+
+        if let Err(e) = self.sink.send_dead_letters(records).await {
+            error!(
+                "Unable to send the dead letters of tenant {}: {}",
+                tenant_id, e
+            );
+        }
+    }
+
+    async fn find_backfill(
+        &self,
+        tenant_id: &str,
+        backfill_id: &str,
+    ) -> Result<BackfillSession, Status> {
+        let backfill_id = Uuid::parse_str(backfill_id)
+            .map_err(|_| Status::invalid_argument("Invalid backfill id"))?;
+
+        self.connector
+            .find_backfill(tenant_id, &backfill_id)
+            .await
+            .map_err(|e| {
+                Status::internal("Unable to fetch backfill")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?
+            .ok_or_else(|| Status::not_found("Backfill not found"))
+    }
+
+    async fn save_backfill(&self, session: &BackfillSession) -> Result<(), Status> {
+        self.connector.save_backfill(session).await.map_err(|e| {
+            Status::internal("Unable to save backfill")
+                .set_source(Arc::new(e.into_error()))
+                .clone()
+        })
+    }
+
+    /// Validates and sanitizes the events and resolves their customer, as processed events and failed events
+    async fn resolve_events(
+        &self,
+        tenant_id: &str,
+        events: Vec<Event>,
+        allow_backfilling: bool,
+    ) -> Result<(Vec<ProcessedEvent>, Vec<FailedEvent>), Status> {
         let mut failed_events = vec![];
 
+        let schemas = self.event_schemas(tenant_id).await?;
+
         // optional checks related to the tenant ? (or cloud only, ex: free limits)
 
         // - get the customer_id from external_customer_id as necessary
@@ -66,20 +236,40 @@ impl EventsServiceGrpc for EventsService {
 
         let now = chrono::Utc::now();
 
-        for event in events {
+        for mut event in events {
+            if let Err(violation) = self.limits.sanitize_properties(&mut event.properties) {
+                failed_events.push(FailedEvent::new(event, violation.code, violation.reason));
+                continue;
+            }
+
+            let violations = schemas.validate(&event.event_name, &event.properties);
+            if !violations.is_empty() {
+                failed_events.push(FailedEvent {
+                    event,
+                    code: IngestFailureCode::SchemaViolation,
+                    reason: violations
+                        .iter()
+                        .map(|v| v.reason.as_str())
+                        .collect::<Vec<_>>()
+                        .join("; "),
+                    violations,
+                });
+                continue;
+            }
+
             match validate_event(&event, &now, allow_backfilling) {
                 Ok((id, ts)) => match id {
                     CustomerId::MeteroidCustomerId(meteroid_id) => resolved.push(
-                        to_processed_event(event, meteroid_id, tenant_id.clone(), ts),
+                        to_processed_event(event, meteroid_id, tenant_id.to_string(), ts),
                     ),
                     CustomerId::ExternalCustomerId(external_id) => {
                         let from_cache =
-                            CUSTOMER_ID_CACHE.get(&(tenant_id.clone(), external_id.clone()));
+                            CUSTOMER_ID_CACHE.get(&(tenant_id.to_string(), external_id.clone()));
                         match from_cache {
                             Some(meteroid_id) => resolved.push(to_processed_event(
                                 event,
                                 meteroid_id.clone(),
-                                tenant_id.clone(),
+                                tenant_id.to_string(),
                                 ts,
                             )),
                             None => {
@@ -90,10 +280,11 @@ impl EventsServiceGrpc for EventsService {
                     }
                 },
                 Err(e) => {
-                    failed_events.push(FailedEvent {
+                    failed_events.push(FailedEvent::new(
                         event,
-                        reason: e.to_string(),
-                    });
+                        IngestFailureCode::InvalidEvent,
+                        e.to_string(),
+                    ));
                 }
             };
         }
@@ -105,7 +296,7 @@ impl EventsServiceGrpc for EventsService {
 
             let res = client
                 .resolve_customer_external_ids(ResolveCustomerExternalIdsRequest {
-                    tenant_id: tenant_id.clone(),
+                    tenant_id: tenant_id.to_string(),
                     external_ids: unresolved_ids,
                 })
                 .await
@@ -118,20 +309,21 @@ impl EventsServiceGrpc for EventsService {
             let res = res.into_inner();
 
             res.unresolved_ids.into_iter().for_each(|external_id| {
-                failed_events.push(FailedEvent {
-                    event: unresolved
+                failed_events.push(FailedEvent::new(
+                    unresolved
                         .iter()
                         .find(|(_, id, _)| id == &external_id)
                         .unwrap()
                         .0
                         .clone(),
-                    reason: "Unable to resolve external id".to_string(),
-                })
+                    IngestFailureCode::CustomerNotFound,
+                    "Unable to resolve external id".to_string(),
+                ))
             });
 
             res.customers.into_iter().for_each(|customer| {
                 CUSTOMER_ID_CACHE.insert(
-                    (tenant_id.clone(), customer.external_id.clone()),
+                    (tenant_id.to_string(), customer.external_id.clone()),
                     customer.meteroid_id.clone(),
                 );
                 let (event, _, ts) = unresolved
@@ -142,46 +334,379 @@ impl EventsServiceGrpc for EventsService {
                 resolved.push(to_processed_event(
                     event.clone(),
                     customer.meteroid_id,
-                    tenant_id.clone(),
+                    tenant_id.to_string(),
                     *ts,
                 ))
             })
         }
 
+        Ok((resolved, failed_events))
+    }
+}
+
+#[tonic::async_trait]
+impl EventsServiceGrpc for EventsService {
+    #[tracing::instrument(skip(self, request))]
+    async fn ingest(
+        &self,
+        request: Request<IngestRequest>,
+    ) -> Result<Response<IngestResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let events = req.events;
+
+        let allow_backfilling = req.allow_backfilling;
+
+        if events.is_empty() {
+            return Err(Status::invalid_argument("No events provided"));
+        } else if events.len() > self.limits.max_batch_size {
+            return Err(Status::invalid_argument(format!(
+                "Too many events provided, a batch is limited to {} events",
+                self.limits.max_batch_size
+            )));
+        }
+
+        self.shed_load(&tenant_id, events.len())?;
+
+        let (resolved, failed_events) = self
+            .resolve_events(&tenant_id, events, allow_backfilling)
+            .await?;
+
+        self.send_dead_letters(&tenant_id, &failed_events).await;
+
+        let (resolved, duplicates) = self.dedup.reserve(resolved);
+
         let default_attributes = &[
             KeyValue {
                 key: "tenant_id".into(),
-                value: tenant_id.into(),
+                value: tenant_id.clone().into(),
             }, // add key ?
         ];
 
-        let res = self
-            .sink
-            .send(resolved, default_attributes)
-            .await
-            .map_err(|e| {
-                Status::internal("Unable to send events")
+        // may include events rejected by the sink, re-rating their period is a no-op
+        let backfilled = if allow_backfilling {
+            BackfilledUsage::from_events(&resolved)
+        } else {
+            vec![]
+        };
+
+        // the events that were not ingested can be retried
+        let reserved: Vec<String> = resolved.iter().map(ProcessedEvent::key).collect();
+        let res = match self.sink.send(resolved, default_attributes).await {
+            Ok(res) => res,
+            Err(e) => {
+                self.dedup.release(reserved);
+                return Err(Status::internal("Unable to send events")
                     .set_source(Arc::new(e))
-                    .clone()
-            })?;
+                    .clone());
+            }
+        };
+        self.dedup.release(res.iter().map(|rec| rec.event.key()));
 
-        let mut failures: Vec<IngestFailure> = failed_events
-            .into_iter()
-            .map(|e| IngestFailure {
-                idempotency_key: e.event.event_id,
-                reason: e.reason,
-            })
-            .collect();
+        let mut failures: Vec<IngestFailure> =
+            failed_events.into_iter().map(to_ingest_failure).collect();
 
         failures.extend(res.into_iter().map(|rec| IngestFailure {
             idempotency_key: rec.event.event_id,
             reason: rec.error.to_string(),
+            code: rec.error.failure_code().into(),
+            violations: vec![],
         }));
 
         if !failures.is_empty() {
             error!("Failed count {}", failures.len());
         }
-        Ok(Response::new(IngestResponse { failures }))
+
+        for failure in &failures {
+            REJECTED_EVENTS_TOTAL.add(
+                1,
+                &[
+                    default_attributes[0].clone(),
+                    KeyValue::new("code", failure.code().as_str_name()),
+                ],
+            );
+        }
+        if !duplicates.is_empty() {
+            DUPLICATE_EVENTS_TOTAL.add(duplicates.len() as u64, default_attributes);
+        }
+
+        self.notify_backfilled_usage(&tenant_id, backfilled).await;
+
+        Ok(Response::new(IngestResponse {
+            failures,
+            duplicates: duplicates.into_iter().map(|event| event.event_id).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn start_backfill(
+        &self,
+        request: Request<StartBackfillRequest>,
+    ) -> Result<Response<StartBackfillResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let max_events_per_second = req
+            .max_events_per_second
+            .unwrap_or(DEFAULT_BACKFILL_EVENTS_PER_SECOND);
+
+        if max_events_per_second == 0 || max_events_per_second > MAX_BACKFILL_EVENTS_PER_SECOND {
+            return Err(Status::invalid_argument(format!(
+                "max_events_per_second must be between 1 and {}",
+                MAX_BACKFILL_EVENTS_PER_SECOND
+            )));
+        }
+
+        let session = BackfillSession::new(tenant_id, max_events_per_second);
+
+        self.save_backfill(&session).await?;
+
+        Ok(Response::new(StartBackfillResponse {
+            backfill: Some(backfill_to_grpc(session)),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn ingest_backfill(
+        &self,
+        request: Request<IngestBackfillRequest>,
+    ) -> Result<Response<IngestBackfillResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let mut session = self.find_backfill(&tenant_id, &req.backfill_id).await?;
+
+        if session.status != BackfillStatus::Open {
+            return Err(Status::failed_precondition("Backfill is not open"));
+        }
+
+        // the client is resuming after an interruption and resent an acknowledged batch
+        if session.is_batch_acknowledged(req.batch_sequence) {
+            return Ok(Response::new(IngestBackfillResponse {
+                failures: vec![],
+                skipped: true,
+                backfill: Some(backfill_to_grpc(session)),
+            }));
+        }
+
+        let events = req.events;
+
+        if events.is_empty() {
+            return Err(Status::invalid_argument("No events provided"));
+        } else if events.len() > MAX_BACKFILL_BATCH_SIZE {
+            return Err(Status::invalid_argument("Too many events provided"));
+        }
+
+        self.shed_load(&tenant_id, events.len())?;
+
+        self.backfill_throttle
+            .try_acquire(
+                session.id,
+                session.max_events_per_second,
+                events.len() as u64,
+            )
+            .map_err(|retry_after| {
+                Status::resource_exhausted(format!(
+                    "Backfill rate limit exceeded, retry in {}ms",
+                    retry_after.as_millis()
+                ))
+            })?;
+
+        let (resolved, failed_events) = self.resolve_events(&tenant_id, events, true).await?;
+
+        self.send_dead_letters(&tenant_id, &failed_events).await;
+
+        // the events of a batch retried after an interruption before its acknowledgment are already staged,
+        // only the ones staged for the first time are counted
+        let event_ids: Vec<String> = resolved
+            .iter()
+            .map(|e| e.event_id.clone())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+
+        let already_staged = self
+            .connector
+            .count_staged_backfill_events(&tenant_id, &session.id, &event_ids)
+            .await
+            .map_err(|e| {
+                Status::internal("Unable to count staged events")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        self.connector
+            .stage_backfill_events(&session.id, resolved)
+            .await
+            .map_err(|e| {
+                Status::internal("Unable to stage events")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        let failures: Vec<IngestFailure> =
+            failed_events.into_iter().map(to_ingest_failure).collect();
+
+        session.events_staged += (event_ids.len() as u64).saturating_sub(already_staged);
+        session.events_failed += failures.len() as u64;
+        session.last_batch_sequence = Some(req.batch_sequence);
+        session.updated_at = Utc::now();
+
+        self.save_backfill(&session).await?;
+
+        Ok(Response::new(IngestBackfillResponse {
+            failures,
+            skipped: false,
+            backfill: Some(backfill_to_grpc(session)),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn get_backfill(
+        &self,
+        request: Request<GetBackfillRequest>,
+    ) -> Result<Response<GetBackfillResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let session = self.find_backfill(&tenant_id, &req.backfill_id).await?;
+
+        Ok(Response::new(GetBackfillResponse {
+            backfill: Some(backfill_to_grpc(session)),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn complete_backfill(
+        &self,
+        request: Request<CompleteBackfillRequest>,
+    ) -> Result<Response<CompleteBackfillResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let mut session = self.find_backfill(&tenant_id, &req.backfill_id).await?;
+
+        if session.status != BackfillStatus::Open {
+            return Err(Status::failed_precondition("Backfill is not open"));
+        }
+
+        if session.events_staged == 0 {
+            return Err(Status::failed_precondition("No events were staged"));
+        }
+
+        session.status = BackfillStatus::Merging;
+        session.updated_at = Utc::now();
+        self.save_backfill(&session).await?;
+
+        match self.connector.merge_backfill(&tenant_id, &session.id).await {
+            Ok(merged) => {
+                session.status = BackfillStatus::Completed;
+                session.events_merged = merged.events_merged;
+                session.updated_at = Utc::now();
+                self.save_backfill(&session).await?;
+                self.backfill_throttle.release(&session.id);
+
+                self.notify_backfilled_usage(&tenant_id, merged.usage).await;
+            }
+            Err(e) => {
+                // merging skips the events already present, so the backfill can safely be completed again
+                session.status = BackfillStatus::Open;
+                session.updated_at = Utc::now();
+                self.save_backfill(&session).await?;
+
+                return Err(Status::internal("Unable to merge backfill")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone());
+            }
+        }
+
+        Ok(Response::new(CompleteBackfillResponse {
+            backfill: Some(backfill_to_grpc(session)),
+        }))
+    }
+
+    #[tracing::instrument(skip(self, request))]
+    async fn abort_backfill(
+        &self,
+        request: Request<AbortBackfillRequest>,
+    ) -> Result<Response<AbortBackfillResponse>, Status> {
+        let tenant_id = request.tenant()?.to_string();
+
+        let req = request.into_inner();
+
+        let mut session = self.find_backfill(&tenant_id, &req.backfill_id).await?;
+
+        if session.status != BackfillStatus::Open {
+            return Err(Status::failed_precondition("Backfill is not open"));
+        }
+
+        self.connector
+            .discard_backfill(&tenant_id, &session.id)
+            .await
+            .map_err(|e| {
+                Status::internal("Unable to discard backfill")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        session.status = BackfillStatus::Aborted;
+        session.updated_at = Utc::now();
+        self.save_backfill(&session).await?;
+        self.backfill_throttle.release(&session.id);
+
+        Ok(Response::new(AbortBackfillResponse {
+            backfill: Some(backfill_to_grpc(session)),
+        }))
+    }
+}
+
+fn backfill_to_grpc(session: BackfillSession) -> GrpcBackfillSession {
+    let status = match session.status {
+        BackfillStatus::Open => backfill_session::Status::Open,
+        BackfillStatus::Merging => backfill_session::Status::Merging,
+        BackfillStatus::Completed => backfill_session::Status::Completed,
+        BackfillStatus::Aborted => backfill_session::Status::Aborted,
+    };
+
+    GrpcBackfillSession {
+        id: session.id.to_string(),
+        status: status.into(),
+        max_events_per_second: session.max_events_per_second,
+        events_staged: session.events_staged,
+        events_failed: session.events_failed,
+        events_merged: session.events_merged,
+        last_batch_sequence: session.last_batch_sequence,
+        created_at: Some(datetime_to_timestamp(session.created_at)),
+        updated_at: Some(datetime_to_timestamp(session.updated_at)),
+    }
+}
+
+fn to_ingest_failure(failed: FailedEvent) -> IngestFailure {
+    IngestFailure {
+        idempotency_key: failed.event.event_id,
+        reason: failed.reason,
+        code: failed.code.into(),
+        violations: failed
+            .violations
+            .into_iter()
+            .map(|v| GrpcSchemaViolation {
+                metric_id: v.metric_id,
+                property: v.property,
+                kind: match v.kind {
+                    ViolationKind::MissingProperty => GrpcViolationKind::MissingProperty,
+                    ViolationKind::InvalidType => GrpcViolationKind::InvalidType,
+                    ViolationKind::ValueNotAllowed => GrpcViolationKind::ValueNotAllowed,
+                }
+                .into(),
+                reason: v.reason,
+            })
+            .collect(),
     }
 }
 
@@ -0,0 +1,225 @@
+use std::sync::RwLock;
+use std::time::Duration;
+
+use chrono::{DateTime, Utc};
+
+use crate::config::IngestSheddingConfig;
+
+// shedding stops once the pressure is back below this share of the thresholds, so it does not flap around them
+const RECOVERY_RATIO: f64 = 0.8;
+const MAX_RETRY_AFTER: Duration = Duration::from_secs(60);
+
+/// Pressure on the storage beyond which the ingestion is refused. Unset thresholds are not checked
+#[derive(Debug, Clone)]
+pub struct SheddingThresholds {
+    pub max_insert_latency: Option<Duration>,
+    pub max_kafka_lag: Option<i64>,
+    /// delay suggested to the clients at the thresholds, scaled by the overload beyond them
+    pub retry_after: Duration,
+}
+
+impl From<&IngestSheddingConfig> for SheddingThresholds {
+    fn from(config: &IngestSheddingConfig) -> Self {
+        SheddingThresholds {
+            max_insert_latency: config.max_insert_latency_ms.map(Duration::from_millis),
+            max_kafka_lag: config.max_kafka_lag,
+            retry_after: Duration::from_secs(config.retry_after_seconds),
+        }
+    }
+}
+
+/// The latest measures of the storage, none if not measured
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct StoragePressure {
+    /// 90th percentile of the recent inserts into the events table
+    pub insert_latency: Option<Duration>,
+    /// lag of the storage consumers on the events topic
+    pub kafka_lag: Option<i64>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SheddingState {
+    pub shedding: bool,
+    /// the threshold exceeded, while shedding
+    pub reason: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub retry_after: Duration,
+    pub pressure: StoragePressure,
+}
+
+/// Refuses the ingested events while the storage is under pressure, instead of piling them up in the topic
+#[derive(Debug)]
+pub struct LoadShedder {
+    thresholds: SheddingThresholds,
+    state: RwLock<SheddingState>,
+}
+
+impl LoadShedder {
+    pub fn new(thresholds: SheddingThresholds) -> Self {
+        LoadShedder {
+            thresholds,
+            state: RwLock::new(SheddingState::default()),
+        }
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.thresholds.max_insert_latency.is_some() || self.thresholds.max_kafka_lag.is_some()
+    }
+
+    pub fn thresholds(&self) -> &SheddingThresholds {
+        &self.thresholds
+    }
+
+    /// Returns the delay to wait for before retrying if the ingestion is shed
+    pub fn check(&self) -> Result<(), Duration> {
+        let state = self.state.read().unwrap_or_else(|e| e.into_inner());
+
+        if state.shedding {
+            Err(state.retry_after)
+        } else {
+            Ok(())
+        }
+    }
+
+    pub fn state(&self) -> SheddingState {
+        self.state.read().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Applies the latest measures of the storage. Returns whether the shedding started or stopped
+    pub fn update(&self, pressure: StoragePressure, now: DateTime<Utc>) -> bool {
+        let mut state = self.state.write().unwrap_or_else(|e| e.into_inner());
+
+        let overloads = self.overloads(&pressure);
+        let ratio = overloads
+            .iter()
+            .map(|(ratio, _)| *ratio)
+            .fold(0.0, f64::max);
+
+        let shedding = ratio > 1.0 || (state.shedding && ratio > RECOVERY_RATIO);
+        let changed = shedding != state.shedding;
+
+        state.pressure = pressure;
+        state.shedding = shedding;
+
+        if shedding {
+            state.reason = overloads
+                .into_iter()
+                .filter(|(ratio, _)| *ratio > RECOVERY_RATIO)
+                .map(|(_, reason)| reason)
+                .reduce(|a, b| format!("{}, {}", a, b));
+            state.retry_after = self
+                .thresholds
+                .retry_after
+                .mul_f64(ratio.max(1.0).ceil())
+                .min(MAX_RETRY_AFTER);
+            if changed {
+                state.since = Some(now);
+            }
+        } else {
+            state.reason = None;
+            state.since = None;
+            state.retry_after = Duration::ZERO;
+        }
+
+        changed
+    }
+
+    /// The ratio of each measure to its threshold, with a description
+    fn overloads(&self, pressure: &StoragePressure) -> Vec<(f64, String)> {
+        let mut overloads = vec![];
+
+        if let (Some(latency), Some(max)) =
+            (pressure.insert_latency, self.thresholds.max_insert_latency)
+        {
+            overloads.push((
+                latency.as_secs_f64() / max.as_secs_f64().max(f64::EPSILON),
+                format!(
+                    "insert latency of {}ms for a maximum of {}ms",
+                    latency.as_millis(),
+                    max.as_millis()
+                ),
+            ));
+        }
+
+        if let (Some(lag), Some(max)) = (pressure.kafka_lag, self.thresholds.max_kafka_lag) {
+            overloads.push((
+                lag as f64 / max.max(1) as f64,
+                format!("kafka lag of {} events for a maximum of {}", lag, max),
+            ));
+        }
+
+        overloads
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn shedder() -> LoadShedder {
+        LoadShedder::new(SheddingThresholds {
+            max_insert_latency: Some(Duration::from_millis(2000)),
+            max_kafka_lag: Some(100_000),
+            retry_after: Duration::from_secs(5),
+        })
+    }
+
+    fn pressure(latency_ms: u64, lag: i64) -> StoragePressure {
+        StoragePressure {
+            insert_latency: Some(Duration::from_millis(latency_ms)),
+            kafka_lag: Some(lag),
+        }
+    }
+
+    #[test]
+    fn test_shedding_with_hysteresis() {
+        let shedder = shedder();
+        let now = Utc::now();
+
+        assert!(!shedder.update(pressure(1500, 90_000), now));
+        assert!(shedder.check().is_ok());
+
+        assert!(shedder.update(pressure(1500, 120_000), now));
+        assert_eq!(shedder.check(), Err(Duration::from_secs(10)));
+        let state = shedder.state();
+        assert_eq!(state.since, Some(now));
+        assert_eq!(
+            state.reason.as_deref(),
+            Some("kafka lag of 120000 events for a maximum of 100000")
+        );
+
+        // still above the recovery ratio
+        assert!(!shedder.update(pressure(1700, 85_000), now));
+        assert!(shedder.check().is_err());
+        assert_eq!(
+            shedder.state().reason.as_deref(),
+            Some("insert latency of 1700ms for a maximum of 2000ms, kafka lag of 85000 events for a maximum of 100000")
+        );
+
+        assert!(shedder.update(pressure(1500, 70_000), now));
+        assert!(shedder.check().is_ok());
+        assert_eq!(shedder.state().since, None);
+    }
+
+    #[test]
+    fn test_retry_after_is_bounded() {
+        let shedder = shedder();
+
+        shedder.update(pressure(100_000, 0), Utc::now());
+
+        assert_eq!(shedder.check(), Err(MAX_RETRY_AFTER));
+    }
+
+    #[test]
+    fn test_unmeasured_pressure_is_ignored() {
+        let shedder = LoadShedder::new(SheddingThresholds {
+            max_insert_latency: None,
+            max_kafka_lag: Some(10),
+            retry_after: Duration::from_secs(5),
+        });
+
+        assert!(!shedder.update(pressure(100_000, 5), Utc::now()));
+        assert!(!shedder.update(StoragePressure::default(), Utc::now()));
+        assert!(shedder.check().is_ok());
+    }
+}
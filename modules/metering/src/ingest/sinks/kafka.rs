@@ -1,5 +1,5 @@
 use crate::config::KafkaConfig;
-use crate::ingest::domain::ProcessedEvent;
+use crate::ingest::domain::{DeadLetter, ProcessedEvent};
 use crate::ingest::errors::IngestError;
 use crate::ingest::metrics::{INGESTED_EVENTS_TOTAL, INGEST_BATCH_SIZE};
 use crate::ingest::sinks::{FailedRecord, Sink};
@@ -18,6 +18,7 @@ use tracing::log::{error, info};
 pub struct KafkaSink {
     producer: FutureProducer,
     topic: String,
+    dead_letter_topic: Option<String>,
 }
 
 // TODO check https://clickhouse.com/docs/en/integrations/kafka/kafka-table-engine#tuning-performance
@@ -44,6 +45,7 @@ impl KafkaSink {
         Ok(KafkaSink {
             producer,
             topic: config.kafka_topic.clone(),
+            dead_letter_topic: config.kafka_dead_letter_topic.clone(),
         })
     }
 
@@ -174,6 +176,53 @@ impl Sink for KafkaSink {
 
         Ok(failed_events)
     }
+
+    #[instrument(skip_all)]
+    async fn send_dead_letters(&self, records: Vec<DeadLetter>) -> Result<(), IngestError> {
+        let Some(topic) = &self.dead_letter_topic else {
+            return Ok(());
+        };
+
+        let mut deliveries = Vec::with_capacity(records.len());
+
+        for record in &records {
+            let payload = serde_json::to_string(record).map_err(|e| {
+                error!("failed to serialize dead letter: {}", e);
+                IngestError::NonRetryableSinkError
+            })?;
+
+            match self.producer.send_result(FutureRecord {
+                topic: topic.as_str(),
+                payload: Some(&payload),
+                partition: None,
+                key: Some(record.key().as_str()),
+                timestamp: None,
+                headers: None,
+            }) {
+                Ok(delivery) => deliveries.push(delivery),
+                Err((e, _)) => {
+                    error!("failed to produce dead letter: {}", e);
+                    return Err(IngestError::RetryableSinkError);
+                }
+            }
+        }
+
+        for delivery in deliveries {
+            match delivery.await {
+                Ok(Ok(_)) => {}
+                Ok(Err((e, _))) => {
+                    error!("failed to produce dead letter to Kafka: {}", e);
+                    return Err(IngestError::RetryableSinkError);
+                }
+                Err(_) => {
+                    error!("failed to produce dead letter to Kafka before write timeout");
+                    return Err(IngestError::RetryableSinkError);
+                }
+            }
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -207,6 +256,7 @@ mod tests {
             kafka_message_timeout_ms: 500,
             kafka_compression_codec: "none".to_string(),
             kafka_topic: "ingest_events".to_string(),
+            kafka_dead_letter_topic: Some("ingest_events_dead_letter".to_string()),
         };
         let sink = KafkaSink::new(&config).expect("failed to create sink");
         (cluster, sink)
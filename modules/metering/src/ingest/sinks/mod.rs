@@ -1,4 +1,4 @@
-use crate::ingest::domain::ProcessedEvent;
+use crate::ingest::domain::{DeadLetter, ProcessedEvent};
 use crate::ingest::errors::IngestError;
 use opentelemetry::KeyValue;
 use tonic::async_trait;
@@ -19,4 +19,9 @@ pub trait Sink {
         events: Vec<ProcessedEvent>,
         attributes: &[KeyValue],
     ) -> Result<Vec<FailedRecord>, IngestError>;
+
+    /// Forwards the events rejected at ingestion. Dropped if the sink has no dead-letter destination
+    async fn send_dead_letters(&self, _records: Vec<DeadLetter>) -> Result<(), IngestError> {
+        Ok(())
+    }
 }
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use uuid::Uuid;
+
+/// Buckets of the sessions idle for longer than this are dropped, the sessions abandoned without completion
+/// would otherwise be kept forever. A resumed session starts again with a full bucket
+const BUCKET_IDLE_TTL: Duration = Duration::from_secs(600);
+
+/// Token bucket refilled at `rate` tokens per second, holding at most one second of tokens.
+/// A batch is accepted as long as the bucket is not in debt, so batches larger than the rate are still accepted
+/// and the following ones are delayed accordingly.
+#[derive(Debug)]
+struct TokenBucket {
+    rate: f64,
+    tokens: f64,
+    last_refill: Instant,
+}
+
+impl TokenBucket {
+    fn new(rate: u32, now: Instant) -> Self {
+        TokenBucket {
+            rate: rate as f64,
+            tokens: rate as f64,
+            last_refill: now,
+        }
+    }
+
+    fn try_acquire(&mut self, count: u64, now: Instant) -> Result<(), Duration> {
+        let elapsed = now
+            .saturating_duration_since(self.last_refill)
+            .as_secs_f64();
+        self.tokens = (self.tokens + elapsed * self.rate).min(self.rate);
+        self.last_refill = now;
+
+        if self.tokens <= 0.0 {
+            return Err(Duration::from_secs_f64(-self.tokens / self.rate));
+        }
+
+        self.tokens -= count as f64;
+        Ok(())
+    }
+}
+
+/// Rate limits of the backfill sessions, independent of the live ingestion.
+/// This is tracked per instance, a backfill is expected to be sent by a single client.
+#[derive(Debug, Default)]
+pub struct BackfillThrottle {
+    buckets: Mutex<HashMap<Uuid, TokenBucket>>,
+}
+
+impl BackfillThrottle {
+    /// Returns the delay to wait for before retrying if the session exceeded its rate
+    pub fn try_acquire(&self, backfill_id: Uuid, rate: u32, count: u64) -> Result<(), Duration> {
+        self.try_acquire_at(backfill_id, rate, count, Instant::now())
+    }
+
+    fn try_acquire_at(
+        &self,
+        backfill_id: Uuid,
+        rate: u32,
+        count: u64,
+        now: Instant,
+    ) -> Result<(), Duration> {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+
+        buckets.retain(|_, bucket| {
+            now.saturating_duration_since(bucket.last_refill) < BUCKET_IDLE_TTL
+        });
+
+        buckets
+            .entry(backfill_id)
+            .or_insert_with(|| TokenBucket::new(rate, now))
+            .try_acquire(count, now)
+    }
+
+    pub fn release(&self, backfill_id: &Uuid) {
+        let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+        buckets.remove(backfill_id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_throttle_within_rate() {
+        let throttle = BackfillThrottle::default();
+        let id = Uuid::new_v4();
+        let now = Instant::now();
+
+        assert!(throttle.try_acquire_at(id, 1000, 500, now).is_ok());
+        assert!(throttle.try_acquire_at(id, 1000, 499, now).is_ok());
+        assert!(throttle.try_acquire_at(id, 1000, 1, now).is_ok());
+        assert!(throttle.try_acquire_at(id, 1000, 1, now).is_err());
+    }
+
+    #[test]
+    fn test_throttle_large_batch_delays_next() {
+        let throttle = BackfillThrottle::default();
+        let id = Uuid::new_v4();
+        let now = Instant::now();
+
+        // 3 seconds worth of events
+        assert!(throttle.try_acquire_at(id, 100, 400, now).is_ok());
+
+        let retry_after = throttle
+            .try_acquire_at(id, 100, 1, now + Duration::from_secs(1))
+            .unwrap_err();
+        assert_eq!(retry_after, Duration::from_secs(2));
+
+        assert!(throttle
+            .try_acquire_at(id, 100, 1, now + Duration::from_millis(3100))
+            .is_ok());
+    }
+
+    #[test]
+    fn test_throttle_sessions_are_independent() {
+        let throttle = BackfillThrottle::default();
+        let now = Instant::now();
+        let first = Uuid::new_v4();
+
+        assert!(throttle.try_acquire_at(first, 10, 10, now).is_ok());
+        assert!(throttle.try_acquire_at(first, 10, 1, now).is_err());
+        assert!(throttle.try_acquire_at(Uuid::new_v4(), 10, 1, now).is_ok());
+
+        throttle.release(&first);
+        assert!(throttle.try_acquire_at(first, 10, 1, now).is_ok());
+    }
+
+    #[test]
+    fn test_throttle_evicts_idle_sessions() {
+        let throttle = BackfillThrottle::default();
+        let now = Instant::now();
+        let idle = Uuid::new_v4();
+        let active = Uuid::new_v4();
+
+        assert!(throttle.try_acquire_at(idle, 10, 10, now).is_ok());
+        assert!(throttle
+            .try_acquire_at(
+                active,
+                10,
+                1,
+                now + BUCKET_IDLE_TTL - Duration::from_secs(1)
+            )
+            .is_ok());
+
+        assert!(throttle
+            .try_acquire_at(active, 10, 1, now + BUCKET_IDLE_TTL)
+            .is_ok());
+
+        let buckets = throttle.buckets.lock().unwrap();
+        assert_eq!(buckets.len(), 1);
+        assert!(buckets.contains_key(&active));
+    }
+}
@@ -2,6 +2,7 @@ pub mod auth;
 pub mod cache;
 pub mod config;
 pub mod connectors;
+pub mod diagnostics;
 pub mod domain;
 pub mod ingest;
 pub mod meters;
@@ -2,13 +2,20 @@ use metering_grpc::meteroid::metering::v1::meters_service_server::MetersService
 use std::sync::Arc;
 
 use metering_grpc::meteroid::metering::v1::meter::AggregationType;
+use metering_grpc::meteroid::metering::v1::Meter as MeterProto;
 use metering_grpc::meteroid::metering::v1::{
-    RegisterMeterRequest, RegisterMeterResponse, UnregisterMeterRequest, UnregisterMeterResponse,
+    CheckMeterViewsRequest, CheckMeterViewsResponse, GetRetentionPolicyRequest,
+    GetRetentionPolicyResponse, MigrateMeterRequest, MigrateMeterResponse,
+    PurgeTenantEventsRequest, PurgeTenantEventsResponse, RegisterMeterRequest,
+    RegisterMeterResponse, RestoreArchivedEventsRequest, RestoreArchivedEventsResponse,
+    RetentionPolicy as RetentionPolicyProto, SetRetentionPolicyRequest, SetRetentionPolicyResponse,
+    UnregisterMeterRequest, UnregisterMeterResponse,
 };
 use tonic::{Request, Response, Status};
 
 use crate::connectors::Connector;
-use crate::domain::Meter;
+use crate::domain::{Meter, RetentionPolicy};
+use crate::utils::{datetime_to_timestamp, timestamp_to_datetime};
 
 #[derive(Clone)]
 pub struct MetersService {
@@ -21,6 +28,30 @@ impl MetersService {
     }
 }
 
+fn to_domain_meter(meter: MeterProto, tenant_id: String) -> Result<Meter, Status> {
+    let aggregation_type: AggregationType = meter
+        .aggregation
+        .try_into()
+        .map_err(|_| Status::internal("unknown aggregation_type"))?;
+
+    Ok(Meter {
+        aggregation: aggregation_type.into(),
+        namespace: tenant_id,
+        meter_slug: meter.meter_slug,
+        event_name: meter.event_name,
+        value_property: meter.aggregation_key,
+        group_by: meter.dimensions,
+    })
+}
+
+fn retention_policy_to_proto(policy: RetentionPolicy) -> RetentionPolicyProto {
+    RetentionPolicyProto {
+        retention_days: policy.retention_days,
+        archive: policy.archive,
+        archived_until: policy.archived_until.map(datetime_to_timestamp),
+    }
+}
+
 #[tonic::async_trait]
 impl MetersServiceGrpc for MetersService {
     #[tracing::instrument(skip_all)]
@@ -34,21 +65,7 @@ impl MetersServiceGrpc for MetersService {
             .meter
             .ok_or_else(|| Status::invalid_argument("No meter provided"))?;
 
-        let aggregation_type: AggregationType = meter
-            .aggregation
-            .try_into()
-            .map_err(|_| Status::internal("unknown aggregation_type"))?;
-
-        let meter_aggregation = aggregation_type.into();
-
-        let meter = Meter {
-            aggregation: meter_aggregation,
-            namespace: req.tenant_id,
-            meter_slug: meter.meter_slug,
-            event_name: meter.event_name,
-            value_property: meter.aggregation_key,
-            group_by: meter.dimensions,
-        };
+        let meter = to_domain_meter(meter, req.tenant_id)?;
 
         self.connector.register_meter(meter).await.map_err(|e| {
             Status::internal("Failed to register meter")
@@ -59,6 +76,28 @@ impl MetersServiceGrpc for MetersService {
         Ok(Response::new(RegisterMeterResponse { metadata: vec![] }))
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn migrate_meter(
+        &self,
+        request: Request<MigrateMeterRequest>,
+    ) -> Result<Response<MigrateMeterResponse>, Status> {
+        let req = request.into_inner();
+
+        let meter = req
+            .meter
+            .ok_or_else(|| Status::invalid_argument("No meter provided"))?;
+
+        let meter = to_domain_meter(meter, req.tenant_id)?;
+
+        self.connector.migrate_meter(meter).await.map_err(|e| {
+            Status::internal("Failed to migrate meter")
+                .set_source(Arc::new(e.into_error()))
+                .clone()
+        })?;
+
+        Ok(Response::new(MigrateMeterResponse {}))
+    }
+
     #[tracing::instrument(skip_all)]
     async fn unregister_meter(
         &self,
@@ -66,4 +105,152 @@ impl MetersServiceGrpc for MetersService {
     ) -> Result<Response<UnregisterMeterResponse>, Status> {
         unimplemented!()
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn purge_tenant_events(
+        &self,
+        request: Request<PurgeTenantEventsRequest>,
+    ) -> Result<Response<PurgeTenantEventsResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.tenant_id.is_empty() {
+            return Err(Status::invalid_argument("No tenant provided"));
+        }
+
+        self.connector
+            .purge_tenant_events(&req.tenant_id, &req.meter_slugs)
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to purge tenant events")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        Ok(Response::new(PurgeTenantEventsResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn check_meter_views(
+        &self,
+        request: Request<CheckMeterViewsRequest>,
+    ) -> Result<Response<CheckMeterViewsResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.tenant_id.is_empty() {
+            return Err(Status::invalid_argument("No tenant provided"));
+        }
+
+        let drift = self
+            .connector
+            .check_meter_views(&req.tenant_id, &req.meter_slugs)
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to check meter views")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        Ok(Response::new(CheckMeterViewsResponse {
+            missing_meter_slugs: drift.missing_meter_slugs,
+            orphaned_views: drift.orphaned_views,
+        }))
+    }
+    #[tracing::instrument(skip_all)]
+    async fn set_retention_policy(
+        &self,
+        request: Request<SetRetentionPolicyRequest>,
+    ) -> Result<Response<SetRetentionPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.tenant_id.is_empty() {
+            return Err(Status::invalid_argument("No tenant provided"));
+        }
+
+        let policy = req
+            .policy
+            .ok_or_else(|| Status::invalid_argument("No policy provided"))?;
+
+        let policy = self
+            .connector
+            .set_retention_policy(RetentionPolicy {
+                tenant_id: req.tenant_id,
+                retention_days: policy.retention_days,
+                archive: policy.archive,
+                archived_until: None,
+            })
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to set retention policy")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        Ok(Response::new(SetRetentionPolicyResponse {
+            policy: Some(retention_policy_to_proto(policy)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_retention_policy(
+        &self,
+        request: Request<GetRetentionPolicyRequest>,
+    ) -> Result<Response<GetRetentionPolicyResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.tenant_id.is_empty() {
+            return Err(Status::invalid_argument("No tenant provided"));
+        }
+
+        let policy = self
+            .connector
+            .find_retention_policy(&req.tenant_id)
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to get retention policy")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        Ok(Response::new(GetRetentionPolicyResponse {
+            policy: policy
+                .filter(|p| p.retention_days > 0)
+                .map(retention_policy_to_proto),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn restore_archived_events(
+        &self,
+        request: Request<RestoreArchivedEventsRequest>,
+    ) -> Result<Response<RestoreArchivedEventsResponse>, Status> {
+        let req = request.into_inner();
+
+        if req.tenant_id.is_empty() {
+            return Err(Status::invalid_argument("No tenant provided"));
+        }
+
+        let from = req
+            .from
+            .map(timestamp_to_datetime)
+            .ok_or_else(|| Status::invalid_argument("No from provided"))?;
+        let to = req
+            .to
+            .map(timestamp_to_datetime)
+            .ok_or_else(|| Status::invalid_argument("No to provided"))?;
+
+        if from >= to {
+            return Err(Status::invalid_argument("from must be before to"));
+        }
+
+        self.connector
+            .restore_archived_events(&req.tenant_id, from, to)
+            .await
+            .map_err(|e| {
+                Status::internal("Failed to restore archived events")
+                    .set_source(Arc::new(e.into_error()))
+                    .clone()
+            })?;
+
+        Ok(Response::new(RestoreArchivedEventsResponse {}))
+    }
 }
@@ -2,6 +2,7 @@ use crate::auth::ExternalApiAuthLayer;
 use crate::config::Config;
 
 use crate::ingest;
+use crate::ingest::shedding::{LoadShedder, StoragePressure};
 
 #[cfg(feature = "kafka")]
 use crate::ingest::sinks::kafka::KafkaSink;
@@ -9,8 +10,10 @@ use crate::ingest::sinks::kafka::KafkaSink;
 use common_grpc::middleware::server as common_middleware;
 
 use common_grpc::middleware::client::{build_layered_client_service, LayeredClientService};
+use metering_grpc::meteroid::metering::v1::events_service_server::EventsServiceServer;
 use meteroid_grpc::meteroid::internal::v1::internal_service_client::InternalServiceClient;
 use std::sync::Arc;
+use std::time::Duration;
 use tonic::transport::{Channel, Endpoint, Server};
 use tonic_tracing_opentelemetry::middleware as otel_middleware;
 
@@ -22,12 +25,16 @@ use crate::connectors::clickhouse::extensions::openstack_ext::OpenstackClickhous
 #[cfg(feature = "clickhouse")]
 use crate::connectors::clickhouse::ClickhouseConnector;
 
+use crate::connectors::Connector;
 #[cfg(not(feature = "clickhouse"))]
 use crate::connectors::PrintConnector;
 
+const ARCHIVAL_INTERVAL: Duration = Duration::from_secs(3600);
+
 fn only_internal(path: &str) -> bool {
     path.starts_with("/meteroid.metering.v1.UsageQueryService")
         || path.starts_with("/meteroid.metering.v1.MetersService")
+        || path.starts_with("/meteroid.metering.v1.DiagnosticsService")
 }
 
 fn only_api(path: &str) -> bool {
@@ -79,7 +86,7 @@ pub async fn start_api_server(config: Config) -> Result<(), Box<dyn std::error::
     let internal_client: InternalServiceClient<LayeredClientService> =
         InternalServiceClient::new(service.clone());
 
-    let (_, health_service) = tonic_health::server::health_reporter();
+    let (mut health_reporter, health_service) = tonic_health::server::health_reporter();
 
     let reflection_service = tonic_reflection::server::Builder::configure()
         .register_encoded_file_descriptor_set(meteroid_grpc::_reflection::FILE_DESCRIPTOR_SET)
@@ -91,13 +98,115 @@ pub async fn start_api_server(config: Config) -> Result<(), Box<dyn std::error::
 
     let api_key_auth_layer = ExternalApiAuthLayer::new(internal_client.clone()).filter(only_api);
 
+    // the lag of the kafka engine consumers, that write the topic to the events table
+    #[cfg(feature = "kafka")]
+    let kafka_lag_probe = crate::diagnostics::kafka_lag::KafkaLagProbe::new(
+        &config.kafka,
+        crate::connectors::clickhouse::KAFKA_CONSUMER_GROUP,
+    )
+    .map_err(|e| log::warn!("Failed to create the kafka lag probe: {}", e))
+    .ok();
+
+    let shedder = Arc::new(LoadShedder::new((&config.ingest_shedding).into()));
+
     // Ingest => Api key only (though we may want a way to ingest from the  for debugging, later)
-    let event_service = ingest::service(internal_client.clone(), sink.clone());
+    let event_service = ingest::service(
+        internal_client.clone(),
+        sink.clone(),
+        connector.clone(),
+        (&config.ingest_limits).into(),
+        (&config.ingest_dedup).into(),
+        shedder.clone(),
+    );
+
+    // The ingestion is refused while the storage is under pressure, rather than piling up events in the topic.
+    // The events service is reported as not serving by the health service meanwhile
+    health_reporter
+        .set_serving::<EventsServiceServer<ingest::service::EventsService>>()
+        .await;
+    if shedder.is_enabled() {
+        let shedder = shedder.clone();
+        let connector = connector.clone();
+        #[cfg(feature = "kafka")]
+        let kafka_lag_probe = kafka_lag_probe.clone();
+        let probe_interval =
+            Duration::from_secs(config.ingest_shedding.probe_interval_seconds.max(1));
+
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(probe_interval);
+            loop {
+                interval.tick().await;
+
+                #[allow(unused_mut)]
+                let mut pressure = StoragePressure::default();
+
+                if shedder.thresholds().max_insert_latency.is_some() {
+                    pressure.insert_latency = connector
+                        .insert_latency()
+                        .await
+                        .map_err(|e| log::warn!("Failed to measure the insert latency: {:?}", e))
+                        .ok()
+                        .flatten();
+                }
+
+                #[cfg(feature = "kafka")]
+                if let (Some(probe), Some(_)) =
+                    (&kafka_lag_probe, shedder.thresholds().max_kafka_lag)
+                {
+                    pressure.kafka_lag = probe
+                        .partition_lags()
+                        .await
+                        .map_err(|e| log::warn!("Failed to read the kafka consumer lag: {}", e))
+                        .ok()
+                        .map(|lags| lags.iter().map(|l| l.lag).sum());
+                }
+
+                if shedder.update(pressure, chrono::Utc::now()) {
+                    let state = shedder.state();
+                    if state.shedding {
+                        log::warn!(
+                            "Shedding the ingestion: {}",
+                            state.reason.unwrap_or_default()
+                        );
+                        health_reporter
+                            .set_not_serving::<EventsServiceServer<ingest::service::EventsService>>(
+                            )
+                            .await;
+                    } else {
+                        log::info!("Resuming the ingestion");
+                        health_reporter
+                            .set_serving::<EventsServiceServer<ingest::service::EventsService>>()
+                            .await;
+                    }
+                }
+            }
+        });
+    }
+
+    // The expired events of the tenants with archival are exported then deleted, the others expire through the TTL.
+    // Concurrent replicas archive the same events to the same files
+    let archival_connector = connector.clone();
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(ARCHIVAL_INTERVAL);
+        loop {
+            interval.tick().await;
+            if let Err(e) = archival_connector.archive_expired_events().await {
+                log::error!("Failed to archive the expired events: {:?}", e);
+            }
+        }
+    });
 
     // Meters & queries => Admin only. Some passthrough is possible via admin
     let meter_service = crate::meters::service(connector.clone());
     let query_service = crate::query::service(connector.clone());
 
+    let diagnostics_service = crate::diagnostics::service(
+        connector.clone(),
+        shedder.clone(),
+        #[cfg(feature = "kafka")]
+        kafka_lag_probe,
+    );
+
     Server::builder()
         .layer(common_middleware::metric::create())
         .layer(api_key_auth_layer.clone())
@@ -110,6 +219,7 @@ pub async fn start_api_server(config: Config) -> Result<(), Box<dyn std::error::
         .add_service(reflection_service)
         .add_service(meter_service)
         .add_service(query_service)
+        .add_service(diagnostics_service)
         .add_service(event_service)
         .serve(config.listen_addr)
         .await?;
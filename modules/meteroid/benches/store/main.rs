@@ -0,0 +1,168 @@
+//! Benchmarks of the hot paths of the store, against a seeded Postgres started with testcontainers.
+//!
+//! Run with `cargo bench -p meteroid --bench store`. The CI compares the results of a pull request
+//! with the ones of its base branch, see `.github/workflows/ci-rust.yaml`.
+
+mod seed;
+
+use std::time::Duration;
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion, Throughput};
+use tokio::runtime::Runtime;
+
+use meteroid_store::compute::InvoiceLineInterface;
+use meteroid_store::domain::enums::InvoiceStatusEnum;
+use meteroid_store::domain::{FeeType, OrderByRequest, PaginationRequest};
+use meteroid_store::repositories::price_components::PriceComponentInterface;
+use meteroid_store::repositories::{InvoiceInterface, SubscriptionInterface};
+
+use seed::{BenchSetup, CUSTOMER_IDS, PLAN_VERSION_IDS, SUBSCRIPTION_IDS, TENANT_ID};
+
+fn list_invoices(c: &mut Criterion, rt: &Runtime, setup: &BenchSetup) {
+    let mut group = c.benchmark_group("list_invoices");
+
+    let filters = [
+        ("tenant", None, None, None),
+        ("customer", Some(CUSTOMER_IDS[0]), None, None),
+        ("status", None, Some(InvoiceStatusEnum::Finalized), None),
+        (
+            "customer_status",
+            Some(CUSTOMER_IDS[1]),
+            Some(InvoiceStatusEnum::Draft),
+            None,
+        ),
+        ("search", None, None, Some("BENCH-0123".to_string())),
+    ];
+
+    for (name, customer_id, status, query) in filters {
+        group.bench_function(name, |b| {
+            b.to_async(rt).iter(|| async {
+                setup
+                    .store
+                    .list_invoices(
+                        TENANT_ID,
+                        customer_id,
+                        status,
+                        query.clone(),
+                        OrderByRequest::DateDesc,
+                        PaginationRequest {
+                            page: 0,
+                            per_page: Some(20),
+                        },
+                    )
+                    .await
+                    .unwrap()
+            })
+        });
+    }
+
+    group.finish();
+}
+
+fn insert_invoice_batch(c: &mut Criterion, rt: &Runtime, setup: &BenchSetup) {
+    let mut group = c.benchmark_group("insert_invoice_batch");
+
+    for size in [10, 100] {
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.to_async(rt).iter_batched(
+                || (0..size).map(seed::invoice_new).collect::<Vec<_>>(),
+                |invoices| async { setup.store.insert_invoice_batch(invoices).await.unwrap() },
+                BatchSize::SmallInput,
+            )
+        });
+    }
+
+    group.finish();
+}
+
+fn price_component_deserialization(c: &mut Criterion, rt: &Runtime, setup: &BenchSetup) {
+    // the fees as stored, from the seeded plans
+    let fees: Vec<serde_json::Value> = rt.block_on(async {
+        let mut fees = vec![];
+        for plan_version_id in PLAN_VERSION_IDS {
+            for component in setup
+                .store
+                .list_price_components(plan_version_id, TENANT_ID)
+                .await
+                .unwrap()
+            {
+                fees.push(serde_json::to_value(&component.fee).unwrap());
+            }
+        }
+        fees
+    });
+
+    let mut group = c.benchmark_group("price_component_deserialization");
+    group.throughput(Throughput::Elements(fees.len() as u64));
+    group.bench_function("fee", |b| {
+        b.iter_batched(
+            || fees.clone(),
+            |fees| {
+                fees.into_iter()
+                    .map(|fee| serde_json::from_value::<FeeType>(fee).unwrap())
+                    .collect::<Vec<_>>()
+            },
+            BatchSize::SmallInput,
+        )
+    });
+    group.finish();
+}
+
+fn compute_invoice_lines(c: &mut Criterion, rt: &Runtime, setup: &BenchSetup) {
+    let subscriptions = rt.block_on(async {
+        let mut subscriptions = vec![];
+        for subscription_id in SUBSCRIPTION_IDS {
+            subscriptions.push(
+                setup
+                    .store
+                    .get_subscription_details(TENANT_ID, subscription_id)
+                    .await
+                    .unwrap(),
+            );
+        }
+        subscriptions
+    });
+
+    let mut group = c.benchmark_group("compute_invoice_lines");
+
+    for subscription in subscriptions.iter() {
+        group.bench_with_input(
+            BenchmarkId::from_parameter(subscription.id),
+            subscription,
+            |b, subscription| {
+                b.to_async(rt).iter(|| async {
+                    setup
+                        .store
+                        .compute_dated_invoice_lines(&subscription.billing_start_date, subscription)
+                        .await
+                        .unwrap()
+                })
+            },
+        );
+    }
+
+    group.finish();
+}
+
+fn store_benches(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let setup = rt.block_on(seed::setup());
+
+    list_invoices(c, &rt, &setup);
+    insert_invoice_batch(c, &rt, &setup);
+    price_component_deserialization(c, &rt, &setup);
+    compute_invoice_lines(c, &rt, &setup);
+
+    // the container is stopped from within the runtime
+    rt.block_on(async move { drop(setup) });
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default()
+        .measurement_time(Duration::from_secs(10))
+        .noise_threshold(0.05);
+    targets = store_benches
+}
+criterion_main!(benches);
@@ -0,0 +1,68 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::{
+    AccountingConnectorEnum, AccountingExportRunStatusEnum, AccountingExportScheduleEnum,
+};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::accounting_export_config)]
+#[diesel(primary_key(tenant_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountingExportConfigRow {
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub schedule: AccountingExportScheduleEnum,
+    pub enabled: bool,
+    pub accounts: serde_json::Value,
+    pub credentials: Option<String>,
+    pub exported_until: Option<NaiveDate>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::accounting_export_config)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountingExportConfigRowNew {
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub schedule: AccountingExportScheduleEnum,
+    pub enabled: bool,
+    pub accounts: serde_json::Value,
+    pub credentials: Option<String>,
+    pub created_by: Uuid,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::accounting_export_run)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountingExportRunRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub status: AccountingExportRunStatusEnum,
+    pub entries: i32,
+    pub document_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::accounting_export_run)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AccountingExportRunRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub status: AccountingExportRunStatusEnum,
+    pub entries: i32,
+    pub document_id: Option<String>,
+    pub error: Option<String>,
+}
@@ -1,4 +1,4 @@
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use uuid::Uuid;
 
 use diesel::{Identifiable, Insertable, Queryable, Selectable};
@@ -14,6 +14,15 @@ pub struct ApiTokenRow {
     pub tenant_id: Uuid,
     pub hash: String,
     pub hint: String,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub request_count: i64,
+    pub daily_request_soft_limit: Option<i64>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub rotated_at: Option<NaiveDateTime>,
+    pub partner_id: Option<Uuid>,
+    pub scopes: Vec<Option<String>>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub expiry_notified_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, Insertable)]
@@ -27,6 +36,10 @@ pub struct ApiTokenRowNew {
     pub tenant_id: Uuid,
     pub hash: String,
     pub hint: String,
+    pub daily_request_soft_limit: Option<i64>,
+    pub partner_id: Option<Uuid>,
+    pub scopes: Vec<Option<String>>,
+    pub expires_at: Option<NaiveDateTime>,
 }
 
 // ApiTokenValidationRow
@@ -40,4 +53,36 @@ pub struct ApiTokenValidationRow {
     #[diesel(select_expression = crate::schema::tenant::organization_id)]
     #[diesel(select_expression_type = crate::schema::tenant::organization_id)]
     pub organization_id: Uuid,
+    pub partner_id: Option<Uuid>,
+    pub scopes: Vec<Option<String>>,
+    pub expires_at: Option<NaiveDateTime>,
+    pub disabled_at: Option<NaiveDateTime>,
+}
+
+/// A token expiring soon, with its owner to notify
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::api_token)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiTokenExpiringRow {
+    pub id: Uuid,
+    pub name: String,
+    pub hint: String,
+    pub tenant_id: Uuid,
+    pub created_by: Uuid,
+    pub expires_at: Option<NaiveDateTime>,
+    #[diesel(select_expression = crate::schema::user::email)]
+    #[diesel(select_expression_type = crate::schema::user::email)]
+    pub owner_email: String,
+    #[diesel(select_expression = crate::schema::tenant::name)]
+    #[diesel(select_expression_type = crate::schema::tenant::name)]
+    pub tenant_name: String,
+}
+
+#[derive(Debug, Queryable, Selectable, Insertable)]
+#[diesel(table_name = crate::schema::api_token_usage_daily)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ApiTokenUsageDailyRow {
+    pub api_token_id: Uuid,
+    pub day: NaiveDate,
+    pub request_count: i64,
 }
@@ -0,0 +1,41 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::audit_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditLogRow {
+    pub id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub actor_id: Uuid,
+    pub rpc: String,
+    pub entity_id: Option<String>,
+    pub diff: Option<serde_json::Value>,
+    pub status_code: i32,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::audit_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct AuditLogRowNew {
+    pub id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub actor_id: Uuid,
+    pub rpc: String,
+    pub entity_id: Option<String>,
+    pub diff: Option<serde_json::Value>,
+    pub status_code: i32,
+}
+
+#[derive(Debug, Default)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub rpc: Option<String>,
+    pub entity_id: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
@@ -25,6 +25,9 @@ pub struct BillableMetricRow {
     pub archived_at: Option<NaiveDateTime>,
     pub tenant_id: Uuid,
     pub product_family_id: Uuid,
+    pub quantity_billing_precision: Option<i32>,
+    pub quantity_display_precision: Option<i32>,
+    pub event_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Clone, Insertable)]
@@ -44,6 +47,9 @@ pub struct BillableMetricRowNew {
     pub created_by: Uuid,
     pub tenant_id: Uuid,
     pub product_family_id: Uuid,
+    pub quantity_billing_precision: Option<i32>,
+    pub quantity_display_precision: Option<i32>,
+    pub event_schema: Option<serde_json::Value>,
 }
 
 #[derive(Debug, Identifiable, Queryable, Selectable)]
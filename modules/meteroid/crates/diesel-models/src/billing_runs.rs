@@ -0,0 +1,59 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, QueryableByName, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::billing_run_summary)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BillingRunSummaryRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub period_start: NaiveDateTime,
+    pub period_end: NaiveDateTime,
+    pub invoices_created: i32,
+    pub invoices_finalized: i32,
+    pub invoices_issued: i32,
+    pub issue_failures: i32,
+    pub totals: serde_json::Value,
+    pub failures: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::billing_run_summary)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct BillingRunSummaryRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub period_start: NaiveDateTime,
+    pub period_end: NaiveDateTime,
+    pub invoices_created: i32,
+    pub invoices_finalized: i32,
+    pub invoices_issued: i32,
+    pub issue_failures: i32,
+    pub totals: serde_json::Value,
+    pub failures: serde_json::Value,
+}
+
+/// An invoice created, finalized or issued (or that failed to be issued) during a billing run
+#[derive(QueryableByName, Debug)]
+pub struct BillingRunInvoiceRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub tenant_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub invoice_number: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub currency: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub total: i64,
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    pub created: bool,
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    pub finalized: bool,
+    #[diesel(sql_type = diesel::sql_types::Bool)]
+    pub issued: bool,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub issue_error: Option<String>,
+}
@@ -0,0 +1,30 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::CollectionStrategyPresetEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::collection_strategy_override)]
+#[diesel(primary_key(tenant_id, segment))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CollectionStrategyOverrideRow {
+    pub tenant_id: Uuid,
+    pub segment: String,
+    pub preset: CollectionStrategyPresetEnum,
+    pub rules: serde_json::Value,
+    pub updated_at: NaiveDateTime,
+    pub updated_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::collection_strategy_override)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CollectionStrategyOverrideRowNew {
+    pub tenant_id: Uuid,
+    pub segment: String,
+    pub preset: CollectionStrategyPresetEnum,
+    pub rules: serde_json::Value,
+    pub updated_at: NaiveDateTime,
+    pub updated_by: Uuid,
+}
@@ -0,0 +1,34 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::ComponentRuleTypeEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::component_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ComponentRuleRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_type: ComponentRuleTypeEnum,
+    pub subject_price_component_id: Option<Uuid>,
+    pub subject_add_on_id: Option<Uuid>,
+    pub target_price_component_id: Option<Uuid>,
+    pub target_add_on_id: Option<Uuid>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::component_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct ComponentRuleRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_type: ComponentRuleTypeEnum,
+    pub subject_price_component_id: Option<Uuid>,
+    pub subject_add_on_id: Option<Uuid>,
+    pub target_price_component_id: Option<Uuid>,
+    pub target_add_on_id: Option<Uuid>,
+    pub created_by: Uuid,
+}
@@ -15,6 +15,8 @@ pub struct ProviderConfigRow {
     pub enabled: bool,
     pub webhook_security: serde_json::Value,
     pub api_security: serde_json::Value,
+    pub is_default: bool,
+    pub fallback_priority: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -26,4 +28,6 @@ pub struct ProviderConfigRowNew {
     pub enabled: bool,
     pub webhook_security: serde_json::Value,
     pub api_security: serde_json::Value,
+    pub is_default: bool,
+    pub fallback_priority: Option<i32>,
 }
@@ -0,0 +1,50 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, QueryableByName, Selectable};
+use uuid::Uuid;
+
+use crate::enums::{CreditLimitEnforcementEnum, CreditLimitSourceEnum};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::credit_limit_exceeded)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CreditLimitExceededRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub source: CreditLimitSourceEnum,
+    pub subscription_id: Option<Uuid>,
+    pub enforcement: CreditLimitEnforcementEnum,
+    pub currency: String,
+    pub credit_limit: i64,
+    pub unpaid_invoices: i64,
+    pub unbilled_usage: i64,
+    pub exceeded_at: NaiveDateTime,
+    pub exceeded_on: NaiveDate,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::credit_limit_exceeded)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CreditLimitExceededRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub source: CreditLimitSourceEnum,
+    pub subscription_id: Option<Uuid>,
+    pub enforcement: CreditLimitEnforcementEnum,
+    pub currency: String,
+    pub credit_limit: i64,
+    pub unpaid_invoices: i64,
+    pub unbilled_usage: i64,
+}
+
+/// Amounts owed by a customer in its currency, in cents
+#[derive(QueryableByName, Debug)]
+pub struct CreditExposureRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub customer_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub unpaid_invoices: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub unbilled_usage: i64,
+}
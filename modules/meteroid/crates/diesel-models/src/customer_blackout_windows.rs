@@ -0,0 +1,30 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::customer_blackout_window)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CustomerBlackoutWindowRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::customer_blackout_window)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct CustomerBlackoutWindowRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    pub created_by: Uuid,
+}
@@ -1,6 +1,12 @@
 use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
+use crate::enums::{
+    BillingEmailStatusEnum, CreditLimitEnforcementEnum, DueDatePolicyEnum,
+    InvoiceDeliveryChannelEnum,
+};
+
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 
 #[derive(Clone, Debug, Identifiable, Queryable, Selectable)]
@@ -25,6 +31,21 @@ pub struct CustomerRow {
     pub billing_address: Option<serde_json::Value>,
     pub shipping_address: Option<serde_json::Value>,
     pub invoicing_entity_id: Uuid,
+    pub net_terms_override: Option<i32>,
+    pub early_payment_discount_percent: Option<Decimal>,
+    pub early_payment_discount_days: Option<i32>,
+    pub due_date_policy: Option<DueDatePolicyEnum>,
+    pub due_date_day: Option<i32>,
+    pub billing_email_status: BillingEmailStatusEnum,
+    pub billing_email_status_reason: Option<String>,
+    pub billing_email_status_updated_at: Option<NaiveDateTime>,
+    // normalized email and alias, maintained by the database when the tenant enforces their uniqueness
+    pub unique_email: Option<String>,
+    pub unique_alias: Option<String>,
+    pub collection_segment: Option<String>,
+    pub credit_limit_cents: Option<i64>,
+    pub credit_limit_enforcement: CreditLimitEnforcementEnum,
+    pub invoice_delivery_channel: Option<InvoiceDeliveryChannelEnum>,
 }
 
 #[derive(Clone, Debug, Queryable, Selectable)]
@@ -54,6 +75,11 @@ pub struct CustomerRowNew {
     pub billing_address: Option<serde_json::Value>,
     pub shipping_address: Option<serde_json::Value>,
     pub invoicing_entity_id: Uuid,
+    pub net_terms_override: Option<i32>,
+    pub early_payment_discount_percent: Option<Decimal>,
+    pub early_payment_discount_days: Option<i32>,
+    pub due_date_policy: Option<DueDatePolicyEnum>,
+    pub due_date_day: Option<i32>,
     // for seed, else default to None
     pub created_at: Option<NaiveDateTime>,
 }
@@ -75,6 +101,19 @@ pub struct CustomerRowPatch {
     pub invoicing_entity_id: Option<Uuid>,
 }
 
+/// Overwrites the payment terms, unset values remove the override
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::customer)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct CustomerRowPaymentTermsPatch {
+    pub net_terms_override: Option<i32>,
+    pub early_payment_discount_percent: Option<Decimal>,
+    pub early_payment_discount_days: Option<i32>,
+    pub due_date_policy: Option<DueDatePolicyEnum>,
+    pub due_date_day: Option<i32>,
+}
+
 #[derive(AsChangeset, Debug)]
 #[diesel(table_name = crate::schema::customer)]
 pub struct CustomerRowAsChangeset {
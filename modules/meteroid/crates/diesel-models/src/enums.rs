@@ -1,3 +1,28 @@
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::AccountingConnectorEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum AccountingConnectorEnum {
+    Csv,
+    QuickbooksOnline,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::AccountingExportRunStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum AccountingExportRunStatusEnum {
+    Succeeded,
+    Failed,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::AccountingExportScheduleEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum AccountingExportScheduleEnum {
+    Daily,
+    Weekly,
+    Monthly,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::ActionAfterTrialEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -7,6 +32,16 @@ pub enum ActionAfterTrialEnum {
     Downgrade,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq)]
+#[ExistingTypePath = "crate::schema::sql_types::BillingEmailStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum BillingEmailStatusEnum {
+    Unverified,
+    Verified,
+    Bounced,
+    Complained,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::BillingMetricAggregateEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -24,9 +59,38 @@ pub enum BillingMetricAggregateEnum {
 #[ExistingTypePath = "crate::schema::sql_types::BillingPeriodEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
 pub enum BillingPeriodEnum {
+    Weekly,
     Monthly,
+    Bimonthly,
     Quarterly,
+    Semiannual,
     Annual,
+    Custom,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::ComponentRuleTypeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum ComponentRuleTypeEnum {
+    Requires,
+    Excludes,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Default, PartialEq)]
+#[ExistingTypePath = "crate::schema::sql_types::CreditLimitEnforcementEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum CreditLimitEnforcementEnum {
+    #[default]
+    Flag,
+    Block,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::CreditLimitSourceEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum CreditLimitSourceEnum {
+    Subscription,
+    UsageThresholdInvoice,
 }
 
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
@@ -49,6 +113,23 @@ pub enum FangTaskState {
     Retried,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceDeliveryChannelEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceDeliveryChannelEnum {
+    Email,
+    Webhook,
+    ProviderHosted,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceDeliveryStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceDeliveryStatusEnum {
+    Delivered,
+    Failed,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::InvoiceExternalStatusEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -72,6 +153,15 @@ pub enum InvoiceStatusEnum {
     Void,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceStuckReasonEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceStuckReasonEnum {
+    StaleDraft,
+    PendingFinalization,
+    IssueRetriesExhausted,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::InvoiceType"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -89,6 +179,8 @@ pub enum InvoiceType {
 pub enum InvoicingProviderEnum {
     Stripe,
     Manual,
+    Adyen,
+    GoCardless,
 }
 
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
@@ -102,6 +194,26 @@ pub enum MrrMovementType {
     Reactivation,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::NotificationNamespaceEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum NotificationNamespaceEnum {
+    Email,
+    Portal,
+    Pdf,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::OnboardingStepEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum OnboardingStepEnum {
+    InvoicingEntity,
+    PaymentProvider,
+    BillableMetric,
+    Plan,
+    Customer,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::OutboxStatus"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -120,6 +232,14 @@ pub enum OrganizationUserRole {
     Member,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::PaymentReminderChannelEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum PaymentReminderChannelEnum {
+    Email,
+    Webhook,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone, Default)]
 #[ExistingTypePath = "crate::schema::sql_types::PlanStatusEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -146,9 +266,30 @@ pub enum PlanTypeEnum {
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
 pub enum SubscriptionFeeBillingPeriod {
     OneTime,
+    Weekly,
     Monthly,
+    Bimonthly,
     Quarterly,
+    Semiannual,
     Annual,
+    Custom,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::SubscriptionBillingModeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SubscriptionBillingModeEnum {
+    Anniversary,
+    Calendar,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::SubscriptionComponentChangeTypeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SubscriptionComponentChangeTypeEnum {
+    Slots,
+    CommittedCapacity,
+    Fee,
 }
 
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
@@ -163,6 +304,108 @@ pub enum SubscriptionEventType {
     Updated,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::SubscriptionMigrationItemStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SubscriptionMigrationItemStatusEnum {
+    Scheduled,
+    Migrated,
+    Grandfathered,
+    Failed,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::SubscriptionMigrationStrategyEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SubscriptionMigrationStrategyEnum {
+    AtNextRenewal,
+    ImmediateWithProration,
+    Grandfather,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::SubscriptionPendingChangeTypeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SubscriptionPendingChangeTypeEnum {
+    PlanSwitch,
+    Cancellation,
+    ComponentChange,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::ProrationRoundingEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum ProrationRoundingEnum {
+    Nearest,
+    NearestEven,
+    Up,
+    Down,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::SeatIncreaseRequestStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum SeatIncreaseRequestStatusEnum {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::ZeroInvoicePolicyEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum ZeroInvoicePolicyEnum {
+    Issue,
+    FinalizeWithoutIssuing,
+    Suppress,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::DueDatePolicyEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum DueDatePolicyEnum {
+    NetDays,
+    EndOfMonth,
+    FixedDay,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceCadenceGroupingEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceCadenceGroupingEnum {
+    Combined,
+    PerCadence,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::CustomerUniquenessEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum CustomerUniquenessEnum {
+    None,
+    Email,
+    Alias,
+    EmailAndAlias,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::CollectionStrategyPresetEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum CollectionStrategyPresetEnum {
+    Lenient,
+    Standard,
+    Strict,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::TaxRegistrationTypeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum TaxRegistrationTypeEnum {
+    Vat,
+    Gst,
+    Ein,
+    Abn,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::TenantEnvironmentEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -175,6 +418,44 @@ pub enum TenantEnvironmentEnum {
     Demo,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, PartialEq)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceApprovalStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceApprovalStatusEnum {
+    Waiting,
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::InvoiceApprovalActionEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum InvoiceApprovalActionEnum {
+    Requested,
+    Approved,
+    Rejected,
+    Escalated,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::TenantMemberRoleEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum TenantMemberRoleEnum {
+    Admin,
+    Billing,
+    ReadOnly,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Default, PartialEq)]
+#[ExistingTypePath = "crate::schema::sql_types::TrialModeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum TrialModeEnum {
+    #[default]
+    Free,
+    CardRequired,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::UnitConversionRoundingEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -187,6 +468,32 @@ pub enum UnitConversionRoundingEnum {
     None,
 }
 
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
+#[ExistingTypePath = "crate::schema::sql_types::UsageAlertThresholdTypeEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum UsageAlertThresholdTypeEnum {
+    Absolute,
+    CapacityPercent,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::UsageAlertRuleWindowEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum UsageAlertRuleWindowEnum {
+    BillingPeriod,
+    RollingDay,
+    RollingWeek,
+    RollingMonth,
+}
+
+#[derive(diesel_derive_enum::DbEnum, Debug, Clone, Copy, PartialEq, Eq)]
+#[ExistingTypePath = "crate::schema::sql_types::UsageAlertRuleStatusEnum"]
+#[DbValueStyle = "SCREAMING_SNAKE_CASE"]
+pub enum UsageAlertRuleStatusEnum {
+    Firing,
+    Resolved,
+}
+
 #[derive(diesel_derive_enum::DbEnum, Debug, Clone)]
 #[ExistingTypePath = "crate::schema::sql_types::WebhookOutEventTypeEnum"]
 #[DbValueStyle = "SCREAMING_SNAKE_CASE"]
@@ -195,4 +502,16 @@ pub enum WebhookOutEventTypeEnum {
     SubscriptionCreated,
     InvoiceCreated,
     InvoiceFinalized,
+    UsageAlertTriggered,
+    InvoiceStuck,
+    InvoicePaymentReminder,
+    CustomerBillingEmailUpdated,
+    UsageCapReached,
+    CreditLimitExceeded,
+    UsageAlertRuleFiring,
+    UsageAlertRuleResolved,
+    SeatIncreaseRequested,
+    InvoiceDeliveryRequested,
+    InvoicePaid,
+    InvoicePaymentRecorded,
 }
@@ -6,6 +6,16 @@ pub struct DatabaseErrorContainer {
     pub error: error_stack::Report<DatabaseError>,
 }
 
+impl DatabaseErrorContainer {
+    /// Name of the violated constraint or unique index, if the error is a constraint violation
+    pub fn constraint_name(&self) -> Option<&str> {
+        match self.error.downcast_ref::<DieselError>() {
+            Some(DieselError::DatabaseError(_, info)) => info.constraint_name(),
+            _ => None,
+        }
+    }
+}
+
 impl From<Report<DatabaseError>> for DatabaseErrorContainer {
     fn from(error: Report<DatabaseError>) -> Self {
         Self { error }
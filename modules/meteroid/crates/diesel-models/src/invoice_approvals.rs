@@ -0,0 +1,118 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::{InvoiceApprovalActionEnum, InvoiceApprovalStatusEnum, TenantMemberRoleEnum};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_approval_step)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalStepRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    pub min_total: Option<i64>,
+    pub currency: Option<String>,
+    pub assignee_id: Option<Uuid>,
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_approval_step)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalStepRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    pub min_total: Option<i64>,
+    pub currency: Option<String>,
+    pub assignee_id: Option<Uuid>,
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    pub created_by: Uuid,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_approval)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub step_id: Option<Uuid>,
+    pub position: i32,
+    pub name: String,
+    pub assignee_id: Option<Uuid>,
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    pub status: InvoiceApprovalStatusEnum,
+    pub requested_total: i64,
+    pub started_at: Option<NaiveDateTime>,
+    pub due_at: Option<NaiveDateTime>,
+    pub escalated_at: Option<NaiveDateTime>,
+    pub decided_at: Option<NaiveDateTime>,
+    pub decided_by: Option<Uuid>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_approval)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub step_id: Option<Uuid>,
+    pub position: i32,
+    pub name: String,
+    pub assignee_id: Option<Uuid>,
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    pub status: InvoiceApprovalStatusEnum,
+    pub requested_total: i64,
+    pub started_at: Option<NaiveDateTime>,
+    pub due_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_approval_event)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalEventRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub approval_id: Option<Uuid>,
+    pub step_name: Option<String>,
+    pub action: InvoiceApprovalActionEnum,
+    pub actor_id: Option<Uuid>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_approval_event)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceApprovalEventRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub approval_id: Option<Uuid>,
+    pub step_name: Option<String>,
+    pub action: InvoiceApprovalActionEnum,
+    pub actor_id: Option<Uuid>,
+    pub comment: Option<String>,
+}
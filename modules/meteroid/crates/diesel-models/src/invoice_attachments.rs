@@ -0,0 +1,36 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_attachment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceAttachmentRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub document_id: String,
+    pub include_in_email: bool,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub removed_at: Option<NaiveDateTime>,
+    pub removed_by: Option<Uuid>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_attachment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceAttachmentRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub document_id: String,
+    pub include_in_email: bool,
+    pub created_by: Uuid,
+}
@@ -0,0 +1,32 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_payment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoicePaymentRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub amount: i64,
+    pub paid_at: NaiveDate,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_payment)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoicePaymentRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub amount: i64,
+    pub paid_at: NaiveDate,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub created_by: Uuid,
+}
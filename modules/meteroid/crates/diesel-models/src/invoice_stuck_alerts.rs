@@ -0,0 +1,48 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, QueryableByName, Selectable};
+use uuid::Uuid;
+
+use crate::enums::{InvoiceStatusEnum, InvoiceStuckReasonEnum};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::invoice_stuck_alert)]
+#[diesel(primary_key(invoice_id))]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceStuckAlertRow {
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub reason: InvoiceStuckReasonEnum,
+    pub alerted_at: NaiveDateTime,
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::invoice_stuck_alert)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceStuckAlertRowNew {
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub reason: InvoiceStuckReasonEnum,
+    pub alerted_at: NaiveDateTime,
+}
+
+/// An invoice that stayed in a status beyond its SLA
+#[derive(QueryableByName, Debug)]
+pub struct StuckInvoiceRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub tenant_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub customer_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub invoice_number: String,
+    #[diesel(sql_type = crate::schema::sql_types::InvoiceStatusEnum)]
+    pub status: InvoiceStatusEnum,
+    #[diesel(sql_type = crate::schema::sql_types::InvoiceStuckReasonEnum)]
+    pub reason: InvoiceStuckReasonEnum,
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub stuck_since: NaiveDateTime,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub last_issue_error: Option<String>,
+}
@@ -1,4 +1,5 @@
 use crate::enums::{
+    BillingPeriodEnum, InvoiceDeliveryChannelEnum, InvoiceDeliveryStatusEnum,
     InvoiceExternalStatusEnum, InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum,
 };
 use chrono::NaiveDate;
@@ -53,6 +54,21 @@ pub struct InvoiceRow {
     pub xml_document_id: Option<String>,
     pub pdf_document_id: Option<String>,
     pub applied_coupon_ids: Vec<Option<Uuid>>,
+    pub suppressed_at: Option<NaiveDateTime>,
+    pub billing_cadence: Option<BillingPeriodEnum>,
+    pub billing_cadence_months: Option<i32>,
+    pub voided_at: Option<NaiveDateTime>,
+    pub void_reason: Option<String>,
+    pub replaces_invoice_id: Option<Uuid>,
+    pub manual_invoice_date: bool,
+    pub usage_outdated_at: Option<NaiveDateTime>,
+    pub manually_edited_at: Option<NaiveDateTime>,
+    pub manually_edited_by: Option<Uuid>,
+    pub delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+    pub delivery_status: Option<InvoiceDeliveryStatusEnum>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub hosted_invoice_url: Option<String>,
+    pub external_pdf_url: Option<String>,
 }
 
 #[derive(Debug, AsChangeset)]
@@ -68,6 +84,27 @@ pub struct InvoiceRowLinesPatch {
     pub applied_credits: i64,
 }
 
+/// The pages of the invoice at its invoicing provider. None keeps the current value
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = crate::schema::invoice)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoiceRowExternalLinksPatch {
+    pub hosted_invoice_url: Option<String>,
+    pub external_pdf_url: Option<String>,
+}
+
+/// The fields of a draft edited by a user. None clears the memo and the due date
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = crate::schema::invoice)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct InvoiceRowDraftEdit {
+    pub memo: Option<String>,
+    pub due_at: Option<NaiveDateTime>,
+    pub manually_edited_at: Option<NaiveDateTime>,
+    pub manually_edited_by: Option<Uuid>,
+}
+
 #[derive(Insertable, Debug)]
 #[diesel(table_name = crate::schema::invoice)]
 pub struct InvoiceRowNew {
@@ -105,6 +142,9 @@ pub struct InvoiceRowNew {
     pub plan_name: Option<String>,
     pub customer_details: serde_json::Value,
     pub seller_details: serde_json::Value,
+    pub billing_cadence: Option<BillingPeriodEnum>,
+    pub billing_cadence_months: Option<i32>,
+    pub replaces_invoice_id: Option<Uuid>,
 }
 
 #[derive(Debug, Queryable, Selectable)]
@@ -1,5 +1,7 @@
+use chrono::NaiveDateTime;
 use uuid::Uuid;
 
+use crate::enums::TaxRegistrationTypeEnum;
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 
 #[derive(Debug, Insertable, Queryable, Identifiable, Selectable)]
@@ -52,3 +54,30 @@ pub struct InvoicingEntityRowPatch {
     pub country: Option<String>,
     pub accounting_currency: Option<String>,
 }
+
+#[derive(Debug, Queryable, Identifiable, Selectable)]
+#[diesel(table_name = crate::schema::invoicing_entity_tax_registration)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoicingEntityTaxRegistrationRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoicing_entity_id: Uuid,
+    pub registration_type: TaxRegistrationTypeEnum,
+    pub jurisdiction: String,
+    pub registration_number: String,
+    pub customer_countries: Vec<Option<String>>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::invoicing_entity_tax_registration)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct InvoicingEntityTaxRegistrationRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoicing_entity_id: Uuid,
+    pub registration_type: TaxRegistrationTypeEnum,
+    pub jurisdiction: String,
+    pub registration_number: String,
+    pub customer_countries: Vec<Option<String>>,
+}
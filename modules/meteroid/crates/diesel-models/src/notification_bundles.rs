@@ -0,0 +1,31 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::NotificationNamespaceEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::notification_bundle)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NotificationBundleRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub namespace: NotificationNamespaceEnum,
+    pub locale: String,
+    pub messages: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::notification_bundle)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct NotificationBundleRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub namespace: NotificationNamespaceEnum,
+    pub locale: String,
+    pub messages: serde_json::Value,
+    pub created_by: Uuid,
+}
@@ -0,0 +1,34 @@
+use chrono::NaiveDateTime;
+use diesel::{Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::OnboardingStepEnum;
+
+#[derive(Queryable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::tenant_onboarding_step)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OnboardingStepRow {
+    pub tenant_id: Uuid,
+    pub step: OnboardingStepEnum,
+    pub completed_at: NaiveDateTime,
+    pub completed_by: Option<Uuid>,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::tenant_onboarding_step)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct OnboardingStepRowNew {
+    pub tenant_id: Uuid,
+    pub step: OnboardingStepEnum,
+    pub completed_by: Option<Uuid>,
+}
+
+/// Steps that can be inferred from the tenant's existing data, regardless of explicit completion.
+#[derive(Debug, Default)]
+pub struct OnboardingDetectedRow {
+    pub invoicing_entity_configured: bool,
+    pub payment_provider_connected: bool,
+    pub has_billable_metric: bool,
+    pub has_active_plan: bool,
+    pub has_customer: bool,
+}
@@ -0,0 +1,67 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::partner)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PartnerRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub rev_share_percent: Decimal,
+    pub created_at: NaiveDateTime,
+    pub archived_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::partner)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PartnerRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub rev_share_percent: Decimal,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::partner_attribution)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PartnerAttributionRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub partner_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub subscription_id: Option<Uuid>,
+    pub rev_share_percent: Option<Decimal>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::partner_attribution)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PartnerAttributionRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub partner_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub subscription_id: Option<Uuid>,
+    pub rev_share_percent: Option<Decimal>,
+}
+
+#[derive(Queryable, Identifiable, Insertable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::partner_commission)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PartnerCommissionRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub partner_id: Uuid,
+    pub invoice_id: Uuid,
+    pub customer_id: Uuid,
+    pub currency: String,
+    pub invoice_amount: i64,
+    pub rev_share_percent: Decimal,
+    pub amount: i64,
+    pub paid_at: NaiveDateTime,
+}
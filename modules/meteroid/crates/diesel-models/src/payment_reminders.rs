@@ -0,0 +1,93 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, QueryableByName, Selectable};
+use uuid::Uuid;
+
+use crate::enums::PaymentReminderChannelEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::payment_reminder_schedule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PaymentReminderScheduleRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::payment_reminder_schedule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PaymentReminderScheduleRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub enabled: bool,
+    pub created_by: Uuid,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::payment_reminder_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PaymentReminderLogRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub schedule_id: Option<Uuid>,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub recipient: Option<String>,
+    pub due_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::payment_reminder_log)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PaymentReminderLogRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub schedule_id: Option<Uuid>,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub recipient: Option<String>,
+    pub due_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+}
+
+/// A reminder of an enabled schedule that is due for an unpaid invoice, and was not sent yet
+#[derive(QueryableByName, Debug)]
+pub struct DuePaymentReminderRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub schedule_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub tenant_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Integer)]
+    pub offset_days: i32,
+    #[diesel(sql_type = crate::schema::sql_types::PaymentReminderChannelEnum)]
+    pub channel: PaymentReminderChannelEnum,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub invoice_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub invoice_number: String,
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub customer_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub customer_name: String,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub invoicing_entity_country: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub recipient: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub currency: String,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub amount_due: i64,
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub due_at: NaiveDateTime,
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub remind_at: NaiveDateTime,
+}
@@ -0,0 +1,28 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::plan_version_feature)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PlanVersionFeatureRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub plan_version_id: Uuid,
+    pub key: String,
+    pub enabled: bool,
+    pub usage_limit: Option<i64>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::plan_version_feature)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PlanVersionFeatureRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub plan_version_id: Uuid,
+    pub key: String,
+    pub enabled: bool,
+    pub usage_limit: Option<i64>,
+}
@@ -1,7 +1,7 @@
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
-use crate::enums::{ActionAfterTrialEnum, BillingPeriodEnum};
+use crate::enums::{ActionAfterTrialEnum, BillingPeriodEnum, TrialModeEnum};
 
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 
@@ -26,6 +26,9 @@ pub struct PlanVersionRow {
     pub trialing_plan_id: Option<Uuid>,
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub trial_is_free: bool,
+    pub trial_mode: TrialModeEnum,
+    pub tax_category: Option<String>,
+    pub inherited_defaults: Vec<String>,
 }
 
 #[derive(Debug, Insertable, Default)]
@@ -48,6 +51,9 @@ pub struct PlanVersionRowNew {
     pub trialing_plan_id: Option<Uuid>,
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub trial_is_free: bool,
+    pub trial_mode: TrialModeEnum,
+    pub tax_category: Option<String>,
+    pub inherited_defaults: Vec<String>,
 }
 
 #[derive(Debug, Queryable, Identifiable, Selectable)]
@@ -69,6 +75,7 @@ pub struct PlanVersionRowLatest {
     pub trialing_plan_id: Option<Uuid>,
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub trial_is_free: bool,
+    pub trial_mode: TrialModeEnum,
     pub period_start_day: Option<i16>,
     pub net_terms: i32,
     pub currency: String,
@@ -90,6 +97,8 @@ pub struct PlanVersionRowPatch {
     pub currency: Option<String>,
     pub net_terms: Option<i32>,
     pub billing_periods: Option<Vec<BillingPeriodEnum>>,
+    pub tax_category: Option<Option<String>>,
+    pub inherited_defaults: Option<Vec<String>>,
 }
 
 #[derive(Debug, AsChangeset)]
@@ -104,4 +113,17 @@ pub struct PlanVersionTrialRowPatch {
     pub trial_is_free: Option<bool>,
     pub trial_duration_days: Option<Option<i32>>,
     pub downgrade_plan_id: Option<Option<Uuid>>,
+    pub trial_mode: Option<TrialModeEnum>,
+    pub inherited_defaults: Option<Vec<String>>,
+}
+
+/// A default of the product family applied to the draft versions inheriting it
+#[derive(Debug, AsChangeset)]
+#[diesel(table_name = crate::schema::plan_version)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct PlanVersionInheritedRowPatch {
+    pub currency: Option<String>,
+    pub trial_duration_days: Option<Option<i32>>,
+    pub net_terms: Option<i32>,
+    pub tax_category: Option<Option<String>>,
 }
@@ -5,6 +5,7 @@ use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 #[derive(Queryable, Debug, Identifiable, AsChangeset, Selectable)]
 #[diesel(table_name = crate::schema::price_component)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
 pub struct PriceComponentRow {
     pub id: Uuid,
     pub name: String,
@@ -12,6 +13,7 @@ pub struct PriceComponentRow {
     pub plan_version_id: Uuid,
     pub product_item_id: Option<Uuid>,
     pub billable_metric_id: Option<Uuid>,
+    pub rate_card_id: Option<Uuid>,
 }
 
 #[derive(Debug, Default, Insertable)]
@@ -24,6 +26,7 @@ pub struct PriceComponentRowNew {
     pub plan_version_id: Uuid,
     pub product_item_id: Option<Uuid>,
     pub billable_metric_id: Option<Uuid>,
+    pub rate_card_id: Option<Uuid>,
 }
 
 // the changeset one
@@ -1,7 +1,7 @@
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
-use diesel::{Identifiable, Insertable, Queryable};
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable};
 
 #[derive(Queryable, Debug, Identifiable)]
 #[diesel(table_name = crate::schema::product_family)]
@@ -14,6 +14,10 @@ pub struct ProductFamilyRow {
     pub updated_at: Option<NaiveDateTime>,
     pub archived_at: Option<NaiveDateTime>,
     pub tenant_id: Uuid,
+    pub default_currency: Option<String>,
+    pub default_trial_duration_days: Option<i32>,
+    pub default_net_terms: Option<i32>,
+    pub default_tax_category: Option<String>,
 }
 
 #[derive(Insertable, Debug)]
@@ -24,4 +28,19 @@ pub struct ProductFamilyRowNew {
     pub name: String,
     pub external_id: String,
     pub tenant_id: Uuid,
+    pub default_currency: Option<String>,
+    pub default_trial_duration_days: Option<i32>,
+    pub default_net_terms: Option<i32>,
+    pub default_tax_category: Option<String>,
+}
+
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::product_family)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct ProductFamilyDefaultsRowPatch {
+    pub default_currency: Option<String>,
+    pub default_trial_duration_days: Option<i32>,
+    pub default_net_terms: Option<i32>,
+    pub default_tax_category: Option<String>,
 }
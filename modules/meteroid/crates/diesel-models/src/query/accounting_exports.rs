@@ -0,0 +1,194 @@
+use crate::accounting_exports::{
+    AccountingExportConfigRow, AccountingExportConfigRowNew, AccountingExportRunRow,
+    AccountingExportRunRowNew,
+};
+use crate::errors::IntoDbResult;
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDate;
+use diesel::upsert::excluded;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl,
+    SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl AccountingExportConfigRowNew {
+    /// Replaces the config of the tenant, keeping how far it was exported
+    pub async fn upsert(&self, conn: &mut PgConn) -> DbResult<AccountingExportConfigRow> {
+        use crate::schema::accounting_export_config::dsl as aec_dsl;
+
+        let query = diesel::insert_into(aec_dsl::accounting_export_config)
+            .values(self)
+            .on_conflict(aec_dsl::tenant_id)
+            .do_update()
+            .set((
+                aec_dsl::connector.eq(excluded(aec_dsl::connector)),
+                aec_dsl::schedule.eq(excluded(aec_dsl::schedule)),
+                aec_dsl::enabled.eq(excluded(aec_dsl::enabled)),
+                aec_dsl::accounts.eq(excluded(aec_dsl::accounts)),
+                aec_dsl::credentials.eq(excluded(aec_dsl::credentials)),
+                aec_dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while upserting accounting export config")
+            .into_db_result()
+    }
+}
+
+impl AccountingExportConfigRow {
+    pub async fn find_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Option<AccountingExportConfigRow>> {
+        use crate::schema::accounting_export_config::dsl as aec_dsl;
+
+        let query = aec_dsl::accounting_export_config
+            .filter(aec_dsl::tenant_id.eq(tenant_id))
+            .select(AccountingExportConfigRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding accounting export config")
+            .into_db_result()
+    }
+
+    pub async fn list_enabled(conn: &mut PgConn) -> DbResult<Vec<AccountingExportConfigRow>> {
+        use crate::schema::accounting_export_config::dsl as aec_dsl;
+
+        let query = aec_dsl::accounting_export_config
+            .filter(aec_dsl::enabled.eq(true))
+            .select(AccountingExportConfigRow::as_select())
+            .order(aec_dsl::tenant_id.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing accounting export configs")
+            .into_db_result()
+    }
+
+    /// Only moves forward, so that a late run does not export a period twice
+    pub async fn advance_exported_until(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        exported_until: NaiveDate,
+    ) -> DbResult<usize> {
+        use crate::schema::accounting_export_config::dsl as aec_dsl;
+
+        let query = diesel::update(aec_dsl::accounting_export_config)
+            .filter(aec_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                aec_dsl::exported_until
+                    .is_null()
+                    .or(aec_dsl::exported_until.lt(exported_until)),
+            )
+            .set(aec_dsl::exported_until.eq(exported_until));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while advancing accounting export")
+            .into_db_result()
+    }
+
+    pub async fn update_credentials(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        credentials: Option<String>,
+    ) -> DbResult<usize> {
+        use crate::schema::accounting_export_config::dsl as aec_dsl;
+
+        let query = diesel::update(aec_dsl::accounting_export_config)
+            .filter(aec_dsl::tenant_id.eq(tenant_id))
+            .set((
+                aec_dsl::credentials.eq(credentials),
+                aec_dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating accounting export credentials")
+            .into_db_result()
+    }
+}
+
+impl AccountingExportRunRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<AccountingExportRunRow> {
+        use crate::schema::accounting_export_run::dsl as aer_dsl;
+
+        let query = diesel::insert_into(aer_dsl::accounting_export_run).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting accounting export run")
+            .into_db_result()
+    }
+}
+
+impl AccountingExportRunRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<AccountingExportRunRow> {
+        use crate::schema::accounting_export_run::dsl as aer_dsl;
+
+        let query = aer_dsl::accounting_export_run
+            .filter(aer_dsl::tenant_id.eq(tenant_id))
+            .filter(aer_dsl::id.eq(id))
+            .select(AccountingExportRunRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding accounting export run")
+            .into_db_result()
+    }
+
+    /// Latest runs first
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        limit: i64,
+    ) -> DbResult<Vec<AccountingExportRunRow>> {
+        use crate::schema::accounting_export_run::dsl as aer_dsl;
+
+        let query = aer_dsl::accounting_export_run
+            .filter(aer_dsl::tenant_id.eq(tenant_id))
+            .select(AccountingExportRunRow::as_select())
+            .order(aer_dsl::created_at.desc())
+            .limit(limit);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing accounting export runs")
+            .into_db_result()
+    }
+}
@@ -1,7 +1,14 @@
-use diesel::{debug_query, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper};
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::upsert::excluded;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, NullableExpressionMethods,
+    QueryDsl, SelectableHelper,
+};
 use error_stack::ResultExt;
 
-use crate::api_tokens::{ApiTokenRow, ApiTokenRowNew, ApiTokenValidationRow};
+use crate::api_tokens::{
+    ApiTokenExpiringRow, ApiTokenRow, ApiTokenRowNew, ApiTokenUsageDailyRow, ApiTokenValidationRow,
+};
 use crate::errors::IntoDbResult;
 use crate::{DbResult, PgConn};
 
@@ -55,6 +62,211 @@ impl ApiTokenRow {
             .attach_printable("Error while fetching api tokens by tenant id")
             .into_db_result()
     }
+
+    pub async fn find_by_id_and_tenant_id(
+        conn: &mut PgConn,
+        param_id: &uuid::Uuid,
+        param_tenant_id: &uuid::Uuid,
+    ) -> DbResult<ApiTokenRow> {
+        use crate::schema::api_token::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = api_token
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while fetching api token by id and tenant id")
+            .into_db_result()
+    }
+
+    /// Adds the requests made with the token since the last recording
+    pub async fn record_usage(
+        conn: &mut PgConn,
+        param_id: &uuid::Uuid,
+        requests: i64,
+        used_at: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::api_token::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(api_token).filter(id.eq(param_id)).set((
+            request_count.eq(request_count + requests),
+            last_used_at.eq(used_at),
+        ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while recording api token usage")
+            .into_db_result()
+    }
+
+    pub async fn disable(
+        conn: &mut PgConn,
+        param_id: &uuid::Uuid,
+        param_tenant_id: &uuid::Uuid,
+        at: NaiveDateTime,
+    ) -> DbResult<ApiTokenRow> {
+        use crate::schema::api_token::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        // a rotated token still valid over its grace period is disabled right away
+        let query = diesel::update(api_token)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(disabled_at.is_null().or(disabled_at.gt(at)))
+            .set(disabled_at.eq(at));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while disabling api token")
+            .into_db_result()
+    }
+
+    /// Disables an enabled token replaced by a new one, once its grace period is over
+    pub async fn revoke_rotated(
+        conn: &mut PgConn,
+        param_id: &uuid::Uuid,
+        param_tenant_id: &uuid::Uuid,
+        at: NaiveDateTime,
+        disabled_from: NaiveDateTime,
+    ) -> DbResult<ApiTokenRow> {
+        use crate::schema::api_token::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(api_token)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(disabled_at.is_null())
+            .set((disabled_at.eq(disabled_from), rotated_at.eq(at)));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while revoking rotated api token")
+            .into_db_result()
+    }
+
+    pub async fn mark_expiry_notified(
+        conn: &mut PgConn,
+        param_id: &uuid::Uuid,
+        at: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::api_token::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(api_token)
+            .filter(id.eq(param_id))
+            .filter(expiry_notified_at.is_null())
+            .set(expiry_notified_at.eq(at));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while marking api token expiry as notified")
+            .into_db_result()
+    }
+}
+
+impl ApiTokenExpiringRow {
+    /// The enabled tokens expiring between now and `until` whose owner was not notified yet, after the cursor
+    pub async fn list_expiring(
+        conn: &mut PgConn,
+        now: NaiveDateTime,
+        until: NaiveDateTime,
+        cursor: Option<uuid::Uuid>,
+        limit: i64,
+    ) -> DbResult<Vec<ApiTokenExpiringRow>> {
+        use crate::schema::api_token::dsl as at_dsl;
+        use crate::schema::tenant::dsl as t_dsl;
+        use crate::schema::user::dsl as u_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let mut query = at_dsl::api_token
+            .inner_join(t_dsl::tenant.on(t_dsl::id.eq(at_dsl::tenant_id)))
+            .inner_join(u_dsl::user.on(u_dsl::id.eq(at_dsl::created_by)))
+            .filter(at_dsl::expires_at.gt(now))
+            .filter(at_dsl::expires_at.le(until))
+            .filter(at_dsl::disabled_at.is_null())
+            .filter(at_dsl::expiry_notified_at.is_null())
+            .order(at_dsl::id.asc())
+            .limit(limit)
+            .select(ApiTokenExpiringRow::as_select())
+            .into_boxed();
+
+        if let Some(cursor) = cursor {
+            query = query.filter(at_dsl::id.gt(cursor));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing expiring api tokens")
+            .into_db_result()
+    }
+}
+
+impl ApiTokenUsageDailyRow {
+    pub async fn increment(&self, conn: &mut PgConn) -> DbResult<usize> {
+        use crate::schema::api_token_usage_daily::dsl as atud_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(atud_dsl::api_token_usage_daily)
+            .values(self)
+            .on_conflict((atud_dsl::api_token_id, atud_dsl::day))
+            .do_update()
+            .set(
+                atud_dsl::request_count
+                    .eq(atud_dsl::request_count + excluded(atud_dsl::request_count)),
+            );
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while incrementing api token daily usage")
+            .into_db_result()
+    }
+
+    pub async fn list_since(
+        conn: &mut PgConn,
+        api_token_id: &uuid::Uuid,
+        since: NaiveDate,
+    ) -> DbResult<Vec<ApiTokenUsageDailyRow>> {
+        use crate::schema::api_token_usage_daily::dsl as atud_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = atud_dsl::api_token_usage_daily
+            .filter(atud_dsl::api_token_id.eq(api_token_id))
+            .filter(atud_dsl::day.ge(since))
+            .order(atud_dsl::day.asc())
+            .select(ApiTokenUsageDailyRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing api token daily usage")
+            .into_db_result()
+    }
 }
 
 impl ApiTokenValidationRow {
@@ -69,6 +281,16 @@ impl ApiTokenValidationRow {
         let query = at_dsl::api_token
             .inner_join(t_dsl::tenant.on(t_dsl::id.eq(at_dsl::tenant_id)))
             .filter(at_dsl::id.eq(api_token_id))
+            .filter(
+                at_dsl::disabled_at
+                    .is_null()
+                    .or(at_dsl::disabled_at.gt(diesel::dsl::now.nullable())),
+            )
+            .filter(
+                at_dsl::expires_at
+                    .is_null()
+                    .or(at_dsl::expires_at.gt(diesel::dsl::now.nullable())),
+            )
             .select(ApiTokenValidationRow::as_select());
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -0,0 +1,74 @@
+use crate::audit_logs::{AuditLogFilter, AuditLogRow, AuditLogRowNew};
+use crate::errors::IntoDbResult;
+use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
+use crate::{DbResult, PgConn};
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use error_stack::ResultExt;
+
+impl AuditLogRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<()> {
+        use crate::schema::audit_log::dsl as al_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(al_dsl::audit_log).values(self);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .attach_printable("Error while inserting audit_log")
+            .into_db_result()
+    }
+}
+
+impl AuditLogRow {
+    pub async fn list(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        filter: AuditLogFilter,
+        pagination: PaginationRequest,
+    ) -> DbResult<PaginatedVec<AuditLogRow>> {
+        use crate::schema::audit_log::dsl as al_dsl;
+
+        let mut query = al_dsl::audit_log
+            .filter(al_dsl::tenant_id.eq(tenant_id))
+            .select(AuditLogRow::as_select())
+            .into_boxed();
+
+        if let Some(actor_id) = filter.actor_id {
+            query = query.filter(al_dsl::actor_id.eq(actor_id));
+        }
+
+        if let Some(rpc) = filter.rpc {
+            query = query.filter(al_dsl::rpc.eq(rpc));
+        }
+
+        if let Some(entity_id) = filter.entity_id {
+            query = query.filter(al_dsl::entity_id.eq(entity_id));
+        }
+
+        if let Some(from) = filter.from {
+            query = query.filter(al_dsl::created_at.ge(from));
+        }
+
+        if let Some(to) = filter.to {
+            query = query.filter(al_dsl::created_at.lt(to));
+        }
+
+        let paginated_query = query
+            .order((al_dsl::created_at.desc(), al_dsl::id.desc()))
+            .paginate(pagination);
+
+        log::debug!(
+            "{}",
+            debug_query::<diesel::pg::Pg, _>(&paginated_query).to_string()
+        );
+
+        paginated_query
+            .load_and_count_pages(conn)
+            .await
+            .attach_printable("Error while listing audit logs")
+            .into_db_result()
+    }
+}
@@ -3,7 +3,7 @@ use crate::errors::IntoDbResult;
 
 use crate::{DbResult, PgConn};
 
-use diesel::debug_query;
+use diesel::{debug_query, ExpressionMethods};
 use error_stack::ResultExt;
 
 impl BiMrrMovementLogRowNew {
@@ -40,4 +40,94 @@ impl BiMrrMovementLogRow {
             .attach_printable("Error while inserting bi_mrr_movement_log")
             .into_db_result()
     }
+
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+        invoice_uid: uuid::Uuid,
+    ) -> DbResult<Vec<BiMrrMovementLogRow>> {
+        use crate::schema::bi_mrr_movement_log::dsl::*;
+        use diesel::QueryDsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = bi_mrr_movement_log
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(invoice_id.eq(invoice_uid))
+            .order(created_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing bi_mrr_movement_log by invoice id")
+            .into_db_result()
+    }
+
+    /// Deletes the movement logs of the tenant, and the daily deltas that the insert trigger aggregated from them.
+    /// Subscription events must be unlinked first
+    pub async fn delete_by_tenant_id(conn: &mut PgConn, tenant_uid: uuid::Uuid) -> DbResult<()> {
+        use crate::schema::bi_delta_mrr_daily::dsl as d_dsl;
+        use crate::schema::bi_mrr_movement_log::dsl as l_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query =
+            diesel::delete(l_dsl::bi_mrr_movement_log).filter(l_dsl::tenant_id.eq(tenant_uid));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting bi_mrr_movement_log")
+            .into_db_result()?;
+
+        let query =
+            diesel::delete(d_dsl::bi_delta_mrr_daily).filter(d_dsl::tenant_id.eq(tenant_uid));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting bi_delta_mrr_daily")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
+    /// Movements of the tenant in a currency up to a date, optionally restricted to some plans, oldest first
+    pub async fn list_for_report(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+        currency_code: &str,
+        plan_ids: &[uuid::Uuid],
+        until: chrono::NaiveDate,
+    ) -> DbResult<Vec<BiMrrMovementLogRow>> {
+        use crate::schema::bi_mrr_movement_log::dsl as l_dsl;
+        use crate::schema::plan_version::dsl as pv_dsl;
+        use diesel::QueryDsl;
+        use diesel_async::RunQueryDsl;
+
+        let mut query = l_dsl::bi_mrr_movement_log
+            .inner_join(pv_dsl::plan_version)
+            .filter(l_dsl::tenant_id.eq(tenant_uid))
+            .filter(l_dsl::currency.eq(currency_code))
+            .filter(l_dsl::applies_to.le(until))
+            .select(crate::schema::bi_mrr_movement_log::all_columns)
+            .order((l_dsl::applies_to.asc(), l_dsl::created_at.asc()))
+            .into_boxed();
+
+        if !plan_ids.is_empty() {
+            query = query.filter(pv_dsl::plan_id.eq_any(plan_ids));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing bi_mrr_movement_log for report")
+            .into_db_result()
+    }
 }
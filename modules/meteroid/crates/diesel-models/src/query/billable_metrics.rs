@@ -46,6 +46,46 @@ impl BillableMetricRow {
             .into_db_result()
     }
 
+    pub async fn list_ids_by_tenant_id(
+        conn: &mut PgConn,
+        param_tenant_id: uuid::Uuid,
+    ) -> DbResult<Vec<uuid::Uuid>> {
+        use crate::schema::billable_metric::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = billable_metric
+            .filter(tenant_id.eq(param_tenant_id))
+            .select(id);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing billable metric ids")
+            .into_db_result()
+    }
+
+    /// The billable metrics of the tenant that are not archived
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        param_tenant_id: uuid::Uuid,
+    ) -> DbResult<Vec<BillableMetricRow>> {
+        use crate::schema::billable_metric::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = billable_metric
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(archived_at.is_null())
+            .order(created_at.asc());
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing billable metrics by tenant id")
+            .into_db_result()
+    }
+
     pub async fn get_by_ids(
         conn: &mut PgConn,
         metric_ids: &[uuid::Uuid],
@@ -0,0 +1,119 @@
+use crate::billing_runs::{BillingRunInvoiceRow, BillingRunSummaryRow, BillingRunSummaryRowNew};
+use crate::errors::IntoDbResult;
+use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl BillingRunInvoiceRow {
+    /// Invoices of all tenants created, finalized or with an issue attempt in [period_start, period_end).
+    /// An invoice is issued in the period if its last attempt succeeded, and failed if it did not
+    pub async fn list(
+        conn: &mut PgConn,
+        period_start: NaiveDateTime,
+        period_end: NaiveDateTime,
+    ) -> DbResult<Vec<BillingRunInvoiceRow>> {
+        let raw_sql = r#"
+SELECT invoice.id, invoice.tenant_id, invoice.invoice_number, invoice.currency, invoice.total,
+       coalesce(invoice.created_at::timestamp >= $1 AND invoice.created_at::timestamp < $2, false) AS created,
+       coalesce(invoice.finalized_at >= $1 AND invoice.finalized_at < $2, false) AS finalized,
+       coalesce(invoice.issued AND invoice.last_issue_attempt_at::timestamp >= $1
+                    AND invoice.last_issue_attempt_at::timestamp < $2, false) AS issued,
+       CASE
+           WHEN NOT invoice.issued AND invoice.last_issue_attempt_at::timestamp >= $1
+               AND invoice.last_issue_attempt_at::timestamp < $2
+               THEN coalesce(invoice.last_issue_error, 'unknown error')
+           END AS issue_error
+FROM invoice
+WHERE (invoice.created_at::timestamp >= $1 AND invoice.created_at::timestamp < $2)
+   OR (invoice.finalized_at >= $1 AND invoice.finalized_at < $2)
+   OR (invoice.last_issue_attempt_at::timestamp >= $1 AND invoice.last_issue_attempt_at::timestamp < $2)
+ORDER BY invoice.tenant_id, invoice.id;
+        "#;
+
+        let query = diesel::sql_query(raw_sql)
+            .bind::<diesel::sql_types::Timestamp, _>(period_start)
+            .bind::<diesel::sql_types::Timestamp, _>(period_end);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing the invoices of a billing run")
+            .into_db_result()
+    }
+}
+
+impl BillingRunSummaryRowNew {
+    /// Inserts the summary unless the period was already summarized for the tenant
+    pub async fn insert_if_absent(
+        &self,
+        conn: &mut PgConn,
+    ) -> DbResult<Option<BillingRunSummaryRow>> {
+        use crate::schema::billing_run_summary::dsl as brs_dsl;
+
+        let query = diesel::insert_into(brs_dsl::billing_run_summary)
+            .values(self)
+            .on_conflict((brs_dsl::tenant_id, brs_dsl::period_start))
+            .do_nothing();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while inserting billing run summary")
+            .into_db_result()
+    }
+}
+
+impl BillingRunSummaryRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<BillingRunSummaryRow> {
+        use crate::schema::billing_run_summary::dsl as brs_dsl;
+
+        let query = brs_dsl::billing_run_summary
+            .filter(brs_dsl::id.eq(id))
+            .filter(brs_dsl::tenant_id.eq(tenant_id))
+            .select(BillingRunSummaryRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding billing run summary")
+            .into_db_result()
+    }
+
+    pub async fn list(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        pagination: PaginationRequest,
+    ) -> DbResult<PaginatedVec<BillingRunSummaryRow>> {
+        use crate::schema::billing_run_summary::dsl as brs_dsl;
+
+        let query = brs_dsl::billing_run_summary
+            .filter(brs_dsl::tenant_id.eq(tenant_id))
+            .select(BillingRunSummaryRow::as_select())
+            .order(brs_dsl::period_start.desc())
+            .paginate(pagination);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_count_pages(conn)
+            .await
+            .attach_printable("Error while listing billing run summaries")
+            .into_db_result()
+    }
+}
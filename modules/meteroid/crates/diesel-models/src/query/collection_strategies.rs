@@ -0,0 +1,97 @@
+use crate::collection_strategies::{
+    CollectionStrategyOverrideRow, CollectionStrategyOverrideRowNew,
+};
+use crate::errors::IntoDbResult;
+use crate::{DbResult, PgConn};
+
+use diesel::upsert::excluded;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl CollectionStrategyOverrideRowNew {
+    pub async fn upsert(&self, conn: &mut PgConn) -> DbResult<CollectionStrategyOverrideRow> {
+        use crate::schema::collection_strategy_override::dsl as cso_dsl;
+
+        let query = diesel::insert_into(cso_dsl::collection_strategy_override)
+            .values(self)
+            .on_conflict((cso_dsl::tenant_id, cso_dsl::segment))
+            .do_update()
+            .set((
+                cso_dsl::preset.eq(excluded(cso_dsl::preset)),
+                cso_dsl::rules.eq(excluded(cso_dsl::rules)),
+                cso_dsl::updated_at.eq(excluded(cso_dsl::updated_at)),
+                cso_dsl::updated_by.eq(excluded(cso_dsl::updated_by)),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while upserting collection strategy override")
+            .into_db_result()
+    }
+}
+
+impl CollectionStrategyOverrideRow {
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<CollectionStrategyOverrideRow>> {
+        use crate::schema::collection_strategy_override::dsl as cso_dsl;
+
+        let query = cso_dsl::collection_strategy_override
+            .filter(cso_dsl::tenant_id.eq(tenant_id))
+            .select(CollectionStrategyOverrideRow::as_select())
+            .order(cso_dsl::segment.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing collection strategy overrides")
+            .into_db_result()
+    }
+
+    pub async fn find_by_segment(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        segment: &str,
+    ) -> DbResult<Option<CollectionStrategyOverrideRow>> {
+        use crate::schema::collection_strategy_override::dsl as cso_dsl;
+
+        let query = cso_dsl::collection_strategy_override
+            .filter(cso_dsl::tenant_id.eq(tenant_id))
+            .filter(cso_dsl::segment.eq(segment))
+            .select(CollectionStrategyOverrideRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding collection strategy override")
+            .into_db_result()
+    }
+
+    /// The customers of the segment fall back to the strategy of the tenant
+    pub async fn delete(conn: &mut PgConn, tenant_id: Uuid, segment: &str) -> DbResult<usize> {
+        use crate::schema::collection_strategy_override::dsl as cso_dsl;
+
+        let query = diesel::delete(cso_dsl::collection_strategy_override)
+            .filter(cso_dsl::tenant_id.eq(tenant_id))
+            .filter(cso_dsl::segment.eq(segment));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting collection strategy override")
+            .into_db_result()
+    }
+}
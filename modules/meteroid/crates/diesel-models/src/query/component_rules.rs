@@ -0,0 +1,62 @@
+use crate::component_rules::{ComponentRuleRow, ComponentRuleRowNew};
+use crate::errors::IntoDbResult;
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl ComponentRuleRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<ComponentRuleRow> {
+        use crate::schema::component_rule::dsl as cr_dsl;
+
+        let query = diesel::insert_into(cr_dsl::component_rule).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting component rule")
+            .into_db_result()
+    }
+}
+
+impl ComponentRuleRow {
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<ComponentRuleRow>> {
+        use crate::schema::component_rule::dsl as cr_dsl;
+
+        let query = cr_dsl::component_rule
+            .filter(cr_dsl::tenant_id.eq(tenant_id))
+            .select(ComponentRuleRow::as_select())
+            .order(cr_dsl::created_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing component rules")
+            .into_db_result()
+    }
+
+    pub async fn delete(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::component_rule::dsl as cr_dsl;
+
+        let query = diesel::delete(cr_dsl::component_rule)
+            .filter(cr_dsl::id.eq(id))
+            .filter(cr_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting component rule")
+            .into_db_result()
+    }
+}
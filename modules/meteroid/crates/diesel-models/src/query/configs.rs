@@ -3,11 +3,14 @@ use crate::errors::IntoDbResult;
 use crate::{DbResult, PgConn};
 
 use crate::enums::InvoicingProviderEnum;
+use diesel::expression_methods::PgSortExpressionMethods;
 use diesel::prelude::{ExpressionMethods, QueryDsl};
-use diesel::{debug_query, DecoratableTarget};
+use diesel::{debug_query, DecoratableTarget, OptionalExtension};
 use error_stack::ResultExt;
 
 impl ProviderConfigRowNew {
+    /// Upserts the configuration of the provider. The default flag of an existing configuration is kept,
+    /// it is only changed through `ProviderConfigRow::set_default`
     pub async fn insert(&self, conn: &mut PgConn) -> DbResult<ProviderConfigRow> {
         use crate::schema::provider_config::dsl::*;
         use diesel_async::RunQueryDsl;
@@ -21,6 +24,7 @@ impl ProviderConfigRowNew {
                 enabled.eq(self.enabled),
                 webhook_security.eq(&self.webhook_security),
                 api_security.eq(&self.api_security),
+                fallback_priority.eq(self.fallback_priority),
             ));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -55,4 +59,119 @@ impl ProviderConfigRow {
             .attach_printable("Error while finding provider config")
             .into_db_result()
     }
+
+    pub async fn list_enabled_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+    ) -> DbResult<Vec<ProviderConfigRow>> {
+        use crate::schema::provider_config::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = provider_config
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(enabled.eq(true))
+            .order((
+                is_default.desc(),
+                fallback_priority.asc().nulls_last(),
+                created_at.asc(),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing provider configs")
+            .into_db_result()
+    }
+
+    pub async fn clear_default(conn: &mut PgConn, tenant_uid: uuid::Uuid) -> DbResult<usize> {
+        use crate::schema::provider_config::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(provider_config)
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(is_default.eq(true))
+            .set(is_default.eq(false));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while clearing default provider config")
+            .into_db_result()
+    }
+
+    pub async fn set_default(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+        provider: InvoicingProviderEnum,
+    ) -> DbResult<Option<ProviderConfigRow>> {
+        use crate::schema::provider_config::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(provider_config)
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(invoicing_provider.eq(provider))
+            .filter(enabled.eq(true))
+            .set(is_default.eq(true));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while setting default provider config")
+            .into_db_result()
+    }
+
+    pub async fn update_fallback_priority(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+        provider: InvoicingProviderEnum,
+        priority: Option<i32>,
+    ) -> DbResult<Option<ProviderConfigRow>> {
+        use crate::schema::provider_config::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(provider_config)
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(invoicing_provider.eq(provider))
+            .filter(enabled.eq(true))
+            .set(fallback_priority.eq(priority));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while updating provider config fallback priority")
+            .into_db_result()
+    }
+
+    pub async fn disable(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+        provider: InvoicingProviderEnum,
+    ) -> DbResult<usize> {
+        use crate::schema::provider_config::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(provider_config)
+            .filter(tenant_id.eq(tenant_uid))
+            .filter(invoicing_provider.eq(provider))
+            .filter(enabled.eq(true))
+            .set((enabled.eq(false), is_default.eq(false)));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while disabling provider config")
+            .into_db_result()
+    }
 }
@@ -0,0 +1,90 @@
+use crate::credit_limits::{CreditExposureRow, CreditLimitExceededRow, CreditLimitExceededRowNew};
+use crate::errors::IntoDbResult;
+use crate::{DbResult, PgConn};
+
+use diesel::{
+    debug_query, sql_types, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl CreditExposureRow {
+    /// Finalized invoices not paid yet, and draft or pending invoices, in the currency of each customer.
+    /// Customers without invoices are omitted
+    pub async fn list_by_customer_ids(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_ids: Vec<Uuid>,
+    ) -> DbResult<Vec<CreditExposureRow>> {
+        let raw_sql = r#"
+SELECT invoice.customer_id,
+       COALESCE(SUM(invoice.amount_due) FILTER (
+           WHERE invoice.status = 'FINALIZED'
+             AND invoice.amount_due > 0
+             AND (invoice.external_status IS NULL OR invoice.external_status NOT IN ('PAID', 'VOID', 'UNCOLLECTIBLE', 'DELETED'))
+       ), 0)::bigint AS unpaid_invoices,
+       COALESCE(SUM(invoice.total) FILTER (WHERE invoice.status IN ('DRAFT', 'PENDING')), 0)::bigint AS unbilled_usage
+FROM invoice
+INNER JOIN customer ON invoice.customer_id = customer.id
+WHERE invoice.tenant_id = $1
+  AND invoice.customer_id = ANY($2)
+  AND invoice.currency = customer.currency
+GROUP BY invoice.customer_id;
+"#;
+
+        diesel::sql_query(raw_sql)
+            .bind::<sql_types::Uuid, _>(tenant_id)
+            .bind::<sql_types::Array<sql_types::Uuid>, _>(customer_ids)
+            .get_results::<CreditExposureRow>(conn)
+            .await
+            .attach_printable("Error while fetching customer credit exposures")
+            .into_db_result()
+    }
+}
+
+impl CreditLimitExceededRowNew {
+    /// None if it was already recorded that day for the same customer, source and subscription
+    pub async fn insert_if_absent(
+        &self,
+        conn: &mut PgConn,
+    ) -> DbResult<Option<CreditLimitExceededRow>> {
+        use crate::schema::credit_limit_exceeded::dsl as cle_dsl;
+
+        let query = diesel::insert_into(cle_dsl::credit_limit_exceeded)
+            .values(self)
+            .on_conflict_do_nothing();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while inserting credit limit exceeded")
+            .into_db_result()
+    }
+}
+
+impl CreditLimitExceededRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<CreditLimitExceededRow> {
+        use crate::schema::credit_limit_exceeded::dsl as cle_dsl;
+
+        let query = cle_dsl::credit_limit_exceeded
+            .filter(cle_dsl::id.eq(id))
+            .filter(cle_dsl::tenant_id.eq(tenant_id))
+            .select(CreditLimitExceededRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding credit limit exceeded by id")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,72 @@
+use crate::customer_blackout_windows::{CustomerBlackoutWindowRow, CustomerBlackoutWindowRowNew};
+use crate::errors::IntoDbResult;
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDate;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl CustomerBlackoutWindowRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<CustomerBlackoutWindowRow> {
+        use crate::schema::customer_blackout_window::dsl as cbw_dsl;
+
+        let query = diesel::insert_into(cbw_dsl::customer_blackout_window).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting customer blackout window")
+            .into_db_result()
+    }
+}
+
+impl CustomerBlackoutWindowRow {
+    /// The windows of the customer ending on or after the given date, by start date
+    pub async fn list_by_customer_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        ending_from: Option<NaiveDate>,
+    ) -> DbResult<Vec<CustomerBlackoutWindowRow>> {
+        use crate::schema::customer_blackout_window::dsl as cbw_dsl;
+
+        let mut query = cbw_dsl::customer_blackout_window
+            .filter(cbw_dsl::tenant_id.eq(tenant_id))
+            .filter(cbw_dsl::customer_id.eq(customer_id))
+            .order(cbw_dsl::start_date.asc())
+            .select(CustomerBlackoutWindowRow::as_select())
+            .into_boxed();
+
+        if let Some(ending_from) = ending_from {
+            query = query.filter(cbw_dsl::end_date.ge(ending_from));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing customer blackout windows")
+            .into_db_result()
+    }
+
+    pub async fn delete(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::customer_blackout_window::dsl as cbw_dsl;
+
+        let query = diesel::delete(cbw_dsl::customer_blackout_window)
+            .filter(cbw_dsl::tenant_id.eq(tenant_id))
+            .filter(cbw_dsl::id.eq(id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting customer blackout window")
+            .into_db_result()
+    }
+}
@@ -1,4 +1,9 @@
-use crate::customers::{CustomerBriefRow, CustomerRow, CustomerRowNew, CustomerRowPatch};
+use crate::customers::{
+    CustomerBriefRow, CustomerRow, CustomerRowNew, CustomerRowPatch, CustomerRowPaymentTermsPatch,
+};
+use crate::enums::{
+    BillingEmailStatusEnum, CreditLimitEnforcementEnum, InvoiceDeliveryChannelEnum,
+};
 use crate::errors::IntoDbResult;
 use crate::extend::order::OrderByRequest;
 use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
@@ -184,6 +189,325 @@ impl CustomerRow {
             .into_db_result()
     }
 
+    pub async fn find_by_stripe_customer_id(
+        conn: &mut PgConn,
+        param_tenant_id: Uuid,
+        stripe_customer_id: String,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Text};
+        use diesel_async::RunQueryDsl;
+
+        let query = customer
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(
+                sql::<Bool>("billing_config -> 'Stripe' ->> 'customer_id' = ")
+                    .bind::<Text, _>(stripe_customer_id),
+            )
+            .select(CustomerRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding customer by stripe customer id")
+            .into_db_result()
+    }
+
+    pub async fn find_by_gocardless_mandate_id(
+        conn: &mut PgConn,
+        param_tenant_id: Uuid,
+        mandate_id: String,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Text};
+        use diesel_async::RunQueryDsl;
+
+        let query = customer
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(
+                sql::<Bool>("billing_config -> 'GoCardless' ->> 'mandate_id' = ")
+                    .bind::<Text, _>(mandate_id),
+            )
+            .select(CustomerRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding customer by gocardless mandate id")
+            .into_db_result()
+    }
+
+    /// Active customers with this email, case-insensitive
+    pub async fn list_by_email(
+        conn: &mut PgConn,
+        param_tenant_id: Uuid,
+        param_email: String,
+    ) -> DbResult<Vec<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Text};
+        use diesel_async::RunQueryDsl;
+
+        let query = customer
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(archived_at.is_null())
+            .filter(
+                sql::<Bool>("lower(email) = lower(")
+                    .bind::<Text, _>(param_email)
+                    .sql(")"),
+            )
+            .select(CustomerRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing customers by email")
+            .into_db_result()
+    }
+
+    /// Active customers billed at this email (invoicing email, else email), case-insensitive, in all tenants if none is given
+    pub async fn list_by_billing_email(
+        conn: &mut PgConn,
+        param_tenant_id: Option<Uuid>,
+        param_email: String,
+    ) -> DbResult<Vec<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel::dsl::sql;
+        use diesel::sql_types::{Bool, Text};
+        use diesel_async::RunQueryDsl;
+
+        let mut query = customer
+            .filter(archived_at.is_null())
+            .filter(
+                sql::<Bool>("lower(coalesce(invoicing_email, email)) = lower(")
+                    .bind::<Text, _>(param_email)
+                    .sql(")"),
+            )
+            .select(CustomerRow::as_select())
+            .into_boxed();
+
+        if let Some(param_tenant_id) = param_tenant_id {
+            query = query.filter(tenant_id.eq(param_tenant_id));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing customers by billing email")
+            .into_db_result()
+    }
+
+    pub async fn update_billing_email_status(
+        conn: &mut PgConn,
+        param_id: Uuid,
+        param_tenant_id: Uuid,
+        param_status: BillingEmailStatusEnum,
+        param_reason: Option<String>,
+        param_updated_at: chrono::NaiveDateTime,
+    ) -> DbResult<CustomerRow> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set((
+                billing_email_status.eq(param_status),
+                billing_email_status_reason.eq(param_reason),
+                billing_email_status_updated_at.eq(param_updated_at),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while updating customer billing email status")
+            .into_db_result()
+    }
+
+    pub async fn update_billing_config(
+        conn: &mut PgConn,
+        param_id: Uuid,
+        param_tenant_id: Uuid,
+        param_billing_config: serde_json::Value,
+    ) -> DbResult<CustomerRow> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set((
+                billing_config.eq(param_billing_config),
+                updated_at.eq(diesel::dsl::now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while updating customer billing config")
+            .into_db_result()
+    }
+
+    pub async fn update_collection_segment(
+        conn: &mut PgConn,
+        param_id: Uuid,
+        param_tenant_id: Uuid,
+        param_segment: Option<String>,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set((
+                collection_segment.eq(param_segment),
+                updated_at.eq(diesel::dsl::now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while updating customer collection segment")
+            .into_db_result()
+    }
+
+    pub async fn update_credit_limit(
+        conn: &mut PgConn,
+        param_id: Uuid,
+        param_tenant_id: Uuid,
+        param_credit_limit_cents: Option<i64>,
+        param_enforcement: CreditLimitEnforcementEnum,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set((
+                credit_limit_cents.eq(param_credit_limit_cents),
+                credit_limit_enforcement.eq(param_enforcement),
+                updated_at.eq(diesel::dsl::now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while updating customer credit limit")
+            .into_db_result()
+    }
+
+    pub async fn update_invoice_delivery_channel(
+        conn: &mut PgConn,
+        param_id: Uuid,
+        param_tenant_id: Uuid,
+        param_channel: Option<InvoiceDeliveryChannelEnum>,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set((
+                invoice_delivery_channel.eq(param_channel),
+                updated_at.eq(diesel::dsl::now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while updating customer invoice delivery channel")
+            .into_db_result()
+    }
+
+    /// Active customer of the tenant with the same alias, or with the same normalized email or alias
+    /// if the tenant enforces their uniqueness
+    pub async fn find_by_unique_keys(
+        conn: &mut PgConn,
+        param_tenant_id: Uuid,
+        param_alias: Option<String>,
+        param_email: Option<String>,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl as c_dsl;
+        use diesel_async::RunQueryDsl;
+
+        // same normalization as the customer trigger
+        let normalize = |v: &String| Some(v.trim().to_lowercase()).filter(|v| !v.is_empty());
+
+        let normalized_alias = param_alias.as_ref().and_then(normalize);
+        let normalized_email = param_email.as_ref().and_then(normalize);
+
+        let query = c_dsl::customer
+            .filter(c_dsl::tenant_id.eq(param_tenant_id))
+            .filter(c_dsl::archived_at.is_null())
+            .filter(
+                c_dsl::alias
+                    .eq(param_alias)
+                    .or(c_dsl::unique_alias.eq(normalized_alias))
+                    .or(c_dsl::unique_email.eq(normalized_email)),
+            )
+            .order(c_dsl::id.asc())
+            .select(CustomerRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding customer by unique keys")
+            .into_db_result()
+    }
+
+    /// Recomputes the unique email and alias of the customers of the tenant, after a change of its uniqueness setting.
+    /// The keys are set by the customer trigger, the update fails if the customers are not unique
+    pub async fn refresh_unique_keys(conn: &mut PgConn, param_tenant_id: Uuid) -> DbResult<usize> {
+        use crate::schema::customer::dsl as c_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(c_dsl::customer)
+            .filter(c_dsl::tenant_id.eq(param_tenant_id))
+            .set((
+                c_dsl::unique_email.eq(None::<String>),
+                c_dsl::unique_alias.eq(None::<String>),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while refreshing the customer unique keys")
+            .into_db_result()
+    }
+
     pub async fn update_balance(conn: &mut PgConn, id: Uuid, delta_cents: i32) -> DbResult<usize> {
         use crate::schema::customer::dsl as c_dsl;
         use diesel_async::RunQueryDsl;
@@ -230,3 +554,29 @@ impl CustomerRowPatch {
             .into_db_result()
     }
 }
+
+impl CustomerRowPaymentTermsPatch {
+    pub async fn update(
+        &self,
+        conn: &mut PgConn,
+        param_customer_id: uuid::Uuid,
+        param_tenant_id: uuid::Uuid,
+    ) -> DbResult<Option<CustomerRow>> {
+        use crate::schema::customer::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(customer)
+            .filter(id.eq(param_customer_id))
+            .filter(tenant_id.eq(param_tenant_id))
+            .set(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while updating customer payment terms")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,273 @@
+use crate::enums::InvoiceApprovalStatusEnum;
+use crate::errors::IntoDbResult;
+use crate::invoice_approvals::{
+    InvoiceApprovalEventRow, InvoiceApprovalEventRowNew, InvoiceApprovalRow, InvoiceApprovalRowNew,
+    InvoiceApprovalStepRow, InvoiceApprovalStepRowNew,
+};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl InvoiceApprovalStepRow {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        batch: Vec<InvoiceApprovalStepRowNew>,
+    ) -> DbResult<Vec<InvoiceApprovalStepRow>> {
+        use crate::schema::invoice_approval_step::dsl as ias_dsl;
+
+        let query = diesel::insert_into(ias_dsl::invoice_approval_step).values(batch);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting invoice approval steps")
+            .into_db_result()
+    }
+
+    /// The approval chain of the tenant, in order
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<InvoiceApprovalStepRow>> {
+        use crate::schema::invoice_approval_step::dsl as ias_dsl;
+
+        let query = ias_dsl::invoice_approval_step
+            .filter(ias_dsl::tenant_id.eq(tenant_id))
+            .order(ias_dsl::position.asc())
+            .select(InvoiceApprovalStepRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice approval steps")
+            .into_db_result()
+    }
+
+    pub async fn delete_by_tenant_id(conn: &mut PgConn, tenant_id: Uuid) -> DbResult<usize> {
+        use crate::schema::invoice_approval_step::dsl as ias_dsl;
+
+        let query =
+            diesel::delete(ias_dsl::invoice_approval_step).filter(ias_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting invoice approval steps")
+            .into_db_result()
+    }
+}
+
+impl InvoiceApprovalRow {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        batch: Vec<InvoiceApprovalRowNew>,
+    ) -> DbResult<Vec<InvoiceApprovalRow>> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = diesel::insert_into(ia_dsl::invoice_approval).values(batch);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting invoice approvals")
+            .into_db_result()
+    }
+
+    /// The steps of the approval of an invoice, in order
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Vec<InvoiceApprovalRow>> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = ia_dsl::invoice_approval
+            .filter(ia_dsl::tenant_id.eq(tenant_id))
+            .filter(ia_dsl::invoice_id.eq(invoice_id))
+            .order(ia_dsl::position.asc())
+            .select(InvoiceApprovalRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice approvals")
+            .into_db_result()
+    }
+
+    /// Replaced by a new approval, their events are kept
+    pub async fn delete_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = diesel::delete(ia_dsl::invoice_approval)
+            .filter(ia_dsl::tenant_id.eq(tenant_id))
+            .filter(ia_dsl::invoice_id.eq(invoice_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting invoice approvals")
+            .into_db_result()
+    }
+
+    /// Approves or rejects the step, if still pending
+    pub async fn decide(
+        conn: &mut PgConn,
+        id: Uuid,
+        status: InvoiceApprovalStatusEnum,
+        decided_by: Uuid,
+        comment: Option<String>,
+        now: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = diesel::update(ia_dsl::invoice_approval)
+            .filter(ia_dsl::id.eq(id))
+            .filter(ia_dsl::status.eq(InvoiceApprovalStatusEnum::Pending))
+            .set((
+                ia_dsl::status.eq(status),
+                ia_dsl::decided_by.eq(decided_by),
+                ia_dsl::decided_at.eq(now),
+                ia_dsl::comment.eq(comment),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deciding invoice approval")
+            .into_db_result()
+    }
+
+    /// Makes a waiting step the current one
+    pub async fn start(
+        conn: &mut PgConn,
+        id: Uuid,
+        now: NaiveDateTime,
+        due_at: Option<NaiveDateTime>,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = diesel::update(ia_dsl::invoice_approval)
+            .filter(ia_dsl::id.eq(id))
+            .filter(ia_dsl::status.eq(InvoiceApprovalStatusEnum::Waiting))
+            .set((
+                ia_dsl::status.eq(InvoiceApprovalStatusEnum::Pending),
+                ia_dsl::started_at.eq(now),
+                ia_dsl::due_at.eq(due_at),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while starting invoice approval")
+            .into_db_result()
+    }
+
+    /// The pending steps past their due date, not escalated yet
+    pub async fn list_overdue(
+        conn: &mut PgConn,
+        now: NaiveDateTime,
+        limit: i64,
+    ) -> DbResult<Vec<InvoiceApprovalRow>> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = ia_dsl::invoice_approval
+            .filter(ia_dsl::status.eq(InvoiceApprovalStatusEnum::Pending))
+            .filter(ia_dsl::escalated_at.is_null())
+            .filter(ia_dsl::due_at.le(now))
+            .order(ia_dsl::due_at.asc())
+            .limit(limit)
+            .select(InvoiceApprovalRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing overdue invoice approvals")
+            .into_db_result()
+    }
+
+    /// Returns 0 if the step was decided or escalated concurrently
+    pub async fn escalate(conn: &mut PgConn, id: Uuid, now: NaiveDateTime) -> DbResult<usize> {
+        use crate::schema::invoice_approval::dsl as ia_dsl;
+
+        let query = diesel::update(ia_dsl::invoice_approval)
+            .filter(ia_dsl::id.eq(id))
+            .filter(ia_dsl::status.eq(InvoiceApprovalStatusEnum::Pending))
+            .filter(ia_dsl::escalated_at.is_null())
+            .set(ia_dsl::escalated_at.eq(now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while escalating invoice approval")
+            .into_db_result()
+    }
+}
+
+impl InvoiceApprovalEventRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<InvoiceApprovalEventRow> {
+        use crate::schema::invoice_approval_event::dsl as iae_dsl;
+
+        let query = diesel::insert_into(iae_dsl::invoice_approval_event).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting invoice approval event")
+            .into_db_result()
+    }
+}
+
+impl InvoiceApprovalEventRow {
+    /// The history of the approvals of an invoice, oldest first
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Vec<InvoiceApprovalEventRow>> {
+        use crate::schema::invoice_approval_event::dsl as iae_dsl;
+
+        let query = iae_dsl::invoice_approval_event
+            .filter(iae_dsl::tenant_id.eq(tenant_id))
+            .filter(iae_dsl::invoice_id.eq(invoice_id))
+            .order((iae_dsl::created_at.asc(), iae_dsl::id.asc()))
+            .select(InvoiceApprovalEventRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice approval events")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,101 @@
+use crate::errors::IntoDbResult;
+use crate::invoice_attachments::{InvoiceAttachmentRow, InvoiceAttachmentRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl InvoiceAttachmentRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<InvoiceAttachmentRow> {
+        use crate::schema::invoice_attachment::dsl as ia_dsl;
+
+        let query = diesel::insert_into(ia_dsl::invoice_attachment).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting invoice attachment")
+            .into_db_result()
+    }
+}
+
+impl InvoiceAttachmentRow {
+    /// The attachments not removed, oldest first
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Vec<InvoiceAttachmentRow>> {
+        use crate::schema::invoice_attachment::dsl as ia_dsl;
+
+        let query = ia_dsl::invoice_attachment
+            .filter(ia_dsl::tenant_id.eq(tenant_id))
+            .filter(ia_dsl::invoice_id.eq(invoice_id))
+            .filter(ia_dsl::removed_at.is_null())
+            .order(ia_dsl::created_at.asc())
+            .select(InvoiceAttachmentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice attachments")
+            .into_db_result()
+    }
+
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<Option<InvoiceAttachmentRow>> {
+        use crate::schema::invoice_attachment::dsl as ia_dsl;
+
+        let query = ia_dsl::invoice_attachment
+            .filter(ia_dsl::tenant_id.eq(tenant_id))
+            .filter(ia_dsl::id.eq(id))
+            .filter(ia_dsl::removed_at.is_null())
+            .select(InvoiceAttachmentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding invoice attachment")
+            .into_db_result()
+    }
+
+    /// The attachment is kept for the audit, its content as well
+    pub async fn remove(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+        removed_by: Uuid,
+    ) -> DbResult<InvoiceAttachmentRow> {
+        use crate::schema::invoice_attachment::dsl as ia_dsl;
+
+        let query = diesel::update(ia_dsl::invoice_attachment)
+            .filter(ia_dsl::tenant_id.eq(tenant_id))
+            .filter(ia_dsl::id.eq(id))
+            .filter(ia_dsl::removed_at.is_null())
+            .set((
+                ia_dsl::removed_at.eq(diesel::dsl::now),
+                ia_dsl::removed_by.eq(removed_by),
+            ))
+            .returning(InvoiceAttachmentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while removing invoice attachment")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,70 @@
+use crate::errors::IntoDbResult;
+use crate::invoice_payments::{InvoicePaymentRow, InvoicePaymentRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl InvoicePaymentRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<InvoicePaymentRow> {
+        use crate::schema::invoice_payment::dsl as ip_dsl;
+
+        let query = diesel::insert_into(ip_dsl::invoice_payment).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting invoice payment")
+            .into_db_result()
+    }
+}
+
+impl InvoicePaymentRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<InvoicePaymentRow> {
+        use crate::schema::invoice_payment::dsl as ip_dsl;
+
+        let query = ip_dsl::invoice_payment
+            .filter(ip_dsl::tenant_id.eq(tenant_id))
+            .filter(ip_dsl::id.eq(id))
+            .select(InvoicePaymentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding invoice payment by id")
+            .into_db_result()
+    }
+
+    /// Oldest first
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Vec<InvoicePaymentRow>> {
+        use crate::schema::invoice_payment::dsl as ip_dsl;
+
+        let query = ip_dsl::invoice_payment
+            .filter(ip_dsl::tenant_id.eq(tenant_id))
+            .filter(ip_dsl::invoice_id.eq(invoice_id))
+            .order((ip_dsl::paid_at.asc(), ip_dsl::created_at.asc()))
+            .select(InvoicePaymentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice payments")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,172 @@
+use crate::errors::IntoDbResult;
+use crate::invoice_stuck_alerts::{InvoiceStuckAlertRow, InvoiceStuckAlertRowNew, StuckInvoiceRow};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::upsert::excluded;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl StuckInvoiceRow {
+    /// Invoices stuck beyond the SLAs of their tenant, for a tenant or for all tenants :
+    /// - drafts whose invoice date is older than `draft_max_age_days`
+    /// - pending invoices not finalized `pending_max_age_hours` after the end of their grace period
+    /// - finalized invoices that could not be issued after `max_issue_attempts`
+    ///
+    /// The given SLAs apply to the tenants that don't set their own
+    pub async fn list(
+        conn: &mut PgConn,
+        tenant_id: Option<Uuid>,
+        now: NaiveDateTime,
+        draft_max_age_days: i32,
+        pending_max_age_hours: i32,
+        max_issue_attempts: i32,
+    ) -> DbResult<Vec<StuckInvoiceRow>> {
+        let raw_sql = r#"
+SELECT invoice.id, invoice.tenant_id, invoice.customer_id, invoice.invoice_number, invoice.status,
+       'STALE_DRAFT'::"InvoiceStuckReasonEnum" AS reason,
+       invoice.invoice_date::timestamp AS stuck_since,
+       invoice.last_issue_error
+FROM invoice
+INNER JOIN tenant ON invoice.tenant_id = tenant.id
+WHERE invoice.status = 'DRAFT'
+  AND invoice.invoice_date < $1 - interval '1 day' * COALESCE(tenant.stuck_draft_max_age_days, $2)
+  AND ($5::uuid IS NULL OR invoice.tenant_id = $5)
+UNION ALL
+SELECT invoice.id, invoice.tenant_id, invoice.customer_id, invoice.invoice_number, invoice.status,
+       'PENDING_FINALIZATION'::"InvoiceStuckReasonEnum" AS reason,
+       invoice.invoice_date + interval '1 hour' * COALESCE(tenant.finalization_grace_period_hours, invoicing_entity.grace_period_hours) AS stuck_since,
+       invoice.last_issue_error
+FROM invoice
+INNER JOIN customer ON invoice.customer_id = customer.id
+INNER JOIN invoicing_entity ON customer.invoicing_entity_id = invoicing_entity.id
+INNER JOIN tenant ON invoice.tenant_id = tenant.id
+WHERE invoice.status = 'PENDING'
+  AND invoice.invoice_date + interval '1 hour' * (COALESCE(tenant.finalization_grace_period_hours, invoicing_entity.grace_period_hours) + COALESCE(tenant.stuck_pending_max_age_hours, $3)) < $1
+  AND ($5::uuid IS NULL OR invoice.tenant_id = $5)
+UNION ALL
+SELECT invoice.id, invoice.tenant_id, invoice.customer_id, invoice.invoice_number, invoice.status,
+       'ISSUE_RETRIES_EXHAUSTED'::"InvoiceStuckReasonEnum" AS reason,
+       coalesce(invoice.last_issue_attempt_at::timestamp, invoice.finalized_at, invoice.created_at::timestamp) AS stuck_since,
+       invoice.last_issue_error
+FROM invoice
+INNER JOIN tenant ON invoice.tenant_id = tenant.id
+WHERE invoice.status = 'FINALIZED'
+  AND invoice.invoicing_provider != 'MANUAL'
+  AND invoice.issued = false
+  AND invoice.suppressed_at IS NULL
+  AND invoice.issue_attempts >= COALESCE(tenant.stuck_max_issue_attempts, $4)
+  AND ($5::uuid IS NULL OR invoice.tenant_id = $5)
+ORDER BY stuck_since;
+        "#;
+
+        let query = diesel::sql_query(raw_sql)
+            .bind::<diesel::sql_types::Timestamp, _>(now)
+            .bind::<diesel::sql_types::Integer, _>(draft_max_age_days)
+            .bind::<diesel::sql_types::Integer, _>(pending_max_age_hours)
+            .bind::<diesel::sql_types::Integer, _>(max_issue_attempts)
+            .bind::<diesel::sql_types::Nullable<diesel::sql_types::Uuid>, _>(tenant_id);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing stuck invoices")
+            .into_db_result()
+    }
+}
+
+impl InvoiceStuckAlertRowNew {
+    /// Records the alert, replacing the previous one if the invoice got stuck for another reason or again
+    pub async fn upsert(&self, conn: &mut PgConn) -> DbResult<InvoiceStuckAlertRow> {
+        use crate::schema::invoice_stuck_alert::dsl as isa_dsl;
+
+        let query = diesel::insert_into(isa_dsl::invoice_stuck_alert)
+            .values(self)
+            .on_conflict(isa_dsl::invoice_id)
+            .do_update()
+            .set((
+                isa_dsl::reason.eq(excluded(isa_dsl::reason)),
+                isa_dsl::alerted_at.eq(excluded(isa_dsl::alerted_at)),
+                isa_dsl::resolved_at.eq(None::<NaiveDateTime>),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while upserting invoice stuck alert")
+            .into_db_result()
+    }
+}
+
+impl InvoiceStuckAlertRow {
+    pub async fn find_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Option<InvoiceStuckAlertRow>> {
+        use crate::schema::invoice_stuck_alert::dsl as isa_dsl;
+
+        let query = isa_dsl::invoice_stuck_alert
+            .filter(isa_dsl::invoice_id.eq(invoice_id))
+            .filter(isa_dsl::tenant_id.eq(tenant_id))
+            .select(InvoiceStuckAlertRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding invoice stuck alert")
+            .into_db_result()
+    }
+
+    /// The alerts still open among the given invoices
+    pub async fn list_unresolved_by_invoice_ids(
+        conn: &mut PgConn,
+        invoice_ids: &[Uuid],
+    ) -> DbResult<Vec<InvoiceStuckAlertRow>> {
+        use crate::schema::invoice_stuck_alert::dsl as isa_dsl;
+
+        let query = isa_dsl::invoice_stuck_alert
+            .filter(isa_dsl::invoice_id.eq_any(invoice_ids))
+            .filter(isa_dsl::resolved_at.is_null())
+            .select(InvoiceStuckAlertRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing invoice stuck alerts")
+            .into_db_result()
+    }
+
+    /// Resolves the open alerts of the invoices that are not stuck anymore, so that they alert again if they get stuck later
+    pub async fn resolve_all_except(
+        conn: &mut PgConn,
+        stuck_invoice_ids: &[Uuid],
+        now: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice_stuck_alert::dsl as isa_dsl;
+
+        let query = diesel::update(isa_dsl::invoice_stuck_alert)
+            .filter(isa_dsl::resolved_at.is_null())
+            .filter(isa_dsl::invoice_id.ne_all(stuck_invoice_ids))
+            .set(isa_dsl::resolved_at.eq(now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while resolving invoice stuck alerts")
+            .into_db_result()
+    }
+}
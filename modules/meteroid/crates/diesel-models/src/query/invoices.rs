@@ -1,12 +1,16 @@
 use crate::errors::IntoDbResult;
 use crate::invoices::{
-    DetailedInvoiceRow, InvoiceRow, InvoiceRowLinesPatch, InvoiceRowNew, InvoiceWithCustomerRow,
+    DetailedInvoiceRow, InvoiceRow, InvoiceRowDraftEdit, InvoiceRowExternalLinksPatch,
+    InvoiceRowLinesPatch, InvoiceRowNew, InvoiceWithCustomerRow,
 };
 use chrono::NaiveDateTime;
 
 use crate::{DbResult, PgConn};
 
-use crate::enums::{InvoiceExternalStatusEnum, InvoiceStatusEnum, InvoicingProviderEnum};
+use crate::enums::{
+    BillingPeriodEnum, InvoiceDeliveryChannelEnum, InvoiceDeliveryStatusEnum,
+    InvoiceExternalStatusEnum, InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum,
+};
 use crate::extend::cursor_pagination::{
     CursorPaginate, CursorPaginatedVec, CursorPaginationRequest,
 };
@@ -14,7 +18,7 @@ use crate::extend::order::OrderByRequest;
 use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
 use diesel::dsl::IntervalDsl;
 use diesel::{
-    debug_query, BoolExpressionMethods, JoinOnDsl, NullableExpressionMethods,
+    debug_query, BoolExpressionMethods, JoinOnDsl, NullableExpressionMethods, OptionalExtension,
     PgTextExpressionMethods, SelectableHelper,
 };
 use diesel::{ExpressionMethods, QueryDsl};
@@ -71,6 +75,29 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    pub async fn find_id_by_external_invoice_id(
+        conn: &mut PgConn,
+        param_tenant_id: uuid::Uuid,
+        param_external_invoice_id: String,
+    ) -> DbResult<Option<uuid::Uuid>> {
+        use crate::schema::invoice::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = invoice
+            .filter(tenant_id.eq(param_tenant_id))
+            .filter(external_invoice_id.eq(param_external_invoice_id))
+            .select(id);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding invoice by external invoice id")
+            .into_db_result()
+    }
+
     pub async fn list(
         conn: &mut PgConn,
         param_tenant_id: uuid::Uuid,
@@ -143,6 +170,86 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    pub async fn list_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        subscription_id: uuid::Uuid,
+    ) -> DbResult<Vec<InvoiceRow>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::subscription_id.eq(subscription_id))
+            .order(i_dsl::invoice_date.asc())
+            .select(InvoiceRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load(conn)
+            .await
+            .attach_printable("Error while fetching invoices by subscription id")
+            .into_db_result()
+    }
+
+    /// The subscription invoices that carry MRR movements, in the order they were issued
+    pub async fn list_mrr_invoices_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<Vec<InvoiceRow>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::subscription_id.is_not_null())
+            .filter(
+                i_dsl::invoice_type.eq_any(vec![InvoiceType::Recurring, InvoiceType::Adjustment]),
+            )
+            .filter(i_dsl::status.ne(InvoiceStatusEnum::Void))
+            .order((i_dsl::invoice_date.asc(), i_dsl::created_at.asc()))
+            .select(InvoiceRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load(conn)
+            .await
+            .attach_printable("Error while fetching mrr invoices by tenant id")
+            .into_db_result()
+    }
+
+    /// The invoices of the tenant finalized with a date within [from, to], including the ones voided since,
+    /// in the order they were issued
+    pub async fn list_finalized_by_invoice_date(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        from: chrono::NaiveDate,
+        to: chrono::NaiveDate,
+    ) -> DbResult<Vec<InvoiceRow>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                i_dsl::status.eq_any(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .filter(i_dsl::finalized_at.is_not_null())
+            .filter(i_dsl::invoice_date.between(from, to))
+            .order((i_dsl::invoice_date.asc(), i_dsl::created_at.asc()))
+            .select(InvoiceRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load(conn)
+            .await
+            .attach_printable("Error while fetching finalized invoices by invoice date")
+            .into_db_result()
+    }
+
     pub async fn insert_invoice_batch(
         conn: &mut PgConn,
         invoices: Vec<InvoiceRowNew>,
@@ -161,6 +268,32 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// Serializes the concurrent updates of the invoice, until the end of the transaction
+    pub async fn lock_for_update(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<()> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .for_update()
+            .select(i_dsl::id)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        let _res: uuid::Uuid = query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while locking invoice for update")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
     pub async fn update_external_status(
         conn: &mut PgConn,
         id: uuid::Uuid,
@@ -187,6 +320,36 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// Deducts a payment from the amount due of a finalized invoice. Returns 0 if the invoice is not finalized
+    /// or the payment exceeds its amount due
+    pub async fn deduct_amount_due(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        amount: i64,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::status.eq(InvoiceStatusEnum::Finalized))
+            .filter(i_dsl::amount_due.ge(amount))
+            .set((
+                i_dsl::amount_due.eq(i_dsl::amount_due - amount),
+                i_dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deducting invoice amount_due")
+            .into_db_result()
+    }
+
     pub async fn list_to_finalize(
         conn: &mut PgConn,
         pagination: CursorPaginationRequest,
@@ -194,17 +357,21 @@ impl InvoiceRow {
         use crate::schema::customer::dsl as c_dsl;
         use crate::schema::invoice::dsl as i_dsl;
         use crate::schema::invoicing_entity::dsl as ie_dsl;
+        use crate::schema::tenant::dsl as t_dsl;
 
         let query = i_dsl::invoice
             .inner_join(c_dsl::customer.on(i_dsl::customer_id.eq(c_dsl::id)))
             .inner_join(ie_dsl::invoicing_entity.on(c_dsl::invoicing_entity_id.eq(ie_dsl::id)))
+            .inner_join(t_dsl::tenant.on(i_dsl::tenant_id.eq(t_dsl::id)))
             .filter(
                 i_dsl::status.ne_all(vec![InvoiceStatusEnum::Void, InvoiceStatusEnum::Finalized]),
             )
-            .filter(diesel::dsl::now.gt(i_dsl::invoice_date
-                + diesel::dsl::sql::<diesel::sql_types::Interval>(
-                    "\"invoicing_entity\".\"grace_period_hours\" * INTERVAL '1 hour'",
-                )))
+            // the invoice date starts at midnight in the billing timezone of the tenant,
+            // the grace period of the tenant overrides the one of the invoicing entity
+            .filter(diesel::dsl::now.gt(diesel::dsl::sql::<diesel::sql_types::Timestamp>(
+                "(\"invoice\".\"invoice_date\"::timestamp AT TIME ZONE \"tenant\".\"billing_timezone\" AT TIME ZONE 'UTC') \
+                + COALESCE(\"tenant\".\"finalization_grace_period_hours\", \"invoicing_entity\".\"grace_period_hours\") * INTERVAL '1 hour'",
+            )))
             .select(InvoiceRow::as_select())
             .cursor_paginate(pagination, "id");
 
@@ -223,6 +390,7 @@ impl InvoiceRow {
         tenant_id: uuid::Uuid,
         new_invoice_number: String,
         applied_coupon_ids: &[uuid::Uuid],
+        seller_details: serde_json::Value,
     ) -> DbResult<usize> {
         use crate::schema::invoice::dsl as i_dsl;
         use diesel_async::RunQueryDsl;
@@ -246,6 +414,7 @@ impl InvoiceRow {
                 i_dsl::finalized_at.eq(now),
                 i_dsl::invoice_number.eq(new_invoice_number),
                 i_dsl::applied_coupon_ids.eq(applied_coupon_ids),
+                i_dsl::seller_details.eq(seller_details),
             ));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -257,6 +426,258 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// Voids a zero-amount invoice instead of finalizing it, keeping it as a record of the processed period
+    pub async fn suppress(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                i_dsl::status.ne_all(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .set((
+                i_dsl::status.eq(InvoiceStatusEnum::Void),
+                i_dsl::updated_at.eq(now),
+                i_dsl::data_updated_at.eq(now),
+                i_dsl::suppressed_at.eq(now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while suppressing invoice")
+            .into_db_result()
+    }
+
+    /// Voids the invoices of a subscription that are not finalized yet, ex: when its trial expires
+    pub async fn void_pending_by_subscription_id(
+        conn: &mut PgConn,
+        subscription_id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::subscription_id.eq(subscription_id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                i_dsl::status.ne_all(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .set((
+                i_dsl::status.eq(InvoiceStatusEnum::Void),
+                i_dsl::updated_at.eq(now),
+                i_dsl::data_updated_at.eq(now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while voiding pending invoices of subscription")
+            .into_db_result()
+    }
+
+    /// Dates a draft invoice manually, before its finalization
+    pub async fn set_manual_invoice_date(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        invoice_date: chrono::NaiveDate,
+        due_at: Option<NaiveDateTime>,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                i_dsl::status.ne_all(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .set((
+                i_dsl::invoice_date.eq(invoice_date),
+                i_dsl::due_at.eq(due_at),
+                i_dsl::manual_invoice_date.eq(true),
+                i_dsl::updated_at.eq(now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while setting the invoice date")
+            .into_db_result()
+    }
+
+    /// The latest invoice date of the finalized invoices numbered by the invoicing entity
+    pub async fn last_finalized_invoice_date(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        invoicing_entity_id: uuid::Uuid,
+    ) -> DbResult<Option<chrono::NaiveDate>> {
+        use crate::schema::customer::dsl as c_dsl;
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .inner_join(c_dsl::customer.on(i_dsl::customer_id.eq(c_dsl::id)))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(c_dsl::invoicing_entity_id.eq(invoicing_entity_id))
+            .filter(
+                i_dsl::status.eq_any(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .filter(i_dsl::finalized_at.is_not_null())
+            .select(diesel::dsl::max(i_dsl::invoice_date));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while fetching the last finalized invoice date")
+            .into_db_result()
+    }
+
+    /// Voids a finalized invoice. Returns 0 if the invoice is not finalized
+    pub async fn void(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        reason: String,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::status.eq(InvoiceStatusEnum::Finalized))
+            .set((
+                i_dsl::status.eq(InvoiceStatusEnum::Void),
+                i_dsl::updated_at.eq(now),
+                i_dsl::voided_at.eq(now),
+                i_dsl::void_reason.eq(reason),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while voiding invoice")
+            .into_db_result()
+    }
+
+    /// Voids the draft recurring invoices of a subscription dated after `date`
+    pub async fn void_subscription_drafts_after(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        subscription_id: uuid::Uuid,
+        date: chrono::NaiveDate,
+    ) -> DbResult<Vec<uuid::Uuid>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::subscription_id.eq(subscription_id))
+            .filter(i_dsl::invoice_type.eq(InvoiceType::Recurring))
+            .filter(i_dsl::invoice_date.gt(date))
+            .filter(
+                i_dsl::status.ne_all(vec![InvoiceStatusEnum::Finalized, InvoiceStatusEnum::Void]),
+            )
+            .set((
+                i_dsl::status.eq(InvoiceStatusEnum::Void),
+                i_dsl::updated_at.eq(now),
+                i_dsl::data_updated_at.eq(now),
+            ))
+            .returning(i_dsl::id);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while voiding subscription draft invoices")
+            .into_db_result()
+    }
+
+    /// The billing cadences of the recurring invoices of a subscription dated after `date`, with the months of a custom cadence.
+    /// None for an invoice of all the cadences
+    pub async fn list_future_recurring_cadences(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        subscription_id: uuid::Uuid,
+        date: chrono::NaiveDate,
+    ) -> DbResult<Vec<(Option<BillingPeriodEnum>, Option<i32>)>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::subscription_id.eq(subscription_id))
+            .filter(i_dsl::invoice_type.eq(InvoiceType::Recurring))
+            .filter(i_dsl::invoice_date.gt(date))
+            .filter(i_dsl::status.ne(InvoiceStatusEnum::Void))
+            .select((i_dsl::billing_cadence, i_dsl::billing_cadence_months));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load(conn)
+            .await
+            .attach_printable("Error while fetching subscription future invoice cadences")
+            .into_db_result()
+    }
+
+    /// Marks a finalized invoice as not to be issued to the invoicing provider
+    pub async fn skip_issue(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::status.eq(InvoiceStatusEnum::Finalized))
+            .set((
+                i_dsl::issued.eq(true),
+                i_dsl::updated_at.eq(chrono::Utc::now().naive_utc()),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while skipping invoice issue")
+            .into_db_result()
+    }
+
     pub async fn save_invoice_documents(
         conn: &mut PgConn,
         id: uuid::Uuid,
@@ -297,8 +718,11 @@ impl InvoiceRow {
             .filter(
                 i_dsl::data_updated_at
                     .is_null()
-                    .or(diesel::dsl::now.gt(i_dsl::invoice_date + 1.hour())),
+                    .or(diesel::dsl::now.gt(i_dsl::invoice_date + 1.hour()))
+                    .or(i_dsl::usage_outdated_at.is_not_null()),
             )
+            // the lines edited by a user are not recomputed
+            .filter(i_dsl::manually_edited_at.is_null())
             .select(InvoiceRow::as_select())
             .cursor_paginate(pagination, "id");
 
@@ -311,18 +735,146 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// The finalized invoices with usage backfilled into their period since they were priced
+    pub async fn list_usage_outdated_finalized(
+        conn: &mut PgConn,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<InvoiceRow>> {
+        use crate::schema::invoice::dsl as i_dsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::status.eq(InvoiceStatusEnum::Finalized))
+            .filter(i_dsl::usage_outdated_at.is_not_null())
+            .select(InvoiceRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while paginating usage outdated invoices")
+            .into_db_result()
+    }
+
+    /// The recurring invoices of the customer dated from the given date, whose usage period may include it
+    pub async fn list_usage_rerating_candidates(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        customer_id: uuid::Uuid,
+        from: chrono::NaiveDate,
+    ) -> DbResult<Vec<InvoiceRow>> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = i_dsl::invoice
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::customer_id.eq(customer_id))
+            .filter(i_dsl::invoice_type.eq(InvoiceType::Recurring))
+            .filter(i_dsl::status.ne(InvoiceStatusEnum::Void))
+            .filter(i_dsl::invoice_date.ge(from))
+            .select(InvoiceRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load(conn)
+            .await
+            .attach_printable("Error while fetching usage rerating candidates")
+            .into_db_result()
+    }
+
+    pub async fn mark_usage_outdated(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        ids: Vec<uuid::Uuid>,
+        now: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::id.eq_any(ids))
+            .set(i_dsl::usage_outdated_at.eq(now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while marking invoices usage as outdated")
+            .into_db_result()
+    }
+
+    /// Only if not marked again since `marked_at`, in which case the invoice is re-rated once more
+    pub async fn clear_usage_outdated(
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        marked_at: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id))
+            .filter(i_dsl::tenant_id.eq(tenant_id))
+            .filter(i_dsl::usage_outdated_at.eq(marked_at))
+            .set(i_dsl::usage_outdated_at.eq(None::<NaiveDateTime>));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while clearing invoice usage outdated")
+            .into_db_result()
+    }
+
+    /// Invoices of customers in a blackout window at the given date are left out, until the window ends
     pub async fn list_to_issue(
         conn: &mut PgConn,
         max_attempts: i32,
+        today: chrono::NaiveDate,
         pagination: CursorPaginationRequest,
     ) -> DbResult<CursorPaginatedVec<InvoiceRow>> {
+        use crate::schema::customer::dsl as c_dsl;
+        use crate::schema::customer_blackout_window::dsl as cbw_dsl;
         use crate::schema::invoice::dsl as i_dsl;
 
         let query = i_dsl::invoice
             .filter(i_dsl::status.eq(InvoiceStatusEnum::Finalized))
-            .filter(i_dsl::invoicing_provider.ne(InvoicingProviderEnum::Manual))
+            // manual invoices are only issued when delivered to the customer by another channel
+            .filter(
+                i_dsl::invoicing_provider
+                    .ne(InvoicingProviderEnum::Manual)
+                    .or(diesel::dsl::exists(
+                        c_dsl::customer
+                            .filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+                                "customer.id = invoice.customer_id",
+                            ))
+                            .filter(
+                                c_dsl::invoice_delivery_channel
+                                    .eq(InvoiceDeliveryChannelEnum::Email)
+                                    .or(c_dsl::invoice_delivery_channel
+                                        .eq(InvoiceDeliveryChannelEnum::Webhook)),
+                            )
+                            .select(diesel::dsl::sql::<diesel::sql_types::Integer>("1")),
+                    )),
+            )
             .filter(i_dsl::issued.eq(false))
             .filter(i_dsl::issue_attempts.lt(max_attempts))
+            .filter(diesel::dsl::not(diesel::dsl::exists(
+                cbw_dsl::customer_blackout_window
+                    // correlated to the outer invoice, not expressible with the dsl
+                    .filter(diesel::dsl::sql::<diesel::sql_types::Bool>(
+                        "customer_blackout_window.customer_id = invoice.customer_id",
+                    ))
+                    .filter(cbw_dsl::start_date.le(today))
+                    .filter(cbw_dsl::end_date.ge(today))
+                    .select(diesel::dsl::sql::<diesel::sql_types::Integer>("1")),
+            )))
             .select(InvoiceRow::as_select())
             .cursor_paginate(pagination, "id");
 
@@ -335,10 +887,15 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// Delivery is recorded when the invoice was sent to the customer through a delivery channel
     pub async fn issue_success(
         conn: &mut PgConn,
         id: uuid::Uuid,
         tenant_id: uuid::Uuid,
+        external_invoice_id: Option<String>,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+        hosted_invoice_url: Option<String>,
+        external_pdf_url: Option<String>,
     ) -> DbResult<usize> {
         use crate::schema::invoice::dsl as i_dsl;
         use diesel_async::RunQueryDsl;
@@ -355,6 +912,13 @@ impl InvoiceRow {
                 i_dsl::issue_attempts.eq(i_dsl::issue_attempts + 1),
                 i_dsl::updated_at.eq(now),
                 i_dsl::last_issue_attempt_at.eq(now),
+                i_dsl::external_invoice_id.eq(external_invoice_id),
+                i_dsl::delivery_channel.eq(delivery_channel),
+                i_dsl::delivery_status
+                    .eq(delivery_channel.map(|_| InvoiceDeliveryStatusEnum::Delivered)),
+                i_dsl::delivered_at.eq(delivery_channel.map(|_| now)),
+                i_dsl::hosted_invoice_url.eq(hosted_invoice_url),
+                i_dsl::external_pdf_url.eq(external_pdf_url),
             ));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -371,6 +935,7 @@ impl InvoiceRow {
         id: uuid::Uuid,
         tenant_id: uuid::Uuid,
         last_issue_error: &str,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
     ) -> DbResult<usize> {
         use crate::schema::invoice::dsl as i_dsl;
         use diesel_async::RunQueryDsl;
@@ -387,6 +952,9 @@ impl InvoiceRow {
                 i_dsl::issue_attempts.eq(i_dsl::issue_attempts + 1),
                 i_dsl::updated_at.eq(now),
                 i_dsl::last_issue_attempt_at.eq(now),
+                i_dsl::delivery_channel.eq(delivery_channel),
+                i_dsl::delivery_status
+                    .eq(delivery_channel.map(|_| InvoiceDeliveryStatusEnum::Failed)),
             ));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -398,6 +966,37 @@ impl InvoiceRow {
             .into_db_result()
     }
 
+    /// Keeps the due dates of the invoices not finalized yet at the start of their day, when the billing timezone of the tenant changes
+    pub async fn shift_due_dates_timezone(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        from_timezone: &str,
+        to_timezone: &str,
+    ) -> DbResult<usize> {
+        use diesel_async::RunQueryDsl;
+
+        let raw_sql = r#"
+UPDATE invoice
+SET due_at = (due_at AT TIME ZONE 'UTC' AT TIME ZONE $2) AT TIME ZONE $3 AT TIME ZONE 'UTC'
+WHERE tenant_id = $1
+  AND status IN ('DRAFT', 'PENDING')
+  AND due_at IS NOT NULL;
+        "#;
+
+        let query = diesel::sql_query(raw_sql)
+            .bind::<diesel::sql_types::Uuid, _>(tenant_id)
+            .bind::<diesel::sql_types::Text, _>(from_timezone)
+            .bind::<diesel::sql_types::Text, _>(to_timezone);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while shifting the due dates of the invoices")
+            .into_db_result()
+    }
+
     pub async fn update_pending_finalization(
         conn: &mut PgConn,
         now: NaiveDateTime,
@@ -406,16 +1005,20 @@ impl InvoiceRow {
 
         // diesel doesn't support update/delete with joins https://github.com/diesel-rs/diesel/issues/1478
         // also the id::eq_any(subquery_with_joins) doesn't work when the subquery is on the same table
+        // The invoice date starts at midnight in the billing timezone of the tenant, `now` being in UTC
+        // The grace period of the tenant overrides the one of the invoicing entity
         let raw_sql = r#"
 UPDATE invoice
 SET status = 'PENDING',
     updated_at = $1
 FROM customer
 INNER JOIN invoicing_entity ON customer.invoicing_entity_id = invoicing_entity.id
+INNER JOIN tenant ON customer.tenant_id = tenant.id
 WHERE invoice.customer_id = customer.id
   AND invoice.status = 'DRAFT'
-  AND invoice.invoice_date < $2
-  AND $3 <= (invoice.invoice_date + interval '1 hour' * invoicing_entity.grace_period_hours);
+  AND (invoice.invoice_date::timestamp AT TIME ZONE tenant.billing_timezone AT TIME ZONE 'UTC') < $2
+  AND $3 <= ((invoice.invoice_date::timestamp AT TIME ZONE tenant.billing_timezone AT TIME ZONE 'UTC')
+      + interval '1 hour' * COALESCE(tenant.finalization_grace_period_hours, invoicing_entity.grace_period_hours));
         "#;
 
         let query = diesel::sql_query(raw_sql)
@@ -433,6 +1036,42 @@ WHERE invoice.customer_id = customer.id
     }
 }
 
+impl InvoiceRowDraftEdit {
+    /// Saves the edit of a user along with the recomputed lines, unless the invoice was finalized or voided meanwhile
+    pub async fn update_draft(
+        &self,
+        conn: &mut PgConn,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        lines: &InvoiceRowLinesPatch,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id).and(i_dsl::tenant_id.eq(tenant_id)))
+            .filter(
+                i_dsl::status.eq_any(vec![InvoiceStatusEnum::Draft, InvoiceStatusEnum::Pending]),
+            )
+            .set((
+                self,
+                lines,
+                i_dsl::updated_at.eq(now),
+                i_dsl::data_updated_at.eq(now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating draft invoice")
+            .into_db_result()
+    }
+}
+
 impl InvoiceRowLinesPatch {
     pub async fn update_lines(
         &self,
@@ -443,9 +1082,10 @@ impl InvoiceRowLinesPatch {
         use crate::schema::invoice::dsl as i_dsl;
         use diesel_async::RunQueryDsl;
 
+        // the recomputed lines include the backfilled usage
         let query = diesel::update(i_dsl::invoice)
             .filter(i_dsl::id.eq(id).and(i_dsl::tenant_id.eq(tenant_id)))
-            .set(self);
+            .set((self, i_dsl::usage_outdated_at.eq(None::<NaiveDateTime>)));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
 
@@ -456,3 +1096,27 @@ impl InvoiceRowLinesPatch {
             .into_db_result()
     }
 }
+
+impl InvoiceRowExternalLinksPatch {
+    pub async fn update(
+        &self,
+        id: uuid::Uuid,
+        tenant_id: uuid::Uuid,
+        conn: &mut PgConn,
+    ) -> DbResult<usize> {
+        use crate::schema::invoice::dsl as i_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(i_dsl::invoice)
+            .filter(i_dsl::id.eq(id).and(i_dsl::tenant_id.eq(tenant_id)))
+            .set((self, i_dsl::updated_at.eq(chrono::Utc::now().naive_utc())));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating invoice external links")
+            .into_db_result()
+    }
+}
@@ -1,5 +1,8 @@
 use crate::errors::IntoDbResult;
-use crate::invoicing_entities::{InvoicingEntityRow, InvoicingEntityRowPatch};
+use crate::invoicing_entities::{
+    InvoicingEntityRow, InvoicingEntityRowPatch, InvoicingEntityTaxRegistrationRow,
+    InvoicingEntityTaxRegistrationRowNew,
+};
 
 use crate::{DbResult, PgConn};
 
@@ -219,3 +222,69 @@ impl InvoicingEntityRowPatch {
             .into_db_result()
     }
 }
+
+impl InvoicingEntityTaxRegistrationRow {
+    pub async fn list_by_invoicing_entity_id(
+        conn: &mut PgConn,
+        invoicing_entity_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+    ) -> DbResult<Vec<InvoicingEntityTaxRegistrationRow>> {
+        use crate::schema::invoicing_entity_tax_registration::dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = dsl::invoicing_entity_tax_registration
+            .filter(dsl::invoicing_entity_id.eq(invoicing_entity_id))
+            .filter(dsl::tenant_id.eq(tenant_id))
+            .order(dsl::created_at.asc())
+            .select(InvoicingEntityTaxRegistrationRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while fetching invoicing entity tax registrations")
+            .into_db_result()
+    }
+
+    pub async fn delete_by_invoicing_entity_id(
+        conn: &mut PgConn,
+        invoicing_entity_id: &uuid::Uuid,
+        tenant_id: &uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::invoicing_entity_tax_registration::dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(dsl::invoicing_entity_tax_registration)
+            .filter(dsl::invoicing_entity_id.eq(invoicing_entity_id))
+            .filter(dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting invoicing entity tax registrations")
+            .into_db_result()
+    }
+}
+
+impl InvoicingEntityTaxRegistrationRowNew {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        rows: &[InvoicingEntityTaxRegistrationRowNew],
+    ) -> DbResult<Vec<InvoicingEntityTaxRegistrationRow>> {
+        use crate::schema::invoicing_entity_tax_registration::dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(dsl::invoicing_entity_tax_registration).values(rows);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting invoicing entity tax registrations")
+            .into_db_result()
+    }
+}
@@ -1,30 +1,56 @@
+pub mod accounting_exports;
 pub mod add_ons;
 pub mod api_tokens;
 pub mod applied_coupons;
+pub mod audit_logs;
 pub mod bi;
 pub mod billable_metrics;
+pub mod billing_runs;
+pub mod collection_strategies;
+pub mod component_rules;
 pub mod configs;
 pub mod coupons;
+pub mod credit_limits;
 pub mod customer_balance_txs;
+pub mod customer_blackout_windows;
 pub mod customers;
 pub mod historical_rates_from_usd;
+pub mod invoice_approvals;
+pub mod invoice_attachments;
+pub mod invoice_payments;
+pub mod invoice_stuck_alerts;
 pub mod invoices;
 pub mod invoicing_entities;
+pub mod notification_bundles;
+pub mod onboarding_steps;
 pub mod organization_members;
 pub mod organizations;
 pub mod outbox;
+pub mod partners;
+pub mod payment_reminders;
+pub mod plan_version_features;
 pub mod plan_versions;
 pub mod plans;
 pub mod price_components;
 pub mod product_families;
 pub mod products;
+pub mod rate_cards;
 pub mod schedules;
+pub mod seat_increase_requests;
 pub mod slot_transactions;
 pub mod stats;
 pub mod subscription_add_ons;
+pub mod subscription_component_history;
 pub mod subscription_components;
 pub mod subscription_events;
+pub mod subscription_migrations;
+pub mod subscription_pending_changes;
+pub mod subscription_phases;
 pub mod subscriptions;
+pub mod tenant_member_roles;
 pub mod tenants;
+pub mod usage_alert_rules;
+pub mod usage_alerts;
+pub mod usage_caps;
 pub mod users;
 pub mod webhooks;
@@ -0,0 +1,78 @@
+use crate::enums::NotificationNamespaceEnum;
+use crate::errors::IntoDbResult;
+use crate::notification_bundles::{NotificationBundleRow, NotificationBundleRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::upsert::excluded;
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl NotificationBundleRowNew {
+    /// Replaces the messages of the bundle of the tenant for the namespace and locale, if it exists
+    pub async fn upsert(&self, conn: &mut PgConn) -> DbResult<NotificationBundleRow> {
+        use crate::schema::notification_bundle::dsl as nb_dsl;
+
+        let query = diesel::insert_into(nb_dsl::notification_bundle)
+            .values(self)
+            .on_conflict((nb_dsl::tenant_id, nb_dsl::namespace, nb_dsl::locale))
+            .do_update()
+            .set((
+                nb_dsl::messages.eq(excluded(nb_dsl::messages)),
+                nb_dsl::updated_at.eq(diesel::dsl::now),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while upserting notification bundle")
+            .into_db_result()
+    }
+}
+
+impl NotificationBundleRow {
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        namespace: Option<NotificationNamespaceEnum>,
+    ) -> DbResult<Vec<NotificationBundleRow>> {
+        use crate::schema::notification_bundle::dsl as nb_dsl;
+
+        let mut query = nb_dsl::notification_bundle
+            .filter(nb_dsl::tenant_id.eq(tenant_id))
+            .select(NotificationBundleRow::as_select())
+            .order((nb_dsl::namespace.asc(), nb_dsl::locale.asc()))
+            .into_boxed();
+
+        if let Some(namespace) = namespace {
+            query = query.filter(nb_dsl::namespace.eq(namespace));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing notification bundles")
+            .into_db_result()
+    }
+
+    pub async fn delete(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::notification_bundle::dsl as nb_dsl;
+
+        let query = diesel::delete(nb_dsl::notification_bundle)
+            .filter(nb_dsl::id.eq(id))
+            .filter(nb_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting notification bundle")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,113 @@
+use crate::enums::PlanStatusEnum;
+use crate::errors::IntoDbResult;
+use crate::onboarding_steps::{OnboardingDetectedRow, OnboardingStepRow, OnboardingStepRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::dsl::{exists, select};
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+
+impl OnboardingStepRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<()> {
+        use crate::schema::tenant_onboarding_step::dsl as os_dsl;
+
+        let query = diesel::insert_into(os_dsl::tenant_onboarding_step)
+            .values(self)
+            .on_conflict((os_dsl::tenant_id, os_dsl::step))
+            .do_nothing();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .attach_printable("Error while inserting onboarding step")
+            .into_db_result()
+    }
+}
+
+impl OnboardingStepRow {
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<Vec<OnboardingStepRow>> {
+        use crate::schema::tenant_onboarding_step::dsl as os_dsl;
+
+        let query = os_dsl::tenant_onboarding_step
+            .filter(os_dsl::tenant_id.eq(tenant_id))
+            .select(OnboardingStepRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing onboarding steps")
+            .into_db_result()
+    }
+
+    pub async fn detect(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<OnboardingDetectedRow> {
+        use crate::schema::billable_metric::dsl as bm_dsl;
+        use crate::schema::customer::dsl as c_dsl;
+        use crate::schema::invoicing_entity::dsl as ie_dsl;
+        use crate::schema::plan::dsl as p_dsl;
+        use crate::schema::provider_config::dsl as pc_dsl;
+
+        // the default invoicing entity is created alongside the tenant, so we only consider it configured once an address was provided
+        let query = select((
+            exists(
+                ie_dsl::invoicing_entity
+                    .filter(ie_dsl::tenant_id.eq(tenant_id))
+                    .filter(ie_dsl::address_line1.is_not_null()),
+            ),
+            exists(
+                pc_dsl::provider_config
+                    .filter(pc_dsl::tenant_id.eq(tenant_id))
+                    .filter(pc_dsl::enabled.eq(true)),
+            ),
+            exists(
+                bm_dsl::billable_metric
+                    .filter(bm_dsl::tenant_id.eq(tenant_id))
+                    .filter(bm_dsl::archived_at.is_null()),
+            ),
+            exists(
+                p_dsl::plan
+                    .filter(p_dsl::tenant_id.eq(tenant_id))
+                    .filter(p_dsl::status.eq(PlanStatusEnum::Active)),
+            ),
+            exists(
+                c_dsl::customer
+                    .filter(c_dsl::tenant_id.eq(tenant_id))
+                    .filter(c_dsl::archived_at.is_null()),
+            ),
+        ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result::<(bool, bool, bool, bool, bool)>(conn)
+            .await
+            .map(
+                |(
+                    invoicing_entity_configured,
+                    payment_provider_connected,
+                    has_billable_metric,
+                    has_active_plan,
+                    has_customer,
+                )| OnboardingDetectedRow {
+                    invoicing_entity_configured,
+                    payment_provider_connected,
+                    has_billable_metric,
+                    has_active_plan,
+                    has_customer,
+                },
+            )
+            .attach_printable("Error while detecting onboarding progress")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,240 @@
+use chrono::NaiveDateTime;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper,
+};
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+use crate::errors::IntoDbResult;
+use crate::partners::{
+    PartnerAttributionRow, PartnerAttributionRowNew, PartnerCommissionRow, PartnerRow,
+    PartnerRowNew,
+};
+use crate::{DbResult, PgConn};
+
+impl PartnerRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<PartnerRow> {
+        use crate::schema::partner::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(p_dsl::partner).values(self);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting partner")
+            .into_db_result()
+    }
+}
+
+impl PartnerRow {
+    pub async fn find_by_id(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<PartnerRow> {
+        use crate::schema::partner::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = p_dsl::partner
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .filter(p_dsl::id.eq(id))
+            .select(PartnerRow::as_select());
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding partner by id")
+            .into_db_result()
+    }
+
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<PartnerRow>> {
+        use crate::schema::partner::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = p_dsl::partner
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .order(p_dsl::name.asc())
+            .select(PartnerRow::as_select());
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing partners")
+            .into_db_result()
+    }
+
+    pub async fn archive(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+        archived_at: NaiveDateTime,
+    ) -> DbResult<PartnerRow> {
+        use crate::schema::partner::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(p_dsl::partner)
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .filter(p_dsl::id.eq(id))
+            .filter(p_dsl::archived_at.is_null())
+            .set(p_dsl::archived_at.eq(archived_at));
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while archiving partner")
+            .into_db_result()
+    }
+}
+
+impl PartnerAttributionRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<PartnerAttributionRow> {
+        use crate::schema::partner_attribution::dsl as pa_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(pa_dsl::partner_attribution).values(self);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting partner attribution")
+            .into_db_result()
+    }
+}
+
+impl PartnerAttributionRow {
+    /// Removes the attribution of the customer or of the subscription
+    pub async fn delete_by_target(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+        subscription_id: Option<Uuid>,
+    ) -> DbResult<usize> {
+        use crate::schema::partner_attribution::dsl as pa_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(pa_dsl::partner_attribution)
+            .filter(pa_dsl::tenant_id.eq(tenant_id))
+            .filter(
+                pa_dsl::customer_id
+                    .eq(customer_id)
+                    .or(pa_dsl::subscription_id.eq(subscription_id)),
+            );
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting partner attribution")
+            .into_db_result()
+    }
+
+    pub async fn list_by_partner_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        partner_id: Uuid,
+    ) -> DbResult<Vec<PartnerAttributionRow>> {
+        use crate::schema::partner_attribution::dsl as pa_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = pa_dsl::partner_attribution
+            .filter(pa_dsl::tenant_id.eq(tenant_id))
+            .filter(pa_dsl::partner_id.eq(partner_id))
+            .order(pa_dsl::created_at.asc())
+            .select(PartnerAttributionRow::as_select());
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing partner attributions")
+            .into_db_result()
+    }
+
+    /// The attributions of the customer and of the subscription to active partners, with their partner
+    pub async fn list_active_for_invoice(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        subscription_id: Option<Uuid>,
+    ) -> DbResult<Vec<(PartnerAttributionRow, PartnerRow)>> {
+        use crate::schema::partner::dsl as p_dsl;
+        use crate::schema::partner_attribution::dsl as pa_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = pa_dsl::partner_attribution
+            .inner_join(p_dsl::partner.on(p_dsl::id.eq(pa_dsl::partner_id)))
+            .filter(pa_dsl::tenant_id.eq(tenant_id))
+            .filter(p_dsl::archived_at.is_null())
+            .filter(
+                pa_dsl::customer_id
+                    .eq(customer_id)
+                    .or(pa_dsl::subscription_id.eq(subscription_id)),
+            )
+            .select((PartnerAttributionRow::as_select(), PartnerRow::as_select()));
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing partner attributions of invoice")
+            .into_db_result()
+    }
+}
+
+impl PartnerCommissionRow {
+    /// Inserts the commission, unless the invoice already has one
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<()> {
+        use crate::schema::partner_commission::dsl as pc_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(pc_dsl::partner_commission)
+            .values(self)
+            .on_conflict(pc_dsl::invoice_id)
+            .do_nothing();
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .map(|_| ())
+            .attach_printable("Error while inserting partner commission")
+            .into_db_result()
+    }
+
+    /// The commissions of the invoices paid in [from, to)
+    pub async fn list_paid_between(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        partner_id: Option<Uuid>,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> DbResult<Vec<PartnerCommissionRow>> {
+        use crate::schema::partner_commission::dsl as pc_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let mut query = pc_dsl::partner_commission
+            .filter(pc_dsl::tenant_id.eq(tenant_id))
+            .filter(pc_dsl::paid_at.ge(from))
+            .filter(pc_dsl::paid_at.lt(to))
+            .order(pc_dsl::paid_at.asc())
+            .select(PartnerCommissionRow::as_select())
+            .into_boxed();
+
+        if let Some(partner_id) = partner_id {
+            query = query.filter(pc_dsl::partner_id.eq(partner_id));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing partner commissions")
+            .into_db_result()
+    }
+}
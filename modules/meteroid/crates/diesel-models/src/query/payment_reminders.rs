@@ -0,0 +1,217 @@
+use crate::errors::IntoDbResult;
+use crate::payment_reminders::{
+    DuePaymentReminderRow, PaymentReminderLogRow, PaymentReminderLogRowNew,
+    PaymentReminderScheduleRow, PaymentReminderScheduleRowNew,
+};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl PaymentReminderScheduleRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<PaymentReminderScheduleRow> {
+        use crate::schema::payment_reminder_schedule::dsl as prs_dsl;
+
+        let query = diesel::insert_into(prs_dsl::payment_reminder_schedule).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting payment reminder schedule")
+            .into_db_result()
+    }
+}
+
+impl PaymentReminderScheduleRow {
+    /// Schedules of the tenant, earliest reminder first
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<PaymentReminderScheduleRow>> {
+        use crate::schema::payment_reminder_schedule::dsl as prs_dsl;
+
+        let query = prs_dsl::payment_reminder_schedule
+            .filter(prs_dsl::tenant_id.eq(tenant_id))
+            .select(PaymentReminderScheduleRow::as_select())
+            .order((prs_dsl::offset_days.asc(), prs_dsl::channel.asc()));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing payment reminder schedules")
+            .into_db_result()
+    }
+
+    pub async fn set_enabled(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+        enabled: bool,
+    ) -> DbResult<PaymentReminderScheduleRow> {
+        use crate::schema::payment_reminder_schedule::dsl as prs_dsl;
+
+        let query = diesel::update(prs_dsl::payment_reminder_schedule)
+            .filter(prs_dsl::id.eq(id))
+            .filter(prs_dsl::tenant_id.eq(tenant_id))
+            .set(prs_dsl::enabled.eq(enabled));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while updating payment reminder schedule")
+            .into_db_result()
+    }
+
+    /// The reminders already sent are kept in the log
+    pub async fn delete(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::payment_reminder_schedule::dsl as prs_dsl;
+
+        let query = diesel::delete(prs_dsl::payment_reminder_schedule)
+            .filter(prs_dsl::id.eq(id))
+            .filter(prs_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting payment reminder schedule")
+            .into_db_result()
+    }
+}
+
+impl PaymentReminderLogRowNew {
+    /// Records the reminder, or returns None if it was already sent for the invoice
+    pub async fn insert_if_absent(
+        &self,
+        conn: &mut PgConn,
+    ) -> DbResult<Option<PaymentReminderLogRow>> {
+        use crate::schema::payment_reminder_log::dsl as prl_dsl;
+
+        let query = diesel::insert_into(prl_dsl::payment_reminder_log)
+            .values(self)
+            .on_conflict((prl_dsl::invoice_id, prl_dsl::offset_days, prl_dsl::channel))
+            .do_nothing();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while inserting payment reminder log")
+            .into_db_result()
+    }
+}
+
+impl PaymentReminderLogRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<PaymentReminderLogRow> {
+        use crate::schema::payment_reminder_log::dsl as prl_dsl;
+
+        let query = prl_dsl::payment_reminder_log
+            .filter(prl_dsl::id.eq(id))
+            .filter(prl_dsl::tenant_id.eq(tenant_id))
+            .select(PaymentReminderLogRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding payment reminder log by id")
+            .into_db_result()
+    }
+
+    pub async fn list_by_invoice_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> DbResult<Vec<PaymentReminderLogRow>> {
+        use crate::schema::payment_reminder_log::dsl as prl_dsl;
+
+        let query = prl_dsl::payment_reminder_log
+            .filter(prl_dsl::invoice_id.eq(invoice_id))
+            .filter(prl_dsl::tenant_id.eq(tenant_id))
+            .select(PaymentReminderLogRow::as_select())
+            .order(prl_dsl::sent_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing payment reminder logs")
+            .into_db_result()
+    }
+}
+
+impl DuePaymentReminderRow {
+    /// Reminders of the enabled schedules whose date has passed, for the finalized invoices that still have an amount due.
+    /// Invoices without a due date are due on their invoice date.
+    /// Reminders older than `max_delay_days` are not sent anymore, so that a new schedule does not remind every past invoice.
+    /// Email reminders require an email on the customer, and are paused while it bounces or after a complaint
+    pub async fn list(
+        conn: &mut PgConn,
+        now: NaiveDateTime,
+        max_delay_days: i32,
+    ) -> DbResult<Vec<DuePaymentReminderRow>> {
+        let raw_sql = r#"
+WITH reminder AS (
+    SELECT schedule.id AS schedule_id, schedule.tenant_id, schedule.offset_days, schedule.channel,
+           invoice.id AS invoice_id, invoice.invoice_number, invoice.customer_id, customer.name AS customer_name,
+           invoicing_entity.country AS invoicing_entity_country,
+           CASE WHEN schedule.channel = 'EMAIL' AND customer.billing_email_status NOT IN ('BOUNCED', 'COMPLAINED')
+                THEN coalesce(customer.invoicing_email, customer.email) END AS recipient,
+           invoice.currency, invoice.amount_due,
+           coalesce(invoice.due_at, invoice.invoice_date::timestamp) AS due_at,
+           coalesce(invoice.due_at, invoice.invoice_date::timestamp) + interval '1 day' * schedule.offset_days AS remind_at
+    FROM payment_reminder_schedule schedule
+    INNER JOIN invoice ON invoice.tenant_id = schedule.tenant_id
+    INNER JOIN customer ON invoice.customer_id = customer.id
+    INNER JOIN invoicing_entity ON customer.invoicing_entity_id = invoicing_entity.id
+    WHERE schedule.enabled
+      AND invoice.status = 'FINALIZED'
+      AND invoice.suppressed_at IS NULL
+      AND invoice.amount_due > 0
+      AND (invoice.external_status IS NULL OR invoice.external_status NOT IN ('PAID', 'VOID', 'UNCOLLECTIBLE', 'DELETED'))
+)
+SELECT reminder.*
+FROM reminder
+WHERE reminder.remind_at <= $1
+  AND reminder.remind_at > $1 - interval '1 day' * $2
+  AND (reminder.channel = 'WEBHOOK' OR reminder.recipient IS NOT NULL)
+  AND NOT EXISTS (
+    SELECT 1 FROM payment_reminder_log log
+    WHERE log.invoice_id = reminder.invoice_id
+      AND log.offset_days = reminder.offset_days
+      AND log.channel = reminder.channel
+  )
+ORDER BY reminder.remind_at;
+        "#;
+
+        let query = diesel::sql_query(raw_sql)
+            .bind::<diesel::sql_types::Timestamp, _>(now)
+            .bind::<diesel::sql_types::Integer, _>(max_delay_days);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing due payment reminders")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,152 @@
+use crate::errors::IntoDbResult;
+use crate::plan_version_features::{PlanVersionFeatureRow, PlanVersionFeatureRowNew};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDate;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl PlanVersionFeatureRowNew {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        batch: Vec<&PlanVersionFeatureRowNew>,
+    ) -> DbResult<Vec<PlanVersionFeatureRow>> {
+        use crate::schema::plan_version_feature::dsl as pvf_dsl;
+
+        let query = diesel::insert_into(pvf_dsl::plan_version_feature).values(batch);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting PlanVersionFeature batch")
+            .into_db_result()
+    }
+}
+
+impl PlanVersionFeatureRow {
+    pub async fn list_by_plan_version_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> DbResult<Vec<PlanVersionFeatureRow>> {
+        use crate::schema::plan_version_feature::dsl as pvf_dsl;
+
+        let query = pvf_dsl::plan_version_feature
+            .filter(pvf_dsl::tenant_id.eq(tenant_id))
+            .filter(pvf_dsl::plan_version_id.eq(plan_version_id))
+            .select(PlanVersionFeatureRow::as_select())
+            .order(pvf_dsl::key.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing plan version features")
+            .into_db_result()
+    }
+
+    /// Features of the plan versions of the subscriptions of the customer in effect at that date, trials included
+    pub async fn list_by_customer_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        date: NaiveDate,
+    ) -> DbResult<Vec<PlanVersionFeatureRow>> {
+        use crate::schema::plan_version_feature::dsl as pvf_dsl;
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = pvf_dsl::plan_version_feature
+            .inner_join(s_dsl::subscription.on(s_dsl::plan_version_id.eq(pvf_dsl::plan_version_id)))
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .filter(s_dsl::customer_id.eq(customer_id))
+            .filter(
+                s_dsl::billing_start_date
+                    .le(date)
+                    .or(s_dsl::trial_start_date.le(date)),
+            )
+            .filter(
+                s_dsl::billing_end_date
+                    .is_null()
+                    .or(s_dsl::billing_end_date.gt(date)),
+            )
+            .select(PlanVersionFeatureRow::as_select())
+            .order(pvf_dsl::key.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing customer features")
+            .into_db_result()
+    }
+
+    pub async fn delete_by_plan_version_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::plan_version_feature::dsl as pvf_dsl;
+
+        let query = diesel::delete(pvf_dsl::plan_version_feature)
+            .filter(pvf_dsl::tenant_id.eq(tenant_id))
+            .filter(pvf_dsl::plan_version_id.eq(plan_version_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting plan version features")
+            .into_db_result()
+    }
+
+    pub async fn clone_all(
+        conn: &mut PgConn,
+        src_plan_version_id: Uuid,
+        dst_plan_version_id: Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::plan_version_feature::dsl as pvf_dsl;
+
+        diesel::define_sql_function! {
+            fn gen_random_uuid() -> Uuid;
+        }
+
+        let query = pvf_dsl::plan_version_feature
+            .filter(pvf_dsl::plan_version_id.eq(src_plan_version_id))
+            .select((
+                gen_random_uuid(),
+                pvf_dsl::tenant_id,
+                diesel::dsl::sql::<diesel::sql_types::Uuid>(
+                    format!("'{}'", dst_plan_version_id).as_str(),
+                ),
+                pvf_dsl::key,
+                pvf_dsl::enabled,
+                pvf_dsl::usage_limit,
+            ))
+            .insert_into(pvf_dsl::plan_version_feature)
+            .into_columns((
+                pvf_dsl::id,
+                pvf_dsl::tenant_id,
+                pvf_dsl::plan_version_id,
+                pvf_dsl::key,
+                pvf_dsl::enabled,
+                pvf_dsl::usage_limit,
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while cloning plan version features")
+            .into_db_result()
+    }
+}
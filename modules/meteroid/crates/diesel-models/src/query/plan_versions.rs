@@ -1,7 +1,7 @@
 use crate::errors::IntoDbResult;
 use crate::plan_versions::{
-    PlanVersionRow, PlanVersionRowLatest, PlanVersionRowNew, PlanVersionRowPatch,
-    PlanVersionTrialRowPatch,
+    PlanVersionInheritedRowPatch, PlanVersionRow, PlanVersionRowLatest, PlanVersionRowNew,
+    PlanVersionRowPatch, PlanVersionTrialRowPatch,
 };
 
 use crate::{DbResult, PgConn};
@@ -237,6 +237,42 @@ impl PlanVersionRowLatest {
     }
 }
 
+impl PlanVersionInheritedRowPatch {
+    /// Applies to the draft versions of the plans of the family that inherit the setting
+    pub async fn update_inheriting_drafts(
+        &self,
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        product_family_id: uuid::Uuid,
+        setting: &str,
+    ) -> DbResult<usize> {
+        use crate::schema::plan::dsl as p_dsl;
+        use crate::schema::plan_version::dsl as pv_dsl;
+        use diesel::PgArrayExpressionMethods;
+        use diesel_async::RunQueryDsl;
+
+        let family_plans = p_dsl::plan
+            .filter(p_dsl::product_family_id.eq(product_family_id))
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .select(p_dsl::id);
+
+        let query = diesel::update(pv_dsl::plan_version)
+            .filter(pv_dsl::tenant_id.eq(tenant_id))
+            .filter(pv_dsl::is_draft_version.eq(true))
+            .filter(pv_dsl::plan_id.eq_any(family_plans))
+            .filter(pv_dsl::inherited_defaults.contains(vec![setting]))
+            .set(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating the plan versions inheriting a default")
+            .into_db_result()
+    }
+}
+
 impl PlanVersionRowPatch {
     pub async fn update_draft(&self, conn: &mut PgConn) -> DbResult<PlanVersionRow> {
         use crate::schema::plan_version::dsl as pv_dsl;
@@ -6,7 +6,7 @@ use std::collections::HashMap;
 
 use crate::{DbResult, PgConn};
 
-use crate::enums::PlanStatusEnum;
+use crate::enums::{PlanStatusEnum, TrialModeEnum};
 use crate::extend::order::OrderByRequest;
 use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
 use diesel::{
@@ -55,6 +55,23 @@ impl PlanRow {
             .into_db_result()
     }
 
+    pub async fn list_by_tenant_id(conn: &mut PgConn, tenant_id: Uuid) -> DbResult<Vec<PlanRow>> {
+        use crate::schema::plan::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = p_dsl::plan
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .order(p_dsl::created_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing plans by tenant id")
+            .into_db_result()
+    }
+
     pub async fn get_by_id_and_tenant_id(
         conn: &mut PgConn,
         id: Uuid,
@@ -275,3 +292,24 @@ pub async fn get_plan_names_by_version_ids(
         .into_db_result()
         .map(|rows: Vec<(Uuid, String)>| rows.into_iter().collect())
 }
+
+pub async fn get_trial_modes_by_version_ids(
+    conn: &mut PgConn,
+    version_ids: Vec<Uuid>,
+) -> DbResult<HashMap<Uuid, TrialModeEnum>> {
+    use crate::schema::plan_version::dsl as pv_dsl;
+    use diesel_async::RunQueryDsl;
+
+    let query = pv_dsl::plan_version
+        .filter(pv_dsl::id.eq_any(version_ids))
+        .select((pv_dsl::id, pv_dsl::trial_mode));
+
+    log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+    query
+        .load(conn)
+        .await
+        .attach_printable("Error while getting trial modes by version ids")
+        .into_db_result()
+        .map(|rows: Vec<(Uuid, TrialModeEnum)>| rows.into_iter().collect())
+}
@@ -171,6 +171,57 @@ impl PriceComponentRow {
             .into_db_result()
     }
 
+    /// The components of the draft plan versions priced by a rate card. Published versions keep their prices
+    pub async fn list_in_draft_versions_by_rate_card_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        rate_card_id: uuid::Uuid,
+    ) -> DbResult<Vec<PriceComponentRow>> {
+        use crate::schema::plan_version::dsl as pv_dsl;
+        use crate::schema::price_component::dsl as pc_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = pc_dsl::price_component
+            .inner_join(pv_dsl::plan_version)
+            .filter(pv_dsl::tenant_id.eq(tenant_id))
+            .filter(pv_dsl::is_draft_version.eq(true))
+            .filter(pc_dsl::rate_card_id.eq(rate_card_id))
+            .select(PriceComponentRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .tap_err(|e| log::error!("Error while fetching price components: {:?}", e))
+            .attach_printable("Error while fetching price components")
+            .into_db_result()
+    }
+
+    pub async fn update_fee(
+        conn: &mut PgConn,
+        component_id: uuid::Uuid,
+        fee_param: serde_json::Value,
+    ) -> DbResult<()> {
+        use crate::schema::price_component::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(price_component)
+            .filter(id.eq(component_id))
+            .set(fee.eq(fee_param));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .tap_err(|e| log::error!("Error while updating price component fee: {:?}", e))
+            .attach_printable("Error while updating price component fee")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
     pub async fn delete_by_id_and_tenant(
         conn: &mut PgConn,
         component_id: uuid::Uuid,
@@ -225,6 +276,7 @@ impl PriceComponentRow {
                 ),
                 pc_dsl::product_item_id,
                 pc_dsl::billable_metric_id,
+                pc_dsl::rate_card_id,
             ))
             .insert_into(pc_dsl::price_component)
             .into_columns((
@@ -234,6 +286,7 @@ impl PriceComponentRow {
                 pc_dsl::plan_version_id,
                 pc_dsl::product_item_id,
                 pc_dsl::billable_metric_id,
+                pc_dsl::rate_card_id,
             ));
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
@@ -1,5 +1,7 @@
 use crate::errors::IntoDbResult;
-use crate::product_families::{ProductFamilyRow, ProductFamilyRowNew};
+use crate::product_families::{
+    ProductFamilyDefaultsRowPatch, ProductFamilyRow, ProductFamilyRowNew,
+};
 
 use crate::{DbResult, PgConn};
 
@@ -60,4 +62,50 @@ impl ProductFamilyRow {
             .attach_printable("Error while finding product family by external_id and tenant_id")
             .into_db_result()
     }
+
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+    ) -> DbResult<ProductFamilyRow> {
+        use crate::schema::product_family::dsl as pf_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = pf_dsl::product_family
+            .filter(pf_dsl::id.eq(id))
+            .filter(pf_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding product family by id")
+            .into_db_result()
+    }
+}
+
+impl ProductFamilyDefaultsRowPatch {
+    pub async fn update(
+        &self,
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+    ) -> DbResult<ProductFamilyRow> {
+        use crate::schema::product_family::dsl as pf_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(pf_dsl::product_family)
+            .filter(pf_dsl::id.eq(id))
+            .filter(pf_dsl::tenant_id.eq(tenant_id))
+            .set((self, pf_dsl::updated_at.eq(diesel::dsl::now)));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while updating product family defaults")
+            .into_db_result()
+    }
 }
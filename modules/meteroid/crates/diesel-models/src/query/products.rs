@@ -50,6 +50,28 @@ impl ProductRow {
             .into_db_result()
     }
 
+    /// The products of the tenant that are not archived
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<ProductRow>> {
+        use crate::schema::product::dsl as p_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = p_dsl::product
+            .filter(p_dsl::tenant_id.eq(tenant_id))
+            .filter(p_dsl::archived_at.is_null())
+            .order(p_dsl::created_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing products by tenant id")
+            .into_db_result()
+    }
+
     pub async fn list(
         conn: &mut PgConn,
         tenant_id: Uuid,
@@ -0,0 +1,124 @@
+use crate::errors::IntoDbResult;
+use crate::rate_cards::{RateCardRow, RateCardRowNew, RateCardVersionRow, RateCardVersionRowNew};
+use crate::{DbResult, PgConn};
+use diesel::{debug_query, ExpressionMethods, QueryDsl};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use tap::TapFallible;
+
+impl RateCardRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<RateCardRow> {
+        use crate::schema::rate_card::dsl as rc_dsl;
+
+        let query = diesel::insert_into(rc_dsl::rate_card).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .tap_err(|e| log::error!("Error while inserting rate card: {:?}", e))
+            .attach_printable("Error while inserting rate card")
+            .into_db_result()
+    }
+}
+
+impl RateCardRow {
+    pub async fn get_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        id: uuid::Uuid,
+    ) -> DbResult<RateCardRow> {
+        use crate::schema::rate_card::dsl as rc_dsl;
+
+        let query = rc_dsl::rate_card
+            .filter(rc_dsl::id.eq(id))
+            .filter(rc_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while getting rate card")
+            .into_db_result()
+    }
+
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<Vec<RateCardRow>> {
+        use crate::schema::rate_card::dsl as rc_dsl;
+
+        let query = rc_dsl::rate_card
+            .filter(rc_dsl::tenant_id.eq(tenant_id))
+            .order(rc_dsl::name.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .tap_err(|e| log::error!("Error while listing rate cards: {:?}", e))
+            .attach_printable("Error while listing rate cards")
+            .into_db_result()
+    }
+}
+
+impl RateCardVersionRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<RateCardVersionRow> {
+        use crate::schema::rate_card_version::dsl as rcv_dsl;
+
+        let query = diesel::insert_into(rcv_dsl::rate_card_version).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .tap_err(|e| log::error!("Error while inserting rate card version: {:?}", e))
+            .attach_printable("Error while inserting rate card version")
+            .into_db_result()
+    }
+}
+
+impl RateCardVersionRow {
+    pub async fn find_latest(
+        conn: &mut PgConn,
+        rate_card_id: uuid::Uuid,
+    ) -> DbResult<RateCardVersionRow> {
+        use crate::schema::rate_card_version::dsl as rcv_dsl;
+
+        let query = rcv_dsl::rate_card_version
+            .filter(rcv_dsl::rate_card_id.eq(rate_card_id))
+            .order(rcv_dsl::version.desc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while getting latest rate card version")
+            .into_db_result()
+    }
+
+    pub async fn list_by_rate_card_id(
+        conn: &mut PgConn,
+        rate_card_id: uuid::Uuid,
+    ) -> DbResult<Vec<RateCardVersionRow>> {
+        use crate::schema::rate_card_version::dsl as rcv_dsl;
+
+        let query = rcv_dsl::rate_card_version
+            .filter(rcv_dsl::rate_card_id.eq(rate_card_id))
+            .order(rcv_dsl::version.desc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .tap_err(|e| log::error!("Error while listing rate card versions: {:?}", e))
+            .attach_printable("Error while listing rate card versions")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,158 @@
+use crate::enums::SeatIncreaseRequestStatusEnum;
+use crate::errors::IntoDbResult;
+use crate::seat_increase_requests::{SeatIncreaseRequestRow, SeatIncreaseRequestRowNew};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl SeatIncreaseRequestRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<SeatIncreaseRequestRow> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let query = diesel::insert_into(sir_dsl::seat_increase_request).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting seat increase request")
+            .into_db_result()
+    }
+}
+
+impl SeatIncreaseRequestRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<SeatIncreaseRequestRow> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let query = sir_dsl::seat_increase_request
+            .filter(sir_dsl::id.eq(id))
+            .filter(sir_dsl::tenant_id.eq(tenant_id))
+            .select(SeatIncreaseRequestRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding seat increase request by id")
+            .into_db_result()
+    }
+
+    /// Most recent first
+    pub async fn list(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Option<Uuid>,
+        status: Option<SeatIncreaseRequestStatusEnum>,
+    ) -> DbResult<Vec<SeatIncreaseRequestRow>> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let mut query = sir_dsl::seat_increase_request
+            .filter(sir_dsl::tenant_id.eq(tenant_id))
+            .into_boxed();
+
+        if let Some(subscription_id) = subscription_id {
+            query = query.filter(sir_dsl::subscription_id.eq(subscription_id));
+        }
+
+        if let Some(status) = status {
+            query = query.filter(sir_dsl::status.eq(status));
+        }
+
+        let query = query
+            .order(sir_dsl::created_at.desc())
+            .select(SeatIncreaseRequestRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing seat increase requests")
+            .into_db_result()
+    }
+
+    /// Approves or rejects a pending request. None if it was already decided
+    pub async fn decide(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+        status: SeatIncreaseRequestStatusEnum,
+        decided_by: Uuid,
+        decision_note: Option<String>,
+        decided_at: NaiveDateTime,
+    ) -> DbResult<Option<SeatIncreaseRequestRow>> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let query = diesel::update(sir_dsl::seat_increase_request)
+            .filter(sir_dsl::id.eq(id))
+            .filter(sir_dsl::tenant_id.eq(tenant_id))
+            .filter(sir_dsl::status.eq(SeatIncreaseRequestStatusEnum::Pending))
+            .set((
+                sir_dsl::status.eq(status),
+                sir_dsl::decided_by.eq(decided_by),
+                sir_dsl::decision_note.eq(decision_note),
+                sir_dsl::decided_at.eq(decided_at),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while deciding seat increase request")
+            .into_db_result()
+    }
+
+    /// Back to pending, when the seats of an approved request could not be added
+    pub async fn reopen(conn: &mut PgConn, id: Uuid) -> DbResult<usize> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let query = diesel::update(sir_dsl::seat_increase_request)
+            .filter(sir_dsl::id.eq(id))
+            .set((
+                sir_dsl::status.eq(SeatIncreaseRequestStatusEnum::Pending),
+                sir_dsl::decided_by.eq(None::<Uuid>),
+                sir_dsl::decision_note.eq(None::<String>),
+                sir_dsl::decided_at.eq(None::<NaiveDateTime>),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while reopening seat increase request")
+            .into_db_result()
+    }
+
+    pub async fn set_invoice_id(
+        conn: &mut PgConn,
+        id: Uuid,
+        invoice_id: Option<Uuid>,
+    ) -> DbResult<SeatIncreaseRequestRow> {
+        use crate::schema::seat_increase_request::dsl as sir_dsl;
+
+        let query = diesel::update(sir_dsl::seat_increase_request)
+            .filter(sir_dsl::id.eq(id))
+            .set(sir_dsl::invoice_id.eq(invoice_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while linking seat increase request to its invoice")
+            .into_db_result()
+    }
+}
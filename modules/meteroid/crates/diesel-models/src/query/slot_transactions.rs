@@ -5,7 +5,7 @@ use crate::{DbResult, PgConn};
 use chrono::NaiveDateTime;
 
 use diesel::sql_types;
-use diesel::{debug_query, QueryableByName};
+use diesel::{debug_query, OptionalExtension, QueryableByName};
 use error_stack::ResultExt;
 
 impl SlotTransactionRow {
@@ -30,7 +30,7 @@ impl SlotTransactionRow {
         // TODO unit instead ?
         price_component_uid: uuid::Uuid,
         at_ts: Option<NaiveDateTime>,
-    ) -> DbResult<FetchTransactionResult> {
+    ) -> DbResult<Option<FetchTransactionResult>> {
         use diesel_async::RunQueryDsl;
 
         let ts = at_ts.unwrap_or_else(|| chrono::Utc::now().naive_utc());
@@ -66,6 +66,7 @@ GROUP BY
             .bind::<sql_types::Timestamp, _>(ts)
             .get_result::<FetchTransactionResult>(conn)
             .await
+            .optional()
             .attach_printable("Error while fetching slot transaction by id")
             .into_db_result()
     }
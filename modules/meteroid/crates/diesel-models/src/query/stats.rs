@@ -15,33 +15,29 @@ impl RevenueTrendRow {
         conn: &mut PgConn,
         period_days: i32,
         tenant_id: Uuid,
+        constant_rate_date: Option<NaiveDate>,
     ) -> DbResult<RevenueTrendRow> {
         let raw_sql = r#"
     WITH period AS (SELECT CURRENT_DATE - INTERVAL '1 day' * $1::integer       AS start_current_period,
                        CURRENT_DATE - INTERVAL '1 day' * ($2::integer * 2) AS start_previous_period),
-    conversion_rates AS (SELECT id,
-                                 (rates ->> (SELECT currency FROM tenant WHERE id = $3))::NUMERIC AS conversion_rate
-                          FROM historical_rates_from_usd),
-    revenue_ytd AS (SELECT COALESCE(SUM(net_revenue_cents * cr.conversion_rate), 0)::bigint AS total_ytd
-                     FROM bi_revenue_daily
-                              JOIN conversion_rates cr ON bi_revenue_daily.historical_rate_id = cr.id
-                     WHERE revenue_date BETWEEN DATE_TRUNC('year', CURRENT_DATE) AND CURRENT_DATE
-                       AND bi_revenue_daily.tenant_id = $4),
-    current_period AS (SELECT COALESCE(SUM(net_revenue_cents_usd * cr.conversion_rate), 0)::bigint AS total
-                        FROM bi_revenue_daily
-                                 JOIN
-                             period ON revenue_date BETWEEN period.start_current_period AND CURRENT_DATE
-                                 JOIN
-                             conversion_rates cr ON bi_revenue_daily.historical_rate_id = cr.id
-                        WHERE bi_revenue_daily.tenant_id = $5),
-    previous_period AS (SELECT COALESCE(SUM(net_revenue_cents_usd * cr.conversion_rate), 0)::bigint AS total
+    restated_revenue AS (SELECT revenue_date,
+                                fn_fx_restate(net_revenue_cents, net_revenue_cents_usd, historical_rate_id,
+                                              bi_revenue_daily.currency, t.currency, $3::date) AS net_revenue
                          FROM bi_revenue_daily
+                                  JOIN tenant t ON t.id = bi_revenue_daily.tenant_id
+                         WHERE bi_revenue_daily.tenant_id = $4),
+    revenue_ytd AS (SELECT COALESCE(SUM(net_revenue), 0)::bigint AS total_ytd
+                     FROM restated_revenue
+                     WHERE revenue_date BETWEEN DATE_TRUNC('year', CURRENT_DATE) AND CURRENT_DATE),
+    current_period AS (SELECT COALESCE(SUM(net_revenue), 0)::bigint AS total
+                        FROM restated_revenue
+                                 JOIN
+                             period ON revenue_date BETWEEN period.start_current_period AND CURRENT_DATE),
+    previous_period AS (SELECT COALESCE(SUM(net_revenue), 0)::bigint AS total
+                         FROM restated_revenue
                                   JOIN
                               period
-                              ON revenue_date BETWEEN period.start_previous_period AND period.start_current_period
-                                  JOIN
-                              conversion_rates cr ON bi_revenue_daily.historical_rate_id = cr.id
-                         WHERE bi_revenue_daily.tenant_id = $6)
+                              ON revenue_date BETWEEN period.start_previous_period AND period.start_current_period)
     SELECT COALESCE(revenue_ytd.total_ytd, 0) AS total_ytd,
            COALESCE(current_period.total, 0)  AS total_current_period,
            COALESCE(previous_period.total, 0) AS total_previous_period
@@ -53,9 +49,7 @@ impl RevenueTrendRow {
         diesel::sql_query(raw_sql)
             .bind::<sql_types::Integer, _>(period_days)
             .bind::<sql_types::Integer, _>(period_days)
-            .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Uuid, _>(tenant_id)
+            .bind::<sql_types::Nullable<sql_types::Date>, _>(constant_rate_date)
             .bind::<sql_types::Uuid, _>(tenant_id)
             .get_result::<RevenueTrendRow>(conn)
             .await
@@ -314,10 +308,10 @@ impl TotalMrrRow {
     pub async fn get(conn: &mut PgConn, tenant_id: Uuid, date: NaiveDate) -> DbResult<TotalMrrRow> {
         let raw_sql = r#"
         SELECT
-           COALESCE(SUM(bd.net_mrr_cents_usd * (hr.rates->>(SELECT currency FROM tenant WHERE id = bd.tenant_id))::NUMERIC), 0)::bigint AS total_net_mrr_cents
+           COALESCE(SUM(fn_fx_restate(bd.net_mrr_cents, bd.net_mrr_cents_usd, bd.historical_rate_id, bd.currency, t.currency, NULL)), 0)::bigint AS total_net_mrr_cents
         FROM
            bi_delta_mrr_daily bd
-               JOIN  historical_rates_from_usd hr ON bd.historical_rate_id = hr.id
+               JOIN tenant t ON t.id = bd.tenant_id
         WHERE
            bd.tenant_id = $1
            AND bd.date <= $2;
@@ -339,58 +333,67 @@ impl TotalMrrChartRow {
         tenant_id: Uuid,
         start_date: NaiveDate,
         end_date: NaiveDate,
+        constant_rate_date: Option<NaiveDate>,
     ) -> DbResult<Vec<TotalMrrChartRow>> {
         let raw_sql = r#"
-        WITH conversion_rates AS (
+        WITH restated AS (
             SELECT
-                id,
-                (rates->>(SELECT currency FROM tenant WHERE id = $1))::NUMERIC AS conversion_rate
+                bd.date,
+                bd.new_business_count,
+                bd.expansion_count,
+                bd.contraction_count,
+                bd.churn_count,
+                bd.reactivation_count,
+                fn_fx_restate(bd.net_mrr_cents, bd.net_mrr_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS net_mrr,
+                fn_fx_restate(bd.new_business_cents, bd.new_business_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS new_business,
+                fn_fx_restate(bd.expansion_cents, bd.expansion_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS expansion,
+                fn_fx_restate(bd.contraction_cents, bd.contraction_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS contraction,
+                fn_fx_restate(bd.churn_cents, bd.churn_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS churn,
+                fn_fx_restate(bd.reactivation_cents, bd.reactivation_cents_usd, bd.historical_rate_id, bd.currency, t.currency, $1::date) AS reactivation
             FROM
-                historical_rates_from_usd
+                bi_delta_mrr_daily bd
+                    JOIN
+                tenant t ON t.id = bd.tenant_id
+            WHERE
+                bd.tenant_id = $2
+              AND bd.date <= $3
         ),
         initial_mrr AS (
             SELECT
-                COALESCE(SUM(bd.net_mrr_cents_usd * cr.conversion_rate), 0)::BIGINT AS total_net_mrr_cents
+                COALESCE(SUM(r.net_mrr), 0)::BIGINT AS total_net_mrr_cents
             FROM
-                bi_delta_mrr_daily bd
-                    JOIN
-                conversion_rates cr ON bd.historical_rate_id = cr.id
+                restated r
             WHERE
-                bd.date < $2
-              AND bd.tenant_id = $3
+                r.date < $4
         )
         SELECT
             bi.date AS period,
-            (im.total_net_mrr_cents + COALESCE(SUM(bi.net_mrr_cents_usd) OVER (ORDER BY bi.date), 0) * cr.conversion_rate)::BIGINT AS total_net_mrr,
-            (bi.net_mrr_cents_usd * cr.conversion_rate)::BIGINT AS net_new_mrr,
-            (bi.new_business_cents_usd * cr.conversion_rate)::BIGINT AS new_business_mrr,
+            (im.total_net_mrr_cents + COALESCE(SUM(bi.net_mrr) OVER (ORDER BY bi.date), 0))::BIGINT AS total_net_mrr,
+            bi.net_mrr::BIGINT AS net_new_mrr,
+            bi.new_business::BIGINT AS new_business_mrr,
             bi.new_business_count,
-            (bi.expansion_cents_usd * cr.conversion_rate)::BIGINT AS expansion_mrr,
+            bi.expansion::BIGINT AS expansion_mrr,
             bi.expansion_count,
-            (bi.contraction_cents_usd * cr.conversion_rate)::BIGINT AS contraction_mrr,
+            bi.contraction::BIGINT AS contraction_mrr,
             bi.contraction_count,
-            (bi.churn_cents_usd * cr.conversion_rate)::BIGINT AS churn_mrr,
+            bi.churn::BIGINT AS churn_mrr,
             bi.churn_count,
-            (bi.reactivation_cents_usd * cr.conversion_rate)::BIGINT AS reactivation_mrr,
+            bi.reactivation::BIGINT AS reactivation_mrr,
             bi.reactivation_count
         FROM
-            bi_delta_mrr_daily bi
-                JOIN
-            conversion_rates cr ON bi.historical_rate_id = cr.id
+            restated bi
                 CROSS JOIN
             initial_mrr im
         WHERE
-            bi.date BETWEEN $4 AND $5
-            AND bi.tenant_id = $6
+            bi.date >= $5
         ORDER BY period"#;
 
         let query = diesel::sql_query(raw_sql)
+            .bind::<sql_types::Nullable<sql_types::Date>, _>(constant_rate_date)
             .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Date, _>(start_date)
-            .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Date, _>(start_date)
             .bind::<sql_types::Date, _>(end_date)
-            .bind::<sql_types::Uuid, _>(tenant_id);
+            .bind::<sql_types::Date, _>(start_date)
+            .bind::<sql_types::Date, _>(start_date);
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
 
@@ -413,68 +416,75 @@ impl TotalMrrByPlanRow {
         plan_ids: &Vec<Uuid>,
         start_date: NaiveDate,
         end_date: NaiveDate,
+        constant_rate_date: Option<NaiveDate>,
     ) -> DbResult<Vec<TotalMrrByPlanRow>> {
         let raw_sql = r#"
-        WITH conversion_rates AS (
-            SELECT
-                id,
-                (rates->>(SELECT currency FROM tenant WHERE id = $1))::NUMERIC AS conversion_rate
-            FROM
-                historical_rates_from_usd
-        ),
-        initial_mrr AS (
+        WITH restated AS (
             SELECT
-                COALESCE(SUM(bi.net_mrr_cents_usd * cr.conversion_rate), 0)::BIGINT AS total_net_mrr_usd,
-                pv.plan_id
+                bi.date,
+                pv.plan_id,
+                bi.new_business_count,
+                bi.expansion_count,
+                bi.contraction_count,
+                bi.churn_count,
+                bi.reactivation_count,
+                fn_fx_restate(bi.net_mrr_cents, bi.net_mrr_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS net_mrr,
+                fn_fx_restate(bi.new_business_cents, bi.new_business_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS new_business,
+                fn_fx_restate(bi.expansion_cents, bi.expansion_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS expansion,
+                fn_fx_restate(bi.contraction_cents, bi.contraction_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS contraction,
+                fn_fx_restate(bi.churn_cents, bi.churn_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS churn,
+                fn_fx_restate(bi.reactivation_cents, bi.reactivation_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date) AS reactivation
             FROM
                 bi_delta_mrr_daily bi
                     JOIN
                 plan_version pv ON bi.plan_version_id = pv.id
                     JOIN
-                conversion_rates cr ON bi.historical_rate_id = cr.id
+                tenant t ON t.id = bi.tenant_id
             WHERE
-                bi.date < $2
-                AND bi.tenant_id = $3
+                bi.tenant_id = $2
+                AND bi.date <= $3
                 AND pv.plan_id = ANY ($4)
+        ),
+        initial_mrr AS (
+            SELECT
+                COALESCE(SUM(r.net_mrr), 0)::BIGINT AS total_net_mrr_usd,
+                r.plan_id
+            FROM
+                restated r
+            WHERE
+                r.date < $5
             GROUP BY
-                pv.plan_id
+                r.plan_id
         )
         SELECT    bi.date,
                   p.id AS plan_id,
                   p.name AS plan_name,
-                  (im.total_net_mrr_usd + COALESCE(SUM(bi.net_mrr_cents_usd) OVER (PARTITION BY p.id ORDER BY bi.date), 0) * cr.conversion_rate)::BIGINT AS total_net_mrr,
-                  (bi.net_mrr_cents_usd * cr.conversion_rate)::BIGINT AS net_new_mrr,
-                  (bi.new_business_cents_usd * cr.conversion_rate)::BIGINT AS new_business_mrr,
+                  (im.total_net_mrr_usd + COALESCE(SUM(bi.net_mrr) OVER (PARTITION BY p.id ORDER BY bi.date), 0))::BIGINT AS total_net_mrr,
+                  bi.net_mrr::BIGINT AS net_new_mrr,
+                  bi.new_business::BIGINT AS new_business_mrr,
                   bi.new_business_count,
-                  (bi.expansion_cents_usd * cr.conversion_rate)::BIGINT AS expansion_mrr,
+                  bi.expansion::BIGINT AS expansion_mrr,
                   bi.expansion_count,
-                  (bi.contraction_cents_usd * cr.conversion_rate)::BIGINT AS contraction_mrr,
+                  bi.contraction::BIGINT AS contraction_mrr,
                   bi.contraction_count,
-                  (bi.churn_cents_usd * cr.conversion_rate)::BIGINT AS churn_mrr,
+                  bi.churn::BIGINT AS churn_mrr,
                   bi.churn_count,
-                  (bi.reactivation_cents_usd * cr.conversion_rate)::BIGINT AS reactivation_mrr,
+                  bi.reactivation::BIGINT AS reactivation_mrr,
                   bi.reactivation_count
-        FROM bi_delta_mrr_daily bi
-                 JOIN plan_version pv on bi.plan_version_id = pv.id
-                 JOIN plan p on pv.plan_id = p.id
-                 JOIN
-             conversion_rates cr ON bi.historical_rate_id = cr.id
-                 JOIN initial_mrr im on pv.plan_id = im.plan_id
-        WHERE bi.date BETWEEN $5 AND $6
-          AND bi.tenant_id = $7
-          AND p.id = ANY ($8)
+        FROM restated bi
+                 JOIN plan p on bi.plan_id = p.id
+                 JOIN initial_mrr im on bi.plan_id = im.plan_id
+        WHERE bi.date >= $6
         ORDER BY date;
         "#;
 
         let query = diesel::sql_query(raw_sql)
+            .bind::<sql_types::Nullable<sql_types::Date>, _>(constant_rate_date)
             .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Date, _>(start_date)
-            .bind::<sql_types::Uuid, _>(tenant_id)
+            .bind::<sql_types::Date, _>(end_date)
             .bind::<sql_types::Array<sql_types::Uuid>, _>(plan_ids)
             .bind::<sql_types::Date, _>(start_date)
-            .bind::<sql_types::Date, _>(end_date)
-            .bind::<sql_types::Uuid, _>(tenant_id)
-            .bind::<sql_types::Array<sql_types::Uuid>, _>(plan_ids);
+            .bind::<sql_types::Date, _>(start_date);
 
         log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
 
@@ -492,30 +502,24 @@ impl MrrBreakdownRow {
         tenant_id: Uuid,
         start_date: NaiveDate,
         end_date: NaiveDate,
+        constant_rate_date: Option<NaiveDate>,
     ) -> DbResult<Option<MrrBreakdownRow>> {
         let raw_sql = r#"
-        WITH conversion_rates AS (
-            SELECT
-                id,
-                (rates->>(SELECT currency FROM tenant WHERE id = $1))::NUMERIC AS rate
-            FROM
-                historical_rates_from_usd
-        )
         SELECT
-            COALESCE(SUM(bi.net_mrr_cents_usd * cr.rate), 0)::BIGINT AS net_new_mrr,
-            COALESCE(SUM(bi.new_business_cents_usd * cr.rate), 0)::BIGINT AS new_business_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.net_mrr_cents, bi.net_mrr_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS net_new_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.new_business_cents, bi.new_business_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS new_business_mrr,
             COALESCE(SUM(bi.new_business_count), 0)::INTEGER AS new_business_count,
-            COALESCE(SUM(bi.expansion_cents_usd * cr.rate), 0)::BIGINT AS expansion_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.expansion_cents, bi.expansion_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS expansion_mrr,
             COALESCE(SUM(bi.expansion_count), 0)::INTEGER AS expansion_count,
-            COALESCE(SUM(bi.contraction_cents_usd * cr.rate), 0)::BIGINT AS contraction_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.contraction_cents, bi.contraction_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS contraction_mrr,
             COALESCE(SUM(bi.contraction_count), 0)::INTEGER AS contraction_count,
-            COALESCE(SUM(bi.churn_cents_usd * cr.rate), 0)::BIGINT AS churn_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.churn_cents, bi.churn_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS churn_mrr,
             COALESCE(SUM(bi.churn_count), 0)::INTEGER AS churn_count,
-            COALESCE(SUM(bi.reactivation_cents_usd * cr.rate), 0)::BIGINT AS reactivation_mrr,
+            COALESCE(SUM(fn_fx_restate(bi.reactivation_cents, bi.reactivation_cents_usd, bi.historical_rate_id, bi.currency, t.currency, $1::date)), 0)::BIGINT AS reactivation_mrr,
             COALESCE(SUM(bi.reactivation_count), 0)::INTEGER AS reactivation_count
         FROM
             bi_delta_mrr_daily bi
-                JOIN conversion_rates cr ON bi.historical_rate_id = cr.id
+                JOIN tenant t ON t.id = bi.tenant_id
         WHERE
             bi.date BETWEEN $2 AND $3
           AND bi.tenant_id = $4
@@ -524,7 +528,7 @@ impl MrrBreakdownRow {
         "#;
 
         diesel::sql_query(raw_sql)
-            .bind::<sql_types::Uuid, _>(tenant_id)
+            .bind::<sql_types::Nullable<sql_types::Date>, _>(constant_rate_date)
             .bind::<sql_types::Date, _>(start_date)
             .bind::<sql_types::Date, _>(end_date)
             .bind::<sql_types::Uuid, _>(tenant_id)
@@ -590,3 +594,87 @@ impl LastMrrMovementsRow {
             .into_db_result()
     }
 }
+
+impl SubscriptionSegmentRow {
+    /// Active subscriptions and MRR by plan, billing period, currency and customer country, at a date and at the compared date.
+    /// The MRR of a subscription is the sum of the movements of its invoices up to the date
+    pub async fn list(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        as_of: NaiveDate,
+        compare_to: NaiveDate,
+    ) -> DbResult<Vec<SubscriptionSegmentRow>> {
+        let raw_sql = r#"
+        WITH segment_subscription AS (SELECT s.id,
+                                             pv.plan_id,
+                                             s.period,
+                                             s.billing_period_months,
+                                             s.currency,
+                                             c.billing_address ->> 'country' AS country,
+                                             (s.activated_at::date <= $2
+                                                 AND (s.billing_end_date IS NULL OR s.billing_end_date >= $2)) AS active,
+                                             (s.activated_at::date <= $3
+                                                 AND (s.billing_end_date IS NULL OR s.billing_end_date >= $3)) AS previously_active
+                                      FROM subscription s
+                                               JOIN plan_version pv ON s.plan_version_id = pv.id
+                                               JOIN customer c ON s.customer_id = c.id
+                                      WHERE s.tenant_id = $1
+                                        AND s.activated_at::date <= GREATEST($2, $3)),
+             subscription_mrr AS (SELECT i.subscription_id,
+                                         SUM(bi.net_mrr_change) FILTER (WHERE bi.applies_to <= $2) AS mrr,
+                                         SUM(bi.net_mrr_change) FILTER (WHERE bi.applies_to <= $3) AS previous_mrr
+                                  FROM bi_mrr_movement_log bi
+                                           JOIN invoice i ON bi.invoice_id = i.id
+                                  WHERE bi.tenant_id = $1
+                                  GROUP BY i.subscription_id)
+        SELECT ss.plan_id,
+               p.name                                                                     AS plan_name,
+               ss.period                                                                  AS billing_period,
+               ss.billing_period_months,
+               ss.currency,
+               ss.country,
+               COUNT(*) FILTER (WHERE ss.active)                                          AS subscriptions,
+               COUNT(*) FILTER (WHERE ss.previously_active)                               AS previous_subscriptions,
+               COALESCE(SUM(m.mrr) FILTER (WHERE ss.active), 0)::BIGINT                   AS mrr_cents,
+               COALESCE(SUM(m.previous_mrr) FILTER (WHERE ss.previously_active), 0)::BIGINT AS previous_mrr_cents
+        FROM segment_subscription ss
+                 JOIN plan p ON ss.plan_id = p.id
+                 LEFT JOIN subscription_mrr m ON m.subscription_id = ss.id
+        WHERE ss.active
+           OR ss.previously_active
+        GROUP BY ss.plan_id, p.name, ss.period, ss.billing_period_months, ss.currency, ss.country
+        ORDER BY p.name, ss.plan_id, ss.period, ss.billing_period_months, ss.currency, ss.country NULLS LAST;
+        "#;
+
+        let query = diesel::sql_query(raw_sql)
+            .bind::<sql_types::Uuid, _>(tenant_id)
+            .bind::<sql_types::Date, _>(as_of)
+            .bind::<sql_types::Date, _>(compare_to);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results::<SubscriptionSegmentRow>(conn)
+            .await
+            .attach_printable("Error while fetching subscription segments")
+            .into_db_result()
+    }
+}
+
+impl ReportSnapshotRow {
+    /// Makes the current transaction a read only snapshot, so that the following queries see the same data
+    /// whatever the workers write meanwhile. Must be the first statement of the transaction
+    pub async fn begin(conn: &mut PgConn) -> DbResult<ReportSnapshotRow> {
+        diesel::sql_query("SET TRANSACTION ISOLATION LEVEL REPEATABLE READ, READ ONLY")
+            .execute(conn)
+            .await
+            .attach_printable("Error while setting the snapshot isolation level")
+            .into_db_result()?;
+
+        diesel::sql_query("SELECT (now() AT TIME ZONE 'UTC')::timestamp AS as_of")
+            .get_result::<ReportSnapshotRow>(conn)
+            .await
+            .attach_printable("Error while fetching the snapshot timestamp")
+            .into_db_result()
+    }
+}
@@ -1,7 +1,7 @@
 use crate::errors::IntoDbResult;
 use crate::subscription_add_ons::{SubscriptionAddOnRow, SubscriptionAddOnRowNew};
 use crate::{DbResult, PgConn};
-use diesel::{debug_query, QueryDsl};
+use diesel::{debug_query, OptionalExtension, QueryDsl};
 use diesel::{ExpressionMethods, SelectableHelper};
 use diesel_async::RunQueryDsl;
 use error_stack::ResultExt;
@@ -46,4 +46,27 @@ impl SubscriptionAddOnRow {
             .attach_printable("Error while listing SubscriptionAddOn by subscription_id")
             .into_db_result()
     }
+
+    /// Removes an add-on from the subscription. Returns the removed add-on, if it was attached
+    pub async fn delete(
+        conn: &mut PgConn,
+        subscription_id: uuid::Uuid,
+        id: uuid::Uuid,
+    ) -> DbResult<Option<SubscriptionAddOnRow>> {
+        use crate::schema::subscription_add_on::dsl as sao_dsl;
+
+        let query = diesel::delete(sao_dsl::subscription_add_on)
+            .filter(sao_dsl::id.eq(id))
+            .filter(sao_dsl::subscription_id.eq(subscription_id))
+            .returning(SubscriptionAddOnRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while deleting SubscriptionAddOn")
+            .into_db_result()
+    }
 }
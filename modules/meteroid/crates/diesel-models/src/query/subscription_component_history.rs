@@ -0,0 +1,68 @@
+use crate::errors::IntoDbResult;
+use crate::subscription_component_history::{
+    SubscriptionComponentHistoryRow, SubscriptionComponentHistoryRowNew,
+};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl SubscriptionComponentHistoryRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<SubscriptionComponentHistoryRow> {
+        use crate::schema::subscription_component_history::dsl as sch_dsl;
+
+        let query = diesel::insert_into(sch_dsl::subscription_component_history).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting subscription component history")
+            .into_db_result()
+    }
+
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        batch: &[SubscriptionComponentHistoryRowNew],
+    ) -> DbResult<Vec<SubscriptionComponentHistoryRow>> {
+        use crate::schema::subscription_component_history::dsl as sch_dsl;
+
+        let query = diesel::insert_into(sch_dsl::subscription_component_history).values(batch);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting subscription component history batch")
+            .into_db_result()
+    }
+}
+
+impl SubscriptionComponentHistoryRow {
+    /// Latest effective changes first
+    pub async fn list_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> DbResult<Vec<SubscriptionComponentHistoryRow>> {
+        use crate::schema::subscription_component_history::dsl as sch_dsl;
+
+        let query = sch_dsl::subscription_component_history
+            .filter(sch_dsl::tenant_id.eq(tenant_id))
+            .filter(sch_dsl::subscription_id.eq(subscription_id))
+            .order((sch_dsl::effective_at.desc(), sch_dsl::created_at.desc()))
+            .select(SubscriptionComponentHistoryRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing subscription component history")
+            .into_db_result()
+    }
+}
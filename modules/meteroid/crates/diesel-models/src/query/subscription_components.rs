@@ -46,6 +46,25 @@ impl SubscriptionComponentRow {
             .into_db_result()
     }
 
+    pub async fn delete_by_subscription_id(
+        conn: &mut PgConn,
+        subscription_id: &uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::subscription_component::dsl as subscription_component_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(subscription_component_dsl::subscription_component)
+            .filter(subscription_component_dsl::subscription_id.eq(subscription_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting SubscriptionComponents by subscription")
+            .into_db_result()
+    }
+
     pub async fn list_subscription_components_by_subscription(
         conn: &mut PgConn,
         tenant_id_params: &uuid::Uuid,
@@ -62,4 +62,74 @@ impl SubscriptionEventRow {
             .attach_printable("Error while fetching subscription events")
             .into_db_result()
     }
+
+    pub async fn link_to_mrr_movement_log(
+        conn: &mut PgConn,
+        event_ids: &[uuid::Uuid],
+        log_id: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::subscription_event::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(subscription_event)
+            .filter(id.eq_any(event_ids))
+            .set(bi_mrr_movement_log_id.eq(log_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while linking subscription events to mrr movement log")
+            .into_db_result()
+    }
+
+    /// Unlinks the events from the movement logs, so they can be recorded again by another invoice
+    pub async fn unlink_mrr_movement_logs(
+        conn: &mut PgConn,
+        log_ids: &[uuid::Uuid],
+    ) -> DbResult<usize> {
+        use crate::schema::subscription_event::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(subscription_event)
+            .filter(bi_mrr_movement_log_id.eq_any(log_ids))
+            .set(bi_mrr_movement_log_id.eq(None::<uuid::Uuid>));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while unlinking subscription events from mrr movement logs")
+            .into_db_result()
+    }
+
+    pub async fn unlink_mrr_movement_logs_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_uid: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::subscription::dsl as s_dsl;
+        use crate::schema::subscription_event::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::update(subscription_event)
+            .filter(
+                subscription_id.eq_any(
+                    s_dsl::subscription
+                        .filter(s_dsl::tenant_id.eq(tenant_uid))
+                        .select(s_dsl::id),
+                ),
+            )
+            .filter(bi_mrr_movement_log_id.is_not_null())
+            .set(bi_mrr_movement_log_id.eq(None::<uuid::Uuid>));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while unlinking subscription events from mrr movement logs")
+            .into_db_result()
+    }
 }
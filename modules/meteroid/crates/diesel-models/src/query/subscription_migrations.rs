@@ -0,0 +1,213 @@
+use crate::enums::SubscriptionMigrationItemStatusEnum;
+use crate::errors::IntoDbResult;
+use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
+use crate::subscription_migrations::{
+    SubscriptionMigrationItemRow, SubscriptionMigrationItemRowNew, SubscriptionMigrationRow,
+    SubscriptionMigrationRowNew,
+};
+use crate::{DbResult, PgConn};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::dsl::not;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl SubscriptionMigrationRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<SubscriptionMigrationRow> {
+        use crate::schema::subscription_migration::dsl as sm_dsl;
+
+        let query = diesel::insert_into(sm_dsl::subscription_migration).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting subscription migration")
+            .into_db_result()
+    }
+}
+
+impl SubscriptionMigrationRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<SubscriptionMigrationRow> {
+        use crate::schema::subscription_migration::dsl as sm_dsl;
+
+        let query = sm_dsl::subscription_migration
+            .filter(sm_dsl::id.eq(id))
+            .filter(sm_dsl::tenant_id.eq(tenant_id))
+            .select(SubscriptionMigrationRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding subscription migration")
+            .into_db_result()
+    }
+
+    /// Adds the outcome of a batch to the counters of the migration
+    pub async fn record_progress(
+        conn: &mut PgConn,
+        id: Uuid,
+        processed: i32,
+        failed: i32,
+        mrr_delta: i64,
+    ) -> DbResult<SubscriptionMigrationRow> {
+        use crate::schema::subscription_migration::dsl as sm_dsl;
+
+        let query = diesel::update(sm_dsl::subscription_migration)
+            .filter(sm_dsl::id.eq(id))
+            .set((
+                sm_dsl::processed_count.eq(sm_dsl::processed_count + processed),
+                sm_dsl::failed_count.eq(sm_dsl::failed_count + failed),
+                sm_dsl::mrr_delta.eq(sm_dsl::mrr_delta + mrr_delta),
+            ))
+            .returning(SubscriptionMigrationRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while recording subscription migration progress")
+            .into_db_result()
+    }
+
+    pub async fn mark_as_completed(
+        conn: &mut PgConn,
+        id: Uuid,
+        now: NaiveDateTime,
+    ) -> DbResult<SubscriptionMigrationRow> {
+        use crate::schema::subscription_migration::dsl as sm_dsl;
+
+        let query = diesel::update(sm_dsl::subscription_migration)
+            .filter(sm_dsl::id.eq(id))
+            .set(sm_dsl::completed_at.eq(now))
+            .returning(SubscriptionMigrationRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while completing subscription migration")
+            .into_db_result()
+    }
+
+    /// Live subscriptions of the plan that are not on the target version yet.
+    /// Subscriptions grandfathered by a previous migration of the plan are left out unless `include_grandfathered`
+    pub async fn list_candidate_subscription_ids(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        plan_id: Uuid,
+        to_plan_version_id: Uuid,
+        from_plan_version_id: Option<Uuid>,
+        include_grandfathered: bool,
+        date: NaiveDate,
+    ) -> DbResult<Vec<Uuid>> {
+        use crate::schema::plan_version::dsl as pv_dsl;
+        use crate::schema::subscription::dsl as s_dsl;
+        use crate::schema::subscription_migration::dsl as sm_dsl;
+        use crate::schema::subscription_migration_item::dsl as smi_dsl;
+
+        let plan_versions = pv_dsl::plan_version
+            .filter(pv_dsl::plan_id.eq(plan_id))
+            .filter(pv_dsl::tenant_id.eq(tenant_id))
+            .select(pv_dsl::id);
+
+        let mut query = s_dsl::subscription
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .filter(s_dsl::plan_version_id.eq_any(plan_versions))
+            .filter(s_dsl::plan_version_id.ne(to_plan_version_id))
+            .filter(s_dsl::canceled_at.is_null())
+            .filter(
+                s_dsl::billing_end_date
+                    .is_null()
+                    .or(s_dsl::billing_end_date.gt(date)),
+            )
+            .select(s_dsl::id)
+            .order(s_dsl::id.asc())
+            .into_boxed();
+
+        if let Some(from_plan_version_id) = from_plan_version_id {
+            query = query.filter(s_dsl::plan_version_id.eq(from_plan_version_id));
+        }
+
+        if !include_grandfathered {
+            let grandfathered = smi_dsl::subscription_migration_item
+                .inner_join(sm_dsl::subscription_migration.on(sm_dsl::id.eq(smi_dsl::migration_id)))
+                .filter(sm_dsl::plan_id.eq(plan_id))
+                .filter(smi_dsl::status.eq(SubscriptionMigrationItemStatusEnum::Grandfathered))
+                .select(smi_dsl::subscription_id);
+
+            query = query.filter(not(s_dsl::id.eq_any(grandfathered)));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing subscription migration candidates")
+            .into_db_result()
+    }
+}
+
+impl SubscriptionMigrationItemRowNew {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        items: &[SubscriptionMigrationItemRowNew],
+    ) -> DbResult<usize> {
+        use crate::schema::subscription_migration_item::dsl as smi_dsl;
+
+        let query = diesel::insert_into(smi_dsl::subscription_migration_item).values(items);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while inserting subscription migration items")
+            .into_db_result()
+    }
+}
+
+impl SubscriptionMigrationItemRow {
+    pub async fn list_by_migration_id(
+        conn: &mut PgConn,
+        migration_id: Uuid,
+        status: Option<SubscriptionMigrationItemStatusEnum>,
+        pagination: PaginationRequest,
+    ) -> DbResult<PaginatedVec<SubscriptionMigrationItemRow>> {
+        use crate::schema::subscription_migration_item::dsl as smi_dsl;
+
+        let mut query = smi_dsl::subscription_migration_item
+            .filter(smi_dsl::migration_id.eq(migration_id))
+            .select(SubscriptionMigrationItemRow::as_select())
+            .order(smi_dsl::id.asc())
+            .into_boxed();
+
+        if let Some(status) = status {
+            query = query.filter(smi_dsl::status.eq(status));
+        }
+
+        let query = query.paginate(pagination);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_count_pages(conn)
+            .await
+            .attach_printable("Error while listing subscription migration items")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,162 @@
+use crate::errors::IntoDbResult;
+use crate::extend::cursor_pagination::{
+    CursorPaginate, CursorPaginatedVec, CursorPaginationRequest,
+};
+use crate::subscription_pending_changes::{
+    SubscriptionPendingChangeRow, SubscriptionPendingChangeRowNew,
+};
+use crate::{DbResult, PgConn};
+
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl SubscriptionPendingChangeRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<SubscriptionPendingChangeRow> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+
+        let query = diesel::insert_into(spc_dsl::subscription_pending_change).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting subscription pending change")
+            .into_db_result()
+    }
+}
+
+impl SubscriptionPendingChangeRow {
+    pub async fn list_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> DbResult<Vec<SubscriptionPendingChangeRow>> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+
+        let query = spc_dsl::subscription_pending_change
+            .filter(spc_dsl::tenant_id.eq(tenant_id))
+            .filter(spc_dsl::subscription_id.eq(subscription_id))
+            .order(spc_dsl::created_at.desc())
+            .select(SubscriptionPendingChangeRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing subscription pending changes")
+            .into_db_result()
+    }
+
+    /// The change waiting for the next renewal of the subscription, if any
+    pub async fn find_scheduled_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> DbResult<Option<SubscriptionPendingChangeRow>> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+
+        let query = spc_dsl::subscription_pending_change
+            .filter(spc_dsl::tenant_id.eq(tenant_id))
+            .filter(spc_dsl::subscription_id.eq(subscription_id))
+            .filter(spc_dsl::applied_at.is_null())
+            .filter(spc_dsl::canceled_at.is_null())
+            .select(SubscriptionPendingChangeRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding scheduled subscription pending change")
+            .into_db_result()
+    }
+
+    /// Scheduled changes taking effect at or before `date`, for all tenants
+    /// Changes due at the local date of the tenants billed in one of the timezones
+    pub async fn list_due(
+        conn: &mut PgConn,
+        date: NaiveDate,
+        billing_timezones: &[String],
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<SubscriptionPendingChangeRow>> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+        use crate::schema::tenant::dsl as t_dsl;
+
+        let query = spc_dsl::subscription_pending_change
+            .filter(
+                spc_dsl::tenant_id.eq_any(
+                    t_dsl::tenant
+                        .filter(t_dsl::billing_timezone.eq_any(billing_timezones))
+                        .select(t_dsl::id),
+                ),
+            )
+            .filter(spc_dsl::effective_at.le(date))
+            .filter(spc_dsl::applied_at.is_null())
+            .filter(spc_dsl::canceled_at.is_null())
+            .select(SubscriptionPendingChangeRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while paginating due subscription pending changes")
+            .into_db_result()
+    }
+
+    /// Cancels a change that was not applied yet
+    pub async fn cancel(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+        now: NaiveDateTime,
+    ) -> DbResult<Option<SubscriptionPendingChangeRow>> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+
+        let query = diesel::update(spc_dsl::subscription_pending_change)
+            .filter(spc_dsl::id.eq(id))
+            .filter(spc_dsl::tenant_id.eq(tenant_id))
+            .filter(spc_dsl::applied_at.is_null())
+            .filter(spc_dsl::canceled_at.is_null())
+            .set(spc_dsl::canceled_at.eq(now))
+            .returning(SubscriptionPendingChangeRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while canceling subscription pending change")
+            .into_db_result()
+    }
+
+    pub async fn mark_as_applied(
+        conn: &mut PgConn,
+        id: Uuid,
+        now: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::subscription_pending_change::dsl as spc_dsl;
+
+        let query = diesel::update(spc_dsl::subscription_pending_change)
+            .filter(spc_dsl::id.eq(id))
+            .filter(spc_dsl::applied_at.is_null())
+            .filter(spc_dsl::canceled_at.is_null())
+            .set(spc_dsl::applied_at.eq(now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while marking subscription pending change as applied")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,49 @@
+use crate::errors::IntoDbResult;
+use crate::subscription_phases::{SubscriptionPhaseRow, SubscriptionPhaseRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl SubscriptionPhaseRow {
+    pub async fn insert_batch(
+        conn: &mut PgConn,
+        batch: Vec<&SubscriptionPhaseRowNew>,
+    ) -> DbResult<Vec<SubscriptionPhaseRow>> {
+        use crate::schema::subscription_phase::dsl as sp_dsl;
+
+        let query = diesel::insert_into(sp_dsl::subscription_phase).values(batch);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while inserting SubscriptionPhase batch")
+            .into_db_result()
+    }
+
+    pub async fn list_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> DbResult<Vec<SubscriptionPhaseRow>> {
+        use crate::schema::subscription_phase::dsl as sp_dsl;
+
+        let query = sp_dsl::subscription_phase
+            .filter(sp_dsl::tenant_id.eq(tenant_id))
+            .filter(sp_dsl::subscription_id.eq(subscription_id))
+            .select(SubscriptionPhaseRow::as_select())
+            .order(sp_dsl::start_date.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing SubscriptionPhase by subscription_id")
+            .into_db_result()
+    }
+}
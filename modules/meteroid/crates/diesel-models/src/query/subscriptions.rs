@@ -3,7 +3,7 @@ use chrono::NaiveDate;
 
 use crate::subscriptions::{
     CancelSubscriptionParams, SubscriptionForDisplayRow, SubscriptionInvoiceCandidateRow,
-    SubscriptionRow, SubscriptionRowNew,
+    SubscriptionRow, SubscriptionRowNew, SubscriptionTrialEndRow,
 };
 use crate::{DbResult, PgConn};
 
@@ -13,7 +13,7 @@ use diesel::{
 };
 use diesel_async::RunQueryDsl;
 
-use crate::enums::InvoiceType;
+use crate::enums::{BillingPeriodEnum, InvoiceType};
 use crate::extend::cursor_pagination::{
     CursorPaginate, CursorPaginatedVec, CursorPaginationRequest,
 };
@@ -137,6 +137,101 @@ impl SubscriptionRow {
         Ok(())
     }
 
+    /// Sets the end of the subscription term. Removing the end date also lifts a pending cancellation
+    pub async fn update_billing_end_date(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+        billing_end_date: Option<NaiveDate>,
+    ) -> DbResult<()> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = diesel::update(s_dsl::subscription)
+            .filter(s_dsl::id.eq(id))
+            .filter(s_dsl::tenant_id.eq(tenant_id));
+
+        let res = match billing_end_date {
+            Some(date) => {
+                let query = query.set(s_dsl::billing_end_date.eq(date));
+                log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+                query.execute(conn).await
+            }
+            None => {
+                let query = query.set((
+                    s_dsl::billing_end_date.eq(None::<NaiveDate>),
+                    s_dsl::canceled_at.eq(None::<chrono::NaiveDateTime>),
+                    s_dsl::cancellation_reason.eq(None::<String>),
+                ));
+                log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+                query.execute(conn).await
+            }
+        };
+
+        res.attach_printable("Error while updating subscription billing end date")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
+    /// After the components of the subscription were replaced, by a plan switch or a component change
+    pub async fn update_plan_version(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+        period: BillingPeriodEnum,
+        billing_period_months: Option<i32>,
+    ) -> DbResult<()> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = diesel::update(s_dsl::subscription)
+            .filter(s_dsl::id.eq(id))
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .set((
+                s_dsl::plan_version_id.eq(plan_version_id),
+                s_dsl::period.eq(period),
+                s_dsl::billing_period_months.eq(billing_period_months),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating subscription plan version")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
+    pub async fn update_period(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+        period: BillingPeriodEnum,
+        billing_period_months: Option<i32>,
+    ) -> DbResult<()> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = diesel::update(s_dsl::subscription)
+            .filter(s_dsl::id.eq(id))
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .set((
+                s_dsl::period.eq(period),
+                s_dsl::billing_period_months.eq(billing_period_months),
+            ));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating subscription period")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
     pub async fn activate_subscription(
         conn: &mut PgConn,
         id: Uuid,
@@ -235,9 +330,11 @@ impl SubscriptionRow {
             .into_db_result()
     }
 
+    /// Candidates of the tenants billed in one of the timezones, `input_date_param` being their local date
     pub async fn list_subscription_to_invoice_candidates(
         conn: &mut PgConn,
         input_date_param: NaiveDate,
+        billing_timezones: &[String],
         pagination: CursorPaginationRequest,
     ) -> DbResult<CursorPaginatedVec<SubscriptionInvoiceCandidateRow>> {
         use crate::schema::invoice::dsl as i_dsl;
@@ -246,8 +343,16 @@ impl SubscriptionRow {
         use crate::schema::plan_version::dsl as pv_dsl;
         use crate::schema::subscription::dsl as s_dsl;
         use crate::schema::subscription_component::dsl as sc_dsl;
+        use crate::schema::tenant::dsl as t_dsl;
 
         let query = s_dsl::subscription
+            .filter(
+                s_dsl::tenant_id.eq_any(
+                    t_dsl::tenant
+                        .filter(t_dsl::billing_timezone.eq_any(billing_timezones))
+                        .select(t_dsl::id),
+                ),
+            )
             // only if not already ended
             .filter(
                 s_dsl::billing_end_date
@@ -258,12 +363,14 @@ impl SubscriptionRow {
             .filter(s_dsl::billing_start_date.lt(input_date_param))
             // only if no future recurring invoice exist.
             // (requires a single recurring invoice in parallel. For now, this is true)
+            // Invoices of a single cadence don't exclude the subscription, the missing cadences are resolved when drafting
             .left_join(
                 i_dsl::invoice.on(s_dsl::id
                     .nullable()
                     .eq(i_dsl::subscription_id)
                     .and(i_dsl::invoice_type.eq(InvoiceType::Recurring))
-                    .and(i_dsl::invoice_date.gt(input_date_param))),
+                    .and(i_dsl::invoice_date.gt(input_date_param))
+                    .and(i_dsl::billing_cadence.is_null())),
             )
             .filter(i_dsl::id.is_null())
             .inner_join(
@@ -282,6 +389,172 @@ impl SubscriptionRow {
             .into_db_result()
     }
 
+    /// Subscriptions in trial that reached their billing start date without being activated nor canceled
+    pub async fn list_ended_trials(
+        conn: &mut PgConn,
+        input_date_param: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<SubscriptionTrialEndRow>> {
+        use crate::schema::plan_version::dsl as pv_dsl;
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = s_dsl::subscription
+            .inner_join(pv_dsl::plan_version)
+            .filter(s_dsl::trial_start_date.is_not_null())
+            .filter(s_dsl::billing_start_date.le(input_date_param))
+            .filter(s_dsl::activated_at.is_null())
+            .filter(s_dsl::canceled_at.is_null())
+            .select(SubscriptionTrialEndRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while fetching ended trials")
+            .into_db_result()
+    }
+
+    /// Active subscriptions with an invoice threshold, for which usage may have to be invoiced early
+    pub async fn list_usage_threshold_candidates(
+        conn: &mut PgConn,
+        input_date_param: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<SubscriptionRow>> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = s_dsl::subscription
+            .filter(s_dsl::invoice_threshold.is_not_null())
+            .filter(s_dsl::activated_at.is_not_null())
+            .filter(s_dsl::billing_start_date.le(input_date_param))
+            .filter(
+                s_dsl::billing_end_date
+                    .is_null()
+                    .or(s_dsl::billing_end_date.gt(input_date_param)),
+            )
+            .select(SubscriptionRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while fetching usage threshold candidates")
+            .into_db_result()
+    }
+
+    /// Active subscriptions with a capped usage or capacity fee, in a component or an add-on
+    pub async fn list_usage_cap_candidates(
+        conn: &mut PgConn,
+        input_date_param: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<SubscriptionRow>> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        // the fees are stored as externally tagged json
+        let has_capped_fee = diesel::dsl::sql::<diesel::sql_types::Bool>(
+            "(exists (select 1 from subscription_component sc \
+               where sc.subscription_id = subscription.id \
+               and coalesce(sc.fee -> 'Usage' ->> 'cap', sc.fee -> 'Capacity' ->> 'cap') is not null) \
+             or exists (select 1 from subscription_add_on sa \
+               where sa.subscription_id = subscription.id \
+               and coalesce(sa.fee -> 'Usage' ->> 'cap', sa.fee -> 'Capacity' ->> 'cap') is not null))",
+        );
+
+        let query = s_dsl::subscription
+            .filter(s_dsl::activated_at.is_not_null())
+            .filter(s_dsl::billing_start_date.le(input_date_param))
+            .filter(
+                s_dsl::billing_end_date
+                    .is_null()
+                    .or(s_dsl::billing_end_date.gt(input_date_param)),
+            )
+            .filter(has_capped_fee)
+            .select(SubscriptionRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while fetching usage cap candidates")
+            .into_db_result()
+    }
+
+    /// Customers with an active subscription billing the metric at that date, through a component or an add-on
+    pub async fn list_customer_ids_billing_metric(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        metric_id: Uuid,
+        input_date_param: NaiveDate,
+    ) -> DbResult<Vec<Uuid>> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        // the fees are stored as externally tagged json
+        let bills_metric = diesel::dsl::sql::<diesel::sql_types::Bool>(
+            "(exists (select 1 from subscription_component sc \
+               where sc.subscription_id = subscription.id \
+               and coalesce(sc.fee -> 'Usage' ->> 'metric_id', sc.fee -> 'Capacity' ->> 'metric_id', sc.fee -> 'Percent' ->> 'metric_id') = ",
+        )
+        .bind::<diesel::sql_types::Text, _>(metric_id.to_string())
+        .sql(
+            ") or exists (select 1 from subscription_add_on sa \
+               where sa.subscription_id = subscription.id \
+               and coalesce(sa.fee -> 'Usage' ->> 'metric_id', sa.fee -> 'Capacity' ->> 'metric_id', sa.fee -> 'Percent' ->> 'metric_id') = ",
+        )
+        .bind::<diesel::sql_types::Text, _>(metric_id.to_string())
+        .sql("))");
+
+        let query = s_dsl::subscription
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .filter(s_dsl::activated_at.is_not_null())
+            .filter(s_dsl::canceled_at.is_null())
+            .filter(s_dsl::billing_start_date.le(input_date_param))
+            .filter(
+                s_dsl::billing_end_date
+                    .is_null()
+                    .or(s_dsl::billing_end_date.ge(input_date_param)),
+            )
+            .filter(bills_metric)
+            .select(s_dsl::customer_id)
+            .distinct();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing the customers billed for a metric")
+            .into_db_result()
+    }
+
+    pub async fn update_threshold_invoiced_until(
+        conn: &mut PgConn,
+        id: Uuid,
+        tenant_id: Uuid,
+        date: NaiveDate,
+    ) -> DbResult<()> {
+        use crate::schema::subscription::dsl as s_dsl;
+
+        let query = diesel::update(s_dsl::subscription)
+            .filter(s_dsl::id.eq(id))
+            .filter(s_dsl::tenant_id.eq(tenant_id))
+            .set(s_dsl::threshold_invoiced_until.eq(date));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating subscription threshold date")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
     pub async fn update_subscription_mrr_delta(
         conn: &mut PgConn,
         subscription_id: uuid::Uuid,
@@ -304,6 +577,24 @@ impl SubscriptionRow {
         Ok(())
     }
 
+    pub async fn reset_mrr_by_tenant_id(conn: &mut PgConn, tenant_uid: uuid::Uuid) -> DbResult<()> {
+        use crate::schema::subscription::dsl::*;
+
+        let query = diesel::update(subscription)
+            .filter(tenant_id.eq(tenant_uid))
+            .set(mrr_cents.eq(0));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while resetting subscriptions MRR")
+            .into_db_result()?;
+
+        Ok(())
+    }
+
     pub async fn lock_subscription_for_update(
         conn: &mut PgConn,
         subscription_id_param: uuid::Uuid,
@@ -0,0 +1,92 @@
+use crate::errors::IntoDbResult;
+use crate::tenant_member_roles::{TenantMemberRoleRow, TenantMemberRoleRowNew};
+use crate::{DbResult, PgConn};
+use diesel::upsert::excluded;
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl TenantMemberRoleRowNew {
+    /// Assigns the role, replacing the previous role of the member on the tenant
+    pub async fn upsert(&self, conn: &mut PgConn) -> DbResult<TenantMemberRoleRow> {
+        use crate::schema::tenant_member_role::dsl as tmr_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(tmr_dsl::tenant_member_role)
+            .values(self)
+            .on_conflict((tmr_dsl::user_id, tmr_dsl::tenant_id))
+            .do_update()
+            .set(tmr_dsl::role.eq(excluded(tmr_dsl::role)));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while upserting tenant member role")
+            .into_db_result()
+    }
+}
+
+impl TenantMemberRoleRow {
+    pub async fn find_by_user_id_and_tenant_id(
+        conn: &mut PgConn,
+        user_id: Uuid,
+        tenant_id: Uuid,
+    ) -> DbResult<Option<TenantMemberRoleRow>> {
+        use crate::schema::tenant_member_role::dsl as tmr_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = tmr_dsl::tenant_member_role
+            .filter(tmr_dsl::user_id.eq(user_id))
+            .filter(tmr_dsl::tenant_id.eq(tenant_id))
+            .select(TenantMemberRoleRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding tenant member role")
+            .into_db_result()
+    }
+
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+    ) -> DbResult<Vec<TenantMemberRoleRow>> {
+        use crate::schema::tenant_member_role::dsl as tmr_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = tmr_dsl::tenant_member_role
+            .filter(tmr_dsl::tenant_id.eq(tenant_id))
+            .order(tmr_dsl::created_at.asc())
+            .select(TenantMemberRoleRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing tenant member roles")
+            .into_db_result()
+    }
+
+    pub async fn delete(conn: &mut PgConn, user_id: Uuid, tenant_id: Uuid) -> DbResult<usize> {
+        use crate::schema::tenant_member_role::dsl as tmr_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(tmr_dsl::tenant_member_role)
+            .filter(tmr_dsl::user_id.eq(user_id))
+            .filter(tmr_dsl::tenant_id.eq(tenant_id));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting tenant member role")
+            .into_db_result()
+    }
+}
@@ -1,5 +1,9 @@
+use crate::enums::{
+    CollectionStrategyPresetEnum, InvoiceCadenceGroupingEnum, ProrationRoundingEnum,
+    ZeroInvoicePolicyEnum,
+};
 use crate::errors::IntoDbResult;
-use crate::tenants::{TenantRow, TenantRowNew, TenantRowPatch};
+use crate::tenants::{TenantPaymentTermsRow, TenantRow, TenantRowNew, TenantRowPatch};
 use crate::{DbResult, PgConn};
 
 use diesel::prelude::{ExpressionMethods, QueryDsl};
@@ -54,6 +58,115 @@ impl TenantRow {
             .into_db_result()
     }
 
+    pub async fn get_proration_rounding_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<ProrationRoundingEnum> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant.filter(id.eq(tenant_id)).select(proration_rounding);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding tenant proration rounding by id")
+            .into_db_result()
+    }
+
+    pub async fn get_zero_invoice_policy_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<ZeroInvoicePolicyEnum> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant.filter(id.eq(tenant_id)).select(zero_invoice_policy);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding tenant zero invoice policy by id")
+            .into_db_result()
+    }
+
+    pub async fn get_invoice_cadence_grouping_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<InvoiceCadenceGroupingEnum> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant
+            .filter(id.eq(tenant_id))
+            .select(invoice_cadence_grouping);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding tenant invoice cadence grouping by id")
+            .into_db_result()
+    }
+
+    pub async fn get_collection_strategy_preset_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<CollectionStrategyPresetEnum> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant
+            .filter(id.eq(tenant_id))
+            .select(collection_strategy_preset);
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding tenant collection strategy preset by id")
+            .into_db_result()
+    }
+
+    pub async fn get_payment_terms_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+    ) -> DbResult<TenantPaymentTermsRow> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant
+            .filter(id.eq(tenant_id))
+            .select(TenantPaymentTermsRow::as_select());
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding tenant payment terms by id")
+            .into_db_result()
+    }
+
+    /// The distinct billing timezones of the active tenants
+    pub async fn list_billing_timezones(conn: &mut PgConn) -> DbResult<Vec<String>> {
+        use crate::schema::tenant::dsl::*;
+        use diesel_async::RunQueryDsl;
+
+        let query = tenant
+            .filter(archived_at.is_null())
+            .select(billing_timezone)
+            .distinct();
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing tenant billing timezones")
+            .into_db_result()
+    }
+
     pub async fn find_by_id_and_organization_id(
         conn: &mut PgConn,
         tenant_id: uuid::Uuid,
@@ -140,3 +253,48 @@ impl TenantRowPatch {
             .into_db_result()
     }
 }
+
+/// Tenant data wiped by a sandbox reset, children first. The catalog (metrics, products, plans, coupons, add-ons) and the configuration are kept
+const SANDBOX_RESET_STATEMENTS: &[&str] = &[
+    "DELETE FROM invoice_stuck_alert WHERE tenant_id = $1",
+    "DELETE FROM customer_balance_pending_tx WHERE tenant_id = $1",
+    "DELETE FROM customer_balance_tx WHERE tenant_id = $1",
+    "DELETE FROM credit_note WHERE tenant_id = $1",
+    "DELETE FROM bi_mrr_movement_log WHERE tenant_id = $1",
+    "DELETE FROM bi_delta_mrr_daily WHERE tenant_id = $1",
+    "DELETE FROM bi_revenue_daily WHERE tenant_id = $1",
+    "DELETE FROM bi_customer_ytd_summary WHERE tenant_id = $1",
+    "DELETE FROM outbox WHERE tenant_id = $1",
+    "DELETE FROM invoice WHERE tenant_id = $1",
+    "DELETE FROM subscription_component_history WHERE tenant_id = $1",
+    "DELETE FROM subscription_pending_change WHERE tenant_id = $1",
+    "DELETE FROM applied_coupon WHERE subscription_id IN (SELECT id FROM subscription WHERE tenant_id = $1)",
+    "DELETE FROM slot_transaction WHERE subscription_id IN (SELECT id FROM subscription WHERE tenant_id = $1)",
+    "DELETE FROM subscription_add_on WHERE subscription_id IN (SELECT id FROM subscription WHERE tenant_id = $1)",
+    "DELETE FROM subscription_component WHERE subscription_id IN (SELECT id FROM subscription WHERE tenant_id = $1)",
+    "DELETE FROM subscription_event WHERE subscription_id IN (SELECT id FROM subscription WHERE tenant_id = $1)",
+    "DELETE FROM subscription WHERE tenant_id = $1",
+    "DELETE FROM usage_alert WHERE tenant_id = $1",
+    "DELETE FROM customer WHERE tenant_id = $1",
+    "UPDATE coupon SET redemption_count = 0, last_redemption_at = NULL WHERE tenant_id = $1",
+    "UPDATE invoicing_entity SET next_invoice_number = 1, next_credit_note_number = 1 WHERE tenant_id = $1",
+];
+
+impl TenantRow {
+    /// Deletes the customers, subscriptions, invoices and reporting data of the tenant. To run in a transaction
+    pub async fn reset_sandbox_data(conn: &mut PgConn, tenant_id: uuid::Uuid) -> DbResult<()> {
+        use diesel::sql_types;
+        use diesel_async::RunQueryDsl;
+
+        for statement in SANDBOX_RESET_STATEMENTS {
+            diesel::sql_query(*statement)
+                .bind::<sql_types::Uuid, _>(tenant_id)
+                .execute(conn)
+                .await
+                .attach_printable("Error while resetting sandbox tenant data")
+                .into_db_result()?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,287 @@
+use crate::errors::IntoDbResult;
+use crate::extend::cursor_pagination::{
+    CursorPaginate, CursorPaginatedVec, CursorPaginationRequest,
+};
+use crate::usage_alert_rules::{
+    UsageAlertRuleEventRow, UsageAlertRuleEventRowNew, UsageAlertRuleRow, UsageAlertRuleRowNew,
+    UsageAlertRuleRowPatch,
+};
+use crate::{DbResult, PgConn};
+
+use chrono::NaiveDateTime;
+use diesel::{
+    debug_query, BoolExpressionMethods, ExpressionMethods, OptionalExtension, QueryDsl,
+    SelectableHelper,
+};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl UsageAlertRuleRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<UsageAlertRuleRow> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = diesel::insert_into(uar_dsl::usage_alert_rule).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting usage alert rule")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRuleRow {
+    /// Archived rules included, for the notifications of their last events
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<UsageAlertRuleRow> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = uar_dsl::usage_alert_rule
+            .filter(uar_dsl::id.eq(id))
+            .filter(uar_dsl::tenant_id.eq(tenant_id))
+            .select(UsageAlertRuleRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding usage alert rule by id")
+            .into_db_result()
+    }
+
+    /// The rules applying to the customer when set, its own and the rules of all customers
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> DbResult<Vec<UsageAlertRuleRow>> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let mut query = uar_dsl::usage_alert_rule
+            .filter(uar_dsl::tenant_id.eq(tenant_id))
+            .filter(uar_dsl::archived_at.is_null())
+            .select(UsageAlertRuleRow::as_select())
+            .order(uar_dsl::created_at.asc())
+            .into_boxed();
+
+        if let Some(customer_id) = customer_id {
+            query = query.filter(
+                uar_dsl::customer_id
+                    .eq(customer_id)
+                    .or(uar_dsl::customer_id.is_null()),
+            );
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing usage alert rules")
+            .into_db_result()
+    }
+
+    /// Active rules of all tenants, for the evaluation worker
+    pub async fn list_active(
+        conn: &mut PgConn,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<UsageAlertRuleRow>> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = uar_dsl::usage_alert_rule
+            .filter(uar_dsl::archived_at.is_null())
+            .select(UsageAlertRuleRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while listing active usage alert rules")
+            .into_db_result()
+    }
+
+    pub async fn archive(conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = diesel::update(uar_dsl::usage_alert_rule)
+            .filter(uar_dsl::id.eq(id))
+            .filter(uar_dsl::tenant_id.eq(tenant_id))
+            .filter(uar_dsl::archived_at.is_null())
+            .set(uar_dsl::archived_at.eq(diesel::dsl::now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while archiving usage alert rule")
+            .into_db_result()
+    }
+
+    pub async fn mark_evaluated(
+        conn: &mut PgConn,
+        id: Uuid,
+        evaluated_at: NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = diesel::update(uar_dsl::usage_alert_rule)
+            .filter(uar_dsl::id.eq(id))
+            .set(uar_dsl::last_evaluated_at.eq(evaluated_at));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while marking usage alert rule as evaluated")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRuleRowPatch {
+    pub async fn update(&self, conn: &mut PgConn, tenant_id: Uuid, id: Uuid) -> DbResult<usize> {
+        use crate::schema::usage_alert_rule::dsl as uar_dsl;
+
+        let query = diesel::update(uar_dsl::usage_alert_rule)
+            .filter(uar_dsl::id.eq(id))
+            .filter(uar_dsl::tenant_id.eq(tenant_id))
+            .filter(uar_dsl::archived_at.is_null())
+            .set(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating usage alert rule")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRuleEventRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<UsageAlertRuleEventRow> {
+        use crate::schema::usage_alert_rule_event::dsl as uare_dsl;
+
+        let query = diesel::insert_into(uare_dsl::usage_alert_rule_event).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting usage alert rule event")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRuleEventRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<UsageAlertRuleEventRow> {
+        use crate::schema::usage_alert_rule_event::dsl as uare_dsl;
+
+        let query = uare_dsl::usage_alert_rule_event
+            .filter(uare_dsl::id.eq(id))
+            .filter(uare_dsl::tenant_id.eq(tenant_id))
+            .select(UsageAlertRuleEventRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding usage alert rule event by id")
+            .into_db_result()
+    }
+
+    /// The current state of the rule for the customer, none until it first fired
+    pub async fn find_latest(
+        conn: &mut PgConn,
+        rule_id: Uuid,
+        customer_id: Uuid,
+    ) -> DbResult<Option<UsageAlertRuleEventRow>> {
+        use crate::schema::usage_alert_rule_event::dsl as uare_dsl;
+
+        let query = uare_dsl::usage_alert_rule_event
+            .filter(uare_dsl::rule_id.eq(rule_id))
+            .filter(uare_dsl::customer_id.eq(customer_id))
+            .order(uare_dsl::created_at.desc())
+            .select(UsageAlertRuleEventRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .optional()
+            .attach_printable("Error while finding the latest usage alert rule event")
+            .into_db_result()
+    }
+
+    /// The current state of the rule for each customer it fired for
+    pub async fn list_latest_by_rule_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+    ) -> DbResult<Vec<UsageAlertRuleEventRow>> {
+        use crate::schema::usage_alert_rule_event::dsl as uare_dsl;
+
+        let query = uare_dsl::usage_alert_rule_event
+            .filter(uare_dsl::rule_id.eq(rule_id))
+            .filter(uare_dsl::tenant_id.eq(tenant_id))
+            .order((uare_dsl::customer_id, uare_dsl::created_at.desc()))
+            .distinct_on(uare_dsl::customer_id)
+            .select(UsageAlertRuleEventRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing the states of usage alert rule")
+            .into_db_result()
+    }
+
+    /// Most recent first
+    pub async fn list_by_rule_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+        customer_id: Option<Uuid>,
+        limit: i64,
+    ) -> DbResult<Vec<UsageAlertRuleEventRow>> {
+        use crate::schema::usage_alert_rule_event::dsl as uare_dsl;
+
+        let mut query = uare_dsl::usage_alert_rule_event
+            .filter(uare_dsl::rule_id.eq(rule_id))
+            .filter(uare_dsl::tenant_id.eq(tenant_id))
+            .order(uare_dsl::created_at.desc())
+            .limit(limit)
+            .select(UsageAlertRuleEventRow::as_select())
+            .into_boxed();
+
+        if let Some(customer_id) = customer_id {
+            query = query.filter(uare_dsl::customer_id.eq(customer_id));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing usage alert rule events")
+            .into_db_result()
+    }
+}
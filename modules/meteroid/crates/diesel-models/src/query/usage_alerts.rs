@@ -0,0 +1,137 @@
+use crate::errors::IntoDbResult;
+use crate::extend::cursor_pagination::{
+    CursorPaginate, CursorPaginatedVec, CursorPaginationRequest,
+};
+use crate::usage_alerts::{UsageAlertRow, UsageAlertRowNew, UsageAlertRowStatePatch};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+
+impl UsageAlertRowNew {
+    pub async fn insert(&self, conn: &mut PgConn) -> DbResult<UsageAlertRow> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let query = diesel::insert_into(ua_dsl::usage_alert).values(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .attach_printable("Error while inserting usage alert")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        id: uuid::Uuid,
+    ) -> DbResult<UsageAlertRow> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let query = ua_dsl::usage_alert
+            .filter(ua_dsl::id.eq(id))
+            .filter(ua_dsl::tenant_id.eq(tenant_id))
+            .select(UsageAlertRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding usage alert by id")
+            .into_db_result()
+    }
+
+    pub async fn list_by_tenant_id(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        customer_id: Option<uuid::Uuid>,
+    ) -> DbResult<Vec<UsageAlertRow>> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let mut query = ua_dsl::usage_alert
+            .filter(ua_dsl::tenant_id.eq(tenant_id))
+            .filter(ua_dsl::archived_at.is_null())
+            .select(UsageAlertRow::as_select())
+            .order(ua_dsl::created_at.asc())
+            .into_boxed();
+
+        if let Some(customer_id) = customer_id {
+            query = query.filter(ua_dsl::customer_id.eq(customer_id));
+        }
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing usage alerts")
+            .into_db_result()
+    }
+
+    /// Active alerts of all tenants, for the evaluation worker
+    pub async fn list_active(
+        conn: &mut PgConn,
+        pagination: CursorPaginationRequest,
+    ) -> DbResult<CursorPaginatedVec<UsageAlertRow>> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let query = ua_dsl::usage_alert
+            .filter(ua_dsl::archived_at.is_null())
+            .select(UsageAlertRow::as_select())
+            .cursor_paginate(pagination, "id");
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .load_and_get_next_cursor(conn, |a| a.id)
+            .await
+            .attach_printable("Error while listing active usage alerts")
+            .into_db_result()
+    }
+
+    pub async fn archive(
+        conn: &mut PgConn,
+        tenant_id: uuid::Uuid,
+        id: uuid::Uuid,
+    ) -> DbResult<usize> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let query = diesel::update(ua_dsl::usage_alert)
+            .filter(ua_dsl::id.eq(id))
+            .filter(ua_dsl::tenant_id.eq(tenant_id))
+            .filter(ua_dsl::archived_at.is_null())
+            .set(ua_dsl::archived_at.eq(diesel::dsl::now));
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while archiving usage alert")
+            .into_db_result()
+    }
+}
+
+impl UsageAlertRowStatePatch {
+    pub async fn update(&self, conn: &mut PgConn, id: uuid::Uuid) -> DbResult<usize> {
+        use crate::schema::usage_alert::dsl as ua_dsl;
+
+        let query = diesel::update(ua_dsl::usage_alert)
+            .filter(ua_dsl::id.eq(id))
+            .set(self);
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while updating usage alert state")
+            .into_db_result()
+    }
+}
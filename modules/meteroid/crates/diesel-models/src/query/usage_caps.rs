@@ -0,0 +1,77 @@
+use crate::errors::IntoDbResult;
+use crate::usage_caps::{UsageCapReachedRow, UsageCapReachedRowNew};
+use crate::{DbResult, PgConn};
+
+use diesel::{debug_query, ExpressionMethods, OptionalExtension, QueryDsl, SelectableHelper};
+use diesel_async::RunQueryDsl;
+use error_stack::ResultExt;
+use uuid::Uuid;
+
+impl UsageCapReachedRowNew {
+    /// None if the cap was already reached in that period
+    pub async fn insert_if_absent(
+        &self,
+        conn: &mut PgConn,
+    ) -> DbResult<Option<UsageCapReachedRow>> {
+        use crate::schema::usage_cap_reached::dsl as ucr_dsl;
+
+        let query = diesel::insert_into(ucr_dsl::usage_cap_reached)
+            .values(self)
+            .on_conflict((ucr_dsl::fee_id, ucr_dsl::period_start))
+            .do_nothing();
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_result(conn)
+            .await
+            .optional()
+            .attach_printable("Error while inserting usage cap reached")
+            .into_db_result()
+    }
+}
+
+impl UsageCapReachedRow {
+    pub async fn find_by_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> DbResult<UsageCapReachedRow> {
+        use crate::schema::usage_cap_reached::dsl as ucr_dsl;
+
+        let query = ucr_dsl::usage_cap_reached
+            .filter(ucr_dsl::id.eq(id))
+            .filter(ucr_dsl::tenant_id.eq(tenant_id))
+            .select(UsageCapReachedRow::as_select());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .first(conn)
+            .await
+            .attach_printable("Error while finding usage cap reached by id")
+            .into_db_result()
+    }
+
+    pub async fn list_by_subscription_id(
+        conn: &mut PgConn,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> DbResult<Vec<UsageCapReachedRow>> {
+        use crate::schema::usage_cap_reached::dsl as ucr_dsl;
+
+        let query = ucr_dsl::usage_cap_reached
+            .filter(ucr_dsl::tenant_id.eq(tenant_id))
+            .filter(ucr_dsl::subscription_id.eq(subscription_id))
+            .select(UsageCapReachedRow::as_select())
+            .order(ucr_dsl::reached_at.asc());
+
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .get_results(conn)
+            .await
+            .attach_printable("Error while listing usage caps reached")
+            .into_db_result()
+    }
+}
@@ -2,8 +2,8 @@ use crate::errors::IntoDbResult;
 use crate::extend::order::OrderByRequest;
 use crate::extend::pagination::{Paginate, PaginatedVec, PaginationRequest};
 use crate::webhooks::{
-    WebhookInEventRow, WebhookInEventRowNew, WebhookOutEndpointRow, WebhookOutEndpointRowNew,
-    WebhookOutEventRow, WebhookOutEventRowNew,
+    WebhookInDedupRowNew, WebhookInEventRow, WebhookInEventRowNew, WebhookOutEndpointRow,
+    WebhookOutEndpointRowNew, WebhookOutEventRow, WebhookOutEventRowNew,
 };
 use crate::{DbResult, PgConn};
 use diesel::{debug_query, ExpressionMethods, JoinOnDsl, QueryDsl, SelectableHelper};
@@ -138,3 +138,63 @@ impl WebhookInEventRowNew {
             .into_db_result()
     }
 }
+
+impl WebhookInDedupRowNew {
+    /// Records the event, unless it was already recorded. Returns the number of inserted rows
+    pub async fn insert_if_absent(&self, conn: &mut PgConn) -> DbResult<usize> {
+        use crate::schema::webhook_in_dedup::dsl as wd_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::insert_into(wd_dsl::webhook_in_dedup)
+            .values(self)
+            .on_conflict((wd_dsl::provider_config_id, wd_dsl::event_id))
+            .do_nothing();
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while inserting webhook_in_dedup")
+            .into_db_result()
+    }
+
+    pub async fn delete(
+        conn: &mut PgConn,
+        provider_config_id: uuid::Uuid,
+        event_id: &str,
+    ) -> DbResult<usize> {
+        use crate::schema::webhook_in_dedup::dsl as wd_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(wd_dsl::webhook_in_dedup)
+            .filter(wd_dsl::provider_config_id.eq(provider_config_id))
+            .filter(wd_dsl::event_id.eq(event_id));
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while deleting webhook_in_dedup")
+            .into_db_result()
+    }
+
+    pub async fn delete_received_before(
+        conn: &mut PgConn,
+        provider_config_id: uuid::Uuid,
+        received_before: chrono::NaiveDateTime,
+    ) -> DbResult<usize> {
+        use crate::schema::webhook_in_dedup::dsl as wd_dsl;
+        use diesel_async::RunQueryDsl;
+
+        let query = diesel::delete(wd_dsl::webhook_in_dedup)
+            .filter(wd_dsl::provider_config_id.eq(provider_config_id))
+            .filter(wd_dsl::received_at.lt(received_before));
+        log::debug!("{}", debug_query::<diesel::pg::Pg, _>(&query).to_string());
+
+        query
+            .execute(conn)
+            .await
+            .attach_printable("Error while purging webhook_in_dedup")
+            .into_db_result()
+    }
+}
@@ -0,0 +1,51 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = crate::schema::rate_card)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RateCardRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::rate_card)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RateCardRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub created_by: Uuid,
+}
+
+#[derive(Queryable, Debug, Identifiable, Selectable)]
+#[diesel(table_name = crate::schema::rate_card_version)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RateCardVersionRow {
+    pub id: Uuid,
+    pub rate_card_id: Uuid,
+    pub version: i32,
+    pub rates: serde_json::Value,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Debug, Insertable)]
+#[diesel(table_name = crate::schema::rate_card_version)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct RateCardVersionRowNew {
+    pub id: Uuid,
+    pub rate_card_id: Uuid,
+    pub version: i32,
+    pub rates: serde_json::Value,
+    pub created_by: Uuid,
+}
@@ -1,10 +1,26 @@
 // @generated automatically by Diesel CLI.
 
 pub mod sql_types {
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "AccountingConnectorEnum"))]
+    pub struct AccountingConnectorEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "AccountingExportRunStatusEnum"))]
+    pub struct AccountingExportRunStatusEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "AccountingExportScheduleEnum"))]
+    pub struct AccountingExportScheduleEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "ActionAfterTrialEnum"))]
     pub struct ActionAfterTrialEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "BillingEmailStatusEnum"))]
+    pub struct BillingEmailStatusEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "BillingMetricAggregateEnum"))]
     pub struct BillingMetricAggregateEnum;
@@ -13,14 +29,58 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "BillingPeriodEnum"))]
     pub struct BillingPeriodEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "CollectionStrategyPresetEnum"))]
+    pub struct CollectionStrategyPresetEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ComponentRuleTypeEnum"))]
+    pub struct ComponentRuleTypeEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "CreditLimitEnforcementEnum"))]
+    pub struct CreditLimitEnforcementEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "CreditLimitSourceEnum"))]
+    pub struct CreditLimitSourceEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "CreditNoteStatus"))]
     pub struct CreditNoteStatus;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "CustomerUniquenessEnum"))]
+    pub struct CustomerUniquenessEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "DueDatePolicyEnum"))]
+    pub struct DueDatePolicyEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "fang_task_state"))]
     pub struct FangTaskState;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceCadenceGroupingEnum"))]
+    pub struct InvoiceCadenceGroupingEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceApprovalActionEnum"))]
+    pub struct InvoiceApprovalActionEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceApprovalStatusEnum"))]
+    pub struct InvoiceApprovalStatusEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceDeliveryChannelEnum"))]
+    pub struct InvoiceDeliveryChannelEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceDeliveryStatusEnum"))]
+    pub struct InvoiceDeliveryStatusEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "InvoiceExternalStatusEnum"))]
     pub struct InvoiceExternalStatusEnum;
@@ -29,6 +89,10 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "InvoiceStatusEnum"))]
     pub struct InvoiceStatusEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "InvoiceStuckReasonEnum"))]
+    pub struct InvoiceStuckReasonEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "InvoiceType"))]
     pub struct InvoiceType;
@@ -41,6 +105,14 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "MRRMovementType"))]
     pub struct MrrMovementType;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "NotificationNamespaceEnum"))]
+    pub struct NotificationNamespaceEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "OnboardingStepEnum"))]
+    pub struct OnboardingStepEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "OrganizationUserRole"))]
     pub struct OrganizationUserRole;
@@ -49,6 +121,10 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "OutboxStatus"))]
     pub struct OutboxStatus;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "PaymentReminderChannelEnum"))]
+    pub struct PaymentReminderChannelEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "PlanStatusEnum"))]
     pub struct PlanStatusEnum;
@@ -57,6 +133,22 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "PlanTypeEnum"))]
     pub struct PlanTypeEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ProrationRoundingEnum"))]
+    pub struct ProrationRoundingEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SeatIncreaseRequestStatusEnum"))]
+    pub struct SeatIncreaseRequestStatusEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SubscriptionBillingModeEnum"))]
+    pub struct SubscriptionBillingModeEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SubscriptionComponentChangeTypeEnum"))]
+    pub struct SubscriptionComponentChangeTypeEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "SubscriptionEventType"))]
     pub struct SubscriptionEventType;
@@ -65,17 +157,95 @@ pub mod sql_types {
     #[diesel(postgres_type(name = "SubscriptionFeeBillingPeriod"))]
     pub struct SubscriptionFeeBillingPeriod;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SubscriptionMigrationItemStatusEnum"))]
+    pub struct SubscriptionMigrationItemStatusEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SubscriptionMigrationStrategyEnum"))]
+    pub struct SubscriptionMigrationStrategyEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "SubscriptionPendingChangeTypeEnum"))]
+    pub struct SubscriptionPendingChangeTypeEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "TaxRegistrationTypeEnum"))]
+    pub struct TaxRegistrationTypeEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "TenantEnvironmentEnum"))]
     pub struct TenantEnvironmentEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "TenantMemberRoleEnum"))]
+    pub struct TenantMemberRoleEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "TrialModeEnum"))]
+    pub struct TrialModeEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "UnitConversionRoundingEnum"))]
     pub struct UnitConversionRoundingEnum;
 
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "UsageAlertThresholdTypeEnum"))]
+    pub struct UsageAlertThresholdTypeEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "UsageAlertRuleStatusEnum"))]
+    pub struct UsageAlertRuleStatusEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "UsageAlertRuleWindowEnum"))]
+    pub struct UsageAlertRuleWindowEnum;
+
     #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
     #[diesel(postgres_type(name = "WebhookOutEventTypeEnum"))]
     pub struct WebhookOutEventTypeEnum;
+
+    #[derive(diesel::query_builder::QueryId, diesel::sql_types::SqlType)]
+    #[diesel(postgres_type(name = "ZeroInvoicePolicyEnum"))]
+    pub struct ZeroInvoicePolicyEnum;
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::AccountingConnectorEnum;
+    use super::sql_types::AccountingExportScheduleEnum;
+
+    accounting_export_config (tenant_id) {
+        tenant_id -> Uuid,
+        connector -> AccountingConnectorEnum,
+        schedule -> AccountingExportScheduleEnum,
+        enabled -> Bool,
+        accounts -> Jsonb,
+        credentials -> Nullable<Text>,
+        exported_until -> Nullable<Date>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        updated_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::AccountingConnectorEnum;
+    use super::sql_types::AccountingExportRunStatusEnum;
+
+    accounting_export_run (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        connector -> AccountingConnectorEnum,
+        period_start -> Date,
+        period_end -> Date,
+        status -> AccountingExportRunStatusEnum,
+        entries -> Int4,
+        document_id -> Nullable<Text>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
 }
 
 diesel::table! {
@@ -98,6 +268,23 @@ diesel::table! {
         tenant_id -> Uuid,
         hash -> Text,
         hint -> Text,
+        last_used_at -> Nullable<Timestamp>,
+        request_count -> Int8,
+        daily_request_soft_limit -> Nullable<Int8>,
+        disabled_at -> Nullable<Timestamp>,
+        rotated_at -> Nullable<Timestamp>,
+        partner_id -> Nullable<Uuid>,
+        scopes -> Array<Nullable<Text>>,
+        expires_at -> Nullable<Timestamp>,
+        expiry_notified_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    api_token_usage_daily (api_token_id, day) {
+        api_token_id -> Uuid,
+        day -> Date,
+        request_count -> Int8,
     }
 }
 
@@ -115,6 +302,20 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    audit_log (id) {
+        id -> Uuid,
+        organization_id -> Nullable<Uuid>,
+        tenant_id -> Nullable<Uuid>,
+        actor_id -> Uuid,
+        rpc -> Text,
+        entity_id -> Nullable<Text>,
+        diff -> Nullable<Jsonb>,
+        status_code -> Int4,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     bi_customer_ytd_summary (tenant_id, customer_id, currency, revenue_year) {
         tenant_id -> Uuid,
@@ -207,6 +408,56 @@ diesel::table! {
         archived_at -> Nullable<Timestamp>,
         tenant_id -> Uuid,
         product_family_id -> Uuid,
+        quantity_billing_precision -> Nullable<Int4>,
+        quantity_display_precision -> Nullable<Int4>,
+        event_schema -> Nullable<Jsonb>,
+    }
+}
+
+diesel::table! {
+    billing_run_summary (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        period_start -> Timestamp,
+        period_end -> Timestamp,
+        invoices_created -> Int4,
+        invoices_finalized -> Int4,
+        invoices_issued -> Int4,
+        issue_failures -> Int4,
+        totals -> Jsonb,
+        failures -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::CollectionStrategyPresetEnum;
+
+    collection_strategy_override (tenant_id, segment) {
+        tenant_id -> Uuid,
+        segment -> Text,
+        preset -> CollectionStrategyPresetEnum,
+        rules -> Jsonb,
+        updated_at -> Timestamp,
+        updated_by -> Uuid,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::ComponentRuleTypeEnum;
+
+    component_rule (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        rule_type -> ComponentRuleTypeEnum,
+        subject_price_component_id -> Nullable<Uuid>,
+        subject_add_on_id -> Nullable<Uuid>,
+        target_price_component_id -> Nullable<Uuid>,
+        target_add_on_id -> Nullable<Uuid>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
     }
 }
 
@@ -230,6 +481,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::CreditLimitSourceEnum;
+    use super::sql_types::CreditLimitEnforcementEnum;
+
+    credit_limit_exceeded (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        customer_id -> Uuid,
+        source -> CreditLimitSourceEnum,
+        subscription_id -> Nullable<Uuid>,
+        enforcement -> CreditLimitEnforcementEnum,
+        currency -> Text,
+        credit_limit -> Int8,
+        unpaid_invoices -> Int8,
+        unbilled_usage -> Int8,
+        exceeded_at -> Timestamp,
+        exceeded_on -> Date,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::CreditNoteStatus;
@@ -251,6 +523,12 @@ diesel::table! {
 }
 
 diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::DueDatePolicyEnum;
+    use super::sql_types::BillingEmailStatusEnum;
+    use super::sql_types::CreditLimitEnforcementEnum;
+    use super::sql_types::InvoiceDeliveryChannelEnum;
+
     customer (id) {
         id -> Uuid,
         name -> Text,
@@ -270,6 +548,33 @@ diesel::table! {
         billing_address -> Nullable<Jsonb>,
         shipping_address -> Nullable<Jsonb>,
         invoicing_entity_id -> Uuid,
+        net_terms_override -> Nullable<Int4>,
+        early_payment_discount_percent -> Nullable<Numeric>,
+        early_payment_discount_days -> Nullable<Int4>,
+        due_date_policy -> Nullable<DueDatePolicyEnum>,
+        due_date_day -> Nullable<Int4>,
+        billing_email_status -> BillingEmailStatusEnum,
+        billing_email_status_reason -> Nullable<Text>,
+        billing_email_status_updated_at -> Nullable<Timestamp>,
+        unique_email -> Nullable<Text>,
+        unique_alias -> Nullable<Text>,
+        collection_segment -> Nullable<Text>,
+        credit_limit_cents -> Nullable<Int8>,
+        credit_limit_enforcement -> CreditLimitEnforcementEnum,
+        invoice_delivery_channel -> Nullable<InvoiceDeliveryChannelEnum>,
+    }
+}
+
+diesel::table! {
+    customer_blackout_window (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        customer_id -> Uuid,
+        start_date -> Date,
+        end_date -> Date,
+        reason -> Nullable<Text>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
     }
 }
 
@@ -355,6 +660,9 @@ diesel::table! {
     use super::sql_types::InvoiceExternalStatusEnum;
     use super::sql_types::InvoicingProviderEnum;
     use super::sql_types::InvoiceType;
+    use super::sql_types::BillingPeriodEnum;
+    use super::sql_types::InvoiceDeliveryChannelEnum;
+    use super::sql_types::InvoiceDeliveryStatusEnum;
 
     invoice (id) {
         id -> Uuid,
@@ -397,6 +705,132 @@ diesel::table! {
         xml_document_id -> Nullable<Text>,
         pdf_document_id -> Nullable<Text>,
         applied_coupon_ids -> Array<Nullable<Uuid>>,
+        suppressed_at -> Nullable<Timestamp>,
+        billing_cadence -> Nullable<BillingPeriodEnum>,
+        voided_at -> Nullable<Timestamp>,
+        void_reason -> Nullable<Text>,
+        replaces_invoice_id -> Nullable<Uuid>,
+        manual_invoice_date -> Bool,
+        usage_outdated_at -> Nullable<Timestamp>,
+        manually_edited_at -> Nullable<Timestamp>,
+        manually_edited_by -> Nullable<Uuid>,
+        delivery_channel -> Nullable<InvoiceDeliveryChannelEnum>,
+        delivery_status -> Nullable<InvoiceDeliveryStatusEnum>,
+        delivered_at -> Nullable<Timestamp>,
+        hosted_invoice_url -> Nullable<Text>,
+        external_pdf_url -> Nullable<Text>,
+        billing_cadence_months -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TenantMemberRoleEnum;
+    use super::sql_types::InvoiceApprovalStatusEnum;
+
+    invoice_approval (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoice_id -> Uuid,
+        step_id -> Nullable<Uuid>,
+        position -> Int4,
+        name -> Text,
+        assignee_id -> Nullable<Uuid>,
+        assignee_role -> Nullable<TenantMemberRoleEnum>,
+        timeout_hours -> Nullable<Int4>,
+        escalation_assignee_id -> Nullable<Uuid>,
+        escalation_role -> Nullable<TenantMemberRoleEnum>,
+        status -> InvoiceApprovalStatusEnum,
+        requested_total -> Int8,
+        started_at -> Nullable<Timestamp>,
+        due_at -> Nullable<Timestamp>,
+        escalated_at -> Nullable<Timestamp>,
+        decided_at -> Nullable<Timestamp>,
+        decided_by -> Nullable<Uuid>,
+        comment -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::InvoiceApprovalActionEnum;
+
+    invoice_approval_event (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoice_id -> Uuid,
+        approval_id -> Nullable<Uuid>,
+        step_name -> Nullable<Text>,
+        action -> InvoiceApprovalActionEnum,
+        actor_id -> Nullable<Uuid>,
+        comment -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TenantMemberRoleEnum;
+
+    invoice_approval_step (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        position -> Int4,
+        name -> Text,
+        min_total -> Nullable<Int8>,
+        currency -> Nullable<Text>,
+        assignee_id -> Nullable<Uuid>,
+        assignee_role -> Nullable<TenantMemberRoleEnum>,
+        timeout_hours -> Nullable<Int4>,
+        escalation_assignee_id -> Nullable<Uuid>,
+        escalation_role -> Nullable<TenantMemberRoleEnum>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+    }
+}
+
+diesel::table! {
+    invoice_attachment (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoice_id -> Uuid,
+        file_name -> Text,
+        content_type -> Text,
+        size_bytes -> Int8,
+        document_id -> Text,
+        include_in_email -> Bool,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        removed_at -> Nullable<Timestamp>,
+        removed_by -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    invoice_payment (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoice_id -> Uuid,
+        amount -> Int8,
+        paid_at -> Date,
+        reference -> Nullable<Text>,
+        note -> Nullable<Text>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::InvoiceStuckReasonEnum;
+
+    invoice_stuck_alert (invoice_id) {
+        invoice_id -> Uuid,
+        tenant_id -> Uuid,
+        reason -> InvoiceStuckReasonEnum,
+        alerted_at -> Timestamp,
+        resolved_at -> Nullable<Timestamp>,
     }
 }
 
@@ -429,6 +863,38 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TaxRegistrationTypeEnum;
+
+    invoicing_entity_tax_registration (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoicing_entity_id -> Uuid,
+        registration_type -> TaxRegistrationTypeEnum,
+        jurisdiction -> Text,
+        registration_number -> Text,
+        customer_countries -> Array<Nullable<Text>>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::NotificationNamespaceEnum;
+
+    notification_bundle (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        namespace -> NotificationNamespaceEnum,
+        locale -> Text,
+        messages -> Jsonb,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        updated_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     organization (id) {
         id -> Uuid,
@@ -471,6 +937,76 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    partner (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        name -> Text,
+        rev_share_percent -> Numeric,
+        created_at -> Timestamp,
+        archived_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    partner_attribution (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        partner_id -> Uuid,
+        customer_id -> Nullable<Uuid>,
+        subscription_id -> Nullable<Uuid>,
+        rev_share_percent -> Nullable<Numeric>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    partner_commission (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        partner_id -> Uuid,
+        invoice_id -> Uuid,
+        customer_id -> Uuid,
+        currency -> Text,
+        invoice_amount -> Int8,
+        rev_share_percent -> Numeric,
+        amount -> Int8,
+        paid_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PaymentReminderChannelEnum;
+
+    payment_reminder_log (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        invoice_id -> Uuid,
+        schedule_id -> Nullable<Uuid>,
+        offset_days -> Int4,
+        channel -> PaymentReminderChannelEnum,
+        recipient -> Nullable<Text>,
+        due_at -> Timestamp,
+        sent_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::PaymentReminderChannelEnum;
+
+    payment_reminder_schedule (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        offset_days -> Int4,
+        channel -> PaymentReminderChannelEnum,
+        enabled -> Bool,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::PlanTypeEnum;
@@ -496,6 +1032,7 @@ diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::BillingPeriodEnum;
     use super::sql_types::ActionAfterTrialEnum;
+    use super::sql_types::TrialModeEnum;
 
     plan_version (id) {
         id -> Uuid,
@@ -515,6 +1052,21 @@ diesel::table! {
         trialing_plan_id -> Nullable<Uuid>,
         action_after_trial -> Nullable<ActionAfterTrialEnum>,
         trial_is_free -> Bool,
+        trial_mode -> TrialModeEnum,
+        tax_category -> Nullable<Text>,
+        inherited_defaults -> Array<Text>,
+    }
+}
+
+diesel::table! {
+    plan_version_feature (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        plan_version_id -> Uuid,
+        key -> Text,
+        enabled -> Bool,
+        usage_limit -> Nullable<Int8>,
+        created_at -> Timestamp,
     }
 }
 
@@ -526,6 +1078,7 @@ diesel::table! {
         plan_version_id -> Uuid,
         product_item_id -> Nullable<Uuid>,
         billable_metric_id -> Nullable<Uuid>,
+        rate_card_id -> Nullable<Uuid>,
     }
 }
 
@@ -552,6 +1105,10 @@ diesel::table! {
         updated_at -> Nullable<Timestamp>,
         archived_at -> Nullable<Timestamp>,
         tenant_id -> Uuid,
+        default_currency -> Nullable<Text>,
+        default_trial_duration_days -> Nullable<Int4>,
+        default_net_terms -> Nullable<Int4>,
+        default_tax_category -> Nullable<Text>,
     }
 }
 
@@ -567,6 +1124,31 @@ diesel::table! {
         enabled -> Bool,
         webhook_security -> Jsonb,
         api_security -> Jsonb,
+        is_default -> Bool,
+        fallback_priority -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    rate_card (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        name -> Text,
+        description -> Nullable<Text>,
+        currency -> Text,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+    }
+}
+
+diesel::table! {
+    rate_card_version (id) {
+        id -> Uuid,
+        rate_card_id -> Uuid,
+        version -> Int4,
+        rates -> Jsonb,
+        created_at -> Timestamp,
+        created_by -> Uuid,
     }
 }
 
@@ -582,6 +1164,27 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SeatIncreaseRequestStatusEnum;
+
+    seat_increase_request (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        subscription_id -> Uuid,
+        price_component_id -> Uuid,
+        seats -> Int4,
+        requested_by -> Nullable<Text>,
+        note -> Nullable<Text>,
+        status -> SeatIncreaseRequestStatusEnum,
+        created_at -> Timestamp,
+        decided_at -> Nullable<Timestamp>,
+        decided_by -> Nullable<Uuid>,
+        decision_note -> Nullable<Text>,
+        invoice_id -> Nullable<Uuid>,
+    }
+}
+
 diesel::table! {
     slot_transaction (id) {
         id -> Uuid,
@@ -597,6 +1200,7 @@ diesel::table! {
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::BillingPeriodEnum;
+    use super::sql_types::SubscriptionBillingModeEnum;
 
     subscription (id) {
         id -> Uuid,
@@ -619,6 +1223,10 @@ diesel::table! {
         currency -> Varchar,
         mrr_cents -> Int8,
         period -> BillingPeriodEnum,
+        threshold_invoiced_until -> Nullable<Date>,
+        billing_mode -> SubscriptionBillingModeEnum,
+        minimum_commitment -> Nullable<Numeric>,
+        billing_period_months -> Nullable<Int4>,
     }
 }
 
@@ -634,6 +1242,7 @@ diesel::table! {
         period -> SubscriptionFeeBillingPeriod,
         fee -> Jsonb,
         created_at -> Timestamp,
+        billing_period_months -> Nullable<Int4>,
     }
 }
 
@@ -649,6 +1258,26 @@ diesel::table! {
         product_item_id -> Nullable<Uuid>,
         period -> SubscriptionFeeBillingPeriod,
         fee -> Jsonb,
+        billing_period_months -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SubscriptionComponentChangeTypeEnum;
+
+    subscription_component_history (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        subscription_id -> Uuid,
+        price_component_id -> Nullable<Uuid>,
+        component_name -> Text,
+        change_type -> SubscriptionComponentChangeTypeEnum,
+        previous_value -> Jsonb,
+        new_value -> Jsonb,
+        effective_at -> Timestamp,
+        created_at -> Timestamp,
+        created_by -> Uuid,
     }
 }
 
@@ -668,9 +1297,84 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SubscriptionMigrationStrategyEnum;
+
+    subscription_migration (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        plan_id -> Uuid,
+        to_plan_version_id -> Uuid,
+        strategy -> SubscriptionMigrationStrategyEnum,
+        total_count -> Int4,
+        processed_count -> Int4,
+        failed_count -> Int4,
+        mrr_delta -> Int8,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        completed_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SubscriptionMigrationItemStatusEnum;
+
+    subscription_migration_item (id) {
+        id -> Uuid,
+        migration_id -> Uuid,
+        subscription_id -> Uuid,
+        from_plan_version_id -> Uuid,
+        status -> SubscriptionMigrationItemStatusEnum,
+        mrr_delta -> Nullable<Int8>,
+        prorated_amount -> Nullable<Int8>,
+        error -> Nullable<Text>,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::SubscriptionPendingChangeTypeEnum;
+
+    subscription_pending_change (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        subscription_id -> Uuid,
+        change_type -> SubscriptionPendingChangeTypeEnum,
+        details -> Jsonb,
+        effective_at -> Date,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        applied_at -> Nullable<Timestamp>,
+        canceled_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    subscription_phase (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        subscription_id -> Uuid,
+        name -> Text,
+        start_date -> Date,
+        end_date -> Date,
+        price_component_id -> Nullable<Uuid>,
+        discount -> Jsonb,
+        created_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     use diesel::sql_types::*;
     use super::sql_types::TenantEnvironmentEnum;
+    use super::sql_types::ProrationRoundingEnum;
+    use super::sql_types::ZeroInvoicePolicyEnum;
+    use super::sql_types::InvoiceCadenceGroupingEnum;
+    use super::sql_types::DueDatePolicyEnum;
+    use super::sql_types::CustomerUniquenessEnum;
+    use super::sql_types::CollectionStrategyPresetEnum;
 
     tenant (id) {
         id -> Uuid,
@@ -682,6 +1386,126 @@ diesel::table! {
         organization_id -> Uuid,
         currency -> Text,
         environment -> TenantEnvironmentEnum,
+        proration_rounding -> ProrationRoundingEnum,
+        zero_invoice_policy -> ZeroInvoicePolicyEnum,
+        invoice_cadence_grouping -> InvoiceCadenceGroupingEnum,
+        default_net_terms -> Nullable<Int4>,
+        due_date_policy -> DueDatePolicyEnum,
+        due_date_day -> Nullable<Int4>,
+        production_tenant_id -> Nullable<Uuid>,
+        finance_email -> Nullable<Text>,
+        customer_uniqueness -> CustomerUniquenessEnum,
+        collection_strategy_preset -> CollectionStrategyPresetEnum,
+        billing_timezone -> Text,
+        finalization_grace_period_hours -> Nullable<Int4>,
+        seat_auto_approve_limit -> Nullable<Int4>,
+        stuck_draft_max_age_days -> Nullable<Int4>,
+        stuck_pending_max_age_hours -> Nullable<Int4>,
+        stuck_max_issue_attempts -> Nullable<Int4>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::TenantMemberRoleEnum;
+
+    tenant_member_role (user_id, tenant_id) {
+        user_id -> Uuid,
+        organization_id -> Uuid,
+        tenant_id -> Uuid,
+        role -> TenantMemberRoleEnum,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::OnboardingStepEnum;
+
+    tenant_onboarding_step (tenant_id, step) {
+        tenant_id -> Uuid,
+        step -> OnboardingStepEnum,
+        completed_at -> Timestamp,
+        completed_by -> Nullable<Uuid>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UsageAlertThresholdTypeEnum;
+
+    usage_alert (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        customer_id -> Uuid,
+        billable_metric_id -> Uuid,
+        threshold_type -> UsageAlertThresholdTypeEnum,
+        threshold_value -> Numeric,
+        hysteresis_percent -> Numeric,
+        notify_email -> Nullable<Text>,
+        triggered -> Bool,
+        triggered_period_start -> Nullable<Date>,
+        last_value -> Nullable<Numeric>,
+        last_triggered_at -> Nullable<Timestamp>,
+        last_checked_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        archived_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UsageAlertRuleWindowEnum;
+
+    usage_alert_rule (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        name -> Text,
+        billable_metric_id -> Uuid,
+        customer_id -> Nullable<Uuid>,
+        usage_window -> UsageAlertRuleWindowEnum,
+        match_all -> Bool,
+        conditions -> Jsonb,
+        notify_webhook -> Bool,
+        notify_email -> Nullable<Text>,
+        last_evaluated_at -> Nullable<Timestamp>,
+        created_at -> Timestamp,
+        created_by -> Uuid,
+        updated_at -> Nullable<Timestamp>,
+        archived_at -> Nullable<Timestamp>,
+    }
+}
+
+diesel::table! {
+    use diesel::sql_types::*;
+    use super::sql_types::UsageAlertRuleStatusEnum;
+
+    usage_alert_rule_event (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        rule_id -> Uuid,
+        customer_id -> Uuid,
+        status -> UsageAlertRuleStatusEnum,
+        usage -> Numeric,
+        growth_percent -> Nullable<Numeric>,
+        window_start -> Date,
+        window_end -> Date,
+        created_at -> Timestamp,
+    }
+}
+
+diesel::table! {
+    usage_cap_reached (id) {
+        id -> Uuid,
+        tenant_id -> Uuid,
+        subscription_id -> Uuid,
+        fee_id -> Uuid,
+        period_start -> Date,
+        period_end -> Date,
+        cap -> Int8,
+        charges -> Int8,
+        reached_at -> Timestamp,
     }
 }
 
@@ -699,6 +1523,14 @@ diesel::table! {
     }
 }
 
+diesel::table! {
+    webhook_in_dedup (provider_config_id, event_id) {
+        provider_config_id -> Uuid,
+        event_id -> Text,
+        received_at -> Timestamp,
+    }
+}
+
 diesel::table! {
     webhook_in_event (id) {
         id -> Uuid,
@@ -725,6 +1557,8 @@ diesel::table! {
         created_at -> Timestamp,
         events_to_listen -> Array<Nullable<WebhookOutEventTypeEnum>>,
         enabled -> Bool,
+        batch_max_events -> Nullable<Int4>,
+        batch_max_wait_seconds -> Nullable<Int4>,
     }
 }
 
@@ -741,11 +1575,16 @@ diesel::table! {
         response_body -> Nullable<Text>,
         http_status_code -> Nullable<Int2>,
         error_message -> Nullable<Text>,
+        batch_id -> Nullable<Uuid>,
     }
 }
 
+diesel::joinable!(accounting_export_config -> tenant (tenant_id));
+diesel::joinable!(accounting_export_run -> tenant (tenant_id));
 diesel::joinable!(add_on -> tenant (tenant_id));
+diesel::joinable!(api_token -> partner (partner_id));
 diesel::joinable!(api_token -> tenant (tenant_id));
+diesel::joinable!(api_token_usage_daily -> api_token (api_token_id));
 diesel::joinable!(applied_coupon -> coupon (coupon_id));
 diesel::joinable!(applied_coupon -> customer (customer_id));
 diesel::joinable!(applied_coupon -> subscription (subscription_id));
@@ -757,7 +1596,12 @@ diesel::joinable!(bi_mrr_movement_log -> tenant (tenant_id));
 diesel::joinable!(bi_revenue_daily -> historical_rates_from_usd (historical_rate_id));
 diesel::joinable!(billable_metric -> product_family (product_family_id));
 diesel::joinable!(billable_metric -> tenant (tenant_id));
+diesel::joinable!(billing_run_summary -> tenant (tenant_id));
+diesel::joinable!(collection_strategy_override -> tenant (tenant_id));
+diesel::joinable!(component_rule -> tenant (tenant_id));
 diesel::joinable!(coupon -> tenant (tenant_id));
+diesel::joinable!(credit_limit_exceeded -> customer (customer_id));
+diesel::joinable!(credit_limit_exceeded -> tenant (tenant_id));
 diesel::joinable!(credit_note -> customer (customer_id));
 diesel::joinable!(credit_note -> invoice (invoice_id));
 diesel::joinable!(credit_note -> plan_version (plan_version_id));
@@ -773,21 +1617,59 @@ diesel::joinable!(customer_balance_tx -> customer (customer_id));
 diesel::joinable!(customer_balance_tx -> invoice (invoice_id));
 diesel::joinable!(customer_balance_tx -> tenant (tenant_id));
 diesel::joinable!(customer_balance_tx -> user (created_by));
+diesel::joinable!(customer_blackout_window -> customer (customer_id));
+diesel::joinable!(customer_blackout_window -> tenant (tenant_id));
 diesel::joinable!(invoice -> customer (customer_id));
 diesel::joinable!(invoice -> plan_version (plan_version_id));
 diesel::joinable!(invoice -> tenant (tenant_id));
+diesel::joinable!(invoice_approval -> invoice (invoice_id));
+diesel::joinable!(invoice_approval -> invoice_approval_step (step_id));
+diesel::joinable!(invoice_approval -> tenant (tenant_id));
+diesel::joinable!(invoice_approval_event -> invoice (invoice_id));
+diesel::joinable!(invoice_approval_event -> invoice_approval (approval_id));
+diesel::joinable!(invoice_approval_event -> tenant (tenant_id));
+diesel::joinable!(invoice_approval_step -> tenant (tenant_id));
+diesel::joinable!(invoice_attachment -> invoice (invoice_id));
+diesel::joinable!(invoice_attachment -> tenant (tenant_id));
+diesel::joinable!(invoice_payment -> invoice (invoice_id));
+diesel::joinable!(invoice_payment -> tenant (tenant_id));
+diesel::joinable!(invoice_stuck_alert -> invoice (invoice_id));
+diesel::joinable!(invoice_stuck_alert -> tenant (tenant_id));
 diesel::joinable!(invoicing_entity -> tenant (tenant_id));
+diesel::joinable!(invoicing_entity_tax_registration -> invoicing_entity (invoicing_entity_id));
+diesel::joinable!(invoicing_entity_tax_registration -> tenant (tenant_id));
+diesel::joinable!(notification_bundle -> tenant (tenant_id));
 diesel::joinable!(organization_member -> organization (organization_id));
 diesel::joinable!(organization_member -> user (user_id));
+diesel::joinable!(partner -> tenant (tenant_id));
+diesel::joinable!(partner_attribution -> customer (customer_id));
+diesel::joinable!(partner_attribution -> partner (partner_id));
+diesel::joinable!(partner_attribution -> subscription (subscription_id));
+diesel::joinable!(partner_attribution -> tenant (tenant_id));
+diesel::joinable!(partner_commission -> invoice (invoice_id));
+diesel::joinable!(partner_commission -> partner (partner_id));
+diesel::joinable!(partner_commission -> tenant (tenant_id));
+diesel::joinable!(payment_reminder_log -> invoice (invoice_id));
+diesel::joinable!(payment_reminder_log -> payment_reminder_schedule (schedule_id));
+diesel::joinable!(payment_reminder_log -> tenant (tenant_id));
+diesel::joinable!(payment_reminder_schedule -> tenant (tenant_id));
 diesel::joinable!(plan -> product_family (product_family_id));
 diesel::joinable!(plan -> tenant (tenant_id));
+diesel::joinable!(plan_version_feature -> plan_version (plan_version_id));
+diesel::joinable!(plan_version_feature -> tenant (tenant_id));
 diesel::joinable!(price_component -> billable_metric (billable_metric_id));
 diesel::joinable!(price_component -> plan_version (plan_version_id));
 diesel::joinable!(price_component -> product (product_item_id));
+diesel::joinable!(price_component -> rate_card (rate_card_id));
 diesel::joinable!(product -> product_family (product_family_id));
 diesel::joinable!(product -> tenant (tenant_id));
 diesel::joinable!(product_family -> tenant (tenant_id));
+diesel::joinable!(rate_card -> tenant (tenant_id));
+diesel::joinable!(rate_card_version -> rate_card (rate_card_id));
 diesel::joinable!(schedule -> plan_version (plan_version_id));
+diesel::joinable!(seat_increase_request -> invoice (invoice_id));
+diesel::joinable!(seat_increase_request -> subscription (subscription_id));
+diesel::joinable!(seat_increase_request -> tenant (tenant_id));
 diesel::joinable!(slot_transaction -> price_component (price_component_id));
 diesel::joinable!(slot_transaction -> subscription (subscription_id));
 diesel::joinable!(subscription -> customer (customer_id));
@@ -798,49 +1680,114 @@ diesel::joinable!(subscription_add_on -> subscription (subscription_id));
 diesel::joinable!(subscription_component -> price_component (price_component_id));
 diesel::joinable!(subscription_component -> product (product_item_id));
 diesel::joinable!(subscription_component -> subscription (subscription_id));
+diesel::joinable!(subscription_component_history -> subscription (subscription_id));
+diesel::joinable!(subscription_component_history -> tenant (tenant_id));
 diesel::joinable!(subscription_event -> bi_mrr_movement_log (bi_mrr_movement_log_id));
 diesel::joinable!(subscription_event -> subscription (subscription_id));
+diesel::joinable!(subscription_migration -> plan (plan_id));
+diesel::joinable!(subscription_migration -> plan_version (to_plan_version_id));
+diesel::joinable!(subscription_migration -> tenant (tenant_id));
+diesel::joinable!(subscription_migration_item -> plan_version (from_plan_version_id));
+diesel::joinable!(subscription_migration_item -> subscription (subscription_id));
+diesel::joinable!(subscription_migration_item -> subscription_migration (migration_id));
+diesel::joinable!(subscription_pending_change -> subscription (subscription_id));
+diesel::joinable!(subscription_pending_change -> tenant (tenant_id));
+diesel::joinable!(subscription_phase -> subscription (subscription_id));
+diesel::joinable!(subscription_phase -> tenant (tenant_id));
 diesel::joinable!(tenant -> organization (organization_id));
+diesel::joinable!(tenant_member_role -> tenant (tenant_id));
+diesel::joinable!(tenant_onboarding_step -> tenant (tenant_id));
+diesel::joinable!(tenant_onboarding_step -> user (completed_by));
+diesel::joinable!(usage_alert -> billable_metric (billable_metric_id));
+diesel::joinable!(usage_alert -> customer (customer_id));
+diesel::joinable!(usage_alert -> tenant (tenant_id));
+diesel::joinable!(usage_alert_rule -> billable_metric (billable_metric_id));
+diesel::joinable!(usage_alert_rule -> customer (customer_id));
+diesel::joinable!(usage_alert_rule -> tenant (tenant_id));
+diesel::joinable!(usage_alert_rule_event -> customer (customer_id));
+diesel::joinable!(usage_alert_rule_event -> tenant (tenant_id));
+diesel::joinable!(usage_alert_rule_event -> usage_alert_rule (rule_id));
+diesel::joinable!(usage_cap_reached -> subscription (subscription_id));
+diesel::joinable!(usage_cap_reached -> tenant (tenant_id));
+diesel::joinable!(webhook_in_dedup -> provider_config (provider_config_id));
 diesel::joinable!(webhook_in_event -> provider_config (provider_config_id));
 diesel::joinable!(webhook_out_endpoint -> tenant (tenant_id));
 diesel::joinable!(webhook_out_event -> webhook_out_endpoint (endpoint_id));
 
 diesel::allow_tables_to_appear_in_same_query!(
+    accounting_export_config,
+    accounting_export_run,
     add_on,
     api_token,
+    api_token_usage_daily,
     applied_coupon,
+    audit_log,
     bi_customer_ytd_summary,
     bi_delta_mrr_daily,
     bi_mrr_movement_log,
     bi_revenue_daily,
     billable_metric,
+    billing_run_summary,
+    collection_strategy_override,
+    component_rule,
     coupon,
+    credit_limit_exceeded,
     credit_note,
     customer,
     customer_balance_pending_tx,
     customer_balance_tx,
+    customer_blackout_window,
     fang_tasks,
     fang_tasks_archive,
     historical_rates_from_usd,
     invoice,
+    invoice_approval,
+    invoice_approval_event,
+    invoice_approval_step,
+    invoice_attachment,
+    invoice_payment,
+    invoice_stuck_alert,
     invoicing_entity,
+    invoicing_entity_tax_registration,
+    notification_bundle,
     organization,
     organization_member,
     outbox,
+    partner,
+    partner_attribution,
+    partner_commission,
+    payment_reminder_log,
+    payment_reminder_schedule,
     plan,
     plan_version,
+    plan_version_feature,
     price_component,
     product,
     product_family,
     provider_config,
+    rate_card,
+    rate_card_version,
     schedule,
+    seat_increase_request,
     slot_transaction,
     subscription,
     subscription_add_on,
     subscription_component,
+    subscription_component_history,
     subscription_event,
+    subscription_migration,
+    subscription_migration_item,
+    subscription_pending_change,
+    subscription_phase,
     tenant,
+    tenant_member_role,
+    tenant_onboarding_step,
+    usage_alert,
+    usage_alert_rule,
+    usage_alert_rule_event,
+    usage_cap_reached,
     user,
+    webhook_in_dedup,
     webhook_in_event,
     webhook_out_endpoint,
     webhook_out_event,
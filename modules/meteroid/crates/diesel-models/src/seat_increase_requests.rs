@@ -0,0 +1,40 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::SeatIncreaseRequestStatusEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::seat_increase_request)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SeatIncreaseRequestRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Uuid,
+    pub seats: i32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+    pub status: SeatIncreaseRequestStatusEnum,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    pub decided_by: Option<Uuid>,
+    pub decision_note: Option<String>,
+    pub invoice_id: Option<Uuid>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::seat_increase_request)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SeatIncreaseRequestRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Uuid,
+    pub seats: i32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+    pub status: SeatIncreaseRequestStatusEnum,
+    pub decided_at: Option<NaiveDateTime>,
+    pub invoice_id: Option<Uuid>,
+}
@@ -1,4 +1,4 @@
-use crate::enums::MrrMovementType;
+use crate::enums::{BillingPeriodEnum, MrrMovementType};
 use chrono::{NaiveDate, NaiveDateTime};
 use diesel::QueryableByName;
 use rust_decimal::Decimal;
@@ -211,3 +211,33 @@ pub struct TotalMrrByPlanRow {
     #[diesel(sql_type = diesel::sql_types::Integer)]
     pub reactivation_count: i32,
 }
+
+#[derive(QueryableByName, Debug)]
+pub struct ReportSnapshotRow {
+    #[diesel(sql_type = diesel::sql_types::Timestamp)]
+    pub as_of: NaiveDateTime,
+}
+
+#[derive(QueryableByName, Debug)]
+pub struct SubscriptionSegmentRow {
+    #[diesel(sql_type = diesel::sql_types::Uuid)]
+    pub plan_id: Uuid,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub plan_name: String,
+    #[diesel(sql_type = crate::schema::sql_types::BillingPeriodEnum)]
+    pub billing_period: BillingPeriodEnum,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Integer>)]
+    pub billing_period_months: Option<i32>,
+    #[diesel(sql_type = diesel::sql_types::Text)]
+    pub currency: String,
+    #[diesel(sql_type = diesel::sql_types::Nullable<diesel::sql_types::Text>)]
+    pub country: Option<String>,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub subscriptions: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub previous_subscriptions: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub mrr_cents: i64,
+    #[diesel(sql_type = diesel::sql_types::BigInt)]
+    pub previous_mrr_cents: i64,
+}
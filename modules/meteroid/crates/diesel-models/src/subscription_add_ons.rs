@@ -15,6 +15,7 @@ pub struct SubscriptionAddOnRow {
     pub period: SubscriptionFeeBillingPeriod,
     pub fee: serde_json::Value,
     pub created_at: NaiveDateTime,
+    pub billing_period_months: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -27,4 +28,5 @@ pub struct SubscriptionAddOnRowNew {
     pub add_on_id: Uuid,
     pub period: SubscriptionFeeBillingPeriod,
     pub fee: serde_json::Value,
+    pub billing_period_months: Option<i32>,
 }
@@ -0,0 +1,38 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::SubscriptionComponentChangeTypeEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::subscription_component_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionComponentHistoryRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Option<Uuid>,
+    pub component_name: String,
+    pub change_type: SubscriptionComponentChangeTypeEnum,
+    pub previous_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+    pub effective_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::subscription_component_history)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionComponentHistoryRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Option<Uuid>,
+    pub component_name: String,
+    pub change_type: SubscriptionComponentChangeTypeEnum,
+    pub previous_value: serde_json::Value,
+    pub new_value: serde_json::Value,
+    pub effective_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
@@ -15,6 +15,7 @@ pub struct SubscriptionComponentRow {
     pub period: SubscriptionFeeBillingPeriod,
     // pub mrr_value: Option<Decimal>,
     pub fee: serde_json::Value,
+    pub billing_period_months: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -28,4 +29,5 @@ pub struct SubscriptionComponentRowNew {
     pub period: SubscriptionFeeBillingPeriod,
     // pub mrr_value: Option<Decimal>,
     pub fee: serde_json::Value,
+    pub billing_period_months: Option<i32>,
 }
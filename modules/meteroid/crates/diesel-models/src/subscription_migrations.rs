@@ -0,0 +1,65 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::{SubscriptionMigrationItemStatusEnum, SubscriptionMigrationStrategyEnum};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::subscription_migration)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionMigrationRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub plan_id: Uuid,
+    pub to_plan_version_id: Uuid,
+    pub strategy: SubscriptionMigrationStrategyEnum,
+    pub total_count: i32,
+    pub processed_count: i32,
+    pub failed_count: i32,
+    pub mrr_delta: i64,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::subscription_migration)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionMigrationRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub plan_id: Uuid,
+    pub to_plan_version_id: Uuid,
+    pub strategy: SubscriptionMigrationStrategyEnum,
+    pub total_count: i32,
+    pub created_by: Uuid,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::subscription_migration_item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionMigrationItemRow {
+    pub id: Uuid,
+    pub migration_id: Uuid,
+    pub subscription_id: Uuid,
+    pub from_plan_version_id: Uuid,
+    pub status: SubscriptionMigrationItemStatusEnum,
+    pub mrr_delta: Option<i64>,
+    pub prorated_amount: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::subscription_migration_item)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionMigrationItemRowNew {
+    pub id: Uuid,
+    pub migration_id: Uuid,
+    pub subscription_id: Uuid,
+    pub from_plan_version_id: Uuid,
+    pub status: SubscriptionMigrationItemStatusEnum,
+    pub mrr_delta: Option<i64>,
+    pub prorated_amount: Option<i64>,
+    pub error: Option<String>,
+}
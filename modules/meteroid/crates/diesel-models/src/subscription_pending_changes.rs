@@ -0,0 +1,34 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::SubscriptionPendingChangeTypeEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::subscription_pending_change)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionPendingChangeRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub change_type: SubscriptionPendingChangeTypeEnum,
+    pub details: serde_json::Value,
+    pub effective_at: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub applied_at: Option<NaiveDateTime>,
+    pub canceled_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::subscription_pending_change)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionPendingChangeRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub change_type: SubscriptionPendingChangeTypeEnum,
+    pub details: serde_json::Value,
+    pub effective_at: NaiveDate,
+    pub created_by: Uuid,
+}
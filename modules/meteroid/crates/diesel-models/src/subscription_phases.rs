@@ -0,0 +1,32 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::subscription_phase)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionPhaseRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub price_component_id: Option<Uuid>,
+    pub discount: serde_json::Value,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::subscription_phase)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionPhaseRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub price_component_id: Option<Uuid>,
+    pub discount: serde_json::Value,
+}
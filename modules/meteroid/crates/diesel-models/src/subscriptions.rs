@@ -5,7 +5,7 @@ use chrono::NaiveDate;
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
-use crate::enums::BillingPeriodEnum;
+use crate::enums::{BillingPeriodEnum, SubscriptionBillingModeEnum, TrialModeEnum};
 use diesel::{Identifiable, Insertable, Queryable, Selectable};
 use rust_decimal::Decimal;
 
@@ -32,6 +32,10 @@ pub struct SubscriptionRow {
     pub currency: String,
     pub mrr_cents: i64,
     pub period: BillingPeriodEnum,
+    pub threshold_invoiced_until: Option<NaiveDate>,
+    pub billing_mode: SubscriptionBillingModeEnum,
+    pub minimum_commitment: Option<Decimal>,
+    pub billing_period_months: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -53,6 +57,9 @@ pub struct SubscriptionRowNew {
     pub currency: String,
     pub mrr_cents: i64,
     pub period: BillingPeriodEnum,
+    pub billing_mode: SubscriptionBillingModeEnum,
+    pub minimum_commitment: Option<Decimal>,
+    pub billing_period_months: Option<i32>,
 }
 
 pub struct CancelSubscriptionParams {
@@ -85,6 +92,19 @@ pub struct SubscriptionForDisplayRow {
     pub plan_id: Uuid,
 }
 
+/// Subscription whose trial has ended without being activated
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::subscription)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct SubscriptionTrialEndRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub billing_start_date: NaiveDate,
+    #[diesel(select_expression = plan_version::trial_mode)]
+    #[diesel(select_expression_type = plan_version::trial_mode)]
+    pub trial_mode: TrialModeEnum,
+}
+
 #[derive(Debug, Queryable, Selectable)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
 pub struct SubscriptionInvoiceCandidateRow {
@@ -104,7 +124,7 @@ pub struct SubscriptionInvoiceCandidateGroupByRow {
 }
 
 mod subscription_invoice_candidate {
-    use crate::enums::BillingPeriodEnum;
+    use crate::enums::{BillingPeriodEnum, SubscriptionBillingModeEnum};
 
     use chrono::{NaiveDate, NaiveDateTime};
 
@@ -125,6 +145,8 @@ mod subscription_invoice_candidate {
         pub activated_at: Option<NaiveDateTime>,
         pub canceled_at: Option<NaiveDateTime>,
         pub period: BillingPeriodEnum,
+        pub billing_period_months: Option<i32>,
+        pub billing_mode: SubscriptionBillingModeEnum,
     }
 
     #[derive(Debug, Queryable, Selectable)]
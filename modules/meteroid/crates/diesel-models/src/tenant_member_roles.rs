@@ -0,0 +1,27 @@
+use chrono::NaiveDateTime;
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+use crate::enums::TenantMemberRoleEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(primary_key(user_id, tenant_id))]
+#[diesel(table_name = crate::schema::tenant_member_role)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TenantMemberRoleRow {
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub tenant_id: Uuid,
+    pub role: TenantMemberRoleEnum,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::tenant_member_role)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TenantMemberRoleRowNew {
+    pub user_id: Uuid,
+    pub organization_id: Uuid,
+    pub tenant_id: Uuid,
+    pub role: TenantMemberRoleEnum,
+}
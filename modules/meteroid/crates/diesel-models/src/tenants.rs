@@ -1,7 +1,11 @@
 use chrono::NaiveDateTime;
 use uuid::Uuid;
 
-use crate::enums::TenantEnvironmentEnum;
+use crate::enums::{
+    CollectionStrategyPresetEnum, CustomerUniquenessEnum, DueDatePolicyEnum,
+    InvoiceCadenceGroupingEnum, ProrationRoundingEnum, TenantEnvironmentEnum,
+    ZeroInvoicePolicyEnum,
+};
 
 use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
 
@@ -19,6 +23,22 @@ pub struct TenantRow {
     // the reporting currency, used in dashboards
     pub currency: String,
     pub environment: TenantEnvironmentEnum,
+    pub proration_rounding: ProrationRoundingEnum,
+    pub zero_invoice_policy: ZeroInvoicePolicyEnum,
+    pub invoice_cadence_grouping: InvoiceCadenceGroupingEnum,
+    pub default_net_terms: Option<i32>,
+    pub due_date_policy: DueDatePolicyEnum,
+    pub due_date_day: Option<i32>,
+    pub production_tenant_id: Option<Uuid>,
+    pub finance_email: Option<String>,
+    pub customer_uniqueness: CustomerUniquenessEnum,
+    pub collection_strategy_preset: CollectionStrategyPresetEnum,
+    pub billing_timezone: String,
+    pub finalization_grace_period_hours: Option<i32>,
+    pub seat_auto_approve_limit: Option<i32>,
+    pub stuck_draft_max_age_days: Option<i32>,
+    pub stuck_pending_max_age_hours: Option<i32>,
+    pub stuck_max_issue_attempts: Option<i32>,
 }
 
 #[derive(Debug, Insertable)]
@@ -31,6 +51,7 @@ pub struct TenantRowNew {
     pub organization_id: Uuid,
     pub currency: String,
     pub environment: TenantEnvironmentEnum,
+    pub production_tenant_id: Option<Uuid>,
 }
 
 #[derive(Debug, AsChangeset)]
@@ -41,4 +62,29 @@ pub struct TenantRowPatch {
     pub slug: Option<String>,
     pub currency: Option<String>,
     pub environment: Option<TenantEnvironmentEnum>,
+    pub proration_rounding: Option<ProrationRoundingEnum>,
+    pub zero_invoice_policy: Option<ZeroInvoicePolicyEnum>,
+    pub invoice_cadence_grouping: Option<InvoiceCadenceGroupingEnum>,
+    pub default_net_terms: Option<i32>,
+    pub due_date_policy: Option<DueDatePolicyEnum>,
+    pub due_date_day: Option<i32>,
+    pub finance_email: Option<String>,
+    pub customer_uniqueness: Option<CustomerUniquenessEnum>,
+    pub collection_strategy_preset: Option<CollectionStrategyPresetEnum>,
+    pub billing_timezone: Option<String>,
+    pub finalization_grace_period_hours: Option<i32>,
+    pub seat_auto_approve_limit: Option<i32>,
+    pub stuck_draft_max_age_days: Option<i32>,
+    pub stuck_pending_max_age_hours: Option<i32>,
+    pub stuck_max_issue_attempts: Option<i32>,
+}
+
+#[derive(Debug, Queryable, Selectable)]
+#[diesel(table_name = crate::schema::tenant)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct TenantPaymentTermsRow {
+    pub default_net_terms: Option<i32>,
+    pub due_date_policy: DueDatePolicyEnum,
+    pub due_date_day: Option<i32>,
+    pub billing_timezone: String,
 }
@@ -0,0 +1,90 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::enums::{UsageAlertRuleStatusEnum, UsageAlertRuleWindowEnum};
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::usage_alert_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRuleRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub billable_metric_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub usage_window: UsageAlertRuleWindowEnum,
+    pub match_all: bool,
+    pub conditions: serde_json::Value,
+    pub notify_webhook: bool,
+    pub notify_email: Option<String>,
+    pub last_evaluated_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: Option<NaiveDateTime>,
+    pub archived_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::usage_alert_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRuleRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub billable_metric_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub usage_window: UsageAlertRuleWindowEnum,
+    pub match_all: bool,
+    pub conditions: serde_json::Value,
+    pub notify_webhook: bool,
+    pub notify_email: Option<String>,
+    pub created_by: Uuid,
+}
+
+/// The definition of the rule, its metric and scope being kept
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::usage_alert_rule)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct UsageAlertRuleRowPatch {
+    pub name: String,
+    pub usage_window: UsageAlertRuleWindowEnum,
+    pub match_all: bool,
+    pub conditions: serde_json::Value,
+    pub notify_webhook: bool,
+    pub notify_email: Option<String>,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::usage_alert_rule_event)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRuleEventRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_id: Uuid,
+    pub customer_id: Uuid,
+    pub status: UsageAlertRuleStatusEnum,
+    pub usage: Decimal,
+    pub growth_percent: Option<Decimal>,
+    pub window_start: NaiveDate,
+    pub window_end: NaiveDate,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::usage_alert_rule_event)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRuleEventRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_id: Uuid,
+    pub customer_id: Uuid,
+    pub status: UsageAlertRuleStatusEnum,
+    pub usage: Decimal,
+    pub growth_percent: Option<Decimal>,
+    pub window_start: NaiveDate,
+    pub window_end: NaiveDate,
+}
@@ -0,0 +1,56 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{AsChangeset, Identifiable, Insertable, Queryable, Selectable};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::enums::UsageAlertThresholdTypeEnum;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::usage_alert)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub billable_metric_id: Uuid,
+    pub threshold_type: UsageAlertThresholdTypeEnum,
+    pub threshold_value: Decimal,
+    pub hysteresis_percent: Decimal,
+    pub notify_email: Option<String>,
+    pub triggered: bool,
+    pub triggered_period_start: Option<NaiveDate>,
+    pub last_value: Option<Decimal>,
+    pub last_triggered_at: Option<NaiveDateTime>,
+    pub last_checked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub archived_at: Option<NaiveDateTime>,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::usage_alert)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageAlertRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub billable_metric_id: Uuid,
+    pub threshold_type: UsageAlertThresholdTypeEnum,
+    pub threshold_value: Decimal,
+    pub hysteresis_percent: Decimal,
+    pub notify_email: Option<String>,
+    pub created_by: Uuid,
+}
+
+/// Outcome of an evaluation run, the whole state is overwritten
+#[derive(AsChangeset, Debug)]
+#[diesel(table_name = crate::schema::usage_alert)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+#[diesel(treat_none_as_null = true)]
+pub struct UsageAlertRowStatePatch {
+    pub triggered: bool,
+    pub triggered_period_start: Option<NaiveDate>,
+    pub last_value: Option<Decimal>,
+    pub last_triggered_at: Option<NaiveDateTime>,
+    pub last_checked_at: Option<NaiveDateTime>,
+}
@@ -0,0 +1,32 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel::{Identifiable, Insertable, Queryable, Selectable};
+use uuid::Uuid;
+
+#[derive(Queryable, Identifiable, Debug, Selectable)]
+#[diesel(table_name = crate::schema::usage_cap_reached)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageCapReachedRow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub fee_id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub cap: i64,
+    pub charges: i64,
+    pub reached_at: NaiveDateTime,
+}
+
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::usage_cap_reached)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct UsageCapReachedRowNew {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub fee_id: Uuid,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub cap: i64,
+    pub charges: i64,
+}
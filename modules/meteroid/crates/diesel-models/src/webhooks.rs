@@ -32,6 +32,15 @@ pub struct WebhookInEventRowNew {
     pub provider_config_id: Uuid,
 }
 
+#[derive(Insertable, Debug)]
+#[diesel(table_name = crate::schema::webhook_in_dedup)]
+#[diesel(check_for_backend(diesel::pg::Pg))]
+pub struct WebhookInDedupRowNew {
+    pub provider_config_id: Uuid,
+    pub event_id: String,
+    pub received_at: NaiveDateTime,
+}
+
 #[derive(Queryable, Identifiable, Debug)]
 #[diesel(table_name = crate::schema::webhook_out_endpoint)]
 #[diesel(check_for_backend(diesel::pg::Pg))]
@@ -44,6 +53,8 @@ pub struct WebhookOutEndpointRow {
     pub created_at: NaiveDateTime,
     pub events_to_listen: Vec<Option<WebhookOutEventTypeEnum>>,
     pub enabled: bool,
+    pub batch_max_events: Option<i32>,
+    pub batch_max_wait_seconds: Option<i32>,
 }
 
 #[derive(Insertable, Debug)]
@@ -57,6 +68,8 @@ pub struct WebhookOutEndpointRowNew {
     pub secret: String,
     pub events_to_listen: Vec<WebhookOutEventTypeEnum>,
     pub enabled: bool,
+    pub batch_max_events: Option<i32>,
+    pub batch_max_wait_seconds: Option<i32>,
 }
 
 #[derive(Queryable, Identifiable, Debug, Selectable)]
@@ -70,6 +83,7 @@ pub struct WebhookOutEventRow {
     pub response_body: Option<String>,
     pub http_status_code: Option<i16>,
     pub error_message: Option<String>,
+    pub batch_id: Option<Uuid>,
 }
 
 #[derive(Debug, Insertable)]
@@ -83,4 +97,5 @@ pub struct WebhookOutEventRowNew {
     pub response_body: Option<String>,
     pub http_status_code: Option<i16>,
     pub error_message: Option<String>,
+    pub batch_id: Option<Uuid>,
 }
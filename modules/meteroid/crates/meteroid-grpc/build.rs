@@ -12,23 +12,36 @@ fn main() -> Result<(), BuildError> {
 
 fn generate_grpc_types(root: &Path) -> Result<(), BuildError> {
     let services = vec![
+        "accountingexports",
         "addons",
         "apitokens",
+        "auditlogs",
         "billablemetrics",
+        "collectionstrategies",
+        "componentrules",
         "customers",
         "coupons",
         "instance",
         "invoices",
         "invoicingentities",
+        "notificationbundles",
         "organizations",
+        "partners",
+        "paymentreminders",
         "plans",
         "pricecomponents",
         "productfamilies",
         "products",
+        "providers",
+        "ratecards",
+        "reports",
+        "roles",
         "schedules",
         "stats",
         "subscriptions",
         "tenants",
+        "usage",
+        "usagealerts",
         "users",
         "webhooksout",
     ];
@@ -12,6 +12,12 @@ macro_rules! include_proto_serde {
 
 pub mod meteroid {
     pub mod api {
+        pub mod accountingexports {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.accountingexports.v1");
+            }
+        }
+
         pub mod addons {
             pub mod v1 {
                 tonic::include_proto!("meteroid.api.addons.v1");
@@ -24,6 +30,12 @@ pub mod meteroid {
             }
         }
 
+        pub mod auditlogs {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.auditlogs.v1");
+            }
+        }
+
         pub mod adjustments {
             pub mod v1 {
                 include_proto_serde!("meteroid.api.adjustments.v1");
@@ -36,6 +48,18 @@ pub mod meteroid {
             }
         }
 
+        pub mod collectionstrategies {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.collectionstrategies.v1");
+            }
+        }
+
+        pub mod componentrules {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.componentrules.v1");
+            }
+        }
+
         pub mod customers {
             pub mod v1 {
                 include_proto_serde!("meteroid.api.customers.v1");
@@ -66,12 +90,29 @@ pub mod meteroid {
             }
         }
 
+        pub mod notificationbundles {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.notificationbundles.v1");
+            }
+        }
         pub mod organizations {
             pub mod v1 {
                 tonic::include_proto!("meteroid.api.organizations.v1");
             }
         }
 
+        pub mod partners {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.partners.v1");
+            }
+        }
+
+        pub mod paymentreminders {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.paymentreminders.v1");
+            }
+        }
+
         pub mod plans {
             pub mod v1 {
                 tonic::include_proto!("meteroid.api.plans.v1");
@@ -102,6 +143,30 @@ pub mod meteroid {
             }
         }
 
+        pub mod providers {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.providers.v1");
+            }
+        }
+
+        pub mod ratecards {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.ratecards.v1");
+            }
+        }
+
+        pub mod reports {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.reports.v1");
+            }
+        }
+
+        pub mod roles {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.roles.v1");
+            }
+        }
+
         pub mod subscriptions {
             pub mod v1 {
                 tonic::include_proto!("meteroid.api.subscriptions.v1");
@@ -120,6 +185,18 @@ pub mod meteroid {
             }
         }
 
+        pub mod usage {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.usage.v1");
+            }
+        }
+
+        pub mod usagealerts {
+            pub mod v1 {
+                tonic::include_proto!("meteroid.api.usagealerts.v1");
+            }
+        }
+
         pub mod users {
             pub mod v1 {
                 tonic::include_proto!("meteroid.api.users.v1");
@@ -139,11 +216,15 @@ pub mod meteroid {
                 include_proto_serde!("meteroid.api.shared.v1");
 
                 impl BillingPeriod {
-                    pub fn months_value(&self) -> u32 {
+                    /// None for a weekly period, or a custom one whose months are set alongside it
+                    pub fn months_value(&self) -> Option<u32> {
                         match self {
-                            BillingPeriod::Monthly => 1,
-                            BillingPeriod::Quarterly => 3,
-                            BillingPeriod::Annual => 12,
+                            BillingPeriod::Weekly | BillingPeriod::Custom => None,
+                            BillingPeriod::Monthly => Some(1),
+                            BillingPeriod::Bimonthly => Some(2),
+                            BillingPeriod::Quarterly => Some(3),
+                            BillingPeriod::Semiannual => Some(6),
+                            BillingPeriod::Annual => Some(12),
                         }
                     }
                 }
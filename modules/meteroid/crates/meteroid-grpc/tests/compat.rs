@@ -0,0 +1,153 @@
+//! Wire compatibility of the core API responses.
+//!
+//! Each response is compared to the bytes it encoded to when its golden file was recorded, so that a change
+//! renumbering or retyping one of its fields fails here before it reaches the integrators.
+//! When a response is extended on purpose, record the new bytes with `UPDATE_GOLDEN=1 cargo test -p meteroid-grpc`
+//! and review the diff of the golden file: the previously recorded bytes must still decode.
+
+use meteroid_grpc::meteroid::api::customers::v1 as customers;
+use meteroid_grpc::meteroid::api::invoices::v1 as invoices;
+use meteroid_grpc::meteroid::api::usage::v1 as usage;
+use prost::Message;
+use std::collections::HashMap;
+use std::fmt::Debug;
+use std::path::PathBuf;
+
+fn golden_path(name: &str) -> PathBuf {
+    PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("tests/golden")
+        .join(format!("{}.hex", name))
+}
+
+fn decode_hex(hex: &str) -> Vec<u8> {
+    let hex = hex.trim();
+    assert!(hex.len() % 2 == 0, "odd number of hex digits");
+
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).expect("invalid hex digit"))
+        .collect()
+}
+
+fn encode_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn assert_golden<M: Message + Default + PartialEq + Debug>(name: &str, message: &M) {
+    let path = golden_path(name);
+    let encoded = message.encode_to_vec();
+
+    if std::env::var("UPDATE_GOLDEN").is_ok() {
+        std::fs::write(&path, format!("{}\n", encode_hex(&encoded))).unwrap();
+        return;
+    }
+
+    let golden = decode_hex(
+        &std::fs::read_to_string(&path)
+            .unwrap_or_else(|e| panic!("missing golden file {}: {}", path.display(), e)),
+    );
+
+    // responses of older servers are still understood by this build
+    assert_eq!(
+        &M::decode(golden.as_slice()).unwrap(),
+        message,
+        "{} no longer decodes to the recorded response",
+        name
+    );
+
+    // and this build answers older clients with the same bytes
+    assert_eq!(
+        encode_hex(&encoded),
+        encode_hex(&golden),
+        "{} no longer encodes to the recorded bytes",
+        name
+    );
+
+    // fields added by newer servers are skipped by this build
+    let mut extended = golden.clone();
+    prost::encoding::string::encode(4999, &"added later".to_string(), &mut extended);
+    assert_eq!(
+        &M::decode(extended.as_slice()).unwrap(),
+        message,
+        "{} does not skip unknown fields",
+        name
+    );
+}
+
+#[test]
+fn get_customer_by_id_response() {
+    let response = customers::GetCustomerByIdResponse {
+        customer: Some(customers::Customer {
+            id: "018c2c82-3df1-7e84-9e05-6e141d0e751a".to_string(),
+            name: "Acme Inc".to_string(),
+            email: Some("billing@acme.com".to_string()),
+            balance_value_cents: 1250,
+            currency: "EUR".to_string(),
+            invoicing_entity_id: "018c2c82-3df1-7e84-9e05-6e141d0e7500".to_string(),
+            billing_email_status: customers::BillingEmailStatus::Verified as i32,
+            invoice_delivery_channel: Some(customers::InvoiceDeliveryChannel::Webhook as i32),
+            ..Default::default()
+        }),
+    };
+
+    assert_golden("get_customer_by_id_response", &response);
+}
+
+#[test]
+fn get_invoice_response() {
+    let response = invoices::GetInvoiceResponse {
+        invoice: Some(invoices::DetailedInvoice {
+            id: "018c2c83-0000-7000-8000-000000000042".to_string(),
+            status: invoices::InvoiceStatus::Finalized as i32,
+            created_at: "2024-11-01T00:00:00".to_string(),
+            tenant_id: "018c2c80-0000-7000-8000-000000000001".to_string(),
+            customer_id: "018c2c82-3df1-7e84-9e05-6e141d0e751a".to_string(),
+            currency: "EUR".to_string(),
+            invoice_number: "INV-0042".to_string(),
+            invoicing_provider: invoices::InvoicingProvider::Stripe as i32,
+            line_items: vec![invoices::LineItem {
+                id: "018c2c84-0000-7000-8000-000000000001".to_string(),
+                name: "Seats".to_string(),
+                total: 4500,
+                subtotal: 5000,
+                quantity: Some("10".to_string()),
+                unit_price: Some("5.00".to_string()),
+                start_date: "2024-10-01".to_string(),
+                end_date: "2024-11-01".to_string(),
+                ..Default::default()
+            }],
+            issued: true,
+            invoice_date: "2024-11-01".to_string(),
+            subtotal: 5000,
+            total: 4500,
+            amount_due: 4500,
+            net_terms: 30,
+            ..Default::default()
+        }),
+    };
+
+    assert_golden("get_invoice_response", &response);
+}
+
+#[test]
+fn query_usage_response() {
+    let response = usage::QueryUsageResponse {
+        metric_id: "018c2c85-0000-7000-8000-000000000007".to_string(),
+        window: usage::UsageWindow::Hour as i32,
+        points: vec![usage::UsagePoint {
+            window_start: Some(prost_types::Timestamp {
+                seconds: 1730419200,
+                nanos: 0,
+            }),
+            window_end: Some(prost_types::Timestamp {
+                seconds: 1730422800,
+                nanos: 0,
+            }),
+            value: "12.5".to_string(),
+            // a single dimension, the encoding order of map entries is not stable
+            dimensions: HashMap::from([("region".to_string(), "eu-west-1".to_string())]),
+        }],
+    };
+
+    assert_golden("query_usage_response", &response);
+}
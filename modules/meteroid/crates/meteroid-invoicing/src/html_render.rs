@@ -3,8 +3,10 @@ use crate::model::*;
 use chrono::prelude::*;
 use chrono::NaiveDate;
 use maud::{html, Markup, DOCTYPE};
-use rust_decimal::Decimal;
+use meteroid_store::domain::notification_bundles::render_template;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rusty_money::{iso, FormattableCurrency};
+use std::collections::HashMap;
 
 #[allow(clippy::all)]
 mod l10n {
@@ -25,6 +27,29 @@ mod l10n {
     }
 }
 
+/// The built-in messages of the language, unless the tenant overrides them
+struct Labels<'a> {
+    lang: &'static str,
+    overrides: &'a HashMap<String, String>,
+}
+
+impl Labels<'_> {
+    fn get<T: ToString>(&self, id: &str, built_in: impl FnOnce(&str) -> T) -> String {
+        match self.overrides.get(id) {
+            Some(template) => template.clone(),
+            None => built_in(self.lang).to_string(),
+        }
+    }
+
+    /// The override of a message with variables, if any
+    fn format(&self, id: &str, args: &[(&str, &str)]) -> Option<String> {
+        self.overrides.get(id).map(|template| {
+            let variables = args.iter().map(|(k, v)| (*k, v.to_string())).collect();
+            render_template(template, &variables)
+        })
+    }
+}
+
 static CSS: &str = include_str!("../assets/styles.css");
 
 pub fn render_invoice(invoice: &Invoice) -> Result<Markup, InvoicingError> {
@@ -38,13 +63,18 @@ pub fn render_invoice(invoice: &Invoice) -> Result<Markup, InvoicingError> {
         _ => "en-US",
     };
 
+    let labels = &Labels {
+        lang,
+        overrides: &invoice.labels,
+    };
+
     Ok(html! {
         (DOCTYPE)
         html lang="en" {
             head {
                 meta charset="UTF-8";
                 meta name="viewport" content="width=device-width, initial-scale=1.0";
-                title { (l10n::invoice::invoice_title(lang) )};
+                title { (labels.get("invoice-title", l10n::invoice::invoice_title))};
                 link href="https://fonts.googleapis.com/css2?family=Inter:wght@400;500;600;700&display=swap" rel="stylesheet"; // TODO include it in docker
                 style {
                     (CSS)
@@ -61,11 +91,11 @@ pub fn render_invoice(invoice: &Invoice) -> Result<Markup, InvoicingError> {
             }
             body class="" {
                 div class="container mx-auto px-2 py-4 bg-white text-sm" {
-                    (render_header(lang, &invoice.organization, &invoice.metadata)?)
-                    (render_billing_info(lang, &invoice.organization, &invoice.customer, &invoice.metadata)?)
-                    (render_invoice_lines(lang, &invoice.lines, &invoice.metadata.currency)?)
-                    (render_invoice_summary(lang, &invoice.metadata ))
-                    (render_legal_info(lang, &invoice.organization, &invoice.metadata)?)
+                    (render_header(labels, &invoice.organization, &invoice.metadata)?)
+                    (render_billing_info(labels, &invoice.organization, &invoice.customer, &invoice.metadata)?)
+                    (render_invoice_lines(labels, &invoice.lines, &invoice.metadata.currency)?)
+                    (render_invoice_summary(labels, &invoice.metadata ))
+                    (render_legal_info(labels, &invoice.organization, &invoice.metadata)?)
                 }
             }
         }
@@ -73,24 +103,25 @@ pub fn render_invoice(invoice: &Invoice) -> Result<Markup, InvoicingError> {
 }
 
 fn render_header(
-    lang: &str,
+    labels: &Labels,
     organization: &Organization,
     invoice: &InvoiceMetadata,
 ) -> Result<Markup, InvoicingError> {
     Ok(html! {
         div class="px-2 flex justify-between items-center border-b pb-4" {
-            h1 class="text-xl font-semibold text-gray-800" { (l10n::invoice::invoice_number(lang, &invoice.number)
+            h1 class="text-xl font-semibold text-gray-800" { (labels.format("invoice-number", &[("invoice_number", &invoice.number)])
+                        .map_or_else(|| l10n::invoice::invoice_number(labels.lang, &invoice.number), Ok)
                         .map_err(|_| InvoicingError::I18nError(format!("Failed to localise invoice number for value: {}", &invoice.number)))? )
             }
              @if let Some(logo_url) = &organization.logo_url {
-                img src=(logo_url) alt=(l10n::invoice::company_logo_alt(lang));
+                img src=(logo_url) alt=(labels.get("company-logo-alt", l10n::invoice::company_logo_alt));
             }
         }
     })
 }
 
 fn render_billing_info(
-    lang: &str,
+    labels: &Labels,
     organization: &Organization,
     customer: &Customer,
     invoice: &InvoiceMetadata,
@@ -98,25 +129,25 @@ fn render_billing_info(
     Ok(html! {
         div class="grid grid-cols-3 mb-8" {
             div class="flex flex-col p-4 border-b border-r border-gray-200" {
-                h2 class="text-md mb-2 text-gray-700" { (l10n::invoice::bill_from(lang)) }
-                (render_address( organization, lang))
+                h2 class="text-md mb-2 text-gray-700" { (labels.get("bill-from", l10n::invoice::bill_from)) }
+                (render_address( organization, labels.lang))
             }
             div class="flex flex-col p-4 border-b border-gray-200" {
-                h2 class="text-md mb-2 text-gray-700" { (l10n::invoice::bill_to(lang)) }
-                 (render_address( customer, lang))
+                h2 class="text-md mb-2 text-gray-700" { (labels.get("bill-to", l10n::invoice::bill_to)) }
+                 (render_address( customer, labels.lang))
             }
             div class="p-4 border-b border-l border-gray-200" {
-                h2 class="text-right text-md mb-2 text-gray-700" { (l10n::invoice::amount_due(lang)) }
+                h2 class="text-right text-md mb-2 text-gray-700" { (labels.get("amount-due", l10n::invoice::amount_due)) }
                 p class="text-right mb-2 text-xl font-bold text-green-600" { (format_currency_minor(invoice.total_amount, &invoice.currency)) }
 
                 div class="grid grid-cols-2 text-xs " {
                   div {
-                    p class="text-gray-600" { (l10n::invoice::issue_date(lang)) }
-                    p class="font-medium" { (format_date(lang, &invoice.issue_date)?) }
+                    p class="text-gray-600" { (labels.get("issue-date", l10n::invoice::issue_date)) }
+                    p class="font-medium" { (format_date(labels.lang, &invoice.issue_date)?) }
                   }
                   div{
-                    p class="text-gray-600" { (l10n::invoice::due_date(lang)) }
-                    p class="font-medium" { (format_date(lang, &invoice.due_date)?) }
+                    p class="text-gray-600" { (labels.get("due-date", l10n::invoice::due_date)) }
+                    p class="font-medium" { (format_date(labels.lang, &invoice.due_date)?) }
                   }
                 }
             }
@@ -128,21 +159,21 @@ fn render_billing_info(
 }
 
 fn render_invoice_lines(
-    lang: &str,
+    labels: &Labels,
     lines: &[InvoiceLine],
     currency: &iso::Currency,
 ) -> Result<Markup, InvoicingError> {
     Ok(html! {
         div class="mb-8" {
-            h2 class="px-2 text-md font-semibold mb-4 text-gray-700 uppercase" { (l10n::invoice::invoice_lines(lang)) }
+            h2 class="px-2 text-md font-semibold mb-4 text-gray-700 uppercase" { (labels.get("invoice-lines", l10n::invoice::invoice_lines)) }
             table class="w-full border-collapse" {
                 thead {
                     tr class="text-gray-500 text-sm" {
-                        th class="p-2 text-left" { (l10n::invoice::description(lang)) }
-                        th class="p-2 text-right" { (l10n::invoice::quantity(lang)) }
-                        th class="p-2 text-right" { (l10n::invoice::unit_price(lang)) }
-                        th class="p-2 text-right" { (l10n::invoice::tax_rate(lang)) }
-                        th class="p-2 text-right" { (l10n::invoice::amount(lang)) }
+                        th class="p-2 text-left" { (labels.get("description", l10n::invoice::description)) }
+                        th class="p-2 text-right" { (labels.get("quantity", l10n::invoice::quantity)) }
+                        th class="p-2 text-right" { (labels.get("unit-price", l10n::invoice::unit_price)) }
+                        th class="p-2 text-right" { (labels.get("tax-rate", l10n::invoice::tax_rate)) }
+                        th class="p-2 text-right" { (labels.get("amount", l10n::invoice::amount)) }
                     }
                 }
                 tbody {
@@ -154,17 +185,17 @@ fn render_invoice_lines(
                                     div class="text-sm text-gray-500" { (desc) }
                                 }
                                 div class="text-sm text-gray-500" {
-                                    (format!("{} → {}", format_date_short(lang, &line.start_date)?, format_date_short(lang, &line.end_date)?))
+                                    (format!("{} → {}", format_date_short(labels.lang, &line.start_date)?, format_date_short(labels.lang, &line.end_date)?))
                                 }
                             }
                             td class="p-2 text-right text-gray-600" {
                                  @if let Some(quantity) = line.quantity {
-                                    (format_quantity(quantity))
+                                    (format_quantity(quantity, line.quantity_precision))
                                 }
                             }
                             td class="p-2 text-right text-gray-600" {
                                  @if let Some(unit_price) = line.unit_price {
-                                    (format_currency_dec(unit_price, currency))
+                                    (format_unit_price(unit_price, currency))
                                 }
                             }
                             td class="p-2 text-right text-gray-600" {
@@ -183,8 +214,8 @@ fn render_invoice_lines(
                                         @for sub_line in &line.sub_lines {
                                             tr {
                                                 td class="p-1 text-gray-600" { (sub_line.name) } // TODO i18n the name (rebuild it from attributes, similar to how we initially build it)
-                                                td class="p-1 text-right text-gray-600" { (format_quantity(sub_line.quantity)) }
-                                                td class="p-1 text-right text-gray-600" { (format_currency_dec(sub_line.unit_price, currency)) }
+                                                td class="p-1 text-right text-gray-600" { (format_quantity(sub_line.quantity, line.quantity_precision)) }
+                                                td class="p-1 text-right text-gray-600" { (format_unit_price(sub_line.unit_price, currency)) }
                                                 td class="p-1 text-right text-gray-700" { (format_currency_minor(sub_line.total, currency)) }
                                             }
                                         }
@@ -199,22 +230,22 @@ fn render_invoice_lines(
     })
 }
 
-fn render_invoice_summary(lang: &str, invoice: &InvoiceMetadata) -> Markup {
+fn render_invoice_summary(labels: &Labels, invoice: &InvoiceMetadata) -> Markup {
     html! {
         div class="grid grid-cols-2 border-b border-gray-200 mb-8" {
             div {}
             div class="mb-4 rounded-lg p-4 bg-gray-50" {
                 table class="w-full" {
                     tr class="font-semibold"  {
-                        td class="p-2" { (l10n::invoice::subtotal(lang)) }
+                        td class="p-2" { (labels.get("subtotal", l10n::invoice::subtotal)) }
                         td class="p-2 text-right font-medium" { (format_currency_minor(invoice.subtotal, &invoice.currency)) }
                     }
                     tr {
-                        td class="p-2 text-gray-600" { (l10n::invoice::tax(lang)) " " (format_percentage_minor(invoice.tax_rate)) }
+                        td class="p-2 text-gray-600" { (labels.get("tax", l10n::invoice::tax)) " " (format_percentage_minor(invoice.tax_rate)) }
                         td class="p-2 text-right font-medium text-gray-800" { (format_currency_minor(invoice.tax_amount, &invoice.currency)) }
                     }
                     tr class="border-t border-gray-200" {
-                        td class="p-2 text-lg font-semibold text-gray-700" { (l10n::invoice::total_due(lang)) }
+                        td class="p-2 text-lg font-semibold text-gray-700" { (labels.get("total-due", l10n::invoice::total_due)) }
                         td class="p-2 text-right text-lg font-bold text-green-600" { (format_currency_minor(invoice.total_amount, &invoice.currency)) }
                     }
                 }
@@ -224,13 +255,13 @@ fn render_invoice_summary(lang: &str, invoice: &InvoiceMetadata) -> Markup {
 }
 
 fn render_legal_info(
-    lang: &str,
+    labels: &Labels,
     organization: &Organization,
     invoice: &InvoiceMetadata,
 ) -> Result<Markup, InvoicingError> {
     let exchange_rate_text = match organization.exchange_rate {
         Some(rate) => {
-            let date = format_date(lang, &invoice.issue_date)
+            let date = format_date(labels.lang, &invoice.issue_date)
                 .map_err(|_| InvoicingError::I18nError("Failed to format date".to_string()))?;
             let equality = format!(
                 "1 {} = {} {}",
@@ -243,18 +274,35 @@ fn render_legal_info(
                 &organization.accounting_currency,
             );
 
-            l10n::invoice::exchange_rate_info(lang, &equality, &amount_converted, &date).ok()
+            labels
+                .format(
+                    "exchange-rate-info",
+                    &[
+                        ("equality", &equality),
+                        ("amount_converted", &amount_converted),
+                        ("date", &date),
+                    ],
+                )
+                .or_else(|| {
+                    l10n::invoice::exchange_rate_info(
+                        labels.lang,
+                        &equality,
+                        &amount_converted,
+                        &date,
+                    )
+                    .ok()
+                })
         }
         None => None,
     };
 
     Ok(html! {
         div class="px-2 mb-8 text-gray-700" {
-            h2 class="text-md font-semibold mb-4 text-gray-700 uppercase" { (l10n::invoice::legal_info(lang)) }
+            h2 class="text-md font-semibold mb-4 text-gray-700 uppercase" { (labels.get("legal-info", l10n::invoice::legal_info)) }
 
             // TODO need proper tax info engine for other EU countries
             @if invoice.tax_rate == 0 {
-                p { (l10n::invoice::vat_exempt_legal(lang)) }
+                p { (labels.get("vat-exempt-legal", l10n::invoice::vat_exempt_legal)) }
             }
             @if let Some(footer_info) = &organization.footer_info {
                 p { (footer_info) }
@@ -281,8 +329,29 @@ fn format_currency_minor(amount: i64, currency: &iso::Currency) -> String {
     rusty_money::Money::from_minor(amount, currency).to_string()
 }
 
-fn format_quantity(quantity: Decimal) -> String {
-    format!("{:.2}", quantity)
+/// The currency format rounds to the minor unit, that would hide the rates below it (ex: $0.00002 per request)
+fn format_unit_price(amount: Decimal, currency: &iso::Currency) -> String {
+    let amount = amount.normalize();
+    if amount.scale() <= currency.exponent() {
+        return format_currency_dec(amount, currency);
+    }
+    if currency.symbol_first() {
+        format!("{}{}", currency.symbol(), amount)
+    } else {
+        format!("{} {}", amount, currency.symbol())
+    }
+}
+
+/// Without precision, the quantity is displayed with all its significant decimals
+fn format_quantity(quantity: Decimal, precision: Option<u32>) -> String {
+    match precision {
+        Some(precision) => format!(
+            "{:.*}",
+            precision as usize,
+            quantity.round_dp_with_strategy(precision, RoundingStrategy::MidpointAwayFromZero)
+        ),
+        None => quantity.normalize().to_string(),
+    }
 }
 
 fn format_percentage_dec(rate: Decimal) -> String {
@@ -346,8 +415,14 @@ where
              p { (country) }
         }
         // p { (entity.email()) }
-        @if let Some(tax_id) = entity.tax_id() {
-            p { "Tax ID: " (tax_id) }
+        @if !entity.tax_registrations().is_empty() {
+            @for registration in entity.tax_registrations() {
+                p { (registration.label) ": " (registration.number) }
+            }
+        } @else {
+            @if let Some(tax_id) = entity.tax_id() {
+                p { "Tax ID: " (tax_id) }
+            }
         }
         }
 
@@ -365,6 +440,7 @@ trait HasAddress {
     fn country(&self) -> Option<&str>;
     // fn email(&self) -> &str;
     fn tax_id(&self) -> Option<&str>;
+    fn tax_registrations(&self) -> &[TaxRegistration];
 }
 
 impl HasAddress for Organization {
@@ -395,6 +471,9 @@ impl HasAddress for Organization {
     fn tax_id(&self) -> Option<&str> {
         self.tax_id.as_deref()
     }
+    fn tax_registrations(&self) -> &[TaxRegistration] {
+        &self.tax_registrations
+    }
 }
 
 impl HasAddress for Customer {
@@ -425,4 +504,31 @@ impl HasAddress for Customer {
     fn tax_id(&self) -> Option<&str> {
         self.tax_id.as_deref()
     }
+    fn tax_registrations(&self) -> &[TaxRegistration] {
+        &[]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    #[test]
+    fn test_format_quantity() {
+        assert_eq!(format_quantity(dec!(0.000001), None), "0.000001");
+        assert_eq!(format_quantity(dec!(12.500), None), "12.5");
+        assert_eq!(format_quantity(dec!(0.0000125), Some(5)), "0.00001");
+        assert_eq!(format_quantity(dec!(0.000015), Some(5)), "0.00002");
+        assert_eq!(format_quantity(dec!(3), Some(2)), "3.00");
+    }
+
+    #[test]
+    fn test_format_unit_price() {
+        assert_eq!(format_unit_price(dec!(0.00002), &iso::USD), "$0.00002");
+        assert_eq!(
+            format_unit_price(dec!(0.5), &iso::USD),
+            format_currency_dec(dec!(0.5), &iso::USD)
+        );
+    }
 }
@@ -1,8 +1,11 @@
 use rust_decimal::Decimal;
 use rusty_money::iso;
+use std::collections::HashMap;
 
 pub struct Invoice {
     pub lang: String,
+    /// messages of the tenant replacing the built-in ones of the language, by message id
+    pub labels: HashMap<String, String>,
     pub organization: Organization,
     pub customer: Customer,
     pub metadata: InvoiceMetadata,
@@ -26,12 +29,20 @@ pub struct Organization {
     pub address: Address,
     pub email: Option<String>,
     pub tax_id: Option<String>,
+    /// displayed instead of the tax id when not empty
+    pub tax_registrations: Vec<TaxRegistration>,
     pub footer_info: Option<String>,
     pub footer_legal: Option<String>,
     pub accounting_currency: iso::Currency,
     pub exchange_rate: Option<Decimal>,
 }
 
+pub struct TaxRegistration {
+    /// ex: VAT, GST
+    pub label: String,
+    pub number: String,
+}
+
 pub struct Customer {
     pub name: String,
     pub legal_number: Option<String>,
@@ -64,6 +75,8 @@ pub struct InvoiceLine {
     pub start_date: chrono::NaiveDate,
     pub end_date: chrono::NaiveDate,
     pub sub_lines: Vec<InvoiceSubLine>,
+    /// decimal places of the quantities of the line and its sub lines. None displays all their decimals
+    pub quantity_precision: Option<u32>,
 }
 
 pub struct InvoiceSubLine {
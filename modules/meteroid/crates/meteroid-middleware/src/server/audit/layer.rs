@@ -0,0 +1,149 @@
+use hyper::{Request, Response};
+
+use common_grpc::middleware::common::filters::Filter;
+use common_grpc::middleware::server::AuthorizedState;
+use common_grpc::GrpcServiceMethod;
+use meteroid_store::domain::audit_logs::{AuditEntity, AuditLogNew};
+use meteroid_store::repositories::audit_logs::AuditLogInterface;
+use meteroid_store::Store;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::Service;
+use tower_layer::Layer;
+use tracing::log;
+
+use crate::server::rpc::is_mutating;
+
+const GRPC_STATUS_HEADER: &str = "grpc-status";
+
+#[derive(Clone)]
+pub struct AuditMiddleware<S> {
+    inner: S,
+    filter: Option<Filter>,
+    store: Store,
+}
+
+#[derive(Clone)]
+pub struct AuditLayer {
+    store: Store,
+    filter: Option<Filter>,
+}
+
+impl AuditLayer {
+    #[allow(clippy::new_without_default)]
+    pub fn new(store: Store) -> Self {
+        AuditLayer {
+            store,
+            filter: None,
+        }
+    }
+
+    #[must_use]
+    pub fn filter(mut self, filter: Filter) -> Self {
+        self.filter = Some(filter);
+        self
+    }
+}
+
+impl<S> Layer<S> for AuditLayer {
+    type Service = AuditMiddleware<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        AuditMiddleware {
+            inner,
+            filter: self.filter,
+            store: self.store.clone(),
+        }
+    }
+}
+
+type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
+
+impl<S, ReqBody, ResBody> Service<Request<ReqBody>> for AuditMiddleware<S>
+where
+    S: Service<Request<ReqBody>, Response = Response<ResBody>, Error = BoxError>
+        + Clone
+        + Send
+        + 'static,
+    S::Future: Send + 'static,
+    ReqBody: Send + 'static,
+    ResBody: Send + 'static,
+{
+    type Response = S::Response;
+    type Error = BoxError;
+    type Future = Pin<Box<dyn Future<Output = Result<Self::Response, Self::Error>> + Send>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request<ReqBody>) -> Self::Future {
+        // This is necessary because tonic internally uses `tower::buffer::Buffer`.
+        // See https://github.com/tower-rs/tower/issues/547#issuecomment-767629149
+        // for details on why this is necessary
+        let clone = self.inner.clone();
+        let mut inner = std::mem::replace(&mut self.inner, clone);
+
+        let sm = GrpcServiceMethod::extract(request.uri());
+
+        // the anonymous calls have no authorized state, and are not audited
+        let authorized_state = request.extensions().get::<AuthorizedState>().cloned();
+
+        let audited = self.filter.map_or(true, |f| f(request.uri().path())) && is_mutating(&sm);
+
+        let authorized_state = match authorized_state {
+            Some(state) if audited => state,
+            _ => return Box::pin(inner.call(request)),
+        };
+
+        let store = self.store.clone();
+
+        let future = async move {
+            let response = inner.call(request).await?;
+
+            // only set for the failed calls, the status of the successful ones is in the trailers
+            let status_code = response
+                .headers()
+                .get(GRPC_STATUS_HEADER)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.parse::<i32>().ok())
+                .unwrap_or(0);
+
+            let entity = response.extensions().get::<AuditEntity>().cloned();
+
+            let (organization_id, tenant_id, actor_id) = match authorized_state {
+                AuthorizedState::Tenant {
+                    actor_id,
+                    tenant_id,
+                    organization_id,
+                } => (Some(organization_id), Some(tenant_id), actor_id),
+                AuthorizedState::Organization {
+                    actor_id,
+                    organization_id,
+                } => (Some(organization_id), None, actor_id),
+                AuthorizedState::User { user_id } => (None, None, user_id),
+            };
+
+            let audit_log = AuditLogNew {
+                organization_id,
+                tenant_id,
+                actor_id,
+                rpc: format!("{}/{}", sm.service, sm.method),
+                entity_id: entity.as_ref().map(|e| e.entity_id.clone()),
+                diff: entity.and_then(|e| e.diff),
+                status_code,
+            };
+
+            tokio::spawn(async move {
+                if let Err(e) = store.insert_audit_log(audit_log).await {
+                    log::error!("Failed to insert audit log: {:?}", e);
+                }
+            });
+
+            Ok(response)
+        };
+
+        Box::pin(future)
+    }
+}
@@ -0,0 +1,10 @@
+use meteroid_store::Store;
+
+pub use layer::AuditLayer;
+pub use layer::AuditMiddleware;
+
+mod layer;
+
+pub fn create(store: Store) -> AuditLayer {
+    AuditLayer::new(store)
+}
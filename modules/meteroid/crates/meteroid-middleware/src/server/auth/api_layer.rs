@@ -6,16 +6,15 @@ use crate::server::auth::strategies::api_key_strategy::validate_api_key;
 use crate::server::auth::strategies::jwt_strategy::{authorize_user, validate_jwt};
 use common_grpc::middleware::common::auth::{API_KEY_HEADER, BEARER_AUTH_HEADER};
 use common_grpc::middleware::common::filters::Filter;
-use common_grpc::middleware::server::auth::AuthenticatedState;
+use common_grpc::middleware::server::auth::{AuthenticatedState, TenantRole};
 use common_grpc::middleware::server::AuthorizedState;
 use common_grpc::GrpcServiceMethod;
 use futures_util::TryFutureExt;
-use http::StatusCode;
 use meteroid_store::Store;
 use std::future::Future;
 use std::pin::Pin;
 use std::task::{Context, Poll};
-use tonic::body::{empty_body, BoxBody};
+use tonic::body::BoxBody;
 use tonic::Status;
 use tower::Service;
 use tower_layer::Layer;
@@ -69,8 +68,9 @@ impl<S> Layer<S> for ApiAuthLayer {
 type BoxError = Box<dyn std::error::Error + Send + Sync + 'static>;
 
 // services that don't require authentication
-const ANONYMOUS_SERVICES: [&str; 3] = [
+const ANONYMOUS_SERVICES: [&str; 4] = [
     "/meteroid.api.instance.v1.InstanceService/GetInstance",
+    "/meteroid.api.instance.v1.InstanceService/ListErrorCodes",
     "/meteroid.api.users.v1.UsersService/Register",
     "/meteroid.api.users.v1.UsersService/Login",
 ];
@@ -123,29 +123,35 @@ where
         let jwt_secret = self.jwt_secret.clone();
 
         let future = async move {
-            let authenticated_state = if metadata.contains_key(API_KEY_HEADER) {
+            let (authenticated_state, partner_scope) = if metadata.contains_key(API_KEY_HEADER) {
                 validate_api_key(&metadata, &store, &sm)
                     .await
                     .map_err(|e| BoxError::from(e) as BoxError)
             } else if metadata.contains_key(BEARER_AUTH_HEADER) {
-                validate_jwt(&metadata, jwt_secret).map_err(|e| BoxError::from(e) as BoxError)
+                validate_jwt(&metadata, jwt_secret)
+                    .map(|state| (state, None))
+                    .map_err(|e| BoxError::from(e) as BoxError)
             } else {
                 Err(Box::new(Status::unauthenticated("No authentication provided")) as BoxError)
             }?;
 
-            let authorized_state = match authenticated_state {
+            // api keys have full access to their tenant
+            let (authorized_state, tenant_role) = match authenticated_state {
                 AuthenticatedState::ApiKey {
                     tenant_id,
                     id,
                     organization_id,
-                } => Ok(AuthorizedState::Tenant {
-                    tenant_id,
-                    organization_id,
-                    actor_id: id,
-                }),
+                } => Ok((
+                    AuthorizedState::Tenant {
+                        tenant_id,
+                        organization_id,
+                        actor_id: id,
+                    },
+                    Some(TenantRole::Admin),
+                )),
                 AuthenticatedState::User { id } => {
                     if UNAUTHORIZED_SERVICES.contains(&request.uri().path()) {
-                        Ok(AuthorizedState::User { user_id: id })
+                        Ok((AuthorizedState::User { user_id: id }, None))
                     } else {
                         authorize_user(&metadata, id, store, sm)
                             .await
@@ -155,18 +161,25 @@ where
             }?;
 
             request.extensions_mut().insert(authorized_state);
+            if let Some(tenant_role) = tenant_role {
+                request.extensions_mut().insert(tenant_role);
+            }
+            if let Some(partner_scope) = partner_scope {
+                request.extensions_mut().insert(partner_scope);
+            }
 
             inner.call(request).await.map_err(Into::into)
         };
 
-        // if the future is an error , we recover by providing an empty REsponse
+        // if the future is an error, we recover with a trailers-only grpc response,
+        // so that the status reaches the grpc-web (browser) clients as well
         let future = future.or_else(|e: BoxError| async move {
             log::warn!("Error in auth middleware: {}", e);
-            let response = Response::builder()
-                .status(StatusCode::UNAUTHORIZED)
-                .body(empty_body())
-                .unwrap();
-            Ok(response)
+            match e.downcast::<Status>() {
+                // keeps the code of the status (permission denied, invalid argument ..)
+                Ok(status) => Ok(status.into_http()),
+                Err(e) => Ok(Status::unauthenticated(e.to_string()).into_http()),
+            }
         });
 
         Box::pin(future)
@@ -3,10 +3,15 @@ use secrecy::SecretString;
 pub use api_layer::ApiAuthLayer;
 pub use api_layer::ApiAuthMiddleware;
 use meteroid_store::Store;
+use uuid::Uuid;
 
 mod api_layer;
 pub mod strategies;
 
+/// The partner a partner-scoped api key is restricted to, in the extensions of its requests
+#[derive(Clone, Copy, Debug)]
+pub struct PartnerScope(pub Uuid);
+
 pub fn create(jwt_secret: SecretString, store: Store) -> ApiAuthLayer {
     ApiAuthLayer::new(jwt_secret, store)
 }
@@ -1,22 +1,62 @@
+use std::collections::HashMap;
+use std::str::FromStr;
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
 use cached::proc_macro::cached;
+use chrono::{NaiveDate, NaiveDateTime};
 use common_grpc::middleware::common::auth::API_KEY_HEADER;
+use common_grpc::middleware::server::auth::api_token_scopes::{
+    scope_resource, scopes_allow, ApiTokenScope,
+};
 use common_grpc::middleware::server::auth::api_token_validator::ApiTokenValidator;
 use common_grpc::middleware::server::auth::AuthenticatedState;
 use common_grpc::GrpcServiceMethod;
 use http::HeaderMap;
+use meteroid_store::domain::api_tokens::ApiTokenUsageRecord;
 use meteroid_store::repositories::api_tokens::ApiTokensInterface;
 use meteroid_store::Store;
 use tonic::Status;
+use tracing::log;
 use uuid::Uuid;
 
-const FORBIDDEN_SERVICES: [&str; 5] = [
+use crate::server::auth::PartnerScope;
+use crate::server::rpc::is_mutating;
+
+const FORBIDDEN_SERVICES: [&str; 7] = [
     "meteroid.api.organizations.v1.OrganizationsService",
     "meteroid.api.users.v1.UsersService",
     "meteroid.api.apitokens.v1.ApiTokensService",
     "meteroid.api.tenants.v1.TenantsService",
     "meteroid.api.instance.v1.InstanceService",
+    "meteroid.api.providers.v1.ProvidersService",
+    "meteroid.api.roles.v1.RolesService",
 ];
 
+// the only method a partner-scoped api key can call
+const PARTNER_SERVICE: &str = "meteroid.api.partners.v1.PartnersService";
+const PARTNER_METHOD: &str = "GetPartnerPayoutReport";
+
+// requests are counted in memory and written in batches, to keep the database out of the request path
+const USAGE_FLUSH_INTERVAL: Duration = Duration::from_secs(30);
+
+static PENDING_USAGE: OnceLock<Mutex<PendingUsage>> = OnceLock::new();
+
+struct PendingUsage {
+    requests: HashMap<(Uuid, NaiveDate), (i64, NaiveDateTime)>,
+    flushed_at: Instant,
+}
+
+#[derive(Clone)]
+struct ResolvedApiKey {
+    organization_id: Uuid,
+    tenant_id: Uuid,
+    partner_id: Option<Uuid>,
+    scopes: Vec<ApiTokenScope>,
+    /// the expiry of the key, or the end of its grace period once rotated
+    valid_until: Option<NaiveDateTime>,
+}
+
 #[cached(
     result = true,
     size = 100,
@@ -28,47 +68,178 @@ async fn validate_api_token_by_id_cached(
     store: &Store,
     validator: &ApiTokenValidator,
     api_key_id: &Uuid,
-) -> Result<(Uuid, Uuid), Status> {
+) -> Result<ResolvedApiKey, Status> {
     let res = store
         .get_api_token_by_id_for_validation(api_key_id)
         .await
-        .map_err(|_| Status::permission_denied("Failed to retrieve api key"))?;
+        .map_err(|_| Status::unauthenticated("Failed to retrieve api key"))?;
 
     validator
         .validate_hash(&res.hash)
-        .map_err(|_| Status::permission_denied("Unauthorized"))?;
+        .map_err(|_| Status::unauthenticated("Unauthorized"))?;
 
-    Ok((res.organization_id, res.tenant_id))
+    let scopes = res
+        .scopes
+        .iter()
+        .map(|scope| ApiTokenScope::from_str(scope))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|_| Status::permission_denied("Invalid api key scopes"))?;
+
+    Ok(ResolvedApiKey {
+        organization_id: res.organization_id,
+        tenant_id: res.tenant_id,
+        partner_id: res.partner_id,
+        scopes,
+        valid_until: res.valid_until(),
+    })
 }
 
+/// Returns the state of the API key, and the partner it is restricted to if any
 pub async fn validate_api_key(
     header_map: &HeaderMap,
     store: &Store,
     gm: &GrpcServiceMethod,
-) -> Result<AuthenticatedState, Status> {
+) -> Result<(AuthenticatedState, Option<PartnerScope>), Status> {
     if FORBIDDEN_SERVICES.contains(&gm.service.as_str()) {
         return Err(Status::permission_denied("Forbidden"));
     }
 
+    let (state, resolved) = resolve_api_key(header_map, store).await?;
+
+    let resource = scope_resource(gm).unwrap_or_default();
+    if !scopes_allow(&resolved.scopes, resource, is_mutating(gm)) {
+        return Err(Status::permission_denied(
+            "The scopes of this API key do not allow this call",
+        ));
+    }
+
+    let partner_scope = resolved.partner_id.map(PartnerScope);
+
+    if partner_scope.is_some() && (gm.service != PARTNER_SERVICE || gm.method != PARTNER_METHOD) {
+        return Err(Status::permission_denied(
+            "This API key is restricted to the partner payout report",
+        ));
+    }
+
+    Ok((state, partner_scope))
+}
+
+/// Resolves the tenant of the API key from the headers, and checks that its scopes allow the access to the resource.
+/// Partner-scoped keys are rejected
+pub async fn authenticate_api_key(
+    header_map: &HeaderMap,
+    store: &Store,
+    resource: &str,
+    write: bool,
+) -> Result<AuthenticatedState, Status> {
+    let (state, resolved) = resolve_api_key(header_map, store).await?;
+
+    if resolved.partner_id.is_some() {
+        return Err(Status::permission_denied(
+            "This API key is restricted to the partner payout report",
+        ));
+    }
+
+    if !scopes_allow(&resolved.scopes, resource, write) {
+        return Err(Status::permission_denied(
+            "The scopes of this API key do not allow this call",
+        ));
+    }
+
+    Ok(state)
+}
+
+async fn resolve_api_key(
+    header_map: &HeaderMap,
+    store: &Store,
+) -> Result<(AuthenticatedState, ResolvedApiKey), Status> {
     let api_key = header_map
         .get(API_KEY_HEADER)
         .ok_or(Status::unauthenticated("Missing API key"))?
         .to_str()
-        .map_err(|_| Status::permission_denied("Invalid API key"))?;
+        .map_err(|_| Status::unauthenticated("Invalid API key"))?;
 
     let validator = ApiTokenValidator::parse_api_key(api_key)
-        .map_err(|_| Status::permission_denied("Invalid API key format."))?;
+        .map_err(|_| Status::unauthenticated("Invalid API key format."))?;
 
     let id = validator.extract_identifier().map_err(|_| {
-        Status::permission_denied("Invalid API key format. Failed to extract identifier")
+        Status::unauthenticated("Invalid API key format. Failed to extract identifier")
     })?;
 
-    let (organization_id, tenant_id) =
-        validate_api_token_by_id_cached(store, &validator, &id).await?;
+    let resolved = validate_api_token_by_id_cached(store, &validator, &id).await?;
 
-    Ok(AuthenticatedState::ApiKey {
-        id,
-        tenant_id,
-        organization_id,
-    })
+    // the key may have expired, or its rotation grace period ended, since it was cached
+    if resolved
+        .valid_until
+        .is_some_and(|valid_until| valid_until <= chrono::Utc::now().naive_utc())
+    {
+        return Err(Status::unauthenticated("API key expired"));
+    }
+
+    record_usage(store, id);
+
+    Ok((
+        AuthenticatedState::ApiKey {
+            id,
+            tenant_id: resolved.tenant_id,
+            organization_id: resolved.organization_id,
+        },
+        resolved,
+    ))
+}
+
+pub async fn invalidate_api_key_cache(api_key_id: &Uuid) {
+    {
+        use cached::Cached;
+        let mut cache = self::VALIDATE_API_TOKEN_BY_ID_CACHED.lock().await;
+        cache.cache_remove(api_key_id);
+    }
+}
+
+/// Counts the request, and writes the pending counts in the background once per flush interval
+fn record_usage(store: &Store, api_key_id: Uuid) {
+    let now = chrono::Utc::now().naive_utc();
+
+    let records = {
+        let mut pending = PENDING_USAGE
+            .get_or_init(|| {
+                Mutex::new(PendingUsage {
+                    requests: HashMap::new(),
+                    flushed_at: Instant::now(),
+                })
+            })
+            .lock()
+            .unwrap_or_else(|e| e.into_inner());
+
+        let entry = pending
+            .requests
+            .entry((api_key_id, now.date()))
+            .or_insert((0, now));
+        entry.0 += 1;
+        entry.1 = now;
+
+        if pending.flushed_at.elapsed() < USAGE_FLUSH_INTERVAL {
+            return;
+        }
+        pending.flushed_at = Instant::now();
+
+        std::mem::take(&mut pending.requests)
+            .into_iter()
+            .map(
+                |((api_token_id, day), (request_count, last_used_at))| ApiTokenUsageRecord {
+                    api_token_id,
+                    day,
+                    request_count,
+                    last_used_at,
+                },
+            )
+            .collect::<Vec<_>>()
+    };
+
+    let store = store.clone();
+    tokio::spawn(async move {
+        if let Err(e) = store.record_api_token_usage(records).await {
+            log::warn!("Failed to record api token usage: {:?}", e);
+        }
+    });
 }
@@ -7,14 +7,17 @@ use uuid::Uuid;
 
 use common_grpc::middleware::common::auth::{BEARER_AUTH_HEADER, INTERNAL_API_CONTEXT_HEADER};
 use common_grpc::middleware::common::jwt::Claims;
-use common_grpc::middleware::server::auth::AuthenticatedState;
+use common_grpc::middleware::server::auth::{AuthenticatedState, TenantRole};
 use common_grpc::middleware::server::AuthorizedState;
 use common_grpc::GrpcServiceMethod;
-use meteroid_store::domain::enums::OrganizationUserRole;
+use meteroid_store::domain::enums::{OrganizationUserRole, TenantMemberRoleEnum};
+use meteroid_store::repositories::tenant_member_roles::TenantMemberRolesInterface;
 use meteroid_store::repositories::users::UserInterface;
 use meteroid_store::repositories::{OrganizationsInterface, TenantInterface};
 use meteroid_store::Store;
 
+use crate::server::rpc::is_mutating;
+
 pub fn validate_jwt(
     header_map: &HeaderMap,
     jwt_secret: SecretString,
@@ -23,7 +26,7 @@ pub fn validate_jwt(
         .get(BEARER_AUTH_HEADER)
         .ok_or(Status::unauthenticated("Missing JWT"))?
         .to_str()
-        .map_err(|_| Status::permission_denied("Invalid JWT"))?;
+        .map_err(|_| Status::unauthenticated("Invalid JWT"))?;
 
     let token = header
         .strip_prefix("Bearer ")
@@ -32,20 +35,36 @@ pub fn validate_jwt(
     let decoding_key = DecodingKey::from_secret(jwt_secret.expose_secret().as_bytes());
     let decoded =
         jsonwebtoken::decode::<Claims>(token, &decoding_key, &jsonwebtoken::Validation::default())
-            .map_err(|_| Status::permission_denied("Invalid JWT"))?;
+            .map_err(|_| Status::unauthenticated("Invalid JWT"))?;
 
-    let user_id = Uuid::parse_str(&decoded.claims.sub)
-        .map_err(|_| Status::permission_denied("Invalid JWT"))?;
+    let user_id =
+        Uuid::parse_str(&decoded.claims.sub).map_err(|_| Status::unauthenticated("Invalid JWT"))?;
 
     // check expiry
     if decoded.claims.exp < chrono::Utc::now().timestamp() as usize {
-        return Err(Status::permission_denied("JWT expired"));
+        return Err(Status::unauthenticated("JWT expired"));
     }
 
     Ok(AuthenticatedState::User { id: user_id })
 }
 
-const OWNER_ONLY_METHODS: [&str; 1] = ["CreateTenant"];
+const OWNER_ONLY_METHODS: [&str; 5] = [
+    "CreateTenant",
+    "CreateSandboxTenant",
+    "PromoteSandboxCatalog",
+    "GetMigrationStatus",
+    "SetEventRetention",
+];
+
+// the services a billing member can mutate, the other roles being either all or nothing
+const BILLING_SERVICES: [&str; 6] = [
+    "meteroid.api.customers.v1.CustomersService",
+    "meteroid.api.subscriptions.v1.SubscriptionsService",
+    "meteroid.api.invoices.v1.InvoicesService",
+    "meteroid.api.coupons.v1.CouponsService",
+    "meteroid.api.paymentreminders.v1.PaymentRemindersService",
+    "meteroid.api.collectionstrategies.v1.CollectionStrategiesService",
+];
 
 #[cached(
     result = true,
@@ -119,6 +138,55 @@ async fn get_user_role_oss_cached(
     Ok(res)
 }
 
+#[cached(
+    result = true,
+    size = 500,
+    time = 60, // 1 min. Invalidated on change, on this instance only
+    key = "(Uuid, Uuid)",
+    convert = r#"{ (*user_id, *tenant_id) }"#
+)]
+async fn get_tenant_role_cached(
+    store: Store,
+    user_id: &Uuid,
+    tenant_id: &Uuid,
+) -> Result<TenantRole, Status> {
+    let role = store
+        .get_tenant_member_role(*user_id, *tenant_id)
+        .await
+        .map_err(|_| Status::permission_denied("Failed to retrieve tenant role"))?;
+
+    Ok(match role {
+        TenantMemberRoleEnum::Admin => TenantRole::Admin,
+        TenantMemberRoleEnum::Billing => TenantRole::Billing,
+        TenantMemberRoleEnum::ReadOnly => TenantRole::ReadOnly,
+    })
+}
+
+pub async fn invalidate_tenant_role_cache(user_id: &Uuid, tenant_id: &Uuid) {
+    {
+        use cached::Cached;
+        let mut cache = self::GET_TENANT_ROLE_CACHED.lock().await;
+        cache.cache_remove(&(*user_id, *tenant_id));
+    }
+}
+
+/// Read-only members cannot call the mutating methods, billing members only those of the billing services
+fn authorize_tenant_role(role: TenantRole, gm: &GrpcServiceMethod) -> Result<(), Status> {
+    let allowed = match role {
+        TenantRole::Admin => true,
+        TenantRole::Billing => !is_mutating(gm) || BILLING_SERVICES.contains(&gm.service.as_str()),
+        TenantRole::ReadOnly => !is_mutating(gm),
+    };
+
+    if allowed {
+        Ok(())
+    } else {
+        Err(Status::permission_denied(
+            "Your role on this tenant does not allow this call",
+        ))
+    }
+}
+
 fn extract_context(header_map: &HeaderMap) -> Result<(String, Option<String>), Status> {
     let context = header_map
         .get(INTERNAL_API_CONTEXT_HEADER)
@@ -151,35 +219,37 @@ pub async fn authorize_user(
     user_id: Uuid,
     store: Store,
     gm: GrpcServiceMethod,
-) -> Result<AuthorizedState, Status> {
+) -> Result<(AuthorizedState, Option<TenantRole>), Status> {
     let (org_slug, tenant_slug) = extract_context(header_map)?;
     let (organization_id, tenant_id) =
         resolve_slugs_cached(store.clone(), org_slug, tenant_slug).await?;
 
     let role = get_user_role_oss_cached(store.clone(), &user_id, &organization_id).await?;
 
-    // if we have a tenant header, we resolve role via tenant (validating tenant access at the same time)
-    let (role, state) = if let Some(tenant_id) = tenant_id {
-        (
-            role,
+    if role == OrganizationUserRole::Member && OWNER_ONLY_METHODS.contains(&gm.method.as_str()) {
+        return Err(Status::permission_denied("Unauthorized"));
+    }
+
+    // if we have a tenant header, we resolve the role on the tenant (validating tenant access at the same time)
+    if let Some(tenant_id) = tenant_id {
+        let tenant_role = get_tenant_role_cached(store, &user_id, &tenant_id).await?;
+        authorize_tenant_role(tenant_role, &gm)?;
+
+        Ok((
             AuthorizedState::Tenant {
                 tenant_id,
                 organization_id,
                 actor_id: user_id,
             },
-        )
+            Some(tenant_role),
+        ))
     } else {
-        (
-            role,
+        Ok((
             AuthorizedState::Organization {
                 organization_id,
                 actor_id: user_id,
             },
-        )
-    };
-    if role == OrganizationUserRole::Member && OWNER_ONLY_METHODS.contains(&gm.method.as_str()) {
-        return Err(Status::permission_denied("Unauthorized"));
+            None,
+        ))
     }
-
-    Ok(state)
 }
@@ -0,0 +1,19 @@
+use common_grpc::GrpcServiceMethod;
+
+// services without any mutating call
+const READ_ONLY_SERVICES: [&str; 1] = ["meteroid.api.stats.v1.StatsService"];
+
+const READ_ONLY_METHOD_PREFIXES: [&str; 9] = [
+    "Get", "List", "Search", "Query", "Preview", "Validate", "Resolve", "Export", "Diff",
+];
+
+const READ_ONLY_METHODS: [&str; 2] = ["Me", "ActiveTenant"];
+
+/// Calls are mutating unless known to be read only, so that a new rpc is audited and restricted by default
+pub fn is_mutating(sm: &GrpcServiceMethod) -> bool {
+    !(READ_ONLY_SERVICES.contains(&sm.service.as_str())
+        || READ_ONLY_METHODS.contains(&sm.method.as_str())
+        || READ_ONLY_METHOD_PREFIXES
+            .iter()
+            .any(|prefix| sm.method.starts_with(prefix)))
+}
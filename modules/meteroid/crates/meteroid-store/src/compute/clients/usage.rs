@@ -1,6 +1,6 @@
 use std::collections::HashMap;
 
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use rust_decimal::Decimal;
 use uuid::Uuid;
 
@@ -24,6 +24,24 @@ impl UsageData {
             .map(|usage| usage.value)
             .unwrap_or(Decimal::ZERO))
     }
+
+    /// The usage left once the included units are consumed, in the order of the groups, with the consumed units
+    pub(crate) fn without_allowance(mut self, included: u64) -> (UsageData, Decimal) {
+        let mut remaining = Decimal::from(included);
+        let mut consumed = Decimal::ZERO;
+
+        for usage in self.data.iter_mut() {
+            if remaining <= Decimal::ZERO {
+                break;
+            }
+            let deducted = usage.value.min(remaining).max(Decimal::ZERO);
+            usage.value -= deducted;
+            remaining -= deducted;
+            consumed += deducted;
+        }
+
+        (self, consumed)
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -32,12 +50,45 @@ pub struct GroupedUsageData {
     pub dimensions: HashMap<String, String>,
 }
 
+/// The time window of the points of a usage series
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UsageWindow {
+    Hour,
+    Day,
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageSeriesPoint {
+    pub window_start: NaiveDateTime,
+    pub window_end: NaiveDateTime,
+    pub value: Decimal,
+    pub dimensions: HashMap<String, String>,
+}
+
 #[derive(Debug, Clone)]
 pub struct Metadata {
     pub key: String,
     pub value: String,
 }
 
+/// The billable metrics of a tenant without meter view, and its meter views matching no metric
+#[derive(Debug, Clone, Default)]
+pub struct MeterViewsDrift {
+    pub missing_metric_ids: Vec<Uuid>,
+    pub orphaned_views: Vec<String>,
+}
+
+/// How long the ingested events of a tenant are kept. The usage aggregated by the meters is kept regardless
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct EventRetentionPolicy {
+    /// 0 keeps the events
+    pub retention_days: u32,
+    /// the expired events are archived to the object storage before their deletion
+    pub archive: bool,
+    /// the events before that point are archived and deleted
+    pub archived_until: Option<NaiveDateTime>,
+}
+
 #[async_trait::async_trait]
 pub trait UsageClient: Send + Sync {
     async fn register_meter(
@@ -54,6 +105,75 @@ pub trait UsageClient: Send + Sync {
         metric: &BillableMetric,
         period: Period,
     ) -> Result<UsageData, ComputeError>;
+
+    /// The usage of the customer over the period, grouped by the given event properties
+    async fn fetch_grouped_usage(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+    ) -> Result<UsageData, ComputeError>;
+
+    /// The usage of the customer per window over the period, grouped by the given event properties
+    async fn fetch_usage_series(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+        window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError>;
+
+    /// The number of events of the customer per window over the period, grouped by the given event properties
+    async fn fetch_event_count_series(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+        window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError>;
+
+    /// Deletes the ingested events of the tenant and the usage of its meters
+    async fn purge_tenant_events(
+        &self,
+        tenant_id: &Uuid,
+        metric_ids: &[Uuid],
+    ) -> Result<(), ComputeError>;
+
+    /// Compares the meter views of the tenant in the metering storage to its billable metrics
+    async fn check_meter_views(
+        &self,
+        tenant_id: &Uuid,
+        metric_ids: &[Uuid],
+    ) -> Result<MeterViewsDrift, ComputeError>;
+
+    async fn set_event_retention(
+        &self,
+        tenant_id: &Uuid,
+        policy: EventRetentionPolicy,
+    ) -> Result<EventRetentionPolicy, ComputeError>;
+
+    /// The default policy keeps the events
+    async fn get_event_retention(
+        &self,
+        tenant_id: &Uuid,
+    ) -> Result<EventRetentionPolicy, ComputeError>;
+
+    /// Restores the archived events of the tenant over the period, for the metering storage to re-aggregate them
+    async fn restore_archived_events(
+        &self,
+        tenant_id: &Uuid,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<(), ComputeError>;
 }
 
 #[derive(Eq, Hash, PartialEq)]
@@ -98,6 +218,85 @@ impl UsageClient for MockUsageClient {
             });
         Ok(usage_data)
     }
+
+    async fn fetch_grouped_usage(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        _group_by: Vec<String>,
+    ) -> Result<UsageData, ComputeError> {
+        self.fetch_usage(tenant_id, customer_id, customer_external_id, metric, period)
+            .await
+    }
+
+    async fn fetch_usage_series(
+        &self,
+        _tenant_id: &Uuid,
+        _customer_id: &Uuid,
+        _customer_external_id: &Option<String>,
+        _metric: &BillableMetric,
+        _period: Period,
+        _group_by: Vec<String>,
+        _window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError> {
+        Ok(vec![])
+    }
+
+    async fn fetch_event_count_series(
+        &self,
+        _tenant_id: &Uuid,
+        _customer_id: &Uuid,
+        _customer_external_id: &Option<String>,
+        _metric: &BillableMetric,
+        _period: Period,
+        _group_by: Vec<String>,
+        _window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError> {
+        Ok(vec![])
+    }
+
+    async fn purge_tenant_events(
+        &self,
+        _tenant_id: &Uuid,
+        _metric_ids: &[Uuid],
+    ) -> Result<(), ComputeError> {
+        Ok(())
+    }
+
+    async fn check_meter_views(
+        &self,
+        _tenant_id: &Uuid,
+        _metric_ids: &[Uuid],
+    ) -> Result<MeterViewsDrift, ComputeError> {
+        Ok(MeterViewsDrift::default())
+    }
+
+    async fn set_event_retention(
+        &self,
+        _tenant_id: &Uuid,
+        policy: EventRetentionPolicy,
+    ) -> Result<EventRetentionPolicy, ComputeError> {
+        Ok(policy)
+    }
+
+    async fn get_event_retention(
+        &self,
+        _tenant_id: &Uuid,
+    ) -> Result<EventRetentionPolicy, ComputeError> {
+        Ok(EventRetentionPolicy::default())
+    }
+
+    async fn restore_archived_events(
+        &self,
+        _tenant_id: &Uuid,
+        _from: NaiveDateTime,
+        _to: NaiveDateTime,
+    ) -> Result<(), ComputeError> {
+        Ok(())
+    }
 }
 
 impl MockUsageClient {
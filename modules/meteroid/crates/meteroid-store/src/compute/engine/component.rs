@@ -1,19 +1,21 @@
 use std::sync::Arc;
 
 use chrono::NaiveDate;
+use itertools::Itertools;
 use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
-use rust_decimal::Decimal;
+use rust_decimal::{Decimal, RoundingStrategy};
 use rust_decimal_macros::dec;
 use uuid::Uuid;
 
-use crate::domain::enums::BillingType;
+use crate::domain::enums::{BillingType, ProrationRoundingEnum};
 use crate::domain::*;
 use crate::utils::local_id::LocalId;
 
 use crate::compute::clients::slots::SlotClient;
 use crate::compute::clients::usage::{GroupedUsageData, UsageData};
 use crate::compute::engine::shared::{only_positive, only_positive_decimal};
-use crate::utils::decimals::ToSubunit;
+use crate::compute::proration;
+use crate::utils::decimals::{ToSubunit, ToUnit};
 
 use super::super::clients::usage::UsageClient;
 use super::super::errors::ComputeError;
@@ -23,6 +25,7 @@ pub struct ComponentEngine {
     usage_client: Arc<dyn UsageClient + Send + Sync>,
     slots_client: Arc<dyn SlotClient + Send + Sync>,
     subscription_details: Arc<SubscriptionDetails>,
+    proration_rounding: ProrationRoundingEnum,
 }
 
 // TODO we can't really use "to_cents" or similar. The minimum unit depends on the currency.
@@ -32,11 +35,13 @@ impl ComponentEngine {
         usage_client: Arc<dyn UsageClient + Send + Sync>,
         slots_client: Arc<dyn SlotClient + Send + Sync>,
         subscription_details: Arc<SubscriptionDetails>,
+        proration_rounding: ProrationRoundingEnum,
     ) -> Self {
         Self {
             usage_client,
             slots_client,
             subscription_details,
+            proration_rounding,
         }
     }
 
@@ -59,6 +64,7 @@ impl ComponentEngine {
                     &dec!(1),
                     fixed_period,
                     periods.proration_factor,
+                    self.proration_rounding,
                     precision,
                 )?);
             }
@@ -70,6 +76,7 @@ impl ComponentEngine {
                         &Decimal::from(*quantity),
                         fixed_period,
                         periods.proration_factor,
+                        self.proration_rounding,
                         precision,
                     )?);
                 }
@@ -85,6 +92,7 @@ impl ComponentEngine {
                         &Decimal::from(*quantity),
                         fixed_period,
                         periods.proration_factor,
+                        self.proration_rounding,
                         precision,
                     )?);
                 }
@@ -95,6 +103,7 @@ impl ComponentEngine {
                             &Decimal::from(*quantity),
                             arrears,
                             periods.proration_factor,
+                            self.proration_rounding,
                             precision,
                         )?);
                     }
@@ -122,6 +131,7 @@ impl ComponentEngine {
                     &Decimal::from(slots),
                     fixed_period,
                     periods.proration_factor,
+                    self.proration_rounding,
                     precision,
                 )?);
             }
@@ -130,191 +140,75 @@ impl ComponentEngine {
                 included,
                 overage_rate,
                 metric_id,
+                cap,
             } => {
-                let mut lines = vec![];
-
                 lines.push(InvoiceLineInner::simple_prorated(
                     rate,
                     &dec!(1),
                     fixed_period,
                     None, // no proration on capacity, as it provides a fixed amount
+                    self.proration_rounding,
                     precision,
                 )?);
 
                 if let Some(arrear_period) = periods.arrear {
-                    if overage_rate > &Decimal::ZERO {
-                        let usage = self
-                            .fetch_usage(arrear_period.clone(), *metric_id)
-                            .await?
-                            .single()?;
-
-                        let overage_units = usage - Decimal::from(*included);
-
-                        if overage_units > Decimal::ZERO {
-                            let overage_price = overage_rate
-                                .to_subunit_opt(precision)
-                                .ok_or(ComputeError::ConversionError)?;
-                            let overage_total = overage_price * overage_units.to_i64().unwrap_or(0);
-
-                            let overage_line = InvoiceLineInner {
-                                quantity: None,
-                                unit_price: None,
-                                total: overage_total as u64,
-                                period: arrear_period,
-                                is_prorated: false,
-                                custom_line_name: None,
-                                sublines: vec![SubLineItem {
-                                    local_id: LocalId::no_prefix(),
-                                    name: "Overage".to_string(),
-                                    total: overage_total,
-                                    quantity: overage_units,
-                                    unit_price: *overage_rate,
-                                    attributes: None,
-                                }],
-                            };
-
-                            lines.push(overage_line);
-                        }
+                    if let Some(overage_line) = self
+                        .overage_line(
+                            *included,
+                            overage_rate,
+                            *metric_id,
+                            arrear_period,
+                            precision,
+                        )
+                        .await?
+                    {
+                        lines.push(overage_line.capped(cap.as_ref(), precision)?);
                     }
                 }
             }
-            SubscriptionFee::Usage { metric_id, model } => {
+            SubscriptionFee::Usage {
+                metric_id,
+                model,
+                cap,
+                included,
+            } => {
                 if let Some(arrear_period) = periods.arrear {
-                    let usage = self.fetch_usage(arrear_period.clone(), *metric_id).await?;
-
-                    match model {
-                        UsagePricingModel::Matrix { rates } => {
-                            let mut sublines = vec![];
-
-                            for rate in rates {
-                                // for each rate, we get the quantity matching that rate
-                                let quantity = usage
-                                    .data
-                                    .iter()
-                                    .find(|usage| {
-                                        let d1 = usage.dimensions.get(&rate.dimension1.key)
-                                            == Some(&rate.dimension1.value);
-
-                                        if let Some(dimension2) = &rate.dimension2 {
-                                            d1 && usage.dimensions.get(&dimension2.key)
-                                                == Some(&dimension2.value)
-                                        } else {
-                                            d1
-                                        }
-                                    })
-                                    .map(|usage| usage.value)
-                                    .unwrap_or(Decimal::ZERO);
-
-                                let price_total = rate.per_unit_price * quantity;
-
-                                let price_cents = only_positive(
-                                    price_total
-                                        .to_subunit_opt(precision)
-                                        .ok_or(ComputeError::ConversionError)?,
-                                );
-
-                                if price_cents > 0 {
-                                    // we concat rate.dimension1.value and rate.dimension2.value (if defined), separed by a coma. No coma if rate.dimension2 is None
-                                    let name = format!(
-                                        "{}{}",
-                                        rate.dimension1.value,
-                                        rate.dimension2
-                                            .as_ref()
-                                            .map(|d| format!(",{}", d.value))
-                                            .unwrap_or_default()
-                                    );
-                                    sublines.push(SubLineItem {
-                                        local_id: LocalId::no_prefix(),
-                                        name, // TODO
-                                        total: price_cents as i64,
-                                        quantity,
-                                        unit_price: rate.per_unit_price,
-                                        attributes: Some(SubLineAttributes::Matrix {
-                                            dimension1_key: rate.dimension1.key.clone(),
-                                            dimension1_value: rate.dimension1.value.clone(),
-                                            dimension2_key: rate
-                                                .dimension2
-                                                .as_ref()
-                                                .map(|d| d.key.clone()),
-                                            dimension2_value: rate
-                                                .dimension2
-                                                .as_ref()
-                                                .map(|d| d.value.clone()),
-                                        }),
-                                    });
-                                }
-                            }
-
-                            lines.push(InvoiceLineInner::from_sublines(
-                                sublines,
-                                arrear_period,
-                                None,
-                            )?);
-                        }
-                        model => {
-                            let usage_units = usage.single()?;
-
-                            //TODO only if price > 0 & usage > 0
-
-                            match model {
-                                UsagePricingModel::PerUnit { rate } => {
-                                    lines.push(InvoiceLineInner::simple(
-                                        rate,
-                                        &usage_units,
-                                        arrear_period,
-                                        precision,
-                                    )?);
-                                }
-                                UsagePricingModel::Tiered { tiers, block_size } => {
-                                    lines.push(fees::compute_tier_price(
-                                        usage_units,
-                                        tiers,
-                                        arrear_period,
-                                        precision,
-                                        block_size,
-                                    )?);
-                                }
-                                UsagePricingModel::Volume { tiers, block_size } => {
-                                    lines.push(fees::compute_volume_price(
-                                        usage_units,
-                                        tiers,
-                                        arrear_period,
-                                        precision,
-                                        block_size,
-                                    )?);
-                                }
-                                UsagePricingModel::Package { block_size, rate } => {
-                                    // TODO we want some additional data in the frontend to display that "x$ per 20", total usage and block usage
-                                    let package_size_decimal = Decimal::from(*block_size);
-                                    let total_packages =
-                                        (usage_units / package_size_decimal).ceil();
-
-                                    let price_total = total_packages * *rate;
-
-                                    lines.push(InvoiceLineInner::from_sublines(
-                                        vec![SubLineItem {
-                                            local_id: LocalId::no_prefix(),
-                                            name: "Package".to_string(),
-                                            total: price_total
-                                                .to_subunit_opt(precision)
-                                                .ok_or(ComputeError::ConversionError)?,
-                                            quantity: total_packages,
-                                            unit_price: *rate,
-                                            attributes: Some(SubLineAttributes::Package {
-                                                raw_usage: usage_units,
-                                            }),
-                                        }],
-                                        arrear_period,
-                                        None,
-                                    )?);
-                                }
-                                UsagePricingModel::Matrix { .. } => unreachable!(),
-                            };
-                        }
-                    }
+                    let usage_line = self
+                        .usage_line(*metric_id, model, *included, arrear_period, precision)
+                        .await?;
+                    lines.push(usage_line.capped(cap.as_ref(), precision)?);
+                }
+            }
+            SubscriptionFee::Percent {
+                metric_id,
+                percentage,
+                minimum,
+                maximum,
+            } => {
+                if let Some(arrear_period) = periods.arrear {
+                    let amount = self
+                        .fetch_usage(arrear_period.clone(), *metric_id)
+                        .await?
+                        .single()?;
+                    let percent_line = percent_line(
+                        amount,
+                        percentage,
+                        minimum.as_ref(),
+                        arrear_period,
+                        precision,
+                    )?;
+                    lines.push(percent_line.capped(maximum.as_ref(), precision)?);
                 }
             }
         }
+        let quantity_display_precision = component.fee_ref().metric_id().and_then(|metric_id| {
+            self.subscription_details
+                .metrics
+                .iter()
+                .find(|metric| metric.id == metric_id)
+                .and_then(|metric| metric.quantity_display_precision())
+        });
+
         Ok(lines
             .into_iter()
             .map(|line| LineItem {
@@ -335,10 +229,284 @@ impl ComponentEngine {
                 metric_id: component.fee_ref().metric_id(),
                 subtotal: line.total as i64, // TODO
                 description: None,
+                quantity_display_precision,
             })
             .collect())
     }
 
+    /// Charges of the capped part of the fee over the period, before the cap applies. None for the fees without cap
+    pub async fn compute_uncapped_charges<T: SubscriptionFeeInterface>(
+        &self,
+        component: &T,
+        period: Period,
+        precision: u8,
+    ) -> Result<Option<u64>, ComputeError> {
+        match component.fee_ref() {
+            SubscriptionFee::Capacity {
+                included,
+                overage_rate,
+                metric_id,
+                cap: Some(_),
+                ..
+            } => {
+                let overage_line = self
+                    .overage_line(*included, overage_rate, *metric_id, period, precision)
+                    .await?;
+                Ok(Some(overage_line.map(|line| line.total).unwrap_or(0)))
+            }
+            SubscriptionFee::Usage {
+                metric_id,
+                model,
+                cap: Some(_),
+                included,
+            } => {
+                let usage_line = self
+                    .usage_line(*metric_id, model, *included, period, precision)
+                    .await?;
+                Ok(Some(usage_line.total))
+            }
+            SubscriptionFee::Percent {
+                metric_id,
+                percentage,
+                minimum,
+                maximum: Some(_),
+            } => {
+                let amount = self
+                    .fetch_usage(period.clone(), *metric_id)
+                    .await?
+                    .single()?;
+                let percent_line =
+                    percent_line(amount, percentage, minimum.as_ref(), period, precision)?;
+                Ok(Some(percent_line.total))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    async fn overage_line(
+        &self,
+        included: u64,
+        overage_rate: &Decimal,
+        metric_id: Uuid,
+        period: Period,
+        precision: u8,
+    ) -> Result<Option<InvoiceLineInner>, ComputeError> {
+        if overage_rate <= &Decimal::ZERO {
+            return Ok(None);
+        }
+
+        let usage = self
+            .fetch_usage(period.clone(), metric_id)
+            .await?
+            .single()?;
+
+        let overage_units = usage - Decimal::from(included);
+
+        if overage_units <= Decimal::ZERO {
+            return Ok(None);
+        }
+
+        let overage_price = overage_rate
+            .to_subunit_opt(precision)
+            .ok_or(ComputeError::ConversionError)?;
+        let overage_total = overage_price * overage_units.to_i64().unwrap_or(0);
+
+        Ok(Some(InvoiceLineInner {
+            quantity: None,
+            unit_price: None,
+            total: overage_total as u64,
+            period,
+            is_prorated: false,
+            custom_line_name: None,
+            sublines: vec![SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: "Overage".to_string(),
+                total: overage_total,
+                quantity: overage_units,
+                unit_price: *overage_rate,
+                attributes: None,
+            }],
+        }))
+    }
+
+    async fn usage_line(
+        &self,
+        metric_id: Uuid,
+        model: &UsagePricingModel,
+        included: Option<u64>,
+        period: Period,
+        precision: u8,
+    ) -> Result<InvoiceLineInner, ComputeError> {
+        let (usage, consumed_allowance) = self
+            .fetch_usage(period.clone(), metric_id)
+            .await?
+            .without_allowance(included.unwrap_or(0));
+
+        let mut line = match model {
+            UsagePricingModel::Matrix { rates } => {
+                let mut sublines = vec![];
+
+                for rate in rates {
+                    // for each rate, we get the quantity matching that rate
+                    let quantity = usage
+                        .data
+                        .iter()
+                        .find(|usage| {
+                            let d1 = usage.dimensions.get(&rate.dimension1.key)
+                                == Some(&rate.dimension1.value);
+
+                            if let Some(dimension2) = &rate.dimension2 {
+                                d1 && usage.dimensions.get(&dimension2.key)
+                                    == Some(&dimension2.value)
+                            } else {
+                                d1
+                            }
+                        })
+                        .map(|usage| usage.value)
+                        .unwrap_or(Decimal::ZERO);
+
+                    let price_total = rate.per_unit_price * quantity;
+
+                    let price_cents = only_positive(
+                        price_total
+                            .to_subunit_opt(precision)
+                            .ok_or(ComputeError::ConversionError)?,
+                    );
+
+                    if price_cents > 0 {
+                        // we concat rate.dimension1.value and rate.dimension2.value (if defined), separed by a coma. No coma if rate.dimension2 is None
+                        let name = format!(
+                            "{}{}",
+                            rate.dimension1.value,
+                            rate.dimension2
+                                .as_ref()
+                                .map(|d| format!(",{}", d.value))
+                                .unwrap_or_default()
+                        );
+                        sublines.push(SubLineItem {
+                            local_id: LocalId::no_prefix(),
+                            name, // TODO
+                            total: price_cents as i64,
+                            quantity,
+                            unit_price: rate.per_unit_price,
+                            attributes: Some(SubLineAttributes::Matrix {
+                                dimension1_key: rate.dimension1.key.clone(),
+                                dimension1_value: rate.dimension1.value.clone(),
+                                dimension2_key: rate.dimension2.as_ref().map(|d| d.key.clone()),
+                                dimension2_value: rate.dimension2.as_ref().map(|d| d.value.clone()),
+                            }),
+                        });
+                    }
+                }
+
+                InvoiceLineInner::from_sublines(sublines, period, None, self.proration_rounding)?
+            }
+            model => {
+                let usage_units = usage.single()?;
+
+                //TODO only if price > 0 & usage > 0
+
+                match model {
+                    UsagePricingModel::PerUnit { rate } => {
+                        InvoiceLineInner::simple(rate, &usage_units, period, precision)?
+                    }
+                    UsagePricingModel::Tiered { tiers, block_size } => {
+                        fees::compute_tier_price(usage_units, tiers, period, precision, block_size)?
+                    }
+                    UsagePricingModel::Volume { tiers, block_size } => fees::compute_volume_price(
+                        usage_units,
+                        tiers,
+                        period,
+                        precision,
+                        block_size,
+                    )?,
+                    UsagePricingModel::Package { block_size, rate } => {
+                        // TODO we want some additional data in the frontend to display that "x$ per 20", total usage and block usage
+                        let package_size_decimal = Decimal::from(*block_size);
+                        let total_packages = (usage_units / package_size_decimal).ceil();
+
+                        let price_total = total_packages * *rate;
+
+                        InvoiceLineInner::from_sublines(
+                            vec![SubLineItem {
+                                local_id: LocalId::no_prefix(),
+                                name: "Package".to_string(),
+                                total: price_total
+                                    .to_subunit_opt(precision)
+                                    .ok_or(ComputeError::ConversionError)?,
+                                quantity: total_packages,
+                                unit_price: *rate,
+                                attributes: Some(SubLineAttributes::Package {
+                                    raw_usage: usage_units,
+                                }),
+                            }],
+                            period,
+                            None,
+                            self.proration_rounding,
+                        )?
+                    }
+                    UsagePricingModel::Matrix { .. } => unreachable!(),
+                }
+            }
+        };
+
+        if consumed_allowance > Decimal::ZERO {
+            line.sublines.push(SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: "Included".to_string(),
+                total: 0,
+                quantity: consumed_allowance,
+                unit_price: Decimal::ZERO,
+                attributes: None,
+            });
+        }
+
+        // the matrix sublines already break the usage down by dimension
+        if !matches!(model, UsagePricingModel::Matrix { .. }) {
+            if let Some(group_key) = self.usage_group_key(metric_id) {
+                let grouped_usage = self
+                    .fetch_grouped_usage(line.period.clone(), metric_id, group_key.clone())
+                    .await?;
+                line.sublines.extend(usage_group_sublines(
+                    &group_key,
+                    grouped_usage,
+                    line.total,
+                    precision,
+                ));
+            }
+        }
+
+        Ok(line)
+    }
+
+    /// Included units of the fee consumed over the period. None for the fees without allowance
+    pub async fn compute_allowance<T: SubscriptionFeeInterface>(
+        &self,
+        component: &T,
+        period: Period,
+    ) -> Result<Option<Allowance>, ComputeError> {
+        let (metric_id, included) = match component.fee_ref() {
+            SubscriptionFee::Usage {
+                metric_id,
+                included: Some(included),
+                ..
+            } => (*metric_id, *included),
+            SubscriptionFee::Capacity {
+                metric_id,
+                included,
+                ..
+            } => (*metric_id, *included),
+            _ => return Ok(None),
+        };
+
+        let (_, consumed) = self
+            .fetch_usage(period, metric_id)
+            .await?
+            .without_allowance(included);
+
+        Ok(Some(Allowance { included, consumed }))
+    }
+
     async fn fetch_usage(
         &self,
         period: Period,
@@ -362,24 +530,64 @@ impl ComponentEngine {
             )
             .await?;
 
-        if let Some(factor) = metric.unit_conversion_factor {
-            if factor == 0 {
-                return Ok(usage);
-            }
-            let usage = UsageData {
-                period: usage.period,
-                data: usage
-                    .data
-                    .iter()
-                    .map(|usage| GroupedUsageData {
-                        value: usage.value / Decimal::from_i32(factor).unwrap_or(dec!(1)),
-                        dimensions: usage.dimensions.clone(),
-                    })
-                    .collect(),
-            };
-            return Ok(usage);
-        }
-        Ok(usage)
+        Ok(UsageData {
+            period: usage.period,
+            data: usage
+                .data
+                .into_iter()
+                .map(|usage| GroupedUsageData {
+                    value: metric.billed_quantity(usage.value),
+                    dimensions: usage.dimensions,
+                })
+                .collect(),
+        })
+    }
+
+    fn usage_group_key(&self, metric_id: Uuid) -> Option<String> {
+        self.subscription_details
+            .metrics
+            .iter()
+            .find(|metric| metric.id == metric_id)
+            .and_then(|metric| metric.usage_group_key.clone())
+            .filter(|key| !key.is_empty())
+    }
+
+    async fn fetch_grouped_usage(
+        &self,
+        period: Period,
+        metric_id: Uuid,
+        group_key: String,
+    ) -> Result<UsageData, ComputeError> {
+        let metric = self
+            .subscription_details
+            .metrics
+            .iter()
+            .find(|metric| metric.id == metric_id)
+            .ok_or(ComputeError::MetricNotFound)?;
+
+        let usage = self
+            .usage_client
+            .fetch_grouped_usage(
+                &self.subscription_details.tenant_id,
+                &self.subscription_details.customer_id,
+                &self.subscription_details.customer_external_id,
+                metric,
+                period,
+                vec![group_key],
+            )
+            .await?;
+
+        Ok(UsageData {
+            period: usage.period,
+            data: usage
+                .data
+                .into_iter()
+                .map(|usage| GroupedUsageData {
+                    value: metric.billed_quantity(usage.value),
+                    dimensions: usage.dimensions,
+                })
+                .collect(),
+        })
     }
 
     async fn fetch_slots(
@@ -401,6 +609,11 @@ impl ComponentEngine {
     }
 }
 
+pub struct Allowance {
+    pub included: u64,
+    pub consumed: Decimal,
+}
+
 pub struct InvoiceLineInner {
     pub total: u64,
     pub quantity: Option<Decimal>,
@@ -417,6 +630,7 @@ impl InvoiceLineInner {
         quantity: &Decimal,
         period: Period,
         proration_factor: Option<f64>,
+        rounding: ProrationRoundingEnum,
         precision: u8,
     ) -> Result<InvoiceLineInner, ComputeError> {
         let unit_price_cents = prorate_dec(*rate, proration_factor);
@@ -428,7 +642,8 @@ impl InvoiceLineInner {
                 .to_subunit_opt(precision)
                 .ok_or(ComputeError::ConversionError)?,
             proration_factor,
-        );
+            rounding,
+        )?;
 
         Ok(InvoiceLineInner {
             quantity: Some(*quantity),
@@ -447,16 +662,24 @@ impl InvoiceLineInner {
         period: Period,
         precision: u8,
     ) -> Result<InvoiceLineInner, ComputeError> {
-        Self::simple_prorated(rate, quantity, period, None, precision)
+        Self::simple_prorated(
+            rate,
+            quantity,
+            period,
+            None,
+            ProrationRoundingEnum::default(),
+            precision,
+        )
     }
 
     pub fn from_sublines(
         sublines: Vec<SubLineItem>,
         period: Period,
         proration_factor: Option<f64>,
+        rounding: ProrationRoundingEnum,
     ) -> Result<InvoiceLineInner, ComputeError> {
         let total = sublines.iter().map(|subline| subline.total).sum::<i64>();
-        let total_cents = prorate(total, proration_factor);
+        let total_cents = prorate(total, proration_factor, rounding)?;
 
         Ok(InvoiceLineInner {
             quantity: None,
@@ -468,16 +691,169 @@ impl InvoiceLineInner {
             sublines,
         })
     }
+
+    /// Limits the total to the cap, the charges above it being credited by a subline
+    pub fn capped(mut self, cap: Option<&Decimal>, precision: u8) -> Result<Self, ComputeError> {
+        let Some(cap) = cap else {
+            return Ok(self);
+        };
+
+        let cap_cents = only_positive(
+            cap.to_subunit_opt(precision)
+                .ok_or(ComputeError::ConversionError)?,
+        );
+
+        if self.total > cap_cents {
+            let excess = (self.total - cap_cents) as i64;
+
+            self.sublines.push(SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: "Cap".to_string(),
+                total: -excess,
+                quantity: dec!(1),
+                unit_price: -excess.to_unit(precision),
+                attributes: None,
+            });
+            self.total = cap_cents;
+        }
+
+        Ok(self)
+    }
 }
 
-fn prorate(price_cents: i64, proration_factor: Option<f64>) -> u64 {
+fn prorate(
+    price_cents: i64,
+    proration_factor: Option<f64>,
+    rounding: ProrationRoundingEnum,
+) -> Result<u64, ComputeError> {
     match proration_factor {
         Some(proration_factor) => {
-            let prorated_price = (price_cents as f64 * proration_factor).round() as i64;
-            only_positive(prorated_price)
+            let factor =
+                Decimal::from_f64(proration_factor).ok_or(ComputeError::ConversionError)?;
+            let prorated_price = proration::prorate_with_factor(price_cents, factor, rounding)?;
+            Ok(only_positive(prorated_price))
+        }
+        None => Ok(only_positive(price_cents)),
+    }
+}
+
+/// Breakdown of the charges of a usage line per value of the usage group key, split in proportion to the usage of
+/// each group. The last group gets the rounding remainder, for the breakdown to add up to the line total
+fn usage_group_sublines(
+    group_key: &str,
+    usage: UsageData,
+    total: u64,
+    precision: u8,
+) -> Vec<SubLineItem> {
+    let groups: Vec<(String, Decimal)> = usage
+        .data
+        .into_iter()
+        .filter(|usage| usage.value > Decimal::ZERO)
+        .map(|usage| {
+            (
+                usage.dimensions.get(group_key).cloned().unwrap_or_default(),
+                usage.value,
+            )
+        })
+        .sorted_by(|a, b| a.0.cmp(&b.0))
+        .collect();
+
+    let quantity: Decimal = groups.iter().map(|(_, value)| *value).sum();
+    if quantity <= Decimal::ZERO {
+        return vec![];
+    }
+
+    let count = groups.len();
+    let mut allocated = 0i64;
+
+    groups
+        .into_iter()
+        .enumerate()
+        .map(|(index, (group_value, group_quantity))| {
+            let group_total = if index == count - 1 {
+                total as i64 - allocated
+            } else {
+                (Decimal::from(total) * group_quantity / quantity)
+                    .round_dp_with_strategy(0, RoundingStrategy::MidpointAwayFromZero)
+                    .to_i64()
+                    .unwrap_or(0)
+            };
+            allocated += group_total;
+
+            SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: if group_value.is_empty() {
+                    "Other".to_string()
+                } else {
+                    group_value.clone()
+                },
+                total: group_total,
+                quantity: group_quantity,
+                unit_price: (group_total.to_unit(precision) / group_quantity).round_dp(8),
+                attributes: Some(SubLineAttributes::UsageGroup {
+                    group_key: group_key.to_string(),
+                    group_value,
+                }),
+            }
+        })
+        .collect()
+}
+
+/// The percentage of the amount aggregated over the period, raised to the minimum of the fee by a subline.
+/// The maximum is applied as a cap by the caller
+fn percent_line(
+    amount: Decimal,
+    percentage: &Decimal,
+    minimum: Option<&Decimal>,
+    period: Period,
+    precision: u8,
+) -> Result<InvoiceLineInner, ComputeError> {
+    let rate = percentage / dec!(100);
+
+    let charge = only_positive(
+        (amount * rate)
+            .to_subunit_opt(precision)
+            .ok_or(ComputeError::ConversionError)?,
+    );
+
+    let mut sublines = vec![SubLineItem {
+        local_id: LocalId::no_prefix(),
+        name: format!(
+            "{}% of {}",
+            percentage.normalize(),
+            amount.round_dp(precision as u32)
+        ),
+        total: charge as i64,
+        quantity: amount,
+        unit_price: rate,
+        attributes: Some(SubLineAttributes::Percent {
+            percentage: *percentage,
+            base_amount: amount,
+        }),
+    }];
+
+    if let Some(minimum) = minimum {
+        let minimum_cents = only_positive(
+            minimum
+                .to_subunit_opt(precision)
+                .ok_or(ComputeError::ConversionError)?,
+        );
+
+        if charge < minimum_cents {
+            let shortfall = (minimum_cents - charge) as i64;
+
+            sublines.push(SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: "Minimum".to_string(),
+                total: shortfall,
+                quantity: dec!(1),
+                unit_price: shortfall.to_unit(precision),
+                attributes: None,
+            });
         }
-        None => only_positive(price_cents),
     }
+
+    InvoiceLineInner::from_sublines(sublines, period, None, ProrationRoundingEnum::default())
 }
 
 fn prorate_dec(price_cents: Decimal, proration_factor: Option<f64>) -> Decimal {
@@ -490,3 +866,144 @@ fn prorate_dec(price_cents: Decimal, proration_factor: Option<f64>) -> Decimal {
         None => only_positive_decimal(price_cents),
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capped() {
+        let period = Period {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        };
+
+        let line = InvoiceLineInner::simple(&dec!(0.5), &dec!(300), period.clone(), 2).unwrap();
+        assert_eq!(line.total, 15000);
+
+        let capped = line.capped(Some(&dec!(100)), 2).unwrap();
+        assert_eq!(capped.total, 10000);
+        assert_eq!(capped.sublines.len(), 1);
+        assert_eq!(capped.sublines[0].total, -5000);
+        assert_eq!(capped.sublines[0].unit_price, dec!(-50));
+
+        let below_cap = InvoiceLineInner::simple(&dec!(0.5), &dec!(100), period, 2)
+            .unwrap()
+            .capped(Some(&dec!(100)), 2)
+            .unwrap();
+        assert_eq!(below_cap.total, 5000);
+        assert!(below_cap.sublines.is_empty());
+    }
+
+    #[test]
+    fn test_percent_line() {
+        let period = Period {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+        };
+
+        let line = percent_line(
+            dec!(12345.67),
+            &dec!(2.5),
+            Some(&dec!(100)),
+            period.clone(),
+            2,
+        )
+        .unwrap()
+        .capped(Some(&dec!(1000)), 2)
+        .unwrap();
+        assert_eq!(line.total, 30864);
+        assert_eq!(line.sublines.len(), 1);
+        assert_eq!(line.sublines[0].name, "2.5% of 12345.67");
+        assert_eq!(line.sublines[0].unit_price, dec!(0.025));
+
+        let below_minimum =
+            percent_line(dec!(1000), &dec!(2.5), Some(&dec!(100)), period.clone(), 2).unwrap();
+        assert_eq!(below_minimum.total, 10000);
+        assert_eq!(below_minimum.sublines[0].total, 2500);
+        assert_eq!(below_minimum.sublines[1].name, "Minimum");
+        assert_eq!(below_minimum.sublines[1].total, 7500);
+
+        let above_maximum = percent_line(dec!(100000), &dec!(2.5), None, period, 2)
+            .unwrap()
+            .capped(Some(&dec!(1000)), 2)
+            .unwrap();
+        assert_eq!(above_maximum.total, 100000);
+        assert_eq!(above_maximum.sublines[1].total, -150000);
+    }
+
+    #[test]
+    fn test_usage_group_sublines() {
+        let group = |model: &str, value: Decimal| GroupedUsageData {
+            value,
+            dimensions: [("model".to_string(), model.to_string())].into(),
+        };
+        let usage = UsageData {
+            data: vec![
+                group("o1", dec!(2)),
+                group("gpt-4o", dec!(1)),
+                group("mini", dec!(0)),
+            ],
+            period: Period {
+                start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            },
+        };
+
+        let sublines = usage_group_sublines("model", usage.clone(), 1000, 2);
+
+        assert_eq!(sublines.len(), 2);
+        assert_eq!(sublines[0].name, "gpt-4o");
+        assert_eq!(sublines[0].total, 333);
+        assert_eq!(sublines[0].unit_price, dec!(3.33));
+        assert_eq!(sublines[1].name, "o1");
+        assert_eq!(sublines[1].total, 667);
+        assert_eq!(sublines[1].quantity, dec!(2));
+        assert_eq!(
+            sublines[1].attributes,
+            Some(SubLineAttributes::UsageGroup {
+                group_key: "model".to_string(),
+                group_value: "o1".to_string(),
+            })
+        );
+
+        assert!(usage_group_sublines(
+            "model",
+            UsageData {
+                data: vec![],
+                period: usage.period
+            },
+            1000,
+            2
+        )
+        .is_empty());
+    }
+
+    #[test]
+    fn test_without_allowance() {
+        let group = |value: Decimal| GroupedUsageData {
+            value,
+            dimensions: Default::default(),
+        };
+        let usage = UsageData {
+            data: vec![group(dec!(300)), group(dec!(500))],
+            period: Period {
+                start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+                end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
+            },
+        };
+
+        let (remaining, consumed) = usage.clone().without_allowance(400);
+        assert_eq!(consumed, dec!(400));
+        assert_eq!(remaining.data[0].value, dec!(0));
+        assert_eq!(remaining.data[1].value, dec!(400));
+
+        let (remaining, consumed) = usage.clone().without_allowance(1000);
+        assert_eq!(consumed, dec!(800));
+        assert_eq!(remaining.data[1].value, dec!(0));
+
+        let (remaining, consumed) = usage.without_allowance(0);
+        assert_eq!(consumed, dec!(0));
+        assert_eq!(remaining.data[1].value, dec!(500));
+    }
+}
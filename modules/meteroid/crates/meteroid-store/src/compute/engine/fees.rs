@@ -1,78 +1,90 @@
 use crate::compute::engine::component::InvoiceLineInner;
 use crate::compute::ComputeError;
-use crate::domain::{Period, SubLineAttributes, SubLineItem, TierRow};
+use crate::domain::{
+    Period, SubLineAttributes, SubLineItem, TierRow, TieredPrice, TieredPricingPreview,
+};
 use crate::utils::decimals::ToSubunit;
 use crate::utils::local_id::LocalId;
 use rust_decimal::Decimal;
 
-pub fn compute_volume_price(
-    usage_units: Decimal,
-    tiers: &[TierRow],
-    period: Period,
-    precision: u8,
-    _block_size: &Option<u64>,
-) -> Result<InvoiceLineInner, ComputeError> {
-    let mut applicable_price_per_unit = Decimal::new(0, 0);
-    let mut applicable_flat_fee = Decimal::new(0, 0);
-    let mut applicable_flat_cap = Decimal::MAX;
-    let mut sorted_rows = tiers.to_owned();
+/// The usage is billed by whole blocks, so it is rounded up to the next multiple of the block size.
+/// The rates remain per unit
+fn round_up_to_block(usage_units: Decimal, block_size: &Option<u64>) -> Decimal {
+    match block_size {
+        Some(size) if *size > 0 => {
+            let size = Decimal::from(*size);
+            (usage_units / size).ceil() * size
+        }
+        _ => usage_units,
+    }
+}
+
+/// The tiers sorted by their first unit, with the first unit of the next tier (excluded from the tier)
+fn tiers_with_upper_bound(tiers: &[TierRow]) -> Vec<(&TierRow, Option<u64>)> {
+    let mut sorted_rows: Vec<&TierRow> = tiers.iter().collect();
     sorted_rows.sort_by_key(|r| r.first_unit);
 
-    let mut iter = sorted_rows.iter().peekable();
+    sorted_rows
+        .iter()
+        .enumerate()
+        .map(|(idx, tier)| (*tier, sorted_rows.get(idx + 1).map(|next| next.first_unit)))
+        .collect()
+}
 
-    let mut subline_attr = None;
+/// The units * rate + flat fee of a tier, limited to its flat cap
+fn capped_tier_fee(units: Decimal, tier: &TierRow) -> Decimal {
+    let fee = units * tier.rate + tier.flat_fee.unwrap_or(Decimal::ZERO);
 
-    // TODO block_size
-    while let Some(tier) = iter.next() {
-        let next_tier = iter.peek();
-        let last_unit: Option<u64> = next_tier.map(|row| row.first_unit - 1);
+    match tier.flat_cap {
+        Some(cap) if fee > cap => cap,
+        _ => fee,
+    }
+}
 
-        if usage_units >= Decimal::from(tier.first_unit)
-            && last_unit
-                .map(|l| usage_units <= Decimal::from(l))
-                .unwrap_or(true)
-        {
-            applicable_price_per_unit = tier.rate;
-            if let Some(flat_fee) = tier.flat_fee {
-                applicable_flat_fee = flat_fee;
-            }
-            if let Some(flat_cap) = tier.flat_cap {
-                applicable_flat_cap = flat_cap;
-            }
+/// Volume pricing: all the usage is priced at the tier it falls in
+pub fn volume_price(
+    usage_units: Decimal,
+    tiers: &[TierRow],
+    precision: u8,
+    block_size: &Option<u64>,
+) -> Result<TieredPrice, ComputeError> {
+    let usage_units = round_up_to_block(usage_units, block_size);
 
-            subline_attr = Some(SubLineAttributes::Volume {
+    let applicable_tier = tiers_with_upper_bound(tiers)
+        .into_iter()
+        .find(|(tier, upper)| {
+            usage_units >= Decimal::from(tier.first_unit)
+                && upper
+                    .map(|u| usage_units < Decimal::from(u))
+                    .unwrap_or(true)
+        });
+
+    // usage below the first tier is not priced
+    let (price, applicable_price_per_unit, subline_attr) = match applicable_tier {
+        Some((tier, upper)) => (
+            capped_tier_fee(usage_units, tier),
+            tier.rate,
+            Some(SubLineAttributes::Volume {
                 first_unit: tier.first_unit,
-                last_unit,
+                last_unit: upper.map(|u| u - 1),
                 flat_cap: tier.flat_cap,
                 flat_fee: tier.flat_fee,
-            });
-
-            break;
-        }
-    }
-    let price = usage_units * applicable_price_per_unit + applicable_flat_fee;
-
-    let price = if price > applicable_flat_cap {
-        applicable_flat_cap
-    } else {
-        price
+            }),
+        ),
+        None => (Decimal::ZERO, Decimal::ZERO, None),
     };
 
-    Ok(InvoiceLineInner {
-        quantity: Some(usage_units),
-        unit_price: None,
-        total: price
-            .to_subunit_opt(precision)
-            .ok_or(ComputeError::ConversionError)? as u64,
-        period,
-        custom_line_name: None,
-        is_prorated: false,
+    let total = price
+        .to_subunit_opt(precision)
+        .ok_or(ComputeError::ConversionError)?;
+
+    Ok(TieredPrice {
+        quantity: usage_units,
+        total,
         sublines: vec![SubLineItem {
             local_id: LocalId::no_prefix(),
             name: "Volume".to_string(),
-            total: price
-                .to_subunit_opt(precision)
-                .ok_or(ComputeError::ConversionError)?,
+            total,
             quantity: usage_units,
             unit_price: applicable_price_per_unit,
             attributes: subline_attr,
@@ -80,88 +92,484 @@ pub fn compute_volume_price(
     })
 }
 
+/// Graduated pricing: each tier prices the units falling in it, its flat cap limiting its own fee
+pub fn graduated_price(
+    usage_units: Decimal,
+    tiers: &[TierRow],
+    precision: u8,
+    block_size: &Option<u64>,
+) -> Result<TieredPrice, ComputeError> {
+    let usage_units = round_up_to_block(usage_units, block_size);
+
+    let mut subtotal = Decimal::ZERO;
+    let mut sub_lines = Vec::new();
+
+    for (tier, last_unit) in tiers_with_upper_bound(tiers) {
+        let first_unit = Decimal::from(tier.first_unit);
+
+        if usage_units <= first_unit {
+            break;
+        }
+
+        let units_in_this_tier = match last_unit {
+            Some(last_unit) => usage_units.min(Decimal::from(last_unit)) - first_unit,
+            None => usage_units - first_unit,
+        };
+
+        if units_in_this_tier <= Decimal::ZERO {
+            continue;
+        }
+
+        let fee = capped_tier_fee(units_in_this_tier, tier);
+        subtotal += fee;
+
+        sub_lines.push(SubLineItem {
+            local_id: LocalId::no_prefix(),
+            name: format!(
+                "{}-{} tier",
+                tier.first_unit,
+                last_unit.map(|s| s.to_string()).unwrap_or("∞".to_string())
+            ),
+            total: fee
+                .to_subunit_opt(precision)
+                .ok_or(ComputeError::ConversionError)?,
+            quantity: units_in_this_tier,
+            unit_price: tier.rate,
+            attributes: Some(SubLineAttributes::Tiered {
+                first_unit: tier.first_unit,
+                last_unit,
+                flat_cap: tier.flat_cap,
+                flat_fee: tier.flat_fee,
+            }),
+        });
+    }
+
+    Ok(TieredPrice {
+        quantity: usage_units,
+        total: subtotal
+            .to_subunit_opt(precision)
+            .ok_or(ComputeError::ConversionError)?,
+        sublines: sub_lines,
+    })
+}
+
+pub fn compute_volume_price(
+    usage_units: Decimal,
+    tiers: &[TierRow],
+    period: Period,
+    precision: u8,
+    block_size: &Option<u64>,
+) -> Result<InvoiceLineInner, ComputeError> {
+    volume_price(usage_units, tiers, precision, block_size)?.into_line(period)
+}
+
 pub fn compute_tier_price(
     usage_units: Decimal,
     tiers: &[TierRow],
     period: Period,
     precision: u8,
-    _block_size: &Option<u64>,
+    block_size: &Option<u64>,
 ) -> Result<InvoiceLineInner, ComputeError> {
-    let mut subtotal = Decimal::new(0, 0);
-    let mut remaining_usage = usage_units;
+    graduated_price(usage_units, tiers, precision, block_size)?.into_line(period)
+}
 
-    let mut sorted_rows = tiers.to_owned();
-    sorted_rows.sort_by_key(|r| r.first_unit);
+/// Prices each usage with the same tiers as graduated and as volume
+pub fn preview_tiered_pricing(
+    usages: &[Decimal],
+    tiers: &[TierRow],
+    precision: u8,
+    block_size: &Option<u64>,
+) -> Result<Vec<TieredPricingPreview>, ComputeError> {
+    usages
+        .iter()
+        .map(|usage| {
+            Ok(TieredPricingPreview {
+                usage: *usage,
+                graduated: graduated_price(*usage, tiers, precision, block_size)?,
+                volume: volume_price(*usage, tiers, precision, block_size)?,
+            })
+        })
+        .collect()
+}
 
-    let mut iter = sorted_rows.iter().peekable();
+impl TieredPrice {
+    fn into_line(self, period: Period) -> Result<InvoiceLineInner, ComputeError> {
+        Ok(InvoiceLineInner {
+            quantity: Some(self.quantity),
+            unit_price: None,
+            total: u64::try_from(self.total).map_err(|_| ComputeError::ConversionError)?,
+            period,
+            custom_line_name: None,
+            is_prorated: false,
+            sublines: self.sublines,
+        })
+    }
+}
 
-    let mut sub_lines = Vec::new();
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
 
-    while let Some(tier) = iter.next() {
-        if remaining_usage.is_zero() {
-            break;
+    fn period() -> Period {
+        Period {
+            start: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            end: NaiveDate::from_ymd_opt(2024, 2, 1).unwrap(),
         }
+    }
 
-        let next_tier = iter.peek();
-        let last_unit: Option<u64> = next_tier.map(|row| row.first_unit);
+    fn tier(
+        first_unit: u64,
+        rate: Decimal,
+        flat_fee: Option<Decimal>,
+        flat_cap: Option<Decimal>,
+    ) -> TierRow {
+        TierRow {
+            first_unit,
+            rate,
+            flat_fee,
+            flat_cap,
+        }
+    }
 
-        let tier_units = match last_unit {
-            Some(last_unit) => Decimal::from(last_unit.saturating_sub(tier.first_unit)),
-            None => Decimal::MAX, // Handle infinite tier
-        };
+    // 0-100 at 1, 100-1000 at 0.5 + 10 capped at 50, 1000+ at 0.1
+    fn tiers() -> Vec<TierRow> {
+        vec![
+            tier(1000, dec!(0.1), None, None),
+            tier(0, dec!(1), None, None),
+            tier(100, dec!(0.5), Some(dec!(10)), Some(dec!(50))),
+        ]
+    }
 
-        let units_in_this_tier = if remaining_usage > tier_units {
-            tier_units
-        } else {
-            remaining_usage
-        };
+    fn tiered(usage: Decimal, block_size: Option<u64>) -> InvoiceLineInner {
+        compute_tier_price(usage, &tiers(), period(), 2, &block_size).unwrap()
+    }
+
+    fn volume(usage: Decimal, block_size: Option<u64>) -> InvoiceLineInner {
+        compute_volume_price(usage, &tiers(), period(), 2, &block_size).unwrap()
+    }
+
+    #[test]
+    fn test_tiered_boundaries() {
+        let line = tiered(dec!(0), None);
+        assert_eq!(line.total, 0);
+        assert!(line.sublines.is_empty());
+
+        assert_eq!(tiered(dec!(1), None).total, 100);
+        assert_eq!(tiered(dec!(99.5), None).total, 9950);
+
+        // the last unit of a tier is the first unit of the next one
+        let line = tiered(dec!(100), None);
+        assert_eq!(line.total, 10000);
+        assert_eq!(line.sublines.len(), 1);
+        assert_eq!(line.sublines[0].name, "0-100 tier");
+
+        // the flat fee is charged from the first unit in the tier
+        let line = tiered(dec!(101), None);
+        assert_eq!(line.total, 11050);
+        assert_eq!(line.sublines.len(), 2);
+        assert_eq!(line.sublines[1].quantity, dec!(1));
+        assert_eq!(line.sublines[1].total, 1050);
+
+        let line = tiered(dec!(1001), None);
+        assert_eq!(line.total, 15010);
+        assert_eq!(line.sublines.len(), 3);
+        assert_eq!(line.sublines[2].name, "1000-∞ tier");
+        assert_eq!(line.sublines[2].quantity, dec!(1));
+    }
+
+    #[test]
+    fn test_tiered_flat_cap() {
+        // 80 * 0.5 + 10 reaches the cap of the tier exactly
+        assert_eq!(tiered(dec!(180), None).sublines[1].total, 5000);
+
+        let line = tiered(dec!(181), None);
+        assert_eq!(line.total, 15000);
+        assert_eq!(line.sublines[1].total, 5000);
+        assert_eq!(line.sublines[1].quantity, dec!(81));
+
+        // the cap of a tier does not limit the next ones
+        let line = tiered(dec!(2000), None);
+        assert_eq!(line.total, 25000);
+        assert_eq!(line.sublines[1].total, 5000);
+        assert_eq!(line.sublines[2].total, 10000);
+
+        // a flat fee above the cap is capped as well
+        let tiers = vec![tier(0, dec!(1), Some(dec!(20)), Some(dec!(15)))];
+        let line = compute_tier_price(dec!(1), &tiers, period(), 2, &None).unwrap();
+        assert_eq!(line.total, 1500);
+    }
+
+    #[test]
+    fn test_tiered_block_size() {
+        // rounded up to 110 units
+        let line = tiered(dec!(101), Some(10));
+        assert_eq!(line.quantity, Some(dec!(110)));
+        assert_eq!(line.total, 11500);
+        assert_eq!(line.sublines[1].quantity, dec!(10));
+
+        assert_eq!(tiered(dec!(100), Some(10)).total, 10000);
+        assert_eq!(tiered(dec!(0.5), Some(10)).total, 1000);
+        assert_eq!(tiered(dec!(0), Some(10)).total, 0);
+        assert_eq!(tiered(dec!(101), Some(1)).total, 11050);
+    }
+
+    #[test]
+    fn test_tiered_usage_below_first_tier() {
+        let tiers = vec![tier(10, dec!(1), Some(dec!(5)), None)];
+
+        let line = compute_tier_price(dec!(10), &tiers, period(), 2, &None).unwrap();
+        assert_eq!(line.total, 0);
+        assert!(line.sublines.is_empty());
+
+        let line = compute_tier_price(dec!(15), &tiers, period(), 2, &None).unwrap();
+        assert_eq!(line.total, 1000);
+        assert_eq!(line.sublines[0].quantity, dec!(5));
+    }
+
+    #[test]
+    fn test_volume_boundaries() {
+        assert_eq!(volume(dec!(0), None).total, 0);
+        assert_eq!(volume(dec!(20), None).total, 2000);
+
+        // between the last unit of a tier and the first unit of the next one
+        let line = volume(dec!(99.5), None);
+        assert_eq!(line.total, 9950);
+        assert_eq!(line.sublines[0].unit_price, dec!(1));
+
+        let line = volume(dec!(100), None);
+        assert_eq!(line.sublines[0].unit_price, dec!(0.5));
+        assert!(matches!(
+            line.sublines[0].attributes,
+            Some(SubLineAttributes::Volume {
+                first_unit: 100,
+                last_unit: Some(999),
+                ..
+            })
+        ));
+
+        let line = volume(dec!(1000), None);
+        assert_eq!(line.total, 10000);
+        assert!(matches!(
+            line.sublines[0].attributes,
+            Some(SubLineAttributes::Volume {
+                first_unit: 1000,
+                last_unit: None,
+                ..
+            })
+        ));
+    }
+
+    #[test]
+    fn test_volume_flat_cap() {
+        assert_eq!(volume(dec!(100), None).total, 5000);
+        assert_eq!(volume(dec!(999), None).total, 5000);
+
+        let tiers = vec![tier(0, dec!(0.5), Some(dec!(10)), Some(dec!(50)))];
+        let volume = |usage| compute_volume_price(usage, &tiers, period(), 2, &None).unwrap();
+
+        // 60 * 0.5 + 10 is below the cap
+        assert_eq!(volume(dec!(60)).total, 4000);
+        // 80 * 0.5 + 10 reaches it
+        assert_eq!(volume(dec!(80)).total, 5000);
+        assert_eq!(volume(dec!(81)).total, 5000);
+    }
+
+    #[test]
+    fn test_volume_block_size() {
+        // rounded up to 100 units, in the capped tier
+        let line = volume(dec!(95), Some(10));
+        assert_eq!(line.quantity, Some(dec!(100)));
+        assert_eq!(line.total, 5000);
+
+        assert_eq!(volume(dec!(90), Some(10)).total, 9000);
+        assert_eq!(volume(dec!(995), Some(10)).total, 10000);
+    }
+
+    #[test]
+    fn test_volume_usage_below_first_tier() {
+        let tiers = vec![tier(10, dec!(1), Some(dec!(5)), None)];
+
+        let line = compute_volume_price(dec!(5), &tiers, period(), 2, &None).unwrap();
+        assert_eq!(line.total, 0);
+        assert!(line.sublines[0].attributes.is_none());
+
+        let line = compute_volume_price(dec!(10), &tiers, period(), 2, &None).unwrap();
+        assert_eq!(line.total, 1500);
+    }
+
+    /// Deterministic generator for the property tests, no need for a dedicated crate
+    struct Lcg(u64);
+
+    impl Lcg {
+        fn next(&mut self, bound: u64) -> u64 {
+            self.0 = self
+                .0
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (self.0 >> 33) % bound
+        }
+
+        fn amount(&mut self) -> Decimal {
+            Decimal::new(self.next(10_000) as i64, 2)
+        }
+
+        /// Tiers starting at 0, with rates not increasing from a tier to the next when `degressive`
+        fn tiers(&mut self, degressive: bool, with_flat: bool) -> Vec<TierRow> {
+            let mut first_unit = 0;
+            let mut rate = self.amount();
+
+            (0..1 + self.next(5))
+                .map(|_| {
+                    let row = tier(
+                        first_unit,
+                        rate,
+                        with_flat
+                            .then(|| self.amount())
+                            .filter(|_| self.next(2) == 0),
+                        with_flat
+                            .then(|| self.amount())
+                            .filter(|_| self.next(2) == 0),
+                    );
+                    first_unit += 1 + self.next(500);
+                    rate = if degressive {
+                        rate - Decimal::new(self.next(100) as i64, 2).min(rate)
+                    } else {
+                        self.amount()
+                    };
+                    row
+                })
+                .collect()
+        }
+    }
 
-        let tier_price = tier.rate;
+    const CASES: usize = 500;
 
-        if units_in_this_tier > Decimal::ZERO {
-            let mut fee = units_in_this_tier * tier_price;
-            if let Some(flat_fee) = tier.flat_fee {
-                fee += flat_fee;
+    #[test]
+    fn prop_single_tier_models_agree() {
+        let mut rng = Lcg(1);
+        for _ in 0..CASES {
+            let tiers = vec![tier(
+                0,
+                rng.amount(),
+                Some(rng.amount()).filter(|_| rng.next(2) == 0),
+                Some(rng.amount()).filter(|_| rng.next(2) == 0),
+            )];
+            let usage = Decimal::from(rng.next(5000));
+
+            let graduated = graduated_price(usage, &tiers, 2, &None).unwrap();
+            let volume = volume_price(usage, &tiers, 2, &None).unwrap();
+
+            if usage.is_zero() {
+                // no unit falls in the tier when graduated
+                assert_eq!(graduated.total, 0);
+            } else {
+                assert_eq!(graduated.total, volume.total, "{:?} at {}", tiers, usage);
             }
-            if let Some(cap) = tier.flat_cap {
-                if fee > cap {
-                    fee = cap;
-                }
+        }
+    }
+
+    #[test]
+    fn prop_models_agree_within_first_tier() {
+        let mut rng = Lcg(2);
+        for _ in 0..CASES {
+            let tiers = rng.tiers(false, true);
+            let upper = tiers.get(1).map(|t| t.first_unit).unwrap_or(10_000);
+            let usage = Decimal::from(1 + rng.next(upper));
+
+            if usage >= Decimal::from(upper) {
+                continue;
             }
-            subtotal += fee;
-
-            sub_lines.push(SubLineItem {
-                local_id: LocalId::no_prefix(),
-                name: format!(
-                    "{}-{} tier",
-                    tier.first_unit,
-                    last_unit.map(|s| s.to_string()).unwrap_or("∞".to_string())
-                ),
-                total: fee
-                    .to_subunit_opt(precision)
-                    .ok_or(ComputeError::ConversionError)?,
-                quantity: units_in_this_tier,
-                unit_price: tier_price,
-                attributes: Some(SubLineAttributes::Tiered {
-                    first_unit: tier.first_unit,
-                    last_unit,
-                    flat_cap: tier.flat_cap,
-                    flat_fee: tier.flat_fee,
-                }),
-            });
+
+            assert_eq!(
+                graduated_price(usage, &tiers, 2, &None).unwrap().total,
+                volume_price(usage, &tiers, 2, &None).unwrap().total,
+                "{:?} at {}",
+                tiers,
+                usage
+            );
         }
-        remaining_usage -= units_in_this_tier;
     }
 
-    Ok(InvoiceLineInner {
-        quantity: Some(usage_units),
-        unit_price: None,
-        total: subtotal
-            .to_subunit_opt(precision)
-            .ok_or(ComputeError::ConversionError)? as u64,
-        period,
-        custom_line_name: None,
-        is_prorated: false,
-        sublines: sub_lines,
-    })
+    #[test]
+    fn prop_graduated_sublines_add_up() {
+        let mut rng = Lcg(3);
+        for _ in 0..CASES {
+            let tiers = rng.tiers(false, true);
+            let block_size = Some(1 + rng.next(20)).filter(|_| rng.next(2) == 0);
+            let usage = Decimal::from(rng.next(3000));
+
+            let graduated = graduated_price(usage, &tiers, 2, &block_size).unwrap();
+
+            assert_eq!(
+                graduated.sublines.iter().map(|s| s.total).sum::<i64>(),
+                graduated.total
+            );
+            assert_eq!(
+                graduated
+                    .sublines
+                    .iter()
+                    .map(|s| s.quantity)
+                    .sum::<Decimal>(),
+                graduated.quantity
+            );
+            assert!(graduated.quantity >= usage);
+        }
+    }
+
+    #[test]
+    fn prop_graduated_is_monotonic() {
+        let mut rng = Lcg(4);
+        for _ in 0..CASES {
+            let tiers = rng.tiers(false, true);
+            let usage = Decimal::from(rng.next(3000));
+            let more = usage + Decimal::from(1 + rng.next(500));
+
+            assert!(
+                graduated_price(usage, &tiers, 2, &None).unwrap().total
+                    <= graduated_price(more, &tiers, 2, &None).unwrap().total,
+                "{:?} at {} and {}",
+                tiers,
+                usage,
+                more
+            );
+        }
+    }
+
+    #[test]
+    fn prop_volume_is_cheaper_with_degressive_rates() {
+        let mut rng = Lcg(5);
+        for _ in 0..CASES {
+            let tiers = rng.tiers(true, false);
+            let usage = Decimal::from(rng.next(3000));
+
+            assert!(
+                volume_price(usage, &tiers, 2, &None).unwrap().total
+                    <= graduated_price(usage, &tiers, 2, &None).unwrap().total,
+                "{:?} at {}",
+                tiers,
+                usage
+            );
+        }
+    }
+
+    #[test]
+    fn prop_block_size_prices_the_rounded_usage() {
+        let mut rng = Lcg(6);
+        for _ in 0..CASES {
+            let tiers = rng.tiers(false, true);
+            let block_size = 1 + rng.next(50);
+            let usage = Decimal::new(rng.next(300_000) as i64, 2);
+            let rounded = (usage / Decimal::from(block_size)).ceil() * Decimal::from(block_size);
+
+            for price in [graduated_price, volume_price] {
+                let blocks = price(usage, &tiers, 2, &Some(block_size)).unwrap();
+                let units = price(rounded, &tiers, 2, &None).unwrap();
+
+                assert_eq!(blocks.quantity, rounded);
+                assert_eq!(blocks.total, units.total);
+            }
+        }
+    }
 }
@@ -3,12 +3,25 @@ use std::sync::Arc;
 use super::period::calculate_component_period;
 use crate::compute::engine::component::ComponentEngine;
 use crate::compute::errors::ComputeError;
+use crate::compute::proration;
 use crate::constants::Currency;
+use crate::domain::entitlements::Entitlement;
+use crate::domain::enums::ProrationRoundingEnum;
+use crate::domain::invoice_cadences::fee_cadence;
+use crate::domain::subscription_component_history::components_at;
+use crate::domain::subscription_phases::apply_phases;
+use crate::domain::usage_caps::UsageCapCharges;
 use crate::domain::*;
+use crate::repositories::subscription_component_history::SubscriptionComponentHistoryInterface;
 use crate::repositories::TenantInterface;
+use crate::utils::decimals::ToSubunit;
+use crate::utils::local_id::LocalId;
+use crate::utils::periods::calculate_periods_for_date;
 use crate::Store;
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveTime};
 use itertools::Itertools;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
 
 #[async_trait::async_trait]
 pub trait InvoiceLineInterface {
@@ -17,6 +30,28 @@ pub trait InvoiceLineInterface {
         invoice_date: &NaiveDate,
         subscription_details: &SubscriptionDetails,
     ) -> Result<Vec<LineItem>, ComputeError>;
+
+    /// Usage accrued in the current period of the usage-based components until `until` (excluded),
+    /// not yet billed by a threshold invoice
+    async fn compute_accrued_usage_lines(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<LineItem>, ComputeError>;
+
+    /// Charges of the capped fees in their current period until `until` (excluded), before the caps apply
+    async fn compute_usage_cap_charges(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<UsageCapCharges>, ComputeError>;
+
+    /// Allowances of the usage and capacity fees in their current period until `until` (excluded)
+    async fn compute_entitlements(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<Entitlement>, ComputeError>;
 }
 
 #[async_trait::async_trait]
@@ -38,60 +73,451 @@ impl InvoiceLineInterface for Store {
             .await
             .map_err(|_| ComputeError::InternalError)?;
 
-        let billing_start_date = subscription_details.billing_start_date;
-        let billing_day = subscription_details.billing_day;
+        let proration_rounding = self
+            .get_proration_rounding_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
         let invoice_date = *invoice_date;
+        let subscription_at_date =
+            &subscription_at(self, subscription_details, invoice_date).await?;
+
+        let mut invoice_lines = compute_fee_lines(
+            self,
+            subscription_at_date,
+            invoice_date,
+            &currency,
+            proration_rounding,
+        )
+        .await?;
+
+        // the fees of the closed period billed in advance were on the invoice opening it
+        let opening_lines = match commitment_closed_period(subscription_at_date, invoice_date) {
+            Some(closed) => {
+                compute_fee_lines(
+                    self,
+                    &subscription_at(self, subscription_details, closed.start).await?,
+                    closed.start,
+                    &currency,
+                    proration_rounding,
+                )
+                .await?
+            }
+            None => vec![],
+        };
+
+        // the commitment is measured on the standard pricing, so the promotions are not trued-up
+        let true_up = minimum_commitment_line(
+            subscription_at_date,
+            invoice_date,
+            &opening_lines,
+            &invoice_lines,
+            currency.precision,
+            proration_rounding,
+        )?;
+
+        apply_phases(
+            &mut invoice_lines,
+            &subscription_at_date.phases,
+            currency.precision,
+        );
+
+        invoice_lines.extend(true_up);
+
+        Ok(invoice_lines)
+    }
+
+    async fn compute_accrued_usage_lines(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<LineItem>, ComputeError> {
+        let currency = self
+            .get_reporting_currency_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
+        let proration_rounding = self
+            .get_proration_rounding_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
+        let subscription_details = &subscription_at(self, subscription_details, *until).await?;
 
         let component_engine = ComponentEngine::new(
             self.usage_client.clone(),
-            Arc::new(self.clone()), // TODO just use store
+            Arc::new(self.clone()),
             Arc::new(subscription_details.clone()),
+            proration_rounding,
         );
 
-        let price_components_lines = compute_invoice_lines(
+        let mut invoice_lines = compute_accrued_usage(
             &component_engine,
             &subscription_details.price_components,
-            billing_start_date,
-            billing_day,
-            invoice_date,
+            subscription_details,
+            *until,
             &currency,
         )
         .await?;
 
-        let add_ons_lines = compute_invoice_lines(
+        invoice_lines.extend(
+            compute_accrued_usage(
+                &component_engine,
+                &subscription_details.add_ons,
+                subscription_details,
+                *until,
+                &currency,
+            )
+            .await?,
+        );
+
+        apply_phases(
+            &mut invoice_lines,
+            &subscription_details.phases,
+            currency.precision,
+        );
+
+        Ok(invoice_lines)
+    }
+
+    async fn compute_usage_cap_charges(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<UsageCapCharges>, ComputeError> {
+        let currency = self
+            .get_reporting_currency_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
+        let proration_rounding = self
+            .get_proration_rounding_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
+        let subscription_details = &subscription_at(self, subscription_details, *until).await?;
+
+        let component_engine = ComponentEngine::new(
+            self.usage_client.clone(),
+            Arc::new(self.clone()),
+            Arc::new(subscription_details.clone()),
+            proration_rounding,
+        );
+
+        let mut charges = compute_cap_charges(
             &component_engine,
-            &subscription_details.add_ons,
-            billing_start_date,
-            billing_day,
-            invoice_date,
+            &subscription_details.price_components,
+            subscription_details,
+            *until,
             &currency,
         )
         .await?;
 
-        let invoice_lines = price_components_lines
-            .into_iter()
-            .chain(add_ons_lines)
-            .collect();
+        charges.extend(
+            compute_cap_charges(
+                &component_engine,
+                &subscription_details.add_ons,
+                subscription_details,
+                *until,
+                &currency,
+            )
+            .await?,
+        );
 
-        Ok(invoice_lines)
+        Ok(charges)
+    }
+
+    async fn compute_entitlements(
+        &self,
+        until: &NaiveDate,
+        subscription_details: &SubscriptionDetails,
+    ) -> Result<Vec<Entitlement>, ComputeError> {
+        let proration_rounding = self
+            .get_proration_rounding_by_tenant_id(subscription_details.tenant_id)
+            .await
+            .map_err(|_| ComputeError::InternalError)?;
+
+        let subscription_details = &subscription_at(self, subscription_details, *until).await?;
+
+        let component_engine = ComponentEngine::new(
+            self.usage_client.clone(),
+            Arc::new(self.clone()),
+            Arc::new(subscription_details.clone()),
+            proration_rounding,
+        );
+
+        let mut entitlements = compute_fee_entitlements(
+            &component_engine,
+            &subscription_details.price_components,
+            subscription_details,
+            *until,
+        )
+        .await?;
+
+        entitlements.extend(
+            compute_fee_entitlements(
+                &component_engine,
+                &subscription_details.add_ons,
+                subscription_details,
+                *until,
+            )
+            .await?,
+        );
+
+        Ok(entitlements)
     }
 }
 
+/// The lines of the price components and add-ons of the invoice dated `invoice_date`, before the promotions
+async fn compute_fee_lines(
+    store: &Store,
+    subscription_details: &SubscriptionDetails,
+    invoice_date: NaiveDate,
+    currency: &Currency,
+    proration_rounding: ProrationRoundingEnum,
+) -> Result<Vec<LineItem>, ComputeError> {
+    let billing_start_date = subscription_details.billing_start_date;
+    let billing_day = subscription_details.anchor_day();
+    let threshold_invoiced_until = subscription_details.threshold_invoiced_until;
+
+    let component_engine = ComponentEngine::new(
+        store.usage_client.clone(),
+        Arc::new(store.clone()), // TODO just use store
+        Arc::new(subscription_details.clone()),
+        proration_rounding,
+    );
+
+    let price_components_lines = compute_invoice_lines(
+        &component_engine,
+        &subscription_details.price_components,
+        billing_start_date,
+        billing_day,
+        invoice_date,
+        threshold_invoiced_until,
+        currency,
+    )
+    .await?;
+
+    let add_ons_lines = compute_invoice_lines(
+        &component_engine,
+        &subscription_details.add_ons,
+        billing_start_date,
+        billing_day,
+        invoice_date,
+        threshold_invoiced_until,
+        currency,
+    )
+    .await?;
+
+    Ok(price_components_lines
+        .into_iter()
+        .chain(add_ons_lines)
+        .collect())
+}
+
+/// The subscription with the component fees in effect on `date`, as changes may have been applied to the components since
+async fn subscription_at(
+    store: &Store,
+    subscription_details: &SubscriptionDetails,
+    date: NaiveDate,
+) -> Result<SubscriptionDetails, ComputeError> {
+    let history = store
+        .list_subscription_component_history(
+            subscription_details.tenant_id,
+            subscription_details.id,
+        )
+        .await
+        .map_err(|_| ComputeError::InternalError)?;
+
+    Ok(SubscriptionDetails {
+        price_components: components_at(
+            &subscription_details.price_components,
+            &history,
+            date.and_time(NaiveTime::MIN),
+        ),
+        ..subscription_details.clone()
+    })
+}
+
+async fn compute_accrued_usage<T: SubscriptionFeeInterface>(
+    component_engine: &ComponentEngine,
+    fee_records: &[T],
+    subscription_details: &SubscriptionDetails,
+    until: NaiveDate,
+    currency: &Currency,
+) -> Result<Vec<LineItem>, ComputeError> {
+    let mut invoice_lines = Vec::new();
+
+    for component in fee_records {
+        if !matches!(
+            component.fee_ref(),
+            SubscriptionFee::Usage { .. } | SubscriptionFee::Percent { .. }
+        ) {
+            continue;
+        }
+
+        let Some(billing_period) = component.period_ref().as_billing_period_opt() else {
+            continue;
+        };
+
+        let current = calculate_periods_for_date(
+            subscription_details.billing_start_date,
+            subscription_details.anchor_day(),
+            until,
+            &billing_period,
+        )
+        .advance;
+
+        let accrued = Period {
+            start: subscription_details
+                .threshold_invoiced_until
+                .map_or(current.start, |d| d.max(current.start)),
+            end: until,
+        };
+
+        if accrued.start >= accrued.end {
+            continue;
+        }
+
+        let periods = ComponentPeriods {
+            arrear: Some(accrued),
+            advance: current,
+            proration_factor: None,
+        };
+
+        let lines = component_engine
+            .compute_component(component, periods, &until, currency.precision)
+            .await?;
+        invoice_lines.extend(lines);
+    }
+
+    Ok(invoice_lines)
+}
+
+async fn compute_cap_charges<T: SubscriptionFeeInterface>(
+    component_engine: &ComponentEngine,
+    fee_records: &[T],
+    subscription_details: &SubscriptionDetails,
+    until: NaiveDate,
+    currency: &Currency,
+) -> Result<Vec<UsageCapCharges>, ComputeError> {
+    let mut charges = Vec::new();
+
+    for component in fee_records {
+        let Some(cap) = component.fee_ref().cap() else {
+            continue;
+        };
+
+        let Some(billing_period) = component.period_ref().as_billing_period_opt() else {
+            continue;
+        };
+
+        let current = calculate_periods_for_date(
+            subscription_details.billing_start_date,
+            subscription_details.anchor_day(),
+            until,
+            &billing_period,
+        )
+        .advance;
+
+        let accrued = Period {
+            start: current.start,
+            end: until.min(current.end),
+        };
+
+        if accrued.start >= accrued.end {
+            continue;
+        }
+
+        let Some(uncapped) = component_engine
+            .compute_uncapped_charges(component, accrued, currency.precision)
+            .await?
+        else {
+            continue;
+        };
+
+        charges.push(UsageCapCharges {
+            fee_id: component.id(),
+            name: component.name_ref().clone(),
+            metric_id: component.fee_ref().metric_id(),
+            period: current,
+            cap: cap
+                .to_subunit_opt(currency.precision)
+                .ok_or(ComputeError::ConversionError)?,
+            charges: uncapped as i64,
+        });
+    }
+
+    Ok(charges)
+}
+
+async fn compute_fee_entitlements<T: SubscriptionFeeInterface>(
+    component_engine: &ComponentEngine,
+    fee_records: &[T],
+    subscription_details: &SubscriptionDetails,
+    until: NaiveDate,
+) -> Result<Vec<Entitlement>, ComputeError> {
+    let mut entitlements = Vec::new();
+
+    for component in fee_records {
+        let Some(metric_id) = component.fee_ref().metric_id() else {
+            continue;
+        };
+
+        let Some(billing_period) = component.period_ref().as_billing_period_opt() else {
+            continue;
+        };
+
+        let current = calculate_periods_for_date(
+            subscription_details.billing_start_date,
+            subscription_details.anchor_day(),
+            until,
+            &billing_period,
+        )
+        .advance;
+
+        let accrued = Period {
+            start: current.start,
+            end: until.min(current.end),
+        };
+
+        if accrued.start >= accrued.end {
+            continue;
+        }
+
+        let Some(allowance) = component_engine
+            .compute_allowance(component, accrued)
+            .await?
+        else {
+            continue;
+        };
+
+        entitlements.push(Entitlement {
+            fee_id: component.id(),
+            name: component.name_ref().clone(),
+            metric_id,
+            period: current,
+            included: allowance.included,
+            consumed: allowance.consumed,
+        });
+    }
+
+    Ok(entitlements)
+}
+
 async fn compute_invoice_lines<T: SubscriptionFeeInterface>(
     component_engine: &ComponentEngine,
     fee_records: &[T],
     billing_start_date: NaiveDate,
-    billing_day: i16,
+    billing_day: u32,
     invoice_date: NaiveDate,
+    threshold_invoiced_until: Option<NaiveDate>,
     currency: &Currency,
 ) -> Result<Vec<LineItem>, ComputeError> {
     let component_groups = fee_records
         .iter()
         .into_group_map_by(|c| c.period_ref().clone());
 
-    // TODO case when invoiced early via threshold (that's for usage-based only)
-    // can be quite easy => we need some last_invoice_threshold date in the subscription, to reduce the usage periods if that date is within the period
-
     let component_period_components: Vec<(ComponentPeriods, Vec<&T>)> = component_groups
         .into_iter()
         .filter_map(|(billing_period, components)| {
@@ -102,7 +528,7 @@ async fn compute_invoice_lines<T: SubscriptionFeeInterface>(
             // - else : invoice date is not the billing start date. We consider advance and arrear fees. No proration to apply
             let period = calculate_component_period(
                 billing_start_date,
-                billing_day as u32,
+                billing_day,
                 invoice_date,
                 &billing_period,
             );
@@ -115,8 +541,14 @@ async fn compute_invoice_lines<T: SubscriptionFeeInterface>(
     let mut invoice_lines = Vec::new();
     for (period, components) in component_period_components {
         for component in components {
+            let period = match component.fee_ref() {
+                SubscriptionFee::Usage { .. } | SubscriptionFee::Percent { .. } => {
+                    exclude_threshold_invoiced(period.clone(), threshold_invoiced_until)
+                }
+                _ => period.clone(),
+            };
             let lines = component_engine
-                .compute_component(component, period.clone(), &invoice_date, currency.precision)
+                .compute_component(component, period, &invoice_date, currency.precision)
                 .await?;
             invoice_lines.extend(lines);
         }
@@ -124,3 +556,254 @@ async fn compute_invoice_lines<T: SubscriptionFeeInterface>(
 
     Ok(invoice_lines)
 }
+
+/// The subscription period closed by the invoice dated `invoice_date`, if the subscription has a minimum commitment
+/// to true-up on that invoice. When the invoices are split per cadence, only the invoice of the subscription period is trued-up
+fn commitment_closed_period(
+    subscription_details: &SubscriptionDetails,
+    invoice_date: NaiveDate,
+) -> Option<Period> {
+    subscription_details.minimum_commitment?;
+
+    let period = &subscription_details.period;
+
+    let bills_period_fees = subscription_details
+        .price_components
+        .iter()
+        .map(|c| c.period_ref())
+        .chain(subscription_details.add_ons.iter().map(|a| a.period_ref()))
+        .any(|p| fee_cadence(p, period) == *period);
+
+    if !bills_period_fees {
+        return None;
+    }
+
+    let periods = calculate_periods_for_date(
+        subscription_details.billing_start_date,
+        subscription_details.anchor_day(),
+        invoice_date,
+        period,
+    );
+
+    periods
+        .arrear
+        .filter(|_| periods.advance.start == invoice_date)
+}
+
+/// True-up of the minimum commitment, billed on the invoice closing a subscription period.
+/// The spend of the period is the total of the lines within the closed period: its recurring fees billed in advance
+/// on the invoice opening it (`opening_lines`), and its usage and arrear fees billed on the closing invoice (`lines`).
+/// A partial first period commits to a prorated amount
+fn minimum_commitment_line(
+    subscription_details: &SubscriptionDetails,
+    invoice_date: NaiveDate,
+    opening_lines: &[LineItem],
+    lines: &[LineItem],
+    precision: u8,
+    rounding: ProrationRoundingEnum,
+) -> Result<Option<LineItem>, ComputeError> {
+    let (Some(commitment), Some(closed)) = (
+        subscription_details.minimum_commitment,
+        commitment_closed_period(subscription_details, invoice_date),
+    ) else {
+        return Ok(None);
+    };
+
+    let period = &subscription_details.period;
+
+    let commitment_cents = commitment
+        .to_subunit_opt(precision)
+        .ok_or(ComputeError::ConversionError)?;
+
+    let proration_factor = calculate_periods_for_date(
+        subscription_details.billing_start_date,
+        subscription_details.anchor_day(),
+        closed.start,
+        period,
+    )
+    .proration_factor;
+
+    let commitment_cents = match proration_factor {
+        Some(factor) => proration::prorate_with_factor(
+            commitment_cents,
+            Decimal::from_f64(factor).ok_or(ComputeError::ConversionError)?,
+            rounding,
+        )?,
+        None => commitment_cents,
+    };
+
+    let spend_lines: Vec<LineItem> = opening_lines.iter().chain(lines).cloned().collect();
+
+    Ok(true_up_line(
+        commitment_cents,
+        closed,
+        &spend_lines,
+        proration_factor.is_some_and(|f| f < 1.0),
+    ))
+}
+
+/// The line billing the difference between the committed amount and the total of the lines within `period`,
+/// if below the commitment. The fees billed in advance for the next period do not count toward the closed one,
+/// the ones billed in advance for `period` do
+fn true_up_line(
+    commitment_cents: i64,
+    period: Period,
+    lines: &[LineItem],
+    is_prorated: bool,
+) -> Option<LineItem> {
+    let spend: i64 = lines
+        .iter()
+        .filter(|l| l.start_date >= period.start && l.end_date <= period.end)
+        .map(|l| l.total)
+        .sum();
+    let true_up = commitment_cents - spend;
+
+    if true_up <= 0 {
+        return None;
+    }
+
+    Some(LineItem {
+        local_id: LocalId::no_prefix(),
+        name: "Minimum commitment true-up".to_string(),
+        total: true_up,
+        subtotal: true_up,
+        quantity: None,
+        unit_price: None,
+        start_date: period.start,
+        end_date: period.end,
+        sub_lines: vec![],
+        is_prorated,
+        price_component_id: None,
+        product_id: None,
+        metric_id: None,
+        description: None,
+        quantity_display_precision: None,
+    })
+}
+
+/// Usage invoiced early, when crossing the invoice threshold, is removed from the arrear period.
+/// A threshold invoice only covers the period it was issued in, so earlier periods are left untouched.
+fn exclude_threshold_invoiced(
+    periods: ComponentPeriods,
+    threshold_invoiced_until: Option<NaiveDate>,
+) -> ComponentPeriods {
+    let arrear = match (periods.arrear, threshold_invoiced_until) {
+        (Some(arrear), Some(until)) if arrear.start < until && until < arrear.end => Some(Period {
+            start: until,
+            end: arrear.end,
+        }),
+        (arrear, _) => arrear,
+    };
+
+    ComponentPeriods { arrear, ..periods }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn periods(arrear: Option<Period>) -> ComponentPeriods {
+        ComponentPeriods {
+            arrear,
+            advance: Period {
+                start: date("2024-02-01"),
+                end: date("2024-03-01"),
+            },
+            proration_factor: None,
+        }
+    }
+
+    #[test]
+    fn test_exclude_threshold_invoiced() {
+        let january = Period {
+            start: date("2024-01-01"),
+            end: date("2024-02-01"),
+        };
+
+        let res = exclude_threshold_invoiced(periods(Some(january.clone())), None);
+        assert_eq!(res.arrear, Some(january.clone()));
+
+        // invoiced mid-period
+        let res =
+            exclude_threshold_invoiced(periods(Some(january.clone())), Some(date("2024-01-20")));
+        assert_eq!(
+            res.arrear,
+            Some(Period {
+                start: date("2024-01-20"),
+                end: date("2024-02-01"),
+            })
+        );
+
+        // invoiced in another period
+        let res =
+            exclude_threshold_invoiced(periods(Some(january.clone())), Some(date("2024-02-10")));
+        assert_eq!(res.arrear, Some(january));
+
+        let res = exclude_threshold_invoiced(periods(None), Some(date("2024-01-20")));
+        assert_eq!(res.arrear, None);
+    }
+
+    #[test]
+    fn test_true_up_line() {
+        let january = Period {
+            start: date("2024-01-01"),
+            end: date("2024-02-01"),
+        };
+
+        let february = Period {
+            start: date("2024-02-01"),
+            end: date("2024-03-01"),
+        };
+
+        let line_in = |period: &Period, total: i64| LineItem {
+            local_id: LocalId::no_prefix(),
+            name: "Fee".to_string(),
+            total,
+            subtotal: total,
+            quantity: None,
+            unit_price: None,
+            start_date: period.start,
+            end_date: period.end,
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id: None,
+            product_id: None,
+            metric_id: None,
+            description: None,
+            quantity_display_precision: None,
+        };
+        let line = |total: i64| line_in(&january, total);
+
+        let true_up = true_up_line(10000, january.clone(), &[line(3000), line(2500)], false)
+            .expect("spend is below the commitment");
+        assert_eq!(true_up.total, 4500);
+        assert_eq!(true_up.start_date, january.start);
+        assert_eq!(true_up.end_date, january.end);
+
+        assert!(true_up_line(10000, january.clone(), &[line(10000)], false).is_none());
+        assert!(true_up_line(10000, january.clone(), &[line(12000)], false).is_none());
+
+        // the advance fee of the closed period, billed on the invoice opening it, is part of the spend.
+        // The advance fee of the next period, billed on the closing invoice, is not
+        let december = Period {
+            start: date("2023-12-01"),
+            end: date("2024-01-01"),
+        };
+        let opening_invoice = [line_in(&december, 500), line(8000)];
+        let closing_invoice = [line(1000), line_in(&february, 8000)];
+
+        let spend_lines: Vec<LineItem> = opening_invoice
+            .iter()
+            .chain(closing_invoice.iter())
+            .cloned()
+            .collect();
+
+        let true_up = true_up_line(10000, january.clone(), &spend_lines, false)
+            .expect("spend is below the commitment");
+        assert_eq!(true_up.total, 1000);
+    }
+}
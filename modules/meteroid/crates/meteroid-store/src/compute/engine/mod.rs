@@ -3,5 +3,5 @@ pub mod invoice;
 
 pub mod period;
 
-mod fees;
+pub(crate) mod fees;
 mod shared;
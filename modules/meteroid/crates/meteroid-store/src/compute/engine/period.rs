@@ -14,12 +14,7 @@ pub fn calculate_component_period(
         return None;
     }
 
-    let billing_period = match billing_period {
-        SubscriptionFeeBillingPeriod::OneTime => None,
-        SubscriptionFeeBillingPeriod::Monthly => Some(BillingPeriodEnum::Monthly),
-        SubscriptionFeeBillingPeriod::Quarterly => Some(BillingPeriodEnum::Quarterly),
-        SubscriptionFeeBillingPeriod::Annual => Some(BillingPeriodEnum::Annual),
-    };
+    let billing_period = billing_period.as_billing_period_opt();
 
     match billing_period {
         None => Some(ComponentPeriods {
@@ -57,7 +52,7 @@ pub fn calculate_component_period(
             };
 
             let proration_factor = if period_idx == 0 {
-                calculate_proration_factor(&advance_period)
+                calculate_proration_factor(&advance_period, &billing_period)
             } else {
                 None
             };
@@ -71,17 +66,31 @@ pub fn calculate_component_period(
     }
 }
 
-fn calculate_proration_factor(period: &Period) -> Option<f64> {
+/// Share of a full period covered by a partial first period, ex: a calendar billed subscription starting mid-month
+fn calculate_proration_factor(period: &Period, billing_period: &BillingPeriodEnum) -> Option<f64> {
+    // weekly periods follow the billing start date, so the first one is never partial
+    if matches!(billing_period, BillingPeriodEnum::Weekly) {
+        return None;
+    }
+
     let days_in_period = period.end.signed_duration_since(period.start).num_days() as u64; // +1 ?
     let days_in_month_from = period.start.days_in_month() as u64;
     let days_in_month_to = period.end.days_in_month() as u64;
 
+    let full_period_start = period
+        .end
+        .checked_sub_months(Months::new(billing_period.as_months()))?;
+    let days_in_full_period = period
+        .end
+        .signed_duration_since(full_period_start)
+        .num_days() as u64;
+
     // if from is end of month and from.day <= to.day. Ex: 2023-02-28 -> 2023-03-28+
     if period.start.day() == days_in_month_from as u32 && period.end.day() >= period.start.day() {
         return None;
     }
 
-    if days_in_period >= days_in_month_from {
+    if days_in_period >= days_in_full_period {
         return None;
     }
 
@@ -90,7 +99,7 @@ fn calculate_proration_factor(period: &Period) -> Option<f64> {
         return None;
     }
 
-    let proration_factor = days_in_period as f64 / days_in_month_from as f64;
+    let proration_factor = days_in_period as f64 / days_in_full_period as f64;
 
     Some(proration_factor)
 }
@@ -100,6 +109,11 @@ fn applies_this_period(
     invoice_date: NaiveDate,
     billing_period: &SubscriptionFeeBillingPeriod,
 ) -> bool {
+    // as the monthly fees, the weekly fees are billed on every invoice
+    if matches!(billing_period, SubscriptionFeeBillingPeriod::Weekly) {
+        return true;
+    }
+
     let month_elapsed = (invoice_date.year() - billing_start_date.year()) * 12
         + (invoice_date.month() as i32)
         - (billing_start_date.month() as i32);
@@ -113,6 +127,10 @@ pub fn calculate_period_range(
     period_index: i32,
     billing_period: &BillingPeriodEnum,
 ) -> Period {
+    if matches!(billing_period, BillingPeriodEnum::Weekly) {
+        return calculate_weekly_period_range(billing_start_date, period_index);
+    }
+
     let months_in_period = billing_period.as_months();
 
     let start_day_after_billing_day = billing_start_date.day() >= billing_day;
@@ -146,6 +164,13 @@ fn calculate_period_idx(
     invoice_date: NaiveDate,
     billing_period: &BillingPeriodEnum,
 ) -> i32 {
+    if matches!(billing_period, BillingPeriodEnum::Weekly) {
+        return invoice_date
+            .signed_duration_since(billing_start_date)
+            .num_days()
+            .div_euclid(7) as i32;
+    }
+
     let month_diff = invoice_date.year() * 12 + invoice_date.month() as i32
         - (billing_start_date.year() * 12 + billing_start_date.month() as i32);
     let day_adjustment =
@@ -155,16 +180,17 @@ fn calculate_period_idx(
             0
         };
 
-    match billing_period {
-        BillingPeriodEnum::Monthly => month_diff + day_adjustment,
-        BillingPeriodEnum::Quarterly => {
-            // Quarterly billing divides the month difference by 3 (since a quarter is 3 months)
-            (month_diff / 3) + day_adjustment
-        }
-        BillingPeriodEnum::Annual => {
-            // Annual billing divides the month difference by 12 (since a year is 12 months)
-            (month_diff / 12) + day_adjustment
-        }
+    // the periods are made of whole months, ex: a semiannual period spans 6 months
+    month_diff / billing_period.as_months() as i32 + day_adjustment
+}
+
+/// Weekly periods follow the billing start date, regardless of the billing day
+fn calculate_weekly_period_range(billing_start_date: NaiveDate, period_index: i32) -> Period {
+    let period_start = billing_start_date + chrono::Duration::weeks(period_index as i64);
+
+    Period {
+        start: period_start,
+        end: period_start + chrono::Duration::weeks(1),
     }
 }
 
@@ -179,7 +205,7 @@ fn add_months_at_billing_day(
 
 #[cfg(test)]
 mod test {
-    use super::{calculate_period_idx, calculate_period_range};
+    use super::{calculate_period_idx, calculate_period_range, calculate_proration_factor};
     use crate::domain::enums::BillingPeriodEnum;
 
     use chrono::NaiveDate;
@@ -384,4 +410,23 @@ mod test {
         );
         assert_eq!(period_idx, expected_period_idx);
     }
+
+    #[rstest]
+    #[case(BillingPeriodEnum::Monthly, "2021-01-01", "2021-02-01", None)]
+    #[case(BillingPeriodEnum::Monthly, "2021-01-10", "2021-02-01", Some(22.0 / 31.0))]
+    #[case(BillingPeriodEnum::Monthly, "2021-01-01", "2021-01-10", Some(9.0 / 31.0))]
+    #[case(BillingPeriodEnum::Quarterly, "2021-01-01", "2021-04-01", None)]
+    #[case(BillingPeriodEnum::Quarterly, "2021-01-10", "2021-04-01", Some(81.0 / 90.0))]
+    #[case(BillingPeriodEnum::Annual, "2021-01-10", "2022-01-01", Some(356.0 / 365.0))]
+    #[trace]
+    fn test_calculate_proration_factor(
+        #[case] billing_period: BillingPeriodEnum,
+        #[case] start: NaiveDate,
+        #[case] end: NaiveDate,
+        #[case] expected_proration_factor: Option<f64>,
+    ) {
+        let proration_factor =
+            calculate_proration_factor(&super::Period { start, end }, &billing_period);
+        assert_eq!(proration_factor, expected_proration_factor);
+    }
 }
@@ -1,7 +1,9 @@
 pub mod clients;
 mod engine;
 mod errors;
+pub mod proration;
 
+pub use engine::fees::preview_tiered_pricing;
 pub use engine::invoice::InvoiceLineInterface;
 pub use engine::period::calculate_period_range;
 pub use errors::ComputeError;
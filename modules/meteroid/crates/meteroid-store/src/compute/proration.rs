@@ -0,0 +1,164 @@
+use crate::compute::errors::ComputeError;
+use crate::domain::enums::ProrationRoundingEnum;
+use crate::domain::Period;
+use rust_decimal::{Decimal, RoundingStrategy};
+
+/// Share of `period` covered by `sub_period`, based on the number of days.
+/// Periods are end-exclusive, so a sub period equal to the full period yields exactly 1.
+pub fn proration_factor(period: &Period, sub_period: &Period) -> Result<Decimal, ComputeError> {
+    if period.end <= period.start
+        || sub_period.end < sub_period.start
+        || sub_period.start < period.start
+        || sub_period.end > period.end
+    {
+        return Err(ComputeError::InvalidPeriod);
+    }
+
+    let period_days = period.end.signed_duration_since(period.start).num_days();
+    let sub_period_days = sub_period
+        .end
+        .signed_duration_since(sub_period.start)
+        .num_days();
+
+    Ok(Decimal::from(sub_period_days) / Decimal::from(period_days))
+}
+
+/// Prorates an amount, in subunits (cents), billed for `period` down to `sub_period`
+pub fn prorate(
+    amount_cents: i64,
+    period: &Period,
+    sub_period: &Period,
+    rounding: ProrationRoundingEnum,
+) -> Result<i64, ComputeError> {
+    let factor = proration_factor(period, sub_period)?;
+
+    prorate_with_factor(amount_cents, factor, rounding)
+}
+
+/// Applies an already computed proration factor to an amount in subunits (cents)
+pub fn prorate_with_factor(
+    amount_cents: i64,
+    factor: Decimal,
+    rounding: ProrationRoundingEnum,
+) -> Result<i64, ComputeError> {
+    round_cents(Decimal::from(amount_cents) * factor, rounding)
+}
+
+pub fn round_cents(amount: Decimal, rounding: ProrationRoundingEnum) -> Result<i64, ComputeError> {
+    let strategy = match rounding {
+        ProrationRoundingEnum::Nearest => RoundingStrategy::MidpointAwayFromZero,
+        ProrationRoundingEnum::NearestEven => RoundingStrategy::MidpointNearestEven,
+        ProrationRoundingEnum::Up => RoundingStrategy::AwayFromZero,
+        ProrationRoundingEnum::Down => RoundingStrategy::ToZero,
+    };
+
+    amount
+        .round_dp_with_strategy(0, strategy)
+        .try_into()
+        .map_err(|_| ComputeError::ConversionError)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rstest::rstest;
+    use rust_decimal_macros::dec;
+
+    fn period(start: &str, end: &str) -> Period {
+        Period {
+            start: NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap(),
+            end: NaiveDate::parse_from_str(end, "%Y-%m-%d").unwrap(),
+        }
+    }
+
+    #[rstest]
+    #[case("2024-01-01", "2024-02-01", "2024-01-01", "2024-02-01", dec!(1))]
+    #[case("2024-01-01", "2024-02-01", "2024-01-16", "2024-02-01", dec!(16) / dec!(31))]
+    #[case("2024-04-01", "2024-05-01", "2024-04-16", "2024-05-01", dec!(0.5))]
+    #[case("2024-02-01", "2024-03-01", "2024-02-15", "2024-03-01", dec!(15) / dec!(29))]
+    #[case("2024-01-01", "2024-02-01", "2024-01-10", "2024-01-10", dec!(0))]
+    #[trace]
+    fn test_proration_factor(
+        #[case] period_start: &str,
+        #[case] period_end: &str,
+        #[case] sub_start: &str,
+        #[case] sub_end: &str,
+        #[case] expected: Decimal,
+    ) {
+        let factor = proration_factor(
+            &period(period_start, period_end),
+            &period(sub_start, sub_end),
+        )
+        .unwrap();
+        assert_eq!(factor, expected);
+    }
+
+    #[rstest]
+    #[case("2024-01-01", "2024-01-01", "2024-01-01", "2024-01-01")]
+    #[case("2024-01-01", "2024-02-01", "2023-12-31", "2024-01-15")]
+    #[case("2024-01-01", "2024-02-01", "2024-01-15", "2024-02-02")]
+    #[case("2024-01-01", "2024-02-01", "2024-01-15", "2024-01-14")]
+    fn test_proration_factor_invalid(
+        #[case] period_start: &str,
+        #[case] period_end: &str,
+        #[case] sub_start: &str,
+        #[case] sub_end: &str,
+    ) {
+        let res = proration_factor(
+            &period(period_start, period_end),
+            &period(sub_start, sub_end),
+        );
+        assert!(matches!(res, Err(ComputeError::InvalidPeriod)));
+    }
+
+    #[rstest]
+    // 1000 * 0.5 = 500 exactly
+    #[case(1000, ProrationRoundingEnum::Nearest, 500)]
+    // 1001 * 0.5 = 500.5
+    #[case(1001, ProrationRoundingEnum::Nearest, 501)]
+    #[case(1001, ProrationRoundingEnum::NearestEven, 500)]
+    #[case(1003, ProrationRoundingEnum::NearestEven, 502)]
+    #[case(1001, ProrationRoundingEnum::Up, 501)]
+    #[case(1001, ProrationRoundingEnum::Down, 500)]
+    // credits are rounded symmetrically
+    #[case(-1001, ProrationRoundingEnum::Nearest, -501)]
+    #[case(-1001, ProrationRoundingEnum::Down, -500)]
+    #[trace]
+    fn test_prorate_rounding(
+        #[case] amount_cents: i64,
+        #[case] rounding: ProrationRoundingEnum,
+        #[case] expected: i64,
+    ) {
+        let res = prorate(
+            amount_cents,
+            &period("2024-04-01", "2024-05-01"),
+            &period("2024-04-16", "2024-05-01"),
+            rounding,
+        )
+        .unwrap();
+        assert_eq!(res, expected);
+    }
+
+    #[test]
+    fn test_prorate_thirds() {
+        // 1000 * 10 / 31 = 322.58
+        let res = prorate(
+            1000,
+            &period("2024-01-01", "2024-02-01"),
+            &period("2024-01-22", "2024-02-01"),
+            ProrationRoundingEnum::Nearest,
+        )
+        .unwrap();
+        assert_eq!(res, 323);
+
+        let res = prorate(
+            1000,
+            &period("2024-01-01", "2024-02-01"),
+            &period("2024-01-22", "2024-02-01"),
+            ProrationRoundingEnum::Down,
+        )
+        .unwrap();
+        assert_eq!(res, 322);
+    }
+}
@@ -1,6 +1,8 @@
 use once_cell::sync::Lazy;
 use serde::Deserialize;
 
+use crate::domain::notification_bundles::DEFAULT_LOCALE;
+
 #[derive(Deserialize, Debug, Clone)]
 pub struct Country {
     pub code: &'static str,
@@ -32,6 +34,13 @@ impl Countries {
     pub fn resolve_country(country: &str) -> Option<Country> {
         COUNTRIES.iter().find(|c| c.code == country).cloned()
     }
+
+    /// The locale of the country, the default locale if it is unknown
+    pub fn resolve_locale(country: &str) -> &'static str {
+        Self::resolve_country(country)
+            .map(|c| c.locale)
+            .unwrap_or(DEFAULT_LOCALE)
+    }
 }
 
 const CURRENCIES_JSON: &str = include_str!("../static/currencies.json");
@@ -0,0 +1,936 @@
+use chrono::{Datelike, Days, NaiveDate, NaiveDateTime};
+use diesel_models::accounting_exports::{
+    AccountingExportConfigRow, AccountingExportConfigRowNew, AccountingExportRunRow,
+    AccountingExportRunRowNew,
+};
+use error_stack::ResultExt;
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::{
+    AccountingConnectorEnum, AccountingExportRunStatusEnum, AccountingExportScheduleEnum,
+    InvoiceStatusEnum,
+};
+use crate::domain::reports::csv_field;
+use crate::domain::Invoice;
+use crate::errors::StoreError;
+use crate::utils::decimals::ToUnit;
+use crate::StoreResult;
+
+/// Longest period exported at once, and how far back the invoices still recognizing revenue are looked up
+pub const MAX_EXPORT_DAYS: i64 = 1100;
+
+/// The accounts of the ledger the entries are booked on, by name for a file or by id for an accounting system
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct LedgerAccounts {
+    pub receivable: String,
+    pub revenue: String,
+    pub deferred_revenue: String,
+    pub tax_payable: String,
+}
+
+impl LedgerAccounts {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        let accounts = [
+            ("receivable", &self.receivable),
+            ("revenue", &self.revenue),
+            ("deferred_revenue", &self.deferred_revenue),
+            ("tax_payable", &self.tax_payable),
+        ];
+
+        for (name, account) in accounts {
+            if account.trim().is_empty() {
+                return Err(StoreError::InvalidArgument(format!(
+                    "The {} account is required",
+                    name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Authorization of the tenant's QuickBooks Online company. The refresh token rotates on each use
+#[derive(Clone, Serialize, Deserialize)]
+pub struct QuickBooksCredentials {
+    pub realm_id: String,
+    pub refresh_token: String,
+}
+
+impl std::fmt::Debug for QuickBooksCredentials {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("QuickBooksCredentials")
+            .field("realm_id", &self.realm_id)
+            .field("refresh_token", &"[redacted]")
+            .finish()
+    }
+}
+
+impl QuickBooksCredentials {
+    pub fn encrypt(&self, key: &SecretString) -> StoreResult<String> {
+        let json = serde_json::to_string(self).map_err(|e| {
+            StoreError::SerdeError("Failed to serialize quickbooks credentials".to_string(), e)
+        })?;
+
+        crate::crypt::encrypt(key, json.as_str()).change_context(StoreError::CryptError(
+            "quickbooks credentials encryption error".into(),
+        ))
+    }
+
+    pub fn decrypt(key: &SecretString, value: &str) -> StoreResult<Self> {
+        let json = crate::crypt::decrypt(key, value).change_context(StoreError::CryptError(
+            "quickbooks credentials decryption error".into(),
+        ))?;
+
+        serde_json::from_str(json.expose_secret()).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to deserialize quickbooks credentials".to_string(),
+                e,
+            )
+            .into()
+        })
+    }
+}
+
+/// How and where the journal entries of a tenant are exported
+#[derive(Debug, Clone)]
+pub struct AccountingExportConfig {
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub schedule: AccountingExportScheduleEnum,
+    pub enabled: bool,
+    pub accounts: LedgerAccounts,
+    pub quickbooks: Option<QuickBooksCredentials>,
+    /// last day exported by the schedule, the next export starts the day after
+    pub exported_until: Option<NaiveDate>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: NaiveDateTime,
+}
+
+impl AccountingExportConfig {
+    pub fn from_row(
+        key: &SecretString,
+        row: AccountingExportConfigRow,
+    ) -> StoreResult<AccountingExportConfig> {
+        let accounts: LedgerAccounts = serde_json::from_value(row.accounts).map_err(|e| {
+            StoreError::SerdeError("Failed to deserialize ledger accounts".to_string(), e)
+        })?;
+
+        let quickbooks = row
+            .credentials
+            .map(|credentials| QuickBooksCredentials::decrypt(key, credentials.as_str()))
+            .transpose()?;
+
+        Ok(AccountingExportConfig {
+            tenant_id: row.tenant_id,
+            connector: row.connector.into(),
+            schedule: row.schedule.into(),
+            enabled: row.enabled,
+            accounts,
+            quickbooks,
+            exported_until: row.exported_until,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountingExportConfigNew {
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub schedule: AccountingExportScheduleEnum,
+    pub enabled: bool,
+    pub accounts: LedgerAccounts,
+    /// kept as is when not provided
+    pub quickbooks: Option<QuickBooksCredentials>,
+    pub created_by: Uuid,
+}
+
+impl AccountingExportConfigNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        self.accounts.validate()?;
+
+        if let Some(quickbooks) = &self.quickbooks {
+            if quickbooks.realm_id.trim().is_empty() || quickbooks.refresh_token.trim().is_empty() {
+                return Err(StoreError::InvalidArgument(
+                    "The QuickBooks company and refresh token are required".to_string(),
+                ));
+            }
+        }
+
+        match (&self.connector, &self.quickbooks) {
+            (AccountingConnectorEnum::QuickbooksOnline, None) => Err(StoreError::InvalidArgument(
+                "The QuickBooks connector requires the authorization of a company".to_string(),
+            )),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_row(&self, key: &SecretString) -> StoreResult<AccountingExportConfigRowNew> {
+        let accounts = serde_json::to_value(&self.accounts).map_err(|e| {
+            StoreError::SerdeError("Failed to serialize ledger accounts".to_string(), e)
+        })?;
+
+        // a file export does not keep the authorization of a previous connector
+        let credentials = match self.connector {
+            AccountingConnectorEnum::Csv => None,
+            AccountingConnectorEnum::QuickbooksOnline => self
+                .quickbooks
+                .as_ref()
+                .map(|quickbooks| quickbooks.encrypt(key))
+                .transpose()?,
+        };
+
+        Ok(AccountingExportConfigRowNew {
+            tenant_id: self.tenant_id,
+            connector: self.connector.into(),
+            schedule: self.schedule.into(),
+            enabled: self.enabled,
+            accounts,
+            credentials,
+            created_by: self.created_by,
+        })
+    }
+}
+
+/// The days covered by an export, both included
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AccountingPeriod {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl AccountingPeriod {
+    pub fn new(start: NaiveDate, end: NaiveDate) -> Result<Self, StoreError> {
+        if end < start {
+            return Err(StoreError::InvalidArgument(
+                "The export period ends before it starts".to_string(),
+            ));
+        }
+
+        if (end - start).num_days() >= MAX_EXPORT_DAYS {
+            return Err(StoreError::InvalidArgument(format!(
+                "The export period cannot exceed {} days",
+                MAX_EXPORT_DAYS
+            )));
+        }
+
+        Ok(AccountingPeriod { start, end })
+    }
+
+    pub fn contains(&self, day: NaiveDate) -> bool {
+        self.start <= day && day <= self.end
+    }
+}
+
+impl AccountingExportScheduleEnum {
+    /// The last period of the schedule that ended before the day
+    pub fn last_complete_period(&self, today: NaiveDate) -> AccountingPeriod {
+        let end = match self {
+            AccountingExportScheduleEnum::Daily => today - Days::new(1),
+            AccountingExportScheduleEnum::Weekly => {
+                today - Days::new(today.weekday().num_days_from_monday() as u64 + 1)
+            }
+            AccountingExportScheduleEnum::Monthly => {
+                today.with_day(1).unwrap_or(today) - Days::new(1)
+            }
+        };
+
+        let start = match self {
+            AccountingExportScheduleEnum::Daily => end,
+            AccountingExportScheduleEnum::Weekly => end - Days::new(6),
+            AccountingExportScheduleEnum::Monthly => end.with_day(1).unwrap_or(end),
+        };
+
+        AccountingPeriod { start, end }
+    }
+}
+
+/// The period the schedule exports next, from the day after the last export to the end of the last complete
+/// period. None when it is already exported
+pub fn due_export_period(
+    schedule: AccountingExportScheduleEnum,
+    exported_until: Option<NaiveDate>,
+    today: NaiveDate,
+) -> Option<AccountingPeriod> {
+    let last = schedule.last_complete_period(today);
+
+    let start = match exported_until {
+        Some(exported_until) => exported_until + Days::new(1),
+        None => last.start,
+    };
+    // an export interrupted for long is caught up in several runs
+    let end = last.end.min(start + Days::new(MAX_EXPORT_DAYS as u64 - 1));
+
+    (start <= end).then_some(AccountingPeriod { start, end })
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JournalEntryKind {
+    /// the invoice is receivable, its revenue deferred until the service is provided
+    Invoice,
+    /// the revenue of the service provided during the period
+    Recognition,
+    /// the voided invoice is not receivable anymore
+    Void,
+}
+
+/// One side of a journal entry, in the minor unit of the currency
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalLine {
+    pub account: String,
+    pub debit: i64,
+    pub credit: i64,
+    pub description: String,
+}
+
+impl JournalLine {
+    /// A negative amount is booked on the other side
+    fn debit(account: &str, amount: i64, description: &str) -> Self {
+        JournalLine {
+            account: account.to_string(),
+            debit: amount.max(0),
+            credit: (-amount).max(0),
+            description: description.to_string(),
+        }
+    }
+
+    fn credit(account: &str, amount: i64, description: &str) -> Self {
+        JournalLine::debit(account, -amount, description)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalEntry {
+    pub kind: JournalEntryKind,
+    pub date: NaiveDate,
+    /// the invoice number
+    pub reference: String,
+    pub description: String,
+    pub currency: String,
+    pub invoice_id: Uuid,
+    pub lines: Vec<JournalLine>,
+}
+
+impl JournalEntry {
+    fn new(
+        kind: JournalEntryKind,
+        date: NaiveDate,
+        invoice: &JournalInvoice,
+        description: String,
+        lines: Vec<JournalLine>,
+    ) -> Option<JournalEntry> {
+        let lines: Vec<JournalLine> = lines
+            .into_iter()
+            .filter(|line| line.debit != 0 || line.credit != 0)
+            .collect();
+
+        (!lines.is_empty()).then(|| JournalEntry {
+            kind,
+            date,
+            reference: invoice.invoice_number.clone(),
+            description,
+            currency: invoice.currency.clone(),
+            invoice_id: invoice.id,
+            lines,
+        })
+    }
+
+    pub fn total_debit(&self) -> i64 {
+        self.lines.iter().map(|line| line.debit).sum()
+    }
+
+    pub fn total_credit(&self) -> i64 {
+        self.lines.iter().map(|line| line.credit).sum()
+    }
+
+    pub fn is_balanced(&self) -> bool {
+        self.total_debit() == self.total_credit()
+    }
+}
+
+/// A line of the invoice, its revenue recognized over its service period
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalInvoiceLine {
+    pub name: String,
+    pub total: i64,
+    pub start_date: NaiveDate,
+    /// excluded
+    pub end_date: NaiveDate,
+}
+
+/// What the journal entries of an invoice are computed from
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JournalInvoice {
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub customer_name: String,
+    pub currency: String,
+    pub invoice_date: NaiveDate,
+    pub total: i64,
+    pub tax_amount: i64,
+    pub voided_on: Option<NaiveDate>,
+    pub lines: Vec<JournalInvoiceLine>,
+}
+
+impl From<&Invoice> for JournalInvoice {
+    fn from(invoice: &Invoice) -> Self {
+        JournalInvoice {
+            id: invoice.id,
+            invoice_number: invoice.invoice_number.clone(),
+            customer_name: invoice.customer_details.name.clone(),
+            currency: invoice.currency.clone(),
+            invoice_date: invoice.invoice_date,
+            total: invoice.total,
+            tax_amount: invoice.tax_amount,
+            voided_on: match invoice.status {
+                InvoiceStatusEnum::Void => invoice.voided_at.map(|voided_at| voided_at.date()),
+                _ => None,
+            },
+            lines: invoice
+                .line_items
+                .iter()
+                .map(|line| JournalInvoiceLine {
+                    name: line.name.clone(),
+                    total: line.total,
+                    start_date: line.start_date,
+                    end_date: line.end_date,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Revenue recognized straight-line over a service period
+#[derive(Debug, Clone)]
+struct RevenueSchedule {
+    description: String,
+    amount: i64,
+    start: NaiveDate,
+    /// excluded
+    end: NaiveDate,
+}
+
+impl RevenueSchedule {
+    /// Recognized up to and including the day, rounded down so that the last day completes the amount
+    fn recognized_through(&self, day: NaiveDate) -> i64 {
+        let days = (self.end - self.start).num_days();
+        if days <= 0 {
+            return if day >= self.start { self.amount } else { 0 };
+        }
+
+        let elapsed = ((day - self.start).num_days() + 1).clamp(0, days);
+
+        (self.amount as i128 * elapsed as i128 / days as i128) as i64
+    }
+}
+
+impl JournalInvoice {
+    /// The revenue of the invoice (its total without tax), split across its lines in proportion of their totals
+    /// as the discounts apply to the whole invoice. Recognized on the invoice date when the lines carry nothing
+    fn revenue_schedules(&self) -> Vec<RevenueSchedule> {
+        let revenue = self.total - self.tax_amount;
+        let weight: i64 = self.lines.iter().map(|line| line.total).sum();
+
+        if weight == 0 {
+            return vec![RevenueSchedule {
+                description: format!("Invoice {}", self.invoice_number),
+                amount: revenue,
+                start: self.invoice_date,
+                end: self.invoice_date,
+            }];
+        }
+
+        let mut allocated = 0;
+        let last = self.lines.len() - 1;
+
+        self.lines
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let amount = if i == last {
+                    revenue - allocated
+                } else {
+                    (revenue as i128 * line.total as i128 / weight as i128) as i64
+                };
+                allocated += amount;
+
+                RevenueSchedule {
+                    description: line.name.clone(),
+                    amount,
+                    start: line.start_date,
+                    end: line.end_date,
+                }
+            })
+            .collect()
+    }
+
+    /// Nothing is recognized before the invoice date, the days of service provided before are caught up on it.
+    /// Nor from the day it is voided
+    fn recognized_through(&self, schedule: &RevenueSchedule, day: NaiveDate) -> i64 {
+        let day = match self.voided_on {
+            Some(voided_on) => day.min(voided_on - Days::new(1)),
+            None => day,
+        };
+
+        if day < self.invoice_date {
+            0
+        } else {
+            schedule.recognized_through(day)
+        }
+    }
+
+    /// The entries of the invoice booked within the period
+    pub fn journal_entries(
+        &self,
+        accounts: &LedgerAccounts,
+        period: &AccountingPeriod,
+    ) -> Vec<JournalEntry> {
+        let schedules = self.revenue_schedules();
+        let revenue = self.total - self.tax_amount;
+        let mut entries = vec![];
+
+        if period.contains(self.invoice_date) {
+            let description = format!("Invoice {} - {}", self.invoice_number, self.customer_name);
+
+            entries.extend(JournalEntry::new(
+                JournalEntryKind::Invoice,
+                self.invoice_date,
+                self,
+                description.clone(),
+                vec![
+                    JournalLine::debit(&accounts.receivable, self.total, &description),
+                    JournalLine::credit(&accounts.tax_payable, self.tax_amount, &description),
+                    JournalLine::credit(&accounts.deferred_revenue, revenue, &description),
+                ],
+            ));
+        }
+
+        let before_period = period.start - Days::new(1);
+        let recognized: Vec<(&RevenueSchedule, i64)> = schedules
+            .iter()
+            .map(|schedule| {
+                let amount = self.recognized_through(schedule, period.end)
+                    - self.recognized_through(schedule, before_period);
+                (schedule, amount)
+            })
+            .collect();
+
+        let description = format!("Revenue recognized on invoice {}", self.invoice_number);
+        let mut lines = vec![JournalLine::debit(
+            &accounts.deferred_revenue,
+            recognized.iter().map(|(_, amount)| amount).sum(),
+            &description,
+        )];
+        lines.extend(recognized.iter().map(|(schedule, amount)| {
+            JournalLine::credit(&accounts.revenue, *amount, &schedule.description)
+        }));
+
+        entries.extend(JournalEntry::new(
+            JournalEntryKind::Recognition,
+            period.end,
+            self,
+            description,
+            lines,
+        ));
+
+        if let Some(voided_on) = self.voided_on.filter(|day| period.contains(*day)) {
+            // the revenue recognized until then is reversed along with what was still deferred
+            let recognized: i64 = schedules
+                .iter()
+                .map(|schedule| self.recognized_through(schedule, voided_on))
+                .sum();
+            let description = format!("Invoice {} voided", self.invoice_number);
+
+            entries.extend(JournalEntry::new(
+                JournalEntryKind::Void,
+                voided_on,
+                self,
+                description.clone(),
+                vec![
+                    JournalLine::credit(&accounts.receivable, self.total, &description),
+                    JournalLine::debit(&accounts.tax_payable, self.tax_amount, &description),
+                    JournalLine::debit(
+                        &accounts.deferred_revenue,
+                        revenue - recognized,
+                        &description,
+                    ),
+                    JournalLine::debit(&accounts.revenue, recognized, &description),
+                ],
+            ));
+        }
+
+        entries
+    }
+}
+
+/// One line per side of each entry, the amounts in the major unit of their currency
+pub fn journal_entries_to_csv(entries: &[JournalEntry]) -> String {
+    let mut csv =
+        String::from("entry,date,reference,description,account,debit,credit,currency,invoice_id\n");
+
+    for (i, entry) in entries.iter().enumerate() {
+        let precision = rusty_money::iso::find(&entry.currency)
+            .map(|currency| currency.exponent)
+            .unwrap_or(2);
+        let amount = |cents: i64| match cents {
+            0 => String::new(),
+            cents => format!("{:.*}", precision as usize, cents.to_unit(precision as u8)),
+        };
+
+        for line in &entry.lines {
+            let fields = [
+                (i + 1).to_string(),
+                entry.date.to_string(),
+                csv_field(&entry.reference),
+                csv_field(&line.description),
+                csv_field(&line.account),
+                amount(line.debit),
+                amount(line.credit),
+                csv_field(&entry.currency),
+                entry.invoice_id.to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+    }
+
+    csv
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountingExportRun {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub status: AccountingExportRunStatusEnum,
+    pub entries: i32,
+    /// the file in the object store, or the last entry created in the accounting system
+    pub document_id: Option<String>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AccountingExportRunRow> for AccountingExportRun {
+    fn from(row: AccountingExportRunRow) -> Self {
+        AccountingExportRun {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            connector: row.connector.into(),
+            period_start: row.period_start,
+            period_end: row.period_end,
+            status: row.status.into(),
+            entries: row.entries,
+            document_id: row.document_id,
+            error: row.error,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct AccountingExportRunNew {
+    pub tenant_id: Uuid,
+    pub connector: AccountingConnectorEnum,
+    pub period: AccountingPeriod,
+    pub status: AccountingExportRunStatusEnum,
+    pub entries: i32,
+    pub document_id: Option<String>,
+    pub error: Option<String>,
+}
+
+impl From<AccountingExportRunNew> for AccountingExportRunRowNew {
+    fn from(run: AccountingExportRunNew) -> Self {
+        AccountingExportRunRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: run.tenant_id,
+            connector: run.connector.into(),
+            period_start: run.period.start,
+            period_end: run.period.end,
+            status: run.status.into(),
+            entries: run.entries,
+            document_id: run.document_id,
+            error: run.error,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn period(start: &str, end: &str) -> AccountingPeriod {
+        AccountingPeriod::new(date(start), date(end)).unwrap()
+    }
+
+    fn accounts() -> LedgerAccounts {
+        LedgerAccounts {
+            receivable: "1200".to_string(),
+            revenue: "4000".to_string(),
+            deferred_revenue: "2400".to_string(),
+            tax_payable: "2200".to_string(),
+        }
+    }
+
+    /// 300 for 30 days of service from the invoice date, 60 of tax
+    fn invoice() -> JournalInvoice {
+        JournalInvoice {
+            id: Uuid::nil(),
+            invoice_number: "INV-1".to_string(),
+            customer_name: "Acme".to_string(),
+            currency: "EUR".to_string(),
+            invoice_date: date("2024-11-01"),
+            total: 36000,
+            tax_amount: 6000,
+            voided_on: None,
+            lines: vec![JournalInvoiceLine {
+                name: "Seats".to_string(),
+                total: 30000,
+                start_date: date("2024-11-01"),
+                end_date: date("2024-12-01"),
+            }],
+        }
+    }
+
+    fn kinds(entries: &[JournalEntry]) -> Vec<JournalEntryKind> {
+        entries.iter().map(|entry| entry.kind).collect()
+    }
+
+    #[test]
+    fn test_invoice_and_daily_recognition() {
+        let entries = invoice().journal_entries(&accounts(), &period("2024-11-01", "2024-11-10"));
+
+        assert_eq!(
+            kinds(&entries),
+            vec![JournalEntryKind::Invoice, JournalEntryKind::Recognition]
+        );
+        assert!(entries.iter().all(JournalEntry::is_balanced));
+
+        assert_eq!(
+            entries[0].lines,
+            vec![
+                JournalLine::debit("1200", 36000, "Invoice INV-1 - Acme"),
+                JournalLine::credit("2200", 6000, "Invoice INV-1 - Acme"),
+                JournalLine::credit("2400", 30000, "Invoice INV-1 - Acme"),
+            ]
+        );
+        // 10 of the 30 days
+        assert_eq!(entries[1].date, date("2024-11-10"));
+        assert_eq!(entries[1].total_credit(), 10000);
+    }
+
+    #[test]
+    fn test_recognition_completes_over_periods() {
+        let mut invoice = invoice();
+        invoice.total = 10000 + 6000;
+        invoice.lines[0].total = 10000;
+
+        let recognized: i64 = [
+            period("2024-11-01", "2024-11-07"),
+            period("2024-11-08", "2024-11-14"),
+            period("2024-11-15", "2024-11-30"),
+            period("2024-12-01", "2024-12-31"),
+        ]
+        .iter()
+        .flat_map(|period| invoice.journal_entries(&accounts(), period))
+        .filter(|entry| entry.kind == JournalEntryKind::Recognition)
+        .map(|entry| entry.total_credit())
+        .sum();
+
+        assert_eq!(recognized, 10000);
+    }
+
+    #[test]
+    fn test_arrear_service_is_caught_up_on_invoice_date() {
+        let mut invoice = invoice();
+        invoice.invoice_date = date("2024-12-01");
+
+        let before = invoice.journal_entries(&accounts(), &period("2024-11-01", "2024-11-30"));
+        assert!(before.is_empty());
+
+        let entries = invoice.journal_entries(&accounts(), &period("2024-12-01", "2024-12-01"));
+        assert_eq!(
+            kinds(&entries),
+            vec![JournalEntryKind::Invoice, JournalEntryKind::Recognition]
+        );
+        assert_eq!(entries[1].total_credit(), 30000);
+    }
+
+    #[test]
+    fn test_discount_is_allocated_to_lines() {
+        let mut invoice = invoice();
+        // 10% off two lines of 200 and 100
+        invoice.total = 27000 + 5400;
+        invoice.tax_amount = 5400;
+        invoice.lines = vec![
+            JournalInvoiceLine {
+                name: "Seats".to_string(),
+                total: 20000,
+                start_date: date("2024-11-01"),
+                end_date: date("2024-11-02"),
+            },
+            JournalInvoiceLine {
+                name: "Setup".to_string(),
+                total: 10000,
+                start_date: date("2024-11-01"),
+                end_date: date("2024-11-01"),
+            },
+        ];
+
+        let entries = invoice.journal_entries(&accounts(), &period("2024-11-01", "2024-11-01"));
+        let recognition = &entries[1];
+
+        assert!(recognition.is_balanced());
+        assert_eq!(
+            recognition.lines[1..].to_vec(),
+            vec![
+                JournalLine::credit("4000", 18000, "Seats"),
+                JournalLine::credit("4000", 9000, "Setup"),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_void_reverses_invoice() {
+        let mut invoice = invoice();
+        invoice.voided_on = Some(date("2024-11-11"));
+
+        let entries = invoice.journal_entries(&accounts(), &period("2024-11-01", "2024-11-30"));
+        assert_eq!(
+            kinds(&entries),
+            vec![
+                JournalEntryKind::Invoice,
+                JournalEntryKind::Recognition,
+                JournalEntryKind::Void
+            ]
+        );
+        assert!(entries.iter().all(JournalEntry::is_balanced));
+
+        // recognized until the day before it was voided
+        assert_eq!(entries[1].total_credit(), 10000);
+        assert_eq!(
+            entries[2].lines,
+            vec![
+                JournalLine::credit("1200", 36000, "Invoice INV-1 voided"),
+                JournalLine::debit("2200", 6000, "Invoice INV-1 voided"),
+                JournalLine::debit("2400", 20000, "Invoice INV-1 voided"),
+                JournalLine::debit("4000", 10000, "Invoice INV-1 voided"),
+            ]
+        );
+
+        let after = invoice.journal_entries(&accounts(), &period("2024-12-01", "2024-12-31"));
+        assert!(after.is_empty());
+    }
+
+    #[test]
+    fn test_negative_amounts_are_booked_on_the_other_side() {
+        let mut invoice = invoice();
+        invoice.total = -12000;
+        invoice.tax_amount = -2000;
+        invoice.lines[0].total = -10000;
+        invoice.lines[0].end_date = invoice.lines[0].start_date;
+
+        let entries = invoice.journal_entries(&accounts(), &period("2024-11-01", "2024-11-01"));
+
+        assert!(entries.iter().all(JournalEntry::is_balanced));
+        assert_eq!(entries[0].lines[0].credit, 12000);
+        assert_eq!(entries[1].lines[0].credit, 10000);
+        assert_eq!(entries[1].lines[1].debit, 10000);
+    }
+
+    #[test]
+    fn test_due_export_period() {
+        let today = date("2024-11-13"); // a Wednesday
+
+        assert_eq!(
+            due_export_period(AccountingExportScheduleEnum::Daily, None, today),
+            Some(period("2024-11-12", "2024-11-12"))
+        );
+        assert_eq!(
+            due_export_period(AccountingExportScheduleEnum::Weekly, None, today),
+            Some(period("2024-11-04", "2024-11-10"))
+        );
+        assert_eq!(
+            due_export_period(AccountingExportScheduleEnum::Monthly, None, today),
+            Some(period("2024-10-01", "2024-10-31"))
+        );
+        assert_eq!(
+            due_export_period(
+                AccountingExportScheduleEnum::Weekly,
+                Some(date("2024-10-27")),
+                today
+            ),
+            Some(period("2024-10-28", "2024-11-10"))
+        );
+        assert_eq!(
+            due_export_period(
+                AccountingExportScheduleEnum::Daily,
+                Some(date("2020-01-01")),
+                today
+            ),
+            Some(period("2020-01-02", "2023-01-05"))
+        );
+        assert_eq!(
+            due_export_period(
+                AccountingExportScheduleEnum::Monthly,
+                Some(date("2024-10-31")),
+                today
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn test_journal_entries_to_csv() {
+        let mut invoice = invoice();
+        invoice.invoice_number = "=INV,1".to_string();
+
+        let entries = invoice.journal_entries(&accounts(), &period("2024-11-01", "2024-11-01"));
+        let csv = journal_entries_to_csv(&entries);
+        let lines: Vec<&str> = csv.lines().collect();
+
+        assert_eq!(lines.len(), 1 + 3 + 2);
+        assert_eq!(
+            lines[1],
+            "1,2024-11-01,\"'=INV,1\",\"Invoice =INV,1 - Acme\",1200,360.00,,EUR,00000000-0000-0000-0000-000000000000"
+        );
+        assert_eq!(
+            lines[5],
+            "2,2024-11-01,\"'=INV,1\",Seats,4000,,10.00,EUR,00000000-0000-0000-0000-000000000000"
+        );
+    }
+
+    #[test]
+    fn test_validate_config() {
+        let mut config = AccountingExportConfigNew {
+            tenant_id: Uuid::nil(),
+            connector: AccountingConnectorEnum::QuickbooksOnline,
+            schedule: AccountingExportScheduleEnum::Monthly,
+            enabled: true,
+            accounts: accounts(),
+            quickbooks: None,
+            created_by: Uuid::nil(),
+        };
+        assert!(config.validate().is_err());
+
+        config.quickbooks = Some(QuickBooksCredentials {
+            realm_id: "9130".to_string(),
+            refresh_token: "token".to_string(),
+        });
+        assert!(config.validate().is_ok());
+
+        config.accounts.revenue = " ".to_string();
+        assert!(config.validate().is_err());
+    }
+}
@@ -1,8 +1,17 @@
-use chrono::NaiveDateTime;
+use chrono::{Duration, NaiveDate, NaiveDateTime};
 use o2o::o2o;
+use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use diesel_models::api_tokens::{ApiTokenRow, ApiTokenRowNew, ApiTokenValidationRow};
+use diesel_models::api_tokens::{
+    ApiTokenExpiringRow, ApiTokenRow, ApiTokenRowNew, ApiTokenUsageDailyRow, ApiTokenValidationRow,
+};
+
+/// The owner of a token is notified this many days before its expiry
+pub const API_TOKEN_EXPIRY_NOTICE_DAYS: i64 = 7;
+
+/// Max hours a rotated token remains valid alongside the new one
+pub const MAX_API_TOKEN_ROTATION_GRACE_HOURS: u32 = 7 * 24;
 
 #[derive(Debug, o2o)]
 #[from_owned(ApiTokenRowNew)]
@@ -10,6 +19,14 @@ pub struct ApiTokenNew {
     pub name: String,
     pub created_by: Uuid,
     pub tenant_id: Uuid,
+    /// requests per day above which the usage is flagged, without rejecting them
+    pub daily_request_soft_limit: Option<i64>,
+    /// restricts the token to the payout report of the partner
+    pub partner_id: Option<Uuid>,
+    /// the resources the token can access, ex: invoices:read. All of them if empty
+    #[from(~.into_iter().flatten().collect())]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
 }
 
 #[derive(Debug, o2o)]
@@ -23,6 +40,26 @@ pub struct ApiToken {
     pub tenant_id: Uuid,
     pub hash: String,
     pub hint: String,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub request_count: i64,
+    pub daily_request_soft_limit: Option<i64>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub rotated_at: Option<NaiveDateTime>,
+    pub partner_id: Option<Uuid>,
+    #[from(~.into_iter().flatten().collect())]
+    #[into(~.into_iter().map(Some).collect())]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    /// when the owner was notified of the upcoming expiry
+    pub expiry_notified_at: Option<NaiveDateTime>,
+}
+
+impl ApiToken {
+    /// The expiry of the token replacing this one at `now`, keeping the lifetime of this one
+    pub fn rotated_expiry(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        self.expires_at
+            .map(|expires_at| now + (expires_at - self.created_at).max(Duration::zero()))
+    }
 }
 
 #[derive(Debug, o2o)]
@@ -32,4 +69,120 @@ pub struct ApiTokenValidation {
     pub tenant_id: Uuid,
     pub organization_id: Uuid,
     pub hash: String,
+    pub partner_id: Option<Uuid>,
+    #[from(~.into_iter().flatten().collect())]
+    pub scopes: Vec<String>,
+    pub expires_at: Option<NaiveDateTime>,
+    /// a rotated token is disabled at the end of its grace period
+    pub disabled_at: Option<NaiveDateTime>,
+}
+
+impl ApiTokenValidation {
+    /// When the token stops authenticating, at its expiry or at the end of its rotation grace period
+    pub fn valid_until(&self) -> Option<NaiveDateTime> {
+        match (self.expires_at, self.disabled_at) {
+            (Some(a), Some(b)) => Some(a.min(b)),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+#[derive(Debug, Clone, o2o)]
+#[from_owned(ApiTokenExpiringRow)]
+pub struct ExpiringApiToken {
+    pub id: Uuid,
+    pub name: String,
+    pub hint: String,
+    pub tenant_id: Uuid,
+    pub created_by: Uuid,
+    pub expires_at: Option<NaiveDateTime>,
+    pub owner_email: String,
+    pub tenant_name: String,
+}
+
+/// Payload of the outbox entry requesting the email reminding the owner to rotate the token
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ApiTokenExpiryEmailPayload {
+    pub api_token_id: Uuid,
+    pub recipient: String,
+    pub token_name: String,
+    pub hint: String,
+    pub tenant_name: String,
+    pub expires_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone, o2o)]
+#[from_owned(ApiTokenUsageDailyRow)]
+pub struct ApiTokenDailyUsage {
+    pub day: NaiveDate,
+    pub request_count: i64,
+}
+
+/// Requests made with a token, recorded by the auth middleware
+#[derive(Debug, Clone)]
+pub struct ApiTokenUsageRecord {
+    pub api_token_id: Uuid,
+    pub day: NaiveDate,
+    pub request_count: i64,
+    pub last_used_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct ApiTokenUsage {
+    pub api_token_id: Uuid,
+    pub request_count: i64,
+    pub last_used_at: Option<NaiveDateTime>,
+    pub daily_request_soft_limit: Option<i64>,
+    pub disabled_at: Option<NaiveDateTime>,
+    pub rotated_at: Option<NaiveDateTime>,
+    /// the requests per day, from the oldest. Days without request are omitted
+    pub daily: Vec<ApiTokenDailyUsage>,
+}
+
+impl ApiTokenUsage {
+    /// Days on which the requests exceeded the soft limit of the token
+    pub fn soft_limit_exceeded_days(&self) -> Vec<NaiveDate> {
+        match self.daily_request_soft_limit {
+            Some(limit) => self
+                .daily
+                .iter()
+                .filter(|d| d.request_count > limit)
+                .map(|d| d.day)
+                .collect(),
+            None => vec![],
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%d %H:%M:%S").unwrap()
+    }
+
+    #[test]
+    fn test_valid_until() {
+        let validation = |expires_at: Option<&str>, disabled_at: Option<&str>| ApiTokenValidation {
+            id: Uuid::nil(),
+            tenant_id: Uuid::nil(),
+            organization_id: Uuid::nil(),
+            hash: String::new(),
+            partner_id: None,
+            scopes: vec![],
+            expires_at: expires_at.map(datetime),
+            disabled_at: disabled_at.map(datetime),
+        };
+
+        assert_eq!(validation(None, None).valid_until(), None);
+        assert_eq!(
+            validation(Some("2024-12-01 00:00:00"), None).valid_until(),
+            Some(datetime("2024-12-01 00:00:00"))
+        );
+        assert_eq!(
+            validation(Some("2024-12-01 00:00:00"), Some("2024-11-05 12:00:00")).valid_until(),
+            Some(datetime("2024-11-05 12:00:00"))
+        );
+    }
 }
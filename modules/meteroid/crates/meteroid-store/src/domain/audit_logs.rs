@@ -0,0 +1,172 @@
+use chrono::NaiveDateTime;
+use diesel_models::audit_logs::{AuditLogFilter as AuditLogFilterRow, AuditLogRow, AuditLogRowNew};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+
+/// A mutating call of the api
+#[derive(Clone, Debug)]
+pub struct AuditLog {
+    pub id: Uuid,
+    pub organization_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub actor_id: Uuid,
+    /// the full grpc method, ex: meteroid.api.customers.v1.CustomersService/PatchCustomer
+    pub rpc: String,
+    pub entity_id: Option<String>,
+    pub diff: Option<Value>,
+    /// the grpc status code of the call
+    pub status_code: i32,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<AuditLogRow> for AuditLog {
+    fn from(row: AuditLogRow) -> Self {
+        AuditLog {
+            id: row.id,
+            organization_id: row.organization_id,
+            tenant_id: row.tenant_id,
+            actor_id: row.actor_id,
+            rpc: row.rpc,
+            entity_id: row.entity_id,
+            diff: row.diff,
+            status_code: row.status_code,
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct AuditLogNew {
+    pub organization_id: Option<Uuid>,
+    pub tenant_id: Option<Uuid>,
+    pub actor_id: Uuid,
+    pub rpc: String,
+    pub entity_id: Option<String>,
+    pub diff: Option<Value>,
+    pub status_code: i32,
+}
+
+impl From<AuditLogNew> for AuditLogRowNew {
+    fn from(log: AuditLogNew) -> Self {
+        AuditLogRowNew {
+            id: Uuid::now_v7(),
+            organization_id: log.organization_id,
+            tenant_id: log.tenant_id,
+            actor_id: log.actor_id,
+            rpc: log.rpc,
+            entity_id: log.entity_id,
+            diff: log.diff,
+            status_code: log.status_code,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct AuditLogFilter {
+    pub actor_id: Option<Uuid>,
+    pub rpc: Option<String>,
+    pub entity_id: Option<String>,
+    pub from: Option<NaiveDateTime>,
+    pub to: Option<NaiveDateTime>,
+}
+
+impl From<AuditLogFilter> for AuditLogFilterRow {
+    fn from(filter: AuditLogFilter) -> Self {
+        AuditLogFilterRow {
+            actor_id: filter.actor_id,
+            rpc: filter.rpc,
+            entity_id: filter.entity_id,
+            from: filter.from,
+            to: filter.to,
+        }
+    }
+}
+
+/// The entity changed by a mutating call, attached by the api to the response so that the audit middleware logs it
+#[derive(Clone, Debug)]
+pub struct AuditEntity {
+    pub entity_id: String,
+    pub diff: Option<Value>,
+}
+
+impl AuditEntity {
+    pub fn new(entity_id: impl ToString) -> Self {
+        AuditEntity {
+            entity_id: entity_id.to_string(),
+            diff: None,
+        }
+    }
+
+    #[must_use]
+    pub fn with_diff(mut self, before: &Value, after: &Value) -> Self {
+        self.diff = json_diff(before, after);
+        self
+    }
+}
+
+/// The fields that changed between the two values, as {"field": {"before": .., "after": ..}}.
+/// Values that are not objects are compared as a whole. None if nothing changed
+pub fn json_diff(before: &Value, after: &Value) -> Option<Value> {
+    if before == after {
+        return None;
+    }
+
+    match (before, after) {
+        (Value::Object(before), Value::Object(after)) => {
+            let mut diff = Map::new();
+
+            for key in before
+                .keys()
+                .chain(after.keys().filter(|k| !before.contains_key(*k)))
+            {
+                let old = before.get(key).unwrap_or(&Value::Null);
+                let new = after.get(key).unwrap_or(&Value::Null);
+
+                if old != new {
+                    diff.insert(key.clone(), change(old, new));
+                }
+            }
+
+            Some(Value::Object(diff))
+        }
+        _ => Some(change(before, after)),
+    }
+}
+
+fn change(before: &Value, after: &Value) -> Value {
+    serde_json::json!({ "before": before, "after": after })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_json_diff() {
+        let before = json!({"name": "Acme", "email": null, "alias": "acme"});
+        let after = json!({"name": "Acme Inc", "email": "billing@acme.com", "alias": "acme"});
+
+        assert_eq!(
+            json_diff(&before, &after),
+            Some(json!({
+                "name": {"before": "Acme", "after": "Acme Inc"},
+                "email": {"before": null, "after": "billing@acme.com"},
+            }))
+        );
+        assert_eq!(json_diff(&before, &before), None);
+
+        // added and removed fields
+        assert_eq!(
+            json_diff(&json!({"a": 1}), &json!({"b": 2})),
+            Some(json!({
+                "a": {"before": 1, "after": null},
+                "b": {"before": null, "after": 2},
+            }))
+        );
+        assert_eq!(
+            json_diff(&json!(1), &json!(2)),
+            Some(json!({"before": 1, "after": 2}))
+        );
+    }
+}
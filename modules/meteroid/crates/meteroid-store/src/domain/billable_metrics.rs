@@ -1,6 +1,7 @@
 use super::enums::{BillingMetricAggregateEnum, UnitConversionRoundingEnum};
 use crate::errors::{StoreError, StoreErrorReport};
 use chrono::NaiveDateTime;
+use rust_decimal::{Decimal, RoundingStrategy};
 use std::collections::HashMap;
 
 use diesel_models::billable_metrics::{BillableMetricMetaRow, BillableMetricRow};
@@ -41,6 +42,71 @@ pub struct BillableMetric {
     pub archived_at: Option<NaiveDateTime>,
     pub tenant_id: Uuid,
     pub product_family_id: Uuid,
+    /// decimal places of the billed quantities, rounded as per the unit conversion rounding. None keeps the metered quantity
+    pub quantity_billing_precision: Option<i32>,
+    /// decimal places of the quantities on the invoices, defaults to the billing precision
+    pub quantity_display_precision: Option<i32>,
+    /// properties expected on the ingested events, none accepts any property
+    #[into(
+        ~.map(| x | serde_json::to_value(& x).map_err(| e | {
+        StoreError::SerdeError("Failed to serialize event_schema".to_string(), e)
+        }))
+        .transpose() ?
+    )]
+    #[from(
+        ~.map(| x | serde_json::from_value(x).map_err(| e | {
+        StoreError::SerdeError("Failed to deserialize event_schema".to_string(), e)
+        }))
+        .transpose() ?
+    )]
+    pub event_schema: Option<EventSchema>,
+}
+
+pub const MAX_QUANTITY_PRECISION: i32 = 12;
+
+impl BillableMetric {
+    /// The metered value in the unit the metric is billed in
+    pub fn convert_units(&self, value: Decimal) -> Decimal {
+        match self.unit_conversion_factor {
+            Some(factor) if factor != 0 => value / Decimal::from(factor),
+            _ => value,
+        }
+    }
+
+    /// The metered value converted and rounded to the billing precision, as charged and shown on the line items
+    pub fn billed_quantity(&self, value: Decimal) -> Decimal {
+        let quantity = self.convert_units(value);
+
+        match self.quantity_billing_precision {
+            Some(precision) => {
+                let strategy = match self.unit_conversion_rounding {
+                    Some(UnitConversionRoundingEnum::Up) => RoundingStrategy::AwayFromZero,
+                    Some(UnitConversionRoundingEnum::Down) => RoundingStrategy::ToZero,
+                    _ => RoundingStrategy::MidpointAwayFromZero,
+                };
+                quantity.round_dp_with_strategy(precision.max(0) as u32, strategy)
+            }
+            None => quantity,
+        }
+    }
+
+    pub fn quantity_display_precision(&self) -> Option<u32> {
+        self.quantity_display_precision
+            .or(self.quantity_billing_precision)
+            .map(|precision| precision.max(0) as u32)
+    }
+}
+
+pub fn validate_quantity_precision(precision: Option<i32>) -> Result<(), StoreError> {
+    match precision {
+        Some(p) if !(0..=MAX_QUANTITY_PRECISION).contains(&p) => {
+            Err(StoreError::InvalidArgument(format!(
+                "quantity precision must be between 0 and {} decimal places",
+                MAX_QUANTITY_PRECISION
+            )))
+        }
+        _ => Ok(()),
+    }
 }
 
 #[derive(Clone, Debug, Serialize, Deserialize)]
@@ -63,6 +129,97 @@ pub enum SegmentationMatrix {
     },
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub enum PropertyType {
+    String,
+    Number,
+    Integer,
+    Boolean,
+}
+
+impl PropertyType {
+    /// Whether the raw value of an event property is of the type. Properties are ingested as strings
+    pub fn accepts(&self, value: &str) -> bool {
+        match self {
+            PropertyType::String => true,
+            PropertyType::Number => value.parse::<f64>().is_ok_and(f64::is_finite),
+            PropertyType::Integer => value.parse::<i64>().is_ok(),
+            PropertyType::Boolean => value == "true" || value == "false",
+        }
+    }
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct PropertySchema {
+    pub key: String,
+    pub property_type: PropertyType,
+    pub required: bool,
+    /// empty allows any value of the type
+    pub allowed_values: Vec<String>,
+}
+
+/// Properties expected on the events of a metric. Properties absent from the schema are accepted
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EventSchema {
+    pub properties: Vec<PropertySchema>,
+}
+
+impl EventSchema {
+    pub fn validate(
+        &self,
+        aggregation_type: &BillingMetricAggregateEnum,
+        aggregation_key: Option<&str>,
+    ) -> Result<(), StoreError> {
+        let mut keys = std::collections::HashSet::new();
+
+        for property in &self.properties {
+            if property.key.trim().is_empty() {
+                return Err(StoreError::InvalidArgument(
+                    "event schema property key cannot be empty".to_string(),
+                ));
+            }
+            if !keys.insert(property.key.as_str()) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "event schema property {} is declared twice",
+                    property.key
+                )));
+            }
+            if let Some(value) = property
+                .allowed_values
+                .iter()
+                .find(|v| !property.property_type.accepts(v))
+            {
+                return Err(StoreError::InvalidArgument(format!(
+                    "allowed value {} of property {} is not a {:?}",
+                    value, property.key, property.property_type
+                )));
+            }
+        }
+
+        // the aggregated values must be numbers, counts accept any value
+        let numeric_aggregation = !matches!(
+            aggregation_type,
+            BillingMetricAggregateEnum::Count | BillingMetricAggregateEnum::CountDistinct
+        );
+        if let Some(property) = aggregation_key
+            .filter(|_| numeric_aggregation)
+            .and_then(|key| self.properties.iter().find(|p| p.key == key))
+        {
+            if !matches!(
+                property.property_type,
+                PropertyType::Number | PropertyType::Integer
+            ) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "aggregated property {} must be a number",
+                    property.key
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct BillableMetricNew {
     pub name: String,
@@ -77,6 +234,9 @@ pub struct BillableMetricNew {
     pub created_by: Uuid,
     pub tenant_id: Uuid,
     pub family_external_id: String,
+    pub quantity_billing_precision: Option<i32>,
+    pub quantity_display_precision: Option<i32>,
+    pub event_schema: Option<EventSchema>,
 }
 
 #[derive(Clone, Debug, o2o)]
@@ -92,3 +252,131 @@ pub struct BillableMetricMeta {
     pub created_at: NaiveDateTime,
     pub archived_at: Option<NaiveDateTime>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn metric(
+        factor: Option<i32>,
+        rounding: Option<UnitConversionRoundingEnum>,
+        billing_precision: Option<i32>,
+    ) -> BillableMetric {
+        BillableMetric {
+            id: Uuid::nil(),
+            name: "Storage".to_string(),
+            description: None,
+            code: "storage".to_string(),
+            aggregation_type: BillingMetricAggregateEnum::Sum,
+            aggregation_key: None,
+            unit_conversion_factor: factor,
+            unit_conversion_rounding: rounding,
+            segmentation_matrix: None,
+            usage_group_key: None,
+            created_at: NaiveDateTime::MIN,
+            created_by: Uuid::nil(),
+            updated_at: None,
+            archived_at: None,
+            tenant_id: Uuid::nil(),
+            product_family_id: Uuid::nil(),
+            quantity_billing_precision: billing_precision,
+            quantity_display_precision: None,
+            event_schema: None,
+        }
+    }
+
+    fn property(key: &str, property_type: PropertyType, allowed_values: &[&str]) -> PropertySchema {
+        PropertySchema {
+            key: key.to_string(),
+            property_type,
+            required: true,
+            allowed_values: allowed_values.iter().map(|v| v.to_string()).collect(),
+        }
+    }
+
+    #[test]
+    fn test_billed_quantity_keeps_the_metered_precision() {
+        let metric = metric(None, None, None);
+
+        assert_eq!(metric.billed_quantity(dec!(0.000001)), dec!(0.000001));
+        assert_eq!(metric.quantity_display_precision(), None);
+    }
+
+    #[test]
+    fn test_billed_quantity_rounds_to_the_billing_precision() {
+        // the floating point noise of the meter aggregation
+        let nearest = metric(None, None, Some(6));
+        assert_eq!(
+            nearest.billed_quantity(dec!(0.0000030000000004)),
+            dec!(0.000003)
+        );
+        assert_eq!(nearest.billed_quantity(dec!(1.2345675)), dec!(1.234568));
+        assert_eq!(nearest.quantity_display_precision(), Some(6));
+
+        let up = metric(Some(1000), Some(UnitConversionRoundingEnum::Up), Some(3));
+        assert_eq!(up.billed_quantity(dec!(1)), dec!(0.001));
+        assert_eq!(up.billed_quantity(dec!(1.2)), dec!(0.002));
+
+        let down = metric(None, Some(UnitConversionRoundingEnum::Down), Some(1));
+        assert_eq!(down.billed_quantity(dec!(1.99)), dec!(1.9));
+    }
+
+    #[test]
+    fn test_validate_quantity_precision() {
+        assert!(validate_quantity_precision(None).is_ok());
+        assert!(validate_quantity_precision(Some(0)).is_ok());
+        assert!(validate_quantity_precision(Some(12)).is_ok());
+        assert!(validate_quantity_precision(Some(13)).is_err());
+        assert!(validate_quantity_precision(Some(-1)).is_err());
+    }
+
+    #[test]
+    fn test_property_type_accepts() {
+        assert!(PropertyType::Number.accepts("1.5"));
+        assert!(PropertyType::Number.accepts("-3"));
+        assert!(!PropertyType::Number.accepts("NaN"));
+        assert!(PropertyType::Integer.accepts("42"));
+        assert!(!PropertyType::Integer.accepts("4.2"));
+        assert!(PropertyType::Boolean.accepts("false"));
+        assert!(!PropertyType::Boolean.accepts("yes"));
+        assert!(PropertyType::String.accepts(""));
+    }
+
+    #[test]
+    fn test_validate_event_schema() {
+        let sum = BillingMetricAggregateEnum::Sum;
+        let schema = |properties| EventSchema { properties };
+
+        assert!(schema(vec![
+            property("tokens", PropertyType::Integer, &[]),
+            property("model", PropertyType::String, &["gpt-4o", "o1"]),
+        ])
+        .validate(&sum, Some("tokens"))
+        .is_ok());
+
+        // duplicated key
+        assert!(schema(vec![
+            property("model", PropertyType::String, &[]),
+            property("model", PropertyType::Integer, &[]),
+        ])
+        .validate(&sum, None)
+        .is_err());
+
+        // allowed value of another type
+        assert!(schema(vec![property(
+            "tier",
+            PropertyType::Integer,
+            &["1", "gold"]
+        )])
+        .validate(&sum, None)
+        .is_err());
+
+        // aggregated property is not a number, unless counted
+        let model = schema(vec![property("model", PropertyType::String, &[])]);
+        assert!(model.validate(&sum, Some("model")).is_err());
+        assert!(model
+            .validate(&BillingMetricAggregateEnum::CountDistinct, Some("model"))
+            .is_ok());
+    }
+}
@@ -0,0 +1,73 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::BillingEmailStatusEnum;
+
+/// Delivery outcome reported by the email provider for a recipient
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum EmailDeliveryEventType {
+    Delivered,
+    /// temporary failure (mailbox full, greylisting ..), the address is kept
+    SoftBounced,
+    /// permanent failure, the address does not exist or rejects our emails
+    HardBounced,
+    /// the recipient reported an email as spam
+    Complained,
+}
+
+impl EmailDeliveryEventType {
+    /// The status of an address after this event, None if it does not change
+    pub fn next_status(&self, current: BillingEmailStatusEnum) -> Option<BillingEmailStatusEnum> {
+        let next = match self {
+            // a delivery does not clear a bounce or a complaint, the email has to be changed
+            EmailDeliveryEventType::Delivered if current == BillingEmailStatusEnum::Unverified => {
+                BillingEmailStatusEnum::Verified
+            }
+            EmailDeliveryEventType::Delivered | EmailDeliveryEventType::SoftBounced => {
+                return None;
+            }
+            EmailDeliveryEventType::HardBounced => BillingEmailStatusEnum::Bounced,
+            EmailDeliveryEventType::Complained => BillingEmailStatusEnum::Complained,
+        };
+
+        (next != current).then_some(next)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct EmailDeliveryEvent {
+    pub event_type: EmailDeliveryEventType,
+    pub recipient: String,
+    /// restricts the event to the customers of a tenant, when the provider reports it
+    pub tenant_id: Option<Uuid>,
+    pub reason: Option<String>,
+    pub occurred_at: NaiveDateTime,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_next_status() {
+        use BillingEmailStatusEnum::*;
+        use EmailDeliveryEventType::*;
+
+        assert_eq!(Delivered.next_status(Unverified), Some(Verified));
+        assert_eq!(Delivered.next_status(Verified), None);
+        assert_eq!(Delivered.next_status(Bounced), None);
+        assert_eq!(Delivered.next_status(Complained), None);
+
+        assert_eq!(SoftBounced.next_status(Unverified), None);
+        assert_eq!(SoftBounced.next_status(Verified), None);
+
+        assert_eq!(HardBounced.next_status(Verified), Some(Bounced));
+        assert_eq!(HardBounced.next_status(Complained), Some(Bounced));
+        assert_eq!(HardBounced.next_status(Bounced), None);
+
+        assert_eq!(Complained.next_status(Verified), Some(Complained));
+        assert_eq!(Complained.next_status(Complained), None);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::BTreeMap;
+
+use chrono::{NaiveDateTime, TimeDelta, Timelike};
+use diesel_models::billing_runs::{
+    BillingRunInvoiceRow, BillingRunSummaryRow, BillingRunSummaryRowNew,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::StoreError;
+
+/// The billing run summarized at `now` : the last full hour, during which the draft, finalize and issue workers
+/// all ran at least once
+pub fn billing_run_period(now: NaiveDateTime) -> (NaiveDateTime, NaiveDateTime) {
+    let end = now
+        .with_nanosecond(0)
+        .and_then(|d| d.with_second(0))
+        .and_then(|d| d.with_minute(0))
+        .unwrap_or(now);
+
+    (end - TimeDelta::hours(1), end)
+}
+
+/// An invoice created, finalized or issued during a billing run, or that failed to be issued
+#[derive(Debug, Clone)]
+pub struct BillingRunInvoice {
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_number: String,
+    pub currency: String,
+    pub total: i64,
+    pub created: bool,
+    pub finalized: bool,
+    pub issued: bool,
+    pub issue_error: Option<String>,
+}
+
+impl From<BillingRunInvoiceRow> for BillingRunInvoice {
+    fn from(row: BillingRunInvoiceRow) -> Self {
+        Self {
+            invoice_id: row.id,
+            tenant_id: row.tenant_id,
+            invoice_number: row.invoice_number,
+            currency: row.currency,
+            total: row.total,
+            created: row.created,
+            finalized: row.finalized,
+            issued: row.issued,
+            issue_error: row.issue_error,
+        }
+    }
+}
+
+/// Amounts of the invoices of a billing run in a currency, in cents
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillingRunTotal {
+    pub currency: String,
+    pub created_amount: i64,
+    pub finalized_amount: i64,
+    pub issued_amount: i64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct BillingRunFailure {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct BillingRunSummary {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub period_start: NaiveDateTime,
+    pub period_end: NaiveDateTime,
+    pub invoices_created: u32,
+    pub invoices_finalized: u32,
+    pub invoices_issued: u32,
+    pub issue_failures: u32,
+    pub totals: Vec<BillingRunTotal>,
+    pub failures: Vec<BillingRunFailure>,
+    pub created_at: NaiveDateTime,
+}
+
+impl TryFrom<BillingRunSummaryRow> for BillingRunSummary {
+    type Error = StoreError;
+
+    fn try_from(row: BillingRunSummaryRow) -> Result<Self, Self::Error> {
+        Ok(Self {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            period_start: row.period_start,
+            period_end: row.period_end,
+            invoices_created: row.invoices_created.max(0) as u32,
+            invoices_finalized: row.invoices_finalized.max(0) as u32,
+            invoices_issued: row.invoices_issued.max(0) as u32,
+            issue_failures: row.issue_failures.max(0) as u32,
+            totals: serde_json::from_value(row.totals).map_err(|e| {
+                StoreError::SerdeError("Failed to deserialize billing run totals".to_string(), e)
+            })?,
+            failures: serde_json::from_value(row.failures).map_err(|e| {
+                StoreError::SerdeError("Failed to deserialize billing run failures".to_string(), e)
+            })?,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BillingRunSummaryNew {
+    pub tenant_id: Uuid,
+    pub period_start: NaiveDateTime,
+    pub period_end: NaiveDateTime,
+    pub invoices_created: u32,
+    pub invoices_finalized: u32,
+    pub invoices_issued: u32,
+    pub issue_failures: u32,
+    pub totals: Vec<BillingRunTotal>,
+    pub failures: Vec<BillingRunFailure>,
+}
+
+impl BillingRunSummaryNew {
+    /// One summary per tenant with activity during the run. Totals are sorted by currency
+    pub fn summarize(
+        period_start: NaiveDateTime,
+        period_end: NaiveDateTime,
+        invoices: Vec<BillingRunInvoice>,
+    ) -> Vec<BillingRunSummaryNew> {
+        let mut by_tenant: BTreeMap<Uuid, Vec<BillingRunInvoice>> = BTreeMap::new();
+
+        for invoice in invoices {
+            by_tenant
+                .entry(invoice.tenant_id)
+                .or_default()
+                .push(invoice);
+        }
+
+        by_tenant
+            .into_iter()
+            .map(|(tenant_id, invoices)| {
+                let mut totals: BTreeMap<String, BillingRunTotal> = BTreeMap::new();
+                let mut summary = BillingRunSummaryNew {
+                    tenant_id,
+                    period_start,
+                    period_end,
+                    invoices_created: 0,
+                    invoices_finalized: 0,
+                    invoices_issued: 0,
+                    issue_failures: 0,
+                    totals: Vec::new(),
+                    failures: Vec::new(),
+                };
+
+                for invoice in invoices {
+                    let total =
+                        totals
+                            .entry(invoice.currency.clone())
+                            .or_insert_with(|| BillingRunTotal {
+                                currency: invoice.currency.clone(),
+                                ..Default::default()
+                            });
+
+                    if invoice.created {
+                        summary.invoices_created += 1;
+                        total.created_amount += invoice.total;
+                    }
+                    if invoice.finalized {
+                        summary.invoices_finalized += 1;
+                        total.finalized_amount += invoice.total;
+                    }
+                    if invoice.issued {
+                        summary.invoices_issued += 1;
+                        total.issued_amount += invoice.total;
+                    }
+                    if let Some(reason) = invoice.issue_error {
+                        summary.issue_failures += 1;
+                        summary.failures.push(BillingRunFailure {
+                            invoice_id: invoice.invoice_id,
+                            invoice_number: invoice.invoice_number,
+                            reason,
+                        });
+                    }
+                }
+
+                summary.totals = totals.into_values().collect();
+                summary
+            })
+            .collect()
+    }
+}
+
+impl TryFrom<BillingRunSummaryNew> for BillingRunSummaryRowNew {
+    type Error = StoreError;
+
+    fn try_from(summary: BillingRunSummaryNew) -> Result<Self, Self::Error> {
+        Ok(BillingRunSummaryRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: summary.tenant_id,
+            period_start: summary.period_start,
+            period_end: summary.period_end,
+            invoices_created: summary.invoices_created as i32,
+            invoices_finalized: summary.invoices_finalized as i32,
+            invoices_issued: summary.invoices_issued as i32,
+            issue_failures: summary.issue_failures as i32,
+            totals: serde_json::to_value(&summary.totals).map_err(|e| {
+                StoreError::SerdeError("Failed to serialize billing run totals".to_string(), e)
+            })?,
+            failures: serde_json::to_value(&summary.failures).map_err(|e| {
+                StoreError::SerdeError("Failed to serialize billing run failures".to_string(), e)
+            })?,
+        })
+    }
+}
+
+/// Payload of the outbox entry requesting the summary email to the finance contact of the tenant
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BillingRunSummaryEmailPayload {
+    pub billing_run_summary_id: Uuid,
+    pub recipient: String,
+    pub tenant_name: String,
+    pub period_start: NaiveDateTime,
+    pub period_end: NaiveDateTime,
+    pub invoices_created: u32,
+    pub invoices_finalized: u32,
+    pub invoices_issued: u32,
+    pub issue_failures: u32,
+    pub totals: Vec<BillingRunTotal>,
+    pub failures: Vec<BillingRunFailure>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn invoice(tenant_id: Uuid, currency: &str, total: i64) -> BillingRunInvoice {
+        BillingRunInvoice {
+            invoice_id: Uuid::now_v7(),
+            tenant_id,
+            invoice_number: "INV-1".to_string(),
+            currency: currency.to_string(),
+            total,
+            created: false,
+            finalized: false,
+            issued: false,
+            issue_error: None,
+        }
+    }
+
+    #[test]
+    fn test_billing_run_period() {
+        let now = NaiveDate::from_ymd_opt(2024, 11, 2)
+            .unwrap()
+            .and_hms_milli_opt(10, 50, 12, 345)
+            .unwrap();
+
+        let (start, end) = billing_run_period(now);
+
+        assert_eq!(
+            start,
+            NaiveDate::from_ymd_opt(2024, 11, 2)
+                .unwrap()
+                .and_hms_opt(9, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            end,
+            NaiveDate::from_ymd_opt(2024, 11, 2)
+                .unwrap()
+                .and_hms_opt(10, 0, 0)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_summarize() {
+        let tenant_a = Uuid::now_v7();
+        let tenant_b = Uuid::now_v7();
+        let (start, end) = billing_run_period(NaiveDateTime::default());
+
+        let invoices = vec![
+            BillingRunInvoice {
+                created: true,
+                finalized: true,
+                ..invoice(tenant_a, "EUR", 1000)
+            },
+            BillingRunInvoice {
+                issued: true,
+                ..invoice(tenant_a, "EUR", 500)
+            },
+            BillingRunInvoice {
+                created: true,
+                ..invoice(tenant_a, "USD", 200)
+            },
+            BillingRunInvoice {
+                issue_error: Some("card declined".to_string()),
+                ..invoice(tenant_b, "EUR", 300)
+            },
+        ];
+
+        let summaries = BillingRunSummaryNew::summarize(start, end, invoices);
+        assert_eq!(summaries.len(), 2);
+
+        let a = summaries.iter().find(|s| s.tenant_id == tenant_a).unwrap();
+        assert_eq!(a.invoices_created, 2);
+        assert_eq!(a.invoices_finalized, 1);
+        assert_eq!(a.invoices_issued, 1);
+        assert_eq!(a.issue_failures, 0);
+        assert_eq!(
+            a.totals,
+            vec![
+                BillingRunTotal {
+                    currency: "EUR".to_string(),
+                    created_amount: 1000,
+                    finalized_amount: 1000,
+                    issued_amount: 500,
+                },
+                BillingRunTotal {
+                    currency: "USD".to_string(),
+                    created_amount: 200,
+                    finalized_amount: 0,
+                    issued_amount: 0,
+                },
+            ]
+        );
+
+        let b = summaries.iter().find(|s| s.tenant_id == tenant_b).unwrap();
+        assert_eq!(b.issue_failures, 1);
+        assert_eq!(b.failures[0].reason, "card declined");
+    }
+}
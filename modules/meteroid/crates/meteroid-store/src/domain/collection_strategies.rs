@@ -0,0 +1,399 @@
+use chrono::NaiveDateTime;
+use diesel_models::collection_strategies::{
+    CollectionStrategyOverrideRow, CollectionStrategyOverrideRowNew,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::CollectionStrategyPresetEnum;
+use crate::domain::payment_reminders::{MAX_DAYS_AFTER_DUE, MAX_DAYS_BEFORE_DUE};
+use crate::errors::StoreError;
+
+pub const MAX_SEGMENT_LENGTH: usize = 64;
+pub const MAX_PAYMENT_RETRIES: usize = 10;
+
+/// Fee added to an unpaid invoice, as a percent of the amount due, once the grace period after the due date elapsed
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct LateFee {
+    pub percent: Decimal,
+    pub grace_days: u32,
+}
+
+impl LateFee {
+    /// Fee on `amount_due` (in cents)
+    pub fn amount_for(&self, amount_due: i64) -> i64 {
+        if amount_due <= 0 {
+            return 0;
+        }
+
+        (Decimal::from(amount_due) * self.percent / Decimal::ONE_HUNDRED)
+            .round()
+            .to_i64()
+            .unwrap_or(0)
+    }
+}
+
+/// How the unpaid invoices of a customer are collected
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CollectionRules {
+    /// days after the due date at which a failed payment is retried, ascending
+    pub retry_schedule_days: Vec<u32>,
+    /// days relative to the due date at which the customer is reminded, negative before it
+    pub reminder_offsets_days: Vec<i32>,
+    /// days after the due date at which the subscriptions are suspended, never if None
+    pub suspend_after_days: Option<u32>,
+    pub late_fee: Option<LateFee>,
+}
+
+impl CollectionRules {
+    pub fn apply(mut self, rules: &CollectionRulesOverride) -> Self {
+        if let Some(retry_schedule_days) = &rules.retry_schedule_days {
+            self.retry_schedule_days = retry_schedule_days.clone();
+        }
+        if let Some(reminder_offsets_days) = &rules.reminder_offsets_days {
+            self.reminder_offsets_days = reminder_offsets_days.clone();
+        }
+        if let Some(suspend_after_days) = rules.suspend_after_days {
+            self.suspend_after_days = Some(suspend_after_days).filter(|d| *d > 0);
+        }
+        if let Some(late_fee) = &rules.late_fee {
+            self.late_fee = Some(late_fee.clone()).filter(|f| f.percent > Decimal::ZERO);
+        }
+        self
+    }
+}
+
+impl CollectionStrategyPresetEnum {
+    pub fn rules(&self) -> CollectionRules {
+        match self {
+            CollectionStrategyPresetEnum::Lenient => CollectionRules {
+                retry_schedule_days: vec![3, 7, 14, 21],
+                reminder_offsets_days: vec![-3, 7, 14, 30],
+                suspend_after_days: None,
+                late_fee: None,
+            },
+            CollectionStrategyPresetEnum::Standard => CollectionRules {
+                retry_schedule_days: vec![1, 3, 7],
+                reminder_offsets_days: vec![-3, 0, 7, 14],
+                suspend_after_days: Some(30),
+                late_fee: Some(LateFee {
+                    percent: dec!(1),
+                    grace_days: 30,
+                }),
+            },
+            CollectionStrategyPresetEnum::Strict => CollectionRules {
+                retry_schedule_days: vec![1, 2, 4],
+                reminder_offsets_days: vec![-7, -1, 0, 3, 7],
+                suspend_after_days: Some(14),
+                late_fee: Some(LateFee {
+                    percent: dec!(2),
+                    grace_days: 7,
+                }),
+            },
+        }
+    }
+}
+
+/// Rules of a segment replacing those of its preset. A suspension after 0 days, or a late fee of 0 percent, disables them
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct CollectionRulesOverride {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub retry_schedule_days: Option<Vec<u32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reminder_offsets_days: Option<Vec<i32>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub suspend_after_days: Option<u32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub late_fee: Option<LateFee>,
+}
+
+impl CollectionRulesOverride {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        let max_after_due = MAX_DAYS_AFTER_DUE as u32;
+
+        if let Some(retries) = &self.retry_schedule_days {
+            if retries.len() > MAX_PAYMENT_RETRIES {
+                return Err(StoreError::InvalidArgument(format!(
+                    "at most {} payment retries can be scheduled",
+                    MAX_PAYMENT_RETRIES
+                )));
+            }
+            if retries.iter().any(|d| *d == 0 || *d > max_after_due)
+                || retries.windows(2).any(|w| w[0] >= w[1])
+            {
+                return Err(StoreError::InvalidArgument(format!(
+                    "payment retries must be in ascending order, between 1 and {} days after the due date",
+                    max_after_due
+                )));
+            }
+        }
+
+        if let Some(reminders) = &self.reminder_offsets_days {
+            if reminders
+                .iter()
+                .any(|d| !(-MAX_DAYS_BEFORE_DUE..=MAX_DAYS_AFTER_DUE).contains(d))
+            {
+                return Err(StoreError::InvalidArgument(format!(
+                    "reminders must be sent between {} days before and {} days after the due date",
+                    MAX_DAYS_BEFORE_DUE, MAX_DAYS_AFTER_DUE
+                )));
+            }
+        }
+
+        if self.suspend_after_days.is_some_and(|d| d > max_after_due) {
+            return Err(StoreError::InvalidArgument(format!(
+                "subscriptions must be suspended within {} days after the due date",
+                max_after_due
+            )));
+        }
+
+        if let Some(late_fee) = &self.late_fee {
+            if late_fee.percent < Decimal::ZERO || late_fee.percent > Decimal::ONE_HUNDRED {
+                return Err(StoreError::InvalidArgument(
+                    "the late fee must be between 0 and 100 percent".to_string(),
+                ));
+            }
+            if late_fee.grace_days > max_after_due {
+                return Err(StoreError::InvalidArgument(format!(
+                    "the late fee grace period cannot exceed {} days",
+                    max_after_due
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Trimmed segment, compared as written
+pub fn normalize_segment(segment: &str) -> Result<String, StoreError> {
+    let segment = segment.trim();
+
+    if segment.is_empty() || segment.chars().count() > MAX_SEGMENT_LENGTH {
+        return Err(StoreError::InvalidArgument(format!(
+            "the segment must be between 1 and {} characters",
+            MAX_SEGMENT_LENGTH
+        )));
+    }
+
+    Ok(segment.to_string())
+}
+
+/// The collection strategy of the tenant replaced for the customers of a segment
+#[derive(Clone, Debug)]
+pub struct CollectionStrategyOverride {
+    pub tenant_id: Uuid,
+    pub segment: String,
+    pub preset: CollectionStrategyPresetEnum,
+    pub rules: CollectionRulesOverride,
+    pub updated_at: NaiveDateTime,
+    pub updated_by: Uuid,
+}
+
+impl TryFrom<CollectionStrategyOverrideRow> for CollectionStrategyOverride {
+    type Error = StoreError;
+
+    fn try_from(row: CollectionStrategyOverrideRow) -> Result<Self, Self::Error> {
+        let rules: CollectionRulesOverride = serde_json::from_value(row.rules).map_err(|e| {
+            StoreError::SerdeError("Failed to deserialize collection rules".to_string(), e)
+        })?;
+
+        Ok(CollectionStrategyOverride {
+            tenant_id: row.tenant_id,
+            segment: row.segment,
+            preset: row.preset.into(),
+            rules,
+            updated_at: row.updated_at,
+            updated_by: row.updated_by,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct CollectionStrategyOverrideNew {
+    pub tenant_id: Uuid,
+    pub segment: String,
+    pub preset: CollectionStrategyPresetEnum,
+    pub rules: CollectionRulesOverride,
+    pub updated_by: Uuid,
+}
+
+impl CollectionStrategyOverrideNew {
+    pub fn into_row(
+        self,
+        now: NaiveDateTime,
+    ) -> Result<CollectionStrategyOverrideRowNew, StoreError> {
+        self.rules.validate()?;
+
+        let rules = serde_json::to_value(&self.rules).map_err(|e| {
+            StoreError::SerdeError("Failed to serialize collection rules".to_string(), e)
+        })?;
+
+        Ok(CollectionStrategyOverrideRowNew {
+            tenant_id: self.tenant_id,
+            segment: normalize_segment(&self.segment)?,
+            preset: self.preset.into(),
+            rules,
+            updated_at: now,
+            updated_by: self.updated_by,
+        })
+    }
+}
+
+/// The collection strategy applying to a customer
+#[derive(Clone, Debug)]
+pub struct CollectionStrategy {
+    pub preset: CollectionStrategyPresetEnum,
+    /// the segment whose override applies, None for the strategy of the tenant
+    pub segment: Option<String>,
+    pub rules: CollectionRules,
+}
+
+impl CollectionStrategy {
+    pub fn resolve(
+        tenant_preset: CollectionStrategyPresetEnum,
+        segment_override: Option<CollectionStrategyOverride>,
+    ) -> Self {
+        match segment_override {
+            Some(o) => CollectionStrategy {
+                preset: o.preset,
+                rules: o.preset.rules().apply(&o.rules),
+                segment: Some(o.segment),
+            },
+            None => CollectionStrategy {
+                preset: tenant_preset,
+                segment: None,
+                rules: tenant_preset.rules(),
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment_override(rules: CollectionRulesOverride) -> CollectionStrategyOverride {
+        CollectionStrategyOverride {
+            tenant_id: Uuid::nil(),
+            segment: "enterprise".to_string(),
+            preset: CollectionStrategyPresetEnum::Lenient,
+            rules,
+            updated_at: NaiveDateTime::MIN,
+            updated_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_presets_are_valid() {
+        for preset in [
+            CollectionStrategyPresetEnum::Lenient,
+            CollectionStrategyPresetEnum::Standard,
+            CollectionStrategyPresetEnum::Strict,
+        ] {
+            let rules = preset.rules();
+            let as_override = CollectionRulesOverride {
+                retry_schedule_days: Some(rules.retry_schedule_days),
+                reminder_offsets_days: Some(rules.reminder_offsets_days),
+                suspend_after_days: rules.suspend_after_days,
+                late_fee: rules.late_fee,
+            };
+            assert!(as_override.validate().is_ok(), "{:?}", preset);
+        }
+    }
+
+    #[test]
+    fn test_resolve() {
+        let tenant = CollectionStrategy::resolve(CollectionStrategyPresetEnum::Strict, None);
+        assert_eq!(tenant.preset, CollectionStrategyPresetEnum::Strict);
+        assert_eq!(tenant.segment, None);
+        assert_eq!(tenant.rules, CollectionStrategyPresetEnum::Strict.rules());
+
+        let segment = CollectionStrategy::resolve(
+            CollectionStrategyPresetEnum::Strict,
+            Some(segment_override(CollectionRulesOverride {
+                suspend_after_days: Some(60),
+                late_fee: Some(LateFee {
+                    percent: dec!(1.5),
+                    grace_days: 15,
+                }),
+                ..Default::default()
+            })),
+        );
+        assert_eq!(segment.preset, CollectionStrategyPresetEnum::Lenient);
+        assert_eq!(segment.segment.as_deref(), Some("enterprise"));
+        assert_eq!(segment.rules.retry_schedule_days, vec![3, 7, 14, 21]);
+        assert_eq!(segment.rules.suspend_after_days, Some(60));
+        assert_eq!(segment.rules.late_fee.unwrap().percent, dec!(1.5));
+    }
+
+    #[test]
+    fn test_override_disables_rules() {
+        let rules =
+            CollectionStrategyPresetEnum::Standard
+                .rules()
+                .apply(&CollectionRulesOverride {
+                    suspend_after_days: Some(0),
+                    late_fee: Some(LateFee {
+                        percent: Decimal::ZERO,
+                        grace_days: 0,
+                    }),
+                    ..Default::default()
+                });
+
+        assert_eq!(rules.suspend_after_days, None);
+        assert_eq!(rules.late_fee, None);
+    }
+
+    #[test]
+    fn test_validate_override() {
+        let retries = |days: Vec<u32>| CollectionRulesOverride {
+            retry_schedule_days: Some(days),
+            ..Default::default()
+        };
+
+        assert!(retries(vec![1, 5, 10]).validate().is_ok());
+        assert!(retries(vec![5, 1]).validate().is_err());
+        assert!(retries(vec![0, 1]).validate().is_err());
+        assert!(retries(vec![1, 1]).validate().is_err());
+
+        let late_fee = |percent: Decimal| CollectionRulesOverride {
+            late_fee: Some(LateFee {
+                percent,
+                grace_days: 10,
+            }),
+            ..Default::default()
+        };
+
+        assert!(late_fee(dec!(5)).validate().is_ok());
+        assert!(late_fee(dec!(101)).validate().is_err());
+
+        assert!(CollectionRulesOverride {
+            reminder_offsets_days: Some(vec![-MAX_DAYS_BEFORE_DUE - 1]),
+            ..Default::default()
+        }
+        .validate()
+        .is_err());
+    }
+
+    #[test]
+    fn test_late_fee_amount() {
+        let fee = LateFee {
+            percent: dec!(1.5),
+            grace_days: 0,
+        };
+
+        assert_eq!(fee.amount_for(10_000), 150);
+        assert_eq!(fee.amount_for(0), 0);
+    }
+
+    #[test]
+    fn test_normalize_segment() {
+        assert_eq!(normalize_segment(" smb ").unwrap(), "smb");
+        assert!(normalize_segment("  ").is_err());
+        assert!(normalize_segment(&"a".repeat(MAX_SEGMENT_LENGTH + 1)).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use chrono::NaiveDateTime;
+use diesel_models::component_rules::{ComponentRuleRow, ComponentRuleRowNew};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::ComponentRuleTypeEnum;
+use crate::errors::StoreError;
+
+/// An item of the catalog that can be sold in a subscription
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ComponentRuleItem {
+    PriceComponent(Uuid),
+    AddOn(Uuid),
+}
+
+impl ComponentRuleItem {
+    fn from_columns(price_component_id: Option<Uuid>, add_on_id: Option<Uuid>) -> Option<Self> {
+        match (price_component_id, add_on_id) {
+            (Some(id), None) => Some(ComponentRuleItem::PriceComponent(id)),
+            (None, Some(id)) => Some(ComponentRuleItem::AddOn(id)),
+            _ => None,
+        }
+    }
+
+    fn price_component_id(&self) -> Option<Uuid> {
+        match self {
+            ComponentRuleItem::PriceComponent(id) => Some(*id),
+            ComponentRuleItem::AddOn(_) => None,
+        }
+    }
+
+    fn add_on_id(&self) -> Option<Uuid> {
+        match self {
+            ComponentRuleItem::PriceComponent(_) => None,
+            ComponentRuleItem::AddOn(id) => Some(*id),
+        }
+    }
+}
+
+impl fmt::Display for ComponentRuleItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ComponentRuleItem::PriceComponent(id) => write!(f, "price component {}", id),
+            ComponentRuleItem::AddOn(id) => write!(f, "add-on {}", id),
+        }
+    }
+}
+
+/// A catalog rule between two items: the subject requires or excludes the target.
+/// Rules are checked when a subscription is created and when its components change
+#[derive(Clone, Debug)]
+pub struct ComponentRule {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_type: ComponentRuleTypeEnum,
+    pub subject: ComponentRuleItem,
+    pub target: ComponentRuleItem,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<ComponentRuleRow> for ComponentRule {
+    type Error = StoreError;
+
+    fn try_from(row: ComponentRuleRow) -> Result<Self, Self::Error> {
+        let subject =
+            ComponentRuleItem::from_columns(row.subject_price_component_id, row.subject_add_on_id);
+        let target =
+            ComponentRuleItem::from_columns(row.target_price_component_id, row.target_add_on_id);
+
+        match (subject, target) {
+            (Some(subject), Some(target)) => Ok(ComponentRule {
+                id: row.id,
+                tenant_id: row.tenant_id,
+                rule_type: row.rule_type.into(),
+                subject,
+                target,
+                created_at: row.created_at,
+                created_by: row.created_by,
+            }),
+            _ => Err(StoreError::InvalidArgument(format!(
+                "component rule {} must have exactly one subject and one target",
+                row.id
+            ))),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct ComponentRuleNew {
+    pub tenant_id: Uuid,
+    pub rule_type: ComponentRuleTypeEnum,
+    pub subject: ComponentRuleItem,
+    pub target: ComponentRuleItem,
+    pub created_by: Uuid,
+}
+
+impl From<ComponentRuleNew> for ComponentRuleRowNew {
+    fn from(rule: ComponentRuleNew) -> Self {
+        ComponentRuleRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: rule.tenant_id,
+            rule_type: rule.rule_type.into(),
+            subject_price_component_id: rule.subject.price_component_id(),
+            subject_add_on_id: rule.subject.add_on_id(),
+            target_price_component_id: rule.target.price_component_id(),
+            target_add_on_id: rule.target.add_on_id(),
+            created_by: rule.created_by,
+        }
+    }
+}
+
+/// A rule that a set of items does not satisfy
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ComponentRuleViolation {
+    pub rule_id: Uuid,
+    pub rule_type: ComponentRuleTypeEnum,
+    pub subject: ComponentRuleItem,
+    pub target: ComponentRuleItem,
+}
+
+impl fmt::Display for ComponentRuleViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.rule_type {
+            ComponentRuleTypeEnum::Requires => {
+                write!(f, "{} requires {}", self.subject, self.target)
+            }
+            ComponentRuleTypeEnum::Excludes => {
+                write!(
+                    f,
+                    "{} cannot be combined with {}",
+                    self.subject, self.target
+                )
+            }
+        }
+    }
+}
+
+/// The rules violated by the items of a subscription.
+/// A rule whose subject is not part of the items does not apply
+pub fn check_component_rules(
+    rules: &[ComponentRule],
+    items: &[ComponentRuleItem],
+) -> Vec<ComponentRuleViolation> {
+    let items: HashSet<&ComponentRuleItem> = items.iter().collect();
+
+    rules
+        .iter()
+        .filter(|rule| items.contains(&rule.subject))
+        .filter(|rule| match rule.rule_type {
+            ComponentRuleTypeEnum::Requires => !items.contains(&rule.target),
+            ComponentRuleTypeEnum::Excludes => items.contains(&rule.target),
+        })
+        .map(|rule| ComponentRuleViolation {
+            rule_id: rule.id,
+            rule_type: rule.rule_type,
+            subject: rule.subject,
+            target: rule.target,
+        })
+        .collect()
+}
+
+/// Fails with all the rules violated by the items of a subscription
+pub fn validate_component_rules(
+    rules: &[ComponentRule],
+    items: &[ComponentRuleItem],
+) -> Result<(), StoreError> {
+    let violations = check_component_rules(rules, items);
+
+    if violations.is_empty() {
+        Ok(())
+    } else {
+        Err(StoreError::ComponentRulesViolated(violations))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(
+        rule_type: ComponentRuleTypeEnum,
+        subject: ComponentRuleItem,
+        target: ComponentRuleItem,
+    ) -> ComponentRule {
+        ComponentRule {
+            id: Uuid::now_v7(),
+            tenant_id: Uuid::nil(),
+            rule_type,
+            subject,
+            target,
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_check_component_rules() {
+        let a = ComponentRuleItem::PriceComponent(Uuid::now_v7());
+        let b = ComponentRuleItem::PriceComponent(Uuid::now_v7());
+        let c = ComponentRuleItem::AddOn(Uuid::now_v7());
+        let d = ComponentRuleItem::AddOn(Uuid::now_v7());
+
+        let rules = vec![
+            rule(ComponentRuleTypeEnum::Requires, b, a),
+            rule(ComponentRuleTypeEnum::Excludes, c, d),
+        ];
+
+        assert!(check_component_rules(&rules, &[]).is_empty());
+        assert!(check_component_rules(&rules, &[a]).is_empty());
+        assert!(check_component_rules(&rules, &[a, b]).is_empty());
+        assert!(check_component_rules(&rules, &[a, c]).is_empty());
+        assert!(check_component_rules(&rules, &[d]).is_empty());
+
+        let violations = check_component_rules(&rules, &[b]);
+        assert_eq!(violations.len(), 1);
+        assert_eq!(violations[0].rule_id, rules[0].id);
+        assert_eq!(violations[0].target, a);
+
+        let violations = check_component_rules(&rules, &[b, c, d]);
+        assert_eq!(violations.len(), 2);
+        assert_eq!(violations[1].rule_type, ComponentRuleTypeEnum::Excludes);
+    }
+}
@@ -16,6 +16,12 @@ pub struct WebhookSecurity {
 #[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct ApiSecurity {
     pub api_key: String,
+    /// Adyen merchant account receiving the payments, not a secret
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub merchant_account: Option<String>,
+    /// prefix of the live endpoints of the Adyen company account, the test endpoints are used without it
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub live_url_prefix: Option<String>,
 }
 
 #[derive(Clone, Debug)]
@@ -27,6 +33,8 @@ pub struct ProviderConfig {
     pub enabled: bool,
     pub webhook_security: WebhookSecurity,
     pub api_security: ApiSecurity,
+    pub is_default: bool,
+    pub fallback_priority: Option<i32>,
 }
 
 impl ProviderConfig {
@@ -56,6 +64,8 @@ impl ProviderConfig {
                 ))?
                 .expose_secret()
                 .clone(),
+            merchant_account: enc_api_sec.merchant_account,
+            live_url_prefix: enc_api_sec.live_url_prefix,
         };
 
         Ok(ProviderConfig {
@@ -66,6 +76,8 @@ impl ProviderConfig {
             enabled: row.enabled,
             webhook_security: wh_sec,
             api_security: api_sec,
+            is_default: row.is_default,
+            fallback_priority: row.fallback_priority,
         })
     }
 }
@@ -77,6 +89,8 @@ pub struct ProviderConfigNew {
     pub enabled: bool,
     pub webhook_security: WebhookSecurity,
     pub api_security: ApiSecurity,
+    pub is_default: bool,
+    pub fallback_priority: Option<i32>,
 }
 
 impl ProviderConfigNew {
@@ -93,6 +107,8 @@ impl ProviderConfigNew {
                 .change_context(StoreError::CryptError(
                     "api_security encryption error".into(),
                 ))?,
+            merchant_account: self.api_security.merchant_account.clone(),
+            live_url_prefix: self.api_security.live_url_prefix.clone(),
         };
 
         let wh_sec = serde_json::to_value(&wh_sec_enc).map_err(|e| {
@@ -110,6 +126,129 @@ impl ProviderConfigNew {
             enabled: self.enabled,
             webhook_security: wh_sec,
             api_security: api_sec,
+            is_default: self.is_default,
+            fallback_priority: self.fallback_priority,
         })
     }
 }
+
+/// The enabled configs to try, in order, when issuing an invoice requesting `requested`: its own config, then the
+/// default of the tenant, then the fallbacks by ascending priority. A config is tried at most once
+pub fn invoicing_provider_candidates(
+    requested: &InvoicingProviderEnum,
+    configs: Vec<ProviderConfig>,
+) -> Vec<ProviderConfig> {
+    let mut configs: Vec<ProviderConfig> = configs.into_iter().filter(|c| c.enabled).collect();
+    let mut candidates = Vec::with_capacity(configs.len());
+
+    if let Some(idx) = configs
+        .iter()
+        .position(|c| &c.invoicing_provider == requested)
+    {
+        candidates.push(configs.remove(idx));
+    }
+
+    if let Some(idx) = configs.iter().position(|c| c.is_default) {
+        candidates.push(configs.remove(idx));
+    }
+
+    let mut fallbacks: Vec<ProviderConfig> = configs
+        .into_iter()
+        .filter(|c| c.fallback_priority.is_some())
+        .collect();
+    fallbacks.sort_by_key(|c| c.fallback_priority);
+
+    candidates.extend(fallbacks);
+    candidates
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(
+        provider: InvoicingProviderEnum,
+        is_default: bool,
+        fallback_priority: Option<i32>,
+    ) -> ProviderConfig {
+        ProviderConfig {
+            id: Uuid::now_v7(),
+            created_at: NaiveDateTime::default(),
+            tenant_id: Uuid::nil(),
+            invoicing_provider: provider,
+            enabled: true,
+            webhook_security: WebhookSecurity {
+                secret: "whsec".to_string(),
+            },
+            api_security: ApiSecurity {
+                api_key: "sk".to_string(),
+                merchant_account: None,
+                live_url_prefix: None,
+            },
+            is_default,
+            fallback_priority,
+        }
+    }
+
+    fn ids(configs: &[ProviderConfig]) -> Vec<Uuid> {
+        configs.iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn test_candidates_order() {
+        let requested = config(InvoicingProviderEnum::Stripe, false, None);
+        let default = config(InvoicingProviderEnum::Manual, true, None);
+        let second = config(InvoicingProviderEnum::Manual, false, Some(2));
+        let first = config(InvoicingProviderEnum::Manual, false, Some(1));
+        let not_fallback = config(InvoicingProviderEnum::Manual, false, None);
+        let disabled = ProviderConfig {
+            enabled: false,
+            ..config(InvoicingProviderEnum::Manual, false, Some(0))
+        };
+
+        let candidates = invoicing_provider_candidates(
+            &InvoicingProviderEnum::Stripe,
+            vec![
+                second.clone(),
+                not_fallback,
+                default.clone(),
+                disabled,
+                first.clone(),
+                requested.clone(),
+            ],
+        );
+
+        assert_eq!(
+            ids(&candidates),
+            vec![requested.id, default.id, first.id, second.id]
+        );
+    }
+
+    #[test]
+    fn test_candidates_requested_is_default() {
+        let requested = config(InvoicingProviderEnum::Stripe, true, Some(1));
+        let fallback = config(InvoicingProviderEnum::Manual, false, Some(2));
+
+        let candidates = invoicing_provider_candidates(
+            &InvoicingProviderEnum::Stripe,
+            vec![fallback.clone(), requested.clone()],
+        );
+
+        assert_eq!(ids(&candidates), vec![requested.id, fallback.id]);
+    }
+
+    #[test]
+    fn test_candidates_without_requested_config() {
+        let default = config(InvoicingProviderEnum::Manual, true, None);
+        let fallback = config(InvoicingProviderEnum::Manual, false, Some(1));
+
+        let candidates = invoicing_provider_candidates(
+            &InvoicingProviderEnum::Stripe,
+            vec![fallback.clone(), default.clone()],
+        );
+
+        assert_eq!(ids(&candidates), vec![default.id, fallback.id]);
+
+        assert!(invoicing_provider_candidates(&InvoicingProviderEnum::Stripe, vec![]).is_empty());
+    }
+}
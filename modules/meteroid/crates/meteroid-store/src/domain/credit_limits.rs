@@ -0,0 +1,212 @@
+use chrono::NaiveDateTime;
+use diesel_models::credit_limits::{
+    CreditExposureRow, CreditLimitExceededRow, CreditLimitExceededRowNew,
+};
+use diesel_models::enums as diesel_enums;
+use uuid::Uuid;
+
+use crate::domain::enums::{CreditLimitEnforcementEnum, CreditLimitSourceEnum};
+use crate::errors::StoreError;
+
+/// Credit granted to a customer, in cents of its currency
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreditLimit {
+    pub cents: i64,
+    pub enforcement: CreditLimitEnforcementEnum,
+}
+
+impl CreditLimit {
+    pub(crate) fn from_row(
+        cents: Option<i64>,
+        enforcement: diesel_enums::CreditLimitEnforcementEnum,
+    ) -> Option<Self> {
+        cents.map(|cents| CreditLimit {
+            cents,
+            enforcement: enforcement.into(),
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.cents < 0 {
+            return Err(StoreError::InvalidArgument(
+                "credit limit must be positive".to_string(),
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// Amounts owed by a customer, in cents of its currency
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CreditExposure {
+    pub customer_id: Uuid,
+    /// finalized invoices not paid yet
+    pub unpaid_invoices: i64,
+    /// draft and pending invoices, with the usage rated so far
+    pub unbilled_usage: i64,
+}
+
+impl CreditExposure {
+    pub fn none(customer_id: Uuid) -> Self {
+        CreditExposure {
+            customer_id,
+            unpaid_invoices: 0,
+            unbilled_usage: 0,
+        }
+    }
+
+    pub fn total(&self) -> i64 {
+        self.unpaid_invoices + self.unbilled_usage
+    }
+
+    /// Whether the customer owes more than its credit limit
+    pub fn exceeds(&self, limit: &CreditLimit) -> bool {
+        self.total() > limit.cents
+    }
+
+    /// Credit left before the limit, zero once it is exceeded
+    pub fn available(&self, limit: &CreditLimit) -> i64 {
+        (limit.cents - self.total()).max(0)
+    }
+}
+
+impl From<CreditExposureRow> for CreditExposure {
+    fn from(row: CreditExposureRow) -> Self {
+        CreditExposure {
+            customer_id: row.customer_id,
+            unpaid_invoices: row.unpaid_invoices,
+            unbilled_usage: row.unbilled_usage,
+        }
+    }
+}
+
+/// A subscription or threshold invoice of a customer over its credit limit, recorded for finance review
+#[derive(Clone, Debug)]
+pub struct CreditLimitExceeded {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub source: CreditLimitSourceEnum,
+    /// none for a blocked subscription, which is never created
+    pub subscription_id: Option<Uuid>,
+    pub enforcement: CreditLimitEnforcementEnum,
+    pub currency: String,
+    pub credit_limit: i64,
+    pub exposure: CreditExposure,
+    pub exceeded_at: NaiveDateTime,
+}
+
+impl From<CreditLimitExceededRow> for CreditLimitExceeded {
+    fn from(row: CreditLimitExceededRow) -> Self {
+        CreditLimitExceeded {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            customer_id: row.customer_id,
+            source: row.source.into(),
+            subscription_id: row.subscription_id,
+            enforcement: row.enforcement.into(),
+            currency: row.currency,
+            credit_limit: row.credit_limit,
+            exposure: CreditExposure {
+                customer_id: row.customer_id,
+                unpaid_invoices: row.unpaid_invoices,
+                unbilled_usage: row.unbilled_usage,
+            },
+            exceeded_at: row.exceeded_at,
+        }
+    }
+}
+
+pub struct CreditLimitExceededNew {
+    pub tenant_id: Uuid,
+    pub source: CreditLimitSourceEnum,
+    pub subscription_id: Option<Uuid>,
+    pub currency: String,
+    pub credit_limit: CreditLimit,
+    pub exposure: CreditExposure,
+}
+
+impl From<CreditLimitExceededNew> for CreditLimitExceededRowNew {
+    fn from(value: CreditLimitExceededNew) -> Self {
+        // a blocked subscription is never created
+        let subscription_id = match (value.source, value.credit_limit.enforcement) {
+            (CreditLimitSourceEnum::Subscription, CreditLimitEnforcementEnum::Block) => None,
+            _ => value.subscription_id,
+        };
+
+        CreditLimitExceededRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: value.tenant_id,
+            customer_id: value.exposure.customer_id,
+            source: value.source.into(),
+            subscription_id,
+            enforcement: value.credit_limit.enforcement.into(),
+            currency: value.currency,
+            credit_limit: value.credit_limit.cents,
+            unpaid_invoices: value.exposure.unpaid_invoices,
+            unbilled_usage: value.exposure.unbilled_usage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn exposure(unpaid_invoices: i64, unbilled_usage: i64) -> CreditExposure {
+        CreditExposure {
+            customer_id: Uuid::nil(),
+            unpaid_invoices,
+            unbilled_usage,
+        }
+    }
+
+    fn limit(cents: i64, enforcement: CreditLimitEnforcementEnum) -> CreditLimit {
+        CreditLimit { cents, enforcement }
+    }
+
+    #[test]
+    fn test_exposure_exceeds_limit() {
+        let limit = limit(10_000, CreditLimitEnforcementEnum::Flag);
+
+        assert!(!exposure(6_000, 4_000).exceeds(&limit));
+        assert!(exposure(6_000, 4_001).exceeds(&limit));
+        assert!(!CreditExposure::none(Uuid::nil()).exceeds(&limit));
+
+        assert_eq!(exposure(6_000, 1_000).available(&limit), 3_000);
+        assert_eq!(exposure(9_000, 2_000).available(&limit), 0);
+    }
+
+    #[test]
+    fn test_blocked_subscription_is_recorded_without_id() {
+        let new = |source, enforcement| CreditLimitExceededNew {
+            tenant_id: Uuid::nil(),
+            source,
+            subscription_id: Some(Uuid::max()),
+            currency: "EUR".to_string(),
+            credit_limit: limit(100, enforcement),
+            exposure: exposure(200, 0),
+        };
+
+        let row: CreditLimitExceededRowNew = new(
+            CreditLimitSourceEnum::Subscription,
+            CreditLimitEnforcementEnum::Block,
+        )
+        .into();
+        assert_eq!(row.subscription_id, None);
+
+        let row: CreditLimitExceededRowNew = new(
+            CreditLimitSourceEnum::Subscription,
+            CreditLimitEnforcementEnum::Flag,
+        )
+        .into();
+        assert_eq!(row.subscription_id, Some(Uuid::max()));
+
+        let row: CreditLimitExceededRowNew = new(
+            CreditLimitSourceEnum::UsageThresholdInvoice,
+            CreditLimitEnforcementEnum::Block,
+        )
+        .into();
+        assert_eq!(row.subscription_id, Some(Uuid::max()));
+    }
+}
@@ -0,0 +1,177 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_models::customer_blackout_windows::{
+    CustomerBlackoutWindowRow, CustomerBlackoutWindowRowNew,
+};
+use o2o::o2o;
+use uuid::Uuid;
+
+use crate::errors::StoreError;
+
+/// a fiscal close rarely exceeds a month, longer windows are most likely a mistake
+pub const MAX_BLACKOUT_WINDOW_DAYS: i64 = 31;
+
+/// A period (both dates included) during which the invoices of a customer must not be issued.
+/// Invoices are still finalized, and issued once the window ends.
+#[derive(Debug, Clone, o2o)]
+#[from_owned(CustomerBlackoutWindowRow)]
+pub struct CustomerBlackoutWindow {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Debug, Clone, o2o)]
+#[owned_into(CustomerBlackoutWindowRowNew)]
+#[ghosts(id: {Uuid::now_v7()})]
+pub struct CustomerBlackoutWindowNew {
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub reason: Option<String>,
+    pub created_by: Uuid,
+}
+
+impl CustomerBlackoutWindow {
+    pub fn contains(&self, date: NaiveDate) -> bool {
+        self.start_date <= date && date <= self.end_date
+    }
+}
+
+impl CustomerBlackoutWindowNew {
+    pub fn validate(&self, today: NaiveDate) -> Result<(), StoreError> {
+        if self.end_date < self.start_date {
+            return Err(StoreError::InvalidArgument(
+                "the end of a blackout window cannot be before its start".to_string(),
+            ));
+        }
+
+        if (self.end_date - self.start_date).num_days() + 1 > MAX_BLACKOUT_WINDOW_DAYS {
+            return Err(StoreError::InvalidArgument(format!(
+                "a blackout window cannot exceed {} days",
+                MAX_BLACKOUT_WINDOW_DAYS
+            )));
+        }
+
+        if self.end_date < today {
+            return Err(StoreError::InvalidArgument(
+                "a blackout window cannot end in the past".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The date from which the invoices of the customer can be issued, if they cannot be today.
+/// Consecutive or overlapping windows defer the issuing until the end of the last one.
+pub fn issue_deferred_until(
+    windows: &[CustomerBlackoutWindow],
+    today: NaiveDate,
+) -> Option<NaiveDate> {
+    let mut sorted: Vec<&CustomerBlackoutWindow> = windows.iter().collect();
+    sorted.sort_by_key(|w| w.start_date);
+
+    let mut deferred_until: Option<NaiveDate> = None;
+
+    for window in sorted {
+        let from = deferred_until.unwrap_or(today);
+        if window.contains(from) {
+            deferred_until = window.end_date.succ_opt();
+        }
+    }
+
+    deferred_until
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn window(start: &str, end: &str) -> CustomerBlackoutWindow {
+        CustomerBlackoutWindow {
+            id: Uuid::now_v7(),
+            tenant_id: Uuid::nil(),
+            customer_id: Uuid::nil(),
+            start_date: date(start),
+            end_date: date(end),
+            reason: None,
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    fn new(start: &str, end: &str) -> CustomerBlackoutWindowNew {
+        CustomerBlackoutWindowNew {
+            tenant_id: Uuid::nil(),
+            customer_id: Uuid::nil(),
+            start_date: date(start),
+            end_date: date(end),
+            reason: Some("fiscal close".to_string()),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        let today = date("2024-12-15");
+
+        assert!(new("2024-12-28", "2025-01-05").validate(today).is_ok());
+        assert!(new("2024-12-01", "2024-12-15").validate(today).is_ok());
+        assert!(new("2024-12-20", "2024-12-20").validate(today).is_ok());
+
+        assert!(new("2024-12-20", "2024-12-19").validate(today).is_err());
+        assert!(new("2024-12-01", "2024-12-14").validate(today).is_err());
+        assert!(new("2024-12-20", "2025-01-20").validate(today).is_err());
+    }
+
+    #[test]
+    fn test_issue_deferred_until() {
+        let today = date("2024-12-30");
+
+        assert_eq!(issue_deferred_until(&[], today), None);
+        assert_eq!(
+            issue_deferred_until(&[window("2025-01-01", "2025-01-05")], today),
+            None
+        );
+        assert_eq!(
+            issue_deferred_until(&[window("2024-12-28", "2024-12-30")], today),
+            Some(date("2024-12-31"))
+        );
+
+        // the next window starts the day the first one ends
+        assert_eq!(
+            issue_deferred_until(
+                &[
+                    window("2024-12-31", "2025-01-10"),
+                    window("2024-12-28", "2024-12-30"),
+                    window("2025-01-20", "2025-01-25"),
+                ],
+                today
+            ),
+            Some(date("2025-01-11"))
+        );
+
+        // overlapping windows
+        assert_eq!(
+            issue_deferred_until(
+                &[
+                    window("2024-12-20", "2025-01-03"),
+                    window("2024-12-29", "2025-01-02"),
+                    window("2025-01-02", "2025-01-08"),
+                ],
+                today
+            ),
+            Some(date("2025-01-09"))
+        );
+    }
+}
@@ -3,10 +3,15 @@ use diesel_models::customers::CustomerRow;
 use diesel_models::customers::{CustomerBriefRow, CustomerRowNew, CustomerRowPatch};
 use error_stack::Report;
 use o2o::o2o;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use uuid::Uuid;
 
+use crate::domain::credit_limits::CreditLimit;
+use crate::domain::enums::{BillingEmailStatusEnum, InvoiceDeliveryChannelEnum};
+use crate::domain::payment_terms::DueDatePolicy;
 use crate::errors::StoreError;
 
 #[derive(Clone, Debug, PartialEq, Eq)]
@@ -29,6 +34,29 @@ pub struct Customer {
     pub currency: String,
     pub billing_address: Option<Address>,
     pub shipping_address: Option<ShippingAddress>,
+    /// days until due, replacing the net terms of the subscription on the customer invoices
+    pub net_terms_override: Option<u32>,
+    pub early_payment_discount: Option<EarlyPaymentDiscount>,
+    /// replaces the due date policy of the tenant on the customer invoices
+    pub due_date_policy: Option<DueDatePolicy>,
+    /// deliverability of the billing email, reset when it changes
+    pub billing_email_status: BillingEmailStatusEnum,
+    /// reason given by the email provider for a bounce or a complaint
+    pub billing_email_status_reason: Option<String>,
+    pub billing_email_status_updated_at: Option<NaiveDateTime>,
+    /// segment selecting the collection strategy override of the tenant
+    pub collection_segment: Option<String>,
+    /// new subscriptions and threshold invoices are flagged or blocked beyond it
+    pub credit_limit: Option<CreditLimit>,
+    /// the invoices are only issued to the invoicing provider when not set
+    pub invoice_delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+}
+
+impl Customer {
+    /// The address invoices and reminders are sent to
+    pub fn billing_email(&self) -> Option<&String> {
+        self.invoicing_email.as_ref().or(self.email.as_ref())
+    }
 }
 
 impl TryFrom<CustomerRow> for Customer {
@@ -54,6 +82,21 @@ impl TryFrom<CustomerRow> for Customer {
             billing_address: value.billing_address.map(|v| v.try_into()).transpose()?,
             shipping_address: value.shipping_address.map(|v| v.try_into()).transpose()?,
             invoicing_entity_id: value.invoicing_entity_id,
+            net_terms_override: value.net_terms_override.map(|v| v as u32),
+            early_payment_discount: EarlyPaymentDiscount::from_row(
+                value.early_payment_discount_percent,
+                value.early_payment_discount_days,
+            ),
+            due_date_policy: DueDatePolicy::from_row(value.due_date_policy, value.due_date_day),
+            billing_email_status: value.billing_email_status.into(),
+            billing_email_status_reason: value.billing_email_status_reason,
+            billing_email_status_updated_at: value.billing_email_status_updated_at,
+            collection_segment: value.collection_segment,
+            credit_limit: CreditLimit::from_row(
+                value.credit_limit_cents,
+                value.credit_limit_enforcement,
+            ),
+            invoice_delivery_channel: value.invoice_delivery_channel.map(Into::into),
         })
     }
 }
@@ -81,6 +124,24 @@ impl TryInto<CustomerRow> for Customer {
             billing_address: self.billing_address.map(|v| v.try_into()).transpose()?,
             shipping_address: self.shipping_address.map(|v| v.try_into()).transpose()?,
             invoicing_entity_id: self.invoicing_entity_id,
+            net_terms_override: self.net_terms_override.map(|v| v as i32),
+            early_payment_discount_percent: self.early_payment_discount.as_ref().map(|d| d.percent),
+            early_payment_discount_days: self.early_payment_discount.map(|d| d.days as i32),
+            due_date_policy: self.due_date_policy.map(|p| p.policy.into()),
+            due_date_day: self.due_date_policy.and_then(|p| p.day).map(|d| d as i32),
+            billing_email_status: self.billing_email_status.into(),
+            billing_email_status_reason: self.billing_email_status_reason,
+            billing_email_status_updated_at: self.billing_email_status_updated_at,
+            unique_email: None,
+            unique_alias: None,
+            collection_segment: self.collection_segment,
+            credit_limit_cents: self.credit_limit.as_ref().map(|l| l.cents),
+            credit_limit_enforcement: self
+                .credit_limit
+                .map(|l| l.enforcement)
+                .unwrap_or_default()
+                .into(),
+            invoice_delivery_channel: self.invoice_delivery_channel.map(Into::into),
         })
     }
 }
@@ -106,6 +167,9 @@ pub struct CustomerNew {
     pub currency: String,
     pub billing_address: Option<Address>,
     pub shipping_address: Option<ShippingAddress>,
+    pub net_terms_override: Option<u32>,
+    pub early_payment_discount: Option<EarlyPaymentDiscount>,
+    pub due_date_policy: Option<DueDatePolicy>,
     //
     pub created_by: Uuid,
     pub invoicing_entity_id: Option<Uuid>,
@@ -147,6 +211,19 @@ impl TryInto<CustomerRowNew> for CustomerNewWrapper {
                 .shipping_address
                 .map(|v| v.try_into())
                 .transpose()?,
+            net_terms_override: self.inner.net_terms_override.map(|v| v as i32),
+            early_payment_discount_percent: self
+                .inner
+                .early_payment_discount
+                .as_ref()
+                .map(|d| d.percent),
+            early_payment_discount_days: self.inner.early_payment_discount.map(|d| d.days as i32),
+            due_date_policy: self.inner.due_date_policy.map(|p| p.policy.into()),
+            due_date_day: self
+                .inner
+                .due_date_policy
+                .and_then(|p| p.day)
+                .map(|d| d as i32),
             created_at: self.inner.force_created_date,
         })
     }
@@ -168,6 +245,60 @@ pub struct CustomerPatch {
     pub invoicing_entity_id: Option<Uuid>,
 }
 
+/// Discount granted when the invoice is paid within `days` of its finalization, ex: 2/10 net 30
+#[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
+pub struct EarlyPaymentDiscount {
+    pub percent: Decimal,
+    pub days: u32,
+}
+
+impl EarlyPaymentDiscount {
+    fn from_row(percent: Option<Decimal>, days: Option<i32>) -> Option<Self> {
+        match (percent, days) {
+            (Some(percent), Some(days)) => Some(EarlyPaymentDiscount {
+                percent,
+                days: days as u32,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.percent <= Decimal::ZERO || self.percent >= Decimal::ONE_HUNDRED {
+            return Err(StoreError::InvalidArgument(
+                "early payment discount must be between 0 and 100 percent".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// Discount on `total` (in cents) for a payment on `paid_at`, if within the discount window
+    pub fn amount_for(
+        &self,
+        total: i64,
+        finalized_at: NaiveDateTime,
+        paid_at: NaiveDateTime,
+    ) -> Option<i64> {
+        let deadline = finalized_at.date() + chrono::Duration::days(self.days as i64);
+
+        if paid_at.date() > deadline || total <= 0 {
+            return None;
+        }
+
+        (Decimal::from(total) * self.percent / Decimal::ONE_HUNDRED)
+            .round()
+            .to_i64()
+            .filter(|amount| *amount > 0)
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct CustomerPaymentTerms {
+    pub net_terms_override: Option<u32>,
+    pub early_payment_discount: Option<EarlyPaymentDiscount>,
+    pub due_date_policy: Option<DueDatePolicy>,
+}
+
 #[derive(Clone, Debug, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Address {
     pub line1: Option<String>,
@@ -242,12 +373,73 @@ impl TryInto<serde_json::Value> for ShippingAddress {
 pub enum BillingConfig {
     Stripe(Stripe),
     Manual,
+    Adyen(Adyen),
+    GoCardless(GoCardless),
+}
+
+impl BillingConfig {
+    /// Whether the customer can be charged automatically
+    pub fn has_payment_method(&self) -> bool {
+        matches!(
+            self,
+            BillingConfig::Stripe(Stripe {
+                default_payment_method_id: Some(_),
+                ..
+            }) | BillingConfig::GoCardless(GoCardless {
+                mandate_id: Some(_),
+                ..
+            })
+        )
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct Stripe {
     pub customer_id: String,
     pub collection_method: i32, // todo fix: models.proto : CollectionMethod
+    /// default payment method of the customer in Stripe, kept in sync by the Stripe webhooks
+    #[serde(default)]
+    pub default_payment_method_id: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct Adyen {
+    /// identifies the shopper at Adyen, and its stored payment methods
+    pub shopper_reference: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct GoCardless {
+    /// the customer at GoCardless
+    pub customer_id: String,
+    /// the direct debit mandate the payments are collected under, removed by the GoCardless webhooks once
+    /// it failed or was cancelled
+    #[serde(default)]
+    pub mandate_id: Option<String>,
+}
+
+/// Customer imported from Stripe, matched to an existing customer or created
+#[derive(Clone, Debug)]
+pub struct StripeCustomerSync {
+    pub stripe_customer_id: String,
+    /// set in the Stripe metadata of customers created from meteroid
+    pub meteroid_customer_id: Option<Uuid>,
+    pub name: Option<String>,
+    pub email: Option<String>,
+    pub phone: Option<String>,
+    pub currency: Option<String>,
+    pub billing_address: Option<Address>,
+    pub default_payment_method_id: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum StripeCustomerSyncOutcome {
+    /// no matching customer, a new one was created
+    Created,
+    /// an existing customer was linked to the Stripe customer
+    Linked,
+    /// the customer was already linked, its details were updated
+    Updated,
 }
 
 impl TryFrom<serde_json::Value> for BillingConfig {
@@ -294,3 +486,36 @@ pub struct CustomerBuyCredits {
     pub cents: i32,
     pub notes: Option<String>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d")
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap()
+    }
+
+    #[test]
+    fn test_early_payment_discount_window() {
+        // 2/10 net 30
+        let discount = EarlyPaymentDiscount {
+            percent: Decimal::TWO,
+            days: 10,
+        };
+        let finalized_at = datetime("2024-01-01");
+
+        assert_eq!(
+            discount.amount_for(10_050, finalized_at, datetime("2024-01-11")),
+            Some(201)
+        );
+        assert_eq!(
+            discount.amount_for(10_050, finalized_at, datetime("2024-01-12")),
+            None
+        );
+        assert_eq!(discount.amount_for(0, finalized_at, finalized_at), None);
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use diesel_models::plan_version_features::{PlanVersionFeatureRow, PlanVersionFeatureRowNew};
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::domain::Period;
+use crate::errors::StoreError;
+
+/// Units included by a usage or capacity fee in its current period, and how much of them was consumed
+#[derive(Debug, Clone)]
+pub struct Entitlement {
+    /// the subscription component or add-on
+    pub fee_id: Uuid,
+    pub name: String,
+    pub metric_id: Uuid,
+    pub period: Period,
+    pub included: u64,
+    pub consumed: Decimal,
+}
+
+impl Entitlement {
+    pub fn remaining(&self) -> Decimal {
+        (Decimal::from(self.included) - self.consumed).max(Decimal::ZERO)
+    }
+}
+
+/// A feature granted by a plan version, gated by the product of the tenant
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanFeature {
+    /// identifier of the feature in the product, ex: sso or seats
+    pub key: String,
+    pub enabled: bool,
+    /// unlimited when None
+    pub limit: Option<i64>,
+}
+
+impl From<PlanVersionFeatureRow> for PlanFeature {
+    fn from(row: PlanVersionFeatureRow) -> Self {
+        PlanFeature {
+            key: row.key,
+            enabled: row.enabled,
+            limit: row.usage_limit,
+        }
+    }
+}
+
+impl PlanFeature {
+    pub fn validate_all(features: &[PlanFeature]) -> Result<(), StoreError> {
+        for (index, feature) in features.iter().enumerate() {
+            if feature.key.trim().is_empty() {
+                return Err(StoreError::InvalidArgument(
+                    "feature key cannot be empty".to_string(),
+                ));
+            }
+
+            if feature.limit.is_some_and(|l| l < 0) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "limit of feature {} cannot be negative",
+                    feature.key
+                )));
+            }
+
+            if features[..index].iter().any(|f| f.key == feature.key) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "feature {} is defined twice",
+                    feature.key
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn into_row(self, tenant_id: Uuid, plan_version_id: Uuid) -> PlanVersionFeatureRowNew {
+        PlanVersionFeatureRowNew {
+            id: Uuid::now_v7(),
+            tenant_id,
+            plan_version_id,
+            key: self.key,
+            enabled: self.enabled,
+            usage_limit: self.limit,
+        }
+    }
+}
+
+/// The features granted to a customer by its subscriptions in effect
+#[derive(Debug, Clone)]
+pub struct CustomerEntitlements {
+    pub customer_id: Uuid,
+    pub features: Vec<PlanFeature>,
+}
+
+/// Merges the features of the plan versions of the subscriptions of a customer.
+/// A feature is enabled when any subscription enables it, with the highest limit among those enabling it
+pub fn merge_features(features: Vec<PlanFeature>) -> Vec<PlanFeature> {
+    let mut merged: BTreeMap<String, PlanFeature> = BTreeMap::new();
+
+    for feature in features {
+        match merged.get_mut(&feature.key) {
+            None => {
+                merged.insert(feature.key.clone(), feature);
+            }
+            Some(existing) => match (existing.enabled, feature.enabled) {
+                (_, false) => {}
+                (false, true) => *existing = feature,
+                (true, true) => {
+                    existing.limit = existing.limit.zip(feature.limit).map(|(a, b)| a.max(b));
+                }
+            },
+        }
+    }
+
+    merged.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feature(key: &str, enabled: bool, limit: Option<i64>) -> PlanFeature {
+        PlanFeature {
+            key: key.to_string(),
+            enabled,
+            limit,
+        }
+    }
+
+    #[test]
+    fn test_merge_features() {
+        let merged = merge_features(vec![
+            feature("seats", true, Some(5)),
+            feature("sso", false, None),
+            feature("seats", true, Some(10)),
+            feature("sso", true, None),
+            feature("api", true, None),
+            feature("api", true, Some(100)),
+            feature("exports", true, Some(3)),
+            feature("exports", false, Some(50)),
+        ]);
+
+        assert_eq!(
+            merged,
+            vec![
+                feature("api", true, None),
+                feature("exports", true, Some(3)),
+                feature("seats", true, Some(10)),
+                feature("sso", true, None),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_validate_features() {
+        assert!(PlanFeature::validate_all(&[feature("sso", true, None)]).is_ok());
+        assert!(PlanFeature::validate_all(&[feature(" ", true, None)]).is_err());
+        assert!(PlanFeature::validate_all(&[feature("seats", true, Some(-1))]).is_err());
+        assert!(PlanFeature::validate_all(&[
+            feature("sso", true, None),
+            feature("sso", false, None)
+        ])
+        .is_err());
+    }
+}
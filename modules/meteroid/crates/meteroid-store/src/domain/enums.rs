@@ -1,8 +1,36 @@
+use crate::errors::StoreError;
 use diesel_models::enums as diesel_enums;
 use o2o::o2o;
+use rust_decimal::Decimal;
 use serde::{Deserialize, Serialize};
 use std::cmp::Ordering;
 
+/// Where the journal entries of a tenant are exported to
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::AccountingConnectorEnum)]
+pub enum AccountingConnectorEnum {
+    /// a CSV file, kept in the object store
+    Csv,
+    QuickbooksOnline,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::AccountingExportRunStatusEnum)]
+pub enum AccountingExportRunStatusEnum {
+    Succeeded,
+    Failed,
+}
+
+/// How often the completed periods are exported
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::AccountingExportScheduleEnum)]
+pub enum AccountingExportScheduleEnum {
+    Daily,
+    /// from Monday to Sunday
+    Weekly,
+    Monthly,
+}
+
 #[derive(o2o, Serialize, Deserialize, PartialEq, Eq, Debug, Clone)]
 #[map_owned(diesel_enums::ActionAfterTrialEnum)]
 pub enum ActionAfterTrialEnum {
@@ -11,6 +39,31 @@ pub enum ActionAfterTrialEnum {
     Downgrade,
 }
 
+/// Deliverability of the billing email of a customer, as reported by the email provider
+#[derive(o2o, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::BillingEmailStatusEnum)]
+pub enum BillingEmailStatusEnum {
+    /// nothing was delivered to the address yet
+    #[default]
+    Unverified,
+    /// an email was delivered to the address
+    Verified,
+    /// the address hard bounced, emails are not sent to it anymore
+    Bounced,
+    /// the recipient reported an email as spam, emails are not sent to it anymore
+    Complained,
+}
+
+impl BillingEmailStatusEnum {
+    /// Whether emails can be sent to the address
+    pub fn is_deliverable(&self) -> bool {
+        !matches!(
+            self,
+            BillingEmailStatusEnum::Bounced | BillingEmailStatusEnum::Complained
+        )
+    }
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone)]
 #[map_owned(diesel_enums::BillingMetricAggregateEnum)]
 pub enum BillingMetricAggregateEnum {
@@ -23,44 +76,156 @@ pub enum BillingMetricAggregateEnum {
     CountDistinct,
 }
 
-#[derive(o2o, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
-#[map_owned(diesel_enums::BillingPeriodEnum)]
+/// Longest custom billing period, in months
+pub const MAX_CUSTOM_BILLING_PERIOD_MONTHS: u32 = 36;
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum BillingPeriodEnum {
+    /// every 7 days from the billing start date, regardless of the billing day
+    Weekly,
     Monthly,
+    /// every 2 months
+    Bimonthly,
     Quarterly,
+    /// every 6 months
+    Semiannual,
     Annual,
+    /// every N months, for the lengths without a named period
+    Custom {
+        months: u32,
+    },
 }
 
 impl BillingPeriodEnum {
+    /// The period of the given number of months, the named periods are used for 1, 2, 3, 6 and 12 months
+    pub fn from_months(months: u32) -> Result<BillingPeriodEnum, StoreError> {
+        match months {
+            1 => Ok(BillingPeriodEnum::Monthly),
+            2 => Ok(BillingPeriodEnum::Bimonthly),
+            3 => Ok(BillingPeriodEnum::Quarterly),
+            6 => Ok(BillingPeriodEnum::Semiannual),
+            12 => Ok(BillingPeriodEnum::Annual),
+            1..=MAX_CUSTOM_BILLING_PERIOD_MONTHS => Ok(BillingPeriodEnum::Custom { months }),
+            _ => Err(StoreError::InvalidArgument(format!(
+                "a billing period must last between 1 and {} months, got {}",
+                MAX_CUSTOM_BILLING_PERIOD_MONTHS, months
+            ))),
+        }
+    }
+
+    /// The period of a row, the months of a custom period being stored in a separate column
+    pub fn from_row(period: diesel_enums::BillingPeriodEnum, months: Option<i32>) -> Self {
+        match period {
+            diesel_enums::BillingPeriodEnum::Weekly => BillingPeriodEnum::Weekly,
+            diesel_enums::BillingPeriodEnum::Monthly => BillingPeriodEnum::Monthly,
+            diesel_enums::BillingPeriodEnum::Bimonthly => BillingPeriodEnum::Bimonthly,
+            diesel_enums::BillingPeriodEnum::Quarterly => BillingPeriodEnum::Quarterly,
+            diesel_enums::BillingPeriodEnum::Semiannual => BillingPeriodEnum::Semiannual,
+            diesel_enums::BillingPeriodEnum::Annual => BillingPeriodEnum::Annual,
+            // the check constraints of the tables require the months of a custom period
+            diesel_enums::BillingPeriodEnum::Custom => BillingPeriodEnum::Custom {
+                months: months.unwrap_or(1) as u32,
+            },
+        }
+    }
+
+    /// The months column of a row, only set for a custom period
+    pub fn custom_months(&self) -> Option<i32> {
+        match self {
+            BillingPeriodEnum::Custom { months } => Some(*months as i32),
+            _ => None,
+        }
+    }
+
+    /// Length of the period in whole months, 0 for a weekly period
     pub fn as_months(&self) -> u32 {
         match self {
+            BillingPeriodEnum::Weekly => 0,
             BillingPeriodEnum::Monthly => 1,
+            BillingPeriodEnum::Bimonthly => 2,
             BillingPeriodEnum::Quarterly => 3,
+            BillingPeriodEnum::Semiannual => 6,
             BillingPeriodEnum::Annual => 12,
+            BillingPeriodEnum::Custom { months } => *months,
+        }
+    }
+
+    /// Length of the period in months, a week being 12/52 of a month
+    pub fn length_in_months(&self) -> Decimal {
+        match self {
+            BillingPeriodEnum::Weekly => Decimal::from(12) / Decimal::from(52),
+            _ => Decimal::from(self.as_months()),
         }
     }
 
     pub fn as_subscription_billing_period(&self) -> SubscriptionFeeBillingPeriod {
         match self {
+            BillingPeriodEnum::Weekly => SubscriptionFeeBillingPeriod::Weekly,
             BillingPeriodEnum::Monthly => SubscriptionFeeBillingPeriod::Monthly,
+            BillingPeriodEnum::Bimonthly => SubscriptionFeeBillingPeriod::Bimonthly,
             BillingPeriodEnum::Quarterly => SubscriptionFeeBillingPeriod::Quarterly,
+            BillingPeriodEnum::Semiannual => SubscriptionFeeBillingPeriod::Semiannual,
             BillingPeriodEnum::Annual => SubscriptionFeeBillingPeriod::Annual,
+            BillingPeriodEnum::Custom { months } => {
+                SubscriptionFeeBillingPeriod::Custom { months: *months }
+            }
+        }
+    }
+}
+
+impl From<BillingPeriodEnum> for diesel_enums::BillingPeriodEnum {
+    fn from(value: BillingPeriodEnum) -> Self {
+        match value {
+            BillingPeriodEnum::Weekly => diesel_enums::BillingPeriodEnum::Weekly,
+            BillingPeriodEnum::Monthly => diesel_enums::BillingPeriodEnum::Monthly,
+            BillingPeriodEnum::Bimonthly => diesel_enums::BillingPeriodEnum::Bimonthly,
+            BillingPeriodEnum::Quarterly => diesel_enums::BillingPeriodEnum::Quarterly,
+            BillingPeriodEnum::Semiannual => diesel_enums::BillingPeriodEnum::Semiannual,
+            BillingPeriodEnum::Annual => diesel_enums::BillingPeriodEnum::Annual,
+            BillingPeriodEnum::Custom { .. } => diesel_enums::BillingPeriodEnum::Custom,
         }
     }
 }
 
 impl PartialOrd for BillingPeriodEnum {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.as_months().cmp(&other.as_months()))
+        Some(self.cmp(other))
     }
 }
 
 impl Ord for BillingPeriodEnum {
     fn cmp(&self, other: &Self) -> Ordering {
-        self.as_months().cmp(&other.as_months())
+        self.length_in_months().cmp(&other.length_in_months())
     }
 }
 
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::ComponentRuleTypeEnum)]
+pub enum ComponentRuleTypeEnum {
+    /// the subject can only be sold with the target
+    Requires,
+    /// the subject and the target cannot be sold together
+    Excludes,
+}
+
+/// What happens to a new subscription or threshold invoice of a customer over its credit limit
+#[derive(o2o, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::CreditLimitEnforcementEnum)]
+pub enum CreditLimitEnforcementEnum {
+    /// recorded for finance review, and allowed
+    #[default]
+    Flag,
+    /// recorded for finance review, and refused
+    Block,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::CreditLimitSourceEnum)]
+pub enum CreditLimitSourceEnum {
+    Subscription,
+    UsageThresholdInvoice,
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone)]
 #[map_owned(diesel_enums::CreditNoteStatus)]
 pub enum CreditNoteStatus {
@@ -79,6 +244,26 @@ pub enum FangTaskState {
     Retried,
 }
 
+/// How the finalized invoices of a customer are sent to it
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::InvoiceDeliveryChannelEnum)]
+pub enum InvoiceDeliveryChannelEnum {
+    /// to the billing email of the customer
+    Email,
+    /// to the webhook endpoints of the tenant, which sends it on its side
+    Webhook,
+    /// by the invoicing provider, on the page it hosts for the invoice
+    ProviderHosted,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::InvoiceDeliveryStatusEnum)]
+pub enum InvoiceDeliveryStatusEnum {
+    Delivered,
+    /// the last attempt failed, retried along with the issuance of the invoice
+    Failed,
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[map_owned(diesel_enums::InvoiceExternalStatusEnum)]
 pub enum InvoiceExternalStatusEnum {
@@ -91,6 +276,27 @@ pub enum InvoiceExternalStatusEnum {
     Void,
 }
 
+impl InvoiceExternalStatusEnum {
+    /// Position in the lifecycle of the invoice at the provider. Paid, void and deleted are final
+    fn lifecycle_rank(&self) -> u8 {
+        match self {
+            InvoiceExternalStatusEnum::Draft => 0,
+            InvoiceExternalStatusEnum::Finalized => 1,
+            InvoiceExternalStatusEnum::PaymentFailed => 2,
+            InvoiceExternalStatusEnum::Uncollectible => 3,
+            InvoiceExternalStatusEnum::Paid
+            | InvoiceExternalStatusEnum::Void
+            | InvoiceExternalStatusEnum::Deleted => 4,
+        }
+    }
+
+    /// The provider notifications can be delivered out of order, a stale one must not move the status
+    /// backwards (ex: paid to finalized). A repeated status is accepted
+    pub fn can_transition_to(&self, next: &InvoiceExternalStatusEnum) -> bool {
+        self == next || self.lifecycle_rank() < next.lifecycle_rank()
+    }
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq)]
 #[map_owned(diesel_enums::InvoiceStatusEnum)]
 pub enum InvoiceStatusEnum {
@@ -100,6 +306,14 @@ pub enum InvoiceStatusEnum {
     Void,
 }
 
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[map_owned(diesel_enums::InvoiceStuckReasonEnum)]
+pub enum InvoiceStuckReasonEnum {
+    StaleDraft,
+    PendingFinalization,
+    IssueRetriesExhausted,
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[map_owned(diesel_enums::InvoiceType)]
 pub enum InvoiceType {
@@ -115,9 +329,11 @@ pub enum InvoiceType {
 pub enum InvoicingProviderEnum {
     Stripe,
     Manual,
+    Adyen,
+    GoCardless,
 }
 
-#[derive(o2o, Serialize, Deserialize, Debug, Clone)]
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 #[map_owned(diesel_enums::MrrMovementType)]
 pub enum MrrMovementType {
     NewBusiness,
@@ -127,6 +343,25 @@ pub enum MrrMovementType {
     Reactivation,
 }
 
+/// The customer-facing content a notification bundle applies to
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[map_owned(diesel_enums::NotificationNamespaceEnum)]
+pub enum NotificationNamespaceEnum {
+    Email,
+    Portal,
+    Pdf,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, Eq, PartialEq, Hash)]
+#[map_owned(diesel_enums::OnboardingStepEnum)]
+pub enum OnboardingStepEnum {
+    InvoicingEntity,
+    PaymentProvider,
+    BillableMetric,
+    Plan,
+    Customer,
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone, Eq, PartialEq)]
 #[map_owned(diesel_enums::OrganizationUserRole)]
 pub enum OrganizationUserRole {
@@ -143,6 +378,16 @@ pub enum OutboxStatus {
     Failed,
 }
 
+/// How a payment reminder reaches the customer
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::PaymentReminderChannelEnum)]
+pub enum PaymentReminderChannelEnum {
+    /// to the invoicing email of the customer
+    Email,
+    /// to the webhook endpoints of the tenant
+    Webhook,
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Default, Clone)]
 #[map_owned(diesel_enums::PlanStatusEnum)]
 pub enum PlanStatusEnum {
@@ -180,6 +425,38 @@ pub enum WebhookOutEventTypeEnum {
     SubscriptionCreated,
     InvoiceCreated,
     InvoiceFinalized,
+    UsageAlertTriggered,
+    InvoiceStuck,
+    InvoicePaymentReminder,
+    CustomerBillingEmailUpdated,
+    UsageCapReached,
+    CreditLimitExceeded,
+    UsageAlertRuleFiring,
+    UsageAlertRuleResolved,
+    SeatIncreaseRequested,
+    InvoiceDeliveryRequested,
+    InvoicePaid,
+    InvoicePaymentRecorded,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::SubscriptionBillingModeEnum)]
+pub enum SubscriptionBillingModeEnum {
+    /// periods renew on the billing day of the subscription
+    #[default]
+    Anniversary,
+    /// periods renew on the 1st of the month
+    Calendar,
+}
+
+impl SubscriptionBillingModeEnum {
+    /// The day of the month the periods of the subscription renew on
+    pub fn anchor_day(&self, billing_day: i16) -> u32 {
+        match self {
+            SubscriptionBillingModeEnum::Anniversary => billing_day as u32,
+            SubscriptionBillingModeEnum::Calendar => 1,
+        }
+    }
 }
 
 #[derive(o2o, Serialize, Deserialize, Debug, Clone)]
@@ -193,41 +470,269 @@ pub enum SubscriptionEventType {
     Updated,
 }
 
-#[derive(o2o, Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
-#[map_owned(diesel_enums::SubscriptionFeeBillingPeriod)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq, Hash)]
 pub enum SubscriptionFeeBillingPeriod {
     OneTime,
+    Weekly,
     Monthly,
+    Bimonthly,
     Quarterly,
+    Semiannual,
     Annual,
+    /// every N months, see [BillingPeriodEnum::Custom]
+    Custom {
+        months: u32,
+    },
 }
 
 impl SubscriptionFeeBillingPeriod {
+    /// The period of a row, the months of a custom period being stored in a separate column
+    pub fn from_row(
+        period: diesel_enums::SubscriptionFeeBillingPeriod,
+        months: Option<i32>,
+    ) -> Self {
+        match period {
+            diesel_enums::SubscriptionFeeBillingPeriod::OneTime => {
+                SubscriptionFeeBillingPeriod::OneTime
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Weekly => {
+                SubscriptionFeeBillingPeriod::Weekly
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Monthly => {
+                SubscriptionFeeBillingPeriod::Monthly
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Bimonthly => {
+                SubscriptionFeeBillingPeriod::Bimonthly
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Quarterly => {
+                SubscriptionFeeBillingPeriod::Quarterly
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Semiannual => {
+                SubscriptionFeeBillingPeriod::Semiannual
+            }
+            diesel_enums::SubscriptionFeeBillingPeriod::Annual => {
+                SubscriptionFeeBillingPeriod::Annual
+            }
+            // the check constraints of the tables require the months of a custom period
+            diesel_enums::SubscriptionFeeBillingPeriod::Custom => {
+                SubscriptionFeeBillingPeriod::Custom {
+                    months: months.unwrap_or(1) as u32,
+                }
+            }
+        }
+    }
+
+    /// The months column of a row, only set for a custom period
+    pub fn custom_months(&self) -> Option<i32> {
+        match self {
+            SubscriptionFeeBillingPeriod::Custom { months } => Some(*months as i32),
+            _ => None,
+        }
+    }
+
+    /// Length of the period in whole months, 0 for a weekly period
     pub fn as_months(&self) -> i32 {
         match self {
             SubscriptionFeeBillingPeriod::OneTime => i32::MAX, // month_elapsed % OneTime.as_months() will only be 0 if month_elapsed is 0
+            SubscriptionFeeBillingPeriod::Weekly => 0,
             SubscriptionFeeBillingPeriod::Monthly => 1,
+            SubscriptionFeeBillingPeriod::Bimonthly => 2,
             SubscriptionFeeBillingPeriod::Quarterly => 3,
+            SubscriptionFeeBillingPeriod::Semiannual => 6,
             SubscriptionFeeBillingPeriod::Annual => 12,
+            SubscriptionFeeBillingPeriod::Custom { months } => *months as i32,
+        }
+    }
+
+    /// Length of the period in months, a week being 12/52 of a month
+    pub fn length_in_months(&self) -> Decimal {
+        match self.as_billing_period_opt() {
+            Some(period) => period.length_in_months(),
+            None => Decimal::from(self.as_months()),
         }
     }
 
     pub fn as_billing_period_opt(&self) -> Option<BillingPeriodEnum> {
         match self {
             SubscriptionFeeBillingPeriod::OneTime => None,
+            SubscriptionFeeBillingPeriod::Weekly => Some(BillingPeriodEnum::Weekly),
             SubscriptionFeeBillingPeriod::Monthly => Some(BillingPeriodEnum::Monthly),
+            SubscriptionFeeBillingPeriod::Bimonthly => Some(BillingPeriodEnum::Bimonthly),
             SubscriptionFeeBillingPeriod::Quarterly => Some(BillingPeriodEnum::Quarterly),
+            SubscriptionFeeBillingPeriod::Semiannual => Some(BillingPeriodEnum::Semiannual),
             SubscriptionFeeBillingPeriod::Annual => Some(BillingPeriodEnum::Annual),
+            SubscriptionFeeBillingPeriod::Custom { months } => {
+                Some(BillingPeriodEnum::Custom { months: *months })
+            }
         }
     }
 }
 
+impl From<SubscriptionFeeBillingPeriod> for diesel_enums::SubscriptionFeeBillingPeriod {
+    fn from(value: SubscriptionFeeBillingPeriod) -> Self {
+        match value {
+            SubscriptionFeeBillingPeriod::OneTime => {
+                diesel_enums::SubscriptionFeeBillingPeriod::OneTime
+            }
+            SubscriptionFeeBillingPeriod::Weekly => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Weekly
+            }
+            SubscriptionFeeBillingPeriod::Monthly => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Monthly
+            }
+            SubscriptionFeeBillingPeriod::Bimonthly => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Bimonthly
+            }
+            SubscriptionFeeBillingPeriod::Quarterly => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Quarterly
+            }
+            SubscriptionFeeBillingPeriod::Semiannual => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Semiannual
+            }
+            SubscriptionFeeBillingPeriod::Annual => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Annual
+            }
+            SubscriptionFeeBillingPeriod::Custom { .. } => {
+                diesel_enums::SubscriptionFeeBillingPeriod::Custom
+            }
+        }
+    }
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::SubscriptionMigrationStrategyEnum)]
+pub enum SubscriptionMigrationStrategyEnum {
+    /// the subscriptions switch to the new version when they renew
+    AtNextRenewal,
+    /// the subscriptions switch right away, and the price difference of the current period is invoiced or credited
+    ImmediateWithProration,
+    /// the subscriptions stay on their version and are left out of later migrations
+    Grandfather,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::SubscriptionMigrationItemStatusEnum)]
+pub enum SubscriptionMigrationItemStatusEnum {
+    Scheduled,
+    Migrated,
+    Grandfathered,
+    Failed,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub enum BillingType {
     Advance,
     Arrears,
 }
 
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::ProrationRoundingEnum)]
+pub enum ProrationRoundingEnum {
+    /// half away from zero
+    #[default]
+    Nearest,
+    /// half to even, aka banker's rounding
+    NearestEven,
+    Up,
+    Down,
+}
+
+/// What to do with invoices whose total is zero after discounts, when finalizing them
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::ZeroInvoicePolicyEnum)]
+pub enum ZeroInvoicePolicyEnum {
+    /// finalized and issued like any other invoice
+    #[default]
+    Issue,
+    /// finalized, but not sent to the invoicing provider
+    FinalizeWithoutIssuing,
+    /// voided and kept as a record of the processed period
+    Suppress,
+}
+
+/// How the due date of an invoice is derived from its net terms
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::DueDatePolicyEnum)]
+pub enum DueDatePolicyEnum {
+    /// net terms days after the invoice date
+    #[default]
+    NetDays,
+    /// net terms days after the end of the month of the invoice date, ex: net 30 EOM
+    EndOfMonth,
+    /// the first occurrence of a day of month once the net terms elapsed
+    FixedDay,
+}
+
+/// How the recurring fees of a subscription with components of different billing periods are invoiced
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::InvoiceCadenceGroupingEnum)]
+pub enum InvoiceCadenceGroupingEnum {
+    /// a single invoice per subscription period, billing the fees due at its date
+    #[default]
+    Combined,
+    /// a separate invoice per billing period, ex: monthly usage and an annual platform fee
+    PerCadence,
+}
+
+/// Customer fields that identify a single active customer of the tenant. Compared case-insensitively
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::CustomerUniquenessEnum)]
+pub enum CustomerUniquenessEnum {
+    /// only the alias is unique, as written
+    #[default]
+    None,
+    Email,
+    Alias,
+    EmailAndAlias,
+}
+
+impl CustomerUniquenessEnum {
+    pub fn enforces_email(&self) -> bool {
+        matches!(
+            self,
+            CustomerUniquenessEnum::Email | CustomerUniquenessEnum::EmailAndAlias
+        )
+    }
+
+    pub fn enforces_alias(&self) -> bool {
+        matches!(
+            self,
+            CustomerUniquenessEnum::Alias | CustomerUniquenessEnum::EmailAndAlias
+        )
+    }
+}
+
+/// Bundle of collection rules of the tenant, or of a customer segment
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[map_owned(diesel_enums::CollectionStrategyPresetEnum)]
+pub enum CollectionStrategyPresetEnum {
+    Lenient,
+    #[default]
+    Standard,
+    Strict,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::TaxRegistrationTypeEnum)]
+pub enum TaxRegistrationTypeEnum {
+    Vat,
+    Gst,
+    Ein,
+    Abn,
+}
+
+impl TaxRegistrationTypeEnum {
+    /// Label printed before the number on invoices
+    pub fn label(&self) -> &'static str {
+        match self {
+            TaxRegistrationTypeEnum::Vat => "VAT",
+            TaxRegistrationTypeEnum::Gst => "GST",
+            TaxRegistrationTypeEnum::Ein => "EIN",
+            TaxRegistrationTypeEnum::Abn => "ABN",
+        }
+    }
+}
+
 #[derive(o2o, Serialize, Deserialize, Debug, Clone)]
 #[map_owned(diesel_enums::TenantEnvironmentEnum)]
 pub enum TenantEnvironmentEnum {
@@ -251,3 +756,115 @@ impl TenantEnvironmentEnum {
         }
     }
 }
+
+/// The role of an organization member on a tenant
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::TenantMemberRoleEnum)]
+pub enum TenantMemberRoleEnum {
+    /// full access to the tenant
+    Admin,
+    /// read access, and write access to the customers, subscriptions, invoices, payment reminders and coupons
+    Billing,
+    /// read access only
+    ReadOnly,
+}
+
+/// Whether a trial requires a payment method
+#[derive(o2o, Serialize, Deserialize, Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::TrialModeEnum)]
+pub enum TrialModeEnum {
+    /// no payment method required, the subscription expires at the end of the trial
+    #[default]
+    Free,
+    /// the customer must have a payment method, the subscription converts and is charged at the end of the trial
+    CardRequired,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::InvoiceExternalStatusEnum::*;
+
+    #[test]
+    fn test_external_status_transitions() {
+        assert!(Draft.can_transition_to(&Finalized));
+        assert!(Finalized.can_transition_to(&Paid));
+        assert!(PaymentFailed.can_transition_to(&PaymentFailed));
+        assert!(PaymentFailed.can_transition_to(&Paid));
+        assert!(Uncollectible.can_transition_to(&Paid));
+        assert!(Paid.can_transition_to(&Paid));
+
+        assert!(!Paid.can_transition_to(&Finalized));
+        assert!(!Paid.can_transition_to(&PaymentFailed));
+        assert!(!Paid.can_transition_to(&Void));
+        assert!(!Finalized.can_transition_to(&Draft));
+        assert!(!Void.can_transition_to(&Paid));
+    }
+
+    #[test]
+    fn test_billing_period_from_months() {
+        use super::BillingPeriodEnum;
+
+        assert_eq!(
+            BillingPeriodEnum::from_months(3).unwrap(),
+            BillingPeriodEnum::Quarterly
+        );
+        assert_eq!(
+            BillingPeriodEnum::from_months(4).unwrap(),
+            BillingPeriodEnum::Custom { months: 4 }
+        );
+        assert_eq!(
+            BillingPeriodEnum::from_months(36).unwrap(),
+            BillingPeriodEnum::Custom { months: 36 }
+        );
+        assert!(BillingPeriodEnum::from_months(0).is_err());
+        assert!(BillingPeriodEnum::from_months(37).is_err());
+    }
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::InvoiceApprovalStatusEnum)]
+pub enum InvoiceApprovalStatusEnum {
+    /// a previous step is not approved yet
+    Waiting,
+    Pending,
+    Approved,
+    Rejected,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::InvoiceApprovalActionEnum)]
+pub enum InvoiceApprovalActionEnum {
+    Requested,
+    Approved,
+    Rejected,
+    Escalated,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::UsageAlertRuleWindowEnum)]
+pub enum UsageAlertRuleWindowEnum {
+    /// the current period of the subscription billing the metric
+    BillingPeriod,
+    /// today
+    RollingDay,
+    /// the last 7 days, today included
+    RollingWeek,
+    /// the last 30 days, today included
+    RollingMonth,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::UsageAlertRuleStatusEnum)]
+pub enum UsageAlertRuleStatusEnum {
+    Firing,
+    Resolved,
+}
+
+#[derive(o2o, Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[map_owned(diesel_enums::SeatIncreaseRequestStatusEnum)]
+pub enum SeatIncreaseRequestStatusEnum {
+    /// above the auto-approval limit of the tenant, waiting for its decision
+    Pending,
+    Approved,
+    Rejected,
+}
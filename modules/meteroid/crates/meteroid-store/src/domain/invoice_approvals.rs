@@ -0,0 +1,536 @@
+use chrono::NaiveDateTime;
+use diesel_models::invoice_approvals::{
+    InvoiceApprovalEventRow, InvoiceApprovalRow, InvoiceApprovalRowNew, InvoiceApprovalStepRow,
+    InvoiceApprovalStepRowNew,
+};
+use o2o::o2o;
+use uuid::Uuid;
+
+use crate::domain::enums::{
+    InvoiceApprovalActionEnum, InvoiceApprovalStatusEnum, TenantMemberRoleEnum,
+};
+use crate::errors::StoreError;
+
+pub const MAX_APPROVAL_STEPS: usize = 10;
+
+/// A step of the approval chain of a tenant
+#[derive(Debug, Clone, o2o)]
+#[from_owned(InvoiceApprovalStepRow)]
+pub struct InvoiceApprovalStep {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub position: i32,
+    pub name: String,
+    /// in cents, the step applies to all the invoices when none
+    pub min_total: Option<i64>,
+    /// the currency of min_total, any currency when none
+    pub currency: Option<String>,
+    pub assignee_id: Option<Uuid>,
+    #[from(~.map(| v | v.into()))]
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    #[from(~.map(| v | v.into()))]
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl InvoiceApprovalStep {
+    pub fn applies_to(&self, total: i64, currency: &str) -> bool {
+        let currency_matches = self
+            .currency
+            .as_ref()
+            .map(|c| c.eq_ignore_ascii_case(currency))
+            .unwrap_or(true);
+
+        match self.min_total {
+            None => currency_matches,
+            Some(min_total) => currency_matches && total >= min_total,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvoiceApprovalStepNew {
+    pub name: String,
+    pub min_total: Option<i64>,
+    pub currency: Option<String>,
+    pub assignee_id: Option<Uuid>,
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+}
+
+impl InvoiceApprovalStepNew {
+    pub fn into_row(
+        self,
+        tenant_id: Uuid,
+        position: i32,
+        created_by: Uuid,
+    ) -> InvoiceApprovalStepRowNew {
+        InvoiceApprovalStepRowNew {
+            id: Uuid::now_v7(),
+            tenant_id,
+            position,
+            name: self.name,
+            min_total: self.min_total,
+            currency: self.currency,
+            assignee_id: self.assignee_id,
+            assignee_role: self.assignee_role.map(Into::into),
+            timeout_hours: self.timeout_hours,
+            escalation_assignee_id: self.escalation_assignee_id,
+            escalation_role: self.escalation_role.map(Into::into),
+            created_by,
+        }
+    }
+}
+
+fn can_approve(role: &Option<TenantMemberRoleEnum>) -> bool {
+    !matches!(role, Some(TenantMemberRoleEnum::ReadOnly))
+}
+
+pub fn validate_approval_chain(steps: &[InvoiceApprovalStepNew]) -> Result<(), StoreError> {
+    if steps.len() > MAX_APPROVAL_STEPS {
+        return Err(StoreError::InvalidArgument(format!(
+            "an approval chain cannot have more than {} steps",
+            MAX_APPROVAL_STEPS
+        )));
+    }
+
+    for (idx, step) in steps.iter().enumerate() {
+        let invalid = |msg: &str| {
+            Err(StoreError::InvalidArgument(format!(
+                "step {} ({}): {}",
+                idx + 1,
+                step.name,
+                msg
+            )))
+        };
+
+        if step.name.trim().is_empty() {
+            return invalid("the name is required");
+        }
+        if step.min_total.is_some_and(|t| t < 0) {
+            return invalid("the minimum total cannot be negative");
+        }
+        if step.assignee_id.is_none() && step.assignee_role.is_none() {
+            return invalid("an assignee or a role is required");
+        }
+        if !can_approve(&step.assignee_role) || !can_approve(&step.escalation_role) {
+            return invalid("read only members cannot approve invoices");
+        }
+        match step.timeout_hours {
+            Some(hours) if hours <= 0 => return invalid("the timeout must be positive"),
+            Some(_) if step.escalation_assignee_id.is_none() && step.escalation_role.is_none() => {
+                return invalid("a step with a timeout requires an escalation assignee or role")
+            }
+            None if step.escalation_assignee_id.is_some() || step.escalation_role.is_some() => {
+                return invalid("a step escalates after a timeout, none is set")
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+/// The steps of the chain the invoice goes through, in order
+pub fn applicable_steps<'a>(
+    chain: &'a [InvoiceApprovalStep],
+    total: i64,
+    currency: &str,
+) -> Vec<&'a InvoiceApprovalStep> {
+    chain
+        .iter()
+        .filter(|step| step.applies_to(total, currency))
+        .collect()
+}
+
+fn due_at(now: NaiveDateTime, timeout_hours: Option<i32>) -> Option<NaiveDateTime> {
+    timeout_hours.map(|hours| now + chrono::Duration::hours(hours as i64))
+}
+
+/// The approvals of the invoice for the applicable steps, the first one pending
+pub fn new_approvals(
+    steps: &[&InvoiceApprovalStep],
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+    total: i64,
+    now: NaiveDateTime,
+) -> Vec<InvoiceApprovalRowNew> {
+    steps
+        .iter()
+        .enumerate()
+        .map(|(idx, step)| {
+            let first = idx == 0;
+            InvoiceApprovalRowNew {
+                id: Uuid::now_v7(),
+                tenant_id,
+                invoice_id,
+                step_id: Some(step.id),
+                position: idx as i32,
+                name: step.name.clone(),
+                assignee_id: step.assignee_id,
+                assignee_role: step.assignee_role.map(Into::into),
+                timeout_hours: step.timeout_hours,
+                escalation_assignee_id: step.escalation_assignee_id,
+                escalation_role: step.escalation_role.map(Into::into),
+                status: if first {
+                    InvoiceApprovalStatusEnum::Pending.into()
+                } else {
+                    InvoiceApprovalStatusEnum::Waiting.into()
+                },
+                requested_total: total,
+                started_at: first.then_some(now),
+                due_at: if first {
+                    due_at(now, step.timeout_hours)
+                } else {
+                    None
+                },
+            }
+        })
+        .collect()
+}
+
+/// A step of the approval of an invoice
+#[derive(Debug, Clone, o2o)]
+#[from_owned(InvoiceApprovalRow)]
+pub struct InvoiceApproval {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub step_id: Option<Uuid>,
+    pub position: i32,
+    pub name: String,
+    pub assignee_id: Option<Uuid>,
+    #[from(~.map(| v | v.into()))]
+    pub assignee_role: Option<TenantMemberRoleEnum>,
+    pub timeout_hours: Option<i32>,
+    pub escalation_assignee_id: Option<Uuid>,
+    #[from(~.map(| v | v.into()))]
+    pub escalation_role: Option<TenantMemberRoleEnum>,
+    #[from(~.into())]
+    pub status: InvoiceApprovalStatusEnum,
+    pub requested_total: i64,
+    pub started_at: Option<NaiveDateTime>,
+    pub due_at: Option<NaiveDateTime>,
+    pub escalated_at: Option<NaiveDateTime>,
+    pub decided_at: Option<NaiveDateTime>,
+    pub decided_by: Option<Uuid>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl InvoiceApproval {
+    /// The assignee decides, or once escalated the escalation assignee as well
+    pub fn can_decide(&self, actor: Uuid, role: TenantMemberRoleEnum) -> bool {
+        if role == TenantMemberRoleEnum::ReadOnly {
+            return false;
+        }
+
+        let matches = |assignee_id: Option<Uuid>, assignee_role: Option<TenantMemberRoleEnum>| {
+            assignee_id == Some(actor) || assignee_role == Some(role)
+        };
+
+        matches(self.assignee_id, self.assignee_role)
+            || (self.escalated_at.is_some()
+                && matches(self.escalation_assignee_id, self.escalation_role))
+    }
+
+    pub fn next_due_at(&self, now: NaiveDateTime) -> Option<NaiveDateTime> {
+        due_at(now, self.timeout_hours)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InvoiceApprovalState {
+    /// no step of the chain applies to the invoice
+    NotRequired,
+    NotRequested,
+    Pending,
+    Approved,
+    Rejected,
+    /// the total of the invoice changed since the approval was requested
+    Outdated,
+}
+
+/// The approvals being ordered by position
+pub fn approval_state(
+    approvals: &[InvoiceApproval],
+    required: bool,
+    total: i64,
+) -> InvoiceApprovalState {
+    if approvals.is_empty() {
+        return if required {
+            InvoiceApprovalState::NotRequested
+        } else {
+            InvoiceApprovalState::NotRequired
+        };
+    }
+
+    if approvals.iter().any(|a| a.requested_total != total) {
+        return InvoiceApprovalState::Outdated;
+    }
+
+    if approvals
+        .iter()
+        .any(|a| a.status == InvoiceApprovalStatusEnum::Rejected)
+    {
+        InvoiceApprovalState::Rejected
+    } else if approvals
+        .iter()
+        .all(|a| a.status == InvoiceApprovalStatusEnum::Approved)
+    {
+        InvoiceApprovalState::Approved
+    } else {
+        InvoiceApprovalState::Pending
+    }
+}
+
+/// Checks that the actor can decide the current step of the approval
+pub fn validate_decision<'a>(
+    approvals: &'a [InvoiceApproval],
+    actor: Uuid,
+    role: TenantMemberRoleEnum,
+) -> Result<&'a InvoiceApproval, StoreError> {
+    let current = approvals
+        .iter()
+        .find(|a| a.status == InvoiceApprovalStatusEnum::Pending)
+        .ok_or(StoreError::InvalidArgument(
+            "the invoice has no pending approval".to_string(),
+        ))?;
+
+    if !current.can_decide(actor, role) {
+        return Err(StoreError::InvalidArgument(format!(
+            "the step {} is not assigned to you",
+            current.name
+        )));
+    }
+
+    // the steps of a chain are decided by different members
+    if approvals
+        .iter()
+        .any(|a| a.id != current.id && a.decided_by == Some(actor))
+    {
+        return Err(StoreError::InvalidArgument(
+            "you already approved a previous step of this invoice".to_string(),
+        ));
+    }
+
+    Ok(current)
+}
+
+/// An entry of the approval history of an invoice
+#[derive(Debug, Clone, o2o)]
+#[from_owned(InvoiceApprovalEventRow)]
+pub struct InvoiceApprovalEvent {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub approval_id: Option<Uuid>,
+    pub step_name: Option<String>,
+    #[from(~.into())]
+    pub action: InvoiceApprovalActionEnum,
+    /// none when done by the system
+    pub actor_id: Option<Uuid>,
+    pub comment: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+#[derive(Debug, Clone)]
+pub struct InvoiceApprovalDetails {
+    pub state: InvoiceApprovalState,
+    pub steps: Vec<InvoiceApproval>,
+    pub history: Vec<InvoiceApprovalEvent>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn step(
+        position: i32,
+        min_total: Option<i64>,
+        currency: Option<&str>,
+        role: TenantMemberRoleEnum,
+    ) -> InvoiceApprovalStep {
+        InvoiceApprovalStep {
+            id: Uuid::now_v7(),
+            tenant_id: Uuid::nil(),
+            position,
+            name: format!("step {}", position),
+            min_total,
+            currency: currency.map(|c| c.to_string()),
+            assignee_id: None,
+            assignee_role: Some(role),
+            timeout_hours: Some(24),
+            escalation_assignee_id: None,
+            escalation_role: Some(TenantMemberRoleEnum::Admin),
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    fn approval(row: InvoiceApprovalRowNew) -> InvoiceApproval {
+        InvoiceApproval {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            invoice_id: row.invoice_id,
+            step_id: row.step_id,
+            position: row.position,
+            name: row.name,
+            assignee_id: row.assignee_id,
+            assignee_role: row.assignee_role.map(Into::into),
+            timeout_hours: row.timeout_hours,
+            escalation_assignee_id: row.escalation_assignee_id,
+            escalation_role: row.escalation_role.map(Into::into),
+            status: row.status.into(),
+            requested_total: row.requested_total,
+            started_at: row.started_at,
+            due_at: row.due_at,
+            escalated_at: None,
+            decided_at: None,
+            decided_by: None,
+            comment: None,
+            created_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_applicable_steps() {
+        // billing ops for all the invoices, then the finance controller above $50k
+        let chain = vec![
+            step(0, None, None, TenantMemberRoleEnum::Billing),
+            step(1, Some(5_000_000), Some("USD"), TenantMemberRoleEnum::Admin),
+        ];
+
+        assert_eq!(applicable_steps(&chain, 4_999_999, "USD").len(), 1);
+        assert_eq!(applicable_steps(&chain, 5_000_000, "USD").len(), 2);
+        assert_eq!(applicable_steps(&chain, 5_000_000, "EUR").len(), 1);
+    }
+
+    #[test]
+    fn test_chain_flow() {
+        let chain = vec![
+            step(0, None, None, TenantMemberRoleEnum::Billing),
+            step(1, None, None, TenantMemberRoleEnum::Admin),
+        ];
+        let now = NaiveDateTime::default();
+        let steps = applicable_steps(&chain, 100, "USD");
+
+        let mut approvals: Vec<InvoiceApproval> =
+            new_approvals(&steps, Uuid::nil(), Uuid::nil(), 100, now)
+                .into_iter()
+                .map(approval)
+                .collect();
+
+        assert_eq!(approvals[0].status, InvoiceApprovalStatusEnum::Pending);
+        assert_eq!(approvals[0].due_at, Some(now + chrono::Duration::hours(24)));
+        assert_eq!(approvals[1].status, InvoiceApprovalStatusEnum::Waiting);
+        assert_eq!(approvals[1].due_at, None);
+        assert_eq!(
+            approval_state(&approvals, true, 100),
+            InvoiceApprovalState::Pending
+        );
+        assert_eq!(
+            approval_state(&approvals, true, 200),
+            InvoiceApprovalState::Outdated
+        );
+
+        let billing_ops = Uuid::now_v7();
+        assert!(
+            validate_decision(&approvals, billing_ops, TenantMemberRoleEnum::ReadOnly).is_err()
+        );
+        assert!(validate_decision(&approvals, billing_ops, TenantMemberRoleEnum::Billing).is_ok());
+
+        approvals[0].status = InvoiceApprovalStatusEnum::Approved;
+        approvals[0].decided_by = Some(billing_ops);
+        approvals[1].status = InvoiceApprovalStatusEnum::Pending;
+
+        // the billing role does not match the second step, and an actor decides a single step
+        assert!(validate_decision(&approvals, billing_ops, TenantMemberRoleEnum::Billing).is_err());
+        assert!(validate_decision(&approvals, billing_ops, TenantMemberRoleEnum::Admin).is_err());
+        assert!(validate_decision(&approvals, Uuid::now_v7(), TenantMemberRoleEnum::Admin).is_ok());
+
+        approvals[1].status = InvoiceApprovalStatusEnum::Approved;
+        assert_eq!(
+            approval_state(&approvals, true, 100),
+            InvoiceApprovalState::Approved
+        );
+    }
+
+    #[test]
+    fn test_escalation() {
+        let controller = Uuid::now_v7();
+        let deputy = Uuid::now_v7();
+
+        let mut approval = approval(
+            new_approvals(
+                &[&InvoiceApprovalStep {
+                    assignee_id: Some(controller),
+                    assignee_role: None,
+                    escalation_assignee_id: Some(deputy),
+                    escalation_role: None,
+                    ..step(0, None, None, TenantMemberRoleEnum::Admin)
+                }],
+                Uuid::nil(),
+                Uuid::nil(),
+                100,
+                NaiveDateTime::default(),
+            )
+            .remove(0),
+        );
+
+        assert!(approval.can_decide(controller, TenantMemberRoleEnum::Billing));
+        assert!(!approval.can_decide(deputy, TenantMemberRoleEnum::Billing));
+
+        approval.escalated_at = Some(NaiveDateTime::default());
+        assert!(approval.can_decide(controller, TenantMemberRoleEnum::Billing));
+        assert!(approval.can_decide(deputy, TenantMemberRoleEnum::Billing));
+    }
+
+    #[test]
+    fn test_validate_approval_chain() {
+        let valid = InvoiceApprovalStepNew {
+            name: "Finance controller".to_string(),
+            min_total: Some(5_000_000),
+            currency: Some("USD".to_string()),
+            assignee_id: None,
+            assignee_role: Some(TenantMemberRoleEnum::Admin),
+            timeout_hours: Some(48),
+            escalation_assignee_id: Some(Uuid::now_v7()),
+            escalation_role: None,
+        };
+        assert!(validate_approval_chain(&[valid.clone()]).is_ok());
+
+        let invalid = [
+            InvoiceApprovalStepNew {
+                assignee_role: None,
+                ..valid.clone()
+            },
+            InvoiceApprovalStepNew {
+                assignee_role: Some(TenantMemberRoleEnum::ReadOnly),
+                ..valid.clone()
+            },
+            InvoiceApprovalStepNew {
+                escalation_assignee_id: None,
+                ..valid.clone()
+            },
+            InvoiceApprovalStepNew {
+                timeout_hours: None,
+                ..valid.clone()
+            },
+            InvoiceApprovalStepNew {
+                min_total: Some(-1),
+                ..valid.clone()
+            },
+        ];
+        for step in invalid {
+            assert!(validate_approval_chain(&[step]).is_err());
+        }
+
+        assert!(validate_approval_chain(&vec![valid; MAX_APPROVAL_STEPS + 1]).is_err());
+    }
+}
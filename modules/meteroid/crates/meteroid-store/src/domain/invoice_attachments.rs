@@ -0,0 +1,237 @@
+use chrono::NaiveDateTime;
+use diesel_models::invoice_attachments::{InvoiceAttachmentRow, InvoiceAttachmentRowNew};
+use o2o::o2o;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::errors::StoreError;
+
+pub const MAX_ATTACHMENT_SIZE_BYTES: i64 = 10 * 1024 * 1024;
+pub const MAX_ATTACHMENTS_PER_INVOICE: usize = 10;
+/// email providers refuse the larger emails, the pdf of the invoice being sent as well
+pub const MAX_EMAIL_ATTACHMENTS_SIZE_BYTES: i64 = 20 * 1024 * 1024;
+
+/// The accepted content types, with the extensions of their files
+const ALLOWED_CONTENT_TYPES: [(&str, &[&str]); 7] = [
+    ("application/pdf", &["pdf"]),
+    ("text/csv", &["csv"]),
+    ("text/plain", &["txt"]),
+    ("image/png", &["png"]),
+    ("image/jpeg", &["jpg", "jpeg"]),
+    (
+        "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
+        &["xlsx"],
+    ),
+    (
+        "application/vnd.openxmlformats-officedocument.wordprocessingml.document",
+        &["docx"],
+    ),
+];
+
+/// A supporting document of an invoice (usage details, timesheets ..)
+#[derive(Debug, Clone, o2o)]
+#[from_owned(InvoiceAttachmentRow)]
+pub struct InvoiceAttachment {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    /// id of the content in the object storage
+    pub document_id: String,
+    pub include_in_email: bool,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub removed_at: Option<NaiveDateTime>,
+    pub removed_by: Option<Uuid>,
+}
+
+#[derive(Debug, Clone, o2o)]
+#[owned_into(InvoiceAttachmentRowNew)]
+#[ghosts(id: {Uuid::now_v7()})]
+pub struct InvoiceAttachmentNew {
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub size_bytes: i64,
+    pub document_id: String,
+    pub include_in_email: bool,
+    pub created_by: Uuid,
+}
+
+/// Checks the file on its own, before storing its content
+pub fn validate_attachment_file(
+    file_name: &str,
+    content_type: &str,
+    size_bytes: i64,
+) -> Result<(), StoreError> {
+    if file_name.trim().is_empty() || file_name.contains(['/', '\\']) {
+        return Err(StoreError::InvalidArgument(
+            "the file name of an attachment cannot be empty or contain a path".to_string(),
+        ));
+    }
+
+    if size_bytes <= 0 || size_bytes > MAX_ATTACHMENT_SIZE_BYTES {
+        return Err(StoreError::InvalidArgument(format!(
+            "an attachment must be of at most {} bytes",
+            MAX_ATTACHMENT_SIZE_BYTES
+        )));
+    }
+
+    let extension = file_name
+        .rsplit_once('.')
+        .map(|(_, ext)| ext.to_lowercase())
+        .unwrap_or_default();
+
+    match ALLOWED_CONTENT_TYPES
+        .iter()
+        .find(|(allowed, _)| allowed.eq_ignore_ascii_case(content_type))
+    {
+        Some((_, extensions)) if extensions.contains(&extension.as_str()) => Ok(()),
+        Some(_) => Err(StoreError::InvalidArgument(format!(
+            "the extension of {} does not match its content type {}",
+            file_name, content_type
+        ))),
+        None => Err(StoreError::InvalidArgument(format!(
+            "attachments of type {} are not accepted",
+            content_type
+        ))),
+    }
+}
+
+impl InvoiceAttachmentNew {
+    /// Checks the limits of the invoice, given its current attachments
+    pub fn validate(&self, existing: &[InvoiceAttachment]) -> Result<(), StoreError> {
+        validate_attachment_file(&self.file_name, &self.content_type, self.size_bytes)?;
+
+        if existing.len() >= MAX_ATTACHMENTS_PER_INVOICE {
+            return Err(StoreError::InvalidArgument(format!(
+                "an invoice cannot have more than {} attachments",
+                MAX_ATTACHMENTS_PER_INVOICE
+            )));
+        }
+
+        if self.include_in_email {
+            let emailed: i64 = existing
+                .iter()
+                .filter(|a| a.include_in_email)
+                .map(|a| a.size_bytes)
+                .sum();
+
+            if emailed + self.size_bytes > MAX_EMAIL_ATTACHMENTS_SIZE_BYTES {
+                return Err(StoreError::InvalidArgument(format!(
+                    "the attachments sent with the invoice email cannot exceed {} bytes",
+                    MAX_EMAIL_ATTACHMENTS_SIZE_BYTES
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// An attachment to send along with the invoice email
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
+pub struct InvoiceEmailAttachment {
+    pub attachment_id: Uuid,
+    pub file_name: String,
+    pub content_type: String,
+    pub document_id: String,
+}
+
+impl From<&InvoiceAttachment> for InvoiceEmailAttachment {
+    fn from(attachment: &InvoiceAttachment) -> Self {
+        InvoiceEmailAttachment {
+            attachment_id: attachment.id,
+            file_name: attachment.file_name.clone(),
+            content_type: attachment.content_type.clone(),
+            document_id: attachment.document_id.clone(),
+        }
+    }
+}
+
+/// Payload of the invoice.finalized outbox event, when some attachments are bundled with the invoice email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceFinalizedPayload {
+    pub email_attachments: Vec<InvoiceEmailAttachment>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attachment(size_bytes: i64, include_in_email: bool) -> InvoiceAttachment {
+        InvoiceAttachment {
+            id: Uuid::now_v7(),
+            tenant_id: Uuid::nil(),
+            invoice_id: Uuid::nil(),
+            file_name: "usage.csv".to_string(),
+            content_type: "text/csv".to_string(),
+            size_bytes,
+            document_id: Uuid::now_v7().to_string(),
+            include_in_email,
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+            removed_at: None,
+            removed_by: None,
+        }
+    }
+
+    fn new(file_name: &str, content_type: &str, size_bytes: i64) -> InvoiceAttachmentNew {
+        InvoiceAttachmentNew {
+            tenant_id: Uuid::nil(),
+            invoice_id: Uuid::nil(),
+            file_name: file_name.to_string(),
+            content_type: content_type.to_string(),
+            size_bytes,
+            document_id: Uuid::now_v7().to_string(),
+            include_in_email: true,
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_validate_attachment_file() {
+        assert!(validate_attachment_file("usage.csv", "text/csv", 1024).is_ok());
+        assert!(validate_attachment_file("Timesheet.JPEG", "image/jpeg", 1024).is_ok());
+
+        assert!(validate_attachment_file("usage.exe", "application/x-msdownload", 1024).is_err());
+        assert!(validate_attachment_file("usage.exe", "text/csv", 1024).is_err());
+        assert!(validate_attachment_file("../usage.csv", "text/csv", 1024).is_err());
+        assert!(validate_attachment_file("usage.csv", "text/csv", 0).is_err());
+        assert!(
+            validate_attachment_file("usage.csv", "text/csv", MAX_ATTACHMENT_SIZE_BYTES + 1)
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_validate_invoice_limits() {
+        let sow = new("sow.pdf", "application/pdf", 8 * 1024 * 1024);
+
+        let existing = vec![attachment(8 * 1024 * 1024, true)];
+        assert!(sow.validate(&existing).is_ok());
+
+        // beyond the size of the email
+        let existing = vec![
+            attachment(8 * 1024 * 1024, true),
+            attachment(8 * 1024 * 1024, true),
+        ];
+        assert!(sow.validate(&existing).is_err());
+        assert!(InvoiceAttachmentNew {
+            include_in_email: false,
+            ..sow.clone()
+        }
+        .validate(&existing)
+        .is_ok());
+
+        let existing = (0..MAX_ATTACHMENTS_PER_INVOICE)
+            .map(|_| attachment(1024, false))
+            .collect::<Vec<_>>();
+        assert!(new("usage.csv", "text/csv", 1024)
+            .validate(&existing)
+            .is_err());
+    }
+}
@@ -0,0 +1,165 @@
+use crate::domain::enums::{
+    BillingPeriodEnum, InvoiceCadenceGroupingEnum, SubscriptionFeeBillingPeriod,
+};
+use crate::domain::{SubscriptionDetails, SubscriptionFeeInterface};
+
+/// The billing period a fee is invoiced at. One-time fees are billed with the subscription period
+pub fn fee_cadence(
+    fee_period: &SubscriptionFeeBillingPeriod,
+    subscription_period: &BillingPeriodEnum,
+) -> BillingPeriodEnum {
+    fee_period
+        .as_billing_period_opt()
+        .unwrap_or_else(|| subscription_period.clone())
+}
+
+/// The cadences of the recurring invoices of a subscription, shortest first.
+/// A single invoice billing all the fees (None) when the cadences are combined
+pub fn invoice_cadences<'a>(
+    grouping: InvoiceCadenceGroupingEnum,
+    subscription_period: &BillingPeriodEnum,
+    fee_periods: impl Iterator<Item = &'a SubscriptionFeeBillingPeriod>,
+) -> Vec<Option<BillingPeriodEnum>> {
+    match grouping {
+        InvoiceCadenceGroupingEnum::Combined => vec![None],
+        InvoiceCadenceGroupingEnum::PerCadence => {
+            let mut cadences: Vec<BillingPeriodEnum> = fee_periods
+                .map(|period| fee_cadence(period, subscription_period))
+                .collect();
+
+            if cadences.is_empty() {
+                cadences.push(subscription_period.clone());
+            }
+
+            cadences.sort_by_key(|c| c.as_months());
+            cadences.dedup();
+
+            cadences.into_iter().map(Some).collect()
+        }
+    }
+}
+
+/// The subscription cadences that have no recurring invoice drafted yet. An invoice of all the cadences covers them all
+pub fn missing_cadences(
+    cadences: Vec<Option<BillingPeriodEnum>>,
+    drafted: &[Option<BillingPeriodEnum>],
+) -> Vec<Option<BillingPeriodEnum>> {
+    if drafted.contains(&None) {
+        return vec![];
+    }
+
+    cadences
+        .into_iter()
+        .filter(|c| !drafted.contains(c))
+        .collect()
+}
+
+fn fees_of_cadence<T: SubscriptionFeeInterface + Clone>(
+    fees: &[T],
+    cadence: &BillingPeriodEnum,
+    subscription_period: &BillingPeriodEnum,
+) -> Vec<T> {
+    fees.iter()
+        .filter(|fee| fee_cadence(fee.period_ref(), subscription_period) == *cadence)
+        .cloned()
+        .collect()
+}
+
+impl SubscriptionDetails {
+    /// The subscription restricted to the fees invoiced at the cadence, or all the fees if not set
+    pub fn with_cadence_fees(&self, cadence: Option<&BillingPeriodEnum>) -> SubscriptionDetails {
+        match cadence {
+            None => self.clone(),
+            Some(cadence) => SubscriptionDetails {
+                price_components: fees_of_cadence(&self.price_components, cadence, &self.period),
+                add_ons: fees_of_cadence(&self.add_ons, cadence, &self.period),
+                ..self.clone()
+            },
+        }
+    }
+
+    /// The cadences of the recurring invoices of the subscription
+    pub fn invoice_cadences(
+        &self,
+        grouping: InvoiceCadenceGroupingEnum,
+    ) -> Vec<Option<BillingPeriodEnum>> {
+        invoice_cadences(
+            grouping,
+            &self.period,
+            self.price_components
+                .iter()
+                .map(|c| c.period_ref())
+                .chain(self.add_ons.iter().map(|a| a.period_ref())),
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_combined_cadences() {
+        let periods = [
+            SubscriptionFeeBillingPeriod::Monthly,
+            SubscriptionFeeBillingPeriod::Annual,
+        ];
+
+        assert_eq!(
+            invoice_cadences(
+                InvoiceCadenceGroupingEnum::Combined,
+                &BillingPeriodEnum::Monthly,
+                periods.iter(),
+            ),
+            vec![None]
+        );
+    }
+
+    #[test]
+    fn test_per_cadence_mixed_subscription() {
+        // monthly usage, an annual platform fee and a one-time setup fee
+        let periods = [
+            SubscriptionFeeBillingPeriod::Annual,
+            SubscriptionFeeBillingPeriod::Monthly,
+            SubscriptionFeeBillingPeriod::OneTime,
+            SubscriptionFeeBillingPeriod::Monthly,
+        ];
+
+        assert_eq!(
+            invoice_cadences(
+                InvoiceCadenceGroupingEnum::PerCadence,
+                &BillingPeriodEnum::Monthly,
+                periods.iter(),
+            ),
+            vec![
+                Some(BillingPeriodEnum::Monthly),
+                Some(BillingPeriodEnum::Annual)
+            ]
+        );
+
+        assert_eq!(
+            invoice_cadences(
+                InvoiceCadenceGroupingEnum::PerCadence,
+                &BillingPeriodEnum::Quarterly,
+                [].iter(),
+            ),
+            vec![Some(BillingPeriodEnum::Quarterly)]
+        );
+    }
+
+    #[test]
+    fn test_missing_cadences() {
+        let cadences = vec![
+            Some(BillingPeriodEnum::Monthly),
+            Some(BillingPeriodEnum::Annual),
+        ];
+
+        assert_eq!(missing_cadences(cadences.clone(), &[]), cadences);
+        assert_eq!(
+            missing_cadences(cadences.clone(), &[Some(BillingPeriodEnum::Annual)]),
+            vec![Some(BillingPeriodEnum::Monthly)]
+        );
+        // a combined invoice drafted before the tenant switched to per cadence invoicing
+        assert!(missing_cadences(cadences, &[None]).is_empty());
+    }
+}
@@ -0,0 +1,85 @@
+use crate::domain::customers::BillingConfig;
+use crate::domain::enums::{BillingEmailStatusEnum, InvoiceDeliveryChannelEnum};
+use crate::domain::invoice_attachments::InvoiceEmailAttachment;
+use crate::domain::Customer;
+use crate::errors::StoreError;
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Payload of the outbox entry requesting the invoice email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct InvoiceDeliveryEmailPayload {
+    pub invoice_id: Uuid,
+    pub recipient: String,
+    pub customer_name: String,
+    pub invoice_number: String,
+    pub currency: String,
+    pub amount_due: i64,
+    pub due_at: Option<NaiveDateTime>,
+    pub pdf_document_id: Option<String>,
+    pub email_attachments: Vec<InvoiceEmailAttachment>,
+}
+
+/// Checks that the customer can receive its invoices through the channel
+pub fn validate_delivery_channel(
+    channel: &InvoiceDeliveryChannelEnum,
+    customer: &Customer,
+) -> Result<(), StoreError> {
+    match channel {
+        InvoiceDeliveryChannelEnum::Email => {
+            email_recipient(customer.billing_email(), &customer.billing_email_status).map(|_| ())
+        }
+        InvoiceDeliveryChannelEnum::Webhook => Ok(()),
+        InvoiceDeliveryChannelEnum::ProviderHosted => match customer.billing_config {
+            BillingConfig::Stripe(_) | BillingConfig::Adyen(_) => Ok(()),
+            BillingConfig::Manual | BillingConfig::GoCardless(_) => {
+                Err(StoreError::InvalidArgument(
+                    "Provider hosted delivery requires a customer billed through Stripe or Adyen"
+                        .to_string(),
+                ))
+            }
+        },
+    }
+}
+
+/// Address the invoice emails are sent to, if it did not bounce
+pub fn email_recipient(
+    billing_email: Option<&String>,
+    status: &BillingEmailStatusEnum,
+) -> Result<String, StoreError> {
+    let recipient = billing_email
+        .filter(|e| !e.trim().is_empty())
+        .ok_or_else(|| {
+            StoreError::InvalidArgument("The customer has no billing email".to_string())
+        })?;
+
+    if !status.is_deliverable() {
+        return Err(StoreError::InvalidArgument(format!(
+            "The billing email {} is not deliverable",
+            recipient
+        )));
+    }
+
+    Ok(recipient.clone())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_email_recipient() {
+        let email = "billing@acme.com".to_string();
+
+        assert_eq!(
+            email_recipient(Some(&email), &BillingEmailStatusEnum::Verified).unwrap(),
+            email
+        );
+        assert!(email_recipient(Some(&email), &BillingEmailStatusEnum::Bounced).is_err());
+        assert!(email_recipient(None, &BillingEmailStatusEnum::Verified).is_err());
+        assert!(
+            email_recipient(Some(&" ".to_string()), &BillingEmailStatusEnum::Verified).is_err()
+        );
+    }
+}
@@ -0,0 +1,510 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use rust_decimal::Decimal;
+use std::collections::HashSet;
+use uuid::Uuid;
+
+use crate::constants::Currencies;
+use crate::domain::enums::{InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum};
+use crate::domain::payment_terms::{PaymentTerms, TenantPaymentTerms};
+use crate::domain::{
+    BillingConfig, Customer, InlineCustomer, InlineInvoicingEntity, InvoiceNew, InvoicingEntity,
+    LineItem,
+};
+use crate::errors::StoreError;
+use crate::utils::decimals::ToSubunit;
+use crate::utils::local_id::{IdType, LocalId};
+
+/// A line of a draft edited by a user
+#[derive(Debug, Clone)]
+pub struct DraftInvoiceLine {
+    /// an existing line of the invoice, a new line if not set
+    pub local_id: Option<String>,
+    pub name: String,
+    pub description: Option<String>,
+    /// when changed with the unit price, the line is priced again from them and its sub lines are removed.
+    /// Required for the new lines
+    pub quantity: Option<Decimal>,
+    pub unit_price: Option<Decimal>,
+    /// the period of the invoice if not set on a new line
+    pub start_date: Option<NaiveDate>,
+    pub end_date: Option<NaiveDate>,
+}
+
+/// The changes of a user to a draft invoice, the fields that are not set being kept as is
+#[derive(Debug, Clone, Default)]
+pub struct DraftInvoiceUpdate {
+    /// all the lines of the invoice, the lines of the invoice that are not listed are removed
+    pub lines: Option<Vec<DraftInvoiceLine>>,
+    /// an empty memo removes it
+    pub memo: Option<String>,
+    pub due_date: Option<NaiveDate>,
+}
+
+impl DraftInvoiceUpdate {
+    pub fn validate(&self, invoice_date: NaiveDate) -> Result<(), StoreError> {
+        if self.lines.is_none() && self.memo.is_none() && self.due_date.is_none() {
+            return Err(StoreError::InvalidArgument(
+                "the update of the draft does not change anything".to_string(),
+            ));
+        }
+
+        if self.due_date.is_some_and(|due| due < invoice_date) {
+            return Err(StoreError::InvalidArgument(
+                "the due date cannot be before the invoice date".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+/// The lines of the draft after the edit. The lines that are kept without a price change keep their computed details
+pub fn apply_draft_lines(
+    current: &[LineItem],
+    lines: Vec<DraftInvoiceLine>,
+    currency: &str,
+    invoice_date: NaiveDate,
+) -> Result<Vec<LineItem>, StoreError> {
+    let precision = Currencies::resolve_currency_precision(currency).ok_or(
+        StoreError::InvalidArgument(format!("unknown currency {}", currency)),
+    )?;
+
+    let mut seen = HashSet::new();
+    let mut edited = Vec::with_capacity(lines.len());
+
+    for line in lines {
+        if line.name.trim().is_empty() {
+            return Err(StoreError::InvalidArgument(
+                "the name of a line cannot be empty".to_string(),
+            ));
+        }
+
+        if line.quantity.is_some_and(|q| q.is_sign_negative()) {
+            return Err(StoreError::InvalidArgument(format!(
+                "the quantity of the line {} cannot be negative",
+                line.name
+            )));
+        }
+
+        if line.quantity.is_some() != line.unit_price.is_some() {
+            return Err(StoreError::InvalidArgument(format!(
+                "the quantity and the unit price of the line {} must be set together",
+                line.name
+            )));
+        }
+
+        let priced = match (line.quantity, line.unit_price) {
+            (Some(quantity), Some(unit_price)) => Some((
+                quantity,
+                unit_price,
+                (quantity * unit_price).to_subunit_opt(precision).ok_or(
+                    StoreError::InvalidArgument(format!(
+                        "the amount of the line {} is too large",
+                        line.name
+                    )),
+                )?,
+            )),
+            _ => None,
+        };
+
+        let mut item = match &line.local_id {
+            Some(local_id) => {
+                if !seen.insert(local_id.clone()) {
+                    return Err(StoreError::InvalidArgument(format!(
+                        "the line {} is listed more than once",
+                        local_id
+                    )));
+                }
+
+                current
+                    .iter()
+                    .find(|l| &l.local_id == local_id)
+                    .cloned()
+                    .ok_or(StoreError::InvalidArgument(format!(
+                        "the line {} is not a line of the invoice",
+                        local_id
+                    )))?
+            }
+            None => {
+                let Some((quantity, unit_price, _)) = priced else {
+                    return Err(StoreError::InvalidArgument(format!(
+                        "the new line {} requires a quantity and a unit price",
+                        line.name
+                    )));
+                };
+
+                LineItem {
+                    local_id: LocalId::no_prefix(),
+                    name: line.name.clone(),
+                    total: 0,
+                    subtotal: 0,
+                    quantity: Some(quantity),
+                    unit_price: Some(unit_price),
+                    start_date: invoice_date,
+                    end_date: invoice_date,
+                    sub_lines: vec![],
+                    is_prorated: false,
+                    price_component_id: None,
+                    product_id: None,
+                    metric_id: None,
+                    description: None,
+                    quantity_display_precision: None,
+                }
+            }
+        };
+
+        if let Some((quantity, unit_price, amount)) = priced {
+            if item.quantity != Some(quantity)
+                || item.unit_price != Some(unit_price)
+                || item.total != amount
+            {
+                item.quantity = Some(quantity);
+                item.unit_price = Some(unit_price);
+                item.total = amount;
+                item.subtotal = amount;
+                item.sub_lines = vec![];
+                item.is_prorated = false;
+            }
+        }
+
+        item.name = line.name;
+        item.description = line.description;
+        item.start_date = line.start_date.unwrap_or(item.start_date);
+        item.end_date = line.end_date.unwrap_or(item.end_date);
+
+        if item.start_date > item.end_date {
+            return Err(StoreError::InvalidArgument(format!(
+                "the line {} ends before it starts",
+                item.name
+            )));
+        }
+
+        edited.push(item);
+    }
+
+    if edited.iter().map(|l| l.subtotal).sum::<i64>() < 0 {
+        return Err(StoreError::InvalidArgument(
+            "the subtotal of the invoice cannot be negative".to_string(),
+        ));
+    }
+
+    Ok(edited)
+}
+
+/// An invoice billing a customer outside of any subscription, like professional services or setup fees
+#[derive(Debug, Clone)]
+pub struct OneOffInvoiceNew {
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    /// all new lines, priced from their quantity and unit price
+    pub lines: Vec<DraftInvoiceLine>,
+    /// today if not set
+    pub invoice_date: Option<NaiveDate>,
+    pub reference: Option<String>,
+    pub memo: Option<String>,
+    /// finalized and numbered right away, otherwise kept as a draft until the end of the grace period
+    pub finalize: bool,
+}
+
+impl OneOffInvoiceNew {
+    /// The invoice date, checked against today
+    pub fn validate(&self, today: NaiveDate) -> Result<NaiveDate, StoreError> {
+        if self.lines.is_empty() {
+            return Err(StoreError::InvalidArgument(
+                "a one-off invoice requires at least one line".to_string(),
+            ));
+        }
+
+        if self.lines.iter().any(|l| l.local_id.is_some()) {
+            return Err(StoreError::InvalidArgument(
+                "the lines of a new invoice cannot reference existing lines".to_string(),
+            ));
+        }
+
+        let invoice_date = self.invoice_date.unwrap_or(today);
+
+        // the past dates would break the order of the numbering sequence
+        if invoice_date < today {
+            return Err(StoreError::InvalidArgument(
+                "the invoice date cannot be in the past".to_string(),
+            ));
+        }
+
+        if self.finalize && invoice_date != today {
+            return Err(StoreError::InvalidArgument(
+                "an invoice finalized right away is dated today".to_string(),
+            ));
+        }
+
+        Ok(invoice_date)
+    }
+
+    /// The draft going through the finalization, with the payment terms of the customer
+    pub fn to_draft(
+        &self,
+        customer: &Customer,
+        invoicing_entity: &InvoicingEntity,
+        tenant_payment_terms: &TenantPaymentTerms,
+        invoice_date: NaiveDate,
+        line_items: Vec<LineItem>,
+        now: NaiveDateTime,
+    ) -> InvoiceNew {
+        let payment_terms = PaymentTerms::resolve(
+            customer,
+            None,
+            tenant_payment_terms,
+            invoicing_entity.net_terms as u32,
+        );
+
+        let invoicing_provider = match customer.billing_config {
+            BillingConfig::Stripe(_) => InvoicingProviderEnum::Stripe,
+            BillingConfig::Manual => InvoicingProviderEnum::Manual,
+            BillingConfig::Adyen(_) => InvoicingProviderEnum::Adyen,
+            BillingConfig::GoCardless(_) => InvoicingProviderEnum::GoCardless,
+        };
+
+        let total = line_items.iter().map(|l| l.total).sum();
+
+        InvoiceNew {
+            status: InvoiceStatusEnum::Draft,
+            external_status: None,
+            tenant_id: self.tenant_id,
+            customer_id: customer.id,
+            subscription_id: None,
+            currency: customer.currency.clone(),
+            external_invoice_id: None,
+            invoice_number: "draft".to_string(),
+            invoicing_provider,
+            line_items,
+            issued: false,
+            issue_attempts: 0,
+            last_issue_attempt_at: None,
+            last_issue_error: None,
+            data_updated_at: None,
+            invoice_date,
+            plan_version_id: None,
+            invoice_type: InvoiceType::OneOff,
+            finalized_at: None,
+            subtotal: total,
+            subtotal_recurring: 0,
+            tax_rate: 0,
+            tax_amount: 0,
+            total,
+            amount_due: total,
+            net_terms: payment_terms.net_terms as i32,
+            reference: self.reference.clone(),
+            memo: self.memo.clone().filter(|m| !m.trim().is_empty()),
+            local_id: LocalId::generate_for(IdType::Invoice),
+            due_at: Some(tenant_payment_terms.due_at(payment_terms.due_date(invoice_date))),
+            plan_name: None,
+            customer_details: InlineCustomer {
+                id: customer.id,
+                name: customer.name.clone(),
+                email: customer.email.clone(),
+                alias: customer.alias.clone(),
+                vat_number: None,
+                billing_address: customer.billing_address.clone(),
+                early_payment_discount: customer.early_payment_discount.clone(),
+                snapshot_at: now,
+            },
+            seller_details: InlineInvoicingEntity {
+                id: invoicing_entity.id,
+                legal_name: invoicing_entity.legal_name.clone(),
+                vat_number: invoicing_entity.vat_number.clone(),
+                address: invoicing_entity.address(),
+                // resolved against the customer country at finalization
+                tax_registrations: vec![],
+                snapshot_at: now,
+            },
+            billing_cadence: None,
+            replaces_invoice_id: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn usage_line() -> LineItem {
+        LineItem {
+            local_id: "usage".to_string(),
+            name: "API calls".to_string(),
+            total: 12000,
+            subtotal: 12000,
+            quantity: Some(dec!(1200)),
+            unit_price: Some(dec!(0.1)),
+            start_date: date("2024-01-01"),
+            end_date: date("2024-01-31"),
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id: None,
+            product_id: None,
+            metric_id: None,
+            description: None,
+            quantity_display_precision: None,
+        }
+    }
+
+    fn edit(local_id: Option<&str>, quantity: Option<Decimal>) -> DraftInvoiceLine {
+        DraftInvoiceLine {
+            local_id: local_id.map(|id| id.to_string()),
+            name: "API calls".to_string(),
+            description: None,
+            quantity,
+            unit_price: quantity.map(|_| dec!(0.1)),
+            start_date: None,
+            end_date: None,
+        }
+    }
+
+    #[test]
+    fn test_apply_draft_lines() {
+        let current = vec![usage_line()];
+        let invoice_date = date("2024-02-01");
+
+        // kept as is
+        let lines = apply_draft_lines(
+            &current,
+            vec![edit(Some("usage"), None)],
+            "EUR",
+            invoice_date,
+        )
+        .unwrap();
+        assert_eq!(lines, current);
+
+        // priced again
+        let lines = apply_draft_lines(
+            &current,
+            vec![edit(Some("usage"), Some(dec!(1000)))],
+            "EUR",
+            invoice_date,
+        )
+        .unwrap();
+        assert_eq!(lines[0].total, 10000);
+        assert_eq!(lines[0].start_date, date("2024-01-01"));
+
+        // a new line, the existing one being removed
+        let lines = apply_draft_lines(
+            &current,
+            vec![DraftInvoiceLine {
+                name: "Onboarding".to_string(),
+                unit_price: Some(dec!(500)),
+                ..edit(None, Some(dec!(1)))
+            }],
+            "EUR",
+            invoice_date,
+        )
+        .unwrap();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].name, "Onboarding");
+        assert_eq!(lines[0].total, 50000);
+        assert_eq!(lines[0].start_date, invoice_date);
+    }
+
+    #[test]
+    fn test_apply_draft_lines_invalid() {
+        let current = vec![usage_line()];
+        let invoice_date = date("2024-02-01");
+
+        let invalid = vec![
+            vec![edit(Some("unknown"), None)],
+            vec![edit(None, None)],
+            vec![edit(Some("usage"), None), edit(Some("usage"), None)],
+            vec![edit(Some("usage"), Some(dec!(-1)))],
+            vec![DraftInvoiceLine {
+                unit_price: Some(dec!(-200)),
+                ..edit(None, Some(dec!(1)))
+            }],
+            vec![DraftInvoiceLine {
+                name: " ".to_string(),
+                ..edit(Some("usage"), None)
+            }],
+            vec![DraftInvoiceLine {
+                start_date: Some(date("2024-02-01")),
+                ..edit(Some("usage"), None)
+            }],
+        ];
+
+        for lines in invalid {
+            assert!(apply_draft_lines(&current, lines, "EUR", invoice_date).is_err());
+        }
+    }
+
+    #[test]
+    fn test_validate_update() {
+        let invoice_date = date("2024-02-01");
+
+        assert!(DraftInvoiceUpdate::default()
+            .validate(invoice_date)
+            .is_err());
+        assert!(DraftInvoiceUpdate {
+            due_date: Some(date("2024-01-31")),
+            ..Default::default()
+        }
+        .validate(invoice_date)
+        .is_err());
+        assert!(DraftInvoiceUpdate {
+            memo: Some("PO 1234".to_string()),
+            due_date: Some(date("2024-03-01")),
+            ..Default::default()
+        }
+        .validate(invoice_date)
+        .is_ok());
+    }
+
+    #[test]
+    fn test_validate_one_off_invoice() {
+        let today = date("2024-02-01");
+        let new = OneOffInvoiceNew {
+            tenant_id: Uuid::nil(),
+            customer_id: Uuid::nil(),
+            lines: vec![edit(None, Some(dec!(1)))],
+            invoice_date: None,
+            reference: None,
+            memo: None,
+            finalize: true,
+        };
+
+        assert_eq!(new.validate(today).unwrap(), today);
+        assert_eq!(
+            OneOffInvoiceNew {
+                invoice_date: Some(date("2024-02-10")),
+                finalize: false,
+                ..new.clone()
+            }
+            .validate(today)
+            .unwrap(),
+            date("2024-02-10")
+        );
+
+        let invalid = vec![
+            OneOffInvoiceNew {
+                lines: vec![],
+                ..new.clone()
+            },
+            OneOffInvoiceNew {
+                lines: vec![edit(Some("usage"), None)],
+                ..new.clone()
+            },
+            OneOffInvoiceNew {
+                invoice_date: Some(date("2024-01-31")),
+                finalize: false,
+                ..new.clone()
+            },
+            OneOffInvoiceNew {
+                invoice_date: Some(date("2024-02-10")),
+                ..new.clone()
+            },
+        ];
+
+        for new in invalid {
+            assert!(new.validate(today).is_err());
+        }
+    }
+}
@@ -24,6 +24,10 @@ pub struct LineItem {
     pub metric_id: Option<Uuid>,
 
     pub description: Option<String>,
+
+    /// decimal places of the quantities of the line and its sub lines on the invoice, from the billable metric
+    #[serde(default)]
+    pub quantity_display_precision: Option<u32>,
 }
 
 #[derive(PartialEq, Debug, Deserialize, Serialize, Eq, Clone)]
@@ -59,4 +63,18 @@ pub enum SubLineAttributes {
         dimension2_key: Option<String>,
         dimension2_value: Option<String>,
     },
+    /// share of the usage line of a value of the usage group key of the metric
+    UsageGroup {
+        group_key: String,
+        group_value: String,
+    },
+    /// share of the amount aggregated by the metric of a percent fee
+    Percent {
+        percentage: Decimal,
+        base_amount: Decimal,
+    },
+    /// discount of a promotional phase of the subscription
+    Promotion {
+        phase_id: Uuid,
+    },
 }
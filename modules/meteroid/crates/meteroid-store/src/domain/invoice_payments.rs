@@ -0,0 +1,193 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_models::invoice_payments::{InvoicePaymentRow, InvoicePaymentRowNew};
+use o2o::o2o;
+use uuid::Uuid;
+
+use crate::domain::enums::{InvoiceStatusEnum, InvoicingProviderEnum};
+use crate::errors::StoreError;
+
+pub const MAX_PAYMENT_REFERENCE_LENGTH: usize = 255;
+
+/// A payment collected outside of any invoicing provider (bank transfer, cheque ..), recorded by the tenant
+#[derive(Debug, Clone, o2o)]
+#[from_owned(InvoicePaymentRow)]
+pub struct InvoicePayment {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    /// in the currency of the invoice
+    pub amount: i64,
+    pub paid_at: NaiveDate,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+#[derive(Debug, Clone, o2o)]
+#[owned_into(InvoicePaymentRowNew)]
+#[ghosts(id: {Uuid::now_v7()})]
+pub struct InvoicePaymentNew {
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    pub amount: i64,
+    pub paid_at: NaiveDate,
+    pub reference: Option<String>,
+    pub note: Option<String>,
+    pub created_by: Uuid,
+}
+
+/// The recorded payment, and what remains due on the invoice
+#[derive(Debug, Clone)]
+pub struct RecordedInvoicePayment {
+    pub payment: InvoicePayment,
+    pub amount_due: i64,
+}
+
+impl RecordedInvoicePayment {
+    pub fn fully_paid(&self) -> bool {
+        self.amount_due == 0
+    }
+}
+
+impl InvoicePaymentNew {
+    /// Payments are recorded against the finalized invoices collected manually, up to their amount due.
+    /// The payments of the invoices collected by a provider are reported by its webhooks
+    pub fn validate(
+        &self,
+        status: &InvoiceStatusEnum,
+        invoicing_provider: &InvoicingProviderEnum,
+        amount_due: i64,
+        today: NaiveDate,
+    ) -> Result<(), StoreError> {
+        if *invoicing_provider != InvoicingProviderEnum::Manual {
+            return Err(StoreError::InvalidArgument(
+                "payments can only be recorded against the invoices collected manually".to_string(),
+            ));
+        }
+
+        if *status != InvoiceStatusEnum::Finalized {
+            return Err(StoreError::InvalidArgument(
+                "payments can only be recorded against finalized invoices".to_string(),
+            ));
+        }
+
+        if self.amount <= 0 {
+            return Err(StoreError::InvalidArgument(
+                "the amount of a payment must be positive".to_string(),
+            ));
+        }
+
+        if self.amount > amount_due {
+            return Err(StoreError::InvalidArgument(format!(
+                "the payment exceeds the amount due of the invoice ({})",
+                amount_due
+            )));
+        }
+
+        if self.paid_at > today {
+            return Err(StoreError::InvalidArgument(
+                "a payment cannot be dated in the future".to_string(),
+            ));
+        }
+
+        if self
+            .reference
+            .as_ref()
+            .is_some_and(|r| r.chars().count() > MAX_PAYMENT_REFERENCE_LENGTH)
+        {
+            return Err(StoreError::InvalidArgument(format!(
+                "the reference of a payment is limited to {} characters",
+                MAX_PAYMENT_REFERENCE_LENGTH
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 11, 19).unwrap()
+    }
+
+    fn payment(amount: i64) -> InvoicePaymentNew {
+        InvoicePaymentNew {
+            tenant_id: Uuid::nil(),
+            invoice_id: Uuid::nil(),
+            amount,
+            paid_at: today(),
+            reference: Some("TRF-2024-1119".to_string()),
+            note: None,
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_validate_partial_and_full_payments() {
+        let finalized = InvoiceStatusEnum::Finalized;
+        let manual = InvoicingProviderEnum::Manual;
+
+        assert!(payment(4000)
+            .validate(&finalized, &manual, 10000, today())
+            .is_ok());
+        assert!(payment(10000)
+            .validate(&finalized, &manual, 10000, today())
+            .is_ok());
+
+        assert!(payment(10001)
+            .validate(&finalized, &manual, 10000, today())
+            .is_err());
+        assert!(payment(0)
+            .validate(&finalized, &manual, 10000, today())
+            .is_err());
+        assert!(payment(-100)
+            .validate(&finalized, &manual, 10000, today())
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_invoice() {
+        let manual = InvoicingProviderEnum::Manual;
+
+        assert!(payment(100)
+            .validate(&InvoiceStatusEnum::Draft, &manual, 10000, today())
+            .is_err());
+        assert!(payment(100)
+            .validate(&InvoiceStatusEnum::Void, &manual, 10000, today())
+            .is_err());
+        assert!(payment(100)
+            .validate(
+                &InvoiceStatusEnum::Finalized,
+                &InvoicingProviderEnum::Stripe,
+                10000,
+                today()
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_validate_payment_details() {
+        let finalized = InvoiceStatusEnum::Finalized;
+        let manual = InvoicingProviderEnum::Manual;
+
+        let future = InvoicePaymentNew {
+            paid_at: today().succ_opt().unwrap(),
+            ..payment(100)
+        };
+        assert!(future
+            .validate(&finalized, &manual, 10000, today())
+            .is_err());
+
+        let long_reference = InvoicePaymentNew {
+            reference: Some("x".repeat(MAX_PAYMENT_REFERENCE_LENGTH + 1)),
+            ..payment(100)
+        };
+        assert!(long_reference
+            .validate(&finalized, &manual, 10000, today())
+            .is_err());
+    }
+}
@@ -0,0 +1,77 @@
+use chrono::NaiveDateTime;
+use diesel_models::invoice_stuck_alerts::{InvoiceStuckAlertRow, StuckInvoiceRow};
+use uuid::Uuid;
+
+use crate::domain::enums::{InvoiceStatusEnum, InvoiceStuckReasonEnum};
+
+/// How long an invoice can stay in a status before it is considered stuck.
+/// The defaults of the instance, a tenant can set its own
+#[derive(Debug, Clone)]
+pub struct InvoiceWatchdogSla {
+    /// after the invoice date
+    pub draft_max_age_days: u32,
+    /// after the end of the grace period of the invoicing entity
+    pub pending_max_age_hours: u32,
+    /// issue attempts after which the issue worker gives up
+    pub max_issue_attempts: u32,
+}
+
+impl Default for InvoiceWatchdogSla {
+    fn default() -> Self {
+        Self {
+            draft_max_age_days: 7,
+            pending_max_age_hours: 24,
+            max_issue_attempts: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct StuckInvoice {
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub invoice_number: String,
+    pub status: InvoiceStatusEnum,
+    pub reason: InvoiceStuckReasonEnum,
+    /// when the SLA started running
+    pub stuck_since: NaiveDateTime,
+    pub last_issue_error: Option<String>,
+}
+
+impl From<StuckInvoiceRow> for StuckInvoice {
+    fn from(row: StuckInvoiceRow) -> Self {
+        Self {
+            invoice_id: row.id,
+            tenant_id: row.tenant_id,
+            customer_id: row.customer_id,
+            invoice_number: row.invoice_number,
+            status: row.status.into(),
+            reason: row.reason.into(),
+            stuck_since: row.stuck_since,
+            last_issue_error: row.last_issue_error,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct InvoiceStuckAlert {
+    pub invoice_id: Uuid,
+    pub tenant_id: Uuid,
+    pub reason: InvoiceStuckReasonEnum,
+    pub alerted_at: NaiveDateTime,
+    /// when the invoice moved on
+    pub resolved_at: Option<NaiveDateTime>,
+}
+
+impl From<InvoiceStuckAlertRow> for InvoiceStuckAlert {
+    fn from(row: InvoiceStuckAlertRow) -> Self {
+        Self {
+            invoice_id: row.invoice_id,
+            tenant_id: row.tenant_id,
+            reason: row.reason.into(),
+            alerted_at: row.alerted_at,
+            resolved_at: row.resolved_at,
+        }
+    }
+}
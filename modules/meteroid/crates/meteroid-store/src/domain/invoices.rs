@@ -1,11 +1,17 @@
 use super::enums::{
+    BillingPeriodEnum, InvoiceDeliveryChannelEnum, InvoiceDeliveryStatusEnum,
     InvoiceExternalStatusEnum, InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum,
+    TaxRegistrationTypeEnum,
 };
 use crate::domain::coupons::CouponDiscount;
 use crate::domain::invoice_lines::LineItem;
-use crate::domain::{Address, AppliedCouponDetailed, Customer, PlanVersionLatest};
+use crate::domain::{
+    Address, AppliedCouponDetailed, Customer, EarlyPaymentDiscount, PlanVersionLatest,
+};
 use crate::errors::{StoreError, StoreErrorReport};
+use crate::utils::datetime::start_of_month;
 use crate::utils::decimals::ToSubunit;
+use crate::utils::local_id::{IdType, LocalId};
 use chrono::{NaiveDate, NaiveDateTime};
 use diesel_models::invoices::DetailedInvoiceRow;
 use diesel_models::invoices::InvoiceRow;
@@ -77,12 +83,50 @@ pub struct Invoice {
     pub seller_details: InlineInvoicingEntity,
     pub pdf_document_id: Option<String>,
     pub xml_document_id: Option<String>,
+    /// set when voided by the zero-amount invoice policy of the tenant
+    pub suppressed_at: Option<NaiveDateTime>,
+    /// the billing period of the fees of a recurring invoice, when invoiced per cadence. All the fees if not set
+    #[from(~.map(| x | BillingPeriodEnum::from_row(x, @.billing_cadence_months)))]
+    pub billing_cadence: Option<BillingPeriodEnum>,
+    pub voided_at: Option<NaiveDateTime>,
+    pub void_reason: Option<String>,
+    /// the voided invoice corrected by this one
+    pub replaces_invoice_id: Option<Uuid>,
+    /// set when the invoice date was chosen at finalization, its revenue is then recognized at that date
+    pub manual_invoice_date: bool,
+    /// set when usage was backfilled into the period of the invoice after it was priced, until re-rated
+    pub usage_outdated_at: Option<NaiveDateTime>,
+    /// set when a user edited the draft, its lines are then no longer recomputed
+    pub manually_edited_at: Option<NaiveDateTime>,
+    pub manually_edited_by: Option<Uuid>,
+    /// the channel of the last delivery attempt, none when only issued to the invoicing provider
+    #[from(~.map(| x | x.into()))]
+    pub delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+    #[from(~.map(| x | x.into()))]
+    pub delivery_status: Option<InvoiceDeliveryStatusEnum>,
+    pub delivered_at: Option<NaiveDateTime>,
+    pub hosted_invoice_url: Option<String>,
+    pub external_pdf_url: Option<String>,
+}
+
+/// The pages of the invoice at its invoicing provider, once finalized there
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExternalInvoiceLinks {
+    pub hosted_invoice_url: Option<String>,
+    pub external_pdf_url: Option<String>,
+}
+
+impl ExternalInvoiceLinks {
+    pub fn is_empty(&self) -> bool {
+        self.hosted_invoice_url.is_none() && self.external_pdf_url.is_none()
+    }
 }
 
 #[derive(Debug, o2o)]
 #[owned_try_into(InvoiceRowNew, StoreErrorReport)]
 #[ghosts(
     id: {uuid::Uuid::now_v7()},
+    billing_cadence_months: {@.billing_cadence.as_ref().and_then(| x | x.custom_months())},
 )]
 pub struct InvoiceNew {
     #[into(~.into())]
@@ -131,6 +175,9 @@ pub struct InvoiceNew {
     StoreError::SerdeError("Failed to serialize seller_details".to_string(), e)
     }) ?)]
     pub seller_details: InlineInvoicingEntity,
+    #[into(~.clone().map(| x | x.into()))]
+    pub billing_cadence: Option<BillingPeriodEnum>,
+    pub replaces_invoice_id: Option<Uuid>,
 }
 
 #[derive(Debug, o2o)]
@@ -187,6 +234,9 @@ pub struct InlineCustomer {
     pub alias: Option<String>,
     pub vat_number: Option<String>,
     pub billing_address: Option<Address>,
+    /// terms at the time of invoicing, applied when the payment is reconciled
+    #[serde(default)]
+    pub early_payment_discount: Option<EarlyPaymentDiscount>,
     pub snapshot_at: NaiveDateTime,
 }
 
@@ -196,9 +246,19 @@ pub struct InlineInvoicingEntity {
     pub legal_name: String,
     pub vat_number: Option<String>,
     pub address: Address,
+    /// registrations displayed for the customer of the invoice, resolved at finalization
+    #[serde(default)]
+    pub tax_registrations: Vec<InlineTaxRegistration>,
     pub snapshot_at: NaiveDateTime,
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InlineTaxRegistration {
+    pub registration_type: TaxRegistrationTypeEnum,
+    pub jurisdiction: String,
+    pub registration_number: String,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq)]
 pub struct InvoiceWithCustomer {
     pub invoice: Invoice,
@@ -235,6 +295,87 @@ impl TryFrom<DetailedInvoiceRow> for DetailedInvoice {
     }
 }
 
+impl Invoice {
+    /// A draft of the same period and lines, correcting this invoice once voided.
+    /// The lines are recomputed at finalization
+    pub fn to_replacement(&self) -> InvoiceNew {
+        InvoiceNew {
+            status: InvoiceStatusEnum::Draft,
+            external_status: None,
+            tenant_id: self.tenant_id,
+            customer_id: self.customer_id,
+            subscription_id: self.subscription_id,
+            currency: self.currency.clone(),
+            external_invoice_id: None,
+            invoice_number: "draft".to_string(),
+            invoicing_provider: self.invoicing_provider.clone(),
+            line_items: self.line_items.clone(),
+            issued: false,
+            issue_attempts: 0,
+            last_issue_attempt_at: None,
+            last_issue_error: None,
+            data_updated_at: None,
+            invoice_date: self.invoice_date,
+            plan_version_id: self.plan_version_id,
+            invoice_type: self.invoice_type.clone(),
+            finalized_at: None,
+            subtotal: self.subtotal,
+            subtotal_recurring: self.subtotal_recurring,
+            tax_rate: self.tax_rate,
+            tax_amount: self.tax_amount,
+            total: self.total,
+            amount_due: self.amount_due,
+            net_terms: self.net_terms,
+            reference: self.reference.clone(),
+            memo: self.memo.clone(),
+            local_id: LocalId::generate_for(IdType::Invoice),
+            due_at: self.due_at,
+            plan_name: self.plan_name.clone(),
+            customer_details: self.customer_details.clone(),
+            seller_details: self.seller_details.clone(),
+            billing_cadence: self.billing_cadence.clone(),
+            replaces_invoice_id: Some(self.id),
+        }
+    }
+}
+
+/// An invoice date set when finalizing a draft must be in the open accounting period, the current month up to today,
+/// and keep the invoice numbers of the invoicing entity in date order
+pub fn validate_manual_invoice_date(
+    invoice_date: NaiveDate,
+    today: NaiveDate,
+    last_finalized_invoice_date: Option<NaiveDate>,
+) -> Result<(), StoreError> {
+    if invoice_date > today {
+        return Err(StoreError::InvalidArgument(
+            "The invoice date cannot be in the future".to_string(),
+        ));
+    }
+
+    if invoice_date < start_of_month(today) {
+        return Err(StoreError::InvalidArgument(format!(
+            "The invoice date {} is outside of the open accounting period, starting {}",
+            invoice_date,
+            start_of_month(today)
+        )));
+    }
+
+    match last_finalized_invoice_date {
+        Some(last) if invoice_date < last => Err(StoreError::InvalidArgument(format!(
+            "The invoice date {} is before the one of the last finalized invoice, {}",
+            invoice_date, last
+        ))),
+        _ => Ok(()),
+    }
+}
+
+/// A voided invoice, and its replacement draft when reissued
+#[derive(Debug, Clone)]
+pub struct VoidedInvoice {
+    pub invoice: DetailedInvoice,
+    pub replacement: Option<DetailedInvoice>,
+}
+
 pub struct InvoiceTotalsParams<'a> {
     pub line_items: &'a Vec<LineItem>,
     pub subscription_applied_coupons: &'a Vec<AppliedCouponDetailed>,
@@ -1,9 +1,13 @@
+use chrono::NaiveDateTime;
 use o2o::o2o;
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::Address;
-use diesel_models::invoicing_entities::{InvoicingEntityRow, InvoicingEntityRowPatch};
+use crate::domain::enums::TaxRegistrationTypeEnum;
+use crate::domain::{Address, InlineTaxRegistration};
+use diesel_models::invoicing_entities::{
+    InvoicingEntityRow, InvoicingEntityRowPatch, InvoicingEntityTaxRegistrationRow,
+};
 
 #[derive(Serialize, Deserialize, o2o)]
 #[map_owned(InvoicingEntityRow)]
@@ -93,3 +97,106 @@ pub struct InvoicingEntityPatch {
     pub vat_number: Option<String>,
     pub country: Option<String>,
 }
+
+/// A registration number of the invoicing entity with a tax authority (VAT, GST, ...)
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct TaxRegistration {
+    pub id: Uuid,
+    pub invoicing_entity_id: Uuid,
+    pub registration_type: TaxRegistrationTypeEnum,
+    /// country code, or region (ex: EU) of the issuing authority
+    pub jurisdiction: String,
+    pub registration_number: String,
+    /// billing countries of the customers whose invoices display the number. Empty for all customers
+    pub customer_countries: Vec<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl TaxRegistration {
+    pub fn is_displayed_for(&self, customer_country: Option<&str>) -> bool {
+        if self.customer_countries.is_empty() {
+            return true;
+        }
+
+        customer_country.is_some_and(|country| {
+            self.customer_countries
+                .iter()
+                .any(|c| c.eq_ignore_ascii_case(country))
+        })
+    }
+
+    /// The registrations to print on the invoice of a customer billed in `customer_country`
+    pub fn displayed_for(
+        registrations: &[TaxRegistration],
+        customer_country: Option<&str>,
+    ) -> Vec<InlineTaxRegistration> {
+        registrations
+            .iter()
+            .filter(|r| r.is_displayed_for(customer_country))
+            .map(|r| InlineTaxRegistration {
+                registration_type: r.registration_type,
+                jurisdiction: r.jurisdiction.clone(),
+                registration_number: r.registration_number.clone(),
+            })
+            .collect()
+    }
+}
+
+impl From<InvoicingEntityTaxRegistrationRow> for TaxRegistration {
+    fn from(row: InvoicingEntityTaxRegistrationRow) -> Self {
+        TaxRegistration {
+            id: row.id,
+            invoicing_entity_id: row.invoicing_entity_id,
+            registration_type: row.registration_type.into(),
+            jurisdiction: row.jurisdiction,
+            registration_number: row.registration_number,
+            customer_countries: row.customer_countries.into_iter().flatten().collect(),
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct TaxRegistrationNew {
+    pub registration_type: TaxRegistrationTypeEnum,
+    pub jurisdiction: String,
+    pub registration_number: String,
+    pub customer_countries: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(customer_countries: Vec<&str>) -> TaxRegistration {
+        TaxRegistration {
+            id: Uuid::nil(),
+            invoicing_entity_id: Uuid::nil(),
+            registration_type: TaxRegistrationTypeEnum::Gst,
+            jurisdiction: "AU".to_string(),
+            registration_number: "51 824 753 556".to_string(),
+            customer_countries: customer_countries.into_iter().map(String::from).collect(),
+            created_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_registration_without_countries_is_always_displayed() {
+        let registration = registration(vec![]);
+
+        assert!(registration.is_displayed_for(Some("FR")));
+        assert!(registration.is_displayed_for(None));
+    }
+
+    #[test]
+    fn test_registration_displayed_for_listed_countries_only() {
+        let registrations = vec![registration(vec!["AU", "nz"])];
+
+        assert_eq!(
+            TaxRegistration::displayed_for(&registrations, Some("NZ")).len(),
+            1
+        );
+        assert!(TaxRegistration::displayed_for(&registrations, Some("US")).is_empty());
+        assert!(TaxRegistration::displayed_for(&registrations, None).is_empty());
+    }
+}
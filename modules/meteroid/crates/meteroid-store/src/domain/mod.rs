@@ -25,26 +25,66 @@ pub mod plans;
 pub mod price_components;
 pub mod tenants;
 
+pub mod accounting_exports;
 pub mod add_ons;
 pub mod adjustments;
 pub mod api_tokens;
+pub mod audit_logs;
 pub mod billable_metrics;
+pub mod billing_emails;
+pub mod billing_runs;
+pub mod collection_strategies;
+pub mod component_rules;
 pub mod configs;
 pub mod coupons;
+pub mod credit_limits;
+pub mod customer_blackout_windows;
+pub mod entitlements;
 pub mod enums;
 pub mod historical_rates;
+pub mod invoice_approvals;
+pub mod invoice_attachments;
+pub mod invoice_cadences;
+pub mod invoice_delivery;
+pub mod invoice_edits;
 pub mod invoice_lines;
+pub mod invoice_payments;
+pub mod invoice_watchdog;
 pub mod invoicing_entities;
 pub mod misc;
+pub mod mrr_movements;
+pub mod notification_bundles;
+pub mod onboarding;
 pub mod organizations;
 pub mod outbox;
+pub mod partners;
+pub mod payment_reminders;
+pub mod payment_terms;
+pub mod plan_catalog;
+pub mod plan_defaults;
+pub mod plan_version_diff;
+pub mod price_component_validation;
 pub mod product_families;
 pub mod products;
+pub mod rate_cards;
+pub mod reports;
 pub mod schedules;
+pub mod seat_increase_requests;
 pub mod stats;
 pub mod subscription_add_ons;
+pub mod subscription_component_history;
 pub mod subscription_components;
 pub mod subscription_coupons;
+pub mod subscription_migrations;
+pub mod subscription_pending_changes;
+pub mod subscription_phases;
 pub mod subscriptions;
+pub mod tenant_environments;
+pub mod tenant_member_roles;
+pub mod usage;
+pub mod usage_alert_rules;
+pub mod usage_alerts;
+pub mod usage_caps;
+pub mod usage_rerating;
 pub mod users;
 pub mod webhooks;
@@ -0,0 +1,139 @@
+use crate::domain::enums::{MrrMovementType, SubscriptionEventType};
+
+/// The single movement recorded for the events of a subscription on a given day
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConsolidatedMrrMovement {
+    pub movement_type: MrrMovementType,
+    pub net_mrr_change: i64,
+    pub description: String,
+}
+
+fn event_description(event_type: &SubscriptionEventType) -> &'static str {
+    match event_type {
+        SubscriptionEventType::Created => "Subscription created",
+        SubscriptionEventType::Activated => "Subscription activated",
+        SubscriptionEventType::Switch => "Switched plan",
+        SubscriptionEventType::Cancelled => "Subscription cancelled",
+        SubscriptionEventType::Reactivated => "Subscription reactivated",
+        SubscriptionEventType::Updated => "Subscription updated",
+    }
+}
+
+/// Merges the same-day events of a subscription into one movement.
+/// Ex: new business + expansion = new business, cancellation + reactivation = nothing
+pub fn consolidate_mrr_movements(
+    events: &[(SubscriptionEventType, Option<i64>)],
+) -> Option<ConsolidatedMrrMovement> {
+    let events: Vec<(&SubscriptionEventType, i64)> = events
+        .iter()
+        .filter_map(|(event_type, delta)| match delta {
+            None | Some(0) => None,
+            Some(d) => Some((event_type, *d)),
+        })
+        .collect();
+
+    let net_mrr_change: i64 = events.iter().map(|(_, d)| d).sum();
+
+    if net_mrr_change == 0 {
+        return None;
+    }
+
+    let has = |predicate: fn(&SubscriptionEventType) -> bool| {
+        events.iter().any(|(event_type, _)| predicate(event_type))
+    };
+
+    let is_new_business = has(|e| {
+        matches!(
+            e,
+            SubscriptionEventType::Created | SubscriptionEventType::Activated
+        )
+    });
+    let is_cancelled = has(|e| matches!(e, SubscriptionEventType::Cancelled));
+    let is_reactivated = has(|e| matches!(e, SubscriptionEventType::Reactivated));
+
+    let movement_type = match (is_new_business, is_cancelled, is_reactivated) {
+        (true, _, _) => MrrMovementType::NewBusiness,
+        (false, true, false) => MrrMovementType::Churn,
+        (false, false, true) => MrrMovementType::Reactivation,
+        _ if net_mrr_change > 0 => MrrMovementType::Expansion,
+        _ => MrrMovementType::Contraction,
+    };
+
+    let description = events
+        .iter()
+        .map(|(event_type, _)| event_description(event_type))
+        .collect::<Vec<_>>()
+        .join(" + ");
+
+    Some(ConsolidatedMrrMovement {
+        movement_type,
+        net_mrr_change,
+        description,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cancellation_and_reactivation_cancel_out() {
+        let movement = consolidate_mrr_movements(&[
+            (SubscriptionEventType::Cancelled, Some(-5000)),
+            (SubscriptionEventType::Reactivated, Some(5000)),
+        ]);
+
+        assert_eq!(movement, None);
+    }
+
+    #[test]
+    fn test_new_business_absorbs_same_day_changes() {
+        let movement = consolidate_mrr_movements(&[
+            (SubscriptionEventType::Activated, Some(5000)),
+            (SubscriptionEventType::Updated, Some(2000)),
+        ])
+        .unwrap();
+
+        assert_eq!(movement.movement_type, MrrMovementType::NewBusiness);
+        assert_eq!(movement.net_mrr_change, 7000);
+        assert_eq!(
+            movement.description,
+            "Subscription activated + Subscription updated"
+        );
+    }
+
+    #[test]
+    fn test_movement_type_from_remaining_events() {
+        let churn = consolidate_mrr_movements(&[
+            (SubscriptionEventType::Updated, Some(1000)),
+            (SubscriptionEventType::Cancelled, Some(-6000)),
+        ])
+        .unwrap();
+        assert_eq!(churn.movement_type, MrrMovementType::Churn);
+        assert_eq!(churn.net_mrr_change, -5000);
+
+        let contraction = consolidate_mrr_movements(&[
+            (SubscriptionEventType::Cancelled, Some(-5000)),
+            (SubscriptionEventType::Reactivated, Some(3000)),
+        ])
+        .unwrap();
+        assert_eq!(contraction.movement_type, MrrMovementType::Contraction);
+
+        let reactivation =
+            consolidate_mrr_movements(&[(SubscriptionEventType::Reactivated, Some(5000))]).unwrap();
+        assert_eq!(reactivation.movement_type, MrrMovementType::Reactivation);
+        assert_eq!(reactivation.description, "Subscription reactivated");
+    }
+
+    #[test]
+    fn test_events_without_delta_are_ignored() {
+        let movement = consolidate_mrr_movements(&[
+            (SubscriptionEventType::Created, None),
+            (SubscriptionEventType::Switch, Some(-1500)),
+        ])
+        .unwrap();
+
+        assert_eq!(movement.movement_type, MrrMovementType::Contraction);
+        assert_eq!(movement.description, "Switched plan");
+    }
+}
@@ -0,0 +1,672 @@
+use std::collections::{BTreeMap, HashMap};
+
+use chrono::NaiveDateTime;
+use diesel_models::notification_bundles::{NotificationBundleRow, NotificationBundleRowNew};
+use uuid::Uuid;
+
+use crate::domain::enums::NotificationNamespaceEnum;
+use crate::domain::enums::NotificationNamespaceEnum::{Email, Pdf, Portal};
+use crate::errors::StoreError;
+
+/// Locale of the built-in messages, and last fallback of the tenant bundles
+pub const DEFAULT_LOCALE: &str = "en-US";
+
+const MAX_TEMPLATE_LENGTH: usize = 5000;
+
+/// A customer-facing message that a tenant can localize
+#[derive(Debug)]
+pub struct MessageDefinition {
+    pub namespace: NotificationNamespaceEnum,
+    pub key: &'static str,
+    /// the variables the template may use, as `{$name}`
+    pub variables: &'static [&'static str],
+    /// templates shipped with meteroid by locale, the default locale first
+    pub built_in: &'static [(&'static str, &'static str)],
+}
+
+const REMINDER_VARIABLES: &[&str] = &[
+    "customer_name",
+    "invoice_number",
+    "amount_due",
+    "due_date",
+    "days",
+];
+
+const fn message(
+    namespace: NotificationNamespaceEnum,
+    key: &'static str,
+    variables: &'static [&'static str],
+    built_in: &'static [(&'static str, &'static str)],
+) -> MessageDefinition {
+    MessageDefinition {
+        namespace,
+        key,
+        variables,
+        built_in,
+    }
+}
+
+/// The PDF messages mirror the fluent bundles of the invoice renderer, that only applies the tenant ones
+pub static MESSAGE_DEFINITIONS: &[MessageDefinition] = &[
+    message(
+        Email,
+        "payment-reminder-subject",
+        &["invoice_number"],
+        &[(DEFAULT_LOCALE, "Payment reminder for invoice {$invoice_number}")],
+    ),
+    message(
+        Email,
+        "payment-reminder-before-due",
+        REMINDER_VARIABLES,
+        &[(DEFAULT_LOCALE, "Hello {$customer_name}, invoice {$invoice_number} of {$amount_due} is due in {$days} days, on {$due_date}.")],
+    ),
+    message(
+        Email,
+        "payment-reminder-on-due",
+        REMINDER_VARIABLES,
+        &[(DEFAULT_LOCALE, "Hello {$customer_name}, invoice {$invoice_number} of {$amount_due} is due today.")],
+    ),
+    message(
+        Email,
+        "payment-reminder-overdue",
+        REMINDER_VARIABLES,
+        &[(DEFAULT_LOCALE, "Hello {$customer_name}, invoice {$invoice_number} of {$amount_due} was due on {$due_date} and is {$days} days overdue.")],
+    ),
+    message(Portal, "portal-title", &[], &[(DEFAULT_LOCALE, "Billing portal")]),
+    message(Portal, "portal-invoices", &[], &[(DEFAULT_LOCALE, "Invoices")]),
+    message(Portal, "portal-subscriptions", &[], &[(DEFAULT_LOCALE, "Subscriptions")]),
+    message(Portal, "portal-payment-methods", &[], &[(DEFAULT_LOCALE, "Payment methods")]),
+    message(
+        Portal,
+        "portal-pay-invoice",
+        &["amount_due"],
+        &[(DEFAULT_LOCALE, "Pay {$amount_due}")],
+    ),
+    message(Portal, "portal-download-invoice", &[], &[(DEFAULT_LOCALE, "Download invoice")]),
+    message(Pdf, "invoice-title", &[], &[(DEFAULT_LOCALE, "Invoice"), ("fr-FR", "Facture")]),
+    message(
+        Pdf,
+        "invoice-number",
+        &["invoice_number"],
+        &[
+            (DEFAULT_LOCALE, "Invoice n°{$invoice_number}"),
+            ("fr-FR", "Facture n°{$invoice_number}"),
+        ],
+    ),
+    message(
+        Pdf,
+        "company-logo-alt",
+        &[],
+        &[(DEFAULT_LOCALE, "Company Logo"), ("fr-FR", "Logo de l'entreprise")],
+    ),
+    message(
+        Pdf,
+        "issue-date",
+        &[],
+        &[(DEFAULT_LOCALE, "Issue Date:"), ("fr-FR", "Date d'émission")],
+    ),
+    message(Pdf, "amount-due", &[], &[(DEFAULT_LOCALE, "Amount Due"), ("fr-FR", "Montant dû")]),
+    message(
+        Pdf,
+        "due-date",
+        &[],
+        &[(DEFAULT_LOCALE, "Due Date:"), ("fr-FR", "Date d'échéance")],
+    ),
+    message(Pdf, "bill-from", &[], &[(DEFAULT_LOCALE, "Bill From"), ("fr-FR", "Emetteur")]),
+    message(Pdf, "bill-to", &[], &[(DEFAULT_LOCALE, "Bill To"), ("fr-FR", "Client")]),
+    message(Pdf, "invoice-lines", &[], &[(DEFAULT_LOCALE, "Invoice Lines"), ("fr-FR", "Détails")]),
+    message(Pdf, "description", &[], &[(DEFAULT_LOCALE, "Description"), ("fr-FR", "Produit")]),
+    message(Pdf, "quantity", &[], &[(DEFAULT_LOCALE, "Quantity"), ("fr-FR", "Quantité")]),
+    message(
+        Pdf,
+        "unit-price",
+        &[],
+        &[(DEFAULT_LOCALE, "Unit Price"), ("fr-FR", "Prix unitaire HT")],
+    ),
+    message(Pdf, "tax-rate", &[], &[(DEFAULT_LOCALE, "Tax Rate"), ("fr-FR", "Taux de TVA")]),
+    message(Pdf, "tax", &[], &[(DEFAULT_LOCALE, "Tax"), ("fr-FR", "TVA")]),
+    message(Pdf, "amount", &[], &[(DEFAULT_LOCALE, "Amount"), ("fr-FR", "Total HT")]),
+    message(Pdf, "subtotal", &[], &[(DEFAULT_LOCALE, "Subtotal"), ("fr-FR", "Sous-total")]),
+    message(Pdf, "total-due", &[], &[(DEFAULT_LOCALE, "Total Due"), ("fr-FR", "Total dû")]),
+    message(
+        Pdf,
+        "legal-info",
+        &[],
+        &[(DEFAULT_LOCALE, "Legal Information"), ("fr-FR", "Informations légales")],
+    ),
+    message(
+        Pdf,
+        "vat-exempt-legal",
+        &[],
+        &[
+            (DEFAULT_LOCALE, "Tax not applicable"),
+            ("fr-FR", "TVA non applicable - art. 259-1 du CGI"),
+        ],
+    ),
+    message(
+        Pdf,
+        "exchange-rate-info",
+        &["date", "equality", "amount_converted"],
+        &[
+            (DEFAULT_LOCALE, "Exchange rate on {$date}:  {$equality} | Total amount converted = {$amount_converted}"),
+            ("fr-FR", "Taux de change au {$date}:  {$equality} | Montant total converti = {$amount_converted}"),
+        ],
+    ),
+];
+
+pub fn message_definitions(
+    namespace: NotificationNamespaceEnum,
+) -> impl Iterator<Item = &'static MessageDefinition> {
+    MESSAGE_DEFINITIONS
+        .iter()
+        .filter(move |d| d.namespace == namespace)
+}
+
+fn find_definition(
+    namespace: NotificationNamespaceEnum,
+    key: &str,
+) -> Option<&'static MessageDefinition> {
+    message_definitions(namespace).find(|d| d.key == key)
+}
+
+enum Segment<'a> {
+    Text(&'a str),
+    Variable(&'a str),
+}
+
+/// Splits a template into its text and its `{$name}` placeholders
+fn parse_template(template: &str) -> Result<Vec<Segment>, String> {
+    let mut segments = vec![];
+    let mut rest = template;
+
+    while let Some(start) = rest.find(['{', '}']) {
+        if rest[start..].starts_with('}') {
+            return Err("unexpected }".to_string());
+        }
+        if start > 0 {
+            segments.push(Segment::Text(&rest[..start]));
+        }
+
+        let after = &rest[start + 1..];
+        let end = after.find('}').ok_or_else(|| "unclosed {".to_string())?;
+        let name = after[..end]
+            .trim()
+            .strip_prefix('$')
+            .ok_or_else(|| format!("{{{}}} is not a variable, use {{$name}}", &after[..end]))?;
+
+        if name.is_empty()
+            || !name
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-')
+        {
+            return Err(format!("invalid variable name {}", name));
+        }
+
+        segments.push(Segment::Variable(name));
+        rest = &after[end + 1..];
+    }
+
+    if !rest.is_empty() {
+        segments.push(Segment::Text(rest));
+    }
+
+    Ok(segments)
+}
+
+/// Replaces the `{$name}` placeholders of a template, the unknown variables are left empty
+pub fn render_template(template: &str, variables: &HashMap<&str, String>) -> String {
+    match parse_template(template) {
+        Ok(segments) => segments
+            .into_iter()
+            .map(|segment| match segment {
+                Segment::Text(text) => text,
+                Segment::Variable(name) => variables.get(name).map_or("", |v| v.as_str()),
+            })
+            .collect(),
+        Err(_) => template.to_string(),
+    }
+}
+
+/// Checks a template against the variables of its message
+pub fn validate_template(definition: &MessageDefinition, template: &str) -> Result<(), StoreError> {
+    let invalid = |reason: String| {
+        StoreError::InvalidArgument(format!("message {}: {}", definition.key, reason))
+    };
+
+    if template.trim().is_empty() {
+        return Err(invalid("the template is empty".to_string()));
+    }
+    if template.len() > MAX_TEMPLATE_LENGTH {
+        return Err(invalid(format!(
+            "the template is longer than {} characters",
+            MAX_TEMPLATE_LENGTH
+        )));
+    }
+
+    for segment in parse_template(template).map_err(invalid)? {
+        if let Segment::Variable(name) = segment {
+            if !definition.variables.contains(&name) {
+                return Err(invalid(if definition.variables.is_empty() {
+                    format!("unknown variable {}, the message has no variables", name)
+                } else {
+                    format!(
+                        "unknown variable {}, expected one of {}",
+                        name,
+                        definition.variables.join(", ")
+                    )
+                }));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// A language (`fr`), optionally with a region (`fr-CA`, `es-419`)
+pub fn validate_locale(locale: &str) -> Result<(), StoreError> {
+    let (language, region) = match locale.split_once('-') {
+        Some((language, region)) => (language, Some(region)),
+        None => (locale, None),
+    };
+
+    let valid_language =
+        (2..=3).contains(&language.len()) && language.chars().all(|c| c.is_ascii_lowercase());
+    let valid_region = region.map_or(true, |r| {
+        (r.len() == 2 && r.chars().all(|c| c.is_ascii_uppercase()))
+            || (r.len() == 3 && r.chars().all(|c| c.is_ascii_digit()))
+    });
+
+    if !valid_language || !valid_region {
+        return Err(StoreError::InvalidArgument(format!(
+            "invalid locale {}, expected a language with an optional region, ex: fr or fr-CA",
+            locale
+        )));
+    }
+
+    Ok(())
+}
+
+fn language_of(locale: &str) -> &str {
+    locale.split('-').next().unwrap_or(locale)
+}
+
+/// The messages of a tenant for a namespace and locale
+#[derive(Clone, Debug)]
+pub struct NotificationBundle {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub namespace: NotificationNamespaceEnum,
+    pub locale: String,
+    /// templates by message key, the missing messages fall back to the other locales
+    pub messages: BTreeMap<String, String>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: NaiveDateTime,
+}
+
+impl TryFrom<NotificationBundleRow> for NotificationBundle {
+    type Error = StoreError;
+
+    fn try_from(row: NotificationBundleRow) -> Result<Self, Self::Error> {
+        let messages = serde_json::from_value(row.messages).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to deserialize notification bundle messages".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(NotificationBundle {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            namespace: row.namespace.into(),
+            locale: row.locale,
+            messages,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct NotificationBundleNew {
+    pub tenant_id: Uuid,
+    pub namespace: NotificationNamespaceEnum,
+    pub locale: String,
+    pub messages: BTreeMap<String, String>,
+    pub created_by: Uuid,
+}
+
+impl NotificationBundleNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        validate_locale(&self.locale)?;
+
+        for (key, template) in &self.messages {
+            let definition = find_definition(self.namespace, key).ok_or_else(|| {
+                StoreError::InvalidArgument(format!(
+                    "unknown message {} in namespace {:?}",
+                    key, self.namespace
+                ))
+            })?;
+
+            validate_template(definition, template)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl TryFrom<NotificationBundleNew> for NotificationBundleRowNew {
+    type Error = StoreError;
+
+    fn try_from(bundle: NotificationBundleNew) -> Result<Self, Self::Error> {
+        let messages = serde_json::to_value(&bundle.messages).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to serialize notification bundle messages".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(NotificationBundleRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: bundle.tenant_id,
+            namespace: bundle.namespace.into(),
+            locale: bundle.locale,
+            messages,
+            created_by: bundle.created_by,
+        })
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum MessageSource {
+    /// a bundle of the tenant, possibly of a fallback locale
+    Tenant {
+        locale: String,
+    },
+    BuiltIn {
+        locale: String,
+    },
+}
+
+#[derive(Clone, Debug)]
+pub struct LocalizedMessage {
+    pub key: String,
+    pub template: String,
+    pub variables: Vec<String>,
+    pub source: MessageSource,
+}
+
+/// All the messages of a namespace, resolved for a locale
+#[derive(Clone, Debug)]
+pub struct LocalizedMessages {
+    pub namespace: NotificationNamespaceEnum,
+    pub locale: String,
+    pub messages: Vec<LocalizedMessage>,
+}
+
+impl LocalizedMessages {
+    /// Each message is taken from the first of:
+    /// - the tenant bundle of the locale, then of its language
+    /// - the built-in message of the language
+    /// - the tenant bundle of the default locale, then of its language
+    /// - the built-in message of the default locale
+    pub fn resolve(
+        namespace: NotificationNamespaceEnum,
+        locale: &str,
+        bundles: &[NotificationBundle],
+    ) -> Self {
+        let tenant_message = |key: &str, locale: &str| {
+            bundles
+                .iter()
+                .filter(|b| b.namespace == namespace && b.locale == locale)
+                .find_map(|b| b.messages.get(key))
+                .map(|template| {
+                    (
+                        template.clone(),
+                        MessageSource::Tenant {
+                            locale: locale.to_string(),
+                        },
+                    )
+                })
+        };
+
+        let language = language_of(locale);
+        let default_language = language_of(DEFAULT_LOCALE);
+
+        let messages = message_definitions(namespace)
+            .map(|definition| {
+                let built_in = |pred: &dyn Fn(&str) -> bool| {
+                    definition
+                        .built_in
+                        .iter()
+                        .find(|(l, _)| pred(l))
+                        .map(|(l, template)| {
+                            (
+                                template.to_string(),
+                                MessageSource::BuiltIn {
+                                    locale: l.to_string(),
+                                },
+                            )
+                        })
+                };
+
+                let (template, source) = tenant_message(definition.key, locale)
+                    .or_else(|| tenant_message(definition.key, language))
+                    .or_else(|| built_in(&|l| l == locale))
+                    .or_else(|| built_in(&|l| language_of(l) == language))
+                    .or_else(|| tenant_message(definition.key, DEFAULT_LOCALE))
+                    .or_else(|| tenant_message(definition.key, default_language))
+                    .or_else(|| built_in(&|_| true))
+                    .unwrap_or_default();
+
+                LocalizedMessage {
+                    key: definition.key.to_string(),
+                    template,
+                    variables: definition.variables.iter().map(|v| v.to_string()).collect(),
+                    source,
+                }
+            })
+            .collect();
+
+        LocalizedMessages {
+            namespace,
+            locale: locale.to_string(),
+            messages,
+        }
+    }
+
+    pub fn get(&self, key: &str) -> Option<&LocalizedMessage> {
+        self.messages.iter().find(|m| m.key == key)
+    }
+
+    pub fn render(&self, key: &str, variables: &HashMap<&str, String>) -> Option<String> {
+        self.get(key)
+            .map(|message| render_template(&message.template, variables))
+    }
+
+    /// The templates that the tenant defined, by key
+    pub fn tenant_templates(&self) -> HashMap<String, String> {
+        self.messages
+            .iter()
+            .filter(|m| matches!(m.source, MessageSource::Tenant { .. }))
+            .map(|m| (m.key.clone(), m.template.clone()))
+            .collect()
+    }
+}
+
+impl Default for MessageSource {
+    fn default() -> Self {
+        MessageSource::BuiltIn {
+            locale: DEFAULT_LOCALE.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn bundle(
+        namespace: NotificationNamespaceEnum,
+        locale: &str,
+        messages: &[(&str, &str)],
+    ) -> NotificationBundle {
+        NotificationBundle {
+            id: Uuid::nil(),
+            tenant_id: Uuid::nil(),
+            namespace,
+            locale: locale.to_string(),
+            messages: messages
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+            updated_at: NaiveDateTime::default(),
+        }
+    }
+
+    fn new(
+        namespace: NotificationNamespaceEnum,
+        locale: &str,
+        key: &str,
+        template: &str,
+    ) -> NotificationBundleNew {
+        NotificationBundleNew {
+            tenant_id: Uuid::nil(),
+            namespace,
+            locale: locale.to_string(),
+            messages: BTreeMap::from([(key.to_string(), template.to_string())]),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_render_template() {
+        let variables = HashMap::from([("invoice_number", "INV-1".to_string())]);
+
+        assert_eq!(
+            render_template("Invoice {$invoice_number} {$missing}!", &variables),
+            "Invoice INV-1 !"
+        );
+        assert_eq!(render_template("no variables", &variables), "no variables");
+    }
+
+    #[test]
+    fn test_validate_bundle() {
+        assert!(
+            new(Pdf, "de-DE", "invoice-number", "Rechnung {$invoice_number}")
+                .validate()
+                .is_ok()
+        );
+        assert!(new(
+            Email,
+            "es-419",
+            "payment-reminder-subject",
+            "Factura {$invoice_number}"
+        )
+        .validate()
+        .is_ok());
+
+        // unknown variable, key, namespace of the key
+        assert!(new(Pdf, "de", "invoice-number", "Rechnung {$number}")
+            .validate()
+            .is_err());
+        assert!(new(Pdf, "de", "invoice-footer", "Danke")
+            .validate()
+            .is_err());
+        assert!(new(Portal, "de", "invoice-title", "Rechnung")
+            .validate()
+            .is_err());
+
+        // syntax
+        for template in [
+            "Rechnung {invoice_number}",
+            "Rechnung {$invoice_number",
+            "}",
+            " ",
+        ] {
+            assert!(new(Pdf, "de", "invoice-number", template)
+                .validate()
+                .is_err());
+        }
+
+        // locale
+        for locale in ["", "DE", "de_DE", "de-de", "deutsch"] {
+            assert!(new(Pdf, locale, "invoice-title", "Rechnung")
+                .validate()
+                .is_err());
+        }
+    }
+
+    #[test]
+    fn test_resolve_fallbacks() {
+        let bundles = vec![
+            bundle(Pdf, "fr-CA", &[("invoice-title", "Facture (CA)")]),
+            bundle(Pdf, "fr", &[("tax", "Taxes")]),
+            bundle(
+                Pdf,
+                "en-US",
+                &[("subtotal", "Sub-total"), ("tax", "Sales tax")],
+            ),
+            bundle(Email, "fr-CA", &[("payment-reminder-subject", "Rappel")]),
+        ];
+
+        let resolved = LocalizedMessages::resolve(Pdf, "fr-CA", &bundles);
+        let source = |key| resolved.get(key).unwrap().source.clone();
+        let template = |key| resolved.get(key).unwrap().template.clone();
+
+        assert_eq!(resolved.messages.len(), message_definitions(Pdf).count());
+        assert_eq!(template("invoice-title"), "Facture (CA)");
+        assert_eq!(
+            source("invoice-title"),
+            MessageSource::Tenant {
+                locale: "fr-CA".to_string()
+            }
+        );
+        assert_eq!(template("tax"), "Taxes");
+        // built-in french is preferred to the tenant default locale
+        assert_eq!(template("subtotal"), "Sous-total");
+        assert_eq!(
+            source("subtotal"),
+            MessageSource::BuiltIn {
+                locale: "fr-FR".to_string()
+            }
+        );
+
+        assert_eq!(
+            resolved.tenant_templates(),
+            HashMap::from([
+                ("invoice-title".to_string(), "Facture (CA)".to_string()),
+                ("tax".to_string(), "Taxes".to_string()),
+            ])
+        );
+
+        // no built-in german, the tenant default locale comes before the built-in default
+        let resolved = LocalizedMessages::resolve(Pdf, "de-DE", &bundles);
+        assert_eq!(resolved.get("subtotal").unwrap().template, "Sub-total");
+        assert_eq!(resolved.get("invoice-title").unwrap().template, "Invoice");
+        assert_eq!(
+            resolved.get("invoice-title").unwrap().source,
+            MessageSource::BuiltIn {
+                locale: DEFAULT_LOCALE.to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_built_in_messages_are_valid() {
+        for definition in MESSAGE_DEFINITIONS {
+            assert_eq!(
+                definition.built_in[0].0, DEFAULT_LOCALE,
+                "{}",
+                definition.key
+            );
+            for (locale, template) in definition.built_in {
+                validate_locale(locale).unwrap();
+                validate_template(definition, template).unwrap();
+            }
+        }
+    }
+}
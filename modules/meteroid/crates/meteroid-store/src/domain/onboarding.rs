@@ -0,0 +1,196 @@
+use crate::domain::enums::OnboardingStepEnum;
+use crate::errors::StoreError;
+use chrono::NaiveDateTime;
+use diesel_models::onboarding_steps::{OnboardingDetectedRow, OnboardingStepRow};
+use uuid::Uuid;
+
+impl OnboardingStepEnum {
+    /// Ordered as presented in the setup wizard
+    pub const ALL: [OnboardingStepEnum; 5] = [
+        OnboardingStepEnum::InvoicingEntity,
+        OnboardingStepEnum::PaymentProvider,
+        OnboardingStepEnum::BillableMetric,
+        OnboardingStepEnum::Plan,
+        OnboardingStepEnum::Customer,
+    ];
+
+    /// Steps that must be completed before this one can be marked as completed
+    pub fn prerequisites(&self) -> &'static [OnboardingStepEnum] {
+        match self {
+            OnboardingStepEnum::InvoicingEntity => &[],
+            OnboardingStepEnum::PaymentProvider => &[OnboardingStepEnum::InvoicingEntity],
+            OnboardingStepEnum::BillableMetric => &[],
+            OnboardingStepEnum::Plan => &[],
+            OnboardingStepEnum::Customer => &[OnboardingStepEnum::InvoicingEntity],
+        }
+    }
+
+    fn is_detected(&self, detected: &OnboardingDetectedRow) -> bool {
+        match self {
+            OnboardingStepEnum::InvoicingEntity => detected.invoicing_entity_configured,
+            OnboardingStepEnum::PaymentProvider => detected.payment_provider_connected,
+            OnboardingStepEnum::BillableMetric => detected.has_billable_metric,
+            OnboardingStepEnum::Plan => detected.has_active_plan,
+            OnboardingStepEnum::Customer => detected.has_customer,
+        }
+    }
+
+    pub fn ensure_detected(&self, detected: &OnboardingDetectedRow) -> Result<(), StoreError> {
+        if self.is_detected(detected) {
+            Ok(())
+        } else {
+            Err(StoreError::OnboardingIncomplete(*self))
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct OnboardingStepStatus {
+    pub step: OnboardingStepEnum,
+    pub completed: bool,
+    /// Set only when the step was explicitly completed via the api
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+#[derive(Clone, Debug)]
+pub struct OnboardingStatus {
+    pub tenant_id: Uuid,
+    pub steps: Vec<OnboardingStepStatus>,
+}
+
+impl OnboardingStatus {
+    /// A step is completed either when it was explicitly marked as such, or when the related data already exists
+    pub fn from_rows(
+        tenant_id: Uuid,
+        rows: Vec<OnboardingStepRow>,
+        detected: OnboardingDetectedRow,
+    ) -> Self {
+        let steps = OnboardingStepEnum::ALL
+            .iter()
+            .map(|step| {
+                let completed_at = rows
+                    .iter()
+                    .find(|r| OnboardingStepEnum::from(r.step.clone()) == *step)
+                    .map(|r| r.completed_at);
+
+                OnboardingStepStatus {
+                    step: *step,
+                    completed: completed_at.is_some() || step.is_detected(&detected),
+                    completed_at,
+                }
+            })
+            .collect();
+
+        OnboardingStatus { tenant_id, steps }
+    }
+
+    pub fn is_step_completed(&self, step: OnboardingStepEnum) -> bool {
+        self.steps.iter().any(|s| s.step == step && s.completed)
+    }
+
+    pub fn is_completed(&self) -> bool {
+        self.steps.iter().all(|s| s.completed)
+    }
+
+    pub fn next_step(&self) -> Option<OnboardingStepEnum> {
+        self.steps.iter().find(|s| !s.completed).map(|s| s.step)
+    }
+
+    pub fn ensure_completed(&self, step: OnboardingStepEnum) -> Result<(), StoreError> {
+        if self.is_step_completed(step) {
+            Ok(())
+        } else {
+            Err(StoreError::OnboardingIncomplete(step))
+        }
+    }
+
+    pub fn ensure_prerequisites(&self, step: OnboardingStepEnum) -> Result<(), StoreError> {
+        step.prerequisites()
+            .iter()
+            .try_for_each(|prerequisite| self.ensure_completed(*prerequisite))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use diesel_models::enums::OnboardingStepEnum as DieselOnboardingStepEnum;
+
+    #[test]
+    fn test_status_merges_detected_and_explicit_steps() {
+        let tenant_id = Uuid::now_v7();
+        let completed_at = chrono::Utc::now().naive_utc();
+
+        let rows = vec![OnboardingStepRow {
+            tenant_id,
+            step: DieselOnboardingStepEnum::InvoicingEntity,
+            completed_at,
+            completed_by: None,
+        }];
+
+        let detected = OnboardingDetectedRow {
+            has_billable_metric: true,
+            ..Default::default()
+        };
+
+        let status = OnboardingStatus::from_rows(tenant_id, rows, detected);
+
+        assert!(status.is_step_completed(OnboardingStepEnum::InvoicingEntity));
+        assert!(status.is_step_completed(OnboardingStepEnum::BillableMetric));
+        assert!(!status.is_step_completed(OnboardingStepEnum::PaymentProvider));
+        assert!(!status.is_completed());
+        assert_eq!(
+            status.next_step(),
+            Some(OnboardingStepEnum::PaymentProvider)
+        );
+        assert!(status
+            .ensure_prerequisites(OnboardingStepEnum::Customer)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_ensure_detected_ignores_explicit_completion() {
+        let tenant_id = Uuid::now_v7();
+
+        let rows = vec![OnboardingStepRow {
+            tenant_id,
+            step: DieselOnboardingStepEnum::InvoicingEntity,
+            completed_at: chrono::Utc::now().naive_utc(),
+            completed_by: None,
+        }];
+
+        let status = OnboardingStatus::from_rows(tenant_id, rows, Default::default());
+
+        assert!(status.is_step_completed(OnboardingStepEnum::InvoicingEntity));
+        assert!(OnboardingStepEnum::InvoicingEntity
+            .ensure_detected(&Default::default())
+            .is_err());
+
+        let detected = OnboardingDetectedRow {
+            invoicing_entity_configured: true,
+            ..Default::default()
+        };
+
+        assert!(OnboardingStepEnum::InvoicingEntity
+            .ensure_detected(&detected)
+            .is_ok());
+    }
+
+    #[test]
+    fn test_prerequisites_are_enforced() {
+        let tenant_id = Uuid::now_v7();
+
+        let status = OnboardingStatus::from_rows(tenant_id, vec![], Default::default());
+
+        assert_eq!(
+            status.next_step(),
+            Some(OnboardingStepEnum::InvoicingEntity)
+        );
+        assert!(status
+            .ensure_prerequisites(OnboardingStepEnum::PaymentProvider)
+            .is_err());
+        assert!(status
+            .ensure_prerequisites(OnboardingStepEnum::BillableMetric)
+            .is_ok());
+    }
+}
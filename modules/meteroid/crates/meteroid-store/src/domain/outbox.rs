@@ -15,6 +15,18 @@ pub enum OutboxEvent {
     InvoiceFinalized,
     #[serde(rename = "invoice.pdf.requested")]
     InvoicePdfRequested,
+    #[serde(rename = "usage_alert.email.requested")]
+    UsageAlertEmailRequested,
+    #[serde(rename = "usage_alert_rule.email.requested")]
+    UsageAlertRuleEmailRequested,
+    #[serde(rename = "invoice.payment_reminder.email.requested")]
+    InvoicePaymentReminderEmailRequested,
+    #[serde(rename = "invoice.delivery.email.requested")]
+    InvoiceDeliveryEmailRequested,
+    #[serde(rename = "billing_run.summary.email.requested")]
+    BillingRunSummaryEmailRequested,
+    #[serde(rename = "api_token.expiry.email.requested")]
+    ApiTokenExpiryEmailRequested,
     // TODO meter created
 }
 
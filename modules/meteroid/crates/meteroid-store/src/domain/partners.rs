@@ -0,0 +1,414 @@
+use std::collections::BTreeMap;
+
+use chrono::{Months, NaiveDate, NaiveDateTime};
+use diesel_models::partners::{
+    PartnerAttributionRow, PartnerAttributionRowNew, PartnerCommissionRow, PartnerRow,
+};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::errors::StoreError;
+
+/// A reseller or referrer, paid a share of the revenue of the customers it brought
+#[derive(Clone, Debug)]
+pub struct Partner {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub rev_share_percent: Decimal,
+    pub created_at: NaiveDateTime,
+    pub archived_at: Option<NaiveDateTime>,
+}
+
+impl From<PartnerRow> for Partner {
+    fn from(row: PartnerRow) -> Self {
+        Partner {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            name: row.name,
+            rev_share_percent: row.rev_share_percent,
+            created_at: row.created_at,
+            archived_at: row.archived_at,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PartnerNew {
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub rev_share_percent: Decimal,
+}
+
+impl PartnerNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.name.trim().is_empty() {
+            return Err(StoreError::InvalidArgument(
+                "the partner name is required".to_string(),
+            ));
+        }
+        validate_rev_share(self.rev_share_percent)
+    }
+}
+
+pub fn validate_rev_share(percent: Decimal) -> Result<(), StoreError> {
+    if percent <= Decimal::ZERO || percent > Decimal::ONE_HUNDRED {
+        return Err(StoreError::InvalidArgument(
+            "the revenue share must be between 0 and 100 percent".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+/// What is attributed to the partner. A subscription attribution overrides the one of its customer
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PartnerAttributionTarget {
+    Customer(Uuid),
+    Subscription(Uuid),
+}
+
+impl PartnerAttributionTarget {
+    pub fn customer_id(&self) -> Option<Uuid> {
+        match self {
+            PartnerAttributionTarget::Customer(id) => Some(*id),
+            PartnerAttributionTarget::Subscription(_) => None,
+        }
+    }
+
+    pub fn subscription_id(&self) -> Option<Uuid> {
+        match self {
+            PartnerAttributionTarget::Customer(_) => None,
+            PartnerAttributionTarget::Subscription(id) => Some(*id),
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PartnerAttribution {
+    pub id: Uuid,
+    pub partner_id: Uuid,
+    pub target: PartnerAttributionTarget,
+    /// overrides the rev share of the partner
+    pub rev_share_percent: Option<Decimal>,
+    pub created_at: NaiveDateTime,
+}
+
+impl TryFrom<PartnerAttributionRow> for PartnerAttribution {
+    type Error = StoreError;
+
+    fn try_from(row: PartnerAttributionRow) -> Result<Self, Self::Error> {
+        let target = match (row.customer_id, row.subscription_id) {
+            (Some(customer_id), None) => PartnerAttributionTarget::Customer(customer_id),
+            (None, Some(subscription_id)) => {
+                PartnerAttributionTarget::Subscription(subscription_id)
+            }
+            _ => {
+                return Err(StoreError::InvalidArgument(
+                    "a partner attribution targets either a customer or a subscription".to_string(),
+                ))
+            }
+        };
+
+        Ok(PartnerAttribution {
+            id: row.id,
+            partner_id: row.partner_id,
+            target,
+            rev_share_percent: row.rev_share_percent,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PartnerAttributionNew {
+    pub tenant_id: Uuid,
+    pub partner_id: Uuid,
+    pub target: PartnerAttributionTarget,
+    pub rev_share_percent: Option<Decimal>,
+}
+
+impl PartnerAttributionNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        self.rev_share_percent
+            .map(validate_rev_share)
+            .transpose()
+            .map(|_| ())
+    }
+}
+
+impl From<PartnerAttributionNew> for PartnerAttributionRowNew {
+    fn from(attribution: PartnerAttributionNew) -> Self {
+        PartnerAttributionRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: attribution.tenant_id,
+            partner_id: attribution.partner_id,
+            customer_id: attribution.target.customer_id(),
+            subscription_id: attribution.target.subscription_id(),
+            rev_share_percent: attribution.rev_share_percent,
+        }
+    }
+}
+
+/// The partner and rev share of an invoice, from the attributions of its subscription and customer
+pub fn resolve_attribution(
+    attributions: &[(PartnerAttribution, Partner)],
+) -> Option<(Uuid, Decimal)> {
+    attributions
+        .iter()
+        .find(|(a, _)| matches!(a.target, PartnerAttributionTarget::Subscription(_)))
+        .or_else(|| attributions.first())
+        .map(|(attribution, partner)| {
+            (
+                partner.id,
+                attribution
+                    .rev_share_percent
+                    .unwrap_or(partner.rev_share_percent),
+            )
+        })
+}
+
+/// The partner share of an amount in cents, rounded to the cent
+pub fn commission_amount(amount: i64, rev_share_percent: Decimal) -> i64 {
+    (Decimal::from(amount) * rev_share_percent / Decimal::ONE_HUNDRED)
+        .round()
+        .to_i64()
+        .unwrap_or(0)
+}
+
+/// The share of a paid invoice owed to a partner
+#[derive(Clone, Debug)]
+pub struct PartnerCommission {
+    pub partner_id: Uuid,
+    pub invoice_id: Uuid,
+    pub customer_id: Uuid,
+    pub currency: String,
+    /// the invoice total, excluding taxes
+    pub invoice_amount: i64,
+    pub rev_share_percent: Decimal,
+    pub amount: i64,
+    pub paid_at: NaiveDateTime,
+}
+
+impl From<PartnerCommissionRow> for PartnerCommission {
+    fn from(row: PartnerCommissionRow) -> Self {
+        PartnerCommission {
+            partner_id: row.partner_id,
+            invoice_id: row.invoice_id,
+            customer_id: row.customer_id,
+            currency: row.currency,
+            invoice_amount: row.invoice_amount,
+            rev_share_percent: row.rev_share_percent,
+            amount: row.amount,
+            paid_at: row.paid_at,
+        }
+    }
+}
+
+/// The first day of the month, and of the following one
+pub fn month_bounds(year: i32, month: u32) -> Result<(NaiveDate, NaiveDate), StoreError> {
+    let start = NaiveDate::from_ymd_opt(year, month, 1)
+        .ok_or_else(|| StoreError::InvalidArgument(format!("invalid month {year}-{month}")))?;
+    let end = start
+        .checked_add_months(Months::new(1))
+        .ok_or_else(|| StoreError::InvalidArgument(format!("invalid month {year}-{month}")))?;
+
+    Ok((start, end))
+}
+
+/// The commissions of a partner in a currency, over the month
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PartnerPayoutLine {
+    pub partner_id: Uuid,
+    pub partner_name: String,
+    pub currency: String,
+    pub invoice_count: u32,
+    pub invoiced_amount: i64,
+    pub commission_amount: i64,
+}
+
+/// What is owed to the partners for the invoices paid in the month
+#[derive(Clone, Debug)]
+pub struct PartnerPayoutReport {
+    /// first day of the month
+    pub month: NaiveDate,
+    pub lines: Vec<PartnerPayoutLine>,
+}
+
+/// Sums the commissions per partner and currency, ordered by partner name
+pub fn payout_lines(
+    commissions: &[PartnerCommission],
+    partners: &[Partner],
+) -> Vec<PartnerPayoutLine> {
+    let mut lines: BTreeMap<(String, Uuid, String), PartnerPayoutLine> = BTreeMap::new();
+
+    for commission in commissions {
+        let partner_name = partners
+            .iter()
+            .find(|p| p.id == commission.partner_id)
+            .map(|p| p.name.clone())
+            .unwrap_or_default();
+
+        let line = lines
+            .entry((
+                partner_name.clone(),
+                commission.partner_id,
+                commission.currency.clone(),
+            ))
+            .or_insert_with(|| PartnerPayoutLine {
+                partner_id: commission.partner_id,
+                partner_name,
+                currency: commission.currency.clone(),
+                invoice_count: 0,
+                invoiced_amount: 0,
+                commission_amount: 0,
+            });
+
+        line.invoice_count += 1;
+        line.invoiced_amount += commission.invoice_amount;
+        line.commission_amount += commission.amount;
+    }
+
+    lines.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn partner(name: &str, rev_share_percent: Decimal) -> Partner {
+        Partner {
+            id: Uuid::now_v7(),
+            tenant_id: Uuid::nil(),
+            name: name.to_string(),
+            rev_share_percent,
+            created_at: NaiveDateTime::default(),
+            archived_at: None,
+        }
+    }
+
+    fn attribution(
+        partner: &Partner,
+        target: PartnerAttributionTarget,
+        rev_share_percent: Option<Decimal>,
+    ) -> (PartnerAttribution, Partner) {
+        (
+            PartnerAttribution {
+                id: Uuid::now_v7(),
+                partner_id: partner.id,
+                target,
+                rev_share_percent,
+                created_at: NaiveDateTime::default(),
+            },
+            partner.clone(),
+        )
+    }
+
+    fn commission(partner: &Partner, currency: &str, invoice_amount: i64) -> PartnerCommission {
+        PartnerCommission {
+            partner_id: partner.id,
+            invoice_id: Uuid::now_v7(),
+            customer_id: Uuid::nil(),
+            currency: currency.to_string(),
+            invoice_amount,
+            rev_share_percent: partner.rev_share_percent,
+            amount: commission_amount(invoice_amount, partner.rev_share_percent),
+            paid_at: NaiveDateTime::default(),
+        }
+    }
+
+    #[test]
+    fn test_resolve_attribution() {
+        let reseller = partner("Reseller", dec!(20));
+        let referrer = partner("Referrer", dec!(10));
+
+        assert_eq!(resolve_attribution(&[]), None);
+
+        let customer = attribution(
+            &reseller,
+            PartnerAttributionTarget::Customer(Uuid::now_v7()),
+            None,
+        );
+        assert_eq!(
+            resolve_attribution(&[customer.clone()]),
+            Some((reseller.id, dec!(20)))
+        );
+
+        // the subscription attribution wins, with its own rev share
+        let subscription = attribution(
+            &referrer,
+            PartnerAttributionTarget::Subscription(Uuid::now_v7()),
+            Some(dec!(5)),
+        );
+        assert_eq!(
+            resolve_attribution(&[customer, subscription]),
+            Some((referrer.id, dec!(5)))
+        );
+    }
+
+    #[test]
+    fn test_commission_amount() {
+        assert_eq!(commission_amount(10000, dec!(15)), 1500);
+        assert_eq!(commission_amount(999, dec!(12.5)), 125);
+        assert_eq!(commission_amount(0, dec!(50)), 0);
+    }
+
+    #[test]
+    fn test_payout_lines() {
+        let reseller = partner("Reseller", dec!(20));
+        let agency = partner("Agency", dec!(10));
+
+        let lines = payout_lines(
+            &[
+                commission(&reseller, "EUR", 10000),
+                commission(&agency, "USD", 5000),
+                commission(&reseller, "EUR", 2500),
+                commission(&reseller, "USD", 1000),
+            ],
+            &[reseller.clone(), agency.clone()],
+        );
+
+        assert_eq!(
+            lines,
+            vec![
+                PartnerPayoutLine {
+                    partner_id: agency.id,
+                    partner_name: "Agency".to_string(),
+                    currency: "USD".to_string(),
+                    invoice_count: 1,
+                    invoiced_amount: 5000,
+                    commission_amount: 500,
+                },
+                PartnerPayoutLine {
+                    partner_id: reseller.id,
+                    partner_name: "Reseller".to_string(),
+                    currency: "EUR".to_string(),
+                    invoice_count: 2,
+                    invoiced_amount: 12500,
+                    commission_amount: 2500,
+                },
+                PartnerPayoutLine {
+                    partner_id: reseller.id,
+                    partner_name: "Reseller".to_string(),
+                    currency: "USD".to_string(),
+                    invoice_count: 1,
+                    invoiced_amount: 1000,
+                    commission_amount: 200,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_month_bounds() {
+        assert_eq!(
+            month_bounds(2024, 12).unwrap(),
+            (
+                NaiveDate::from_ymd_opt(2024, 12, 1).unwrap(),
+                NaiveDate::from_ymd_opt(2025, 1, 1).unwrap()
+            )
+        );
+        assert!(month_bounds(2024, 13).is_err());
+    }
+}
@@ -0,0 +1,301 @@
+use std::cmp::Ordering;
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel_models::payment_reminders::{
+    DuePaymentReminderRow, PaymentReminderLogRow, PaymentReminderScheduleRow,
+    PaymentReminderScheduleRowNew,
+};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::constants::{Countries, Currencies};
+use crate::domain::enums::PaymentReminderChannelEnum;
+use crate::domain::notification_bundles::LocalizedMessages;
+use crate::errors::StoreError;
+
+/// Earliest reminder, in days before the due date
+pub const MAX_DAYS_BEFORE_DUE: i32 = 60;
+/// Latest reminder, in days after the due date
+pub const MAX_DAYS_AFTER_DUE: i32 = 180;
+/// Reminders that could not be sent within this delay are dropped, instead of reaching the customer late
+pub const MAX_REMINDER_DELAY_DAYS: u32 = 3;
+
+/// When a reminder is sent, relatively to the due date of the invoice
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PaymentReminderTiming {
+    BeforeDue,
+    OnDue,
+    Overdue,
+}
+
+impl PaymentReminderTiming {
+    pub fn from_offset(offset_days: i32) -> Self {
+        match offset_days.cmp(&0) {
+            Ordering::Less => PaymentReminderTiming::BeforeDue,
+            Ordering::Equal => PaymentReminderTiming::OnDue,
+            Ordering::Greater => PaymentReminderTiming::Overdue,
+        }
+    }
+}
+
+/// A reminder sent by the tenant for each unpaid invoice, `offset_days` from its due date (negative before)
+#[derive(Clone, Debug)]
+pub struct PaymentReminderSchedule {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub enabled: bool,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl From<PaymentReminderScheduleRow> for PaymentReminderSchedule {
+    fn from(row: PaymentReminderScheduleRow) -> Self {
+        PaymentReminderSchedule {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            offset_days: row.offset_days,
+            channel: row.channel.into(),
+            enabled: row.enabled,
+            created_at: row.created_at,
+            created_by: row.created_by,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PaymentReminderScheduleNew {
+    pub tenant_id: Uuid,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub enabled: bool,
+    pub created_by: Uuid,
+}
+
+impl PaymentReminderScheduleNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if !(-MAX_DAYS_BEFORE_DUE..=MAX_DAYS_AFTER_DUE).contains(&self.offset_days) {
+            return Err(StoreError::InvalidArgument(format!(
+                "reminders must be sent between {} days before and {} days after the due date",
+                MAX_DAYS_BEFORE_DUE, MAX_DAYS_AFTER_DUE
+            )));
+        }
+
+        Ok(())
+    }
+}
+
+impl From<PaymentReminderScheduleNew> for PaymentReminderScheduleRowNew {
+    fn from(schedule: PaymentReminderScheduleNew) -> Self {
+        PaymentReminderScheduleRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: schedule.tenant_id,
+            offset_days: schedule.offset_days,
+            channel: schedule.channel.into(),
+            enabled: schedule.enabled,
+            created_by: schedule.created_by,
+        }
+    }
+}
+
+/// A reminder sent for an invoice
+#[derive(Clone, Debug)]
+pub struct PaymentReminderLog {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub invoice_id: Uuid,
+    /// None once the schedule is deleted
+    pub schedule_id: Option<Uuid>,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    /// the email the reminder was sent to
+    pub recipient: Option<String>,
+    /// the due date of the invoice when the reminder was sent
+    pub due_at: NaiveDateTime,
+    pub sent_at: NaiveDateTime,
+}
+
+impl From<PaymentReminderLogRow> for PaymentReminderLog {
+    fn from(row: PaymentReminderLogRow) -> Self {
+        PaymentReminderLog {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            invoice_id: row.invoice_id,
+            schedule_id: row.schedule_id,
+            offset_days: row.offset_days,
+            channel: row.channel.into(),
+            recipient: row.recipient,
+            due_at: row.due_at,
+            sent_at: row.sent_at,
+        }
+    }
+}
+
+/// A reminder to send for an unpaid invoice
+#[derive(Clone, Debug)]
+pub struct DuePaymentReminder {
+    pub schedule_id: Uuid,
+    pub tenant_id: Uuid,
+    pub offset_days: i32,
+    pub channel: PaymentReminderChannelEnum,
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    /// locale of the country of the invoicing entity
+    pub locale: String,
+    pub recipient: Option<String>,
+    pub currency: String,
+    pub amount_due: i64,
+    pub due_at: NaiveDateTime,
+    pub remind_at: NaiveDateTime,
+}
+
+impl From<DuePaymentReminderRow> for DuePaymentReminder {
+    fn from(row: DuePaymentReminderRow) -> Self {
+        DuePaymentReminder {
+            schedule_id: row.schedule_id,
+            tenant_id: row.tenant_id,
+            offset_days: row.offset_days,
+            channel: row.channel.into(),
+            invoice_id: row.invoice_id,
+            invoice_number: row.invoice_number,
+            customer_id: row.customer_id,
+            customer_name: row.customer_name,
+            locale: Countries::resolve_locale(&row.invoicing_entity_country).to_string(),
+            recipient: row.recipient,
+            currency: row.currency,
+            amount_due: row.amount_due,
+            due_at: row.due_at,
+            remind_at: row.remind_at,
+        }
+    }
+}
+
+impl DuePaymentReminder {
+    /// Subject and body of the reminder email, from the email messages resolved for its locale
+    pub fn render_email(&self, messages: &LocalizedMessages) -> (String, String) {
+        let precision = Currencies::resolve_currency_precision(&self.currency).unwrap_or(2);
+        let amount_due = format!(
+            "{} {}",
+            Decimal::new(self.amount_due, precision as u32),
+            self.currency
+        );
+
+        let variables = HashMap::from([
+            ("customer_name", self.customer_name.clone()),
+            ("invoice_number", self.invoice_number.clone()),
+            ("amount_due", amount_due),
+            ("due_date", self.due_at.format("%Y-%m-%d").to_string()),
+            ("days", self.offset_days.unsigned_abs().to_string()),
+        ]);
+
+        let body_key = match PaymentReminderTiming::from_offset(self.offset_days) {
+            PaymentReminderTiming::BeforeDue => "payment-reminder-before-due",
+            PaymentReminderTiming::OnDue => "payment-reminder-on-due",
+            PaymentReminderTiming::Overdue => "payment-reminder-overdue",
+        };
+
+        (
+            messages
+                .render("payment-reminder-subject", &variables)
+                .unwrap_or_default(),
+            messages.render(body_key, &variables).unwrap_or_default(),
+        )
+    }
+}
+
+/// Payload of the outbox entry requesting the reminder email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PaymentReminderEmailPayload {
+    pub payment_reminder_id: Uuid,
+    pub invoice_id: Uuid,
+    pub recipient: String,
+    pub customer_name: String,
+    pub invoice_number: String,
+    pub currency: String,
+    pub amount_due: i64,
+    pub due_at: NaiveDateTime,
+    pub timing: PaymentReminderTiming,
+    /// days before or after the due date
+    pub days: u32,
+    pub locale: String,
+    pub subject: String,
+    pub body: String,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::enums::NotificationNamespaceEnum;
+
+    fn schedule(offset_days: i32) -> PaymentReminderScheduleNew {
+        PaymentReminderScheduleNew {
+            tenant_id: Uuid::nil(),
+            offset_days,
+            channel: PaymentReminderChannelEnum::Email,
+            enabled: true,
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_timing() {
+        assert_eq!(
+            PaymentReminderTiming::from_offset(-3),
+            PaymentReminderTiming::BeforeDue
+        );
+        assert_eq!(
+            PaymentReminderTiming::from_offset(0),
+            PaymentReminderTiming::OnDue
+        );
+        assert_eq!(
+            PaymentReminderTiming::from_offset(7),
+            PaymentReminderTiming::Overdue
+        );
+    }
+
+    #[test]
+    fn test_render_email() {
+        let reminder = DuePaymentReminder {
+            schedule_id: Uuid::nil(),
+            tenant_id: Uuid::nil(),
+            offset_days: 5,
+            channel: PaymentReminderChannelEnum::Email,
+            invoice_id: Uuid::nil(),
+            invoice_number: "INV-42".to_string(),
+            customer_id: Uuid::nil(),
+            customer_name: "Acme".to_string(),
+            locale: "en-US".to_string(),
+            recipient: None,
+            currency: "EUR".to_string(),
+            amount_due: 12050,
+            due_at: NaiveDateTime::parse_from_str("2024-10-01 00:00:00", "%Y-%m-%d %H:%M:%S")
+                .unwrap(),
+            remind_at: NaiveDateTime::default(),
+        };
+
+        let messages = LocalizedMessages::resolve(NotificationNamespaceEnum::Email, "en-US", &[]);
+
+        let (subject, body) = reminder.render_email(&messages);
+
+        assert_eq!(subject, "Payment reminder for invoice INV-42");
+        assert_eq!(
+            body,
+            "Hello Acme, invoice INV-42 of 120.50 EUR was due on 2024-10-01 and is 5 days overdue."
+        );
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(schedule(-7).validate().is_ok());
+        assert!(schedule(0).validate().is_ok());
+        assert!(schedule(MAX_DAYS_AFTER_DUE).validate().is_ok());
+        assert!(schedule(-MAX_DAYS_BEFORE_DUE - 1).validate().is_err());
+        assert!(schedule(MAX_DAYS_AFTER_DUE + 1).validate().is_err());
+    }
+}
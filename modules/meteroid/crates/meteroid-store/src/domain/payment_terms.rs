@@ -0,0 +1,188 @@
+use chrono::{Datelike, NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
+use common_utils::date::NaiveDateExt;
+use diesel_models::tenants::TenantPaymentTermsRow;
+use serde::{Deserialize, Serialize};
+
+use crate::domain::enums::DueDatePolicyEnum;
+use crate::domain::Customer;
+use crate::errors::StoreError;
+use crate::utils::datetime::{end_of_month, start_of_day_utc};
+
+/// When an invoice is due, from its date and net terms
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct DueDatePolicy {
+    pub policy: DueDatePolicyEnum,
+    /// day of month of the fixed day policy, the last day of the shorter months
+    pub day: Option<u32>,
+}
+
+impl DueDatePolicy {
+    pub fn from_row(
+        policy: Option<diesel_models::enums::DueDatePolicyEnum>,
+        day: Option<i32>,
+    ) -> Option<Self> {
+        policy.map(|policy| DueDatePolicy {
+            policy: policy.into(),
+            day: day.map(|d| d as u32),
+        })
+    }
+
+    pub fn validate(&self) -> Result<(), StoreError> {
+        match (self.policy, self.day) {
+            (DueDatePolicyEnum::FixedDay, Some(1..=31)) => Ok(()),
+            (DueDatePolicyEnum::FixedDay, _) => Err(StoreError::InvalidArgument(
+                "the fixed day due date policy requires a day between 1 and 31".to_string(),
+            )),
+            (_, None) => Ok(()),
+            (_, Some(_)) => Err(StoreError::InvalidArgument(
+                "a due date day is only allowed with the fixed day policy".to_string(),
+            )),
+        }
+    }
+
+    pub fn due_date(&self, invoice_date: NaiveDate, net_terms: u32) -> NaiveDate {
+        let net_terms = chrono::Duration::days(net_terms as i64);
+
+        match (self.policy, self.day) {
+            (DueDatePolicyEnum::NetDays, _) => invoice_date + net_terms,
+            (DueDatePolicyEnum::EndOfMonth, _) => end_of_month(invoice_date) + net_terms,
+            (DueDatePolicyEnum::FixedDay, None) => invoice_date + net_terms,
+            (DueDatePolicyEnum::FixedDay, Some(day)) => {
+                let earliest = invoice_date + net_terms;
+                let in_month =
+                    |date: NaiveDate| date.with_day(day.min(date.days_in_month())).unwrap_or(date);
+
+                let candidate = in_month(earliest);
+                if candidate >= earliest {
+                    candidate
+                } else {
+                    in_month(end_of_month(earliest).succ_opt().unwrap_or(earliest))
+                }
+            }
+        }
+    }
+}
+
+/// Payment terms of the tenant, applied when the customer does not set them
+#[derive(Clone, Debug, Default)]
+pub struct TenantPaymentTerms {
+    pub default_net_terms: Option<u32>,
+    pub due_date_policy: DueDatePolicy,
+    /// the due dates start at midnight in this timezone
+    pub billing_timezone: Tz,
+}
+
+impl TenantPaymentTerms {
+    /// The UTC time at which an invoice is due, from its due date
+    pub fn due_at(&self, due_date: NaiveDate) -> NaiveDateTime {
+        start_of_day_utc(due_date, self.billing_timezone)
+    }
+}
+
+impl From<TenantPaymentTermsRow> for TenantPaymentTerms {
+    fn from(row: TenantPaymentTermsRow) -> Self {
+        TenantPaymentTerms {
+            default_net_terms: row.default_net_terms.map(|v| v as u32),
+            due_date_policy: DueDatePolicy {
+                policy: row.due_date_policy.into(),
+                day: row.due_date_day.map(|d| d as u32),
+            },
+            // the timezone is validated when set
+            billing_timezone: row.billing_timezone.parse().unwrap_or(Tz::UTC),
+        }
+    }
+}
+
+/// The payment terms of an invoice
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PaymentTerms {
+    pub net_terms: u32,
+    pub due_date_policy: DueDatePolicy,
+}
+
+impl PaymentTerms {
+    /// The net terms of the customer override the ones of the subscription, then of the tenant and of the invoicing entity.
+    /// The due date policy of the customer overrides the one of the tenant
+    pub fn resolve(
+        customer: &Customer,
+        subscription_net_terms: Option<u32>,
+        tenant: &TenantPaymentTerms,
+        invoicing_entity_net_terms: u32,
+    ) -> PaymentTerms {
+        PaymentTerms {
+            net_terms: customer
+                .net_terms_override
+                .or(subscription_net_terms)
+                .or(tenant.default_net_terms)
+                .unwrap_or(invoicing_entity_net_terms),
+            due_date_policy: customer.due_date_policy.unwrap_or(tenant.due_date_policy),
+        }
+    }
+
+    pub fn due_date(&self, invoice_date: NaiveDate) -> NaiveDate {
+        self.due_date_policy.due_date(invoice_date, self.net_terms)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn policy(policy: DueDatePolicyEnum, day: Option<u32>) -> DueDatePolicy {
+        DueDatePolicy { policy, day }
+    }
+
+    #[test]
+    fn test_net_days() {
+        let net_days = policy(DueDatePolicyEnum::NetDays, None);
+
+        assert_eq!(
+            net_days.due_date(date("2024-01-15"), 30),
+            date("2024-02-14")
+        );
+        assert_eq!(net_days.due_date(date("2024-01-15"), 0), date("2024-01-15"));
+    }
+
+    #[test]
+    fn test_end_of_month() {
+        let eom = policy(DueDatePolicyEnum::EndOfMonth, None);
+
+        assert_eq!(eom.due_date(date("2024-01-15"), 30), date("2024-03-01"));
+        assert_eq!(eom.due_date(date("2024-02-03"), 0), date("2024-02-29"));
+    }
+
+    #[test]
+    fn test_fixed_day() {
+        let tenth = policy(DueDatePolicyEnum::FixedDay, Some(10));
+
+        assert_eq!(tenth.due_date(date("2024-01-05"), 0), date("2024-01-10"));
+        assert_eq!(tenth.due_date(date("2024-01-05"), 5), date("2024-01-10"));
+        assert_eq!(tenth.due_date(date("2024-01-05"), 30), date("2024-02-10"));
+
+        // the last day of the shorter months
+        let last = policy(DueDatePolicyEnum::FixedDay, Some(31));
+        assert_eq!(last.due_date(date("2024-02-01"), 10), date("2024-02-29"));
+        assert_eq!(last.due_date(date("2024-04-15"), 0), date("2024-04-30"));
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(policy(DueDatePolicyEnum::FixedDay, Some(15))
+            .validate()
+            .is_ok());
+        assert!(policy(DueDatePolicyEnum::FixedDay, None)
+            .validate()
+            .is_err());
+        assert!(policy(DueDatePolicyEnum::FixedDay, Some(32))
+            .validate()
+            .is_err());
+        assert!(policy(DueDatePolicyEnum::NetDays, Some(15))
+            .validate()
+            .is_err());
+    }
+}
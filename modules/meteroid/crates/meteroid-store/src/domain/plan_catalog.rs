@@ -0,0 +1,174 @@
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::{ActionAfterTrialEnum, BillingPeriodEnum, PlanTypeEnum, TrialModeEnum};
+use crate::domain::price_components::FeeType;
+
+/// Version of the catalog document format, bumped on breaking changes
+pub const CATALOG_DOCUMENT_VERSION: u32 = 1;
+
+/// A portable export of the plans of a tenant, to be imported in another tenant or kept in git.
+/// Entities are identified by their external id, code or name. The ids of the exporting tenant are kept
+/// only to resolve the references of the fees, they are never reused on import
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogDocument {
+    pub version: u32,
+    pub exported_at: NaiveDateTime,
+    pub product_families: Vec<CatalogProductFamily>,
+    pub products: Vec<CatalogProduct>,
+    /// metrics referenced by the price components, they must exist in the importing tenant
+    pub billable_metrics: Vec<CatalogReference>,
+    /// rate cards referenced by the price components, they must exist in the importing tenant
+    pub rate_cards: Vec<CatalogReference>,
+    pub plans: Vec<CatalogPlan>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogProductFamily {
+    pub external_id: String,
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogProduct {
+    pub id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub product_family_external_id: String,
+}
+
+/// A metric by code or a rate card by name
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogReference {
+    pub id: Uuid,
+    pub key: String,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogPlan {
+    pub external_id: String,
+    pub name: String,
+    pub description: Option<String>,
+    pub plan_type: PlanTypeEnum,
+    pub product_family_external_id: String,
+    /// the last published version
+    pub version: CatalogPlanVersion,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogPlanVersion {
+    pub currency: String,
+    pub net_terms: i32,
+    pub period_start_day: Option<i16>,
+    pub billing_cycles: Option<i32>,
+    pub billing_periods: Vec<BillingPeriodEnum>,
+    pub trial: Option<CatalogPlanTrial>,
+    #[serde(default)]
+    pub tax_category: Option<String>,
+    pub price_components: Vec<CatalogPriceComponent>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogPlanTrial {
+    pub duration_days: Option<i32>,
+    pub trialing_plan_external_id: Option<String>,
+    pub downgrade_plan_external_id: Option<String>,
+    pub action_after_trial: Option<ActionAfterTrialEnum>,
+    pub is_free: bool,
+    #[serde(default)]
+    pub mode: TrialModeEnum,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CatalogPriceComponent {
+    pub name: String,
+    pub fee: FeeType,
+    pub product_id: Option<Uuid>,
+    pub billable_metric_id: Option<Uuid>,
+    pub rate_card_id: Option<Uuid>,
+}
+
+/// How an imported plan whose external id already exists in the tenant is handled
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum CatalogConflictStrategy {
+    /// nothing is imported
+    #[default]
+    Fail,
+    /// the existing plan is kept as is
+    Skip,
+    /// the imported version replaces the draft version of the existing plan, to be reviewed and published
+    NewDraftVersion,
+}
+
+#[derive(Clone, Debug)]
+pub struct CatalogImport {
+    pub document: CatalogDocument,
+    pub on_conflict: CatalogConflictStrategy,
+    /// only detects the conflicts and counts the entities that would be created
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CatalogConflictKind {
+    /// a plan with the same external id exists, resolved by the conflict strategy
+    PlanExists,
+    /// the external id of an archived plan cannot be reused
+    PlanArchived,
+    /// the existing plan has another type or product family
+    PlanMismatch,
+    MissingBillableMetric(String),
+    MissingRateCard(String),
+}
+
+impl CatalogConflictKind {
+    pub fn is_resolved_by(&self, strategy: CatalogConflictStrategy) -> bool {
+        matches!(self, CatalogConflictKind::PlanExists) && strategy != CatalogConflictStrategy::Fail
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CatalogConflict {
+    pub plan_external_id: String,
+    pub kind: CatalogConflictKind,
+}
+
+/// The entities created by an import, or that would be created when it is not applied
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatalogImportReport {
+    /// false for a dry run, or when a conflict is not resolved by the strategy
+    pub applied: bool,
+    pub conflicts: Vec<CatalogConflict>,
+    pub product_families: u32,
+    pub products: u32,
+    /// new plans, created with a draft version
+    pub plans: u32,
+    /// draft versions of the existing plans
+    pub plan_versions: u32,
+    /// external ids of the existing plans that were kept
+    pub skipped_plans: Vec<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_conflict_resolution() {
+        use CatalogConflictStrategy::*;
+
+        assert!(!CatalogConflictKind::PlanExists.is_resolved_by(Fail));
+        assert!(CatalogConflictKind::PlanExists.is_resolved_by(Skip));
+        assert!(CatalogConflictKind::PlanExists.is_resolved_by(NewDraftVersion));
+
+        for kind in [
+            CatalogConflictKind::PlanArchived,
+            CatalogConflictKind::PlanMismatch,
+            CatalogConflictKind::MissingBillableMetric("api_calls".to_string()),
+            CatalogConflictKind::MissingRateCard("standard".to_string()),
+        ] {
+            assert!(!kind.is_resolved_by(Skip));
+            assert!(!kind.is_resolved_by(NewDraftVersion));
+        }
+    }
+}
@@ -0,0 +1,211 @@
+use crate::domain::{PlanVersion, ProductFamilyDefaults};
+
+/// A setting of a plan version that can be inherited from its product family
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlanDefaultSetting {
+    Currency,
+    TrialDuration,
+    NetTerms,
+    TaxCategory,
+}
+
+impl PlanDefaultSetting {
+    /// The key stored in the inherited defaults of the plan versions
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PlanDefaultSetting::Currency => "currency",
+            PlanDefaultSetting::TrialDuration => "trial_duration",
+            PlanDefaultSetting::NetTerms => "net_terms",
+            PlanDefaultSetting::TaxCategory => "tax_category",
+        }
+    }
+}
+
+/// Where the effective value of a setting comes from
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConfigSource {
+    PlanVersion,
+    ProductFamily,
+    /// neither the plan nor its family sets it, ex: the currency of the tenant
+    Default,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConfigValue<T> {
+    pub value: T,
+    pub source: ConfigSource,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PlanVersionEffectiveConfig {
+    pub currency: ConfigValue<String>,
+    pub trial_duration_days: ConfigValue<Option<i32>>,
+    pub net_terms: ConfigValue<i32>,
+    pub tax_category: ConfigValue<Option<String>>,
+}
+
+/// The settings of a new plan version, with the unset ones taken from the family defaults then the tenant
+pub struct InheritedSettings {
+    pub currency: String,
+    pub trial_duration_days: Option<i32>,
+    pub net_terms: i32,
+    pub tax_category: Option<String>,
+    pub inherited: Vec<String>,
+}
+
+pub fn inherit_defaults(
+    currency: Option<String>,
+    trial_duration_days: Option<i32>,
+    net_terms: Option<i32>,
+    tax_category: Option<String>,
+    defaults: &ProductFamilyDefaults,
+    tenant_currency: String,
+) -> InheritedSettings {
+    let mut inherited = vec![];
+
+    let mut inherit = |setting: PlanDefaultSetting, is_set: bool| {
+        if !is_set {
+            inherited.push(setting.as_str().to_string());
+        }
+    };
+
+    inherit(PlanDefaultSetting::Currency, currency.is_some());
+    inherit(
+        PlanDefaultSetting::TrialDuration,
+        trial_duration_days.is_some(),
+    );
+    inherit(PlanDefaultSetting::NetTerms, net_terms.is_some());
+    inherit(PlanDefaultSetting::TaxCategory, tax_category.is_some());
+
+    InheritedSettings {
+        currency: currency
+            .or_else(|| defaults.currency.clone())
+            .unwrap_or(tenant_currency),
+        trial_duration_days: trial_duration_days.or(defaults.trial_duration_days),
+        net_terms: net_terms.or(defaults.net_terms).unwrap_or(0),
+        tax_category: tax_category.or_else(|| defaults.tax_category.clone()),
+        inherited,
+    }
+}
+
+/// The stored values of the version are the effective ones, the inherited ones being kept in sync with the family
+/// while the version is a draft. Only their source is resolved here
+pub fn resolve_effective_config(
+    version: &PlanVersion,
+    defaults: &ProductFamilyDefaults,
+) -> PlanVersionEffectiveConfig {
+    let source = |setting: PlanDefaultSetting, family_default_is_set: bool| {
+        if !version
+            .inherited_defaults
+            .iter()
+            .any(|s| s == setting.as_str())
+        {
+            ConfigSource::PlanVersion
+        } else if family_default_is_set {
+            ConfigSource::ProductFamily
+        } else {
+            ConfigSource::Default
+        }
+    };
+
+    PlanVersionEffectiveConfig {
+        currency: ConfigValue {
+            value: version.currency.clone(),
+            source: source(PlanDefaultSetting::Currency, defaults.currency.is_some()),
+        },
+        trial_duration_days: ConfigValue {
+            value: version.trial_duration_days,
+            source: source(
+                PlanDefaultSetting::TrialDuration,
+                defaults.trial_duration_days.is_some(),
+            ),
+        },
+        net_terms: ConfigValue {
+            value: version.net_terms,
+            source: source(PlanDefaultSetting::NetTerms, defaults.net_terms.is_some()),
+        },
+        tax_category: ConfigValue {
+            value: version.tax_category.clone(),
+            source: source(
+                PlanDefaultSetting::TaxCategory,
+                defaults.tax_category.is_some(),
+            ),
+        },
+    }
+}
+
+/// The inherited settings that remain once the given ones are set on the version
+pub fn remove_inherited(inherited: &[String], overridden: &[PlanDefaultSetting]) -> Vec<String> {
+    inherited
+        .iter()
+        .filter(|s| !overridden.iter().any(|o| o.as_str() == s.as_str()))
+        .cloned()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inherit_and_resolve() {
+        let defaults = ProductFamilyDefaults {
+            currency: Some("USD".to_string()),
+            trial_duration_days: Some(14),
+            net_terms: None,
+            tax_category: Some("saas".to_string()),
+        };
+
+        let settings = inherit_defaults(
+            None,
+            None,
+            None,
+            Some("digital".to_string()),
+            &defaults,
+            "EUR".to_string(),
+        );
+
+        assert_eq!(settings.currency, "USD");
+        assert_eq!(settings.trial_duration_days, Some(14));
+        assert_eq!(settings.net_terms, 0);
+        assert_eq!(settings.tax_category.as_deref(), Some("digital"));
+        assert_eq!(
+            settings.inherited,
+            vec!["currency", "trial_duration", "net_terms"]
+        );
+
+        let version = PlanVersion {
+            id: uuid::Uuid::nil(),
+            is_draft_version: true,
+            plan_id: uuid::Uuid::nil(),
+            version: 1,
+            tenant_id: uuid::Uuid::nil(),
+            period_start_day: None,
+            net_terms: settings.net_terms,
+            currency: settings.currency,
+            billing_cycles: None,
+            created_at: chrono::NaiveDateTime::default(),
+            created_by: uuid::Uuid::nil(),
+            billing_periods: vec![],
+            trialing_plan_id: None,
+            action_after_trial: None,
+            trial_is_free: false,
+            trial_mode: Default::default(),
+            downgrade_plan_id: None,
+            trial_duration_days: settings.trial_duration_days,
+            tax_category: settings.tax_category,
+            inherited_defaults: remove_inherited(
+                &settings.inherited,
+                &[PlanDefaultSetting::TrialDuration],
+            ),
+        };
+
+        let config = resolve_effective_config(&version, &defaults);
+
+        assert_eq!(config.currency.source, ConfigSource::ProductFamily);
+        assert_eq!(config.trial_duration_days.source, ConfigSource::PlanVersion);
+        assert_eq!(config.net_terms.source, ConfigSource::Default);
+        assert_eq!(config.tax_category.source, ConfigSource::PlanVersion);
+        assert_eq!(config.tax_category.value.as_deref(), Some("digital"));
+    }
+}
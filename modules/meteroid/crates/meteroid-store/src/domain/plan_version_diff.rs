@@ -0,0 +1,569 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+
+use itertools::Itertools;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::domain::price_components::{FeeType, MatrixDimension, TierRow, UsagePricingModel};
+use crate::domain::{PlanVersion, PriceComponent};
+
+/// Whether a changed field is a price, or another setting of the plan version or of the fee
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FieldChangeKind {
+    Price,
+    Setting,
+}
+
+/// A field whose value differs between two versions, None when it is not set in one of them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange {
+    /// path of the field, ex: `rates.Monthly` or `tiers.1000.rate`
+    pub field: String,
+    pub kind: FieldChangeKind,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ComponentChangeType {
+    Added,
+    Removed,
+    Changed,
+}
+
+/// A price component that differs between two versions. Unchanged components are not part of the diff
+#[derive(Clone, Debug)]
+pub struct ComponentDiff {
+    pub change_type: ComponentChangeType,
+    pub name: String,
+    pub from_component_id: Option<Uuid>,
+    pub to_component_id: Option<Uuid>,
+    /// the fields of an added component have no `before`, those of a removed one no `after`
+    pub changes: Vec<FieldChange>,
+}
+
+impl ComponentDiff {
+    pub fn has_price_changes(&self) -> bool {
+        self.changes
+            .iter()
+            .any(|c| c.kind == FieldChangeKind::Price)
+    }
+}
+
+#[derive(Clone, Debug)]
+pub struct PlanVersionDiff {
+    pub from_version_id: Uuid,
+    pub to_version_id: Uuid,
+    /// changes of the version settings: currency, billing periods, trial ..
+    pub changes: Vec<FieldChange>,
+    pub components: Vec<ComponentDiff>,
+}
+
+impl PlanVersionDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty() && self.components.is_empty()
+    }
+}
+
+/// Compares two versions of a plan.
+/// Components are cloned with new ids in each version, so they are matched by name, then by product
+pub fn diff_plan_versions(
+    from: &PlanVersion,
+    from_components: &[PriceComponent],
+    to: &PlanVersion,
+    to_components: &[PriceComponent],
+) -> PlanVersionDiff {
+    let mut pairs: Vec<(Option<&PriceComponent>, Option<&PriceComponent>)> = vec![];
+    let mut unmatched_to: Vec<&PriceComponent> = to_components.iter().collect();
+    let mut unmatched_from: Vec<&PriceComponent> = vec![];
+
+    for component in from_components {
+        match unmatched_to.iter().position(|c| c.name == component.name) {
+            Some(idx) => pairs.push((Some(component), Some(unmatched_to.remove(idx)))),
+            None => unmatched_from.push(component),
+        }
+    }
+
+    // a renamed component keeps its product
+    for component in unmatched_from {
+        let same_product = component.product_item_id.and_then(|product_id| {
+            unmatched_to
+                .iter()
+                .position(|c| c.product_item_id == Some(product_id))
+        });
+
+        match same_product {
+            Some(idx) => pairs.push((Some(component), Some(unmatched_to.remove(idx)))),
+            None => pairs.push((Some(component), None)),
+        }
+    }
+
+    pairs.extend(unmatched_to.into_iter().map(|c| (None, Some(c))));
+
+    let components = pairs
+        .into_iter()
+        .filter_map(|(before, after)| diff_components(before, after))
+        .collect();
+
+    PlanVersionDiff {
+        from_version_id: from.id,
+        to_version_id: to.id,
+        changes: diff_fields(&version_fields(from), &version_fields(to)),
+        components,
+    }
+}
+
+fn diff_components(
+    before: Option<&PriceComponent>,
+    after: Option<&PriceComponent>,
+) -> Option<ComponentDiff> {
+    let fields = |c: Option<&PriceComponent>| {
+        c.map(|c| {
+            let mut fields = fee_fields(&c.fee);
+            fields.insert(
+                "name".to_string(),
+                (FieldChangeKind::Setting, c.name.clone()),
+            );
+            fields
+        })
+        .unwrap_or_default()
+    };
+
+    let changes = diff_fields(&fields(before), &fields(after));
+
+    let change_type = match (before, after) {
+        (Some(_), Some(_)) if changes.is_empty() => return None,
+        (Some(_), Some(_)) => ComponentChangeType::Changed,
+        (Some(_), None) => ComponentChangeType::Removed,
+        (None, Some(_)) => ComponentChangeType::Added,
+        (None, None) => return None,
+    };
+
+    Some(ComponentDiff {
+        change_type,
+        name: after.or(before).map(|c| c.name.clone()).unwrap_or_default(),
+        from_component_id: before.map(|c| c.id),
+        to_component_id: after.map(|c| c.id),
+        changes,
+    })
+}
+
+type Fields = BTreeMap<String, (FieldChangeKind, String)>;
+
+fn diff_fields(before: &Fields, after: &Fields) -> Vec<FieldChange> {
+    before
+        .keys()
+        .chain(after.keys())
+        .unique()
+        .sorted()
+        .filter_map(|field| {
+            let b = before.get(field);
+            let a = after.get(field);
+
+            if b.map(|(_, v)| v) == a.map(|(_, v)| v) {
+                return None;
+            }
+
+            Some(FieldChange {
+                field: field.clone(),
+                kind: a.or(b).map(|(k, _)| *k).unwrap_or(FieldChangeKind::Setting),
+                before: b.map(|(_, v)| v.clone()),
+                after: a.map(|(_, v)| v.clone()),
+            })
+        })
+        .collect()
+}
+
+fn version_fields(version: &PlanVersion) -> Fields {
+    let mut fields = Fields::new();
+    let mut setting = |field: &str, value: Option<String>| {
+        if let Some(value) = value {
+            fields.insert(field.to_string(), (FieldChangeKind::Setting, value));
+        }
+    };
+
+    setting("currency", Some(version.currency.clone()));
+    setting("net_terms", Some(version.net_terms.to_string()));
+    setting("tax_category", version.tax_category.clone());
+    setting(
+        "period_start_day",
+        version.period_start_day.map(|d| d.to_string()),
+    );
+    setting(
+        "billing_cycles",
+        version.billing_cycles.map(|c| c.to_string()),
+    );
+    setting(
+        "billing_periods",
+        Some(
+            version
+                .billing_periods
+                .iter()
+                .map(|p| format!("{:?}", p))
+                .join(", "),
+        ),
+    );
+    setting(
+        "trial.duration_days",
+        version.trial_duration_days.map(|d| d.to_string()),
+    );
+    setting(
+        "trial.trialing_plan_id",
+        version.trialing_plan_id.map(|id| id.to_string()),
+    );
+    setting(
+        "trial.downgrade_plan_id",
+        version.downgrade_plan_id.map(|id| id.to_string()),
+    );
+    setting(
+        "trial.action_after_trial",
+        version.action_after_trial.as_ref().map(debug),
+    );
+    setting("trial.is_free", Some(version.trial_is_free.to_string()));
+    setting("trial.mode", Some(debug(&version.trial_mode)));
+
+    fields
+}
+
+/// Flattens a fee into comparable fields. Rates, tiers and thresholds are keyed by their term or first unit,
+/// so that a change of price is reported on the row instead of shifting the following ones
+fn fee_fields(fee: &FeeType) -> Fields {
+    let mut fields = Fields::new();
+    let mut price = |field: String, value: &Decimal| {
+        fields.insert(field, (FieldChangeKind::Price, decimal(value)));
+    };
+
+    let mut settings: Vec<(String, String)> = vec![];
+
+    match fee {
+        FeeType::Rate { rates } => {
+            settings.push(("fee_type".to_string(), "Rate".to_string()));
+            for rate in rates {
+                price(format!("rates.{:?}", rate.term), &rate.price);
+            }
+        }
+        FeeType::Slot {
+            rates,
+            slot_unit_name,
+            upgrade_policy,
+            downgrade_policy,
+            minimum_count,
+            quota,
+        } => {
+            settings.push(("fee_type".to_string(), "Slot".to_string()));
+            for rate in rates {
+                price(format!("rates.{:?}", rate.term), &rate.price);
+            }
+            settings.push(("slot_unit_name".to_string(), slot_unit_name.clone()));
+            settings.push(("upgrade_policy".to_string(), debug(upgrade_policy)));
+            settings.push(("downgrade_policy".to_string(), debug(downgrade_policy)));
+            if let Some(minimum_count) = minimum_count {
+                settings.push(("minimum_count".to_string(), minimum_count.to_string()));
+            }
+            if let Some(quota) = quota {
+                settings.push(("quota".to_string(), quota.to_string()));
+            }
+        }
+        FeeType::Capacity {
+            metric_id,
+            thresholds,
+            cap,
+        } => {
+            settings.push(("fee_type".to_string(), "Capacity".to_string()));
+            settings.push(("metric_id".to_string(), metric_id.to_string()));
+            for threshold in thresholds {
+                let key = format!("thresholds.{}", threshold.included_amount);
+                price(format!("{}.price", key), &threshold.price);
+                price(
+                    format!("{}.per_unit_overage", key),
+                    &threshold.per_unit_overage,
+                );
+            }
+            if let Some(cap) = cap {
+                price("cap".to_string(), cap);
+            }
+        }
+        FeeType::Usage {
+            metric_id,
+            pricing,
+            cap,
+            included,
+        } => {
+            settings.push(("fee_type".to_string(), "Usage".to_string()));
+            settings.push(("metric_id".to_string(), metric_id.to_string()));
+            if let Some(included) = included {
+                settings.push(("included".to_string(), included.to_string()));
+            }
+
+            match pricing {
+                UsagePricingModel::PerUnit { rate } => {
+                    settings.push(("pricing_model".to_string(), "PerUnit".to_string()));
+                    price("rate".to_string(), rate);
+                }
+                UsagePricingModel::Tiered { tiers, block_size }
+                | UsagePricingModel::Volume { tiers, block_size } => {
+                    let model = match pricing {
+                        UsagePricingModel::Tiered { .. } => "Tiered",
+                        _ => "Volume",
+                    };
+                    settings.push(("pricing_model".to_string(), model.to_string()));
+                    if let Some(block_size) = block_size {
+                        settings.push(("block_size".to_string(), block_size.to_string()));
+                    }
+                    for tier in tiers {
+                        tier_fields(tier, &mut price);
+                    }
+                }
+                UsagePricingModel::Package { block_size, rate } => {
+                    settings.push(("pricing_model".to_string(), "Package".to_string()));
+                    settings.push(("block_size".to_string(), block_size.to_string()));
+                    price("rate".to_string(), rate);
+                }
+                UsagePricingModel::Matrix { rates } => {
+                    settings.push(("pricing_model".to_string(), "Matrix".to_string()));
+                    for row in rates {
+                        let key = std::iter::once(&row.dimension1)
+                            .chain(row.dimension2.as_ref())
+                            .map(dimension)
+                            .join(",");
+                        price(format!("rates.{}", key), &row.per_unit_price);
+                    }
+                }
+            }
+            if let Some(cap) = cap {
+                price("cap".to_string(), cap);
+            }
+        }
+        FeeType::ExtraRecurring {
+            unit_price,
+            quantity,
+            billing_type,
+            cadence,
+        } => {
+            settings.push(("fee_type".to_string(), "ExtraRecurring".to_string()));
+            price("unit_price".to_string(), unit_price);
+            settings.push(("quantity".to_string(), quantity.to_string()));
+            settings.push(("billing_type".to_string(), debug(billing_type)));
+            settings.push(("cadence".to_string(), debug(cadence)));
+        }
+        FeeType::OneTime {
+            unit_price,
+            quantity,
+        } => {
+            settings.push(("fee_type".to_string(), "OneTime".to_string()));
+            price("unit_price".to_string(), unit_price);
+            settings.push(("quantity".to_string(), quantity.to_string()));
+        }
+        FeeType::Percent {
+            metric_id,
+            percentage,
+            minimum,
+            maximum,
+        } => {
+            settings.push(("fee_type".to_string(), "Percent".to_string()));
+            settings.push(("metric_id".to_string(), metric_id.to_string()));
+            price("percentage".to_string(), percentage);
+            if let Some(minimum) = minimum {
+                price("minimum".to_string(), minimum);
+            }
+            if let Some(maximum) = maximum {
+                price("maximum".to_string(), maximum);
+            }
+        }
+    }
+
+    fields.extend(
+        settings
+            .into_iter()
+            .map(|(field, value)| (field, (FieldChangeKind::Setting, value))),
+    );
+
+    fields
+}
+
+fn tier_fields(tier: &TierRow, price: &mut impl FnMut(String, &Decimal)) {
+    let key = format!("tiers.{}", tier.first_unit);
+    price(format!("{}.rate", key), &tier.rate);
+    if let Some(flat_fee) = &tier.flat_fee {
+        price(format!("{}.flat_fee", key), flat_fee);
+    }
+    if let Some(flat_cap) = &tier.flat_cap {
+        price(format!("{}.flat_cap", key), flat_cap);
+    }
+}
+
+fn dimension(dimension: &MatrixDimension) -> String {
+    format!("{}={}", dimension.key, dimension.value)
+}
+
+/// 10.00 and 10 are the same price
+fn decimal(value: &Decimal) -> String {
+    value.normalize().to_string()
+}
+
+fn debug<T: Debug>(value: &T) -> String {
+    format!("{:?}", value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::enums::{BillingPeriodEnum, TrialModeEnum};
+    use crate::domain::price_components::TermRate;
+    use rust_decimal_macros::dec;
+
+    fn version() -> PlanVersion {
+        PlanVersion {
+            id: Uuid::now_v7(),
+            is_draft_version: false,
+            plan_id: Uuid::nil(),
+            version: 1,
+            tenant_id: Uuid::nil(),
+            period_start_day: None,
+            net_terms: 30,
+            currency: "EUR".to_string(),
+            billing_cycles: None,
+            created_at: chrono::NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+            billing_periods: vec![BillingPeriodEnum::Monthly],
+            trialing_plan_id: None,
+            action_after_trial: None,
+            trial_is_free: false,
+            trial_mode: TrialModeEnum::default(),
+            downgrade_plan_id: None,
+            trial_duration_days: None,
+            tax_category: None,
+            inherited_defaults: vec![],
+        }
+    }
+
+    fn component(name: &str, fee: FeeType) -> PriceComponent {
+        PriceComponent {
+            id: Uuid::now_v7(),
+            name: name.to_string(),
+            fee,
+            product_item_id: None,
+            rate_card_id: None,
+        }
+    }
+
+    fn rate(monthly: Decimal) -> FeeType {
+        FeeType::Rate {
+            rates: vec![TermRate {
+                term: BillingPeriodEnum::Monthly,
+                price: monthly,
+            }],
+        }
+    }
+
+    #[test]
+    fn test_diff_plan_versions() {
+        let from = version();
+        let to = PlanVersion {
+            id: Uuid::now_v7(),
+            net_terms: 15,
+            ..version()
+        };
+
+        let from_components = vec![
+            component("Platform", rate(dec!(10.00))),
+            component("Seats", rate(dec!(5))),
+            component(
+                "Setup",
+                FeeType::OneTime {
+                    unit_price: dec!(100),
+                    quantity: 1,
+                },
+            ),
+        ];
+        let to_components = vec![
+            component("Platform", rate(dec!(10))),
+            component("Seats", rate(dec!(6))),
+            component(
+                "Onboarding",
+                FeeType::OneTime {
+                    unit_price: dec!(100),
+                    quantity: 1,
+                },
+            ),
+        ];
+
+        let diff = diff_plan_versions(&from, &from_components, &to, &to_components);
+
+        assert_eq!(
+            diff.changes,
+            vec![FieldChange {
+                field: "net_terms".to_string(),
+                kind: FieldChangeKind::Setting,
+                before: Some("30".to_string()),
+                after: Some("15".to_string()),
+            }]
+        );
+
+        // the platform fee has the same price
+        assert_eq!(diff.components.len(), 3);
+
+        let seats = &diff.components[0];
+        assert_eq!(seats.change_type, ComponentChangeType::Changed);
+        assert!(seats.has_price_changes());
+        assert_eq!(
+            seats.changes,
+            vec![FieldChange {
+                field: "rates.Monthly".to_string(),
+                kind: FieldChangeKind::Price,
+                before: Some("5".to_string()),
+                after: Some("6".to_string()),
+            }]
+        );
+
+        assert_eq!(diff.components[1].change_type, ComponentChangeType::Removed);
+        assert_eq!(diff.components[1].name, "Setup");
+        assert!(diff.components[1].changes.iter().all(|c| c.after.is_none()));
+
+        assert_eq!(diff.components[2].change_type, ComponentChangeType::Added);
+        assert_eq!(diff.components[2].name, "Onboarding");
+        assert!(diff.components[2]
+            .changes
+            .iter()
+            .all(|c| c.before.is_none()));
+    }
+
+    #[test]
+    fn test_diff_renamed_component() {
+        let product_id = Some(Uuid::now_v7());
+
+        let from_components = vec![PriceComponent {
+            product_item_id: product_id,
+            ..component("Seats", rate(dec!(5)))
+        }];
+        let to_components = vec![PriceComponent {
+            product_item_id: product_id,
+            ..component("Users", rate(dec!(5)))
+        }];
+
+        let diff = diff_plan_versions(&version(), &from_components, &version(), &to_components);
+
+        assert_eq!(diff.components.len(), 1);
+        assert_eq!(diff.components[0].change_type, ComponentChangeType::Changed);
+        assert!(!diff.components[0].has_price_changes());
+        assert_eq!(diff.components[0].changes[0].field, "name");
+    }
+
+    #[test]
+    fn test_diff_fee_type_change() {
+        let before = fee_fields(&rate(dec!(5)));
+        let after = fee_fields(&FeeType::OneTime {
+            unit_price: dec!(5),
+            quantity: 1,
+        });
+
+        let changes = diff_fields(&before, &after);
+        let fields: Vec<&str> = changes.iter().map(|c| c.field.as_str()).collect();
+
+        assert_eq!(
+            fields,
+            vec!["fee_type", "quantity", "rates.Monthly", "unit_price"]
+        );
+    }
+}
@@ -12,9 +12,14 @@ use diesel_models::plans::PlanWithVersionRow;
 use o2o::o2o;
 use uuid::Uuid;
 // TODO duplicate as well
-use super::enums::{ActionAfterTrialEnum, BillingPeriodEnum, PlanStatusEnum, PlanTypeEnum};
+use super::enums::{
+    ActionAfterTrialEnum, BillingPeriodEnum, PlanStatusEnum, PlanTypeEnum, TrialModeEnum,
+};
 
+use crate::domain::plan_defaults::{inherit_defaults, PlanDefaultSetting};
 use crate::domain::price_components::{PriceComponent, PriceComponentNewInternal};
+use crate::domain::ProductFamilyDefaults;
+use crate::errors::StoreError;
 
 #[derive(Debug, Clone)]
 pub struct PlanNew {
@@ -54,11 +59,13 @@ pub struct FullPlanNew {
 pub struct PlanVersionNewInternal {
     pub is_draft_version: bool,
     pub period_start_day: Option<i16>,
-    pub net_terms: i32,
+    /// the unset settings are inherited from the product family
+    pub net_terms: Option<i32>,
     pub currency: Option<String>,
     pub billing_cycles: Option<i32>,
     pub billing_periods: Vec<BillingPeriodEnum>,
     pub trial: Option<PlanTrial>,
+    pub tax_category: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -70,6 +77,22 @@ pub struct PlanTrial {
     pub trialing_plan_id: Option<Uuid>,
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub require_pre_authorization: bool,
+    pub mode: TrialModeEnum,
+}
+
+impl PlanTrial {
+    /// A card-required trial converts into a paid subscription, so it cannot end with a block or a downgrade
+    pub fn validate(&self) -> Result<(), StoreError> {
+        match (&self.mode, &self.action_after_trial) {
+            (TrialModeEnum::CardRequired, Some(ActionAfterTrialEnum::Block))
+            | (TrialModeEnum::CardRequired, Some(ActionAfterTrialEnum::Downgrade)) => {
+                Err(StoreError::InvalidArgument(
+                    "a card required trial must be charged after the trial".to_string(),
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -82,7 +105,20 @@ pub struct PlanVersionNew {
 }
 
 impl PlanVersionNew {
-    pub fn into_raw(self, tenant_currency: String) -> PlanVersionRowNew {
+    pub fn into_raw(
+        self,
+        tenant_currency: String,
+        defaults: &ProductFamilyDefaults,
+    ) -> PlanVersionRowNew {
+        let settings = inherit_defaults(
+            self.internal.currency,
+            self.internal.trial.as_ref().map(|v| v.duration_days as i32),
+            self.internal.net_terms,
+            self.internal.tax_category,
+            defaults,
+            tenant_currency,
+        );
+
         PlanVersionRowNew {
             id: Uuid::now_v7(),
             plan_id: self.plan_id,
@@ -90,7 +126,7 @@ impl PlanVersionNew {
             version: self.version,
             tenant_id: self.tenant_id,
             is_draft_version: self.internal.is_draft_version,
-            trial_duration_days: self.internal.trial.as_ref().map(|v| v.duration_days as i32),
+            trial_duration_days: settings.trial_duration_days,
             action_after_trial: self
                 .internal
                 .trial
@@ -113,9 +149,15 @@ impl PlanVersionNew {
                 .as_ref()
                 .map(|v| v.require_pre_authorization)
                 .unwrap_or(false),
+            trial_mode: self
+                .internal
+                .trial
+                .as_ref()
+                .map(|v| v.mode.into())
+                .unwrap_or_default(),
             period_start_day: self.internal.period_start_day,
-            net_terms: self.internal.net_terms,
-            currency: self.internal.currency.unwrap_or(tenant_currency),
+            net_terms: settings.net_terms,
+            currency: settings.currency,
             billing_cycles: self.internal.billing_cycles,
             billing_periods: self
                 .internal
@@ -123,6 +165,8 @@ impl PlanVersionNew {
                 .into_iter()
                 .map(|v| v.into())
                 .collect::<Vec<_>>(),
+            tax_category: settings.tax_category,
+            inherited_defaults: settings.inherited,
         }
     }
 }
@@ -158,14 +202,19 @@ pub struct PlanVersion {
     pub billing_cycles: Option<i32>,
     pub created_at: NaiveDateTime,
     pub created_by: Uuid,
-    #[from(~.into_iter().filter_map(| v | v.map(| v | v.into())).collect::< Vec < _ >> ())]
+    #[from(~.into_iter().filter_map(| v | v.map(| v | BillingPeriodEnum::from_row(v, None))).collect::< Vec < _ >> ())]
     pub billing_periods: Vec<BillingPeriodEnum>,
     pub trialing_plan_id: Option<Uuid>,
     #[from(~.map(| v | v.into()))]
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub trial_is_free: bool,
+    #[from(~.into())]
+    pub trial_mode: TrialModeEnum,
     pub downgrade_plan_id: Option<Uuid>,
     pub trial_duration_days: Option<i32>,
+    pub tax_category: Option<String>,
+    /// the settings taken from the product family, see [PlanDefaultSetting]
+    pub inherited_defaults: Vec<String>,
 }
 
 pub struct FullPlan {
@@ -212,12 +261,15 @@ pub struct PlanVersionLatest {
     #[from(~.map(| v | v.into()))]
     pub action_after_trial: Option<ActionAfterTrialEnum>,
     pub trial_is_free: bool,
+    #[from(~.into())]
+    pub trial_mode: TrialModeEnum,
     pub downgrade_plan_id: Option<Uuid>,
     pub trial_duration_days: Option<i32>,
 }
 
 #[derive(Debug, o2o)]
 #[owned_into(PlanVersionRowPatch)]
+#[ghosts(inherited_defaults: {None})]
 pub struct PlanVersionPatch {
     pub id: Uuid,
     pub tenant_id: Uuid,
@@ -225,6 +277,34 @@ pub struct PlanVersionPatch {
     pub net_terms: Option<i32>,
     #[into(~.map(| x | x.into_iter().map(| v | v.into()).collect::< Vec < _ >> ()))]
     pub billing_periods: Option<Vec<BillingPeriodEnum>>,
+    pub tax_category: Option<Option<String>>,
+}
+
+impl PlanVersionPatch {
+    /// The inherited settings patched to another value, that are no longer inherited
+    pub fn overridden_defaults(&self, current: &PlanVersion) -> Vec<PlanDefaultSetting> {
+        let mut overridden = vec![];
+
+        if self
+            .currency
+            .as_ref()
+            .is_some_and(|c| *c != current.currency)
+        {
+            overridden.push(PlanDefaultSetting::Currency);
+        }
+        if self.net_terms.is_some_and(|n| n != current.net_terms) {
+            overridden.push(PlanDefaultSetting::NetTerms);
+        }
+        if self
+            .tax_category
+            .as_ref()
+            .is_some_and(|t| *t != current.tax_category)
+        {
+            overridden.push(PlanDefaultSetting::TaxCategory);
+        }
+
+        overridden
+    }
 }
 
 pub struct PlanAndVersionPatch {
@@ -0,0 +1,772 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use rust_decimal::Decimal;
+
+use crate::domain::enums::{BillingMetricAggregateEnum, BillingPeriodEnum};
+use crate::domain::price_components::{
+    CapacityThreshold, FeeType, MatrixRow, TermRate, TierRow, UsagePricingModel,
+};
+use crate::domain::{BillableMetric, SegmentationMatrix};
+
+/// Unit prices are stored with 8 decimals, more are lost on save
+const UNIT_PRICE_PRECISION: u32 = 8;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PriceComponentIssueSeverity {
+    /// the fee cannot be invoiced as is
+    Error,
+    /// the fee can be invoiced, but probably not as intended
+    Warning,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PriceComponentIssue {
+    pub severity: PriceComponentIssueSeverity,
+    /// path of the offending value in the fee, ex: `tiers[1].first_unit`
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Default)]
+struct Issues(Vec<PriceComponentIssue>);
+
+impl Issues {
+    fn error(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(PriceComponentIssue {
+            severity: PriceComponentIssueSeverity::Error,
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    fn warning(&mut self, field: impl Into<String>, message: impl Into<String>) {
+        self.0.push(PriceComponentIssue {
+            severity: PriceComponentIssueSeverity::Warning,
+            field: field.into(),
+            message: message.into(),
+        });
+    }
+
+    /// Fixed amounts are charged as is, so they must fit the currency
+    fn amount(&mut self, field: &str, amount: &Decimal, currency_precision: u8) {
+        if amount.is_sign_negative() && !amount.is_zero() {
+            self.error(field, "must not be negative");
+        } else if amount.is_zero() {
+            self.warning(field, "is zero, nothing will be charged");
+        }
+
+        if amount.normalize().scale() > currency_precision as u32 {
+            self.warning(
+                field,
+                format!(
+                    "has more than {} decimals, it will be rounded on invoices",
+                    currency_precision
+                ),
+            );
+        }
+    }
+
+    /// Unit prices are multiplied by a quantity before rounding, so they can go below the currency precision
+    fn unit_price(&mut self, field: &str, price: &Decimal) {
+        if price.is_sign_negative() && !price.is_zero() {
+            self.error(field, "must not be negative");
+        } else if price.is_zero() {
+            self.warning(field, "is zero, nothing will be charged");
+        }
+
+        if price.normalize().scale() > UNIT_PRICE_PRECISION {
+            self.error(
+                field,
+                format!("must not have more than {} decimals", UNIT_PRICE_PRECISION),
+            );
+        }
+    }
+
+    /// A zero cap would bill nothing for the capped charges
+    fn cap(&mut self, cap: &Option<Decimal>, currency_precision: u8) {
+        if let Some(cap) = cap {
+            self.cap_amount("cap", cap, currency_precision);
+        }
+    }
+
+    fn cap_amount(&mut self, field: &str, cap: &Decimal, currency_precision: u8) {
+        if *cap <= Decimal::ZERO {
+            self.error(field, "must be greater than 0");
+        } else {
+            self.amount(field, cap, currency_precision);
+        }
+    }
+
+    fn term_rates(&mut self, rates: &[TermRate], currency_precision: u8) {
+        if rates.is_empty() {
+            self.error("rates", "at least one rate is required");
+        }
+
+        let mut terms: Vec<&BillingPeriodEnum> = vec![];
+        for (idx, rate) in rates.iter().enumerate() {
+            if terms.contains(&&rate.term) {
+                self.error(
+                    format!("rates[{}].term", idx),
+                    format!("duplicate rate for term {:?}", rate.term),
+                );
+            }
+            terms.push(&rate.term);
+            if let BillingPeriodEnum::Custom { months } = &rate.term {
+                // the named terms are used for their number of months
+                if !matches!(
+                    BillingPeriodEnum::from_months(*months),
+                    Ok(BillingPeriodEnum::Custom { .. })
+                ) {
+                    self.error(
+                        format!("rates[{}].term", idx),
+                        format!("invalid custom term of {} months", months),
+                    );
+                }
+            }
+            self.amount(
+                &format!("rates[{}].price", idx),
+                &rate.price,
+                currency_precision,
+            );
+        }
+    }
+
+    fn capacity_thresholds(&mut self, thresholds: &[CapacityThreshold], currency_precision: u8) {
+        if thresholds.is_empty() {
+            self.error("thresholds", "at least one threshold is required");
+        }
+
+        let mut included: HashSet<u64> = HashSet::new();
+        for (idx, threshold) in thresholds.iter().enumerate() {
+            if !included.insert(threshold.included_amount) {
+                self.error(
+                    format!("thresholds[{}].included_amount", idx),
+                    format!(
+                        "duplicate threshold for included amount {}",
+                        threshold.included_amount
+                    ),
+                );
+            }
+            self.amount(
+                &format!("thresholds[{}].price", idx),
+                &threshold.price,
+                currency_precision,
+            );
+            self.unit_price(
+                &format!("thresholds[{}].per_unit_overage", idx),
+                &threshold.per_unit_overage,
+            );
+        }
+    }
+
+    fn tiers(&mut self, tiers: &[TierRow], block_size: &Option<u64>, currency_precision: u8) {
+        if tiers.is_empty() {
+            self.error("tiers", "at least one tier is required");
+            return;
+        }
+
+        if tiers[0].first_unit != 0 {
+            self.error(
+                "tiers[0].first_unit",
+                "the first tier must start at 0, usage below it would not be priced",
+            );
+        }
+
+        // the last unit of a tier is the first unit of the next one
+        for (idx, pair) in tiers.windows(2).enumerate() {
+            if pair[1].first_unit <= pair[0].first_unit {
+                self.error(
+                    format!("tiers[{}].first_unit", idx + 1),
+                    format!(
+                        "overlaps the previous tier starting at {}",
+                        pair[0].first_unit
+                    ),
+                );
+            }
+        }
+
+        for (idx, tier) in tiers.iter().enumerate() {
+            self.unit_price(&format!("tiers[{}].rate", idx), &tier.rate);
+            if let Some(flat_fee) = &tier.flat_fee {
+                self.amount(
+                    &format!("tiers[{}].flat_fee", idx),
+                    flat_fee,
+                    currency_precision,
+                );
+            }
+            if let Some(flat_cap) = &tier.flat_cap {
+                self.amount(
+                    &format!("tiers[{}].flat_cap", idx),
+                    flat_cap,
+                    currency_precision,
+                );
+            }
+        }
+
+        if *block_size == Some(0) {
+            self.error("block_size", "must be greater than 0");
+        }
+    }
+
+    fn matrix(&mut self, rates: &[MatrixRow], metric: Option<&BillableMetric>) {
+        if rates.is_empty() {
+            self.error("rates", "at least one matrix rate is required");
+        }
+
+        let mut cells: HashSet<(&str, Option<&str>)> = HashSet::new();
+        for (idx, row) in rates.iter().enumerate() {
+            let cell = (
+                row.dimension1.value.as_str(),
+                row.dimension2.as_ref().map(|d| d.value.as_str()),
+            );
+            if !cells.insert(cell) {
+                self.error(
+                    format!("rates[{}]", idx),
+                    format!("duplicate rate for {}", format_cell(cell)),
+                );
+            }
+            self.unit_price(
+                &format!("rates[{}].per_unit_price", idx),
+                &row.per_unit_price,
+            );
+        }
+
+        self.matrix_dimensions(rates, metric);
+    }
+
+    /// The rates must price exactly the segments of the metric, with its dimension keys
+    fn matrix_dimensions(&mut self, rates: &[MatrixRow], metric: Option<&BillableMetric>) {
+        let matrix = match metric.and_then(|m| m.segmentation_matrix.as_ref()) {
+            Some(matrix) => matrix,
+            None => {
+                if metric.is_some() {
+                    self.error(
+                        "metric_id",
+                        "matrix pricing requires a metric with a segmentation matrix",
+                    );
+                }
+                return;
+            }
+        };
+
+        let (dimension1_key, dimension2_key, expected) = matrix_cells(matrix);
+
+        for (idx, row) in rates.iter().enumerate() {
+            if row.dimension1.key != dimension1_key {
+                self.error(
+                    format!("rates[{}].dimension1.key", idx),
+                    format!("expected dimension {}", dimension1_key),
+                );
+            }
+
+            match (&row.dimension2, &dimension2_key) {
+                (Some(dimension2), Some(key)) if &dimension2.key != key => self.error(
+                    format!("rates[{}].dimension2.key", idx),
+                    format!("expected dimension {}", key),
+                ),
+                (None, Some(key)) => self.error(
+                    format!("rates[{}].dimension2", idx),
+                    format!("missing value for dimension {}", key),
+                ),
+                (Some(_), None) => self.error(
+                    format!("rates[{}].dimension2", idx),
+                    "the metric is segmented on a single dimension",
+                ),
+                _ => {}
+            }
+
+            let cell = (
+                row.dimension1.value.as_str(),
+                row.dimension2.as_ref().map(|d| d.value.as_str()),
+            );
+            if !expected.contains(&cell) {
+                self.error(
+                    format!("rates[{}]", idx),
+                    format!(
+                        "{} is not a segment of the metric, its usage would never match",
+                        format_cell(cell)
+                    ),
+                );
+            }
+        }
+
+        let cells: HashSet<(&str, Option<&str>)> = rates
+            .iter()
+            .map(|row| {
+                (
+                    row.dimension1.value.as_str(),
+                    row.dimension2.as_ref().map(|d| d.value.as_str()),
+                )
+            })
+            .collect();
+        let mut missing: Vec<_> = expected.difference(&cells).copied().collect();
+        missing.sort();
+        for cell in missing {
+            self.error(
+                "rates",
+                format!(
+                    "missing rate for {}, its usage would not be invoiced",
+                    format_cell(cell)
+                ),
+            );
+        }
+    }
+}
+
+/// All the (dimension1, dimension2) value pairs a matrix fee must price
+fn matrix_cells(
+    matrix: &SegmentationMatrix,
+) -> (&str, Option<&str>, HashSet<(&str, Option<&str>)>) {
+    match matrix {
+        SegmentationMatrix::Single(dimension) => (
+            dimension.key.as_str(),
+            None,
+            dimension
+                .values
+                .iter()
+                .map(|v| (v.as_str(), None))
+                .collect(),
+        ),
+        SegmentationMatrix::Double {
+            dimension1,
+            dimension2,
+        } => (
+            dimension1.key.as_str(),
+            Some(dimension2.key.as_str()),
+            dimension1
+                .values
+                .iter()
+                .flat_map(|v1| {
+                    dimension2
+                        .values
+                        .iter()
+                        .map(move |v2| (v1.as_str(), Some(v2.as_str())))
+                })
+                .collect(),
+        ),
+        SegmentationMatrix::Linked {
+            dimension1_key,
+            dimension2_key,
+            values,
+        } => (
+            dimension1_key.as_str(),
+            Some(dimension2_key.as_str()),
+            values
+                .iter()
+                .flat_map(|(v1, v2s)| v2s.iter().map(move |v2| (v1.as_str(), Some(v2.as_str()))))
+                .collect(),
+        ),
+    }
+}
+
+fn format_cell((value1, value2): (&str, Option<&str>)) -> String {
+    match value2 {
+        Some(value2) => format!("{},{}", value1, value2),
+        None => value1.to_string(),
+    }
+}
+
+/// A matrix rate of a price component that does not match the segmentation matrix of its metric
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidMatrixDimension {
+    pub price_component: String,
+    /// path of the offending rate in the fee, ex: `rates[1].dimension1.key`
+    pub field: String,
+    pub message: String,
+}
+
+impl fmt::Display for InvalidMatrixDimension {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} {}: {}",
+            self.price_component, self.field, self.message
+        )
+    }
+}
+
+impl FeeType {
+    /// Checks the fee before it is saved, so that pricing mistakes don't surface at invoicing time.
+    /// `metric` is the billable metric referenced by the fee, if any was found.
+    pub fn validate(
+        &self,
+        metric: Option<&BillableMetric>,
+        currency_precision: u8,
+    ) -> Vec<PriceComponentIssue> {
+        let mut issues = Issues::default();
+
+        if self.metric_id().is_some() && metric.is_none() {
+            issues.error("metric_id", "billable metric not found");
+        }
+
+        match self {
+            FeeType::Rate { rates } => issues.term_rates(rates, currency_precision),
+            FeeType::Slot {
+                rates,
+                slot_unit_name,
+                minimum_count,
+                quota,
+                ..
+            } => {
+                issues.term_rates(rates, currency_precision);
+                if slot_unit_name.trim().is_empty() {
+                    issues.error("slot_unit_name", "is required");
+                }
+                if let (Some(minimum_count), Some(quota)) = (minimum_count, quota) {
+                    if minimum_count > quota {
+                        issues.error("minimum_count", "must not be greater than the quota");
+                    }
+                }
+            }
+            FeeType::Capacity {
+                thresholds, cap, ..
+            } => {
+                issues.capacity_thresholds(thresholds, currency_precision);
+                issues.cap(cap, currency_precision);
+            }
+            FeeType::Usage { pricing, cap, .. } => {
+                match pricing {
+                    UsagePricingModel::PerUnit { rate } => issues.unit_price("rate", rate),
+                    UsagePricingModel::Tiered { tiers, block_size }
+                    | UsagePricingModel::Volume { tiers, block_size } => {
+                        issues.tiers(tiers, block_size, currency_precision)
+                    }
+                    UsagePricingModel::Package { block_size, rate } => {
+                        if *block_size == 0 {
+                            issues.error("block_size", "must be greater than 0");
+                        }
+                        issues.amount("rate", rate, currency_precision);
+                    }
+                    UsagePricingModel::Matrix { rates } => issues.matrix(rates, metric),
+                }
+                issues.cap(cap, currency_precision);
+            }
+            FeeType::ExtraRecurring {
+                unit_price,
+                quantity,
+                ..
+            }
+            | FeeType::OneTime {
+                unit_price,
+                quantity,
+            } => {
+                if *quantity == 0 {
+                    issues.error("quantity", "must be greater than 0");
+                }
+                issues.amount("unit_price", unit_price, currency_precision);
+            }
+            FeeType::Percent {
+                percentage,
+                minimum,
+                maximum,
+                ..
+            } => {
+                if metric.is_some_and(|m| {
+                    matches!(
+                        m.aggregation_type,
+                        BillingMetricAggregateEnum::Count
+                            | BillingMetricAggregateEnum::CountDistinct
+                    )
+                }) {
+                    issues.warning(
+                        "metric_id",
+                        "the metric counts events, the percentage applies to an amount",
+                    );
+                }
+                if *percentage <= Decimal::ZERO || *percentage > Decimal::ONE_HUNDRED {
+                    issues.error("percentage", "must be greater than 0 and at most 100");
+                }
+                if let Some(minimum) = minimum {
+                    issues.amount("minimum", minimum, currency_precision);
+                }
+                if let Some(maximum) = maximum {
+                    issues.cap_amount("maximum", maximum, currency_precision);
+                }
+                if let (Some(minimum), Some(maximum)) = (minimum, maximum) {
+                    if minimum > maximum {
+                        issues.error("minimum", "must not be greater than the maximum");
+                    }
+                }
+            }
+        }
+
+        issues.0
+    }
+
+    /// The rates of a matrix fee that do not match the segmentation matrix of its metric.
+    /// These cannot be invoiced, so unlike the other issues they block saving and publishing the fee
+    pub fn invalid_matrix_dimensions(
+        &self,
+        price_component: &str,
+        metric: Option<&BillableMetric>,
+    ) -> Vec<InvalidMatrixDimension> {
+        let FeeType::Usage {
+            pricing: UsagePricingModel::Matrix { rates },
+            ..
+        } = self
+        else {
+            return vec![];
+        };
+
+        let mut issues = Issues::default();
+        issues.matrix_dimensions(rates, metric);
+
+        issues
+            .0
+            .into_iter()
+            .map(|issue| InvalidMatrixDimension {
+                price_component: price_component.to_string(),
+                field: issue.field,
+                message: issue.message,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::price_components::MatrixDimension;
+    use crate::domain::Dimension;
+    use rust_decimal_macros::dec;
+    use uuid::Uuid;
+
+    fn metric(segmentation_matrix: Option<SegmentationMatrix>) -> BillableMetric {
+        BillableMetric {
+            id: Uuid::nil(),
+            name: "api calls".to_string(),
+            description: None,
+            code: "api_calls".to_string(),
+            aggregation_type: BillingMetricAggregateEnum::Count,
+            aggregation_key: None,
+            unit_conversion_factor: None,
+            unit_conversion_rounding: None,
+            segmentation_matrix,
+            usage_group_key: None,
+            created_at: chrono::NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+            updated_at: None,
+            archived_at: None,
+            tenant_id: Uuid::nil(),
+            product_family_id: Uuid::nil(),
+            quantity_billing_precision: None,
+            quantity_display_precision: None,
+            event_schema: None,
+        }
+    }
+
+    fn matrix_row(value1: &str, value2: &str, price: Decimal) -> MatrixRow {
+        MatrixRow {
+            dimension1: MatrixDimension {
+                key: "region".to_string(),
+                value: value1.to_string(),
+            },
+            dimension2: Some(MatrixDimension {
+                key: "tier".to_string(),
+                value: value2.to_string(),
+            }),
+            per_unit_price: price,
+        }
+    }
+
+    fn errors(issues: &[PriceComponentIssue]) -> Vec<&PriceComponentIssue> {
+        issues
+            .iter()
+            .filter(|i| i.severity == PriceComponentIssueSeverity::Error)
+            .collect()
+    }
+
+    #[test]
+    fn test_matrix_completeness() {
+        let metric = metric(Some(SegmentationMatrix::Double {
+            dimension1: Dimension {
+                key: "region".to_string(),
+                values: vec!["eu".to_string(), "us".to_string()],
+            },
+            dimension2: Dimension {
+                key: "tier".to_string(),
+                values: vec!["standard".to_string()],
+            },
+        }));
+
+        let fee = FeeType::Usage {
+            metric_id: metric.id,
+            pricing: UsagePricingModel::Matrix {
+                rates: vec![
+                    matrix_row("eu", "standard", dec!(0.01)),
+                    matrix_row("apac", "standard", dec!(0.01)),
+                ],
+            },
+            cap: None,
+            included: None,
+        };
+
+        let issues = fee.validate(Some(&metric), 2);
+        let errors = errors(&issues);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field, "rates[1]");
+        assert!(errors[1].message.contains("us,standard"));
+    }
+
+    #[test]
+    fn test_matrix_requires_segmented_metric() {
+        let metric = metric(None);
+
+        let fee = FeeType::Usage {
+            metric_id: metric.id,
+            pricing: UsagePricingModel::Matrix {
+                rates: vec![matrix_row("eu", "standard", dec!(0.01))],
+            },
+            cap: None,
+            included: None,
+        };
+
+        let issues = fee.validate(Some(&metric), 2);
+
+        assert_eq!(errors(&issues).len(), 1);
+        assert_eq!(issues[0].field, "metric_id");
+    }
+
+    #[test]
+    fn test_invalid_matrix_dimensions() {
+        let metric = metric(Some(SegmentationMatrix::Single(Dimension {
+            key: "region".to_string(),
+            values: vec!["eu".to_string()],
+        })));
+
+        let fee = FeeType::Usage {
+            metric_id: metric.id,
+            pricing: UsagePricingModel::Matrix {
+                rates: vec![matrix_row("eu", "standard", dec!(-1))],
+            },
+            cap: None,
+            included: None,
+        };
+
+        let invalid = fee.invalid_matrix_dimensions("API calls", Some(&metric));
+
+        // the negative price is not a dimension issue
+        assert_eq!(invalid.len(), 3);
+        assert_eq!(invalid[0].field, "rates[0].dimension2");
+        assert_eq!(invalid[1].field, "rates[0]");
+        assert_eq!(invalid[2].field, "rates");
+        assert_eq!(
+            invalid[0].to_string(),
+            "API calls rates[0].dimension2: the metric is segmented on a single dimension"
+        );
+        assert!(FeeType::OneTime {
+            unit_price: dec!(1),
+            quantity: 1
+        }
+        .invalid_matrix_dimensions("setup", None)
+        .is_empty());
+    }
+
+    #[test]
+    fn test_overlapping_tiers() {
+        let tier = |first_unit: u64, rate: Decimal| TierRow {
+            first_unit,
+            rate,
+            flat_fee: None,
+            flat_cap: None,
+        };
+
+        let fee = FeeType::Usage {
+            metric_id: Uuid::nil(),
+            pricing: UsagePricingModel::Tiered {
+                tiers: vec![tier(0, dec!(1)), tier(100, dec!(0.5)), tier(100, dec!(-1))],
+                block_size: None,
+            },
+            cap: None,
+            included: None,
+        };
+
+        let issues = fee.validate(Some(&metric(None)), 2);
+        let errors = errors(&issues);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field, "tiers[2].first_unit");
+        assert_eq!(errors[1].field, "tiers[2].rate");
+    }
+
+    #[test]
+    fn test_cap() {
+        let fee = |cap: Decimal| FeeType::Usage {
+            metric_id: Uuid::nil(),
+            pricing: UsagePricingModel::PerUnit { rate: dec!(0.1) },
+            cap: Some(cap),
+            included: None,
+        };
+
+        let issues = fee(dec!(0)).validate(Some(&metric(None)), 2);
+        let errors = errors(&issues);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "cap");
+        assert!(fee(dec!(500)).validate(Some(&metric(None)), 2).is_empty());
+    }
+
+    #[test]
+    fn test_percent_bounds() {
+        let fee = |percentage: Decimal, minimum: Decimal, maximum: Decimal| FeeType::Percent {
+            metric_id: Uuid::nil(),
+            percentage,
+            minimum: Some(minimum),
+            maximum: Some(maximum),
+        };
+
+        let issues = fee(dec!(120), dec!(50), dec!(10)).validate(Some(&metric(None)), 2);
+        let errors = errors(&issues);
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].field, "percentage");
+        assert_eq!(errors[1].field, "minimum");
+        // the test metric counts the events
+        let issues = fee(dec!(2.9), dec!(10), dec!(500)).validate(Some(&metric(None)), 2);
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, PriceComponentIssueSeverity::Warning);
+    }
+
+    #[test]
+    fn test_custom_term() {
+        let fee = FeeType::Rate {
+            rates: vec![
+                TermRate {
+                    term: BillingPeriodEnum::Custom { months: 4 },
+                    price: dec!(40),
+                },
+                // a quarterly rate
+                TermRate {
+                    term: BillingPeriodEnum::Custom { months: 3 },
+                    price: dec!(30),
+                },
+                TermRate {
+                    term: BillingPeriodEnum::Weekly,
+                    price: dec!(10),
+                },
+            ],
+        };
+
+        let issues = fee.validate(None, 2);
+        let errors = errors(&issues);
+
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].field, "rates[1].term");
+    }
+
+    #[test]
+    fn test_currency_precision() {
+        let fee = FeeType::OneTime {
+            unit_price: dec!(10.005),
+            quantity: 1,
+        };
+
+        let issues = fee.validate(None, 2);
+
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, PriceComponentIssueSeverity::Warning);
+        assert!(fee.validate(None, 3).is_empty());
+    }
+}
@@ -4,7 +4,7 @@ use uuid::Uuid;
 // TODO duplicate as well
 use super::enums::{BillingPeriodEnum, BillingType, SubscriptionFeeBillingPeriod};
 
-use crate::domain::SubscriptionFee;
+use crate::domain::{SubLineItem, SubscriptionFee};
 use crate::errors::StoreError;
 use diesel_models::price_components::{PriceComponentRow, PriceComponentRowNew};
 use serde::{Deserialize, Serialize};
@@ -15,6 +15,8 @@ pub struct PriceComponent {
     pub name: String,
     pub fee: FeeType,
     pub product_item_id: Option<Uuid>,
+    /// the rate card pricing the usage fee, updated with it while the plan version is a draft
+    pub rate_card_id: Option<Uuid>,
 }
 
 impl TryInto<PriceComponent> for PriceComponentRow {
@@ -31,6 +33,7 @@ impl TryInto<PriceComponent> for PriceComponentRow {
             name: self.name,
             fee,
             product_item_id: self.product_item_id,
+            rate_card_id: self.rate_card_id,
         })
     }
 }
@@ -41,6 +44,7 @@ pub struct PriceComponentNew {
     pub fee: FeeType,
     pub product_item_id: Option<Uuid>,
     pub plan_version_id: Uuid,
+    pub rate_card_id: Option<Uuid>,
 }
 
 #[derive(Debug, Clone)]
@@ -65,6 +69,7 @@ impl TryInto<PriceComponentRowNew> for PriceComponentNew {
             fee: json_fee,
             product_item_id: self.product_item_id,
             billable_metric_id: self.fee.metric_id(),
+            rate_card_id: self.rate_card_id,
         })
     }
 }
@@ -114,6 +119,24 @@ pub struct TierRow {
     pub flat_cap: Option<rust_decimal::Decimal>,
 }
 
+/// The usage priced by the tiers of a graduated or volume model
+#[derive(Clone, Debug)]
+pub struct TieredPrice {
+    /// the usage, rounded up to the block size
+    pub quantity: rust_decimal::Decimal,
+    /// in subunits
+    pub total: i64,
+    pub sublines: Vec<SubLineItem>,
+}
+
+/// A usage priced by the same tiers as graduated and as volume, to compare the two models
+#[derive(Clone, Debug)]
+pub struct TieredPricingPreview {
+    pub usage: rust_decimal::Decimal,
+    pub graduated: TieredPrice,
+    pub volume: TieredPrice,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub enum FeeType {
     Rate {
@@ -130,10 +153,16 @@ pub enum FeeType {
     Capacity {
         metric_id: Uuid,
         thresholds: Vec<CapacityThreshold>,
+        /// maximum overage billed per period
+        cap: Option<rust_decimal::Decimal>,
     },
     Usage {
         metric_id: Uuid,
         pricing: UsagePricingModel,
+        /// maximum usage charges billed per period
+        cap: Option<rust_decimal::Decimal>,
+        /// units included for free each period, the usage above it is priced
+        included: Option<u64>,
     },
     ExtraRecurring {
         unit_price: rust_decimal::Decimal,
@@ -145,6 +174,17 @@ pub enum FeeType {
         unit_price: rust_decimal::Decimal,
         quantity: u32,
     },
+    /// a share of the monetary amount aggregated by the metric, ex: 2% of the payment volume.
+    /// The metric aggregates the amount in the currency of the plan, converted from subunits by its unit conversion if needed
+    Percent {
+        metric_id: Uuid,
+        /// in percent, 2.5 for 2.5%
+        percentage: rust_decimal::Decimal,
+        /// minimum billed per period
+        minimum: Option<rust_decimal::Decimal>,
+        /// maximum billed per period
+        maximum: Option<rust_decimal::Decimal>,
+    },
 }
 
 impl FeeType {
@@ -152,6 +192,7 @@ impl FeeType {
         match self {
             FeeType::Capacity { metric_id, .. } => Some(*metric_id),
             FeeType::Usage { metric_id, .. } => Some(*metric_id),
+            FeeType::Percent { metric_id, .. } => Some(*metric_id),
             _ => None,
         }
     }
@@ -179,7 +220,8 @@ impl FeeType {
                 quota,
                 slot_unit_name,
                 rates,
-                ..
+                upgrade_policy,
+                downgrade_policy,
             } => {
                 if rates.len() != 1 {
                     return Err(StoreError::InvalidArgument(format!(
@@ -196,12 +238,15 @@ impl FeeType {
                         min_slots: *minimum_count,
                         max_slots: *quota,
                         initial_slots: minimum_count.unwrap_or(0),
+                        upgrade_policy: upgrade_policy.clone(),
+                        downgrade_policy: downgrade_policy.clone(),
                     },
                 ))
             }
             FeeType::Capacity {
                 metric_id,
                 thresholds,
+                cap,
             } => {
                 if thresholds.len() != 1 {
                     return Err(StoreError::InvalidArgument(format!(
@@ -217,6 +262,7 @@ impl FeeType {
                         overage_rate: thresholds[0].per_unit_overage,
                         included: thresholds[0].included_amount,
                         rate: thresholds[0].price,
+                        cap: *cap,
                     },
                 ))
             }
@@ -231,11 +277,18 @@ impl FeeType {
                     quantity: *quantity,
                 },
             )),
-            FeeType::Usage { metric_id, pricing } => Ok((
+            FeeType::Usage {
+                metric_id,
+                pricing,
+                cap,
+                included,
+            } => Ok((
                 SubscriptionFeeBillingPeriod::Monthly,
                 SubscriptionFee::Usage {
                     metric_id: *metric_id,
                     model: pricing.clone(),
+                    cap: *cap,
+                    included: *included,
                 },
             )),
             FeeType::ExtraRecurring {
@@ -251,6 +304,20 @@ impl FeeType {
                     billing_type: billing_type.clone(),
                 },
             )),
+            FeeType::Percent {
+                metric_id,
+                percentage,
+                minimum,
+                maximum,
+            } => Ok((
+                SubscriptionFeeBillingPeriod::Monthly,
+                SubscriptionFee::Percent {
+                    metric_id: *metric_id,
+                    percentage: *percentage,
+                    minimum: *minimum,
+                    maximum: *maximum,
+                },
+            )),
         }
     }
 
@@ -302,7 +369,8 @@ impl FeeType {
                 minimum_count,
                 slot_unit_name,
                 quota,
-                ..
+                upgrade_policy,
+                downgrade_policy,
             } => {
                 let billing_period = billing_period.as_ref().ok_or_else(|| {
                     StoreError::InvalidArgument("Missing billing period".to_string())
@@ -333,12 +401,15 @@ impl FeeType {
                         min_slots: *minimum_count,
                         max_slots: *quota,
                         initial_slots,
+                        upgrade_policy: upgrade_policy.clone(),
+                        downgrade_policy: downgrade_policy.clone(),
                     },
                 ))
             }
             FeeType::Capacity {
                 metric_id,
                 thresholds,
+                cap,
             } => {
                 let committed_capacity = committed_capacity.ok_or_else(|| {
                     StoreError::InvalidArgument("Missing committed capacity".to_string())
@@ -367,16 +438,18 @@ impl FeeType {
                         overage_rate: threshold.per_unit_overage,
                         included: threshold.included_amount,
                         rate: threshold.price,
+                        cap: *cap,
                     },
                 ))
             }
             // all other case should fail, as they just cannot be parametrized
-            FeeType::Usage { .. } | FeeType::ExtraRecurring { .. } | FeeType::OneTime { .. } => {
-                Err(StoreError::InvalidArgument(format!(
-                    "Cannot parameterize fee type: {:?}",
-                    self
-                )))
-            }
+            FeeType::Usage { .. }
+            | FeeType::ExtraRecurring { .. }
+            | FeeType::OneTime { .. }
+            | FeeType::Percent { .. } => Err(StoreError::InvalidArgument(format!(
+                "Cannot parameterize fee type: {:?}",
+                self
+            ))),
         }
     }
 }
@@ -394,12 +467,16 @@ pub struct CapacityThreshold {
     pub per_unit_overage: rust_decimal::Decimal,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub enum UpgradePolicy {
+    /// the added slots are charged immediately, prorated to the remainder of the period
+    #[default]
     Prorated,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, Default)]
 pub enum DowngradePolicy {
+    /// the removed slots stay active and billed until the end of the period
+    #[default]
     RemoveAtEndOfPeriod,
 }
@@ -2,6 +2,7 @@ use chrono::NaiveDateTime;
 use o2o::o2o;
 use uuid::Uuid;
 
+use diesel_models::product_families::ProductFamilyDefaultsRowPatch;
 use diesel_models::product_families::ProductFamilyRow;
 use diesel_models::product_families::ProductFamilyRowNew;
 
@@ -16,6 +17,10 @@ pub struct ProductFamily {
     pub updated_at: Option<NaiveDateTime>,
     pub archived_at: Option<NaiveDateTime>,
     pub tenant_id: Uuid,
+    pub default_currency: Option<String>,
+    pub default_trial_duration_days: Option<i32>,
+    pub default_net_terms: Option<i32>,
+    pub default_tax_category: Option<String>,
 }
 
 #[derive(Clone, Debug, o2o)]
@@ -25,4 +30,39 @@ pub struct ProductFamilyNew {
     pub name: String,
     pub external_id: String,
     pub tenant_id: Uuid,
+    pub default_currency: Option<String>,
+    pub default_trial_duration_days: Option<i32>,
+    pub default_net_terms: Option<i32>,
+    pub default_tax_category: Option<String>,
+}
+
+/// Settings inherited by the new plans of the family, unless set on the plan
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct ProductFamilyDefaults {
+    pub currency: Option<String>,
+    pub trial_duration_days: Option<i32>,
+    pub net_terms: Option<i32>,
+    pub tax_category: Option<String>,
+}
+
+impl From<ProductFamilyDefaults> for ProductFamilyDefaultsRowPatch {
+    fn from(value: ProductFamilyDefaults) -> Self {
+        ProductFamilyDefaultsRowPatch {
+            default_currency: value.currency,
+            default_trial_duration_days: value.trial_duration_days,
+            default_net_terms: value.net_terms,
+            default_tax_category: value.tax_category,
+        }
+    }
+}
+
+impl ProductFamily {
+    pub fn defaults(&self) -> ProductFamilyDefaults {
+        ProductFamilyDefaults {
+            currency: self.default_currency.clone(),
+            trial_duration_days: self.default_trial_duration_days,
+            net_terms: self.default_net_terms,
+            tax_category: self.default_tax_category.clone(),
+        }
+    }
 }
@@ -0,0 +1,258 @@
+use crate::domain::{FeeType, UsagePricingModel};
+use crate::errors::StoreError;
+use chrono::NaiveDateTime;
+use diesel_models::rate_cards::{
+    RateCardRow, RateCardRowNew, RateCardVersionRow, RateCardVersionRowNew,
+};
+use error_stack::Report;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// The price of a metric in a rate card
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RateCardRate {
+    pub metric_id: Uuid,
+    pub pricing: UsagePricingModel,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateCard {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl From<RateCardRow> for RateCard {
+    fn from(row: RateCardRow) -> Self {
+        RateCard {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            name: row.name,
+            description: row.description,
+            currency: row.currency,
+            created_at: row.created_at,
+            created_by: row.created_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateCardVersion {
+    pub id: Uuid,
+    pub rate_card_id: Uuid,
+    pub version: i32,
+    pub rates: Vec<RateCardRate>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<RateCardVersionRow> for RateCardVersion {
+    type Error = Report<StoreError>;
+
+    fn try_from(row: RateCardVersionRow) -> Result<Self, Self::Error> {
+        let rates: Vec<RateCardRate> = serde_json::from_value(row.rates).map_err(|e| {
+            StoreError::SerdeError("Failed to deserialize rate card rates".to_string(), e)
+        })?;
+
+        Ok(RateCardVersion {
+            id: row.id,
+            rate_card_id: row.rate_card_id,
+            version: row.version,
+            rates,
+            created_at: row.created_at,
+            created_by: row.created_by,
+        })
+    }
+}
+
+impl RateCardVersion {
+    pub fn pricing_for(&self, metric_id: Uuid) -> Option<&UsagePricingModel> {
+        self.rates
+            .iter()
+            .find(|r| r.metric_id == metric_id)
+            .map(|r| &r.pricing)
+    }
+
+    /// The usage fee priced with the rate of its metric in this version
+    pub fn apply_to(&self, fee: &FeeType) -> Result<FeeType, StoreError> {
+        match fee {
+            FeeType::Usage {
+                metric_id,
+                cap,
+                included,
+                ..
+            } => {
+                let pricing = self.pricing_for(*metric_id).ok_or_else(|| {
+                    StoreError::InvalidArgument(format!(
+                        "Metric {} is not priced by the rate card",
+                        metric_id
+                    ))
+                })?;
+
+                Ok(FeeType::Usage {
+                    metric_id: *metric_id,
+                    pricing: pricing.clone(),
+                    cap: *cap,
+                    included: *included,
+                })
+            }
+            _ => Err(StoreError::InvalidArgument(
+                "Only usage fees can be priced by a rate card".to_string(),
+            )),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateCardDetails {
+    pub rate_card: RateCard,
+    pub current_version: RateCardVersion,
+}
+
+#[derive(Debug, Clone)]
+pub struct RateCardNew {
+    pub tenant_id: Uuid,
+    pub name: String,
+    pub description: Option<String>,
+    pub currency: String,
+    pub rates: Vec<RateCardRate>,
+    pub created_by: Uuid,
+}
+
+impl RateCardNew {
+    pub fn to_row(&self) -> RateCardRowNew {
+        RateCardRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: self.tenant_id,
+            name: self.name.clone(),
+            description: self.description.clone(),
+            currency: self.currency.clone(),
+            created_by: self.created_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RateCardVersionNew {
+    pub tenant_id: Uuid,
+    pub rate_card_id: Uuid,
+    pub rates: Vec<RateCardRate>,
+    pub created_by: Uuid,
+}
+
+impl RateCardVersionNew {
+    pub fn to_row(&self, version: i32) -> Result<RateCardVersionRowNew, StoreError> {
+        validate_rates(&self.rates)?;
+
+        let rates = serde_json::to_value(&self.rates).map_err(|e| {
+            StoreError::SerdeError("Failed to serialize rate card rates".to_string(), e)
+        })?;
+
+        Ok(RateCardVersionRowNew {
+            id: Uuid::now_v7(),
+            rate_card_id: self.rate_card_id,
+            version,
+            rates,
+            created_by: self.created_by,
+        })
+    }
+}
+
+fn validate_rates(rates: &[RateCardRate]) -> Result<(), StoreError> {
+    if rates.is_empty() {
+        return Err(StoreError::InvalidArgument(
+            "A rate card must price at least one metric".to_string(),
+        ));
+    }
+
+    let mut metric_ids: Vec<Uuid> = rates.iter().map(|r| r.metric_id).collect();
+    metric_ids.sort();
+    metric_ids.dedup();
+
+    if metric_ids.len() != rates.len() {
+        return Err(StoreError::InvalidArgument(
+            "A metric can only be priced once in a rate card".to_string(),
+        ));
+    }
+
+    Ok(())
+}
+
+/// The result of a new rate card version, with the number of draft price components updated
+#[derive(Debug, Clone)]
+pub struct RateCardPublication {
+    pub rate_card: RateCardDetails,
+    pub updated_price_components: usize,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn version(rates: Vec<RateCardRate>) -> RateCardVersion {
+        RateCardVersion {
+            id: Uuid::now_v7(),
+            rate_card_id: Uuid::now_v7(),
+            version: 1,
+            rates,
+            created_at: chrono::Utc::now().naive_utc(),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_apply_to_usage_fee() {
+        let metric_id = Uuid::now_v7();
+        let version = version(vec![RateCardRate {
+            metric_id,
+            pricing: UsagePricingModel::PerUnit { rate: dec!(0.02) },
+        }]);
+
+        let fee = FeeType::Usage {
+            metric_id,
+            pricing: UsagePricingModel::PerUnit { rate: dec!(0.05) },
+            cap: None,
+            included: None,
+        };
+
+        match version.apply_to(&fee).unwrap() {
+            FeeType::Usage {
+                pricing: UsagePricingModel::PerUnit { rate },
+                ..
+            } => assert_eq!(rate, dec!(0.02)),
+            _ => panic!("unexpected fee"),
+        }
+
+        let other_metric = FeeType::Usage {
+            metric_id: Uuid::now_v7(),
+            pricing: UsagePricingModel::PerUnit { rate: dec!(0.05) },
+            cap: None,
+            included: None,
+        };
+        assert!(version.apply_to(&other_metric).is_err());
+
+        let one_time = FeeType::OneTime {
+            unit_price: dec!(10),
+            quantity: 1,
+        };
+        assert!(version.apply_to(&one_time).is_err());
+    }
+
+    #[test]
+    fn test_validate_rates() {
+        let metric_id = Uuid::now_v7();
+        let rate = RateCardRate {
+            metric_id,
+            pricing: UsagePricingModel::PerUnit { rate: dec!(1) },
+        };
+
+        assert!(validate_rates(&[]).is_err());
+        assert!(validate_rates(&[rate.clone()]).is_ok());
+        assert!(validate_rates(&[rate.clone(), rate]).is_err());
+    }
+}
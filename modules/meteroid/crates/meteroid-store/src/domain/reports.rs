@@ -0,0 +1,462 @@
+use chrono::{Datelike, Days, Months, NaiveDate};
+use diesel_models::bi::BiMrrMovementLogRow;
+use diesel_models::stats::SubscriptionSegmentRow;
+use uuid::Uuid;
+
+use crate::domain::enums::{BillingPeriodEnum, MrrMovementType};
+
+// ~3 years of daily periods
+pub const MAX_REPORT_PERIODS: usize = 1100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ReportGranularity {
+    Day,
+    Week,
+    Month,
+    Quarter,
+    Year,
+}
+
+impl ReportGranularity {
+    /// First day of the period containing the date. Weeks start on monday
+    fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            ReportGranularity::Day => date,
+            ReportGranularity::Week => {
+                date - Days::new(date.weekday().num_days_from_monday() as u64)
+            }
+            ReportGranularity::Month => date.with_day(1).unwrap_or(date),
+            ReportGranularity::Quarter => {
+                NaiveDate::from_ymd_opt(date.year(), (date.month0() / 3) * 3 + 1, 1).unwrap_or(date)
+            }
+            ReportGranularity::Year => NaiveDate::from_ymd_opt(date.year(), 1, 1).unwrap_or(date),
+        }
+    }
+
+    fn next_period_start(&self, date: NaiveDate) -> NaiveDate {
+        let start = self.period_start(date);
+        match self {
+            ReportGranularity::Day => start + Days::new(1),
+            ReportGranularity::Week => start + Days::new(7),
+            ReportGranularity::Month => start + Months::new(1),
+            ReportGranularity::Quarter => start + Months::new(3),
+            ReportGranularity::Year => start + Months::new(12),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportRequest {
+    pub tenant_id: Uuid,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub granularity: ReportGranularity,
+    /// all plans if empty
+    pub plan_ids: Vec<Uuid>,
+    /// the reporting currency of the tenant if not set. Movements in other currencies are not included
+    pub currency: Option<String>,
+}
+
+#[derive(Debug, Clone)]
+pub struct ReportMovement {
+    pub applies_to: NaiveDate,
+    pub movement_type: MrrMovementType,
+    pub net_mrr_change: i64,
+}
+
+impl From<BiMrrMovementLogRow> for ReportMovement {
+    fn from(row: BiMrrMovementLogRow) -> Self {
+        ReportMovement {
+            applies_to: row.applies_to,
+            movement_type: row.movement_type.into(),
+            net_mrr_change: row.net_mrr_change,
+        }
+    }
+}
+
+/// The MRR movements of a period. Contraction and churn are negative
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MrrReportPeriod {
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub starting_mrr: i64,
+    pub new_business: i64,
+    pub expansion: i64,
+    pub contraction: i64,
+    pub churn: i64,
+    pub reactivation: i64,
+    pub ending_mrr: i64,
+    pub starting_subscriptions: i64,
+    pub churned_subscriptions: i64,
+    pub ending_subscriptions: i64,
+}
+
+impl MrrReportPeriod {
+    pub fn net_new_mrr(&self) -> i64 {
+        self.ending_mrr - self.starting_mrr
+    }
+
+    /// Share of the starting MRR lost to churn and contraction
+    pub fn revenue_churn_rate(&self) -> Option<f64> {
+        percent(-(self.churn + self.contraction), self.starting_mrr)
+    }
+
+    /// Share of the subscriptions paying at the start of the period that churned
+    pub fn subscription_churn_rate(&self) -> Option<f64> {
+        percent(self.churned_subscriptions, self.starting_subscriptions)
+    }
+
+    /// MRR kept from the subscriptions paying at the start of the period, new business excluded
+    pub fn net_revenue_retention(&self) -> Option<f64> {
+        percent(
+            self.starting_mrr + self.expansion + self.reactivation + self.contraction + self.churn,
+            self.starting_mrr,
+        )
+    }
+
+    /// Average MRR per paying subscription at the end of the period
+    pub fn arpu(&self) -> Option<i64> {
+        (self.ending_subscriptions > 0).then(|| self.ending_mrr / self.ending_subscriptions)
+    }
+}
+
+fn percent(value: i64, total: i64) -> Option<f64> {
+    (total > 0).then(|| ((value as f64 / total as f64) * 10000.0).round() / 100.0)
+}
+
+#[derive(Debug, Clone)]
+pub struct MrrReport {
+    pub currency: String,
+    pub periods: Vec<MrrReportPeriod>,
+}
+
+/// Splits the date range in periods and aggregates the movements in each.
+/// The movements must be sorted by date, and include the ones before the range to compute the starting MRR
+pub fn build_mrr_report_periods(
+    movements: &[ReportMovement],
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    granularity: ReportGranularity,
+) -> Vec<MrrReportPeriod> {
+    let mut mrr = 0;
+    let mut subscriptions = 0;
+    let mut movements = movements.iter().peekable();
+
+    // movements before the report
+    while let Some(m) = movements.next_if(|m| m.applies_to < start_date) {
+        mrr += m.net_mrr_change;
+        subscriptions += subscription_change(&m.movement_type);
+    }
+
+    let mut periods = vec![];
+    let mut period_start = start_date;
+
+    while period_start <= end_date {
+        let period_end = (granularity.next_period_start(period_start) - Days::new(1)).min(end_date);
+
+        let mut period = MrrReportPeriod {
+            start_date: period_start,
+            end_date: period_end,
+            starting_mrr: mrr,
+            starting_subscriptions: subscriptions,
+            ..Default::default()
+        };
+
+        while let Some(m) = movements.next_if(|m| m.applies_to <= period_end) {
+            match m.movement_type {
+                MrrMovementType::NewBusiness => period.new_business += m.net_mrr_change,
+                MrrMovementType::Expansion => period.expansion += m.net_mrr_change,
+                MrrMovementType::Contraction => period.contraction += m.net_mrr_change,
+                MrrMovementType::Churn => {
+                    period.churn += m.net_mrr_change;
+                    period.churned_subscriptions += 1;
+                }
+                MrrMovementType::Reactivation => period.reactivation += m.net_mrr_change,
+            }
+            mrr += m.net_mrr_change;
+            subscriptions += subscription_change(&m.movement_type);
+        }
+
+        period.ending_mrr = mrr;
+        period.ending_subscriptions = subscriptions;
+        periods.push(period);
+
+        period_start = period_end + Days::new(1);
+    }
+
+    periods
+}
+
+pub fn count_report_periods(
+    start_date: NaiveDate,
+    end_date: NaiveDate,
+    granularity: ReportGranularity,
+) -> usize {
+    let mut count = 0;
+    let mut period_start = start_date;
+    while period_start <= end_date {
+        count += 1;
+        period_start = granularity.next_period_start(period_start);
+    }
+    count
+}
+
+fn subscription_change(movement_type: &MrrMovementType) -> i64 {
+    match movement_type {
+        MrrMovementType::NewBusiness | MrrMovementType::Reactivation => 1,
+        MrrMovementType::Churn => -1,
+        MrrMovementType::Expansion | MrrMovementType::Contraction => 0,
+    }
+}
+
+/// The active subscriptions and MRR of a segment, at the report date and at the compared date
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SubscriptionSegment {
+    pub plan_id: Uuid,
+    pub plan_name: String,
+    pub billing_period: BillingPeriodEnum,
+    pub currency: String,
+    /// country of the billing address of the customers, if any
+    pub country: Option<String>,
+    pub subscriptions: i64,
+    pub previous_subscriptions: i64,
+    pub mrr_cents: i64,
+    pub previous_mrr_cents: i64,
+}
+
+impl SubscriptionSegment {
+    pub fn subscriptions_delta(&self) -> i64 {
+        self.subscriptions - self.previous_subscriptions
+    }
+
+    pub fn mrr_delta_cents(&self) -> i64 {
+        self.mrr_cents - self.previous_mrr_cents
+    }
+}
+
+impl From<SubscriptionSegmentRow> for SubscriptionSegment {
+    fn from(row: SubscriptionSegmentRow) -> Self {
+        SubscriptionSegment {
+            plan_id: row.plan_id,
+            plan_name: row.plan_name,
+            billing_period: BillingPeriodEnum::from_row(
+                row.billing_period,
+                row.billing_period_months,
+            ),
+            currency: row.currency,
+            country: row.country,
+            subscriptions: row.subscriptions,
+            previous_subscriptions: row.previous_subscriptions,
+            mrr_cents: row.mrr_cents,
+            previous_mrr_cents: row.previous_mrr_cents,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionSegmentReport {
+    pub as_of: NaiveDate,
+    pub compare_to: NaiveDate,
+    pub segments: Vec<SubscriptionSegment>,
+}
+
+impl SubscriptionSegmentReport {
+    /// One line per segment, the MRR in the minor unit of its currency
+    pub fn to_csv(&self) -> String {
+        let mut csv = String::from(
+            "plan_id,plan,billing_period,currency,country,subscriptions,previous_subscriptions,subscriptions_delta,mrr_cents,previous_mrr_cents,mrr_delta_cents\n",
+        );
+
+        for segment in &self.segments {
+            let fields = [
+                segment.plan_id.to_string(),
+                csv_field(&segment.plan_name),
+                billing_period_label(&segment.billing_period),
+                csv_field(&segment.currency),
+                csv_field(segment.country.as_deref().unwrap_or_default()),
+                segment.subscriptions.to_string(),
+                segment.previous_subscriptions.to_string(),
+                segment.subscriptions_delta().to_string(),
+                segment.mrr_cents.to_string(),
+                segment.previous_mrr_cents.to_string(),
+                segment.mrr_delta_cents().to_string(),
+            ];
+            csv.push_str(&fields.join(","));
+            csv.push('\n');
+        }
+
+        csv
+    }
+}
+
+fn billing_period_label(period: &BillingPeriodEnum) -> String {
+    match period {
+        BillingPeriodEnum::Weekly => "weekly".to_string(),
+        BillingPeriodEnum::Monthly => "monthly".to_string(),
+        BillingPeriodEnum::Bimonthly => "bimonthly".to_string(),
+        BillingPeriodEnum::Quarterly => "quarterly".to_string(),
+        BillingPeriodEnum::Semiannual => "semiannual".to_string(),
+        BillingPeriodEnum::Annual => "annual".to_string(),
+        BillingPeriodEnum::Custom { months } => format!("{}_months", months),
+    }
+}
+
+/// Quotes the value if needed (RFC 4180). Values starting with a formula character are prefixed,
+/// so that a spreadsheet does not evaluate a plan name
+pub(crate) fn csv_field(value: &str) -> String {
+    let value = if value.starts_with(['=', '+', '-', '@']) {
+        format!("'{}", value)
+    } else {
+        value.to_string()
+    };
+
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn movement(applies_to: &str, movement_type: MrrMovementType, change: i64) -> ReportMovement {
+        ReportMovement {
+            applies_to: date(applies_to),
+            movement_type,
+            net_mrr_change: change,
+        }
+    }
+
+    #[test]
+    fn test_monthly_periods() {
+        let movements = vec![
+            movement("2023-12-10", MrrMovementType::NewBusiness, 10000),
+            movement("2023-12-20", MrrMovementType::NewBusiness, 10000),
+            movement("2024-01-05", MrrMovementType::Expansion, 2000),
+            movement("2024-01-15", MrrMovementType::Churn, -10000),
+            movement("2024-02-01", MrrMovementType::NewBusiness, 5000),
+            movement("2024-02-10", MrrMovementType::Contraction, -1000),
+        ];
+
+        let periods = build_mrr_report_periods(
+            &movements,
+            date("2024-01-10"),
+            date("2024-02-29"),
+            ReportGranularity::Month,
+        );
+
+        assert_eq!(periods.len(), 2);
+
+        let january = &periods[0];
+        assert_eq!(january.start_date, date("2024-01-10"));
+        assert_eq!(january.end_date, date("2024-01-31"));
+        assert_eq!(january.starting_mrr, 22000);
+        assert_eq!(january.starting_subscriptions, 2);
+        assert_eq!(january.churn, -10000);
+        assert_eq!(january.ending_mrr, 12000);
+        assert_eq!(january.ending_subscriptions, 1);
+        assert_eq!(january.subscription_churn_rate(), Some(50.0));
+        assert_eq!(january.revenue_churn_rate(), Some(45.45));
+        assert_eq!(january.net_revenue_retention(), Some(54.55));
+        assert_eq!(january.arpu(), Some(12000));
+
+        let february = &periods[1];
+        assert_eq!(february.start_date, date("2024-02-01"));
+        assert_eq!(february.end_date, date("2024-02-29"));
+        assert_eq!(february.new_business, 5000);
+        assert_eq!(february.net_new_mrr(), 4000);
+        assert_eq!(february.net_revenue_retention(), Some(91.67));
+        assert_eq!(february.arpu(), Some(8000));
+    }
+
+    #[test]
+    fn test_rates_without_starting_mrr() {
+        let periods = build_mrr_report_periods(
+            &[movement("2024-01-03", MrrMovementType::NewBusiness, 10000)],
+            date("2024-01-01"),
+            date("2024-01-14"),
+            ReportGranularity::Week,
+        );
+
+        // 2024-01-01 is a monday
+        assert_eq!(periods.len(), 2);
+        assert_eq!(periods[0].revenue_churn_rate(), None);
+        assert_eq!(periods[0].net_revenue_retention(), None);
+        assert_eq!(periods[1].starting_mrr, 10000);
+        assert_eq!(periods[1].net_revenue_retention(), Some(100.0));
+    }
+
+    #[test]
+    fn test_count_report_periods() {
+        assert_eq!(
+            count_report_periods(
+                date("2024-01-15"),
+                date("2024-12-31"),
+                ReportGranularity::Quarter
+            ),
+            4
+        );
+        assert_eq!(
+            count_report_periods(
+                date("2024-01-01"),
+                date("2024-01-31"),
+                ReportGranularity::Day
+            ),
+            31
+        );
+        assert_eq!(
+            count_report_periods(
+                date("2024-02-01"),
+                date("2024-01-31"),
+                ReportGranularity::Year
+            ),
+            0
+        );
+    }
+
+    fn segment(plan_name: &str, country: Option<&str>) -> SubscriptionSegment {
+        SubscriptionSegment {
+            plan_id: Uuid::nil(),
+            plan_name: plan_name.to_string(),
+            billing_period: BillingPeriodEnum::Monthly,
+            currency: "EUR".to_string(),
+            country: country.map(|c| c.to_string()),
+            subscriptions: 12,
+            previous_subscriptions: 10,
+            mrr_cents: 120000,
+            previous_mrr_cents: 125000,
+        }
+    }
+
+    #[test]
+    fn test_subscription_segments_csv() {
+        let report = SubscriptionSegmentReport {
+            as_of: date("2024-03-31"),
+            compare_to: date("2024-02-29"),
+            segments: vec![
+                segment("Pro", Some("FR")),
+                segment("Pro, \"annual\"", None),
+                segment("=HYPERLINK(1)", Some("DE")),
+            ],
+        };
+
+        let csv = report.to_csv();
+        let lines = csv.lines().collect::<Vec<_>>();
+
+        assert_eq!(lines.len(), 4);
+        assert_eq!(
+            lines[1],
+            "00000000-0000-0000-0000-000000000000,Pro,monthly,EUR,FR,12,10,2,120000,125000,-5000"
+        );
+        assert!(lines[2].starts_with(
+            "00000000-0000-0000-0000-000000000000,\"Pro, \"\"annual\"\"\",monthly,EUR,,"
+        ));
+        assert!(lines[3].contains(",'=HYPERLINK(1),"));
+    }
+}
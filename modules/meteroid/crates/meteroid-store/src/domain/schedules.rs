@@ -74,7 +74,7 @@ impl TryFrom<ScheduleRow> for Schedule {
     fn try_from(value: ScheduleRow) -> Result<Self, Self::Error> {
         Ok(Schedule {
             id: value.id,
-            billing_period: value.billing_period.into(),
+            billing_period: BillingPeriodEnum::from_row(value.billing_period, None),
             plan_version_id: value.plan_version_id,
             ramps: value.ramps.try_into()?,
         })
@@ -0,0 +1,120 @@
+use chrono::NaiveDateTime;
+use diesel_models::seat_increase_requests::{SeatIncreaseRequestRow, SeatIncreaseRequestRowNew};
+use o2o::o2o;
+use uuid::Uuid;
+
+use crate::domain::enums::SeatIncreaseRequestStatusEnum;
+use crate::domain::SlotUpdate;
+use crate::errors::StoreError;
+
+/// Seats an end customer asks to add to a slot component of its subscription, from the portal
+#[derive(Debug, Clone, PartialEq, Eq, o2o)]
+#[from_owned(SeatIncreaseRequestRow)]
+pub struct SeatIncreaseRequest {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Uuid,
+    pub seats: i32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+    #[from(~.into())]
+    pub status: SeatIncreaseRequestStatusEnum,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    /// not set when approved automatically
+    pub decided_by: Option<Uuid>,
+    pub decision_note: Option<String>,
+    /// the prorated charge of the added seats
+    pub invoice_id: Option<Uuid>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SeatIncreaseRequestNew {
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Uuid,
+    pub seats: i32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+}
+
+impl SeatIncreaseRequestNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.seats <= 0 {
+            return Err(StoreError::InvalidArgument(
+                "a seat increase request adds at least one seat".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Approved without review when within the limit of the tenant, or when the tenant has no limit
+    pub fn is_auto_approved(&self, auto_approve_limit: Option<i32>) -> bool {
+        auto_approve_limit.map_or(true, |limit| self.seats <= limit)
+    }
+
+    /// Approved with the seats already added when approved automatically, pending otherwise
+    pub fn into_row(
+        self,
+        now: NaiveDateTime,
+        auto_approved: Option<&SlotUpdate>,
+    ) -> SeatIncreaseRequestRowNew {
+        let (status, decided_at, invoice_id) = match auto_approved {
+            Some(update) => (
+                SeatIncreaseRequestStatusEnum::Approved,
+                Some(now),
+                update.invoice_id,
+            ),
+            None => (SeatIncreaseRequestStatusEnum::Pending, None, None),
+        };
+
+        SeatIncreaseRequestRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: self.tenant_id,
+            subscription_id: self.subscription_id,
+            price_component_id: self.price_component_id,
+            seats: self.seats,
+            requested_by: self.requested_by.filter(|r| !r.trim().is_empty()),
+            note: self.note.filter(|n| !n.trim().is_empty()),
+            status: status.into(),
+            decided_at,
+            invoice_id,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn new(seats: i32) -> SeatIncreaseRequestNew {
+        SeatIncreaseRequestNew {
+            tenant_id: Uuid::nil(),
+            subscription_id: Uuid::nil(),
+            price_component_id: Uuid::nil(),
+            seats,
+            requested_by: Some("jane@acme.com".to_string()),
+            note: Some(" ".to_string()),
+        }
+    }
+
+    #[test]
+    fn test_auto_approval() {
+        assert!(new(50).is_auto_approved(None));
+        assert!(new(10).is_auto_approved(Some(10)));
+        assert!(!new(11).is_auto_approved(Some(10)));
+        assert!(!new(1).is_auto_approved(Some(0)));
+
+        assert!(new(0).validate().is_err());
+
+        let row = new(11).into_row(NaiveDateTime::default(), None);
+        assert_eq!(
+            row.status,
+            diesel_models::enums::SeatIncreaseRequestStatusEnum::Pending
+        );
+        assert_eq!(row.decided_at, None);
+        assert_eq!(row.note, None);
+    }
+}
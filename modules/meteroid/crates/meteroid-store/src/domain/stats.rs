@@ -1,4 +1,4 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use uuid::Uuid;
 
 pub enum TrendScope {
@@ -18,6 +18,18 @@ pub struct Trend {
     pub scope: TrendScope,
 }
 
+/// The dashboard figures, all computed from the same snapshot of the data
+pub struct GeneralStats {
+    pub net_revenue: Trend,
+    pub active_subscriptions: i64,
+    pub pending_invoices: CountAndValue,
+    pub signups: Trend,
+    pub trial_conversion_rate: f32,
+    pub total_mrr: i64,
+    /// the time of the snapshot
+    pub as_of: NaiveDateTime,
+}
+
 pub struct SignupDataPoint {
     pub x: String,
     pub total: i64,
@@ -69,15 +81,37 @@ pub struct RevenueByCustomerRequest {
     pub currency: Option<String>,
 }
 
+/// How amounts booked in other currencies are restated in the tenant reporting currency.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum FxRestatementMode {
+    /// Each amount is converted at the rate in effect when it was booked.
+    #[default]
+    HistoricalRates,
+    /// All amounts are converted at the rates in effect on a single date, removing FX fluctuations.
+    ConstantCurrency { rate_date: NaiveDate },
+}
+
+impl FxRestatementMode {
+    pub fn constant_rate_date(&self) -> Option<NaiveDate> {
+        match self {
+            FxRestatementMode::HistoricalRates => None,
+            FxRestatementMode::ConstantCurrency { rate_date } => Some(*rate_date),
+        }
+    }
+}
+
 pub struct MrrChartRequest {
     pub tenant_id: Uuid,
     pub start_date: NaiveDate,
     pub end_date: NaiveDate,
     pub plans_id: Option<Vec<Uuid>>,
+    pub fx_mode: FxRestatementMode,
 }
 
 pub struct MrrChartResponse {
     pub series: Vec<MrrChartSeries>,
+    /// the time of the snapshot the series are computed from
+    pub as_of: NaiveDateTime,
 }
 
 pub struct MrrChartDataPoint {
@@ -144,6 +178,7 @@ impl MRRBreakdownScope {
 pub struct MRRBreakdownRequest {
     pub scope: MRRBreakdownScope,
     pub tenant_id: Uuid,
+    pub fx_mode: FxRestatementMode,
 }
 
 pub struct CountAndValue {
@@ -162,6 +197,12 @@ pub struct MRRBreakdown {
     // scheduled_mrr_movements: CountAndValue,
 }
 
+pub struct MRRBreakdownResponse {
+    pub breakdown: MRRBreakdown,
+    /// the time of the snapshot the breakdown is computed from
+    pub as_of: NaiveDateTime,
+}
+
 pub struct MrrLogRequest {
     pub tenant_id: Uuid,
     pub before: Option<String>,
@@ -173,6 +214,11 @@ pub struct MrrLogResponse {
     pub entries: Vec<MrrLogEntry>,
 }
 
+pub struct MrrRebuildSummary {
+    pub invoices_processed: usize,
+    pub movements_recorded: usize,
+}
+
 pub enum MrrMovementType {
     NewBusiness,
     Expansion,
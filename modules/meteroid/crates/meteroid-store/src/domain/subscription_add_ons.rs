@@ -1,5 +1,5 @@
 use crate::domain::enums::{BillingPeriodEnum, SubscriptionFeeBillingPeriod};
-use crate::domain::{SubscriptionFee, SubscriptionFeeInterface};
+use crate::domain::{Period, SubscriptionFee, SubscriptionFeeInterface};
 use crate::errors::StoreError;
 use chrono::NaiveDateTime;
 use diesel_models::subscription_add_ons::{SubscriptionAddOnRow, SubscriptionAddOnRowNew};
@@ -18,6 +18,11 @@ pub struct SubscriptionAddOn {
 }
 
 impl SubscriptionFeeInterface for SubscriptionAddOn {
+    #[inline]
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
     #[inline]
     fn price_component_id(&self) -> Option<Uuid> {
         None
@@ -61,7 +66,7 @@ impl TryInto<SubscriptionAddOn> for SubscriptionAddOnRow {
             subscription_id: self.subscription_id,
             add_on_id: self.add_on_id,
             name: self.name,
-            period: self.period.into(),
+            period: SubscriptionFeeBillingPeriod::from_row(self.period, self.billing_period_months),
             fee: decoded_fee,
             created_at: self.created_at,
         })
@@ -94,6 +99,7 @@ impl TryInto<SubscriptionAddOnRowNew> for SubscriptionAddOnNew {
             subscription_id: self.subscription_id,
             add_on_id: self.internal.add_on_id,
             name: self.internal.name,
+            billing_period_months: self.internal.period.custom_months(),
             period: self.internal.period.into(),
             fee,
         })
@@ -131,3 +137,15 @@ pub struct CreateSubscriptionAddOn {
 pub struct CreateSubscriptionAddOns {
     pub add_ons: Vec<CreateSubscriptionAddOn>,
 }
+
+/// Add-on attached to or detached from a live subscription
+#[derive(Debug, Clone)]
+pub struct SubscriptionAddOnUpdate {
+    pub add_on: SubscriptionAddOn,
+    /// the current period of the add-on
+    pub period: Period,
+    /// charged (positive) for the rest of the period when attached, credited (negative) to the customer balance when
+    /// detached, in cents
+    pub prorated_amount: i64,
+    pub invoice_id: Option<Uuid>,
+}
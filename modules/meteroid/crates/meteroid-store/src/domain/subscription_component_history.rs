@@ -0,0 +1,318 @@
+use chrono::NaiveDateTime;
+use diesel_models::enums::SubscriptionComponentChangeTypeEnum;
+use diesel_models::subscription_component_history::{
+    SubscriptionComponentHistoryRow, SubscriptionComponentHistoryRowNew,
+};
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::{SubscriptionComponent, SubscriptionComponentNewInternal, SubscriptionFee};
+use crate::errors::StoreError;
+
+/// A parameter of a subscription component, before and after the change
+#[derive(Debug, Clone)]
+pub enum ComponentParameterChange {
+    Slots {
+        previous: u32,
+        new: u32,
+    },
+    CommittedCapacity {
+        previous: SubscriptionFee,
+        new: SubscriptionFee,
+    },
+    /// any other change of the fee, like an override of the rate
+    Fee {
+        previous: SubscriptionFee,
+        new: SubscriptionFee,
+    },
+}
+
+impl ComponentParameterChange {
+    fn change_type(&self) -> SubscriptionComponentChangeTypeEnum {
+        match self {
+            ComponentParameterChange::Slots { .. } => SubscriptionComponentChangeTypeEnum::Slots,
+            ComponentParameterChange::CommittedCapacity { .. } => {
+                SubscriptionComponentChangeTypeEnum::CommittedCapacity
+            }
+            ComponentParameterChange::Fee { .. } => SubscriptionComponentChangeTypeEnum::Fee,
+        }
+    }
+
+    /// The fee replaced by this change. Slot changes keep the fee as is
+    pub fn previous_fee(&self) -> Option<&SubscriptionFee> {
+        match self {
+            ComponentParameterChange::Slots { .. } => None,
+            ComponentParameterChange::CommittedCapacity { previous, .. }
+            | ComponentParameterChange::Fee { previous, .. } => Some(previous),
+        }
+    }
+
+    /// The change between two fees of the same component, if they differ
+    pub fn between_fees(previous: &SubscriptionFee, new: &SubscriptionFee) -> Option<Self> {
+        let unchanged = serde_json::to_value(previous).ok() == serde_json::to_value(new).ok();
+
+        match (previous, new) {
+            _ if unchanged => None,
+            (SubscriptionFee::Capacity { .. }, SubscriptionFee::Capacity { .. }) => {
+                Some(ComponentParameterChange::CommittedCapacity {
+                    previous: previous.clone(),
+                    new: new.clone(),
+                })
+            }
+            _ => Some(ComponentParameterChange::Fee {
+                previous: previous.clone(),
+                new: new.clone(),
+            }),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionComponentHistory {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Option<Uuid>,
+    pub component_name: String,
+    pub change: ComponentParameterChange,
+    pub effective_at: NaiveDateTime,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<SubscriptionComponentHistoryRow> for SubscriptionComponentHistory {
+    type Error = Report<StoreError>;
+
+    fn try_from(row: SubscriptionComponentHistoryRow) -> Result<Self, Self::Error> {
+        let deserialization_error = |e| {
+            StoreError::SerdeError(
+                "Failed to deserialize subscription component history".to_string(),
+                e,
+            )
+        };
+
+        let change = match row.change_type {
+            SubscriptionComponentChangeTypeEnum::Slots => ComponentParameterChange::Slots {
+                previous: serde_json::from_value(row.previous_value)
+                    .map_err(deserialization_error)?,
+                new: serde_json::from_value(row.new_value).map_err(deserialization_error)?,
+            },
+            SubscriptionComponentChangeTypeEnum::CommittedCapacity => {
+                ComponentParameterChange::CommittedCapacity {
+                    previous: serde_json::from_value(row.previous_value)
+                        .map_err(deserialization_error)?,
+                    new: serde_json::from_value(row.new_value).map_err(deserialization_error)?,
+                }
+            }
+            SubscriptionComponentChangeTypeEnum::Fee => ComponentParameterChange::Fee {
+                previous: serde_json::from_value(row.previous_value)
+                    .map_err(deserialization_error)?,
+                new: serde_json::from_value(row.new_value).map_err(deserialization_error)?,
+            },
+        };
+
+        Ok(SubscriptionComponentHistory {
+            id: row.id,
+            subscription_id: row.subscription_id,
+            price_component_id: row.price_component_id,
+            component_name: row.component_name,
+            change,
+            effective_at: row.effective_at,
+            created_at: row.created_at,
+            created_by: row.created_by,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionComponentHistoryNew {
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Option<Uuid>,
+    pub component_name: String,
+    pub change: ComponentParameterChange,
+    pub effective_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<SubscriptionComponentHistoryNew> for SubscriptionComponentHistoryRowNew {
+    type Error = Report<StoreError>;
+
+    fn try_from(value: SubscriptionComponentHistoryNew) -> Result<Self, Self::Error> {
+        let serialization_error = |e| {
+            StoreError::SerdeError(
+                "Failed to serialize subscription component history".to_string(),
+                e,
+            )
+        };
+
+        let (previous_value, new_value) = match &value.change {
+            ComponentParameterChange::Slots { previous, new } => (
+                serde_json::to_value(previous).map_err(serialization_error)?,
+                serde_json::to_value(new).map_err(serialization_error)?,
+            ),
+            ComponentParameterChange::CommittedCapacity { previous, new }
+            | ComponentParameterChange::Fee { previous, new } => (
+                serde_json::to_value(previous).map_err(serialization_error)?,
+                serde_json::to_value(new).map_err(serialization_error)?,
+            ),
+        };
+
+        Ok(SubscriptionComponentHistoryRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: value.tenant_id,
+            subscription_id: value.subscription_id,
+            price_component_id: value.price_component_id,
+            component_name: value.component_name,
+            change_type: value.change.change_type(),
+            previous_value,
+            new_value,
+            effective_at: value.effective_at,
+            created_by: value.created_by,
+        })
+    }
+}
+
+/// Fee changes between the current components of a subscription and the ones replacing them, matched by price component
+pub fn component_fee_changes(
+    current: &[SubscriptionComponent],
+    replacements: &[SubscriptionComponentNewInternal],
+) -> Vec<(Option<Uuid>, String, ComponentParameterChange)> {
+    replacements
+        .iter()
+        .filter_map(|replacement| {
+            let price_component_id = replacement.price_component_id?;
+            let previous = current
+                .iter()
+                .find(|c| c.price_component_id == Some(price_component_id))?;
+
+            ComponentParameterChange::between_fees(&previous.fee, &replacement.fee)
+                .map(|change| (Some(price_component_id), replacement.name.clone(), change))
+        })
+        .collect()
+}
+
+/// The components with the fees in effect at `at`. The components hold the latest fees,
+/// so a fee replaced after `at` is restored from the earliest change that replaced it
+pub fn components_at(
+    components: &[SubscriptionComponent],
+    history: &[SubscriptionComponentHistory],
+    at: NaiveDateTime,
+) -> Vec<SubscriptionComponent> {
+    components
+        .iter()
+        .map(|component| {
+            let replaced_fee = history
+                .iter()
+                .filter(|h| {
+                    component.price_component_id.is_some()
+                        && h.price_component_id == component.price_component_id
+                        && h.effective_at > at
+                })
+                .filter_map(|h| h.change.previous_fee().map(|fee| (h.effective_at, fee)))
+                .min_by_key(|(effective_at, _)| *effective_at)
+                .map(|(_, fee)| fee.clone());
+
+            SubscriptionComponent {
+                fee: replaced_fee.unwrap_or_else(|| component.fee.clone()),
+                ..component.clone()
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::enums::SubscriptionFeeBillingPeriod;
+    use rust_decimal_macros::dec;
+
+    fn capacity(included: u64) -> SubscriptionFee {
+        SubscriptionFee::Capacity {
+            rate: dec!(100),
+            included,
+            overage_rate: dec!(0.1),
+            metric_id: Uuid::nil(),
+            cap: None,
+        }
+    }
+
+    fn datetime(s: &str) -> NaiveDateTime {
+        NaiveDateTime::parse_from_str(s, "%Y-%m-%dT%H:%M:%S").unwrap()
+    }
+
+    fn change(
+        price_component_id: Uuid,
+        previous: SubscriptionFee,
+        new: SubscriptionFee,
+        effective_at: &str,
+    ) -> SubscriptionComponentHistory {
+        SubscriptionComponentHistory {
+            id: Uuid::now_v7(),
+            subscription_id: Uuid::nil(),
+            price_component_id: Some(price_component_id),
+            component_name: "API calls".to_string(),
+            change: ComponentParameterChange::between_fees(&previous, &new).unwrap(),
+            effective_at: datetime(effective_at),
+            created_at: datetime(effective_at),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    #[test]
+    fn test_components_at_restores_replaced_fees() {
+        let price_component_id = Uuid::now_v7();
+        let component = SubscriptionComponent {
+            id: Uuid::now_v7(),
+            price_component_id: Some(price_component_id),
+            product_item_id: None,
+            subscription_id: Uuid::nil(),
+            name: "API calls".to_string(),
+            period: SubscriptionFeeBillingPeriod::Monthly,
+            fee: capacity(3000),
+        };
+
+        let history = vec![
+            change(
+                price_component_id,
+                capacity(1000),
+                capacity(2000),
+                "2024-02-01T00:00:00",
+            ),
+            change(
+                price_component_id,
+                capacity(2000),
+                capacity(3000),
+                "2024-03-01T00:00:00",
+            ),
+        ];
+
+        let included_at =
+            |at: &str| match components_at(&[component.clone()], &history, datetime(at))[0].fee {
+                SubscriptionFee::Capacity { included, .. } => included,
+                _ => panic!("not a capacity fee"),
+            };
+
+        assert_eq!(included_at("2024-01-15T00:00:00"), 1000);
+        assert_eq!(included_at("2024-02-01T00:00:00"), 2000);
+        assert_eq!(included_at("2024-02-15T00:00:00"), 2000);
+        assert_eq!(included_at("2024-03-01T00:00:00"), 3000);
+    }
+
+    #[test]
+    fn test_between_fees() {
+        assert!(ComponentParameterChange::between_fees(&capacity(1000), &capacity(1000)).is_none());
+
+        assert!(matches!(
+            ComponentParameterChange::between_fees(&capacity(1000), &capacity(2000)),
+            Some(ComponentParameterChange::CommittedCapacity { .. })
+        ));
+
+        assert!(matches!(
+            ComponentParameterChange::between_fees(
+                &SubscriptionFee::Rate { rate: dec!(10) },
+                &SubscriptionFee::Rate { rate: dec!(12) }
+            ),
+            Some(ComponentParameterChange::Fee { .. })
+        ));
+    }
+}
@@ -5,10 +5,11 @@ use diesel_models::subscription_components::{
 use serde::{Deserialize, Serialize};
 use uuid::Uuid;
 
-use crate::domain::UsagePricingModel;
+use crate::domain::{DowngradePolicy, UpgradePolicy, UsagePricingModel};
 use crate::errors::StoreError;
 
 pub trait SubscriptionFeeInterface {
+    fn id(&self) -> Uuid;
     fn price_component_id(&self) -> Option<Uuid>;
     fn product_item_id(&self) -> Option<Uuid>;
     fn subscription_id(&self) -> Uuid;
@@ -29,6 +30,11 @@ pub struct SubscriptionComponent {
 }
 
 impl SubscriptionFeeInterface for SubscriptionComponent {
+    #[inline]
+    fn id(&self) -> Uuid {
+        self.id
+    }
+
     #[inline]
     fn price_component_id(&self) -> Option<Uuid> {
         self.price_component_id
@@ -73,7 +79,7 @@ impl TryInto<SubscriptionComponent> for SubscriptionComponentRow {
             product_item_id: self.product_item_id,
             subscription_id: self.subscription_id,
             name: self.name,
-            period: self.period.into(),
+            period: SubscriptionFeeBillingPeriod::from_row(self.period, self.billing_period_months),
             fee: decoded_fee,
         })
     }
@@ -108,13 +114,14 @@ impl TryInto<SubscriptionComponentRowNew> for SubscriptionComponentNew {
             price_component_id: self.internal.price_component_id,
             product_item_id: self.internal.product_item_id,
             name: self.internal.name,
+            billing_period_months: self.internal.period.custom_months(),
             period: self.internal.period.into(),
             fee,
         })
     }
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CreateSubscriptionComponents {
     pub parameterized_components: Vec<ComponentParameterization>,
     pub overridden_components: Vec<ComponentOverride>,
@@ -122,31 +129,31 @@ pub struct CreateSubscriptionComponents {
     pub remove_components: Vec<Uuid>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentParameterization {
     pub component_id: Uuid,
     pub parameters: ComponentParameters,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentParameters {
     pub initial_slot_count: Option<u32>,
     pub billing_period: Option<BillingPeriodEnum>,
     pub committed_capacity: Option<u64>,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ComponentOverride {
     pub component_id: Uuid,
     pub component: SubscriptionComponentNewInternal,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ExtraComponent {
     pub component: SubscriptionComponentNewInternal,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SubscriptionComponentNewInternal {
     pub price_component_id: Option<Uuid>,
     pub product_item_id: Option<Uuid>,
@@ -177,6 +184,8 @@ pub enum SubscriptionFee {
         included: u64,
         overage_rate: rust_decimal::Decimal,
         metric_id: Uuid,
+        /// maximum overage billed per period
+        cap: Option<rust_decimal::Decimal>,
     },
     Slot {
         unit: String,
@@ -184,11 +193,24 @@ pub enum SubscriptionFee {
         min_slots: Option<u32>,
         max_slots: Option<u32>,
         initial_slots: u32,
-        // upgrade downgrade policies TODO
+        #[serde(default)]
+        upgrade_policy: UpgradePolicy,
+        #[serde(default)]
+        downgrade_policy: DowngradePolicy,
     },
     Usage {
         metric_id: Uuid,
         model: UsagePricingModel,
+        /// maximum usage charges billed per period
+        cap: Option<rust_decimal::Decimal>,
+        /// units included for free each period, the usage above it is priced
+        included: Option<u64>,
+    },
+    Percent {
+        metric_id: Uuid,
+        percentage: rust_decimal::Decimal,
+        minimum: Option<rust_decimal::Decimal>,
+        maximum: Option<rust_decimal::Decimal>,
     },
 }
 
@@ -197,6 +219,17 @@ impl SubscriptionFee {
         match self {
             SubscriptionFee::Usage { metric_id, .. } => Some(*metric_id),
             SubscriptionFee::Capacity { metric_id, .. } => Some(*metric_id),
+            SubscriptionFee::Percent { metric_id, .. } => Some(*metric_id),
+            _ => None,
+        }
+    }
+
+    /// The maximum billed per period for the usage charges, or the overage of a capacity
+    pub fn cap(&self) -> Option<rust_decimal::Decimal> {
+        match self {
+            SubscriptionFee::Usage { cap, .. } => *cap,
+            SubscriptionFee::Capacity { cap, .. } => *cap,
+            SubscriptionFee::Percent { maximum, .. } => *maximum,
             _ => None,
         }
     }
@@ -211,7 +244,8 @@ impl SubscriptionFee {
             | SubscriptionFee::Capacity { .. } => true,
             SubscriptionFee::OneTime { .. }
             | SubscriptionFee::Recurring { .. }
-            | SubscriptionFee::Usage { .. } => false,
+            | SubscriptionFee::Usage { .. }
+            | SubscriptionFee::Percent { .. } => false,
         }
     }
 }
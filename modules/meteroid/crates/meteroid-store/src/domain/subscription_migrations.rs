@@ -0,0 +1,322 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use diesel_models::subscription_migrations::{
+    SubscriptionMigrationItemRow, SubscriptionMigrationRow,
+};
+use uuid::Uuid;
+
+use crate::domain::enums::{
+    BillingPeriodEnum, SubscriptionMigrationItemStatusEnum, SubscriptionMigrationStrategyEnum,
+};
+use crate::domain::{
+    ComponentParameterization, ComponentParameters, CreateSubscriptionComponents, ExtraComponent,
+    FeeType, PriceComponent, SubscriptionComponent, SubscriptionComponentNewInternal,
+    SubscriptionFee,
+};
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionMigrationNew {
+    pub tenant_id: Uuid,
+    pub to_plan_version_id: Uuid,
+    /// only migrates the subscriptions on this version. All the previous versions of the plan otherwise
+    pub from_plan_version_id: Option<Uuid>,
+    pub strategy: SubscriptionMigrationStrategyEnum,
+    /// also migrates the subscriptions grandfathered by a previous migration of the plan
+    pub include_grandfathered: bool,
+    pub created_by: Uuid,
+}
+
+/// A bulk move of the subscriptions of a plan to one of its versions.
+/// The counters are updated after each batch, so they report the progress of a running migration
+#[derive(Debug, Clone)]
+pub struct SubscriptionMigration {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub plan_id: Uuid,
+    pub to_plan_version_id: Uuid,
+    pub strategy: SubscriptionMigrationStrategyEnum,
+    pub total_count: u32,
+    pub processed_count: u32,
+    pub failed_count: u32,
+    /// MRR change of the migrated and scheduled subscriptions, in cents
+    pub mrr_delta: i64,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub completed_at: Option<NaiveDateTime>,
+}
+
+impl From<SubscriptionMigrationRow> for SubscriptionMigration {
+    fn from(row: SubscriptionMigrationRow) -> Self {
+        Self {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            plan_id: row.plan_id,
+            to_plan_version_id: row.to_plan_version_id,
+            strategy: row.strategy.into(),
+            total_count: row.total_count.max(0) as u32,
+            processed_count: row.processed_count.max(0) as u32,
+            failed_count: row.failed_count.max(0) as u32,
+            mrr_delta: row.mrr_delta,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            completed_at: row.completed_at,
+        }
+    }
+}
+
+/// Outcome of the migration for a subscription
+#[derive(Debug, Clone)]
+pub struct SubscriptionMigrationItem {
+    pub id: Uuid,
+    pub migration_id: Uuid,
+    pub subscription_id: Uuid,
+    pub from_plan_version_id: Uuid,
+    pub status: SubscriptionMigrationItemStatusEnum,
+    /// MRR change, in cents. Projected when the switch is scheduled for the next renewal
+    pub mrr_delta: Option<i64>,
+    /// amount invoiced (positive) or credited (negative) for the rest of the current period, in cents
+    pub prorated_amount: Option<i64>,
+    pub error: Option<String>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<SubscriptionMigrationItemRow> for SubscriptionMigrationItem {
+    fn from(row: SubscriptionMigrationItemRow) -> Self {
+        Self {
+            id: row.id,
+            migration_id: row.migration_id,
+            subscription_id: row.subscription_id,
+            from_plan_version_id: row.from_plan_version_id,
+            status: row.status.into(),
+            mrr_delta: row.mrr_delta,
+            prorated_amount: row.prorated_amount,
+            error: row.error,
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// Parameters of the components of the target version, carried over from the current components of a subscription.
+/// Components are matched on their product, then on their name. A matched component keeps its billing period,
+/// slots and committed capacity when the target component offers them.
+/// Components added to the subscription outside of its plan are kept as extra components
+pub fn carry_over_components(
+    current: &[SubscriptionComponent],
+    current_slots: &HashMap<Uuid, u32>,
+    target: &[PriceComponent],
+    subscription_period: &BillingPeriodEnum,
+) -> CreateSubscriptionComponents {
+    let mut parameterized_components = Vec::new();
+
+    for price_component in target {
+        let matched = current
+            .iter()
+            .filter(|c| c.price_component_id.is_some())
+            .find(|c| {
+                c.product_item_id.is_some() && c.product_item_id == price_component.product_item_id
+            })
+            .or_else(|| {
+                current
+                    .iter()
+                    .filter(|c| c.price_component_id.is_some())
+                    .find(|c| c.name == price_component.name)
+            });
+
+        let previous_period = matched.and_then(|c| c.period.as_billing_period_opt());
+
+        let pick_period = |terms: Vec<&BillingPeriodEnum>| {
+            previous_period
+                .as_ref()
+                .filter(|p| terms.contains(p))
+                .or_else(|| terms.iter().find(|t| **t == subscription_period).copied())
+                .or_else(|| terms.first().copied())
+                .cloned()
+        };
+
+        let parameters = match &price_component.fee {
+            FeeType::Rate { rates } if rates.len() > 1 => ComponentParameters {
+                initial_slot_count: None,
+                billing_period: pick_period(rates.iter().map(|r| &r.term).collect()),
+                committed_capacity: None,
+            },
+            FeeType::Slot { rates, .. } => ComponentParameters {
+                initial_slot_count: matched.and_then(|c| match &c.fee {
+                    SubscriptionFee::Slot { initial_slots, .. } => {
+                        Some(current_slots.get(&c.id).copied().unwrap_or(*initial_slots))
+                    }
+                    _ => None,
+                }),
+                billing_period: pick_period(rates.iter().map(|r| &r.term).collect()),
+                committed_capacity: None,
+            },
+            FeeType::Capacity { thresholds, .. } if thresholds.len() > 1 => ComponentParameters {
+                initial_slot_count: None,
+                billing_period: None,
+                committed_capacity: matched
+                    .and_then(|c| match &c.fee {
+                        SubscriptionFee::Capacity { included, .. } => Some(*included),
+                        _ => None,
+                    })
+                    .filter(|included| thresholds.iter().any(|t| t.included_amount == *included)),
+            },
+            _ => continue,
+        };
+
+        parameterized_components.push(ComponentParameterization {
+            component_id: price_component.id,
+            parameters,
+        });
+    }
+
+    let extra_components = current
+        .iter()
+        .filter(|c| c.price_component_id.is_none())
+        .map(|c| ExtraComponent {
+            component: SubscriptionComponentNewInternal {
+                price_component_id: None,
+                product_item_id: c.product_item_id,
+                name: c.name.clone(),
+                period: c.period.clone(),
+                fee: c.fee.clone(),
+                is_override: false,
+            },
+        })
+        .collect();
+
+    CreateSubscriptionComponents {
+        parameterized_components,
+        overridden_components: vec![],
+        extra_components,
+        remove_components: vec![],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::enums::SubscriptionFeeBillingPeriod;
+    use crate::domain::{DowngradePolicy, TermRate, UpgradePolicy};
+    use rust_decimal_macros::dec;
+
+    fn rates(terms: &[BillingPeriodEnum]) -> Vec<TermRate> {
+        terms
+            .iter()
+            .map(|term| TermRate {
+                term: term.clone(),
+                price: dec!(10),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_carry_over_components() {
+        let product_item_id = Uuid::now_v7();
+
+        let current = vec![
+            SubscriptionComponent {
+                id: Uuid::now_v7(),
+                price_component_id: Some(Uuid::now_v7()),
+                product_item_id: Some(product_item_id),
+                subscription_id: Uuid::nil(),
+                name: "Platform".to_string(),
+                period: SubscriptionFeeBillingPeriod::Annual,
+                fee: SubscriptionFee::Rate { rate: dec!(100) },
+            },
+            SubscriptionComponent {
+                id: Uuid::now_v7(),
+                price_component_id: Some(Uuid::now_v7()),
+                product_item_id: None,
+                subscription_id: Uuid::nil(),
+                name: "Seats".to_string(),
+                period: SubscriptionFeeBillingPeriod::Monthly,
+                fee: SubscriptionFee::Slot {
+                    unit: "seat".to_string(),
+                    unit_rate: dec!(5),
+                    min_slots: None,
+                    max_slots: None,
+                    initial_slots: 2,
+                    upgrade_policy: UpgradePolicy::Prorated,
+                    downgrade_policy: DowngradePolicy::RemoveAtEndOfPeriod,
+                },
+            },
+            SubscriptionComponent {
+                id: Uuid::now_v7(),
+                price_component_id: None,
+                product_item_id: None,
+                subscription_id: Uuid::nil(),
+                name: "Onboarding".to_string(),
+                period: SubscriptionFeeBillingPeriod::OneTime,
+                fee: SubscriptionFee::OneTime {
+                    rate: dec!(50),
+                    quantity: 1,
+                },
+            },
+        ];
+
+        let target = vec![
+            PriceComponent {
+                id: Uuid::now_v7(),
+                name: "Platform fee".to_string(),
+                fee: FeeType::Rate {
+                    rates: rates(&[BillingPeriodEnum::Monthly, BillingPeriodEnum::Annual]),
+                },
+                product_item_id: Some(product_item_id),
+                rate_card_id: None,
+            },
+            PriceComponent {
+                id: Uuid::now_v7(),
+                name: "Seats".to_string(),
+                fee: FeeType::Slot {
+                    rates: rates(&[BillingPeriodEnum::Monthly]),
+                    slot_unit_name: "seat".to_string(),
+                    upgrade_policy: UpgradePolicy::Prorated,
+                    downgrade_policy: DowngradePolicy::RemoveAtEndOfPeriod,
+                    minimum_count: None,
+                    quota: None,
+                },
+                product_item_id: None,
+                rate_card_id: None,
+            },
+            PriceComponent {
+                id: Uuid::now_v7(),
+                name: "Support".to_string(),
+                fee: FeeType::Rate {
+                    rates: rates(&[BillingPeriodEnum::Monthly]),
+                },
+                product_item_id: None,
+                rate_card_id: None,
+            },
+        ];
+
+        let current_slots = HashMap::from([(current[1].id, 7)]);
+
+        let components = carry_over_components(
+            &current,
+            &current_slots,
+            &target,
+            &BillingPeriodEnum::Monthly,
+        );
+
+        // the single rate component needs no parameters
+        assert_eq!(components.parameterized_components.len(), 2);
+
+        let platform = &components.parameterized_components[0];
+        assert_eq!(platform.component_id, target[0].id);
+        assert_eq!(
+            platform.parameters.billing_period,
+            Some(BillingPeriodEnum::Annual)
+        );
+
+        let seats = &components.parameterized_components[1];
+        assert_eq!(seats.component_id, target[1].id);
+        assert_eq!(seats.parameters.initial_slot_count, Some(7));
+        assert_eq!(
+            seats.parameters.billing_period,
+            Some(BillingPeriodEnum::Monthly)
+        );
+
+        assert_eq!(components.extra_components.len(), 1);
+        assert_eq!(components.extra_components[0].component.name, "Onboarding");
+    }
+}
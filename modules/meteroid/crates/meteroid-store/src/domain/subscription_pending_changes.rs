@@ -0,0 +1,129 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_models::enums::SubscriptionPendingChangeTypeEnum;
+use diesel_models::subscription_pending_changes::{
+    SubscriptionPendingChangeRow, SubscriptionPendingChangeRowNew,
+};
+use error_stack::Report;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::CreateSubscriptionComponents;
+use crate::errors::StoreError;
+
+/// A change of the subscription that takes effect at its next renewal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum SubscriptionChange {
+    /// moves the subscription to another plan version, with components parameterized as on creation
+    PlanSwitch {
+        plan_version_id: Uuid,
+        components: Option<CreateSubscriptionComponents>,
+    },
+    Cancellation {
+        reason: Option<String>,
+    },
+    /// re-parameterizes the components of the current plan version
+    ComponentChange {
+        components: CreateSubscriptionComponents,
+    },
+}
+
+impl SubscriptionChange {
+    fn change_type(&self) -> SubscriptionPendingChangeTypeEnum {
+        match self {
+            SubscriptionChange::PlanSwitch { .. } => SubscriptionPendingChangeTypeEnum::PlanSwitch,
+            SubscriptionChange::Cancellation { .. } => {
+                SubscriptionPendingChangeTypeEnum::Cancellation
+            }
+            SubscriptionChange::ComponentChange { .. } => {
+                SubscriptionPendingChangeTypeEnum::ComponentChange
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SubscriptionPendingChangeStatus {
+    Scheduled,
+    Applied,
+    Canceled,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionPendingChange {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub change: SubscriptionChange,
+    pub effective_at: NaiveDate,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub applied_at: Option<NaiveDateTime>,
+    pub canceled_at: Option<NaiveDateTime>,
+}
+
+impl SubscriptionPendingChange {
+    pub fn status(&self) -> SubscriptionPendingChangeStatus {
+        match (self.applied_at, self.canceled_at) {
+            (Some(_), _) => SubscriptionPendingChangeStatus::Applied,
+            (None, Some(_)) => SubscriptionPendingChangeStatus::Canceled,
+            (None, None) => SubscriptionPendingChangeStatus::Scheduled,
+        }
+    }
+}
+
+impl TryFrom<SubscriptionPendingChangeRow> for SubscriptionPendingChange {
+    type Error = Report<StoreError>;
+
+    fn try_from(row: SubscriptionPendingChangeRow) -> Result<Self, Self::Error> {
+        let change: SubscriptionChange = serde_json::from_value(row.details).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to deserialize subscription pending change".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(SubscriptionPendingChange {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            subscription_id: row.subscription_id,
+            change,
+            effective_at: row.effective_at,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            applied_at: row.applied_at,
+            canceled_at: row.canceled_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionPendingChangeNew {
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub change: SubscriptionChange,
+    pub effective_at: NaiveDate,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<SubscriptionPendingChangeNew> for SubscriptionPendingChangeRowNew {
+    type Error = Report<StoreError>;
+
+    fn try_from(value: SubscriptionPendingChangeNew) -> Result<Self, Self::Error> {
+        let details = serde_json::to_value(&value.change).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to serialize subscription pending change".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(SubscriptionPendingChangeRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: value.tenant_id,
+            subscription_id: value.subscription_id,
+            change_type: value.change.change_type(),
+            details,
+            effective_at: value.effective_at,
+            created_by: value.created_by,
+        })
+    }
+}
@@ -0,0 +1,288 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_models::subscription_phases::{SubscriptionPhaseRow, SubscriptionPhaseRowNew};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use rust_decimal_macros::dec;
+use uuid::Uuid;
+
+use crate::domain::adjustments::discount::StandardDiscount;
+use crate::domain::{LineItem, SubLineAttributes, SubLineItem};
+use crate::errors::StoreError;
+use crate::utils::decimals::ToUnit;
+use crate::utils::local_id::LocalId;
+
+/// A time-bound promotional phase of a subscription (ex: 3 months at a reduced rate).
+/// Standard pricing applies again at the end of the phase
+#[derive(Debug, Clone)]
+pub struct SubscriptionPhase {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub name: String,
+    pub start_date: NaiveDate,
+    /// exclusive
+    pub end_date: NaiveDate,
+    /// the fees of that plan price component only, all the fees when None
+    pub price_component_id: Option<Uuid>,
+    /// a percent of each fee, or an amount per invoice
+    pub discount: StandardDiscount,
+    pub created_at: NaiveDateTime,
+}
+
+impl SubscriptionPhase {
+    /// The lines billing a period that starts during the phase are discounted
+    pub fn applies_to(&self, line: &LineItem) -> bool {
+        self.start_date <= line.start_date
+            && line.start_date < self.end_date
+            && self
+                .price_component_id
+                .map_or(true, |id| line.price_component_id == Some(id))
+    }
+}
+
+impl TryFrom<SubscriptionPhaseRow> for SubscriptionPhase {
+    type Error = StoreError;
+
+    fn try_from(row: SubscriptionPhaseRow) -> Result<Self, Self::Error> {
+        let discount: StandardDiscount = serde_json::from_value(row.discount).map_err(|e| {
+            StoreError::SerdeError("Failed to deserialize phase discount".to_string(), e)
+        })?;
+
+        Ok(SubscriptionPhase {
+            id: row.id,
+            subscription_id: row.subscription_id,
+            name: row.name,
+            start_date: row.start_date,
+            end_date: row.end_date,
+            price_component_id: row.price_component_id,
+            discount,
+            created_at: row.created_at,
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct CreateSubscriptionPhase {
+    pub name: String,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub price_component_id: Option<Uuid>,
+    pub discount: StandardDiscount,
+}
+
+impl CreateSubscriptionPhase {
+    pub fn validate(&self, plan_price_component_ids: &[Uuid]) -> Result<(), StoreError> {
+        if self.start_date >= self.end_date {
+            return Err(StoreError::InvalidArgument(format!(
+                "phase {} must end after its start",
+                self.name
+            )));
+        }
+
+        if let StandardDiscount::Percent(percent) = &self.discount {
+            if percent.percentage <= Decimal::ZERO || percent.percentage > Decimal::ONE_HUNDRED {
+                return Err(StoreError::InvalidArgument(format!(
+                    "phase {} percentage must be between 0 and 100",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(price_component_id) = self.price_component_id {
+            if !plan_price_component_ids.contains(&price_component_id) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "price component {} of phase {} is not part of the plan",
+                    price_component_id, self.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn into_row(
+        self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> Result<SubscriptionPhaseRowNew, StoreError> {
+        let discount = serde_json::to_value(&self.discount).map_err(|e| {
+            StoreError::SerdeError("Failed to serialize phase discount".to_string(), e)
+        })?;
+
+        Ok(SubscriptionPhaseRowNew {
+            id: Uuid::now_v7(),
+            tenant_id,
+            subscription_id,
+            name: self.name,
+            start_date: self.start_date,
+            end_date: self.end_date,
+            price_component_id: self.price_component_id,
+            discount,
+        })
+    }
+}
+
+/// Discounts the lines covered by the phases, as a negative subline.
+/// An amount discount is spread over the covered lines of the invoice, in order
+pub fn apply_phases(lines: &mut [LineItem], phases: &[SubscriptionPhase], precision: u8) {
+    for phase in phases {
+        let mut remaining_amount = match &phase.discount {
+            StandardDiscount::Amount(amount) => amount.value_in_cents as i64,
+            StandardDiscount::Percent(_) => 0,
+        };
+
+        for line in lines.iter_mut().filter(|l| phase.applies_to(l)) {
+            let discount = match &phase.discount {
+                StandardDiscount::Percent(percent) => {
+                    (Decimal::from(line.total) * percent.percentage / Decimal::ONE_HUNDRED)
+                        .round_dp(0)
+                        .to_i64()
+                        .unwrap_or(0)
+                }
+                StandardDiscount::Amount(_) => remaining_amount,
+            }
+            .min(line.total);
+
+            if discount <= 0 {
+                continue;
+            }
+
+            remaining_amount -= discount;
+
+            line.sub_lines.push(SubLineItem {
+                local_id: LocalId::no_prefix(),
+                name: format!("Promotion: {}", phase.name),
+                total: -discount,
+                quantity: dec!(1),
+                unit_price: -discount.to_unit(precision),
+                attributes: Some(SubLineAttributes::Promotion { phase_id: phase.id }),
+            });
+            line.total -= discount;
+            line.subtotal -= discount;
+        }
+    }
+}
+
+/// Amount deducted from the line by the promotional phases
+pub fn line_promotion_discount(line: &LineItem) -> i64 {
+    -line
+        .sub_lines
+        .iter()
+        .filter(|s| matches!(s.attributes, Some(SubLineAttributes::Promotion { .. })))
+        .map(|s| s.total)
+        .sum::<i64>()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::domain::adjustments::discount::{Amount, Percent};
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn line(start: &str, total: i64, price_component_id: Option<Uuid>) -> LineItem {
+        LineItem {
+            local_id: LocalId::no_prefix(),
+            name: "Fee".to_string(),
+            total,
+            subtotal: total,
+            quantity: None,
+            unit_price: None,
+            start_date: date(start),
+            end_date: date(start) + chrono::Months::new(1),
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id,
+            product_id: None,
+            metric_id: None,
+            description: None,
+            quantity_display_precision: None,
+        }
+    }
+
+    fn phase(discount: StandardDiscount, price_component_id: Option<Uuid>) -> SubscriptionPhase {
+        SubscriptionPhase {
+            id: Uuid::now_v7(),
+            subscription_id: Uuid::now_v7(),
+            name: "Launch".to_string(),
+            start_date: date("2024-01-01"),
+            end_date: date("2024-04-01"),
+            price_component_id,
+            discount,
+            created_at: date("2024-01-01").and_hms_opt(0, 0, 0).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_apply_percent_phase() {
+        let component = Uuid::now_v7();
+        let phases = [phase(
+            StandardDiscount::Percent(Percent {
+                percentage: dec!(50),
+            }),
+            Some(component),
+        )];
+
+        let mut lines = vec![
+            line("2024-03-01", 1000, Some(component)),
+            line("2024-03-01", 1000, None),
+            line("2024-04-01", 1000, Some(component)),
+        ];
+        apply_phases(&mut lines, &phases, 2);
+
+        assert_eq!(lines[0].total, 500);
+        assert_eq!(lines[0].sub_lines.len(), 1);
+        assert_eq!(line_promotion_discount(&lines[0]), 500);
+        assert_eq!(lines[0].sub_lines[0].total, -500);
+        assert_eq!(lines[0].sub_lines[0].unit_price, dec!(-5));
+        // other component
+        assert_eq!(lines[1].total, 1000);
+        // after the phase
+        assert_eq!(lines[2].total, 1000);
+    }
+
+    #[test]
+    fn test_apply_amount_phase() {
+        let phases = [phase(
+            StandardDiscount::Amount(Amount {
+                value_in_cents: 1500,
+            }),
+            None,
+        )];
+
+        let mut lines = vec![
+            line("2024-01-01", 1000, None),
+            line("2024-01-01", 1000, None),
+        ];
+        apply_phases(&mut lines, &phases, 2);
+
+        assert_eq!(lines[0].total, 0);
+        assert_eq!(lines[1].total, 500);
+    }
+
+    #[test]
+    fn test_validate_phase() {
+        let component = Uuid::now_v7();
+        let create = |start: &str, end: &str, percentage: Decimal| CreateSubscriptionPhase {
+            name: "Launch".to_string(),
+            start_date: date(start),
+            end_date: date(end),
+            price_component_id: Some(component),
+            discount: StandardDiscount::Percent(Percent { percentage }),
+        };
+
+        assert!(create("2024-01-01", "2024-04-01", dec!(20))
+            .validate(&[component])
+            .is_ok());
+        assert!(create("2024-04-01", "2024-01-01", dec!(20))
+            .validate(&[component])
+            .is_err());
+        assert!(create("2024-01-01", "2024-04-01", dec!(120))
+            .validate(&[component])
+            .is_err());
+        assert!(create("2024-01-01", "2024-04-01", dec!(20))
+            .validate(&[])
+            .is_err());
+    }
+}
@@ -3,15 +3,18 @@ use chrono::{NaiveDate, NaiveDateTime};
 use o2o::o2o;
 use uuid::Uuid;
 
-use crate::domain::enums::BillingPeriodEnum;
+use crate::domain::enums::{BillingPeriodEnum, SubscriptionBillingModeEnum, TrialModeEnum};
 use crate::domain::subscription_add_ons::{CreateSubscriptionAddOns, SubscriptionAddOn};
+use crate::domain::subscription_phases::{CreateSubscriptionPhase, SubscriptionPhase};
 use crate::domain::{
     AppliedCouponDetailed, BillableMetric, CreateSubscriptionComponents, CreateSubscriptionCoupons,
-    Schedule, SubscriptionComponent,
+    LineItem, Period, Schedule, SubscriptionComponent,
 };
+use crate::errors::StoreError;
 use diesel_models::subscriptions::SubscriptionRowNew;
 use diesel_models::subscriptions::{
     SubscriptionForDisplayRow, SubscriptionInvoiceCandidateRow, SubscriptionRow,
+    SubscriptionTrialEndRow,
 };
 
 #[derive(Debug, Clone, o2o)]
@@ -35,8 +38,11 @@ pub struct CreatedSubscription {
     pub canceled_at: Option<NaiveDateTime>,
     pub cancellation_reason: Option<String>,
     pub mrr_cents: i64,
-    #[from(~.into())]
+    #[from(BillingPeriodEnum::from_row(~, @.billing_period_months))]
     pub period: BillingPeriodEnum,
+    #[from(~.into())]
+    pub billing_mode: SubscriptionBillingModeEnum,
+    pub minimum_commitment: Option<rust_decimal::Decimal>,
 }
 
 #[derive(Debug, Clone)]
@@ -66,6 +72,9 @@ pub struct Subscription {
     pub cancellation_reason: Option<String>,
     pub mrr_cents: u64,
     pub period: BillingPeriodEnum,
+    pub threshold_invoiced_until: Option<NaiveDate>,
+    pub billing_mode: SubscriptionBillingModeEnum,
+    pub minimum_commitment: Option<rust_decimal::Decimal>,
 }
 
 impl From<SubscriptionForDisplayRow> for Subscription {
@@ -94,7 +103,13 @@ impl From<SubscriptionForDisplayRow> for Subscription {
             canceled_at: val.subscription.canceled_at,
             cancellation_reason: val.subscription.cancellation_reason,
             mrr_cents: val.subscription.mrr_cents as u64,
-            period: val.subscription.period.into(),
+            period: BillingPeriodEnum::from_row(
+                val.subscription.period,
+                val.subscription.billing_period_months,
+            ),
+            threshold_invoiced_until: val.subscription.threshold_invoiced_until,
+            billing_mode: val.subscription.billing_mode.into(),
+            minimum_commitment: val.subscription.minimum_commitment,
         }
     }
 }
@@ -102,7 +117,9 @@ impl From<SubscriptionForDisplayRow> for Subscription {
 #[derive(Debug, Clone)]
 pub struct SubscriptionNew {
     pub customer_id: Uuid,
+    /// ignored in calendar mode, where periods renew on the 1st
     pub billing_day: i16,
+    pub billing_mode: SubscriptionBillingModeEnum,
     pub currency: String,
     pub trial_start_date: Option<NaiveDate>,
     pub billing_start_date: NaiveDate,
@@ -113,9 +130,24 @@ pub struct SubscriptionNew {
     pub invoice_memo: Option<String>,
     pub invoice_threshold: Option<rust_decimal::Decimal>,
     pub activated_at: Option<NaiveDateTime>,
+    /// minimum spend per billing period, trued-up on the invoice closing the period
+    pub minimum_commitment: Option<rust_decimal::Decimal>,
 }
 
 impl SubscriptionNew {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self
+            .minimum_commitment
+            .is_some_and(|c| c < rust_decimal::Decimal::ZERO)
+        {
+            return Err(StoreError::InvalidArgument(
+                "the minimum commitment cannot be negative".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
     pub fn map_to_row(
         self,
         period: BillingPeriodEnum,
@@ -125,7 +157,7 @@ impl SubscriptionNew {
         SubscriptionRowNew {
             id: uuid::Uuid::now_v7(),
             customer_id: self.customer_id,
-            billing_day: self.billing_day,
+            billing_day: self.billing_mode.anchor_day(self.billing_day) as i16,
             tenant_id,
             currency: self.currency,
             trial_start_date: self.trial_start_date,
@@ -142,7 +174,10 @@ impl SubscriptionNew {
                 self.activated_at
             },
             mrr_cents: 0,
+            billing_period_months: period.custom_months(),
             period: period.into(),
+            billing_mode: self.billing_mode.into(),
+            minimum_commitment: self.minimum_commitment,
         }
     }
 }
@@ -153,6 +188,46 @@ pub struct CreateSubscription {
     pub price_components: Option<CreateSubscriptionComponents>,
     pub add_ons: Option<CreateSubscriptionAddOns>,
     pub coupons: Option<CreateSubscriptionCoupons>,
+    /// promotional phases, applied on top of the standard pricing
+    pub phases: Vec<CreateSubscriptionPhase>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SubscriptionBatchFailure {
+    /// position of the item in the submitted batch
+    pub index: usize,
+    pub reason: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct CreatedSubscriptionsBatch {
+    /// created subscriptions, with their position in the submitted batch
+    pub created: Vec<(usize, CreatedSubscription)>,
+    pub failures: Vec<SubscriptionBatchFailure>,
+}
+
+#[derive(Debug, Clone)]
+pub struct SlotUpdate {
+    pub current_value: u32,
+    /// value once the removals scheduled for the end of the period are effective
+    pub next_period_value: u32,
+    pub period: Period,
+    /// immediate charge for the added slots, prorated to the remainder of the period
+    pub prorated_amount: i64,
+    pub invoice_id: Option<Uuid>,
+}
+
+/// Expected invoice of a subscription at a future billing date
+#[derive(Debug, Clone)]
+pub struct BillingScheduleEntry {
+    pub invoice_date: NaiveDate,
+    pub lines: Vec<LineItem>,
+    pub subtotal: i64,
+    /// discount from the plan ramp applying at that date
+    pub ramp_discount: i64,
+    pub total: i64,
+    /// first billing date after the trial
+    pub is_trial_end: bool,
 }
 
 #[derive(Debug, Clone)]
@@ -172,6 +247,7 @@ pub struct SubscriptionDetails {
     pub price_components: Vec<SubscriptionComponent>,
     pub add_ons: Vec<SubscriptionAddOn>,
     pub applied_coupons: Vec<AppliedCouponDetailed>,
+    pub phases: Vec<SubscriptionPhase>,
     pub metrics: Vec<BillableMetric>,
     pub mrr_cents: u64,
 
@@ -190,6 +266,29 @@ pub struct SubscriptionDetails {
     pub created_by: Uuid,
     pub trial_start_date: Option<chrono::NaiveDate>,
     pub period: BillingPeriodEnum,
+    /// usage before that date was already billed by a threshold invoice
+    pub threshold_invoiced_until: Option<NaiveDate>,
+    pub billing_mode: SubscriptionBillingModeEnum,
+    /// minimum spend per billing period
+    pub minimum_commitment: Option<rust_decimal::Decimal>,
+}
+
+impl SubscriptionDetails {
+    /// The day of the month the periods renew on
+    pub fn anchor_day(&self) -> u32 {
+        self.billing_mode.anchor_day(self.billing_day)
+    }
+}
+
+/// Subscription whose trial ended without being activated
+#[derive(Debug, Clone, o2o)]
+#[from_owned(SubscriptionTrialEndRow)]
+pub struct SubscriptionTrialEnd {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub billing_start_date: NaiveDate,
+    #[from(~.into())]
+    pub trial_mode: TrialModeEnum,
 }
 
 #[derive(Debug, Clone)]
@@ -207,6 +306,14 @@ pub struct SubscriptionInvoiceCandidate {
     pub currency: String,
     pub net_terms: i32,
     pub period: BillingPeriodEnum,
+    pub billing_mode: SubscriptionBillingModeEnum,
+}
+
+impl SubscriptionInvoiceCandidate {
+    /// The day of the month the periods renew on
+    pub fn anchor_day(&self) -> u32 {
+        self.billing_mode.anchor_day(self.billing_day)
+    }
 }
 
 impl From<SubscriptionInvoiceCandidateRow> for SubscriptionInvoiceCandidate {
@@ -226,7 +333,44 @@ impl From<SubscriptionInvoiceCandidateRow> for SubscriptionInvoiceCandidate {
             currency: val.plan_version.currency,
             net_terms: val.plan_version.net_terms,
             // version: self.plan_version.version,
-            period: val.subscription.period.into(),
+            period: BillingPeriodEnum::from_row(
+                val.subscription.period,
+                val.subscription.billing_period_months,
+            ),
+            billing_mode: val.subscription.billing_mode.into(),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn subscription(minimum_commitment: Option<rust_decimal::Decimal>) -> SubscriptionNew {
+        SubscriptionNew {
+            customer_id: Uuid::nil(),
+            billing_day: 1,
+            billing_mode: SubscriptionBillingModeEnum::Anniversary,
+            currency: "EUR".to_string(),
+            trial_start_date: None,
+            billing_start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            billing_end_date: None,
+            plan_version_id: Uuid::nil(),
+            created_by: Uuid::nil(),
+            net_terms: 0,
+            invoice_memo: None,
+            invoice_threshold: None,
+            activated_at: None,
+            minimum_commitment,
+        }
+    }
+
+    #[test]
+    fn test_validate_minimum_commitment() {
+        assert!(subscription(None).validate().is_ok());
+        assert!(subscription(Some(dec!(0))).validate().is_ok());
+        assert!(subscription(Some(dec!(100))).validate().is_ok());
+        assert!(subscription(Some(dec!(-0.01))).validate().is_err());
+    }
+}
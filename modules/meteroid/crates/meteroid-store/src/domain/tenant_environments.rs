@@ -0,0 +1,95 @@
+use std::collections::HashMap;
+
+use uuid::Uuid;
+
+use crate::domain::Tenant;
+
+/// How a catalog is copied between linked tenants
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CatalogCopyMode {
+    /// into a new sandbox tenant, plans keep their status and published versions
+    Mirror,
+    /// from a sandbox to its production tenant. Plans are copied as drafts, to be reviewed and published in production
+    Promote,
+}
+
+/// The entities created in the target tenant by a catalog copy.
+/// Families, metrics, products, rate cards and add-ons already in the target are matched and kept as is
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct CatalogCopyReport {
+    pub product_families: u32,
+    pub billable_metrics: u32,
+    pub products: u32,
+    pub rate_cards: u32,
+    pub add_ons: u32,
+    pub plans: u32,
+    /// draft versions of the plans that already existed in the target
+    pub plan_versions: u32,
+    /// external ids of the plans that were not copied, without a published version or archived in the target
+    pub skipped_plans: Vec<String>,
+}
+
+#[derive(Clone, Debug)]
+pub struct SandboxTenantNew {
+    pub production_tenant_id: Uuid,
+    pub organization_id: Uuid,
+    pub name: String,
+    pub copy_catalog: bool,
+    pub created_by: Uuid,
+}
+
+#[derive(Clone, Debug)]
+pub struct SandboxTenant {
+    pub tenant: Tenant,
+    pub catalog: CatalogCopyReport,
+}
+
+/// Replaces the ids of the source tenant entities referenced in a json document (ex: the metric of a fee) by the ids of the copies
+pub fn remap_ids(value: &mut serde_json::Value, ids: &HashMap<Uuid, Uuid>) {
+    match value {
+        serde_json::Value::String(s) => {
+            if let Some(target) = Uuid::parse_str(s).ok().and_then(|id| ids.get(&id)) {
+                *s = target.to_string();
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(|v| remap_ids(v, ids)),
+        serde_json::Value::Object(map) => map.values_mut().for_each(|v| remap_ids(v, ids)),
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_remap_ids() {
+        let source = Uuid::now_v7();
+        let target = Uuid::now_v7();
+        let unknown = Uuid::now_v7();
+
+        let ids = HashMap::from([(source, target)]);
+
+        let mut fee = json!({
+            "Usage": {
+                "metric_id": source.to_string(),
+                "pricing": {"PerUnit": {"rates": [{"price": "1.5"}]}}
+            },
+            "others": [source.to_string(), unknown.to_string(), "not an id", 3]
+        });
+
+        remap_ids(&mut fee, &ids);
+
+        assert_eq!(
+            fee,
+            json!({
+                "Usage": {
+                    "metric_id": target.to_string(),
+                    "pricing": {"PerUnit": {"rates": [{"price": "1.5"}]}}
+                },
+                "others": [target.to_string(), unknown.to_string(), "not an id", 3]
+            })
+        );
+    }
+}
@@ -0,0 +1,64 @@
+use chrono::NaiveDateTime;
+use diesel_models::tenant_member_roles::TenantMemberRoleRow;
+use uuid::Uuid;
+
+use crate::domain::enums::{OrganizationUserRole, TenantMemberRoleEnum};
+
+/// The role assigned to an organization member on a tenant
+#[derive(Clone, Debug)]
+pub struct TenantMemberRole {
+    pub user_id: Uuid,
+    pub tenant_id: Uuid,
+    pub role: TenantMemberRoleEnum,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<TenantMemberRoleRow> for TenantMemberRole {
+    fn from(row: TenantMemberRoleRow) -> Self {
+        TenantMemberRole {
+            user_id: row.user_id,
+            tenant_id: row.tenant_id,
+            role: row.role.into(),
+            created_at: row.created_at,
+        }
+    }
+}
+
+/// The organization admins are admins of all the tenants.
+/// The members without an assigned role are admins, as before the roles existed
+pub fn resolve_tenant_role(
+    organization_role: &OrganizationUserRole,
+    assigned: Option<TenantMemberRoleEnum>,
+) -> TenantMemberRoleEnum {
+    match organization_role {
+        OrganizationUserRole::Admin => TenantMemberRoleEnum::Admin,
+        OrganizationUserRole::Member => assigned.unwrap_or(TenantMemberRoleEnum::Admin),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resolve_tenant_role() {
+        assert_eq!(
+            resolve_tenant_role(
+                &OrganizationUserRole::Admin,
+                Some(TenantMemberRoleEnum::ReadOnly)
+            ),
+            TenantMemberRoleEnum::Admin
+        );
+        assert_eq!(
+            resolve_tenant_role(
+                &OrganizationUserRole::Member,
+                Some(TenantMemberRoleEnum::Billing)
+            ),
+            TenantMemberRoleEnum::Billing
+        );
+        assert_eq!(
+            resolve_tenant_role(&OrganizationUserRole::Member, None),
+            TenantMemberRoleEnum::Admin
+        );
+    }
+}
@@ -2,9 +2,16 @@ use chrono::NaiveDateTime;
 use o2o::o2o;
 use uuid::Uuid;
 
-use crate::domain::enums::TenantEnvironmentEnum;
+use crate::domain::enums::{
+    CollectionStrategyPresetEnum, CustomerUniquenessEnum, DueDatePolicyEnum,
+    InvoiceCadenceGroupingEnum, ProrationRoundingEnum, TenantEnvironmentEnum,
+    ZeroInvoicePolicyEnum,
+};
 use diesel_models::tenants::{TenantRow, TenantRowNew, TenantRowPatch};
 
+/// late usage is rarely worth holding an invoice for more than a month
+pub const MAX_FINALIZATION_GRACE_PERIOD_HOURS: i32 = 31 * 24;
+
 #[derive(Clone, Debug, o2o)]
 #[from_owned(TenantRow)]
 #[owned_into(TenantRow)]
@@ -19,11 +26,43 @@ pub struct Tenant {
     pub currency: String,
     #[map(~.into())]
     pub environment: TenantEnvironmentEnum,
+    #[map(~.into())]
+    pub proration_rounding: ProrationRoundingEnum,
+    #[map(~.into())]
+    pub zero_invoice_policy: ZeroInvoicePolicyEnum,
+    #[map(~.into())]
+    pub invoice_cadence_grouping: InvoiceCadenceGroupingEnum,
+    /// days until due of the invoices, when neither the customer nor the subscription sets them
+    pub default_net_terms: Option<i32>,
+    #[map(~.into())]
+    pub due_date_policy: DueDatePolicyEnum,
+    /// day of month of the fixed day due date policy
+    pub due_date_day: Option<i32>,
+    /// the production tenant of a sandbox tenant, its catalog can be promoted to it
+    pub production_tenant_id: Option<Uuid>,
+    /// receives the summary of every billing run with activity
+    pub finance_email: Option<String>,
+    #[map(~.into())]
+    pub customer_uniqueness: CustomerUniquenessEnum,
+    /// collection strategy of the customers outside of an overridden segment
+    #[map(~.into())]
+    pub collection_strategy_preset: CollectionStrategyPresetEnum,
+    /// IANA timezone in which the billing periods are cut and the invoices fall due
+    pub billing_timezone: String,
+    /// hours after the invoice date before its finalization, overriding the grace period of the invoicing entities
+    pub finalization_grace_period_hours: Option<i32>,
+    /// seats added by an end customer in a single request without the approval of the tenant.
+    /// All the requests are approved automatically when not set
+    pub seat_auto_approve_limit: Option<i32>,
+    /// SLAs of the invoice watchdog, the defaults of the instance apply when not set
+    pub stuck_draft_max_age_days: Option<i32>,
+    pub stuck_pending_max_age_hours: Option<i32>,
+    pub stuck_max_issue_attempts: Option<i32>,
 }
 
 #[derive(Clone, Debug, o2o)]
 #[owned_into(TenantRowNew)]
-#[ghosts(id: {uuid::Uuid::now_v7()})]
+#[ghosts(id: {uuid::Uuid::now_v7()}, production_tenant_id: {None})]
 pub struct FullTenantNew {
     pub name: String,
     pub slug: String,
@@ -37,6 +76,7 @@ pub struct FullTenantNew {
 pub struct TenantNew {
     pub name: String,
     pub environment: TenantEnvironmentEnum,
+    pub production_tenant_id: Option<Uuid>,
 }
 
 #[derive(Clone, Debug, o2o)]
@@ -49,4 +89,25 @@ pub struct TenantUpdate {
     #[map(~.map(| x | x.into()))]
     pub environment: Option<TenantEnvironmentEnum>,
     pub currency: Option<String>,
+    #[map(~.map(| x | x.into()))]
+    pub proration_rounding: Option<ProrationRoundingEnum>,
+    #[map(~.map(| x | x.into()))]
+    pub zero_invoice_policy: Option<ZeroInvoicePolicyEnum>,
+    #[map(~.map(| x | x.into()))]
+    pub invoice_cadence_grouping: Option<InvoiceCadenceGroupingEnum>,
+    pub default_net_terms: Option<i32>,
+    #[map(~.map(| x | x.into()))]
+    pub due_date_policy: Option<DueDatePolicyEnum>,
+    pub due_date_day: Option<i32>,
+    pub finance_email: Option<String>,
+    #[map(~.map(| x | x.into()))]
+    pub customer_uniqueness: Option<CustomerUniquenessEnum>,
+    #[map(~.map(| x | x.into()))]
+    pub collection_strategy_preset: Option<CollectionStrategyPresetEnum>,
+    pub billing_timezone: Option<String>,
+    pub finalization_grace_period_hours: Option<i32>,
+    pub seat_auto_approve_limit: Option<i32>,
+    pub stuck_draft_max_age_days: Option<i32>,
+    pub stuck_pending_max_age_hours: Option<i32>,
+    pub stuck_max_issue_attempts: Option<i32>,
 }
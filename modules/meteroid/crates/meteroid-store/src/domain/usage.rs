@@ -0,0 +1,277 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::compute::clients::usage::{GroupedUsageData, UsageSeriesPoint, UsageWindow};
+use crate::domain::Period;
+use crate::errors::StoreError;
+
+/// The number of event properties a usage query can be grouped by
+pub const MAX_USAGE_GROUP_BY: usize = 3;
+
+/// The usage of a customer for a metric, charted per window
+#[derive(Debug, Clone)]
+pub struct UsageQuery {
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub metric_id: Uuid,
+    pub period: Period,
+    pub group_by: Vec<String>,
+    pub window: UsageWindow,
+}
+
+impl UsageQuery {
+    /// The number of days a query can span at its window, bounding the points returned by the metering service
+    pub fn max_days(&self) -> i64 {
+        match self.window {
+            UsageWindow::Hour => 31,
+            UsageWindow::Day => 366,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.period.start >= self.period.end {
+            return Err(StoreError::InvalidArgument(
+                "The usage period must end after its start".to_string(),
+            ));
+        }
+
+        let days = (self.period.end - self.period.start).num_days();
+        if days > self.max_days() {
+            return Err(StoreError::InvalidArgument(format!(
+                "The usage period spans {} days, the maximum at this window is {}",
+                days,
+                self.max_days()
+            )));
+        }
+
+        if self.group_by.len() > MAX_USAGE_GROUP_BY {
+            return Err(StoreError::InvalidArgument(format!(
+                "The usage can be grouped by {} properties at most",
+                MAX_USAGE_GROUP_BY
+            )));
+        }
+
+        if self.group_by.iter().any(|key| key.trim().is_empty()) {
+            return Err(StoreError::InvalidArgument(
+                "The group by properties cannot be empty".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageSeries {
+    pub metric_id: Uuid,
+    pub period: Period,
+    pub window: UsageWindow,
+    pub points: Vec<UsageSeriesPoint>,
+}
+
+/// The usage behind a usage-based invoice line, as metered now
+#[derive(Debug, Clone)]
+pub struct LineItemUsageDetail {
+    pub invoice_id: Uuid,
+    pub line_item_id: String,
+    pub metric_id: Uuid,
+    pub metric_code: String,
+    pub period: Period,
+    /// as charged on the line, after the included units
+    pub line_quantity: Option<Decimal>,
+    /// the usage of the period in the unit the metric is billed in, rounded to its billing precision
+    pub usage: Decimal,
+    pub event_count: u64,
+    pub group_by: Vec<String>,
+    /// ordered by day
+    pub days: Vec<UsageBreakdown>,
+    /// by values of the group by properties, ordered by decreasing usage
+    pub dimensions: Vec<UsageBreakdown>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageBreakdown {
+    /// set for the daily breakdown
+    pub window_start: Option<NaiveDateTime>,
+    pub window_end: Option<NaiveDateTime>,
+    /// set for the breakdown by dimension
+    pub dimensions: BTreeMap<String, String>,
+    pub value: Decimal,
+    pub event_count: u64,
+}
+
+/// The usage per day with its number of events, the counts being summed over their dimensions
+pub fn daily_breakdown(
+    values: Vec<UsageSeriesPoint>,
+    counts: &[UsageSeriesPoint],
+) -> Vec<UsageBreakdown> {
+    let mut days: BTreeMap<NaiveDateTime, UsageBreakdown> = BTreeMap::new();
+
+    for point in values {
+        days.entry(point.window_start)
+            .or_insert_with(|| empty_day(&point))
+            .value += point.value;
+    }
+
+    for point in counts {
+        days.entry(point.window_start)
+            .or_insert_with(|| empty_day(point))
+            .event_count += event_count(point.value);
+    }
+
+    days.into_values().collect()
+}
+
+/// The usage per values of the dimensions over the period, with its number of events
+pub fn dimension_breakdown(
+    values: Vec<GroupedUsageData>,
+    counts: &[UsageSeriesPoint],
+) -> Vec<UsageBreakdown> {
+    let mut event_counts: BTreeMap<BTreeMap<String, String>, u64> = BTreeMap::new();
+    for point in counts {
+        let dimensions = point.dimensions.clone().into_iter().collect();
+        *event_counts.entry(dimensions).or_default() += event_count(point.value);
+    }
+
+    let mut breakdown: Vec<UsageBreakdown> = values
+        .into_iter()
+        .map(|usage| {
+            let dimensions: BTreeMap<String, String> = usage.dimensions.into_iter().collect();
+            UsageBreakdown {
+                window_start: None,
+                window_end: None,
+                event_count: event_counts.get(&dimensions).copied().unwrap_or(0),
+                dimensions,
+                value: usage.value,
+            }
+        })
+        .collect();
+
+    breakdown.sort_by(|a, b| b.value.cmp(&a.value));
+    breakdown
+}
+
+fn empty_day(point: &UsageSeriesPoint) -> UsageBreakdown {
+    UsageBreakdown {
+        window_start: Some(point.window_start),
+        window_end: Some(point.window_end),
+        dimensions: BTreeMap::new(),
+        value: Decimal::ZERO,
+        event_count: 0,
+    }
+}
+
+fn event_count(value: Decimal) -> u64 {
+    value.to_u64().unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn query(start: &str, end: &str, window: UsageWindow, group_by: &[&str]) -> UsageQuery {
+        UsageQuery {
+            tenant_id: Uuid::nil(),
+            customer_id: Uuid::nil(),
+            metric_id: Uuid::nil(),
+            period: Period {
+                start: NaiveDate::parse_from_str(start, "%Y-%m-%d").unwrap(),
+                end: NaiveDate::parse_from_str(end, "%Y-%m-%d").unwrap(),
+            },
+            group_by: group_by.iter().map(|k| k.to_string()).collect(),
+            window,
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(
+            query("2024-01-01", "2024-02-01", UsageWindow::Hour, &["model"])
+                .validate()
+                .is_ok()
+        );
+        assert!(query("2024-01-01", "2024-01-01", UsageWindow::Day, &[])
+            .validate()
+            .is_err());
+        // too many hourly points
+        assert!(query("2024-01-01", "2024-03-01", UsageWindow::Hour, &[])
+            .validate()
+            .is_err());
+        assert!(query("2024-01-01", "2024-03-01", UsageWindow::Day, &[])
+            .validate()
+            .is_ok());
+        assert!(query(
+            "2024-01-01",
+            "2024-02-01",
+            UsageWindow::Day,
+            &["a", "b", "c", "d"]
+        )
+        .validate()
+        .is_err());
+        assert!(query("2024-01-01", "2024-02-01", UsageWindow::Day, &[" "])
+            .validate()
+            .is_err());
+    }
+
+    fn point(day: u32, value: i64, dimensions: &[(&str, &str)]) -> UsageSeriesPoint {
+        let start = NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        UsageSeriesPoint {
+            window_start: start,
+            window_end: start + chrono::Duration::days(1),
+            value: Decimal::from(value),
+            dimensions: dimensions
+                .iter()
+                .map(|(k, v)| (k.to_string(), v.to_string()))
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn test_daily_breakdown() {
+        let values = vec![point(1, 100, &[]), point(2, 49, &[])];
+        let counts = vec![
+            point(1, 3, &[("model", "a")]),
+            point(1, 2, &[("model", "b")]),
+            point(3, 1, &[("model", "a")]),
+        ];
+
+        let days = daily_breakdown(values, &counts);
+
+        assert_eq!(days.len(), 3);
+        assert_eq!(days[0].value, Decimal::from(100));
+        assert_eq!(days[0].event_count, 5);
+        assert_eq!(days[1].value, Decimal::from(49));
+        assert_eq!(days[1].event_count, 0);
+        assert_eq!(days[2].value, Decimal::ZERO);
+        assert_eq!(days[2].event_count, 1);
+    }
+
+    #[test]
+    fn test_dimension_breakdown() {
+        let grouped = |value: i64, model: &str| GroupedUsageData {
+            value: Decimal::from(value),
+            dimensions: [("model".to_string(), model.to_string())].into(),
+        };
+        let counts = vec![
+            point(1, 3, &[("model", "a")]),
+            point(2, 4, &[("model", "a")]),
+            point(1, 2, &[("model", "b")]),
+        ];
+
+        let dimensions = dimension_breakdown(vec![grouped(40, "a"), grouped(109, "b")], &counts);
+
+        assert_eq!(dimensions.len(), 2);
+        assert_eq!(dimensions[0].dimensions.get("model").unwrap(), "b");
+        assert_eq!(dimensions[0].value, Decimal::from(109));
+        assert_eq!(dimensions[0].event_count, 2);
+        assert_eq!(dimensions[1].event_count, 7);
+    }
+}
@@ -0,0 +1,491 @@
+use chrono::{Days, NaiveDate, NaiveDateTime};
+use diesel_models::usage_alert_rules::{
+    UsageAlertRuleEventRow, UsageAlertRuleEventRowNew, UsageAlertRuleRow, UsageAlertRuleRowNew,
+    UsageAlertRuleRowPatch,
+};
+use error_stack::Report;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::enums::{UsageAlertRuleStatusEnum, UsageAlertRuleWindowEnum};
+use crate::domain::Period;
+use crate::errors::{StoreError, StoreErrorReport};
+
+pub const MAX_RULE_CONDITIONS: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ComparisonOperator {
+    GreaterThan,
+    GreaterThanOrEqual,
+    LessThan,
+    LessThanOrEqual,
+}
+
+impl ComparisonOperator {
+    pub fn compare(&self, value: Decimal, threshold: Decimal) -> bool {
+        match self {
+            ComparisonOperator::GreaterThan => value > threshold,
+            ComparisonOperator::GreaterThanOrEqual => value >= threshold,
+            ComparisonOperator::LessThan => value < threshold,
+            ComparisonOperator::LessThanOrEqual => value <= threshold,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum UsageAlertCondition {
+    /// the usage of the window, in units of the billable metric
+    Usage {
+        operator: ComparisonOperator,
+        value: Decimal,
+    },
+    /// the change of the usage from the previous window of the same length, in percent.
+    /// Never met when there was no usage in the previous window
+    GrowthPercent {
+        operator: ComparisonOperator,
+        value: Decimal,
+    },
+}
+
+impl UsageAlertCondition {
+    pub fn is_met(&self, measure: &UsageAlertMeasure) -> bool {
+        match self {
+            UsageAlertCondition::Usage { operator, value } => {
+                operator.compare(measure.usage, *value)
+            }
+            UsageAlertCondition::GrowthPercent { operator, value } => measure
+                .growth_percent()
+                .is_some_and(|growth| operator.compare(growth, *value)),
+        }
+    }
+}
+
+/// The usage of a customer measured for a rule
+#[derive(Debug, Clone)]
+pub struct UsageAlertMeasure {
+    pub window: Period,
+    pub usage: Decimal,
+    /// only measured when a condition depends on the growth
+    pub previous_usage: Option<Decimal>,
+}
+
+impl UsageAlertMeasure {
+    pub fn growth_percent(&self) -> Option<Decimal> {
+        self.previous_usage
+            .filter(|previous| !previous.is_zero())
+            .map(|previous| ((self.usage - previous) * Decimal::ONE_HUNDRED / previous).round_dp(2))
+    }
+}
+
+impl UsageAlertRuleWindowEnum {
+    /// The rolling window ending today, none for the billing period that depends on the subscription
+    pub fn rolling_period(&self, today: NaiveDate) -> Option<Period> {
+        let days = match self {
+            UsageAlertRuleWindowEnum::BillingPeriod => return None,
+            UsageAlertRuleWindowEnum::RollingDay => 1,
+            UsageAlertRuleWindowEnum::RollingWeek => 7,
+            UsageAlertRuleWindowEnum::RollingMonth => 30,
+        };
+
+        let end = today.succ_opt()?;
+
+        Some(Period {
+            start: end.checked_sub_days(Days::new(days))?,
+            end,
+        })
+    }
+}
+
+/// The window of the same length right before, the growth being measured against it
+pub fn previous_window(window: &Period) -> Period {
+    let length = window.end - window.start;
+
+    Period {
+        start: window.start - length,
+        end: window.start,
+    }
+}
+
+/// What the rule checks and how it notifies, editable once created
+#[derive(Debug, Clone, PartialEq)]
+pub struct UsageAlertRuleDefinition {
+    pub name: String,
+    pub window: UsageAlertRuleWindowEnum,
+    /// all the conditions must be met, otherwise any of them
+    pub match_all: bool,
+    pub conditions: Vec<UsageAlertCondition>,
+    pub notify_webhook: bool,
+    pub notify_email: Option<String>,
+}
+
+impl UsageAlertRuleDefinition {
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.name.trim().is_empty() {
+            return Err(StoreError::InvalidArgument(
+                "the name of the rule cannot be empty".to_string(),
+            ));
+        }
+
+        if self.conditions.is_empty() || self.conditions.len() > MAX_RULE_CONDITIONS {
+            return Err(StoreError::InvalidArgument(format!(
+                "a rule must have between 1 and {} conditions",
+                MAX_RULE_CONDITIONS
+            )));
+        }
+
+        for condition in &self.conditions {
+            if let UsageAlertCondition::Usage { value, .. } = condition {
+                if value.is_sign_negative() {
+                    return Err(StoreError::InvalidArgument(
+                        "the usage of a condition cannot be negative".to_string(),
+                    ));
+                }
+            }
+        }
+
+        if let Some(email) = &self.notify_email {
+            if !email.contains('@') || email.contains(char::is_whitespace) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "invalid notification email {}",
+                    email
+                )));
+            }
+        }
+
+        if !self.notify_webhook && self.notify_email.is_none() {
+            return Err(StoreError::InvalidArgument(
+                "a rule must notify through webhooks or email".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    pub fn depends_on_growth(&self) -> bool {
+        self.conditions
+            .iter()
+            .any(|c| matches!(c, UsageAlertCondition::GrowthPercent { .. }))
+    }
+
+    pub fn is_met(&self, measure: &UsageAlertMeasure) -> bool {
+        if self.match_all {
+            self.conditions.iter().all(|c| c.is_met(measure))
+        } else {
+            self.conditions.iter().any(|c| c.is_met(measure))
+        }
+    }
+
+    fn conditions_json(&self) -> Result<serde_json::Value, StoreErrorReport> {
+        serde_json::to_value(&self.conditions).map_err(|e| {
+            Report::from(StoreError::SerdeError(
+                "Failed to serialize usage alert rule conditions".to_string(),
+                e,
+            ))
+        })
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageAlertRule {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub billable_metric_id: Uuid,
+    /// all the customers billed for the metric if not set
+    pub customer_id: Option<Uuid>,
+    pub definition: UsageAlertRuleDefinition,
+    pub last_evaluated_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+    pub updated_at: Option<NaiveDateTime>,
+}
+
+impl TryFrom<UsageAlertRuleRow> for UsageAlertRule {
+    type Error = StoreErrorReport;
+
+    fn try_from(row: UsageAlertRuleRow) -> Result<Self, Self::Error> {
+        let conditions = serde_json::from_value(row.conditions).map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to deserialize usage alert rule conditions".to_string(),
+                e,
+            )
+        })?;
+
+        Ok(UsageAlertRule {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            billable_metric_id: row.billable_metric_id,
+            customer_id: row.customer_id,
+            definition: UsageAlertRuleDefinition {
+                name: row.name,
+                window: row.usage_window.into(),
+                match_all: row.match_all,
+                conditions,
+                notify_webhook: row.notify_webhook,
+                notify_email: row.notify_email,
+            },
+            last_evaluated_at: row.last_evaluated_at,
+            created_at: row.created_at,
+            created_by: row.created_by,
+            updated_at: row.updated_at,
+        })
+    }
+}
+
+impl UsageAlertRule {
+    /// The status the rule moves to for the customer, given its current state. None when unchanged,
+    /// so that a rule fires once and only fires again after it resolved
+    pub fn transition(
+        &self,
+        met: bool,
+        current: Option<&UsageAlertRuleEvent>,
+    ) -> Option<UsageAlertRuleStatusEnum> {
+        let firing = current.is_some_and(|e| e.status == UsageAlertRuleStatusEnum::Firing);
+
+        match (met, firing) {
+            (true, false) => Some(UsageAlertRuleStatusEnum::Firing),
+            (false, true) => Some(UsageAlertRuleStatusEnum::Resolved),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageAlertRuleNew {
+    pub tenant_id: Uuid,
+    pub billable_metric_id: Uuid,
+    pub customer_id: Option<Uuid>,
+    pub definition: UsageAlertRuleDefinition,
+    pub created_by: Uuid,
+}
+
+impl TryFrom<UsageAlertRuleNew> for UsageAlertRuleRowNew {
+    type Error = StoreErrorReport;
+
+    fn try_from(rule: UsageAlertRuleNew) -> Result<Self, Self::Error> {
+        Ok(UsageAlertRuleRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: rule.tenant_id,
+            conditions: rule.definition.conditions_json()?,
+            name: rule.definition.name,
+            billable_metric_id: rule.billable_metric_id,
+            customer_id: rule.customer_id,
+            usage_window: rule.definition.window.into(),
+            match_all: rule.definition.match_all,
+            notify_webhook: rule.definition.notify_webhook,
+            notify_email: rule.definition.notify_email,
+            created_by: rule.created_by,
+        })
+    }
+}
+
+impl TryFrom<UsageAlertRuleDefinition> for UsageAlertRuleRowPatch {
+    type Error = StoreErrorReport;
+
+    fn try_from(definition: UsageAlertRuleDefinition) -> Result<Self, Self::Error> {
+        Ok(UsageAlertRuleRowPatch {
+            conditions: definition.conditions_json()?,
+            name: definition.name,
+            usage_window: definition.window.into(),
+            match_all: definition.match_all,
+            notify_webhook: definition.notify_webhook,
+            notify_email: definition.notify_email,
+            updated_at: Some(chrono::Utc::now().naive_utc()),
+        })
+    }
+}
+
+/// A change of status of a rule for a customer, the last one being its current state
+#[derive(Debug, Clone)]
+pub struct UsageAlertRuleEvent {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub rule_id: Uuid,
+    pub customer_id: Uuid,
+    pub status: UsageAlertRuleStatusEnum,
+    pub usage: Decimal,
+    pub growth_percent: Option<Decimal>,
+    pub window: Period,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<UsageAlertRuleEventRow> for UsageAlertRuleEvent {
+    fn from(row: UsageAlertRuleEventRow) -> Self {
+        UsageAlertRuleEvent {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            rule_id: row.rule_id,
+            customer_id: row.customer_id,
+            status: row.status.into(),
+            usage: row.usage,
+            growth_percent: row.growth_percent,
+            window: Period {
+                start: row.window_start,
+                end: row.window_end,
+            },
+            created_at: row.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageAlertRuleEventNew {
+    pub tenant_id: Uuid,
+    pub rule_id: Uuid,
+    pub customer_id: Uuid,
+    pub status: UsageAlertRuleStatusEnum,
+    pub measure: UsageAlertMeasure,
+}
+
+impl From<UsageAlertRuleEventNew> for UsageAlertRuleEventRowNew {
+    fn from(event: UsageAlertRuleEventNew) -> Self {
+        UsageAlertRuleEventRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: event.tenant_id,
+            rule_id: event.rule_id,
+            customer_id: event.customer_id,
+            status: event.status.into(),
+            usage: event.measure.usage,
+            growth_percent: event.measure.growth_percent(),
+            window_start: event.measure.window.start,
+            window_end: event.measure.window.end,
+        }
+    }
+}
+
+/// Payload of the outbox entry requesting the email of a rule that fired or resolved
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAlertRuleEmailPayload {
+    pub rule_id: Uuid,
+    pub rule_name: String,
+    pub event_id: Uuid,
+    pub status: UsageAlertRuleStatusEnum,
+    pub recipient: String,
+    pub customer_name: String,
+    pub metric_name: String,
+    pub window_start: NaiveDate,
+    pub window_end: NaiveDate,
+    pub usage: Decimal,
+    pub growth_percent: Option<Decimal>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal_macros::dec;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    fn definition(match_all: bool) -> UsageAlertRuleDefinition {
+        UsageAlertRuleDefinition {
+            name: "API spike".to_string(),
+            window: UsageAlertRuleWindowEnum::RollingWeek,
+            match_all,
+            conditions: vec![
+                UsageAlertCondition::Usage {
+                    operator: ComparisonOperator::GreaterThan,
+                    value: dec!(1000),
+                },
+                UsageAlertCondition::GrowthPercent {
+                    operator: ComparisonOperator::GreaterThan,
+                    value: dec!(50),
+                },
+            ],
+            notify_webhook: true,
+            notify_email: None,
+        }
+    }
+
+    fn measure(usage: Decimal, previous_usage: Option<Decimal>) -> UsageAlertMeasure {
+        UsageAlertMeasure {
+            window: Period {
+                start: date("2024-01-01"),
+                end: date("2024-01-08"),
+            },
+            usage,
+            previous_usage,
+        }
+    }
+
+    #[test]
+    fn test_composite_conditions() {
+        let all = definition(true);
+        let any = definition(false);
+
+        // +100%
+        assert!(all.is_met(&measure(dec!(2000), Some(dec!(1000)))));
+        // +10%
+        assert!(!all.is_met(&measure(dec!(1100), Some(dec!(1000)))));
+        assert!(any.is_met(&measure(dec!(1100), Some(dec!(1000)))));
+        // no usage before, the growth is undefined
+        assert!(!all.is_met(&measure(dec!(2000), Some(Decimal::ZERO))));
+        assert!(!any.is_met(&measure(dec!(500), Some(Decimal::ZERO))));
+    }
+
+    #[test]
+    fn test_growth_percent() {
+        assert_eq!(
+            measure(dec!(150), Some(dec!(200))).growth_percent(),
+            Some(dec!(-25))
+        );
+        assert_eq!(measure(dec!(150), None).growth_percent(), None);
+    }
+
+    #[test]
+    fn test_windows() {
+        let today = date("2024-03-10");
+
+        let week = UsageAlertRuleWindowEnum::RollingWeek
+            .rolling_period(today)
+            .unwrap();
+        assert_eq!(week.start, date("2024-03-04"));
+        assert_eq!(week.end, date("2024-03-11"));
+
+        let previous = previous_window(&week);
+        assert_eq!(previous.start, date("2024-02-26"));
+        assert_eq!(previous.end, date("2024-03-04"));
+
+        assert!(UsageAlertRuleWindowEnum::BillingPeriod
+            .rolling_period(today)
+            .is_none());
+    }
+
+    #[test]
+    fn test_validate_definition() {
+        assert!(definition(true).validate().is_ok());
+
+        let invalid = vec![
+            UsageAlertRuleDefinition {
+                conditions: vec![],
+                ..definition(true)
+            },
+            UsageAlertRuleDefinition {
+                name: " ".to_string(),
+                ..definition(true)
+            },
+            UsageAlertRuleDefinition {
+                notify_webhook: false,
+                ..definition(true)
+            },
+            UsageAlertRuleDefinition {
+                notify_email: Some("ops".to_string()),
+                ..definition(true)
+            },
+            UsageAlertRuleDefinition {
+                conditions: vec![UsageAlertCondition::Usage {
+                    operator: ComparisonOperator::LessThan,
+                    value: dec!(-1),
+                }],
+                ..definition(true)
+            },
+        ];
+
+        for definition in invalid {
+            assert!(definition.validate().is_err());
+        }
+    }
+}
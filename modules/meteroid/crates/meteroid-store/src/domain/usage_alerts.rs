@@ -0,0 +1,270 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_models::enums::UsageAlertThresholdTypeEnum;
+use diesel_models::usage_alerts::{UsageAlertRow, UsageAlertRowNew};
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+use crate::domain::Period;
+
+/// Default share of the threshold the usage must drop below for a triggered alert to re-arm
+pub const DEFAULT_HYSTERESIS_PERCENT: Decimal = Decimal::TEN;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UsageAlertThreshold {
+    /// in units of the billable metric
+    Absolute(Decimal),
+    /// percentage of the amount included in the capacity commitment
+    CapacityPercent(Decimal),
+}
+
+impl UsageAlertThreshold {
+    /// Threshold in units of the billable metric. Capacity thresholds require the included amount of the capacity component
+    pub fn resolve(&self, capacity_included: Option<u64>) -> Option<Decimal> {
+        match self {
+            UsageAlertThreshold::Absolute(value) => Some(*value),
+            UsageAlertThreshold::CapacityPercent(percent) => capacity_included
+                .map(|included| Decimal::from(included) * percent / Decimal::ONE_HUNDRED),
+        }
+    }
+
+    fn to_row(&self) -> (UsageAlertThresholdTypeEnum, Decimal) {
+        match self {
+            UsageAlertThreshold::Absolute(value) => (UsageAlertThresholdTypeEnum::Absolute, *value),
+            UsageAlertThreshold::CapacityPercent(percent) => {
+                (UsageAlertThresholdTypeEnum::CapacityPercent, *percent)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageAlert {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub billable_metric_id: Uuid,
+    pub threshold: UsageAlertThreshold,
+    pub hysteresis_percent: Decimal,
+    pub notify_email: Option<String>,
+    pub triggered: bool,
+    pub triggered_period_start: Option<NaiveDate>,
+    pub last_value: Option<Decimal>,
+    pub last_triggered_at: Option<NaiveDateTime>,
+    pub last_checked_at: Option<NaiveDateTime>,
+    pub created_at: NaiveDateTime,
+    pub created_by: Uuid,
+}
+
+impl From<UsageAlertRow> for UsageAlert {
+    fn from(row: UsageAlertRow) -> Self {
+        let threshold = match row.threshold_type {
+            UsageAlertThresholdTypeEnum::Absolute => {
+                UsageAlertThreshold::Absolute(row.threshold_value)
+            }
+            UsageAlertThresholdTypeEnum::CapacityPercent => {
+                UsageAlertThreshold::CapacityPercent(row.threshold_value)
+            }
+        };
+
+        UsageAlert {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            customer_id: row.customer_id,
+            billable_metric_id: row.billable_metric_id,
+            threshold,
+            hysteresis_percent: row.hysteresis_percent,
+            notify_email: row.notify_email,
+            triggered: row.triggered,
+            triggered_period_start: row.triggered_period_start,
+            last_value: row.last_value,
+            last_triggered_at: row.last_triggered_at,
+            last_checked_at: row.last_checked_at,
+            created_at: row.created_at,
+            created_by: row.created_by,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum UsageAlertTransition {
+    /// the usage crossed the threshold, the alert must be emitted
+    Fire,
+    /// the usage dropped enough below the threshold, or a new period started
+    Rearm,
+    Unchanged,
+}
+
+impl UsageAlert {
+    /// An alert fires once when the usage reaches the threshold, and only fires again once re-armed,
+    /// so that a usage hovering around the threshold doesn't emit an alert on every run.
+    pub fn transition(
+        &self,
+        usage: Decimal,
+        threshold: Decimal,
+        period_start: NaiveDate,
+    ) -> UsageAlertTransition {
+        let new_period = self.triggered_period_start != Some(period_start);
+
+        if self.triggered && !new_period {
+            let rearm_below =
+                threshold * (Decimal::ONE_HUNDRED - self.hysteresis_percent) / Decimal::ONE_HUNDRED;
+
+            return if usage < rearm_below {
+                UsageAlertTransition::Rearm
+            } else {
+                UsageAlertTransition::Unchanged
+            };
+        }
+
+        if usage >= threshold {
+            UsageAlertTransition::Fire
+        } else if self.triggered {
+            UsageAlertTransition::Rearm
+        } else {
+            UsageAlertTransition::Unchanged
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageAlertNew {
+    pub tenant_id: Uuid,
+    pub customer_id: Uuid,
+    pub billable_metric_id: Uuid,
+    pub threshold: UsageAlertThreshold,
+    pub hysteresis_percent: Option<Decimal>,
+    pub notify_email: Option<String>,
+    pub created_by: Uuid,
+}
+
+impl From<UsageAlertNew> for UsageAlertRowNew {
+    fn from(alert: UsageAlertNew) -> Self {
+        let (threshold_type, threshold_value) = alert.threshold.to_row();
+
+        UsageAlertRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: alert.tenant_id,
+            customer_id: alert.customer_id,
+            billable_metric_id: alert.billable_metric_id,
+            threshold_type,
+            threshold_value,
+            hysteresis_percent: alert
+                .hysteresis_percent
+                .unwrap_or(DEFAULT_HYSTERESIS_PERCENT),
+            notify_email: alert.notify_email,
+            created_by: alert.created_by,
+        }
+    }
+}
+
+/// Alert emitted during an evaluation run
+#[derive(Debug, Clone)]
+pub struct TriggeredUsageAlert {
+    pub alert: UsageAlert,
+    pub subscription_id: Uuid,
+    pub period: Period,
+    pub usage: Decimal,
+    pub threshold: Decimal,
+}
+
+/// Payload of the outbox entry requesting the alert email
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UsageAlertEmailPayload {
+    pub usage_alert_id: Uuid,
+    pub recipient: String,
+    pub customer_name: String,
+    pub metric_name: String,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub usage: Decimal,
+    pub threshold: Decimal,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn alert(triggered: bool, triggered_period_start: Option<NaiveDate>) -> UsageAlert {
+        UsageAlert {
+            id: Uuid::nil(),
+            tenant_id: Uuid::nil(),
+            customer_id: Uuid::nil(),
+            billable_metric_id: Uuid::nil(),
+            threshold: UsageAlertThreshold::Absolute(Decimal::from(100)),
+            hysteresis_percent: DEFAULT_HYSTERESIS_PERCENT,
+            notify_email: None,
+            triggered,
+            triggered_period_start,
+            last_value: None,
+            last_triggered_at: None,
+            last_checked_at: None,
+            created_at: NaiveDateTime::default(),
+            created_by: Uuid::nil(),
+        }
+    }
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_fires_when_threshold_reached() {
+        let alert = alert(false, None);
+        let threshold = Decimal::from(100);
+        let period_start = date("2024-01-01");
+
+        assert_eq!(
+            alert.transition(Decimal::from(99), threshold, period_start),
+            UsageAlertTransition::Unchanged
+        );
+        assert_eq!(
+            alert.transition(Decimal::from(100), threshold, period_start),
+            UsageAlertTransition::Fire
+        );
+    }
+
+    #[test]
+    fn test_does_not_refire_within_hysteresis() {
+        let period_start = date("2024-01-01");
+        let alert = alert(true, Some(period_start));
+        let threshold = Decimal::from(100);
+
+        assert_eq!(
+            alert.transition(Decimal::from(150), threshold, period_start),
+            UsageAlertTransition::Unchanged
+        );
+        assert_eq!(
+            alert.transition(Decimal::from(95), threshold, period_start),
+            UsageAlertTransition::Unchanged
+        );
+        assert_eq!(
+            alert.transition(Decimal::from(89), threshold, period_start),
+            UsageAlertTransition::Rearm
+        );
+    }
+
+    #[test]
+    fn test_new_period_rearms_or_refires() {
+        let alert = alert(true, Some(date("2024-01-01")));
+        let threshold = Decimal::from(100);
+        let next_period_start = date("2024-02-01");
+
+        assert_eq!(
+            alert.transition(Decimal::from(10), threshold, next_period_start),
+            UsageAlertTransition::Rearm
+        );
+        assert_eq!(
+            alert.transition(Decimal::from(120), threshold, next_period_start),
+            UsageAlertTransition::Fire
+        );
+    }
+
+    #[test]
+    fn test_capacity_threshold() {
+        let threshold = UsageAlertThreshold::CapacityPercent(Decimal::from(80));
+
+        assert_eq!(threshold.resolve(Some(1000)), Some(Decimal::from(800)));
+        assert_eq!(threshold.resolve(None), None);
+    }
+}
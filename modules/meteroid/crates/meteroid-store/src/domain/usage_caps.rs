@@ -0,0 +1,92 @@
+use chrono::NaiveDateTime;
+use diesel_models::usage_caps::{UsageCapReachedRow, UsageCapReachedRowNew};
+use uuid::Uuid;
+
+use crate::domain::Period;
+
+/// Charges of a capped usage or capacity fee over its current period, before the cap applies.
+/// Amounts are in cents
+#[derive(Debug, Clone)]
+pub struct UsageCapCharges {
+    /// the subscription component or add-on
+    pub fee_id: Uuid,
+    pub name: String,
+    pub metric_id: Option<Uuid>,
+    pub period: Period,
+    pub cap: i64,
+    pub charges: i64,
+}
+
+impl UsageCapCharges {
+    /// Charges above this point are not billed
+    pub fn limit_reached(&self) -> bool {
+        self.charges >= self.cap
+    }
+}
+
+/// State of a cap in the current period, polled by the product to enforce hard limits
+#[derive(Debug, Clone)]
+pub struct UsageCapStatus {
+    pub subscription_id: Uuid,
+    pub charges: UsageCapCharges,
+    /// first time the limit was observed as reached in the period
+    pub reached_at: Option<NaiveDateTime>,
+}
+
+impl UsageCapStatus {
+    pub fn limit_reached(&self) -> bool {
+        self.reached_at.is_some() || self.charges.limit_reached()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageCapReached {
+    pub id: Uuid,
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub fee_id: Uuid,
+    pub period: Period,
+    pub cap: i64,
+    pub charges: i64,
+    pub reached_at: NaiveDateTime,
+}
+
+impl From<UsageCapReachedRow> for UsageCapReached {
+    fn from(row: UsageCapReachedRow) -> Self {
+        UsageCapReached {
+            id: row.id,
+            tenant_id: row.tenant_id,
+            subscription_id: row.subscription_id,
+            fee_id: row.fee_id,
+            period: Period {
+                start: row.period_start,
+                end: row.period_end,
+            },
+            cap: row.cap,
+            charges: row.charges,
+            reached_at: row.reached_at,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct UsageCapReachedNew {
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub charges: UsageCapCharges,
+}
+
+impl From<UsageCapReachedNew> for UsageCapReachedRowNew {
+    fn from(reached: UsageCapReachedNew) -> Self {
+        UsageCapReachedRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: reached.tenant_id,
+            subscription_id: reached.subscription_id,
+            fee_id: reached.charges.fee_id,
+            period_start: reached.charges.period.start,
+            period_end: reached.charges.period.end,
+            cap: reached.charges.cap,
+            charges: reached.charges.charges,
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use std::collections::BTreeMap;
+
+use chrono::NaiveDateTime;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::domain::{Invoice, LineItem};
+use crate::utils::local_id::LocalId;
+
+/// Events of the customer ingested after the fact, with timestamps between `from` and `to` (inclusive)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BackfilledUsage {
+    pub customer_id: Uuid,
+    pub from: NaiveDateTime,
+    pub to: NaiveDateTime,
+}
+
+impl BackfilledUsage {
+    /// Whether the events fall into the period of a usage line of the invoice
+    pub fn affects(&self, invoice: &Invoice) -> bool {
+        invoice.customer_id == self.customer_id
+            && invoice.line_items.iter().any(|line| self.overlaps(line))
+    }
+
+    fn overlaps(&self, line: &LineItem) -> bool {
+        line.metric_id.is_some()
+            && self.from.date() < line.end_date
+            && self.to.date() >= line.start_date
+    }
+}
+
+/// The outcome of the re-rating of a finalized invoice
+#[derive(Debug, Clone, PartialEq)]
+pub enum UsageRerating {
+    /// No change of the usage, or the invoice was marked again meanwhile and is re-rated later
+    Unchanged,
+    /// The additional usage, invoiced separately
+    Invoiced(Invoice),
+    /// The usage that was overbilled, in cents credited to the customer balance
+    Credited(i64),
+}
+
+/// The difference between the usage lines of the invoice and the lines re-rated over the same periods,
+/// as one line per usage component
+pub fn usage_adjustment_lines(invoiced: &[LineItem], rerated: &[LineItem]) -> Vec<LineItem> {
+    struct Delta<'a> {
+        line: &'a LineItem,
+        total: i64,
+        quantity: Decimal,
+    }
+
+    let mut deltas: BTreeMap<(Option<Uuid>, Option<Uuid>), Delta> = BTreeMap::new();
+
+    for (sign, lines) in [(1, rerated), (-1, invoiced)] {
+        for line in lines.iter().filter(|l| l.metric_id.is_some()) {
+            let delta = deltas
+                .entry((line.price_component_id, line.metric_id))
+                .or_insert(Delta {
+                    line,
+                    total: 0,
+                    quantity: Decimal::ZERO,
+                });
+            delta.total += sign * line.total;
+            delta.quantity += Decimal::from(sign) * line.quantity.unwrap_or(Decimal::ZERO);
+        }
+    }
+
+    deltas
+        .into_values()
+        .filter(|delta| delta.total != 0)
+        .map(|delta| LineItem {
+            local_id: LocalId::no_prefix(),
+            name: delta.line.name.clone(),
+            total: delta.total,
+            subtotal: delta.total,
+            quantity: Some(delta.quantity),
+            unit_price: None,
+            start_date: delta.line.start_date,
+            end_date: delta.line.end_date,
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id: delta.line.price_component_id,
+            product_id: delta.line.product_id,
+            metric_id: delta.line.metric_id,
+            description: Some("Usage adjustment".to_string()),
+            quantity_display_precision: delta.line.quantity_display_precision,
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use rust_decimal_macros::dec;
+
+    fn usage_line(component: Uuid, total: i64, quantity: Decimal) -> LineItem {
+        LineItem {
+            local_id: LocalId::no_prefix(),
+            name: "API calls".to_string(),
+            total,
+            subtotal: total,
+            quantity: Some(quantity),
+            unit_price: Some(dec!(0.1)),
+            start_date: NaiveDate::from_ymd_opt(2024, 10, 1).unwrap(),
+            end_date: NaiveDate::from_ymd_opt(2024, 11, 1).unwrap(),
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id: Some(component),
+            product_id: None,
+            metric_id: Some(Uuid::nil()),
+            description: None,
+            quantity_display_precision: Some(2),
+        }
+    }
+
+    fn fee_line(total: i64) -> LineItem {
+        LineItem {
+            metric_id: None,
+            ..usage_line(Uuid::max(), total, Decimal::ONE)
+        }
+    }
+
+    #[test]
+    fn test_usage_adjustment_lines() {
+        let calls = Uuid::from_u128(1);
+        let storage = Uuid::from_u128(2);
+
+        let invoiced = vec![
+            fee_line(1000),
+            usage_line(calls, 500, dec!(50)),
+            usage_line(storage, 300, dec!(30)),
+        ];
+        let rerated = vec![
+            fee_line(1200),
+            usage_line(calls, 800, dec!(80)),
+            usage_line(storage, 300, dec!(30)),
+        ];
+
+        let lines = usage_adjustment_lines(&invoiced, &rerated);
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].price_component_id, Some(calls));
+        assert_eq!(lines[0].total, 300);
+        assert_eq!(lines[0].quantity, Some(dec!(30)));
+        assert_eq!(lines[0].unit_price, None);
+        assert_eq!(lines[0].quantity_display_precision, Some(2));
+    }
+
+    #[test]
+    fn test_usage_adjustment_lines_overbilled() {
+        let calls = Uuid::from_u128(1);
+
+        let lines = usage_adjustment_lines(
+            &[usage_line(calls, 500, dec!(50))],
+            &[usage_line(calls, 400, dec!(40))],
+        );
+
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0].total, -100);
+        assert_eq!(lines[0].quantity, Some(dec!(-10)));
+    }
+
+    #[test]
+    fn test_backfilled_usage_affects() {
+        let line = usage_line(Uuid::from_u128(1), 500, dec!(50));
+        let usage = |from: (u32, u32), to: (u32, u32)| BackfilledUsage {
+            customer_id: Uuid::nil(),
+            from: NaiveDate::from_ymd_opt(2024, from.0, from.1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+            to: NaiveDate::from_ymd_opt(2024, to.0, to.1)
+                .unwrap()
+                .and_hms_opt(12, 0, 0)
+                .unwrap(),
+        };
+
+        assert!(usage((10, 15), (10, 16)).overlaps(&line));
+        assert!(usage((9, 15), (10, 1)).overlaps(&line));
+        assert!(!usage((9, 15), (9, 30)).overlaps(&line));
+        assert!(!usage((11, 1), (11, 2)).overlaps(&line));
+        assert!(!usage((10, 15), (10, 16)).overlaps(&fee_line(1000)));
+    }
+}
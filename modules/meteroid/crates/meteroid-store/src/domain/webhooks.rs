@@ -24,6 +24,48 @@ pub struct WebhookOutEndpoint {
     pub created_at: NaiveDateTime,
     pub events_to_listen: Vec<WebhookOutEventTypeEnum>,
     pub enabled: bool,
+    pub batching: Option<WebhookOutBatching>,
+}
+
+/// Delivery of the events of an endpoint in batches, sent when the batch is full
+/// or when its oldest event waited long enough. Events are sent one by one when not set
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WebhookOutBatching {
+    pub max_events: u32,
+    pub max_wait_seconds: u32,
+}
+
+impl WebhookOutBatching {
+    pub const MAX_EVENTS: u32 = 1000;
+    pub const MAX_WAIT_SECONDS: u32 = 3600;
+
+    pub fn from_row(max_events: Option<i32>, max_wait_seconds: Option<i32>) -> Option<Self> {
+        match (max_events, max_wait_seconds) {
+            (Some(max_events), Some(max_wait_seconds)) => Some(WebhookOutBatching {
+                max_events: max_events as u32,
+                max_wait_seconds: max_wait_seconds as u32,
+            }),
+            _ => None,
+        }
+    }
+
+    pub fn validate(&self) -> Result<(), StoreError> {
+        if self.max_events == 0 || self.max_events > Self::MAX_EVENTS {
+            return Err(StoreError::InvalidArgument(format!(
+                "the batch size must be between 1 and {}",
+                Self::MAX_EVENTS
+            )));
+        }
+
+        if self.max_wait_seconds == 0 || self.max_wait_seconds > Self::MAX_WAIT_SECONDS {
+            return Err(StoreError::InvalidArgument(format!(
+                "the batch wait must be between 1 and {} seconds",
+                Self::MAX_WAIT_SECONDS
+            )));
+        }
+
+        Ok(())
+    }
 }
 
 impl WebhookOutEndpoint {
@@ -51,6 +93,10 @@ impl WebhookOutEndpoint {
                 .map_into()
                 .collect(),
             enabled: row.enabled,
+            batching: WebhookOutBatching::from_row(
+                row.batch_max_events,
+                row.batch_max_wait_seconds,
+            ),
         })
     }
 }
@@ -62,6 +108,7 @@ pub struct WebhookOutEndpointNew {
     pub description: Option<String>,
     pub events_to_listen: Vec<WebhookOutEventTypeEnum>,
     pub enabled: bool,
+    pub batching: Option<WebhookOutBatching>,
 }
 
 impl WebhookOutEndpointNew {
@@ -83,6 +130,8 @@ impl WebhookOutEndpointNew {
                 .map_into()
                 .collect(),
             enabled: self.enabled,
+            batch_max_events: self.batching.map(|b| b.max_events as i32),
+            batch_max_wait_seconds: self.batching.map(|b| b.max_wait_seconds as i32),
         })
     }
 }
@@ -100,6 +149,7 @@ pub struct WebhookOutEvent {
     pub response_body: Option<String>,
     pub http_status_code: Option<i16>,
     pub error_message: Option<String>,
+    pub batch_id: Option<Uuid>,
 }
 
 #[derive(Clone, Debug, o2o)]
@@ -115,6 +165,7 @@ pub struct WebhookOutEventNew {
     pub response_body: Option<String>,
     pub http_status_code: Option<i16>,
     pub error_message: Option<String>,
+    pub batch_id: Option<Uuid>,
 }
 
 #[derive(Clone, Debug, o2o)]
@@ -42,8 +42,22 @@ pub enum StoreError {
     UserRegistrationClosed(String),
     #[error("Negative customer balance: {0:?}")]
     NegativeCustomerBalanceError(error_stack::Report<DatabaseError>),
+    #[error("Onboarding step {0:?} must be completed first")]
+    OnboardingIncomplete(crate::domain::enums::OnboardingStepEnum),
+    #[error("Component rules violated: {}", .0.iter().map(|v| v.to_string()).collect::<Vec<_>>().join(", "))]
+    ComponentRulesViolated(Vec<crate::domain::component_rules::ComponentRuleViolation>),
+    #[error("Invalid matrix dimensions: {}", .0.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("; "))]
+    InvalidMatrixDimensions(Vec<crate::domain::price_component_validation::InvalidMatrixDimension>),
+    #[error("Credit limit of customer {customer_id} exceeded: {exposure} owed for a limit of {credit_limit}")]
+    CreditLimitExceeded {
+        customer_id: uuid::Uuid,
+        exposure: i64,
+        credit_limit: i64,
+    },
     #[error("Metering Service error: {0}")]
     MeteringServiceError(String, #[source] ComputeError),
+    #[error("Conflict: {0}")]
+    Conflict(String),
 }
 
 // used in some o2o macros failing to compile, https://github.com/meteroid-oss/meteroid/actions/runs/10921372280/job/30313299862
@@ -0,0 +1,233 @@
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::accounting_exports::{
+    AccountingExportConfigRow, AccountingExportRunRow, AccountingExportRunRowNew,
+};
+use diesel_models::invoices::InvoiceRow;
+
+use crate::domain::accounting_exports::{
+    AccountingExportConfig, AccountingExportConfigNew, AccountingExportRun, AccountingExportRunNew,
+    AccountingPeriod, JournalEntry, JournalInvoice, LedgerAccounts, QuickBooksCredentials,
+    MAX_EXPORT_DAYS,
+};
+use crate::domain::enums::{AccountingConnectorEnum, AccountingExportRunStatusEnum};
+use crate::domain::Invoice;
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+const MAX_EXPORT_RUNS: i64 = 100;
+
+#[async_trait::async_trait]
+pub trait AccountingExportInterface {
+    /// Replaces the config of the tenant. The QuickBooks authorization is kept when not provided, and the
+    /// schedule resumes from the last exported day
+    async fn upsert_accounting_export_config(
+        &self,
+        config: AccountingExportConfigNew,
+    ) -> StoreResult<AccountingExportConfig>;
+
+    async fn find_accounting_export_config(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Option<AccountingExportConfig>>;
+
+    async fn list_enabled_accounting_export_configs(
+        &self,
+    ) -> StoreResult<Vec<AccountingExportConfig>>;
+
+    /// The entries booked within the period: the invoices finalized or voided, and the revenue recognized on
+    /// the invoices of the previous periods
+    async fn build_journal_entries(
+        &self,
+        tenant_id: Uuid,
+        accounts: &LedgerAccounts,
+        period: AccountingPeriod,
+    ) -> StoreResult<Vec<JournalEntry>>;
+
+    /// Keeps the refresh token issued in exchange of the previous one
+    async fn update_quickbooks_credentials(
+        &self,
+        tenant_id: Uuid,
+        credentials: QuickBooksCredentials,
+    ) -> StoreResult<()>;
+
+    /// Records the run, and when `scheduled` and successful moves the schedule past its period
+    async fn record_accounting_export_run(
+        &self,
+        run: AccountingExportRunNew,
+        scheduled: bool,
+    ) -> StoreResult<AccountingExportRun>;
+
+    async fn find_accounting_export_run(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<AccountingExportRun>;
+
+    /// The latest runs first
+    async fn list_accounting_export_runs(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<AccountingExportRun>>;
+}
+
+#[async_trait::async_trait]
+impl AccountingExportInterface for Store {
+    async fn upsert_accounting_export_config(
+        &self,
+        mut config: AccountingExportConfigNew,
+    ) -> StoreResult<AccountingExportConfig> {
+        let mut conn = self.get_conn().await?;
+
+        if config.quickbooks.is_none()
+            && config.connector == AccountingConnectorEnum::QuickbooksOnline
+        {
+            config.quickbooks =
+                AccountingExportConfigRow::find_by_tenant_id(&mut conn, config.tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .map(|row| AccountingExportConfig::from_row(&self.settings.crypt_key, row))
+                    .transpose()?
+                    .and_then(|existing| existing.quickbooks);
+        }
+
+        config.validate()?;
+
+        let row = config.to_row(&self.settings.crypt_key)?;
+
+        let row = row
+            .upsert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        AccountingExportConfig::from_row(&self.settings.crypt_key, row)
+    }
+
+    async fn find_accounting_export_config(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Option<AccountingExportConfig>> {
+        let mut conn = self.get_conn().await?;
+
+        AccountingExportConfigRow::find_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .map(|row| AccountingExportConfig::from_row(&self.settings.crypt_key, row))
+            .transpose()
+    }
+
+    async fn list_enabled_accounting_export_configs(
+        &self,
+    ) -> StoreResult<Vec<AccountingExportConfig>> {
+        let mut conn = self.get_conn().await?;
+
+        AccountingExportConfigRow::list_enabled(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(|row| AccountingExportConfig::from_row(&self.settings.crypt_key, row))
+            .collect()
+    }
+
+    async fn build_journal_entries(
+        &self,
+        tenant_id: Uuid,
+        accounts: &LedgerAccounts,
+        period: AccountingPeriod,
+    ) -> StoreResult<Vec<JournalEntry>> {
+        let mut conn = self.get_conn().await?;
+
+        // the invoices of the previous periods may still recognize revenue within this one
+        let from = period.start - chrono::Days::new(MAX_EXPORT_DAYS as u64);
+
+        let invoices: Vec<Invoice> =
+            InvoiceRow::list_finalized_by_invoice_date(&mut conn, tenant_id, from, period.end)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<StoreResult<_>>()?;
+
+        let mut entries: Vec<JournalEntry> = invoices
+            .iter()
+            .flat_map(|invoice| JournalInvoice::from(invoice).journal_entries(accounts, &period))
+            .collect();
+        entries.sort_by_key(|entry| entry.date);
+
+        Ok(entries)
+    }
+
+    async fn update_quickbooks_credentials(
+        &self,
+        tenant_id: Uuid,
+        credentials: QuickBooksCredentials,
+    ) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let encrypted = credentials.encrypt(&self.settings.crypt_key)?;
+
+        AccountingExportConfigRow::update_credentials(&mut conn, tenant_id, Some(encrypted))
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(())
+    }
+
+    async fn record_accounting_export_run(
+        &self,
+        run: AccountingExportRunNew,
+        scheduled: bool,
+    ) -> StoreResult<AccountingExportRun> {
+        let advance_to = (scheduled && run.status == AccountingExportRunStatusEnum::Succeeded)
+            .then_some(run.period.end);
+        let tenant_id = run.tenant_id;
+        let row: AccountingExportRunRowNew = run.into();
+
+        self.transaction(|conn| {
+            async move {
+                let run = row
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                if let Some(advance_to) = advance_to {
+                    AccountingExportConfigRow::advance_exported_until(conn, tenant_id, advance_to)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                }
+
+                Ok(run)
+            }
+            .scope_boxed()
+        })
+        .await
+        .map(Into::into)
+    }
+
+    async fn find_accounting_export_run(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<AccountingExportRun> {
+        let mut conn = self.get_conn().await?;
+
+        AccountingExportRunRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_accounting_export_runs(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<AccountingExportRun>> {
+        let mut conn = self.get_conn().await?;
+
+        AccountingExportRunRow::list_by_tenant_id(&mut conn, tenant_id, MAX_EXPORT_RUNS)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+}
@@ -2,17 +2,25 @@ use argon2::{
     password_hash::{rand_core::OsRng, PasswordHasher, SaltString},
     Argon2,
 };
+use chrono::{Days, NaiveDate, NaiveDateTime};
 use common_eventbus::Event;
-use diesel_models::api_tokens::{ApiTokenRow, ApiTokenRowNew, ApiTokenValidationRow};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::api_tokens::{
+    ApiTokenExpiringRow, ApiTokenRow, ApiTokenRowNew, ApiTokenUsageDailyRow, ApiTokenValidationRow,
+};
+use diesel_models::partners::PartnerRow;
 use diesel_models::tenants::TenantRow;
 use error_stack::Report;
 use nanoid::nanoid;
 use tracing_log::log;
 use uuid::Uuid;
 
-use crate::domain::api_tokens::ApiToken;
+use crate::domain::api_tokens::{
+    ApiToken, ApiTokenExpiryEmailPayload, ApiTokenUsage, ApiTokenUsageRecord, ExpiringApiToken,
+    API_TOKEN_EXPIRY_NOTICE_DAYS, MAX_API_TOKEN_ROTATION_GRACE_HOURS,
+};
 use crate::domain::enums::TenantEnvironmentEnum;
-use crate::domain::ApiTokenValidation;
+use crate::domain::{ApiTokenValidation, OutboxEvent, OutboxNew};
 use crate::errors::StoreError;
 use crate::store::Store;
 use crate::{domain, StoreResult};
@@ -32,8 +40,42 @@ pub trait ApiTokensInterface {
     ) -> StoreResult<ApiTokenValidation>;
 
     async fn insert_api_token(&self, plan: domain::ApiTokenNew) -> StoreResult<(String, ApiToken)>;
+
+    /// Total and daily requests of the token, for the days since `since`
+    async fn get_api_token_usage(
+        &self,
+        tenant_id: &Uuid,
+        id: &Uuid,
+        since: NaiveDate,
+    ) -> StoreResult<ApiTokenUsage>;
+
+    async fn record_api_token_usage(&self, records: Vec<ApiTokenUsageRecord>) -> StoreResult<()>;
+
+    /// A disabled token cannot authenticate anymore, and cannot be enabled again
+    async fn disable_api_token(&self, tenant_id: &Uuid, id: &Uuid) -> StoreResult<ApiToken>;
+
+    /// Creates a new token with the settings of an enabled one, and disables the latter in the same transaction,
+    /// right away or at the end of the grace period. The new token keeps the lifetime of the rotated one
+    async fn rotate_api_token(
+        &self,
+        tenant_id: &Uuid,
+        id: &Uuid,
+        created_by: &Uuid,
+        grace_period_hours: Option<u32>,
+    ) -> StoreResult<(String, ApiToken)>;
+
+    /// Requests an email to the owners of the tokens expiring within the notice period, once per token
+    async fn notify_expiring_api_tokens(
+        &self,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<ExpiringApiToken>>;
 }
 
+const EXPIRING_API_TOKENS_BATCH_SIZE: i64 = 100;
+
+/// Max days of daily usage returned for a token
+pub const MAX_API_TOKEN_USAGE_DAYS: u64 = 90;
+
 #[async_trait::async_trait]
 impl ApiTokensInterface for Store {
     async fn find_api_tokens_by_tenant_id(
@@ -76,6 +118,16 @@ impl ApiTokensInterface for Store {
         &self,
         entity: domain::ApiTokenNew,
     ) -> StoreResult<(String, ApiToken)> {
+        if entity
+            .expires_at
+            .is_some_and(|expires_at| expires_at <= chrono::Utc::now().naive_utc())
+        {
+            return Err(StoreError::InvalidArgument(
+                "the expiry of the api token must be in the future".to_string(),
+            )
+            .into());
+        }
+
         let mut conn = self.get_conn().await?;
 
         let id = Uuid::now_v7();
@@ -86,38 +138,17 @@ impl ApiTokensInterface for Store {
 
         let env: TenantEnvironmentEnum = tenant.environment.into();
 
-        // api key is ex: ${pv for private key ?? pb for publishable key}_${tenant.env}_ + random
-        let prefix = format!("pv_{}_", env.as_short_string());
-
-        // encode in base62. Identifier is added to the api key, and used to retrieve the hash.
-        let id_part = base62::encode(id.as_u128());
-
-        // Generate the api key
-        let api_key_random = nanoid!(28, &common_utils::rng::BASE62_ALPHABET);
-        let api_key = format!("{}{}/{}", &prefix, &api_key_random, &id_part);
-
-        // Generate the hash that we will store in db
-        let argon2 = Argon2::new(
-            argon2::Algorithm::Argon2id,
-            argon2::Version::V0x13,
-            argon2::Params::new(5 * 1024, 1, 1, None).unwrap(),
-        );
-        let salt = SaltString::generate(&mut OsRng);
-        let api_key_hash = argon2
-            .hash_password(api_key_random.as_bytes(), &salt)
-            .map_err(|e| {
-                log::error!("Unable to hash api key: {}", e);
-                StoreError::InvalidArgument("unable to hash api key".to_string())
-            })?
-            .to_string();
-
-        // generate a hint that will also be stored
-        let hint = format!(
-            "{}{}...{}",
-            &prefix,
-            &api_key_random[..4],
-            &id_part[id_part.len() - 4..]
-        );
+        if let Some(partner_id) = entity.partner_id {
+            PartnerRow::find_by_id(&mut conn, entity.tenant_id, partner_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+        }
+
+        let GeneratedApiKey {
+            api_key,
+            hash: api_key_hash,
+            hint,
+        } = generate_api_key(&env, &id)?;
 
         let insertable_entity = ApiTokenRowNew {
             id,
@@ -127,6 +158,10 @@ impl ApiTokensInterface for Store {
             tenant_id: entity.tenant_id,
             hash: api_key_hash,
             hint,
+            daily_request_soft_limit: entity.daily_request_soft_limit,
+            partner_id: entity.partner_id,
+            scopes: entity.scopes.into_iter().map(Some).collect(),
+            expires_at: entity.expires_at,
         };
 
         let result: Result<ApiToken, Report<StoreError>> = insertable_entity
@@ -147,4 +182,293 @@ impl ApiTokensInterface for Store {
 
         result.map(|res| (api_key, res))
     }
+
+    async fn get_api_token_usage(
+        &self,
+        tenant_id: &Uuid,
+        id: &Uuid,
+        since: NaiveDate,
+    ) -> StoreResult<ApiTokenUsage> {
+        let oldest = chrono::Utc::now().date_naive() - Days::new(MAX_API_TOKEN_USAGE_DAYS);
+        if since < oldest {
+            return Err(StoreError::InvalidArgument(format!(
+                "Usage is available for the last {} days",
+                MAX_API_TOKEN_USAGE_DAYS
+            ))
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let api_token = ApiTokenRow::find_by_id_and_tenant_id(&mut conn, id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let daily = ApiTokenUsageDailyRow::list_since(&mut conn, id, since)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(ApiTokenUsage {
+            api_token_id: api_token.id,
+            request_count: api_token.request_count,
+            last_used_at: api_token.last_used_at,
+            daily_request_soft_limit: api_token.daily_request_soft_limit,
+            disabled_at: api_token.disabled_at,
+            rotated_at: api_token.rotated_at,
+            daily: daily.into_iter().map(Into::into).collect(),
+        })
+    }
+
+    async fn record_api_token_usage(&self, records: Vec<ApiTokenUsageRecord>) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        for record in records {
+            ApiTokenRow::record_usage(
+                &mut conn,
+                &record.api_token_id,
+                record.request_count,
+                record.last_used_at,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            ApiTokenUsageDailyRow {
+                api_token_id: record.api_token_id,
+                day: record.day,
+                request_count: record.request_count,
+            }
+            .increment(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+        }
+
+        Ok(())
+    }
+
+    async fn disable_api_token(&self, tenant_id: &Uuid, id: &Uuid) -> StoreResult<ApiToken> {
+        let mut conn = self.get_conn().await?;
+
+        ApiTokenRow::disable(&mut conn, id, tenant_id, chrono::Utc::now().naive_utc())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn rotate_api_token(
+        &self,
+        tenant_id: &Uuid,
+        id: &Uuid,
+        created_by: &Uuid,
+        grace_period_hours: Option<u32>,
+    ) -> StoreResult<(String, ApiToken)> {
+        let grace_period_hours = grace_period_hours.unwrap_or(0);
+        if grace_period_hours > MAX_API_TOKEN_ROTATION_GRACE_HOURS {
+            return Err(StoreError::InvalidArgument(format!(
+                "the grace period of a rotated api token is limited to {} hours",
+                MAX_API_TOKEN_ROTATION_GRACE_HOURS
+            ))
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let tenant = TenantRow::find_by_id(&mut conn, *tenant_id)
+            .await
+            .map_err(|err| StoreError::DatabaseError(err.error))?;
+
+        let env: TenantEnvironmentEnum = tenant.environment.into();
+
+        let new_id = Uuid::now_v7();
+
+        let GeneratedApiKey {
+            api_key,
+            hash,
+            hint,
+        } = generate_api_key(&env, &new_id)?;
+
+        let tenant_id = *tenant_id;
+        let id = *id;
+        let created_by = *created_by;
+
+        let api_token = self
+            .transaction_with(&mut conn, |conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+
+                    let disabled_from = now + chrono::Duration::hours(grace_period_hours as i64);
+
+                    let previous: ApiToken =
+                        ApiTokenRow::revoke_rotated(conn, &id, &tenant_id, now, disabled_from)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into();
+                    let expires_at = previous.rotated_expiry(now);
+
+                    ApiTokenRowNew {
+                        id: new_id,
+                        name: previous.name,
+                        created_at: now,
+                        created_by,
+                        tenant_id,
+                        hash,
+                        hint,
+                        daily_request_soft_limit: previous.daily_request_soft_limit,
+                        partner_id: previous.partner_id,
+                        scopes: previous.scopes.into_iter().map(Some).collect(),
+                        expires_at,
+                    }
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::api_token_created(created_by, new_id))
+            .await;
+
+        Ok((api_key, api_token.into()))
+    }
+
+    async fn notify_expiring_api_tokens(
+        &self,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<ExpiringApiToken>> {
+        let until = now + chrono::Duration::days(API_TOKEN_EXPIRY_NOTICE_DAYS);
+
+        let mut notified = vec![];
+        let mut cursor = None;
+
+        loop {
+            let mut conn = self.get_conn().await?;
+
+            let rows = ApiTokenExpiringRow::list_expiring(
+                &mut conn,
+                now,
+                until,
+                cursor,
+                EXPIRING_API_TOKENS_BATCH_SIZE,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            drop(conn);
+
+            let done = (rows.len() as i64) < EXPIRING_API_TOKENS_BATCH_SIZE;
+            cursor = rows.last().map(|row| row.id);
+
+            for row in rows {
+                let token: ExpiringApiToken = row.into();
+                let Some(expires_at) = token.expires_at else {
+                    continue;
+                };
+
+                let payload = serde_json::to_value(ApiTokenExpiryEmailPayload {
+                    api_token_id: token.id,
+                    recipient: token.owner_email.clone(),
+                    token_name: token.name.clone(),
+                    hint: token.hint.clone(),
+                    tenant_name: token.tenant_name.clone(),
+                    expires_at,
+                })
+                .map_err(|e| {
+                    StoreError::SerdeError(
+                        "Failed to serialize api token expiry email payload".to_string(),
+                        e,
+                    )
+                })?;
+
+                let token_id = token.id;
+                let tenant_id = token.tenant_id;
+
+                // a token notified concurrently is skipped
+                let marked = self
+                    .transaction(|conn| {
+                        async move {
+                            let marked = ApiTokenRow::mark_expiry_notified(conn, &token_id, now)
+                                .await
+                                .map_err(Into::<Report<StoreError>>::into)?;
+
+                            if marked > 0 {
+                                self.internal
+                                    .insert_outbox_item(
+                                        conn,
+                                        OutboxNew {
+                                            event_type: OutboxEvent::ApiTokenExpiryEmailRequested,
+                                            resource_id: token_id,
+                                            tenant_id,
+                                            payload: Some(payload),
+                                        },
+                                    )
+                                    .await?;
+                            }
+
+                            Ok(marked > 0)
+                        }
+                        .scope_boxed()
+                    })
+                    .await?;
+
+                if marked {
+                    notified.push(token);
+                }
+            }
+
+            if done {
+                break;
+            }
+        }
+
+        Ok(notified)
+    }
+}
+
+struct GeneratedApiKey {
+    api_key: String,
+    hash: String,
+    hint: String,
+}
+
+fn generate_api_key(env: &TenantEnvironmentEnum, id: &Uuid) -> StoreResult<GeneratedApiKey> {
+    // api key is ex: ${pv for private key ?? pb for publishable key}_${tenant.env}_ + random
+    let prefix = format!("pv_{}_", env.as_short_string());
+
+    // encode in base62. Identifier is added to the api key, and used to retrieve the hash.
+    let id_part = base62::encode(id.as_u128());
+
+    // Generate the api key
+    let api_key_random = nanoid!(28, &common_utils::rng::BASE62_ALPHABET);
+    let api_key = format!("{}{}/{}", &prefix, &api_key_random, &id_part);
+
+    // Generate the hash that we will store in db
+    let argon2 = Argon2::new(
+        argon2::Algorithm::Argon2id,
+        argon2::Version::V0x13,
+        argon2::Params::new(5 * 1024, 1, 1, None).unwrap(),
+    );
+    let salt = SaltString::generate(&mut OsRng);
+    let hash = argon2
+        .hash_password(api_key_random.as_bytes(), &salt)
+        .map_err(|e| {
+            log::error!("Unable to hash api key: {}", e);
+            StoreError::InvalidArgument("unable to hash api key".to_string())
+        })?
+        .to_string();
+
+    // generate a hint that will also be stored
+    let hint = format!(
+        "{}{}...{}",
+        &prefix,
+        &api_key_random[..4],
+        &id_part[id_part.len() - 4..]
+    );
+
+    Ok(GeneratedApiKey {
+        api_key,
+        hash,
+        hint,
+    })
 }
@@ -0,0 +1,50 @@
+use crate::domain::audit_logs::{AuditLog, AuditLogFilter, AuditLogNew};
+use crate::domain::{PaginatedVec, PaginationRequest};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use diesel_models::audit_logs::{AuditLogRow, AuditLogRowNew};
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait AuditLogInterface {
+    async fn insert_audit_log(&self, log: AuditLogNew) -> StoreResult<()>;
+
+    async fn list_audit_logs(
+        &self,
+        tenant_id: Uuid,
+        filter: AuditLogFilter,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<AuditLog>>;
+}
+
+#[async_trait::async_trait]
+impl AuditLogInterface for Store {
+    async fn insert_audit_log(&self, log: AuditLogNew) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        AuditLogRowNew::from(log)
+            .insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+    }
+
+    async fn list_audit_logs(
+        &self,
+        tenant_id: Uuid,
+        filter: AuditLogFilter,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<AuditLog>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows = AuditLogRow::list(&mut conn, tenant_id, filter.into(), pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(PaginatedVec {
+            items: rows.items.into_iter().map(Into::into).collect(),
+            total_pages: rows.total_pages,
+            total_results: rows.total_results,
+        })
+    }
+}
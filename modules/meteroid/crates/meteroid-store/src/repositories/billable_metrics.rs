@@ -7,7 +7,8 @@ use diesel_models::billable_metrics::{BillableMetricRow, BillableMetricRowNew};
 use diesel_models::product_families::ProductFamilyRow;
 
 use crate::domain::{
-    BillableMetric, BillableMetricMeta, BillableMetricNew, PaginatedVec, PaginationRequest,
+    validate_quantity_precision, BillableMetric, BillableMetricMeta, BillableMetricNew,
+    PaginatedVec, PaginationRequest,
 };
 use crate::errors::StoreError;
 use crate::{domain, Store, StoreResult};
@@ -31,6 +32,12 @@ pub trait BillableMetricInterface {
         &self,
         billable_metric: domain::BillableMetricNew,
     ) -> StoreResult<domain::BillableMetric>;
+
+    /// The active metrics of the tenant with an event schema
+    async fn list_billable_metrics_with_event_schema(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<domain::BillableMetric>>;
 }
 
 #[async_trait::async_trait]
@@ -78,6 +85,15 @@ impl BillableMetricInterface for Store {
         &self,
         billable_metric: BillableMetricNew,
     ) -> StoreResult<BillableMetric> {
+        validate_quantity_precision(billable_metric.quantity_billing_precision)?;
+        validate_quantity_precision(billable_metric.quantity_display_precision)?;
+        if let Some(schema) = &billable_metric.event_schema {
+            schema.validate(
+                &billable_metric.aggregation_type,
+                billable_metric.aggregation_key.as_deref(),
+            )?;
+        }
+
         let mut conn = self.get_conn().await?;
 
         let family = ProductFamilyRow::find_by_external_id_and_tenant_id(
@@ -112,6 +128,16 @@ impl BillableMetricInterface for Store {
             created_by: billable_metric.created_by,
             tenant_id: billable_metric.tenant_id,
             product_family_id: family.id,
+            quantity_billing_precision: billable_metric.quantity_billing_precision,
+            quantity_display_precision: billable_metric.quantity_display_precision,
+            event_schema: billable_metric
+                .event_schema
+                .map(|x| {
+                    serde_json::to_value(&x).map_err(|e| {
+                        StoreError::SerdeError("Failed to serialize event_schema".to_string(), e)
+                    })
+                })
+                .transpose()?,
         };
 
         let res: BillableMetric = self
@@ -151,4 +177,23 @@ impl BillableMetricInterface for Store {
 
         Ok(res)
     }
+    async fn list_billable_metrics_with_event_schema(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<BillableMetric>> {
+        let mut conn = self.get_conn().await?;
+
+        let metrics: Vec<BillableMetric> =
+            BillableMetricRow::list_by_tenant_id(&mut conn, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<_, _>>()?;
+
+        Ok(metrics
+            .into_iter()
+            .filter(|m| m.event_schema.is_some())
+            .collect())
+    }
 }
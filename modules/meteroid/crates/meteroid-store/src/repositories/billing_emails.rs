@@ -0,0 +1,67 @@
+use common_eventbus::Event;
+use diesel_models::customers::CustomerRow;
+use error_stack::Report;
+
+use crate::domain::billing_emails::EmailDeliveryEvent;
+use crate::domain::Customer;
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait BillingEmailsInterface {
+    /// Applies a delivery event from the email provider to the customers billed at the recipient address.
+    /// Returns the customers whose billing email status changed.
+    async fn record_email_delivery_event(
+        &self,
+        event: EmailDeliveryEvent,
+    ) -> StoreResult<Vec<Customer>>;
+}
+
+#[async_trait::async_trait]
+impl BillingEmailsInterface for Store {
+    async fn record_email_delivery_event(
+        &self,
+        event: EmailDeliveryEvent,
+    ) -> StoreResult<Vec<Customer>> {
+        let mut conn = self.get_conn().await?;
+
+        let customers =
+            CustomerRow::list_by_billing_email(&mut conn, event.tenant_id, event.recipient.clone())
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        let mut updated_customers = vec![];
+
+        for row in customers {
+            let customer: Customer = row.try_into()?;
+
+            let Some(status) = event.event_type.next_status(customer.billing_email_status) else {
+                continue;
+            };
+
+            let updated: Customer = CustomerRow::update_billing_email_status(
+                &mut conn,
+                customer.id,
+                customer.tenant_id,
+                status.into(),
+                event.reason.clone(),
+                event.occurred_at,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .try_into()?;
+
+            let _ = self
+                .eventbus
+                .publish(Event::customer_billing_email_updated(
+                    updated.id,
+                    updated.tenant_id,
+                ))
+                .await;
+
+            updated_customers.push(updated);
+        }
+
+        Ok(updated_customers)
+    }
+}
@@ -0,0 +1,171 @@
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::billing_runs::{
+    BillingRunInvoiceRow, BillingRunSummaryRow, BillingRunSummaryRowNew,
+};
+use diesel_models::tenants::TenantRow;
+
+use crate::domain::billing_runs::{
+    BillingRunInvoice, BillingRunSummary, BillingRunSummaryEmailPayload, BillingRunSummaryNew,
+};
+use crate::domain::{OutboxEvent, OutboxNew, PaginatedVec, PaginationRequest};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait BillingRunsInterface {
+    /// Records the summary of the billing run of every tenant with activity in the period, and requests the summary
+    /// email when the tenant has a finance email. Periods already summarized are skipped.
+    /// Returns the new summaries
+    async fn summarize_billing_run(
+        &self,
+        period_start: NaiveDateTime,
+        period_end: NaiveDateTime,
+    ) -> StoreResult<Vec<BillingRunSummary>>;
+
+    /// Most recent first
+    async fn list_billing_run_summaries(
+        &self,
+        tenant_id: Uuid,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<BillingRunSummary>>;
+
+    async fn find_billing_run_summary(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<BillingRunSummary>;
+}
+
+#[async_trait::async_trait]
+impl BillingRunsInterface for Store {
+    async fn summarize_billing_run(
+        &self,
+        period_start: NaiveDateTime,
+        period_end: NaiveDateTime,
+    ) -> StoreResult<Vec<BillingRunSummary>> {
+        let mut conn = self.get_conn().await?;
+
+        let invoices = BillingRunInvoiceRow::list(&mut conn, period_start, period_end)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(BillingRunInvoice::from)
+            .collect();
+
+        let summaries = BillingRunSummaryNew::summarize(period_start, period_end, invoices);
+
+        let mut inserted = Vec::new();
+
+        for summary in summaries {
+            let tenant = TenantRow::find_by_id(&mut conn, summary.tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+            let row: BillingRunSummaryRowNew = summary.clone().try_into()?;
+
+            let email_payload = match tenant.finance_email {
+                Some(recipient) => Some(
+                    serde_json::to_value(BillingRunSummaryEmailPayload {
+                        billing_run_summary_id: row.id,
+                        recipient,
+                        tenant_name: tenant.name,
+                        period_start: summary.period_start,
+                        period_end: summary.period_end,
+                        invoices_created: summary.invoices_created,
+                        invoices_finalized: summary.invoices_finalized,
+                        invoices_issued: summary.invoices_issued,
+                        issue_failures: summary.issue_failures,
+                        totals: summary.totals,
+                        failures: summary.failures,
+                    })
+                    .map_err(|e| {
+                        StoreError::SerdeError(
+                            "Failed to serialize billing run summary email payload".to_string(),
+                            e,
+                        )
+                    })?,
+                ),
+                None => None,
+            };
+
+            let tenant_id = tenant.id;
+
+            let summary_row = self
+                .transaction(|conn| {
+                    async move {
+                        let Some(summary_row) = row
+                            .insert_if_absent(conn)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                        else {
+                            return Ok(None);
+                        };
+
+                        if let Some(payload) = email_payload {
+                            self.internal
+                                .insert_outbox_item(
+                                    conn,
+                                    OutboxNew {
+                                        event_type: OutboxEvent::BillingRunSummaryEmailRequested,
+                                        resource_id: summary_row.id,
+                                        tenant_id,
+                                        payload: Some(payload),
+                                    },
+                                )
+                                .await?;
+                        }
+
+                        Ok(Some(summary_row))
+                    }
+                    .scope_boxed()
+                })
+                .await?;
+
+            if let Some(summary_row) = summary_row {
+                inserted.push(summary_row.try_into()?);
+            }
+        }
+
+        Ok(inserted)
+    }
+
+    async fn list_billing_run_summaries(
+        &self,
+        tenant_id: Uuid,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<BillingRunSummary>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows = BillingRunSummaryRow::list(&mut conn, tenant_id, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(PaginatedVec {
+            items: rows
+                .items
+                .into_iter()
+                .map(BillingRunSummary::try_from)
+                .collect::<Result<Vec<_>, _>>()?,
+            total_pages: rows.total_pages,
+            total_results: rows.total_results,
+        })
+    }
+
+    async fn find_billing_run_summary(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<BillingRunSummary> {
+        let mut conn = self.get_conn().await?;
+
+        let row = BillingRunSummaryRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(row.try_into()?)
+    }
+}
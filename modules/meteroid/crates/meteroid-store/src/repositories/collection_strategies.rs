@@ -0,0 +1,159 @@
+use chrono::Utc;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::collection_strategies::CollectionStrategyOverrideRow;
+use diesel_models::customers::CustomerRow;
+use diesel_models::tenants::TenantRow;
+
+use crate::domain::collection_strategies::{
+    normalize_segment, CollectionStrategy, CollectionStrategyOverride,
+    CollectionStrategyOverrideNew,
+};
+use crate::domain::Customer;
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait CollectionStrategyInterface {
+    /// Creates or replaces the collection strategy of the customers of the segment
+    async fn upsert_collection_strategy_override(
+        &self,
+        strategy: CollectionStrategyOverrideNew,
+    ) -> StoreResult<CollectionStrategyOverride>;
+
+    async fn list_collection_strategy_overrides(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<CollectionStrategyOverride>>;
+
+    /// The customers of the segment keep it, and fall back to the strategy of the tenant
+    async fn delete_collection_strategy_override(
+        &self,
+        tenant_id: Uuid,
+        segment: String,
+    ) -> StoreResult<()>;
+
+    /// Moves the customer to the segment, or out of any segment if None
+    async fn set_customer_collection_segment(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        segment: Option<String>,
+    ) -> StoreResult<Customer>;
+
+    /// The strategy of the segment of the customer if overridden, else the strategy of the tenant
+    async fn get_customer_collection_strategy(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CollectionStrategy>;
+}
+
+#[async_trait::async_trait]
+impl CollectionStrategyInterface for Store {
+    async fn upsert_collection_strategy_override(
+        &self,
+        strategy: CollectionStrategyOverrideNew,
+    ) -> StoreResult<CollectionStrategyOverride> {
+        let row = strategy.into_row(Utc::now().naive_utc())?;
+
+        let mut conn = self.get_conn().await?;
+
+        let row = row
+            .upsert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(row.try_into()?)
+    }
+
+    async fn list_collection_strategy_overrides(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<CollectionStrategyOverride>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows = CollectionStrategyOverrideRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(rows
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?)
+    }
+
+    async fn delete_collection_strategy_override(
+        &self,
+        tenant_id: Uuid,
+        segment: String,
+    ) -> StoreResult<()> {
+        let segment = normalize_segment(&segment)?;
+
+        let mut conn = self.get_conn().await?;
+
+        let deleted = CollectionStrategyOverrideRow::delete(&mut conn, tenant_id, &segment)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if deleted == 0 {
+            return Err(StoreError::ValueNotFound(format!(
+                "collection strategy of segment {}",
+                segment
+            ))
+            .into());
+        }
+
+        Ok(())
+    }
+
+    async fn set_customer_collection_segment(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        segment: Option<String>,
+    ) -> StoreResult<Customer> {
+        let segment = segment.map(|s| normalize_segment(&s)).transpose()?;
+
+        let mut conn = self.get_conn().await?;
+
+        CustomerRow::update_collection_segment(&mut conn, customer_id, tenant_id, segment)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .ok_or_else(|| StoreError::ValueNotFound(format!("customer {}", customer_id)))?
+            .try_into()
+    }
+
+    async fn get_customer_collection_strategy(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CollectionStrategy> {
+        let mut conn = self.get_conn().await?;
+
+        let customer = CustomerRow::find_by_id(&mut conn, customer_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let tenant_preset = TenantRow::get_collection_strategy_preset_by_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let segment_override = match &customer.collection_segment {
+            Some(segment) => {
+                CollectionStrategyOverrideRow::find_by_segment(&mut conn, tenant_id, segment)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .map(TryInto::try_into)
+                    .transpose()?
+            }
+            None => None,
+        };
+
+        Ok(CollectionStrategy::resolve(
+            tenant_preset.into(),
+            segment_override,
+        ))
+    }
+}
@@ -0,0 +1,146 @@
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::add_ons::AddOnRow;
+use diesel_models::component_rules::{ComponentRuleRow, ComponentRuleRowNew};
+use diesel_models::price_components::PriceComponentRow;
+use diesel_models::PgConn;
+
+use crate::domain::component_rules::{
+    check_component_rules, ComponentRule, ComponentRuleItem, ComponentRuleNew,
+    ComponentRuleViolation,
+};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait ComponentRulesInterface {
+    async fn insert_component_rule(&self, rule: ComponentRuleNew) -> StoreResult<ComponentRule>;
+
+    async fn list_component_rules(&self, tenant_id: Uuid) -> StoreResult<Vec<ComponentRule>>;
+
+    async fn delete_component_rule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    /// The rules of the tenant violated by a combination of items
+    async fn check_component_rules(
+        &self,
+        tenant_id: Uuid,
+        items: Vec<ComponentRuleItem>,
+    ) -> StoreResult<Vec<ComponentRuleViolation>>;
+}
+
+#[async_trait::async_trait]
+impl ComponentRulesInterface for Store {
+    async fn insert_component_rule(&self, rule: ComponentRuleNew) -> StoreResult<ComponentRule> {
+        if rule.subject == rule.target {
+            return Err(StoreError::InvalidArgument(
+                "a component rule cannot target its own subject".to_string(),
+            )
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let subject_plan_version_id =
+            find_item_plan_version_id(&mut conn, rule.tenant_id, &rule.subject).await?;
+        let target_plan_version_id =
+            find_item_plan_version_id(&mut conn, rule.tenant_id, &rule.target).await?;
+
+        // price components are sold with their plan version, a rule between two versions would never apply
+        if let (Some(subject), Some(target)) = (subject_plan_version_id, target_plan_version_id) {
+            if subject != target {
+                return Err(StoreError::InvalidArgument(
+                    "price components of a rule must belong to the same plan version".to_string(),
+                )
+                .into());
+            }
+        }
+
+        let existing = load_component_rules(&mut conn, rule.tenant_id).await?;
+
+        if existing
+            .iter()
+            .any(|r| r.subject == rule.subject && r.target == rule.target)
+        {
+            return Err(StoreError::InvalidArgument(format!(
+                "a rule already exists between {} and {}",
+                rule.subject, rule.target
+            ))
+            .into());
+        }
+
+        let row: ComponentRuleRowNew = rule.into();
+
+        let inserted = row
+            .insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(inserted.try_into()?)
+    }
+
+    async fn list_component_rules(&self, tenant_id: Uuid) -> StoreResult<Vec<ComponentRule>> {
+        let mut conn = self.get_conn().await?;
+
+        load_component_rules(&mut conn, tenant_id).await
+    }
+
+    async fn delete_component_rule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let deleted = ComponentRuleRow::delete(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if deleted == 0 {
+            return Err(StoreError::ValueNotFound(format!("component rule {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    async fn check_component_rules(
+        &self,
+        tenant_id: Uuid,
+        items: Vec<ComponentRuleItem>,
+    ) -> StoreResult<Vec<ComponentRuleViolation>> {
+        let mut conn = self.get_conn().await?;
+
+        let rules = load_component_rules(&mut conn, tenant_id).await?;
+
+        Ok(check_component_rules(&rules, &items))
+    }
+}
+
+/// Checks that the item belongs to the tenant, and returns the plan version of a price component
+async fn find_item_plan_version_id(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    item: &ComponentRuleItem,
+) -> StoreResult<Option<Uuid>> {
+    match item {
+        ComponentRuleItem::PriceComponent(id) => PriceComponentRow::get_by_id(conn, tenant_id, *id)
+            .await
+            .map(|row| Some(row.plan_version_id))
+            .map_err(Into::<Report<StoreError>>::into),
+        ComponentRuleItem::AddOn(id) => AddOnRow::get_by_id(conn, tenant_id, *id)
+            .await
+            .map(|_| None)
+            .map_err(Into::<Report<StoreError>>::into),
+    }
+}
+
+/// The rules of the tenant, to check the items of a subscription
+pub(crate) async fn load_component_rules(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+) -> StoreResult<Vec<ComponentRule>> {
+    let rows = ComponentRuleRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(rows
+        .into_iter()
+        .map(ComponentRule::try_from)
+        .collect::<Result<Vec<_>, _>>()?)
+}
@@ -1,8 +1,9 @@
+use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_models::configs::ProviderConfigRow;
 use error_stack::Report;
 use uuid::Uuid;
 
-use crate::domain::configs::{ProviderConfig, ProviderConfigNew};
+use crate::domain::configs::{invoicing_provider_candidates, ProviderConfig, ProviderConfigNew};
 use crate::domain::enums::InvoicingProviderEnum;
 use crate::errors::StoreError;
 use crate::{Store, StoreResult};
@@ -19,6 +20,34 @@ pub trait ConfigsInterface {
         provider: InvoicingProviderEnum,
         tenant_id: Uuid,
     ) -> StoreResult<ProviderConfig>;
+
+    async fn list_provider_configs(&self, tenant_id: Uuid) -> StoreResult<Vec<ProviderConfig>>;
+
+    async fn set_default_provider_config(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<ProviderConfig>;
+
+    async fn set_provider_config_fallback_priority(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+        fallback_priority: Option<i32>,
+    ) -> StoreResult<ProviderConfig>;
+
+    async fn disable_provider_config(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<()>;
+
+    /// Enabled configurations to try, in order, when issuing an invoice requesting `provider`
+    async fn resolve_invoicing_provider_configs(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<ProviderConfig>>;
 }
 
 #[async_trait::async_trait]
@@ -28,13 +57,39 @@ impl ConfigsInterface for Store {
         config: ProviderConfigNew,
     ) -> StoreResult<ProviderConfig> {
         let insertable = config.to_row(&self.settings.crypt_key)?;
+        let provider = config.invoicing_provider;
 
-        let mut conn = self.get_conn().await?;
+        let row = self
+            .transaction(|conn| {
+                async move {
+                    if insertable.is_default {
+                        ProviderConfigRow::clear_default(conn, insertable.tenant_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
 
-        let row = insertable
-            .insert(&mut conn)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+                    let row = insertable
+                        .insert(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    // the upsert keeps the default flag of an existing configuration
+                    if insertable.is_default && !row.is_default {
+                        return ProviderConfigRow::set_default(
+                            conn,
+                            insertable.tenant_id,
+                            insertable.invoicing_provider.clone(),
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .ok_or_else(|| provider_config_not_found(&provider));
+                    }
+
+                    Ok(row)
+                }
+                .scope_boxed()
+            })
+            .await?;
 
         ProviderConfig::from_row(&self.settings.crypt_key, row)
     }
@@ -52,4 +107,92 @@ impl ConfigsInterface for Store {
 
         ProviderConfig::from_row(&self.settings.crypt_key, row)
     }
+
+    async fn list_provider_configs(&self, tenant_id: Uuid) -> StoreResult<Vec<ProviderConfig>> {
+        let mut conn = self.get_conn().await?;
+
+        ProviderConfigRow::list_enabled_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(|row| ProviderConfig::from_row(&self.settings.crypt_key, row))
+            .collect()
+    }
+
+    async fn set_default_provider_config(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<ProviderConfig> {
+        let row = self
+            .transaction(|conn| {
+                async move {
+                    ProviderConfigRow::clear_default(conn, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    ProviderConfigRow::set_default(conn, tenant_id, provider.clone().into())
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .ok_or_else(|| provider_config_not_found(&provider))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        ProviderConfig::from_row(&self.settings.crypt_key, row)
+    }
+
+    async fn set_provider_config_fallback_priority(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+        fallback_priority: Option<i32>,
+    ) -> StoreResult<ProviderConfig> {
+        let mut conn = self.get_conn().await?;
+
+        let row = ProviderConfigRow::update_fallback_priority(
+            &mut conn,
+            tenant_id,
+            provider.clone().into(),
+            fallback_priority,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .ok_or_else(|| provider_config_not_found(&provider))?;
+
+        ProviderConfig::from_row(&self.settings.crypt_key, row)
+    }
+
+    async fn disable_provider_config(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let updated = ProviderConfigRow::disable(&mut conn, tenant_id, provider.clone().into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if updated == 0 {
+            return Err(provider_config_not_found(&provider));
+        }
+
+        Ok(())
+    }
+
+    async fn resolve_invoicing_provider_configs(
+        &self,
+        provider: InvoicingProviderEnum,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<ProviderConfig>> {
+        let configs = self.list_provider_configs(tenant_id).await?;
+
+        Ok(invoicing_provider_candidates(&provider, configs))
+    }
+}
+
+fn provider_config_not_found(provider: &InvoicingProviderEnum) -> Report<StoreError> {
+    StoreError::ValueNotFound(format!("No enabled {:?} provider config", provider)).into()
 }
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+
+use error_stack::Report;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::credit_limits::{
+    CreditExposureRow, CreditLimitExceededRow, CreditLimitExceededRowNew,
+};
+use diesel_models::customers::CustomerRow;
+
+use crate::domain::credit_limits::{
+    CreditExposure, CreditLimit, CreditLimitExceeded, CreditLimitExceededNew,
+};
+use crate::domain::enums::{CreditLimitEnforcementEnum, CreditLimitSourceEnum};
+use crate::domain::Customer;
+use crate::errors::StoreError;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait CreditLimitInterface {
+    /// Replaces the credit limit of the customer, none removes it
+    async fn update_customer_credit_limit(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        credit_limit: Option<CreditLimit>,
+    ) -> StoreResult<Customer>;
+
+    /// Unpaid invoices and unbilled usage of the customer, in its currency
+    async fn get_customer_credit_exposure(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CreditExposure>;
+
+    async fn find_credit_limit_exceeded(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<CreditLimitExceeded>;
+}
+
+#[async_trait::async_trait]
+impl CreditLimitInterface for Store {
+    async fn update_customer_credit_limit(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        credit_limit: Option<CreditLimit>,
+    ) -> StoreResult<Customer> {
+        if let Some(limit) = &credit_limit {
+            limit.validate()?;
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let updated: Customer = CustomerRow::update_credit_limit(
+            &mut conn,
+            customer_id,
+            tenant_id,
+            credit_limit.as_ref().map(|l| l.cents),
+            credit_limit
+                .map(|l| l.enforcement)
+                .unwrap_or_default()
+                .into(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .ok_or(StoreError::ValueNotFound("customer not found".to_string()))?
+        .try_into()?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::customer_patched(actor, updated.id, tenant_id))
+            .await;
+
+        Ok(updated)
+    }
+
+    async fn get_customer_credit_exposure(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CreditExposure> {
+        let mut conn = self.get_conn().await?;
+
+        // fails if the customer is not in the tenant
+        CustomerRow::find_by_id(&mut conn, customer_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let mut exposures = list_credit_exposures(&mut conn, tenant_id, vec![customer_id]).await?;
+
+        Ok(exposures
+            .remove(&customer_id)
+            .unwrap_or(CreditExposure::none(customer_id)))
+    }
+
+    async fn find_credit_limit_exceeded(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<CreditLimitExceeded> {
+        let mut conn = self.get_conn().await?;
+
+        CreditLimitExceededRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+}
+
+/// Exposures of the customers with invoices in their currency, by customer id
+pub(crate) async fn list_credit_exposures(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    customer_ids: Vec<Uuid>,
+) -> StoreResult<HashMap<Uuid, CreditExposure>> {
+    if customer_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = CreditExposureRow::list_by_customer_ids(conn, tenant_id, customer_ids)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.customer_id, row.into()))
+        .collect())
+}
+
+impl Store {
+    /// Records the exposure of a customer beyond its credit limit for finance review, once a day per source and
+    /// subscription, and emits it through webhooks. Returns false if the limit is enforced by blocking.
+    pub(crate) async fn enforce_credit_limit(
+        &self,
+        conn: &mut PgConn,
+        customer: &Customer,
+        exposure: &CreditExposure,
+        source: CreditLimitSourceEnum,
+        subscription_id: Option<Uuid>,
+    ) -> StoreResult<bool> {
+        let Some(limit) = &customer.credit_limit else {
+            return Ok(true);
+        };
+
+        if !exposure.exceeds(limit) {
+            return Ok(true);
+        }
+
+        let row: CreditLimitExceededRowNew = CreditLimitExceededNew {
+            tenant_id: customer.tenant_id,
+            source,
+            subscription_id,
+            currency: customer.currency.clone(),
+            credit_limit: limit.clone(),
+            exposure: exposure.clone(),
+        }
+        .into();
+
+        let inserted = row
+            .insert_if_absent(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if let Some(inserted) = inserted {
+            let _ = self
+                .eventbus
+                .publish(Event::credit_limit_exceeded(
+                    inserted.id,
+                    customer.tenant_id,
+                ))
+                .await;
+        }
+
+        Ok(limit.enforcement != CreditLimitEnforcementEnum::Block)
+    }
+}
@@ -22,6 +22,17 @@ impl CustomerBalance {
         tenant_id: Uuid,
         cents: i32,
         invoice_id: Option<Uuid>,
+    ) -> StoreResult<CustomerBalanceUpdate> {
+        Self::update_with_note(conn, customer_id, tenant_id, cents, invoice_id, None).await
+    }
+
+    pub async fn update_with_note(
+        conn: &mut PgConn,
+        customer_id: Uuid,
+        tenant_id: Uuid,
+        cents: i32,
+        invoice_id: Option<Uuid>,
+        note: Option<String>,
     ) -> StoreResult<CustomerBalanceUpdate> {
         let _ = CustomerRow::select_for_update(conn, customer_id, tenant_id)
             .await
@@ -44,7 +55,7 @@ impl CustomerBalance {
             id: Uuid::now_v7(),
             amount_cents: cents,
             balance_cents_after: customer_row_updated.balance_value_cents,
-            note: None,
+            note,
             invoice_id,
             tenant_id,
             customer_id,
@@ -0,0 +1,107 @@
+use crate::domain::customer_blackout_windows::{
+    issue_deferred_until, CustomerBlackoutWindow, CustomerBlackoutWindowNew,
+};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use chrono::NaiveDate;
+use diesel_models::customer_blackout_windows::{
+    CustomerBlackoutWindowRow, CustomerBlackoutWindowRowNew,
+};
+use diesel_models::customers::CustomerRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait CustomerBlackoutWindowInterface {
+    /// The windows ending on or after the given date, or all of them
+    async fn list_customer_blackout_windows(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        ending_from: Option<NaiveDate>,
+    ) -> StoreResult<Vec<CustomerBlackoutWindow>>;
+
+    async fn insert_customer_blackout_window(
+        &self,
+        window: CustomerBlackoutWindowNew,
+    ) -> StoreResult<CustomerBlackoutWindow>;
+
+    async fn delete_customer_blackout_window(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    /// The date from which the invoices of the customer will be issued, if they cannot be at the given date
+    async fn get_invoice_issue_deferred_until(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        today: NaiveDate,
+    ) -> StoreResult<Option<NaiveDate>>;
+}
+
+#[async_trait::async_trait]
+impl CustomerBlackoutWindowInterface for Store {
+    async fn list_customer_blackout_windows(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        ending_from: Option<NaiveDate>,
+    ) -> StoreResult<Vec<CustomerBlackoutWindow>> {
+        let mut conn = self.get_conn().await?;
+
+        CustomerBlackoutWindowRow::list_by_customer_id(
+            &mut conn,
+            tenant_id,
+            customer_id,
+            ending_from,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn insert_customer_blackout_window(
+        &self,
+        window: CustomerBlackoutWindowNew,
+    ) -> StoreResult<CustomerBlackoutWindow> {
+        window.validate(chrono::Utc::now().date_naive())?;
+
+        let mut conn = self.get_conn().await?;
+
+        // checks the customer belongs to the tenant
+        CustomerRow::find_by_id(&mut conn, window.customer_id, window.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        CustomerBlackoutWindowRowNew::from(window)
+            .insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn delete_customer_blackout_window(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let deleted = CustomerBlackoutWindowRow::delete(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if deleted == 0 {
+            return Err(StoreError::ValueNotFound(format!("blackout window {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    async fn get_invoice_issue_deferred_until(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        today: NaiveDate,
+    ) -> StoreResult<Option<NaiveDate>> {
+        let windows = self
+            .list_customer_blackout_windows(tenant_id, customer_id, Some(today))
+            .await?;
+
+        Ok(issue_deferred_until(&windows, today))
+    }
+}
@@ -2,25 +2,35 @@ use diesel_async::scoped_futures::ScopedFutureExt;
 use error_stack::Report;
 use uuid::Uuid;
 
-use crate::domain::enums::{InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum};
+use crate::domain::enums::{
+    BillingEmailStatusEnum, InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum,
+};
+use crate::domain::payment_terms::{PaymentTerms, TenantPaymentTerms};
 use crate::domain::{
-    Customer, CustomerBrief, CustomerBuyCredits, CustomerNew, CustomerNewWrapper, CustomerPatch,
-    CustomerTopUpBalance, DetailedInvoice, InlineCustomer, InlineInvoicingEntity, InvoiceNew,
-    InvoiceTotals, InvoiceTotalsParams, InvoicingEntity, LineItem, OrderByRequest, PaginatedVec,
-    PaginationRequest,
+    BillingConfig, Customer, CustomerBrief, CustomerBuyCredits, CustomerNew, CustomerNewWrapper,
+    CustomerPatch, CustomerPaymentTerms, CustomerTopUpBalance, DetailedInvoice, GoCardless,
+    InlineCustomer, InlineInvoicingEntity, InvoiceNew, InvoiceTotals, InvoiceTotalsParams,
+    InvoicingEntity, LineItem, OrderByRequest, PaginatedVec, PaginationRequest, StripeCustomerSync,
+    StripeCustomerSyncOutcome,
 };
 use crate::errors::StoreError;
 use crate::repositories::customer_balance::CustomerBalance;
 use crate::repositories::invoices::insert_invoice;
-use crate::repositories::invoicing_entities::InvoicingEntityInterface;
+use crate::repositories::invoicing_entities::{
+    displayed_tax_registrations, InvoicingEntityInterface,
+};
 use crate::repositories::InvoiceInterface;
 use crate::store::Store;
 use crate::utils::local_id::{IdType, LocalId};
 use crate::StoreResult;
 use common_eventbus::Event;
 use diesel_models::customer_balance_txs::CustomerBalancePendingTxRowNew;
-use diesel_models::customers::{CustomerRow, CustomerRowNew, CustomerRowPatch};
+use diesel_models::customers::{
+    CustomerRow, CustomerRowNew, CustomerRowPatch, CustomerRowPaymentTermsPatch,
+};
+use diesel_models::errors::DatabaseErrorContainer;
 use diesel_models::invoicing_entities::InvoicingEntityRow;
+use diesel_models::tenants::TenantRow;
 
 #[async_trait::async_trait]
 pub trait CustomersInterface {
@@ -56,6 +66,15 @@ pub trait CustomersInterface {
         tenant_id: Uuid,
     ) -> StoreResult<Vec<Customer>>;
 
+    /// Returns the active customer with the same alias, or the same email or alias when the tenant enforces their
+    /// uniqueness. The customer is created if none matches, the flag is set in that case.
+    /// Safe to retry, concurrent calls return the same customer when the tenant enforces uniqueness
+    async fn find_or_create_customer(
+        &self,
+        customer: CustomerNew,
+        tenant_id: Uuid,
+    ) -> StoreResult<(Customer, bool)>;
+
     async fn patch_customer(
         &self,
         actor: Uuid,
@@ -63,9 +82,44 @@ pub trait CustomersInterface {
         customer: CustomerPatch,
     ) -> StoreResult<Option<Customer>>;
 
+    async fn update_customer_payment_terms(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        terms: CustomerPaymentTerms,
+    ) -> StoreResult<Customer>;
+
     async fn top_up_customer_balance(&self, req: CustomerTopUpBalance) -> StoreResult<Customer>;
 
     async fn buy_customer_credits(&self, req: CustomerBuyCredits) -> StoreResult<DetailedInvoice>;
+
+    /// Links the Stripe customer to a customer, matched by its already linked Stripe id, then by the meteroid id
+    /// in the Stripe metadata, then by email. A customer is created if none matches.
+    async fn sync_stripe_customer(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer: StripeCustomerSync,
+    ) -> StoreResult<(Customer, StripeCustomerSyncOutcome)>;
+
+    /// Falls back to manual billing for the customer linked to a deleted Stripe customer
+    async fn unlink_stripe_customer(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        stripe_customer_id: String,
+    ) -> StoreResult<Option<Customer>>;
+
+    /// Replaces the GoCardless mandate the customer is collected under, or removes it once failed, cancelled
+    /// or expired. Returns the customer collected under the mandate, if any
+    async fn replace_gocardless_mandate(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        mandate_id: String,
+        new_mandate_id: Option<String>,
+    ) -> StoreResult<Option<Customer>>;
 }
 
 #[async_trait::async_trait]
@@ -161,6 +215,10 @@ impl CustomersInterface for Store {
         customer: CustomerNew,
         tenant_id: Uuid,
     ) -> StoreResult<Customer> {
+        if let Some(policy) = &customer.due_date_policy {
+            policy.validate()?;
+        }
+
         let mut conn = self.get_conn().await?;
 
         let invoicing_entity = self
@@ -177,7 +235,7 @@ impl CustomersInterface for Store {
         let res: Customer = customer
             .insert(&mut conn)
             .await
-            .map_err(Into::into)
+            .map_err(customer_conflict)
             .and_then(TryInto::try_into)?;
 
         let _ = self
@@ -231,7 +289,7 @@ impl CustomersInterface for Store {
 
         let res: Vec<Customer> = CustomerRow::insert_customer_batch(&mut conn, insertable_batch)
             .await
-            .map_err(Into::into)
+            .map_err(customer_conflict)
             .and_then(|v| v.into_iter().map(TryInto::try_into).collect())?;
 
         let _ = futures::future::join_all(res.clone().into_iter().map(|res| {
@@ -248,6 +306,39 @@ impl CustomersInterface for Store {
         Ok(res)
     }
 
+    async fn find_or_create_customer(
+        &self,
+        customer: CustomerNew,
+        tenant_id: Uuid,
+    ) -> StoreResult<(Customer, bool)> {
+        let mut conn = self.get_conn().await?;
+
+        let alias = customer.alias.clone();
+        let email = customer.email.clone();
+
+        if let Some(existing) =
+            CustomerRow::find_by_unique_keys(&mut conn, tenant_id, alias.clone(), email.clone())
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+        {
+            return Ok((existing.try_into()?, false));
+        }
+
+        match self.insert_customer(customer, tenant_id).await {
+            Ok(created) => Ok((created, true)),
+            // created concurrently
+            Err(err) if matches!(err.current_context(), StoreError::DuplicateValue { .. }) => {
+                let existing = CustomerRow::find_by_unique_keys(&mut conn, tenant_id, alias, email)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .ok_or(err)?;
+
+                Ok((existing.try_into()?, false))
+            }
+            Err(err) => Err(err),
+        }
+    }
+
     async fn patch_customer(
         &self,
         actor: Uuid,
@@ -256,6 +347,17 @@ impl CustomersInterface for Store {
     ) -> StoreResult<Option<Customer>> {
         let mut conn = self.get_conn().await?;
 
+        let previous_billing_email =
+            if customer.email.is_some() || customer.invoicing_email.is_some() {
+                let previous: Customer = CustomerRow::find_by_id(&mut conn, customer.id, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .try_into()?;
+                previous.billing_email().cloned()
+            } else {
+                None
+            };
+
         let patch_model: CustomerRowPatch = CustomerRowPatch {
             id: customer.id,
             name: customer.name,
@@ -273,12 +375,35 @@ impl CustomersInterface for Store {
         let updated = patch_model
             .update(&mut conn, tenant_id)
             .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+            .map_err(customer_conflict)?;
 
         match updated {
             None => Ok(None),
             Some(updated) => {
-                let updated: Customer = updated.try_into()?;
+                let mut updated: Customer = updated.try_into()?;
+
+                // the status tracks an address, a new billing email has to be verified again
+                if previous_billing_email.is_some()
+                    && previous_billing_email.as_ref() != updated.billing_email()
+                    && updated.billing_email_status != BillingEmailStatusEnum::Unverified
+                {
+                    updated = CustomerRow::update_billing_email_status(
+                        &mut conn,
+                        updated.id,
+                        tenant_id,
+                        BillingEmailStatusEnum::Unverified.into(),
+                        None,
+                        chrono::Utc::now().naive_utc(),
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .try_into()?;
+
+                    let _ = self
+                        .eventbus
+                        .publish(Event::customer_billing_email_updated(updated.id, tenant_id))
+                        .await;
+                }
 
                 let _ = self
                     .eventbus
@@ -290,6 +415,315 @@ impl CustomersInterface for Store {
         }
     }
 
+    async fn update_customer_payment_terms(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        terms: CustomerPaymentTerms,
+    ) -> StoreResult<Customer> {
+        if let Some(discount) = &terms.early_payment_discount {
+            discount.validate()?;
+        }
+
+        if let Some(policy) = &terms.due_date_policy {
+            policy.validate()?;
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let patch = CustomerRowPaymentTermsPatch {
+            net_terms_override: terms.net_terms_override.map(|v| v as i32),
+            early_payment_discount_percent: terms
+                .early_payment_discount
+                .as_ref()
+                .map(|d| d.percent),
+            early_payment_discount_days: terms
+                .early_payment_discount
+                .as_ref()
+                .map(|d| d.days as i32),
+            due_date_policy: terms.due_date_policy.map(|p| p.policy.into()),
+            due_date_day: terms.due_date_policy.and_then(|p| p.day).map(|d| d as i32),
+        };
+
+        let updated: Customer = patch
+            .update(&mut conn, customer_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .ok_or(StoreError::ValueNotFound("customer not found".to_string()))?
+            .try_into()?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::customer_patched(actor, updated.id, tenant_id))
+            .await;
+
+        Ok(updated)
+    }
+
+    async fn sync_stripe_customer(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        sync: StripeCustomerSync,
+    ) -> StoreResult<(Customer, StripeCustomerSyncOutcome)> {
+        let (customer, outcome) = self
+            .transaction(|conn| {
+                async move {
+                    let linked = CustomerRow::find_by_stripe_customer_id(
+                        conn,
+                        tenant_id,
+                        sync.stripe_customer_id.clone(),
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    if let Some(linked) = linked {
+                        CustomerRowPatch {
+                            id: linked.id,
+                            name: sync.name.clone(),
+                            alias: None,
+                            email: sync.email.clone(),
+                            invoicing_email: None,
+                            phone: sync.phone.clone(),
+                            balance_value_cents: None,
+                            currency: None,
+                            billing_address: sync
+                                .billing_address
+                                .clone()
+                                .map(TryInto::try_into)
+                                .transpose()?,
+                            shipping_address: None,
+                            invoicing_entity_id: None,
+                        }
+                        .update(conn, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                        let customer: Customer = linked.try_into()?;
+                        let updated = CustomerRow::update_billing_config(
+                            conn,
+                            customer.id,
+                            tenant_id,
+                            stripe_billing_config(&customer.billing_config, &sync).try_into()?,
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                        return Ok((updated, StripeCustomerSyncOutcome::Updated));
+                    }
+
+                    let matched = match sync.meteroid_customer_id {
+                        Some(id) => CustomerRow::list_by_ids(conn, vec![id])
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into_iter()
+                            .find(|c| c.tenant_id == tenant_id),
+                        None => None,
+                    };
+
+                    let matched = match (matched, &sync.email) {
+                        (Some(matched), _) => Some(matched),
+                        (None, Some(email)) => {
+                            let mut candidates =
+                                CustomerRow::list_by_email(conn, tenant_id, email.clone())
+                                    .await
+                                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                            if candidates.len() > 1 {
+                                return Err(StoreError::InvalidArgument(format!(
+                                    "{} customers match the email of the Stripe customer {}",
+                                    candidates.len(),
+                                    sync.stripe_customer_id
+                                ))
+                                .into());
+                            }
+                            candidates.pop()
+                        }
+                        (None, None) => None,
+                    };
+
+                    match matched {
+                        Some(matched) => {
+                            let customer: Customer = matched.try_into()?;
+
+                            if let BillingConfig::Stripe(stripe) = &customer.billing_config {
+                                return Err(StoreError::InvalidArgument(format!(
+                                    "customer {} is already linked to the Stripe customer {}",
+                                    customer.id, stripe.customer_id
+                                ))
+                                .into());
+                            }
+
+                            let updated = CustomerRow::update_billing_config(
+                                conn,
+                                customer.id,
+                                tenant_id,
+                                stripe_billing_config(&customer.billing_config, &sync)
+                                    .try_into()?,
+                            )
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                            Ok((updated, StripeCustomerSyncOutcome::Linked))
+                        }
+                        None => {
+                            let invoicing_entity =
+                                InvoicingEntityRow::get_default_invoicing_entity_for_tenant(
+                                    conn, &tenant_id,
+                                )
+                                .await
+                                .map_err(Into::<Report<StoreError>>::into)?;
+
+                            let currency = match &sync.currency {
+                                Some(currency) => currency.to_uppercase(),
+                                None => self
+                                    .internal
+                                    .get_reporting_currency_by_tenant_id(conn, tenant_id)
+                                    .await?
+                                    .code
+                                    .to_string(),
+                            };
+
+                            let customer_new: CustomerRowNew = CustomerNewWrapper {
+                                inner: CustomerNew {
+                                    name: sync
+                                        .name
+                                        .clone()
+                                        .or(sync.email.clone())
+                                        .unwrap_or(sync.stripe_customer_id.clone()),
+                                    billing_config: stripe_billing_config(
+                                        &BillingConfig::Manual,
+                                        &sync,
+                                    ),
+                                    alias: None,
+                                    email: sync.email.clone(),
+                                    invoicing_email: None,
+                                    phone: sync.phone.clone(),
+                                    balance_value_cents: 0,
+                                    currency,
+                                    billing_address: sync.billing_address.clone(),
+                                    shipping_address: None,
+                                    net_terms_override: None,
+                                    early_payment_discount: None,
+                                    due_date_policy: None,
+                                    created_by: actor,
+                                    invoicing_entity_id: None,
+                                    force_created_date: None,
+                                },
+                                tenant_id,
+                                invoicing_entity_id: invoicing_entity.id,
+                            }
+                            .try_into()?;
+
+                            let created = customer_new
+                                .insert(conn)
+                                .await
+                                .map_err(Into::<Report<StoreError>>::into)?;
+
+                            Ok((created, StripeCustomerSyncOutcome::Created))
+                        }
+                    }
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let customer: Customer = customer.try_into()?;
+
+        let event = match outcome {
+            StripeCustomerSyncOutcome::Created => {
+                Event::customer_created(actor, customer.id, tenant_id)
+            }
+            StripeCustomerSyncOutcome::Linked | StripeCustomerSyncOutcome::Updated => {
+                Event::customer_patched(actor, customer.id, tenant_id)
+            }
+        };
+        let _ = self.eventbus.publish(event).await;
+
+        Ok((customer, outcome))
+    }
+
+    async fn unlink_stripe_customer(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        stripe_customer_id: String,
+    ) -> StoreResult<Option<Customer>> {
+        let mut conn = self.get_conn().await?;
+
+        let linked =
+            CustomerRow::find_by_stripe_customer_id(&mut conn, tenant_id, stripe_customer_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        let linked = match linked {
+            Some(linked) => linked,
+            None => return Ok(None),
+        };
+
+        let updated: Customer = CustomerRow::update_billing_config(
+            &mut conn,
+            linked.id,
+            tenant_id,
+            BillingConfig::Manual.try_into()?,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .try_into()?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::customer_patched(actor, updated.id, tenant_id))
+            .await;
+
+        Ok(Some(updated))
+    }
+
+    async fn replace_gocardless_mandate(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        mandate_id: String,
+        new_mandate_id: Option<String>,
+    ) -> StoreResult<Option<Customer>> {
+        let mut conn = self.get_conn().await?;
+
+        let linked: Customer =
+            match CustomerRow::find_by_gocardless_mandate_id(&mut conn, tenant_id, mandate_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+            {
+                Some(linked) => linked.try_into()?,
+                None => return Ok(None),
+            };
+
+        let billing_config = match linked.billing_config {
+            BillingConfig::GoCardless(gocardless) => BillingConfig::GoCardless(GoCardless {
+                mandate_id: new_mandate_id,
+                ..gocardless
+            }),
+            other => other,
+        };
+
+        let updated: Customer = CustomerRow::update_billing_config(
+            &mut conn,
+            linked.id,
+            tenant_id,
+            billing_config.try_into()?,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .try_into()?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::customer_patched(actor, updated.id, tenant_id))
+            .await;
+
+        Ok(Some(updated))
+    }
+
     async fn top_up_customer_balance(&self, req: CustomerTopUpBalance) -> StoreResult<Customer> {
         self.transaction(|conn| {
             async move {
@@ -329,6 +763,7 @@ impl CustomersInterface for Store {
                         product_id: None,
                         metric_id: None,
                         description: None,
+                        quantity_display_precision: None,
                     }];
 
                     let totals = InvoiceTotals::from_params(InvoiceTotalsParams {
@@ -353,12 +788,37 @@ impl CustomersInterface for Store {
 
                     let address = invoicing_entity.address();
 
-                    let due_at = if invoicing_entity.net_terms > 0 {
-                        Some(
-                            (now.date()
-                                + chrono::Duration::days(invoicing_entity.net_terms as i64))
-                            .and_time(chrono::NaiveTime::MIN),
-                        )
+                    let tax_registrations = displayed_tax_registrations(
+                        conn,
+                        &invoicing_entity.id,
+                        &req.tenant_id,
+                        customer
+                            .billing_address
+                            .as_ref()
+                            .and_then(|a| a.get("country"))
+                            .and_then(|c| c.as_str()),
+                    )
+                    .await?;
+
+                    let tenant_payment_terms: TenantPaymentTerms =
+                        TenantRow::get_payment_terms_by_id(conn, req.tenant_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into();
+
+                    let domain_customer: Customer = customer.clone().try_into()?;
+
+                    let payment_terms = PaymentTerms::resolve(
+                        &domain_customer,
+                        None,
+                        &tenant_payment_terms,
+                        invoicing_entity.net_terms as u32,
+                    );
+
+                    let net_terms = payment_terms.net_terms as i32;
+
+                    let due_at = if net_terms > 0 {
+                        Some(tenant_payment_terms.due_at(payment_terms.due_date(now.date())))
                     } else {
                         None
                     };
@@ -388,7 +848,7 @@ impl CustomersInterface for Store {
                         invoice_date: now.date(),
                         total: totals.total,
                         amount_due: totals.amount_due,
-                        net_terms: invoicing_entity.net_terms,
+                        net_terms,
                         reference: None,
                         memo: None, // TODO
                         plan_version_id: None,
@@ -406,6 +866,8 @@ impl CustomersInterface for Store {
                             alias: customer.alias,
                             email: customer.email,
                             vat_number: None, // TODO
+                            // credits are sold at face value
+                            early_payment_discount: None,
                             snapshot_at: now,
                         },
                         seller_details: InlineInvoicingEntity {
@@ -413,8 +875,11 @@ impl CustomersInterface for Store {
                             id: invoicing_entity.id,
                             legal_name: invoicing_entity.legal_name.clone(),
                             vat_number: invoicing_entity.vat_number.clone(),
+                            tax_registrations,
                             snapshot_at: now,
                         },
+                        billing_cadence: None,
+                        replaces_invoice_id: None,
                     };
 
                     let inserted_invoice = insert_invoice(conn, invoice_new).await?;
@@ -452,3 +917,37 @@ impl CustomersInterface for Store {
         self.find_invoice_by_id(req.tenant_id, invoice.id).await
     }
 }
+
+/// Conflict on one of the unique keys of the customers of a tenant
+pub(crate) fn customer_conflict(err: DatabaseErrorContainer) -> Report<StoreError> {
+    let key = match err.constraint_name() {
+        Some("customer_tenant_id_unique_email_idx") => "email",
+        Some("customer_tenant_id_unique_alias_idx" | "customer_tenant_id_alias_idx") => "alias",
+        _ => return err.into(),
+    };
+
+    err.error.change_context(StoreError::DuplicateValue {
+        entity: "customer",
+        key: Some(key.to_string()),
+    })
+}
+
+// matches CustomerBillingConfig.Stripe.CollectionMethod in the api
+const STRIPE_SEND_INVOICE: i32 = 0;
+const STRIPE_CHARGE_AUTOMATICALLY: i32 = 1;
+
+/// Keeps the collection method of an already linked customer, otherwise charges automatically if there is
+/// a payment method to charge
+fn stripe_billing_config(current: &BillingConfig, sync: &StripeCustomerSync) -> BillingConfig {
+    let collection_method = match current {
+        BillingConfig::Stripe(stripe) => stripe.collection_method,
+        _ if sync.default_payment_method_id.is_some() => STRIPE_CHARGE_AUTOMATICALLY,
+        _ => STRIPE_SEND_INVOICE,
+    };
+
+    BillingConfig::Stripe(crate::domain::Stripe {
+        customer_id: sync.stripe_customer_id.clone(),
+        collection_method,
+        default_payment_method_id: sync.default_payment_method_id.clone(),
+    })
+}
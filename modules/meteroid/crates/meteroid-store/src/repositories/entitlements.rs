@@ -0,0 +1,177 @@
+use cached::proc_macro::cached;
+use cached::Cached;
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::plan_version_features::{PlanVersionFeatureRow, PlanVersionFeatureRowNew};
+use diesel_models::plan_versions::PlanVersionRow;
+
+use crate::compute::InvoiceLineInterface;
+use crate::domain::entitlements::{merge_features, CustomerEntitlements, Entitlement, PlanFeature};
+use crate::errors::StoreError;
+use crate::repositories::SubscriptionInterface;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait EntitlementInterface {
+    /// Included units of the usage and capacity fees of the subscription, consumed and remaining in their current period
+    async fn get_entitlements(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<Entitlement>>;
+
+    /// Replaces the features granted by the plan version
+    async fn set_plan_version_features(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+        features: Vec<PlanFeature>,
+    ) -> StoreResult<Vec<PlanFeature>>;
+
+    async fn list_plan_version_features(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> StoreResult<Vec<PlanFeature>>;
+
+    /// Features granted to the customer by its subscriptions in effect, to gate the product.
+    /// Cached, and invalidated when a subscription of the customer starts, switches plan or is canceled
+    async fn get_customer_entitlements(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CustomerEntitlements>;
+}
+
+#[async_trait::async_trait]
+impl EntitlementInterface for Store {
+    async fn get_entitlements(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<Entitlement>> {
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        // usage of today is included
+        let until = now.date().succ_opt().unwrap_or(now.date());
+
+        if until <= subscription.billing_start_date {
+            return Ok(vec![]);
+        }
+
+        let entitlements = self.compute_entitlements(&until, &subscription).await?;
+
+        Ok(entitlements)
+    }
+
+    async fn set_plan_version_features(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+        features: Vec<PlanFeature>,
+    ) -> StoreResult<Vec<PlanFeature>> {
+        PlanFeature::validate_all(&features)?;
+
+        let inserted = self
+            .transaction(|conn| {
+                async move {
+                    PlanVersionRow::find_by_id_and_tenant_id(conn, plan_version_id, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    PlanVersionFeatureRow::delete_by_plan_version_id(
+                        conn,
+                        tenant_id,
+                        plan_version_id,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    let rows: Vec<PlanVersionFeatureRowNew> = features
+                        .into_iter()
+                        .map(|f| f.into_row(tenant_id, plan_version_id))
+                        .collect();
+
+                    PlanVersionFeatureRowNew::insert_batch(conn, rows.iter().collect())
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // any customer may be subscribed to the version
+        invalidate_all_customer_entitlements_cache().await;
+
+        Ok(inserted.into_iter().map(Into::into).collect())
+    }
+
+    async fn list_plan_version_features(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> StoreResult<Vec<PlanFeature>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows =
+            PlanVersionFeatureRow::list_by_plan_version_id(&mut conn, tenant_id, plan_version_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_customer_entitlements(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+    ) -> StoreResult<CustomerEntitlements> {
+        let mut conn = self.get_conn().await?;
+
+        get_customer_entitlements_cached(&mut conn, tenant_id, customer_id).await
+    }
+}
+
+// the subscriptions reaching their end date are not invalidated, they expire with the cache entry
+#[cached(
+    result = true,
+    size = 10000,
+    time = 300, // 5min
+    key = "(Uuid, Uuid)",
+    convert = r#"{ (tenant_id, customer_id) }"#
+)]
+async fn get_customer_entitlements_cached(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    customer_id: Uuid,
+) -> StoreResult<CustomerEntitlements> {
+    let today = chrono::Utc::now().date_naive();
+
+    let rows = PlanVersionFeatureRow::list_by_customer_id(conn, tenant_id, customer_id, today)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(CustomerEntitlements {
+        customer_id,
+        features: merge_features(rows.into_iter().map(Into::into).collect()),
+    })
+}
+
+pub async fn invalidate_customer_entitlements_cache(tenant_id: Uuid, customer_id: Uuid) {
+    GET_CUSTOMER_ENTITLEMENTS_CACHED
+        .lock()
+        .await
+        .cache_remove(&(tenant_id, customer_id));
+}
+
+async fn invalidate_all_customer_entitlements_cache() {
+    GET_CUSTOMER_ENTITLEMENTS_CACHED.lock().await.cache_clear();
+}
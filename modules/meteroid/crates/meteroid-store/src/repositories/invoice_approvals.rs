@@ -0,0 +1,476 @@
+use crate::domain::enums::{
+    InvoiceApprovalActionEnum, InvoiceApprovalStatusEnum, InvoiceStatusEnum, TenantMemberRoleEnum,
+};
+use crate::domain::invoice_approvals::{
+    applicable_steps, approval_state, new_approvals, validate_approval_chain, validate_decision,
+    InvoiceApproval, InvoiceApprovalDetails, InvoiceApprovalEvent, InvoiceApprovalState,
+    InvoiceApprovalStep, InvoiceApprovalStepNew,
+};
+use crate::domain::Invoice;
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::invoice_approvals::{
+    InvoiceApprovalEventRow, InvoiceApprovalEventRowNew, InvoiceApprovalRow, InvoiceApprovalStepRow,
+};
+use diesel_models::invoices::InvoiceRow;
+use diesel_models::PgConn;
+use error_stack::Report;
+use uuid::Uuid;
+
+const ESCALATION_BATCH_SIZE: i64 = 100;
+
+#[async_trait::async_trait]
+pub trait InvoiceApprovalInterface {
+    async fn get_invoice_approval_chain(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<InvoiceApprovalStep>>;
+
+    /// Replaces the approval chain of the tenant. The approvals already requested keep their steps
+    async fn set_invoice_approval_chain(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        steps: Vec<InvoiceApprovalStepNew>,
+    ) -> StoreResult<Vec<InvoiceApprovalStep>>;
+
+    async fn get_invoice_approval(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<InvoiceApprovalDetails>;
+
+    /// Starts the approval of a draft invoice for its current total, replacing a previous one.
+    /// The actor is none when requested by the finalization
+    async fn request_invoice_approval(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Option<Uuid>,
+    ) -> StoreResult<InvoiceApprovalDetails>;
+
+    /// Approves the current step, the next one becoming pending
+    async fn approve_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        role: TenantMemberRoleEnum,
+        comment: Option<String>,
+    ) -> StoreResult<InvoiceApprovalDetails>;
+
+    /// Rejects the current step, and so the approval, until it is requested again
+    async fn reject_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        role: TenantMemberRoleEnum,
+        comment: Option<String>,
+    ) -> StoreResult<InvoiceApprovalDetails>;
+
+    /// Hands the overdue steps to their escalation assignee, returns the number of escalated steps
+    async fn escalate_overdue_invoice_approvals(&self, now: NaiveDateTime) -> StoreResult<usize>;
+}
+
+#[async_trait::async_trait]
+impl InvoiceApprovalInterface for Store {
+    async fn get_invoice_approval_chain(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<InvoiceApprovalStep>> {
+        let mut conn = self.get_conn().await?;
+
+        InvoiceApprovalStepRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn set_invoice_approval_chain(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        steps: Vec<InvoiceApprovalStepNew>,
+    ) -> StoreResult<Vec<InvoiceApprovalStep>> {
+        validate_approval_chain(&steps)?;
+
+        let rows = steps
+            .into_iter()
+            .enumerate()
+            .map(|(idx, step)| step.into_row(tenant_id, idx as i32, actor))
+            .collect::<Vec<_>>();
+
+        self.transaction(|conn| {
+            async move {
+                InvoiceApprovalStepRow::delete_by_tenant_id(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                if rows.is_empty() {
+                    return Ok(vec![]);
+                }
+
+                let mut inserted = InvoiceApprovalStepRow::insert_batch(conn, rows)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                inserted.sort_by_key(|s| s.position);
+
+                Ok(inserted.into_iter().map(Into::into).collect())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn get_invoice_approval(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<InvoiceApprovalDetails> {
+        let mut conn = self.get_conn().await?;
+
+        let invoice: Invoice = InvoiceRow::find_by_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .invoice
+            .try_into()?;
+
+        approval_details(&mut conn, &invoice).await
+    }
+
+    async fn request_invoice_approval(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Option<Uuid>,
+    ) -> StoreResult<InvoiceApprovalDetails> {
+        self.transaction(|conn| {
+            async move {
+                let invoice = lock_draft_invoice(conn, tenant_id, invoice_id).await?;
+
+                request_approval(conn, &invoice, actor, chrono::Utc::now().naive_utc()).await?;
+
+                approval_details(conn, &invoice).await
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn approve_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        role: TenantMemberRoleEnum,
+        comment: Option<String>,
+    ) -> StoreResult<InvoiceApprovalDetails> {
+        decide(
+            self,
+            tenant_id,
+            invoice_id,
+            actor,
+            role,
+            comment,
+            InvoiceApprovalStatusEnum::Approved,
+        )
+        .await
+    }
+
+    async fn reject_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        role: TenantMemberRoleEnum,
+        comment: Option<String>,
+    ) -> StoreResult<InvoiceApprovalDetails> {
+        decide(
+            self,
+            tenant_id,
+            invoice_id,
+            actor,
+            role,
+            comment,
+            InvoiceApprovalStatusEnum::Rejected,
+        )
+        .await
+    }
+
+    async fn escalate_overdue_invoice_approvals(&self, now: NaiveDateTime) -> StoreResult<usize> {
+        let mut conn = self.get_conn().await?;
+
+        let overdue = InvoiceApprovalRow::list_overdue(&mut conn, now, ESCALATION_BATCH_SIZE)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let mut escalated = 0;
+
+        for approval in overdue {
+            let done = self
+                .transaction(|conn| {
+                    async move {
+                        let updated = InvoiceApprovalRow::escalate(conn, approval.id, now)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                        // decided in the meantime
+                        if updated == 0 {
+                            return Ok(false);
+                        }
+
+                        InvoiceApprovalEventRowNew {
+                            id: Uuid::now_v7(),
+                            tenant_id: approval.tenant_id,
+                            invoice_id: approval.invoice_id,
+                            approval_id: Some(approval.id),
+                            step_name: Some(approval.name.clone()),
+                            action: InvoiceApprovalActionEnum::Escalated.into(),
+                            actor_id: None,
+                            comment: None,
+                        }
+                        .insert(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                        Ok(true)
+                    }
+                    .scope_boxed()
+                })
+                .await?;
+
+            if done {
+                escalated += 1;
+            }
+        }
+
+        Ok(escalated)
+    }
+}
+
+async fn decide(
+    store: &Store,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+    actor: Uuid,
+    role: TenantMemberRoleEnum,
+    comment: Option<String>,
+    status: InvoiceApprovalStatusEnum,
+) -> StoreResult<InvoiceApprovalDetails> {
+    store
+        .transaction(|conn| {
+            async move {
+                let invoice = lock_draft_invoice(conn, tenant_id, invoice_id).await?;
+                let now = chrono::Utc::now().naive_utc();
+
+                let approvals = list_approvals(conn, tenant_id, invoice_id).await?;
+
+                if approval_state(&approvals, true, invoice.total) == InvoiceApprovalState::Outdated
+                {
+                    return Err(StoreError::InvalidArgument(
+                        "the total of the invoice changed, its approval must be requested again"
+                            .to_string(),
+                    )
+                    .into());
+                }
+
+                let current = validate_decision(&approvals, actor, role)?;
+
+                InvoiceApprovalRow::decide(
+                    conn,
+                    current.id,
+                    status.into(),
+                    actor,
+                    comment.clone(),
+                    now,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                insert_event(
+                    conn,
+                    &invoice,
+                    Some(current),
+                    match status {
+                        InvoiceApprovalStatusEnum::Rejected => InvoiceApprovalActionEnum::Rejected,
+                        _ => InvoiceApprovalActionEnum::Approved,
+                    },
+                    Some(actor),
+                    comment,
+                )
+                .await?;
+
+                if status == InvoiceApprovalStatusEnum::Approved {
+                    if let Some(next) = approvals
+                        .iter()
+                        .find(|a| a.status == InvoiceApprovalStatusEnum::Waiting)
+                    {
+                        InvoiceApprovalRow::start(conn, next.id, now, next.next_due_at(now))
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+                }
+
+                approval_details(conn, &invoice).await
+            }
+            .scope_boxed()
+        })
+        .await
+}
+
+/// Whether the invoice can be finalized. If its approval is missing or outdated, it is requested
+pub(crate) async fn ensure_invoice_approved(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+    now: NaiveDateTime,
+) -> StoreResult<bool> {
+    let chain = list_chain(conn, invoice.tenant_id).await?;
+    let required = !applicable_steps(&chain, invoice.total, &invoice.currency).is_empty();
+
+    if !required {
+        return Ok(true);
+    }
+
+    let approvals = list_approvals(conn, invoice.tenant_id, invoice.id).await?;
+
+    match approval_state(&approvals, required, invoice.total) {
+        InvoiceApprovalState::NotRequired | InvoiceApprovalState::Approved => Ok(true),
+        InvoiceApprovalState::Pending | InvoiceApprovalState::Rejected => Ok(false),
+        InvoiceApprovalState::NotRequested | InvoiceApprovalState::Outdated => {
+            request_approval(conn, invoice, None, now).await?;
+            Ok(false)
+        }
+    }
+}
+
+async fn request_approval(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+    actor: Option<Uuid>,
+    now: NaiveDateTime,
+) -> StoreResult<()> {
+    let chain = list_chain(conn, invoice.tenant_id).await?;
+    let steps = applicable_steps(&chain, invoice.total, &invoice.currency);
+
+    if steps.is_empty() {
+        return Err(StoreError::InvalidArgument(
+            "no step of the approval chain applies to this invoice".to_string(),
+        )
+        .into());
+    }
+
+    InvoiceApprovalRow::delete_by_invoice_id(conn, invoice.tenant_id, invoice.id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    InvoiceApprovalRow::insert_batch(
+        conn,
+        new_approvals(&steps, invoice.tenant_id, invoice.id, invoice.total, now),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    insert_event(
+        conn,
+        invoice,
+        None,
+        InvoiceApprovalActionEnum::Requested,
+        actor,
+        None,
+    )
+    .await
+}
+
+async fn lock_draft_invoice(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+) -> StoreResult<Invoice> {
+    InvoiceRow::lock_for_update(conn, invoice_id, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let invoice: Invoice = InvoiceRow::find_by_id(conn, tenant_id, invoice_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .invoice
+        .try_into()?;
+
+    if invoice.status != InvoiceStatusEnum::Draft && invoice.status != InvoiceStatusEnum::Pending {
+        return Err(StoreError::InvalidArgument(
+            "only draft invoices go through an approval".to_string(),
+        )
+        .into());
+    }
+
+    Ok(invoice)
+}
+
+async fn list_chain(conn: &mut PgConn, tenant_id: Uuid) -> StoreResult<Vec<InvoiceApprovalStep>> {
+    InvoiceApprovalStepRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+}
+
+async fn list_approvals(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    invoice_id: Uuid,
+) -> StoreResult<Vec<InvoiceApproval>> {
+    InvoiceApprovalRow::list_by_invoice_id(conn, tenant_id, invoice_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+}
+
+async fn insert_event(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+    approval: Option<&InvoiceApproval>,
+    action: InvoiceApprovalActionEnum,
+    actor: Option<Uuid>,
+    comment: Option<String>,
+) -> StoreResult<()> {
+    InvoiceApprovalEventRowNew {
+        id: Uuid::now_v7(),
+        tenant_id: invoice.tenant_id,
+        invoice_id: invoice.id,
+        approval_id: approval.map(|a| a.id),
+        step_name: approval.map(|a| a.name.clone()),
+        action: action.into(),
+        actor_id: actor,
+        comment,
+    }
+    .insert(conn)
+    .await
+    .map(|_| ())
+    .map_err(Into::<Report<StoreError>>::into)
+}
+
+async fn approval_details(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+) -> StoreResult<InvoiceApprovalDetails> {
+    let chain = list_chain(conn, invoice.tenant_id).await?;
+    let required = !applicable_steps(&chain, invoice.total, &invoice.currency).is_empty();
+
+    let steps = list_approvals(conn, invoice.tenant_id, invoice.id).await?;
+
+    let history: Vec<InvoiceApprovalEvent> =
+        InvoiceApprovalEventRow::list_by_invoice_id(conn, invoice.tenant_id, invoice.id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+    Ok(InvoiceApprovalDetails {
+        state: approval_state(&steps, required, invoice.total),
+        steps,
+        history,
+    })
+}
@@ -0,0 +1,135 @@
+use crate::domain::enums::InvoiceStatusEnum;
+use crate::domain::invoice_attachments::{InvoiceAttachment, InvoiceAttachmentNew};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::invoice_attachments::{InvoiceAttachmentRow, InvoiceAttachmentRowNew};
+use diesel_models::invoices::InvoiceRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait InvoiceAttachmentInterface {
+    /// Records an attachment whose content is already stored, within the limits of the invoice
+    async fn insert_invoice_attachment(
+        &self,
+        attachment: InvoiceAttachmentNew,
+    ) -> StoreResult<InvoiceAttachment>;
+
+    async fn list_invoice_attachments(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<InvoiceAttachment>>;
+
+    async fn get_invoice_attachment(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<InvoiceAttachment>;
+
+    /// An attachment already sent with the email of a finalized invoice cannot be removed
+    async fn remove_invoice_attachment(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<InvoiceAttachment>;
+}
+
+#[async_trait::async_trait]
+impl InvoiceAttachmentInterface for Store {
+    async fn insert_invoice_attachment(
+        &self,
+        attachment: InvoiceAttachmentNew,
+    ) -> StoreResult<InvoiceAttachment> {
+        self.transaction(|conn| {
+            async move {
+                // locks the invoice so that concurrent uploads do not exceed the limits
+                InvoiceRow::lock_for_update(conn, attachment.invoice_id, attachment.tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let existing: Vec<InvoiceAttachment> = InvoiceAttachmentRow::list_by_invoice_id(
+                    conn,
+                    attachment.tenant_id,
+                    attachment.invoice_id,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(Into::into)
+                .collect();
+
+                attachment.validate(&existing)?;
+
+                InvoiceAttachmentRowNew::from(attachment)
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)
+                    .map(Into::into)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn list_invoice_attachments(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<InvoiceAttachment>> {
+        let mut conn = self.get_conn().await?;
+
+        InvoiceAttachmentRow::list_by_invoice_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_invoice_attachment(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<InvoiceAttachment> {
+        let mut conn = self.get_conn().await?;
+
+        InvoiceAttachmentRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .map(Into::into)
+            .ok_or(StoreError::ValueNotFound(format!("invoice attachment {}", id)).into())
+    }
+
+    async fn remove_invoice_attachment(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<InvoiceAttachment> {
+        let attachment = self.get_invoice_attachment(tenant_id, id).await?;
+
+        if attachment.include_in_email {
+            let mut conn = self.get_conn().await?;
+
+            let invoice = InvoiceRow::find_by_id(&mut conn, tenant_id, attachment.invoice_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .invoice;
+
+            if InvoiceStatusEnum::from(invoice.status) != InvoiceStatusEnum::Draft {
+                return Err(StoreError::InvalidArgument(
+                    "the attachment was sent with the invoice email".to_string(),
+                )
+                .into());
+            }
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        InvoiceAttachmentRow::remove(&mut conn, tenant_id, id, actor)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+}
@@ -0,0 +1,139 @@
+use crate::domain::enums::InvoiceDeliveryChannelEnum;
+use crate::domain::invoice_attachments::{InvoiceAttachment, InvoiceEmailAttachment};
+use crate::domain::invoice_delivery::{
+    email_recipient, validate_delivery_channel, InvoiceDeliveryEmailPayload,
+};
+use crate::domain::outbox::{OutboxEvent, OutboxNew};
+use crate::domain::{Customer, Invoice};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use common_eventbus::Event;
+use diesel_models::customers::CustomerRow;
+use diesel_models::invoice_attachments::InvoiceAttachmentRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait InvoiceDeliveryInterface {
+    /// Replaces the channel the invoices of the customer are delivered through, none only issues them to the invoicing provider
+    async fn update_customer_invoice_delivery(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        channel: Option<InvoiceDeliveryChannelEnum>,
+    ) -> StoreResult<Customer>;
+
+    /// Requests the invoice email to the billing email of the customer
+    async fn request_invoice_delivery_email(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+    ) -> StoreResult<()>;
+
+    /// Notifies the webhook endpoints of the tenant, so that they deliver the invoice themselves
+    async fn request_invoice_delivery_webhook(&self, invoice: &Invoice) -> StoreResult<()>;
+}
+
+#[async_trait::async_trait]
+impl InvoiceDeliveryInterface for Store {
+    async fn update_customer_invoice_delivery(
+        &self,
+        actor: Uuid,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        channel: Option<InvoiceDeliveryChannelEnum>,
+    ) -> StoreResult<Customer> {
+        let mut conn = self.get_conn().await?;
+
+        if let Some(channel) = &channel {
+            let customer: Customer = CustomerRow::find_by_id(&mut conn, customer_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .try_into()?;
+
+            validate_delivery_channel(channel, &customer)?;
+        }
+
+        let updated: Customer = CustomerRow::update_invoice_delivery_channel(
+            &mut conn,
+            customer_id,
+            tenant_id,
+            channel.map(Into::into),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .ok_or(StoreError::ValueNotFound("customer not found".to_string()))?
+        .try_into()?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::customer_patched(actor, updated.id, tenant_id))
+            .await;
+
+        Ok(updated)
+    }
+
+    async fn request_invoice_delivery_email(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+    ) -> StoreResult<()> {
+        let recipient = email_recipient(customer.billing_email(), &customer.billing_email_status)?;
+
+        let mut conn = self.get_conn().await?;
+
+        let email_attachments: Vec<InvoiceEmailAttachment> =
+            InvoiceAttachmentRow::list_by_invoice_id(&mut conn, invoice.tenant_id, invoice.id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(InvoiceAttachment::from)
+                .filter(|a| a.include_in_email)
+                .map(|a| InvoiceEmailAttachment::from(&a))
+                .collect();
+
+        let payload = serde_json::to_value(InvoiceDeliveryEmailPayload {
+            invoice_id: invoice.id,
+            recipient,
+            customer_name: customer.name.clone(),
+            invoice_number: invoice.invoice_number.clone(),
+            currency: invoice.currency.clone(),
+            amount_due: invoice.amount_due,
+            due_at: invoice.due_at,
+            pdf_document_id: invoice.pdf_document_id.clone(),
+            email_attachments,
+        })
+        .map_err(|e| {
+            StoreError::SerdeError(
+                "Failed to serialize invoice delivery email payload".to_string(),
+                e,
+            )
+        })?;
+
+        self.internal
+            .insert_outbox_item(
+                &mut conn,
+                OutboxNew {
+                    event_type: OutboxEvent::InvoiceDeliveryEmailRequested,
+                    resource_id: invoice.id,
+                    tenant_id: invoice.tenant_id,
+                    payload: Some(payload),
+                },
+            )
+            .await
+            .map(|_| ())
+    }
+
+    async fn request_invoice_delivery_webhook(&self, invoice: &Invoice) -> StoreResult<()> {
+        let _ = self
+            .eventbus
+            .publish(Event::invoice_delivery_requested(
+                invoice.id,
+                invoice.tenant_id,
+            ))
+            .await;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,147 @@
+use crate::domain::enums::InvoiceExternalStatusEnum;
+use crate::domain::invoice_payments::{InvoicePayment, InvoicePaymentNew, RecordedInvoicePayment};
+use crate::domain::Invoice;
+use crate::errors::StoreError;
+use crate::repositories::invoices::process_invoice_paid;
+use crate::{Store, StoreResult};
+use common_eventbus::Event;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::invoice_payments::{InvoicePaymentRow, InvoicePaymentRowNew};
+use diesel_models::invoices::InvoiceRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait InvoicePaymentInterface {
+    /// Records a payment collected outside of any provider and deducts it from the amount due of the invoice.
+    /// The invoice is paid once nothing remains due
+    async fn record_invoice_payment(
+        &self,
+        payment: InvoicePaymentNew,
+    ) -> StoreResult<RecordedInvoicePayment>;
+
+    async fn find_invoice_payment(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<InvoicePayment>;
+
+    async fn list_invoice_payments(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<InvoicePayment>>;
+}
+
+#[async_trait::async_trait]
+impl InvoicePaymentInterface for Store {
+    async fn record_invoice_payment(
+        &self,
+        payment: InvoicePaymentNew,
+    ) -> StoreResult<RecordedInvoicePayment> {
+        let tenant_id = payment.tenant_id;
+        let invoice_id = payment.invoice_id;
+        let actor = payment.created_by;
+
+        let recorded = self
+            .transaction(|conn| {
+                async move {
+                    // serializes the concurrent payments, each one being checked against the remaining amount due
+                    InvoiceRow::lock_for_update(conn, invoice_id, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    let invoice: Invoice = InvoiceRow::find_by_id(conn, tenant_id, invoice_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .invoice
+                        .try_into()?;
+
+                    payment.validate(
+                        &invoice.status,
+                        &invoice.invoicing_provider,
+                        invoice.amount_due,
+                        chrono::Utc::now().date_naive(),
+                    )?;
+
+                    let deducted =
+                        InvoiceRow::deduct_amount_due(conn, invoice_id, tenant_id, payment.amount)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                    // the invoice changed since it was read, the payment must not be recorded against it
+                    if deducted == 0 {
+                        return Err(StoreError::Conflict(format!(
+                            "Invoice {} changed while recording the payment",
+                            invoice_id
+                        ))
+                        .into());
+                    }
+
+                    let payment: InvoicePayment = InvoicePaymentRowNew::from(payment)
+                        .insert(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .into();
+
+                    let recorded = RecordedInvoicePayment {
+                        amount_due: invoice.amount_due - payment.amount,
+                        payment,
+                    };
+
+                    if recorded.fully_paid() {
+                        InvoiceRow::update_external_status(
+                            conn,
+                            invoice_id,
+                            tenant_id,
+                            InvoiceExternalStatusEnum::Paid.into(),
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                        process_invoice_paid(conn, &invoice, true).await?;
+                    }
+
+                    Ok(recorded)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let _ = self
+            .eventbus
+            .publish(Event::invoice_payment_recorded(
+                actor,
+                recorded.payment.id,
+                tenant_id,
+            ))
+            .await;
+
+        if recorded.fully_paid() {
+            let _ = self
+                .eventbus
+                .publish(Event::invoice_paid(invoice_id, tenant_id))
+                .await;
+        }
+
+        Ok(recorded)
+    }
+
+    async fn find_invoice_payment(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<InvoicePayment> {
+        let mut conn = self.get_conn().await?;
+
+        InvoicePaymentRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_invoice_payments(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<InvoicePayment>> {
+        let mut conn = self.get_conn().await?;
+
+        InvoicePaymentRow::list_by_invoice_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+}
@@ -0,0 +1,145 @@
+use std::collections::HashMap;
+
+use chrono::NaiveDateTime;
+use error_stack::Report;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::invoice_stuck_alerts::{
+    InvoiceStuckAlertRow, InvoiceStuckAlertRowNew, StuckInvoiceRow,
+};
+
+use crate::domain::invoice_watchdog::{InvoiceStuckAlert, InvoiceWatchdogSla, StuckInvoice};
+use crate::errors::StoreError;
+use crate::store::{PgConn, Store};
+use crate::StoreResult;
+
+#[async_trait::async_trait]
+pub trait InvoiceWatchdogInterface {
+    /// Invoices of the tenant that stayed in a status beyond the SLA
+    async fn list_stuck_invoices(
+        &self,
+        tenant_id: Uuid,
+        sla: &InvoiceWatchdogSla,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<StuckInvoice>>;
+
+    /// Alerts (webhook) once for every invoice that got stuck, or got stuck for another reason, since the last run.
+    /// Returns the newly alerted invoices
+    async fn alert_stuck_invoices(
+        &self,
+        sla: &InvoiceWatchdogSla,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<StuckInvoice>>;
+
+    async fn find_invoice_stuck_alert(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<InvoiceStuckAlert>;
+}
+
+#[async_trait::async_trait]
+impl InvoiceWatchdogInterface for Store {
+    async fn list_stuck_invoices(
+        &self,
+        tenant_id: Uuid,
+        sla: &InvoiceWatchdogSla,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<StuckInvoice>> {
+        let mut conn = self.get_conn().await?;
+
+        list_stuck_invoices(&mut conn, Some(tenant_id), sla, now).await
+    }
+
+    async fn alert_stuck_invoices(
+        &self,
+        sla: &InvoiceWatchdogSla,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<StuckInvoice>> {
+        let mut conn = self.get_conn().await?;
+
+        let stuck = list_stuck_invoices(&mut conn, None, sla, now).await?;
+
+        let stuck_ids = stuck.iter().map(|i| i.invoice_id).collect::<Vec<_>>();
+
+        let alerted: HashMap<Uuid, InvoiceStuckAlert> =
+            InvoiceStuckAlertRow::list_unresolved_by_invoice_ids(&mut conn, &stuck_ids)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(|row| (row.invoice_id, row.into()))
+                .collect();
+
+        let mut newly_stuck = Vec::new();
+
+        for invoice in stuck {
+            let already_alerted = alerted
+                .get(&invoice.invoice_id)
+                .is_some_and(|alert| alert.reason == invoice.reason);
+
+            if already_alerted {
+                continue;
+            }
+
+            InvoiceStuckAlertRowNew {
+                invoice_id: invoice.invoice_id,
+                tenant_id: invoice.tenant_id,
+                reason: invoice.reason.into(),
+                alerted_at: now,
+            }
+            .upsert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            let _ = self
+                .eventbus
+                .publish(Event::invoice_stuck(invoice.invoice_id, invoice.tenant_id))
+                .await;
+
+            newly_stuck.push(invoice);
+        }
+
+        // the other open alerts are for invoices that moved on
+        InvoiceStuckAlertRow::resolve_all_except(&mut conn, &stuck_ids, now)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(newly_stuck)
+    }
+
+    async fn find_invoice_stuck_alert(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<InvoiceStuckAlert> {
+        let mut conn = self.get_conn().await?;
+
+        InvoiceStuckAlertRow::find_by_invoice_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .map(Into::into)
+            .ok_or_else(|| {
+                StoreError::ValueNotFound(format!("stuck alert for invoice {}", invoice_id)).into()
+            })
+    }
+}
+
+async fn list_stuck_invoices(
+    conn: &mut PgConn,
+    tenant_id: Option<Uuid>,
+    sla: &InvoiceWatchdogSla,
+    now: NaiveDateTime,
+) -> StoreResult<Vec<StuckInvoice>> {
+    StuckInvoiceRow::list(
+        conn,
+        tenant_id,
+        now,
+        sla.draft_max_age_days as i32,
+        sla.pending_max_age_hours as i32,
+        sla.max_issue_attempts as i32,
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)
+    .map(|rows| rows.into_iter().map(Into::into).collect())
+}
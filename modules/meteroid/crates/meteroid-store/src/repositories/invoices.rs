@@ -1,26 +1,46 @@
-use crate::domain::enums::{InvoiceExternalStatusEnum, InvoiceType};
+use crate::domain::enums::{
+    InvoiceDeliveryChannelEnum, InvoiceExternalStatusEnum, InvoiceType, ZeroInvoicePolicyEnum,
+};
+use crate::domain::mrr_movements::consolidate_mrr_movements;
 use crate::errors::StoreError;
 use crate::store::Store;
 use crate::{domain, StoreResult};
-use chrono::NaiveDateTime;
+use chrono::{NaiveDate, NaiveDateTime};
 use diesel_async::scoped_futures::ScopedFutureExt;
-use diesel_models::enums::{MrrMovementType, SubscriptionEventType};
 use diesel_models::{DbResult, PgConn};
 use error_stack::Report;
 
 use crate::compute::InvoiceLineInterface;
+use crate::domain::invoice_attachments::{
+    InvoiceAttachment, InvoiceEmailAttachment, InvoiceFinalizedPayload,
+};
+use crate::domain::invoice_edits::{apply_draft_lines, DraftInvoiceUpdate, OneOffInvoiceNew};
+use crate::domain::invoices::validate_manual_invoice_date;
+use crate::domain::payment_terms::PaymentTerms;
 use crate::domain::{
-    CursorPaginatedVec, CursorPaginationRequest, DetailedInvoice, Invoice, InvoiceLinesPatch,
-    InvoiceNew, InvoiceWithCustomer, OrderByRequest, OutboxEvent, PaginatedVec, PaginationRequest,
+    CursorPaginatedVec, CursorPaginationRequest, DetailedInvoice, ExternalInvoiceLinks,
+    InlineInvoicingEntity, Invoice, InvoiceLinesPatch, InvoiceNew, InvoiceWithCustomer,
+    OrderByRequest, OutboxEvent, PaginatedVec, PaginationRequest, VoidedInvoice,
 };
 use crate::repositories::customer_balance::CustomerBalance;
-use crate::repositories::SubscriptionInterface;
+use crate::repositories::invoice_approvals::ensure_invoice_approved;
+use crate::repositories::invoicing_entities::{
+    displayed_tax_registrations, InvoicingEntityInterface,
+};
+use crate::repositories::partners::record_partner_commission;
+use crate::repositories::{CustomersInterface, SubscriptionInterface, TenantInterface};
 use crate::utils::decimals::ToUnit;
 use common_eventbus::Event;
 use diesel_models::applied_coupons::{AppliedCouponDetailedRow, AppliedCouponRow};
+use diesel_models::bi::{BiMrrMovementLogRow, BiMrrMovementLogRowNew};
 use diesel_models::customer_balance_txs::CustomerBalancePendingTxRow;
-use diesel_models::invoices::{InvoiceRow, InvoiceRowLinesPatch, InvoiceRowNew};
+use diesel_models::invoice_attachments::InvoiceAttachmentRow;
+use diesel_models::invoices::{
+    InvoiceRow, InvoiceRowDraftEdit, InvoiceRowExternalLinksPatch, InvoiceRowLinesPatch,
+    InvoiceRowNew,
+};
 use diesel_models::invoicing_entities::InvoicingEntityRow;
+use diesel_models::subscription_events::SubscriptionEventRow;
 use diesel_models::subscriptions::SubscriptionRow;
 use tracing_log::log;
 use uuid::Uuid;
@@ -54,12 +74,36 @@ pub trait InvoiceInterface {
         external_status: InvoiceExternalStatusEnum,
     ) -> StoreResult<()>;
 
+    /// Stores the pages of the invoice at its invoicing provider, keeping the current ones when not provided
+    async fn update_invoice_external_links(
+        &self,
+        invoice_id: Uuid,
+        tenant_id: Uuid,
+        links: ExternalInvoiceLinks,
+    ) -> StoreResult<()>;
+
+    /// The invoice issued to its provider under this id, for the providers that cannot carry our ids
+    async fn find_invoice_id_by_external_id(
+        &self,
+        tenant_id: Uuid,
+        external_invoice_id: String,
+    ) -> StoreResult<Option<Uuid>>;
+
     async fn list_invoices_to_finalize(
         &self,
         pagination: CursorPaginationRequest,
     ) -> StoreResult<CursorPaginatedVec<Invoice>>;
 
-    async fn finalize_invoice(&self, id: Uuid, tenant_id: Uuid) -> StoreResult<()>;
+    /// Finalizes a draft invoice. The invoice date can be set manually, within the open accounting period,
+    /// and is then used for its number, its due date and its revenue
+    /// An invoice the approval chain of the tenant applies to is left as is until approved, its approval
+    /// being requested if needed
+    async fn finalize_invoice(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        invoice_date: Option<NaiveDate>,
+    ) -> StoreResult<()>;
 
     async fn list_outdated_invoices(
         &self,
@@ -74,13 +118,21 @@ pub trait InvoiceInterface {
 
     async fn list_invoices_by_ids(&self, ids: Vec<Uuid>) -> StoreResult<Vec<Invoice>>;
 
-    async fn invoice_issue_success(&self, id: Uuid, tenant_id: Uuid) -> StoreResult<()>;
+    async fn invoice_issue_success(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        external_invoice_id: Option<String>,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+        links: ExternalInvoiceLinks,
+    ) -> StoreResult<()>;
 
     async fn invoice_issue_error(
         &self,
         id: Uuid,
         tenant_id: Uuid,
         last_issue_error: &str,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
     ) -> StoreResult<()>;
 
     async fn update_pending_finalization_invoices(&self, now: NaiveDateTime) -> StoreResult<()>;
@@ -95,6 +147,33 @@ pub trait InvoiceInterface {
         pdf_id: String,
         xml_id: Option<String>,
     ) -> StoreResult<()>;
+
+    /// Voids a finalized invoice and reverses its MRR movements.
+    /// When reissued, a replacement draft referencing it is created for the same period
+    async fn void_invoice(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        reason: String,
+        reissue: bool,
+    ) -> StoreResult<VoidedInvoice>;
+
+    /// Applies the edit of a user to a draft or pending invoice and recomputes its totals.
+    /// Its lines are then no longer recomputed, neither by the price worker nor at the finalization
+    async fn update_draft_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        update: DraftInvoiceUpdate,
+    ) -> StoreResult<DetailedInvoice>;
+
+    /// Bills the customer for arbitrary lines, without a subscription. The draft follows the same finalization
+    /// and numbering as the subscription invoices, and is finalized right away if requested
+    async fn create_one_off_invoice(
+        &self,
+        invoice: OneOffInvoiceNew,
+    ) -> StoreResult<DetailedInvoice>;
 }
 
 #[async_trait::async_trait]
@@ -185,40 +264,98 @@ impl InvoiceInterface for Store {
         tenant_id: Uuid,
         external_status: InvoiceExternalStatusEnum,
     ) -> StoreResult<()> {
-        self.transaction(|conn| {
-            async move {
-                InvoiceRow::update_external_status(
-                    conn,
-                    invoice_id,
-                    tenant_id,
-                    external_status.clone().into(),
-                )
-                .await
-                .map_err(Into::<Report<StoreError>>::into)?;
+        let paid = self
+            .transaction(|conn| {
+                async move {
+                    InvoiceRow::lock_for_update(conn, invoice_id, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    let invoice: Invoice = InvoiceRow::find_by_id(conn, tenant_id, invoice_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .invoice
+                        .try_into()?;
+
+                    if let Some(current) = &invoice.external_status {
+                        if !current.can_transition_to(&external_status) {
+                            log::info!(
+                                "Ignoring stale external status {:?} of invoice {}, already {:?}",
+                                external_status,
+                                invoice_id,
+                                current
+                            );
+                            return Ok(false);
+                        }
+                    }
 
-                if external_status == InvoiceExternalStatusEnum::Paid {
-                    let subscription_id = SubscriptionRow::get_subscription_id_by_invoice_id(
+                    InvoiceRow::update_external_status(
                         conn,
-                        &tenant_id,
-                        &invoice_id,
+                        invoice_id,
+                        tenant_id,
+                        external_status.clone().into(),
                     )
                     .await
                     .map_err(Into::<Report<StoreError>>::into)?;
 
-                    if let Some(subscription_id) = subscription_id {
-                        SubscriptionRow::activate_subscription(conn, subscription_id, tenant_id)
-                            .await
-                            .map_err(Into::<Report<StoreError>>::into)?;
+                    if external_status != InvoiceExternalStatusEnum::Paid {
+                        return Ok(false);
                     }
 
-                    process_pending_tx(conn, invoice_id).await?;
+                    // the provider may notify the payment more than once
+                    let newly_paid =
+                        invoice.external_status != Some(InvoiceExternalStatusEnum::Paid);
+
+                    process_invoice_paid(conn, &invoice, newly_paid).await?;
+
+                    Ok(newly_paid)
                 }
+                .scope_boxed()
+            })
+            .await?;
+
+        if paid {
+            let _ = self
+                .eventbus
+                .publish(Event::invoice_paid(invoice_id, tenant_id))
+                .await;
+        }
 
-                Ok(())
-            }
-            .scope_boxed()
-        })
+        Ok(())
+    }
+
+    async fn update_invoice_external_links(
+        &self,
+        invoice_id: Uuid,
+        tenant_id: Uuid,
+        links: ExternalInvoiceLinks,
+    ) -> StoreResult<()> {
+        if links.is_empty() {
+            return Ok(());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        InvoiceRowExternalLinksPatch {
+            hosted_invoice_url: links.hosted_invoice_url,
+            external_pdf_url: links.external_pdf_url,
+        }
+        .update(invoice_id, tenant_id, &mut conn)
         .await
+        .map(|_| ())
+        .map_err(Into::<Report<StoreError>>::into)
+    }
+
+    async fn find_invoice_id_by_external_id(
+        &self,
+        tenant_id: Uuid,
+        external_invoice_id: String,
+    ) -> StoreResult<Option<Uuid>> {
+        let mut conn = self.get_conn().await?;
+
+        InvoiceRow::find_id_by_external_invoice_id(&mut conn, tenant_id, external_invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
     }
 
     async fn list_invoices_to_finalize(
@@ -243,83 +380,222 @@ impl InvoiceInterface for Store {
         Ok(res)
     }
 
-    async fn finalize_invoice(&self, id: Uuid, tenant_id: Uuid) -> StoreResult<()> {
+    async fn finalize_invoice(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        invoice_date: Option<NaiveDate>,
+    ) -> StoreResult<()> {
         let patch = compute_invoice_patch(self, id, tenant_id).await?;
         let applied_coupons_amounts = patch.applied_coupons.clone();
         let row_patch = patch.try_into()?;
 
-        self.transaction(|conn| {
-            async move {
-                let refreshed = refresh_invoice_data(conn, id, tenant_id, &row_patch).await?;
-                if refreshed.invoice.applied_credits > 0 {
-                    CustomerBalance::update(
+        let zero_invoice_policy = self.get_zero_invoice_policy_by_tenant_id(tenant_id).await?;
+
+        let tenant_payment_terms = match invoice_date {
+            Some(_) => Some(self.get_payment_terms_by_tenant_id(tenant_id).await?),
+            None => None,
+        };
+
+        let finalized = self
+            .transaction(|conn| {
+                async move {
+                    let refreshed = refresh_invoice_data(conn, id, tenant_id, &row_patch).await?;
+
+                    // stays a draft until the approval chain of the tenant approves its total
+                    if !ensure_invoice_approved(
                         conn,
-                        refreshed.customer.id,
-                        tenant_id,
-                        -refreshed.invoice.applied_credits as i32,
-                        Some(refreshed.invoice.id),
+                        &refreshed.invoice,
+                        chrono::Utc::now().naive_utc(),
                     )
-                    .await?;
-                }
+                    .await?
+                    {
+                        return Ok(false);
+                    }
 
-                let invoicing_entity = InvoicingEntityRow::select_for_update_by_id_and_tenant(
-                    conn,
-                    &refreshed.customer.invoicing_entity_id,
-                    &tenant_id,
-                )
-                .await
-                .map_err(Into::<Report<StoreError>>::into)?;
-
-                let new_invoice_number = self.internal.format_invoice_number(
-                    invoicing_entity.next_invoice_number,
-                    invoicing_entity.invoice_number_pattern,
-                    refreshed.invoice.invoice_date,
-                );
-
-                let applied_coupons_ids =
-                    refresh_applied_coupons(conn, &refreshed, &applied_coupons_amounts).await?;
-
-                let res = InvoiceRow::finalize(
-                    conn,
-                    id,
-                    tenant_id,
-                    new_invoice_number,
-                    &applied_coupons_ids,
-                )
-                .await
-                .map_err(Into::<Report<StoreError>>::into)?;
+                    // nothing to collect nor to deduct from the balance, the invoice is kept as a record of the period only.
+                    // The coupons are left untouched, a voided invoice does not consume their redemptions
+                    if refreshed.invoice.total == 0
+                        && zero_invoice_policy == ZeroInvoicePolicyEnum::Suppress
+                    {
+                        InvoiceRow::suppress(conn, id, tenant_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
 
-                InvoicingEntityRow::update_invoicing_entity_number(
-                    conn,
-                    &refreshed.customer.invoicing_entity_id,
-                    &tenant_id,
-                    invoicing_entity.next_invoice_number,
-                )
-                .await
-                .map_err(Into::<Report<StoreError>>::into)?;
+                        return Ok(false);
+                    }
 
-                self.internal
-                    .insert_outbox_item(
-                        conn,
-                        domain::OutboxNew {
-                            event_type: OutboxEvent::InvoiceFinalized,
-                            resource_id: id,
+                    let applied_coupons_ids =
+                        refresh_applied_coupons(conn, &refreshed, &applied_coupons_amounts).await?;
+
+                    if refreshed.invoice.applied_credits > 0 {
+                        CustomerBalance::update(
+                            conn,
+                            refreshed.customer.id,
                             tenant_id,
-                            payload: None,
-                        },
+                            -refreshed.invoice.applied_credits as i32,
+                            Some(refreshed.invoice.id),
+                        )
+                        .await?;
+                    }
+
+                    let invoicing_entity = InvoicingEntityRow::select_for_update_by_id_and_tenant(
+                        conn,
+                        &refreshed.customer.invoicing_entity_id,
+                        &tenant_id,
                     )
-                    .await?;
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
 
-                Ok(res)
-            }
-            .scope_boxed()
-        })
-        .await?;
+                    // the invoicing entity is locked, so that the dates follow the numbering sequence
+                    let invoice_date = match (invoice_date, tenant_payment_terms) {
+                        (Some(invoice_date), Some(tenant_payment_terms)) => {
+                            let last_finalized_invoice_date =
+                                InvoiceRow::last_finalized_invoice_date(
+                                    conn,
+                                    tenant_id,
+                                    refreshed.customer.invoicing_entity_id,
+                                )
+                                .await
+                                .map_err(Into::<Report<StoreError>>::into)?;
+
+                            validate_manual_invoice_date(
+                                invoice_date,
+                                chrono::Utc::now().date_naive(),
+                                last_finalized_invoice_date,
+                            )?;
+
+                            let payment_terms = PaymentTerms {
+                                net_terms: refreshed.invoice.net_terms as u32,
+                                due_date_policy: refreshed
+                                    .customer
+                                    .due_date_policy
+                                    .unwrap_or(tenant_payment_terms.due_date_policy),
+                            };
+
+                            let due_at = refreshed.invoice.due_at.map(|_| {
+                                tenant_payment_terms.due_at(payment_terms.due_date(invoice_date))
+                            });
+
+                            InvoiceRow::set_manual_invoice_date(
+                                conn,
+                                id,
+                                tenant_id,
+                                invoice_date,
+                                due_at,
+                            )
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
 
-        let _ = self
-            .eventbus
-            .publish(Event::invoice_finalized(id, tenant_id))
-            .await;
+                            invoice_date
+                        }
+                        _ => refreshed.invoice.invoice_date,
+                    };
+
+                    let customer_country = refreshed
+                        .invoice
+                        .customer_details
+                        .billing_address
+                        .as_ref()
+                        .and_then(|a| a.country.as_deref());
+
+                    let seller_details = InlineInvoicingEntity {
+                        tax_registrations: displayed_tax_registrations(
+                            conn,
+                            &refreshed.customer.invoicing_entity_id,
+                            &tenant_id,
+                            customer_country,
+                        )
+                        .await?,
+                        ..refreshed.invoice.seller_details.clone()
+                    };
+                    let seller_details = serde_json::to_value(&seller_details).map_err(|e| {
+                        StoreError::SerdeError("Failed to serialize seller_details".to_string(), e)
+                    })?;
+
+                    let new_invoice_number = self.internal.format_invoice_number(
+                        invoicing_entity.next_invoice_number,
+                        invoicing_entity.invoice_number_pattern,
+                        invoice_date,
+                    );
+
+                    InvoiceRow::finalize(
+                        conn,
+                        id,
+                        tenant_id,
+                        new_invoice_number,
+                        &applied_coupons_ids,
+                        seller_details,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    InvoicingEntityRow::update_invoicing_entity_number(
+                        conn,
+                        &refreshed.customer.invoicing_entity_id,
+                        &tenant_id,
+                        invoicing_entity.next_invoice_number,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    // fully covered by credits when suppression is enabled, so finalized to record the balance usage
+                    if refreshed.invoice.amount_due == 0
+                        && zero_invoice_policy != ZeroInvoicePolicyEnum::Issue
+                    {
+                        InvoiceRow::skip_issue(conn, id, tenant_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+
+                    let email_attachments: Vec<InvoiceEmailAttachment> =
+                        InvoiceAttachmentRow::list_by_invoice_id(conn, tenant_id, id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into_iter()
+                            .map(InvoiceAttachment::from)
+                            .filter(|a| a.include_in_email)
+                            .map(|a| InvoiceEmailAttachment::from(&a))
+                            .collect();
+
+                    let payload = if email_attachments.is_empty() {
+                        None
+                    } else {
+                        Some(
+                            serde_json::to_value(InvoiceFinalizedPayload { email_attachments })
+                                .map_err(|e| {
+                                    StoreError::SerdeError(
+                                        "Failed to serialize invoice finalized payload".to_string(),
+                                        e,
+                                    )
+                                })?,
+                        )
+                    };
+
+                    self.internal
+                        .insert_outbox_item(
+                            conn,
+                            domain::OutboxNew {
+                                event_type: OutboxEvent::InvoiceFinalized,
+                                resource_id: id,
+                                tenant_id,
+                                payload,
+                            },
+                        )
+                        .await?;
+
+                    Ok(true)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        if finalized {
+            let _ = self
+                .eventbus
+                .publish(Event::invoice_finalized(id, tenant_id))
+                .await;
+        }
 
         Ok(())
     }
@@ -353,9 +629,14 @@ impl InvoiceInterface for Store {
     ) -> StoreResult<CursorPaginatedVec<Invoice>> {
         let mut conn = self.get_conn().await?;
 
-        let invoices = InvoiceRow::list_to_issue(&mut conn, max_attempts, pagination.into())
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+        let invoices = InvoiceRow::list_to_issue(
+            &mut conn,
+            max_attempts,
+            chrono::Utc::now().date_naive(),
+            pagination.into(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
 
         let res: CursorPaginatedVec<Invoice> = CursorPaginatedVec {
             items: invoices
@@ -382,13 +663,28 @@ impl InvoiceInterface for Store {
             .collect::<Result<Vec<_>, _>>()
     }
 
-    async fn invoice_issue_success(&self, id: Uuid, tenant_id: Uuid) -> StoreResult<()> {
+    async fn invoice_issue_success(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        external_invoice_id: Option<String>,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
+        links: ExternalInvoiceLinks,
+    ) -> StoreResult<()> {
         let mut conn = self.get_conn().await?;
 
-        InvoiceRow::issue_success(&mut conn, id, tenant_id)
-            .await
-            .map(|_| ())
-            .map_err(Into::<Report<StoreError>>::into)
+        InvoiceRow::issue_success(
+            &mut conn,
+            id,
+            tenant_id,
+            external_invoice_id,
+            delivery_channel.map(Into::into),
+            links.hosted_invoice_url,
+            links.external_pdf_url,
+        )
+        .await
+        .map(|_| ())
+        .map_err(Into::<Report<StoreError>>::into)
     }
 
     async fn invoice_issue_error(
@@ -396,13 +692,20 @@ impl InvoiceInterface for Store {
         id: Uuid,
         tenant_id: Uuid,
         last_issue_error: &str,
+        delivery_channel: Option<InvoiceDeliveryChannelEnum>,
     ) -> StoreResult<()> {
         let mut conn = self.get_conn().await?;
 
-        InvoiceRow::issue_error(&mut conn, id, tenant_id, last_issue_error)
-            .await
-            .map(|_| ())
-            .map_err(Into::<Report<StoreError>>::into)
+        InvoiceRow::issue_error(
+            &mut conn,
+            id,
+            tenant_id,
+            last_issue_error,
+            delivery_channel.map(Into::into),
+        )
+        .await
+        .map(|_| ())
+        .map_err(Into::<Report<StoreError>>::into)
     }
 
     async fn update_pending_finalization_invoices(&self, now: NaiveDateTime) -> StoreResult<()> {
@@ -440,6 +743,206 @@ impl InvoiceInterface for Store {
             .map(|_| ())
             .map_err(Into::<Report<StoreError>>::into)
     }
+
+    async fn void_invoice(
+        &self,
+        id: Uuid,
+        tenant_id: Uuid,
+        reason: String,
+        reissue: bool,
+    ) -> StoreResult<VoidedInvoice> {
+        let replacement_id = self
+            .transaction(|conn| {
+                async move {
+                    let invoice: Invoice = InvoiceRow::find_by_id(conn, tenant_id, id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)
+                        .and_then(|row| row.invoice.try_into())?;
+
+                    let voided = InvoiceRow::void(conn, id, tenant_id, reason)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    if voided == 0 {
+                        return Err(StoreError::InvalidArgument(format!(
+                            "Invoice {} is not finalized and cannot be voided",
+                            id
+                        ))
+                        .into());
+                    }
+
+                    reverse_mrr(conn, &invoice).await?;
+
+                    // the credits applied at finalization are given back
+                    if invoice.applied_credits > 0 {
+                        CustomerBalance::update(
+                            conn,
+                            invoice.customer_id,
+                            tenant_id,
+                            invoice.applied_credits as i32,
+                            Some(invoice.id),
+                        )
+                        .await?;
+                    }
+
+                    if !reissue {
+                        return Ok(None);
+                    }
+
+                    let replacement = insert_invoice(conn, invoice.to_replacement()).await?;
+
+                    Ok(Some(replacement.id))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        let invoice = self.find_invoice_by_id(tenant_id, id).await?;
+
+        let replacement = match replacement_id {
+            Some(replacement_id) => Some(self.find_invoice_by_id(tenant_id, replacement_id).await?),
+            None => None,
+        };
+
+        Ok(VoidedInvoice {
+            invoice,
+            replacement,
+        })
+    }
+
+    async fn update_draft_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        actor: Uuid,
+        update: DraftInvoiceUpdate,
+    ) -> StoreResult<DetailedInvoice> {
+        let invoice = self.find_invoice_by_id(tenant_id, invoice_id).await?;
+
+        if !matches!(
+            invoice.invoice.status,
+            domain::enums::InvoiceStatusEnum::Draft | domain::enums::InvoiceStatusEnum::Pending
+        ) {
+            return Err(StoreError::InvalidArgument(
+                "only draft invoices can be edited".to_string(),
+            )
+            .into());
+        }
+
+        update.validate(invoice.invoice.invoice_date)?;
+
+        let lines = match update.lines {
+            Some(lines) => apply_draft_lines(
+                &invoice.invoice.line_items,
+                lines,
+                &invoice.invoice.currency,
+                invoice.invoice.invoice_date,
+            )?,
+            None => invoice.invoice.line_items.clone(),
+        };
+
+        let applied_coupons = match invoice.invoice.subscription_id {
+            Some(subscription_id) if invoice.invoice.invoice_type == InvoiceType::Recurring => {
+                self.get_subscription_details(tenant_id, subscription_id)
+                    .await?
+                    .applied_coupons
+            }
+            _ => vec![],
+        };
+
+        let lines_patch: InvoiceRowLinesPatch =
+            InvoiceLinesPatch::new(&invoice, lines, &applied_coupons).try_into()?;
+
+        let due_at = match update.due_date {
+            Some(due_date) => Some(
+                self.get_payment_terms_by_tenant_id(tenant_id)
+                    .await?
+                    .due_at(due_date),
+            ),
+            None => invoice.invoice.due_at,
+        };
+
+        let memo = match update.memo {
+            Some(memo) if memo.trim().is_empty() => None,
+            Some(memo) => Some(memo),
+            None => invoice.invoice.memo.clone(),
+        };
+
+        let edit = InvoiceRowDraftEdit {
+            memo,
+            due_at,
+            manually_edited_at: Some(chrono::Utc::now().naive_utc()),
+            manually_edited_by: Some(actor),
+        };
+
+        let mut conn = self.get_conn().await?;
+
+        let updated = edit
+            .update_draft(&mut conn, invoice_id, tenant_id, &lines_patch)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if updated == 0 {
+            return Err(StoreError::InvalidArgument(
+                "the invoice was finalized or voided meanwhile".to_string(),
+            )
+            .into());
+        }
+
+        InvoiceRow::find_by_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::into)
+            .and_then(|row| row.try_into())
+    }
+
+    async fn create_one_off_invoice(
+        &self,
+        invoice: OneOffInvoiceNew,
+    ) -> StoreResult<DetailedInvoice> {
+        let tenant_id = invoice.tenant_id;
+        let now = chrono::Utc::now().naive_utc();
+
+        let invoice_date = invoice.validate(now.date())?;
+
+        let customer = self
+            .find_customer_by_id(invoice.customer_id, tenant_id)
+            .await?;
+
+        if customer.archived_at.is_some() {
+            return Err(StoreError::InvalidArgument(
+                "an archived customer cannot be invoiced".to_string(),
+            )
+            .into());
+        }
+
+        let line_items =
+            apply_draft_lines(&[], invoice.lines.clone(), &customer.currency, invoice_date)?;
+
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(customer.invoicing_entity_id))
+            .await?;
+
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
+
+        let draft = invoice.to_draft(
+            &customer,
+            &invoicing_entity,
+            &tenant_payment_terms,
+            invoice_date,
+            line_items,
+            now,
+        );
+
+        let mut conn = self.get_conn().await?;
+
+        let inserted = insert_invoice(&mut conn, draft).await?;
+
+        if invoice.finalize {
+            self.finalize_invoice(inserted.id, tenant_id, None).await?;
+        }
+
+        self.find_invoice_by_id(tenant_id, inserted.id).await
+    }
 }
 
 /*
@@ -447,7 +950,6 @@ impl InvoiceInterface for Store {
 TODO special cases :
 - cancellation/all invoice => all mrr logs after that should be cancelled, unless reactivation
 - cancellation : recalculate the mrr delta (as the one in the event was calculated before the invoice was created)
-- consolidation if multiple events in the same day. Ex: new business + expansion = new business, or cancellation + reactivation => nothing
  */
 async fn process_mrr(inserted: &domain::Invoice, conn: &mut PgConn) -> StoreResult<()> {
     log::info!("Processing MRR logs for invoice {}", inserted.id);
@@ -458,84 +960,138 @@ async fn process_mrr(inserted: &domain::Invoice, conn: &mut PgConn) -> StoreResu
             .subscription_id
             .ok_or(StoreError::ValueNotFound("subscription_id is null".into()))?;
 
-        let subscription_events = diesel_models::subscription_events::SubscriptionEventRow::fetch_by_subscription_id_and_date(
+        let mrr_delta_cents = record_mrr_movement(
             conn,
-            subscription_id,
-            inserted.invoice_date,
+            MrrMovementSource {
+                tenant_id: inserted.tenant_id,
+                subscription_id,
+                invoice_id: inserted.id,
+                invoice_date: inserted.invoice_date,
+                currency: inserted.currency.clone(),
+                plan_version_id: inserted.plan_version_id.unwrap(), // TODO
+            },
         )
+        .await?;
+
+        SubscriptionRow::update_subscription_mrr_delta(conn, subscription_id, mrr_delta_cents)
             .await
             .map_err(Into::<Report<StoreError>>::into)?;
+    }
+    Ok(())
+}
 
-        let mut mrr_logs = vec![];
-
-        for event in subscription_events {
-            let mrr_delta = match event.mrr_delta {
-                None | Some(0) => continue,
-                Some(c) => c,
-            };
-
-            let movement_type = match event.event_type {
-                // TODO
-                // SubscriptionEventType::Created => continue,
-                SubscriptionEventType::Created => MrrMovementType::NewBusiness, // TODO
-                SubscriptionEventType::Activated => MrrMovementType::NewBusiness,
-                SubscriptionEventType::Switch => {
-                    if mrr_delta > 0 {
-                        MrrMovementType::Expansion
-                    } else {
-                        MrrMovementType::Contraction
-                    }
-                }
-                SubscriptionEventType::Cancelled => MrrMovementType::Churn,
-                SubscriptionEventType::Reactivated => MrrMovementType::Reactivation,
-                SubscriptionEventType::Updated => {
-                    if mrr_delta > 0 {
-                        MrrMovementType::Expansion
-                    } else {
-                        MrrMovementType::Contraction
-                    }
-                }
-            };
-
-            // TODO proper description from event_type + details
-            let description = match event.event_type {
-                SubscriptionEventType::Created => "Subscription created",
-                SubscriptionEventType::Activated => "Subscription activated",
-                SubscriptionEventType::Switch => "Switched plan",
-                SubscriptionEventType::Cancelled => "Subscription cancelled",
-                SubscriptionEventType::Reactivated => "Subscription reactivated",
-                SubscriptionEventType::Updated => "Subscription updated",
-            };
-
-            let new_log = diesel_models::bi::BiMrrMovementLogRowNew {
-                id: Uuid::now_v7(),
-                description: description.to_string(),
-                movement_type,
-                net_mrr_change: mrr_delta,
-                currency: inserted.currency.clone(),
-                applies_to: inserted.invoice_date,
-                invoice_id: inserted.id,
-                credit_note_id: None,
-                plan_version_id: inserted.plan_version_id.unwrap(), // TODO
-                tenant_id: inserted.tenant_id,
-            };
+/// Records the opposite of the MRR movements of a voided invoice. Its subscription events are unlinked,
+/// so that a replacement invoice records them again
+async fn reverse_mrr(conn: &mut PgConn, voided: &Invoice) -> StoreResult<()> {
+    let Some(subscription_id) = voided.subscription_id else {
+        return Ok(());
+    };
 
-            mrr_logs.push(new_log);
-        }
+    let logs = BiMrrMovementLogRow::list_by_invoice_id(conn, voided.tenant_id, voided.id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
 
-        let mrr_delta_cents: i64 = mrr_logs.iter().map(|l| l.net_mrr_change).sum();
+    if logs.is_empty() {
+        return Ok(());
+    }
 
-        diesel_models::bi::BiMrrMovementLogRow::insert_movement_log_batch(conn, mrr_logs)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+    let log_ids = logs.iter().map(|l| l.id).collect::<Vec<_>>();
+    let mrr_delta_cents: i64 = logs.iter().map(|l| l.net_mrr_change).sum();
+
+    let reversals = logs
+        .into_iter()
+        .map(|log| BiMrrMovementLogRowNew {
+            id: Uuid::now_v7(),
+            description: format!("Reversal of voided invoice {}", voided.invoice_number),
+            movement_type: log.movement_type,
+            net_mrr_change: -log.net_mrr_change,
+            currency: log.currency,
+            applies_to: log.applies_to,
+            invoice_id: voided.id,
+            credit_note_id: None,
+            plan_version_id: log.plan_version_id,
+            tenant_id: log.tenant_id,
+        })
+        .collect();
+
+    BiMrrMovementLogRow::insert_movement_log_batch(conn, reversals)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    SubscriptionEventRow::unlink_mrr_movement_logs(conn, &log_ids)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    SubscriptionRow::update_subscription_mrr_delta(conn, subscription_id, -mrr_delta_cents)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
 
-        SubscriptionRow::update_subscription_mrr_delta(conn, subscription_id, mrr_delta_cents)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-    }
     Ok(())
 }
 
+pub(crate) struct MrrMovementSource {
+    pub tenant_id: Uuid,
+    pub subscription_id: Uuid,
+    pub invoice_id: Uuid,
+    pub invoice_date: NaiveDate,
+    pub currency: String,
+    pub plan_version_id: Uuid,
+}
+
+/// Consolidates the events of the invoice date that are not part of a movement yet into a single log, and links them to it.
+/// Returns the mrr change
+pub(crate) async fn record_mrr_movement(
+    conn: &mut PgConn,
+    source: MrrMovementSource,
+) -> StoreResult<i64> {
+    let subscription_events = SubscriptionEventRow::fetch_by_subscription_id_and_date(
+        conn,
+        source.subscription_id,
+        source.invoice_date,
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?
+    .into_iter()
+    .filter(|e| e.bi_mrr_movement_log_id.is_none())
+    .collect::<Vec<_>>();
+
+    let events: Vec<(domain::enums::SubscriptionEventType, Option<i64>)> = subscription_events
+        .iter()
+        .map(|e| (e.event_type.clone().into(), e.mrr_delta))
+        .collect();
+
+    let movement = match consolidate_mrr_movements(&events) {
+        None => return Ok(0),
+        Some(movement) => movement,
+    };
+
+    let new_log = diesel_models::bi::BiMrrMovementLogRowNew {
+        id: Uuid::now_v7(),
+        description: movement.description,
+        movement_type: movement.movement_type.into(),
+        net_mrr_change: movement.net_mrr_change,
+        currency: source.currency,
+        applies_to: source.invoice_date,
+        invoice_id: source.invoice_id,
+        credit_note_id: None,
+        plan_version_id: source.plan_version_id,
+        tenant_id: source.tenant_id,
+    };
+
+    let inserted_log = new_log
+        .insert(conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let event_ids = subscription_events.iter().map(|e| e.id).collect::<Vec<_>>();
+
+    SubscriptionEventRow::link_to_mrr_movement_log(conn, &event_ids, inserted_log.id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(inserted_log.net_mrr_change)
+}
+
 async fn refresh_invoice_data(
     conn: &mut PgConn,
     id: Uuid,
@@ -561,6 +1117,27 @@ async fn compute_invoice_patch(
 ) -> StoreResult<InvoiceLinesPatch> {
     let invoice = store.find_invoice_by_id(tenant_id, invoice_id).await?;
 
+    // off-cycle invoices (added slots, usage threshold) are not recomputed from the subscription
+    if invoice.invoice.invoice_type != InvoiceType::Recurring {
+        let lines = invoice.invoice.line_items.clone();
+        return Ok(InvoiceLinesPatch::new(&invoice, lines, &[]));
+    }
+
+    // the lines edited by a user are kept, only the totals are recomputed
+    if invoice.invoice.manually_edited_at.is_some() {
+        let applied_coupons = match invoice.invoice.subscription_id {
+            Some(subscription_id) => {
+                store
+                    .get_subscription_details(tenant_id, subscription_id)
+                    .await?
+                    .applied_coupons
+            }
+            None => vec![],
+        };
+        let lines = invoice.invoice.line_items.clone();
+        return Ok(InvoiceLinesPatch::new(&invoice, lines, &applied_coupons));
+    }
+
     match invoice.invoice.subscription_id {
         None => Err(StoreError::InvalidArgument(
             "Cannot refresh invoice without subscription_id".into(),
@@ -571,7 +1148,11 @@ async fn compute_invoice_patch(
                 .get_subscription_details(tenant_id, subscription_id)
                 .await?;
             let lines = store
-                .compute_dated_invoice_lines(&invoice.invoice.invoice_date, &subscription_details)
+                .compute_dated_invoice_lines(
+                    &invoice.invoice.invoice_date,
+                    &subscription_details
+                        .with_cadence_fees(invoice.invoice.billing_cadence.as_ref()),
+                )
                 .await?;
 
             Ok(InvoiceLinesPatch::new(
@@ -597,6 +1178,69 @@ pub async fn insert_invoice(conn: &mut PgConn, invoice: InvoiceNew) -> StoreResu
     Ok(inserted)
 }
 
+/// Credits the customer balance with the early payment discount of the invoice, if paid within the discount window
+async fn apply_early_payment_discount(conn: &mut PgConn, invoice: &Invoice) -> StoreResult<()> {
+    let (Some(discount), Some(finalized_at)) = (
+        invoice.customer_details.early_payment_discount.as_ref(),
+        invoice.finalized_at,
+    ) else {
+        return Ok(());
+    };
+
+    let amount = discount.amount_for(
+        invoice.amount_due,
+        finalized_at,
+        chrono::Utc::now().naive_utc(),
+    );
+
+    if let Some(amount) = amount.filter(|a| *a > 0) {
+        let cents = i32::try_from(amount).map_err(|_| {
+            StoreError::InvalidArgument("early payment discount is out of range".to_string())
+        })?;
+
+        CustomerBalance::update_with_note(
+            conn,
+            invoice.customer_id,
+            invoice.tenant_id,
+            cents,
+            Some(invoice.id),
+            Some(format!(
+                "Early payment discount ({}% within {} days)",
+                discount.percent, discount.days
+            )),
+        )
+        .await?;
+    }
+
+    Ok(())
+}
+
+/// Settles a paid invoice: the early payment discount and the partner commission once, then the activation of its
+/// subscription and the balance transaction pending its payment
+pub(crate) async fn process_invoice_paid(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+    newly_paid: bool,
+) -> StoreResult<()> {
+    if newly_paid {
+        apply_early_payment_discount(conn, invoice).await?;
+        record_partner_commission(conn, invoice, chrono::Utc::now().naive_utc()).await?;
+    }
+
+    let subscription_id =
+        SubscriptionRow::get_subscription_id_by_invoice_id(conn, &invoice.tenant_id, &invoice.id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+    if let Some(subscription_id) = subscription_id {
+        SubscriptionRow::activate_subscription(conn, subscription_id, invoice.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+    }
+
+    process_pending_tx(conn, invoice.id).await
+}
+
 async fn process_pending_tx(conn: &mut PgConn, invoice_id: Uuid) -> StoreResult<()> {
     let pending_tx = CustomerBalancePendingTxRow::find_unprocessed_by_invoice_id(conn, invoice_id)
         .await
@@ -1,12 +1,17 @@
 use chrono::{Datelike, NaiveDate};
+use diesel_async::scoped_futures::ScopedFutureExt;
 use error_stack::Report;
 use uuid::Uuid;
 
-use diesel_models::invoicing_entities::{InvoicingEntityRow, InvoicingEntityRowPatch};
+use diesel_models::invoicing_entities::{
+    InvoicingEntityRow, InvoicingEntityRowPatch, InvoicingEntityTaxRegistrationRow,
+    InvoicingEntityTaxRegistrationRowNew,
+};
 use diesel_models::organizations::OrganizationRow;
 
-use crate::domain::invoicing_entities::InvoicingEntity;
-use crate::domain::{InvoicingEntityNew, InvoicingEntityPatch};
+use crate::domain::enums::TaxRegistrationTypeEnum;
+use crate::domain::invoicing_entities::{InvoicingEntity, TaxRegistration, TaxRegistrationNew};
+use crate::domain::{InlineTaxRegistration, InvoicingEntityNew, InvoicingEntityPatch};
 use crate::errors::StoreError;
 use crate::store::{PgConn, Store, StoreInternal};
 use crate::utils::local_id::{IdType, LocalId};
@@ -38,6 +43,20 @@ pub trait InvoicingEntityInterface {
         invoicing_entity: InvoicingEntityPatch,
         tenant_id: Uuid,
     ) -> StoreResult<InvoicingEntity>;
+
+    async fn list_invoicing_entity_tax_registrations(
+        &self,
+        tenant_id: Uuid,
+        invoicing_entity_id: Uuid,
+    ) -> StoreResult<Vec<TaxRegistration>>;
+
+    /// Replaces all the tax registrations of the invoicing entity
+    async fn set_invoicing_entity_tax_registrations(
+        &self,
+        tenant_id: Uuid,
+        invoicing_entity_id: Uuid,
+        registrations: Vec<TaxRegistrationNew>,
+    ) -> StoreResult<Vec<TaxRegistration>>;
 }
 
 #[async_trait::async_trait]
@@ -152,6 +171,131 @@ impl InvoicingEntityInterface for Store {
 
         Ok(res.into())
     }
+
+    async fn list_invoicing_entity_tax_registrations(
+        &self,
+        tenant_id: Uuid,
+        invoicing_entity_id: Uuid,
+    ) -> StoreResult<Vec<TaxRegistration>> {
+        let mut conn = self.get_conn().await?;
+
+        let registrations = InvoicingEntityTaxRegistrationRow::list_by_invoicing_entity_id(
+            &mut conn,
+            &invoicing_entity_id,
+            &tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(|v| v.into())
+        .collect();
+
+        Ok(registrations)
+    }
+
+    async fn set_invoicing_entity_tax_registrations(
+        &self,
+        tenant_id: Uuid,
+        invoicing_entity_id: Uuid,
+        registrations: Vec<TaxRegistrationNew>,
+    ) -> StoreResult<Vec<TaxRegistration>> {
+        let mut rows = Vec::with_capacity(registrations.len());
+        let mut seen: Vec<(TaxRegistrationTypeEnum, String)> = Vec::new();
+
+        for registration in registrations {
+            let jurisdiction = registration.jurisdiction.trim().to_uppercase();
+            let registration_number = registration.registration_number.trim().to_string();
+
+            if jurisdiction.is_empty() || registration_number.is_empty() {
+                return Err(StoreError::InvalidArgument(
+                    "tax registrations require a jurisdiction and a number".to_string(),
+                )
+                .into());
+            }
+
+            let key = (registration.registration_type, jurisdiction.clone());
+            if seen.contains(&key) {
+                return Err(StoreError::InvalidArgument(format!(
+                    "duplicate {} registration for {}",
+                    registration.registration_type.label(),
+                    jurisdiction
+                ))
+                .into());
+            }
+            seen.push(key);
+
+            rows.push(InvoicingEntityTaxRegistrationRowNew {
+                id: Uuid::now_v7(),
+                tenant_id,
+                invoicing_entity_id,
+                registration_type: registration.registration_type.into(),
+                jurisdiction,
+                registration_number,
+                customer_countries: registration
+                    .customer_countries
+                    .into_iter()
+                    .map(|c| Some(c.trim().to_uppercase()))
+                    .collect(),
+            });
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        // ensures the entity belongs to the tenant
+        InvoicingEntityRow::get_invoicing_entity_by_id_and_tenant(
+            &mut conn,
+            &invoicing_entity_id,
+            &tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        let inserted = self
+            .transaction_with(&mut conn, |conn| {
+                async move {
+                    InvoicingEntityTaxRegistrationRow::delete_by_invoicing_entity_id(
+                        conn,
+                        &invoicing_entity_id,
+                        &tenant_id,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    InvoicingEntityTaxRegistrationRowNew::insert_batch(conn, &rows)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        Ok(inserted.into_iter().map(|v| v.into()).collect())
+    }
+}
+
+/// The registrations of the invoicing entity to print on the invoices of a customer billed in `customer_country`
+pub(crate) async fn displayed_tax_registrations(
+    conn: &mut PgConn,
+    invoicing_entity_id: &Uuid,
+    tenant_id: &Uuid,
+    customer_country: Option<&str>,
+) -> StoreResult<Vec<InlineTaxRegistration>> {
+    let registrations: Vec<TaxRegistration> =
+        InvoicingEntityTaxRegistrationRow::list_by_invoicing_entity_id(
+            conn,
+            invoicing_entity_id,
+            tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(|v| v.into())
+        .collect();
+
+    Ok(TaxRegistration::displayed_for(
+        &registrations,
+        customer_country,
+    ))
 }
 
 impl StoreInternal {
@@ -0,0 +1,119 @@
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::notification_bundles::{NotificationBundleRow, NotificationBundleRowNew};
+
+use crate::domain::enums::NotificationNamespaceEnum;
+use crate::domain::notification_bundles::{
+    validate_locale, LocalizedMessages, NotificationBundle, NotificationBundleNew,
+};
+use crate::errors::StoreError;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait NotificationBundleInterface {
+    /// Creates the bundle of the namespace and locale, or replaces its messages
+    async fn upsert_notification_bundle(
+        &self,
+        bundle: NotificationBundleNew,
+    ) -> StoreResult<NotificationBundle>;
+
+    async fn list_notification_bundles(
+        &self,
+        tenant_id: Uuid,
+        namespace: Option<NotificationNamespaceEnum>,
+    ) -> StoreResult<Vec<NotificationBundle>>;
+
+    async fn delete_notification_bundle(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    /// All the messages of the namespace for the locale, from the tenant bundles or the built-in ones
+    async fn resolve_notification_messages(
+        &self,
+        tenant_id: Uuid,
+        namespace: NotificationNamespaceEnum,
+        locale: String,
+    ) -> StoreResult<LocalizedMessages>;
+}
+
+#[async_trait::async_trait]
+impl NotificationBundleInterface for Store {
+    async fn upsert_notification_bundle(
+        &self,
+        bundle: NotificationBundleNew,
+    ) -> StoreResult<NotificationBundle> {
+        bundle.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        let row: NotificationBundleRowNew = bundle.try_into()?;
+
+        let upserted = row
+            .upsert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        NotificationBundle::try_from(upserted).map_err(Into::into)
+    }
+
+    async fn list_notification_bundles(
+        &self,
+        tenant_id: Uuid,
+        namespace: Option<NotificationNamespaceEnum>,
+    ) -> StoreResult<Vec<NotificationBundle>> {
+        let mut conn = self.get_conn().await?;
+
+        list_bundles(&mut conn, tenant_id, namespace).await
+    }
+
+    async fn delete_notification_bundle(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let deleted = NotificationBundleRow::delete(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if deleted == 0 {
+            return Err(StoreError::ValueNotFound(format!("notification bundle {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    async fn resolve_notification_messages(
+        &self,
+        tenant_id: Uuid,
+        namespace: NotificationNamespaceEnum,
+        locale: String,
+    ) -> StoreResult<LocalizedMessages> {
+        validate_locale(&locale)?;
+
+        let mut conn = self.get_conn().await?;
+
+        resolve_messages(&mut conn, tenant_id, namespace, &locale).await
+    }
+}
+
+async fn list_bundles(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    namespace: Option<NotificationNamespaceEnum>,
+) -> StoreResult<Vec<NotificationBundle>> {
+    NotificationBundleRow::list_by_tenant_id(conn, tenant_id, namespace.map(Into::into))
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(|row| NotificationBundle::try_from(row).map_err(Into::into))
+        .collect()
+}
+
+pub(crate) async fn resolve_messages(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    namespace: NotificationNamespaceEnum,
+    locale: &str,
+) -> StoreResult<LocalizedMessages> {
+    let bundles = list_bundles(conn, tenant_id, Some(namespace)).await?;
+
+    Ok(LocalizedMessages::resolve(namespace, locale, &bundles))
+}
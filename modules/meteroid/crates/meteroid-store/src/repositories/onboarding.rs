@@ -0,0 +1,89 @@
+use crate::domain::enums::OnboardingStepEnum;
+use crate::domain::onboarding::OnboardingStatus;
+use crate::errors::StoreError;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+use diesel_models::onboarding_steps::{OnboardingStepRow, OnboardingStepRowNew};
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait OnboardingInterface {
+    async fn get_onboarding_status(&self, tenant_id: Uuid) -> StoreResult<OnboardingStatus>;
+
+    async fn complete_onboarding_step(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        step: OnboardingStepEnum,
+    ) -> StoreResult<OnboardingStatus>;
+
+    /// Fails unless the data of the step exists, a step marked as completed without it does not count
+    async fn ensure_onboarding_step_detected(
+        &self,
+        tenant_id: Uuid,
+        step: OnboardingStepEnum,
+    ) -> StoreResult<()>;
+}
+
+#[async_trait::async_trait]
+impl OnboardingInterface for Store {
+    async fn get_onboarding_status(&self, tenant_id: Uuid) -> StoreResult<OnboardingStatus> {
+        let mut conn = self.get_conn().await?;
+
+        get_onboarding_status(&mut conn, tenant_id).await
+    }
+
+    async fn complete_onboarding_step(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        step: OnboardingStepEnum,
+    ) -> StoreResult<OnboardingStatus> {
+        let mut conn = self.get_conn().await?;
+
+        let status = get_onboarding_status(&mut conn, tenant_id).await?;
+
+        status.ensure_prerequisites(step)?;
+
+        OnboardingStepRowNew {
+            tenant_id,
+            step: step.into(),
+            completed_by: Some(actor),
+        }
+        .insert(&mut conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        get_onboarding_status(&mut conn, tenant_id).await
+    }
+
+    async fn ensure_onboarding_step_detected(
+        &self,
+        tenant_id: Uuid,
+        step: OnboardingStepEnum,
+    ) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let detected = OnboardingStepRow::detect(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        step.ensure_detected(&detected).map_err(Into::into)
+    }
+}
+
+async fn get_onboarding_status(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+) -> StoreResult<OnboardingStatus> {
+    let rows = OnboardingStepRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let detected = OnboardingStepRow::detect(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(OnboardingStatus::from_rows(tenant_id, rows, detected))
+}
@@ -81,6 +81,7 @@ impl OrganizationsInterface for Store {
         let tenant_new = TenantNew {
             name: "Production".to_string(),
             environment: TenantEnvironmentEnum::Production,
+            production_tenant_id: None,
         };
 
         let (org_created, tenant_created) = self
@@ -0,0 +1,262 @@
+use chrono::{NaiveDateTime, NaiveTime};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::customers::CustomerRow;
+use diesel_models::partners::{
+    PartnerAttributionRow, PartnerAttributionRowNew, PartnerCommissionRow, PartnerRow,
+    PartnerRowNew,
+};
+use diesel_models::subscriptions::SubscriptionRow;
+use diesel_models::PgConn;
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::partners::{
+    commission_amount, month_bounds, payout_lines, resolve_attribution, Partner,
+    PartnerAttribution, PartnerAttributionNew, PartnerAttributionTarget, PartnerCommission,
+    PartnerNew, PartnerPayoutReport,
+};
+use crate::domain::Invoice;
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait PartnersInterface {
+    async fn insert_partner(&self, partner: PartnerNew) -> StoreResult<Partner>;
+
+    async fn list_partners(&self, tenant_id: Uuid) -> StoreResult<Vec<Partner>>;
+
+    /// An archived partner earns no commission on the invoices paid afterward
+    async fn archive_partner(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<Partner>;
+
+    /// Attributes the customer or subscription to the partner, replacing its previous attribution
+    async fn set_partner_attribution(
+        &self,
+        attribution: PartnerAttributionNew,
+    ) -> StoreResult<PartnerAttribution>;
+
+    async fn remove_partner_attribution(
+        &self,
+        tenant_id: Uuid,
+        target: PartnerAttributionTarget,
+    ) -> StoreResult<()>;
+
+    async fn list_partner_attributions(
+        &self,
+        tenant_id: Uuid,
+        partner_id: Uuid,
+    ) -> StoreResult<Vec<PartnerAttribution>>;
+
+    /// The commissions of the invoices paid in the month, per partner and currency
+    async fn get_partner_payout_report(
+        &self,
+        tenant_id: Uuid,
+        year: i32,
+        month: u32,
+        partner_id: Option<Uuid>,
+    ) -> StoreResult<PartnerPayoutReport>;
+}
+
+#[async_trait::async_trait]
+impl PartnersInterface for Store {
+    async fn insert_partner(&self, partner: PartnerNew) -> StoreResult<Partner> {
+        partner.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        PartnerRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: partner.tenant_id,
+            name: partner.name,
+            rev_share_percent: partner.rev_share_percent,
+        }
+        .insert(&mut conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(Into::into)
+    }
+
+    async fn list_partners(&self, tenant_id: Uuid) -> StoreResult<Vec<Partner>> {
+        let mut conn = self.get_conn().await?;
+
+        PartnerRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn archive_partner(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<Partner> {
+        let mut conn = self.get_conn().await?;
+
+        PartnerRow::archive(&mut conn, tenant_id, id, chrono::Utc::now().naive_utc())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn set_partner_attribution(
+        &self,
+        attribution: PartnerAttributionNew,
+    ) -> StoreResult<PartnerAttribution> {
+        attribution.validate()?;
+
+        self.transaction(|conn| {
+            async move {
+                let tenant_id = attribution.tenant_id;
+
+                let partner = PartnerRow::find_by_id(conn, tenant_id, attribution.partner_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                if partner.archived_at.is_some() {
+                    return Err(
+                        StoreError::InvalidArgument("the partner is archived".to_string()).into(),
+                    );
+                }
+
+                match attribution.target {
+                    PartnerAttributionTarget::Customer(customer_id) => {
+                        CustomerRow::find_by_id(conn, customer_id, tenant_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+                    PartnerAttributionTarget::Subscription(subscription_id) => {
+                        SubscriptionRow::get_subscription_by_id(conn, &tenant_id, &subscription_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+                }
+
+                PartnerAttributionRow::delete_by_target(
+                    conn,
+                    tenant_id,
+                    attribution.target.customer_id(),
+                    attribution.target.subscription_id(),
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let row = PartnerAttributionRowNew::from(attribution)
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                PartnerAttribution::try_from(row).map_err(Into::<Report<StoreError>>::into)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn remove_partner_attribution(
+        &self,
+        tenant_id: Uuid,
+        target: PartnerAttributionTarget,
+    ) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        PartnerAttributionRow::delete_by_target(
+            &mut conn,
+            tenant_id,
+            target.customer_id(),
+            target.subscription_id(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|_| ())
+    }
+
+    async fn list_partner_attributions(
+        &self,
+        tenant_id: Uuid,
+        partner_id: Uuid,
+    ) -> StoreResult<Vec<PartnerAttribution>> {
+        let mut conn = self.get_conn().await?;
+
+        PartnerAttributionRow::list_by_partner_id(&mut conn, tenant_id, partner_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(|row| PartnerAttribution::try_from(row).map_err(Into::<Report<StoreError>>::into))
+            .collect()
+    }
+
+    async fn get_partner_payout_report(
+        &self,
+        tenant_id: Uuid,
+        year: i32,
+        month: u32,
+        partner_id: Option<Uuid>,
+    ) -> StoreResult<PartnerPayoutReport> {
+        let (start, end) = month_bounds(year, month)?;
+
+        let mut conn = self.get_conn().await?;
+
+        let commissions: Vec<PartnerCommission> = PartnerCommissionRow::list_paid_between(
+            &mut conn,
+            tenant_id,
+            partner_id,
+            start.and_time(NaiveTime::MIN),
+            end.and_time(NaiveTime::MIN),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(Into::into)
+        .collect();
+
+        let partners: Vec<Partner> = PartnerRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(Into::into)
+            .collect();
+
+        Ok(PartnerPayoutReport {
+            month: start,
+            lines: payout_lines(&commissions, &partners),
+        })
+    }
+}
+
+/// Records the commission of the partner the invoice is attributed to, if any
+pub(crate) async fn record_partner_commission(
+    conn: &mut PgConn,
+    invoice: &Invoice,
+    paid_at: NaiveDateTime,
+) -> StoreResult<()> {
+    let attributions = PartnerAttributionRow::list_active_for_invoice(
+        conn,
+        invoice.tenant_id,
+        invoice.customer_id,
+        invoice.subscription_id,
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?
+    .into_iter()
+    .map(|(attribution, partner)| {
+        PartnerAttribution::try_from(attribution).map(|a| (a, Partner::from(partner)))
+    })
+    .collect::<Result<Vec<_>, _>>()?;
+
+    let Some((partner_id, rev_share_percent)) = resolve_attribution(&attributions) else {
+        return Ok(());
+    };
+
+    let invoice_amount = invoice.total - invoice.tax_amount;
+
+    PartnerCommissionRow {
+        id: Uuid::now_v7(),
+        tenant_id: invoice.tenant_id,
+        partner_id,
+        invoice_id: invoice.id,
+        customer_id: invoice.customer_id,
+        currency: invoice.currency.clone(),
+        invoice_amount,
+        rev_share_percent,
+        amount: commission_amount(invoice_amount, rev_share_percent),
+        paid_at,
+    }
+    .insert(conn)
+    .await
+    .map_err(Into::<Report<StoreError>>::into)
+}
@@ -0,0 +1,295 @@
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::invoices::InvoiceRow;
+use diesel_models::payment_reminders::{
+    DuePaymentReminderRow, PaymentReminderLogRow, PaymentReminderLogRowNew,
+    PaymentReminderScheduleRow, PaymentReminderScheduleRowNew,
+};
+
+use crate::domain::enums::{NotificationNamespaceEnum, PaymentReminderChannelEnum};
+use crate::domain::payment_reminders::{
+    DuePaymentReminder, PaymentReminderEmailPayload, PaymentReminderLog, PaymentReminderSchedule,
+    PaymentReminderScheduleNew, PaymentReminderTiming, MAX_REMINDER_DELAY_DAYS,
+};
+use crate::domain::{OutboxEvent, OutboxNew};
+use crate::errors::StoreError;
+use crate::repositories::notification_bundles::resolve_messages;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait PaymentRemindersInterface {
+    async fn insert_payment_reminder_schedule(
+        &self,
+        schedule: PaymentReminderScheduleNew,
+    ) -> StoreResult<PaymentReminderSchedule>;
+
+    async fn list_payment_reminder_schedules(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<PaymentReminderSchedule>>;
+
+    async fn set_payment_reminder_schedule_enabled(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        enabled: bool,
+    ) -> StoreResult<PaymentReminderSchedule>;
+
+    async fn delete_payment_reminder_schedule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    async fn find_payment_reminder_by_id(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<PaymentReminderLog>;
+
+    /// The reminders sent for the invoice, oldest first
+    async fn list_invoice_payment_reminders(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<PaymentReminderLog>>;
+
+    /// The reminders of all tenants to send, for the unpaid invoices
+    async fn list_due_payment_reminders(
+        &self,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<DuePaymentReminder>>;
+
+    /// Logs the reminder and sends it through its channel.
+    /// Returns None if it was already sent for the invoice
+    async fn send_payment_reminder(
+        &self,
+        reminder: DuePaymentReminder,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<PaymentReminderLog>>;
+}
+
+#[async_trait::async_trait]
+impl PaymentRemindersInterface for Store {
+    async fn insert_payment_reminder_schedule(
+        &self,
+        schedule: PaymentReminderScheduleNew,
+    ) -> StoreResult<PaymentReminderSchedule> {
+        schedule.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        let existing = PaymentReminderScheduleRow::list_by_tenant_id(&mut conn, schedule.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let channel: diesel_models::enums::PaymentReminderChannelEnum = schedule.channel.into();
+
+        if existing
+            .iter()
+            .any(|s| s.offset_days == schedule.offset_days && s.channel == channel)
+        {
+            return Err(StoreError::InvalidArgument(format!(
+                "a reminder is already scheduled {} days from the due date on this channel",
+                schedule.offset_days
+            ))
+            .into());
+        }
+
+        let row: PaymentReminderScheduleRowNew = schedule.into();
+
+        row.insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_payment_reminder_schedules(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<PaymentReminderSchedule>> {
+        let mut conn = self.get_conn().await?;
+
+        PaymentReminderScheduleRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn set_payment_reminder_schedule_enabled(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        enabled: bool,
+    ) -> StoreResult<PaymentReminderSchedule> {
+        let mut conn = self.get_conn().await?;
+
+        PaymentReminderScheduleRow::set_enabled(&mut conn, tenant_id, id, enabled)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn delete_payment_reminder_schedule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let deleted = PaymentReminderScheduleRow::delete(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if deleted == 0 {
+            return Err(
+                StoreError::ValueNotFound(format!("payment reminder schedule {}", id)).into(),
+            );
+        }
+
+        Ok(())
+    }
+
+    async fn find_payment_reminder_by_id(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<PaymentReminderLog> {
+        let mut conn = self.get_conn().await?;
+
+        PaymentReminderLogRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_invoice_payment_reminders(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<Vec<PaymentReminderLog>> {
+        let mut conn = self.get_conn().await?;
+
+        // the invoice must belong to the tenant
+        InvoiceRow::find_by_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        PaymentReminderLogRow::list_by_invoice_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn list_due_payment_reminders(
+        &self,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<DuePaymentReminder>> {
+        let mut conn = self.get_conn().await?;
+
+        DuePaymentReminderRow::list(&mut conn, now, MAX_REMINDER_DELAY_DAYS as i32)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn send_payment_reminder(
+        &self,
+        reminder: DuePaymentReminder,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<PaymentReminderLog>> {
+        let row = PaymentReminderLogRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: reminder.tenant_id,
+            invoice_id: reminder.invoice_id,
+            schedule_id: Some(reminder.schedule_id),
+            offset_days: reminder.offset_days,
+            channel: reminder.channel.into(),
+            recipient: reminder.recipient.clone(),
+            due_at: reminder.due_at,
+            sent_at: now,
+        };
+
+        let email_payload = match (&reminder.channel, &reminder.recipient) {
+            (PaymentReminderChannelEnum::Email, Some(recipient)) => {
+                let mut conn = self.get_conn().await?;
+
+                let messages = resolve_messages(
+                    &mut conn,
+                    reminder.tenant_id,
+                    NotificationNamespaceEnum::Email,
+                    &reminder.locale,
+                )
+                .await?;
+
+                let (subject, body) = reminder.render_email(&messages);
+
+                Some(
+                    serde_json::to_value(PaymentReminderEmailPayload {
+                        payment_reminder_id: row.id,
+                        invoice_id: reminder.invoice_id,
+                        recipient: recipient.clone(),
+                        customer_name: reminder.customer_name.clone(),
+                        invoice_number: reminder.invoice_number.clone(),
+                        currency: reminder.currency.clone(),
+                        amount_due: reminder.amount_due,
+                        due_at: reminder.due_at,
+                        timing: PaymentReminderTiming::from_offset(reminder.offset_days),
+                        days: reminder.offset_days.unsigned_abs(),
+                        locale: reminder.locale.clone(),
+                        subject,
+                        body,
+                    })
+                    .map_err(|e| {
+                        StoreError::SerdeError(
+                            "Failed to serialize payment reminder email payload".to_string(),
+                            e,
+                        )
+                    })?,
+                )
+            }
+            _ => None,
+        };
+
+        let tenant_id = reminder.tenant_id;
+
+        let logged = self
+            .transaction(|conn| {
+                async move {
+                    let Some(logged) = row
+                        .insert_if_absent(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                    else {
+                        return Ok(None);
+                    };
+
+                    if let Some(payload) = email_payload {
+                        self.internal
+                            .insert_outbox_item(
+                                conn,
+                                OutboxNew {
+                                    event_type: OutboxEvent::InvoicePaymentReminderEmailRequested,
+                                    resource_id: logged.id,
+                                    tenant_id,
+                                    payload: Some(payload),
+                                },
+                            )
+                            .await?;
+                    }
+
+                    Ok(Some(logged))
+                }
+                .scope_boxed()
+            })
+            .await?
+            .map(PaymentReminderLog::from);
+
+        if let Some(logged) = &logged {
+            if logged.channel == PaymentReminderChannelEnum::Webhook {
+                let _ = self
+                    .eventbus
+                    .publish(Event::invoice_payment_reminder(logged.id, tenant_id))
+                    .await;
+            }
+        }
+
+        Ok(logged)
+    }
+}
@@ -0,0 +1,589 @@
+use std::collections::HashMap;
+
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::enums::BillingPeriodEnum;
+use crate::domain::plan_catalog::{
+    CatalogConflict, CatalogConflictKind, CatalogConflictStrategy, CatalogDocument, CatalogImport,
+    CatalogImportReport, CatalogPlan, CatalogPlanTrial, CatalogPlanVersion, CatalogPriceComponent,
+    CatalogProduct, CatalogProductFamily, CatalogReference, CATALOG_DOCUMENT_VERSION,
+};
+use crate::domain::price_components::FeeType;
+use crate::domain::tenant_environments::remap_ids;
+use crate::errors::StoreError;
+use crate::store::{PgConn, Store};
+use crate::StoreResult;
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::enums::{PlanStatusEnum, PlanTypeEnum};
+use diesel_models::plan_versions::{PlanVersionRow, PlanVersionRowNew};
+use diesel_models::plans::{PlanRow, PlanRowNew};
+use diesel_models::price_components::{PriceComponentRow, PriceComponentRowNew};
+use diesel_models::product_families::{ProductFamilyRow, ProductFamilyRowNew};
+use diesel_models::products::{ProductRow, ProductRowNew};
+use diesel_models::rate_cards::RateCardRow;
+
+#[async_trait::async_trait]
+pub trait PlanCatalogInterface {
+    /// Exports the product families and the last published version of the plans of a tenant
+    async fn export_plan_catalog(&self, tenant_id: Uuid) -> StoreResult<CatalogDocument>;
+
+    /// Imports a catalog document. Product families and products are matched by external id and name,
+    /// new plans are created with a draft version. Nothing is imported if a conflict is not resolved by the strategy
+    async fn import_plan_catalog(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        import: CatalogImport,
+    ) -> StoreResult<CatalogImportReport>;
+}
+
+#[async_trait::async_trait]
+impl PlanCatalogInterface for Store {
+    async fn export_plan_catalog(&self, tenant_id: Uuid) -> StoreResult<CatalogDocument> {
+        let mut conn = self.get_conn().await?;
+
+        let families = ProductFamilyRow::list(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .filter(|f| f.archived_at.is_none())
+            .collect::<Vec<_>>();
+
+        let family_external_ids: HashMap<Uuid, String> = families
+            .iter()
+            .map(|f| (f.id, f.external_id.clone()))
+            .collect();
+
+        let products = ProductRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let metrics = BillableMetricRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let rate_cards = RateCardRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let plans = PlanRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .filter(|p| p.archived_at.is_none() && !matches!(p.status, PlanStatusEnum::Archived))
+            .collect::<Vec<_>>();
+
+        let plan_external_ids: HashMap<Uuid, String> = plans
+            .iter()
+            .map(|p| (p.id, p.external_id.clone()))
+            .collect();
+
+        let mut exported_plans = vec![];
+        let mut product_ids = vec![];
+        let mut metric_ids = vec![];
+        let mut rate_card_ids = vec![];
+
+        for plan in plans {
+            let Some(product_family_external_id) =
+                family_external_ids.get(&plan.product_family_id).cloned()
+            else {
+                continue;
+            };
+
+            // plans without a published version are not part of the catalog yet
+            let Some(version) = PlanVersionRow::find_latest_by_plan_id_and_tenant_id(
+                &mut conn,
+                plan.id,
+                tenant_id,
+                Some(false),
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            else {
+                continue;
+            };
+
+            let price_components =
+                PriceComponentRow::list_by_plan_version_id(&mut conn, tenant_id, version.id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .into_iter()
+                    .map(|component| {
+                        product_ids.extend(component.product_item_id);
+                        metric_ids.extend(component.billable_metric_id);
+                        rate_card_ids.extend(component.rate_card_id);
+
+                        let fee: FeeType = serde_json::from_value(component.fee).map_err(|e| {
+                            StoreError::SerdeError(
+                                "Failed to deserialize price component fee".to_string(),
+                                e,
+                            )
+                        })?;
+
+                        Ok(CatalogPriceComponent {
+                            name: component.name,
+                            fee,
+                            product_id: component.product_item_id,
+                            billable_metric_id: component.billable_metric_id,
+                            rate_card_id: component.rate_card_id,
+                        })
+                    })
+                    .collect::<Result<Vec<_>, StoreError>>()?;
+
+            let trial = version
+                .trial_duration_days
+                .is_some()
+                .then(|| CatalogPlanTrial {
+                    duration_days: version.trial_duration_days,
+                    trialing_plan_external_id: version
+                        .trialing_plan_id
+                        .and_then(|id| plan_external_ids.get(&id).cloned()),
+                    downgrade_plan_external_id: version
+                        .downgrade_plan_id
+                        .and_then(|id| plan_external_ids.get(&id).cloned()),
+                    action_after_trial: version.action_after_trial.map(Into::into),
+                    is_free: version.trial_is_free,
+                    mode: version.trial_mode.into(),
+                });
+
+            exported_plans.push(CatalogPlan {
+                external_id: plan.external_id,
+                name: plan.name,
+                description: plan.description,
+                plan_type: plan.plan_type.into(),
+                product_family_external_id,
+                version: CatalogPlanVersion {
+                    currency: version.currency,
+                    net_terms: version.net_terms,
+                    period_start_day: version.period_start_day,
+                    billing_cycles: version.billing_cycles,
+                    billing_periods: version
+                        .billing_periods
+                        .into_iter()
+                        .flatten()
+                        .map(|period| BillingPeriodEnum::from_row(period, None))
+                        .collect(),
+                    trial,
+                    tax_category: version.tax_category,
+                    price_components,
+                },
+            });
+        }
+
+        Ok(CatalogDocument {
+            version: CATALOG_DOCUMENT_VERSION,
+            exported_at: chrono::Utc::now().naive_utc(),
+            product_families: families
+                .into_iter()
+                .map(|f| CatalogProductFamily {
+                    external_id: f.external_id,
+                    name: f.name,
+                })
+                .collect(),
+            products: products
+                .into_iter()
+                .filter(|p| product_ids.contains(&p.id))
+                .filter_map(|p| {
+                    family_external_ids
+                        .get(&p.product_family_id)
+                        .map(|family| CatalogProduct {
+                            id: p.id,
+                            name: p.name,
+                            description: p.description,
+                            product_family_external_id: family.clone(),
+                        })
+                })
+                .collect(),
+            billable_metrics: metrics
+                .into_iter()
+                .filter(|m| metric_ids.contains(&m.id))
+                .map(|m| CatalogReference {
+                    id: m.id,
+                    key: m.code,
+                })
+                .collect(),
+            rate_cards: rate_cards
+                .into_iter()
+                .filter(|r| rate_card_ids.contains(&r.id))
+                .map(|r| CatalogReference {
+                    id: r.id,
+                    key: r.name,
+                })
+                .collect(),
+            plans: exported_plans,
+        })
+    }
+
+    async fn import_plan_catalog(
+        &self,
+        tenant_id: Uuid,
+        actor: Uuid,
+        import: CatalogImport,
+    ) -> StoreResult<CatalogImportReport> {
+        let CatalogImport {
+            document,
+            on_conflict,
+            dry_run,
+        } = import;
+
+        if document.version != CATALOG_DOCUMENT_VERSION {
+            return Err(StoreError::InvalidArgument(format!(
+                "unsupported catalog document version {}, expected {}",
+                document.version, CATALOG_DOCUMENT_VERSION
+            ))
+            .into());
+        }
+
+        for plan in &document.plans {
+            if !document
+                .product_families
+                .iter()
+                .any(|f| f.external_id == plan.product_family_external_id)
+            {
+                return Err(StoreError::InvalidArgument(format!(
+                    "product family {} of plan {} is not in the document",
+                    plan.product_family_external_id, plan.external_id
+                ))
+                .into());
+            }
+        }
+
+        self.transaction(|conn| {
+            async move {
+                import_catalog(conn, tenant_id, actor, document, on_conflict, dry_run).await
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+async fn import_catalog(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    actor: Uuid,
+    document: CatalogDocument,
+    on_conflict: CatalogConflictStrategy,
+    dry_run: bool,
+) -> StoreResult<CatalogImportReport> {
+    let mut report = CatalogImportReport::default();
+    // document id -> tenant id, of the references of the fees
+    let mut ids: HashMap<Uuid, Uuid> = HashMap::new();
+
+    let target_families = ProductFamilyRow::list(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+    let target_products = ProductRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+    let target_metrics = BillableMetricRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+    let target_rate_cards = RateCardRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+    let target_plans = PlanRow::list_by_tenant_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    for metric in &document.billable_metrics {
+        if let Some(existing) = target_metrics.iter().find(|m| m.code == metric.key) {
+            ids.insert(metric.id, existing.id);
+        }
+    }
+    for rate_card in &document.rate_cards {
+        if let Some(existing) = target_rate_cards.iter().find(|r| r.name == rate_card.key) {
+            ids.insert(rate_card.id, existing.id);
+        }
+    }
+
+    // conflicts are all detected before anything is written
+    for plan in &document.plans {
+        if let Some(existing) = target_plans
+            .iter()
+            .find(|p| p.external_id == plan.external_id)
+        {
+            let family_matches = target_families.iter().any(|f| {
+                f.id == existing.product_family_id
+                    && f.external_id == plan.product_family_external_id
+            });
+            let plan_type: PlanTypeEnum = plan.plan_type.clone().into();
+
+            let kind = if existing.archived_at.is_some()
+                || matches!(existing.status, PlanStatusEnum::Archived)
+            {
+                CatalogConflictKind::PlanArchived
+            } else if !family_matches || existing.plan_type != plan_type {
+                CatalogConflictKind::PlanMismatch
+            } else {
+                CatalogConflictKind::PlanExists
+            };
+
+            report.conflicts.push(CatalogConflict {
+                plan_external_id: plan.external_id.clone(),
+                kind,
+            });
+        }
+
+        for component in &plan.version.price_components {
+            if let Some(metric_id) = component.billable_metric_id {
+                if !ids.contains_key(&metric_id) {
+                    let code = document
+                        .billable_metrics
+                        .iter()
+                        .find(|m| m.id == metric_id)
+                        .map(|m| m.key.clone())
+                        .unwrap_or_else(|| metric_id.to_string());
+
+                    report.conflicts.push(CatalogConflict {
+                        plan_external_id: plan.external_id.clone(),
+                        kind: CatalogConflictKind::MissingBillableMetric(code),
+                    });
+                }
+            }
+
+            if let Some(rate_card_id) = component.rate_card_id {
+                if !ids.contains_key(&rate_card_id) {
+                    let name = document
+                        .rate_cards
+                        .iter()
+                        .find(|r| r.id == rate_card_id)
+                        .map(|r| r.key.clone())
+                        .unwrap_or_else(|| rate_card_id.to_string());
+
+                    report.conflicts.push(CatalogConflict {
+                        plan_external_id: plan.external_id.clone(),
+                        kind: CatalogConflictKind::MissingRateCard(name),
+                    });
+                }
+            }
+        }
+    }
+
+    let blocked = report
+        .conflicts
+        .iter()
+        .any(|c| !c.kind.is_resolved_by(on_conflict));
+
+    // product families, by external id
+    let mut family_ids: HashMap<String, Uuid> = HashMap::new();
+    for family in &document.product_families {
+        let family_id = match target_families
+            .iter()
+            .find(|f| f.external_id == family.external_id)
+        {
+            Some(existing) => existing.id,
+            None => {
+                report.product_families += 1;
+                if blocked || dry_run {
+                    continue;
+                }
+                ProductFamilyRowNew {
+                    id: Uuid::now_v7(),
+                    name: family.name.clone(),
+                    external_id: family.external_id.clone(),
+                    tenant_id,
+                    default_currency: None,
+                    default_trial_duration_days: None,
+                    default_net_terms: None,
+                    default_tax_category: None,
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .id
+            }
+        };
+        family_ids.insert(family.external_id.clone(), family_id);
+    }
+
+    // products, by name within their family
+    for product in &document.products {
+        let family_id = family_ids.get(&product.product_family_external_id).copied();
+
+        let existing = family_id.and_then(|family_id| {
+            target_products
+                .iter()
+                .find(|p| p.name == product.name && p.product_family_id == family_id)
+        });
+
+        let product_id = match (existing, family_id) {
+            (Some(existing), _) => existing.id,
+            (None, family_id) => {
+                report.products += 1;
+                let Some(family_id) = family_id.filter(|_| !blocked && !dry_run) else {
+                    continue;
+                };
+                ProductRowNew {
+                    id: Uuid::now_v7(),
+                    name: product.name.clone(),
+                    description: product.description.clone(),
+                    created_by: actor,
+                    tenant_id,
+                    product_family_id: family_id,
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .id
+            }
+        };
+        ids.insert(product.id, product_id);
+    }
+
+    // plans, by external id. All the plans are resolved before their versions, that can reference each other
+    let mut plan_ids: HashMap<String, Uuid> = HashMap::new();
+    let mut plans_to_import = vec![];
+    for plan in document.plans {
+        let existing = target_plans
+            .iter()
+            .find(|p| p.external_id == plan.external_id);
+
+        let (plan_id, is_new) = match (existing, on_conflict) {
+            // nothing is imported
+            (Some(_), CatalogConflictStrategy::Fail) => continue,
+            (Some(existing), CatalogConflictStrategy::Skip) => {
+                report.skipped_plans.push(plan.external_id.clone());
+                plan_ids.insert(plan.external_id, existing.id);
+                continue;
+            }
+            (Some(existing), _) => {
+                report.plan_versions += 1;
+                (existing.id, false)
+            }
+            (None, _) => {
+                report.plans += 1;
+                if blocked || dry_run {
+                    continue;
+                }
+
+                let family_id = family_ids
+                    .get(&plan.product_family_external_id)
+                    .copied()
+                    .ok_or_else(|| {
+                        StoreError::ValueNotFound(format!(
+                            "product family {}",
+                            plan.product_family_external_id
+                        ))
+                    })?;
+
+                let inserted = PlanRowNew {
+                    id: Uuid::now_v7(),
+                    name: plan.name.clone(),
+                    description: plan.description.clone(),
+                    created_by: actor,
+                    tenant_id,
+                    product_family_id: family_id,
+                    external_id: plan.external_id.clone(),
+                    plan_type: plan.plan_type.clone().into(),
+                    status: PlanStatusEnum::Draft,
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                (inserted.id, true)
+            }
+        };
+
+        plan_ids.insert(plan.external_id.clone(), plan_id);
+        plans_to_import.push((plan_id, is_new, plan.version));
+    }
+
+    if blocked || dry_run {
+        return Ok(report);
+    }
+
+    for (plan_id, is_new, version) in plans_to_import {
+        let next_version = if is_new {
+            1
+        } else {
+            PlanVersionRow::get_latest_by_plan_id_and_tenant_id(conn, plan_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .version
+                + 1
+        };
+
+        let trial = version.trial;
+
+        let inserted = PlanVersionRowNew {
+            id: Uuid::now_v7(),
+            is_draft_version: true,
+            plan_id,
+            version: next_version,
+            trial_duration_days: trial.as_ref().and_then(|t| t.duration_days),
+            downgrade_plan_id: trial
+                .as_ref()
+                .and_then(|t| t.downgrade_plan_external_id.as_ref())
+                .and_then(|id| plan_ids.get(id).copied()),
+            tenant_id,
+            period_start_day: version.period_start_day,
+            net_terms: version.net_terms,
+            currency: version.currency,
+            billing_cycles: version.billing_cycles,
+            created_by: actor,
+            billing_periods: version
+                .billing_periods
+                .into_iter()
+                .map(Into::into)
+                .collect(),
+            trialing_plan_id: trial
+                .as_ref()
+                .and_then(|t| t.trialing_plan_external_id.as_ref())
+                .and_then(|id| plan_ids.get(id).copied()),
+            action_after_trial: trial
+                .as_ref()
+                .and_then(|t| t.action_after_trial.clone())
+                .map(Into::into),
+            trial_is_free: trial.as_ref().is_some_and(|t| t.is_free),
+            trial_mode: trial.map(|t| t.mode).unwrap_or_default().into(),
+            tax_category: version.tax_category,
+            inherited_defaults: vec![],
+        }
+        .insert(conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        if !is_new {
+            PlanVersionRow::delete_others_draft(conn, inserted.id, plan_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+        }
+
+        let components = version
+            .price_components
+            .into_iter()
+            .map(|component| {
+                let mut fee = serde_json::to_value(&component.fee).map_err(|e| {
+                    StoreError::SerdeError("Failed to serialize price component fee".to_string(), e)
+                })?;
+                remap_ids(&mut fee, &ids);
+
+                Ok(PriceComponentRowNew {
+                    id: Uuid::now_v7(),
+                    name: component.name,
+                    fee,
+                    plan_version_id: inserted.id,
+                    product_item_id: component.product_id.and_then(|id| ids.get(&id).copied()),
+                    billable_metric_id: component
+                        .billable_metric_id
+                        .and_then(|id| ids.get(&id).copied()),
+                    rate_card_id: component.rate_card_id.and_then(|id| ids.get(&id).copied()),
+                })
+            })
+            .collect::<Result<Vec<_>, StoreError>>()?;
+
+        if !components.is_empty() {
+            PriceComponentRow::insert_batch(conn, components)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+        }
+    }
+
+    report.applied = true;
+
+    Ok(report)
+}
@@ -1,14 +1,20 @@
 use crate::store::Store;
 use crate::StoreResult;
 
+use crate::domain::enums::TrialModeEnum;
+use crate::domain::plan_defaults::{
+    remove_inherited, resolve_effective_config, PlanDefaultSetting, PlanVersionEffectiveConfig,
+};
+use crate::domain::plan_version_diff::{diff_plan_versions, PlanVersionDiff};
 use crate::domain::{
     FullPlan, FullPlanNew, OrderByRequest, PaginatedVec, PaginationRequest, Plan,
     PlanAndVersionPatch, PlanFilters, PlanForList, PlanPatch, PlanVersion, PlanVersionLatest,
-    PlanVersionNew, PlanWithVersion, PriceComponent, PriceComponentNew, TrialPatch,
+    PlanVersionNew, PlanWithVersion, PriceComponent, PriceComponentNew, ProductFamily, TrialPatch,
 };
 use common_eventbus::Event;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::AsyncConnection;
+use diesel_models::plan_version_features::PlanVersionFeatureRow;
 use diesel_models::plan_versions::{
     PlanVersionRow, PlanVersionRowLatest, PlanVersionRowNew, PlanVersionRowPatch,
     PlanVersionTrialRowPatch,
@@ -21,6 +27,7 @@ use error_stack::Report;
 use uuid::Uuid;
 
 use crate::errors::StoreError;
+use crate::repositories::price_components::check_matrix_dimensions;
 
 #[async_trait::async_trait]
 pub trait PlansInterface {
@@ -107,11 +114,30 @@ pub trait PlansInterface {
     async fn patch_draft_plan(&self, patch: PlanAndVersionPatch) -> StoreResult<PlanWithVersion>;
 
     async fn patch_trial(&self, patch: TrialPatch) -> StoreResult<PlanWithVersion>;
+
+    /// The settings of the version, with whether they are set on the version or inherited
+    async fn get_plan_version_effective_config(
+        &self,
+        plan_version_id: Uuid,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<PlanVersionEffectiveConfig>;
+
+    /// What changed from a version to another version of the same plan, ex: from the published version to the draft
+    async fn diff_plan_versions(
+        &self,
+        from_plan_version_id: Uuid,
+        to_plan_version_id: Uuid,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<PlanVersionDiff>;
 }
 
 #[async_trait::async_trait]
 impl PlansInterface for Store {
     async fn insert_plan(&self, full_plan: FullPlanNew) -> StoreResult<FullPlan> {
+        if let Some(trial) = full_plan.version.trial.as_ref() {
+            trial.validate()?;
+        }
+
         let mut conn = self.get_conn().await?;
 
         let FullPlanNew {
@@ -120,12 +146,13 @@ impl PlansInterface for Store {
             price_components,
         } = full_plan;
 
-        let product_family = ProductFamilyRow::find_by_external_id_and_tenant_id(
+        let product_family: ProductFamily = ProductFamilyRow::find_by_external_id_and_tenant_id(
             &mut conn,
             plan.product_family_external_id.as_str(),
             plan.tenant_id,
         )
         .await
+        .map(Into::into)
         .map_err(|err| StoreError::DatabaseError(err.error))?;
 
         let tenant = TenantRow::find_by_id(&mut conn, plan.tenant_id)
@@ -149,7 +176,7 @@ impl PlansInterface for Store {
                         version: 1,
                         created_by: inserted.created_by,
                     }
-                    .into_raw(tenant.currency);
+                    .into_raw(tenant.currency, &product_family.defaults());
 
                     let inserted_plan_version_new: PlanVersion = plan_version_to_insert
                         .insert(conn)
@@ -168,6 +195,7 @@ impl PlansInterface for Store {
                                     name: p.name,
                                     product_item_id: p.product_item_id,
                                     fee: p.fee,
+                                    rate_card_id: None,
                                 }
                                 .try_into()
                             })
@@ -405,6 +433,9 @@ impl PlansInterface for Store {
                     trialing_plan_id: original.trialing_plan_id,
                     action_after_trial: original.action_after_trial,
                     trial_is_free: original.trial_is_free,
+                    trial_mode: original.trial_mode,
+                    tax_category: original.tax_category,
+                    inherited_defaults: original.inherited_defaults,
                     tenant_id: original.tenant_id,
                     period_start_day: original.period_start_day,
                     net_terms: original.net_terms,
@@ -425,6 +456,10 @@ impl PlansInterface for Store {
                     .await
                     .map_err(Into::<Report<StoreError>>::into)?;
 
+                PlanVersionFeatureRow::clone_all(conn, original.id, new.id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
                 Ok(new.into())
             }
             .scope_boxed()
@@ -443,6 +478,28 @@ impl PlansInterface for Store {
                 async move {
                     // TODO validations
                     // - all components on committed must have values for all periods
+                    let components: Vec<PriceComponent> =
+                        PriceComponentRow::list_by_plan_version_id(
+                            conn,
+                            auth_tenant_id,
+                            plan_version_id,
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .into_iter()
+                        .map(TryInto::try_into)
+                        .collect::<Result<_, _>>()?;
+
+                    check_matrix_dimensions(
+                        conn,
+                        auth_tenant_id,
+                        &components
+                            .iter()
+                            .map(|c| (c.name.as_str(), &c.fee))
+                            .collect::<Vec<_>>(),
+                    )
+                    .await?;
+
                     let published = PlanVersionRow::publish(conn, plan_version_id, auth_tenant_id)
                         .await
                         .map_err(Into::<Report<StoreError>>::into)?;
@@ -568,7 +625,22 @@ impl PlansInterface for Store {
         let version = self
             .transaction(|conn| {
                 async move {
-                    let patch_version: PlanVersionRowPatch = patch.version.into();
+                    let current: PlanVersion = PlanVersionRow::find_by_id_and_tenant_id(
+                        conn,
+                        patch.version.id,
+                        patch.version.tenant_id,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .into();
+
+                    let overridden = patch.version.overridden_defaults(&current);
+
+                    let mut patch_version: PlanVersionRowPatch = patch.version.into();
+                    if !overridden.is_empty() {
+                        patch_version.inherited_defaults =
+                            Some(remove_inherited(&current.inherited_defaults, &overridden));
+                    }
 
                     let patched_version = patch_version
                         .update_draft(conn)
@@ -601,11 +673,28 @@ impl PlansInterface for Store {
     }
 
     async fn patch_trial(&self, patch: TrialPatch) -> StoreResult<PlanWithVersion> {
+        if let Some(trial) = patch.trial.as_ref() {
+            trial.validate()?;
+        }
+
         let mut conn = self.get_conn().await?;
 
         let version = self
             .transaction(|conn| {
                 async move {
+                    let current = PlanVersionRow::find_by_id_and_tenant_id(
+                        conn,
+                        patch.plan_version_id,
+                        patch.tenant_id,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    let inherited_defaults = Some(remove_inherited(
+                        &current.inherited_defaults,
+                        &[PlanDefaultSetting::TrialDuration],
+                    ));
+
                     let patch: PlanVersionTrialRowPatch = match patch.trial {
                         None => PlanVersionTrialRowPatch {
                             id: patch.plan_version_id,
@@ -615,6 +704,8 @@ impl PlansInterface for Store {
                             trial_is_free: Some(false),
                             trial_duration_days: Some(None),
                             downgrade_plan_id: Some(None),
+                            trial_mode: Some(TrialModeEnum::Free.into()),
+                            inherited_defaults,
                         },
                         Some(trial) => PlanVersionTrialRowPatch {
                             id: patch.plan_version_id,
@@ -624,6 +715,8 @@ impl PlansInterface for Store {
                             trial_is_free: Some(trial.require_pre_authorization),
                             trial_duration_days: Some(Some(trial.duration_days as i32)),
                             downgrade_plan_id: Some(trial.downgrade_plan_id),
+                            trial_mode: Some(trial.mode.into()),
+                            inherited_defaults,
                         },
                     };
 
@@ -643,4 +736,87 @@ impl PlansInterface for Store {
             .map_err(Into::into)
             .map(Into::into)
     }
+
+    async fn get_plan_version_effective_config(
+        &self,
+        plan_version_id: Uuid,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<PlanVersionEffectiveConfig> {
+        let mut conn = self.get_conn().await?;
+
+        let version: PlanVersion =
+            PlanVersionRow::find_by_id_and_tenant_id(&mut conn, plan_version_id, auth_tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into();
+
+        let plan = PlanRow::get_by_id_and_tenant_id(&mut conn, version.plan_id, auth_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let product_family: ProductFamily =
+            ProductFamilyRow::find_by_id(&mut conn, plan.product_family_id, auth_tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into();
+
+        Ok(resolve_effective_config(
+            &version,
+            &product_family.defaults(),
+        ))
+    }
+
+    async fn diff_plan_versions(
+        &self,
+        from_plan_version_id: Uuid,
+        to_plan_version_id: Uuid,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<PlanVersionDiff> {
+        let mut conn = self.get_conn().await?;
+
+        let from: PlanVersion = PlanVersionRow::find_by_id_and_tenant_id(
+            &mut conn,
+            from_plan_version_id,
+            auth_tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into();
+
+        let to: PlanVersion =
+            PlanVersionRow::find_by_id_and_tenant_id(&mut conn, to_plan_version_id, auth_tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into();
+
+        if from.plan_id != to.plan_id {
+            return Err(StoreError::InvalidArgument(
+                "only the versions of a same plan can be compared".to_string(),
+            )
+            .into());
+        }
+
+        let from_components: Vec<PriceComponent> =
+            PriceComponentRow::list_by_plan_version_id(&mut conn, auth_tenant_id, from.id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?;
+
+        let to_components: Vec<PriceComponent> =
+            PriceComponentRow::list_by_plan_version_id(&mut conn, auth_tenant_id, to.id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(diff_plan_versions(
+            &from,
+            &from_components,
+            &to,
+            &to_components,
+        ))
+    }
 }
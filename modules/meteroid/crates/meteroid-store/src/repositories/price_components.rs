@@ -1,12 +1,24 @@
-use crate::store::Store;
+use std::collections::HashMap;
+
+use crate::store::{PgConn, Store};
 use crate::StoreResult;
 use error_stack::Report;
 
-use crate::domain::price_components::{PriceComponent, PriceComponentNew};
+use crate::compute::preview_tiered_pricing;
+use crate::constants::Currencies;
+use crate::domain::price_component_validation::{InvalidMatrixDimension, PriceComponentIssue};
+use crate::domain::price_components::{
+    FeeType, PriceComponent, PriceComponentNew, TierRow, TieredPricingPreview, UsagePricingModel,
+};
+use crate::domain::BillableMetric;
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::plan_versions::PlanVersionRow;
 use diesel_models::price_components::PriceComponentRow;
+use rust_decimal::Decimal;
 use uuid::Uuid;
 
 use crate::errors::StoreError;
+use crate::repositories::rate_cards::resolve_rate_card_fee;
 
 #[async_trait::async_trait]
 pub trait PriceComponentInterface {
@@ -25,6 +37,7 @@ pub trait PriceComponentInterface {
     async fn create_price_component(
         &self,
         price_component: PriceComponentNew,
+        tenant_id: Uuid,
     ) -> StoreResult<PriceComponent>;
 
     async fn create_price_component_batch(
@@ -40,6 +53,24 @@ pub trait PriceComponentInterface {
     ) -> StoreResult<Option<PriceComponent>>;
 
     async fn delete_price_component(&self, component_id: Uuid, tenant_id: Uuid) -> StoreResult<()>;
+
+    /// Checks a fee against the metric it prices and the currency of the plan version, without saving it
+    async fn validate_price_component(
+        &self,
+        fee: &FeeType,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> StoreResult<Vec<PriceComponentIssue>>;
+
+    /// Prices the given usages with the tiers both as graduated and as volume, before publishing
+    async fn preview_tiered_pricing(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+        tiers: &[TierRow],
+        block_size: Option<u64>,
+        usages: &[Decimal],
+    ) -> StoreResult<Vec<TieredPricingPreview>>;
 }
 
 #[async_trait::async_trait]
@@ -78,9 +109,39 @@ impl PriceComponentInterface for Store {
     async fn create_price_component(
         &self,
         price_component: PriceComponentNew,
+        tenant_id: Uuid,
     ) -> StoreResult<PriceComponent> {
         let mut conn = self.get_conn().await?;
-        let price_component = price_component.try_into()?;
+
+        let fee = match price_component.rate_card_id {
+            Some(rate_card_id) => {
+                let plan_version = PlanVersionRow::find_by_id_and_tenant_id(
+                    &mut conn,
+                    price_component.plan_version_id,
+                    tenant_id,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                resolve_rate_card_fee(
+                    &mut conn,
+                    tenant_id,
+                    rate_card_id,
+                    &plan_version.currency,
+                    &price_component.fee,
+                )
+                .await?
+            }
+            None => price_component.fee,
+        };
+
+        check_matrix_dimensions(&mut conn, tenant_id, &[(&price_component.name, &fee)]).await?;
+
+        let price_component = PriceComponentNew {
+            fee,
+            ..price_component
+        }
+        .try_into()?;
         let inserted = PriceComponentRow::insert(&mut conn, price_component)
             .await
             .map_err(Into::<Report<StoreError>>::into)?;
@@ -112,18 +173,41 @@ impl PriceComponentInterface for Store {
         tenant_id: Uuid,
         plan_version_id: Uuid,
     ) -> StoreResult<Option<PriceComponent>> {
-        let json_fee = serde_json::to_value(&price_component.fee).map_err(|e| {
+        let mut conn = self.get_conn().await?;
+
+        let fee = match price_component.rate_card_id {
+            Some(rate_card_id) => {
+                let plan_version =
+                    PlanVersionRow::find_by_id_and_tenant_id(&mut conn, plan_version_id, tenant_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                resolve_rate_card_fee(
+                    &mut conn,
+                    tenant_id,
+                    rate_card_id,
+                    &plan_version.currency,
+                    &price_component.fee,
+                )
+                .await?
+            }
+            None => price_component.fee,
+        };
+
+        check_matrix_dimensions(&mut conn, tenant_id, &[(&price_component.name, &fee)]).await?;
+
+        let json_fee = serde_json::to_value(&fee).map_err(|e| {
             StoreError::SerdeError("Failed to serialize price component fee".to_string(), e)
         })?;
 
-        let mut conn = self.get_conn().await?;
         let price_component: PriceComponentRow = PriceComponentRow {
             id: price_component.id,
             plan_version_id,
             name: price_component.name,
             product_item_id: price_component.product_item_id,
             fee: json_fee,
-            billable_metric_id: price_component.fee.metric_id(),
+            billable_metric_id: fee.metric_id(),
+            rate_card_id: price_component.rate_card_id,
         };
         let updated = price_component
             .update(&mut conn, tenant_id)
@@ -146,4 +230,110 @@ impl PriceComponentInterface for Store {
             .map_err(Into::<Report<StoreError>>::into)?;
         Ok(())
     }
+
+    async fn validate_price_component(
+        &self,
+        fee: &FeeType,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+    ) -> StoreResult<Vec<PriceComponentIssue>> {
+        let mut conn = self.get_conn().await?;
+
+        let plan_version =
+            PlanVersionRow::find_by_id_and_tenant_id(&mut conn, plan_version_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        let currency_precision =
+            Currencies::resolve_currency_precision(&plan_version.currency).ok_or(
+                StoreError::ValueNotFound(format!("Currency {} not found", plan_version.currency)),
+            )?;
+
+        let metric: Option<BillableMetric> = match fee.metric_id() {
+            Some(metric_id) => BillableMetricRow::get_by_ids(&mut conn, &[metric_id], &tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .next()
+                .map(TryInto::try_into)
+                .transpose()?,
+            None => None,
+        };
+
+        Ok(fee.validate(metric.as_ref(), currency_precision))
+    }
+
+    async fn preview_tiered_pricing(
+        &self,
+        tenant_id: Uuid,
+        plan_version_id: Uuid,
+        tiers: &[TierRow],
+        block_size: Option<u64>,
+        usages: &[Decimal],
+    ) -> StoreResult<Vec<TieredPricingPreview>> {
+        let mut conn = self.get_conn().await?;
+
+        let plan_version =
+            PlanVersionRow::find_by_id_and_tenant_id(&mut conn, plan_version_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        let currency_precision =
+            Currencies::resolve_currency_precision(&plan_version.currency).ok_or(
+                StoreError::ValueNotFound(format!("Currency {} not found", plan_version.currency)),
+            )?;
+
+        if tiers.is_empty() {
+            return Err(
+                StoreError::InvalidArgument("at least one tier is required".to_string()).into(),
+            );
+        }
+
+        preview_tiered_pricing(usages, tiers, currency_precision, &block_size)
+            .map_err(Into::<Report<StoreError>>::into)
+    }
+}
+
+/// Rejects the matrix fees whose rates do not match the segmentation matrix of their metric
+pub(crate) async fn check_matrix_dimensions(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    fees: &[(&str, &FeeType)],
+) -> StoreResult<()> {
+    let metric_ids: Vec<Uuid> = fees
+        .iter()
+        .filter_map(|(_, fee)| match fee {
+            FeeType::Usage {
+                metric_id,
+                pricing: UsagePricingModel::Matrix { .. },
+                ..
+            } => Some(*metric_id),
+            _ => None,
+        })
+        .collect();
+
+    if metric_ids.is_empty() {
+        return Ok(());
+    }
+
+    let metrics: HashMap<Uuid, BillableMetric> =
+        BillableMetricRow::get_by_ids(conn, &metric_ids, &tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(|row| BillableMetric::try_from(row).map(|m| (m.id, m)))
+            .collect::<Result<_, _>>()?;
+
+    let invalid: Vec<InvalidMatrixDimension> = fees
+        .iter()
+        .flat_map(|(name, fee)| {
+            fee.invalid_matrix_dimensions(name, fee.metric_id().and_then(|id| metrics.get(&id)))
+        })
+        .collect();
+
+    if invalid.is_empty() {
+        Ok(())
+    } else {
+        Err(StoreError::InvalidMatrixDimensions(invalid).into())
+    }
 }
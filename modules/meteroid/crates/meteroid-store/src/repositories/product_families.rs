@@ -1,7 +1,16 @@
+use crate::constants::Currencies;
+use crate::domain::plan_defaults::PlanDefaultSetting;
+use crate::errors::StoreError;
 use crate::store::{PgConn, Store, StoreInternal};
 use crate::{domain, StoreResult};
 use common_eventbus::Event;
-use diesel_models::product_families::{ProductFamilyRow, ProductFamilyRowNew};
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::plan_versions::PlanVersionInheritedRowPatch;
+use diesel_models::product_families::{
+    ProductFamilyDefaultsRowPatch, ProductFamilyRow, ProductFamilyRowNew,
+};
+use diesel_models::tenants::TenantRow;
+use error_stack::Report;
 use uuid::Uuid;
 
 #[async_trait::async_trait]
@@ -22,6 +31,14 @@ pub trait ProductFamilyInterface {
         external_id: &str,
         auth_tenant_id: Uuid,
     ) -> StoreResult<domain::ProductFamily>;
+
+    /// Also applies the defaults to the draft plan versions of the family that inherit them
+    async fn update_product_family_defaults(
+        &self,
+        external_id: &str,
+        defaults: domain::ProductFamilyDefaults,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<domain::ProductFamily>;
 }
 
 impl StoreInternal {
@@ -86,4 +103,105 @@ impl ProductFamilyInterface for Store {
             .map_err(Into::into)
             .map(Into::into)
     }
+
+    async fn update_product_family_defaults(
+        &self,
+        external_id: &str,
+        defaults: domain::ProductFamilyDefaults,
+        auth_tenant_id: Uuid,
+    ) -> StoreResult<domain::ProductFamily> {
+        if let Some(currency) = defaults.currency.as_ref() {
+            Currencies::resolve_currency_precision(currency).ok_or(StoreError::InvalidArgument(
+                format!("unknown currency {}", currency),
+            ))?;
+        }
+        if defaults.trial_duration_days.is_some_and(|d| d < 0)
+            || defaults.net_terms.is_some_and(|n| n < 0)
+        {
+            return Err(StoreError::InvalidArgument(
+                "the default trial duration and net terms cannot be negative".to_string(),
+            )
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let family = ProductFamilyRow::find_by_external_id_and_tenant_id(
+            &mut conn,
+            external_id,
+            auth_tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        let tenant = TenantRow::find_by_id(&mut conn, auth_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        self.transaction_with(&mut conn, |conn| {
+            async move {
+                let patch: ProductFamilyDefaultsRowPatch = defaults.clone().into();
+                let updated = patch
+                    .update(conn, family.id, auth_tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                // the drafts fall back to the tenant values when a default is unset
+                let inherited = [
+                    (
+                        PlanDefaultSetting::Currency,
+                        PlanVersionInheritedRowPatch {
+                            currency: Some(defaults.currency.unwrap_or(tenant.currency)),
+                            trial_duration_days: None,
+                            net_terms: None,
+                            tax_category: None,
+                        },
+                    ),
+                    (
+                        PlanDefaultSetting::TrialDuration,
+                        PlanVersionInheritedRowPatch {
+                            currency: None,
+                            trial_duration_days: Some(defaults.trial_duration_days),
+                            net_terms: None,
+                            tax_category: None,
+                        },
+                    ),
+                    (
+                        PlanDefaultSetting::NetTerms,
+                        PlanVersionInheritedRowPatch {
+                            currency: None,
+                            trial_duration_days: None,
+                            net_terms: Some(defaults.net_terms.unwrap_or(0)),
+                            tax_category: None,
+                        },
+                    ),
+                    (
+                        PlanDefaultSetting::TaxCategory,
+                        PlanVersionInheritedRowPatch {
+                            currency: None,
+                            trial_duration_days: None,
+                            net_terms: None,
+                            tax_category: Some(defaults.tax_category),
+                        },
+                    ),
+                ];
+
+                for (setting, patch) in inherited {
+                    patch
+                        .update_inheriting_drafts(
+                            conn,
+                            auth_tenant_id,
+                            updated.id,
+                            setting.as_str(),
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                }
+
+                Ok(updated.into())
+            }
+            .scope_boxed()
+        })
+        .await
+    }
 }
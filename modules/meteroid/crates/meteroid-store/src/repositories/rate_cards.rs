@@ -0,0 +1,247 @@
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::price_components::PriceComponentRow;
+use diesel_models::rate_cards::{RateCardRow, RateCardVersionRow};
+
+use crate::constants::Currencies;
+use crate::domain::price_components::FeeType;
+use crate::domain::rate_cards::{
+    RateCard, RateCardDetails, RateCardNew, RateCardPublication, RateCardRate, RateCardVersion,
+    RateCardVersionNew,
+};
+use crate::errors::StoreError;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait RateCardInterface {
+    async fn list_rate_cards(&self, tenant_id: Uuid) -> StoreResult<Vec<RateCard>>;
+
+    async fn get_rate_card(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<RateCardDetails>;
+
+    async fn list_rate_card_versions(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<Vec<RateCardVersion>>;
+
+    async fn create_rate_card(&self, rate_card: RateCardNew) -> StoreResult<RateCardDetails>;
+
+    /// Adds a version to the rate card, and reprices the components of the draft plan versions referencing it
+    async fn publish_rate_card_version(
+        &self,
+        version: RateCardVersionNew,
+    ) -> StoreResult<RateCardPublication>;
+}
+
+#[async_trait::async_trait]
+impl RateCardInterface for Store {
+    async fn list_rate_cards(&self, tenant_id: Uuid) -> StoreResult<Vec<RateCard>> {
+        let mut conn = self.get_conn().await?;
+
+        RateCardRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn get_rate_card(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<RateCardDetails> {
+        let mut conn = self.get_conn().await?;
+
+        get_rate_card_details(&mut conn, tenant_id, id).await
+    }
+
+    async fn list_rate_card_versions(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<Vec<RateCardVersion>> {
+        let mut conn = self.get_conn().await?;
+
+        RateCardRow::get_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        RateCardVersionRow::list_by_rate_card_id(&mut conn, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    async fn create_rate_card(&self, rate_card: RateCardNew) -> StoreResult<RateCardDetails> {
+        Currencies::resolve_currency_precision(&rate_card.currency).ok_or(
+            StoreError::InvalidArgument(format!("Unknown currency {}", rate_card.currency)),
+        )?;
+
+        let mut conn = self.get_conn().await?;
+
+        validate_rate_metrics(&mut conn, rate_card.tenant_id, &rate_card.rates).await?;
+
+        self.transaction_with(&mut conn, |conn| {
+            async move {
+                let inserted: RateCard = rate_card
+                    .to_row()
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .into();
+
+                let current_version: RateCardVersion = RateCardVersionNew {
+                    tenant_id: rate_card.tenant_id,
+                    rate_card_id: inserted.id,
+                    rates: rate_card.rates,
+                    created_by: rate_card.created_by,
+                }
+                .to_row(1)?
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .try_into()?;
+
+                Ok(RateCardDetails {
+                    rate_card: inserted,
+                    current_version,
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn publish_rate_card_version(
+        &self,
+        version: RateCardVersionNew,
+    ) -> StoreResult<RateCardPublication> {
+        let mut conn = self.get_conn().await?;
+
+        validate_rate_metrics(&mut conn, version.tenant_id, &version.rates).await?;
+
+        self.transaction_with(&mut conn, |conn| {
+            async move {
+                let current =
+                    get_rate_card_details(conn, version.tenant_id, version.rate_card_id).await?;
+
+                let published: RateCardVersion = version
+                    .to_row(current.current_version.version + 1)?
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .try_into()?;
+
+                let components = PriceComponentRow::list_in_draft_versions_by_rate_card_id(
+                    conn,
+                    version.tenant_id,
+                    version.rate_card_id,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let updated_price_components = components.len();
+
+                for component in components {
+                    let component_id = component.id;
+                    let component: crate::domain::PriceComponent = component.try_into()?;
+
+                    let fee = published.apply_to(&component.fee).map_err(|e| {
+                        Report::new(e).attach_printable(format!(
+                            "Price component {} cannot be priced by the new version",
+                            component_id
+                        ))
+                    })?;
+
+                    let json_fee = serde_json::to_value(&fee).map_err(|e| {
+                        StoreError::SerdeError(
+                            "Failed to serialize price component fee".to_string(),
+                            e,
+                        )
+                    })?;
+
+                    PriceComponentRow::update_fee(conn, component_id, json_fee)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                }
+
+                Ok(RateCardPublication {
+                    rate_card: RateCardDetails {
+                        rate_card: current.rate_card,
+                        current_version: published,
+                    },
+                    updated_price_components,
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+async fn get_rate_card_details(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    id: Uuid,
+) -> StoreResult<RateCardDetails> {
+    let rate_card: RateCard = RateCardRow::get_by_id(conn, tenant_id, id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into();
+
+    let current_version = RateCardVersionRow::find_latest(conn, id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .try_into()?;
+
+    Ok(RateCardDetails {
+        rate_card,
+        current_version,
+    })
+}
+
+async fn validate_rate_metrics(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    rates: &[RateCardRate],
+) -> StoreResult<()> {
+    let metric_ids: Vec<Uuid> = rates.iter().map(|r| r.metric_id).collect();
+
+    let metrics = BillableMetricRow::get_by_ids(conn, &metric_ids, &tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    if let Some(missing) = metric_ids
+        .iter()
+        .find(|id| !metrics.iter().any(|m| m.id == **id))
+    {
+        return Err(StoreError::InvalidArgument(format!("Unknown metric {}", missing)).into());
+    }
+
+    Ok(())
+}
+
+/// The fee of a price component priced by a rate card, with the rate of the latest rate card version
+pub(crate) async fn resolve_rate_card_fee(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    rate_card_id: Uuid,
+    plan_version_currency: &str,
+    fee: &FeeType,
+) -> StoreResult<FeeType> {
+    let details = get_rate_card_details(conn, tenant_id, rate_card_id).await?;
+
+    if details.rate_card.currency != plan_version_currency {
+        return Err(StoreError::InvalidArgument(format!(
+            "The rate card currency {} does not match the plan currency {}",
+            details.rate_card.currency, plan_version_currency
+        ))
+        .into());
+    }
+
+    details
+        .current_version
+        .apply_to(fee)
+        .map_err(Into::<Report<StoreError>>::into)
+}
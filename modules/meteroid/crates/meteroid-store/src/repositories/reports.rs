@@ -0,0 +1,130 @@
+use cached::proc_macro::cached;
+use chrono::NaiveDate;
+use diesel_models::bi::BiMrrMovementLogRow;
+use diesel_models::stats::SubscriptionSegmentRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::reports::{
+    build_mrr_report_periods, count_report_periods, MrrReport, ReportMovement, ReportRequest,
+    SubscriptionSegmentReport, MAX_REPORT_PERIODS,
+};
+use crate::errors::StoreError;
+use crate::store::PgConn;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait ReportsInterface {
+    /// MRR movements per period, from which the churn, retention and ARPU reports are derived
+    async fn mrr_report(&self, request: ReportRequest) -> StoreResult<MrrReport>;
+
+    /// Active subscriptions and MRR by plan, billing period, currency and customer country, compared to a previous date
+    async fn subscription_segment_report(
+        &self,
+        tenant_id: Uuid,
+        as_of: NaiveDate,
+        compare_to: NaiveDate,
+    ) -> StoreResult<SubscriptionSegmentReport>;
+}
+
+#[async_trait::async_trait]
+impl ReportsInterface for Store {
+    async fn mrr_report(&self, request: ReportRequest) -> StoreResult<MrrReport> {
+        if request.start_date > request.end_date {
+            return Err(StoreError::InvalidArgument(
+                "start_date must be before end_date".to_string(),
+            )
+            .into());
+        }
+
+        if count_report_periods(request.start_date, request.end_date, request.granularity)
+            > MAX_REPORT_PERIODS
+        {
+            return Err(StoreError::InvalidArgument(format!(
+                "Too many periods, a report is limited to {} periods",
+                MAX_REPORT_PERIODS
+            ))
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let currency = match request.currency {
+            Some(currency) => currency,
+            None => self
+                .internal
+                .get_reporting_currency_by_tenant_id(&mut conn, request.tenant_id)
+                .await?
+                .code
+                .to_string(),
+        };
+
+        let mut plan_ids = request.plan_ids;
+        plan_ids.sort();
+        plan_ids.dedup();
+
+        let movements = list_report_movements_cached(
+            &mut conn,
+            request.tenant_id,
+            currency.clone(),
+            plan_ids,
+            request.end_date,
+        )
+        .await?;
+
+        Ok(MrrReport {
+            currency,
+            periods: build_mrr_report_periods(
+                &movements,
+                request.start_date,
+                request.end_date,
+                request.granularity,
+            ),
+        })
+    }
+
+    async fn subscription_segment_report(
+        &self,
+        tenant_id: Uuid,
+        as_of: NaiveDate,
+        compare_to: NaiveDate,
+    ) -> StoreResult<SubscriptionSegmentReport> {
+        if compare_to >= as_of {
+            return Err(
+                StoreError::InvalidArgument("compare_to must be before as_of".to_string()).into(),
+            );
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let segments = SubscriptionSegmentRow::list(&mut conn, tenant_id, as_of, compare_to)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(SubscriptionSegmentReport {
+            as_of,
+            compare_to,
+            segments: segments.into_iter().map(Into::into).collect(),
+        })
+    }
+}
+
+#[cached(
+    result = true,
+    size = 100,
+    time = 300, // 5min
+    key = "(Uuid, String, Vec<Uuid>, NaiveDate)",
+    convert = r#"{ (tenant_id, currency.clone(), plan_ids.clone(), until) }"#
+)]
+async fn list_report_movements_cached(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    currency: String,
+    plan_ids: Vec<Uuid>,
+    until: NaiveDate,
+) -> StoreResult<Vec<ReportMovement>> {
+    BiMrrMovementLogRow::list_for_report(conn, tenant_id, &currency, &plan_ids, until)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+}
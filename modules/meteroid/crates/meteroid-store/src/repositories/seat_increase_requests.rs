@@ -0,0 +1,256 @@
+use crate::domain::enums::SeatIncreaseRequestStatusEnum;
+use crate::domain::seat_increase_requests::{SeatIncreaseRequest, SeatIncreaseRequestNew};
+use crate::errors::StoreError;
+use crate::repositories::subscriptions::{find_slot_component, SubscriptionSlotsInterface};
+use crate::repositories::SubscriptionInterface;
+use crate::{Store, StoreResult};
+use common_eventbus::Event;
+use diesel_models::seat_increase_requests::SeatIncreaseRequestRow;
+use diesel_models::tenants::TenantRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait SeatIncreaseRequestInterface {
+    /// Adds the seats right away when within the auto-approve limit of the tenant,
+    /// otherwise keeps the request pending until the tenant decides on it
+    async fn request_seat_increase(
+        &self,
+        request: SeatIncreaseRequestNew,
+        actor: Uuid,
+    ) -> StoreResult<SeatIncreaseRequest>;
+
+    async fn find_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<SeatIncreaseRequest>;
+
+    async fn list_seat_increase_requests(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Option<Uuid>,
+        status: Option<SeatIncreaseRequestStatusEnum>,
+    ) -> StoreResult<Vec<SeatIncreaseRequest>>;
+
+    /// Approves a pending request and adds its seats
+    async fn approve_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+        note: Option<String>,
+    ) -> StoreResult<SeatIncreaseRequest>;
+
+    async fn reject_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+        note: Option<String>,
+    ) -> StoreResult<SeatIncreaseRequest>;
+}
+
+#[async_trait::async_trait]
+impl SeatIncreaseRequestInterface for Store {
+    async fn request_seat_increase(
+        &self,
+        request: SeatIncreaseRequestNew,
+        actor: Uuid,
+    ) -> StoreResult<SeatIncreaseRequest> {
+        request.validate()?;
+
+        let tenant_id = request.tenant_id;
+
+        let subscription = self
+            .get_subscription_details(tenant_id, request.subscription_id)
+            .await?;
+
+        if subscription.canceled_at.is_some() {
+            return Err(StoreError::InvalidArgument(
+                "Cannot request seats on a canceled subscription".to_string(),
+            )
+            .into());
+        }
+
+        find_slot_component(&subscription.price_components, request.price_component_id)?;
+
+        let mut conn = self.get_conn().await?;
+
+        let auto_approve_limit = TenantRow::find_by_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .seat_auto_approve_limit;
+
+        let now = chrono::Utc::now().naive_utc();
+
+        if request.is_auto_approved(auto_approve_limit) {
+            let update = self
+                .add_slot_transaction(
+                    tenant_id,
+                    request.subscription_id,
+                    request.price_component_id,
+                    request.seats,
+                    actor,
+                    Some(now),
+                )
+                .await?;
+
+            return request
+                .into_row(now, Some(&update))
+                .insert(&mut conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)
+                .map(Into::into);
+        }
+
+        let inserted: SeatIncreaseRequest = request
+            .into_row(now, None)
+            .insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into();
+
+        let _ = self
+            .eventbus
+            .publish(Event::seat_increase_requested(inserted.id, tenant_id))
+            .await;
+
+        Ok(inserted)
+    }
+
+    async fn find_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<SeatIncreaseRequest> {
+        let mut conn = self.get_conn().await?;
+
+        SeatIncreaseRequestRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_seat_increase_requests(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Option<Uuid>,
+        status: Option<SeatIncreaseRequestStatusEnum>,
+    ) -> StoreResult<Vec<SeatIncreaseRequest>> {
+        let mut conn = self.get_conn().await?;
+
+        SeatIncreaseRequestRow::list(
+            &mut conn,
+            tenant_id,
+            subscription_id,
+            status.map(Into::into),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn approve_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+        note: Option<String>,
+    ) -> StoreResult<SeatIncreaseRequest> {
+        // claimed first, so that two approvals cannot add the seats twice
+        let claimed = decide(
+            self,
+            tenant_id,
+            id,
+            SeatIncreaseRequestStatusEnum::Approved,
+            actor,
+            note,
+        )
+        .await?;
+
+        let update = match self
+            .add_slot_transaction(
+                tenant_id,
+                claimed.subscription_id,
+                claimed.price_component_id,
+                claimed.seats,
+                actor,
+                None,
+            )
+            .await
+        {
+            Ok(update) => update,
+            Err(err) => {
+                let mut conn = self.get_conn().await?;
+                SeatIncreaseRequestRow::reopen(&mut conn, id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                return Err(err);
+            }
+        };
+
+        let mut conn = self.get_conn().await?;
+
+        SeatIncreaseRequestRow::set_invoice_id(&mut conn, id, update.invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn reject_seat_increase_request(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        actor: Uuid,
+        note: Option<String>,
+    ) -> StoreResult<SeatIncreaseRequest> {
+        decide(
+            self,
+            tenant_id,
+            id,
+            SeatIncreaseRequestStatusEnum::Rejected,
+            actor,
+            note,
+        )
+        .await
+    }
+}
+
+async fn decide(
+    store: &Store,
+    tenant_id: Uuid,
+    id: Uuid,
+    status: SeatIncreaseRequestStatusEnum,
+    actor: Uuid,
+    note: Option<String>,
+) -> StoreResult<SeatIncreaseRequest> {
+    let mut conn = store.get_conn().await?;
+
+    let decided = SeatIncreaseRequestRow::decide(
+        &mut conn,
+        tenant_id,
+        id,
+        status.into(),
+        actor,
+        note.filter(|n| !n.trim().is_empty()),
+        chrono::Utc::now().naive_utc(),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    match decided {
+        Some(row) => Ok(row.into()),
+        None => {
+            // not found, or no longer pending
+            SeatIncreaseRequestRow::find_by_id(&mut conn, tenant_id, id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+            Err(StoreError::InvalidArgument(
+                "The seat increase request was already decided".to_string(),
+            )
+            .into())
+        }
+    }
+}
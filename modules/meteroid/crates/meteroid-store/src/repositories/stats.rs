@@ -1,13 +1,21 @@
 use crate::domain::stats::*;
 use crate::errors::StoreError;
+use crate::repositories::invoices::{record_mrr_movement, MrrMovementSource};
+use crate::store::{PgConn, StoreInternal};
 use crate::utils::decimals::ToSubunit;
 use crate::{Store, StoreResult};
+use chrono::NaiveDate;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use diesel_models::bi::BiMrrMovementLogRow;
+use diesel_models::invoices::InvoiceRow;
 use diesel_models::stats::{
     ActiveSubscriptionsCountRow, CustomerTopRevenueRow, DailyNewSignups90DaysRow,
     LastMrrMovementsRow, MrrBreakdownRow, NewSignupsTrend90DaysRow, PendingInvoicesTotalRow,
-    RevenueTrendRow, SubscriptionTrialConversionRateRow, SubscriptionTrialToPaidConversionRow,
-    TotalMrrByPlanRow, TotalMrrChartRow, TotalMrrRow,
+    ReportSnapshotRow, RevenueTrendRow, SubscriptionTrialConversionRateRow,
+    SubscriptionTrialToPaidConversionRow, TotalMrrByPlanRow, TotalMrrChartRow, TotalMrrRow,
 };
+use diesel_models::subscription_events::SubscriptionEventRow;
+use diesel_models::subscriptions::SubscriptionRow;
 use diesel_models::tenants::TenantRow;
 use error_stack::Report;
 use rust_decimal::prelude::ToPrimitive;
@@ -16,12 +24,13 @@ use uuid::Uuid;
 
 #[async_trait::async_trait]
 pub trait StatsInterface {
-    async fn net_revenue(&self, tenant_id: Uuid) -> StoreResult<Trend>;
-    async fn active_subscriptions(&self, tenant_id: Uuid) -> StoreResult<i64>;
-    async fn pending_invoices(&self, tenant_id: Uuid) -> StoreResult<CountAndValue>;
-    async fn signups(&self, tenant_id: Uuid) -> StoreResult<Trend>;
+    /// The dashboard figures, computed in a single snapshot so that they are consistent with each other while the workers write
+    async fn general_stats(
+        &self,
+        tenant_id: Uuid,
+        fx_mode: FxRestatementMode,
+    ) -> StoreResult<GeneralStats>;
     async fn signups_sparkline(&self, tenant_id: Uuid) -> StoreResult<SignupSparklineResponse>;
-    async fn trial_conversion_rate(&self, tenant_id: Uuid) -> StoreResult<f32>;
     async fn trial_conversion_rate_sparkline(
         &self,
         tenant_id: Uuid,
@@ -30,80 +39,42 @@ pub trait StatsInterface {
         &self,
         request: RevenueByCustomerRequest,
     ) -> StoreResult<Vec<RevenueByCustomer>>;
-    async fn total_mrr(&self, tenant_id: Uuid) -> StoreResult<i64>;
     async fn total_mrr_chart(&self, request: MrrChartRequest) -> StoreResult<MrrChartResponse>;
-    async fn mrr_breakdown(&self, request: MRRBreakdownRequest) -> StoreResult<MRRBreakdown>;
+    async fn mrr_breakdown(
+        &self,
+        request: MRRBreakdownRequest,
+    ) -> StoreResult<MRRBreakdownResponse>;
     async fn mrr_log(&self, request: MrrLogRequest) -> StoreResult<MrrLogResponse>;
+    /// Rebuilds the MRR movement logs, the daily MRR deltas and the subscriptions MRR of the tenant from the subscription events
+    async fn rebuild_mrr_movement_logs(&self, tenant_id: Uuid) -> StoreResult<MrrRebuildSummary>;
 }
 
 #[async_trait::async_trait]
 impl StatsInterface for Store {
-    async fn net_revenue(&self, tenant_id: Uuid) -> StoreResult<Trend> {
-        let mut conn = self.get_conn().await?;
-
-        let trend = RevenueTrendRow::get(&mut conn, 7, tenant_id)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-        let (change, percent) = calculate_trend(trend.total_ytd, trend.total_previous_period);
-
-        Ok(Trend {
-            current: trend.total_ytd,
-            change_amount: change,
-            change_percent: percent,
-            positive_is_good: true,
-            scope: TrendScope::Trend7d,
-        })
-    }
-
-    async fn active_subscriptions(&self, tenant_id: Uuid) -> StoreResult<i64> {
-        let mut conn = self.get_conn().await?;
-
-        ActiveSubscriptionsCountRow::get(&mut conn, tenant_id, None)
-            .await
-            .map_err(Into::into)
-            .map(|x| x.count.into())
-    }
-
-    async fn pending_invoices(&self, tenant_id: Uuid) -> StoreResult<CountAndValue> {
-        let mut conn = self.get_conn().await?;
-
-        let trend = PendingInvoicesTotalRow::get(&mut conn, tenant_id)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-        let currency = self
-            .internal
-            .get_reporting_currency_by_tenant_id(&mut conn, tenant_id)
-            .await?;
-
-        Ok(CountAndValue {
-            count: trend.total,
-            value: trend.total_cents.to_subunit_opt(currency.precision).ok_or(
-                StoreError::InvalidArgument(
-                    "Failed to convert pending invoice total cents".to_string(),
-                ),
-            )?,
-        })
-    }
-
-    async fn signups(&self, tenant_id: Uuid) -> StoreResult<Trend> {
-        let mut conn = self.get_conn().await?;
-
-        let trend = NewSignupsTrend90DaysRow::get(&mut conn, tenant_id)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-        let (change, percent) =
-            calculate_trend(trend.total_last_90_days, trend.total_previous_90_days);
-
-        Ok(Trend {
-            current: trend.total_last_90_days,
-            change_amount: change,
-            change_percent: percent,
-            positive_is_good: true,
-            scope: TrendScope::Trend30d,
+    async fn general_stats(
+        &self,
+        tenant_id: Uuid,
+        fx_mode: FxRestatementMode,
+    ) -> StoreResult<GeneralStats> {
+        self.transaction(|conn| {
+            async move {
+                let snapshot = ReportSnapshotRow::begin(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                Ok(GeneralStats {
+                    net_revenue: net_revenue(conn, tenant_id, fx_mode).await?,
+                    active_subscriptions: active_subscriptions(conn, tenant_id).await?,
+                    pending_invoices: pending_invoices(&self.internal, conn, tenant_id).await?,
+                    signups: signups(conn, tenant_id).await?,
+                    trial_conversion_rate: trial_conversion_rate(conn, tenant_id).await?,
+                    total_mrr: total_mrr(conn, tenant_id, snapshot.as_of.date()).await?,
+                    as_of: snapshot.as_of,
+                })
+            }
+            .scope_boxed()
         })
+        .await
     }
 
     async fn signups_sparkline(&self, tenant_id: Uuid) -> StoreResult<SignupSparklineResponse> {
@@ -129,20 +100,6 @@ impl StatsInterface for Store {
         Ok(SignupSparklineResponse { series })
     }
 
-    async fn trial_conversion_rate(&self, tenant_id: Uuid) -> StoreResult<f32> {
-        let mut conn = self.get_conn().await?;
-
-        let all_time = SubscriptionTrialConversionRateRow::get(&mut conn, tenant_id)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-        Ok(all_time
-            .all_time_conversion_rate_percentage
-            .round_dp(1)
-            .to_f32()
-            .unwrap_or(0.0))
-    }
-
     async fn trial_conversion_rate_sparkline(
         &self,
         tenant_id: Uuid,
@@ -259,170 +216,196 @@ impl StatsInterface for Store {
             .collect())
     }
 
-    async fn total_mrr(&self, tenant_id: Uuid) -> StoreResult<i64> {
-        let mut conn = self.get_conn().await?;
-
-        TotalMrrRow::get(&mut conn, tenant_id, chrono::Utc::now().naive_utc().date())
-            .await
-            .map_err(Into::<Report<StoreError>>::into)
-            .map(|x| x.total_net_mrr_cents)
-    }
-
     async fn total_mrr_chart(&self, request: MrrChartRequest) -> StoreResult<MrrChartResponse> {
-        let mut conn = self.get_conn().await?;
+        self.transaction(|conn| {
+            async move {
+                let snapshot = ReportSnapshotRow::begin(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let total = TotalMrrChartRow::list(
+                    conn,
+                    request.tenant_id,
+                    request.start_date,
+                    request.end_date,
+                    request.fx_mode.constant_rate_date(),
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let total_mrr_series = MrrChartSeries {
+                    name: "Total MRR".to_string(),
+                    code: "total_mrr".to_string(),
+                    plan: None,
+                    data: total
+                        .iter()
+                        .map(|d| MrrChartDataPoint {
+                            x: d.period.to_string(),
+                            data: MRRBreakdown {
+                                new_business: CountAndValue {
+                                    count: d.new_business_count,
+                                    value: d.new_business_mrr,
+                                },
+                                expansion: CountAndValue {
+                                    count: d.expansion_count,
+                                    value: d.expansion_mrr,
+                                },
+                                contraction: CountAndValue {
+                                    count: d.contraction_count,
+                                    value: d.contraction_mrr,
+                                },
+                                churn: CountAndValue {
+                                    count: d.churn_count,
+                                    value: d.churn_mrr,
+                                },
+                                reactivation: CountAndValue {
+                                    count: d.reactivation_count,
+                                    value: d.reactivation_mrr,
+                                },
+                                net_new_mrr: d.net_new_mrr,
+                                total_net_mrr: d.total_net_mrr,
+                            },
+                        })
+                        .collect(),
+                };
 
-        let total = TotalMrrChartRow::list(
-            &mut conn,
-            request.tenant_id,
-            request.start_date,
-            request.end_date,
-        )
+                let mut series_map: HashMap<String, MrrChartSeries> = HashMap::new();
+                if request.plans_id.is_some() {
+                    let plans_data = TotalMrrByPlanRow::list(
+                        conn,
+                        request.tenant_id,
+                        &request.plans_id.unwrap(),
+                        request.start_date,
+                        request.end_date,
+                        request.fx_mode.constant_rate_date(),
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    for data in plans_data {
+                        let data_point = MrrChartDataPoint {
+                            x: data.date.format("%Y-%m-%d").to_string(),
+                            data: MRRBreakdown {
+                                new_business: CountAndValue {
+                                    count: data.new_business_count,
+                                    value: data.new_business_mrr,
+                                },
+                                expansion: CountAndValue {
+                                    count: data.expansion_count,
+                                    value: data.expansion_mrr,
+                                },
+                                contraction: CountAndValue {
+                                    count: data.contraction_count,
+                                    value: data.contraction_mrr,
+                                },
+                                churn: CountAndValue {
+                                    count: data.churn_count,
+                                    value: data.churn_mrr,
+                                },
+                                reactivation: CountAndValue {
+                                    count: data.reactivation_count,
+                                    value: data.reactivation_mrr,
+                                },
+                                net_new_mrr: data.net_new_mrr,
+                                total_net_mrr: data.total_net_mrr,
+                            },
+                        };
+
+                        series_map
+                            .entry(data.plan_name.clone())
+                            .or_insert_with(|| MrrChartSeries {
+                                name: data.plan_name.clone(),
+                                code: format!("mrr_breakdown_plan_{}", data.plan_id),
+                                plan: Some(PlanBrief {
+                                    id: data.plan_id,
+                                    name: data.plan_name.clone(),
+                                }),
+                                data: vec![],
+                            })
+                            .data
+                            .push(data_point);
+                    }
+                }
+                let mut series: Vec<MrrChartSeries> = series_map.into_values().collect();
+                series.push(total_mrr_series);
+
+                Ok(MrrChartResponse {
+                    series,
+                    as_of: snapshot.as_of,
+                })
+            }
+            .scope_boxed()
+        })
         .await
-        .map_err(Into::<Report<StoreError>>::into)?;
+    }
 
-        let total_mrr_series = MrrChartSeries {
-            name: "Total MRR".to_string(),
-            code: "total_mrr".to_string(),
-            plan: None,
-            data: total
-                .iter()
-                .map(|d| MrrChartDataPoint {
-                    x: d.period.to_string(),
-                    data: MRRBreakdown {
-                        new_business: CountAndValue {
-                            count: d.new_business_count,
-                            value: d.new_business_mrr,
-                        },
-                        expansion: CountAndValue {
-                            count: d.expansion_count,
-                            value: d.expansion_mrr,
-                        },
-                        contraction: CountAndValue {
-                            count: d.contraction_count,
-                            value: d.contraction_mrr,
-                        },
-                        churn: CountAndValue {
-                            count: d.churn_count,
-                            value: d.churn_mrr,
-                        },
-                        reactivation: CountAndValue {
-                            count: d.reactivation_count,
-                            value: d.reactivation_mrr,
-                        },
-                        net_new_mrr: d.net_new_mrr,
-                        total_net_mrr: d.total_net_mrr,
+    async fn mrr_breakdown(
+        &self,
+        request: MRRBreakdownRequest,
+    ) -> StoreResult<MRRBreakdownResponse> {
+        self.transaction(|conn| {
+            async move {
+                let snapshot = ReportSnapshotRow::begin(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let now = snapshot.as_of.date();
+                let (start_date, end_date) = request.scope.to_date_range(now);
+
+                let breakdown = MrrBreakdownRow::get(
+                    conn,
+                    request.tenant_id,
+                    start_date,
+                    end_date,
+                    request.fx_mode.constant_rate_date(),
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let breakdown = match breakdown {
+                    None => MRRBreakdown {
+                        new_business: CountAndValue { count: 0, value: 0 },
+                        expansion: CountAndValue { count: 0, value: 0 },
+                        contraction: CountAndValue { count: 0, value: 0 },
+                        churn: CountAndValue { count: 0, value: 0 },
+                        reactivation: CountAndValue { count: 0, value: 0 },
+                        net_new_mrr: 0,
+                        total_net_mrr: 0,
                     },
-                })
-                .collect(),
-        };
-
-        let mut series_map: HashMap<String, MrrChartSeries> = HashMap::new();
-        if request.plans_id.is_some() {
-            let plans_data = TotalMrrByPlanRow::list(
-                &mut conn,
-                request.tenant_id,
-                &request.plans_id.unwrap(),
-                request.start_date,
-                request.end_date,
-            )
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-            for data in plans_data {
-                let data_point = MrrChartDataPoint {
-                    x: data.date.format("%Y-%m-%d").to_string(),
-                    data: MRRBreakdown {
+                    Some(breakdown) => MRRBreakdown {
                         new_business: CountAndValue {
-                            count: data.new_business_count,
-                            value: data.new_business_mrr,
+                            count: breakdown.new_business_count,
+                            value: breakdown.new_business_mrr,
                         },
                         expansion: CountAndValue {
-                            count: data.expansion_count,
-                            value: data.expansion_mrr,
+                            count: breakdown.expansion_count,
+                            value: breakdown.expansion_mrr,
                         },
                         contraction: CountAndValue {
-                            count: data.contraction_count,
-                            value: data.contraction_mrr,
+                            count: breakdown.contraction_count,
+                            value: breakdown.contraction_mrr,
                         },
                         churn: CountAndValue {
-                            count: data.churn_count,
-                            value: data.churn_mrr,
+                            count: breakdown.churn_count,
+                            value: breakdown.churn_mrr,
                         },
                         reactivation: CountAndValue {
-                            count: data.reactivation_count,
-                            value: data.reactivation_mrr,
+                            count: breakdown.reactivation_count,
+                            value: breakdown.reactivation_mrr,
                         },
-                        net_new_mrr: data.net_new_mrr,
-                        total_net_mrr: data.total_net_mrr,
+                        net_new_mrr: breakdown.net_new_mrr,
+                        total_net_mrr: 0,
                     },
                 };
 
-                series_map
-                    .entry(data.plan_name.clone())
-                    .or_insert_with(|| MrrChartSeries {
-                        name: data.plan_name.clone(),
-                        code: format!("mrr_breakdown_plan_{}", data.plan_id),
-                        plan: Some(PlanBrief {
-                            id: data.plan_id,
-                            name: data.plan_name.clone(),
-                        }),
-                        data: vec![],
-                    })
-                    .data
-                    .push(data_point);
+                Ok(MRRBreakdownResponse {
+                    breakdown,
+                    as_of: snapshot.as_of,
+                })
             }
-        }
-        let mut series: Vec<MrrChartSeries> = series_map.into_values().collect();
-        series.push(total_mrr_series);
-
-        Ok(MrrChartResponse { series })
-    }
-
-    async fn mrr_breakdown(&self, request: MRRBreakdownRequest) -> StoreResult<MRRBreakdown> {
-        let mut conn = self.get_conn().await?;
-
-        let now = chrono::Utc::now().naive_utc().date();
-        let (start_date, end_date) = request.scope.to_date_range(now);
-
-        let breakdown = MrrBreakdownRow::get(&mut conn, request.tenant_id, start_date, end_date)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
-
-        match breakdown {
-            None => Ok(MRRBreakdown {
-                new_business: CountAndValue { count: 0, value: 0 },
-                expansion: CountAndValue { count: 0, value: 0 },
-                contraction: CountAndValue { count: 0, value: 0 },
-                churn: CountAndValue { count: 0, value: 0 },
-                reactivation: CountAndValue { count: 0, value: 0 },
-                net_new_mrr: 0,
-                total_net_mrr: 0,
-            }),
-            Some(breakdown) => Ok(MRRBreakdown {
-                new_business: CountAndValue {
-                    count: breakdown.new_business_count,
-                    value: breakdown.new_business_mrr,
-                },
-                expansion: CountAndValue {
-                    count: breakdown.expansion_count,
-                    value: breakdown.expansion_mrr,
-                },
-                contraction: CountAndValue {
-                    count: breakdown.contraction_count,
-                    value: breakdown.contraction_mrr,
-                },
-                churn: CountAndValue {
-                    count: breakdown.churn_count,
-                    value: breakdown.churn_mrr,
-                },
-                reactivation: CountAndValue {
-                    count: breakdown.reactivation_count,
-                    value: breakdown.reactivation_mrr,
-                },
-                net_new_mrr: breakdown.net_new_mrr,
-                total_net_mrr: 0,
-            }),
-        }
+            .scope_boxed()
+        })
+        .await
     }
 
     async fn mrr_log(&self, request: MrrLogRequest) -> StoreResult<MrrLogResponse> {
@@ -469,6 +452,155 @@ impl StatsInterface for Store {
                 .collect(),
         })
     }
+
+    async fn rebuild_mrr_movement_logs(&self, tenant_id: Uuid) -> StoreResult<MrrRebuildSummary> {
+        self.transaction(|conn| {
+            async move {
+                SubscriptionEventRow::unlink_mrr_movement_logs_by_tenant_id(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                BiMrrMovementLogRow::delete_by_tenant_id(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                SubscriptionRow::reset_mrr_by_tenant_id(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let invoices = InvoiceRow::list_mrr_invoices_by_tenant_id(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let mut summary = MrrRebuildSummary {
+                    invoices_processed: invoices.len(),
+                    movements_recorded: 0,
+                };
+
+                for invoice in invoices {
+                    let (subscription_id, plan_version_id) =
+                        match (invoice.subscription_id, invoice.plan_version_id) {
+                            (Some(s), Some(p)) => (s, p),
+                            _ => continue,
+                        };
+
+                    let mrr_delta_cents = record_mrr_movement(
+                        conn,
+                        MrrMovementSource {
+                            tenant_id,
+                            subscription_id,
+                            invoice_id: invoice.id,
+                            invoice_date: invoice.invoice_date,
+                            currency: invoice.currency,
+                            plan_version_id,
+                        },
+                    )
+                    .await?;
+
+                    if mrr_delta_cents != 0 {
+                        summary.movements_recorded += 1;
+
+                        SubscriptionRow::update_subscription_mrr_delta(
+                            conn,
+                            subscription_id,
+                            mrr_delta_cents,
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+                }
+
+                Ok(summary)
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+async fn net_revenue(
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+    fx_mode: FxRestatementMode,
+) -> StoreResult<Trend> {
+    let trend = RevenueTrendRow::get(conn, 7, tenant_id, fx_mode.constant_rate_date())
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let (change, percent) = calculate_trend(trend.total_ytd, trend.total_previous_period);
+
+    Ok(Trend {
+        current: trend.total_ytd,
+        change_amount: change,
+        change_percent: percent,
+        positive_is_good: true,
+        scope: TrendScope::Trend7d,
+    })
+}
+
+async fn active_subscriptions(conn: &mut PgConn, tenant_id: Uuid) -> StoreResult<i64> {
+    ActiveSubscriptionsCountRow::get(conn, tenant_id, None)
+        .await
+        .map_err(Into::into)
+        .map(|x| x.count.into())
+}
+
+async fn pending_invoices(
+    internal: &StoreInternal,
+    conn: &mut PgConn,
+    tenant_id: Uuid,
+) -> StoreResult<CountAndValue> {
+    let trend = PendingInvoicesTotalRow::get(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let currency = internal
+        .get_reporting_currency_by_tenant_id(conn, tenant_id)
+        .await?;
+
+    Ok(CountAndValue {
+        count: trend.total,
+        value: trend.total_cents.to_subunit_opt(currency.precision).ok_or(
+            StoreError::InvalidArgument(
+                "Failed to convert pending invoice total cents".to_string(),
+            ),
+        )?,
+    })
+}
+
+async fn signups(conn: &mut PgConn, tenant_id: Uuid) -> StoreResult<Trend> {
+    let trend = NewSignupsTrend90DaysRow::get(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let (change, percent) = calculate_trend(trend.total_last_90_days, trend.total_previous_90_days);
+
+    Ok(Trend {
+        current: trend.total_last_90_days,
+        change_amount: change,
+        change_percent: percent,
+        positive_is_good: true,
+        scope: TrendScope::Trend30d,
+    })
+}
+
+async fn trial_conversion_rate(conn: &mut PgConn, tenant_id: Uuid) -> StoreResult<f32> {
+    let all_time = SubscriptionTrialConversionRateRow::get(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(all_time
+        .all_time_conversion_rate_percentage
+        .round_dp(1)
+        .to_f32()
+        .unwrap_or(0.0))
+}
+
+async fn total_mrr(conn: &mut PgConn, tenant_id: Uuid, date: NaiveDate) -> StoreResult<i64> {
+    TotalMrrRow::get(conn, tenant_id, date)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|x| x.total_net_mrr_cents)
 }
 
 fn map_movement_type(m: diesel_models::enums::MrrMovementType) -> MrrMovementType {
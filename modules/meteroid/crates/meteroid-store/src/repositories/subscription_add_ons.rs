@@ -0,0 +1,474 @@
+use chrono::NaiveDate;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use rust_decimal::prelude::*;
+use uuid::Uuid;
+
+use crate::compute::proration;
+use crate::constants::Currencies;
+use crate::domain::add_ons::AddOn;
+use crate::domain::component_rules::{validate_component_rules, ComponentRuleItem};
+use crate::domain::enums::{
+    BillingPeriodEnum, InvoiceType, SubscriptionEventType, SubscriptionFeeBillingPeriod,
+};
+use crate::domain::subscription_add_ons::{SubscriptionAddOn, SubscriptionAddOnUpdate};
+use crate::domain::{
+    CreateSubscriptionAddOn, CreateSubscriptionAddOns, LineItem, Period, SubscriptionAddOnNew,
+    SubscriptionDetails, SubscriptionFee,
+};
+use crate::errors::StoreError;
+use crate::repositories::component_rules::load_component_rules;
+use crate::repositories::customer_balance::CustomerBalance;
+use crate::repositories::invoices::insert_invoice;
+use crate::repositories::invoicing_entities::InvoicingEntityInterface;
+use crate::repositories::subscriptions::{
+    calculate_mrr, off_cycle_invoice, process_create_subscription_add_ons,
+};
+use crate::repositories::{CustomersInterface, SubscriptionInterface, TenantInterface};
+use crate::store::Store;
+use crate::utils::decimals::ToSubunit;
+use crate::utils::local_id::LocalId;
+use crate::StoreResult;
+use diesel_models::add_ons::AddOnRow;
+use diesel_models::subscription_add_ons::{SubscriptionAddOnRow, SubscriptionAddOnRowNew};
+use diesel_models::subscription_events::SubscriptionEventRow;
+use diesel_models::subscriptions::SubscriptionRow;
+
+#[async_trait::async_trait]
+pub trait SubscriptionAddOnInterface {
+    /// Adds a catalog add-on to a live subscription. Its fee for the rest of the current period is invoiced right away
+    async fn attach_subscription_add_on(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        add_on: CreateSubscriptionAddOn,
+        actor: Uuid,
+    ) -> StoreResult<SubscriptionAddOnUpdate>;
+
+    /// Removes an add-on from a subscription. Its fee for the unused rest of the current period is credited to the
+    /// customer balance
+    async fn detach_subscription_add_on(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        subscription_add_on_id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<SubscriptionAddOnUpdate>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionAddOnInterface for Store {
+    async fn attach_subscription_add_on(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        add_on: CreateSubscriptionAddOn,
+        actor: Uuid,
+    ) -> StoreResult<SubscriptionAddOnUpdate> {
+        let now = chrono::Utc::now().naive_utc();
+
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        if subscription.canceled_at.is_some() {
+            return Err(StoreError::InvalidArgument(
+                "Cannot attach an add-on to a canceled subscription".to_string(),
+            )
+            .into());
+        }
+
+        if subscription
+            .add_ons
+            .iter()
+            .any(|a| a.add_on_id == add_on.add_on_id)
+        {
+            return Err(StoreError::InvalidArgument(
+                "This add-on is already attached to the subscription".to_string(),
+            )
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let catalog_add_on: AddOn = AddOnRow::get_by_id(&mut conn, tenant_id, add_on.add_on_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .try_into()?;
+
+        let internal = process_create_subscription_add_ons(
+            &Some(CreateSubscriptionAddOns {
+                add_ons: vec![add_on],
+            }),
+            &[catalog_add_on],
+        )?
+        .pop()
+        .ok_or(StoreError::InvalidArgument(
+            "Add-on could not be resolved".to_string(),
+        ))?;
+
+        let rule_items = subscription
+            .price_components
+            .iter()
+            .filter_map(|c| c.price_component_id.map(ComponentRuleItem::PriceComponent))
+            .chain(
+                subscription
+                    .add_ons
+                    .iter()
+                    .map(|a| ComponentRuleItem::AddOn(a.add_on_id)),
+            )
+            .chain(std::iter::once(ComponentRuleItem::AddOn(
+                internal.add_on_id,
+            )))
+            .collect::<Vec<_>>();
+
+        let rules = load_component_rules(&mut conn, tenant_id).await?;
+
+        validate_component_rules(&rules, &rule_items)?;
+
+        drop(conn);
+
+        let precision = Currencies::resolve_currency_precision(&subscription.currency).ok_or(
+            StoreError::ValueNotFound(format!("Currency {} not found", subscription.currency)),
+        )?;
+
+        let period = add_on_period(&subscription, &internal.period, now.date());
+        let mrr_delta = calculate_mrr(&internal.fee, &internal.period, precision);
+        let full_amount = fee_period_amount(&internal.fee, precision)?;
+
+        let proration_rounding = self.get_proration_rounding_by_tenant_id(tenant_id).await?;
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
+
+        let customer = self
+            .find_customer_by_id(subscription.customer_id, tenant_id)
+            .await?;
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(customer.invoicing_entity_id))
+            .await?;
+
+        let new_period = billing_period_with(&subscription, Some(&internal.period), None);
+
+        let row: SubscriptionAddOnRowNew = SubscriptionAddOnNew {
+            subscription_id,
+            internal: internal.clone(),
+        }
+        .try_into()?;
+
+        self.transaction(|conn| {
+            async move {
+                let inserted: SubscriptionAddOn =
+                    SubscriptionAddOnRow::insert_batch(conn, vec![&row])
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?
+                        .pop()
+                        .ok_or(StoreError::InsertError)?
+                        .try_into()?;
+
+                if new_period != subscription.period {
+                    SubscriptionRow::update_period(
+                        conn,
+                        subscription_id,
+                        tenant_id,
+                        new_period.clone().into(),
+                        new_period.custom_months(),
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                }
+
+                SubscriptionEventRow {
+                    id: Uuid::now_v7(),
+                    subscription_id,
+                    event_type: SubscriptionEventType::Updated.into(),
+                    details: Some(serde_json::json!({
+                        "add_on_id": inserted.add_on_id,
+                        "subscription_add_on_id": inserted.id,
+                        "attached_by": actor,
+                    })),
+                    created_at: now,
+                    mrr_delta: Some(mrr_delta),
+                    bi_mrr_movement_log_id: None,
+                    applies_to: now.date(),
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let mut prorated_amount = 0;
+                let mut invoice_id = None;
+
+                // charged from the first invoice otherwise
+                if subscription.activated_at.is_some() {
+                    let remaining_period = Period {
+                        start: now.date(),
+                        end: period.end,
+                    };
+
+                    prorated_amount = match internal.fee {
+                        SubscriptionFee::OneTime { .. } => full_amount,
+                        _ => proration::prorate(
+                            full_amount,
+                            &period,
+                            &remaining_period,
+                            proration_rounding,
+                        )?,
+                    };
+
+                    if prorated_amount > 0 {
+                        let line = LineItem {
+                            local_id: LocalId::no_prefix(),
+                            name: inserted.name.clone(),
+                            total: prorated_amount,
+                            subtotal: prorated_amount,
+                            quantity: Some(Decimal::ONE),
+                            unit_price: None,
+                            start_date: remaining_period.start,
+                            end_date: remaining_period.end,
+                            sub_lines: vec![],
+                            is_prorated: prorated_amount != full_amount,
+                            price_component_id: None,
+                            product_id: None,
+                            metric_id: None,
+                            description: Some(format!("Add-on {} attached", inserted.name)),
+                            quantity_display_precision: None,
+                        };
+
+                        let invoice = off_cycle_invoice(
+                            &subscription,
+                            &customer,
+                            &invoicing_entity,
+                            &tenant_payment_terms,
+                            InvoiceType::OneOff,
+                            now.date(),
+                            vec![line],
+                        )?;
+
+                        invoice_id = Some(insert_invoice(conn, invoice).await?.id);
+                    }
+                }
+
+                Ok(SubscriptionAddOnUpdate {
+                    add_on: inserted,
+                    period,
+                    prorated_amount,
+                    invoice_id,
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+
+    async fn detach_subscription_add_on(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        subscription_add_on_id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<SubscriptionAddOnUpdate> {
+        let now = chrono::Utc::now().naive_utc();
+
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        if subscription.canceled_at.is_some() {
+            return Err(StoreError::InvalidArgument(
+                "Cannot detach an add-on from a canceled subscription".to_string(),
+            )
+            .into());
+        }
+
+        let add_on = subscription
+            .add_ons
+            .iter()
+            .find(|a| a.id == subscription_add_on_id)
+            .cloned()
+            .ok_or(StoreError::ValueNotFound(format!(
+                "add-on {} not attached to the subscription",
+                subscription_add_on_id
+            )))?;
+
+        // the remaining items must still satisfy the catalog rules
+        let rule_items = subscription
+            .price_components
+            .iter()
+            .filter_map(|c| c.price_component_id.map(ComponentRuleItem::PriceComponent))
+            .chain(
+                subscription
+                    .add_ons
+                    .iter()
+                    .filter(|a| a.id != subscription_add_on_id)
+                    .map(|a| ComponentRuleItem::AddOn(a.add_on_id)),
+            )
+            .collect::<Vec<_>>();
+
+        let mut conn = self.get_conn().await?;
+        let rules = load_component_rules(&mut conn, tenant_id).await?;
+        drop(conn);
+
+        validate_component_rules(&rules, &rule_items)?;
+
+        let precision = Currencies::resolve_currency_precision(&subscription.currency).ok_or(
+            StoreError::ValueNotFound(format!("Currency {} not found", subscription.currency)),
+        )?;
+
+        let period = add_on_period(&subscription, &add_on.period, now.date());
+        let mrr_delta = -calculate_mrr(&add_on.fee, &add_on.period, precision);
+        let full_amount = fee_period_amount(&add_on.fee, precision)?;
+
+        let proration_rounding = self.get_proration_rounding_by_tenant_id(tenant_id).await?;
+
+        let new_period = billing_period_with(&subscription, None, Some(subscription_add_on_id));
+
+        self.transaction(|conn| {
+            async move {
+                SubscriptionAddOnRow::delete(conn, subscription_id, subscription_add_on_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .ok_or(StoreError::ValueNotFound(format!(
+                        "add-on {} not attached to the subscription",
+                        subscription_add_on_id
+                    )))?;
+
+                if new_period != subscription.period {
+                    SubscriptionRow::update_period(
+                        conn,
+                        subscription_id,
+                        tenant_id,
+                        new_period.clone().into(),
+                        new_period.custom_months(),
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                }
+
+                SubscriptionEventRow {
+                    id: Uuid::now_v7(),
+                    subscription_id,
+                    event_type: SubscriptionEventType::Updated.into(),
+                    details: Some(serde_json::json!({
+                        "add_on_id": add_on.add_on_id,
+                        "subscription_add_on_id": add_on.id,
+                        "detached_by": actor,
+                    })),
+                    created_at: now,
+                    mrr_delta: Some(mrr_delta),
+                    bi_mrr_movement_log_id: None,
+                    applies_to: now.date(),
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let mut prorated_amount = 0;
+
+                // one-time fees are not refunded
+                if subscription.activated_at.is_some()
+                    && !matches!(add_on.fee, SubscriptionFee::OneTime { .. })
+                {
+                    let remaining_period = Period {
+                        start: now.date(),
+                        end: period.end,
+                    };
+
+                    let credit = proration::prorate(
+                        full_amount,
+                        &period,
+                        &remaining_period,
+                        proration_rounding,
+                    )?;
+
+                    if credit > 0 {
+                        let cents = i32::try_from(credit).map_err(|_| {
+                            StoreError::InvalidArgument("prorated credit is too large".to_string())
+                        })?;
+
+                        CustomerBalance::update_with_note(
+                            conn,
+                            subscription.customer_id,
+                            tenant_id,
+                            cents,
+                            None,
+                            Some(format!("Add-on {} detached", add_on.name)),
+                        )
+                        .await?;
+
+                        prorated_amount = -credit;
+                    }
+                }
+
+                Ok(SubscriptionAddOnUpdate {
+                    add_on,
+                    period,
+                    prorated_amount,
+                    invoice_id: None,
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+/// The current period of an add-on billed on `fee_period`, or of the subscription for one-time fees
+fn add_on_period(
+    subscription: &SubscriptionDetails,
+    fee_period: &SubscriptionFeeBillingPeriod,
+    date: NaiveDate,
+) -> Period {
+    let billing_period = fee_period
+        .as_billing_period_opt()
+        .unwrap_or(subscription.period.clone());
+
+    crate::utils::periods::calculate_periods_for_date(
+        subscription.billing_start_date,
+        subscription.anchor_day(),
+        date,
+        &billing_period,
+    )
+    .advance
+}
+
+/// Shortest billing period of the subscription once an add-on is attached or detached
+fn billing_period_with(
+    subscription: &SubscriptionDetails,
+    attached: Option<&SubscriptionFeeBillingPeriod>,
+    detached: Option<Uuid>,
+) -> BillingPeriodEnum {
+    subscription
+        .price_components
+        .iter()
+        .map(|c| &c.period)
+        .chain(
+            subscription
+                .add_ons
+                .iter()
+                .filter(|a| Some(a.id) != detached)
+                .map(|a| &a.period),
+        )
+        .chain(attached)
+        .filter_map(|p| p.as_billing_period_opt())
+        .min()
+        .unwrap_or(BillingPeriodEnum::Monthly)
+}
+
+/// Fixed amount billed for a full period of the fee, in cents. Usage is billed in arrears and is not part of it
+fn fee_period_amount(fee: &SubscriptionFee, precision: u8) -> StoreResult<i64> {
+    let amount = match fee {
+        SubscriptionFee::Rate { rate } => *rate,
+        SubscriptionFee::Capacity { rate, .. } => *rate,
+        SubscriptionFee::OneTime { rate, quantity } => rate * Decimal::from(*quantity),
+        SubscriptionFee::Recurring { rate, quantity, .. } => rate * Decimal::from(*quantity),
+        SubscriptionFee::Slot {
+            unit_rate,
+            initial_slots,
+            ..
+        } => unit_rate * Decimal::from(*initial_slots),
+        SubscriptionFee::Usage { .. } | SubscriptionFee::Percent { .. } => Decimal::ZERO,
+    };
+
+    amount
+        .to_subunit_opt(precision)
+        .ok_or(StoreError::InvalidArgument("Invalid add-on fee".to_string()).into())
+}
@@ -0,0 +1,64 @@
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::subscription_component_history::{
+    SubscriptionComponentHistory, SubscriptionComponentHistoryNew,
+};
+use crate::errors::StoreError;
+use crate::store::{PgConn, Store};
+use crate::StoreResult;
+use diesel_models::subscription_component_history::{
+    SubscriptionComponentHistoryRow, SubscriptionComponentHistoryRowNew,
+};
+
+#[async_trait::async_trait]
+pub trait SubscriptionComponentHistoryInterface {
+    /// Parameter changes of the subscription components, latest effective first
+    async fn list_subscription_component_history(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> StoreResult<Vec<SubscriptionComponentHistory>>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionComponentHistoryInterface for Store {
+    async fn list_subscription_component_history(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> StoreResult<Vec<SubscriptionComponentHistory>> {
+        let mut conn = self.get_conn().await?;
+
+        SubscriptionComponentHistoryRow::list_by_subscription_id(
+            &mut conn,
+            tenant_id,
+            subscription_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<_>, _>>()
+    }
+}
+
+pub(crate) async fn insert_component_history(
+    conn: &mut PgConn,
+    history: Vec<SubscriptionComponentHistoryNew>,
+) -> StoreResult<()> {
+    if history.is_empty() {
+        return Ok(());
+    }
+
+    let rows = history
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<SubscriptionComponentHistoryRowNew>, _>>()?;
+
+    SubscriptionComponentHistoryRowNew::insert_batch(conn, &rows)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(())
+}
@@ -0,0 +1,559 @@
+use std::collections::HashMap;
+
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use crate::compute::proration;
+use crate::constants::Currencies;
+use crate::domain::enums::{
+    InvoiceType, SubscriptionEventType, SubscriptionMigrationItemStatusEnum,
+    SubscriptionMigrationStrategyEnum,
+};
+use crate::domain::subscription_migrations::{
+    carry_over_components, SubscriptionMigration, SubscriptionMigrationItem,
+    SubscriptionMigrationNew,
+};
+use crate::domain::subscription_pending_changes::SubscriptionChange;
+use crate::domain::{
+    LineItem, PaginatedVec, PaginationRequest, Period, PriceComponent, SubscriptionDetails,
+    SubscriptionFee, TenantContext,
+};
+use crate::errors::StoreError;
+use crate::repositories::customer_balance::CustomerBalance;
+use crate::repositories::entitlements::invalidate_customer_entitlements_cache;
+use crate::repositories::invoices::insert_invoice;
+use crate::repositories::invoicing_entities::InvoicingEntityInterface;
+use crate::repositories::subscription_pending_changes::{
+    resolve_changed_components, switch_subscription_components, SubscriptionPendingChangeInterface,
+};
+use crate::repositories::subscriptions::{active_slots_at, calculate_mrr, off_cycle_invoice};
+use crate::repositories::{CustomersInterface, SubscriptionInterface, TenantInterface};
+use crate::store::{PgConn, Store};
+use crate::utils::local_id::LocalId;
+use crate::StoreResult;
+use diesel_models::plan_versions::PlanVersionRow;
+use diesel_models::price_components::PriceComponentRow;
+use diesel_models::subscription_events::SubscriptionEventRow;
+use diesel_models::subscription_migrations::{
+    SubscriptionMigrationItemRow, SubscriptionMigrationItemRowNew, SubscriptionMigrationRow,
+    SubscriptionMigrationRowNew,
+};
+
+/// Subscriptions processed between two progress updates of a migration
+const MIGRATION_BATCH_SIZE: usize = 50;
+
+#[async_trait::async_trait]
+pub trait SubscriptionMigrationsInterface {
+    /// Moves the live subscriptions of the plan of the target version to it, in batches.
+    /// A subscription that cannot be migrated is recorded as failed and does not stop the migration
+    async fn migrate_subscriptions(
+        &self,
+        migration: SubscriptionMigrationNew,
+    ) -> StoreResult<SubscriptionMigration>;
+
+    async fn get_subscription_migration(
+        &self,
+        tenant_id: Uuid,
+        migration_id: Uuid,
+    ) -> StoreResult<SubscriptionMigration>;
+
+    async fn list_subscription_migration_items(
+        &self,
+        tenant_id: Uuid,
+        migration_id: Uuid,
+        status: Option<SubscriptionMigrationItemStatusEnum>,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<SubscriptionMigrationItem>>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionMigrationsInterface for Store {
+    async fn migrate_subscriptions(
+        &self,
+        migration: SubscriptionMigrationNew,
+    ) -> StoreResult<SubscriptionMigration> {
+        let mut conn = self.get_conn().await?;
+
+        let plan_version = PlanVersionRow::find_by_id_and_tenant_id(
+            &mut conn,
+            migration.to_plan_version_id,
+            migration.tenant_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        if plan_version.is_draft_version {
+            return Err(StoreError::InvalidArgument(
+                "cannot migrate subscriptions to a draft plan version".to_string(),
+            )
+            .into());
+        }
+
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let subscription_ids = SubscriptionMigrationRow::list_candidate_subscription_ids(
+            &mut conn,
+            migration.tenant_id,
+            plan_version.plan_id,
+            plan_version.id,
+            migration.from_plan_version_id,
+            migration.include_grandfathered,
+            today,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        let migration_row = SubscriptionMigrationRowNew {
+            id: Uuid::now_v7(),
+            tenant_id: migration.tenant_id,
+            plan_id: plan_version.plan_id,
+            to_plan_version_id: plan_version.id,
+            strategy: migration.strategy.into(),
+            total_count: subscription_ids.len() as i32,
+            created_by: migration.created_by,
+        }
+        .insert(&mut conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        let price_components: Vec<PriceComponent> = PriceComponentRow::list_by_plan_version_id(
+            &mut conn,
+            migration.tenant_id,
+            plan_version.id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(TryInto::try_into)
+        .collect::<Result<Vec<_>, _>>()?;
+
+        drop(conn);
+
+        for batch in subscription_ids.chunks(MIGRATION_BATCH_SIZE) {
+            let mut items = Vec::with_capacity(batch.len());
+
+            for subscription_id in batch {
+                let item = self
+                    .migrate_subscription(
+                        &migration,
+                        migration_row.id,
+                        *subscription_id,
+                        &price_components,
+                    )
+                    .await;
+
+                items.push(item);
+            }
+
+            let failed = items.iter().filter(|i| i.error.is_some()).count() as i32;
+            let mrr_delta = items.iter().filter_map(|i| i.mrr_delta).sum::<i64>();
+
+            let mut conn = self.get_conn().await?;
+
+            SubscriptionMigrationItemRowNew::insert_batch(&mut conn, &items)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+            SubscriptionMigrationRow::record_progress(
+                &mut conn,
+                migration_row.id,
+                items.len() as i32,
+                failed,
+                mrr_delta,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        SubscriptionMigrationRow::mark_as_completed(
+            &mut conn,
+            migration_row.id,
+            chrono::Utc::now().naive_utc(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(Into::into)
+    }
+
+    async fn get_subscription_migration(
+        &self,
+        tenant_id: Uuid,
+        migration_id: Uuid,
+    ) -> StoreResult<SubscriptionMigration> {
+        let mut conn = self.get_conn().await?;
+
+        SubscriptionMigrationRow::find_by_id(&mut conn, tenant_id, migration_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_subscription_migration_items(
+        &self,
+        tenant_id: Uuid,
+        migration_id: Uuid,
+        status: Option<SubscriptionMigrationItemStatusEnum>,
+        pagination: PaginationRequest,
+    ) -> StoreResult<PaginatedVec<SubscriptionMigrationItem>> {
+        let mut conn = self.get_conn().await?;
+
+        // checks that the migration belongs to the tenant
+        SubscriptionMigrationRow::find_by_id(&mut conn, tenant_id, migration_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let rows = SubscriptionMigrationItemRow::list_by_migration_id(
+            &mut conn,
+            migration_id,
+            status.map(Into::into),
+            pagination.into(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(PaginatedVec {
+            items: rows.items.into_iter().map(Into::into).collect(),
+            total_pages: rows.total_pages,
+            total_results: rows.total_results,
+        })
+    }
+}
+
+/// Outcome of the migration of a single subscription
+struct MigrationOutcome {
+    status: SubscriptionMigrationItemStatusEnum,
+    mrr_delta: Option<i64>,
+    prorated_amount: Option<i64>,
+}
+
+impl Store {
+    async fn migrate_subscription(
+        &self,
+        migration: &SubscriptionMigrationNew,
+        migration_id: Uuid,
+        subscription_id: Uuid,
+        price_components: &[PriceComponent],
+    ) -> SubscriptionMigrationItemRowNew {
+        let subscription = self
+            .get_subscription_details(migration.tenant_id, subscription_id)
+            .await;
+
+        let from_plan_version_id = subscription
+            .as_ref()
+            .map(|s| s.plan_version_id)
+            .unwrap_or(Uuid::nil());
+        let customer_id = subscription.as_ref().map(|s| s.customer_id).ok();
+
+        let outcome = match subscription {
+            Ok(subscription) => {
+                self.apply_migration_strategy(
+                    migration,
+                    migration_id,
+                    &subscription,
+                    price_components,
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        if let (Some(customer_id), Ok(outcome)) = (customer_id, &outcome) {
+            if outcome.status == SubscriptionMigrationItemStatusEnum::Migrated {
+                invalidate_customer_entitlements_cache(migration.tenant_id, customer_id).await;
+            }
+        }
+
+        let (outcome, error) = match outcome {
+            Ok(outcome) => (outcome, None),
+            Err(e) => {
+                log::warn!(
+                    "Failed to migrate subscription {} in migration {}: {:?}",
+                    subscription_id,
+                    migration_id,
+                    e
+                );
+
+                (
+                    MigrationOutcome {
+                        status: SubscriptionMigrationItemStatusEnum::Failed,
+                        mrr_delta: None,
+                        prorated_amount: None,
+                    },
+                    Some(e.current_context().to_string()),
+                )
+            }
+        };
+
+        SubscriptionMigrationItemRowNew {
+            id: Uuid::now_v7(),
+            migration_id,
+            subscription_id,
+            from_plan_version_id,
+            status: outcome.status.into(),
+            mrr_delta: outcome.mrr_delta,
+            prorated_amount: outcome.prorated_amount,
+            error,
+        }
+    }
+
+    async fn apply_migration_strategy(
+        &self,
+        migration: &SubscriptionMigrationNew,
+        migration_id: Uuid,
+        subscription: &SubscriptionDetails,
+        price_components: &[PriceComponent],
+    ) -> StoreResult<MigrationOutcome> {
+        match migration.strategy {
+            SubscriptionMigrationStrategyEnum::Grandfather => Ok(MigrationOutcome {
+                status: SubscriptionMigrationItemStatusEnum::Grandfathered,
+                mrr_delta: None,
+                prorated_amount: None,
+            }),
+            SubscriptionMigrationStrategyEnum::AtNextRenewal => {
+                let mut conn = self.get_conn().await?;
+
+                let change =
+                    migration_change(&mut conn, migration, subscription, price_components).await?;
+
+                // projected, the event is recorded when the switch is applied
+                let (_, components) =
+                    resolve_changed_components(&mut conn, subscription, &change).await?;
+
+                let precision = Currencies::resolve_currency_precision(&subscription.currency)
+                    .ok_or(StoreError::ValueNotFound(format!(
+                        "Currency {} not found",
+                        subscription.currency
+                    )))?;
+
+                let previous_mrr = subscription
+                    .price_components
+                    .iter()
+                    .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+                    .sum::<i64>();
+
+                let mrr = components
+                    .iter()
+                    .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+                    .sum::<i64>();
+
+                drop(conn);
+
+                self.schedule_subscription_change(
+                    subscription.id,
+                    change,
+                    TenantContext {
+                        actor: migration.created_by,
+                        tenant_id: migration.tenant_id,
+                    },
+                )
+                .await?;
+
+                Ok(MigrationOutcome {
+                    status: SubscriptionMigrationItemStatusEnum::Scheduled,
+                    mrr_delta: Some(mrr - previous_mrr),
+                    prorated_amount: None,
+                })
+            }
+            SubscriptionMigrationStrategyEnum::ImmediateWithProration => {
+                let change = {
+                    let mut conn = self.get_conn().await?;
+                    migration_change(&mut conn, migration, subscription, price_components).await?
+                };
+
+                self.migrate_subscription_immediately(migration, migration_id, subscription, change)
+                    .await
+            }
+        }
+    }
+
+    /// Switches the subscription now. The price difference for the rest of the current period is invoiced
+    /// when the subscription gets more expensive, and credited to the customer balance otherwise
+    async fn migrate_subscription_immediately(
+        &self,
+        migration: &SubscriptionMigrationNew,
+        migration_id: Uuid,
+        subscription: &SubscriptionDetails,
+        change: SubscriptionChange,
+    ) -> StoreResult<MigrationOutcome> {
+        let tenant_id = migration.tenant_id;
+        let now = chrono::Utc::now().naive_utc();
+        let today = now.date();
+
+        let proration_rounding = self.get_proration_rounding_by_tenant_id(tenant_id).await?;
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
+
+        let customer = self
+            .find_customer_by_id(subscription.customer_id, tenant_id)
+            .await?;
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(customer.invoicing_entity_id))
+            .await?;
+
+        // not prorated before the subscription is billed
+        let period = (subscription.activated_at.is_some()
+            && subscription.billing_start_date <= today)
+            .then(|| {
+                crate::utils::periods::calculate_periods_for_date(
+                    subscription.billing_start_date,
+                    subscription.anchor_day(),
+                    today,
+                    &subscription.period,
+                )
+                .advance
+            });
+
+        self.transaction(|conn| {
+            async move {
+                let (plan_version_id, components) =
+                    resolve_changed_components(conn, subscription, &change).await?;
+
+                let mrr_delta = switch_subscription_components(
+                    conn,
+                    subscription,
+                    plan_version_id,
+                    components,
+                    now,
+                    migration.created_by,
+                )
+                .await?;
+
+                SubscriptionEventRow {
+                    id: Uuid::now_v7(),
+                    subscription_id: subscription.id,
+                    event_type: SubscriptionEventType::Switch.into(),
+                    details: Some(serde_json::json!({
+                        "subscription_migration_id": migration_id,
+                        "previous_plan_version_id": subscription.plan_version_id,
+                        "plan_version_id": plan_version_id,
+                    })),
+                    created_at: now,
+                    mrr_delta: Some(mrr_delta),
+                    bi_mrr_movement_log_id: None,
+                    applies_to: today,
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let Some(period) = period.filter(|p| p.end > today) else {
+                    return Ok(MigrationOutcome {
+                        status: SubscriptionMigrationItemStatusEnum::Migrated,
+                        mrr_delta: Some(mrr_delta),
+                        prorated_amount: None,
+                    });
+                };
+
+                let remaining_period = Period {
+                    start: today,
+                    end: period.end,
+                };
+
+                let period_delta = (Decimal::from(mrr_delta)
+                    * subscription.period.length_in_months())
+                .to_i64()
+                .unwrap_or(0);
+
+                let prorated_amount = proration::prorate(
+                    period_delta,
+                    &period,
+                    &remaining_period,
+                    proration_rounding,
+                )?;
+
+                let description = format!(
+                    "Plan change from version {} on {}",
+                    subscription.version, today
+                );
+
+                if prorated_amount > 0 {
+                    let line = LineItem {
+                        local_id: LocalId::no_prefix(),
+                        name: subscription.plan_name.clone(),
+                        total: prorated_amount,
+                        subtotal: prorated_amount,
+                        quantity: Some(Decimal::ONE),
+                        unit_price: None,
+                        start_date: remaining_period.start,
+                        end_date: remaining_period.end,
+                        sub_lines: vec![],
+                        is_prorated: true,
+                        price_component_id: None,
+                        product_id: None,
+                        metric_id: None,
+                        description: Some(description),
+                        quantity_display_precision: None,
+                    };
+
+                    let invoice = off_cycle_invoice(
+                        subscription,
+                        &customer,
+                        &invoicing_entity,
+                        &tenant_payment_terms,
+                        InvoiceType::Adjustment,
+                        today,
+                        vec![line],
+                    )?;
+
+                    insert_invoice(conn, invoice).await?;
+                } else if prorated_amount < 0 {
+                    let credit = i32::try_from(-prorated_amount).map_err(|_| {
+                        StoreError::InvalidArgument("prorated credit is too large".to_string())
+                    })?;
+
+                    CustomerBalance::update_with_note(
+                        conn,
+                        subscription.customer_id,
+                        tenant_id,
+                        credit,
+                        None,
+                        Some(description),
+                    )
+                    .await?;
+                }
+
+                Ok(MigrationOutcome {
+                    status: SubscriptionMigrationItemStatusEnum::Migrated,
+                    mrr_delta: Some(mrr_delta),
+                    prorated_amount: Some(prorated_amount),
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+/// Plan switch to the target version, with the parameters of the current components carried over
+async fn migration_change(
+    conn: &mut PgConn,
+    migration: &SubscriptionMigrationNew,
+    subscription: &SubscriptionDetails,
+    price_components: &[PriceComponent],
+) -> StoreResult<SubscriptionChange> {
+    let now = chrono::Utc::now().naive_utc();
+    let mut current_slots = HashMap::new();
+
+    for component in &subscription.price_components {
+        if let SubscriptionFee::Slot { .. } = component.fee {
+            let slots = active_slots_at(conn, component, subscription.id, now).await?;
+            current_slots.insert(component.id, slots);
+        }
+    }
+
+    let components = carry_over_components(
+        &subscription.price_components,
+        &current_slots,
+        price_components,
+        &subscription.period,
+    );
+
+    Ok(SubscriptionChange::PlanSwitch {
+        plan_version_id: migration.to_plan_version_id,
+        components: Some(components),
+    })
+}
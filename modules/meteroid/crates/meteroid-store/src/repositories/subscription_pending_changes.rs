@@ -0,0 +1,597 @@
+use std::collections::HashMap;
+
+use chrono::{NaiveDate, NaiveDateTime, NaiveTime};
+use chrono_tz::Tz;
+use common_eventbus::Event;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use itertools::Itertools;
+use uuid::Uuid;
+
+use crate::constants::Currencies;
+use crate::domain::component_rules::{validate_component_rules, ComponentRuleItem};
+use crate::domain::enums::{BillingPeriodEnum, SubscriptionEventType};
+use crate::domain::subscription_component_history::{
+    component_fee_changes, SubscriptionComponentHistoryNew,
+};
+use crate::domain::subscription_pending_changes::{
+    SubscriptionChange, SubscriptionPendingChange, SubscriptionPendingChangeNew,
+};
+use crate::domain::{
+    BillableMetric, CreateSubscriptionComponents, CursorPaginatedVec, CursorPaginationRequest,
+    PriceComponent, Schedule, SubscriptionComponent, SubscriptionComponentNew,
+    SubscriptionComponentNewInternal, SubscriptionDetails, TenantContext,
+};
+use crate::errors::StoreError;
+use crate::repositories::component_rules::load_component_rules;
+use crate::repositories::entitlements::invalidate_customer_entitlements_cache;
+use crate::repositories::subscription_component_history::insert_component_history;
+use crate::repositories::subscriptions::{calculate_mrr, process_create_subscription_components};
+use crate::repositories::SubscriptionInterface;
+use crate::store::{PgConn, Store};
+use crate::StoreResult;
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::plan_versions::PlanVersionRow;
+use diesel_models::price_components::PriceComponentRow;
+use diesel_models::schedules::ScheduleRow;
+use diesel_models::subscription_components::{
+    SubscriptionComponentRow, SubscriptionComponentRowNew,
+};
+use diesel_models::subscription_events::SubscriptionEventRow;
+use diesel_models::subscription_pending_changes::{
+    SubscriptionPendingChangeRow, SubscriptionPendingChangeRowNew,
+};
+use diesel_models::subscriptions::{CancelSubscriptionParams, SubscriptionRow};
+
+#[async_trait::async_trait]
+pub trait SubscriptionPendingChangeInterface {
+    /// Schedules a change for the next renewal of the subscription. A single change can be scheduled at a time
+    async fn schedule_subscription_change(
+        &self,
+        subscription_id: Uuid,
+        change: SubscriptionChange,
+        context: TenantContext,
+    ) -> StoreResult<SubscriptionPendingChange>;
+
+    async fn cancel_subscription_pending_change(
+        &self,
+        pending_change_id: Uuid,
+        tenant_id: Uuid,
+    ) -> StoreResult<SubscriptionPendingChange>;
+
+    async fn list_subscription_pending_changes(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> StoreResult<Vec<SubscriptionPendingChange>>;
+
+    async fn list_due_subscription_pending_changes(
+        &self,
+        date: NaiveDate,
+        billing_timezones: &[Tz],
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<SubscriptionPendingChange>>;
+
+    async fn apply_subscription_pending_change(
+        &self,
+        pending_change: &SubscriptionPendingChange,
+    ) -> StoreResult<()>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionPendingChangeInterface for Store {
+    async fn schedule_subscription_change(
+        &self,
+        subscription_id: Uuid,
+        change: SubscriptionChange,
+        context: TenantContext,
+    ) -> StoreResult<SubscriptionPendingChange> {
+        let subscription = self
+            .get_subscription_details(context.tenant_id, subscription_id)
+            .await?;
+
+        if subscription.canceled_at.is_some() {
+            return Err(StoreError::InvalidArgument("subscription is canceled".to_string()).into());
+        }
+
+        let today = chrono::Utc::now().naive_utc().date();
+
+        let effective_at = subscription
+            .calculate_cancellable_end_of_period_date(today)
+            .ok_or(StoreError::InvalidArgument(
+                "subscription has no next renewal".to_string(),
+            ))?;
+
+        if subscription
+            .billing_end_date
+            .is_some_and(|end| end <= effective_at)
+        {
+            return Err(StoreError::InvalidArgument(
+                "subscription ends before its next renewal".to_string(),
+            )
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        if SubscriptionPendingChangeRow::find_scheduled_by_subscription_id(
+            &mut conn,
+            context.tenant_id,
+            subscription_id,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .is_some()
+        {
+            return Err(StoreError::InvalidArgument(
+                "a change is already scheduled for this subscription".to_string(),
+            )
+            .into());
+        }
+
+        // the components are resolved again when applied, as the plan may be edited in between
+        resolve_changed_components(&mut conn, &subscription, &change).await?;
+
+        let row: SubscriptionPendingChangeRowNew = SubscriptionPendingChangeNew {
+            tenant_id: context.tenant_id,
+            subscription_id,
+            change,
+            effective_at,
+            created_by: context.actor,
+        }
+        .try_into()?;
+
+        row.insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .try_into()
+    }
+
+    async fn cancel_subscription_pending_change(
+        &self,
+        pending_change_id: Uuid,
+        tenant_id: Uuid,
+    ) -> StoreResult<SubscriptionPendingChange> {
+        let mut conn = self.get_conn().await?;
+
+        SubscriptionPendingChangeRow::cancel(
+            &mut conn,
+            pending_change_id,
+            tenant_id,
+            chrono::Utc::now().naive_utc(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .ok_or(StoreError::ValueNotFound(
+            "no scheduled change with this id".to_string(),
+        ))?
+        .try_into()
+    }
+
+    async fn list_subscription_pending_changes(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+    ) -> StoreResult<Vec<SubscriptionPendingChange>> {
+        let mut conn = self.get_conn().await?;
+
+        SubscriptionPendingChangeRow::list_by_subscription_id(&mut conn, tenant_id, subscription_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()
+    }
+
+    async fn list_due_subscription_pending_changes(
+        &self,
+        date: NaiveDate,
+        billing_timezones: &[Tz],
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<SubscriptionPendingChange>> {
+        let mut conn = self.get_conn().await?;
+
+        let billing_timezones = billing_timezones
+            .iter()
+            .map(|tz| tz.name().to_string())
+            .collect::<Vec<_>>();
+
+        let rows = SubscriptionPendingChangeRow::list_due(
+            &mut conn,
+            date,
+            &billing_timezones,
+            pagination.into(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: rows
+                .items
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+            next_cursor: rows.next_cursor,
+        })
+    }
+
+    async fn apply_subscription_pending_change(
+        &self,
+        pending_change: &SubscriptionPendingChange,
+    ) -> StoreResult<()> {
+        let subscription = self
+            .get_subscription_details(pending_change.tenant_id, pending_change.subscription_id)
+            .await?;
+
+        let customer_id = subscription.customer_id;
+
+        let applied = self
+            .transaction(|conn| {
+                async move {
+                    let now = chrono::Utc::now().naive_utc();
+
+                    let marked =
+                        SubscriptionPendingChangeRow::mark_as_applied(conn, pending_change.id, now)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                    // canceled or applied concurrently
+                    if marked == 0 {
+                        return Ok(false);
+                    }
+
+                    let effective_at = pending_change.effective_at;
+
+                    let event = match &pending_change.change {
+                        SubscriptionChange::Cancellation { reason } => {
+                            SubscriptionRow::cancel_subscription(
+                                conn,
+                                CancelSubscriptionParams {
+                                    subscription_id: subscription.id,
+                                    tenant_id: subscription.tenant_id,
+                                    canceled_at: now,
+                                    billing_end_date: effective_at,
+                                    reason: reason.clone(),
+                                },
+                            )
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                            SubscriptionEventRow {
+                                id: Uuid::now_v7(),
+                                subscription_id: subscription.id,
+                                event_type: SubscriptionEventType::Cancelled.into(),
+                                details: None,
+                                created_at: now,
+                                mrr_delta: Some(-(subscription.mrr_cents as i64)),
+                                bi_mrr_movement_log_id: None,
+                                applies_to: effective_at,
+                            }
+                        }
+                        SubscriptionChange::PlanSwitch { .. }
+                        | SubscriptionChange::ComponentChange { .. } => {
+                            let (plan_version_id, components) = resolve_changed_components(
+                                conn,
+                                &subscription,
+                                &pending_change.change,
+                            )
+                            .await?;
+
+                            let mrr_delta = switch_subscription_components(
+                                conn,
+                                &subscription,
+                                plan_version_id,
+                                components,
+                                effective_at.and_time(NaiveTime::MIN),
+                                pending_change.created_by,
+                            )
+                            .await?;
+
+                            let event_type = match pending_change.change {
+                                SubscriptionChange::PlanSwitch { .. } => {
+                                    SubscriptionEventType::Switch
+                                }
+                                _ => SubscriptionEventType::Updated,
+                            };
+
+                            SubscriptionEventRow {
+                                id: Uuid::now_v7(),
+                                subscription_id: subscription.id,
+                                event_type: event_type.into(),
+                                details: Some(serde_json::json!({
+                                    "pending_change_id": pending_change.id,
+                                    "previous_plan_version_id": subscription.plan_version_id,
+                                    "plan_version_id": plan_version_id,
+                                })),
+                                created_at: now,
+                                mrr_delta: Some(mrr_delta),
+                                bi_mrr_movement_log_id: None,
+                                applies_to: effective_at,
+                            }
+                        }
+                    };
+
+                    event
+                        .insert(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    Ok(true)
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        if applied {
+            invalidate_customer_entitlements_cache(pending_change.tenant_id, customer_id).await;
+
+            if let SubscriptionChange::Cancellation { .. } = pending_change.change {
+                let _ = self
+                    .eventbus
+                    .publish(Event::subscription_canceled(
+                        pending_change.created_by,
+                        pending_change.subscription_id,
+                        pending_change.tenant_id,
+                    ))
+                    .await;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replaces the components of the subscription and moves it to the plan version, recording the fee changes in the
+/// component history. Returns the MRR delta, in cents
+pub(crate) async fn switch_subscription_components(
+    conn: &mut PgConn,
+    subscription: &SubscriptionDetails,
+    plan_version_id: Uuid,
+    components: Vec<SubscriptionComponentNewInternal>,
+    effective_at: NaiveDateTime,
+    actor: Uuid,
+) -> StoreResult<i64> {
+    let precision = Currencies::resolve_currency_precision(&subscription.currency).ok_or(
+        StoreError::ValueNotFound(format!("Currency {} not found", subscription.currency)),
+    )?;
+
+    let previous_mrr = subscription
+        .price_components
+        .iter()
+        .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+        .sum::<i64>();
+
+    let mrr = components
+        .iter()
+        .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+        .sum::<i64>();
+
+    let period = changed_billing_period(subscription, &components);
+
+    let history = component_fee_changes(&subscription.price_components, &components)
+        .into_iter()
+        .map(
+            |(price_component_id, component_name, change)| SubscriptionComponentHistoryNew {
+                tenant_id: subscription.tenant_id,
+                subscription_id: subscription.id,
+                price_component_id,
+                component_name,
+                change,
+                effective_at,
+                created_by: actor,
+            },
+        )
+        .collect();
+
+    insert_component_history(conn, history).await?;
+
+    SubscriptionComponentRow::delete_by_subscription_id(conn, &subscription.id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let insertable = components
+        .into_iter()
+        .map(|internal| {
+            SubscriptionComponentNew {
+                subscription_id: subscription.id,
+                internal,
+            }
+            .try_into()
+        })
+        .collect::<Result<Vec<SubscriptionComponentRowNew>, _>>()?;
+
+    SubscriptionComponentRow::insert_subscription_component_batch(
+        conn,
+        insertable.iter().collect(),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    SubscriptionRow::update_plan_version(
+        conn,
+        subscription.id,
+        subscription.tenant_id,
+        plan_version_id,
+        period.clone().into(),
+        period.custom_months(),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    Ok(mrr - previous_mrr)
+}
+
+/// The subscription as it will be once the pending change is applied, to project its invoices from `effective_at`
+pub(crate) async fn subscription_after_change(
+    conn: &mut PgConn,
+    subscription: &SubscriptionDetails,
+    change: &SubscriptionChange,
+    effective_at: NaiveDate,
+) -> StoreResult<SubscriptionDetails> {
+    if let SubscriptionChange::Cancellation { .. } = change {
+        return Ok(SubscriptionDetails {
+            billing_end_date: Some(
+                subscription
+                    .billing_end_date
+                    .map_or(effective_at, |end| end.min(effective_at)),
+            ),
+            ..subscription.clone()
+        });
+    }
+
+    let (plan_version_id, components) =
+        resolve_changed_components(conn, subscription, change).await?;
+
+    let period = changed_billing_period(subscription, &components);
+
+    let price_components: Vec<SubscriptionComponent> = components
+        .into_iter()
+        .map(|c| SubscriptionComponent {
+            id: Uuid::now_v7(),
+            price_component_id: c.price_component_id,
+            product_item_id: c.product_item_id,
+            subscription_id: subscription.id,
+            name: c.name,
+            period: c.period,
+            fee: c.fee,
+        })
+        .collect();
+
+    let schedules: Vec<Schedule> = if plan_version_id == subscription.plan_version_id {
+        subscription.schedules.clone()
+    } else {
+        ScheduleRow::list(conn, plan_version_id, subscription.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?
+    };
+
+    let metric_ids = price_components
+        .iter()
+        .filter_map(|c| c.metric_id())
+        .chain(
+            subscription
+                .add_ons
+                .iter()
+                .filter_map(|a| a.fee.metric_id()),
+        )
+        .unique()
+        .collect::<Vec<_>>();
+
+    let metrics: Vec<BillableMetric> =
+        BillableMetricRow::get_by_ids(conn, &metric_ids, &subscription.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(SubscriptionDetails {
+        plan_version_id,
+        price_components,
+        schedules,
+        metrics,
+        period,
+        ..subscription.clone()
+    })
+}
+
+/// Billing period of the subscription with the changed components, the shortest of its fees
+fn changed_billing_period(
+    subscription: &SubscriptionDetails,
+    components: &[SubscriptionComponentNewInternal],
+) -> BillingPeriodEnum {
+    components
+        .iter()
+        .map(|c| &c.period)
+        .chain(subscription.add_ons.iter().map(|a| &a.period))
+        .filter_map(|p| p.as_billing_period_opt())
+        .min()
+        .unwrap_or(BillingPeriodEnum::Monthly)
+}
+
+/// Target plan version of a plan switch or component change, and the subscription components it results in
+pub(crate) async fn resolve_changed_components(
+    conn: &mut PgConn,
+    subscription: &SubscriptionDetails,
+    change: &SubscriptionChange,
+) -> StoreResult<(Uuid, Vec<SubscriptionComponentNewInternal>)> {
+    let (plan_version_id, components): (Uuid, Option<CreateSubscriptionComponents>) = match change {
+        SubscriptionChange::Cancellation { .. } => {
+            return Ok((subscription.plan_version_id, vec![]));
+        }
+        SubscriptionChange::PlanSwitch {
+            plan_version_id,
+            components,
+        } => {
+            if *plan_version_id == subscription.plan_version_id {
+                return Err(StoreError::InvalidArgument(
+                    "subscription is already on this plan version".to_string(),
+                )
+                .into());
+            }
+
+            let plan_version = PlanVersionRow::find_by_id_and_tenant_id(
+                conn,
+                *plan_version_id,
+                subscription.tenant_id,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            if plan_version.is_draft_version {
+                return Err(StoreError::InvalidArgument(
+                    "cannot switch to a draft plan version".to_string(),
+                )
+                .into());
+            }
+
+            if plan_version.currency != subscription.currency {
+                return Err(StoreError::InvalidArgument(format!(
+                    "cannot switch to a plan version in {}, the subscription is in {}",
+                    plan_version.currency, subscription.currency
+                ))
+                .into());
+            }
+
+            (*plan_version_id, components.clone())
+        }
+        SubscriptionChange::ComponentChange { components } => {
+            (subscription.plan_version_id, Some(components.clone()))
+        }
+    };
+
+    let price_components: Vec<PriceComponent> =
+        PriceComponentRow::list_by_plan_version_id(conn, subscription.tenant_id, plan_version_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect::<Result<Vec<_>, _>>()?;
+
+    let processed = process_create_subscription_components(
+        &components,
+        &HashMap::from([(plan_version_id, price_components)]),
+        &plan_version_id,
+    )?;
+
+    // the add-ons of the subscription are kept through the change
+    let rule_items = processed
+        .iter()
+        .filter_map(|c| c.price_component_id.map(ComponentRuleItem::PriceComponent))
+        .chain(
+            subscription
+                .add_ons
+                .iter()
+                .map(|a| ComponentRuleItem::AddOn(a.add_on_id)),
+        )
+        .collect::<Vec<_>>();
+
+    let rules = load_component_rules(conn, subscription.tenant_id).await?;
+
+    validate_component_rules(&rules, &rule_items)?;
+
+    Ok((plan_version_id, processed))
+}
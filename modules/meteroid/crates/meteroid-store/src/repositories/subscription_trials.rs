@@ -0,0 +1,85 @@
+use chrono::NaiveDate;
+use diesel_models::invoices::InvoiceRow;
+use diesel_models::subscriptions::SubscriptionRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::enums::TrialModeEnum;
+use crate::domain::{
+    CursorPaginatedVec, CursorPaginationRequest, SubscriptionTrialEnd, TenantContext,
+};
+use crate::errors::StoreError;
+use crate::repositories::subscriptions::CancellationEffectiveAt;
+use crate::repositories::SubscriptionInterface;
+use crate::{Store, StoreResult};
+
+const TRIAL_EXPIRED_REASON: &str = "trial expired";
+
+#[async_trait::async_trait]
+pub trait SubscriptionTrialsInterface {
+    /// Subscriptions in trial that reached their billing start date at that date without being activated
+    async fn list_ended_trials(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<SubscriptionTrialEnd>>;
+
+    /// Ends the trial of a subscription, following the trial mode of its plan :
+    /// a free trial expires and the subscription is canceled at its billing start date,
+    /// a card-required trial is converted and the subscription is activated, to be charged on its payment method.
+    async fn end_trial(&self, trial: &SubscriptionTrialEnd) -> StoreResult<()>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionTrialsInterface for Store {
+    async fn list_ended_trials(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<SubscriptionTrialEnd>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows = SubscriptionRow::list_ended_trials(&mut conn, date, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: rows.items.into_iter().map(Into::into).collect(),
+            next_cursor: rows.next_cursor,
+        })
+    }
+
+    async fn end_trial(&self, trial: &SubscriptionTrialEnd) -> StoreResult<()> {
+        match trial.trial_mode {
+            TrialModeEnum::Free => {
+                let mut conn = self.get_conn().await?;
+
+                // the initial invoice was drafted for the end of the trial, it will not be collected
+                InvoiceRow::void_pending_by_subscription_id(&mut conn, trial.id, trial.tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                self.cancel_subscription(
+                    trial.id,
+                    Some(TRIAL_EXPIRED_REASON.to_string()),
+                    CancellationEffectiveAt::Date(trial.billing_start_date),
+                    TenantContext {
+                        // ended by the system
+                        actor: Uuid::nil(),
+                        tenant_id: trial.tenant_id,
+                    },
+                )
+                .await?;
+            }
+            TrialModeEnum::CardRequired => {
+                let mut conn = self.get_conn().await?;
+
+                SubscriptionRow::activate_subscription(&mut conn, trial.id, trial.tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+            }
+        }
+
+        Ok(())
+    }
+}
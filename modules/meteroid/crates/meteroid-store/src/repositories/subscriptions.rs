@@ -1,36 +1,57 @@
 use crate::domain::enums::{
-    BillingPeriodEnum, InvoiceStatusEnum, InvoiceType, InvoicingProviderEnum,
-    SubscriptionEventType, SubscriptionFeeBillingPeriod,
+    BillingPeriodEnum, CreditLimitSourceEnum, InvoiceCadenceGroupingEnum, InvoiceStatusEnum,
+    InvoiceType, InvoicingProviderEnum, SubscriptionEventType, SubscriptionFeeBillingPeriod,
+    TrialModeEnum,
 };
 use crate::domain::{
-    BillableMetric, BillingConfig, CreateSubscription, CreateSubscriptionAddOns,
-    CreateSubscriptionComponents, CreateSubscriptionCoupons, CreatedSubscription,
-    CursorPaginatedVec, CursorPaginationRequest, Customer, InlineCustomer, InlineInvoicingEntity,
-    InvoicingEntity, PaginatedVec, PaginationRequest, PriceComponent, Schedule, Subscription,
-    SubscriptionAddOnCustomization, SubscriptionAddOnNew, SubscriptionAddOnNewInternal,
+    BillableMetric, BillingConfig, BillingScheduleEntry, CreateSubscription,
+    CreateSubscriptionAddOns, CreateSubscriptionComponents, CreateSubscriptionCoupons,
+    CreatedSubscription, CreatedSubscriptionsBatch, CursorPaginatedVec, CursorPaginationRequest,
+    Customer, DowngradePolicy, InlineCustomer, InlineInvoicingEntity, Invoice, InvoiceNew,
+    InvoicingEntity, LineItem, PaginatedVec, PaginationRequest, Period, PlanRampAdjustment,
+    PriceComponent, Schedule, SlotUpdate, Subscription, SubscriptionAddOnCustomization,
+    SubscriptionAddOnNew, SubscriptionAddOnNewInternal, SubscriptionBatchFailure,
     SubscriptionComponent, SubscriptionComponentNew, SubscriptionComponentNewInternal,
-    SubscriptionDetails, SubscriptionFee, SubscriptionInvoiceCandidate, SubscriptionNew,
+    SubscriptionDetails, SubscriptionFee, SubscriptionInvoiceCandidate, UpgradePolicy,
 };
 use crate::errors::StoreError;
 use crate::store::{PgConn, Store};
 use crate::utils::decimals::ToSubunit;
 use crate::{domain, StoreResult};
 use chrono::{NaiveDate, NaiveTime};
+use chrono_tz::Tz;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use diesel_async::AsyncConnection;
 use diesel_models::errors::{DatabaseError, DatabaseErrorContainer};
-use error_stack::{report, Report};
+use error_stack::{report, AttachmentKind, FrameKind, Report};
 use itertools::Itertools;
 use std::collections::HashMap;
 use uuid::Uuid;
 
+use crate::compute::{proration, InvoiceLineInterface};
 use crate::constants::Currencies;
 use crate::domain::add_ons::AddOn;
+use crate::domain::adjustments::discount::StandardDiscount;
+use crate::domain::component_rules::{validate_component_rules, ComponentRule, ComponentRuleItem};
 use crate::domain::coupons::{Coupon, CouponDiscount};
+use crate::domain::credit_limits::CreditExposure;
+use crate::domain::invoice_cadences::missing_cadences;
+use crate::domain::payment_terms::{PaymentTerms, TenantPaymentTerms};
 use crate::domain::subscription_add_ons::SubscriptionAddOn;
+use crate::domain::subscription_component_history::{
+    ComponentParameterChange, SubscriptionComponentHistoryNew,
+};
+use crate::domain::subscription_pending_changes::SubscriptionPendingChange;
+use crate::domain::subscription_phases::SubscriptionPhase;
+use crate::repositories::component_rules::load_component_rules;
+use crate::repositories::credit_limits::{list_credit_exposures, CreditLimitInterface};
+use crate::repositories::entitlements::invalidate_customer_entitlements_cache;
 use crate::repositories::historical_rates::HistoricalRatesInterface;
+use crate::repositories::invoices::insert_invoice;
 use crate::repositories::invoicing_entities::InvoicingEntityInterface;
-use crate::repositories::{CustomersInterface, InvoiceInterface};
+use crate::repositories::subscription_component_history::insert_component_history;
+use crate::repositories::subscription_pending_changes::subscription_after_change;
+use crate::repositories::{CustomersInterface, InvoiceInterface, TenantInterface};
 use crate::utils::local_id::{IdType, LocalId};
 use common_eventbus::Event;
 use diesel_models::add_ons::AddOnRow;
@@ -39,8 +60,9 @@ use diesel_models::applied_coupons::{
 };
 use diesel_models::billable_metrics::BillableMetricRow;
 use diesel_models::coupons::CouponRow;
+use diesel_models::invoices::InvoiceRow;
 use diesel_models::price_components::PriceComponentRow;
-use diesel_models::query::plans::get_plan_names_by_version_ids;
+use diesel_models::query::plans::{get_plan_names_by_version_ids, get_trial_modes_by_version_ids};
 use diesel_models::schedules::ScheduleRow;
 use diesel_models::slot_transactions::SlotTransactionRow;
 use diesel_models::subscription_add_ons::{SubscriptionAddOnRow, SubscriptionAddOnRowNew};
@@ -48,7 +70,10 @@ use diesel_models::subscription_components::{
     SubscriptionComponentRow, SubscriptionComponentRowNew,
 };
 use diesel_models::subscription_events::SubscriptionEventRow;
+use diesel_models::subscription_pending_changes::SubscriptionPendingChangeRow;
+use diesel_models::subscription_phases::{SubscriptionPhaseRow, SubscriptionPhaseRowNew};
 use diesel_models::subscriptions::{SubscriptionRow, SubscriptionRowNew};
+use diesel_models::tenants::TenantRow;
 use diesel_models::DbResult;
 use rust_decimal::prelude::*;
 
@@ -71,6 +96,14 @@ pub trait SubscriptionInterface {
         tenant_id: Uuid,
     ) -> StoreResult<Vec<CreatedSubscription>>;
 
+    /// Inserts the valid subscriptions of the batch, along with their components, events and initial invoices,
+    /// in a single transaction. Items failing validation or insertion are reported instead of failing the whole batch.
+    async fn create_subscriptions_batch(
+        &self,
+        batch: Vec<CreateSubscription>,
+        tenant_id: Uuid,
+    ) -> StoreResult<CreatedSubscriptionsBatch>;
+
     async fn get_subscription_details(
         &self,
         tenant_id: Uuid,
@@ -102,22 +135,31 @@ pub trait SubscriptionInterface {
     async fn list_subscription_invoice_candidates(
         &self,
         date: NaiveDate,
+        billing_timezones: &[Tz],
         pagination: CursorPaginationRequest,
     ) -> StoreResult<CursorPaginatedVec<SubscriptionInvoiceCandidate>>;
+
+    /// Drafts the next recurring invoices of a candidate subscription. With per cadence invoicing,
+    /// one invoice per billing period of its fees that has no upcoming invoice yet
+    async fn draft_recurring_invoices(
+        &self,
+        subscription: &SubscriptionInvoiceCandidate,
+        customer: &Customer,
+        invoicing_entity: &InvoicingEntity,
+        date: NaiveDate,
+    ) -> StoreResult<Vec<InvoiceNew>>;
 }
 
 // TODO we need to always pass the tenant id and match it with the resource, if not within the resource.
 // and even within it's probably still unsafe no ? Ex: creating components against a wrong subscription within a different tenant
 
-fn calculate_mrr(
+pub(crate) fn calculate_mrr(
     fee: &SubscriptionFee,
     period: &SubscriptionFeeBillingPeriod,
     precision: u8,
 ) -> i64 {
     let mut total_cents = 0;
 
-    let period_as_months = period.as_months() as i64;
-
     match fee {
         SubscriptionFee::Rate { rate } => {
             total_cents = rate.to_subunit_opt(precision).unwrap_or(0);
@@ -137,14 +179,14 @@ fn calculate_mrr(
             total_cents =
                 (*initial_slots as i64) * unit_rate.to_subunit_opt(precision).unwrap_or(0);
         }
-        SubscriptionFee::OneTime { .. } | SubscriptionFee::Usage { .. } => {
+        SubscriptionFee::OneTime { .. }
+        | SubscriptionFee::Usage { .. }
+        | SubscriptionFee::Percent { .. } => {
             // doesn't count as mrr
         }
     }
 
-    let _mrr = total_cents / period_as_months;
-
-    let mrr_monthly = Decimal::from(total_cents) / Decimal::from(period_as_months);
+    let mrr_monthly = Decimal::from(total_cents) / period.length_in_months();
 
     mrr_monthly.to_i64().unwrap_or(0)
 }
@@ -159,411 +201,1143 @@ pub trait SubscriptionSlotsInterface {
         ts: Option<chrono::NaiveDateTime>,
     ) -> StoreResult<u32>;
 
+    /// Adds (positive delta) or removes (negative delta) slots, following the upgrade and downgrade policies of the component
     async fn add_slot_transaction(
         &self,
         tenant_id: Uuid,
         subscription_id: Uuid,
         price_component_id: Uuid,
-        slots: i32,
-    ) -> StoreResult<i32>;
+        delta: i32,
+        actor: Uuid,
+        ts: Option<chrono::NaiveDateTime>,
+    ) -> StoreResult<SlotUpdate>;
 }
 
 #[async_trait::async_trait]
 impl SubscriptionSlotsInterface for Store {
     async fn get_current_slots_value(
         &self,
-        _tenant_id: Uuid,
+        tenant_id: Uuid,
         subscription_id: Uuid,
         price_component_id: Uuid,
         ts: Option<chrono::NaiveDateTime>,
     ) -> StoreResult<u32> {
         let mut conn = self.get_conn().await?;
 
-        SlotTransactionRow::fetch_by_subscription_id_and_price_component_id(
+        let components: Vec<SubscriptionComponent> =
+            SubscriptionComponentRow::list_subscription_components_by_subscription(
+                &mut conn,
+                &tenant_id,
+                &subscription_id,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(|s| s.try_into())
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let component = find_slot_component(&components, price_component_id)?;
+
+        active_slots_at(
             &mut conn,
+            component,
             subscription_id,
-            price_component_id,
-            ts,
+            ts.unwrap_or_else(|| chrono::Utc::now().naive_utc()),
         )
         .await
-        .map(|c| c.current_active_slots as u32)
-        .map_err(Into::into)
     }
 
     async fn add_slot_transaction(
         &self,
-        _tenant_id: Uuid,
-        _subscription_id: Uuid,
-        _price_component_id: Uuid,
-        _slots: i32,
-    ) -> StoreResult<i32> {
-        todo!()
-        /*
-        sequenceDiagram
-            participant User
-            participant Billing Software
-            participant Database
-            participant Stripe API
-
-            User->>Billing Software: Request to add seats
-            Billing Software->>Database: Check current subscription details
-            Database-->>Billing Software: Return subscription details
-            Billing Software->>Billing Software: Calculate prorated amount for additional seats
-            Billing Software->>User: Display prorated charge and request payment approval
-
-            User->>Billing Software: Approve payment
-            Billing Software->>Stripe API: Create payment intent with prorated amount
-            Stripe API-->>Billing Software: Payment intent created (awaiting confirmation)
-
-            User->>Stripe API: Confirm and process payment
-            Stripe API-->>Billing Software: Payment success notification
-            Billing Software->>Database: Update subscription (add seats)
-            Database-->>Billing Software: Subscription updated
-            Billing Software->>Stripe API: Generate invoice for the transaction
-            Stripe API-->>Billing Software: Invoice generated
-            Billing Software->>User: Confirm seat addition and send invoice
-
-            Billing Software->>Database: Log transaction details
-            Database-->>Billing Software: Transaction logged
-            Billing Software->>User: Notify transaction completion
-
-                 */
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        price_component_id: Uuid,
+        delta: i32,
+        actor: Uuid,
+        ts: Option<chrono::NaiveDateTime>,
+    ) -> StoreResult<SlotUpdate> {
+        if delta == 0 {
+            return Err(StoreError::InvalidArgument("Slot delta cannot be 0".to_string()).into());
+        }
+
+        let now = ts.unwrap_or_else(|| chrono::Utc::now().naive_utc());
+
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        if subscription.canceled_at.is_some() {
+            return Err(StoreError::InvalidArgument(
+                "Cannot update the slots of a canceled subscription".to_string(),
+            )
+            .into());
+        }
+
+        let component =
+            find_slot_component(&subscription.price_components, price_component_id)?.clone();
+
+        let billing_period = component
+            .period
+            .as_billing_period_opt()
+            .unwrap_or(subscription.period.clone());
+
+        let period = crate::utils::periods::calculate_periods_for_date(
+            subscription.billing_start_date,
+            subscription.anchor_day(),
+            now.date(),
+            &billing_period,
+        )
+        .advance;
+        let period_end = period.end.and_time(NaiveTime::MIN);
+
+        let precision = Currencies::resolve_currency_precision(&subscription.currency).ok_or(
+            StoreError::ValueNotFound(format!("Currency {} not found", subscription.currency)),
+        )?;
+
+        let proration_rounding = self.get_proration_rounding_by_tenant_id(tenant_id).await?;
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
+
+        let customer = self
+            .find_customer_by_id(subscription.customer_id, tenant_id)
+            .await?;
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(customer.invoicing_entity_id))
+            .await?;
+
+        self.transaction(|conn| {
+            async move {
+                // serializes the concurrent transactions of the subscription, each one being checked against
+                // the slots left by the previous ones
+                SubscriptionRow::lock_subscription_for_update(conn, subscription_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                let current = active_slots_at(conn, &component, subscription_id, now).await?;
+                let next_period =
+                    active_slots_at(conn, &component, subscription_id, period_end).await?;
+
+                let SubscriptionFee::Slot {
+                    unit,
+                    unit_rate,
+                    min_slots,
+                    max_slots,
+                    upgrade_policy,
+                    downgrade_policy,
+                    ..
+                } = &component.fee
+                else {
+                    return Err(
+                        StoreError::InvalidArgument("Not a slot component".to_string()).into(),
+                    );
+                };
+
+                let (new_current, new_next_period) =
+                    apply_slot_delta(current, next_period, delta, *min_slots, *max_slots)?;
+
+                let effective_at = if delta > 0 {
+                    match upgrade_policy {
+                        UpgradePolicy::Prorated => now,
+                    }
+                } else {
+                    match downgrade_policy {
+                        DowngradePolicy::RemoveAtEndOfPeriod => period_end,
+                    }
+                };
+
+                SlotTransactionRow {
+                    id: Uuid::now_v7(),
+                    price_component_id,
+                    subscription_id,
+                    delta,
+                    prev_active_slots: current as i32,
+                    effective_at,
+                    transaction_at: now,
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let slots_change = if delta > 0 {
+                    ComponentParameterChange::Slots {
+                        previous: current,
+                        new: new_current,
+                    }
+                } else {
+                    ComponentParameterChange::Slots {
+                        previous: next_period,
+                        new: new_next_period,
+                    }
+                };
+
+                insert_component_history(
+                    conn,
+                    vec![SubscriptionComponentHistoryNew {
+                        tenant_id,
+                        subscription_id,
+                        price_component_id: Some(price_component_id),
+                        component_name: component.name.clone(),
+                        change: slots_change,
+                        effective_at,
+                        created_by: actor,
+                    }],
+                )
+                .await?;
+
+                let unit_rate_cents =
+                    unit_rate
+                        .to_subunit_opt(precision)
+                        .ok_or(StoreError::InvalidArgument(
+                            "Invalid slot unit rate".to_string(),
+                        ))?;
+
+                let mrr_delta = Decimal::from(unit_rate_cents * delta as i64)
+                    / component.period.length_in_months();
+
+                SubscriptionEventRow {
+                    id: Uuid::now_v7(),
+                    subscription_id,
+                    event_type: SubscriptionEventType::Updated.into(),
+                    details: Some(serde_json::json!({
+                        "price_component_id": price_component_id,
+                        "slots_delta": delta,
+                    })),
+                    created_at: now,
+                    mrr_delta: mrr_delta.to_i64(),
+                    bi_mrr_movement_log_id: None,
+                    applies_to: effective_at.date(),
+                }
+                .insert(conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                let mut prorated_amount = 0;
+                let mut invoice_id = None;
+
+                if delta > 0 {
+                    let remaining_period = Period {
+                        start: now.date(),
+                        end: period.end,
+                    };
+
+                    prorated_amount = proration::prorate(
+                        unit_rate_cents * delta as i64,
+                        &period,
+                        &remaining_period,
+                        proration_rounding,
+                    )?;
+
+                    if prorated_amount > 0 {
+                        let line = LineItem {
+                            local_id: LocalId::no_prefix(),
+                            name: component.name.clone(),
+                            total: prorated_amount,
+                            subtotal: prorated_amount,
+                            quantity: Some(Decimal::from(delta)),
+                            unit_price: Some(*unit_rate),
+                            start_date: remaining_period.start,
+                            end_date: remaining_period.end,
+                            sub_lines: vec![],
+                            is_prorated: true,
+                            price_component_id: Some(price_component_id),
+                            product_id: component.product_item_id,
+                            metric_id: None,
+                            description: Some(format!("{} {} added", delta, unit)),
+                            quantity_display_precision: None,
+                        };
+
+                        let invoice = off_cycle_invoice(
+                            &subscription,
+                            &customer,
+                            &invoicing_entity,
+                            &tenant_payment_terms,
+                            InvoiceType::OneOff,
+                            now.date(),
+                            vec![line],
+                        )?;
+
+                        invoice_id = Some(insert_invoice(conn, invoice).await?.id);
+                    }
+                }
+
+                Ok(SlotUpdate {
+                    current_value: new_current,
+                    next_period_value: new_next_period,
+                    period: period.clone(),
+                    prorated_amount,
+                    invoice_id,
+                })
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
+
+pub(crate) fn find_slot_component(
+    components: &[SubscriptionComponent],
+    price_component_id: Uuid,
+) -> StoreResult<&SubscriptionComponent> {
+    components
+        .iter()
+        .find(|c| {
+            c.price_component_id == Some(price_component_id)
+                && matches!(c.fee, SubscriptionFee::Slot { .. })
+        })
+        .ok_or(
+            StoreError::ValueNotFound(format!(
+                "No slot component for price component {}",
+                price_component_id
+            ))
+            .into(),
+        )
+}
+
+/// Active slots at a point in time. Without any transaction, the subscription is still on its initial slots
+pub(crate) async fn active_slots_at(
+    conn: &mut PgConn,
+    component: &SubscriptionComponent,
+    subscription_id: Uuid,
+    ts: chrono::NaiveDateTime,
+) -> StoreResult<u32> {
+    let price_component_id = component
+        .price_component_id
+        .ok_or(StoreError::InvalidArgument(
+            "Slot component without price component".to_string(),
+        ))?;
+
+    let res = SlotTransactionRow::fetch_by_subscription_id_and_price_component_id(
+        conn,
+        subscription_id,
+        price_component_id,
+        Some(ts),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    match (res, &component.fee) {
+        (Some(res), _) => Ok(res.current_active_slots.max(0) as u32),
+        (None, SubscriptionFee::Slot { initial_slots, .. }) => Ok(*initial_slots),
+        (None, _) => Err(StoreError::InvalidArgument("Not a slot component".to_string()).into()),
+    }
+}
+
+/// Added slots are active immediately, removed slots stay active until the end of the period.
+/// Returns the active slots, and the slots for the next period.
+fn apply_slot_delta(
+    current: u32,
+    next_period: u32,
+    delta: i32,
+    min_slots: Option<u32>,
+    max_slots: Option<u32>,
+) -> Result<(u32, u32), StoreError> {
+    let new_next_period = next_period as i64 + delta as i64;
+    let new_current = if delta > 0 {
+        current as i64 + delta as i64
+    } else {
+        current as i64
+    };
+
+    let min_slots = min_slots.unwrap_or(0);
+    if new_next_period < min_slots as i64 {
+        return Err(StoreError::InvalidArgument(format!(
+            "Slots cannot go below the minimum of {}",
+            min_slots
+        )));
     }
+
+    if let Some(max_slots) = max_slots {
+        if new_current > max_slots as i64 {
+            return Err(StoreError::InvalidArgument(format!(
+                "Slots cannot go above the maximum of {}",
+                max_slots
+            )));
+        }
+    }
+
+    Ok((new_current as u32, new_next_period as u32))
+}
+
+/// Invoice billed outside of the subscription cycle, like added slots or usage crossing the invoice threshold
+pub(crate) fn off_cycle_invoice(
+    subscription: &SubscriptionDetails,
+    customer: &Customer,
+    invoicing_entity: &InvoicingEntity,
+    tenant_payment_terms: &TenantPaymentTerms,
+    invoice_type: InvoiceType,
+    invoice_date: NaiveDate,
+    line_items: Vec<LineItem>,
+) -> StoreResult<InvoiceNew> {
+    let candidate = SubscriptionInvoiceCandidate {
+        id: subscription.id,
+        tenant_id: subscription.tenant_id,
+        customer_id: subscription.customer_id,
+        plan_version_id: subscription.plan_version_id,
+        plan_name: subscription.plan_name.clone(),
+        billing_start_date: subscription.billing_start_date,
+        billing_end_date: subscription.billing_end_date,
+        billing_day: subscription.billing_day,
+        activated_at: subscription.activated_at,
+        canceled_at: subscription.canceled_at,
+        currency: subscription.currency.clone(),
+        net_terms: subscription.net_terms as i32,
+        period: subscription.period.clone(),
+        billing_mode: subscription.billing_mode,
+    };
+
+    let draft = subscription_to_draft(
+        &candidate,
+        customer,
+        invoicing_entity,
+        tenant_payment_terms,
+        invoice_date,
+        None,
+    )?;
+
+    let payment_terms = PaymentTerms::resolve(
+        customer,
+        Some(subscription.net_terms),
+        tenant_payment_terms,
+        invoicing_entity.net_terms as u32,
+    );
+
+    let total = line_items.iter().map(|l| l.total).sum();
+
+    Ok(InvoiceNew {
+        invoice_type,
+        invoice_date,
+        due_at: Some(tenant_payment_terms.due_at(payment_terms.due_date(invoice_date))),
+        line_items,
+        subtotal: total,
+        total,
+        amount_due: total,
+        plan_name: Some(subscription.plan_name.clone()),
+        ..draft
+    })
 }
 
 #[async_trait::async_trait]
-impl SubscriptionInterface for Store {
-    async fn insert_subscription(
+pub trait SubscriptionUsageThresholdInterface {
+    /// Active subscriptions with an invoice threshold at that date
+    async fn list_usage_threshold_candidates(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<CreatedSubscription>>;
+
+    /// Invoices the usage accrued in the current period if it crosses the invoice threshold of the subscription.
+    /// The invoiced usage is then excluded from the end of period invoice.
+    /// Not invoiced while the customer is beyond a blocking credit limit.
+    async fn invoice_usage_threshold(
         &self,
-        params: CreateSubscription,
         tenant_id: Uuid,
-    ) -> StoreResult<CreatedSubscription> {
-        self.insert_subscription_batch(vec![params], tenant_id)
-            .await?
-            .pop()
-            .ok_or(StoreError::InsertError.into())
+        subscription_id: Uuid,
+        date: NaiveDate,
+    ) -> StoreResult<Option<Invoice>>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionUsageThresholdInterface for Store {
+    async fn list_usage_threshold_candidates(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<CreatedSubscription>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows =
+            SubscriptionRow::list_usage_threshold_candidates(&mut conn, date, pagination.into())
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: rows.items.into_iter().map(Into::into).collect(),
+            next_cursor: rows.next_cursor,
+        })
     }
 
-    async fn insert_subscription_batch(
+    async fn invoice_usage_threshold(
         &self,
-        batch: Vec<CreateSubscription>,
         tenant_id: Uuid,
-    ) -> StoreResult<Vec<CreatedSubscription>> {
-        let mut conn: PgConn = self.get_conn().await?;
+        subscription_id: Uuid,
+        date: NaiveDate,
+    ) -> StoreResult<Option<Invoice>> {
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        let Some(threshold) = subscription.invoice_threshold else {
+            return Ok(None);
+        };
 
-        struct DieselModelWrapper {
-            subscription: SubscriptionRowNew,
-            price_components: Vec<SubscriptionComponentRowNew>,
-            add_ons: Vec<SubscriptionAddOnRowNew>,
-            coupons: Vec<AppliedCouponRowNew>,
-            event: SubscriptionEventRow,
+        let precision = Currencies::resolve_currency_precision(&subscription.currency).ok_or(
+            StoreError::ValueNotFound(format!("Currency {} not found", subscription.currency)),
+        )?;
+
+        let threshold_cents =
+            threshold
+                .to_subunit_opt(precision)
+                .ok_or(StoreError::InvalidArgument(
+                    "Invalid invoice threshold".to_string(),
+                ))?;
+
+        if threshold_cents <= 0 {
+            return Ok(None);
         }
 
-        let plan_version_ids = batch
-            .iter()
-            .map(|c| c.subscription.plan_version_id)
-            .collect::<Vec<_>>();
+        let lines = self
+            .compute_accrued_usage_lines(&date, &subscription)
+            .await?;
 
-        let plan_names = get_plan_names_by_version_ids(&mut conn, plan_version_ids)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+        let accrued: i64 = lines.iter().map(|l| l.total).sum();
 
-        let db_price_components_by_plan_version = PriceComponentRow::get_by_plan_ids(
-            &mut conn,
-            &batch
-                .iter()
-                .map(|c| c.subscription.plan_version_id)
-                .collect::<Vec<_>>(),
-        )
-        .await
-        .map_err(Into::<Report<StoreError>>::into)?;
+        if accrued < threshold_cents {
+            return Ok(None);
+        }
 
-        let all_add_ons: Vec<AddOn> = AddOnRow::list_by_ids(
-            &mut conn,
-            &batch
-                .iter()
-                .filter_map(|x| x.add_ons.as_ref())
-                .flat_map(|x| &x.add_ons)
-                .map(|x| x.add_on_id)
-                .unique()
-                .collect::<Vec<_>>(),
-            &tenant_id,
-        )
-        .await
-        .map_err(Into::<Report<StoreError>>::into)
-        .and_then(|x| x.into_iter().map(TryInto::try_into).collect())?;
+        let customer = self
+            .find_customer_by_id(subscription.customer_id, tenant_id)
+            .await?;
 
-        let all_coupons: Vec<Coupon> = CouponRow::list_by_ids(
-            &mut conn,
-            &batch
-                .iter()
-                .filter_map(|x| x.coupons.as_ref())
-                .flat_map(|x| &x.coupons)
-                .map(|x| x.coupon_id)
-                .unique()
-                .collect::<Vec<_>>(),
-            &tenant_id,
-        )
-        .await
-        .map_err(Into::<Report<StoreError>>::into)
-        .and_then(|x| x.into_iter().map(TryInto::try_into).collect())?;
+        if customer.credit_limit.is_some() {
+            let exposure = self
+                .get_customer_credit_exposure(tenant_id, customer.id)
+                .await?;
+
+            let mut conn = self.get_conn().await?;
+
+            let allowed = self
+                .enforce_credit_limit(
+                    &mut conn,
+                    &customer,
+                    &exposure,
+                    CreditLimitSourceEnum::UsageThresholdInvoice,
+                    Some(subscription_id),
+                )
+                .await?;
+
+            // the usage stays in the end of period invoice
+            if !allowed {
+                return Ok(None);
+            }
+        }
 
-        let customer_ids = batch
-            .iter()
-            .map(|c| c.subscription.customer_id)
-            .unique()
-            .collect::<Vec<_>>();
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(customer.invoicing_entity_id))
+            .await?;
 
-        let customers = self
-            .list_customers_by_ids(customer_ids)
-            .await
-            .map_err(Into::<Report<StoreError>>::into)?;
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
 
-        let invoicing_entities = self.list_invoicing_entities(tenant_id).await?;
+        let invoice = off_cycle_invoice(
+            &subscription,
+            &customer,
+            &invoicing_entity,
+            &tenant_payment_terms,
+            InvoiceType::UsageThreshold,
+            date,
+            lines,
+        )?;
 
-        // map the price components thanks to .try_into
-        let price_components_by_plan_version: HashMap<Uuid, Vec<PriceComponent>> =
-            db_price_components_by_plan_version
-                .into_iter()
-                .map(|(k, v)| {
-                    let converted_vec: Result<Vec<PriceComponent>, _> =
-                        v.into_iter().map(|c| c.try_into()).collect();
-                    converted_vec.map(|vec| (k, vec))
-                })
-                .collect::<Result<HashMap<_, _>, _>>()?;
+        let threshold_invoiced_until = subscription.threshold_invoiced_until;
 
-        let mut insertable: Vec<DieselModelWrapper> = Vec::with_capacity(batch.len());
+        let inserted = self
+            .transaction(|conn| {
+                async move {
+                    SubscriptionRow::lock_subscription_for_update(conn, subscription_id)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
 
-        for params in batch {
-            let CreateSubscription {
-                subscription,
-                price_components,
-                add_ons,
-                coupons,
-            } = params;
+                    // another run already invoiced that usage
+                    let current: Subscription =
+                        SubscriptionRow::get_subscription_by_id(conn, &tenant_id, &subscription_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into();
 
-            // first we need to process the CreateSubscriptionComponents into Vec<SubscriptionComponentNew>
-            // we will need the plan version components
+                    if current.threshold_invoiced_until != threshold_invoiced_until {
+                        return Ok(None);
+                    }
 
-            let customer = customers
-                .iter()
-                .find(|c| c.id == subscription.customer_id)
-                .ok_or(StoreError::InsertError)?;
+                    let inserted = insert_invoice(conn, invoice).await?;
 
-            let subscription_currency = &subscription.currency.clone();
+                    SubscriptionRow::update_threshold_invoiced_until(
+                        conn,
+                        subscription_id,
+                        tenant_id,
+                        date,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
 
-            let precision = Currencies::resolve_currency_precision(subscription_currency)
-                .ok_or(StoreError::InsertError)?;
+                    Ok(Some(inserted))
+                }
+                .scope_boxed()
+            })
+            .await?;
 
-            let insertable_subscription_components = process_create_subscription_components(
-                &price_components,
-                &price_components_by_plan_version,
-                &subscription,
-            )?;
+        if let Some(inserted) = &inserted {
+            self.finalize_invoice(inserted.id, tenant_id, None).await?;
+        }
 
-            let insertable_subscription_add_ons =
-                process_create_subscription_add_ons(&add_ons, &all_add_ons)?;
+        Ok(inserted)
+    }
+}
 
-            // at this point we can know the period
-            let period = extract_billing_period(
-                &insertable_subscription_components,
-                &insertable_subscription_add_ons,
-            );
+#[async_trait::async_trait]
+pub trait SubscriptionBillingScheduleInterface {
+    /// Next `count` billing dates from `from` (included), with the expected amounts per component
+    async fn get_billing_schedule(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        from: NaiveDate,
+        count: u32,
+    ) -> StoreResult<Vec<BillingScheduleEntry>>;
+}
 
-            let should_activate = customer.billing_config != BillingConfig::Manual;
-            let insertable_subscription: SubscriptionRowNew =
-                subscription.map_to_row(period, should_activate, tenant_id);
+/// Upper bound of billing periods considered for a schedule, to stop on subscriptions with nothing left to bill
+const MAX_SCHEDULE_PERIODS: i32 = 240;
 
-            let insertable_subscription_coupons = process_create_subscription_coupons(
-                &insertable_subscription,
-                &coupons,
-                &all_coupons,
-            )?;
+#[async_trait::async_trait]
+impl SubscriptionBillingScheduleInterface for Store {
+    async fn get_billing_schedule(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        from: NaiveDate,
+        count: u32,
+    ) -> StoreResult<Vec<BillingScheduleEntry>> {
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
 
-            let cmrr = insertable_subscription_components
-                .iter()
-                .map(|c| calculate_mrr(&c.fee, &c.period, precision))
-                .sum::<i64>();
+        // the scheduled change applies from its effective date, as it will once applied by the draft worker
+        let mut conn = self.get_conn().await?;
 
-            let ao_mrr = insertable_subscription_add_ons
-                .iter()
-                .map(|c| calculate_mrr(&c.fee, &c.period, precision))
-                .sum::<i64>();
+        let pending_change: Option<SubscriptionPendingChange> =
+            SubscriptionPendingChangeRow::find_scheduled_by_subscription_id(
+                &mut conn,
+                tenant_id,
+                subscription_id,
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .map(TryInto::try_into)
+            .transpose()?;
+
+        let changed = match pending_change {
+            Some(pending_change) => {
+                let changed = subscription_after_change(
+                    &mut conn,
+                    &subscription,
+                    &pending_change.change,
+                    pending_change.effective_at,
+                )
+                .await?;
+                Some((pending_change.effective_at, changed))
+            }
+            None => None,
+        };
+
+        drop(conn);
+
+        // the trial runs until the billing start, its end is flagged on the first invoice from then on
+        let mut trial_end_pending = subscription
+            .trial_start_date
+            .is_some_and(|start| start < subscription.billing_start_date);
+
+        let mut entries = Vec::new();
 
-            let mrr_delta = cmrr + ao_mrr;
+        for period_idx in 0..MAX_SCHEDULE_PERIODS {
+            if entries.len() >= count as usize {
+                break;
+            }
 
-            let mrr_delta = calculate_coupons_discount(
-                self,
-                &all_coupons,
-                subscription_currency,
-                Decimal::from_i64(mrr_delta).unwrap_or(Decimal::ZERO),
+            // components of longer periods are billed on a subset of the monthly billing dates
+            let invoice_date = crate::utils::periods::calculate_period_range(
+                subscription.billing_start_date,
+                subscription.anchor_day(),
+                period_idx,
+                &BillingPeriodEnum::Monthly,
             )
-            .await?
-            .to_i64()
-            .unwrap_or(0);
+            .start;
 
-            let insertable_subscription_components = insertable_subscription_components
-                .into_iter()
-                .map(|c| SubscriptionComponentNew {
-                    subscription_id: insertable_subscription.id,
-                    internal: c,
-                })
-                .collect::<Vec<_>>();
+            let current = match &changed {
+                Some((effective_at, changed)) if invoice_date >= *effective_at => changed,
+                _ => &subscription,
+            };
 
-            let insertable_subscription_add_ons = insertable_subscription_add_ons
-                .into_iter()
-                .map(|internal| SubscriptionAddOnNew {
-                    subscription_id: insertable_subscription.id,
-                    internal,
-                })
-                .collect::<Vec<_>>();
+            if current
+                .billing_end_date
+                .is_some_and(|end| invoice_date > end)
+            {
+                break;
+            }
 
-            let insertable_event = SubscriptionEventRow {
-                id: Uuid::now_v7(),
-                subscription_id: insertable_subscription.id,
-                event_type: SubscriptionEventType::Created.into(),
-                details: None,
-                created_at: chrono::Utc::now().naive_utc(),
-                mrr_delta: Some(mrr_delta),
-                bi_mrr_movement_log_id: None,
-                applies_to: insertable_subscription.billing_start_date,
+            // the earlier dates are only computed to locate the end of the trial
+            if invoice_date < from && !trial_end_pending {
+                continue;
+            }
+
+            let lines = self
+                .compute_dated_invoice_lines(&invoice_date, current)
+                .await?;
+
+            if lines.is_empty() {
+                continue;
+            }
+
+            let is_trial_end = std::mem::take(&mut trial_end_pending);
+
+            if invoice_date < from {
+                continue;
+            }
+
+            let subtotal: i64 = lines.iter().map(|l| l.total).sum();
+
+            let months_elapsed =
+                ramp_months_elapsed(current.billing_start_date, current.anchor_day(), period_idx);
+
+            let (ramp_discount, total) = match ramp_adjustment_at(current, months_elapsed) {
+                Some(ramp) => {
+                    let discount = ramp_discount(ramp, subtotal);
+                    let total = (subtotal - discount).max(ramp.minimum.value_in_cents as i64);
+                    (discount, total)
+                }
+                None => (0, subtotal),
             };
 
-            insertable.push(DieselModelWrapper {
-                subscription: insertable_subscription,
-                price_components: insertable_subscription_components
-                    .into_iter()
-                    .map(|c| c.try_into())
-                    .collect::<Result<Vec<_>, _>>()?,
-                add_ons: insertable_subscription_add_ons
-                    .into_iter()
-                    .map(|c| c.try_into())
-                    .collect::<Result<Vec<_>, _>>()?,
-                coupons: insertable_subscription_coupons,
-                event: insertable_event,
-            })
+            entries.push(BillingScheduleEntry {
+                invoice_date,
+                lines,
+                subtotal,
+                ramp_discount,
+                total,
+                is_trial_end,
+            });
         }
 
-        let insertable_subscription_components = insertable
-            .iter()
-            .flat_map(|c| c.price_components.iter())
-            .collect::<Vec<_>>();
+        Ok(entries)
+    }
+}
 
-        let insertable_subscription_add_ons = insertable
-            .iter()
-            .flat_map(|c| c.add_ons.iter())
-            .collect::<Vec<_>>();
+/// Months elapsed since the start of the first full billing period, at the `period_idx` monthly billing date.
+/// A partial first period precedes the first full one and shares its ramp
+fn ramp_months_elapsed(billing_start_date: NaiveDate, anchor_day: u32, period_idx: i32) -> i32 {
+    let first_period_is_partial = crate::utils::periods::calculate_periods_for_date(
+        billing_start_date,
+        anchor_day,
+        billing_start_date,
+        &BillingPeriodEnum::Monthly,
+    )
+    .proration_factor
+    .is_some();
 
-        let insertable_subscription_coupons = insertable
-            .iter()
-            .flat_map(|c| c.coupons.iter())
-            .collect::<Vec<_>>();
+    if first_period_is_partial {
+        (period_idx - 1).max(0)
+    } else {
+        period_idx
+    }
+}
+
+/// Ramp of the subscription schedule covering the given month since the first full billing period
+fn ramp_adjustment_at(
+    subscription: &SubscriptionDetails,
+    months_elapsed: i32,
+) -> Option<&PlanRampAdjustment> {
+    let schedule = subscription
+        .schedules
+        .iter()
+        .find(|s| s.billing_period == subscription.period)?;
+
+    let mut ramp_start = 0;
+    for ramp in schedule.ramps.ramps.iter().sorted_by_key(|r| r.index) {
+        match ramp.duration_in_months {
+            Some(duration) => {
+                let ramp_end = ramp_start + duration as i32;
+                if months_elapsed < ramp_end {
+                    return Some(&ramp.ramp_adjustment);
+                }
+                ramp_start = ramp_end;
+            }
+            // the last ramp has no end
+            None => return Some(&ramp.ramp_adjustment),
+        }
+    }
 
-        let insertable_subscriptions = insertable.iter().map(|c| &c.subscription).collect();
+    None
+}
 
-        let insertable_subscription_events: Vec<&SubscriptionEventRow> =
-            insertable.iter().map(|c| &c.event).collect();
+fn ramp_discount(ramp: &PlanRampAdjustment, subtotal: i64) -> i64 {
+    let discount = match &ramp.discount {
+        StandardDiscount::Amount(amount) => amount.value_in_cents as i64,
+        StandardDiscount::Percent(percent) => (Decimal::from(subtotal) * percent.percentage
+            / Decimal::ONE_HUNDRED)
+            .round_dp(0)
+            .to_i64()
+            .unwrap_or(0),
+    };
 
-        let inserted_subscriptions = conn
-            .transaction(|conn| {
-                async move {
-                    let inserted_subscriptions: Vec<CreatedSubscription> =
-                        SubscriptionRow::insert_subscription_batch(conn, insertable_subscriptions)
-                            .await
-                            .map_err(Into::<DatabaseErrorContainer>::into)
-                            .map(|v| v.into_iter().map(Into::into).collect())?;
+    discount.min(subtotal).max(0)
+}
+
+#[async_trait::async_trait]
+pub trait SubscriptionTermInterface {
+    /// Extends or shortens the subscription term. `None` removes the end date, lifting a pending cancellation
+    async fn update_subscription_term(
+        &self,
+        subscription_id: Uuid,
+        billing_end_date: Option<NaiveDate>,
+        context: domain::TenantContext,
+    ) -> StoreResult<Subscription>;
+}
+
+#[async_trait::async_trait]
+impl SubscriptionTermInterface for Store {
+    async fn update_subscription_term(
+        &self,
+        subscription_id: Uuid,
+        billing_end_date: Option<NaiveDate>,
+        context: domain::TenantContext,
+    ) -> StoreResult<Subscription> {
+        let subscription = self
+            .get_subscription_details(context.tenant_id, subscription_id)
+            .await?;
+
+        let previous_end_date = subscription.billing_end_date;
+
+        if previous_end_date == billing_end_date {
+            return Err(
+                StoreError::InvalidArgument("billing end date is unchanged".to_string()).into(),
+            );
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let invoices: Vec<Invoice> =
+            InvoiceRow::list_by_subscription_id(&mut conn, context.tenant_id, subscription_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(|s| s.try_into())
+                .collect::<Result<Vec<_>, _>>()?;
+
+        if let Some(end_date) = billing_end_date {
+            if end_date <= subscription.billing_start_date {
+                return Err(StoreError::InvalidArgument(
+                    "billing end date must be after the billing start date".to_string(),
+                )
+                .into());
+            }
+
+            if end_date < chrono::Utc::now().date_naive() {
+                return Err(StoreError::InvalidArgument(
+                    "billing end date cannot be in the past".to_string(),
+                )
+                .into());
+            }
+
+            // periods already billed to the customer cannot be taken back by the new term
+            let invoiced_until = invoices
+                .iter()
+                .filter(|i| i.status == InvoiceStatusEnum::Finalized)
+                .flat_map(|i| i.line_items.iter().map(|l| l.end_date))
+                .chain(subscription.threshold_invoiced_until)
+                .max();
+
+            if let Some(invoiced_until) = invoiced_until {
+                if end_date < invoiced_until {
+                    return Err(StoreError::InvalidArgument(format!(
+                        "billing end date cannot be before {}, already invoiced",
+                        invoiced_until
+                    ))
+                    .into());
+                }
+            }
+        }
+
+        let mrr = subscription.mrr_cents as i64;
 
-                    SubscriptionComponentRow::insert_subscription_component_batch(
+        let db_subscription = self
+            .transaction_with(&mut conn, |conn| {
+                async move {
+                    SubscriptionRow::update_billing_end_date(
                         conn,
-                        insertable_subscription_components,
+                        subscription_id,
+                        context.tenant_id,
+                        billing_end_date,
                     )
                     .await
-                    .map_err(Into::<DatabaseErrorContainer>::into)?;
+                    .map_err(Into::<Report<StoreError>>::into)?;
 
-                    SubscriptionAddOnRow::insert_batch(conn, insertable_subscription_add_ons)
+                    if let Some(end_date) = billing_end_date {
+                        InvoiceRow::void_subscription_drafts_after(
+                            conn,
+                            context.tenant_id,
+                            subscription_id,
+                            end_date,
+                        )
                         .await
-                        .map_err(Into::<DatabaseErrorContainer>::into)?;
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                    }
 
-                    apply_coupons(
-                        conn,
-                        &insertable_subscription_coupons,
-                        &inserted_subscriptions,
-                        tenant_id,
-                    )
-                    .await?;
+                    let details = serde_json::json!({
+                        "previous_billing_end_date": previous_end_date,
+                        "billing_end_date": billing_end_date,
+                    });
 
-                    SubscriptionEventRow::insert_batch(conn, insertable_subscription_events)
-                        .await
-                        .map_err(Into::<DatabaseErrorContainer>::into)?;
+                    let mut events = vec![];
+
+                    // the churn planned at the previous end no longer happens
+                    if let Some(previous_end_date) = previous_end_date {
+                        let planned_churn =
+                            SubscriptionEventRow::fetch_by_subscription_id_and_date(
+                                conn,
+                                subscription_id,
+                                previous_end_date,
+                            )
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .into_iter()
+                            .any(|e| {
+                                matches!(
+                                    e.event_type,
+                                    diesel_models::enums::SubscriptionEventType::Cancelled
+                                ) && e.bi_mrr_movement_log_id.is_none()
+                            });
+
+                        if planned_churn {
+                            events.push(SubscriptionEventRow {
+                                id: Uuid::now_v7(),
+                                subscription_id,
+                                event_type: SubscriptionEventType::Reactivated.into(),
+                                details: Some(details.clone()),
+                                created_at: chrono::Utc::now().naive_utc(),
+                                mrr_delta: Some(mrr),
+                                bi_mrr_movement_log_id: None,
+                                applies_to: previous_end_date,
+                            });
+                        }
+                    }
+
+                    if let Some(end_date) = billing_end_date {
+                        events.push(SubscriptionEventRow {
+                            id: Uuid::now_v7(),
+                            subscription_id,
+                            event_type: SubscriptionEventType::Cancelled.into(),
+                            details: Some(details),
+                            created_at: chrono::Utc::now().naive_utc(),
+                            mrr_delta: Some(-mrr),
+                            bi_mrr_movement_log_id: None,
+                            applies_to: end_date,
+                        });
+                    }
 
-                    Ok::<_, DatabaseErrorContainer>(inserted_subscriptions)
+                    for event in events {
+                        event
+                            .insert(conn)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+
+                    SubscriptionRow::get_subscription_by_id(
+                        conn,
+                        &context.tenant_id,
+                        &subscription_id,
+                    )
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)
                 }
                 .scope_boxed()
             })
             .await?;
 
+        // remaining drafts are recomputed against the new term
+        for invoice in invoices.iter().filter(|i| {
+            i.status == InvoiceStatusEnum::Draft
+                && billing_end_date.map_or(true, |end| i.invoice_date <= end)
+        }) {
+            self.refresh_invoice_data(invoice.id, context.tenant_id)
+                .await?;
+        }
+
+        let subscription: Subscription = db_subscription.into();
+
+        let _ = self
+            .eventbus
+            .publish(Event::subscription_term_updated(
+                context.actor,
+                subscription.id,
+                subscription.tenant_id,
+            ))
+            .await;
+
+        invalidate_customer_entitlements_cache(subscription.tenant_id, subscription.customer_id)
+            .await;
+
+        Ok(subscription)
+    }
+}
+
+#[async_trait::async_trait]
+impl SubscriptionInterface for Store {
+    async fn insert_subscription(
+        &self,
+        params: CreateSubscription,
+        tenant_id: Uuid,
+    ) -> StoreResult<CreatedSubscription> {
+        self.insert_subscription_batch(vec![params], tenant_id)
+            .await?
+            .pop()
+            .ok_or(StoreError::InsertError.into())
+    }
+
+    async fn insert_subscription_batch(
+        &self,
+        batch: Vec<CreateSubscription>,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<CreatedSubscription>> {
+        let mut conn: PgConn = self.get_conn().await?;
+
+        let context = load_batch_context(self, &mut conn, &batch, tenant_id).await?;
+
+        let mut insertable: Vec<DieselModelWrapper> = Vec::with_capacity(batch.len());
+
+        for params in batch {
+            insertable.push(prepare_subscription(self, params, &context, tenant_id).await?);
+        }
+
+        let inserted_subscriptions = conn
+            .transaction(|conn| {
+                async move { insert_prepared_subscriptions(conn, &insertable, tenant_id).await }
+                    .scope_boxed()
+            })
+            .await?;
+
         // we now want to insert the invoices, ONLY for manual providers
-        let insertable_invoices = inserted_subscriptions
-            .iter()
-            .filter(|s| s.activated_at.is_some())
-            .map(|s| {
-                let customer = customers
-                    .iter()
-                    .find(|c| c.id == s.customer_id)
-                    .ok_or(StoreError::InsertError)?;
-
-                let invoicing_entity = invoicing_entities
-                    .iter()
-                    .find(|c| c.id == customer.invoicing_entity_id)
-                    .ok_or(StoreError::InsertError)?;
-
-                match customer.billing_config {
-                    BillingConfig::Stripe(_) => Ok(None),
-                    BillingConfig::Manual => {
-                        let plan_name = plan_names
-                            .get(&s.plan_version_id)
-                            .ok_or(StoreError::InsertError)?;
-
-                        let sub = SubscriptionInvoiceCandidate {
-                            id: s.id,
-                            tenant_id: s.tenant_id,
-                            customer_id: s.customer_id,
-                            plan_version_id: s.plan_version_id,
-                            billing_start_date: s.billing_start_date,
-                            billing_end_date: s.billing_end_date,
-                            billing_day: s.billing_day,
-                            activated_at: s.activated_at,
-                            canceled_at: s.canceled_at,
-                            currency: s.currency.clone(),
-                            net_terms: s.net_terms,
-                            plan_name: plan_name.clone(),
-                            period: s.period.clone(),
-                        };
+        let insertable_invoices = draft_initial_invoices(&inserted_subscriptions, &context)?;
+
+        // not in transaction, make sure the draft worker can pick them up
+        self.insert_invoice_batch(insertable_invoices).await?;
 
-                        subscription_to_draft(&sub, customer, invoicing_entity).map(Some)
+        publish_subscriptions_created(self, &inserted_subscriptions).await;
+
+        for subscription in &inserted_subscriptions {
+            invalidate_customer_entitlements_cache(
+                subscription.tenant_id,
+                subscription.customer_id,
+            )
+            .await;
+        }
+
+        Ok(inserted_subscriptions)
+    }
+
+    async fn create_subscriptions_batch(
+        &self,
+        batch: Vec<CreateSubscription>,
+        tenant_id: Uuid,
+    ) -> StoreResult<CreatedSubscriptionsBatch> {
+        let mut conn: PgConn = self.get_conn().await?;
+
+        let context = load_batch_context(self, &mut conn, &batch, tenant_id).await?;
+
+        let mut insertable: Vec<DieselModelWrapper> = Vec::with_capacity(batch.len());
+        let mut index_by_subscription_id: HashMap<Uuid, usize> = HashMap::new();
+        let mut failures: Vec<SubscriptionBatchFailure> = vec![];
+
+        for (index, params) in batch.into_iter().enumerate() {
+            match prepare_subscription(self, params, &context, tenant_id).await {
+                Ok(prepared) => {
+                    index_by_subscription_id.insert(prepared.subscription.id, index);
+                    insertable.push(prepared);
+                }
+                Err(e) => failures.push(SubscriptionBatchFailure {
+                    index,
+                    reason: batch_failure_reason(&e),
+                }),
+            }
+        }
+
+        if insertable.is_empty() {
+            return Ok(CreatedSubscriptionsBatch {
+                created: vec![],
+                failures,
+            });
+        }
+
+        // subscriptions and their initial invoices are committed together, so a retry of the batch cannot duplicate invoices
+        let (inserted_subscriptions, insert_failures) = self
+            .transaction_with(&mut conn, |conn| {
+                async move {
+                    let context = &context;
+                    let mut inserted_subscriptions = Vec::with_capacity(insertable.len());
+                    let mut insert_failures: Vec<(Uuid, String)> = vec![];
+
+                    // keeps each statement below the postgres bind parameters limit
+                    for chunk in insertable.chunks(BATCH_INSERT_CHUNK_SIZE) {
+                        match insert_batch_items_savepoint(conn, chunk, context, tenant_id).await {
+                            Ok(inserted) => inserted_subscriptions.extend(inserted),
+                            // one item in error rolls back its chunk, so the items are retried
+                            // one by one to only report the failing ones
+                            Err(_) => {
+                                for item in chunk {
+                                    match insert_batch_items_savepoint(
+                                        conn,
+                                        std::slice::from_ref(item),
+                                        context,
+                                        tenant_id,
+                                    )
+                                    .await
+                                    {
+                                        Ok(inserted) => inserted_subscriptions.extend(inserted),
+                                        Err(e) => insert_failures.push((
+                                            item.subscription.id,
+                                            batch_failure_reason(&report!(e)),
+                                        )),
+                                    }
+                                }
+                            }
+                        }
                     }
+
+                    Ok((inserted_subscriptions, insert_failures))
                 }
+                .scope_boxed()
             })
-            .collect::<Result<Vec<Option<_>>, _>>()?
-            .into_iter()
-            .flatten()
-            .collect::<Vec<_>>();
+            .await?;
+
+        for (subscription_id, reason) in insert_failures {
+            let index = index_by_subscription_id
+                .get(&subscription_id)
+                .ok_or(StoreError::InsertError)?;
+            failures.push(SubscriptionBatchFailure {
+                index: *index,
+                reason,
+            });
+        }
+        failures.sort_by_key(|f| f.index);
 
-        // not in transaction, make sure the draft worker can pick them up
-        self.insert_invoice_batch(insertable_invoices).await?;
+        publish_subscriptions_created(self, &inserted_subscriptions).await;
 
-        let _ = futures::future::join_all(inserted_subscriptions.clone().into_iter().map(|res| {
-            self.eventbus.publish(Event::subscription_created(
-                res.created_by,
-                res.id,
-                res.tenant_id,
-            ))
-        }))
-        .await
-        .into_iter()
-        .collect::<Result<Vec<_>, _>>();
+        for subscription in &inserted_subscriptions {
+            invalidate_customer_entitlements_cache(
+                subscription.tenant_id,
+                subscription.customer_id,
+            )
+            .await;
+        }
 
-        Ok(inserted_subscriptions)
+        let created = inserted_subscriptions
+            .into_iter()
+            .map(|s| {
+                index_by_subscription_id
+                    .get(&s.id)
+                    .map(|index| (*index, s))
+                    .ok_or(StoreError::InsertError)
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(CreatedSubscriptionsBatch { created, failures })
     }
 
     /// todo parallelize db calls
@@ -631,6 +1405,14 @@ impl SubscriptionInterface for Store {
                 .map(|s| s.try_into())
                 .collect::<Result<Vec<_>, _>>()?;
 
+        let phases: Vec<SubscriptionPhase> =
+            SubscriptionPhaseRow::list_by_subscription_id(&mut conn, tenant_id, subscription_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(|s| s.try_into())
+                .collect::<Result<Vec<_>, _>>()?;
+
         let billable_metrics: Vec<BillableMetric> =
             BillableMetricRow::get_by_ids(&mut conn, &metric_ids, &subscription.tenant_id)
                 .await
@@ -653,6 +1435,7 @@ impl SubscriptionInterface for Store {
             price_components: subscription_components,
             add_ons: subscription_add_ons,
             applied_coupons,
+            phases,
             metrics: billable_metrics,
             mrr_cents: subscription.mrr_cents,
             version: subscription.version,
@@ -669,6 +1452,9 @@ impl SubscriptionInterface for Store {
             created_by: subscription.created_by,
             trial_start_date: subscription.trial_start_date,
             period: subscription.period,
+            threshold_invoiced_until: subscription.threshold_invoiced_until,
+            billing_mode: subscription.billing_mode,
+            minimum_commitment: subscription.minimum_commitment,
         })
     }
 
@@ -778,6 +1564,9 @@ impl SubscriptionInterface for Store {
             ))
             .await;
 
+        invalidate_customer_entitlements_cache(subscription.tenant_id, subscription.customer_id)
+            .await;
+
         Ok(subscription)
     }
 
@@ -816,13 +1605,20 @@ impl SubscriptionInterface for Store {
     async fn list_subscription_invoice_candidates(
         &self,
         date: NaiveDate,
+        billing_timezones: &[Tz],
         pagination: CursorPaginationRequest,
     ) -> StoreResult<CursorPaginatedVec<SubscriptionInvoiceCandidate>> {
         let mut conn = self.get_conn().await?;
 
+        let billing_timezones = billing_timezones
+            .iter()
+            .map(|tz| tz.name().to_string())
+            .collect::<Vec<_>>();
+
         let db_subscriptions = SubscriptionRow::list_subscription_to_invoice_candidates(
             &mut conn,
             date,
+            &billing_timezones,
             pagination.into(),
         )
         .await
@@ -839,9 +1635,613 @@ impl SubscriptionInterface for Store {
 
         Ok(res)
     }
+
+    async fn draft_recurring_invoices(
+        &self,
+        subscription: &SubscriptionInvoiceCandidate,
+        customer: &Customer,
+        invoicing_entity: &InvoicingEntity,
+        date: NaiveDate,
+    ) -> StoreResult<Vec<InvoiceNew>> {
+        let grouping = self
+            .get_invoice_cadence_grouping_by_tenant_id(subscription.tenant_id)
+            .await?;
+
+        let tenant_payment_terms = self
+            .get_payment_terms_by_tenant_id(subscription.tenant_id)
+            .await?;
+
+        if grouping == InvoiceCadenceGroupingEnum::Combined {
+            return subscription_to_draft(
+                subscription,
+                customer,
+                invoicing_entity,
+                &tenant_payment_terms,
+                date,
+                None,
+            )
+            .map(|draft| vec![draft]);
+        }
+
+        let details = self
+            .get_subscription_details(subscription.tenant_id, subscription.id)
+            .await?;
+
+        let mut conn = self.get_conn().await?;
+
+        let drafted = InvoiceRow::list_future_recurring_cadences(
+            &mut conn,
+            subscription.tenant_id,
+            subscription.id,
+            date,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(|(cadence, months)| cadence.map(|c| BillingPeriodEnum::from_row(c, months)))
+        .collect::<Vec<_>>();
+
+        missing_cadences(details.invoice_cadences(grouping), &drafted)
+            .into_iter()
+            .map(|cadence| {
+                subscription_to_draft(
+                    subscription,
+                    customer,
+                    invoicing_entity,
+                    &tenant_payment_terms,
+                    date,
+                    cadence,
+                )
+            })
+            .collect()
+    }
+}
+
+const BATCH_INSERT_CHUNK_SIZE: usize = 500;
+
+struct DieselModelWrapper {
+    subscription: SubscriptionRowNew,
+    price_components: Vec<SubscriptionComponentRowNew>,
+    add_ons: Vec<SubscriptionAddOnRowNew>,
+    coupons: Vec<AppliedCouponRowNew>,
+    phases: Vec<SubscriptionPhaseRowNew>,
+    event: SubscriptionEventRow,
+}
+
+/// Data shared by all the subscriptions of a batch, loaded once
+struct SubscriptionBatchContext {
+    plan_names: HashMap<Uuid, String>,
+    trial_modes: HashMap<Uuid, TrialModeEnum>,
+    price_components_by_plan_version: HashMap<Uuid, Vec<PriceComponent>>,
+    all_add_ons: Vec<AddOn>,
+    all_coupons: Vec<Coupon>,
+    customers: Vec<Customer>,
+    invoicing_entities: Vec<InvoicingEntity>,
+    payment_terms: TenantPaymentTerms,
+    component_rules: Vec<ComponentRule>,
+    /// of the customers with a credit limit
+    credit_exposures: HashMap<Uuid, CreditExposure>,
+}
+
+async fn load_batch_context(
+    store: &Store,
+    conn: &mut PgConn,
+    batch: &[CreateSubscription],
+    tenant_id: Uuid,
+) -> StoreResult<SubscriptionBatchContext> {
+    let plan_version_ids = batch
+        .iter()
+        .map(|c| c.subscription.plan_version_id)
+        .collect::<Vec<_>>();
+
+    let plan_names = get_plan_names_by_version_ids(conn, plan_version_ids.clone())
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let trial_modes = get_trial_modes_by_version_ids(conn, plan_version_ids)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into_iter()
+        .map(|(id, mode)| (id, mode.into()))
+        .collect();
+
+    let db_price_components_by_plan_version = PriceComponentRow::get_by_plan_ids(
+        conn,
+        &batch
+            .iter()
+            .map(|c| c.subscription.plan_version_id)
+            .collect::<Vec<_>>(),
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)?;
+
+    let all_add_ons: Vec<AddOn> = AddOnRow::list_by_ids(
+        conn,
+        &batch
+            .iter()
+            .filter_map(|x| x.add_ons.as_ref())
+            .flat_map(|x| &x.add_ons)
+            .map(|x| x.add_on_id)
+            .unique()
+            .collect::<Vec<_>>(),
+        &tenant_id,
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)
+    .and_then(|x| x.into_iter().map(TryInto::try_into).collect())?;
+
+    let all_coupons: Vec<Coupon> = CouponRow::list_by_ids(
+        conn,
+        &batch
+            .iter()
+            .filter_map(|x| x.coupons.as_ref())
+            .flat_map(|x| &x.coupons)
+            .map(|x| x.coupon_id)
+            .unique()
+            .collect::<Vec<_>>(),
+        &tenant_id,
+    )
+    .await
+    .map_err(Into::<Report<StoreError>>::into)
+    .and_then(|x| x.into_iter().map(TryInto::try_into).collect())?;
+
+    let customer_ids = batch
+        .iter()
+        .map(|c| c.subscription.customer_id)
+        .unique()
+        .collect::<Vec<_>>();
+
+    let customers = store
+        .list_customers_by_ids(customer_ids)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    let invoicing_entities = store.list_invoicing_entities(tenant_id).await?;
+
+    let payment_terms: TenantPaymentTerms = TenantRow::get_payment_terms_by_id(conn, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?
+        .into();
+
+    let component_rules = load_component_rules(conn, tenant_id).await?;
+
+    let credit_exposures = list_credit_exposures(
+        conn,
+        tenant_id,
+        customers
+            .iter()
+            .filter(|c| c.credit_limit.is_some())
+            .map(|c| c.id)
+            .collect(),
+    )
+    .await?;
+
+    // map the price components thanks to .try_into
+    let price_components_by_plan_version: HashMap<Uuid, Vec<PriceComponent>> =
+        db_price_components_by_plan_version
+            .into_iter()
+            .map(|(k, v)| {
+                let converted_vec: Result<Vec<PriceComponent>, _> =
+                    v.into_iter().map(|c| c.try_into()).collect();
+                converted_vec.map(|vec| (k, vec))
+            })
+            .collect::<Result<HashMap<_, _>, _>>()?;
+
+    Ok(SubscriptionBatchContext {
+        plan_names,
+        trial_modes,
+        price_components_by_plan_version,
+        all_add_ons,
+        all_coupons,
+        customers,
+        invoicing_entities,
+        payment_terms,
+        component_rules,
+        credit_exposures,
+    })
+}
+
+async fn prepare_subscription(
+    store: &Store,
+    params: CreateSubscription,
+    context: &SubscriptionBatchContext,
+    tenant_id: Uuid,
+) -> StoreResult<DieselModelWrapper> {
+    let CreateSubscription {
+        subscription,
+        price_components,
+        add_ons,
+        coupons,
+        phases,
+    } = params;
+
+    subscription.validate()?;
+
+    // first we need to process the CreateSubscriptionComponents into Vec<SubscriptionComponentNew>
+    // we will need the plan version components
+
+    let customer = context
+        .customers
+        .iter()
+        .find(|c| c.id == subscription.customer_id)
+        .ok_or(StoreError::InsertError)?;
+
+    let subscription_currency = &subscription.currency.clone();
+
+    let precision = Currencies::resolve_currency_precision(subscription_currency)
+        .ok_or(StoreError::InsertError)?;
+
+    let insertable_subscription_components = process_create_subscription_components(
+        &price_components,
+        &context.price_components_by_plan_version,
+        &subscription.plan_version_id,
+    )?;
+
+    let insertable_subscription_add_ons =
+        process_create_subscription_add_ons(&add_ons, &context.all_add_ons)?;
+
+    let rule_items = insertable_subscription_components
+        .iter()
+        .filter_map(|c| c.price_component_id.map(ComponentRuleItem::PriceComponent))
+        .chain(
+            insertable_subscription_add_ons
+                .iter()
+                .map(|a| ComponentRuleItem::AddOn(a.add_on_id)),
+        )
+        .collect::<Vec<_>>();
+
+    validate_component_rules(&context.component_rules, &rule_items)?;
+
+    let plan_price_component_ids = insertable_subscription_components
+        .iter()
+        .filter_map(|c| c.price_component_id)
+        .collect::<Vec<_>>();
+
+    for phase in &phases {
+        phase.validate(&plan_price_component_ids)?;
+    }
+
+    // at this point we can know the period
+    let period = extract_billing_period(
+        &insertable_subscription_components,
+        &insertable_subscription_add_ons,
+    );
+
+    let in_trial = subscription.trial_start_date.is_some();
+
+    let trial_mode = context
+        .trial_modes
+        .get(&subscription.plan_version_id)
+        .copied()
+        .unwrap_or_default();
+
+    if in_trial
+        && trial_mode == TrialModeEnum::CardRequired
+        && !customer.billing_config.has_payment_method()
+    {
+        return Err(StoreError::InvalidArgument(
+            "the trial of this plan requires a customer with a payment method".to_string(),
+        )
+        .into());
+    }
+
+    // a trial is activated when it ends (card required) or when its first invoice is paid
+    let should_activate = customer.billing_config != BillingConfig::Manual && !in_trial;
+    let insertable_subscription: SubscriptionRowNew =
+        subscription.map_to_row(period, should_activate, tenant_id);
+
+    if let Some(limit) = &customer.credit_limit {
+        let exposure = context
+            .credit_exposures
+            .get(&customer.id)
+            .cloned()
+            .unwrap_or(CreditExposure::none(customer.id));
+
+        let mut conn = store.get_conn().await?;
+
+        let allowed = store
+            .enforce_credit_limit(
+                &mut conn,
+                customer,
+                &exposure,
+                CreditLimitSourceEnum::Subscription,
+                Some(insertable_subscription.id),
+            )
+            .await?;
+
+        if !allowed {
+            return Err(StoreError::CreditLimitExceeded {
+                customer_id: customer.id,
+                exposure: exposure.total(),
+                credit_limit: limit.cents,
+            }
+            .into());
+        }
+    }
+
+    let insertable_subscription_coupons = process_create_subscription_coupons(
+        &insertable_subscription,
+        &coupons,
+        &context.all_coupons,
+    )?;
+
+    let insertable_subscription_phases = phases
+        .into_iter()
+        .map(|p| p.into_row(tenant_id, insertable_subscription.id))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let cmrr = insertable_subscription_components
+        .iter()
+        .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+        .sum::<i64>();
+
+    let ao_mrr = insertable_subscription_add_ons
+        .iter()
+        .map(|c| calculate_mrr(&c.fee, &c.period, precision))
+        .sum::<i64>();
+
+    let mrr_delta = cmrr + ao_mrr;
+
+    let mrr_delta = calculate_coupons_discount(
+        store,
+        &context.all_coupons,
+        subscription_currency,
+        Decimal::from_i64(mrr_delta).unwrap_or(Decimal::ZERO),
+    )
+    .await?
+    .to_i64()
+    .unwrap_or(0);
+
+    let insertable_subscription_components = insertable_subscription_components
+        .into_iter()
+        .map(|c| SubscriptionComponentNew {
+            subscription_id: insertable_subscription.id,
+            internal: c,
+        })
+        .collect::<Vec<_>>();
+
+    let insertable_subscription_add_ons = insertable_subscription_add_ons
+        .into_iter()
+        .map(|internal| SubscriptionAddOnNew {
+            subscription_id: insertable_subscription.id,
+            internal,
+        })
+        .collect::<Vec<_>>();
+
+    let insertable_event = SubscriptionEventRow {
+        id: Uuid::now_v7(),
+        subscription_id: insertable_subscription.id,
+        event_type: SubscriptionEventType::Created.into(),
+        details: None,
+        created_at: chrono::Utc::now().naive_utc(),
+        mrr_delta: Some(mrr_delta),
+        bi_mrr_movement_log_id: None,
+        applies_to: insertable_subscription.billing_start_date,
+    };
+
+    Ok(DieselModelWrapper {
+        subscription: insertable_subscription,
+        price_components: insertable_subscription_components
+            .into_iter()
+            .map(|c| c.try_into())
+            .collect::<Result<Vec<_>, _>>()?,
+        add_ons: insertable_subscription_add_ons
+            .into_iter()
+            .map(|c| c.try_into())
+            .collect::<Result<Vec<_>, _>>()?,
+        coupons: insertable_subscription_coupons,
+        phases: insertable_subscription_phases,
+        event: insertable_event,
+    })
+}
+
+async fn insert_prepared_subscriptions(
+    conn: &mut PgConn,
+    insertable: &[DieselModelWrapper],
+    tenant_id: Uuid,
+) -> Result<Vec<CreatedSubscription>, DatabaseErrorContainer> {
+    let insertable_subscription_components = insertable
+        .iter()
+        .flat_map(|c| c.price_components.iter())
+        .collect::<Vec<_>>();
+
+    let insertable_subscription_add_ons = insertable
+        .iter()
+        .flat_map(|c| c.add_ons.iter())
+        .collect::<Vec<_>>();
+
+    let insertable_subscription_coupons = insertable
+        .iter()
+        .flat_map(|c| c.coupons.iter())
+        .collect::<Vec<_>>();
+
+    let insertable_subscription_phases = insertable
+        .iter()
+        .flat_map(|c| c.phases.iter())
+        .collect::<Vec<_>>();
+
+    let insertable_subscriptions = insertable.iter().map(|c| &c.subscription).collect();
+
+    let insertable_subscription_events: Vec<&SubscriptionEventRow> =
+        insertable.iter().map(|c| &c.event).collect();
+
+    let inserted_subscriptions: Vec<CreatedSubscription> =
+        SubscriptionRow::insert_subscription_batch(conn, insertable_subscriptions)
+            .await
+            .map_err(Into::<DatabaseErrorContainer>::into)
+            .map(|v| v.into_iter().map(Into::into).collect())?;
+
+    SubscriptionComponentRow::insert_subscription_component_batch(
+        conn,
+        insertable_subscription_components,
+    )
+    .await
+    .map_err(Into::<DatabaseErrorContainer>::into)?;
+
+    SubscriptionAddOnRow::insert_batch(conn, insertable_subscription_add_ons)
+        .await
+        .map_err(Into::<DatabaseErrorContainer>::into)?;
+
+    SubscriptionPhaseRow::insert_batch(conn, insertable_subscription_phases)
+        .await
+        .map_err(Into::<DatabaseErrorContainer>::into)?;
+
+    apply_coupons(
+        conn,
+        &insertable_subscription_coupons,
+        &inserted_subscriptions,
+        tenant_id,
+    )
+    .await?;
+
+    SubscriptionEventRow::insert_batch(conn, insertable_subscription_events)
+        .await
+        .map_err(Into::<DatabaseErrorContainer>::into)?;
+
+    Ok(inserted_subscriptions)
+}
+
+/// Inserts the items and their initial invoices in a savepoint, so that a failure only rolls back these items
+async fn insert_batch_items_savepoint(
+    conn: &mut PgConn,
+    items: &[DieselModelWrapper],
+    context: &SubscriptionBatchContext,
+    tenant_id: Uuid,
+) -> Result<Vec<CreatedSubscription>, StoreError> {
+    conn.transaction(|conn| {
+        async move {
+            insert_batch_items(conn, items, context, tenant_id)
+                .await
+                .map_err(StoreError::TransactionStoreError)
+        }
+        .scope_boxed()
+    })
+    .await
+}
+
+async fn insert_batch_items(
+    conn: &mut PgConn,
+    items: &[DieselModelWrapper],
+    context: &SubscriptionBatchContext,
+    tenant_id: Uuid,
+) -> StoreResult<Vec<CreatedSubscription>> {
+    let inserted = insert_prepared_subscriptions(conn, items, tenant_id)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+    for invoice in draft_initial_invoices(&inserted, context)? {
+        insert_invoice(conn, invoice).await?;
+    }
+
+    Ok(inserted)
+}
+
+/// The error of a batch item along with its attached details, e.g. the offending value
+fn batch_failure_reason(error: &Report<StoreError>) -> String {
+    let context = match error.current_context() {
+        StoreError::TransactionStoreError(inner) => return batch_failure_reason(inner),
+        StoreError::DatabaseError(db_error) => {
+            std::iter::once(db_error.current_context().to_string())
+                .chain(printable_attachments(db_error.frames()))
+                .join(": ")
+        }
+        e => e.to_string(),
+    };
+
+    std::iter::once(context)
+        .chain(printable_attachments(error.frames()))
+        .join(": ")
+}
+
+fn printable_attachments<'a>(
+    frames: impl Iterator<Item = &'a error_stack::Frame> + 'a,
+) -> impl Iterator<Item = String> + 'a {
+    frames.filter_map(|frame| match frame.kind() {
+        FrameKind::Attachment(AttachmentKind::Printable(printable)) => Some(printable.to_string()),
+        _ => None,
+    })
 }
 
-fn process_create_subscription_add_ons(
+/// Draft invoices for the activated subscriptions of customers billed manually
+fn draft_initial_invoices(
+    inserted_subscriptions: &[CreatedSubscription],
+    context: &SubscriptionBatchContext,
+) -> StoreResult<Vec<InvoiceNew>> {
+    Ok(inserted_subscriptions
+        .iter()
+        .filter(|s| s.activated_at.is_some())
+        .map(|s| {
+            let customer = context
+                .customers
+                .iter()
+                .find(|c| c.id == s.customer_id)
+                .ok_or(StoreError::InsertError)?;
+
+            let invoicing_entity = context
+                .invoicing_entities
+                .iter()
+                .find(|c| c.id == customer.invoicing_entity_id)
+                .ok_or(StoreError::InsertError)?;
+
+            match customer.billing_config {
+                BillingConfig::Stripe(_)
+                | BillingConfig::Adyen(_)
+                | BillingConfig::GoCardless(_) => Ok(None),
+                BillingConfig::Manual => {
+                    let plan_name = context
+                        .plan_names
+                        .get(&s.plan_version_id)
+                        .ok_or(StoreError::InsertError)?;
+
+                    let sub = SubscriptionInvoiceCandidate {
+                        id: s.id,
+                        tenant_id: s.tenant_id,
+                        customer_id: s.customer_id,
+                        plan_version_id: s.plan_version_id,
+                        billing_start_date: s.billing_start_date,
+                        billing_end_date: s.billing_end_date,
+                        billing_day: s.billing_day,
+                        activated_at: s.activated_at,
+                        canceled_at: s.canceled_at,
+                        currency: s.currency.clone(),
+                        net_terms: s.net_terms,
+                        plan_name: plan_name.clone(),
+                        period: s.period.clone(),
+                        billing_mode: s.billing_mode,
+                    };
+
+                    subscription_to_draft(
+                        &sub,
+                        customer,
+                        invoicing_entity,
+                        &context.payment_terms,
+                        s.billing_start_date,
+                        None,
+                    )
+                    .map(Some)
+                }
+            }
+        })
+        .collect::<Result<Vec<Option<_>>, _>>()?
+        .into_iter()
+        .flatten()
+        .collect::<Vec<_>>())
+}
+
+async fn publish_subscriptions_created(store: &Store, subscriptions: &[CreatedSubscription]) {
+    let _ = futures::future::join_all(subscriptions.iter().map(|res| {
+        store.eventbus.publish(Event::subscription_created(
+            res.created_by,
+            res.id,
+            res.tenant_id,
+        ))
+    }))
+    .await
+    .into_iter()
+    .collect::<Result<Vec<_>, _>>();
+}
+
+pub(crate) fn process_create_subscription_add_ons(
     create: &Option<CreateSubscriptionAddOns>,
     add_ons: &[AddOn],
 ) -> Result<Vec<SubscriptionAddOnNewInternal>, StoreError> {
@@ -1147,10 +2547,10 @@ fn extract_billing_period(
         .unwrap_or(BillingPeriodEnum::Monthly)
 }
 
-fn process_create_subscription_components(
+pub(crate) fn process_create_subscription_components(
     param: &Option<CreateSubscriptionComponents>,
     map: &HashMap<Uuid, Vec<PriceComponent>>,
-    sub: &SubscriptionNew,
+    plan_version_id: &Uuid,
 ) -> Result<Vec<SubscriptionComponentNewInternal>, StoreError> {
     let mut processed_components = Vec::new();
 
@@ -1167,7 +2567,7 @@ fn process_create_subscription_components(
         };
 
     let binding = vec![];
-    let plan_price_components = map.get(&sub.plan_version_id).unwrap_or(&binding);
+    let plan_price_components = map.get(plan_version_id).unwrap_or(&binding);
 
     let mut removed_components = Vec::new();
 
@@ -1238,7 +2638,10 @@ fn process_create_subscription_components(
 }
 
 impl SubscriptionDetails {
-    fn calculate_cancellable_end_of_period_date(&self, now: NaiveDate) -> Option<NaiveDate> {
+    pub(crate) fn calculate_cancellable_end_of_period_date(
+        &self,
+        now: NaiveDate,
+    ) -> Option<NaiveDate> {
         // to calculate billing period :
         // if there is a commitment, use that commitment (currently no commitment so let's ignore)
         // else, we take the longest period from the main components (rate/slots/capacity), as that's what the user has already paid
@@ -1258,7 +2661,7 @@ impl SubscriptionDetails {
 
         let periods = crate::utils::periods::calculate_periods_for_date(
             self.billing_start_date,
-            self.billing_day as u32,
+            self.anchor_day(),
             now,
             &period,
         );
@@ -1267,29 +2670,43 @@ impl SubscriptionDetails {
     }
 }
 
+/// Drafts the invoice of the period running at `date`. In calendar mode, the first period runs until the 1st of the next month.
+/// With a cadence, the invoice only bills the fees of that billing period, and is dated at the end of its period
 pub fn subscription_to_draft(
     subscription: &SubscriptionInvoiceCandidate,
     customer: &Customer,
     invoicing_entity: &InvoicingEntity,
+    tenant_payment_terms: &TenantPaymentTerms,
+    date: NaiveDate,
+    cadence: Option<BillingPeriodEnum>,
 ) -> StoreResult<domain::invoices::InvoiceNew> {
     let cust_bill_cfg = &customer.billing_config;
-    let billing_start_date = subscription.billing_start_date;
-    let billing_day = subscription.billing_day as u32;
 
-    let period = crate::utils::periods::calculate_period_range(
-        billing_start_date,
-        billing_day,
-        0, // TODO ???
-        &subscription.period,
-    );
+    let period = crate::utils::periods::calculate_periods_for_date(
+        subscription.billing_start_date,
+        subscription.anchor_day(),
+        date,
+        cadence.as_ref().unwrap_or(&subscription.period),
+    )
+    .advance;
 
     let invoicing_provider = match cust_bill_cfg {
         BillingConfig::Stripe(_) => InvoicingProviderEnum::Stripe,
         BillingConfig::Manual => InvoicingProviderEnum::Manual,
+        BillingConfig::Adyen(_) => InvoicingProviderEnum::Adyen,
+        BillingConfig::GoCardless(_) => InvoicingProviderEnum::GoCardless,
     };
 
-    let due_date = (period.end + chrono::Duration::days(subscription.net_terms as i64))
-        .and_time(NaiveTime::MIN);
+    let payment_terms = PaymentTerms::resolve(
+        customer,
+        Some(subscription.net_terms as u32),
+        tenant_payment_terms,
+        invoicing_entity.net_terms as u32,
+    );
+
+    let net_terms = payment_terms.net_terms as i32;
+
+    let due_date = tenant_payment_terms.due_at(payment_terms.due_date(period.end));
 
     // should we have a draft number ? TODO re-set optional, and also implement it in finalize, and fetch from tenant config
     let invoice_number = "draft";
@@ -1319,7 +2736,7 @@ pub fn subscription_to_draft(
         tax_amount: 0,
         total: 0,
         amount_due: 0,
-        net_terms: subscription.net_terms,
+        net_terms,
         reference: None,
         memo: None,
         local_id: LocalId::generate_for(IdType::Invoice),
@@ -1333,6 +2750,7 @@ pub fn subscription_to_draft(
             vat_number: None,
             email: customer.email.clone(),
             alias: customer.alias.clone(),
+            early_payment_discount: customer.early_payment_discount.clone(),
             snapshot_at: chrono::Utc::now().naive_utc(),
         },
         seller_details: InlineInvoicingEntity {
@@ -1340,9 +2758,39 @@ pub fn subscription_to_draft(
             legal_name: invoicing_entity.legal_name.clone(),
             vat_number: invoicing_entity.vat_number.clone(),
             address: invoicing_entity.address(),
+            // resolved against the customer country at finalization
+            tax_registrations: vec![],
             snapshot_at: chrono::Utc::now().naive_utc(),
         },
+        billing_cadence: cadence,
+        replaces_invoice_id: None,
     };
 
     Ok(invoice)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(s: &str) -> NaiveDate {
+        NaiveDate::parse_from_str(s, "%Y-%m-%d").unwrap()
+    }
+
+    #[test]
+    fn test_ramp_months_elapsed() {
+        // starting on the billing day, every period is full
+        assert_eq!(ramp_months_elapsed(date("2024-01-01"), 1, 0), 0);
+        assert_eq!(ramp_months_elapsed(date("2024-01-01"), 1, 3), 3);
+
+        // partial period from the 5th to the 10th, the first full period starts on 2024-01-10
+        assert_eq!(ramp_months_elapsed(date("2024-01-05"), 10, 0), 0);
+        assert_eq!(ramp_months_elapsed(date("2024-01-05"), 10, 1), 0);
+        assert_eq!(ramp_months_elapsed(date("2024-01-05"), 10, 2), 1);
+
+        // partial period from the 15th to the 1st of the next month
+        assert_eq!(ramp_months_elapsed(date("2024-01-15"), 1, 0), 0);
+        assert_eq!(ramp_months_elapsed(date("2024-01-15"), 1, 1), 0);
+        assert_eq!(ramp_months_elapsed(date("2024-01-15"), 1, 13), 12);
+    }
+}
@@ -0,0 +1,584 @@
+use std::collections::HashMap;
+
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use crate::domain::enums::TenantEnvironmentEnum;
+use crate::domain::tenant_environments::{
+    remap_ids, CatalogCopyMode, CatalogCopyReport, SandboxTenant, SandboxTenantNew,
+};
+use crate::domain::{BillableMetric, InvoicingEntityNew, OrganizationWithTenants, TenantNew};
+use crate::errors::StoreError;
+use crate::repositories::{OrganizationsInterface, TenantInterface};
+use crate::store::{PgConn, Store, StoreInternal};
+use crate::StoreResult;
+use diesel_models::add_ons::{AddOnRow, AddOnRowNew};
+use diesel_models::billable_metrics::{BillableMetricRow, BillableMetricRowNew};
+use diesel_models::enums::PlanStatusEnum;
+use diesel_models::plan_versions::{PlanVersionRow, PlanVersionRowNew};
+use diesel_models::plans::{PlanRow, PlanRowNew};
+use diesel_models::price_components::{PriceComponentRow, PriceComponentRowNew};
+use diesel_models::product_families::{ProductFamilyRow, ProductFamilyRowNew};
+use diesel_models::products::{ProductRow, ProductRowNew};
+use diesel_models::rate_cards::{
+    RateCardRow, RateCardRowNew, RateCardVersionRow, RateCardVersionRowNew,
+};
+use diesel_models::schedules::{ScheduleRow, ScheduleRowNew};
+
+#[async_trait::async_trait]
+pub trait TenantEnvironmentsInterface {
+    /// Creates a sandbox tenant linked to a production tenant, optionally with a copy of its catalog
+    async fn create_sandbox_tenant(&self, sandbox: SandboxTenantNew) -> StoreResult<SandboxTenant>;
+
+    /// Copies the catalog of a sandbox tenant to its production tenant. The plans are copied as draft versions, to be published in production
+    async fn promote_sandbox_catalog(
+        &self,
+        sandbox_tenant_id: Uuid,
+        organization_id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<CatalogCopyReport>;
+}
+
+#[async_trait::async_trait]
+impl TenantEnvironmentsInterface for Store {
+    async fn create_sandbox_tenant(&self, sandbox: SandboxTenantNew) -> StoreResult<SandboxTenant> {
+        let production = self
+            .find_tenant_by_id_and_organization(
+                sandbox.production_tenant_id,
+                sandbox.organization_id,
+            )
+            .await?;
+
+        if !matches!(production.environment, TenantEnvironmentEnum::Production) {
+            return Err(StoreError::InvalidArgument(
+                "sandbox tenants can only be created from a production tenant".to_string(),
+            )
+            .into());
+        }
+
+        let OrganizationWithTenants {
+            organization,
+            tenants,
+        } = self
+            .get_organizations_with_tenants_by_id(sandbox.organization_id)
+            .await?;
+
+        let (sandbox_tenant, new_metrics, catalog) = self
+            .transaction(|conn| {
+                async move {
+                    let tenant = self
+                        .internal
+                        .insert_tenant_with_default_entities(
+                            conn,
+                            TenantNew {
+                                name: sandbox.name,
+                                environment: TenantEnvironmentEnum::Sandbox,
+                                production_tenant_id: Some(production.id),
+                            },
+                            sandbox.organization_id,
+                            organization.trade_name.clone(),
+                            organization.default_country.clone(),
+                            tenants.iter().map(|x| x.slug.clone()).collect(),
+                            InvoicingEntityNew::default(),
+                        )
+                        .await?;
+
+                    let (new_metrics, catalog) = if sandbox.copy_catalog {
+                        self.internal
+                            .copy_catalog(
+                                conn,
+                                production.id,
+                                tenant.id,
+                                sandbox.created_by,
+                                CatalogCopyMode::Mirror,
+                            )
+                            .await?
+                    } else {
+                        (vec![], CatalogCopyReport::default())
+                    };
+
+                    Ok((tenant, new_metrics, catalog))
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        // after the transaction, as the meter views are not transactional. A failure is logged, the meters can be registered again from the metrics
+        self.register_copied_meters(sandbox_tenant.id, &new_metrics)
+            .await;
+
+        Ok(SandboxTenant {
+            tenant: sandbox_tenant,
+            catalog,
+        })
+    }
+
+    async fn promote_sandbox_catalog(
+        &self,
+        sandbox_tenant_id: Uuid,
+        organization_id: Uuid,
+        actor: Uuid,
+    ) -> StoreResult<CatalogCopyReport> {
+        let sandbox = self
+            .find_tenant_by_id_and_organization(sandbox_tenant_id, organization_id)
+            .await?;
+
+        let production_tenant_id = match (sandbox.environment, sandbox.production_tenant_id) {
+            (TenantEnvironmentEnum::Sandbox, Some(production_tenant_id)) => production_tenant_id,
+            _ => {
+                return Err(StoreError::InvalidArgument(
+                    "only sandbox tenants linked to a production tenant can be promoted"
+                        .to_string(),
+                )
+                .into());
+            }
+        };
+
+        // the production tenant must still belong to the organization
+        let production = self
+            .find_tenant_by_id_and_organization(production_tenant_id, organization_id)
+            .await?;
+
+        let (new_metrics, report) = self
+            .transaction(|conn| {
+                async move {
+                    self.internal
+                        .copy_catalog(
+                            conn,
+                            sandbox.id,
+                            production.id,
+                            actor,
+                            CatalogCopyMode::Promote,
+                        )
+                        .await
+                }
+                .scope_boxed()
+            })
+            .await?;
+
+        self.register_copied_meters(production.id, &new_metrics)
+            .await;
+
+        Ok(report)
+    }
+}
+
+impl Store {
+    async fn register_copied_meters(&self, tenant_id: Uuid, metrics: &[BillableMetric]) {
+        for metric in metrics {
+            if let Err(err) = self.usage_client.register_meter(&tenant_id, metric).await {
+                log::error!(
+                    "Failed to register the meter of copied metric {}: {:?}",
+                    metric.id,
+                    err
+                );
+            }
+        }
+    }
+}
+
+impl StoreInternal {
+    /// Copies the product families, billable metrics, products, rate cards, add-ons and plans of a tenant to another.
+    /// Entities are matched by their external id, code or name, only the missing ones are created.
+    /// Returns the created metrics, whose meters must be registered
+    pub async fn copy_catalog(
+        &self,
+        conn: &mut PgConn,
+        source_tenant_id: Uuid,
+        target_tenant_id: Uuid,
+        actor: Uuid,
+        mode: CatalogCopyMode,
+    ) -> StoreResult<(Vec<BillableMetric>, CatalogCopyReport)> {
+        let mut report = CatalogCopyReport::default();
+        // source id -> target id, of all the copied or matched entities
+        let mut ids: HashMap<Uuid, Uuid> = HashMap::new();
+
+        // product families, by external id
+        let target_families = ProductFamilyRow::list(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        for family in ProductFamilyRow::list(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .filter(|f| f.archived_at.is_none())
+        {
+            let target_id = match target_families
+                .iter()
+                .find(|f| f.external_id == family.external_id)
+            {
+                Some(existing) => existing.id,
+                None => {
+                    report.product_families += 1;
+                    ProductFamilyRowNew {
+                        id: Uuid::now_v7(),
+                        name: family.name,
+                        external_id: family.external_id,
+                        tenant_id: target_tenant_id,
+                        default_currency: family.default_currency,
+                        default_trial_duration_days: family.default_trial_duration_days,
+                        default_net_terms: family.default_net_terms,
+                        default_tax_category: family.default_tax_category,
+                    }
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .id
+                }
+            };
+            ids.insert(family.id, target_id);
+        }
+
+        // billable metrics, by code
+        let target_metrics = BillableMetricRow::list_by_tenant_id(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let mut new_metrics = vec![];
+        for metric in BillableMetricRow::list_by_tenant_id(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+        {
+            if let Some(existing) = target_metrics.iter().find(|m| m.code == metric.code) {
+                ids.insert(metric.id, existing.id);
+                continue;
+            }
+
+            let Some(family_id) = ids.get(&metric.product_family_id).copied() else {
+                continue;
+            };
+
+            let inserted: BillableMetric = BillableMetricRowNew {
+                id: Uuid::now_v7(),
+                name: metric.name,
+                description: metric.description,
+                code: metric.code,
+                aggregation_type: metric.aggregation_type,
+                aggregation_key: metric.aggregation_key,
+                unit_conversion_factor: metric.unit_conversion_factor,
+                unit_conversion_rounding: metric.unit_conversion_rounding,
+                segmentation_matrix: metric.segmentation_matrix,
+                usage_group_key: metric.usage_group_key,
+                created_by: actor,
+                tenant_id: target_tenant_id,
+                product_family_id: family_id,
+                quantity_billing_precision: metric.quantity_billing_precision,
+                quantity_display_precision: metric.quantity_display_precision,
+                event_schema: metric.event_schema,
+            }
+            .insert(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .and_then(TryInto::try_into)?;
+
+            report.billable_metrics += 1;
+            ids.insert(metric.id, inserted.id);
+            new_metrics.push(inserted);
+        }
+
+        // products, by name within their family
+        let target_products = ProductRow::list_by_tenant_id(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        for product in ProductRow::list_by_tenant_id(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+        {
+            let Some(family_id) = ids.get(&product.product_family_id).copied() else {
+                continue;
+            };
+
+            let target_id = match target_products
+                .iter()
+                .find(|p| p.name == product.name && p.product_family_id == family_id)
+            {
+                Some(existing) => existing.id,
+                None => {
+                    report.products += 1;
+                    ProductRowNew {
+                        id: Uuid::now_v7(),
+                        name: product.name,
+                        description: product.description,
+                        created_by: actor,
+                        tenant_id: target_tenant_id,
+                        product_family_id: family_id,
+                    }
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .id
+                }
+            };
+            ids.insert(product.id, target_id);
+        }
+
+        // rate cards, by name, with their latest version
+        let target_rate_cards = RateCardRow::list_by_tenant_id(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        for rate_card in RateCardRow::list_by_tenant_id(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+        {
+            if let Some(existing) = target_rate_cards.iter().find(|r| r.name == rate_card.name) {
+                ids.insert(rate_card.id, existing.id);
+                continue;
+            }
+
+            let latest = RateCardVersionRow::find_latest(conn, rate_card.id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+            let inserted = RateCardRowNew {
+                id: Uuid::now_v7(),
+                tenant_id: target_tenant_id,
+                name: rate_card.name,
+                description: rate_card.description,
+                currency: rate_card.currency,
+                created_by: actor,
+            }
+            .insert(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            let mut rates = latest.rates;
+            remap_ids(&mut rates, &ids);
+
+            RateCardVersionRowNew {
+                id: Uuid::now_v7(),
+                rate_card_id: inserted.id,
+                version: 1,
+                rates,
+                created_by: actor,
+            }
+            .insert(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            report.rate_cards += 1;
+            ids.insert(rate_card.id, inserted.id);
+        }
+
+        // add-ons, by name
+        let target_add_ons = AddOnRow::list_by_tenant_id(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        for add_on in AddOnRow::list_by_tenant_id(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+        {
+            if target_add_ons.iter().any(|a| a.name == add_on.name) {
+                continue;
+            }
+
+            let mut fee = add_on.fee;
+            remap_ids(&mut fee, &ids);
+
+            AddOnRowNew {
+                id: Uuid::now_v7(),
+                name: add_on.name,
+                fee,
+                tenant_id: target_tenant_id,
+            }
+            .insert(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            report.add_ons += 1;
+        }
+
+        // plans, by external id. All the plans are matched or created before their versions, that can reference each other
+        let target_plans = PlanRow::list_by_tenant_id(conn, target_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let mut plans_to_copy = vec![];
+        for plan in PlanRow::list_by_tenant_id(conn, source_tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .filter(|p| p.archived_at.is_none() && !matches!(p.status, PlanStatusEnum::Archived))
+        {
+            let Some(family_id) = ids.get(&plan.product_family_id).copied() else {
+                continue;
+            };
+
+            let Some(version) = PlanVersionRow::find_latest_by_plan_id_and_tenant_id(
+                conn,
+                plan.id,
+                source_tenant_id,
+                Some(false),
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            else {
+                report.skipped_plans.push(plan.external_id);
+                continue;
+            };
+
+            let existing = target_plans
+                .iter()
+                .find(|p| p.external_id == plan.external_id);
+
+            // the external id of an archived plan cannot be reused
+            if existing.is_some_and(|p| {
+                p.archived_at.is_some() || matches!(p.status, PlanStatusEnum::Archived)
+            }) {
+                report.skipped_plans.push(plan.external_id);
+                continue;
+            }
+
+            let (target_plan_id, is_new) = match (existing, mode) {
+                (Some(existing), CatalogCopyMode::Promote) => (existing.id, false),
+                // a mirror only completes the target catalog
+                (Some(existing), CatalogCopyMode::Mirror) => {
+                    ids.insert(plan.id, existing.id);
+                    continue;
+                }
+                (None, _) => {
+                    let status = match mode {
+                        CatalogCopyMode::Mirror => plan.status.clone(),
+                        CatalogCopyMode::Promote => PlanStatusEnum::Draft,
+                    };
+
+                    let inserted = PlanRowNew {
+                        id: Uuid::now_v7(),
+                        name: plan.name.clone(),
+                        description: plan.description.clone(),
+                        created_by: actor,
+                        tenant_id: target_tenant_id,
+                        product_family_id: family_id,
+                        external_id: plan.external_id.clone(),
+                        plan_type: plan.plan_type.clone(),
+                        status,
+                    }
+                    .insert(conn)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+
+                    report.plans += 1;
+                    (inserted.id, true)
+                }
+            };
+
+            ids.insert(plan.id, target_plan_id);
+            plans_to_copy.push((target_plan_id, is_new, version));
+        }
+
+        for (target_plan_id, is_new, version) in plans_to_copy {
+            let next_version = if is_new {
+                1
+            } else {
+                PlanVersionRow::get_latest_by_plan_id_and_tenant_id(
+                    conn,
+                    target_plan_id,
+                    target_tenant_id,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .version
+                    + 1
+            };
+
+            let inserted = PlanVersionRowNew {
+                id: Uuid::now_v7(),
+                is_draft_version: matches!(mode, CatalogCopyMode::Promote),
+                plan_id: target_plan_id,
+                version: next_version,
+                trial_duration_days: version.trial_duration_days,
+                downgrade_plan_id: version
+                    .downgrade_plan_id
+                    .and_then(|id| ids.get(&id).copied()),
+                tenant_id: target_tenant_id,
+                period_start_day: version.period_start_day,
+                net_terms: version.net_terms,
+                currency: version.currency,
+                billing_cycles: version.billing_cycles,
+                created_by: actor,
+                billing_periods: version.billing_periods.into_iter().flatten().collect(),
+                trialing_plan_id: version
+                    .trialing_plan_id
+                    .and_then(|id| ids.get(&id).copied()),
+                action_after_trial: version.action_after_trial,
+                trial_is_free: version.trial_is_free,
+                trial_mode: version.trial_mode,
+                tax_category: version.tax_category,
+                inherited_defaults: version.inherited_defaults,
+            }
+            .insert(conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            if !is_new {
+                PlanVersionRow::delete_others_draft(
+                    conn,
+                    inserted.id,
+                    target_plan_id,
+                    target_tenant_id,
+                )
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+                report.plan_versions += 1;
+            }
+
+            let components =
+                PriceComponentRow::list_by_plan_version_id(conn, source_tenant_id, version.id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?
+                    .into_iter()
+                    .map(|component| {
+                        let mut fee = component.fee;
+                        remap_ids(&mut fee, &ids);
+
+                        PriceComponentRowNew {
+                            id: Uuid::now_v7(),
+                            name: component.name,
+                            fee,
+                            plan_version_id: inserted.id,
+                            product_item_id: component
+                                .product_item_id
+                                .and_then(|id| ids.get(&id).copied()),
+                            billable_metric_id: component
+                                .billable_metric_id
+                                .and_then(|id| ids.get(&id).copied()),
+                            rate_card_id: component
+                                .rate_card_id
+                                .and_then(|id| ids.get(&id).copied()),
+                        }
+                    })
+                    .collect::<Vec<_>>();
+
+            if !components.is_empty() {
+                PriceComponentRow::insert_batch(conn, components)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+            }
+
+            let schedules = ScheduleRow::list(conn, version.id, source_tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .into_iter()
+                .map(|schedule| ScheduleRowNew {
+                    id: Uuid::now_v7(),
+                    billing_period: schedule.billing_period,
+                    plan_version_id: inserted.id,
+                    ramps: schedule.ramps,
+                })
+                .collect::<Vec<_>>();
+
+            if !schedules.is_empty() {
+                ScheduleRow::insert_schedule_batch(conn, schedules)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+            }
+        }
+
+        Ok((new_metrics, report))
+    }
+}
@@ -0,0 +1,110 @@
+use crate::domain::enums::{OrganizationUserRole, TenantMemberRoleEnum};
+use crate::domain::tenant_member_roles::{resolve_tenant_role, TenantMemberRole};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+use diesel_models::tenant_member_roles::{TenantMemberRoleRow, TenantMemberRoleRowNew};
+use diesel_models::users::UserRow;
+use error_stack::Report;
+use uuid::Uuid;
+
+#[async_trait::async_trait]
+pub trait TenantMemberRolesInterface {
+    /// The effective role of the user on the tenant. Fails if the user is not a member of the organization of the tenant
+    async fn get_tenant_member_role(
+        &self,
+        user_id: Uuid,
+        tenant_id: Uuid,
+    ) -> StoreResult<TenantMemberRoleEnum>;
+
+    async fn list_tenant_member_roles(&self, tenant_id: Uuid)
+        -> StoreResult<Vec<TenantMemberRole>>;
+
+    async fn assign_tenant_member_role(
+        &self,
+        organization_id: Uuid,
+        tenant_id: Uuid,
+        user_id: Uuid,
+        role: TenantMemberRoleEnum,
+    ) -> StoreResult<TenantMemberRole>;
+
+    /// The member is back to the default role, admin
+    async fn remove_tenant_member_role(&self, tenant_id: Uuid, user_id: Uuid) -> StoreResult<()>;
+}
+
+#[async_trait::async_trait]
+impl TenantMemberRolesInterface for Store {
+    async fn get_tenant_member_role(
+        &self,
+        user_id: Uuid,
+        tenant_id: Uuid,
+    ) -> StoreResult<TenantMemberRoleEnum> {
+        let mut conn = self.get_conn().await?;
+
+        let user = UserRow::find_by_id_and_tenant_id(&mut conn, user_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let assigned =
+            TenantMemberRoleRow::find_by_user_id_and_tenant_id(&mut conn, user_id, tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(resolve_tenant_role(
+            &user.role.into(),
+            assigned.map(|row| row.role.into()),
+        ))
+    }
+
+    async fn list_tenant_member_roles(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<Vec<TenantMemberRole>> {
+        let mut conn = self.get_conn().await?;
+
+        TenantMemberRoleRow::list_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn assign_tenant_member_role(
+        &self,
+        organization_id: Uuid,
+        tenant_id: Uuid,
+        user_id: Uuid,
+        role: TenantMemberRoleEnum,
+    ) -> StoreResult<TenantMemberRole> {
+        let mut conn = self.get_conn().await?;
+
+        let user = UserRow::find_by_id_and_org_id(&mut conn, user_id, organization_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if OrganizationUserRole::from(user.role) == OrganizationUserRole::Admin {
+            return Err(StoreError::InvalidArgument(
+                "organization admins are admins of all the tenants".to_string(),
+            )
+            .into());
+        }
+
+        TenantMemberRoleRowNew {
+            user_id,
+            organization_id,
+            tenant_id,
+            role: role.into(),
+        }
+        .upsert(&mut conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(Into::into)
+    }
+
+    async fn remove_tenant_member_role(&self, tenant_id: Uuid, user_id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        TenantMemberRoleRow::delete(&mut conn, user_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|_| ())
+    }
+}
@@ -1,14 +1,26 @@
 use crate::domain::{InvoicingEntityNew, OrganizationWithTenants, Tenant, TenantNew, TenantUpdate};
 use cached::proc_macro::cached;
 use cached::Cached;
+use chrono::NaiveDateTime;
+use chrono_tz::Tz;
 use diesel_async::scoped_futures::ScopedFutureExt;
 use error_stack::Report;
 
+use crate::compute::clients::usage::{EventRetentionPolicy, MeterViewsDrift};
 use crate::constants::{Currencies, Currency};
+use crate::domain::enums::{
+    InvoiceCadenceGroupingEnum, ProrationRoundingEnum, TenantEnvironmentEnum, ZeroInvoicePolicyEnum,
+};
+use crate::domain::payment_terms::{DueDatePolicy, TenantPaymentTerms};
+use crate::domain::tenants::MAX_FINALIZATION_GRACE_PERIOD_HOURS;
 use crate::errors::StoreError;
+use crate::repositories::customers::customer_conflict;
 use crate::repositories::OrganizationsInterface;
 use crate::store::{PgConn, Store, StoreInternal};
 use crate::{domain, StoreResult};
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::customers::CustomerRow;
+use diesel_models::invoices::InvoiceRow;
 use diesel_models::organizations::OrganizationRow;
 use diesel_models::tenants::{TenantRow, TenantRowNew, TenantRowPatch};
 use uuid::Uuid;
@@ -38,6 +50,61 @@ pub trait TenantInterface {
     ) -> StoreResult<Vec<Tenant>>;
 
     async fn get_reporting_currency_by_tenant_id(&self, tenant_id: Uuid) -> StoreResult<Currency>;
+
+    async fn get_proration_rounding_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<ProrationRoundingEnum>;
+
+    async fn get_zero_invoice_policy_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<ZeroInvoicePolicyEnum>;
+
+    async fn get_invoice_cadence_grouping_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<InvoiceCadenceGroupingEnum>;
+
+    async fn get_payment_terms_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<TenantPaymentTerms>;
+
+    /// The timezones the billing workers cut the periods in
+    async fn list_tenant_billing_timezones(&self) -> StoreResult<Vec<Tz>>;
+
+    /// Wipes the customers, subscriptions, invoices, usage events and reporting data of a sandbox tenant, keeping its catalog and configuration.
+    /// The confirmation must be the slug of the tenant
+    async fn reset_sandbox_tenant(
+        &self,
+        tenant_id: Uuid,
+        organization_id: Uuid,
+        confirmation: String,
+    ) -> StoreResult<()>;
+
+    /// Compares the meter views of the tenant in the metering storage to its billable metrics
+    async fn check_tenant_meter_views(&self, tenant_id: Uuid) -> StoreResult<MeterViewsDrift>;
+
+    /// Sets how long the ingested events of the tenant are kept, and whether they are archived before their deletion
+    async fn set_tenant_event_retention(
+        &self,
+        tenant_id: Uuid,
+        policy: EventRetentionPolicy,
+    ) -> StoreResult<EventRetentionPolicy>;
+
+    async fn get_tenant_event_retention(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<EventRetentionPolicy>;
+
+    /// Restores the archived events of the tenant over the period, so that its metrics can be re-aggregated
+    async fn restore_tenant_archived_events(
+        &self,
+        tenant_id: Uuid,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> StoreResult<()>;
 }
 
 #[async_trait::async_trait]
@@ -75,6 +142,102 @@ impl TenantInterface for Store {
         organization_id: Uuid,
         tenant_id: Uuid,
     ) -> StoreResult<Tenant> {
+        if tenant.default_net_terms.is_some_and(|v| v < 0) {
+            return Err(StoreError::InvalidArgument(
+                "the default net terms cannot be negative".to_string(),
+            )
+            .into());
+        }
+
+        if tenant
+            .finalization_grace_period_hours
+            .is_some_and(|v| !(0..=MAX_FINALIZATION_GRACE_PERIOD_HOURS).contains(&v))
+        {
+            return Err(StoreError::InvalidArgument(format!(
+                "the finalization grace period must be between 0 and {} hours",
+                MAX_FINALIZATION_GRACE_PERIOD_HOURS
+            ))
+            .into());
+        }
+
+        if [
+            tenant.stuck_draft_max_age_days,
+            tenant.stuck_pending_max_age_hours,
+            tenant.stuck_max_issue_attempts,
+        ]
+        .iter()
+        .any(|v| v.is_some_and(|v| v <= 0))
+        {
+            return Err(StoreError::InvalidArgument(
+                "the invoice watchdog SLAs must be positive".to_string(),
+            )
+            .into());
+        }
+
+        if tenant.seat_auto_approve_limit.is_some_and(|v| v < 0) {
+            return Err(StoreError::InvalidArgument(
+                "the seat auto-approval limit cannot be negative".to_string(),
+            )
+            .into());
+        }
+
+        if tenant
+            .finance_email
+            .as_ref()
+            .is_some_and(|email| !email.contains('@'))
+        {
+            return Err(StoreError::InvalidArgument(
+                "the finance email is not a valid email address".to_string(),
+            )
+            .into());
+        }
+
+        match tenant.due_date_policy {
+            Some(policy) => DueDatePolicy {
+                policy,
+                day: tenant.due_date_day.map(|d| d.max(0) as u32),
+            }
+            .validate()?,
+            None if tenant.due_date_day.is_some() => {
+                return Err(StoreError::InvalidArgument(
+                    "the due date day must be set along with the due date policy".to_string(),
+                )
+                .into());
+            }
+            None => {}
+        }
+
+        if tenant
+            .environment
+            .as_ref()
+            .is_some_and(|env| !matches!(env, TenantEnvironmentEnum::Sandbox))
+        {
+            let current = self
+                .find_tenant_by_id_and_organization(tenant_id, organization_id)
+                .await?;
+
+            if current.production_tenant_id.is_some() {
+                return Err(StoreError::InvalidArgument(
+                    "the environment of a sandbox tenant linked to a production tenant cannot be changed"
+                        .to_string(),
+                )
+                .into());
+            }
+        }
+
+        if tenant
+            .billing_timezone
+            .as_ref()
+            .is_some_and(|tz| tz.parse::<Tz>().is_err())
+        {
+            return Err(StoreError::InvalidArgument(
+                "the billing timezone is not a known IANA timezone".to_string(),
+            )
+            .into());
+        }
+
+        let uniqueness_changed = tenant.customer_uniqueness.is_some();
+
         let res = self
             .transaction(|conn| {
                 async move {
@@ -86,6 +249,20 @@ impl TenantInterface for Store {
                             .map_err(Into::<Report<StoreError>>::into)?;
                     }
 
+                    let previous_timezone = match &tenant.billing_timezone {
+                        Some(_) => Some(
+                            TenantRow::find_by_id_and_organization_id(
+                                conn,
+                                tenant_id,
+                                organization_id,
+                            )
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?
+                            .billing_timezone,
+                        ),
+                        None => None,
+                    };
+
                     let patch: TenantRowPatch = tenant.into();
 
                     let updated_tenant = patch
@@ -93,6 +270,27 @@ impl TenantInterface for Store {
                         .await
                         .map_err(Into::<Report<StoreError>>::into)?;
 
+                    // the open invoices keep the same due date, at the midnight of the new timezone
+                    if let Some(previous_timezone) = previous_timezone
+                        .filter(|previous| *previous != updated_tenant.billing_timezone)
+                    {
+                        InvoiceRow::shift_due_dates_timezone(
+                            conn,
+                            tenant_id,
+                            &previous_timezone,
+                            &updated_tenant.billing_timezone,
+                        )
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+                    }
+
+                    // fails on customers that are already duplicated
+                    if uniqueness_changed {
+                        CustomerRow::refresh_unique_keys(conn, tenant_id)
+                            .await
+                            .map_err(customer_conflict)?;
+                    }
+
                     Ok(updated_tenant.into())
                 }
                 .scope_boxed()
@@ -147,6 +345,194 @@ impl TenantInterface for Store {
             .get_reporting_currency_by_tenant_id(&mut conn, tenant_id)
             .await
     }
+    async fn get_proration_rounding_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<ProrationRoundingEnum> {
+        let mut conn = self.get_conn().await?;
+
+        TenantRow::get_proration_rounding_by_id(&mut conn, tenant_id)
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    async fn get_zero_invoice_policy_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<ZeroInvoicePolicyEnum> {
+        let mut conn = self.get_conn().await?;
+
+        TenantRow::get_zero_invoice_policy_by_id(&mut conn, tenant_id)
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    async fn get_invoice_cadence_grouping_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<InvoiceCadenceGroupingEnum> {
+        let mut conn = self.get_conn().await?;
+
+        TenantRow::get_invoice_cadence_grouping_by_id(&mut conn, tenant_id)
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    async fn get_payment_terms_by_tenant_id(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<TenantPaymentTerms> {
+        let mut conn = self.get_conn().await?;
+
+        TenantRow::get_payment_terms_by_id(&mut conn, tenant_id)
+            .await
+            .map(Into::into)
+            .map_err(Into::into)
+    }
+
+    async fn list_tenant_billing_timezones(&self) -> StoreResult<Vec<Tz>> {
+        let mut conn = self.get_conn().await?;
+
+        let timezones = TenantRow::list_billing_timezones(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        // validated on update, an unknown timezone would only come from a manual edit
+        let mut timezones = timezones
+            .iter()
+            .map(|tz| tz.parse().unwrap_or(Tz::UTC))
+            .collect::<Vec<Tz>>();
+        timezones.sort_by_key(|tz| tz.name());
+        timezones.dedup();
+
+        Ok(timezones)
+    }
+
+    async fn reset_sandbox_tenant(
+        &self,
+        tenant_id: Uuid,
+        organization_id: Uuid,
+        confirmation: String,
+    ) -> StoreResult<()> {
+        let tenant = self
+            .find_tenant_by_id_and_organization(tenant_id, organization_id)
+            .await?;
+
+        if !matches!(tenant.environment, TenantEnvironmentEnum::Sandbox) {
+            return Err(StoreError::InvalidArgument(
+                "only sandbox tenants can be reset".to_string(),
+            )
+            .into());
+        }
+
+        if confirmation != tenant.slug {
+            return Err(StoreError::InvalidArgument(
+                "the confirmation does not match the tenant slug".to_string(),
+            )
+            .into());
+        }
+
+        let mut conn = self.get_conn().await?;
+
+        let metric_ids = BillableMetricRow::list_ids_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        self.transaction_with(&mut conn, |conn| {
+            async move {
+                TenantRow::reset_sandbox_data(conn, tenant_id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)
+            }
+            .scope_boxed()
+        })
+        .await?;
+
+        // after the database, as the reset can safely be retried if the purge fails
+        self.usage_client
+            .purge_tenant_events(&tenant_id, &metric_ids)
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to purge tenant events".to_string(), e)
+            })?;
+
+        Ok(())
+    }
+
+    async fn check_tenant_meter_views(&self, tenant_id: Uuid) -> StoreResult<MeterViewsDrift> {
+        let mut conn = self.get_conn().await?;
+
+        let metric_ids = BillableMetricRow::list_ids_by_tenant_id(&mut conn, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        self.usage_client
+            .check_meter_views(&tenant_id, &metric_ids)
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to check meter views".to_string(), e)
+                    .into()
+            })
+    }
+
+    async fn set_tenant_event_retention(
+        &self,
+        tenant_id: Uuid,
+        policy: EventRetentionPolicy,
+    ) -> StoreResult<EventRetentionPolicy> {
+        if policy.archive && policy.retention_days == 0 {
+            return Err(StoreError::InvalidArgument(
+                "the events can only be archived with a retention".to_string(),
+            )
+            .into());
+        }
+
+        self.usage_client
+            .set_event_retention(&tenant_id, policy)
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to set event retention".to_string(), e)
+                    .into()
+            })
+    }
+
+    async fn get_tenant_event_retention(
+        &self,
+        tenant_id: Uuid,
+    ) -> StoreResult<EventRetentionPolicy> {
+        self.usage_client
+            .get_event_retention(&tenant_id)
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to get event retention".to_string(), e)
+                    .into()
+            })
+    }
+
+    async fn restore_tenant_archived_events(
+        &self,
+        tenant_id: Uuid,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> StoreResult<()> {
+        if from >= to {
+            return Err(StoreError::InvalidArgument(
+                "the restored period must end after its start".to_string(),
+            )
+            .into());
+        }
+
+        self.usage_client
+            .restore_archived_events(&tenant_id, from, to)
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to restore archived events".to_string(), e)
+                    .into()
+            })
+    }
 }
 
 impl StoreInternal {
@@ -187,6 +573,7 @@ impl StoreInternal {
             name: tenant.name,
             slug,
             organization_id,
+            production_tenant_id: tenant.production_tenant_id,
         };
 
         let inserted: Tenant = insertable_tenant
@@ -207,6 +594,10 @@ impl StoreInternal {
                     name: "Default".to_string(),
                     external_id: "default".to_string(),
                     tenant_id: inserted.id,
+                    default_currency: None,
+                    default_trial_duration_days: None,
+                    default_net_terms: None,
+                    default_tax_category: None,
                 },
             )
             .await?;
@@ -0,0 +1,239 @@
+use error_stack::Report;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::customers::CustomerRow;
+use diesel_models::invoices::InvoiceRow;
+
+use crate::compute::clients::usage::{GroupedUsageData, UsageSeriesPoint, UsageWindow};
+use crate::domain::usage::{
+    daily_breakdown, dimension_breakdown, LineItemUsageDetail, UsageQuery, UsageSeries,
+    MAX_USAGE_GROUP_BY,
+};
+use crate::domain::{BillableMetric, Customer, DetailedInvoice, Period};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait UsageInterface {
+    /// The usage of a customer for a metric per window, queried from the metering service
+    async fn query_usage(&self, query: UsageQuery) -> StoreResult<UsageSeries>;
+
+    /// The usage behind a usage-based line of an invoice, per day and per dimension, with the number of events.
+    /// Grouped by the usage group key of the metric when no property is given
+    async fn get_line_item_usage_detail(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        line_item_id: &str,
+        group_by: Vec<String>,
+    ) -> StoreResult<LineItemUsageDetail>;
+}
+
+#[async_trait::async_trait]
+impl UsageInterface for Store {
+    async fn query_usage(&self, query: UsageQuery) -> StoreResult<UsageSeries> {
+        query.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        let customer: Customer =
+            CustomerRow::find_by_id(&mut conn, query.customer_id, query.tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .try_into()?;
+
+        let metric: BillableMetric =
+            BillableMetricRow::find_by_id(&mut conn, query.metric_id, query.tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+                .try_into()?;
+
+        let points = self
+            .usage_client
+            .fetch_usage_series(
+                &query.tenant_id,
+                &customer.id,
+                &customer.alias,
+                &metric,
+                query.period.clone(),
+                query.group_by,
+                query.window,
+            )
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to fetch usage series".to_string(), e)
+            })?;
+
+        // charted in the unit the metric is billed in
+        let points = points
+            .into_iter()
+            .map(|p| UsageSeriesPoint {
+                value: metric.convert_units(p.value),
+                ..p
+            })
+            .collect();
+
+        Ok(UsageSeries {
+            metric_id: metric.id,
+            period: query.period,
+            window: query.window,
+            points,
+        })
+    }
+
+    async fn get_line_item_usage_detail(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+        line_item_id: &str,
+        group_by: Vec<String>,
+    ) -> StoreResult<LineItemUsageDetail> {
+        let mut conn = self.get_conn().await?;
+
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = InvoiceRow::find_by_id(&mut conn, tenant_id, invoice_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .try_into()?;
+
+        let line = invoice
+            .line_items
+            .iter()
+            .find(|l| l.local_id == line_item_id)
+            .ok_or_else(|| StoreError::ValueNotFound("invoice line not found".to_string()))?;
+
+        let metric_id = line.metric_id.ok_or_else(|| {
+            StoreError::InvalidArgument("The invoice line is not usage-based".to_string())
+        })?;
+
+        let metric: BillableMetric = BillableMetricRow::find_by_id(&mut conn, metric_id, tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .try_into()?;
+
+        let group_by = if group_by.is_empty() {
+            metric
+                .usage_group_key
+                .clone()
+                .filter(|key| !key.trim().is_empty())
+                .into_iter()
+                .collect()
+        } else {
+            group_by
+        };
+
+        if group_by.len() > MAX_USAGE_GROUP_BY || group_by.iter().any(|key| key.trim().is_empty()) {
+            return Err(StoreError::InvalidArgument(format!(
+                "The usage can be grouped by {} non-empty properties at most",
+                MAX_USAGE_GROUP_BY
+            ))
+            .into());
+        }
+
+        let period = Period {
+            start: line.start_date,
+            end: line.end_date,
+        };
+
+        let metering_error =
+            |e| StoreError::MeteringServiceError("Failed to fetch line usage".to_string(), e);
+
+        let usage: Decimal = self
+            .usage_client
+            .fetch_usage(
+                &tenant_id,
+                &customer.id,
+                &customer.alias,
+                &metric,
+                period.clone(),
+            )
+            .await
+            .map_err(metering_error)?
+            .data
+            .iter()
+            .map(|u| u.value)
+            .sum();
+
+        let daily_values = self
+            .usage_client
+            .fetch_usage_series(
+                &tenant_id,
+                &customer.id,
+                &customer.alias,
+                &metric,
+                period.clone(),
+                vec![],
+                UsageWindow::Day,
+            )
+            .await
+            .map_err(metering_error)?;
+
+        // counted per day and dimension, as the counts add up both ways
+        let counts = self
+            .usage_client
+            .fetch_event_count_series(
+                &tenant_id,
+                &customer.id,
+                &customer.alias,
+                &metric,
+                period.clone(),
+                group_by.clone(),
+                UsageWindow::Day,
+            )
+            .await
+            .map_err(metering_error)?;
+
+        let dimensions = if group_by.is_empty() {
+            vec![]
+        } else {
+            let grouped = self
+                .usage_client
+                .fetch_grouped_usage(
+                    &tenant_id,
+                    &customer.id,
+                    &customer.alias,
+                    &metric,
+                    period.clone(),
+                    group_by.clone(),
+                )
+                .await
+                .map_err(metering_error)?
+                .data
+                .into_iter()
+                .map(|u| GroupedUsageData {
+                    value: metric.convert_units(u.value),
+                    dimensions: u.dimensions,
+                })
+                .collect();
+
+            dimension_breakdown(grouped, &counts)
+        };
+
+        let daily_values = daily_values
+            .into_iter()
+            .map(|p| UsageSeriesPoint {
+                value: metric.convert_units(p.value),
+                ..p
+            })
+            .collect();
+
+        let days = daily_breakdown(daily_values, &counts);
+
+        Ok(LineItemUsageDetail {
+            invoice_id: invoice.id,
+            line_item_id: line.local_id.clone(),
+            metric_id: metric.id,
+            metric_code: metric.code.clone(),
+            period,
+            line_quantity: line.quantity,
+            usage: metric.billed_quantity(usage),
+            event_count: days.iter().map(|d| d.event_count).sum(),
+            group_by,
+            days,
+            dimensions,
+        })
+    }
+}
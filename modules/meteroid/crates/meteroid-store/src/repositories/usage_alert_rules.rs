@@ -0,0 +1,491 @@
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::customers::CustomerRow;
+use diesel_models::subscriptions::SubscriptionRow;
+use diesel_models::usage_alert_rules::{
+    UsageAlertRuleEventRow, UsageAlertRuleEventRowNew, UsageAlertRuleRow, UsageAlertRuleRowNew,
+    UsageAlertRuleRowPatch,
+};
+
+use crate::domain::enums::UsageAlertRuleStatusEnum;
+use crate::domain::usage_alert_rules::{
+    previous_window, UsageAlertMeasure, UsageAlertRule, UsageAlertRuleDefinition,
+    UsageAlertRuleEmailPayload, UsageAlertRuleEvent, UsageAlertRuleEventNew, UsageAlertRuleNew,
+};
+use crate::domain::{
+    BillableMetric, CursorPaginatedVec, CursorPaginationRequest, Customer, OutboxEvent, OutboxNew,
+    Period,
+};
+use crate::errors::StoreError;
+use crate::{Store, StoreResult};
+
+const MAX_RULE_EVENTS: i64 = 200;
+
+#[async_trait::async_trait]
+pub trait UsageAlertRuleInterface {
+    async fn insert_usage_alert_rule(&self, rule: UsageAlertRuleNew)
+        -> StoreResult<UsageAlertRule>;
+
+    async fn find_usage_alert_rule_by_id(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageAlertRule>;
+
+    /// The rules of the tenant, or the rules applying to the customer
+    async fn list_usage_alert_rules(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlertRule>>;
+
+    /// Replaces the definition of the rule. Its metric and scope cannot change, as its state depends on them
+    async fn update_usage_alert_rule(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        definition: UsageAlertRuleDefinition,
+    ) -> StoreResult<UsageAlertRule>;
+
+    async fn archive_usage_alert_rule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    /// The current state of the rule for each customer it fired for
+    async fn list_usage_alert_rule_states(
+        &self,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>>;
+
+    /// The firing and resolution history of the rule, most recent first
+    async fn list_usage_alert_rule_events(
+        &self,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>>;
+
+    async fn find_usage_alert_rule_event(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageAlertRuleEvent>;
+
+    async fn list_active_usage_alert_rules(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<UsageAlertRule>>;
+
+    /// Measures the usage of the customers in the scope of the rule and records the rule firing or
+    /// resolving for them, notified through webhooks and email. Returns the recorded events
+    async fn evaluate_usage_alert_rule(
+        &self,
+        rule: UsageAlertRule,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>>;
+}
+
+#[async_trait::async_trait]
+impl UsageAlertRuleInterface for Store {
+    async fn insert_usage_alert_rule(
+        &self,
+        rule: UsageAlertRuleNew,
+    ) -> StoreResult<UsageAlertRule> {
+        rule.definition.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        // both must belong to the tenant
+        if let Some(customer_id) = rule.customer_id {
+            CustomerRow::find_by_id(&mut conn, customer_id, rule.tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+        }
+        BillableMetricRow::find_by_id(&mut conn, rule.billable_metric_id, rule.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let row: UsageAlertRuleRowNew = rule.try_into()?;
+
+        row.insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .and_then(TryInto::try_into)
+    }
+
+    async fn find_usage_alert_rule_by_id(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageAlertRule> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRuleRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .and_then(TryInto::try_into)
+    }
+
+    async fn list_usage_alert_rules(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlertRule>> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRuleRow::list_by_tenant_id(&mut conn, tenant_id, customer_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .into_iter()
+            .map(TryInto::try_into)
+            .collect()
+    }
+
+    async fn update_usage_alert_rule(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+        definition: UsageAlertRuleDefinition,
+    ) -> StoreResult<UsageAlertRule> {
+        definition.validate()?;
+
+        let mut conn = self.get_conn().await?;
+
+        let patch: UsageAlertRuleRowPatch = definition.try_into()?;
+
+        let updated = patch
+            .update(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if updated == 0 {
+            return Err(StoreError::ValueNotFound(format!("usage alert rule {}", id)).into());
+        }
+
+        UsageAlertRuleRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .and_then(TryInto::try_into)
+    }
+
+    async fn archive_usage_alert_rule(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let updated = UsageAlertRuleRow::archive(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if updated == 0 {
+            return Err(StoreError::ValueNotFound(format!("usage alert rule {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    async fn list_usage_alert_rule_states(
+        &self,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRuleEventRow::list_latest_by_rule_id(&mut conn, tenant_id, rule_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn list_usage_alert_rule_events(
+        &self,
+        tenant_id: Uuid,
+        rule_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRuleEventRow::list_by_rule_id(
+            &mut conn,
+            tenant_id,
+            rule_id,
+            customer_id,
+            MAX_RULE_EVENTS,
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+        .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn find_usage_alert_rule_event(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageAlertRuleEvent> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRuleEventRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_active_usage_alert_rules(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<UsageAlertRule>> {
+        let mut conn = self.get_conn().await?;
+
+        let rules = UsageAlertRuleRow::list_active(&mut conn, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: rules
+                .items
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<StoreResult<Vec<_>>>()?,
+            next_cursor: rules.next_cursor,
+        })
+    }
+
+    async fn evaluate_usage_alert_rule(
+        &self,
+        rule: UsageAlertRule,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageAlertRuleEvent>> {
+        let mut conn = self.get_conn().await?;
+
+        let metric: BillableMetric =
+            BillableMetricRow::find_by_id(&mut conn, rule.billable_metric_id, rule.tenant_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)
+                .and_then(TryInto::try_into)?;
+
+        let customer_ids = match rule.customer_id {
+            Some(customer_id) => vec![customer_id],
+            None => SubscriptionRow::list_customer_ids_billing_metric(
+                &mut conn,
+                rule.tenant_id,
+                rule.billable_metric_id,
+                now.date(),
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?,
+        };
+
+        drop(conn);
+
+        let mut events = Vec::new();
+
+        for customer_id in customer_ids {
+            // a customer failing to be measured doesn't prevent the others from being alerted
+            match self
+                .evaluate_usage_alert_rule_for(&rule, &metric, customer_id, now)
+                .await
+            {
+                Ok(Some(event)) => events.push(event),
+                Ok(None) => {}
+                Err(e) => log::warn!(
+                    "Failed to evaluate usage alert rule {} for customer {}: {:?}",
+                    rule.id,
+                    customer_id,
+                    e
+                ),
+            }
+        }
+
+        let mut conn = self.get_conn().await?;
+        UsageAlertRuleRow::mark_evaluated(&mut conn, rule.id, now)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(events)
+    }
+}
+
+impl Store {
+    async fn evaluate_usage_alert_rule_for(
+        &self,
+        rule: &UsageAlertRule,
+        metric: &BillableMetric,
+        customer_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<UsageAlertRuleEvent>> {
+        let today = now.date();
+
+        let window = match rule.definition.window.rolling_period(today) {
+            Some(window) => window,
+            None => {
+                let Some(metered) = self
+                    .find_metered_subscription(
+                        rule.tenant_id,
+                        customer_id,
+                        rule.billable_metric_id,
+                        now,
+                    )
+                    .await?
+                else {
+                    // nothing to measure against, the rule stays as is until a subscription bills the metric
+                    return Ok(None);
+                };
+
+                // the usage of the period so far, today included
+                Period {
+                    start: metered.period.start,
+                    end: today
+                        .succ_opt()
+                        .map_or(metered.period.end, |d| d.min(metered.period.end)),
+                }
+            }
+        };
+
+        let mut conn = self.get_conn().await?;
+
+        let customer: Customer = CustomerRow::find_by_id(&mut conn, customer_id, rule.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .and_then(TryInto::try_into)?;
+
+        let current = UsageAlertRuleEventRow::find_latest(&mut conn, rule.id, customer_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?
+            .map(UsageAlertRuleEvent::from);
+
+        drop(conn);
+
+        let usage = self.fetch_window_usage(&customer, metric, &window).await?;
+
+        let previous_usage = if rule.definition.depends_on_growth() {
+            Some(
+                self.fetch_window_usage(&customer, metric, &previous_window(&window))
+                    .await?,
+            )
+        } else {
+            None
+        };
+
+        let measure = UsageAlertMeasure {
+            window,
+            usage,
+            previous_usage,
+        };
+
+        let met = rule.definition.is_met(&measure);
+
+        let Some(status) = rule.transition(met, current.as_ref()) else {
+            return Ok(None);
+        };
+
+        let row: UsageAlertRuleEventRowNew = UsageAlertRuleEventNew {
+            tenant_id: rule.tenant_id,
+            rule_id: rule.id,
+            customer_id,
+            status,
+            measure: measure.clone(),
+        }
+        .into();
+
+        let email_payload = rule
+            .definition
+            .notify_email
+            .clone()
+            .map(|recipient| {
+                serde_json::to_value(UsageAlertRuleEmailPayload {
+                    rule_id: rule.id,
+                    rule_name: rule.definition.name.clone(),
+                    event_id: row.id,
+                    status,
+                    recipient,
+                    customer_name: customer.name.clone(),
+                    metric_name: metric.name.clone(),
+                    window_start: measure.window.start,
+                    window_end: measure.window.end,
+                    usage: measure.usage,
+                    growth_percent: measure.growth_percent(),
+                })
+                .map_err(|e| {
+                    StoreError::SerdeError(
+                        "Failed to serialize usage alert rule email payload".to_string(),
+                        e,
+                    )
+                })
+            })
+            .transpose()?;
+
+        let tenant_id = rule.tenant_id;
+
+        let event: UsageAlertRuleEvent = self
+            .transaction(|conn| {
+                async move {
+                    let event = row
+                        .insert(conn)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                    if let Some(payload) = email_payload {
+                        self.internal
+                            .insert_outbox_item(
+                                conn,
+                                OutboxNew {
+                                    event_type: OutboxEvent::UsageAlertRuleEmailRequested,
+                                    resource_id: event.id,
+                                    tenant_id,
+                                    payload: Some(payload),
+                                },
+                            )
+                            .await?;
+                    }
+
+                    Ok(event)
+                }
+                .scope_boxed()
+            })
+            .await?
+            .into();
+
+        if rule.definition.notify_webhook {
+            let bus_event = match status {
+                UsageAlertRuleStatusEnum::Firing => {
+                    Event::usage_alert_rule_firing(event.id, tenant_id)
+                }
+                UsageAlertRuleStatusEnum::Resolved => {
+                    Event::usage_alert_rule_resolved(event.id, tenant_id)
+                }
+            };
+
+            let _ = self.eventbus.publish(bus_event).await;
+        }
+
+        Ok(Some(event))
+    }
+
+    async fn fetch_window_usage(
+        &self,
+        customer: &Customer,
+        metric: &BillableMetric,
+        window: &Period,
+    ) -> StoreResult<Decimal> {
+        let usage = self
+            .usage_client
+            .fetch_usage(
+                &customer.tenant_id,
+                &customer.id,
+                &customer.alias,
+                metric,
+                window.clone(),
+            )
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to fetch usage".to_string(), e)
+            })?;
+
+        let total: Decimal = usage.data.iter().map(|d| d.value).sum();
+
+        Ok(metric.convert_units(total))
+    }
+}
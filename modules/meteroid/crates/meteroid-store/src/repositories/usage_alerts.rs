@@ -0,0 +1,421 @@
+use chrono::NaiveDateTime;
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use rust_decimal::Decimal;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::billable_metrics::BillableMetricRow;
+use diesel_models::customers::CustomerRow;
+use diesel_models::usage_alerts::{UsageAlertRow, UsageAlertRowNew, UsageAlertRowStatePatch};
+
+use crate::domain::enums::BillingPeriodEnum;
+use crate::domain::usage_alerts::{
+    TriggeredUsageAlert, UsageAlert, UsageAlertEmailPayload, UsageAlertNew, UsageAlertThreshold,
+    UsageAlertTransition,
+};
+use crate::domain::{
+    BillableMetric, CursorPaginatedVec, CursorPaginationRequest, Customer, OutboxEvent, OutboxNew,
+    PaginationRequest, Period, SubscriptionDetails, SubscriptionFee,
+};
+use crate::errors::StoreError;
+use crate::repositories::SubscriptionInterface;
+use crate::utils::periods::calculate_periods_for_date;
+use crate::{Store, StoreResult};
+
+const MAX_CUSTOMER_SUBSCRIPTIONS: u32 = 50;
+
+#[async_trait::async_trait]
+pub trait UsageAlertInterface {
+    async fn insert_usage_alert(&self, alert: UsageAlertNew) -> StoreResult<UsageAlert>;
+
+    async fn find_usage_alert_by_id(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<UsageAlert>;
+
+    async fn list_usage_alerts(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlert>>;
+
+    async fn archive_usage_alert(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()>;
+
+    async fn list_active_usage_alerts(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<UsageAlert>>;
+
+    /// Compares the usage of the current period with the alert threshold, and emits the alert
+    /// through webhooks and email when it is crossed
+    async fn evaluate_usage_alert(
+        &self,
+        alert: UsageAlert,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<TriggeredUsageAlert>>;
+}
+
+#[async_trait::async_trait]
+impl UsageAlertInterface for Store {
+    async fn insert_usage_alert(&self, alert: UsageAlertNew) -> StoreResult<UsageAlert> {
+        let mut conn = self.get_conn().await?;
+
+        match &alert.threshold {
+            UsageAlertThreshold::Absolute(value) if *value <= Decimal::ZERO => {
+                return Err(
+                    StoreError::InvalidArgument("threshold must be positive".to_string()).into(),
+                );
+            }
+            UsageAlertThreshold::CapacityPercent(percent) if *percent <= Decimal::ZERO => {
+                return Err(StoreError::InvalidArgument(
+                    "capacity percentage must be positive".to_string(),
+                )
+                .into());
+            }
+            _ => {}
+        }
+
+        if let Some(hysteresis) = alert.hysteresis_percent {
+            if hysteresis < Decimal::ZERO || hysteresis > Decimal::ONE_HUNDRED {
+                return Err(StoreError::InvalidArgument(
+                    "hysteresis must be between 0 and 100".to_string(),
+                )
+                .into());
+            }
+        }
+
+        // both must belong to the tenant
+        CustomerRow::find_by_id(&mut conn, alert.customer_id, alert.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+        BillableMetricRow::find_by_id(&mut conn, alert.billable_metric_id, alert.tenant_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        let row: UsageAlertRowNew = alert.into();
+
+        row.insert(&mut conn)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn find_usage_alert_by_id(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<UsageAlert> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_usage_alerts(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Option<Uuid>,
+    ) -> StoreResult<Vec<UsageAlert>> {
+        let mut conn = self.get_conn().await?;
+
+        UsageAlertRow::list_by_tenant_id(&mut conn, tenant_id, customer_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(|rows| rows.into_iter().map(Into::into).collect())
+    }
+
+    async fn archive_usage_alert(&self, tenant_id: Uuid, id: Uuid) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        let updated = UsageAlertRow::archive(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        if updated == 0 {
+            return Err(StoreError::ValueNotFound(format!("usage alert {}", id)).into());
+        }
+
+        Ok(())
+    }
+
+    async fn list_active_usage_alerts(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<UsageAlert>> {
+        let mut conn = self.get_conn().await?;
+
+        let alerts = UsageAlertRow::list_active(&mut conn, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: alerts.items.into_iter().map(Into::into).collect(),
+            next_cursor: alerts.next_cursor,
+        })
+    }
+
+    async fn evaluate_usage_alert(
+        &self,
+        alert: UsageAlert,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<TriggeredUsageAlert>> {
+        let Some(metered) = self
+            .find_metered_subscription(
+                alert.tenant_id,
+                alert.customer_id,
+                alert.billable_metric_id,
+                now,
+            )
+            .await?
+        else {
+            // nothing to measure against, the alert stays as is until a subscription uses the metric
+            let mut conn = self.get_conn().await?;
+            state_patch(&alert, alert.last_value, now)
+                .update(&mut conn, alert.id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+            return Ok(None);
+        };
+
+        let Some(threshold) = alert.threshold.resolve(metered.capacity_included) else {
+            log::warn!(
+                "Usage alert {} is relative to a capacity, but subscription {} has no capacity commitment for metric {}",
+                alert.id,
+                metered.subscription.id,
+                alert.billable_metric_id
+            );
+            return Ok(None);
+        };
+
+        let usage = self.fetch_alert_usage(&metered).await?;
+
+        let transition = alert.transition(usage, threshold, metered.period.start);
+
+        let mut patch = state_patch(&alert, Some(usage), now);
+
+        match transition {
+            UsageAlertTransition::Unchanged => {
+                let mut conn = self.get_conn().await?;
+                patch
+                    .update(&mut conn, alert.id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                Ok(None)
+            }
+            UsageAlertTransition::Rearm => {
+                patch.triggered = false;
+                patch.triggered_period_start = None;
+
+                let mut conn = self.get_conn().await?;
+                patch
+                    .update(&mut conn, alert.id)
+                    .await
+                    .map_err(Into::<Report<StoreError>>::into)?;
+                Ok(None)
+            }
+            UsageAlertTransition::Fire => {
+                patch.triggered = true;
+                patch.triggered_period_start = Some(metered.period.start);
+                patch.last_triggered_at = Some(now);
+
+                let customer = self.alert_customer(&alert).await?;
+                // the billing email of the customer is skipped if it bounced
+                let recipient = alert.notify_email.clone().or(customer
+                    .billing_email()
+                    .filter(|_| customer.billing_email_status.is_deliverable())
+                    .cloned());
+
+                let email_payload = recipient
+                    .map(|recipient| {
+                        serde_json::to_value(UsageAlertEmailPayload {
+                            usage_alert_id: alert.id,
+                            recipient,
+                            customer_name: customer.name.clone(),
+                            metric_name: metered.metric.name.clone(),
+                            period_start: metered.period.start,
+                            period_end: metered.period.end,
+                            usage,
+                            threshold,
+                        })
+                        .map_err(|e| {
+                            StoreError::SerdeError(
+                                "Failed to serialize usage alert email payload".to_string(),
+                                e,
+                            )
+                        })
+                    })
+                    .transpose()?;
+
+                let alert_id = alert.id;
+                let tenant_id = alert.tenant_id;
+
+                self.transaction(|conn| {
+                    async move {
+                        patch
+                            .update(conn, alert_id)
+                            .await
+                            .map_err(Into::<Report<StoreError>>::into)?;
+
+                        if let Some(payload) = email_payload {
+                            self.internal
+                                .insert_outbox_item(
+                                    conn,
+                                    OutboxNew {
+                                        event_type: OutboxEvent::UsageAlertEmailRequested,
+                                        resource_id: alert_id,
+                                        tenant_id,
+                                        payload: Some(payload),
+                                    },
+                                )
+                                .await?;
+                        }
+
+                        Ok(())
+                    }
+                    .scope_boxed()
+                })
+                .await?;
+
+                let _ = self
+                    .eventbus
+                    .publish(Event::usage_alert_triggered(alert_id, tenant_id))
+                    .await;
+
+                Ok(Some(TriggeredUsageAlert {
+                    subscription_id: metered.subscription.id,
+                    period: metered.period,
+                    alert,
+                    usage,
+                    threshold,
+                }))
+            }
+        }
+    }
+}
+
+/// Subscription of the customer billing the metric, with the period in progress
+pub(crate) struct MeteredSubscription {
+    pub(crate) subscription: SubscriptionDetails,
+    pub(crate) metric: BillableMetric,
+    pub(crate) period: Period,
+    pub(crate) capacity_included: Option<u64>,
+}
+
+fn state_patch(
+    alert: &UsageAlert,
+    last_value: Option<Decimal>,
+    now: NaiveDateTime,
+) -> UsageAlertRowStatePatch {
+    UsageAlertRowStatePatch {
+        triggered: alert.triggered,
+        triggered_period_start: alert.triggered_period_start,
+        last_value,
+        last_triggered_at: alert.last_triggered_at,
+        last_checked_at: Some(now),
+    }
+}
+
+impl Store {
+    async fn alert_customer(&self, alert: &UsageAlert) -> StoreResult<Customer> {
+        let mut conn = self.get_conn().await?;
+
+        CustomerRow::find_by_id(&mut conn, alert.customer_id, alert.tenant_id)
+            .await
+            .map_err(Into::into)
+            .and_then(TryInto::try_into)
+    }
+
+    pub(crate) async fn find_metered_subscription(
+        &self,
+        tenant_id: Uuid,
+        customer_id: Uuid,
+        metric_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Option<MeteredSubscription>> {
+        let today = now.date();
+
+        let subscriptions = self
+            .list_subscriptions(
+                tenant_id,
+                Some(customer_id),
+                None,
+                PaginationRequest {
+                    per_page: Some(MAX_CUSTOMER_SUBSCRIPTIONS),
+                    page: 0,
+                },
+            )
+            .await?;
+
+        let active = subscriptions.items.into_iter().filter(|s| {
+            s.activated_at.is_some()
+                && s.canceled_at.is_none()
+                && s.billing_start_date <= today
+                && s.billing_end_date.map_or(true, |end| end >= today)
+        });
+
+        for subscription in active {
+            let details = self
+                .get_subscription_details(tenant_id, subscription.id)
+                .await?;
+
+            let Some(component) = details
+                .price_components
+                .iter()
+                .find(|c| c.metric_id() == Some(metric_id))
+            else {
+                continue;
+            };
+
+            let Some(metric) = details.metrics.iter().find(|m| m.id == metric_id) else {
+                continue;
+            };
+
+            let capacity_included = match &component.fee {
+                SubscriptionFee::Capacity { included, .. } => Some(*included),
+                _ => None,
+            };
+
+            // usage is billed in arrears on the component period, the alert follows the same window
+            let billing_period = component
+                .period
+                .as_billing_period_opt()
+                .unwrap_or(BillingPeriodEnum::Monthly);
+
+            let period = calculate_periods_for_date(
+                details.billing_start_date,
+                details.anchor_day(),
+                today,
+                &billing_period,
+            )
+            .advance;
+
+            return Ok(Some(MeteredSubscription {
+                metric: metric.clone(),
+                period,
+                capacity_included,
+                subscription: details,
+            }));
+        }
+
+        Ok(None)
+    }
+
+    async fn fetch_alert_usage(&self, metered: &MeteredSubscription) -> StoreResult<Decimal> {
+        let details = &metered.subscription;
+        let metric = &metered.metric;
+
+        let usage = self
+            .usage_client
+            .fetch_usage(
+                &details.tenant_id,
+                &details.customer_id,
+                &details.customer_external_id,
+                metric,
+                metered.period.clone(),
+            )
+            .await
+            .map_err(|e| {
+                StoreError::MeteringServiceError("Failed to fetch usage".to_string(), e)
+            })?;
+
+        let total: Decimal = usage.data.iter().map(|d| d.value).sum();
+
+        Ok(metric.convert_units(total))
+    }
+}
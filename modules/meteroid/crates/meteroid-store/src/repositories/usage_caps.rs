@@ -0,0 +1,182 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use error_stack::Report;
+use uuid::Uuid;
+
+use common_eventbus::Event;
+use diesel_models::subscriptions::SubscriptionRow;
+use diesel_models::usage_caps::{UsageCapReachedRow, UsageCapReachedRowNew};
+
+use crate::compute::InvoiceLineInterface;
+use crate::domain::usage_caps::{
+    UsageCapCharges, UsageCapReached, UsageCapReachedNew, UsageCapStatus,
+};
+use crate::domain::{CreatedSubscription, CursorPaginatedVec, CursorPaginationRequest};
+use crate::errors::StoreError;
+use crate::repositories::SubscriptionInterface;
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait UsageCapInterface {
+    /// State of the capped fees of the subscription in their current period
+    async fn list_usage_caps(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageCapStatus>>;
+
+    /// Records the caps reached in the current period and emits them through webhooks, once per period.
+    /// Returns the newly reached caps
+    async fn evaluate_usage_caps(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageCapReached>>;
+
+    async fn find_usage_cap_reached(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageCapReached>;
+
+    /// Active subscriptions with a capped fee at that date
+    async fn list_usage_cap_candidates(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<CreatedSubscription>>;
+}
+
+#[async_trait::async_trait]
+impl UsageCapInterface for Store {
+    async fn list_usage_caps(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageCapStatus>> {
+        let charges = self
+            .usage_cap_charges(tenant_id, subscription_id, now)
+            .await?;
+
+        let mut conn = self.get_conn().await?;
+
+        let reached =
+            UsageCapReachedRow::list_by_subscription_id(&mut conn, tenant_id, subscription_id)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(charges
+            .into_iter()
+            .map(|charges| {
+                let reached_at = reached
+                    .iter()
+                    .find(|r| r.fee_id == charges.fee_id && r.period_start == charges.period.start)
+                    .map(|r| r.reached_at);
+
+                UsageCapStatus {
+                    subscription_id,
+                    charges,
+                    reached_at,
+                }
+            })
+            .collect())
+    }
+
+    async fn evaluate_usage_caps(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageCapReached>> {
+        let charges = self
+            .usage_cap_charges(tenant_id, subscription_id, now)
+            .await?;
+
+        let mut conn = self.get_conn().await?;
+        let mut inserted = Vec::new();
+
+        for charges in charges.into_iter().filter(|c| c.limit_reached()) {
+            let row: UsageCapReachedRowNew = UsageCapReachedNew {
+                tenant_id,
+                subscription_id,
+                charges,
+            }
+            .into();
+
+            let Some(reached) = row
+                .insert_if_absent(&mut conn)
+                .await
+                .map_err(Into::<Report<StoreError>>::into)?
+            else {
+                continue;
+            };
+
+            let _ = self
+                .eventbus
+                .publish(Event::usage_cap_reached(reached.id, tenant_id))
+                .await;
+
+            inserted.push(reached.into());
+        }
+
+        Ok(inserted)
+    }
+
+    async fn find_usage_cap_reached(
+        &self,
+        tenant_id: Uuid,
+        id: Uuid,
+    ) -> StoreResult<UsageCapReached> {
+        let mut conn = self.get_conn().await?;
+
+        UsageCapReachedRow::find_by_id(&mut conn, tenant_id, id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)
+            .map(Into::into)
+    }
+
+    async fn list_usage_cap_candidates(
+        &self,
+        date: NaiveDate,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<CreatedSubscription>> {
+        let mut conn = self.get_conn().await?;
+
+        let rows = SubscriptionRow::list_usage_cap_candidates(&mut conn, date, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: rows.items.into_iter().map(Into::into).collect(),
+            next_cursor: rows.next_cursor,
+        })
+    }
+}
+
+impl Store {
+    async fn usage_cap_charges(
+        &self,
+        tenant_id: Uuid,
+        subscription_id: Uuid,
+        now: NaiveDateTime,
+    ) -> StoreResult<Vec<UsageCapCharges>> {
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+
+        // usage of today is included
+        let until = now.date().succ_opt().unwrap_or(now.date());
+
+        if until <= subscription.billing_start_date {
+            return Ok(vec![]);
+        }
+
+        let charges = self
+            .compute_usage_cap_charges(&until, &subscription)
+            .await?;
+
+        Ok(charges)
+    }
+}
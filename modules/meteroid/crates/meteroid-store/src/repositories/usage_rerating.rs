@@ -0,0 +1,205 @@
+use std::collections::BTreeSet;
+
+use diesel_async::scoped_futures::ScopedFutureExt;
+use error_stack::Report;
+use uuid::Uuid;
+
+use diesel_models::invoices::InvoiceRow;
+
+use crate::compute::InvoiceLineInterface;
+use crate::domain::enums::{InvoiceStatusEnum, InvoiceType};
+use crate::domain::usage_rerating::{usage_adjustment_lines, BackfilledUsage, UsageRerating};
+use crate::domain::{CursorPaginatedVec, CursorPaginationRequest, Invoice};
+use crate::errors::StoreError;
+use crate::repositories::customer_balance::CustomerBalance;
+use crate::repositories::invoices::insert_invoice;
+use crate::repositories::invoicing_entities::InvoicingEntityInterface;
+use crate::repositories::subscriptions::off_cycle_invoice;
+use crate::repositories::{InvoiceInterface, SubscriptionInterface, TenantInterface};
+use crate::{Store, StoreResult};
+
+#[async_trait::async_trait]
+pub trait UsageReratingInterface {
+    /// Marks the invoices whose usage periods include the backfilled events as outdated.
+    /// The drafts are then recomputed by the price worker, the finalized invoices are re-rated
+    async fn mark_backfilled_usage(
+        &self,
+        tenant_id: Uuid,
+        usages: Vec<BackfilledUsage>,
+    ) -> StoreResult<usize>;
+
+    async fn list_usage_outdated_invoices(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<Invoice>>;
+
+    /// Invoices the additional usage of a finalized invoice, or credits the customer for the usage that was overbilled
+    async fn rerate_finalized_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<UsageRerating>;
+}
+
+#[async_trait::async_trait]
+impl UsageReratingInterface for Store {
+    async fn mark_backfilled_usage(
+        &self,
+        tenant_id: Uuid,
+        usages: Vec<BackfilledUsage>,
+    ) -> StoreResult<usize> {
+        let mut conn = self.get_conn().await?;
+
+        let mut invoice_ids = BTreeSet::new();
+
+        for usage in &usages {
+            if usage.from > usage.to {
+                return Err(StoreError::InvalidArgument(format!(
+                    "backfilled usage of customer {} ends before it starts",
+                    usage.customer_id
+                ))
+                .into());
+            }
+
+            let candidates = InvoiceRow::list_usage_rerating_candidates(
+                &mut conn,
+                tenant_id,
+                usage.customer_id,
+                usage.from.date(),
+            )
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+            for row in candidates {
+                let invoice: Invoice = row.try_into()?;
+                if usage.affects(&invoice) {
+                    invoice_ids.insert(invoice.id);
+                }
+            }
+        }
+
+        if invoice_ids.is_empty() {
+            return Ok(0);
+        }
+
+        InvoiceRow::mark_usage_outdated(
+            &mut conn,
+            tenant_id,
+            invoice_ids.into_iter().collect(),
+            chrono::Utc::now().naive_utc(),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)
+    }
+
+    async fn list_usage_outdated_invoices(
+        &self,
+        pagination: CursorPaginationRequest,
+    ) -> StoreResult<CursorPaginatedVec<Invoice>> {
+        let mut conn = self.get_conn().await?;
+
+        let invoices = InvoiceRow::list_usage_outdated_finalized(&mut conn, pagination.into())
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(CursorPaginatedVec {
+            items: invoices
+                .items
+                .into_iter()
+                .map(TryInto::try_into)
+                .collect::<Result<Vec<_>, _>>()?,
+            next_cursor: invoices.next_cursor,
+        })
+    }
+
+    async fn rerate_finalized_invoice(
+        &self,
+        tenant_id: Uuid,
+        invoice_id: Uuid,
+    ) -> StoreResult<UsageRerating> {
+        let detailed = self.find_invoice_by_id(tenant_id, invoice_id).await?;
+        let invoice = detailed.invoice;
+
+        let (Some(marked_at), InvoiceStatusEnum::Finalized) =
+            (invoice.usage_outdated_at, &invoice.status)
+        else {
+            return Ok(UsageRerating::Unchanged);
+        };
+
+        let subscription_id = invoice.subscription_id.ok_or(StoreError::InvalidArgument(
+            "Cannot re-rate invoice without subscription_id".into(),
+        ))?;
+
+        let subscription = self
+            .get_subscription_details(tenant_id, subscription_id)
+            .await?;
+        let rerated_lines = self
+            .compute_dated_invoice_lines(
+                &invoice.invoice_date,
+                &subscription.with_cadence_fees(invoice.billing_cadence.as_ref()),
+            )
+            .await?;
+
+        let adjustment_lines = usage_adjustment_lines(&invoice.line_items, &rerated_lines);
+        let delta: i64 = adjustment_lines.iter().map(|l| l.total).sum();
+
+        let tenant_payment_terms = self.get_payment_terms_by_tenant_id(tenant_id).await?;
+        let invoicing_entity = self
+            .get_invoicing_entity(tenant_id, Some(detailed.customer.invoicing_entity_id))
+            .await?;
+        let customer = detailed.customer;
+
+        self.transaction(|conn| {
+            async move {
+                let cleared =
+                    InvoiceRow::clear_usage_outdated(conn, invoice.id, tenant_id, marked_at)
+                        .await
+                        .map_err(Into::<Report<StoreError>>::into)?;
+
+                // marked again meanwhile, the next run re-rates it with the latest usage
+                if cleared == 0 {
+                    return Ok(UsageRerating::Unchanged);
+                }
+
+                if delta > 0 {
+                    let adjustment = off_cycle_invoice(
+                        &subscription,
+                        &customer,
+                        &invoicing_entity,
+                        &tenant_payment_terms,
+                        InvoiceType::Adjustment,
+                        chrono::Utc::now().naive_utc().date(),
+                        adjustment_lines,
+                    )?;
+
+                    let inserted = insert_invoice(conn, adjustment).await?;
+
+                    Ok(UsageRerating::Invoiced(inserted))
+                } else if delta < 0 {
+                    let credit = i32::try_from(-delta).map_err(|_| {
+                        StoreError::InvalidArgument("usage credit is too large".to_string())
+                    })?;
+
+                    CustomerBalance::update_with_note(
+                        conn,
+                        customer.id,
+                        tenant_id,
+                        credit,
+                        Some(invoice.id),
+                        Some(format!(
+                            "Usage adjustment of invoice {}",
+                            invoice.invoice_number
+                        )),
+                    )
+                    .await?;
+
+                    Ok(UsageRerating::Credited(-delta))
+                } else {
+                    Ok(UsageRerating::Unchanged)
+                }
+            }
+            .scope_boxed()
+        })
+        .await
+    }
+}
@@ -5,12 +5,17 @@ use crate::domain::webhooks::{
 use crate::domain::{OrderByRequest, PaginatedVec, PaginationRequest};
 use crate::errors::StoreError;
 use crate::{Store, StoreResult};
+use chrono::NaiveDateTime;
 use diesel_models::webhooks::{
-    WebhookInEventRowNew, WebhookOutEndpointRow, WebhookOutEventRow, WebhookOutEventRowNew,
+    WebhookInDedupRowNew, WebhookInEventRowNew, WebhookOutEndpointRow, WebhookOutEventRow,
+    WebhookOutEventRowNew,
 };
 use error_stack::Report;
 use uuid::Uuid;
 
+// providers retry the failed deliveries for a few days
+const WEBHOOK_IN_DEDUP_DAYS: i64 = 7;
+
 #[async_trait::async_trait]
 pub trait WebhooksInterface {
     async fn insert_webhook_out_endpoint(
@@ -40,6 +45,22 @@ pub trait WebhooksInterface {
         &self,
         event: WebhookInEventNew,
     ) -> StoreResult<WebhookInEvent>;
+
+    /// Records a provider event before processing it. Returns false when the event was already received in the
+    /// last days, as a replay or a duplicate delivery
+    async fn claim_webhook_in_event(
+        &self,
+        provider_config_id: Uuid,
+        event_id: String,
+        received_at: NaiveDateTime,
+    ) -> StoreResult<bool>;
+
+    /// Forgets an event that failed to process, so that the retry of the provider is processed
+    async fn release_webhook_in_event(
+        &self,
+        provider_config_id: Uuid,
+        event_id: &str,
+    ) -> StoreResult<()>;
 }
 
 #[async_trait::async_trait]
@@ -48,6 +69,10 @@ impl WebhooksInterface for Store {
         &self,
         endpoint: WebhookOutEndpointNew,
     ) -> StoreResult<WebhookOutEndpoint> {
+        if let Some(batching) = &endpoint.batching {
+            batching.validate()?;
+        }
+
         let insertable = endpoint.to_row(&self.settings.crypt_key)?;
 
         let mut conn = self.get_conn().await?;
@@ -134,4 +159,46 @@ impl WebhooksInterface for Store {
             .map(Into::into)
             .map_err(Into::into)
     }
+
+    async fn claim_webhook_in_event(
+        &self,
+        provider_config_id: Uuid,
+        event_id: String,
+        received_at: NaiveDateTime,
+    ) -> StoreResult<bool> {
+        let mut conn = self.get_conn().await?;
+
+        WebhookInDedupRowNew::delete_received_before(
+            &mut conn,
+            provider_config_id,
+            received_at - chrono::Duration::days(WEBHOOK_IN_DEDUP_DAYS),
+        )
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        let inserted = WebhookInDedupRowNew {
+            provider_config_id,
+            event_id,
+            received_at,
+        }
+        .insert_if_absent(&mut conn)
+        .await
+        .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(inserted > 0)
+    }
+
+    async fn release_webhook_in_event(
+        &self,
+        provider_config_id: Uuid,
+        event_id: &str,
+    ) -> StoreResult<()> {
+        let mut conn = self.get_conn().await?;
+
+        WebhookInDedupRowNew::delete(&mut conn, provider_config_id, event_id)
+            .await
+            .map_err(Into::<Report<StoreError>>::into)?;
+
+        Ok(())
+    }
 }
@@ -1,4 +1,5 @@
-use chrono::{Datelike, Months, NaiveDate, Weekday};
+use chrono::{Datelike, Months, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
 
 pub fn start_of_week(date: NaiveDate) -> NaiveDate {
     if date.weekday() == Weekday::Mon {
@@ -74,6 +75,25 @@ pub fn sub_months(date: NaiveDate, n: u32) -> NaiveDate {
         .expect("invalid date")
 }
 
+/// The date in the timezone at the given UTC time
+pub fn local_date(now: NaiveDateTime, timezone: Tz) -> NaiveDate {
+    timezone.from_utc_datetime(&now).date_naive()
+}
+
+/// The UTC time at which the date starts in the timezone.
+/// When a DST transition skips the midnight, the day starts at the end of the transition
+pub fn start_of_day_utc(date: NaiveDate, timezone: Tz) -> NaiveDateTime {
+    (0..24)
+        .filter_map(|hour| {
+            timezone
+                .from_local_datetime(&date.and_time(NaiveTime::from_hms_opt(hour, 0, 0)?))
+                .earliest()
+        })
+        .next()
+        .map(|start| start.naive_utc())
+        .unwrap_or(date.and_time(NaiveTime::MIN))
+}
+
 #[cfg(test)]
 mod tests {
     use crate::utils::datetime::*;
@@ -144,6 +164,52 @@ mod tests {
         assert_eq!(end_of_year, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
     }
 
+    #[test]
+    fn test_local_date() {
+        let now = NaiveDate::from_ymd_opt(2024, 2, 21)
+            .unwrap()
+            .and_hms_opt(18, 30, 0)
+            .unwrap();
+
+        assert_eq!(local_date(now, Tz::UTC), now.date());
+        assert_eq!(
+            local_date(now, Tz::Asia__Singapore),
+            NaiveDate::from_ymd_opt(2024, 2, 22).unwrap()
+        );
+        assert_eq!(
+            local_date(now, Tz::America__Los_Angeles),
+            NaiveDate::from_ymd_opt(2024, 2, 21).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_start_of_day_utc() {
+        let date = NaiveDate::from_ymd_opt(2024, 2, 21).unwrap();
+
+        assert_eq!(
+            start_of_day_utc(date, Tz::UTC),
+            date.and_hms_opt(0, 0, 0).unwrap()
+        );
+        assert_eq!(
+            start_of_day_utc(date, Tz::Asia__Singapore),
+            NaiveDate::from_ymd_opt(2024, 2, 20)
+                .unwrap()
+                .and_hms_opt(16, 0, 0)
+                .unwrap()
+        );
+        assert_eq!(
+            start_of_day_utc(date, Tz::America__New_York),
+            date.and_hms_opt(5, 0, 0).unwrap()
+        );
+
+        // the clocks skip from 00:00 to 01:00 in Santiago
+        let dst = NaiveDate::from_ymd_opt(2024, 9, 8).unwrap();
+        assert_eq!(
+            start_of_day_utc(dst, Tz::America__Santiago),
+            dst.and_hms_opt(4, 0, 0).unwrap()
+        );
+    }
+
     #[test]
     fn test_sub_months() {
         let date = NaiveDate::from_ymd_opt(2024, 2, 21).unwrap();
@@ -1,6 +1,8 @@
+use crate::customer::{Customer, List, ListCustomers};
 use crate::error::{ErrorResponse, StripeError};
 use crate::invoice::{CreateInvoice, CreateInvoiceItem, Invoice, InvoiceItem};
 use crate::request::{Outcome, RetryStrategy};
+use crate::tax_rate::{CreateTaxRate, ListTaxRates, TaxRate};
 use bytes::Bytes;
 use common_domain::StripeSecret;
 use reqwest::header::{HeaderMap, HeaderValue};
@@ -121,6 +123,18 @@ impl StripeClient {
         )
     }
 
+    pub fn void_invoice(
+        &self,
+        invoice_id: &'_ str,
+        secret_key: &'_ StripeSecret,
+    ) -> Response<Invoice> {
+        self.post(
+            &format!("/invoices/{}/void", invoice_id),
+            secret_key,
+            RetryStrategy::default(),
+        )
+    }
+
     pub fn create_invoice_item(
         &self,
         params: CreateInvoiceItem<'_>,
@@ -136,6 +150,65 @@ impl StripeClient {
         )
     }
 
+    pub fn list_customers(
+        &self,
+        params: ListCustomers<'_>,
+        secret_key: &'_ StripeSecret,
+    ) -> Response<List<Customer>> {
+        self.get_query("/customers", params, secret_key, RetryStrategy::default())
+    }
+
+    pub fn list_tax_rates(
+        &self,
+        params: ListTaxRates<'_>,
+        secret_key: &'_ StripeSecret,
+    ) -> Response<List<TaxRate>> {
+        self.get_query("/tax_rates", params, secret_key, RetryStrategy::default())
+    }
+
+    pub fn create_tax_rate(
+        &self,
+        params: CreateTaxRate<'_>,
+        secret_key: &'_ StripeSecret,
+        idempotency_key: String,
+    ) -> Response<TaxRate> {
+        self.post_form(
+            "/tax_rates",
+            params,
+            secret_key,
+            idempotency_key,
+            RetryStrategy::default(),
+        )
+    }
+
+    /// Make a `GET` http request with urlencoded query parameters
+    fn get_query<T: DeserializeOwned + Send + 'static, P: Serialize>(
+        &self,
+        path: &str,
+        params: P,
+        secret_key: &StripeSecret,
+        retry_strategy: RetryStrategy,
+    ) -> Response<T> {
+        let mut url = self.url(path);
+
+        let mut params_buffer = Vec::new();
+        let qs_ser = &mut serde_qs::Serializer::new(&mut params_buffer);
+
+        if let Err(qs_ser_err) = serde_path_to_error::serialize(&params, qs_ser) {
+            return self.err(StripeError::QueryStringSerialize(qs_ser_err));
+        }
+
+        let query = std::str::from_utf8(params_buffer.as_slice())
+            .expect("Unable to extract string from params_buffer");
+        if !query.is_empty() {
+            url.set_query(Some(query));
+        }
+
+        let request_builder = self.create_init_request(Method::GET, url, &secret_key.0, None);
+
+        self.execute(request_builder, retry_strategy)
+    }
+
     fn post<T: DeserializeOwned + Send + 'static>(
         &self,
         path: &str,
@@ -0,0 +1,66 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key holding the meteroid customer id, to match a Stripe customer to an existing customer
+pub const METEROID_CUSTOMER_ID_METADATA: &str = "meteroid_customer_id";
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Address {
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub line1: Option<String>,
+    pub line2: Option<String>,
+    pub postal_code: Option<String>,
+    pub state: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct InvoiceSettings {
+    /// ID of the payment method used as the default for subscriptions and invoices of the customer.
+    pub default_payment_method: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct Customer {
+    pub id: String,
+
+    pub email: Option<String>,
+
+    pub name: Option<String>,
+
+    pub phone: Option<String>,
+
+    /// Three-letter ISO code for the currency the customer can be charged in, lowercase.
+    pub currency: Option<String>,
+
+    pub address: Option<Address>,
+
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+
+    #[serde(default)]
+    pub invoice_settings: InvoiceSettings,
+
+    /// Only set on deleted customers.
+    #[serde(default)]
+    pub deleted: bool,
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ListCustomers<'a> {
+    /// A limit on the number of objects to be returned, between 1 and 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+
+    /// A cursor for pagination, the id of the last object of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starting_after: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize)]
+pub struct List<T> {
+    pub data: Vec<T>,
+
+    /// True if there are more objects after this page.
+    pub has_more: bool,
+}
@@ -7,6 +7,23 @@ pub struct MeteroidMetadata {
     pub meteroid_customer_id: String,
 }
 
+/// Metadata of an invoice item, describing the line of the meteroid invoice it was created from
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct MeteroidLineMetadata {
+    pub meteroid_invoice_id: String,
+    pub meteroid_line_id: String,
+    /// first day of the period of the line, as YYYY-MM-DD
+    pub meteroid_period_start: String,
+    /// last day of the period of the line, as YYYY-MM-DD
+    pub meteroid_period_end: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meteroid_price_component_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meteroid_quantity: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub meteroid_unit_price: Option<String>,
+}
+
 #[derive(Clone, Debug, Default, Deserialize, Serialize)]
 pub struct Period {
     /// The end date of this usage period.
@@ -47,6 +64,14 @@ pub struct Invoice {
     pub status: Option<InvoiceStatus>,
 
     pub currency: Option<String>,
+
+    /// The URL of the page where the customer can view and pay the invoice, once finalized.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub hosted_invoice_url: Option<String>,
+
+    /// The link to download the PDF of the invoice, once finalized.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub invoice_pdf: Option<String>,
 }
 
 /// An enum representing the possible values of an `Invoice`'s `collection_method` field.
@@ -89,6 +114,10 @@ pub struct CreateInvoice<'a> {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub customer: Option<&'a str>,
 
+    /// The tax rates that apply to the items of the invoice that do not have their own.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub default_tax_rates: Vec<&'a str>,
+
     /// Set of [key-value pairs](https://stripe.com/docs/api/metadata) that you can attach to an object.
     ///
     /// This can be useful for storing additional information about the object in a structured format.
@@ -135,6 +164,9 @@ pub struct CreateInvoiceItem<'a> {
     /// See the [Revenue Recognition documentation](https://stripe.com/docs/revenue-recognition/methodology/subscriptions-and-invoicing) for details.
     #[serde(skip_serializing_if = "Option::is_none")]
     pub period: Option<Period>,
+
+    /// Set of [key-value pairs](https://stripe.com/docs/api/metadata) that you can attach to an object.
+    pub metadata: MeteroidLineMetadata,
 }
 
 /// The resource representing a Stripe "InvoiceItem".
@@ -1,5 +1,7 @@
 pub mod client;
+pub mod customer;
 pub mod error;
 pub mod invoice;
 mod request;
+pub mod tax_rate;
 pub mod webhook;
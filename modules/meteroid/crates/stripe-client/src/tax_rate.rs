@@ -0,0 +1,58 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Metadata key holding the meteroid tax rate, in percent, a Stripe tax rate was created for
+pub const METEROID_TAX_RATE_METADATA: &str = "meteroid_tax_rate";
+
+/// The resource representing a Stripe "TaxRate".
+///
+/// For more details see <https://stripe.com/docs/api/tax_rates/object>
+#[derive(Clone, Debug, Default, Deserialize, Serialize)]
+pub struct TaxRate {
+    pub id: String,
+
+    /// The display name of the tax rate, shown to the customer on the invoice.
+    pub display_name: String,
+
+    /// The tax rate percentage out of 100.
+    pub percentage: f64,
+
+    /// Whether the tax rate is included in the amount of the items it applies to.
+    pub inclusive: bool,
+
+    /// Archived tax rates can no longer be applied to new invoices.
+    pub active: bool,
+
+    #[serde(default)]
+    pub metadata: HashMap<String, String>,
+}
+
+#[derive(Clone, Debug, Serialize, Default)]
+pub struct ListTaxRates<'a> {
+    /// Only return the active, or the archived, tax rates.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub active: Option<bool>,
+
+    /// A limit on the number of objects to be returned, between 1 and 100.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+
+    /// A cursor for pagination, the id of the last object of the previous page.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub starting_after: Option<&'a str>,
+}
+
+#[derive(Clone, Debug, Serialize)]
+pub struct CreateTaxRate<'a> {
+    /// The display name of the tax rate, shown to the customer on the invoice.
+    pub display_name: &'a str,
+
+    /// The tax rate percentage out of 100.
+    pub percentage: f64,
+
+    /// Whether the tax rate is included in the amount of the items it applies to.
+    pub inclusive: bool,
+
+    /// Set of [key-value pairs](https://stripe.com/docs/api/metadata) that you can attach to an object.
+    pub metadata: HashMap<&'a str, String>,
+}
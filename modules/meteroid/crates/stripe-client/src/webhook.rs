@@ -1,3 +1,4 @@
+use crate::customer::Customer;
 use crate::error::WebhookError;
 use crate::invoice::Invoice;
 use chrono::Utc;
@@ -14,6 +15,9 @@ pub mod event_type {
     pub const INVOICE_PAID: &str = "invoice.paid";
     pub const INVOICE_VOIDED: &str = "invoice.voided";
     pub const INVOICE_MARKED_UNCOLLECTIBLE: &str = "invoice.marked_uncollectible";
+    pub const CUSTOMER_CREATED: &str = "customer.created";
+    pub const CUSTOMER_UPDATED: &str = "customer.updated";
+    pub const CUSTOMER_DELETED: &str = "customer.deleted";
 }
 
 pub static INVOICE_WEBHOOKS: [&str; 7] = [
@@ -26,10 +30,17 @@ pub static INVOICE_WEBHOOKS: [&str; 7] = [
     event_type::INVOICE_MARKED_UNCOLLECTIBLE,
 ];
 
+pub static CUSTOMER_WEBHOOKS: [&str; 3] = [
+    event_type::CUSTOMER_CREATED,
+    event_type::CUSTOMER_UPDATED,
+    event_type::CUSTOMER_DELETED,
+];
+
 #[derive(Clone, Debug, Deserialize, Serialize)]
 #[serde(tag = "object", rename_all = "snake_case")]
 pub enum EventObject {
     Invoice(Invoice),
+    Customer(Customer),
 }
 
 impl Default for EventObject {
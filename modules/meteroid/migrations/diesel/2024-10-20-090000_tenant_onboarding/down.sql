@@ -0,0 +1,3 @@
+drop table if exists tenant_onboarding_step;
+
+drop type if exists "OnboardingStepEnum";
@@ -0,0 +1,10 @@
+create type "OnboardingStepEnum" as enum ('INVOICING_ENTITY', 'PAYMENT_PROVIDER', 'BILLABLE_METRIC', 'PLAN', 'CUSTOMER');
+
+create table if not exists tenant_onboarding_step
+(
+  tenant_id    uuid                 not null references tenant on update cascade on delete cascade,
+  step         "OnboardingStepEnum" not null,
+  completed_at timestamp(3)         not null default CURRENT_TIMESTAMP,
+  completed_by uuid references "user" on delete set null,
+  primary key (tenant_id, step)
+);
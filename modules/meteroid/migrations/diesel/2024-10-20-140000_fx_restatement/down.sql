@@ -0,0 +1 @@
+drop function if exists fn_fx_restate(numeric, numeric, uuid, text, text, date);
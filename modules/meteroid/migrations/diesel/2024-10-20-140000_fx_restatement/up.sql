@@ -0,0 +1,59 @@
+-- Restates an amount in the reporting currency.
+-- Amounts already in the reporting currency are returned as is.
+-- Without a constant rate date, the usd amount (converted at the time of the movement) is converted using the rates of that same day.
+-- With a constant rate date, the original amount is converted using the latest rates known at that date, to neutralize fx fluctuations.
+-- When the rates of the reference day do not quote a currency, the nearest earlier rates quoting it are used.
+-- If no such rates exist, an error is raised rather than returning NULL, which would silently be dropped from the aggregates.
+create or replace function fn_fx_restate(amount_cents numeric,
+                                         amount_cents_usd numeric,
+                                         historical_rate_id uuid,
+                                         currency text,
+                                         reporting_currency text,
+                                         constant_rate_date date) returns numeric
+  language plpgsql
+  stable
+as
+$$
+DECLARE
+  reference_date date;
+  source_rate    numeric;
+  target_rate    numeric;
+BEGIN
+  IF currency = reporting_currency THEN
+    RETURN amount_cents;
+  END IF;
+
+  IF constant_rate_date IS NULL THEN
+    SELECT h.date INTO reference_date FROM historical_rates_from_usd h WHERE h.id = historical_rate_id;
+
+    SELECT (f.rates ->> reporting_currency)::NUMERIC
+    INTO target_rate
+    FROM historical_rates_from_usd f
+    WHERE f.date <= reference_date
+      AND f.rates ? reporting_currency
+    ORDER BY f.date DESC
+    LIMIT 1;
+
+    IF target_rate IS NULL THEN
+      RAISE EXCEPTION 'No % rate available on or before %', reporting_currency, reference_date;
+    END IF;
+
+    RETURN amount_cents_usd * target_rate;
+  END IF;
+
+  SELECT (f.rates ->> currency)::NUMERIC, (f.rates ->> reporting_currency)::NUMERIC
+  INTO source_rate, target_rate
+  FROM historical_rates_from_usd f
+  WHERE f.date <= constant_rate_date
+    AND f.rates ? currency
+    AND f.rates ? reporting_currency
+  ORDER BY f.date DESC
+  LIMIT 1;
+
+  IF source_rate IS NULL OR target_rate IS NULL THEN
+    RAISE EXCEPTION 'No % to % rate available on or before %', currency, reporting_currency, constant_rate_date;
+  END IF;
+
+  RETURN convert_currency(amount_cents, source_rate, target_rate);
+END;
+$$;
@@ -0,0 +1,5 @@
+drop index if exists provider_config_default_idx;
+
+alter table provider_config
+  drop column is_default,
+  drop column fallback_priority;
@@ -0,0 +1,15 @@
+alter table provider_config
+  add column is_default        boolean not null default false,
+  add column fallback_priority integer;
+
+create unique index if not exists provider_config_default_idx
+  on provider_config (tenant_id)
+  where (enabled = true and is_default = true);
+
+-- tenants that already configured a provider keep using it by default
+update provider_config
+set is_default = true
+where id in (select distinct on (tenant_id) id
+             from provider_config
+             where enabled = true
+             order by tenant_id, created_at);
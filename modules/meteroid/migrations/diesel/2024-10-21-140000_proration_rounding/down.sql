@@ -0,0 +1,4 @@
+alter table tenant
+  drop column proration_rounding;
+
+drop type "ProrationRoundingEnum";
@@ -0,0 +1,4 @@
+create type "ProrationRoundingEnum" as enum ('NEAREST', 'NEAREST_EVEN', 'UP', 'DOWN');
+
+alter table tenant
+  add column proration_rounding "ProrationRoundingEnum" not null default 'NEAREST';
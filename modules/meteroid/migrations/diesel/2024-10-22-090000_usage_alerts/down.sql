@@ -0,0 +1,5 @@
+drop table if exists usage_alert;
+
+drop type if exists "UsageAlertThresholdTypeEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
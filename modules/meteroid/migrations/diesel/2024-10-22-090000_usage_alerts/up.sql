@@ -0,0 +1,26 @@
+create type "UsageAlertThresholdTypeEnum" as enum ('ABSOLUTE', 'CAPACITY_PERCENT');
+
+alter type "WebhookOutEventTypeEnum" add value 'USAGE_ALERT_TRIGGERED';
+
+create table if not exists usage_alert
+(
+  id                  uuid                          not null primary key,
+  tenant_id           uuid                          not null references tenant on update cascade on delete cascade,
+  customer_id         uuid                          not null references customer on update cascade on delete cascade,
+  billable_metric_id  uuid                          not null references billable_metric on update cascade on delete cascade,
+  threshold_type      "UsageAlertThresholdTypeEnum" not null,
+  threshold_value     numeric                       not null,
+  -- the alert re-arms once the usage drops this % below the threshold, or when a new period starts
+  hysteresis_percent  numeric                       not null default 10,
+  notify_email        text,
+  triggered           boolean                       not null default false,
+  triggered_period_start date,
+  last_value          numeric,
+  last_triggered_at   timestamp(3),
+  last_checked_at     timestamp(3),
+  created_at          timestamp(3)                  not null default CURRENT_TIMESTAMP,
+  created_by          uuid                          not null,
+  archived_at         timestamp(3)
+);
+
+create index if not exists usage_alert_tenant_id_customer_id_idx on usage_alert (tenant_id, customer_id);
@@ -0,0 +1,6 @@
+alter table customer
+  drop constraint customer_early_payment_discount_check,
+  drop constraint customer_net_terms_override_check,
+  drop column early_payment_discount_days,
+  drop column early_payment_discount_percent,
+  drop column net_terms_override;
@@ -0,0 +1,10 @@
+alter table customer
+  add column net_terms_override integer,
+  add column early_payment_discount_percent numeric,
+  add column early_payment_discount_days integer,
+  add constraint customer_net_terms_override_check check (net_terms_override >= 0),
+  add constraint customer_early_payment_discount_check check (
+    (early_payment_discount_percent is null and early_payment_discount_days is null)
+      or (early_payment_discount_percent > 0 and early_payment_discount_percent < 100
+      and early_payment_discount_days >= 0)
+    );
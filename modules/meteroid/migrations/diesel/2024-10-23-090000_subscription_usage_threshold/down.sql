@@ -0,0 +1,2 @@
+alter table subscription
+  drop column threshold_invoiced_until;
@@ -0,0 +1,3 @@
+-- usage before this date was billed early, in an interim invoice, as it crossed the invoice_threshold
+alter table subscription
+  add column threshold_invoiced_until date;
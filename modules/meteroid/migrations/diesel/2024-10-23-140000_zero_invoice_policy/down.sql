@@ -0,0 +1,7 @@
+alter table invoice
+  drop column suppressed_at;
+
+alter table tenant
+  drop column zero_invoice_policy;
+
+drop type "ZeroInvoicePolicyEnum";
@@ -0,0 +1,8 @@
+create type "ZeroInvoicePolicyEnum" as enum ('ISSUE', 'FINALIZE_WITHOUT_ISSUING', 'SUPPRESS');
+
+alter table tenant
+  add column zero_invoice_policy "ZeroInvoicePolicyEnum" not null default 'ISSUE';
+
+-- zero-amount invoices suppressed by the tenant policy are voided, but kept as a record of the processed period
+alter table invoice
+  add column suppressed_at timestamp;
@@ -0,0 +1,5 @@
+drop table if exists invoice_stuck_alert;
+
+drop type if exists "InvoiceStuckReasonEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
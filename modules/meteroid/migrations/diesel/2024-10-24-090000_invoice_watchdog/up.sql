@@ -0,0 +1,14 @@
+create type "InvoiceStuckReasonEnum" as enum ('STALE_DRAFT', 'PENDING_FINALIZATION', 'ISSUE_RETRIES_EXHAUSTED');
+
+alter type "WebhookOutEventTypeEnum" add value 'INVOICE_STUCK';
+
+-- invoices the watchdog alerted on, so that an alert is sent once per invoice and reason
+create table if not exists invoice_stuck_alert
+(
+  invoice_id uuid                     not null primary key references invoice on update cascade on delete cascade,
+  tenant_id  uuid                     not null references tenant on update cascade on delete cascade,
+  reason     "InvoiceStuckReasonEnum" not null,
+  alerted_at timestamp(3)             not null default CURRENT_TIMESTAMP
+);
+
+create index if not exists invoice_stuck_alert_tenant_id_idx on invoice_stuck_alert (tenant_id);
@@ -0,0 +1,3 @@
+drop table if exists subscription_pending_change;
+
+drop type if exists "SubscriptionPendingChangeTypeEnum";
@@ -0,0 +1,27 @@
+create type "SubscriptionPendingChangeTypeEnum" as enum ('PLAN_SWITCH', 'CANCELLATION', 'COMPONENT_CHANGE');
+
+-- changes scheduled for the next renewal of a subscription, applied by the draft worker
+create table if not exists subscription_pending_change
+(
+  id              uuid                                not null primary key,
+  tenant_id       uuid                                not null references tenant on update cascade on delete cascade,
+  subscription_id uuid                                not null references subscription on update cascade on delete cascade,
+  change_type     "SubscriptionPendingChangeTypeEnum" not null,
+  details         jsonb                               not null,
+  effective_at    date                                not null,
+  created_at      timestamp(3)                        not null default CURRENT_TIMESTAMP,
+  created_by      uuid                                not null,
+  applied_at      timestamp(3),
+  canceled_at     timestamp(3)
+);
+
+create index if not exists subscription_pending_change_subscription_id_idx on subscription_pending_change (subscription_id);
+
+-- a single change can be scheduled at a time
+create unique index if not exists subscription_pending_change_scheduled_idx
+  on subscription_pending_change (subscription_id)
+  where applied_at is null and canceled_at is null;
+
+create index if not exists subscription_pending_change_due_idx
+  on subscription_pending_change (effective_at)
+  where applied_at is null and canceled_at is null;
@@ -0,0 +1,2 @@
+drop table if exists invoicing_entity_tax_registration;
+drop type if exists "TaxRegistrationTypeEnum";
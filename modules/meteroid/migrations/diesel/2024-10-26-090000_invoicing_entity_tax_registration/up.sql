@@ -0,0 +1,19 @@
+create type "TaxRegistrationTypeEnum" as enum ('VAT', 'GST', 'EIN', 'ABN');
+
+-- registration numbers of an invoicing entity, displayed on the invoices of the matching customers
+create table if not exists invoicing_entity_tax_registration
+(
+  id                  uuid                      not null primary key,
+  tenant_id           uuid                      not null references tenant on update cascade on delete cascade,
+  invoicing_entity_id uuid                      not null references invoicing_entity on update cascade on delete cascade,
+  registration_type   "TaxRegistrationTypeEnum" not null,
+  -- country code (or region, ex: EU) of the authority that issued the number
+  jurisdiction        text                      not null,
+  registration_number text                      not null,
+  -- billing countries of the customers whose invoices display the number. Empty for all customers
+  customer_countries  text[]                    not null default '{}',
+  created_at          timestamp(3)              not null default CURRENT_TIMESTAMP
+);
+
+create unique index if not exists invoicing_entity_tax_registration_type_jurisdiction_idx
+  on invoicing_entity_tax_registration (invoicing_entity_id, registration_type, jurisdiction);
@@ -0,0 +1,3 @@
+drop table if exists subscription_component_history;
+
+drop type if exists "SubscriptionComponentChangeTypeEnum";
@@ -0,0 +1,22 @@
+create type "SubscriptionComponentChangeTypeEnum" as enum ('SLOTS', 'COMMITTED_CAPACITY', 'FEE');
+
+-- parameter changes of the subscription components. The component row holds the latest values,
+-- the history gives the values in effect at a given date
+create table if not exists subscription_component_history
+(
+  id                 uuid                                  not null primary key,
+  tenant_id          uuid                                  not null references tenant on update cascade on delete cascade,
+  subscription_id    uuid                                  not null references subscription on update cascade on delete cascade,
+  price_component_id uuid,
+  component_name     text                                  not null,
+  change_type        "SubscriptionComponentChangeTypeEnum" not null,
+  -- slot count for slots, subscription fee otherwise
+  previous_value     jsonb                                 not null,
+  new_value          jsonb                                 not null,
+  effective_at       timestamp(3)                          not null,
+  created_at         timestamp(3)                          not null default CURRENT_TIMESTAMP,
+  created_by         uuid                                  not null
+);
+
+create index if not exists subscription_component_history_subscription_id_idx
+  on subscription_component_history (subscription_id, effective_at);
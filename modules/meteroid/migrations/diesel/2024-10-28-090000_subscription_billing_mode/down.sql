@@ -0,0 +1,4 @@
+alter table subscription
+  drop column if exists billing_mode;
+
+drop type if exists "SubscriptionBillingModeEnum";
@@ -0,0 +1,11 @@
+create type "SubscriptionBillingModeEnum" as enum ('ANNIVERSARY', 'CALENDAR');
+
+-- ANNIVERSARY : periods renew on the billing day. CALENDAR : periods renew on the 1st of the month.
+-- In both modes, the first period up to the first renewal is prorated if partial
+alter table subscription
+  add column billing_mode "SubscriptionBillingModeEnum" not null default 'ANNIVERSARY';
+
+update subscription
+set billing_mode = 'CALENDAR'
+where billing_day = 1
+  and extract(day from billing_start_date) <> 1;
@@ -0,0 +1,8 @@
+drop table if exists api_token_usage_daily;
+
+alter table api_token
+  drop column if exists last_used_at,
+  drop column if exists request_count,
+  drop column if exists daily_request_soft_limit,
+  drop column if exists disabled_at,
+  drop column if exists rotated_at;
@@ -0,0 +1,15 @@
+alter table api_token
+  add column last_used_at             timestamp(3),
+  add column request_count            bigint not null default 0,
+  -- requests per day above which the token usage is flagged. Requests are not rejected
+  add column daily_request_soft_limit bigint,
+  add column disabled_at              timestamp(3),
+  add column rotated_at               timestamp(3);
+
+create table if not exists api_token_usage_daily
+(
+  api_token_id  uuid   not null references api_token on update cascade on delete cascade,
+  day           date   not null,
+  request_count bigint not null default 0,
+  primary key (api_token_id, day)
+);
@@ -0,0 +1,5 @@
+alter table price_component
+  drop column if exists rate_card_id;
+
+drop table if exists rate_card_version;
+drop table if exists rate_card;
@@ -0,0 +1,30 @@
+create table if not exists rate_card
+(
+  id          uuid         not null primary key,
+  tenant_id   uuid         not null references tenant on update cascade on delete cascade,
+  name        text         not null,
+  description text,
+  currency    text         not null,
+  created_at  timestamp(3) not null default CURRENT_TIMESTAMP,
+  created_by  uuid         not null
+);
+
+create unique index if not exists rate_card_tenant_id_name_key on rate_card (tenant_id, name);
+
+create table if not exists rate_card_version
+(
+  id           uuid         not null primary key,
+  rate_card_id uuid         not null references rate_card on update cascade on delete cascade,
+  version      integer      not null,
+  -- the usage pricing of each metric of the rate card
+  rates        jsonb        not null,
+  created_at   timestamp(3) not null default CURRENT_TIMESTAMP,
+  created_by   uuid         not null
+);
+
+create unique index if not exists rate_card_version_rate_card_id_version_key on rate_card_version (rate_card_id, version);
+
+alter table price_component
+  add column rate_card_id uuid references rate_card on update cascade on delete set null;
+
+create index if not exists price_component_rate_card_id_idx on price_component (rate_card_id);
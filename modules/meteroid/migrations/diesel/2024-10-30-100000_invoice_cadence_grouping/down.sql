@@ -0,0 +1,7 @@
+alter table invoice
+  drop column billing_cadence;
+
+alter table tenant
+  drop column invoice_cadence_grouping;
+
+drop type "InvoiceCadenceGroupingEnum";
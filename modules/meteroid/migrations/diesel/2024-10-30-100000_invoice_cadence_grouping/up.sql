@@ -0,0 +1,8 @@
+create type "InvoiceCadenceGroupingEnum" as enum ('COMBINED', 'PER_CADENCE');
+
+alter table tenant
+  add column invoice_cadence_grouping "InvoiceCadenceGroupingEnum" not null default 'COMBINED';
+
+-- the cadence of the components billed by a recurring invoice. All the components if null
+alter table invoice
+  add column billing_cadence "BillingPeriodEnum";
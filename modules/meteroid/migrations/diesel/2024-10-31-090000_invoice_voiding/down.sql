@@ -0,0 +1,6 @@
+drop index invoice_replaces_invoice_id_idx;
+
+alter table invoice
+  drop column replaces_invoice_id,
+  drop column void_reason,
+  drop column voided_at;
@@ -0,0 +1,7 @@
+alter table invoice
+  add column voided_at timestamp,
+  add column void_reason text,
+  -- the voided invoice this one corrects
+  add column replaces_invoice_id uuid references invoice (id) on delete set null;
+
+create index invoice_replaces_invoice_id_idx on invoice (replaces_invoice_id);
@@ -0,0 +1,10 @@
+alter table customer
+  drop column due_date_day,
+  drop column due_date_policy;
+
+alter table tenant
+  drop column due_date_day,
+  drop column due_date_policy,
+  drop column default_net_terms;
+
+drop type "DueDatePolicyEnum";
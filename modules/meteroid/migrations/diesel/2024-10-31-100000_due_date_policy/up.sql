@@ -0,0 +1,11 @@
+create type "DueDatePolicyEnum" as enum ('NET_DAYS', 'END_OF_MONTH', 'FIXED_DAY');
+
+-- defaults of the tenant, when not set on the customer
+alter table tenant
+  add column default_net_terms integer,
+  add column due_date_policy "DueDatePolicyEnum" not null default 'NET_DAYS',
+  add column due_date_day integer check (due_date_day between 1 and 31);
+
+alter table customer
+  add column due_date_policy "DueDatePolicyEnum",
+  add column due_date_day integer check (due_date_day between 1 and 31);
@@ -0,0 +1,49 @@
+-- the invoice date was set when finalizing the draft, rather than the one of the period
+alter table invoice
+  add column manual_invoice_date boolean not null default false;
+
+-- the revenue of an invoice dated manually is recognized at its invoice date, otherwise at its finalization
+
+create or replace function fn_update_customer_ytd_summary_invoice() returns trigger
+  language plpgsql
+as
+$$
+BEGIN
+  INSERT INTO bi_customer_ytd_summary (tenant_id, customer_id, revenue_year, currency, total_revenue_cents)
+  VALUES (NEW.tenant_id, NEW.customer_id,
+          DATE_PART('year', CASE WHEN NEW.manual_invoice_date THEN NEW.invoice_date::timestamp ELSE NEW.finalized_at END)::integer,
+          NEW.currency, NEW.amount_due)
+  ON CONFLICT (tenant_id, customer_id, currency, revenue_year) DO UPDATE
+    SET total_revenue_cents = bi_customer_ytd_summary.total_revenue_cents + EXCLUDED.total_revenue_cents;
+  RETURN NEW;
+END;
+$$;
+
+create or replace function fn_update_revenue_invoice() returns trigger
+  language plpgsql
+as
+$$
+DECLARE
+  net_revenue_cents_usd BIGINT;
+  historical_rate_record RECORD;
+  recognized_at TIMESTAMP;
+BEGIN
+  recognized_at := CASE WHEN NEW.manual_invoice_date THEN NEW.invoice_date::timestamp ELSE NEW.finalized_at END;
+
+  SELECT id, (rates->>NEW.currency)::NUMERIC as rate INTO historical_rate_record
+  FROM historical_rates_from_usd
+  WHERE date <= recognized_at
+  ORDER BY date DESC
+  LIMIT 1;
+
+  net_revenue_cents_usd := NEW.amount_due / historical_rate_record.rate;
+
+  INSERT INTO bi_revenue_daily (tenant_id, plan_version_id, currency, revenue_date, net_revenue_cents, historical_rate_id, net_revenue_cents_usd)
+  VALUES (NEW.tenant_id, NEW.plan_version_id, NEW.currency, DATE_TRUNC('day', recognized_at), NEW.amount_due, historical_rate_record.id, net_revenue_cents_usd)
+  ON CONFLICT (tenant_id, plan_version_id, currency, revenue_date) DO UPDATE
+    SET net_revenue_cents = bi_revenue_daily.net_revenue_cents + EXCLUDED.net_revenue_cents,
+        net_revenue_cents_usd = bi_revenue_daily.net_revenue_cents_usd + EXCLUDED.net_revenue_cents_usd,
+        historical_rate_id = EXCLUDED.historical_rate_id;
+  RETURN NEW;
+END;
+$$;
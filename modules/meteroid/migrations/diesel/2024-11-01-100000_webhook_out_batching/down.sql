@@ -0,0 +1,9 @@
+drop index if exists webhook_out_event_batch_id_idx;
+
+alter table webhook_out_event
+  drop column batch_id;
+
+alter table webhook_out_endpoint
+  drop constraint webhook_out_endpoint_batching_check,
+  drop column batch_max_wait_seconds,
+  drop column batch_max_events;
@@ -0,0 +1,14 @@
+-- endpoints receiving their events in batches. Both null : one request per event
+alter table webhook_out_endpoint
+  add column batch_max_events integer,
+  add column batch_max_wait_seconds integer,
+  add constraint webhook_out_endpoint_batching_check check (
+    (batch_max_events is null and batch_max_wait_seconds is null)
+      or (batch_max_events > 0 and batch_max_wait_seconds > 0)
+  );
+
+-- the events delivered in the same request share the batch id
+alter table webhook_out_event
+  add column batch_id uuid;
+
+create index webhook_out_event_batch_id_idx on webhook_out_event (batch_id) where batch_id is not null;
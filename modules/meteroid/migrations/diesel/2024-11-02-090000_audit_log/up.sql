@@ -0,0 +1,16 @@
+-- the mutating calls of the api. No foreign key, the logs are kept after the deletion of their tenant or actor
+create table audit_log
+(
+  id              uuid      not null primary key,
+  organization_id uuid,
+  tenant_id       uuid,
+  actor_id        uuid      not null,
+  rpc             text      not null,
+  entity_id       text,
+  diff            jsonb,
+  status_code     integer   not null,
+  created_at      timestamp not null default now()
+);
+
+create index audit_log_tenant_id_created_at_idx on audit_log (tenant_id, created_at desc);
+create index audit_log_tenant_id_entity_id_idx on audit_log (tenant_id, entity_id) where entity_id is not null;
@@ -0,0 +1,4 @@
+alter table api_token drop column if exists partner_id;
+drop table if exists partner_commission;
+drop table if exists partner_attribution;
+drop table if exists partner;
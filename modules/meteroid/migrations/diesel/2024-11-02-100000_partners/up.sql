@@ -0,0 +1,51 @@
+-- resellers and referrers, paid a share of the revenue of the customers they brought
+create table partner
+(
+  id                uuid      not null primary key,
+  tenant_id         uuid      not null references tenant on delete cascade,
+  name              text      not null,
+  rev_share_percent numeric   not null,
+  created_at        timestamp not null default now(),
+  archived_at       timestamp
+);
+
+create index partner_tenant_id_idx on partner (tenant_id);
+
+-- a customer, or a single subscription, attributed to a partner
+create table partner_attribution
+(
+  id                uuid      not null primary key,
+  tenant_id         uuid      not null references tenant on delete cascade,
+  partner_id        uuid      not null references partner on delete cascade,
+  customer_id       uuid references customer on delete cascade,
+  subscription_id   uuid references subscription on delete cascade,
+  -- overrides the rev share of the partner
+  rev_share_percent numeric,
+  created_at        timestamp not null default now(),
+  constraint partner_attribution_target_check check ((customer_id is null) <> (subscription_id is null))
+);
+
+create unique index partner_attribution_customer_id_idx on partner_attribution (customer_id) where customer_id is not null;
+create unique index partner_attribution_subscription_id_idx on partner_attribution (subscription_id) where subscription_id is not null;
+create index partner_attribution_partner_id_idx on partner_attribution (partner_id);
+
+-- the share of a paid invoice owed to the partner, with the rate applied at the time of the payment
+create table partner_commission
+(
+  id                uuid      not null primary key,
+  tenant_id         uuid      not null references tenant on delete cascade,
+  partner_id        uuid      not null references partner on delete cascade,
+  invoice_id        uuid      not null unique references invoice on delete cascade,
+  customer_id       uuid      not null,
+  currency          text      not null,
+  -- the invoice total, excluding taxes
+  invoice_amount    bigint    not null,
+  rev_share_percent numeric   not null,
+  amount            bigint    not null,
+  paid_at           timestamp not null
+);
+
+create index partner_commission_tenant_id_paid_at_idx on partner_commission (tenant_id, paid_at);
+
+-- api tokens restricted to the payout report of a partner
+alter table api_token add column partner_id uuid references partner on delete cascade;
@@ -0,0 +1,2 @@
+drop table if exists tenant_member_role;
+drop type if exists "TenantMemberRoleEnum";
@@ -0,0 +1,15 @@
+create type "TenantMemberRoleEnum" as enum ('ADMIN', 'BILLING', 'READ_ONLY');
+
+-- the role of an organization member on a tenant. Members without a role are admins of the tenants
+create table tenant_member_role
+(
+  user_id         uuid                   not null,
+  organization_id uuid                   not null,
+  tenant_id       uuid                   not null references tenant on delete cascade,
+  role            "TenantMemberRoleEnum" not null,
+  created_at      timestamp              not null default now(),
+  primary key (user_id, tenant_id),
+  foreign key (user_id, organization_id) references organization_member (user_id, organization_id) on delete cascade
+);
+
+create index tenant_member_role_tenant_id_idx on tenant_member_role (tenant_id);
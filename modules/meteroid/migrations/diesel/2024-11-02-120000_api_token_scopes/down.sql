@@ -0,0 +1,2 @@
+alter table api_token drop column if exists expires_at;
+alter table api_token drop column if exists scopes;
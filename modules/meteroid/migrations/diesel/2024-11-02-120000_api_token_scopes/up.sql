@@ -0,0 +1,3 @@
+-- the resources the token can access, ex: invoices:read, metering:write. A token without scopes can access all of them
+alter table api_token add column scopes text[] not null default '{}';
+alter table api_token add column expires_at timestamp;
@@ -0,0 +1,7 @@
+drop table if exists payment_reminder_log;
+
+drop table if exists payment_reminder_schedule;
+
+drop type if exists "PaymentReminderChannelEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
@@ -0,0 +1,33 @@
+create type "PaymentReminderChannelEnum" as enum ('EMAIL', 'WEBHOOK');
+
+alter type "WebhookOutEventTypeEnum" add value 'INVOICE_PAYMENT_REMINDER';
+
+create table if not exists payment_reminder_schedule
+(
+  id          uuid                         not null primary key,
+  tenant_id   uuid                         not null references tenant on update cascade on delete cascade,
+  -- days relative to the due date of the invoice, negative before it
+  offset_days integer                      not null,
+  channel     "PaymentReminderChannelEnum" not null,
+  enabled     boolean                      not null default true,
+  created_at  timestamp(3)                 not null default CURRENT_TIMESTAMP,
+  created_by  uuid                         not null,
+  unique (tenant_id, offset_days, channel)
+);
+
+create table if not exists payment_reminder_log
+(
+  id          uuid                         not null primary key,
+  tenant_id   uuid                         not null references tenant on update cascade on delete cascade,
+  invoice_id  uuid                         not null references invoice on update cascade on delete cascade,
+  schedule_id uuid                         references payment_reminder_schedule on update cascade on delete set null,
+  offset_days integer                      not null,
+  channel     "PaymentReminderChannelEnum" not null,
+  recipient   text,
+  due_at      timestamp(3)                 not null,
+  sent_at     timestamp(3)                 not null,
+  -- a reminder is sent once per invoice, even if its schedule is recreated
+  unique (invoice_id, offset_days, channel)
+);
+
+create index if not exists payment_reminder_log_tenant_id_invoice_id_idx on payment_reminder_log (tenant_id, invoice_id);
@@ -0,0 +1,3 @@
+drop index if exists tenant_production_tenant_id_idx;
+
+alter table tenant drop column if exists production_tenant_id;
@@ -0,0 +1,4 @@
+-- the production tenant a sandbox tenant was created from, its catalog can be promoted back to it
+alter table tenant add column production_tenant_id uuid references tenant on delete set null;
+
+create index tenant_production_tenant_id_idx on tenant (production_tenant_id);
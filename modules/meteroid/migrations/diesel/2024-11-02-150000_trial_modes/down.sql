@@ -0,0 +1,3 @@
+alter table plan_version drop column if exists trial_mode;
+
+drop type if exists "TrialModeEnum";
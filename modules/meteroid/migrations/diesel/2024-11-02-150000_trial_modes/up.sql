@@ -0,0 +1,6 @@
+-- FREE trials need no payment method and expire at their end, CARD_REQUIRED trials convert and are charged
+create type "TrialModeEnum" as enum ('FREE', 'CARD_REQUIRED');
+
+alter table plan_version add column trial_mode "TrialModeEnum" not null default 'FREE';
+
+update plan_version set trial_mode = 'CARD_REQUIRED' where action_after_trial = 'CHARGE';
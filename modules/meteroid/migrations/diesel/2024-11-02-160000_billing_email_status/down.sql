@@ -0,0 +1,10 @@
+drop index if exists customer_billing_email_idx;
+
+alter table customer
+  drop column if exists billing_email_status,
+  drop column if exists billing_email_status_reason,
+  drop column if exists billing_email_status_updated_at;
+
+drop type if exists "BillingEmailStatusEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
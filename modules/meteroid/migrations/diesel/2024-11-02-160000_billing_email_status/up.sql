@@ -0,0 +1,11 @@
+create type "BillingEmailStatusEnum" as enum ('UNVERIFIED', 'VERIFIED', 'BOUNCED', 'COMPLAINED');
+
+alter type "WebhookOutEventTypeEnum" add value 'CUSTOMER_BILLING_EMAIL_UPDATED';
+
+-- status of the billing email of the customer (invoicing email, else email), from the email provider callbacks
+alter table customer
+  add column billing_email_status            "BillingEmailStatusEnum" not null default 'UNVERIFIED',
+  add column billing_email_status_reason     text,
+  add column billing_email_status_updated_at timestamp(3);
+
+create index if not exists customer_billing_email_idx on customer (lower(coalesce(invoicing_email, email)));
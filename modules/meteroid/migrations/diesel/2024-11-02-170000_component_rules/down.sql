@@ -0,0 +1,2 @@
+drop table if exists component_rule;
+drop type if exists "ComponentRuleTypeEnum";
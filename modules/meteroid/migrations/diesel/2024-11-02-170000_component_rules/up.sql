@@ -0,0 +1,19 @@
+create type "ComponentRuleTypeEnum" as enum ('REQUIRES', 'EXCLUDES');
+
+-- a rule between two items of the catalog, each one being either a price component or an add-on
+create table if not exists component_rule
+(
+  id                         uuid                    not null primary key,
+  tenant_id                  uuid                    not null references tenant on update cascade on delete cascade,
+  rule_type                  "ComponentRuleTypeEnum" not null,
+  subject_price_component_id uuid references price_component on update cascade on delete cascade,
+  subject_add_on_id          uuid references add_on on update cascade on delete cascade,
+  target_price_component_id  uuid references price_component on update cascade on delete cascade,
+  target_add_on_id           uuid references add_on on update cascade on delete cascade,
+  created_at                 timestamp(3)            not null default CURRENT_TIMESTAMP,
+  created_by                 uuid                    not null,
+  check (num_nonnulls(subject_price_component_id, subject_add_on_id) = 1),
+  check (num_nonnulls(target_price_component_id, target_add_on_id) = 1)
+);
+
+create index if not exists component_rule_tenant_id_idx on component_rule (tenant_id);
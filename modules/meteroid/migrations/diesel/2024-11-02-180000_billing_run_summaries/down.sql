@@ -0,0 +1,3 @@
+drop table if exists billing_run_summary;
+
+alter table tenant drop column if exists finance_email;
@@ -0,0 +1,19 @@
+alter table tenant add column finance_email text;
+
+create table billing_run_summary
+(
+  id                 uuid primary key,
+  tenant_id          uuid      not null references tenant on delete cascade,
+  period_start       timestamp not null,
+  period_end         timestamp not null,
+  invoices_created   integer   not null,
+  invoices_finalized integer   not null,
+  invoices_issued    integer   not null,
+  issue_failures     integer   not null,
+  totals             jsonb     not null,
+  failures           jsonb     not null,
+  created_at         timestamp not null default now(),
+  unique (tenant_id, period_start)
+);
+
+create index billing_run_summary_tenant_id_period_start_idx on billing_run_summary (tenant_id, period_start desc);
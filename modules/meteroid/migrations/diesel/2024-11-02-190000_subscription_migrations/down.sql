@@ -0,0 +1,5 @@
+drop table if exists subscription_migration_item;
+drop table if exists subscription_migration;
+
+drop type if exists "SubscriptionMigrationItemStatusEnum";
+drop type if exists "SubscriptionMigrationStrategyEnum";
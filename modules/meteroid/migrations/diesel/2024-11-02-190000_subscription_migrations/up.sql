@@ -0,0 +1,38 @@
+create type "SubscriptionMigrationStrategyEnum" as enum ('AT_NEXT_RENEWAL', 'IMMEDIATE_WITH_PRORATION', 'GRANDFATHER');
+create type "SubscriptionMigrationItemStatusEnum" as enum ('SCHEDULED', 'MIGRATED', 'GRANDFATHERED', 'FAILED');
+
+-- a migration of the subscriptions of a plan to one of its published versions, processed in batches
+create table if not exists subscription_migration
+(
+  id                 uuid                                not null primary key,
+  tenant_id          uuid                                not null references tenant on update cascade on delete cascade,
+  plan_id            uuid                                not null references plan on update cascade on delete cascade,
+  to_plan_version_id uuid                                not null references plan_version on update cascade on delete cascade,
+  strategy           "SubscriptionMigrationStrategyEnum" not null,
+  total_count        integer                             not null default 0,
+  processed_count    integer                             not null default 0,
+  failed_count       integer                             not null default 0,
+  mrr_delta          bigint                              not null default 0,
+  created_at         timestamp(3)                        not null default CURRENT_TIMESTAMP,
+  created_by         uuid                                not null,
+  completed_at       timestamp(3)
+);
+
+create index if not exists subscription_migration_tenant_id_idx on subscription_migration (tenant_id);
+
+-- the outcome of a migration for each subscription
+create table if not exists subscription_migration_item
+(
+  id                     uuid                                  not null primary key,
+  migration_id           uuid                                  not null references subscription_migration on update cascade on delete cascade,
+  subscription_id        uuid                                  not null references subscription on update cascade on delete cascade,
+  from_plan_version_id   uuid                                  not null references plan_version on update cascade on delete cascade,
+  status                 "SubscriptionMigrationItemStatusEnum" not null,
+  mrr_delta              bigint,
+  prorated_amount        bigint,
+  error                  text,
+  created_at             timestamp(3)                          not null default CURRENT_TIMESTAMP
+);
+
+create index if not exists subscription_migration_item_migration_id_idx on subscription_migration_item (migration_id);
+create index if not exists subscription_migration_item_subscription_id_idx on subscription_migration_item (subscription_id);
@@ -0,0 +1,14 @@
+drop trigger if exists tr_customer_unique_keys on customer;
+drop function if exists fn_set_customer_unique_keys;
+
+drop index if exists customer_tenant_id_unique_alias_idx;
+drop index if exists customer_tenant_id_unique_email_idx;
+
+alter table customer
+  drop column unique_alias,
+  drop column unique_email;
+
+alter table tenant
+  drop column customer_uniqueness;
+
+drop type "CustomerUniquenessEnum";
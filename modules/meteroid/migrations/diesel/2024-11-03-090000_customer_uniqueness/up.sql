@@ -0,0 +1,42 @@
+create type "CustomerUniquenessEnum" as enum ('NONE', 'EMAIL', 'ALIAS', 'EMAIL_AND_ALIAS');
+
+alter table tenant
+  add column customer_uniqueness "CustomerUniquenessEnum" not null default 'NONE';
+
+-- normalized email and alias of the active customers, set when the tenant enforces their uniqueness
+alter table customer
+  add column unique_email text,
+  add column unique_alias text;
+
+create unique index customer_tenant_id_unique_email_idx
+  on customer (tenant_id, unique_email) where unique_email is not null;
+
+create unique index customer_tenant_id_unique_alias_idx
+  on customer (tenant_id, unique_alias) where unique_alias is not null;
+
+create or replace function fn_set_customer_unique_keys() returns trigger
+  language plpgsql
+as
+$$
+DECLARE
+  uniqueness "CustomerUniquenessEnum";
+BEGIN
+  SELECT customer_uniqueness INTO uniqueness FROM tenant WHERE id = NEW.tenant_id;
+
+  NEW.unique_email := CASE
+                        WHEN uniqueness IN ('EMAIL', 'EMAIL_AND_ALIAS') AND NEW.archived_at IS NULL
+                          THEN nullif(lower(trim(NEW.email)), '')
+    END;
+  NEW.unique_alias := CASE
+                        WHEN uniqueness IN ('ALIAS', 'EMAIL_AND_ALIAS') AND NEW.archived_at IS NULL
+                          THEN nullif(lower(trim(NEW.alias)), '')
+    END;
+  RETURN NEW;
+END;
+$$;
+
+create trigger tr_customer_unique_keys
+  before insert or update
+  on customer
+  for each row
+execute function fn_set_customer_unique_keys();
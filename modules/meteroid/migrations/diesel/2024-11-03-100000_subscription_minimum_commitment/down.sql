@@ -0,0 +1,3 @@
+alter table subscription
+  drop constraint if exists subscription_minimum_commitment_check,
+  drop column if exists minimum_commitment;
@@ -0,0 +1,4 @@
+-- minimum spend per billing period. The invoice closing a period bills the difference when its total falls below it
+alter table subscription
+  add column minimum_commitment numeric,
+  add constraint subscription_minimum_commitment_check check (minimum_commitment is null or minimum_commitment >= 0);
@@ -0,0 +1,3 @@
+drop table if exists usage_cap_reached;
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
@@ -0,0 +1,18 @@
+alter type "WebhookOutEventTypeEnum" add value 'USAGE_CAP_REACHED';
+
+-- a cap of a usage or capacity fee reached during a period. The fee is a subscription component or add-on
+create table if not exists usage_cap_reached
+(
+  id              uuid         not null primary key,
+  tenant_id       uuid         not null references tenant on update cascade on delete cascade,
+  subscription_id uuid         not null references subscription on update cascade on delete cascade,
+  fee_id          uuid         not null,
+  period_start    date         not null,
+  period_end      date         not null,
+  cap             bigint       not null,
+  charges         bigint       not null,
+  reached_at      timestamp(3) not null default CURRENT_TIMESTAMP,
+  unique (fee_id, period_start)
+);
+
+create index if not exists usage_cap_reached_subscription_id_idx on usage_cap_reached (subscription_id);
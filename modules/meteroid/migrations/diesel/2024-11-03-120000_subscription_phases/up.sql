@@ -0,0 +1,18 @@
+-- a time-bound promotional phase of a subscription (ex: 3 months at a reduced rate), before standard pricing resumes
+create table if not exists subscription_phase
+(
+  id                 uuid         not null primary key,
+  tenant_id          uuid         not null references tenant on update cascade on delete cascade,
+  subscription_id    uuid         not null references subscription on update cascade on delete cascade,
+  name               text         not null,
+  start_date         date         not null,
+  -- exclusive
+  end_date           date         not null,
+  -- the fees of that plan price component only, all fees when null
+  price_component_id uuid,
+  discount           jsonb        not null,
+  created_at         timestamp(3) not null default CURRENT_TIMESTAMP,
+  check (start_date < end_date)
+);
+
+create index if not exists subscription_phase_subscription_id_idx on subscription_phase (subscription_id);
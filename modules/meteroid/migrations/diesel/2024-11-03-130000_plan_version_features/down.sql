@@ -0,0 +1 @@
+drop table if exists plan_version_feature;
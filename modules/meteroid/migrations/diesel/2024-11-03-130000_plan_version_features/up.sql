@@ -0,0 +1,16 @@
+-- a feature granted by a plan version, gated by the product of the tenant
+create table if not exists plan_version_feature
+(
+  id              uuid         not null primary key,
+  tenant_id       uuid         not null references tenant on update cascade on delete cascade,
+  plan_version_id uuid         not null references plan_version on update cascade on delete cascade,
+  -- identifier of the feature in the product, ex: sso or seats
+  key             text         not null,
+  enabled         boolean      not null,
+  -- unlimited when null
+  usage_limit     bigint,
+  created_at      timestamp(3) not null default CURRENT_TIMESTAMP,
+  unique (plan_version_id, key)
+);
+
+create index if not exists plan_version_feature_tenant_id_idx on plan_version_feature (tenant_id);
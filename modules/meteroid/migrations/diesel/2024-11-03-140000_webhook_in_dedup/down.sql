@@ -0,0 +1 @@
+drop table if exists webhook_in_dedup;
@@ -0,0 +1,11 @@
+-- provider events already processed, to drop the replays and the duplicate deliveries
+create table if not exists webhook_in_dedup
+(
+  provider_config_id uuid         not null references provider_config on update cascade on delete cascade,
+  -- id of the event at the provider
+  event_id           text         not null,
+  received_at        timestamp(3) not null,
+  primary key (provider_config_id, event_id)
+);
+
+create index if not exists webhook_in_dedup_received_at_idx on webhook_in_dedup (received_at);
@@ -0,0 +1,11 @@
+drop table if exists collection_strategy_override;
+
+drop index if exists customer_tenant_id_collection_segment_idx;
+
+alter table customer
+  drop column collection_segment;
+
+alter table tenant
+  drop column collection_strategy_preset;
+
+drop type "CollectionStrategyPresetEnum";
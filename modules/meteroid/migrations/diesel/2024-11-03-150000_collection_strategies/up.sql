@@ -0,0 +1,24 @@
+create type "CollectionStrategyPresetEnum" as enum ('LENIENT', 'STANDARD', 'STRICT');
+
+-- the collection strategy of the customers without segment override
+alter table tenant
+  add column collection_strategy_preset "CollectionStrategyPresetEnum" not null default 'STANDARD';
+
+alter table customer
+  add column collection_segment text;
+
+create index if not exists customer_tenant_id_collection_segment_idx
+  on customer (tenant_id, collection_segment) where collection_segment is not null;
+
+-- replaces the collection strategy of the tenant for the customers of a segment
+create table if not exists collection_strategy_override
+(
+  tenant_id  uuid                           not null references tenant on update cascade on delete cascade,
+  segment    text                           not null,
+  preset     "CollectionStrategyPresetEnum" not null,
+  -- rules replacing those of the preset
+  rules      jsonb                          not null default '{}',
+  updated_at timestamp(3)                   not null default CURRENT_TIMESTAMP,
+  updated_by uuid                           not null,
+  primary key (tenant_id, segment)
+);
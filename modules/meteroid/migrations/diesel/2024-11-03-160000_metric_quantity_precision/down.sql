@@ -0,0 +1,3 @@
+alter table billable_metric
+  drop column quantity_display_precision,
+  drop column quantity_billing_precision;
@@ -0,0 +1,5 @@
+-- decimal places of the quantities of the metric, null keeps the quantity as aggregated by the meter
+alter table billable_metric
+  add column quantity_billing_precision integer check (quantity_billing_precision between 0 and 12),
+  -- on the invoices, defaults to the billing precision
+  add column quantity_display_precision integer check (quantity_display_precision between 0 and 12);
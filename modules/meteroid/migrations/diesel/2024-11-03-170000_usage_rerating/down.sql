@@ -0,0 +1,4 @@
+drop index if exists invoice_usage_outdated_at_idx;
+
+alter table invoice
+  drop column usage_outdated_at;
@@ -0,0 +1,7 @@
+-- set when events were backfilled into the usage period of the invoice after it was priced.
+-- The drafts are recomputed, the finalized invoices are adjusted by an adjustment invoice or a credit
+alter table invoice
+  add column usage_outdated_at timestamp;
+
+create index if not exists invoice_usage_outdated_at_idx
+  on invoice (usage_outdated_at) where usage_outdated_at is not null;
@@ -0,0 +1,10 @@
+drop table if exists credit_limit_exceeded;
+
+alter table customer
+  drop column credit_limit_enforcement,
+  drop column credit_limit_cents;
+
+drop type "CreditLimitSourceEnum";
+drop type "CreditLimitEnforcementEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
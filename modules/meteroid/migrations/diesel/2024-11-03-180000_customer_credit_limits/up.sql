@@ -0,0 +1,30 @@
+alter type "WebhookOutEventTypeEnum" add value 'CREDIT_LIMIT_EXCEEDED';
+
+create type "CreditLimitEnforcementEnum" as enum ('FLAG', 'BLOCK');
+create type "CreditLimitSourceEnum" as enum ('SUBSCRIPTION', 'USAGE_THRESHOLD_INVOICE');
+
+-- in cents of the customer currency, no limit when null
+alter table customer
+  add column credit_limit_cents       bigint check (credit_limit_cents >= 0),
+  add column credit_limit_enforcement "CreditLimitEnforcementEnum" not null default 'FLAG';
+
+-- a subscription or a threshold invoice of a customer whose exposure was over the credit limit
+create table if not exists credit_limit_exceeded
+(
+  id              uuid                         not null primary key,
+  tenant_id       uuid                         not null references tenant on update cascade on delete cascade,
+  customer_id     uuid                         not null references customer on update cascade on delete cascade,
+  source          "CreditLimitSourceEnum"      not null,
+  -- none for a blocked subscription, which is never created
+  subscription_id uuid,
+  enforcement     "CreditLimitEnforcementEnum" not null,
+  currency        text                         not null,
+  credit_limit    bigint                       not null,
+  unpaid_invoices bigint                       not null,
+  unbilled_usage  bigint                       not null,
+  exceeded_at     timestamp(3)                 not null default CURRENT_TIMESTAMP,
+  exceeded_on     date                         not null default CURRENT_DATE,
+  unique (customer_id, source, subscription_id, exceeded_on)
+);
+
+create index if not exists credit_limit_exceeded_tenant_id_exceeded_at_idx on credit_limit_exceeded (tenant_id, exceeded_at);
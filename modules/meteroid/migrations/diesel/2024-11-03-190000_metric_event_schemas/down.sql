@@ -0,0 +1,2 @@
+alter table billable_metric
+  drop column event_schema;
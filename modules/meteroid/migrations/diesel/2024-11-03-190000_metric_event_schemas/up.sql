@@ -0,0 +1,3 @@
+-- properties expected on the events of the metric, validated by the metering service at ingestion
+alter table billable_metric
+  add column event_schema jsonb;
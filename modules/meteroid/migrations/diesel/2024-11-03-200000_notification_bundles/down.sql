@@ -0,0 +1,2 @@
+drop table if exists notification_bundle;
+drop type if exists "NotificationNamespaceEnum";
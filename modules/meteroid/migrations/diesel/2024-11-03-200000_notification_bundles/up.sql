@@ -0,0 +1,17 @@
+create type "NotificationNamespaceEnum" as enum ('EMAIL', 'PORTAL', 'PDF');
+
+-- customer-facing messages of a tenant for a locale, overriding the built-in messages of the namespace
+create table if not exists notification_bundle
+(
+  id         uuid                        not null primary key,
+  tenant_id  uuid                        not null references tenant on update cascade on delete cascade,
+  namespace  "NotificationNamespaceEnum" not null,
+  -- ex: fr, fr-CA
+  locale     text                        not null,
+  -- templates by message key
+  messages   jsonb                       not null,
+  created_at timestamp(3)                not null default CURRENT_TIMESTAMP,
+  created_by uuid                        not null,
+  updated_at timestamp(3)                not null default CURRENT_TIMESTAMP,
+  unique (tenant_id, namespace, locale)
+);
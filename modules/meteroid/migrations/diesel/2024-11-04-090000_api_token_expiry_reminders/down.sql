@@ -0,0 +1,2 @@
+drop index if exists api_token_expiring_idx;
+alter table api_token drop column if exists expiry_notified_at;
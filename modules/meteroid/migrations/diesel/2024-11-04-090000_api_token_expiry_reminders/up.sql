@@ -0,0 +1,5 @@
+-- the owner of the token is notified once before its expiry
+alter table api_token add column expiry_notified_at timestamp;
+
+create index if not exists api_token_expiring_idx on api_token (expires_at)
+  where expires_at is not null and expiry_notified_at is null;
@@ -0,0 +1,9 @@
+alter table plan_version
+  drop column inherited_defaults,
+  drop column tax_category;
+
+alter table product_family
+  drop column default_tax_category,
+  drop column default_net_terms,
+  drop column default_trial_duration_days,
+  drop column default_currency;
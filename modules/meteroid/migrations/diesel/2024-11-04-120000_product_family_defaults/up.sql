@@ -0,0 +1,11 @@
+-- settings taken by the new plan versions of the family that don't set them
+alter table product_family
+  add column default_currency            text,
+  add column default_trial_duration_days integer,
+  add column default_net_terms           integer,
+  add column default_tax_category        text;
+
+alter table plan_version
+  add column tax_category       text,
+  -- settings not set on the version, resolved from the product family defaults. Ex: {currency,net_terms}
+  add column inherited_defaults text[] not null default '{}';
@@ -0,0 +1,20 @@
+-- supporting documents of an invoice (usage details, timesheets ..), the content being in the object storage
+create table if not exists invoice_attachment
+(
+  id               uuid         not null primary key,
+  tenant_id        uuid         not null references tenant on update cascade on delete cascade,
+  invoice_id       uuid         not null references invoice on update cascade on delete cascade,
+  file_name        text         not null,
+  content_type     text         not null,
+  size_bytes       bigint       not null,
+  -- id of the content in the object storage
+  document_id      text         not null,
+  -- sent along with the invoice email when the invoice is finalized
+  include_in_email boolean      not null default false,
+  created_at       timestamp(3) not null default CURRENT_TIMESTAMP,
+  created_by       uuid         not null,
+  removed_at       timestamp(3),
+  removed_by       uuid
+);
+
+create index if not exists invoice_attachment_invoice_idx on invoice_attachment (invoice_id) where removed_at is null;
@@ -0,0 +1,5 @@
+drop table if exists invoice_approval_event;
+drop table if exists invoice_approval;
+drop table if exists invoice_approval_step;
+drop type if exists "InvoiceApprovalActionEnum";
+drop type if exists "InvoiceApprovalStatusEnum";
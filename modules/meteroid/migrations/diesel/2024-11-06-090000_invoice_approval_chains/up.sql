@@ -0,0 +1,70 @@
+create type "InvoiceApprovalStatusEnum" as enum ('WAITING', 'PENDING', 'APPROVED', 'REJECTED');
+create type "InvoiceApprovalActionEnum" as enum ('REQUESTED', 'APPROVED', 'REJECTED', 'ESCALATED');
+
+-- the approval chain of a tenant, the invoices go through its applicable steps in order before they are finalized
+create table invoice_approval_step
+(
+  id                     uuid primary key,
+  tenant_id              uuid      not null references tenant on delete cascade,
+  position               integer   not null,
+  name                   text      not null,
+  -- the step applies to the invoices of at least this total (in cents), in the currency if set. To all when null
+  min_total              bigint,
+  currency               text,
+  -- a given member approves the step, else any member with the role
+  assignee_id            uuid,
+  assignee_role          "TenantMemberRoleEnum",
+  -- without decision after it, the step escalates to the escalation assignee or role
+  timeout_hours          integer,
+  escalation_assignee_id uuid,
+  escalation_role        "TenantMemberRoleEnum",
+  created_at             timestamp not null default now(),
+  created_by             uuid      not null,
+  unique (tenant_id, position)
+);
+
+-- the steps of the chain applied to an invoice, copied so that editing the chain does not affect the pending ones
+create table invoice_approval
+(
+  id                     uuid primary key,
+  tenant_id              uuid                        not null references tenant on delete cascade,
+  invoice_id             uuid                        not null references invoice on delete cascade,
+  step_id                uuid                        references invoice_approval_step on delete set null,
+  position               integer                     not null,
+  name                   text                        not null,
+  assignee_id            uuid,
+  assignee_role          "TenantMemberRoleEnum",
+  timeout_hours          integer,
+  escalation_assignee_id uuid,
+  escalation_role        "TenantMemberRoleEnum",
+  status                 "InvoiceApprovalStatusEnum" not null,
+  -- the total of the invoice when the approval was requested, a different total requires a new approval
+  requested_total        bigint                      not null,
+  started_at             timestamp,
+  due_at                 timestamp,
+  escalated_at           timestamp,
+  decided_at             timestamp,
+  decided_by             uuid,
+  comment                text,
+  created_at             timestamp                   not null default now(),
+  unique (invoice_id, position)
+);
+
+create index invoice_approval_due_at_idx on invoice_approval (due_at) where status = 'PENDING' and escalated_at is null;
+
+-- the history of the approvals of an invoice, kept when a new approval is requested
+create table invoice_approval_event
+(
+  id          uuid primary key,
+  tenant_id   uuid                        not null references tenant on delete cascade,
+  invoice_id  uuid                        not null references invoice on delete cascade,
+  approval_id uuid                        references invoice_approval on delete set null,
+  step_name   text,
+  action      "InvoiceApprovalActionEnum" not null,
+  -- none when done by the system, ex: the escalation
+  actor_id    uuid,
+  comment     text,
+  created_at  timestamp                   not null default now()
+);
+
+create index invoice_approval_event_invoice_id_idx on invoice_approval_event (invoice_id);
@@ -0,0 +1 @@
+-- enum values cannot be removed from "BillingPeriodEnum" and "SubscriptionFeeBillingPeriod"
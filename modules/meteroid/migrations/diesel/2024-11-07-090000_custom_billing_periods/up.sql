@@ -0,0 +1,5 @@
+alter type "BillingPeriodEnum" add value 'BIMONTHLY' before 'QUARTERLY';
+alter type "BillingPeriodEnum" add value 'SEMIANNUAL' before 'ANNUAL';
+
+alter type "SubscriptionFeeBillingPeriod" add value 'BIMONTHLY' before 'QUARTERLY';
+alter type "SubscriptionFeeBillingPeriod" add value 'SEMIANNUAL' before 'ANNUAL';
@@ -0,0 +1,16 @@
+-- periods during which the invoices of a customer are not issued (ex: its fiscal close), finalization still happens
+create table if not exists customer_blackout_window
+(
+  id          uuid         not null primary key,
+  tenant_id   uuid         not null references tenant on update cascade on delete cascade,
+  customer_id uuid         not null references customer on update cascade on delete cascade,
+  -- both included
+  start_date  date         not null,
+  end_date    date         not null,
+  reason      text,
+  created_at  timestamp(3) not null default CURRENT_TIMESTAMP,
+  created_by  uuid         not null,
+  constraint customer_blackout_window_dates_check check (end_date >= start_date)
+);
+
+create index if not exists customer_blackout_window_customer_idx on customer_blackout_window (customer_id, end_date);
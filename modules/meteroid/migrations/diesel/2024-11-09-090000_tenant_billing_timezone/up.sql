@@ -0,0 +1,4 @@
+-- IANA timezone in which the billing days of the tenant start (period boundaries, grace periods and due dates).
+-- Existing tenants are billed in UTC, their drafts and due dates are unchanged
+alter table tenant
+  add column billing_timezone text not null default 'UTC';
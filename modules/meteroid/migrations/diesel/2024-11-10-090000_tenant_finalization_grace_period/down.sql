@@ -0,0 +1,2 @@
+alter table tenant
+  drop column finalization_grace_period_hours;
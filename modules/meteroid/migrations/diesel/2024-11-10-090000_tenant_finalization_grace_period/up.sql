@@ -0,0 +1,4 @@
+-- hours after the invoice date during which late usage is still added and drafts can be edited, before the finalization.
+-- Overrides the grace period of the invoicing entities of the tenant when set
+alter table tenant
+  add column finalization_grace_period_hours integer;
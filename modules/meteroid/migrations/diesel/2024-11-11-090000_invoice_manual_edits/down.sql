@@ -0,0 +1,3 @@
+alter table invoice
+  drop column manually_edited_at,
+  drop column manually_edited_by;
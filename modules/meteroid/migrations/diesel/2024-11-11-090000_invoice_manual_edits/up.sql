@@ -0,0 +1,4 @@
+-- set when a user edited the lines, memo or due date of a draft. The price worker then keeps its lines
+alter table invoice
+  add column manually_edited_at timestamp,
+  add column manually_edited_by uuid;
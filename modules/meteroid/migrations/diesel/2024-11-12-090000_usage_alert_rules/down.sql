@@ -0,0 +1,7 @@
+drop table if exists usage_alert_rule_event;
+drop table if exists usage_alert_rule;
+
+drop type if exists "UsageAlertRuleStatusEnum";
+drop type if exists "UsageAlertRuleWindowEnum";
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
@@ -0,0 +1,47 @@
+-- the usage of the current billing period, or of the last day, 7 days or 30 days (today included)
+create type "UsageAlertRuleWindowEnum" as enum ('BILLING_PERIOD', 'ROLLING_DAY', 'ROLLING_WEEK', 'ROLLING_MONTH');
+
+create type "UsageAlertRuleStatusEnum" as enum ('FIRING', 'RESOLVED');
+
+alter type "WebhookOutEventTypeEnum" add value 'USAGE_ALERT_RULE_FIRING';
+alter type "WebhookOutEventTypeEnum" add value 'USAGE_ALERT_RULE_RESOLVED';
+
+create table if not exists usage_alert_rule
+(
+  id                 uuid                       not null primary key,
+  tenant_id          uuid                       not null references tenant on update cascade on delete cascade,
+  name               text                       not null,
+  billable_metric_id uuid                       not null references billable_metric on update cascade on delete cascade,
+  -- all the customers billed for the metric if null
+  customer_id        uuid references customer on update cascade on delete cascade,
+  usage_window       "UsageAlertRuleWindowEnum" not null,
+  -- all the conditions must be met, otherwise any of them
+  match_all          boolean                    not null default true,
+  conditions         jsonb                      not null,
+  notify_webhook     boolean                    not null default true,
+  notify_email       text,
+  last_evaluated_at  timestamp(3),
+  created_at         timestamp(3)               not null default CURRENT_TIMESTAMP,
+  created_by         uuid                       not null,
+  updated_at         timestamp(3),
+  archived_at        timestamp(3)
+);
+
+create index if not exists usage_alert_rule_tenant_id_idx on usage_alert_rule (tenant_id);
+
+-- the transitions of a rule for a customer, the last one being its current state
+create table if not exists usage_alert_rule_event
+(
+  id             uuid                       not null primary key,
+  tenant_id      uuid                       not null references tenant on update cascade on delete cascade,
+  rule_id        uuid                       not null references usage_alert_rule on update cascade on delete cascade,
+  customer_id    uuid                       not null references customer on update cascade on delete cascade,
+  status         "UsageAlertRuleStatusEnum" not null,
+  usage          numeric                    not null,
+  growth_percent numeric,
+  window_start   date                       not null,
+  window_end     date                       not null,
+  created_at     timestamp(3)               not null default CURRENT_TIMESTAMP
+);
+
+create index if not exists usage_alert_rule_event_rule_id_customer_id_idx on usage_alert_rule_event (rule_id, customer_id, created_at);
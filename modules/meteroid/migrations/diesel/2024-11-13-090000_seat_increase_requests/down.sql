@@ -0,0 +1,5 @@
+drop table if exists seat_increase_request;
+drop type if exists "SeatIncreaseRequestStatusEnum";
+
+alter table tenant
+  drop column seat_auto_approve_limit;
@@ -0,0 +1,30 @@
+-- seats added in a single request of an end customer without the approval of the tenant. All the requests are approved when null
+alter table tenant
+  add column seat_auto_approve_limit integer;
+
+create type "SeatIncreaseRequestStatusEnum" as enum ('PENDING', 'APPROVED', 'REJECTED');
+
+alter type "WebhookOutEventTypeEnum" add value 'SEAT_INCREASE_REQUESTED';
+
+create table if not exists seat_increase_request
+(
+  id                 uuid                            not null primary key,
+  tenant_id          uuid                            not null references tenant on update cascade on delete cascade,
+  subscription_id    uuid                            not null references subscription on update cascade on delete cascade,
+  price_component_id uuid                            not null,
+  seats              integer                         not null check (seats > 0),
+  -- the end customer contact, as provided by the portal
+  requested_by       text,
+  note               text,
+  status             "SeatIncreaseRequestStatusEnum" not null,
+  created_at         timestamp(3)                    not null default CURRENT_TIMESTAMP,
+  decided_at         timestamp(3),
+  -- null when approved automatically
+  decided_by         uuid,
+  decision_note      text,
+  -- the prorated charge of the added seats
+  invoice_id         uuid references invoice on update cascade on delete set null
+);
+
+create index if not exists seat_increase_request_tenant_id_status_idx on seat_increase_request (tenant_id, status);
+create index if not exists seat_increase_request_subscription_id_idx on seat_increase_request (subscription_id);
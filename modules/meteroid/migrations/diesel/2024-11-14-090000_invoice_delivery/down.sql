@@ -0,0 +1,11 @@
+alter table invoice
+  drop column delivery_channel,
+  drop column delivery_status,
+  drop column delivered_at,
+  drop column hosted_invoice_url;
+
+alter table customer
+  drop column invoice_delivery_channel;
+
+drop type if exists "InvoiceDeliveryStatusEnum";
+drop type if exists "InvoiceDeliveryChannelEnum";
@@ -0,0 +1,15 @@
+create type "InvoiceDeliveryChannelEnum" as enum ('EMAIL', 'WEBHOOK', 'PROVIDER_HOSTED');
+create type "InvoiceDeliveryStatusEnum" as enum ('DELIVERED', 'FAILED');
+
+-- the invoices of the customer are only issued to their invoicing provider when null
+alter table customer
+  add column invoice_delivery_channel "InvoiceDeliveryChannelEnum";
+
+alter table invoice
+  add column delivery_channel   "InvoiceDeliveryChannelEnum",
+  add column delivery_status    "InvoiceDeliveryStatusEnum",
+  add column delivered_at       timestamp(3),
+  -- the page of the invoice at the invoicing provider, when delivered by it
+  add column hosted_invoice_url text;
+
+alter type "WebhookOutEventTypeEnum" add value 'INVOICE_DELIVERY_REQUESTED';
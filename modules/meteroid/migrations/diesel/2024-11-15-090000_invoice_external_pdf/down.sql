@@ -0,0 +1,2 @@
+alter table invoice
+  drop column external_pdf_url;
@@ -0,0 +1,3 @@
+-- the PDF of the invoice at the invoicing provider, once finalized there
+alter table invoice
+  add column external_pdf_url text;
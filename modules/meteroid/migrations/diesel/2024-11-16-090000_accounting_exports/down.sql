@@ -0,0 +1,6 @@
+drop table if exists accounting_export_run;
+drop table if exists accounting_export_config;
+
+drop type if exists "AccountingExportRunStatusEnum";
+drop type if exists "AccountingExportScheduleEnum";
+drop type if exists "AccountingConnectorEnum";
@@ -0,0 +1,38 @@
+create type "AccountingConnectorEnum" as enum ('CSV', 'QUICKBOOKS_ONLINE');
+create type "AccountingExportScheduleEnum" as enum ('DAILY', 'WEEKLY', 'MONTHLY');
+create type "AccountingExportRunStatusEnum" as enum ('SUCCEEDED', 'FAILED');
+
+-- the journal entries of a tenant are exported once each period of the schedule is complete
+create table if not exists accounting_export_config
+(
+  tenant_id      uuid                           not null primary key references tenant on update cascade on delete cascade,
+  connector      "AccountingConnectorEnum"      not null,
+  schedule       "AccountingExportScheduleEnum" not null,
+  enabled        boolean                        not null default true,
+  -- codes of the ledger accounts of the tenant, by role
+  accounts       jsonb                          not null,
+  -- encrypted, the connection to the accounting software if the connector needs one
+  credentials    text,
+  -- last day exported, included
+  exported_until date,
+  created_at     timestamp(3)                   not null default CURRENT_TIMESTAMP,
+  created_by     uuid                           not null,
+  updated_at     timestamp(3)                   not null default CURRENT_TIMESTAMP
+);
+
+create table if not exists accounting_export_run
+(
+  id           uuid                            not null primary key,
+  tenant_id    uuid                            not null references tenant on update cascade on delete cascade,
+  connector    "AccountingConnectorEnum"       not null,
+  period_start date                            not null,
+  period_end   date                            not null,
+  status       "AccountingExportRunStatusEnum" not null,
+  entries      integer                         not null,
+  -- the exported file, for the flat-file connector
+  document_id  text,
+  error        text,
+  created_at   timestamp(3)                    not null default CURRENT_TIMESTAMP
+);
+
+create index if not exists accounting_export_run_tenant_id_idx on accounting_export_run (tenant_id, created_at desc);
@@ -0,0 +1 @@
+alter type "InvoicingProviderEnum" add value if not exists 'ADYEN';
@@ -0,0 +1,17 @@
+drop index if exists invoice_external_invoice_id_idx;
+
+-- values cannot be dropped from an enum, the type is recreated without it
+delete from provider_config where invoicing_provider = 'GO_CARDLESS';
+update invoice set invoicing_provider = 'MANUAL' where invoicing_provider = 'GO_CARDLESS';
+
+alter type "InvoicingProviderEnum" rename to "InvoicingProviderEnum_old";
+create type "InvoicingProviderEnum" as enum ('STRIPE', 'MANUAL', 'ADYEN');
+
+alter table invoice
+  alter column invoicing_provider type "InvoicingProviderEnum"
+    using invoicing_provider::text::"InvoicingProviderEnum";
+alter table provider_config
+  alter column invoicing_provider type "InvoicingProviderEnum"
+    using invoicing_provider::text::"InvoicingProviderEnum";
+
+drop type "InvoicingProviderEnum_old";
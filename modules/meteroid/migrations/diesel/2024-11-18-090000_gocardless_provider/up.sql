@@ -0,0 +1,6 @@
+alter type "InvoicingProviderEnum" add value if not exists 'GO_CARDLESS';
+
+-- the GoCardless payments reference the invoices by their id at GoCardless only
+create index if not exists invoice_external_invoice_id_idx
+  on invoice (tenant_id, external_invoice_id)
+  where external_invoice_id is not null;
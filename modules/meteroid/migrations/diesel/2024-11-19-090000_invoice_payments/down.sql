@@ -0,0 +1,3 @@
+drop table if exists invoice_payment;
+
+-- enum values cannot be removed from "WebhookOutEventTypeEnum"
@@ -0,0 +1,20 @@
+alter type "WebhookOutEventTypeEnum" add value 'INVOICE_PAID';
+alter type "WebhookOutEventTypeEnum" add value 'INVOICE_PAYMENT_RECORDED';
+
+-- payments collected outside of any invoicing provider (bank transfers, cheques ..), recorded by the tenant
+create table if not exists invoice_payment
+(
+  id         uuid         not null primary key,
+  tenant_id  uuid         not null references tenant on update cascade on delete cascade,
+  invoice_id uuid         not null references invoice on update cascade on delete cascade,
+  -- in the currency of the invoice, deducted from its amount due
+  amount     bigint       not null check (amount > 0),
+  paid_at    date         not null,
+  -- the reference of the transfer, the cheque number ..
+  reference  text,
+  note       text,
+  created_at timestamp(3) not null default CURRENT_TIMESTAMP,
+  created_by uuid         not null
+);
+
+create index if not exists invoice_payment_invoice_id_idx on invoice_payment (tenant_id, invoice_id);
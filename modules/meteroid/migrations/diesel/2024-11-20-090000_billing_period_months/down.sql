@@ -0,0 +1,9 @@
+alter table schedule drop constraint if exists schedule_billing_period_custom_check;
+alter table plan_version drop constraint if exists plan_version_billing_periods_custom_check;
+
+alter table invoice drop column if exists billing_cadence_months;
+alter table subscription_add_on drop column if exists billing_period_months;
+alter table subscription_component drop column if exists billing_period_months;
+alter table subscription drop column if exists billing_period_months;
+
+-- enum values cannot be removed from "BillingPeriodEnum" and "SubscriptionFeeBillingPeriod"
@@ -0,0 +1,36 @@
+alter type "BillingPeriodEnum" add value 'WEEKLY' before 'MONTHLY';
+alter type "BillingPeriodEnum" add value 'CUSTOM';
+
+alter type "SubscriptionFeeBillingPeriod" add value 'WEEKLY' before 'MONTHLY';
+alter type "SubscriptionFeeBillingPeriod" add value 'CUSTOM';
+
+-- the length of a CUSTOM period, in months. The named periods are used for 1, 2, 3, 6 and 12 months
+-- the new enum values cannot be used before the transaction is committed, the checks compare the text of the periods
+alter table subscription
+  add column billing_period_months integer check (billing_period_months between 1 and 36),
+  add constraint subscription_billing_period_months_check
+    check ((period::text = 'CUSTOM') = (billing_period_months is not null));
+
+alter table subscription_component
+  add column billing_period_months integer check (billing_period_months between 1 and 36),
+  add constraint subscription_component_billing_period_months_check
+    check ((period::text = 'CUSTOM') = (billing_period_months is not null));
+
+alter table subscription_add_on
+  add column billing_period_months integer check (billing_period_months between 1 and 36),
+  add constraint subscription_add_on_billing_period_months_check
+    check ((period::text = 'CUSTOM') = (billing_period_months is not null));
+
+alter table invoice
+  add column billing_cadence_months integer check (billing_cadence_months between 1 and 36),
+  add constraint invoice_billing_cadence_months_check
+    check (coalesce(billing_cadence::text = 'CUSTOM', false) = (billing_cadence_months is not null));
+
+-- the plans and schedules only offer the named periods
+alter table plan_version
+  add constraint plan_version_billing_periods_custom_check
+    check (not ('CUSTOM' = any (billing_periods::text[])));
+
+alter table schedule
+  add constraint schedule_billing_period_custom_check
+    check (billing_period::text <> 'CUSTOM');
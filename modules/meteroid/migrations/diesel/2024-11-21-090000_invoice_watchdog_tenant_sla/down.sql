@@ -0,0 +1,9 @@
+drop index if exists invoice_stuck_alert_unresolved_idx;
+
+alter table invoice_stuck_alert
+  drop column resolved_at;
+
+alter table tenant
+  drop column stuck_draft_max_age_days,
+  drop column stuck_pending_max_age_hours,
+  drop column stuck_max_issue_attempts;
@@ -0,0 +1,11 @@
+-- SLAs of the invoice watchdog, overriding the defaults of the instance when set
+alter table tenant
+  add column stuck_draft_max_age_days    integer check (stuck_draft_max_age_days > 0),
+  add column stuck_pending_max_age_hours integer check (stuck_pending_max_age_hours > 0),
+  add column stuck_max_issue_attempts    integer check (stuck_max_issue_attempts > 0);
+
+-- the alerts of the invoices that moved on are kept as resolved, an alert is sent again if the invoice gets stuck later
+alter table invoice_stuck_alert
+  add column resolved_at timestamp(3);
+
+create index if not exists invoice_stuck_alert_unresolved_idx on invoice_stuck_alert (invoice_id) where resolved_at is null;
@@ -0,0 +1,522 @@
+/*
+    Invoices through Adyen, which has no invoice object: the amount due is paid on a payment link
+    referencing the invoice, and the AUTHORISATION notifications of the standard webhook report the payments.
+
+    The provider config of the tenant holds its api key, its merchant account and the HMAC key of the webhook.
+*/
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use base64::Engine;
+use error_stack::{bail, Report, Result, ResultExt};
+use hyper::StatusCode;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use meteroid_store::domain::configs::ApiSecurity;
+use meteroid_store::domain::enums::InvoiceExternalStatusEnum;
+use meteroid_store::domain::{BillingConfig, Customer, ExternalInvoiceLinks, Invoice};
+use meteroid_store::repositories::InvoiceInterface;
+use meteroid_store::Store;
+
+use super::types::{AdapterCommon, InvoicingAdapter, ParsedRequest, WebhookAdapter};
+use crate::errors;
+use crate::errors::InvoicingAdapterError;
+
+static ADYEN: std::sync::OnceLock<Adyen> = std::sync::OnceLock::new();
+
+const TEST_CHECKOUT_URL: &str = "https://checkout-test.adyen.com/v71";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const REQUEST_RETRIES: u32 = 3;
+
+// longest validity of a payment link
+const MAX_LINK_VALIDITY_DAYS: i64 = 70;
+
+pub mod event_code {
+    pub const AUTHORISATION: &str = "AUTHORISATION";
+}
+
+pub mod link_status {
+    pub const ACTIVE: &str = "active";
+    pub const COMPLETED: &str = "completed";
+    pub const EXPIRED: &str = "expired";
+    pub const PAYMENT_PENDING: &str = "paymentPending";
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PaymentLink {
+    pub id: String,
+    pub url: String,
+    pub status: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Notification {
+    notification_items: Vec<NotificationItem>,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationItem {
+    #[serde(rename = "NotificationRequestItem")]
+    item: NotificationRequestItem,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct NotificationRequestItem {
+    #[serde(default)]
+    additional_data: std::collections::HashMap<String, String>,
+    amount: NotificationAmount,
+    event_code: String,
+    merchant_account_code: String,
+    merchant_reference: String,
+    #[serde(default)]
+    original_reference: Option<String>,
+    psp_reference: String,
+    success: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct NotificationAmount {
+    currency: String,
+    value: i64,
+}
+
+impl NotificationRequestItem {
+    /// The fields signed by Adyen, in order
+    fn signed_payload(&self) -> String {
+        [
+            self.psp_reference.as_str(),
+            self.original_reference.as_deref().unwrap_or_default(),
+            self.merchant_account_code.as_str(),
+            self.merchant_reference.as_str(),
+            self.amount.value.to_string().as_str(),
+            self.amount.currency.as_str(),
+            self.event_code.as_str(),
+            self.success.as_str(),
+        ]
+        .join(":")
+    }
+
+    fn is_success(&self) -> bool {
+        self.success == "true"
+    }
+}
+
+#[derive(Clone)]
+pub struct Adyen {
+    client: ClientWithMiddleware,
+}
+
+impl AdapterCommon for Adyen {
+    fn id(&self) -> &'static str {
+        "adyen"
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookAdapter for Adyen {
+    async fn verify_webhook(
+        &self,
+        request: &ParsedRequest,
+        security: &SecretString,
+    ) -> Result<bool, errors::AdapterWebhookError> {
+        let notification = Self::parse_notification(request)?;
+
+        let key = hex::decode(security.expose_secret())
+            .change_context(errors::AdapterWebhookError::SignatureVerificationFailed)
+            .attach_printable("The HMAC key is not hexadecimal")?;
+
+        for item in notification.notification_items.iter().map(|i| &i.item) {
+            let signature = item
+                .additional_data
+                .get("hmacSignature")
+                .ok_or(errors::AdapterWebhookError::SignatureNotFound)?;
+
+            if !Self::is_valid_signature(&key, item, signature) {
+                bail!(errors::AdapterWebhookError::SignatureVerificationFailed);
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn get_optimistic_webhook_response(&self) -> axum::response::Response {
+        // any other response is considered a failed delivery by Adyen
+        (StatusCode::OK, "[accepted]").into_response()
+    }
+
+    fn event_id(&self, request: &ParsedRequest) -> Option<String> {
+        let notification = Self::parse_notification(request).ok()?;
+
+        // a payment link can be paid after failed attempts, each with its psp reference
+        notification.notification_items.first().map(|i| {
+            format!(
+                "{}:{}:{}",
+                i.item.psp_reference, i.item.event_code, i.item.success
+            )
+        })
+    }
+
+    async fn process_webhook_event(
+        &self,
+        request: &ParsedRequest,
+        tenant_id: Uuid,
+        store: Store,
+    ) -> Result<bool, errors::AdapterWebhookError> {
+        let notification = Self::parse_notification(request)?;
+
+        for item in notification.notification_items.into_iter().map(|i| i.item) {
+            let external_status = match Self::external_status_to_service(&item) {
+                Some(status) => status,
+                None => bail!(errors::AdapterWebhookError::EventTypeNotSupported(
+                    item.event_code
+                )),
+            };
+
+            // the reference of the payment link is the invoice id
+            let invoice_id = Uuid::parse_str(&item.merchant_reference)
+                .change_context(errors::AdapterWebhookError::BodyDecodingFailed)?;
+
+            store
+                .update_invoice_external_status(invoice_id, tenant_id, external_status)
+                .await
+                .change_context(errors::AdapterWebhookError::DatabaseError)?;
+        }
+
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoicingAdapter for Adyen {
+    async fn send_invoice(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+        api_security: &ApiSecurity,
+    ) -> Result<String, InvoicingAdapterError> {
+        if invoice.amount_due <= 0 {
+            bail!(Report::new(InvoicingAdapterError::InvalidData)
+                .attach_printable("A payment link requires an amount due"));
+        }
+
+        let merchant_account = Self::merchant_account(api_security)?;
+        let expires_at = chrono::Utc::now() + chrono::Duration::days(MAX_LINK_VALIDITY_DAYS);
+
+        let response = self
+            .client
+            .post(format!("{}/paymentLinks", Self::checkout_url(api_security)))
+            .header("X-API-Key", api_security.api_key.as_str())
+            .header("Idempotency-Key", invoice.id.to_string())
+            .json(&Self::payment_link_payload(
+                invoice,
+                customer,
+                merchant_account,
+                expires_at,
+            ))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .change_context(InvoicingAdapterError::AdyenError)?;
+
+        let link: PaymentLink = Self::parse(response).await?;
+
+        Ok(link.id)
+    }
+
+    async fn void_invoice(
+        &self,
+        invoice: &Invoice,
+        api_security: &ApiSecurity,
+    ) -> Result<(), InvoicingAdapterError> {
+        let link_id = invoice
+            .external_invoice_id
+            .as_deref()
+            .ok_or(InvoicingAdapterError::InvalidData)
+            .attach_printable("The invoice was not issued to Adyen")?;
+
+        // a completed link cannot be expired, the payment has to be refunded from Adyen
+        let response = self
+            .client
+            .patch(format!(
+                "{}/paymentLinks/{}",
+                Self::checkout_url(api_security),
+                link_id
+            ))
+            .header("X-API-Key", api_security.api_key.as_str())
+            .json(&serde_json::json!({ "status": link_status::EXPIRED }))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .change_context(InvoicingAdapterError::AdyenError)?;
+
+        let _: PaymentLink = Self::parse(response).await?;
+
+        Ok(())
+    }
+
+    async fn finalize_hosted_invoice(
+        &self,
+        external_invoice_id: &str,
+        api_security: &ApiSecurity,
+    ) -> Result<ExternalInvoiceLinks, InvoicingAdapterError> {
+        // the link is active once created, there is no PDF at Adyen
+        let link = self
+            .fetch_payment_link(external_invoice_id, api_security)
+            .await?;
+
+        Ok(ExternalInvoiceLinks {
+            hosted_invoice_url: Some(link.url),
+            external_pdf_url: None,
+        })
+    }
+}
+
+impl Adyen {
+    pub fn new() -> Self {
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(REQUEST_RETRIES);
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        Adyen { client }
+    }
+
+    pub fn get() -> &'static Self {
+        ADYEN.get_or_init(Adyen::new)
+    }
+
+    /// The payment link of the invoice, and whether it was paid
+    pub async fn fetch_payment_link(
+        &self,
+        link_id: &str,
+        api_security: &ApiSecurity,
+    ) -> Result<PaymentLink, InvoicingAdapterError> {
+        let response = self
+            .client
+            .get(format!(
+                "{}/paymentLinks/{}",
+                Self::checkout_url(api_security),
+                link_id
+            ))
+            .header("X-API-Key", api_security.api_key.as_str())
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .change_context(InvoicingAdapterError::AdyenError)?;
+
+        Self::parse(response).await
+    }
+
+    /// The status of the invoice matching the status of its payment link
+    pub fn link_status_to_service(status: &str) -> Option<InvoiceExternalStatusEnum> {
+        match status {
+            link_status::ACTIVE | link_status::PAYMENT_PENDING => {
+                Some(InvoiceExternalStatusEnum::Finalized)
+            }
+            link_status::COMPLETED => Some(InvoiceExternalStatusEnum::Paid),
+            link_status::EXPIRED => Some(InvoiceExternalStatusEnum::Void),
+            _ => None,
+        }
+    }
+
+    // payment links are captured automatically, an authorised payment pays the invoice
+    fn external_status_to_service(
+        item: &NotificationRequestItem,
+    ) -> Option<InvoiceExternalStatusEnum> {
+        match (item.event_code.as_str(), item.is_success()) {
+            (event_code::AUTHORISATION, true) => Some(InvoiceExternalStatusEnum::Paid),
+            (event_code::AUTHORISATION, false) => Some(InvoiceExternalStatusEnum::PaymentFailed),
+            _ => None,
+        }
+    }
+
+    fn parse_notification(
+        request: &ParsedRequest,
+    ) -> Result<Notification, errors::AdapterWebhookError> {
+        serde_json::from_value(request.json_body.clone())
+            .change_context(errors::AdapterWebhookError::BodyDecodingFailed)
+    }
+
+    fn is_valid_signature(key: &[u8], item: &NotificationRequestItem, signature: &str) -> bool {
+        let expected = hmac_sha256::HMAC::mac(item.signed_payload().as_bytes(), key);
+
+        base64::engine::general_purpose::STANDARD
+            .decode(signature)
+            .is_ok_and(|signature| {
+                signature.len() == expected.len()
+                    && signature
+                        .iter()
+                        .zip(expected.iter())
+                        .fold(0, |acc, (a, b)| acc | (a ^ b))
+                        == 0
+            })
+    }
+
+    fn merchant_account(api_security: &ApiSecurity) -> Result<&str, InvoicingAdapterError> {
+        api_security
+            .merchant_account
+            .as_deref()
+            .filter(|account| !account.trim().is_empty())
+            .ok_or(InvoicingAdapterError::ProviderNotConfigured)
+            .attach_printable("The Adyen merchant account is missing")
+    }
+
+    fn checkout_url(api_security: &ApiSecurity) -> String {
+        match &api_security.live_url_prefix {
+            Some(prefix) => format!(
+                "https://{}-checkout-live.adyenpayments.com/checkout/v71",
+                prefix
+            ),
+            None => TEST_CHECKOUT_URL.to_string(),
+        }
+    }
+
+    fn shopper_reference(customer: &Customer) -> String {
+        match &customer.billing_config {
+            BillingConfig::Adyen(adyen) => adyen.shopper_reference.clone(),
+            // invoices falling back to Adyen
+            _ => customer.id.to_string(),
+        }
+    }
+
+    fn payment_link_payload(
+        invoice: &Invoice,
+        customer: &Customer,
+        merchant_account: &str,
+        expires_at: chrono::DateTime<chrono::Utc>,
+    ) -> serde_json::Value {
+        serde_json::json!({
+            "reference": invoice.id.to_string(),
+            "merchantAccount": merchant_account,
+            "amount": {
+                "value": invoice.amount_due,
+                "currency": invoice.currency,
+            },
+            "description": format!("Invoice {}", invoice.invoice_number),
+            "shopperReference": Self::shopper_reference(customer),
+            "shopperEmail": customer.billing_email(),
+            "expiresAt": expires_at.to_rfc3339_opts(chrono::SecondsFormat::Secs, true),
+            "reusable": false,
+        })
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, InvoicingAdapterError> {
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!(Report::new(InvoicingAdapterError::AdyenError)
+                .attach_printable(format!("Adyen responded {}: {}", status, body)));
+        }
+
+        response
+            .json()
+            .await
+            .change_context(InvoicingAdapterError::AdyenError)
+    }
+}
+
+impl Default for Adyen {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // HMAC key of a test webhook, as shown in the Adyen customer area
+    const HMAC_KEY: &str = "44782DEF547AAA06C910C43932B1EB0C71FC68D9D0C057550C48EC2ACF6BA056";
+
+    fn item(success: &str) -> NotificationRequestItem {
+        NotificationRequestItem {
+            additional_data: Default::default(),
+            amount: NotificationAmount {
+                currency: "EUR".to_string(),
+                value: 1130,
+            },
+            event_code: "AUTHORISATION".to_string(),
+            merchant_account_code: "TestMerchant".to_string(),
+            merchant_reference: "TestPayment-1407325143704".to_string(),
+            original_reference: None,
+            psp_reference: "7914073381342284".to_string(),
+            success: success.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_signed_payload() {
+        assert_eq!(
+            item("true").signed_payload(),
+            "7914073381342284::TestMerchant:TestPayment-1407325143704:1130:EUR:AUTHORISATION:true"
+        );
+    }
+
+    #[test]
+    fn test_signature() {
+        let key = hex::decode(HMAC_KEY).unwrap();
+        let signed = hmac_sha256::HMAC::mac(item("true").signed_payload().as_bytes(), &key);
+        let signature = base64::engine::general_purpose::STANDARD.encode(signed);
+
+        assert!(Adyen::is_valid_signature(&key, &item("true"), &signature));
+        // the outcome of the payment is signed
+        assert!(!Adyen::is_valid_signature(&key, &item("false"), &signature));
+        assert!(!Adyen::is_valid_signature(
+            &key,
+            &item("true"),
+            "not base64"
+        ));
+    }
+
+    #[test]
+    fn test_external_status() {
+        assert_eq!(
+            Adyen::external_status_to_service(&item("true")),
+            Some(InvoiceExternalStatusEnum::Paid)
+        );
+        assert_eq!(
+            Adyen::external_status_to_service(&item("false")),
+            Some(InvoiceExternalStatusEnum::PaymentFailed)
+        );
+
+        let refund = NotificationRequestItem {
+            event_code: "REFUND".to_string(),
+            ..item("true")
+        };
+        assert_eq!(Adyen::external_status_to_service(&refund), None);
+
+        assert_eq!(
+            Adyen::link_status_to_service(link_status::COMPLETED),
+            Some(InvoiceExternalStatusEnum::Paid)
+        );
+    }
+
+    #[test]
+    fn test_checkout_url() {
+        let mut api_security = ApiSecurity {
+            api_key: "key".to_string(),
+            merchant_account: Some("TestMerchant".to_string()),
+            live_url_prefix: None,
+        };
+        assert_eq!(Adyen::checkout_url(&api_security), TEST_CHECKOUT_URL);
+
+        api_security.live_url_prefix = Some("1797a841fbb37ca7-AdyenDemo".to_string());
+        assert_eq!(
+            Adyen::checkout_url(&api_security),
+            "https://1797a841fbb37ca7-AdyenDemo-checkout-live.adyenpayments.com/checkout/v71"
+        );
+
+        api_security.merchant_account = Some(" ".to_string());
+        assert!(Adyen::merchant_account(&api_security).is_err());
+    }
+}
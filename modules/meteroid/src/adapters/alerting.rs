@@ -0,0 +1,348 @@
+/*
+    Escalates the critical failures of the billing pipeline (worker runs, issue error spikes, ingestion stalls)
+    to the on-call tooling, PagerDuty or Opsgenie.
+
+    Routes are configured as a json list in ALERTING_ROUTES, ex:
+    [
+        {"provider": "pager_duty", "key": "<routing key>", "min_severity": "critical"},
+        {"provider": "opsgenie", "key": "<api key>", "min_severity": "warning", "tenant_id": "<uuid>"}
+    ]
+    The routes of a tenant replace the global ones (without tenant_id) for the alerts of this tenant.
+*/
+use std::collections::HashMap;
+use std::fmt::Display;
+use std::str::FromStr;
+use std::time::Duration;
+
+use envconfig::Envconfig;
+use error_stack::{bail, Report, Result};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use secrecy::{ExposeSecret, SecretString};
+use serde::{Deserialize, Deserializer, Serialize};
+use uuid::Uuid;
+
+use crate::errors::AlertingError;
+
+static ALERTER: std::sync::OnceLock<Alerter> = std::sync::OnceLock::new();
+
+const PAGER_DUTY_EVENTS_URL: &str = "https://events.pagerduty.com/v2/enqueue";
+const OPSGENIE_ALERTS_URL: &str = "https://api.opsgenie.com/v2/alerts";
+
+const ALERT_REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+const ALERT_RETRIES: u32 = 3;
+
+const ALERT_SOURCE: &str = "meteroid";
+
+#[derive(Envconfig, Debug, Clone)]
+pub struct AlertingConfig {
+    #[envconfig(from = "ALERTING_ROUTES", default = "[]")]
+    pub routes: AlertRoutes,
+
+    /// issue failures of a tenant in a single run of the issue worker before alerting
+    #[envconfig(from = "ALERTING_ISSUE_ERROR_SPIKE_THRESHOLD", default = "10")]
+    pub issue_error_spike_threshold: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertSeverity {
+    Warning,
+    Error,
+    Critical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AlertProvider {
+    PagerDuty,
+    Opsgenie,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct AlertRoute {
+    pub provider: AlertProvider,
+    /// PagerDuty routing key, or Opsgenie api key
+    #[serde(deserialize_with = "deserialize_secret")]
+    pub key: SecretString,
+    pub min_severity: AlertSeverity,
+    /// global route if not set
+    pub tenant_id: Option<Uuid>,
+}
+
+fn deserialize_secret<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> std::result::Result<SecretString, D::Error> {
+    String::deserialize(deserializer).map(SecretString::new)
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct AlertRoutes(pub Vec<AlertRoute>);
+
+impl FromStr for AlertRoutes {
+    type Err = serde_json::Error;
+
+    fn from_str(s: &str) -> std::result::Result<Self, Self::Err> {
+        serde_json::from_str(s).map(AlertRoutes)
+    }
+}
+
+impl AlertRoutes {
+    /// The routes receiving an alert : the ones of its tenant if any, else the global ones
+    pub fn select(&self, alert: &Alert) -> Vec<&AlertRoute> {
+        let tenant_routes: Vec<&AlertRoute> = self
+            .0
+            .iter()
+            .filter(|r| r.tenant_id.is_some() && r.tenant_id == alert.tenant_id)
+            .collect();
+
+        let routes = if tenant_routes.is_empty() {
+            self.0.iter().filter(|r| r.tenant_id.is_none()).collect()
+        } else {
+            tenant_routes
+        };
+
+        routes
+            .into_iter()
+            .filter(|r| alert.severity >= r.min_severity)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Alert {
+    pub severity: AlertSeverity,
+    pub summary: String,
+    /// groups the repeated alerts of a same failure into a single incident
+    pub dedup_key: String,
+    pub tenant_id: Option<Uuid>,
+    pub details: HashMap<String, String>,
+}
+
+impl Alert {
+    pub fn worker_failure(worker: &str, error: &impl Display) -> Self {
+        Alert {
+            severity: AlertSeverity::Critical,
+            summary: format!("Billing worker {} failed", worker),
+            dedup_key: format!("worker-failure-{}", worker),
+            tenant_id: None,
+            details: HashMap::from([
+                ("worker".to_string(), worker.to_string()),
+                ("error".to_string(), error.to_string()),
+            ]),
+        }
+    }
+
+    pub fn issue_error_spike(tenant_id: Uuid, failures: u32) -> Self {
+        Alert {
+            severity: AlertSeverity::Error,
+            summary: format!(
+                "{} invoices of tenant {} failed to be issued",
+                failures, tenant_id
+            ),
+            dedup_key: format!("issue-error-spike-{}", tenant_id),
+            tenant_id: Some(tenant_id),
+            details: HashMap::from([("failures".to_string(), failures.to_string())]),
+        }
+    }
+
+    pub fn ingestion_stalled(tenant_id: Uuid, error: &impl Display) -> Self {
+        Alert {
+            severity: AlertSeverity::Critical,
+            summary: format!("Usage events of tenant {} cannot be ingested", tenant_id),
+            dedup_key: format!("ingestion-stalled-{}", tenant_id),
+            tenant_id: Some(tenant_id),
+            details: HashMap::from([("error".to_string(), error.to_string())]),
+        }
+    }
+
+    fn pager_duty_payload(&self, routing_key: &str) -> serde_json::Value {
+        serde_json::json!({
+            "routing_key": routing_key,
+            "event_action": "trigger",
+            "dedup_key": self.dedup_key,
+            "payload": {
+                "summary": self.summary,
+                "source": ALERT_SOURCE,
+                "severity": self.severity,
+                "custom_details": self.details_with_tenant(),
+            }
+        })
+    }
+
+    fn opsgenie_payload(&self) -> serde_json::Value {
+        let priority = match self.severity {
+            AlertSeverity::Critical => "P1",
+            AlertSeverity::Error => "P2",
+            AlertSeverity::Warning => "P3",
+        };
+
+        serde_json::json!({
+            "message": self.summary,
+            "alias": self.dedup_key,
+            "priority": priority,
+            "source": ALERT_SOURCE,
+            "details": self.details_with_tenant(),
+        })
+    }
+
+    fn details_with_tenant(&self) -> HashMap<String, String> {
+        let mut details = self.details.clone();
+        if let Some(tenant_id) = self.tenant_id {
+            details.insert("tenant_id".to_string(), tenant_id.to_string());
+        }
+        details
+    }
+}
+
+#[derive(Clone)]
+pub struct Alerter {
+    client: ClientWithMiddleware,
+    routes: AlertRoutes,
+}
+
+impl Alerter {
+    pub fn new(routes: AlertRoutes) -> Self {
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(ALERT_RETRIES);
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        Alerter { client, routes }
+    }
+
+    pub fn get() -> &'static Self {
+        ALERTER.get_or_init(|| Alerter::new(crate::config::Config::get().alerting.routes.clone()))
+    }
+
+    /// Sends the alert to its routes. Failures are logged, as alerting must not break the billing pipeline
+    #[tracing::instrument(skip_all)]
+    pub async fn send(&self, alert: &Alert) {
+        let routes = self.routes.select(alert);
+
+        if routes.is_empty() {
+            log::debug!("No alerting route for alert: {}", alert.summary);
+            return;
+        }
+
+        for route in routes {
+            if let Err(e) = self.deliver(route, alert).await {
+                log::error!(
+                    "Failed to send alert '{}' to {:?}: {:?}",
+                    alert.summary,
+                    route.provider,
+                    e
+                );
+            }
+        }
+    }
+
+    async fn deliver(&self, route: &AlertRoute, alert: &Alert) -> Result<(), AlertingError> {
+        let request = match route.provider {
+            AlertProvider::PagerDuty => self
+                .client
+                .post(PAGER_DUTY_EVENTS_URL)
+                .json(&alert.pager_duty_payload(route.key.expose_secret())),
+            AlertProvider::Opsgenie => self
+                .client
+                .post(OPSGENIE_ALERTS_URL)
+                .header(
+                    "Authorization",
+                    format!("GenieKey {}", route.key.expose_secret()),
+                )
+                .json(&alert.opsgenie_payload()),
+        };
+
+        let response = request
+            .timeout(ALERT_REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                Report::new(AlertingError::DeliveryFailed).attach_printable(e.to_string())
+            })?;
+
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                Report::new(AlertingError::ProviderRejected(status.as_u16()))
+                    .attach_printable(body)
+            );
+        }
+
+        Ok(())
+    }
+}
+
+/// Escalates the failed run of a billing worker, leaving its result unchanged
+pub async fn escalate_worker_failure<T, E: error_stack::Context>(
+    worker: &'static str,
+    res: Result<T, E>,
+) -> Result<T, E> {
+    if let Err(err) = &res {
+        Alerter::get()
+            .send(&Alert::worker_failure(worker, err))
+            .await;
+    }
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn route(min_severity: AlertSeverity, tenant_id: Option<Uuid>) -> AlertRoute {
+        AlertRoute {
+            provider: AlertProvider::PagerDuty,
+            key: SecretString::new("key".to_string()),
+            min_severity,
+            tenant_id,
+        }
+    }
+
+    #[test]
+    fn test_parse_routes() {
+        let routes = AlertRoutes::from_str(
+            r#"[
+                {"provider": "pager_duty", "key": "abc", "min_severity": "critical"},
+                {"provider": "opsgenie", "key": "def", "min_severity": "warning", "tenant_id": "018c2c82-3df1-7e84-9e05-6e141d0e751a"}
+            ]"#,
+        )
+        .unwrap();
+
+        assert_eq!(routes.0.len(), 2);
+        assert_eq!(routes.0[0].provider, AlertProvider::PagerDuty);
+        assert_eq!(routes.0[0].tenant_id, None);
+        assert_eq!(routes.0[1].provider, AlertProvider::Opsgenie);
+        assert_eq!(routes.0[1].min_severity, AlertSeverity::Warning);
+
+        assert!(AlertRoutes::from_str("[]").unwrap().0.is_empty());
+    }
+
+    #[test]
+    fn test_select_routes() {
+        let tenant_id = Uuid::now_v7();
+
+        let routes = AlertRoutes(vec![
+            route(AlertSeverity::Critical, None),
+            route(AlertSeverity::Warning, None),
+            route(AlertSeverity::Critical, Some(tenant_id)),
+        ]);
+
+        // global routes, filtered by severity
+        let spike = Alert::issue_error_spike(Uuid::now_v7(), 12);
+        assert_eq!(routes.select(&spike).len(), 1);
+
+        let failure = Alert::worker_failure("issue", &"boom");
+        assert_eq!(routes.select(&failure).len(), 2);
+
+        // the tenant routes replace the global ones
+        let tenant_spike = Alert::issue_error_spike(tenant_id, 12);
+        assert!(routes.select(&tenant_spike).is_empty());
+
+        let tenant_stall = Alert::ingestion_stalled(tenant_id, &"unavailable");
+        let selected = routes.select(&tenant_stall);
+        assert_eq!(selected.len(), 1);
+        assert_eq!(selected[0].tenant_id, Some(tenant_id));
+    }
+}
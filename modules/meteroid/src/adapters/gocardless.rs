@@ -0,0 +1,551 @@
+/*
+    Collects the invoices by direct debit through GoCardless: a payment is created under the mandate of the
+    customer when the invoice is issued, and the payment events of the webhook update the invoice.
+
+    The mandates are kept in the billing config of the customers. A failed, cancelled or expired mandate is
+    removed, so that the next invoices are not issued under it, and a replaced mandate is swapped for the new one.
+*/
+use std::time::Duration;
+
+use axum::response::IntoResponse;
+use error_stack::{bail, Report, Result, ResultExt};
+use hyper::StatusCode;
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+use uuid::Uuid;
+
+use meteroid_store::domain::configs::ApiSecurity;
+use meteroid_store::domain::enums::InvoiceExternalStatusEnum;
+use meteroid_store::domain::{BillingConfig, Customer, ExternalInvoiceLinks, Invoice};
+use meteroid_store::repositories::{CustomersInterface, InvoiceInterface};
+use meteroid_store::Store;
+
+use super::types::{AdapterCommon, InvoicingAdapter, ParsedRequest, WebhookAdapter};
+use crate::errors;
+use crate::errors::InvoicingAdapterError;
+
+static GOCARDLESS: std::sync::OnceLock<GoCardless> = std::sync::OnceLock::new();
+
+// changes coming from GoCardless are not made by a user
+const GOCARDLESS_ACTOR: Uuid = Uuid::nil();
+
+const LIVE_API_URL: &str = "https://api.gocardless.com";
+const SANDBOX_API_URL: &str = "https://api-sandbox.gocardless.com";
+const SANDBOX_TOKEN_PREFIX: &str = "sandbox_";
+const API_VERSION: &str = "2015-07-06";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const REQUEST_RETRIES: u32 = 3;
+
+pub mod resource_type {
+    pub const MANDATES: &str = "mandates";
+    pub const PAYMENTS: &str = "payments";
+}
+
+pub mod action {
+    pub const BLOCKED: &str = "blocked";
+    pub const CANCELLED: &str = "cancelled";
+    pub const CONFIRMED: &str = "confirmed";
+    pub const EXPIRED: &str = "expired";
+    pub const FAILED: &str = "failed";
+    pub const PAID_OUT: &str = "paid_out";
+    pub const REPLACED: &str = "replaced";
+}
+
+#[derive(Debug, Deserialize)]
+struct PaymentResponse {
+    payments: Payment,
+}
+
+#[derive(Debug, Deserialize)]
+struct Payment {
+    id: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ErrorResponse {
+    error: ApiError,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiError {
+    #[serde(default)]
+    errors: Vec<ApiErrorDetail>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ApiErrorDetail {
+    reason: String,
+    #[serde(default)]
+    links: std::collections::HashMap<String, String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookBody {
+    events: Vec<WebhookEvent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct WebhookEvent {
+    id: String,
+    resource_type: String,
+    action: String,
+    #[serde(default)]
+    links: EventLinks,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct EventLinks {
+    payment: Option<String>,
+    mandate: Option<String>,
+    new_mandate: Option<String>,
+}
+
+/// What a mandate event changes on the customer collected under it
+#[derive(Debug, PartialEq, Eq)]
+enum MandateChange {
+    /// the mandate cannot be collected under anymore
+    Removed,
+    Replaced(String),
+}
+
+#[derive(Clone)]
+pub struct GoCardless {
+    client: ClientWithMiddleware,
+}
+
+impl AdapterCommon for GoCardless {
+    fn id(&self) -> &'static str {
+        "gocardless"
+    }
+}
+
+#[async_trait::async_trait]
+impl WebhookAdapter for GoCardless {
+    async fn verify_webhook(
+        &self,
+        request: &ParsedRequest,
+        security: &SecretString,
+    ) -> Result<bool, errors::AdapterWebhookError> {
+        let signature = request
+            .headers
+            .get("Webhook-Signature")
+            .ok_or(errors::AdapterWebhookError::SignatureNotFound)?
+            .to_str()
+            .map_err(|_| Report::new(errors::AdapterWebhookError::SignatureNotFound))?;
+
+        if !Self::is_valid_signature(security.expose_secret(), &request.raw_body, signature) {
+            bail!(errors::AdapterWebhookError::SignatureVerificationFailed);
+        }
+
+        Ok(true)
+    }
+
+    fn get_optimistic_webhook_response(&self) -> axum::response::Response {
+        (StatusCode::OK, "OK").into_response()
+    }
+
+    fn event_id(&self, request: &ParsedRequest) -> Option<String> {
+        // the events are sent in batches, a batch is delivered again with the same events
+        Self::parse_body(request)
+            .ok()?
+            .events
+            .first()
+            .map(|event| event.id.clone())
+    }
+
+    async fn process_webhook_event(
+        &self,
+        request: &ParsedRequest,
+        tenant_id: Uuid,
+        store: Store,
+    ) -> Result<bool, errors::AdapterWebhookError> {
+        let body = Self::parse_body(request)?;
+
+        for event in body.events {
+            match event.resource_type.as_str() {
+                resource_type::PAYMENTS => {
+                    self.process_payment_event(event, tenant_id, &store).await?
+                }
+                resource_type::MANDATES => {
+                    self.process_mandate_event(event, tenant_id, &store).await?
+                }
+                // the batches mix all the events of the account
+                _ => log::debug!(
+                    "Ignoring GoCardless event {} on {}",
+                    event.id,
+                    event.resource_type
+                ),
+            }
+        }
+
+        Ok(true)
+    }
+}
+
+#[async_trait::async_trait]
+impl InvoicingAdapter for GoCardless {
+    async fn send_invoice(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+        api_security: &ApiSecurity,
+    ) -> Result<String, InvoicingAdapterError> {
+        if invoice.amount_due <= 0 {
+            bail!(Report::new(InvoicingAdapterError::InvalidData)
+                .attach_printable("A direct debit payment requires an amount due"));
+        }
+
+        let mandate_id = Self::extract_mandate_id(customer)?;
+
+        // collected at the earliest charge date of the mandate
+        let response = self
+            .client
+            .post(format!("{}/payments", Self::api_url(api_security)))
+            .bearer_auth(api_security.api_key.as_str())
+            .header("GoCardless-Version", API_VERSION)
+            .header("Idempotency-Key", invoice.id.to_string())
+            .json(&Self::payment_payload(invoice, mandate_id))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .change_context(InvoicingAdapterError::GoCardlessError)?;
+
+        // the payment was created by a previous attempt
+        if response.status() == reqwest::StatusCode::CONFLICT {
+            let body = response.text().await.unwrap_or_default();
+
+            return Self::conflicting_resource_id(&body)
+                .ok_or(InvoicingAdapterError::GoCardlessError)
+                .attach_printable(body);
+        }
+
+        let created: PaymentResponse = Self::parse(response).await?;
+
+        Ok(created.payments.id)
+    }
+
+    async fn void_invoice(
+        &self,
+        invoice: &Invoice,
+        api_security: &ApiSecurity,
+    ) -> Result<(), InvoicingAdapterError> {
+        let payment_id = invoice
+            .external_invoice_id
+            .as_deref()
+            .ok_or(InvoicingAdapterError::InvalidData)
+            .attach_printable("The invoice was not issued to GoCardless")?;
+
+        // only a payment not yet submitted to the banks can be cancelled
+        let response = self
+            .client
+            .post(format!(
+                "{}/payments/{}/actions/cancel",
+                Self::api_url(api_security),
+                payment_id
+            ))
+            .bearer_auth(api_security.api_key.as_str())
+            .header("GoCardless-Version", API_VERSION)
+            .json(&serde_json::json!({ "data": {} }))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .change_context(InvoicingAdapterError::GoCardlessError)?;
+
+        let _: PaymentResponse = Self::parse(response).await?;
+
+        Ok(())
+    }
+
+    async fn finalize_hosted_invoice(
+        &self,
+        _external_invoice_id: &str,
+        _api_security: &ApiSecurity,
+    ) -> Result<ExternalInvoiceLinks, InvoicingAdapterError> {
+        // a direct debit is collected without a page to pay on
+        Ok(ExternalInvoiceLinks::default())
+    }
+}
+
+impl GoCardless {
+    pub fn new() -> Self {
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(REQUEST_RETRIES);
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        GoCardless { client }
+    }
+
+    pub fn get() -> &'static Self {
+        GOCARDLESS.get_or_init(GoCardless::new)
+    }
+
+    async fn process_payment_event(
+        &self,
+        event: WebhookEvent,
+        tenant_id: Uuid,
+        store: &Store,
+    ) -> Result<(), errors::AdapterWebhookError> {
+        let Some(external_status) = Self::external_status_to_service(&event.action) else {
+            return Ok(());
+        };
+
+        let payment_id = event
+            .links
+            .payment
+            .ok_or(errors::AdapterWebhookError::BodyDecodingFailed)?;
+
+        let invoice_id = store
+            .find_invoice_id_by_external_id(tenant_id, payment_id.clone())
+            .await
+            .change_context(errors::AdapterWebhookError::DatabaseError)?;
+
+        match invoice_id {
+            Some(invoice_id) => store
+                .update_invoice_external_status(invoice_id, tenant_id, external_status)
+                .await
+                .change_context(errors::AdapterWebhookError::DatabaseError),
+            None => {
+                log::info!(
+                    "Ignoring GoCardless payment {} of tenant {}, not created for an invoice",
+                    payment_id,
+                    tenant_id
+                );
+                Ok(())
+            }
+        }
+    }
+
+    async fn process_mandate_event(
+        &self,
+        event: WebhookEvent,
+        tenant_id: Uuid,
+        store: &Store,
+    ) -> Result<(), errors::AdapterWebhookError> {
+        let Some(change) = Self::mandate_change(&event.action, &event.links) else {
+            return Ok(());
+        };
+
+        let mandate_id = event
+            .links
+            .mandate
+            .ok_or(errors::AdapterWebhookError::BodyDecodingFailed)?;
+
+        let new_mandate_id = match change {
+            MandateChange::Removed => None,
+            MandateChange::Replaced(new_mandate_id) => Some(new_mandate_id),
+        };
+
+        let customer = store
+            .replace_gocardless_mandate(
+                GOCARDLESS_ACTOR,
+                tenant_id,
+                mandate_id.clone(),
+                new_mandate_id,
+            )
+            .await
+            .change_context(errors::AdapterWebhookError::DatabaseError)?;
+
+        if let Some(customer) = customer {
+            log::info!(
+                "GoCardless mandate {} of customer {} {}",
+                mandate_id,
+                customer.id,
+                event.action
+            );
+        }
+
+        Ok(())
+    }
+
+    fn external_status_to_service(action: &str) -> Option<InvoiceExternalStatusEnum> {
+        match action {
+            action::CONFIRMED | action::PAID_OUT => Some(InvoiceExternalStatusEnum::Paid),
+            action::FAILED => Some(InvoiceExternalStatusEnum::PaymentFailed),
+            action::CANCELLED => Some(InvoiceExternalStatusEnum::Void),
+            _ => None,
+        }
+    }
+
+    fn mandate_change(action: &str, links: &EventLinks) -> Option<MandateChange> {
+        match action {
+            action::FAILED | action::CANCELLED | action::EXPIRED | action::BLOCKED => {
+                Some(MandateChange::Removed)
+            }
+            action::REPLACED => Some(
+                links
+                    .new_mandate
+                    .clone()
+                    .map_or(MandateChange::Removed, MandateChange::Replaced),
+            ),
+            _ => None,
+        }
+    }
+
+    fn parse_body(request: &ParsedRequest) -> Result<WebhookBody, errors::AdapterWebhookError> {
+        serde_json::from_value(request.json_body.clone())
+            .change_context(errors::AdapterWebhookError::BodyDecodingFailed)
+    }
+
+    fn is_valid_signature(secret: &str, body: &[u8], signature: &str) -> bool {
+        let expected = hex::encode(hmac_sha256::HMAC::mac(body, secret.as_bytes()));
+
+        expected.len() == signature.len()
+            && expected
+                .bytes()
+                .zip(signature.bytes())
+                .fold(0, |acc, (a, b)| acc | (a ^ b))
+                == 0
+    }
+
+    fn extract_mandate_id(customer: &Customer) -> Result<&str, InvoicingAdapterError> {
+        match &customer.billing_config {
+            BillingConfig::GoCardless(gocardless) => gocardless
+                .mandate_id
+                .as_deref()
+                .ok_or(InvoicingAdapterError::InvalidData)
+                .attach_printable("The customer has no active GoCardless mandate"),
+            _ => bail!(InvoicingAdapterError::InvalidData),
+        }
+    }
+
+    fn api_url(api_security: &ApiSecurity) -> &'static str {
+        if api_security.api_key.starts_with(SANDBOX_TOKEN_PREFIX) {
+            SANDBOX_API_URL
+        } else {
+            LIVE_API_URL
+        }
+    }
+
+    fn payment_payload(invoice: &Invoice, mandate_id: &str) -> serde_json::Value {
+        serde_json::json!({
+            "payments": {
+                "amount": invoice.amount_due,
+                "currency": invoice.currency,
+                "description": format!("Invoice {}", invoice.invoice_number),
+                "metadata": {
+                    "meteroid_invoice_id": invoice.id.to_string(),
+                    "meteroid_tenant_id": invoice.tenant_id.to_string(),
+                },
+                "links": {
+                    "mandate": mandate_id,
+                },
+            }
+        })
+    }
+
+    fn conflicting_resource_id(body: &str) -> Option<String> {
+        serde_json::from_str::<ErrorResponse>(body)
+            .ok()?
+            .error
+            .errors
+            .into_iter()
+            .find(|e| e.reason == "idempotent_creation_conflict")
+            .and_then(|mut e| e.links.remove("conflicting_resource_id"))
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, InvoicingAdapterError> {
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!(Report::new(InvoicingAdapterError::GoCardlessError)
+                .attach_printable(format!("GoCardless responded {}: {}", status, body)));
+        }
+
+        response
+            .json()
+            .await
+            .change_context(InvoicingAdapterError::GoCardlessError)
+    }
+}
+
+impl Default for GoCardless {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature() {
+        let body = br#"{"events":[{"id":"EV123","resource_type":"payments","action":"confirmed","links":{"payment":"PM123"}}]}"#;
+        let signature = hex::encode(hmac_sha256::HMAC::mac(body, b"secret"));
+
+        assert!(GoCardless::is_valid_signature("secret", body, &signature));
+        assert!(!GoCardless::is_valid_signature("other", body, &signature));
+        assert!(!GoCardless::is_valid_signature("secret", b"{}", &signature));
+    }
+
+    #[test]
+    fn test_external_status() {
+        assert_eq!(
+            GoCardless::external_status_to_service(action::CONFIRMED),
+            Some(InvoiceExternalStatusEnum::Paid)
+        );
+        assert_eq!(
+            GoCardless::external_status_to_service(action::FAILED),
+            Some(InvoiceExternalStatusEnum::PaymentFailed)
+        );
+        assert_eq!(
+            GoCardless::external_status_to_service(action::CANCELLED),
+            Some(InvoiceExternalStatusEnum::Void)
+        );
+        assert_eq!(GoCardless::external_status_to_service("submitted"), None);
+    }
+
+    #[test]
+    fn test_mandate_change() {
+        let links = EventLinks {
+            payment: None,
+            mandate: Some("MD1".to_string()),
+            new_mandate: Some("MD2".to_string()),
+        };
+
+        assert_eq!(
+            GoCardless::mandate_change(action::REPLACED, &links),
+            Some(MandateChange::Replaced("MD2".to_string()))
+        );
+        assert_eq!(
+            GoCardless::mandate_change(action::EXPIRED, &links),
+            Some(MandateChange::Removed)
+        );
+        assert_eq!(GoCardless::mandate_change("active", &links), None);
+    }
+
+    #[test]
+    fn test_conflicting_resource_id() {
+        let body = r#"{"error":{"type":"invalid_state","code":409,"errors":[{"reason":"idempotent_creation_conflict","message":"A resource has already been created with this idempotency key","links":{"conflicting_resource_id":"PM123"}}]}}"#;
+
+        assert_eq!(
+            GoCardless::conflicting_resource_id(body),
+            Some("PM123".to_string())
+        );
+        assert_eq!(GoCardless::conflicting_resource_id("not json"), None);
+    }
+
+    #[test]
+    fn test_api_url() {
+        let api_security = ApiSecurity {
+            api_key: "sandbox_token".to_string(),
+            merchant_account: None,
+            live_url_prefix: None,
+        };
+
+        assert_eq!(GoCardless::api_url(&api_security), SANDBOX_API_URL);
+        assert_eq!(
+            GoCardless::api_url(&ApiSecurity {
+                api_key: "live_token".to_string(),
+                ..api_security
+            }),
+            LIVE_API_URL
+        );
+    }
+}
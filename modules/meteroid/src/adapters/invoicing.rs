@@ -0,0 +1,36 @@
+use meteroid_store::domain::enums::InvoicingProviderEnum;
+
+use crate::adapters::adyen::Adyen;
+use crate::adapters::gocardless::GoCardless;
+use crate::adapters::stripe::Stripe;
+use crate::adapters::types::InvoicingAdapter;
+
+static INVOICING_ADAPTERS: std::sync::OnceLock<InvoicingAdapters> = std::sync::OnceLock::new();
+
+/// The adapters of the invoicing providers
+#[derive(Clone)]
+pub struct InvoicingAdapters {
+    pub stripe: Stripe,
+    pub adyen: Adyen,
+    pub gocardless: GoCardless,
+}
+
+impl InvoicingAdapters {
+    pub fn get() -> &'static Self {
+        INVOICING_ADAPTERS.get_or_init(|| InvoicingAdapters {
+            stripe: Stripe::get().clone(),
+            adyen: Adyen::get().clone(),
+            gocardless: GoCardless::get().clone(),
+        })
+    }
+
+    /// None for the invoices that are not sent to a provider
+    pub fn for_provider(&self, provider: &InvoicingProviderEnum) -> Option<&dyn InvoicingAdapter> {
+        match provider {
+            InvoicingProviderEnum::Stripe => Some(&self.stripe),
+            InvoicingProviderEnum::Adyen => Some(&self.adyen),
+            InvoicingProviderEnum::GoCardless => Some(&self.gocardless),
+            InvoicingProviderEnum::Manual => None,
+        }
+    }
+}
@@ -1,2 +1,7 @@
+pub mod adyen;
+pub mod alerting;
+pub mod gocardless;
+pub mod invoicing;
+pub mod quickbooks;
 pub mod stripe;
 pub mod types;
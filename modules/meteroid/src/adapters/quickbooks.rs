@@ -0,0 +1,299 @@
+/*
+    Books the journal entries of the accounting exports in QuickBooks Online.
+
+    The tenant authorizes its company through the OAuth flow of the Meteroid app (QUICKBOOKS_CLIENT_ID),
+    the export keeps its refresh token, which is rotated on each export.
+*/
+use std::time::Duration;
+
+use envconfig::Envconfig;
+use error_stack::{bail, Report, Result};
+use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
+use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
+use rust_decimal::prelude::ToPrimitive;
+use secrecy::{ExposeSecret, SecretString};
+use serde::Deserialize;
+
+use meteroid_store::domain::accounting_exports::{
+    JournalEntry, JournalEntryKind, QuickBooksCredentials,
+};
+use meteroid_store::utils::decimals::ToUnit;
+
+use crate::errors::AccountingExportError;
+
+static QUICKBOOKS: std::sync::OnceLock<QuickBooks> = std::sync::OnceLock::new();
+
+const TOKEN_URL: &str = "https://oauth.platform.intuit.com/oauth2/v1/tokens/bearer";
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+const REQUEST_RETRIES: u32 = 3;
+
+// limit of the DocNumber of a journal entry
+const MAX_DOC_NUMBER_LENGTH: usize = 21;
+
+#[derive(Envconfig, Debug, Clone)]
+pub struct QuickBooksConfig {
+    #[envconfig(from = "QUICKBOOKS_CLIENT_ID")]
+    pub client_id: Option<String>,
+
+    #[envconfig(from = "QUICKBOOKS_CLIENT_SECRET")]
+    pub client_secret: Option<SecretString>,
+
+    /// https://sandbox-quickbooks.api.intuit.com for the sandbox companies
+    #[envconfig(
+        from = "QUICKBOOKS_API_URL",
+        default = "https://quickbooks.api.intuit.com"
+    )]
+    pub api_url: String,
+}
+
+#[derive(Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    refresh_token: String,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct JournalEntryResponse {
+    journal_entry: CreatedJournalEntry,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "PascalCase")]
+struct CreatedJournalEntry {
+    id: String,
+}
+
+/// An access token, and the refresh token replacing the one it was exchanged for
+pub struct QuickBooksSession {
+    pub access_token: SecretString,
+    pub credentials: QuickBooksCredentials,
+}
+
+#[derive(Clone)]
+pub struct QuickBooks {
+    client: ClientWithMiddleware,
+    config: QuickBooksConfig,
+}
+
+impl QuickBooks {
+    pub fn new(config: QuickBooksConfig) -> Self {
+        let retry_policy = ExponentialBackoff::builder().build_with_max_retries(REQUEST_RETRIES);
+        let client = ClientBuilder::new(reqwest::Client::new())
+            .with(RetryTransientMiddleware::new_with_policy(retry_policy))
+            .build();
+
+        QuickBooks { client, config }
+    }
+
+    pub fn get() -> &'static Self {
+        QUICKBOOKS.get_or_init(|| QuickBooks::new(crate::config::Config::get().quickbooks.clone()))
+    }
+
+    /// Exchanges the refresh token for an access token. The previous refresh token stops working once the
+    /// new one is used, so the returned credentials must be kept
+    pub async fn open_session(
+        &self,
+        credentials: &QuickBooksCredentials,
+    ) -> Result<QuickBooksSession, AccountingExportError> {
+        let (client_id, client_secret) = match (&self.config.client_id, &self.config.client_secret)
+        {
+            (Some(client_id), Some(client_secret)) => (client_id, client_secret),
+            _ => bail!(
+                Report::new(AccountingExportError::NotConfigured).attach_printable(
+                    "QUICKBOOKS_CLIENT_ID and QUICKBOOKS_CLIENT_SECRET are required"
+                )
+            ),
+        };
+
+        let response = self
+            .client
+            .post(TOKEN_URL)
+            .basic_auth(client_id, Some(client_secret.expose_secret()))
+            .header("Accept", "application/json")
+            .form(&[
+                ("grant_type", "refresh_token"),
+                ("refresh_token", credentials.refresh_token.as_str()),
+            ])
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                Report::new(AccountingExportError::RequestFailed).attach_printable(e.to_string())
+            })?;
+
+        let tokens: TokenResponse = Self::parse(response).await?;
+
+        Ok(QuickBooksSession {
+            access_token: SecretString::new(tokens.access_token),
+            credentials: QuickBooksCredentials {
+                realm_id: credentials.realm_id.clone(),
+                refresh_token: tokens.refresh_token,
+            },
+        })
+    }
+
+    /// Creates the entry, once: a retried request is recognized by its id. Returns the id of the entry
+    pub async fn create_journal_entry(
+        &self,
+        session: &QuickBooksSession,
+        entry: &JournalEntry,
+    ) -> Result<String, AccountingExportError> {
+        let url = format!(
+            "{}/v3/company/{}/journalentry",
+            self.config.api_url.trim_end_matches('/'),
+            session.credentials.realm_id
+        );
+
+        let response = self
+            .client
+            .post(url)
+            .query(&[
+                ("requestid", request_id(entry)),
+                ("minorversion", "70".into()),
+            ])
+            .bearer_auth(session.access_token.expose_secret())
+            .header("Accept", "application/json")
+            .json(&journal_entry_payload(entry))
+            .timeout(REQUEST_TIMEOUT)
+            .send()
+            .await
+            .map_err(|e| {
+                Report::new(AccountingExportError::RequestFailed).attach_printable(e.to_string())
+            })?;
+
+        let created: JournalEntryResponse = Self::parse(response).await?;
+
+        Ok(created.journal_entry.id)
+    }
+
+    async fn parse<T: serde::de::DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, AccountingExportError> {
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            bail!(
+                Report::new(AccountingExportError::ProviderRejected(status.as_u16()))
+                    .attach_printable(body)
+            );
+        }
+
+        response.json().await.map_err(|e| {
+            Report::new(AccountingExportError::RequestFailed).attach_printable(e.to_string())
+        })
+    }
+}
+
+/// Identifies the entry across exports (at most 50 characters)
+fn request_id(entry: &JournalEntry) -> String {
+    let kind = match entry.kind {
+        JournalEntryKind::Invoice => "i",
+        JournalEntryKind::Recognition => "r",
+        JournalEntryKind::Void => "v",
+    };
+
+    format!(
+        "{}{}{}",
+        entry.invoice_id.simple(),
+        kind,
+        entry.date.format("%Y%m%d")
+    )
+}
+
+fn journal_entry_payload(entry: &JournalEntry) -> serde_json::Value {
+    let precision = rusty_money::iso::find(&entry.currency)
+        .map(|currency| currency.exponent)
+        .unwrap_or(2);
+
+    let lines: Vec<serde_json::Value> = entry
+        .lines
+        .iter()
+        .map(|line| {
+            let (posting_type, amount) = if line.debit > 0 {
+                ("Debit", line.debit)
+            } else {
+                ("Credit", line.credit)
+            };
+
+            serde_json::json!({
+                "Description": line.description,
+                "Amount": amount.to_unit(precision as u8).to_f64(),
+                "DetailType": "JournalEntryLineDetail",
+                "JournalEntryLineDetail": {
+                    "PostingType": posting_type,
+                    "AccountRef": { "value": line.account },
+                },
+            })
+        })
+        .collect();
+
+    serde_json::json!({
+        "TxnDate": entry.date.format("%Y-%m-%d").to_string(),
+        "DocNumber": entry.reference.chars().take(MAX_DOC_NUMBER_LENGTH).collect::<String>(),
+        "PrivateNote": entry.description,
+        "CurrencyRef": { "value": entry.currency },
+        "Line": lines,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+    use meteroid_store::domain::accounting_exports::JournalLine;
+    use uuid::Uuid;
+
+    fn entry() -> JournalEntry {
+        JournalEntry {
+            kind: JournalEntryKind::Recognition,
+            date: NaiveDate::from_ymd_opt(2024, 11, 30).unwrap(),
+            reference: "INV-2024-000000000042".to_string(),
+            description: "Revenue recognized on invoice INV-2024-000000000042".to_string(),
+            currency: "EUR".to_string(),
+            invoice_id: Uuid::nil(),
+            lines: vec![
+                JournalLine {
+                    account: "2400".to_string(),
+                    debit: 12345,
+                    credit: 0,
+                    description: "Revenue recognized".to_string(),
+                },
+                JournalLine {
+                    account: "4000".to_string(),
+                    debit: 0,
+                    credit: 12345,
+                    description: "Seats".to_string(),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_journal_entry_payload() {
+        let payload = journal_entry_payload(&entry());
+
+        assert_eq!(payload["TxnDate"], "2024-11-30");
+        assert_eq!(payload["DocNumber"], "INV-2024-00000000004");
+        assert_eq!(payload["CurrencyRef"]["value"], "EUR");
+
+        let lines = payload["Line"].as_array().unwrap();
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0]["Amount"], 123.45);
+        assert_eq!(lines[0]["JournalEntryLineDetail"]["PostingType"], "Debit");
+        assert_eq!(lines[1]["JournalEntryLineDetail"]["PostingType"], "Credit");
+        assert_eq!(
+            lines[1]["JournalEntryLineDetail"]["AccountRef"]["value"],
+            "4000"
+        );
+    }
+
+    #[test]
+    fn test_request_id() {
+        let id = request_id(&entry());
+
+        assert_eq!(id, "00000000000000000000000000000000r20241130");
+        assert!(id.len() <= 50);
+    }
+}
@@ -4,8 +4,10 @@ use error_stack::Result;
 use hyper::StatusCode;
 use secrecy::ExposeSecret;
 use secrecy::SecretString;
+use stripe_client::customer::{ListCustomers, METEROID_CUSTOMER_ID_METADATA};
 use stripe_client::invoice::{CollectionMethod, CreateInvoice, MeteroidMetadata};
-use stripe_client::invoice::{CreateInvoiceItem, Invoice, Period};
+use stripe_client::invoice::{CreateInvoiceItem, Invoice, MeteroidLineMetadata, Period};
+use stripe_client::tax_rate::{CreateTaxRate, ListTaxRates, TaxRate, METEROID_TAX_RATE_METADATA};
 use stripe_client::webhook::Event;
 use stripe_client::webhook::EventObject;
 
@@ -18,16 +20,40 @@ use axum::response::IntoResponse;
 use common_domain::StripeSecret;
 use error_stack::ResultExt;
 use meteroid_grpc::meteroid::api::customers::v1::customer_billing_config;
+use meteroid_store::domain::configs::ApiSecurity;
 use meteroid_store::domain::enums::InvoiceExternalStatusEnum;
-use meteroid_store::domain::{BillingConfig, Customer, LineItem, Stripe as BillingConfigStripe};
-use meteroid_store::repositories::InvoiceInterface;
+use meteroid_store::domain::{
+    Address, BillingConfig, Customer, ExternalInvoiceLinks, LineItem,
+    Stripe as BillingConfigStripe, StripeCustomerSync, StripeCustomerSyncOutcome,
+};
+use meteroid_store::repositories::{CustomersInterface, InvoiceInterface};
 use meteroid_store::{domain, Store};
+use std::collections::HashMap;
 use stripe_client::webhook::event_type;
 use stripe_client::webhook::StripeWebhook;
 use uuid::Uuid;
 
 static STRIPE: std::sync::OnceLock<Stripe> = std::sync::OnceLock::new();
 
+// changes coming from Stripe are not made by a user
+const STRIPE_ACTOR: Uuid = Uuid::nil();
+
+const CUSTOMERS_PAGE_SIZE: u64 = 100;
+
+const TAX_RATES_PAGE_SIZE: u64 = 100;
+
+const TAX_RATE_DISPLAY_NAME: &str = "Tax";
+
+/// Result of a full customer sync from Stripe
+#[derive(Debug, Default)]
+pub struct StripeCustomersSyncReport {
+    pub created: u32,
+    pub linked: u32,
+    pub updated: u32,
+    /// customers that could not be matched unambiguously, or failed to sync
+    pub failed: u32,
+}
+
 #[derive(Debug, Clone)]
 pub struct Stripe {
     pub client: stripe_client::client::StripeClient,
@@ -74,9 +100,18 @@ impl WebhookAdapter for Stripe {
         (StatusCode::OK, "OK").into_response()
     }
 
+    fn event_id(&self, request: &ParsedRequest) -> Option<String> {
+        request
+            .json_body
+            .get("id")
+            .and_then(|id| id.as_str())
+            .map(String::from)
+    }
+
     async fn process_webhook_event(
         &self,
         request: &ParsedRequest,
+        tenant_id: Uuid,
         store: Store,
     ) -> Result<bool, errors::AdapterWebhookError> {
         let parsed = StripeWebhook::parse_event(request.json_body.to_string().as_str())
@@ -88,6 +123,10 @@ impl WebhookAdapter for Stripe {
             EventObject::Invoice(invoice) => {
                 self.process_invoice_events(parsed, invoice, store).await
             }
+            EventObject::Customer(customer) => {
+                self.process_customer_events(parsed, customer, tenant_id, store)
+                    .await
+            }
         }?;
 
         Ok(true)
@@ -100,15 +139,20 @@ impl InvoicingAdapter for Stripe {
         &self,
         invoice: &domain::Invoice,
         customer: &Customer,
-        api_key: SecretString,
-    ) -> Result<(), InvoicingAdapterError> {
-        let api_key = &StripeSecret(api_key);
+        api_security: &ApiSecurity,
+    ) -> Result<String, InvoicingAdapterError> {
+        let api_key = &Self::api_key(api_security);
 
         let stripe_customer = Self::extract_stripe_customer_id(customer)?;
         let collection_method = Self::extract_stripe_collection_method(customer)?;
+        let tax_rate_id = self.resolve_tax_rate(invoice.tax_rate, api_key).await?;
 
-        let create_invoice =
-            Self::db_invoice_to_external(invoice, &stripe_customer, collection_method);
+        let create_invoice = Self::db_invoice_to_external(
+            invoice,
+            &stripe_customer,
+            collection_method,
+            tax_rate_id.as_deref(),
+        );
 
         let created_stripe_invoice = self
             .client
@@ -128,8 +172,41 @@ impl InvoicingAdapter for Stripe {
                 .change_context(InvoicingAdapterError::StripeError)?;
         }
 
+        Ok(created_stripe_invoice.id)
+    }
+
+    async fn void_invoice(
+        &self,
+        invoice: &domain::Invoice,
+        api_security: &ApiSecurity,
+    ) -> Result<(), InvoicingAdapterError> {
+        let stripe_invoice_id = invoice
+            .external_invoice_id
+            .as_deref()
+            .ok_or(InvoicingAdapterError::InvalidData)
+            .attach_printable("The invoice was not issued to Stripe")?;
+
+        self.client
+            .void_invoice(stripe_invoice_id, &Self::api_key(api_security))
+            .await
+            .change_context(InvoicingAdapterError::StripeError)?;
+
         Ok(())
     }
+
+    async fn finalize_hosted_invoice(
+        &self,
+        external_invoice_id: &str,
+        api_security: &ApiSecurity,
+    ) -> Result<ExternalInvoiceLinks, InvoicingAdapterError> {
+        let finalized = self
+            .client
+            .finalize_invoice(external_invoice_id, &Self::api_key(api_security))
+            .await
+            .change_context(InvoicingAdapterError::StripeError)?;
+
+        Ok(Self::external_links(&finalized))
+    }
 }
 
 impl Stripe {
@@ -139,6 +216,10 @@ impl Stripe {
         })
     }
 
+    fn api_key(api_security: &ApiSecurity) -> StripeSecret {
+        StripeSecret(SecretString::new(api_security.api_key.clone()))
+    }
+
     fn external_status_to_service(&self, event_type: String) -> Option<InvoiceExternalStatusEnum> {
         match event_type.as_str() {
             event_type::INVOICE_CREATED => Some(InvoiceExternalStatusEnum::Draft),
@@ -179,13 +260,204 @@ impl Stripe {
             .await
             .change_context(errors::AdapterWebhookError::DatabaseError)?;
 
+        // set once the invoice is finalized at Stripe, including by the tenant from its dashboard
+        store
+            .update_invoice_external_links(invoice_id, tenant_id, Self::external_links(&invoice))
+            .await
+            .change_context(errors::AdapterWebhookError::DatabaseError)?;
+
         Ok(true)
     }
 
+    async fn process_customer_events(
+        &self,
+        parsed: Event,
+        customer: stripe_client::customer::Customer,
+        tenant_id: Uuid,
+        store: Store,
+    ) -> Result<bool, errors::AdapterWebhookError> {
+        match parsed.event_type.as_str() {
+            event_type::CUSTOMER_CREATED | event_type::CUSTOMER_UPDATED => {
+                store
+                    .sync_stripe_customer(STRIPE_ACTOR, tenant_id, Self::customer_to_sync(customer))
+                    .await
+                    .change_context(errors::AdapterWebhookError::DatabaseError)?;
+            }
+            event_type::CUSTOMER_DELETED => {
+                store
+                    .unlink_stripe_customer(STRIPE_ACTOR, tenant_id, customer.id)
+                    .await
+                    .change_context(errors::AdapterWebhookError::DatabaseError)?;
+            }
+            _ => bail!(errors::AdapterWebhookError::EventTypeNotSupported(
+                parsed.event_type
+            )),
+        }
+
+        Ok(true)
+    }
+
+    /// Imports all the Stripe customers of the tenant, linking them to the existing customers when they match
+    pub async fn sync_customers(
+        &self,
+        store: &Store,
+        tenant_id: Uuid,
+        actor: Uuid,
+        api_key: SecretString,
+    ) -> Result<StripeCustomersSyncReport, InvoicingAdapterError> {
+        let api_key = &StripeSecret(api_key);
+        let mut report = StripeCustomersSyncReport::default();
+        let mut starting_after: Option<String> = None;
+
+        loop {
+            let page = self
+                .client
+                .list_customers(
+                    ListCustomers {
+                        limit: Some(CUSTOMERS_PAGE_SIZE),
+                        starting_after: starting_after.as_deref(),
+                    },
+                    api_key,
+                )
+                .await
+                .change_context(InvoicingAdapterError::StripeError)?;
+
+            starting_after = page.data.last().map(|c| c.id.clone());
+
+            for customer in page.data {
+                let stripe_customer_id = customer.id.clone();
+
+                match store
+                    .sync_stripe_customer(actor, tenant_id, Self::customer_to_sync(customer))
+                    .await
+                {
+                    Ok((_, StripeCustomerSyncOutcome::Created)) => report.created += 1,
+                    Ok((_, StripeCustomerSyncOutcome::Linked)) => report.linked += 1,
+                    Ok((_, StripeCustomerSyncOutcome::Updated)) => report.updated += 1,
+                    Err(err) => {
+                        log::warn!(
+                            "Failed to sync Stripe customer {}: {:?}",
+                            stripe_customer_id,
+                            err
+                        );
+                        report.failed += 1;
+                    }
+                }
+            }
+
+            if !page.has_more || starting_after.is_none() {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// The Stripe tax rate matching the tax rate of the invoice, created on first use. None when untaxed
+    async fn resolve_tax_rate(
+        &self,
+        tax_rate: i32,
+        api_key: &StripeSecret,
+    ) -> Result<Option<String>, InvoicingAdapterError> {
+        if tax_rate <= 0 {
+            return Ok(None);
+        }
+
+        let mut starting_after: Option<String> = None;
+
+        loop {
+            let page = self
+                .client
+                .list_tax_rates(
+                    ListTaxRates {
+                        active: Some(true),
+                        limit: Some(TAX_RATES_PAGE_SIZE),
+                        starting_after: starting_after.as_deref(),
+                    },
+                    api_key,
+                )
+                .await
+                .change_context(InvoicingAdapterError::StripeError)?;
+
+            starting_after = page.data.last().map(|t| t.id.clone());
+
+            if let Some(existing) = page
+                .data
+                .into_iter()
+                .find(|t| Self::is_meteroid_tax_rate(t, tax_rate))
+            {
+                return Ok(Some(existing.id));
+            }
+
+            if !page.has_more || starting_after.is_none() {
+                break;
+            }
+        }
+
+        let created = self
+            .client
+            .create_tax_rate(
+                CreateTaxRate {
+                    display_name: TAX_RATE_DISPLAY_NAME,
+                    percentage: tax_rate as f64,
+                    inclusive: false,
+                    metadata: HashMap::from([(METEROID_TAX_RATE_METADATA, tax_rate.to_string())]),
+                },
+                api_key,
+                format!("meteroid-tax-rate-{}", tax_rate),
+            )
+            .await
+            .change_context(InvoicingAdapterError::StripeError)?;
+
+        Ok(Some(created.id))
+    }
+
+    /// Only the tax rates created by meteroid are reused, the ones of the tenant may carry another jurisdiction or name
+    fn is_meteroid_tax_rate(stripe_tax_rate: &TaxRate, tax_rate: i32) -> bool {
+        stripe_tax_rate.active
+            && !stripe_tax_rate.inclusive
+            && stripe_tax_rate.percentage == tax_rate as f64
+            && stripe_tax_rate
+                .metadata
+                .get(METEROID_TAX_RATE_METADATA)
+                .is_some_and(|rate| *rate == tax_rate.to_string())
+    }
+
+    fn external_links(stripe_invoice: &Invoice) -> ExternalInvoiceLinks {
+        ExternalInvoiceLinks {
+            hosted_invoice_url: stripe_invoice.hosted_invoice_url.clone(),
+            external_pdf_url: stripe_invoice.invoice_pdf.clone(),
+        }
+    }
+
+    fn customer_to_sync(customer: stripe_client::customer::Customer) -> StripeCustomerSync {
+        StripeCustomerSync {
+            meteroid_customer_id: customer
+                .metadata
+                .get(METEROID_CUSTOMER_ID_METADATA)
+                .and_then(|id| Uuid::parse_str(id).ok()),
+            stripe_customer_id: customer.id,
+            name: customer.name,
+            email: customer.email,
+            phone: customer.phone,
+            currency: customer.currency,
+            billing_address: customer.address.map(|address| Address {
+                line1: address.line1,
+                line2: address.line2,
+                city: address.city,
+                country: address.country,
+                state: address.state,
+                zip_code: address.postal_code,
+            }),
+            default_payment_method_id: customer.invoice_settings.default_payment_method,
+        }
+    }
+
     fn db_invoice_to_external<'a>(
         invoice: &'a domain::Invoice,
         stripe_customer: &'a String,
         collection_method: CollectionMethod,
+        tax_rate_id: Option<&'a str>,
     ) -> CreateInvoice<'a> {
         CreateInvoice {
             auto_advance: Some(false),
@@ -202,6 +474,7 @@ impl Stripe {
                 _ => None,
             },
             customer: Some(stripe_customer.as_ref()),
+            default_tax_rates: tax_rate_id.into_iter().collect(),
             metadata: MeteroidMetadata {
                 meteroid_invoice_id: invoice.id.to_string(),
                 meteroid_customer_id: invoice.customer_id.to_string(),
@@ -225,9 +498,22 @@ impl Stripe {
                 start: Some(Self::chrono_date_to_timestamp(line.start_date)?),
                 end: Some(Self::chrono_date_to_timestamp(line.end_date)?),
             }),
+            metadata: Self::line_metadata(invoice.id, line),
         })
     }
 
+    fn line_metadata(invoice_id: Uuid, line: &LineItem) -> MeteroidLineMetadata {
+        MeteroidLineMetadata {
+            meteroid_invoice_id: invoice_id.to_string(),
+            meteroid_line_id: line.local_id.clone(),
+            meteroid_period_start: line.start_date.to_string(),
+            meteroid_period_end: line.end_date.to_string(),
+            meteroid_price_component_id: line.price_component_id.map(|id| id.to_string()),
+            meteroid_quantity: line.quantity.map(|q| q.normalize().to_string()),
+            meteroid_unit_price: line.unit_price.map(|p| p.normalize().to_string()),
+        }
+    }
+
     fn chrono_date_to_timestamp(date: chrono::NaiveDate) -> Result<i64, InvoicingAdapterError> {
         let date_time = date
             .and_hms_opt(0, 0, 0)
@@ -265,7 +551,131 @@ impl Stripe {
     ) -> Result<&BillingConfigStripe, InvoicingAdapterError> {
         match &customer.billing_config {
             BillingConfig::Stripe(s) => Ok(s),
-            BillingConfig::Manual => bail!(InvoicingAdapterError::InvalidData),
+            _ => bail!(InvoicingAdapterError::InvalidData),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+    use std::str::FromStr;
+
+    #[test]
+    fn test_line_metadata() {
+        let invoice_id = Uuid::now_v7();
+        let price_component_id = Uuid::now_v7();
+
+        let line = LineItem {
+            local_id: "line_1".to_string(),
+            name: "Seats".to_string(),
+            total: 5000,
+            subtotal: 5000,
+            quantity: Some(Decimal::from_str("10.000").unwrap()),
+            unit_price: Some(Decimal::from_str("5.00000000").unwrap()),
+            start_date: chrono::NaiveDate::from_ymd_opt(2024, 10, 1).unwrap(),
+            end_date: chrono::NaiveDate::from_ymd_opt(2024, 10, 31).unwrap(),
+            sub_lines: vec![],
+            is_prorated: false,
+            price_component_id: Some(price_component_id),
+            product_id: None,
+            metric_id: None,
+            description: None,
+            quantity_display_precision: None,
+        };
+
+        let metadata = Stripe::line_metadata(invoice_id, &line);
+
+        assert_eq!(metadata.meteroid_invoice_id, invoice_id.to_string());
+        assert_eq!(metadata.meteroid_line_id, "line_1");
+        assert_eq!(metadata.meteroid_period_start, "2024-10-01");
+        assert_eq!(metadata.meteroid_period_end, "2024-10-31");
+        assert_eq!(
+            metadata.meteroid_price_component_id,
+            Some(price_component_id.to_string())
+        );
+        assert_eq!(metadata.meteroid_quantity.as_deref(), Some("10"));
+        assert_eq!(metadata.meteroid_unit_price.as_deref(), Some("5"));
+    }
+
+    #[test]
+    fn test_is_meteroid_tax_rate() {
+        let tax_rate = TaxRate {
+            id: "txr_1".to_string(),
+            display_name: TAX_RATE_DISPLAY_NAME.to_string(),
+            percentage: 20.0,
+            inclusive: false,
+            active: true,
+            metadata: HashMap::from([(METEROID_TAX_RATE_METADATA.to_string(), "20".to_string())]),
+        };
+
+        assert!(Stripe::is_meteroid_tax_rate(&tax_rate, 20));
+        assert!(!Stripe::is_meteroid_tax_rate(&tax_rate, 10));
+
+        let inclusive = TaxRate {
+            inclusive: true,
+            ..tax_rate.clone()
+        };
+        assert!(!Stripe::is_meteroid_tax_rate(&inclusive, 20));
+
+        // created by the tenant
+        let unmarked = TaxRate {
+            metadata: HashMap::new(),
+            ..tax_rate
+        };
+        assert!(!Stripe::is_meteroid_tax_rate(&unmarked, 20));
+    }
+
+    #[test]
+    fn test_customer_to_sync() {
+        let meteroid_customer_id = Uuid::now_v7();
+
+        let customer = stripe_client::customer::Customer {
+            id: "cus_1".to_string(),
+            email: Some("billing@acme.com".to_string()),
+            name: Some("Acme".to_string()),
+            phone: None,
+            currency: Some("eur".to_string()),
+            address: Some(stripe_client::customer::Address {
+                city: Some("Paris".to_string()),
+                country: Some("FR".to_string()),
+                line1: Some("1 rue de Rivoli".to_string()),
+                line2: None,
+                postal_code: Some("75001".to_string()),
+                state: None,
+            }),
+            metadata: HashMap::from([(
+                METEROID_CUSTOMER_ID_METADATA.to_string(),
+                meteroid_customer_id.to_string(),
+            )]),
+            invoice_settings: stripe_client::customer::InvoiceSettings {
+                default_payment_method: Some("pm_1".to_string()),
+            },
+            deleted: false,
+        };
+
+        let sync = Stripe::customer_to_sync(customer.clone());
+
+        assert_eq!(sync.stripe_customer_id, "cus_1");
+        assert_eq!(sync.meteroid_customer_id, Some(meteroid_customer_id));
+        assert_eq!(sync.email.as_deref(), Some("billing@acme.com"));
+        assert_eq!(sync.currency.as_deref(), Some("eur"));
+        assert_eq!(sync.default_payment_method_id.as_deref(), Some("pm_1"));
+
+        let address = sync.billing_address.unwrap();
+        assert_eq!(address.zip_code.as_deref(), Some("75001"));
+        assert_eq!(address.city.as_deref(), Some("Paris"));
+        assert_eq!(address.country.as_deref(), Some("FR"));
+
+        // a metadata that is not a customer id is ignored, the customer is matched by email
+        let sync = Stripe::customer_to_sync(stripe_client::customer::Customer {
+            metadata: HashMap::from([(
+                METEROID_CUSTOMER_ID_METADATA.to_string(),
+                "acme".to_string(),
+            )]),
+            ..customer
+        });
+        assert_eq!(sync.meteroid_customer_id, None);
+    }
+}
@@ -3,7 +3,8 @@ use error_stack::Result;
 use secrecy::SecretString;
 use std::fmt::Debug;
 
-use meteroid_store::domain::{Customer, Invoice};
+use meteroid_store::domain::configs::ApiSecurity;
+use meteroid_store::domain::{Customer, ExternalInvoiceLinks, Invoice};
 use meteroid_store::Store;
 
 pub enum IncomingWebhookEvent {
@@ -33,21 +34,40 @@ pub trait WebhookAdapter: AdapterCommon + Sync {
 
     fn get_optimistic_webhook_response(&self) -> axum::response::Response;
 
+    /// Id of the event at the provider, to drop the replays and the duplicate deliveries
+    fn event_id(&self, request: &ParsedRequest) -> Option<String>;
+
     async fn process_webhook_event(
         &self,
         request: &ParsedRequest,
+        tenant_id: uuid::Uuid,
         store: Store,
     ) -> Result<bool, errors::AdapterWebhookError>;
 }
 
+/// Issues the invoices at a provider, with the api credentials of the tenant
 #[axum::async_trait]
 pub trait InvoicingAdapter: AdapterCommon + Sync {
+    /// Returns the id of the invoice at the provider
     async fn send_invoice(
         &self,
         invoice: &Invoice,
         customer: &Customer,
-        api_key: SecretString,
+        api_security: &ApiSecurity,
+    ) -> Result<String, errors::InvoicingAdapterError>;
+
+    async fn void_invoice(
+        &self,
+        invoice: &Invoice,
+        api_security: &ApiSecurity,
     ) -> Result<(), errors::InvoicingAdapterError>;
+
+    /// Finalizes the invoice sent to the provider, and returns the page where the customer can pay it and its PDF
+    async fn finalize_hosted_invoice(
+        &self,
+        external_invoice_id: &str,
+        api_security: &ApiSecurity,
+    ) -> Result<ExternalInvoiceLinks, errors::InvoicingAdapterError>;
 }
 
 pub trait Adapter: Send + Debug + WebhookAdapter {}
@@ -0,0 +1,53 @@
+use error_stack::Report;
+use std::error::Error;
+use thiserror::Error;
+
+use common_grpc_error_as_tonic_macros_impl::ErrorAsTonic;
+use meteroid_store::errors::StoreError;
+
+use crate::errors::AccountingExportError;
+
+#[derive(Debug, Error, ErrorAsTonic)]
+pub enum AccountingExportApiError {
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
+    #[error("Not found: {0}")]
+    #[code(NotFound)]
+    NotFound(String),
+
+    #[error("Failed precondition: {0}")]
+    #[code(FailedPrecondition)]
+    FailedPrecondition(String),
+
+    #[error("Store error: {0}")]
+    #[code(Internal)]
+    StoreError(String, #[source] Box<dyn Error>),
+
+    #[error("Export error: {0}")]
+    #[code(Internal)]
+    ExportError(String, #[source] Box<dyn Error>),
+}
+
+impl From<Report<StoreError>> for AccountingExportApiError {
+    fn from(value: Report<StoreError>) -> Self {
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            StoreError::ValueNotFound(msg) => Self::NotFound(msg.clone()),
+            _ => Self::StoreError(
+                "Error in accounting exports service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
+    }
+}
+
+impl From<Report<AccountingExportError>> for AccountingExportApiError {
+    fn from(value: Report<AccountingExportError>) -> Self {
+        Self::ExportError(
+            "Error while exporting the journal entries".to_string(),
+            Box::new(value.into_error()),
+        )
+    }
+}
@@ -0,0 +1,133 @@
+pub mod enums {
+    use meteroid_grpc::meteroid::api::accountingexports::v1 as server;
+    use meteroid_store::domain::enums::{
+        AccountingConnectorEnum, AccountingExportRunStatusEnum, AccountingExportScheduleEnum,
+    };
+
+    pub fn connector_to_server(connector: AccountingConnectorEnum) -> server::AccountingConnector {
+        match connector {
+            AccountingConnectorEnum::Csv => server::AccountingConnector::Csv,
+            AccountingConnectorEnum::QuickbooksOnline => {
+                server::AccountingConnector::QuickbooksOnline
+            }
+        }
+    }
+
+    pub fn connector_server_to_domain(
+        connector: server::AccountingConnector,
+    ) -> AccountingConnectorEnum {
+        match connector {
+            server::AccountingConnector::Csv => AccountingConnectorEnum::Csv,
+            server::AccountingConnector::QuickbooksOnline => {
+                AccountingConnectorEnum::QuickbooksOnline
+            }
+        }
+    }
+
+    pub fn schedule_to_server(
+        schedule: AccountingExportScheduleEnum,
+    ) -> server::AccountingExportSchedule {
+        match schedule {
+            AccountingExportScheduleEnum::Daily => server::AccountingExportSchedule::Daily,
+            AccountingExportScheduleEnum::Weekly => server::AccountingExportSchedule::Weekly,
+            AccountingExportScheduleEnum::Monthly => server::AccountingExportSchedule::Monthly,
+        }
+    }
+
+    pub fn schedule_server_to_domain(
+        schedule: server::AccountingExportSchedule,
+    ) -> AccountingExportScheduleEnum {
+        match schedule {
+            server::AccountingExportSchedule::Daily => AccountingExportScheduleEnum::Daily,
+            server::AccountingExportSchedule::Weekly => AccountingExportScheduleEnum::Weekly,
+            server::AccountingExportSchedule::Monthly => AccountingExportScheduleEnum::Monthly,
+        }
+    }
+
+    pub fn run_status_to_server(
+        status: AccountingExportRunStatusEnum,
+    ) -> server::AccountingExportRunStatus {
+        match status {
+            AccountingExportRunStatusEnum::Succeeded => {
+                server::AccountingExportRunStatus::Succeeded
+            }
+            AccountingExportRunStatusEnum::Failed => server::AccountingExportRunStatus::Failed,
+        }
+    }
+}
+
+pub mod configs {
+    use crate::api::shared::mapping::date::chrono_to_proto;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::accountingexports::v1 as server;
+    use meteroid_store::domain::accounting_exports::{
+        AccountingExportConfig, AccountingExportConfigNew, LedgerAccounts, QuickBooksCredentials,
+    };
+    use uuid::Uuid;
+
+    pub fn domain_to_server(config: AccountingExportConfig) -> server::AccountingExportConfig {
+        server::AccountingExportConfig {
+            connector: super::enums::connector_to_server(config.connector).into(),
+            schedule: super::enums::schedule_to_server(config.schedule).into(),
+            enabled: config.enabled,
+            accounts: Some(server::LedgerAccounts {
+                receivable: config.accounts.receivable,
+                revenue: config.accounts.revenue,
+                deferred_revenue: config.accounts.deferred_revenue,
+                tax_payable: config.accounts.tax_payable,
+            }),
+            // the refresh token is never returned
+            quickbooks_realm_id: config.quickbooks.map(|quickbooks| quickbooks.realm_id),
+            exported_until: config.exported_until.map(chrono_to_proto),
+            updated_at: Some(chrono_to_timestamp(config.updated_at)),
+        }
+    }
+
+    pub fn upsert_req_server_to_domain(
+        req: server::UpsertAccountingExportConfigRequest,
+        tenant_id: Uuid,
+        actor: Uuid,
+    ) -> AccountingExportConfigNew {
+        let accounts = req.accounts.clone().unwrap_or_default();
+
+        AccountingExportConfigNew {
+            tenant_id,
+            connector: super::enums::connector_server_to_domain(req.connector()),
+            schedule: super::enums::schedule_server_to_domain(req.schedule()),
+            enabled: req.enabled,
+            accounts: LedgerAccounts {
+                receivable: accounts.receivable,
+                revenue: accounts.revenue,
+                deferred_revenue: accounts.deferred_revenue,
+                tax_payable: accounts.tax_payable,
+            },
+            quickbooks: req.quickbooks.map(|quickbooks| QuickBooksCredentials {
+                realm_id: quickbooks.realm_id,
+                refresh_token: quickbooks.refresh_token,
+            }),
+            created_by: actor,
+        }
+    }
+}
+
+pub mod runs {
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::shared::mapping::date::chrono_to_proto;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::accountingexports::v1 as server;
+    use meteroid_store::domain::accounting_exports::AccountingExportRun;
+
+    pub fn domain_to_server(run: AccountingExportRun) -> server::AccountingExportRun {
+        server::AccountingExportRun {
+            id: run.id.as_proto(),
+            connector: super::enums::connector_to_server(run.connector).into(),
+            period_start: Some(chrono_to_proto(run.period_start)),
+            period_end: Some(chrono_to_proto(run.period_end)),
+            status: super::enums::run_status_to_server(run.status).into(),
+            entries: run.entries,
+            document_id: run.document_id,
+            error: run.error,
+            created_at: Some(chrono_to_timestamp(run.created_at)),
+        }
+    }
+}
@@ -0,0 +1,24 @@
+use crate::services::storage::ObjectStoreService;
+use meteroid_grpc::meteroid::api::accountingexports::v1::accounting_exports_service_server::AccountingExportsServiceServer;
+use meteroid_store::Store;
+use std::sync::Arc;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct AccountingExportsServiceComponents {
+    pub store: Store,
+    pub object_store: Arc<dyn ObjectStoreService>,
+}
+
+pub fn service(
+    store: Store,
+    object_store: Arc<dyn ObjectStoreService>,
+) -> AccountingExportsServiceServer<AccountingExportsServiceComponents> {
+    let inner = AccountingExportsServiceComponents {
+        store,
+        object_store,
+    };
+    AccountingExportsServiceServer::new(inner)
+}
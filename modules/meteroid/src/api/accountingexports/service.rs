@@ -0,0 +1,191 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::accountingexports::v1::accounting_exports_service_server::AccountingExportsService;
+use meteroid_grpc::meteroid::api::accountingexports::v1::{
+    DownloadAccountingExportRequest, DownloadAccountingExportResponse,
+    GetAccountingExportConfigRequest, GetAccountingExportConfigResponse,
+    ListAccountingExportRunsRequest, ListAccountingExportRunsResponse, RunAccountingExportRequest,
+    RunAccountingExportResponse, UpsertAccountingExportConfigRequest,
+    UpsertAccountingExportConfigResponse,
+};
+use meteroid_store::domain::accounting_exports::AccountingPeriod;
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::enums::AccountingConnectorEnum;
+use meteroid_store::repositories::accounting_exports::AccountingExportInterface;
+
+use crate::api::accountingexports::error::AccountingExportApiError;
+use crate::api::accountingexports::mapping::{configs, runs};
+use crate::api::accountingexports::AccountingExportsServiceComponents;
+use crate::api::shared::mapping::date::chrono_from_proto;
+use crate::api::utils::{audited, parse_uuid};
+use crate::services::accounting_export::run_accounting_export;
+use crate::services::storage::Prefix;
+
+#[tonic::async_trait]
+impl AccountingExportsService for AccountingExportsServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn get_accounting_export_config(
+        &self,
+        request: Request<GetAccountingExportConfigRequest>,
+    ) -> Result<Response<GetAccountingExportConfigResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let config = self
+            .store
+            .find_accounting_export_config(tenant_id)
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?
+            .map(configs::domain_to_server);
+
+        Ok(Response::new(GetAccountingExportConfigResponse { config }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn upsert_accounting_export_config(
+        &self,
+        request: Request<UpsertAccountingExportConfigRequest>,
+    ) -> Result<Response<UpsertAccountingExportConfigResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let config = self
+            .store
+            .upsert_accounting_export_config(configs::upsert_req_server_to_domain(
+                req, tenant_id, actor,
+            ))
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?;
+
+        Ok(audited(
+            UpsertAccountingExportConfigResponse {
+                config: Some(configs::domain_to_server(config)),
+            },
+            AuditEntity::new(tenant_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_accounting_export_runs(
+        &self,
+        request: Request<ListAccountingExportRunsRequest>,
+    ) -> Result<Response<ListAccountingExportRunsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let runs = self
+            .store
+            .list_accounting_export_runs(tenant_id)
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?
+            .into_iter()
+            .map(runs::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListAccountingExportRunsResponse { runs }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn run_accounting_export(
+        &self,
+        request: Request<RunAccountingExportRequest>,
+    ) -> Result<Response<RunAccountingExportResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let (start, end) = match (
+            req.period_start.and_then(chrono_from_proto),
+            req.period_end.and_then(chrono_from_proto),
+        ) {
+            (Some(start), Some(end)) => (start, end),
+            _ => {
+                return Err(AccountingExportApiError::InvalidArgument(
+                    "A valid period start and end are required".to_string(),
+                )
+                .into())
+            }
+        };
+
+        let period = AccountingPeriod::new(start, end)
+            .map_err(|e| AccountingExportApiError::InvalidArgument(e.to_string()))?;
+
+        let config = self
+            .store
+            .find_accounting_export_config(tenant_id)
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?
+            .ok_or_else(|| {
+                AccountingExportApiError::FailedPrecondition(
+                    "The accounting export is not configured".to_string(),
+                )
+            })?;
+
+        let run = run_accounting_export(&self.store, &self.object_store, &config, period, false)
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?;
+
+        let id = run.id;
+
+        Ok(audited(
+            RunAccountingExportResponse {
+                run: Some(runs::domain_to_server(run)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn download_accounting_export(
+        &self,
+        request: Request<DownloadAccountingExportRequest>,
+    ) -> Result<Response<DownloadAccountingExportResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let run_id = parse_uuid(&req.run_id, "run_id")?;
+
+        let run = self
+            .store
+            .find_accounting_export_run(tenant_id, run_id)
+            .await
+            .map_err(Into::<AccountingExportApiError>::into)?;
+
+        let document_id = match (run.connector, &run.document_id) {
+            (AccountingConnectorEnum::Csv, Some(document_id)) => {
+                parse_uuid(document_id, "document_id")?
+            }
+            _ => {
+                return Err(AccountingExportApiError::FailedPrecondition(
+                    "The run did not produce a file".to_string(),
+                )
+                .into())
+            }
+        };
+
+        let data = self
+            .object_store
+            .retrieve(document_id, Prefix::AccountingExport)
+            .await
+            .map_err(|e| {
+                AccountingExportApiError::ExportError(
+                    "Failed to retrieve the exported file".to_string(),
+                    Box::new(e.into_error()),
+                )
+            })?;
+
+        let csv = String::from_utf8(data.to_vec()).map_err(|e| {
+            AccountingExportApiError::ExportError(
+                "The exported file is not a valid CSV".to_string(),
+                Box::new(e),
+            )
+        })?;
+
+        Ok(Response::new(DownloadAccountingExportResponse {
+            csv,
+            file_name: format!(
+                "journal-entries-{}-{}.csv",
+                run.period_start, run.period_end
+            ),
+        }))
+    }
+}
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::addons::v1::add_ons_service_server::AddOnsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod mapping;
 mod service;
 
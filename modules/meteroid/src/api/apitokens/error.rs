@@ -8,6 +8,10 @@ use meteroid_store::errors::StoreError;
 
 #[derive(Debug, Error, ErrorAsTonic)]
 pub enum ApiTokenApiError {
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
     #[error("Password hash error: {0}")]
     #[code(Internal)]
     PasswordHashError(String),
@@ -19,7 +23,12 @@ pub enum ApiTokenApiError {
 
 impl From<Report<StoreError>> for ApiTokenApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        Self::StoreError("Error in api token service".to_string(), err)
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            _ => Self::StoreError(
+                "Error in api token service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
     }
 }
@@ -1,7 +1,10 @@
 pub mod api_token {
-    use meteroid_grpc::meteroid::api::apitokens::v1::ApiToken;
+    use meteroid_grpc::meteroid::api::apitokens::v1::{
+        ApiToken, ApiTokenDailyUsage, ApiTokenUsage,
+    };
     use meteroid_store::domain;
 
+    use crate::api::shared::conversions::ProtoConv;
     use crate::api::shared::mapping::datetime::chrono_to_timestamp;
 
     pub fn domain_to_api(api_token: domain::api_tokens::ApiToken) -> ApiToken {
@@ -12,6 +15,35 @@ pub mod api_token {
             hint: api_token.hint,
             created_at: Some(chrono_to_timestamp(api_token.created_at)),
             created_by: api_token.created_by.to_string(),
+            last_used_at: api_token.last_used_at.map(chrono_to_timestamp),
+            request_count: api_token.request_count as u64,
+            daily_request_soft_limit: api_token.daily_request_soft_limit.map(|l| l as u64),
+            disabled_at: api_token.disabled_at.map(chrono_to_timestamp),
+            rotated_at: api_token.rotated_at.map(chrono_to_timestamp),
+            partner_id: api_token.partner_id.map(|id| id.to_string()),
+            scopes: api_token.scopes,
+            expires_at: api_token.expires_at.map(chrono_to_timestamp),
+            expiry_notified_at: api_token.expiry_notified_at.map(chrono_to_timestamp),
+        }
+    }
+
+    pub fn usage_domain_to_api(usage: domain::api_tokens::ApiTokenUsage) -> ApiTokenUsage {
+        let exceeded_days = usage.soft_limit_exceeded_days();
+
+        ApiTokenUsage {
+            api_token_id: usage.api_token_id.to_string(),
+            request_count: usage.request_count as u64,
+            last_used_at: usage.last_used_at.map(chrono_to_timestamp),
+            daily_request_soft_limit: usage.daily_request_soft_limit.map(|l| l as u64),
+            daily: usage
+                .daily
+                .into_iter()
+                .map(|d| ApiTokenDailyUsage {
+                    day: d.day.as_proto(),
+                    request_count: d.request_count as u64,
+                    soft_limit_exceeded: exceeded_days.contains(&d.day),
+                })
+                .collect(),
         }
     }
 }
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::apitokens::v1::api_tokens_service_server::ApiTokensServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod mapping;
 mod service;
 
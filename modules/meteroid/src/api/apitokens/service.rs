@@ -1,18 +1,31 @@
+use std::str::FromStr;
+
 use tonic::{Request, Response, Status};
+use uuid::Uuid;
 
+use common_grpc::middleware::server::auth::api_token_scopes::ApiTokenScope;
 use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::apitokens::v1::{
     api_tokens_service_server::ApiTokensService, CreateApiTokenRequest, CreateApiTokenResponse,
-    GetApiTokenByIdRequest, GetApiTokenByIdResponse, ListApiTokensRequest, ListApiTokensResponse,
+    DisableApiTokenRequest, DisableApiTokenResponse, GetApiTokenByIdRequest,
+    GetApiTokenByIdResponse, GetApiTokenUsageRequest, GetApiTokenUsageResponse,
+    ListApiTokensRequest, ListApiTokensResponse, RotateApiTokenRequest, RotateApiTokenResponse,
 };
+use meteroid_middleware::server::auth::strategies::api_key_strategy::invalidate_api_key_cache;
 use meteroid_store::domain;
-use meteroid_store::repositories::api_tokens::ApiTokensInterface;
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::repositories::api_tokens::{ApiTokensInterface, MAX_API_TOKEN_USAGE_DAYS};
 
 use crate::api::apitokens::error::ApiTokenApiError;
+use crate::api::shared::conversions::FromProtoOpt;
+use crate::api::shared::mapping::datetime::chrono_from_timestamp;
+use crate::api::utils::audited;
 use crate::{api::utils::parse_uuid, parse_uuid};
 
 use super::{mapping, ApiTokensServiceComponents};
 
+const DEFAULT_USAGE_DAYS: u32 = 30;
+
 #[tonic::async_trait]
 impl ApiTokensService for ApiTokensServiceComponents {
     #[tracing::instrument(skip_all)]
@@ -50,27 +63,38 @@ impl ApiTokensService for ApiTokensServiceComponents {
         let tenant_id = request.tenant()?;
         let req = request.into_inner();
 
+        let scopes = req
+            .scopes
+            .iter()
+            .map(|scope| {
+                ApiTokenScope::from_str(scope)
+                    .map(|scope| scope.to_string())
+                    .map_err(|e| ApiTokenApiError::InvalidArgument(e.to_string()))
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
         let (api_key, res) = self
             .store
             .insert_api_token(domain::ApiTokenNew {
                 name: req.name,
                 created_by: actor,
                 tenant_id,
+                daily_request_soft_limit: req.daily_request_soft_limit.map(|l| l as i64),
+                partner_id: Uuid::from_proto_opt(req.partner_id)?,
+                scopes,
+                expires_at: req.expires_at.map(chrono_from_timestamp).transpose()?,
             })
             .await
-            .map_err(|e| {
-                ApiTokenApiError::StoreError(
-                    "Unable to create api token".to_string(),
-                    Box::new(e.into_error()),
-                )
-            })?;
+            .map_err(Into::<ApiTokenApiError>::into)?;
+
+        let entity = AuditEntity::new(res.id);
 
         let response = CreateApiTokenResponse {
             api_key,
             details: Some(mapping::api_token::domain_to_api(res)),
         };
 
-        Ok(Response::new(response))
+        Ok(audited(response, entity))
     }
 
     #[tracing::instrument(skip_all)]
@@ -96,4 +120,86 @@ impl ApiTokensService for ApiTokensServiceComponents {
             hash: result.hash,
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_api_token_usage(
+        &self,
+        request: Request<GetApiTokenUsageRequest>,
+    ) -> Result<Response<GetApiTokenUsageResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let days = req.days.unwrap_or(DEFAULT_USAGE_DAYS);
+        if days == 0 || days as u64 > MAX_API_TOKEN_USAGE_DAYS {
+            return Err(ApiTokenApiError::InvalidArgument(format!(
+                "days must be between 1 and {}",
+                MAX_API_TOKEN_USAGE_DAYS
+            ))
+            .into());
+        }
+
+        let since = chrono::Utc::now().date_naive() - chrono::Days::new(days as u64 - 1);
+
+        let usage = self
+            .store
+            .get_api_token_usage(&tenant_id, &parse_uuid!(&req.id)?, since)
+            .await
+            .map_err(Into::<ApiTokenApiError>::into)?;
+
+        Ok(Response::new(GetApiTokenUsageResponse {
+            usage: Some(mapping::api_token::usage_domain_to_api(usage)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn disable_api_token(
+        &self,
+        request: Request<DisableApiTokenRequest>,
+    ) -> Result<Response<DisableApiTokenResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+        let id = parse_uuid!(&req.id)?;
+
+        let api_token = self
+            .store
+            .disable_api_token(&tenant_id, &id)
+            .await
+            .map_err(Into::<ApiTokenApiError>::into)?;
+
+        invalidate_api_key_cache(&id).await;
+
+        Ok(audited(
+            DisableApiTokenResponse {
+                api_token: Some(mapping::api_token::domain_to_api(api_token)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn rotate_api_token(
+        &self,
+        request: Request<RotateApiTokenRequest>,
+    ) -> Result<Response<RotateApiTokenResponse>, Status> {
+        let actor = request.actor()?;
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+        let id = parse_uuid!(&req.id)?;
+
+        let (api_key, api_token) = self
+            .store
+            .rotate_api_token(&tenant_id, &id, &actor, req.grace_period_hours)
+            .await
+            .map_err(Into::<ApiTokenApiError>::into)?;
+
+        invalidate_api_key_cache(&id).await;
+
+        Ok(audited(
+            RotateApiTokenResponse {
+                api_key,
+                details: Some(mapping::api_token::domain_to_api(api_token)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
 }
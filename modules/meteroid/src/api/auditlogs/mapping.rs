@@ -0,0 +1,17 @@
+pub mod audit_log {
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::auditlogs::v1::AuditLog as AuditLogProto;
+    use meteroid_store::domain::audit_logs::AuditLog;
+
+    pub fn to_proto(log: AuditLog) -> AuditLogProto {
+        AuditLogProto {
+            id: log.id.to_string(),
+            actor_id: log.actor_id.to_string(),
+            rpc: log.rpc,
+            entity_id: log.entity_id,
+            diff: log.diff.map(|d| d.to_string()),
+            status_code: log.status_code,
+            created_at: Some(chrono_to_timestamp(log.created_at)),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::auditlogs::v1::audit_logs_service_server::AuditLogsServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct AuditLogsServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> AuditLogsServiceServer<AuditLogsServiceComponents> {
+    let inner = AuditLogsServiceComponents { store };
+    AuditLogsServiceServer::new(inner)
+}
@@ -0,0 +1,53 @@
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::auditlogs::v1::audit_logs_service_server::AuditLogsService;
+use meteroid_grpc::meteroid::api::auditlogs::v1::{ListAuditLogsRequest, ListAuditLogsResponse};
+use meteroid_store::domain;
+use meteroid_store::domain::audit_logs::AuditLogFilter;
+use meteroid_store::repositories::audit_logs::AuditLogInterface;
+
+use crate::api::auditlogs::error::AuditLogApiError;
+use crate::api::auditlogs::mapping::audit_log;
+use crate::api::auditlogs::AuditLogsServiceComponents;
+use crate::api::shared::conversions::FromProtoOpt;
+use crate::api::shared::mapping::datetime::chrono_from_timestamp;
+use crate::api::utils::PaginationExt;
+
+#[tonic::async_trait]
+impl AuditLogsService for AuditLogsServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn list_audit_logs(
+        &self,
+        request: Request<ListAuditLogsRequest>,
+    ) -> Result<Response<ListAuditLogsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+
+        let filter = AuditLogFilter {
+            actor_id: Uuid::from_proto_opt(req.actor_id)?,
+            rpc: req.rpc,
+            entity_id: req.entity_id,
+            from: req.from.map(chrono_from_timestamp).transpose()?,
+            to: req.to.map(chrono_from_timestamp).transpose()?,
+        };
+
+        let pagination_req = domain::PaginationRequest {
+            page: req.pagination.as_ref().map(|p| p.offset).unwrap_or(0),
+            per_page: req.pagination.as_ref().map(|p| p.limit),
+        };
+
+        let res = self
+            .store
+            .list_audit_logs(tenant_id, filter, pagination_req)
+            .await
+            .map_err(Into::<AuditLogApiError>::into)?;
+
+        Ok(Response::new(ListAuditLogsResponse {
+            pagination_meta: req.pagination.into_response(res.total_results as u32),
+            audit_logs: res.items.into_iter().map(audit_log::to_proto).collect(),
+        }))
+    }
+}
@@ -0,0 +1,100 @@
+use super::AppState;
+
+use crate::errors;
+use crate::webhook::Webhook;
+use axum::{
+    body::Body,
+    extract::{DefaultBodyLimit, State},
+    http::{Request, StatusCode},
+    response::{IntoResponse, Response},
+};
+use axum::{routing::post, Router};
+
+use chrono::NaiveDateTime;
+use error_stack::{bail, Result, ResultExt};
+use meteroid_store::domain::billing_emails::{EmailDeliveryEvent, EmailDeliveryEventType};
+use meteroid_store::repositories::billing_emails::BillingEmailsInterface;
+use secrecy::ExposeSecret;
+use serde::Deserialize;
+use uuid::Uuid;
+
+/// Delivery callbacks of the email provider, signed following the standard-webhooks spec
+pub fn email_events_routes() -> Router<AppState> {
+    Router::new()
+        .route("/v1/events", post(axum_handler))
+        .layer(DefaultBodyLimit::max(4096))
+}
+
+#[derive(Debug, Deserialize)]
+struct EmailEventPayload {
+    #[serde(rename = "type")]
+    event_type: EmailDeliveryEventType,
+    recipient: String,
+    tenant_id: Option<Uuid>,
+    reason: Option<String>,
+    occurred_at: Option<NaiveDateTime>,
+}
+
+#[axum::debug_handler]
+async fn axum_handler(State(app_state): State<AppState>, req: Request<Body>) -> impl IntoResponse {
+    match handler(req, app_state).await {
+        Ok(r) => r.into_response(),
+        Err(e) => {
+            log::error!("Error handling email event: {}", e);
+            e.current_context().clone().into_response()
+        }
+    }
+}
+
+async fn handler(
+    req: Request<Body>,
+    app_state: AppState,
+) -> Result<Response, errors::AdapterWebhookError> {
+    let received_at = chrono::Utc::now().naive_utc();
+
+    let Some(secret) = app_state.email_events_webhook_secret.as_ref() else {
+        bail!(errors::AdapterWebhookError::ProviderNotSupported(
+            "email events".into()
+        ))
+    };
+
+    let (parts, body) = req.into_parts();
+    let bytes = axum::body::to_bytes(body, usize::MAX)
+        .await
+        .change_context(errors::AdapterWebhookError::BodyDecodingFailed)?;
+
+    Webhook::new(secret.expose_secret())
+        .change_context(errors::AdapterWebhookError::Unauthorized)?
+        .verify(&bytes, &parts.headers)
+        .change_context(errors::AdapterWebhookError::SignatureVerificationFailed)?;
+
+    let payload: EmailEventPayload = serde_json::from_slice(&bytes)
+        .change_context(errors::AdapterWebhookError::BodyDecodingFailed)?;
+
+    log::trace!(
+        "Received email event {:?} for recipient {}",
+        payload.event_type,
+        payload.recipient
+    );
+
+    let updated = app_state
+        .store
+        .record_email_delivery_event(EmailDeliveryEvent {
+            event_type: payload.event_type,
+            recipient: payload.recipient,
+            tenant_id: payload.tenant_id,
+            reason: payload.reason,
+            occurred_at: payload.occurred_at.unwrap_or(received_at),
+        })
+        .await
+        .change_context(errors::AdapterWebhookError::DatabaseError)?;
+
+    if !updated.is_empty() {
+        log::info!(
+            "Updated the billing email status of {} customer(s)",
+            updated.len()
+        );
+    }
+
+    Ok(StatusCode::OK.into_response())
+}
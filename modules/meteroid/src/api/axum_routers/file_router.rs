@@ -18,6 +18,7 @@ use error_stack::{Report, Result, ResultExt};
 use fang::Deserialize;
 use image::ImageFormat::Png;
 use jsonwebtoken::{decode, DecodingKey, Validation};
+use meteroid_store::repositories::invoice_attachments::InvoiceAttachmentInterface;
 use meteroid_store::repositories::InvoiceInterface;
 use secrecy::ExposeSecret;
 use uuid::Uuid;
@@ -26,6 +27,10 @@ pub fn file_routes() -> Router<AppState> {
     Router::new()
         .route("/v1/logo/:uid", get(get_logo))
         .route("/v1/invoice/pdf/:invoice_uid", get(get_invoice_pdf))
+        .route(
+            "/v1/invoice/attachment/:attachment_id",
+            get(get_invoice_attachment),
+        )
 }
 
 #[axum::debug_handler]
@@ -137,3 +142,72 @@ async fn get_invoice_pdf_handler(
             .into_response()),
     }
 }
+
+#[axum::debug_handler]
+async fn get_invoice_attachment(
+    Path(attachment_id): Path<String>,
+    Query(params): Query<TokenParams>,
+    State(app_state): State<AppState>,
+) -> impl IntoResponse {
+    match get_invoice_attachment_handler(attachment_id, params, app_state).await {
+        Ok(r) => r.into_response(),
+        Err(e) => {
+            log::error!("Error handling webhook: {}", e);
+            e.current_context().clone().into_response()
+        }
+    }
+}
+
+/// The attachments are shared with the token of their invoice
+async fn get_invoice_attachment_handler(
+    attachment_id: String,
+    token: TokenParams,
+    app_state: AppState,
+) -> Result<Response, errors::RestApiError> {
+    let claims = decode::<ShareableEntityClaims>(
+        &token.token,
+        &DecodingKey::from_secret(app_state.jwt_secret.expose_secret().as_bytes()),
+        &Validation::default(),
+    )
+    .map_err(|_| Report::new(errors::RestApiError::Unauthorized))?
+    .claims;
+
+    let attachment_id =
+        Uuid::parse_str(&attachment_id).change_context(errors::RestApiError::InvalidInput)?;
+
+    let attachment = app_state
+        .store
+        .get_invoice_attachment(claims.tenant_id, attachment_id)
+        .await
+        .change_context(errors::RestApiError::StoreError)?;
+
+    if attachment.invoice_id != claims.entity_id {
+        return Err(Report::new(errors::RestApiError::Forbidden));
+    }
+
+    let data = app_state
+        .object_store
+        .retrieve(
+            Uuid::parse_str(&attachment.document_id)
+                .change_context(errors::RestApiError::StoreError)?,
+            Prefix::InvoiceAttachment,
+        )
+        .await
+        .change_context(errors::RestApiError::ObjectStoreError)?;
+
+    Ok((
+        StatusCode::OK,
+        [
+            ("Content-Type", attachment.content_type),
+            (
+                "Content-Disposition",
+                format!(
+                    "attachment; filename=\"{}\"",
+                    attachment.file_name.replace('"', "")
+                ),
+            ),
+        ],
+        data,
+    )
+        .into_response())
+}
@@ -1,12 +1,18 @@
+use crate::adapters::adyen::Adyen;
+use crate::adapters::gocardless::GoCardless;
 use crate::adapters::stripe::Stripe;
 use crate::services::storage::ObjectStoreService;
+use metering_grpc::meteroid::metering::v1::events_service_client::EventsServiceClient;
 use meteroid_store::Store;
 use secrecy::SecretString;
 use std::sync::Arc;
+use tonic::transport::Channel;
 
+mod email_events_router;
 mod file_router;
 mod webhook_in_router;
 
+pub use email_events_router::email_events_routes;
 pub use file_router::file_routes;
 pub use webhook_in_router::webhook_in_routes;
 
@@ -15,5 +21,9 @@ pub struct AppState {
     pub object_store: Arc<dyn ObjectStoreService>,
     pub store: Store,
     pub stripe_adapter: Arc<Stripe>,
+    pub adyen_adapter: Arc<Adyen>,
+    pub gocardless_adapter: Arc<GoCardless>,
     pub jwt_secret: SecretString,
+    pub email_events_webhook_secret: Option<SecretString>,
+    pub events_client: EventsServiceClient<Channel>,
 }
@@ -17,6 +17,7 @@ use meteroid_store::domain::webhooks::WebhookInEventNew;
 use meteroid_store::repositories::configs::ConfigsInterface;
 use meteroid_store::repositories::webhooks::WebhooksInterface;
 use secrecy::SecretString;
+use std::sync::Arc;
 
 pub fn webhook_in_routes() -> Router<AppState> {
     Router::new()
@@ -55,6 +56,8 @@ async fn handler(
 
     let provider = match provider_str.as_str() {
         "stripe" => InvoicingProviderEnum::Stripe,
+        "adyen" => InvoicingProviderEnum::Adyen,
+        "gocardless" => InvoicingProviderEnum::GoCardless,
         // add other providers here
         _ => bail!(errors::AdapterWebhookError::UnknownProvider(provider_str)),
     };
@@ -109,8 +112,10 @@ async fn handler(
     // metrics TODO
 
     // - get adapter
-    let adapter = match provider {
+    let adapter: Arc<dyn WebhookAdapter + Send + Sync> = match provider {
         InvoicingProviderEnum::Stripe => app_state.stripe_adapter,
+        InvoicingProviderEnum::Adyen => app_state.adyen_adapter,
+        InvoicingProviderEnum::GoCardless => app_state.gocardless_adapter,
         InvoicingProviderEnum::Manual => bail!(errors::AdapterWebhookError::ProviderNotSupported(
             "Manual".into()
         )),
@@ -145,11 +150,43 @@ async fn handler(
 
     let response = adapter.get_optimistic_webhook_response();
 
+    let event_id = adapter.event_id(&parsed_request);
+    let provider_config_id = provider_config.id;
+
     // then process specific event
     tokio::spawn(async move {
-        adapter
-            .process_webhook_event(&parsed_request, app_state.store.clone())
-            .await
+        let store = app_state.store.clone();
+
+        if let Some(event_id) = &event_id {
+            match store
+                .claim_webhook_in_event(provider_config_id, event_id.clone(), received_at)
+                .await
+            {
+                Ok(true) => {}
+                Ok(false) => {
+                    log::info!("Ignoring already received webhook event {}", event_id);
+                    return;
+                }
+                Err(e) => {
+                    log::error!("Failed to claim webhook event {}: {:?}", event_id, e);
+                    return;
+                }
+            }
+        }
+
+        let res = adapter
+            .process_webhook_event(&parsed_request, tenant_id, store.clone())
+            .await;
+
+        // a new delivery of a failed event, ex: resent from the provider dashboard, is processed again
+        if let (Err(_), Some(event_id)) = (&res, &event_id) {
+            if let Err(e) = store
+                .release_webhook_in_event(provider_config_id, event_id)
+                .await
+            {
+                log::error!("Failed to release webhook event {}: {:?}", event_id, e);
+            }
+        }
     });
 
     Ok(response)
@@ -1,32 +1,47 @@
+use crate::adapters::adyen::Adyen;
+use crate::adapters::gocardless::GoCardless;
 use crate::adapters::stripe::Stripe;
 use crate::api::axum_routers;
+use crate::api::rest;
 use crate::services::storage::ObjectStoreService;
 use axum::{
     extract::DefaultBodyLimit, http::StatusCode, http::Uri, response::IntoResponse, Router,
 };
+use metering_grpc::meteroid::metering::v1::events_service_client::EventsServiceClient;
 use meteroid_store::Store;
 use secrecy::SecretString;
 use std::net::SocketAddr;
 use std::sync::Arc;
 use tokio::net::TcpListener;
+use tonic::transport::Channel;
 
 pub async fn serve(
     listen_addr: SocketAddr,
     object_store: Arc<dyn ObjectStoreService>,
     stripe_adapter: Arc<Stripe>,
+    adyen_adapter: Arc<Adyen>,
+    gocardless_adapter: Arc<GoCardless>,
     store: Store,
     jwt_secret: SecretString,
+    email_events_webhook_secret: Option<SecretString>,
+    events_client: EventsServiceClient<Channel>,
 ) {
     let app_state = axum_routers::AppState {
         object_store,
         store,
         stripe_adapter,
+        adyen_adapter,
+        gocardless_adapter,
         jwt_secret,
+        email_events_webhook_secret,
+        events_client,
     };
 
     let app = Router::new()
         .nest("/files", axum_routers::file_routes())
         .nest("/webhooks", axum_routers::webhook_in_routes())
+        .nest("/email", axum_routers::email_events_routes())
+        .merge(rest::rest_routes())
         .fallback(handler_404)
         .with_state(app_state)
         .layer(DefaultBodyLimit::max(4096));
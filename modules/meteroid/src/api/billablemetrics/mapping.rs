@@ -90,6 +90,60 @@ pub mod unit_conversion_rounding {
     }
 }
 
+pub mod event_schema {
+    use meteroid_grpc::meteroid::api::billablemetrics::v1 as server;
+    use meteroid_grpc::meteroid::api::billablemetrics::v1::event_schema::PropertyType;
+    use meteroid_store::domain::billable_metrics::{self as domain, EventSchema, PropertySchema};
+
+    fn type_server_to_domain(value: PropertyType) -> domain::PropertyType {
+        match value {
+            PropertyType::String => domain::PropertyType::String,
+            PropertyType::Number => domain::PropertyType::Number,
+            PropertyType::Integer => domain::PropertyType::Integer,
+            PropertyType::Boolean => domain::PropertyType::Boolean,
+        }
+    }
+
+    fn type_domain_to_server(value: domain::PropertyType) -> PropertyType {
+        match value {
+            domain::PropertyType::String => PropertyType::String,
+            domain::PropertyType::Number => PropertyType::Number,
+            domain::PropertyType::Integer => PropertyType::Integer,
+            domain::PropertyType::Boolean => PropertyType::Boolean,
+        }
+    }
+
+    pub fn server_to_domain(value: server::EventSchema) -> EventSchema {
+        EventSchema {
+            properties: value
+                .properties
+                .into_iter()
+                .map(|p| PropertySchema {
+                    property_type: type_server_to_domain(p.property_type()),
+                    key: p.key,
+                    required: p.required,
+                    allowed_values: p.allowed_values,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn domain_to_server(value: EventSchema) -> server::EventSchema {
+        server::EventSchema {
+            properties: value
+                .properties
+                .into_iter()
+                .map(|p| server::event_schema::Property {
+                    key: p.key,
+                    property_type: type_domain_to_server(p.property_type).into(),
+                    required: p.required,
+                    allowed_values: p.allowed_values,
+                })
+                .collect(),
+        }
+    }
+}
+
 pub mod metric {
     use error_stack::Report;
     use meteroid_grpc::meteroid::api::billablemetrics::v1 as server;
@@ -133,6 +187,13 @@ pub mod metric {
                 archived_at: value.archived_at.map(chrono_to_timestamp),
                 created_at: Some(chrono_to_timestamp(value.created_at)),
                 usage_group_key: value.usage_group_key,
+                quantity_precision: Some(server::QuantityPrecision {
+                    billing: value.quantity_billing_precision.map(|p| p as u32),
+                    display: value.quantity_display_precision.map(|p| p as u32),
+                }),
+                event_schema: value
+                    .event_schema
+                    .map(super::event_schema::domain_to_server),
             }))
         }
     }
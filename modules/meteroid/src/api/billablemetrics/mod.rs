@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::billablemetrics::v1::billable_metrics_service_server::BillableMetricsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
@@ -63,6 +63,19 @@ impl BillableMetricsService for BillableMetricsComponents {
                 created_by: actor,
                 tenant_id,
                 family_external_id: inner.family_external_id,
+                quantity_billing_precision: inner
+                    .quantity_precision
+                    .as_ref()
+                    .and_then(|p| p.billing)
+                    .map(|p| p.min(i32::MAX as u32) as i32),
+                quantity_display_precision: inner
+                    .quantity_precision
+                    .as_ref()
+                    .and_then(|p| p.display)
+                    .map(|p| p.min(i32::MAX as u32) as i32),
+                event_schema: inner
+                    .event_schema
+                    .map(mapping::event_schema::server_to_domain),
             })
             .await
             .map_err(Into::<BillableMetricApiError>::into)?;
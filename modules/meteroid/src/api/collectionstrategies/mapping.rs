@@ -0,0 +1,140 @@
+pub mod preset {
+    use meteroid_grpc::meteroid::api::collectionstrategies::v1::CollectionStrategyPreset as CollectionStrategyPresetProto;
+    use meteroid_store::domain::enums::CollectionStrategyPresetEnum;
+
+    pub fn to_proto(preset: CollectionStrategyPresetEnum) -> CollectionStrategyPresetProto {
+        match preset {
+            CollectionStrategyPresetEnum::Lenient => CollectionStrategyPresetProto::Lenient,
+            CollectionStrategyPresetEnum::Standard => CollectionStrategyPresetProto::Standard,
+            CollectionStrategyPresetEnum::Strict => CollectionStrategyPresetProto::Strict,
+        }
+    }
+
+    pub fn from_proto(preset: CollectionStrategyPresetProto) -> CollectionStrategyPresetEnum {
+        match preset {
+            CollectionStrategyPresetProto::Lenient => CollectionStrategyPresetEnum::Lenient,
+            CollectionStrategyPresetProto::Standard => CollectionStrategyPresetEnum::Standard,
+            CollectionStrategyPresetProto::Strict => CollectionStrategyPresetEnum::Strict,
+        }
+    }
+}
+
+pub mod rules {
+    use crate::api::shared::conversions::ProtoConv;
+    use meteroid_grpc::meteroid::api::collectionstrategies::v1::{
+        CollectionRules as CollectionRulesProto,
+        CollectionRulesOverride as CollectionRulesOverrideProto, LateFee as LateFeeProto,
+        ReminderCadence, RetrySchedule,
+    };
+    use meteroid_store::domain::collection_strategies::{
+        CollectionRules, CollectionRulesOverride, LateFee,
+    };
+    use rust_decimal::Decimal;
+    use tonic::Status;
+
+    fn late_fee_to_proto(late_fee: LateFee) -> LateFeeProto {
+        LateFeeProto {
+            percent: late_fee.percent.as_proto(),
+            grace_days: late_fee.grace_days,
+        }
+    }
+
+    fn late_fee_from_proto(late_fee: LateFeeProto) -> Result<LateFee, Status> {
+        Ok(LateFee {
+            percent: Decimal::from_proto_ref(&late_fee.percent)?,
+            grace_days: late_fee.grace_days,
+        })
+    }
+
+    pub fn to_proto(rules: CollectionRules) -> CollectionRulesProto {
+        CollectionRulesProto {
+            retry_schedule_days: rules.retry_schedule_days,
+            reminder_offsets_days: rules.reminder_offsets_days,
+            suspend_after_days: rules.suspend_after_days,
+            late_fee: rules.late_fee.map(late_fee_to_proto),
+        }
+    }
+
+    pub fn override_to_proto(rules: CollectionRulesOverride) -> CollectionRulesOverrideProto {
+        CollectionRulesOverrideProto {
+            retry_schedule: rules.retry_schedule_days.map(|days| RetrySchedule { days }),
+            reminder_cadence: rules
+                .reminder_offsets_days
+                .map(|offset_days| ReminderCadence { offset_days }),
+            suspend_after_days: rules.suspend_after_days,
+            late_fee: rules.late_fee.map(late_fee_to_proto),
+        }
+    }
+
+    pub fn override_from_proto(
+        rules: CollectionRulesOverrideProto,
+    ) -> Result<CollectionRulesOverride, Status> {
+        Ok(CollectionRulesOverride {
+            retry_schedule_days: rules.retry_schedule.map(|s| s.days),
+            reminder_offsets_days: rules.reminder_cadence.map(|c| c.offset_days),
+            suspend_after_days: rules.suspend_after_days,
+            late_fee: rules.late_fee.map(late_fee_from_proto).transpose()?,
+        })
+    }
+}
+
+pub mod strategies {
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::collectionstrategies::v1::{
+        CollectionStrategy as CollectionStrategyProto,
+        CollectionStrategyOverride as CollectionStrategyOverrideProto,
+        CollectionStrategyPresetRules, UpsertCollectionStrategyOverrideRequest,
+    };
+    use meteroid_store::domain::collection_strategies::{
+        CollectionStrategy, CollectionStrategyOverride, CollectionStrategyOverrideNew,
+    };
+    use meteroid_store::domain::enums::CollectionStrategyPresetEnum;
+    use tonic::Status;
+    use uuid::Uuid;
+
+    pub fn preset_to_proto(preset: CollectionStrategyPresetEnum) -> CollectionStrategyPresetRules {
+        CollectionStrategyPresetRules {
+            preset: super::preset::to_proto(preset).into(),
+            rules: Some(super::rules::to_proto(preset.rules())),
+        }
+    }
+
+    pub fn override_to_proto(
+        strategy: CollectionStrategyOverride,
+    ) -> CollectionStrategyOverrideProto {
+        CollectionStrategyOverrideProto {
+            segment: strategy.segment,
+            preset: super::preset::to_proto(strategy.preset).into(),
+            rules: Some(super::rules::override_to_proto(strategy.rules)),
+            updated_at: Some(chrono_to_timestamp(strategy.updated_at)),
+        }
+    }
+
+    pub fn upsert_req_to_domain(
+        req: UpsertCollectionStrategyOverrideRequest,
+        tenant_id: Uuid,
+        actor: Uuid,
+    ) -> Result<CollectionStrategyOverrideNew, Status> {
+        let preset = super::preset::from_proto(req.preset());
+
+        Ok(CollectionStrategyOverrideNew {
+            tenant_id,
+            segment: req.segment,
+            preset,
+            rules: req
+                .rules
+                .map(super::rules::override_from_proto)
+                .transpose()?
+                .unwrap_or_default(),
+            updated_by: actor,
+        })
+    }
+
+    pub fn to_proto(strategy: CollectionStrategy) -> CollectionStrategyProto {
+        CollectionStrategyProto {
+            preset: super::preset::to_proto(strategy.preset).into(),
+            segment: strategy.segment,
+            rules: Some(super::rules::to_proto(strategy.rules)),
+        }
+    }
+}
@@ -0,0 +1,17 @@
+use meteroid_grpc::meteroid::api::collectionstrategies::v1::collection_strategies_service_server::CollectionStrategiesServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+pub mod mapping;
+mod service;
+
+pub struct CollectionStrategiesServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(
+    store: Store,
+) -> CollectionStrategiesServiceServer<CollectionStrategiesServiceComponents> {
+    let inner = CollectionStrategiesServiceComponents { store };
+    CollectionStrategiesServiceServer::new(inner)
+}
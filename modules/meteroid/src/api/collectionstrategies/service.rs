@@ -0,0 +1,151 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::collectionstrategies::v1::collection_strategies_service_server::CollectionStrategiesService;
+use meteroid_grpc::meteroid::api::collectionstrategies::v1::{
+    DeleteCollectionStrategyOverrideRequest, DeleteCollectionStrategyOverrideResponse,
+    GetCustomerCollectionStrategyRequest, GetCustomerCollectionStrategyResponse,
+    ListCollectionStrategyOverridesRequest, ListCollectionStrategyOverridesResponse,
+    ListCollectionStrategyPresetsRequest, ListCollectionStrategyPresetsResponse,
+    SetCustomerCollectionSegmentRequest, SetCustomerCollectionSegmentResponse,
+    UpsertCollectionStrategyOverrideRequest, UpsertCollectionStrategyOverrideResponse,
+};
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::enums::CollectionStrategyPresetEnum;
+use meteroid_store::repositories::collection_strategies::CollectionStrategyInterface;
+
+use crate::api::collectionstrategies::error::CollectionStrategyApiError;
+use crate::api::collectionstrategies::mapping::strategies;
+use crate::api::collectionstrategies::CollectionStrategiesServiceComponents;
+use crate::api::utils::{audited, parse_uuid};
+
+#[tonic::async_trait]
+impl CollectionStrategiesService for CollectionStrategiesServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn list_collection_strategy_presets(
+        &self,
+        _request: Request<ListCollectionStrategyPresetsRequest>,
+    ) -> Result<Response<ListCollectionStrategyPresetsResponse>, Status> {
+        let presets = [
+            CollectionStrategyPresetEnum::Lenient,
+            CollectionStrategyPresetEnum::Standard,
+            CollectionStrategyPresetEnum::Strict,
+        ]
+        .into_iter()
+        .map(strategies::preset_to_proto)
+        .collect();
+
+        Ok(Response::new(ListCollectionStrategyPresetsResponse {
+            presets,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_collection_strategy_overrides(
+        &self,
+        request: Request<ListCollectionStrategyOverridesRequest>,
+    ) -> Result<Response<ListCollectionStrategyOverridesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let overrides = self
+            .store
+            .list_collection_strategy_overrides(tenant_id)
+            .await
+            .map_err(Into::<CollectionStrategyApiError>::into)?
+            .into_iter()
+            .map(strategies::override_to_proto)
+            .collect();
+
+        Ok(Response::new(ListCollectionStrategyOverridesResponse {
+            overrides,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn upsert_collection_strategy_override(
+        &self,
+        request: Request<UpsertCollectionStrategyOverrideRequest>,
+    ) -> Result<Response<UpsertCollectionStrategyOverrideResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let strategy = self
+            .store
+            .upsert_collection_strategy_override(strategies::upsert_req_to_domain(
+                req, tenant_id, actor,
+            )?)
+            .await
+            .map_err(Into::<CollectionStrategyApiError>::into)?;
+
+        let segment = strategy.segment.clone();
+
+        Ok(audited(
+            UpsertCollectionStrategyOverrideResponse {
+                strategy: Some(strategies::override_to_proto(strategy)),
+            },
+            AuditEntity::new(segment),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_collection_strategy_override(
+        &self,
+        request: Request<DeleteCollectionStrategyOverrideRequest>,
+    ) -> Result<Response<DeleteCollectionStrategyOverrideResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        self.store
+            .delete_collection_strategy_override(tenant_id, req.segment.clone())
+            .await
+            .map_err(Into::<CollectionStrategyApiError>::into)?;
+
+        Ok(audited(
+            DeleteCollectionStrategyOverrideResponse {},
+            AuditEntity::new(req.segment),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_customer_collection_segment(
+        &self,
+        request: Request<SetCustomerCollectionSegmentRequest>,
+    ) -> Result<Response<SetCustomerCollectionSegmentResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        self.store
+            .set_customer_collection_segment(tenant_id, customer_id, req.segment)
+            .await
+            .map_err(Into::<CollectionStrategyApiError>::into)?;
+
+        Ok(audited(
+            SetCustomerCollectionSegmentResponse {},
+            AuditEntity::new(customer_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_customer_collection_strategy(
+        &self,
+        request: Request<GetCustomerCollectionStrategyRequest>,
+    ) -> Result<Response<GetCustomerCollectionStrategyResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let strategy = self
+            .store
+            .get_customer_collection_strategy(tenant_id, customer_id)
+            .await
+            .map_err(Into::<CollectionStrategyApiError>::into)?;
+
+        Ok(Response::new(GetCustomerCollectionStrategyResponse {
+            strategy: Some(strategies::to_proto(strategy)),
+        }))
+    }
+}
@@ -0,0 +1,80 @@
+pub mod rule_type {
+    use meteroid_grpc::meteroid::api::componentrules::v1::ComponentRuleType as ComponentRuleTypeProto;
+    use meteroid_store::domain::enums::ComponentRuleTypeEnum;
+
+    pub fn to_proto(rule_type: ComponentRuleTypeEnum) -> ComponentRuleTypeProto {
+        match rule_type {
+            ComponentRuleTypeEnum::Requires => ComponentRuleTypeProto::Requires,
+            ComponentRuleTypeEnum::Excludes => ComponentRuleTypeProto::Excludes,
+        }
+    }
+
+    pub fn from_proto(rule_type: ComponentRuleTypeProto) -> ComponentRuleTypeEnum {
+        match rule_type {
+            ComponentRuleTypeProto::Requires => ComponentRuleTypeEnum::Requires,
+            ComponentRuleTypeProto::Excludes => ComponentRuleTypeEnum::Excludes,
+        }
+    }
+}
+
+pub mod items {
+    use meteroid_grpc::meteroid::api::componentrules::v1::component_rule_item::Item;
+    use meteroid_grpc::meteroid::api::componentrules::v1::ComponentRuleItem as ComponentRuleItemProto;
+    use meteroid_store::domain::component_rules::ComponentRuleItem;
+
+    use crate::api::componentrules::error::ComponentRuleApiError;
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::utils::parse_uuid;
+
+    pub fn to_proto(item: ComponentRuleItem) -> ComponentRuleItemProto {
+        let item = match item {
+            ComponentRuleItem::PriceComponent(id) => Item::PriceComponentId(id.as_proto()),
+            ComponentRuleItem::AddOn(id) => Item::AddOnId(id.as_proto()),
+        };
+
+        ComponentRuleItemProto { item: Some(item) }
+    }
+
+    pub fn from_proto(
+        item: Option<ComponentRuleItemProto>,
+        field: &str,
+    ) -> Result<ComponentRuleItem, tonic::Status> {
+        match item.and_then(|i| i.item) {
+            Some(Item::PriceComponentId(id)) => Ok(ComponentRuleItem::PriceComponent(parse_uuid(
+                &id,
+                "price_component_id",
+            )?)),
+            Some(Item::AddOnId(id)) => Ok(ComponentRuleItem::AddOn(parse_uuid(&id, "add_on_id")?)),
+            None => Err(ComponentRuleApiError::MissingArgument(field.to_string()).into()),
+        }
+    }
+}
+
+pub mod rules {
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::componentrules::v1::{
+        ComponentRule as ComponentRuleProto, ComponentRuleViolation as ComponentRuleViolationProto,
+    };
+    use meteroid_store::domain::component_rules::{ComponentRule, ComponentRuleViolation};
+
+    pub fn domain_to_server(rule: ComponentRule) -> ComponentRuleProto {
+        ComponentRuleProto {
+            id: rule.id.as_proto(),
+            rule_type: super::rule_type::to_proto(rule.rule_type).into(),
+            subject: Some(super::items::to_proto(rule.subject)),
+            target: Some(super::items::to_proto(rule.target)),
+            created_at: Some(chrono_to_timestamp(rule.created_at)),
+        }
+    }
+
+    pub fn violation_to_server(violation: ComponentRuleViolation) -> ComponentRuleViolationProto {
+        ComponentRuleViolationProto {
+            message: violation.to_string(),
+            rule_id: violation.rule_id.as_proto(),
+            rule_type: super::rule_type::to_proto(violation.rule_type).into(),
+            subject: Some(super::items::to_proto(violation.subject)),
+            target: Some(super::items::to_proto(violation.target)),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::componentrules::v1::component_rules_service_server::ComponentRulesServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct ComponentRulesServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> ComponentRulesServiceServer<ComponentRulesServiceComponents> {
+    let inner = ComponentRulesServiceComponents { store };
+    ComponentRulesServiceServer::new(inner)
+}
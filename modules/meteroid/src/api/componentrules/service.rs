@@ -0,0 +1,119 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::componentrules::v1::component_rules_service_server::ComponentRulesService;
+use meteroid_grpc::meteroid::api::componentrules::v1::{
+    CreateComponentRuleRequest, CreateComponentRuleResponse, DeleteComponentRuleRequest,
+    DeleteComponentRuleResponse, ListComponentRulesRequest, ListComponentRulesResponse,
+    ValidateComponentRulesRequest, ValidateComponentRulesResponse,
+};
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::component_rules::ComponentRuleNew;
+use meteroid_store::repositories::component_rules::ComponentRulesInterface;
+
+use crate::api::componentrules::error::ComponentRuleApiError;
+use crate::api::componentrules::mapping::{items, rule_type, rules};
+use crate::api::componentrules::ComponentRulesServiceComponents;
+use crate::api::utils::{audited, parse_uuid};
+
+#[tonic::async_trait]
+impl ComponentRulesService for ComponentRulesServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn create_component_rule(
+        &self,
+        request: Request<CreateComponentRuleRequest>,
+    ) -> Result<Response<CreateComponentRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let rule = ComponentRuleNew {
+            tenant_id,
+            rule_type: rule_type::from_proto(req.rule_type()),
+            subject: items::from_proto(req.subject, "subject")?,
+            target: items::from_proto(req.target, "target")?,
+            created_by: actor,
+        };
+
+        let rule = self
+            .store
+            .insert_component_rule(rule)
+            .await
+            .map_err(Into::<ComponentRuleApiError>::into)?;
+
+        let id = rule.id;
+
+        Ok(audited(
+            CreateComponentRuleResponse {
+                rule: Some(rules::domain_to_server(rule)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_component_rules(
+        &self,
+        request: Request<ListComponentRulesRequest>,
+    ) -> Result<Response<ListComponentRulesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let rules = self
+            .store
+            .list_component_rules(tenant_id)
+            .await
+            .map_err(Into::<ComponentRuleApiError>::into)?
+            .into_iter()
+            .map(rules::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListComponentRulesResponse { rules }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_component_rule(
+        &self,
+        request: Request<DeleteComponentRuleRequest>,
+    ) -> Result<Response<DeleteComponentRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .delete_component_rule(tenant_id, id)
+            .await
+            .map_err(Into::<ComponentRuleApiError>::into)?;
+
+        Ok(audited(
+            DeleteComponentRuleResponse {},
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn validate_component_rules(
+        &self,
+        request: Request<ValidateComponentRulesRequest>,
+    ) -> Result<Response<ValidateComponentRulesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let items = req
+            .items
+            .into_iter()
+            .map(|item| items::from_proto(Some(item), "items"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let violations = self
+            .store
+            .check_component_rules(tenant_id, items)
+            .await
+            .map_err(Into::<ComponentRuleApiError>::into)?
+            .into_iter()
+            .map(rules::violation_to_server)
+            .collect();
+
+        Ok(Response::new(ValidateComponentRulesResponse { violations }))
+    }
+}
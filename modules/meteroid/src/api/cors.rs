@@ -1,25 +1,84 @@
+use envconfig::Envconfig;
 use http::header::HeaderName;
+use http::HeaderValue;
 use std::time::Duration;
 use tower_http::cors::{AllowOrigin, CorsLayer};
 
-const DEFAULT_MAX_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+use common_grpc::middleware::common::auth::{
+    API_KEY_HEADER, BEARER_AUTH_HEADER, INTERNAL_API_CONTEXT_HEADER,
+};
+
 const DEFAULT_EXPOSED_HEADERS: [&str; 3] =
     ["grpc-status", "grpc-message", "grpc-status-details-bin"];
-const DEFAULT_ALLOW_HEADERS: [&str; 7] = [
-    "x-grpc-web",
-    "content-type",
-    "x-user-agent",
-    "grpc-timeout",
-    "x-api-key",
-    "authorization",
-    "x-md-context",
-];
-
-pub fn cors() -> CorsLayer {
+const DEFAULT_ALLOW_HEADERS: [&str; 4] =
+    ["x-grpc-web", "content-type", "x-user-agent", "grpc-timeout"];
+
+/// CORS of the api server, for the browser clients calling it through grpc-web
+#[derive(Envconfig, Debug, Clone)]
+pub struct CorsConfig {
+    /// comma separated origins allowed to call the api, any origin if "*"
+    #[envconfig(from = "CORS_ALLOWED_ORIGINS", default = "*")]
+    pub allowed_origins: String,
+
+    #[envconfig(from = "CORS_ALLOW_CREDENTIALS", default = "true")]
+    pub allow_credentials: bool,
+
+    #[envconfig(from = "CORS_MAX_AGE_SECONDS", default = "86400")]
+    pub max_age_seconds: u64,
+}
+
+impl CorsConfig {
+    /// The allowed origins, None if any origin is allowed. Invalid origins are ignored
+    fn origins(&self) -> Option<Vec<HeaderValue>> {
+        if self.allowed_origins.trim() == "*" {
+            return None;
+        }
+
+        let origins = self
+            .allowed_origins
+            .split(',')
+            .map(str::trim)
+            .filter(|o| !o.is_empty())
+            .filter_map(|o| match HeaderValue::from_str(o) {
+                // rejected by the origin list, a wildcard must be configured alone
+                Ok(_) if o == "*" => {
+                    log::warn!("Ignoring CORS wildcard origin listed with other origins");
+                    None
+                }
+                Ok(v) => Some(v),
+                Err(_) => {
+                    log::warn!("Ignoring invalid CORS origin: {}", o);
+                    None
+                }
+            })
+            .collect();
+
+        Some(origins)
+    }
+
+    fn allow_origin(&self) -> AllowOrigin {
+        match self.origins() {
+            Some(origins) => AllowOrigin::list(origins),
+            // the origin is mirrored, as credentials can't be allowed with a wildcard
+            None => AllowOrigin::mirror_request(),
+        }
+    }
+}
+
+pub fn cors(config: &CorsConfig) -> CorsLayer {
+    // the auth metadata is sent as headers by the browser clients
+    let auth_headers = [
+        API_KEY_HEADER,
+        BEARER_AUTH_HEADER,
+        INTERNAL_API_CONTEXT_HEADER,
+    ]
+    .iter()
+    .map(|h| HeaderName::from_bytes(h.to_lowercase().as_bytes()).expect("valid header"));
+
     CorsLayer::new()
-        .allow_origin(AllowOrigin::mirror_request())
-        .allow_credentials(true)
-        .max_age(DEFAULT_MAX_AGE)
+        .allow_origin(config.allow_origin())
+        .allow_credentials(config.allow_credentials)
+        .max_age(Duration::from_secs(config.max_age_seconds))
         .expose_headers(
             DEFAULT_EXPOSED_HEADERS
                 .iter()
@@ -32,6 +91,65 @@ pub fn cors() -> CorsLayer {
                 .iter()
                 .cloned()
                 .map(HeaderName::from_static)
+                .chain(auth_headers)
                 .collect::<Vec<HeaderName>>(),
         )
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(allowed_origins: &str) -> CorsConfig {
+        CorsConfig {
+            allowed_origins: allowed_origins.to_string(),
+            allow_credentials: true,
+            max_age_seconds: 86400,
+        }
+    }
+
+    #[test]
+    fn test_origins_wildcard() {
+        assert_eq!(config("*").origins(), None);
+        assert_eq!(config(" * ").origins(), None);
+    }
+
+    #[test]
+    fn test_origins_list() {
+        let origins = config("https://app.meteroid.com, http://localhost:5173,,").origins();
+
+        assert_eq!(
+            origins,
+            Some(vec![
+                HeaderValue::from_static("https://app.meteroid.com"),
+                HeaderValue::from_static("http://localhost:5173"),
+            ])
+        );
+    }
+
+    #[test]
+    fn test_origins_ignores_invalid() {
+        let origins = config("https://app.meteroid.com,https://bad\norigin.com").origins();
+
+        assert_eq!(
+            origins,
+            Some(vec![HeaderValue::from_static("https://app.meteroid.com")])
+        );
+
+        // the wildcard is only honored alone
+        let origins = config("https://app.meteroid.com,*").origins();
+
+        assert_eq!(
+            origins,
+            Some(vec![HeaderValue::from_static("https://app.meteroid.com")])
+        );
+    }
+
+    #[test]
+    fn test_cors_layer_accepts_configured_origins() {
+        // tower-http panics on a wildcard passed with other origins
+        let _ = cors(&config("*"));
+        let _ = cors(&config("https://app.meteroid.com,*"));
+        let _ = cors(&config(""));
+    }
+}
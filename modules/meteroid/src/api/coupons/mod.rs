@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::coupons::v1::coupons_service_server::CouponsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
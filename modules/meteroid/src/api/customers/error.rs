@@ -23,6 +23,18 @@ pub enum CustomerApiError {
     #[code(FailedPrecondition)]
     FailedPrecondition(String),
 
+    #[error("{0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    #[code(NotFound)]
+    NotFound(String),
+
+    #[error("A customer with the same {0} already exists")]
+    #[code(AlreadyExists)]
+    AlreadyExists(String),
+
     #[error("Store error: {0}")]
     #[code(Internal)]
     StoreError(String, #[source] Box<dyn Error>),
@@ -41,6 +53,12 @@ impl From<Report<StoreError>> for CustomerApiError {
                 StoreError::NegativeCustomerBalanceError(_) => {
                     Self::FailedPrecondition("negative customer balance".into())
                 }
+                StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+                StoreError::ValueNotFound(msg) => Self::NotFound(msg.clone()),
+                StoreError::DuplicateValue {
+                    entity: "customer",
+                    key,
+                } => Self::AlreadyExists(key.clone().unwrap_or_else(|| "key".to_string())),
                 _ => Self::StoreError(
                     "Error in customer service".to_string(),
                     Box::new(value.into_error()),
@@ -1,8 +1,13 @@
 pub mod customer {
     use error_stack::Report;
+    use rust_decimal::Decimal;
+    use tonic::Status;
 
     use meteroid_grpc::meteroid::api::customers::v1 as server;
     use meteroid_store::domain;
+    use meteroid_store::domain::credit_limits::{CreditExposure, CreditLimit};
+    use meteroid_store::domain::customer_blackout_windows::CustomerBlackoutWindow;
+    use meteroid_store::domain::enums::CreditLimitEnforcementEnum;
     use meteroid_store::errors::StoreError;
 
     use crate::api::customers::error::CustomerApiError;
@@ -23,6 +28,7 @@ pub mod customer {
                                 server::customer_billing_config::Stripe {
                                     customer_id: value.customer_id,
                                     collection_method: value.collection_method,
+                                    default_payment_method_id: value.default_payment_method_id,
                                 },
                             ),
                         ),
@@ -37,6 +43,29 @@ pub mod customer {
                         ),
                     }))
                 }
+                domain::BillingConfig::Adyen(value) => {
+                    Ok(ServerBillingConfigWrapper(server::CustomerBillingConfig {
+                        billing_config_oneof: Some(
+                            server::customer_billing_config::BillingConfigOneof::Adyen(
+                                server::customer_billing_config::Adyen {
+                                    shopper_reference: value.shopper_reference,
+                                },
+                            ),
+                        ),
+                    }))
+                }
+                domain::BillingConfig::GoCardless(value) => {
+                    Ok(ServerBillingConfigWrapper(server::CustomerBillingConfig {
+                        billing_config_oneof: Some(
+                            server::customer_billing_config::BillingConfigOneof::GoCardless(
+                                server::customer_billing_config::GoCardless {
+                                    customer_id: value.customer_id,
+                                    mandate_id: value.mandate_id,
+                                },
+                            ),
+                        ),
+                    }))
+                }
             }
         }
     }
@@ -53,12 +82,26 @@ pub mod customer {
                         domain::Stripe {
                             customer_id: value.customer_id,
                             collection_method: value.collection_method, //todo fix this
+                            default_payment_method_id: value.default_payment_method_id,
                         },
                     )))
                 }
                 Some(server::customer_billing_config::BillingConfigOneof::Manual(_)) => {
                     Ok(DomainBillingConfigWrapper(domain::BillingConfig::Manual))
                 }
+                Some(server::customer_billing_config::BillingConfigOneof::Adyen(value)) => Ok(
+                    DomainBillingConfigWrapper(domain::BillingConfig::Adyen(domain::Adyen {
+                        shopper_reference: value.shopper_reference,
+                    })),
+                ),
+                Some(server::customer_billing_config::BillingConfigOneof::GoCardless(value)) => {
+                    Ok(DomainBillingConfigWrapper(
+                        domain::BillingConfig::GoCardless(domain::GoCardless {
+                            customer_id: value.customer_id,
+                            mandate_id: value.mandate_id,
+                        }),
+                    ))
+                }
                 None => Err(CustomerApiError::MissingArgument(
                     "billing_config".to_string(),
                 )),
@@ -134,6 +177,181 @@ pub mod customer {
         }
     }
 
+    pub struct ServerPaymentTermsWrapper(pub server::PaymentTerms);
+
+    impl From<domain::CustomerPaymentTerms> for ServerPaymentTermsWrapper {
+        fn from(value: domain::CustomerPaymentTerms) -> Self {
+            ServerPaymentTermsWrapper(server::PaymentTerms {
+                net_terms_override: value.net_terms_override,
+                early_payment_discount: value.early_payment_discount.map(|d| {
+                    server::EarlyPaymentDiscount {
+                        percent: d.percent.as_proto(),
+                        days: d.days,
+                    }
+                }),
+                due_date_policy: value.due_date_policy.map(|p| server::DueDatePolicy {
+                    r#type: due_date_policy_type_to_server(p.policy).into(),
+                    day: p.day,
+                }),
+            })
+        }
+    }
+
+    pub struct DomainPaymentTermsWrapper(pub domain::CustomerPaymentTerms);
+
+    impl TryFrom<server::PaymentTerms> for DomainPaymentTermsWrapper {
+        type Error = Status;
+
+        fn try_from(value: server::PaymentTerms) -> Result<Self, Self::Error> {
+            Ok(DomainPaymentTermsWrapper(domain::CustomerPaymentTerms {
+                net_terms_override: value.net_terms_override,
+                early_payment_discount: value
+                    .early_payment_discount
+                    .map(|d| -> Result<_, Status> {
+                        Ok(domain::EarlyPaymentDiscount {
+                            percent: Decimal::from_proto_ref(&d.percent)?,
+                            days: d.days,
+                        })
+                    })
+                    .transpose()?,
+                due_date_policy: value.due_date_policy.map(|p| {
+                    domain::payment_terms::DueDatePolicy {
+                        policy: due_date_policy_type_to_domain(p.r#type()),
+                        day: p.day,
+                    }
+                }),
+            }))
+        }
+    }
+
+    pub struct ServerCreditLimitWrapper(pub server::CreditLimit);
+
+    impl From<CreditLimit> for ServerCreditLimitWrapper {
+        fn from(value: CreditLimit) -> Self {
+            let enforcement = match value.enforcement {
+                CreditLimitEnforcementEnum::Flag => server::credit_limit::Enforcement::Flag,
+                CreditLimitEnforcementEnum::Block => server::credit_limit::Enforcement::Block,
+            };
+
+            ServerCreditLimitWrapper(server::CreditLimit {
+                limit_cents: value.cents,
+                enforcement: enforcement.into(),
+            })
+        }
+    }
+
+    pub struct DomainCreditLimitWrapper(pub CreditLimit);
+
+    impl From<server::CreditLimit> for DomainCreditLimitWrapper {
+        fn from(value: server::CreditLimit) -> Self {
+            let enforcement = match value.enforcement() {
+                server::credit_limit::Enforcement::Flag => CreditLimitEnforcementEnum::Flag,
+                server::credit_limit::Enforcement::Block => CreditLimitEnforcementEnum::Block,
+            };
+
+            DomainCreditLimitWrapper(CreditLimit {
+                cents: value.limit_cents,
+                enforcement,
+            })
+        }
+    }
+
+    pub fn credit_exposure_to_server(
+        exposure: CreditExposure,
+        credit_limit: Option<CreditLimit>,
+    ) -> server::CreditExposure {
+        server::CreditExposure {
+            unpaid_invoices_cents: exposure.unpaid_invoices,
+            unbilled_usage_cents: exposure.unbilled_usage,
+            total_cents: exposure.total(),
+            available_cents: credit_limit.as_ref().map(|l| exposure.available(l)),
+            limit_exceeded: credit_limit.as_ref().is_some_and(|l| exposure.exceeds(l)),
+            credit_limit: credit_limit.map(|l| ServerCreditLimitWrapper::from(l).0),
+        }
+    }
+
+    pub fn blackout_window_to_server(window: CustomerBlackoutWindow) -> server::BlackoutWindow {
+        server::BlackoutWindow {
+            id: window.id.as_proto(),
+            start_date: window.start_date.as_proto(),
+            end_date: window.end_date.as_proto(),
+            reason: window.reason,
+            created_at: window.created_at.as_proto(),
+            created_by: window.created_by.as_proto(),
+        }
+    }
+
+    fn due_date_policy_type_to_server(
+        policy: domain::enums::DueDatePolicyEnum,
+    ) -> server::due_date_policy::Type {
+        match policy {
+            domain::enums::DueDatePolicyEnum::NetDays => server::due_date_policy::Type::NetDays,
+            domain::enums::DueDatePolicyEnum::EndOfMonth => {
+                server::due_date_policy::Type::EndOfMonth
+            }
+            domain::enums::DueDatePolicyEnum::FixedDay => server::due_date_policy::Type::FixedDay,
+        }
+    }
+
+    fn due_date_policy_type_to_domain(
+        policy: server::due_date_policy::Type,
+    ) -> domain::enums::DueDatePolicyEnum {
+        match policy {
+            server::due_date_policy::Type::NetDays => domain::enums::DueDatePolicyEnum::NetDays,
+            server::due_date_policy::Type::EndOfMonth => {
+                domain::enums::DueDatePolicyEnum::EndOfMonth
+            }
+            server::due_date_policy::Type::FixedDay => domain::enums::DueDatePolicyEnum::FixedDay,
+        }
+    }
+
+    fn billing_email_status_to_server(
+        status: domain::enums::BillingEmailStatusEnum,
+    ) -> server::BillingEmailStatus {
+        match status {
+            domain::enums::BillingEmailStatusEnum::Unverified => {
+                server::BillingEmailStatus::Unverified
+            }
+            domain::enums::BillingEmailStatusEnum::Verified => server::BillingEmailStatus::Verified,
+            domain::enums::BillingEmailStatusEnum::Bounced => server::BillingEmailStatus::Bounced,
+            domain::enums::BillingEmailStatusEnum::Complained => {
+                server::BillingEmailStatus::Complained
+            }
+        }
+    }
+
+    pub fn invoice_delivery_channel_to_server(
+        channel: domain::enums::InvoiceDeliveryChannelEnum,
+    ) -> server::InvoiceDeliveryChannel {
+        match channel {
+            domain::enums::InvoiceDeliveryChannelEnum::Email => {
+                server::InvoiceDeliveryChannel::Email
+            }
+            domain::enums::InvoiceDeliveryChannelEnum::Webhook => {
+                server::InvoiceDeliveryChannel::Webhook
+            }
+            domain::enums::InvoiceDeliveryChannelEnum::ProviderHosted => {
+                server::InvoiceDeliveryChannel::ProviderHosted
+            }
+        }
+    }
+
+    pub fn invoice_delivery_channel_to_domain(
+        channel: server::InvoiceDeliveryChannel,
+    ) -> domain::enums::InvoiceDeliveryChannelEnum {
+        match channel {
+            server::InvoiceDeliveryChannel::Email => {
+                domain::enums::InvoiceDeliveryChannelEnum::Email
+            }
+            server::InvoiceDeliveryChannel::Webhook => {
+                domain::enums::InvoiceDeliveryChannelEnum::Webhook
+            }
+            server::InvoiceDeliveryChannel::ProviderHosted => {
+                domain::enums::InvoiceDeliveryChannelEnum::ProviderHosted
+            }
+        }
+    }
+
     pub struct ServerCustomerWrapper(pub server::Customer);
 
     impl TryFrom<domain::Customer> for ServerCustomerWrapper {
@@ -163,6 +381,27 @@ pub mod customer {
                     .map(ServerShippingAddressWrapper::try_from)
                     .transpose()?
                     .map(|v| v.0),
+                payment_terms: Some(
+                    ServerPaymentTermsWrapper::from(domain::CustomerPaymentTerms {
+                        net_terms_override: value.net_terms_override,
+                        early_payment_discount: value.early_payment_discount,
+                        due_date_policy: value.due_date_policy,
+                    })
+                    .0,
+                ),
+                billing_email_status: billing_email_status_to_server(value.billing_email_status)
+                    .into(),
+                billing_email_status_reason: value.billing_email_status_reason,
+                billing_email_status_updated_at: value
+                    .billing_email_status_updated_at
+                    .map(chrono_to_timestamp),
+                collection_segment: value.collection_segment,
+                credit_limit: value
+                    .credit_limit
+                    .map(|l| ServerCreditLimitWrapper::from(l).0),
+                invoice_delivery_channel: value
+                    .invoice_delivery_channel
+                    .map(|c| invoice_delivery_channel_to_server(c).into()),
             }))
         }
     }
@@ -187,3 +426,45 @@ pub mod customer {
         }
     }
 }
+
+pub mod audit {
+    use meteroid_store::domain::Customer;
+    use serde_json::{json, Value};
+
+    /// The editable fields of the customer, diffed in the audit log
+    pub fn customer_to_json(customer: &Customer) -> Value {
+        json!({
+            "name": customer.name,
+            "alias": customer.alias,
+            "email": customer.email,
+            "invoicing_email": customer.invoicing_email,
+            "phone": customer.phone,
+            "balance_value_cents": customer.balance_value_cents,
+            "currency": customer.currency,
+            "invoicing_entity_id": customer.invoicing_entity_id,
+            "billing_address": customer.billing_address,
+            "shipping_address": customer.shipping_address,
+        })
+    }
+
+    pub fn payment_terms_to_json(customer: &Customer) -> Value {
+        json!({
+            "net_terms_override": customer.net_terms_override,
+            "early_payment_discount": customer.early_payment_discount,
+            "due_date_policy": customer.due_date_policy,
+        })
+    }
+
+    pub fn credit_limit_to_json(customer: &Customer) -> Value {
+        json!({
+            "credit_limit_cents": customer.credit_limit.as_ref().map(|l| l.cents),
+            "credit_limit_enforcement": customer.credit_limit.as_ref().map(|l| l.enforcement),
+        })
+    }
+
+    pub fn invoice_delivery_to_json(customer: &Customer) -> Value {
+        json!({
+            "invoice_delivery_channel": customer.invoice_delivery_channel,
+        })
+    }
+}
@@ -5,27 +5,44 @@ use uuid::Uuid;
 use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::customers::v1::list_customer_request::SortBy;
 use meteroid_grpc::meteroid::api::customers::v1::{
-    customers_service_server::CustomersService, BuyCustomerCreditsRequest,
-    BuyCustomerCreditsResponse, CreateCustomerRequest, CreateCustomerResponse, CustomerBrief,
-    GetCustomerByAliasRequest, GetCustomerByAliasResponse, GetCustomerByIdRequest,
-    GetCustomerByIdResponse, ListCustomerRequest, ListCustomerResponse, PatchCustomerRequest,
-    PatchCustomerResponse, TopUpCustomerBalanceRequest, TopUpCustomerBalanceResponse,
+    customers_service_server::CustomersService, AddCustomerBlackoutWindowRequest,
+    AddCustomerBlackoutWindowResponse, BuyCustomerCreditsRequest, BuyCustomerCreditsResponse,
+    CreateCustomerRequest, CreateCustomerResponse, CustomerBrief, FindOrCreateCustomerRequest,
+    FindOrCreateCustomerResponse, GetCustomerByAliasRequest, GetCustomerByAliasResponse,
+    GetCustomerByIdRequest, GetCustomerByIdResponse, GetCustomerCreditExposureRequest,
+    GetCustomerCreditExposureResponse, ListCustomerBlackoutWindowsRequest,
+    ListCustomerBlackoutWindowsResponse, ListCustomerRequest, ListCustomerResponse,
+    PatchCustomerRequest, PatchCustomerResponse, RemoveCustomerBlackoutWindowRequest,
+    RemoveCustomerBlackoutWindowResponse, TopUpCustomerBalanceRequest,
+    TopUpCustomerBalanceResponse, UpdateCustomerCreditLimitRequest,
+    UpdateCustomerCreditLimitResponse, UpdateCustomerInvoiceDeliveryRequest,
+    UpdateCustomerInvoiceDeliveryResponse, UpdateCustomerPaymentTermsRequest,
+    UpdateCustomerPaymentTermsResponse,
 };
 use meteroid_store::domain;
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::customer_blackout_windows::CustomerBlackoutWindowNew;
 use meteroid_store::domain::{
     CustomerBuyCredits, CustomerNew, CustomerPatch, CustomerTopUpBalance, OrderByRequest,
 };
 use meteroid_store::errors::StoreError;
+use meteroid_store::repositories::credit_limits::CreditLimitInterface;
+use meteroid_store::repositories::customer_blackout_windows::CustomerBlackoutWindowInterface;
+use meteroid_store::repositories::invoice_delivery::InvoiceDeliveryInterface;
 use meteroid_store::repositories::CustomersInterface;
 
 use crate::api::customers::error::CustomerApiError;
+use crate::api::customers::mapping::audit;
 use crate::api::customers::mapping::customer::{
-    DomainAddressWrapper, DomainBillingConfigWrapper, DomainShippingAddressWrapper,
-    ServerCustomerBriefWrapper, ServerCustomerWrapper,
+    blackout_window_to_server, credit_exposure_to_server, invoice_delivery_channel_to_domain,
+    DomainAddressWrapper, DomainBillingConfigWrapper, DomainCreditLimitWrapper,
+    DomainPaymentTermsWrapper, DomainShippingAddressWrapper, ServerCustomerBriefWrapper,
+    ServerCustomerWrapper,
 };
-use crate::api::shared::conversions::FromProtoOpt;
-use crate::api::utils::parse_uuid;
+use crate::api::redaction::Redact;
+use crate::api::shared::conversions::{FromProtoOpt, ProtoConv};
 use crate::api::utils::PaginationExt;
+use crate::api::utils::{audited, parse_uuid};
 
 use super::CustomerServiceComponents;
 
@@ -44,34 +61,7 @@ impl CustomersService for CustomerServiceComponents {
             .data
             .ok_or(CustomerApiError::MissingArgument("no data".into()))?;
 
-        let billing_config = match inner.billing_config {
-            Some(b) => DomainBillingConfigWrapper::try_from(b)?.0,
-            None => domain::BillingConfig::Manual,
-        };
-
-        let customer_new = CustomerNew {
-            name: inner.name,
-            created_by: actor,
-            invoicing_entity_id: Uuid::from_proto_opt(inner.invoicing_entity_id)?,
-            billing_config,
-            alias: inner.alias,
-            email: inner.email,
-            invoicing_email: inner.invoicing_email,
-            phone: inner.phone,
-            balance_value_cents: 0,
-            currency: inner.currency,
-            billing_address: inner
-                .billing_address
-                .map(DomainAddressWrapper::try_from)
-                .transpose()?
-                .map(|v| v.0),
-            shipping_address: inner
-                .shipping_address
-                .map(DomainShippingAddressWrapper::try_from)
-                .transpose()?
-                .map(|v| v.0),
-            force_created_date: None,
-        };
+        let customer_new = customer_new_from_server(inner, actor)?;
 
         let customer = self
             .store
@@ -81,9 +71,50 @@ impl CustomersService for CustomerServiceComponents {
             .map(|v| v.0)
             .map_err(Into::<CustomerApiError>::into)?;
 
-        Ok(Response::new(CreateCustomerResponse {
-            customer: Some(customer),
-        }))
+        let entity = AuditEntity::new(&customer.id);
+
+        Ok(audited(
+            CreateCustomerResponse {
+                customer: Some(customer),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn find_or_create_customer(
+        &self,
+        request: Request<FindOrCreateCustomerRequest>,
+    ) -> Result<Response<FindOrCreateCustomerResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let inner = request
+            .into_inner()
+            .data
+            .ok_or(CustomerApiError::MissingArgument("no data".into()))?;
+
+        let customer_new = customer_new_from_server(inner, actor)?;
+
+        let (customer, created) = self
+            .store
+            .find_or_create_customer(customer_new, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let customer = ServerCustomerBriefWrapper::try_from(customer)
+            .map(|v| v.0)
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let entity = AuditEntity::new(&customer.id);
+
+        Ok(audited(
+            FindOrCreateCustomerResponse {
+                customer: Some(customer),
+                created,
+            },
+            entity,
+        ))
     }
 
     #[tracing::instrument(skip_all)]
@@ -101,13 +132,21 @@ impl CustomersService for CustomerServiceComponents {
                 "customer payload missing".to_string(),
             ))?;
 
-        let _ = self
+        let customer_id = parse_uuid(&customer.id, "id")?;
+
+        let before = self
+            .store
+            .find_customer_by_id(customer_id, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let after = self
             .store
             .patch_customer(
                 actor,
                 tenant_id,
                 CustomerPatch {
-                    id: parse_uuid(&customer.id, "id")?,
+                    id: customer_id,
                     name: customer.name.clone(),
                     alias: customer.alias.clone(),
                     email: customer.email.clone(),
@@ -127,7 +166,15 @@ impl CustomersService for CustomerServiceComponents {
             .await
             .map_err(Into::<CustomerApiError>::into)?;
 
-        Ok(Response::new(PatchCustomerResponse {}))
+        let entity = match after {
+            Some(after) => AuditEntity::new(customer_id).with_diff(
+                &audit::customer_to_json(&before),
+                &audit::customer_to_json(&after),
+            ),
+            None => AuditEntity::new(customer_id),
+        };
+
+        Ok(audited(PatchCustomerResponse {}, entity))
     }
 
     #[tracing::instrument(skip_all)]
@@ -136,6 +183,7 @@ impl CustomersService for CustomerServiceComponents {
         request: Request<ListCustomerRequest>,
     ) -> Result<Response<ListCustomerResponse>, Status> {
         let tenant_id = request.tenant()?;
+        let role = request.tenant_role()?;
 
         let inner = request.into_inner();
 
@@ -163,7 +211,7 @@ impl CustomersService for CustomerServiceComponents {
             customers: res
                 .items
                 .into_iter()
-                .map(|l| ServerCustomerBriefWrapper::try_from(l).map(|v| v.0))
+                .map(|l| ServerCustomerBriefWrapper::try_from(l).map(|v| v.0.redacted_for(role)))
                 .collect::<Vec<Result<CustomerBrief, Report<StoreError>>>>()
                 .into_iter()
                 .collect::<Result<Vec<_>, _>>()
@@ -179,6 +227,7 @@ impl CustomersService for CustomerServiceComponents {
         request: Request<GetCustomerByIdRequest>,
     ) -> Result<Response<GetCustomerByIdResponse>, Status> {
         let tenant_id = request.tenant()?;
+        let role = request.tenant_role()?;
 
         let req = request.into_inner();
         let customer_id = parse_uuid(&req.id, "id")?;
@@ -188,7 +237,7 @@ impl CustomersService for CustomerServiceComponents {
             .find_customer_by_id(customer_id, tenant_id)
             .await
             .and_then(ServerCustomerWrapper::try_from)
-            .map(|v| v.0)
+            .map(|v| v.0.redacted_for(role))
             .map_err(Into::<CustomerApiError>::into)?;
 
         Ok(Response::new(GetCustomerByIdResponse {
@@ -201,6 +250,7 @@ impl CustomersService for CustomerServiceComponents {
         &self,
         request: Request<GetCustomerByAliasRequest>,
     ) -> Result<Response<GetCustomerByAliasResponse>, Status> {
+        let role = request.tenant_role()?;
         let req = request.into_inner();
 
         let customer = self
@@ -208,7 +258,7 @@ impl CustomersService for CustomerServiceComponents {
             .find_customer_by_alias(req.alias.clone())
             .await
             .and_then(ServerCustomerWrapper::try_from)
-            .map(|v| v.0)
+            .map(|v| v.0.redacted_for(role))
             .map_err(Into::<CustomerApiError>::into)?;
 
         Ok(Response::new(GetCustomerByAliasResponse {
@@ -241,9 +291,12 @@ impl CustomersService for CustomerServiceComponents {
             .map(|v| v.0)
             .map_err(Into::<CustomerApiError>::into)?;
 
-        Ok(Response::new(TopUpCustomerBalanceResponse {
-            customer: Some(customer),
-        }))
+        Ok(audited(
+            TopUpCustomerBalanceResponse {
+                customer: Some(customer),
+            },
+            AuditEntity::new(customer_id),
+        ))
     }
 
     #[tracing::instrument(skip_all)]
@@ -275,8 +328,294 @@ impl CustomersService for CustomerServiceComponents {
             })
             .map_err(Into::<CustomerApiError>::into)?;
 
-        Ok(Response::new(BuyCustomerCreditsResponse {
-            invoice: Some(invoice),
+        Ok(audited(
+            BuyCustomerCreditsResponse {
+                invoice: Some(invoice),
+            },
+            AuditEntity::new(customer_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_customer_payment_terms(
+        &self,
+        request: Request<UpdateCustomerPaymentTermsRequest>,
+    ) -> Result<Response<UpdateCustomerPaymentTermsResponse>, Status> {
+        let actor = request.actor()?;
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let terms = req
+            .payment_terms
+            .ok_or(CustomerApiError::MissingArgument("payment_terms".into()))?;
+        let terms = DomainPaymentTermsWrapper::try_from(terms)?.0;
+
+        let before = self
+            .store
+            .find_customer_by_id(customer_id, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let after = self
+            .store
+            .update_customer_payment_terms(actor, tenant_id, customer_id, terms)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let entity = AuditEntity::new(customer_id).with_diff(
+            &audit::payment_terms_to_json(&before),
+            &audit::payment_terms_to_json(&after),
+        );
+
+        let customer = ServerCustomerWrapper::try_from(after)
+            .map(|v| v.0)
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        Ok(audited(
+            UpdateCustomerPaymentTermsResponse {
+                customer: Some(customer),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_customer_credit_limit(
+        &self,
+        request: Request<UpdateCustomerCreditLimitRequest>,
+    ) -> Result<Response<UpdateCustomerCreditLimitResponse>, Status> {
+        let actor = request.actor()?;
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let credit_limit = req
+            .credit_limit
+            .map(|l| DomainCreditLimitWrapper::from(l).0);
+
+        let before = self
+            .store
+            .find_customer_by_id(customer_id, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let after = self
+            .store
+            .update_customer_credit_limit(actor, tenant_id, customer_id, credit_limit)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let entity = AuditEntity::new(customer_id).with_diff(
+            &audit::credit_limit_to_json(&before),
+            &audit::credit_limit_to_json(&after),
+        );
+
+        let customer = ServerCustomerWrapper::try_from(after)
+            .map(|v| v.0)
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        Ok(audited(
+            UpdateCustomerCreditLimitResponse {
+                customer: Some(customer),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_customer_invoice_delivery(
+        &self,
+        request: Request<UpdateCustomerInvoiceDeliveryRequest>,
+    ) -> Result<Response<UpdateCustomerInvoiceDeliveryResponse>, Status> {
+        let actor = request.actor()?;
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let channel = req
+            .channel
+            .is_some()
+            .then(|| invoice_delivery_channel_to_domain(req.channel()));
+
+        let before = self
+            .store
+            .find_customer_by_id(customer_id, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let after = self
+            .store
+            .update_customer_invoice_delivery(actor, tenant_id, customer_id, channel)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let entity = AuditEntity::new(customer_id).with_diff(
+            &audit::invoice_delivery_to_json(&before),
+            &audit::invoice_delivery_to_json(&after),
+        );
+
+        let customer = ServerCustomerWrapper::try_from(after)
+            .map(|v| v.0)
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        Ok(audited(
+            UpdateCustomerInvoiceDeliveryResponse {
+                customer: Some(customer),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_customer_credit_exposure(
+        &self,
+        request: Request<GetCustomerCreditExposureRequest>,
+    ) -> Result<Response<GetCustomerCreditExposureResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let customer = self
+            .store
+            .find_customer_by_id(customer_id, tenant_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let exposure = self
+            .store
+            .get_customer_credit_exposure(tenant_id, customer_id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        Ok(Response::new(GetCustomerCreditExposureResponse {
+            exposure: Some(credit_exposure_to_server(exposure, customer.credit_limit)),
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_customer_blackout_windows(
+        &self,
+        request: Request<ListCustomerBlackoutWindowsRequest>,
+    ) -> Result<Response<ListCustomerBlackoutWindowsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let customer_id = parse_uuid(&req.customer_id, "customer_id")?;
+
+        let ending_from = (!req.include_past).then(|| chrono::Utc::now().date_naive());
+
+        let windows = self
+            .store
+            .list_customer_blackout_windows(tenant_id, customer_id, ending_from)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?
+            .into_iter()
+            .map(blackout_window_to_server)
+            .collect();
+
+        Ok(Response::new(ListCustomerBlackoutWindowsResponse {
+            windows,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn add_customer_blackout_window(
+        &self,
+        request: Request<AddCustomerBlackoutWindowRequest>,
+    ) -> Result<Response<AddCustomerBlackoutWindowResponse>, Status> {
+        let actor = request.actor()?;
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+
+        let window = self
+            .store
+            .insert_customer_blackout_window(CustomerBlackoutWindowNew {
+                tenant_id,
+                customer_id: parse_uuid(&req.customer_id, "customer_id")?,
+                start_date: chrono::NaiveDate::from_proto(req.start_date)?,
+                end_date: chrono::NaiveDate::from_proto(req.end_date)?,
+                reason: req.reason,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        let entity = AuditEntity::new(window.customer_id);
+
+        Ok(audited(
+            AddCustomerBlackoutWindowResponse {
+                window: Some(blackout_window_to_server(window)),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn remove_customer_blackout_window(
+        &self,
+        request: Request<RemoveCustomerBlackoutWindowRequest>,
+    ) -> Result<Response<RemoveCustomerBlackoutWindowResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .delete_customer_blackout_window(tenant_id, id)
+            .await
+            .map_err(Into::<CustomerApiError>::into)?;
+
+        Ok(audited(
+            RemoveCustomerBlackoutWindowResponse {},
+            AuditEntity::new(id),
+        ))
+    }
+}
+
+fn customer_new_from_server(
+    inner: meteroid_grpc::meteroid::api::customers::v1::CustomerNew,
+    actor: Uuid,
+) -> Result<CustomerNew, Status> {
+    let billing_config = match inner.billing_config {
+        Some(b) => DomainBillingConfigWrapper::try_from(b)?.0,
+        None => domain::BillingConfig::Manual,
+    };
+
+    let payment_terms = match inner.payment_terms {
+        Some(terms) => DomainPaymentTermsWrapper::try_from(terms)?.0,
+        None => domain::CustomerPaymentTerms::default(),
+    };
+
+    Ok(CustomerNew {
+        name: inner.name,
+        created_by: actor,
+        invoicing_entity_id: Uuid::from_proto_opt(inner.invoicing_entity_id)?,
+        billing_config,
+        alias: inner.alias,
+        email: inner.email,
+        invoicing_email: inner.invoicing_email,
+        phone: inner.phone,
+        balance_value_cents: 0,
+        currency: inner.currency,
+        billing_address: inner
+            .billing_address
+            .map(DomainAddressWrapper::try_from)
+            .transpose()?
+            .map(|v| v.0),
+        shipping_address: inner
+            .shipping_address
+            .map(DomainShippingAddressWrapper::try_from)
+            .transpose()?
+            .map(|v| v.0),
+        force_created_date: None,
+        net_terms_override: payment_terms.net_terms_override,
+        early_payment_discount: payment_terms.early_payment_discount,
+        due_date_policy: payment_terms.due_date_policy,
+    })
 }
@@ -5,22 +5,49 @@ use meteroid_store::domain;
 
 pub(crate) mod billing_period {
     use super::*;
+    use tonic::Status;
 
-    pub fn from_proto(period: api_shared::BillingPeriod) -> domain::enums::BillingPeriodEnum {
+    /// The months are only read for a custom period
+    pub fn from_proto(
+        period: api_shared::BillingPeriod,
+        months: Option<u32>,
+    ) -> Result<domain::enums::BillingPeriodEnum, Status> {
         match period {
-            api_shared::BillingPeriod::Monthly => domain::enums::BillingPeriodEnum::Monthly,
-            api_shared::BillingPeriod::Quarterly => domain::enums::BillingPeriodEnum::Quarterly,
-            api_shared::BillingPeriod::Annual => domain::enums::BillingPeriodEnum::Annual,
+            api_shared::BillingPeriod::Weekly => Ok(domain::enums::BillingPeriodEnum::Weekly),
+            api_shared::BillingPeriod::Monthly => Ok(domain::enums::BillingPeriodEnum::Monthly),
+            api_shared::BillingPeriod::Bimonthly => Ok(domain::enums::BillingPeriodEnum::Bimonthly),
+            api_shared::BillingPeriod::Quarterly => Ok(domain::enums::BillingPeriodEnum::Quarterly),
+            api_shared::BillingPeriod::Semiannual => {
+                Ok(domain::enums::BillingPeriodEnum::Semiannual)
+            }
+            api_shared::BillingPeriod::Annual => Ok(domain::enums::BillingPeriodEnum::Annual),
+            api_shared::BillingPeriod::Custom => {
+                let months = months.ok_or(Status::invalid_argument(
+                    "the months of the custom billing period are missing",
+                ))?;
+
+                domain::enums::BillingPeriodEnum::from_months(months)
+                    .map_err(|e| Status::invalid_argument(e.to_string()))
+            }
         }
     }
 
     pub fn to_proto(period: domain::enums::BillingPeriodEnum) -> api_shared::BillingPeriod {
         match period {
+            domain::enums::BillingPeriodEnum::Weekly => api_shared::BillingPeriod::Weekly,
             domain::enums::BillingPeriodEnum::Monthly => api_shared::BillingPeriod::Monthly,
+            domain::enums::BillingPeriodEnum::Bimonthly => api_shared::BillingPeriod::Bimonthly,
             domain::enums::BillingPeriodEnum::Quarterly => api_shared::BillingPeriod::Quarterly,
+            domain::enums::BillingPeriodEnum::Semiannual => api_shared::BillingPeriod::Semiannual,
             domain::enums::BillingPeriodEnum::Annual => api_shared::BillingPeriod::Annual,
+            domain::enums::BillingPeriodEnum::Custom { .. } => api_shared::BillingPeriod::Custom,
         }
     }
+
+    /// The months set alongside a custom period
+    pub fn months_to_proto(period: &domain::enums::BillingPeriodEnum) -> Option<u32> {
+        period.custom_months().map(|months| months as u32)
+    }
 }
 
 pub(crate) mod discount {
@@ -1,5 +1,75 @@
+use common_grpc_error_as_tonic_macros::{ErrorCatalog, ErrorCodeDescriptor};
+
+use crate::api::addons::error::AddOnApiError;
+use crate::api::apitokens::error::ApiTokenApiError;
+use crate::api::auditlogs::error::AuditLogApiError;
+use crate::api::billablemetrics::error::BillableMetricApiError;
+use crate::api::collectionstrategies::error::CollectionStrategyApiError;
+use crate::api::componentrules::error::ComponentRuleApiError;
+use crate::api::coupons::error::CouponApiError;
+use crate::api::customers::error::CustomerApiError;
+use crate::api::instance::error::InstanceApiError;
+use crate::api::invoices::error::InvoiceApiError;
+use crate::api::invoicingentities::error::InvoicingEntitiesApiError;
+use crate::api::notificationbundles::error::NotificationBundleApiError;
+use crate::api::organizations::error::OrganizationApiError;
+use crate::api::partners::error::PartnerApiError;
+use crate::api::paymentreminders::error::PaymentReminderApiError;
+use crate::api::plans::error::PlanApiError;
+use crate::api::pricecomponents::error::PriceComponentApiError;
+use crate::api::productfamilies::error::ProductFamilyApiError;
+use crate::api::productitems::error::ProductApiError;
+use crate::api::providers::error::ProviderApiError;
+use crate::api::ratecards::error::RateCardApiError;
+use crate::api::reports::error::ReportApiError;
+use crate::api::roles::error::RoleApiError;
+use crate::api::schedules::error::ScheduleApiError;
+use crate::api::subscriptions::error::SubscriptionApiError;
+use crate::api::tenants::error::TenantApiError;
+use crate::api::usage::error::UsageApiError;
+use crate::api::usagealerts::error::UsageAlertApiError;
+use crate::api::users::error::UserApiError;
+use crate::api::webhooksout::error::WebhookApiError;
+
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
 pub enum DatabaseError {
     #[error("Json parsing error : {0}")]
     JsonParsingError(String),
 }
+
+/// The error codes of the API services, returned in the `ErrorInfo` details of their errors
+pub fn error_codes() -> Vec<ErrorCodeDescriptor> {
+    [
+        AddOnApiError::ERROR_CODES,
+        ApiTokenApiError::ERROR_CODES,
+        AuditLogApiError::ERROR_CODES,
+        BillableMetricApiError::ERROR_CODES,
+        CollectionStrategyApiError::ERROR_CODES,
+        ComponentRuleApiError::ERROR_CODES,
+        CouponApiError::ERROR_CODES,
+        CustomerApiError::ERROR_CODES,
+        InstanceApiError::ERROR_CODES,
+        InvoiceApiError::ERROR_CODES,
+        InvoicingEntitiesApiError::ERROR_CODES,
+        NotificationBundleApiError::ERROR_CODES,
+        OrganizationApiError::ERROR_CODES,
+        PartnerApiError::ERROR_CODES,
+        PaymentReminderApiError::ERROR_CODES,
+        PlanApiError::ERROR_CODES,
+        PriceComponentApiError::ERROR_CODES,
+        ProductFamilyApiError::ERROR_CODES,
+        ProductApiError::ERROR_CODES,
+        ProviderApiError::ERROR_CODES,
+        RateCardApiError::ERROR_CODES,
+        ReportApiError::ERROR_CODES,
+        RoleApiError::ERROR_CODES,
+        ScheduleApiError::ERROR_CODES,
+        SubscriptionApiError::ERROR_CODES,
+        TenantApiError::ERROR_CODES,
+        UsageApiError::ERROR_CODES,
+        UsageAlertApiError::ERROR_CODES,
+        UserApiError::ERROR_CODES,
+        WebhookApiError::ERROR_CODES,
+    ]
+    .concat()
+}
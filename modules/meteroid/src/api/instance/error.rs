@@ -11,6 +11,10 @@ pub enum InstanceApiError {
     #[error("Store error: {0}")]
     #[code(Internal)]
     StoreError(String, #[source] Box<dyn Error>),
+
+    #[error("Migration error: {0}")]
+    #[code(Internal)]
+    MigrationError(String, #[source] Box<dyn Error>),
 }
 
 impl From<Report<StoreError>> for InstanceApiError {
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::instance::v1::instance_service_server::InstanceServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod service;
 
 pub struct InstanceServiceComponents {
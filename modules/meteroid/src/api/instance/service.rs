@@ -3,16 +3,24 @@ use tonic::{Request, Response, Status};
 use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::instance::v1::get_countries_response::Country as GrpcCountry;
 use meteroid_grpc::meteroid::api::instance::v1::get_currencies_response::Currency as GrpcCurrency;
+use meteroid_grpc::meteroid::api::instance::v1::get_migration_status_response::{
+    PostgresMigrationStatus, TenantMeterViewsStatus,
+};
 use meteroid_grpc::meteroid::api::instance::v1::instance_service_server::InstanceService;
+use meteroid_grpc::meteroid::api::instance::v1::list_error_codes_response::ErrorCode as GrpcErrorCode;
 use meteroid_grpc::meteroid::api::instance::v1::{
     GetCountriesRequest, GetCountriesResponse, GetCurrenciesRequest, GetCurrenciesResponse,
     GetInstanceRequest, GetInstanceResponse, GetInviteRequest, GetInviteResponse,
+    GetMigrationStatusRequest, GetMigrationStatusResponse, ListErrorCodesRequest,
+    ListErrorCodesResponse,
 };
 use meteroid_store::constants::{COUNTRIES, CURRENCIES};
-use meteroid_store::repositories::OrganizationsInterface;
+use meteroid_store::repositories::{OrganizationsInterface, TenantInterface};
 
+use crate::api::errors::error_codes;
 use crate::api::instance::error::InstanceApiError;
 use crate::api::instance::InstanceServiceComponents;
+use crate::migrations;
 
 #[tonic::async_trait]
 impl InstanceService for InstanceServiceComponents {
@@ -80,4 +88,72 @@ impl InstanceService for InstanceServiceComponents {
 
         Ok(Response::new(GetCurrenciesResponse { currencies }))
     }
+
+    async fn list_error_codes(
+        &self,
+        _request: Request<ListErrorCodesRequest>,
+    ) -> Result<Response<ListErrorCodesResponse>, Status> {
+        let error_codes = error_codes()
+            .into_iter()
+            .map(|error_code| GrpcErrorCode {
+                code: error_code.code.to_string(),
+                grpc_code: format!("{:?}", error_code.grpc_code),
+                description: error_code.description.to_string(),
+            })
+            .collect();
+
+        Ok(Response::new(ListErrorCodesResponse { error_codes }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_migration_status(
+        &self,
+        request: Request<GetMigrationStatusRequest>,
+    ) -> Result<Response<GetMigrationStatusResponse>, Status> {
+        let organization_id = request.organization()?;
+
+        let status = migrations::status(&self.store.pool).await.map_err(|e| {
+            InstanceApiError::MigrationError("Failed to get the migration status".to_string(), e)
+        })?;
+
+        let tenants = self
+            .store
+            .list_tenants_by_organization_id(organization_id)
+            .await
+            .map_err(Into::<InstanceApiError>::into)?;
+
+        let mut meter_views = Vec::with_capacity(tenants.len());
+        for tenant in tenants {
+            // a tenant failing its check is reported, without hiding the others
+            let tenant_status = match self.store.check_tenant_meter_views(tenant.id).await {
+                Ok(drift) => TenantMeterViewsStatus {
+                    tenant_id: tenant.id.to_string(),
+                    missing_metric_ids: drift
+                        .missing_metric_ids
+                        .iter()
+                        .map(|id| id.to_string())
+                        .collect(),
+                    orphaned_views: drift.orphaned_views,
+                    error: None,
+                },
+                Err(e) => TenantMeterViewsStatus {
+                    tenant_id: tenant.id.to_string(),
+                    missing_metric_ids: vec![],
+                    orphaned_views: vec![],
+                    error: Some(e.current_context().to_string()),
+                },
+            };
+            meter_views.push(tenant_status);
+        }
+
+        Ok(Response::new(GetMigrationStatusResponse {
+            postgres: Some(PostgresMigrationStatus {
+                drift_detected: status.has_drift(),
+                applied: status.applied,
+                pending: status.pending,
+                unknown: status.unknown,
+            }),
+            meter_views,
+        }))
+    }
 }
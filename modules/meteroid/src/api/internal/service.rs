@@ -4,13 +4,20 @@ use tonic::{Request, Response, Status};
 
 use meteroid_grpc::meteroid::internal::v1::internal_service_server::InternalService;
 use meteroid_grpc::meteroid::internal::v1::{
+    event_property_schema, EventPropertySchema, ListEventSchemasRequest, ListEventSchemasResponse,
+    MetricEventSchema, RerateBackfilledUsageRequest, RerateBackfilledUsageResponse,
     ResolveApiKeyRequest, ResolveApiKeyResponse, ResolveCustomerExternalIdsRequest,
     ResolveCustomerExternalIdsResponse, ResolvedId,
 };
+use meteroid_store::domain::billable_metrics::PropertyType;
+use meteroid_store::domain::usage_rerating::BackfilledUsage;
 use meteroid_store::repositories::api_tokens::ApiTokensInterface;
+use meteroid_store::repositories::billable_metrics::BillableMetricInterface;
+use meteroid_store::repositories::usage_rerating::UsageReratingInterface;
 
 use crate::api::internal::error::InternalApiError;
 use crate::api::internal::InternalServiceComponents;
+use crate::api::shared::mapping::datetime::{chrono_from_timestamp, chrono_to_timestamp};
 use crate::{api::utils::parse_uuid, parse_uuid};
 use meteroid_store::repositories::customers::CustomersInterface;
 
@@ -70,6 +77,88 @@ impl InternalService for InternalServiceComponents {
             tenant_id: res.tenant_id.to_string(),
             organization_id: res.organization_id.to_string(),
             hash: res.hash,
+            scopes: res.scopes,
+            expires_at: res.expires_at.map(chrono_to_timestamp),
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn rerate_backfilled_usage(
+        &self,
+        request: Request<RerateBackfilledUsageRequest>,
+    ) -> Result<Response<RerateBackfilledUsageResponse>, Status> {
+        let inner = request.into_inner();
+
+        let tenant_id = parse_uuid!(inner.tenant_id)?;
+
+        let usages = inner
+            .usages
+            .into_iter()
+            .map(|usage| {
+                let missing = || Status::invalid_argument("Missing backfilled usage period");
+                Ok(BackfilledUsage {
+                    customer_id: parse_uuid!(usage.customer_id)?,
+                    from: chrono_from_timestamp(usage.from.ok_or_else(missing)?)?,
+                    to: chrono_from_timestamp(usage.to.ok_or_else(missing)?)?,
+                })
+            })
+            .collect::<Result<Vec<_>, Status>>()?;
+
+        let outdated = self
+            .store
+            .mark_backfilled_usage(tenant_id, usages)
+            .await
+            .map_err(Into::<InternalApiError>::into)?;
+
+        Ok(Response::new(RerateBackfilledUsageResponse {
+            outdated_invoices: outdated as u32,
+        }))
+    }
+    #[tracing::instrument(skip_all)]
+    async fn list_event_schemas(
+        &self,
+        request: Request<ListEventSchemasRequest>,
+    ) -> Result<Response<ListEventSchemasResponse>, Status> {
+        let inner = request.into_inner();
+
+        let tenant_id = parse_uuid!(inner.tenant_id)?;
+
+        let metrics = self
+            .store
+            .list_billable_metrics_with_event_schema(tenant_id)
+            .await
+            .map_err(Into::<InternalApiError>::into)?;
+
+        let schemas = metrics
+            .into_iter()
+            .filter_map(|metric| {
+                metric.event_schema.map(|schema| MetricEventSchema {
+                    metric_id: metric.id.to_string(),
+                    event_name: metric.code,
+                    properties: schema
+                        .properties
+                        .into_iter()
+                        .map(|p| EventPropertySchema {
+                            key: p.key,
+                            property_type: match p.property_type {
+                                PropertyType::String => event_property_schema::PropertyType::String,
+                                PropertyType::Number => event_property_schema::PropertyType::Number,
+                                PropertyType::Integer => {
+                                    event_property_schema::PropertyType::Integer
+                                }
+                                PropertyType::Boolean => {
+                                    event_property_schema::PropertyType::Boolean
+                                }
+                            }
+                            .into(),
+                            required: p.required,
+                            allowed_values: p.allowed_values,
+                        })
+                        .collect(),
+                })
+            })
+            .collect();
+
+        Ok(Response::new(ListEventSchemasResponse { schemas }))
+    }
 }
@@ -3,24 +3,46 @@ use std::error::Error;
 use error_stack::Report;
 use thiserror::Error;
 
-use crate::errors::InvoicingRenderError;
+use crate::errors::{InvoicingRenderError, ObjectStoreError};
 use common_grpc_error_as_tonic_macros_impl::ErrorAsTonic;
 use meteroid_store::errors::StoreError;
 
 #[derive(Debug, Error, ErrorAsTonic)]
 pub enum InvoiceApiError {
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+    #[error("Not found: {0}")]
+    #[code(NotFound)]
+    NotFound(String),
+    #[error("Provider error: {0}")]
+    #[code(Unavailable)]
+    ProviderError(String),
+    #[error("Conflict: {0}")]
+    #[code(Aborted)]
+    Conflict(String),
     #[error("Store error: {0}")]
     #[code(Internal)]
     StoreError(String, #[source] Box<dyn Error>),
     #[error("Render error: {0}")]
     #[code(Internal)]
     RenderError(String, #[source] Box<dyn Error>),
+    #[error("Object store error: {0}")]
+    #[code(Internal)]
+    ObjectStoreError(String, #[source] Box<dyn Error>),
 }
 
 impl From<Report<StoreError>> for InvoiceApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        Self::StoreError("Error in invoice service".to_string(), err)
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            StoreError::ValueNotFound(msg) => Self::NotFound(msg.clone()),
+            StoreError::Conflict(msg) => Self::Conflict(msg.clone()),
+            _ => Self::StoreError(
+                "Error in invoice service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
     }
 }
 
@@ -30,3 +52,13 @@ impl From<Report<InvoicingRenderError>> for InvoiceApiError {
         Self::RenderError("Error in invoice service".to_string(), err)
     }
 }
+
+impl From<Report<ObjectStoreError>> for InvoiceApiError {
+    fn from(value: Report<ObjectStoreError>) -> Self {
+        let err = Box::new(value.into_error());
+        Self::ObjectStoreError(
+            "Error with object store in invoice service".to_string(),
+            err,
+        )
+    }
+}
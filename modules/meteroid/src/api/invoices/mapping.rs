@@ -1,15 +1,21 @@
 pub mod invoices {
-    use crate::api::customers::mapping::customer::ServerAddressWrapper;
+    use crate::api::customers::mapping::customer::{
+        invoice_delivery_channel_to_server, ServerAddressWrapper,
+    };
     use crate::api::sharable::ShareableEntityClaims;
-    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use crate::api::shared::conversions::{AsProtoOpt, FromProtoOpt, ProtoConv};
+    use chrono::NaiveDate;
     use error_stack::ResultExt;
     use meteroid_grpc::meteroid::api::invoices::v1::{
-        DetailedInvoice, InlineCustomer, Invoice, InvoiceStatus, InvoiceType, InvoicingProvider,
-        LineItem,
+        DetailedInvoice, DraftInvoiceLine, InlineCustomer, Invoice, InvoiceAttachment,
+        InvoiceDeliveryStatus, InvoicePayment, InvoiceStatus, InvoiceStuckReason, InvoiceType,
+        InvoicingProvider, LineItem, StuckInvoice,
     };
     use meteroid_store::domain;
+    use meteroid_store::domain::invoice_edits::DraftInvoiceLine as DomainDraftInvoiceLine;
     use meteroid_store::domain::invoice_lines as domain_invoice_lines;
     use meteroid_store::errors::StoreError;
+    use rust_decimal::Decimal;
     use secrecy::{ExposeSecret, SecretString};
 
     fn status_domain_to_server(value: domain::enums::InvoiceStatusEnum) -> InvoiceStatus {
@@ -21,6 +27,15 @@ pub mod invoices {
         }
     }
 
+    fn delivery_status_domain_to_server(
+        value: domain::enums::InvoiceDeliveryStatusEnum,
+    ) -> InvoiceDeliveryStatus {
+        match value {
+            domain::enums::InvoiceDeliveryStatusEnum::Delivered => InvoiceDeliveryStatus::Delivered,
+            domain::enums::InvoiceDeliveryStatusEnum::Failed => InvoiceDeliveryStatus::Failed,
+        }
+    }
+
     pub fn status_server_to_domain(
         status: Option<i32>,
     ) -> Option<domain::enums::InvoiceStatusEnum> {
@@ -42,6 +57,8 @@ pub mod invoices {
         match value {
             domain::enums::InvoicingProviderEnum::Stripe => InvoicingProvider::Stripe,
             domain::enums::InvoicingProviderEnum::Manual => InvoicingProvider::Manual,
+            domain::enums::InvoicingProviderEnum::Adyen => InvoicingProvider::Adyen,
+            domain::enums::InvoicingProviderEnum::GoCardless => InvoicingProvider::GoCardless,
         }
     }
 
@@ -101,6 +118,7 @@ pub mod invoices {
                     is_prorated: line.is_prorated,
                     product_id: line.product_id.as_proto(),
                     description: line.description,
+                    quantity_display_precision: line.quantity_display_precision,
                     sub_line_items: line.sub_lines.into_iter().map(
                         |sub_line| {
                             let attributes = match sub_line.attributes {
@@ -141,7 +159,23 @@ pub mod invoices {
                                         }
                                     ))
                                 }
-                                None => None
+                                Some(domain_invoice_lines::SubLineAttributes::UsageGroup { group_key, group_value }) => {
+                                    Some(meteroid_grpc::meteroid::api::invoices::v1::sub_line_item::SublineAttributes::UsageGroup(
+                                        meteroid_grpc::meteroid::api::invoices::v1::sub_line_item::UsageGroup {
+                                            group_key,
+                                            group_value,
+                                        }
+                                    ))
+                                }
+                                Some(domain_invoice_lines::SubLineAttributes::Percent { percentage, base_amount }) => {
+                                    Some(meteroid_grpc::meteroid::api::invoices::v1::sub_line_item::SublineAttributes::Percent(
+                                        meteroid_grpc::meteroid::api::invoices::v1::sub_line_item::Percent {
+                                            percentage: percentage.as_proto(),
+                                            base_amount: base_amount.as_proto(),
+                                        }
+                                    ))
+                                }
+                                Some(domain_invoice_lines::SubLineAttributes::Promotion { .. }) | None => None
                             };
 
                             meteroid_grpc::meteroid::api::invoices::v1::SubLineItem {
@@ -208,6 +242,65 @@ pub mod invoices {
             document_sharing_key: share_key,
             pdf_document_id: invoice.pdf_document_id,
             xml_document_id: invoice.xml_document_id,
+            suppressed_at: invoice.suppressed_at.as_proto(),
+            voided_at: invoice.voided_at.as_proto(),
+            void_reason: invoice.void_reason,
+            replaces_invoice_id: invoice.replaces_invoice_id.as_proto(),
+            manual_invoice_date: invoice.manual_invoice_date,
+            attachments: vec![],
+            issue_deferred_until: None,
+            manually_edited_at: invoice.manually_edited_at.as_proto(),
+            delivery_channel: invoice
+                .delivery_channel
+                .map(|c| invoice_delivery_channel_to_server(c).into()),
+            delivery_status: invoice
+                .delivery_status
+                .map(|s| delivery_status_domain_to_server(s).into()),
+            delivered_at: invoice.delivered_at.as_proto(),
+            hosted_invoice_url: invoice.hosted_invoice_url,
+            external_pdf_url: invoice.external_pdf_url,
+        })
+    }
+
+    pub fn attachment_to_server(
+        attachment: domain::invoice_attachments::InvoiceAttachment,
+    ) -> InvoiceAttachment {
+        InvoiceAttachment {
+            id: attachment.id.as_proto(),
+            invoice_id: attachment.invoice_id.as_proto(),
+            file_name: attachment.file_name,
+            content_type: attachment.content_type,
+            size_bytes: attachment.size_bytes,
+            include_in_email: attachment.include_in_email,
+            created_at: attachment.created_at.as_proto(),
+            created_by: attachment.created_by.as_proto(),
+        }
+    }
+
+    pub fn payment_to_server(payment: domain::invoice_payments::InvoicePayment) -> InvoicePayment {
+        InvoicePayment {
+            id: payment.id.as_proto(),
+            invoice_id: payment.invoice_id.as_proto(),
+            amount: payment.amount,
+            paid_at: payment.paid_at.as_proto(),
+            reference: payment.reference,
+            note: payment.note,
+            created_at: payment.created_at.as_proto(),
+            created_by: payment.created_by.as_proto(),
+        }
+    }
+
+    pub fn draft_line_server_to_domain(
+        line: DraftInvoiceLine,
+    ) -> Result<DomainDraftInvoiceLine, tonic::Status> {
+        Ok(DomainDraftInvoiceLine {
+            local_id: line.id,
+            name: line.name,
+            description: line.description,
+            quantity: Decimal::from_proto_opt(line.quantity)?,
+            unit_price: Decimal::from_proto_opt(line.unit_price)?,
+            start_date: NaiveDate::from_proto_opt(line.start_date)?,
+            end_date: NaiveDate::from_proto_opt(line.end_date)?,
         })
     }
 
@@ -229,4 +322,232 @@ pub mod invoices {
             total: value.invoice.total,
         }
     }
+
+    pub fn stuck_domain_to_server(value: domain::invoice_watchdog::StuckInvoice) -> StuckInvoice {
+        let reason = match value.reason {
+            domain::enums::InvoiceStuckReasonEnum::StaleDraft => InvoiceStuckReason::StaleDraft,
+            domain::enums::InvoiceStuckReasonEnum::PendingFinalization => {
+                InvoiceStuckReason::PendingFinalization
+            }
+            domain::enums::InvoiceStuckReasonEnum::IssueRetriesExhausted => {
+                InvoiceStuckReason::IssueRetriesExhausted
+            }
+        };
+
+        StuckInvoice {
+            invoice_id: value.invoice_id.to_string(),
+            invoice_number: value.invoice_number,
+            customer_id: value.customer_id.to_string(),
+            status: status_domain_to_server(value.status).into(),
+            reason: reason.into(),
+            stuck_since: value.stuck_since.as_proto(),
+            last_issue_error: value.last_issue_error,
+        }
+    }
+}
+
+pub mod billing_runs {
+    use crate::api::shared::conversions::ProtoConv;
+    use meteroid_grpc::meteroid::api::invoices::v1::{
+        BillingRunFailure, BillingRunSummary, BillingRunTotal,
+    };
+    use meteroid_store::domain::billing_runs as domain;
+
+    pub fn summary_to_server(summary: domain::BillingRunSummary) -> BillingRunSummary {
+        BillingRunSummary {
+            id: summary.id.as_proto(),
+            period_start: summary.period_start.as_proto(),
+            period_end: summary.period_end.as_proto(),
+            invoices_created: summary.invoices_created,
+            invoices_finalized: summary.invoices_finalized,
+            invoices_issued: summary.invoices_issued,
+            issue_failures: summary.issue_failures,
+            totals: summary
+                .totals
+                .into_iter()
+                .map(|total| BillingRunTotal {
+                    currency: total.currency,
+                    created_amount: total.created_amount,
+                    finalized_amount: total.finalized_amount,
+                    issued_amount: total.issued_amount,
+                })
+                .collect(),
+            failures: summary
+                .failures
+                .into_iter()
+                .map(|failure| BillingRunFailure {
+                    invoice_id: failure.invoice_id.as_proto(),
+                    invoice_number: failure.invoice_number,
+                    reason: failure.reason,
+                })
+                .collect(),
+            created_at: summary.created_at.as_proto(),
+        }
+    }
+}
+
+pub mod approvals {
+    use crate::api::roles::mapping::role;
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use crate::api::utils::parse_uuid_opt;
+    use meteroid_grpc::meteroid::api::invoices::v1::{
+        invoice_approval, invoice_approval_details, invoice_approval_event, InvoiceApproval,
+        InvoiceApprovalDetails, InvoiceApprovalEvent, InvoiceApprovalStep, InvoiceApprovalStepNew,
+    };
+    use meteroid_grpc::meteroid::api::roles::v1::TenantMemberRole as TenantMemberRoleProto;
+    use meteroid_store::domain::enums::{
+        InvoiceApprovalActionEnum, InvoiceApprovalStatusEnum, TenantMemberRoleEnum,
+    };
+    use meteroid_store::domain::invoice_approvals as domain;
+    use tonic::Status;
+
+    fn role_to_server(value: Option<TenantMemberRoleEnum>) -> Option<i32> {
+        value.map(|r| role::to_proto(r).into())
+    }
+
+    fn role_from_server(
+        value: Option<i32>,
+        field: &str,
+    ) -> Result<Option<TenantMemberRoleEnum>, Status> {
+        value
+            .map(|r| {
+                TenantMemberRoleProto::try_from(r)
+                    .map(role::from_proto)
+                    .map_err(|e| {
+                        Status::invalid_argument(format!("Failed to parse {}: {}", field, e))
+                    })
+            })
+            .transpose()
+    }
+
+    pub fn step_to_server(step: domain::InvoiceApprovalStep) -> InvoiceApprovalStep {
+        InvoiceApprovalStep {
+            id: step.id.as_proto(),
+            name: step.name,
+            min_total: step.min_total,
+            currency: step.currency,
+            assignee_id: step.assignee_id.as_proto(),
+            assignee_role: role_to_server(step.assignee_role),
+            timeout_hours: step.timeout_hours,
+            escalation_assignee_id: step.escalation_assignee_id.as_proto(),
+            escalation_role: role_to_server(step.escalation_role),
+        }
+    }
+
+    pub fn step_new_from_server(
+        step: InvoiceApprovalStepNew,
+    ) -> Result<domain::InvoiceApprovalStepNew, Status> {
+        Ok(domain::InvoiceApprovalStepNew {
+            name: step.name,
+            min_total: step.min_total,
+            currency: step.currency,
+            assignee_id: parse_uuid_opt(&step.assignee_id, "assignee_id")?,
+            assignee_role: role_from_server(step.assignee_role, "assignee_role")?,
+            timeout_hours: step.timeout_hours,
+            escalation_assignee_id: parse_uuid_opt(
+                &step.escalation_assignee_id,
+                "escalation_assignee_id",
+            )?,
+            escalation_role: role_from_server(step.escalation_role, "escalation_role")?,
+        })
+    }
+
+    fn status_to_server(value: InvoiceApprovalStatusEnum) -> invoice_approval::Status {
+        match value {
+            InvoiceApprovalStatusEnum::Waiting => invoice_approval::Status::Waiting,
+            InvoiceApprovalStatusEnum::Pending => invoice_approval::Status::Pending,
+            InvoiceApprovalStatusEnum::Approved => invoice_approval::Status::Approved,
+            InvoiceApprovalStatusEnum::Rejected => invoice_approval::Status::Rejected,
+        }
+    }
+
+    fn action_to_server(value: InvoiceApprovalActionEnum) -> invoice_approval_event::Action {
+        match value {
+            InvoiceApprovalActionEnum::Requested => invoice_approval_event::Action::Requested,
+            InvoiceApprovalActionEnum::Approved => invoice_approval_event::Action::Approved,
+            InvoiceApprovalActionEnum::Rejected => invoice_approval_event::Action::Rejected,
+            InvoiceApprovalActionEnum::Escalated => invoice_approval_event::Action::Escalated,
+        }
+    }
+
+    fn state_to_server(value: domain::InvoiceApprovalState) -> invoice_approval_details::State {
+        match value {
+            domain::InvoiceApprovalState::NotRequired => {
+                invoice_approval_details::State::NotRequired
+            }
+            domain::InvoiceApprovalState::NotRequested => {
+                invoice_approval_details::State::NotRequested
+            }
+            domain::InvoiceApprovalState::Pending => invoice_approval_details::State::Pending,
+            domain::InvoiceApprovalState::Approved => invoice_approval_details::State::Approved,
+            domain::InvoiceApprovalState::Rejected => invoice_approval_details::State::Rejected,
+            domain::InvoiceApprovalState::Outdated => invoice_approval_details::State::Outdated,
+        }
+    }
+
+    fn approval_to_server(approval: domain::InvoiceApproval) -> InvoiceApproval {
+        InvoiceApproval {
+            id: approval.id.as_proto(),
+            position: approval.position,
+            name: approval.name,
+            assignee_id: approval.assignee_id.as_proto(),
+            assignee_role: role_to_server(approval.assignee_role),
+            escalation_assignee_id: approval.escalation_assignee_id.as_proto(),
+            escalation_role: role_to_server(approval.escalation_role),
+            status: status_to_server(approval.status).into(),
+            requested_total: approval.requested_total,
+            started_at: approval.started_at.as_proto(),
+            due_at: approval.due_at.as_proto(),
+            escalated_at: approval.escalated_at.as_proto(),
+            decided_at: approval.decided_at.as_proto(),
+            decided_by: approval.decided_by.as_proto(),
+            comment: approval.comment,
+        }
+    }
+
+    fn event_to_server(event: domain::InvoiceApprovalEvent) -> InvoiceApprovalEvent {
+        InvoiceApprovalEvent {
+            id: event.id.as_proto(),
+            step_name: event.step_name,
+            action: action_to_server(event.action).into(),
+            actor_id: event.actor_id.as_proto(),
+            comment: event.comment,
+            created_at: event.created_at.as_proto(),
+        }
+    }
+
+    pub fn details_to_server(details: domain::InvoiceApprovalDetails) -> InvoiceApprovalDetails {
+        InvoiceApprovalDetails {
+            state: state_to_server(details.state).into(),
+            steps: details.steps.into_iter().map(approval_to_server).collect(),
+            history: details.history.into_iter().map(event_to_server).collect(),
+        }
+    }
+}
+
+pub mod audit {
+    use meteroid_store::domain::Invoice;
+    use serde_json::{json, Value};
+
+    /// The fields of a draft editable by a user, diffed in the audit log
+    pub fn draft_to_json(invoice: &Invoice) -> Value {
+        json!({
+            "line_items": invoice.line_items.iter().map(|l| json!({
+                "id": l.local_id,
+                "name": l.name,
+                "description": l.description,
+                "quantity": l.quantity,
+                "unit_price": l.unit_price,
+                "total": l.total,
+                "start_date": l.start_date,
+                "end_date": l.end_date,
+            })).collect::<Vec<_>>(),
+            "memo": invoice.memo,
+            "due_at": invoice.due_at,
+            "subtotal": invoice.subtotal,
+            "tax_amount": invoice.tax_amount,
+            "total": invoice.total,
+            "amount_due": invoice.amount_due,
+        })
+    }
 }
@@ -1,10 +1,12 @@
 use crate::services::invoice_rendering::HtmlRenderingService;
+use crate::services::storage::ObjectStoreService;
 use meteroid_grpc::meteroid::api::invoices::v1::invoices_service_server::InvoicesServiceServer;
+use meteroid_store::domain::invoice_watchdog::InvoiceWatchdogSla;
 use meteroid_store::Store;
 use secrecy::SecretString;
 use std::sync::Arc;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
@@ -12,11 +14,15 @@ pub struct InvoiceServiceComponents {
     pub store: Store,
     pub html_rendering: HtmlRenderingService,
     pub jwt_secret: SecretString,
+    pub watchdog_sla: InvoiceWatchdogSla,
+    pub object_store: Arc<dyn ObjectStoreService>,
 }
 
 pub fn service(
     store: Store,
     jwt_secret: SecretString,
+    watchdog_sla: InvoiceWatchdogSla,
+    object_store: Arc<dyn ObjectStoreService>,
 ) -> InvoicesServiceServer<InvoiceServiceComponents> {
     let html_rendering = HtmlRenderingService::new(Arc::new(store.clone()));
 
@@ -24,6 +30,8 @@ pub fn service(
         store,
         html_rendering,
         jwt_secret,
+        watchdog_sla,
+        object_store,
     };
 
     InvoicesServiceServer::new(inner)
@@ -1,20 +1,54 @@
+use bytes::Bytes;
+use chrono::NaiveDate;
+use error_stack::Report;
 use tonic::{Request, Response, Status};
 
 use common_grpc::middleware::server::auth::RequestExt;
+use common_grpc::middleware::server::auth::TenantRole;
 use meteroid_grpc::meteroid::api::invoices::v1::{
-    invoices_service_server::InvoicesService, list_invoices_request::SortBy, GetInvoiceRequest,
-    GetInvoiceResponse, Invoice, ListInvoicesRequest, ListInvoicesResponse, PreviewInvoiceRequest,
-    PreviewInvoiceResponse, RefreshInvoiceDataRequest, RefreshInvoiceDataResponse,
-    RequestPdfGenerationRequest, RequestPdfGenerationResponse,
+    invoices_service_server::InvoicesService, list_invoices_request::SortBy,
+    AddInvoiceAttachmentRequest, AddInvoiceAttachmentResponse, ApproveInvoiceRequest,
+    ApproveInvoiceResponse, CreateOneOffInvoiceRequest, CreateOneOffInvoiceResponse,
+    FinalizeInvoiceRequest, FinalizeInvoiceResponse, GetBillingRunSummaryRequest,
+    GetBillingRunSummaryResponse, GetInvoiceApprovalChainRequest, GetInvoiceApprovalChainResponse,
+    GetInvoiceApprovalRequest, GetInvoiceApprovalResponse, GetInvoiceRequest, GetInvoiceResponse,
+    Invoice, InvoiceStatus, InvoicingProvider, ListBillingRunSummariesRequest,
+    ListBillingRunSummariesResponse, ListInvoiceAttachmentsRequest, ListInvoiceAttachmentsResponse,
+    ListInvoicePaymentsRequest, ListInvoicePaymentsResponse, ListInvoicesRequest,
+    ListInvoicesResponse, ListStuckInvoicesRequest, ListStuckInvoicesResponse,
+    PreviewInvoiceRequest, PreviewInvoiceResponse, RecordInvoicePaymentRequest,
+    RecordInvoicePaymentResponse, RefreshInvoiceDataRequest, RefreshInvoiceDataResponse,
+    RejectInvoiceRequest, RejectInvoiceResponse, RemoveInvoiceAttachmentRequest,
+    RemoveInvoiceAttachmentResponse, RequestInvoiceApprovalRequest, RequestInvoiceApprovalResponse,
+    RequestPdfGenerationRequest, RequestPdfGenerationResponse, SetInvoiceApprovalChainRequest,
+    SetInvoiceApprovalChainResponse, UpdateDraftInvoiceRequest, UpdateDraftInvoiceResponse,
+    VoidInvoiceRequest, VoidInvoiceResponse,
 };
 use meteroid_store::domain;
+use meteroid_store::domain::enums::InvoiceStatusEnum;
+use meteroid_store::domain::invoice_attachments::{validate_attachment_file, InvoiceAttachmentNew};
+use meteroid_store::domain::invoice_edits::{DraftInvoiceUpdate, OneOffInvoiceNew};
+use meteroid_store::domain::invoice_payments::InvoicePaymentNew;
 use meteroid_store::domain::{OrderByRequest, OutboxEvent};
+use meteroid_store::repositories::billing_runs::BillingRunsInterface;
+use meteroid_store::repositories::configs::ConfigsInterface;
+use meteroid_store::repositories::customer_blackout_windows::CustomerBlackoutWindowInterface;
+use meteroid_store::repositories::invoice_approvals::InvoiceApprovalInterface;
+use meteroid_store::repositories::invoice_attachments::InvoiceAttachmentInterface;
+use meteroid_store::repositories::invoice_payments::InvoicePaymentInterface;
+use meteroid_store::repositories::invoice_watchdog::InvoiceWatchdogInterface;
 use meteroid_store::repositories::outbox::OutboxInterface;
 use meteroid_store::repositories::InvoiceInterface;
 
+use crate::adapters::invoicing::InvoicingAdapters;
+
 use crate::api::invoices::error::InvoiceApiError;
-use crate::api::utils::parse_uuid;
+use crate::api::redaction::Redact;
+use crate::api::roles::mapping::role;
+use crate::api::shared::conversions::{AsProtoOpt, FromProtoOpt};
 use crate::api::utils::PaginationExt;
+use crate::api::utils::{audited, parse_uuid, AuditEntity};
+use crate::services::storage::Prefix;
 
 use super::{mapping, InvoiceServiceComponents};
 
@@ -77,12 +111,14 @@ impl InvoicesService for InvoiceServiceComponents {
         request: Request<GetInvoiceRequest>,
     ) -> Result<Response<GetInvoiceResponse>, Status> {
         let tenant_id = request.tenant()?;
+        let role = request.tenant_role()?;
 
         let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
 
-        let invoice = self
+        let mut invoice = self
             .store
-            .find_invoice_by_id(tenant_id, parse_uuid(&req.id, "id")?)
+            .find_invoice_by_id(tenant_id, id)
             .await
             .and_then(|inv| {
                 mapping::invoices::domain_invoice_with_plan_details_to_server(
@@ -90,8 +126,36 @@ impl InvoicesService for InvoiceServiceComponents {
                     self.jwt_secret.clone(),
                 )
             })
+            .map(|inv| inv.redacted_for(role))
             .map_err(Into::<InvoiceApiError>::into)?;
 
+        invoice.attachments = self
+            .store
+            .list_invoice_attachments(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::invoices::attachment_to_server)
+            .collect();
+
+        if invoice.status() == InvoiceStatus::Finalized
+            && invoice.invoicing_provider() != InvoicingProvider::Manual
+            && !invoice.issued
+        {
+            let customer_id = parse_uuid(&invoice.customer_id, "customer_id")?;
+
+            invoice.issue_deferred_until = self
+                .store
+                .get_invoice_issue_deferred_until(
+                    tenant_id,
+                    customer_id,
+                    chrono::Utc::now().date_naive(),
+                )
+                .await
+                .map_err(Into::<InvoiceApiError>::into)?
+                .as_proto();
+        }
+
         let response = GetInvoiceResponse {
             invoice: Some(invoice),
         };
@@ -178,4 +242,627 @@ impl InvoicesService for InvoiceServiceComponents {
 
         Ok(Response::new(response))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_stuck_invoices(
+        &self,
+        request: Request<ListStuckInvoicesRequest>,
+    ) -> Result<Response<ListStuckInvoicesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let invoices = self
+            .store
+            .list_stuck_invoices(
+                tenant_id,
+                &self.watchdog_sla,
+                chrono::Utc::now().naive_utc(),
+            )
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::invoices::stuck_domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListStuckInvoicesResponse { invoices }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn void_invoice(
+        &self,
+        request: Request<VoidInvoiceRequest>,
+    ) -> Result<Response<VoidInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+
+        if req.reason.trim().is_empty() {
+            return Err(InvoiceApiError::InvalidArgument(
+                "A reason is required to void an invoice".to_string(),
+            )
+            .into());
+        }
+
+        let invoice = self
+            .store
+            .find_invoice_by_id(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .invoice;
+
+        if invoice.status != InvoiceStatusEnum::Finalized {
+            return Err(InvoiceApiError::InvalidArgument(
+                "Only finalized invoices can be voided".to_string(),
+            )
+            .into());
+        }
+
+        // voided at the provider first, so that the invoice cannot be paid anymore
+        if invoice.issued && invoice.external_invoice_id.is_some() {
+            if let Some(adapter) =
+                InvoicingAdapters::get().for_provider(&invoice.invoicing_provider)
+            {
+                let config = self
+                    .store
+                    .find_provider_config(invoice.invoicing_provider.clone(), tenant_id)
+                    .await
+                    .map_err(Into::<InvoiceApiError>::into)?;
+
+                adapter
+                    .void_invoice(&invoice, &config.api_security)
+                    .await
+                    .map_err(|e| InvoiceApiError::ProviderError(e.to_string()))?;
+            }
+        }
+
+        let voided = self
+            .store
+            .void_invoice(id, tenant_id, req.reason, req.reissue)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let replacement = voided
+            .replacement
+            .map(|r| {
+                mapping::invoices::domain_invoice_with_plan_details_to_server(
+                    r,
+                    self.jwt_secret.clone(),
+                )
+            })
+            .transpose()
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let invoice = mapping::invoices::domain_invoice_with_plan_details_to_server(
+            voided.invoice,
+            self.jwt_secret.clone(),
+        )
+        .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(Response::new(VoidInvoiceResponse {
+            invoice: Some(invoice),
+            replacement,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn finalize_invoice(
+        &self,
+        request: Request<FinalizeInvoiceRequest>,
+    ) -> Result<Response<FinalizeInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+        let invoice_date = NaiveDate::from_proto_opt(req.invoice_date)?;
+
+        let invoice = self
+            .store
+            .find_invoice_by_id(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .invoice;
+
+        if invoice.status != InvoiceStatusEnum::Draft
+            && invoice.status != InvoiceStatusEnum::Pending
+        {
+            return Err(InvoiceApiError::InvalidArgument(
+                "Only draft invoices can be finalized".to_string(),
+            )
+            .into());
+        }
+
+        self.store
+            .finalize_invoice(id, tenant_id, invoice_date)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let finalized = self
+            .store
+            .find_invoice_by_id(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        // left as is by the finalization until its approval chain approves it
+        if finalized.invoice.status == InvoiceStatusEnum::Draft
+            || finalized.invoice.status == InvoiceStatusEnum::Pending
+        {
+            return Err(InvoiceApiError::InvalidArgument(
+                "The invoice cannot be finalized until it is approved".to_string(),
+            )
+            .into());
+        }
+
+        let invoice = mapping::invoices::domain_invoice_with_plan_details_to_server(
+            finalized,
+            self.jwt_secret.clone(),
+        )
+        .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(Response::new(FinalizeInvoiceResponse {
+            invoice: Some(invoice),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_draft_invoice(
+        &self,
+        request: Request<UpdateDraftInvoiceRequest>,
+    ) -> Result<Response<UpdateDraftInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+
+        let lines = req
+            .lines
+            .map(|lines| {
+                lines
+                    .lines
+                    .into_iter()
+                    .map(mapping::invoices::draft_line_server_to_domain)
+                    .collect::<Result<Vec<_>, _>>()
+            })
+            .transpose()?;
+
+        let update = DraftInvoiceUpdate {
+            lines,
+            memo: req.memo,
+            due_date: NaiveDate::from_proto_opt(req.due_date)?,
+        };
+
+        let before = self
+            .store
+            .find_invoice_by_id(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let after = self
+            .store
+            .update_draft_invoice(tenant_id, id, actor, update)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let entity = AuditEntity::new(id).with_diff(
+            &mapping::audit::draft_to_json(&before.invoice),
+            &mapping::audit::draft_to_json(&after.invoice),
+        );
+
+        let invoice = mapping::invoices::domain_invoice_with_plan_details_to_server(
+            after,
+            self.jwt_secret.clone(),
+        )
+        .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            UpdateDraftInvoiceResponse {
+                invoice: Some(invoice),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_one_off_invoice(
+        &self,
+        request: Request<CreateOneOffInvoiceRequest>,
+    ) -> Result<Response<CreateOneOffInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+
+        let invoice = OneOffInvoiceNew {
+            tenant_id,
+            customer_id: parse_uuid(&req.customer_id, "customer_id")?,
+            lines: req
+                .lines
+                .into_iter()
+                .map(mapping::invoices::draft_line_server_to_domain)
+                .collect::<Result<Vec<_>, _>>()?,
+            invoice_date: NaiveDate::from_proto_opt(req.invoice_date)?,
+            reference: req.reference,
+            memo: req.memo,
+            finalize: req.finalize,
+        };
+
+        let created = self
+            .store
+            .create_one_off_invoice(invoice)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let entity = AuditEntity::new(created.invoice.id);
+
+        let invoice = mapping::invoices::domain_invoice_with_plan_details_to_server(
+            created,
+            self.jwt_secret.clone(),
+        )
+        .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            CreateOneOffInvoiceResponse {
+                invoice: Some(invoice),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_billing_run_summaries(
+        &self,
+        request: Request<ListBillingRunSummariesRequest>,
+    ) -> Result<Response<ListBillingRunSummariesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+
+        let pagination_req = domain::PaginationRequest {
+            page: req.pagination.as_ref().map(|p| p.offset).unwrap_or(0),
+            per_page: req.pagination.as_ref().map(|p| p.limit),
+        };
+
+        let res = self
+            .store
+            .list_billing_run_summaries(tenant_id, pagination_req)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(Response::new(ListBillingRunSummariesResponse {
+            pagination_meta: req.pagination.into_response(res.total_results as u32),
+            summaries: res
+                .items
+                .into_iter()
+                .map(mapping::billing_runs::summary_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_billing_run_summary(
+        &self,
+        request: Request<GetBillingRunSummaryRequest>,
+    ) -> Result<Response<GetBillingRunSummaryResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+
+        let summary = self
+            .store
+            .find_billing_run_summary(tenant_id, id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(Response::new(GetBillingRunSummaryResponse {
+            summary: Some(mapping::billing_runs::summary_to_server(summary)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn add_invoice_attachment(
+        &self,
+        request: Request<AddInvoiceAttachmentRequest>,
+    ) -> Result<Response<AddInvoiceAttachmentResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+        let size_bytes = req.data.len() as i64;
+
+        // checked before storing the content, the limits of the invoice being checked on insert
+        validate_attachment_file(&req.file_name, &req.content_type, size_bytes)
+            .map_err(|e| Into::<InvoiceApiError>::into(Report::new(e)))?;
+
+        let document_id = self
+            .object_store
+            .store(Bytes::from(req.data), Prefix::InvoiceAttachment)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let attachment = self
+            .store
+            .insert_invoice_attachment(InvoiceAttachmentNew {
+                tenant_id,
+                invoice_id,
+                file_name: req.file_name,
+                content_type: req.content_type,
+                size_bytes,
+                document_id: document_id.to_string(),
+                include_in_email: req.include_in_email,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let id = attachment.id;
+
+        Ok(audited(
+            AddInvoiceAttachmentResponse {
+                attachment: Some(mapping::invoices::attachment_to_server(attachment)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_invoice_attachments(
+        &self,
+        request: Request<ListInvoiceAttachmentsRequest>,
+    ) -> Result<Response<ListInvoiceAttachmentsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let attachments = self
+            .store
+            .list_invoice_attachments(tenant_id, invoice_id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::invoices::attachment_to_server)
+            .collect();
+
+        Ok(Response::new(ListInvoiceAttachmentsResponse {
+            attachments,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn remove_invoice_attachment(
+        &self,
+        request: Request<RemoveInvoiceAttachmentRequest>,
+    ) -> Result<Response<RemoveInvoiceAttachmentResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .remove_invoice_attachment(tenant_id, id, actor)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            RemoveInvoiceAttachmentResponse {},
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_invoice_approval_chain(
+        &self,
+        request: Request<GetInvoiceApprovalChainRequest>,
+    ) -> Result<Response<GetInvoiceApprovalChainResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let steps = self
+            .store
+            .get_invoice_approval_chain(tenant_id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::approvals::step_to_server)
+            .collect();
+
+        Ok(Response::new(GetInvoiceApprovalChainResponse { steps }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_invoice_approval_chain(
+        &self,
+        request: Request<SetInvoiceApprovalChainRequest>,
+    ) -> Result<Response<SetInvoiceApprovalChainResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        // billing members can call the invoices service, but the chain is what controls them
+        if request.tenant_role()? != TenantRole::Admin {
+            return Err(Status::permission_denied(
+                "Only admins can configure the invoice approval chain",
+            ));
+        }
+
+        let steps = request
+            .into_inner()
+            .steps
+            .into_iter()
+            .map(mapping::approvals::step_new_from_server)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let steps = self
+            .store
+            .set_invoice_approval_chain(tenant_id, actor, steps)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::approvals::step_to_server)
+            .collect();
+
+        Ok(audited(
+            SetInvoiceApprovalChainResponse { steps },
+            AuditEntity::new(tenant_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_invoice_approval(
+        &self,
+        request: Request<GetInvoiceApprovalRequest>,
+    ) -> Result<Response<GetInvoiceApprovalResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let approval = self
+            .store
+            .get_invoice_approval(tenant_id, invoice_id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(Response::new(GetInvoiceApprovalResponse {
+            approval: Some(mapping::approvals::details_to_server(approval)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn request_invoice_approval(
+        &self,
+        request: Request<RequestInvoiceApprovalRequest>,
+    ) -> Result<Response<RequestInvoiceApprovalResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let approval = self
+            .store
+            .request_invoice_approval(tenant_id, invoice_id, Some(actor))
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            RequestInvoiceApprovalResponse {
+                approval: Some(mapping::approvals::details_to_server(approval)),
+            },
+            AuditEntity::new(invoice_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn approve_invoice(
+        &self,
+        request: Request<ApproveInvoiceRequest>,
+    ) -> Result<Response<ApproveInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let role = role::extension_to_domain(request.tenant_role()?);
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let approval = self
+            .store
+            .approve_invoice(tenant_id, invoice_id, actor, role, req.comment)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            ApproveInvoiceResponse {
+                approval: Some(mapping::approvals::details_to_server(approval)),
+            },
+            AuditEntity::new(invoice_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn reject_invoice(
+        &self,
+        request: Request<RejectInvoiceRequest>,
+    ) -> Result<Response<RejectInvoiceResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let role = role::extension_to_domain(request.tenant_role()?);
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let approval = self
+            .store
+            .reject_invoice(tenant_id, invoice_id, actor, role, req.comment)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        Ok(audited(
+            RejectInvoiceResponse {
+                approval: Some(mapping::approvals::details_to_server(approval)),
+            },
+            AuditEntity::new(invoice_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn record_invoice_payment(
+        &self,
+        request: Request<RecordInvoicePaymentRequest>,
+    ) -> Result<Response<RecordInvoicePaymentResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let paid_at = NaiveDate::from_proto_opt(req.paid_at)?
+            .unwrap_or_else(|| chrono::Utc::now().date_naive());
+
+        let recorded = self
+            .store
+            .record_invoice_payment(InvoicePaymentNew {
+                tenant_id,
+                invoice_id,
+                amount: req.amount,
+                paid_at,
+                reference: req.reference,
+                note: req.note,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?;
+
+        let id = recorded.payment.id;
+
+        Ok(audited(
+            RecordInvoicePaymentResponse {
+                payment: Some(mapping::invoices::payment_to_server(recorded.payment)),
+                amount_due: recorded.amount_due,
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_invoice_payments(
+        &self,
+        request: Request<ListInvoicePaymentsRequest>,
+    ) -> Result<Response<ListInvoicePaymentsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let payments = self
+            .store
+            .list_invoice_payments(tenant_id, invoice_id)
+            .await
+            .map_err(Into::<InvoiceApiError>::into)?
+            .into_iter()
+            .map(mapping::invoices::payment_to_server)
+            .collect();
+
+        Ok(Response::new(ListInvoicePaymentsResponse { payments }))
+    }
 }
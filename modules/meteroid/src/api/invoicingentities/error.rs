@@ -28,11 +28,13 @@ pub enum InvoicingEntitiesApiError {
 
 impl From<Report<StoreError>> for InvoicingEntitiesApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        InvoicingEntitiesApiError::StoreError(
-            "Error in invoicing entities service".to_string(),
-            err,
-        )
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            _ => InvoicingEntitiesApiError::StoreError(
+                "Error in invoicing entities service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
     }
 }
 impl From<Report<ObjectStoreError>> for InvoicingEntitiesApiError {
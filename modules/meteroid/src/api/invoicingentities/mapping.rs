@@ -1,6 +1,7 @@
 pub mod invoicing_entities {
     use crate::api::shared::conversions::ProtoConv;
     use meteroid_grpc::meteroid::api::invoicingentities::v1 as server;
+    use meteroid_store::domain::enums::TaxRegistrationTypeEnum;
     use meteroid_store::domain::invoicing_entities as domain;
     use uuid::Uuid;
 
@@ -77,4 +78,47 @@ pub mod invoicing_entities {
             accounting_currency: domain.accounting_currency,
         }
     }
+
+    fn tax_registration_type_to_proto(
+        registration_type: TaxRegistrationTypeEnum,
+    ) -> server::TaxRegistrationType {
+        match registration_type {
+            TaxRegistrationTypeEnum::Vat => server::TaxRegistrationType::Vat,
+            TaxRegistrationTypeEnum::Gst => server::TaxRegistrationType::Gst,
+            TaxRegistrationTypeEnum::Ein => server::TaxRegistrationType::Ein,
+            TaxRegistrationTypeEnum::Abn => server::TaxRegistrationType::Abn,
+        }
+    }
+
+    fn tax_registration_type_from_proto(
+        registration_type: server::TaxRegistrationType,
+    ) -> TaxRegistrationTypeEnum {
+        match registration_type {
+            server::TaxRegistrationType::Vat => TaxRegistrationTypeEnum::Vat,
+            server::TaxRegistrationType::Gst => TaxRegistrationTypeEnum::Gst,
+            server::TaxRegistrationType::Ein => TaxRegistrationTypeEnum::Ein,
+            server::TaxRegistrationType::Abn => TaxRegistrationTypeEnum::Abn,
+        }
+    }
+
+    pub fn tax_registration_to_proto(domain: domain::TaxRegistration) -> server::TaxRegistration {
+        server::TaxRegistration {
+            id: domain.id.as_proto(),
+            registration_type: tax_registration_type_to_proto(domain.registration_type) as i32,
+            jurisdiction: domain.jurisdiction,
+            registration_number: domain.registration_number,
+            customer_countries: domain.customer_countries,
+        }
+    }
+
+    pub fn tax_registration_from_proto(
+        proto: server::TaxRegistrationData,
+    ) -> domain::TaxRegistrationNew {
+        domain::TaxRegistrationNew {
+            registration_type: tax_registration_type_from_proto(proto.registration_type()),
+            jurisdiction: proto.jurisdiction,
+            registration_number: proto.registration_number,
+            customer_countries: proto.customer_countries,
+        }
+    }
 }
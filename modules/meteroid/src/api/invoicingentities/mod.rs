@@ -3,7 +3,7 @@ use meteroid_grpc::meteroid::api::invoicingentities::v1::invoicing_entities_serv
 use meteroid_store::Store;
 use std::sync::Arc;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
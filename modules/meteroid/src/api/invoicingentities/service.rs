@@ -8,8 +8,9 @@ use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::invoicingentities::v1::{
     invoicing_entities_service_server::InvoicingEntitiesService, CreateInvoicingEntityRequest,
     CreateInvoicingEntityResponse, GetInvoicingEntityRequest, GetInvoicingEntityResponse,
-    ListInvoicingEntitiesRequest, ListInvoicingEntitiesResponse, UpdateInvoicingEntityRequest,
-    UpdateInvoicingEntityResponse, UploadInvoicingEntityLogoRequest,
+    ListInvoicingEntitiesRequest, ListInvoicingEntitiesResponse, ListTaxRegistrationsRequest,
+    ListTaxRegistrationsResponse, SetTaxRegistrationsRequest, SetTaxRegistrationsResponse,
+    UpdateInvoicingEntityRequest, UpdateInvoicingEntityResponse, UploadInvoicingEntityLogoRequest,
     UploadInvoicingEntityLogoResponse,
 };
 use meteroid_store::domain::InvoicingEntityPatch;
@@ -167,6 +168,60 @@ impl InvoicingEntitiesService for InvoicingEntitiesServiceComponents {
             logo_uid: logo_attachment_id,
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_tax_registrations(
+        &self,
+        request: Request<ListTaxRegistrationsRequest>,
+    ) -> Result<Response<ListTaxRegistrationsResponse>, Status> {
+        let tenant = request.tenant()?;
+        let req = request.into_inner();
+
+        let tax_registrations = self
+            .store
+            .list_invoicing_entity_tax_registrations(
+                tenant,
+                Uuid::from_proto(req.invoicing_entity_id)?,
+            )
+            .await
+            .map_err(Into::<InvoicingEntitiesApiError>::into)?
+            .into_iter()
+            .map(mapping::invoicing_entities::tax_registration_to_proto)
+            .collect();
+
+        Ok(Response::new(ListTaxRegistrationsResponse {
+            tax_registrations,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_tax_registrations(
+        &self,
+        request: Request<SetTaxRegistrationsRequest>,
+    ) -> Result<Response<SetTaxRegistrationsResponse>, Status> {
+        let tenant = request.tenant()?;
+        let req = request.into_inner();
+
+        let tax_registrations = self
+            .store
+            .set_invoicing_entity_tax_registrations(
+                tenant,
+                Uuid::from_proto(req.invoicing_entity_id)?,
+                req.tax_registrations
+                    .into_iter()
+                    .map(mapping::invoicing_entities::tax_registration_from_proto)
+                    .collect(),
+            )
+            .await
+            .map_err(Into::<InvoicingEntitiesApiError>::into)?
+            .into_iter()
+            .map(mapping::invoicing_entities::tax_registration_to_proto)
+            .collect();
+
+        Ok(Response::new(SetTaxRegistrationsResponse {
+            tax_registrations,
+        }))
+    }
 }
 const MAX_IMAGE_SIZE: usize = 2 * 1024 * 1024; // 2 MB
 const MAX_H: u32 = 160;
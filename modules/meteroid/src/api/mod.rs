@@ -1,14 +1,18 @@
-mod cors;
+pub mod cors;
 pub mod server;
 
 pub mod shared;
 pub mod utils;
 
+pub mod accountingexports;
 pub mod addons;
 pub mod apitokens;
+pub mod auditlogs;
 mod axum_routers;
 pub mod axum_server;
 pub mod billablemetrics;
+pub mod collectionstrategies;
+pub mod componentrules;
 pub mod coupons;
 pub mod customers;
 mod domain_mapping;
@@ -17,15 +21,26 @@ pub mod instance;
 pub mod internal;
 pub mod invoices;
 pub mod invoicingentities;
+pub mod notificationbundles;
 pub mod organizations;
+pub mod partners;
+pub mod paymentreminders;
 pub mod plans;
 pub mod pricecomponents;
 pub mod productfamilies;
 pub mod productitems;
+pub mod providers;
+pub mod ratecards;
+mod redaction;
+pub mod reports;
+mod rest;
+pub mod roles;
 pub mod schedules;
 mod sharable;
 pub mod stats;
 pub mod subscriptions;
 pub mod tenants;
+pub mod usage;
+pub mod usagealerts;
 pub mod users;
 pub mod webhooksout;
@@ -0,0 +1,55 @@
+pub mod namespace {
+    use meteroid_grpc::meteroid::api::notificationbundles::v1::NotificationNamespace as NotificationNamespaceProto;
+    use meteroid_store::domain::enums::NotificationNamespaceEnum;
+
+    pub fn to_proto(namespace: NotificationNamespaceEnum) -> NotificationNamespaceProto {
+        match namespace {
+            NotificationNamespaceEnum::Email => NotificationNamespaceProto::Email,
+            NotificationNamespaceEnum::Portal => NotificationNamespaceProto::Portal,
+            NotificationNamespaceEnum::Pdf => NotificationNamespaceProto::Pdf,
+        }
+    }
+
+    pub fn from_proto(namespace: NotificationNamespaceProto) -> NotificationNamespaceEnum {
+        match namespace {
+            NotificationNamespaceProto::Email => NotificationNamespaceEnum::Email,
+            NotificationNamespaceProto::Portal => NotificationNamespaceEnum::Portal,
+            NotificationNamespaceProto::Pdf => NotificationNamespaceEnum::Pdf,
+        }
+    }
+}
+
+pub mod bundles {
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::notificationbundles::v1::localized_message::Source;
+    use meteroid_grpc::meteroid::api::notificationbundles::v1::{
+        LocalizedMessage as LocalizedMessageProto, NotificationBundle as NotificationBundleProto,
+    };
+    use meteroid_store::domain::notification_bundles::{
+        LocalizedMessage, MessageSource, NotificationBundle,
+    };
+
+    pub fn domain_to_server(bundle: NotificationBundle) -> NotificationBundleProto {
+        NotificationBundleProto {
+            id: bundle.id.as_proto(),
+            namespace: super::namespace::to_proto(bundle.namespace).into(),
+            locale: bundle.locale,
+            messages: bundle.messages.into_iter().collect(),
+            created_at: Some(chrono_to_timestamp(bundle.created_at)),
+            updated_at: Some(chrono_to_timestamp(bundle.updated_at)),
+        }
+    }
+
+    pub fn message_to_server(message: LocalizedMessage) -> LocalizedMessageProto {
+        LocalizedMessageProto {
+            key: message.key,
+            template: message.template,
+            variables: message.variables,
+            source: Some(match message.source {
+                MessageSource::Tenant { locale } => Source::TenantLocale(locale),
+                MessageSource::BuiltIn { locale } => Source::BuiltInLocale(locale),
+            }),
+        }
+    }
+}
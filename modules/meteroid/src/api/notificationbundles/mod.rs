@@ -0,0 +1,17 @@
+use meteroid_grpc::meteroid::api::notificationbundles::v1::notification_bundles_service_server::NotificationBundlesServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct NotificationBundlesServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(
+    store: Store,
+) -> NotificationBundlesServiceServer<NotificationBundlesServiceComponents> {
+    let inner = NotificationBundlesServiceComponents { store };
+    NotificationBundlesServiceServer::new(inner)
+}
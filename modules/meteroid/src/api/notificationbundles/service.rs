@@ -0,0 +1,127 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::notificationbundles::v1::notification_bundles_service_server::NotificationBundlesService;
+use meteroid_grpc::meteroid::api::notificationbundles::v1::{
+    DeleteNotificationBundleRequest, DeleteNotificationBundleResponse,
+    ListNotificationBundlesRequest, ListNotificationBundlesResponse,
+    ResolveNotificationMessagesRequest, ResolveNotificationMessagesResponse,
+    UpsertNotificationBundleRequest, UpsertNotificationBundleResponse,
+};
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::notification_bundles::NotificationBundleNew;
+use meteroid_store::repositories::notification_bundles::NotificationBundleInterface;
+
+use crate::api::notificationbundles::error::NotificationBundleApiError;
+use crate::api::notificationbundles::mapping::{bundles, namespace};
+use crate::api::notificationbundles::NotificationBundlesServiceComponents;
+use crate::api::utils::{audited, parse_uuid};
+
+#[tonic::async_trait]
+impl NotificationBundlesService for NotificationBundlesServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn upsert_notification_bundle(
+        &self,
+        request: Request<UpsertNotificationBundleRequest>,
+    ) -> Result<Response<UpsertNotificationBundleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let bundle = NotificationBundleNew {
+            tenant_id,
+            namespace: namespace::from_proto(req.namespace()),
+            locale: req.locale,
+            messages: req.messages.into_iter().collect(),
+            created_by: actor,
+        };
+
+        let bundle = self
+            .store
+            .upsert_notification_bundle(bundle)
+            .await
+            .map_err(Into::<NotificationBundleApiError>::into)?;
+
+        let id = bundle.id;
+
+        Ok(audited(
+            UpsertNotificationBundleResponse {
+                bundle: Some(bundles::domain_to_server(bundle)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_notification_bundles(
+        &self,
+        request: Request<ListNotificationBundlesRequest>,
+    ) -> Result<Response<ListNotificationBundlesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let namespace = req
+            .namespace
+            .is_some()
+            .then(|| namespace::from_proto(req.namespace()));
+
+        let bundles = self
+            .store
+            .list_notification_bundles(tenant_id, namespace)
+            .await
+            .map_err(Into::<NotificationBundleApiError>::into)?
+            .into_iter()
+            .map(bundles::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListNotificationBundlesResponse { bundles }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_notification_bundle(
+        &self,
+        request: Request<DeleteNotificationBundleRequest>,
+    ) -> Result<Response<DeleteNotificationBundleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .delete_notification_bundle(tenant_id, id)
+            .await
+            .map_err(Into::<NotificationBundleApiError>::into)?;
+
+        Ok(audited(
+            DeleteNotificationBundleResponse {},
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn resolve_notification_messages(
+        &self,
+        request: Request<ResolveNotificationMessagesRequest>,
+    ) -> Result<Response<ResolveNotificationMessagesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let messages = self
+            .store
+            .resolve_notification_messages(
+                tenant_id,
+                namespace::from_proto(req.namespace()),
+                req.locale,
+            )
+            .await
+            .map_err(Into::<NotificationBundleApiError>::into)?
+            .messages
+            .into_iter()
+            .map(bundles::message_to_server)
+            .collect();
+
+        Ok(Response::new(ResolveNotificationMessagesResponse {
+            messages,
+        }))
+    }
+}
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::organizations::v1::organizations_service_server::OrganizationsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
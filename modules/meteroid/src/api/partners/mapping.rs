@@ -0,0 +1,102 @@
+pub mod partner {
+    use meteroid_grpc::meteroid::api::partners::v1 as server;
+    use meteroid_store::domain::partners::Partner;
+
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+
+    pub fn to_proto(partner: Partner) -> server::Partner {
+        server::Partner {
+            id: partner.id.as_proto(),
+            name: partner.name,
+            rev_share_percent: partner.rev_share_percent.as_proto(),
+            created_at: Some(chrono_to_timestamp(partner.created_at)),
+            archived_at: partner.archived_at.map(chrono_to_timestamp),
+        }
+    }
+}
+
+pub mod attribution {
+    use meteroid_grpc::meteroid::api::partners::v1 as server;
+    use meteroid_store::domain::partners::{PartnerAttribution, PartnerAttributionTarget};
+    use tonic::Status;
+    use uuid::Uuid;
+
+    use crate::api::partners::error::PartnerApiError;
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+
+    pub fn to_proto(attribution: PartnerAttribution) -> server::PartnerAttribution {
+        let target = match attribution.target {
+            PartnerAttributionTarget::Customer(id) => {
+                server::partner_attribution::Target::CustomerId(id.as_proto())
+            }
+            PartnerAttributionTarget::Subscription(id) => {
+                server::partner_attribution::Target::SubscriptionId(id.as_proto())
+            }
+        };
+
+        server::PartnerAttribution {
+            id: attribution.id.as_proto(),
+            partner_id: attribution.partner_id.as_proto(),
+            target: Some(target),
+            rev_share_percent: attribution.rev_share_percent.as_proto(),
+            created_at: Some(chrono_to_timestamp(attribution.created_at)),
+        }
+    }
+
+    pub fn set_target_from_proto(
+        target: Option<server::set_partner_attribution_request::Target>,
+    ) -> Result<PartnerAttributionTarget, Status> {
+        use server::set_partner_attribution_request::Target;
+
+        match target {
+            Some(Target::CustomerId(id)) => {
+                Ok(PartnerAttributionTarget::Customer(Uuid::from_proto(id)?))
+            }
+            Some(Target::SubscriptionId(id)) => Ok(PartnerAttributionTarget::Subscription(
+                Uuid::from_proto(id)?,
+            )),
+            None => Err(missing_target()),
+        }
+    }
+
+    pub fn remove_target_from_proto(
+        target: Option<server::remove_partner_attribution_request::Target>,
+    ) -> Result<PartnerAttributionTarget, Status> {
+        use server::remove_partner_attribution_request::Target;
+
+        match target {
+            Some(Target::CustomerId(id)) => {
+                Ok(PartnerAttributionTarget::Customer(Uuid::from_proto(id)?))
+            }
+            Some(Target::SubscriptionId(id)) => Ok(PartnerAttributionTarget::Subscription(
+                Uuid::from_proto(id)?,
+            )),
+            None => Err(missing_target()),
+        }
+    }
+
+    fn missing_target() -> Status {
+        PartnerApiError::MissingArgument("a customer or a subscription is required".to_string())
+            .into()
+    }
+}
+
+pub mod payout {
+    use meteroid_grpc::meteroid::api::partners::v1 as server;
+    use meteroid_store::domain::partners::PartnerPayoutLine;
+
+    use crate::api::shared::conversions::ProtoConv;
+
+    pub fn line_to_proto(line: PartnerPayoutLine) -> server::PartnerPayoutLine {
+        server::PartnerPayoutLine {
+            partner_id: line.partner_id.as_proto(),
+            partner_name: line.partner_name,
+            currency: line.currency,
+            invoice_count: line.invoice_count,
+            invoiced_amount: line.invoiced_amount,
+            commission_amount: line.commission_amount,
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::partners::v1::partners_service_server::PartnersServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct PartnersServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> PartnersServiceServer<PartnersServiceComponents> {
+    let inner = PartnersServiceComponents { store };
+    PartnersServiceServer::new(inner)
+}
@@ -0,0 +1,199 @@
+use rust_decimal::Decimal;
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::partners::v1::partners_service_server::PartnersService;
+use meteroid_grpc::meteroid::api::partners::v1::{
+    ArchivePartnerRequest, ArchivePartnerResponse, CreatePartnerRequest, CreatePartnerResponse,
+    GetPartnerPayoutReportRequest, GetPartnerPayoutReportResponse, ListPartnerAttributionsRequest,
+    ListPartnerAttributionsResponse, ListPartnersRequest, ListPartnersResponse,
+    RemovePartnerAttributionRequest, RemovePartnerAttributionResponse,
+    SetPartnerAttributionRequest, SetPartnerAttributionResponse,
+};
+use meteroid_middleware::server::auth::PartnerScope;
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::domain::partners::{
+    PartnerAttributionNew, PartnerAttributionTarget, PartnerNew,
+};
+use meteroid_store::repositories::partners::PartnersInterface;
+
+use crate::api::partners::error::PartnerApiError;
+use crate::api::partners::mapping::{attribution, partner, payout};
+use crate::api::partners::PartnersServiceComponents;
+use crate::api::shared::conversions::{FromProtoOpt, ProtoConv};
+use crate::api::utils::audited;
+
+#[tonic::async_trait]
+impl PartnersService for PartnersServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn create_partner(
+        &self,
+        request: Request<CreatePartnerRequest>,
+    ) -> Result<Response<CreatePartnerResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let res = self
+            .store
+            .insert_partner(PartnerNew {
+                tenant_id,
+                name: req.name,
+                rev_share_percent: Decimal::from_proto(req.rev_share_percent)?,
+            })
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        let entity = AuditEntity::new(res.id);
+
+        Ok(audited(
+            CreatePartnerResponse {
+                partner: Some(partner::to_proto(res)),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_partners(
+        &self,
+        request: Request<ListPartnersRequest>,
+    ) -> Result<Response<ListPartnersResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let res = self
+            .store
+            .list_partners(tenant_id)
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        Ok(Response::new(ListPartnersResponse {
+            partners: res.into_iter().map(partner::to_proto).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn archive_partner(
+        &self,
+        request: Request<ArchivePartnerRequest>,
+    ) -> Result<Response<ArchivePartnerResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = Uuid::from_proto(req.id)?;
+
+        let res = self
+            .store
+            .archive_partner(tenant_id, id)
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        Ok(audited(
+            ArchivePartnerResponse {
+                partner: Some(partner::to_proto(res)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_partner_attribution(
+        &self,
+        request: Request<SetPartnerAttributionRequest>,
+    ) -> Result<Response<SetPartnerAttributionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let res = self
+            .store
+            .set_partner_attribution(PartnerAttributionNew {
+                tenant_id,
+                partner_id: Uuid::from_proto(req.partner_id)?,
+                target: attribution::set_target_from_proto(req.target)?,
+                rev_share_percent: Decimal::from_proto_opt(req.rev_share_percent)?,
+            })
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        let entity = AuditEntity::new(res.id);
+
+        Ok(audited(
+            SetPartnerAttributionResponse {
+                attribution: Some(attribution::to_proto(res)),
+            },
+            entity,
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn remove_partner_attribution(
+        &self,
+        request: Request<RemovePartnerAttributionRequest>,
+    ) -> Result<Response<RemovePartnerAttributionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let target = attribution::remove_target_from_proto(req.target)?;
+
+        self.store
+            .remove_partner_attribution(tenant_id, target)
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        let entity_id = match target {
+            PartnerAttributionTarget::Customer(id) | PartnerAttributionTarget::Subscription(id) => {
+                id
+            }
+        };
+
+        Ok(audited(
+            RemovePartnerAttributionResponse {},
+            AuditEntity::new(entity_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_partner_attributions(
+        &self,
+        request: Request<ListPartnerAttributionsRequest>,
+    ) -> Result<Response<ListPartnerAttributionsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let res = self
+            .store
+            .list_partner_attributions(tenant_id, Uuid::from_proto(req.partner_id)?)
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        Ok(Response::new(ListPartnerAttributionsResponse {
+            attributions: res.into_iter().map(attribution::to_proto).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_partner_payout_report(
+        &self,
+        request: Request<GetPartnerPayoutReportRequest>,
+    ) -> Result<Response<GetPartnerPayoutReportResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        // a partner-scoped api key only sees its own partner
+        let partner_scope = request.extensions().get::<PartnerScope>().copied();
+        let req = request.into_inner();
+
+        let partner_id = match partner_scope {
+            Some(PartnerScope(partner_id)) => Some(partner_id),
+            None => Uuid::from_proto_opt(req.partner_id)?,
+        };
+
+        let res = self
+            .store
+            .get_partner_payout_report(tenant_id, req.year, req.month, partner_id)
+            .await
+            .map_err(Into::<PartnerApiError>::into)?;
+
+        Ok(Response::new(GetPartnerPayoutReportResponse {
+            lines: res.lines.into_iter().map(payout::line_to_proto).collect(),
+        }))
+    }
+}
@@ -0,0 +1,75 @@
+pub mod channel {
+    use meteroid_grpc::meteroid::api::paymentreminders::v1::PaymentReminderChannel as PaymentReminderChannelProto;
+    use meteroid_store::domain::enums::PaymentReminderChannelEnum;
+
+    pub fn to_proto(channel: PaymentReminderChannelEnum) -> PaymentReminderChannelProto {
+        match channel {
+            PaymentReminderChannelEnum::Email => PaymentReminderChannelProto::Email,
+            PaymentReminderChannelEnum::Webhook => PaymentReminderChannelProto::Webhook,
+        }
+    }
+
+    pub fn from_proto(channel: PaymentReminderChannelProto) -> PaymentReminderChannelEnum {
+        match channel {
+            PaymentReminderChannelProto::Email => PaymentReminderChannelEnum::Email,
+            PaymentReminderChannelProto::Webhook => PaymentReminderChannelEnum::Webhook,
+        }
+    }
+}
+
+pub mod schedules {
+    use crate::api::shared::conversions::ProtoConv;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::paymentreminders::v1::{
+        CreatePaymentReminderScheduleRequest,
+        PaymentReminderSchedule as PaymentReminderScheduleProto,
+    };
+    use meteroid_store::domain::payment_reminders::{
+        PaymentReminderSchedule, PaymentReminderScheduleNew,
+    };
+    use uuid::Uuid;
+
+    pub fn domain_to_server(schedule: PaymentReminderSchedule) -> PaymentReminderScheduleProto {
+        PaymentReminderScheduleProto {
+            id: schedule.id.as_proto(),
+            offset_days: schedule.offset_days,
+            channel: super::channel::to_proto(schedule.channel).into(),
+            enabled: schedule.enabled,
+            created_at: Some(chrono_to_timestamp(schedule.created_at)),
+        }
+    }
+
+    pub fn create_req_server_to_domain(
+        req: CreatePaymentReminderScheduleRequest,
+        tenant_id: Uuid,
+        actor: Uuid,
+    ) -> PaymentReminderScheduleNew {
+        PaymentReminderScheduleNew {
+            tenant_id,
+            offset_days: req.offset_days,
+            channel: super::channel::from_proto(req.channel()),
+            enabled: req.enabled.unwrap_or(true),
+            created_by: actor,
+        }
+    }
+}
+
+pub mod reminders {
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::paymentreminders::v1::PaymentReminder as PaymentReminderProto;
+    use meteroid_store::domain::payment_reminders::PaymentReminderLog;
+
+    pub fn domain_to_server(reminder: PaymentReminderLog) -> PaymentReminderProto {
+        PaymentReminderProto {
+            id: reminder.id.as_proto(),
+            invoice_id: reminder.invoice_id.as_proto(),
+            schedule_id: reminder.schedule_id.as_proto(),
+            offset_days: reminder.offset_days,
+            channel: super::channel::to_proto(reminder.channel).into(),
+            recipient: reminder.recipient,
+            due_at: Some(chrono_to_timestamp(reminder.due_at)),
+            sent_at: Some(chrono_to_timestamp(reminder.sent_at)),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::paymentreminders::v1::payment_reminders_service_server::PaymentRemindersServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct PaymentRemindersServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> PaymentRemindersServiceServer<PaymentRemindersServiceComponents> {
+    let inner = PaymentRemindersServiceComponents { store };
+    PaymentRemindersServiceServer::new(inner)
+}
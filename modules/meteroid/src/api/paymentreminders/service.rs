@@ -0,0 +1,138 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::paymentreminders::v1::payment_reminders_service_server::PaymentRemindersService;
+use meteroid_grpc::meteroid::api::paymentreminders::v1::{
+    CreatePaymentReminderScheduleRequest, CreatePaymentReminderScheduleResponse,
+    DeletePaymentReminderScheduleRequest, DeletePaymentReminderScheduleResponse,
+    ListInvoicePaymentRemindersRequest, ListInvoicePaymentRemindersResponse,
+    ListPaymentReminderSchedulesRequest, ListPaymentReminderSchedulesResponse,
+    UpdatePaymentReminderScheduleRequest, UpdatePaymentReminderScheduleResponse,
+};
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::repositories::payment_reminders::PaymentRemindersInterface;
+
+use crate::api::paymentreminders::error::PaymentReminderApiError;
+use crate::api::paymentreminders::mapping::{reminders, schedules};
+use crate::api::paymentreminders::PaymentRemindersServiceComponents;
+use crate::api::utils::{audited, parse_uuid};
+
+#[tonic::async_trait]
+impl PaymentRemindersService for PaymentRemindersServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn create_payment_reminder_schedule(
+        &self,
+        request: Request<CreatePaymentReminderScheduleRequest>,
+    ) -> Result<Response<CreatePaymentReminderScheduleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let schedule = self
+            .store
+            .insert_payment_reminder_schedule(schedules::create_req_server_to_domain(
+                req, tenant_id, actor,
+            ))
+            .await
+            .map_err(Into::<PaymentReminderApiError>::into)?;
+
+        let id = schedule.id;
+
+        Ok(audited(
+            CreatePaymentReminderScheduleResponse {
+                schedule: Some(schedules::domain_to_server(schedule)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_payment_reminder_schedules(
+        &self,
+        request: Request<ListPaymentReminderSchedulesRequest>,
+    ) -> Result<Response<ListPaymentReminderSchedulesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let schedules = self
+            .store
+            .list_payment_reminder_schedules(tenant_id)
+            .await
+            .map_err(Into::<PaymentReminderApiError>::into)?
+            .into_iter()
+            .map(schedules::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListPaymentReminderSchedulesResponse {
+            schedules,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_payment_reminder_schedule(
+        &self,
+        request: Request<UpdatePaymentReminderScheduleRequest>,
+    ) -> Result<Response<UpdatePaymentReminderScheduleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        let schedule = self
+            .store
+            .set_payment_reminder_schedule_enabled(tenant_id, id, req.enabled)
+            .await
+            .map_err(Into::<PaymentReminderApiError>::into)?;
+
+        Ok(audited(
+            UpdatePaymentReminderScheduleResponse {
+                schedule: Some(schedules::domain_to_server(schedule)),
+            },
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_payment_reminder_schedule(
+        &self,
+        request: Request<DeletePaymentReminderScheduleRequest>,
+    ) -> Result<Response<DeletePaymentReminderScheduleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .delete_payment_reminder_schedule(tenant_id, id)
+            .await
+            .map_err(Into::<PaymentReminderApiError>::into)?;
+
+        Ok(audited(
+            DeletePaymentReminderScheduleResponse {},
+            AuditEntity::new(id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_invoice_payment_reminders(
+        &self,
+        request: Request<ListInvoicePaymentRemindersRequest>,
+    ) -> Result<Response<ListInvoicePaymentRemindersResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let reminders = self
+            .store
+            .list_invoice_payment_reminders(tenant_id, invoice_id)
+            .await
+            .map_err(Into::<PaymentReminderApiError>::into)?
+            .into_iter()
+            .map(reminders::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListInvoicePaymentRemindersResponse {
+            reminders,
+        }))
+    }
+}
@@ -11,6 +11,10 @@ pub enum PlanApiError {
     #[code(InvalidArgument)]
     InvalidArgument(String),
 
+    #[error("{0}")]
+    #[code(FailedPrecondition)]
+    FailedPrecondition(String),
+
     #[error("Store error: {0}")]
     #[code(Internal)]
     StoreError(String, #[source] Box<dyn Error>),
@@ -18,7 +22,20 @@ pub enum PlanApiError {
 
 impl From<Report<StoreError>> for PlanApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        PlanApiError::StoreError("Error in plan service".to_string(), err)
+        let context = match value.current_context() {
+            StoreError::TransactionStoreError(inner) => inner.current_context(),
+            context => context,
+        };
+
+        match context {
+            StoreError::InvalidArgument(msg) => PlanApiError::InvalidArgument(msg.clone()),
+            StoreError::InvalidMatrixDimensions(_) => {
+                PlanApiError::FailedPrecondition(context.to_string())
+            }
+            _ => {
+                let err = Box::new(value.into_error());
+                PlanApiError::StoreError("Error in plan service".to_string(), err)
+            }
+        }
     }
 }
@@ -4,13 +4,16 @@ pub mod plans {
         plan_billing_configuration as billing_config_grpc, ListPlanVersion, PlanOverview,
     };
     use meteroid_grpc::meteroid::api::plans::v1::{
-        trial_config::ActionAfterTrial, ListPlan, ListSubscribablePlanVersion, Plan,
-        PlanBillingConfiguration, PlanDetails, PlanStatus, PlanType, PlanVersion, TrialConfig,
+        trial_config::ActionAfterTrial, trial_config::TrialMode, ListPlan,
+        ListSubscribablePlanVersion, Plan, PlanBillingConfiguration, PlanDetails, PlanStatus,
+        PlanType, PlanVersion, TrialConfig,
     };
 
     use crate::api::shared::conversions::AsProtoOpt;
     use meteroid_store::domain;
-    use meteroid_store::domain::enums::{ActionAfterTrialEnum, PlanStatusEnum, PlanTypeEnum};
+    use meteroid_store::domain::enums::{
+        ActionAfterTrialEnum, PlanStatusEnum, PlanTypeEnum, TrialModeEnum,
+    };
 
     pub struct PlanDetailsWrapper(pub PlanDetails);
 
@@ -20,6 +23,8 @@ pub mod plans {
 
     pub struct ActionAfterTrialWrapper(pub ActionAfterTrial);
 
+    pub struct TrialModeWrapper(pub TrialMode);
+
     pub struct PlanStatusWrapper(pub PlanStatus);
 
     pub struct ListPlanWrapper(pub ListPlan);
@@ -56,6 +61,7 @@ pub mod plans {
                             .into(),
                         duration_days: days as u32,
                         trial_is_free: version.trial_is_free,
+                        trial_mode: TrialModeWrapper::from(version.trial_mode).0.into(),
                     }),
                     _ => None,
                 }
@@ -99,6 +105,7 @@ pub mod plans {
                 trial_config: trial_config(&value),
                 billing_config: billing_config(&value),
                 currency: value.currency,
+                tax_category: value.tax_category,
             })
         }
     }
@@ -177,6 +184,24 @@ pub mod plans {
         }
     }
 
+    impl From<TrialModeWrapper> for TrialModeEnum {
+        fn from(val: TrialModeWrapper) -> Self {
+            match val.0 {
+                TrialMode::Free => TrialModeEnum::Free,
+                TrialMode::CardRequired => TrialModeEnum::CardRequired,
+            }
+        }
+    }
+
+    impl From<TrialModeEnum> for TrialModeWrapper {
+        fn from(e: TrialModeEnum) -> Self {
+            Self(match e {
+                TrialModeEnum::Free => TrialMode::Free,
+                TrialModeEnum::CardRequired => TrialMode::CardRequired,
+            })
+        }
+    }
+
     impl From<PlanStatusWrapper> for PlanStatusEnum {
         fn from(val: PlanStatusWrapper) -> Self {
             match val.0 {
@@ -233,6 +258,7 @@ pub mod plans {
                             .into(),
                         duration_days: days as u32,
                         trial_is_free: value.trial_is_free,
+                        trial_mode: TrialModeWrapper::from(value.trial_mode).0.into(),
                     }),
                     _ => None,
                 },
@@ -297,3 +323,265 @@ pub mod plans {
     //     }
     // }
 }
+
+pub mod catalog {
+    use meteroid_grpc::meteroid::api::plans::v1::{
+        catalog_import_conflict::Kind, import_plans_request::ConflictStrategy,
+        CatalogImportConflict, ImportPlansResponse,
+    };
+    use meteroid_store::domain::plan_catalog::{
+        CatalogConflict, CatalogConflictKind, CatalogConflictStrategy, CatalogImportReport,
+    };
+
+    pub fn conflict_strategy_to_domain(strategy: ConflictStrategy) -> CatalogConflictStrategy {
+        match strategy {
+            ConflictStrategy::Fail => CatalogConflictStrategy::Fail,
+            ConflictStrategy::Skip => CatalogConflictStrategy::Skip,
+            ConflictStrategy::NewDraftVersion => CatalogConflictStrategy::NewDraftVersion,
+        }
+    }
+
+    fn conflict_to_server(conflict: CatalogConflict) -> CatalogImportConflict {
+        let (kind, reference) = match conflict.kind {
+            CatalogConflictKind::PlanExists => (Kind::PlanExists, None),
+            CatalogConflictKind::PlanArchived => (Kind::PlanArchived, None),
+            CatalogConflictKind::PlanMismatch => (Kind::PlanMismatch, None),
+            CatalogConflictKind::MissingBillableMetric(code) => {
+                (Kind::MissingBillableMetric, Some(code))
+            }
+            CatalogConflictKind::MissingRateCard(name) => (Kind::MissingRateCard, Some(name)),
+        };
+
+        CatalogImportConflict {
+            plan_external_id: conflict.plan_external_id,
+            kind: kind.into(),
+            reference,
+        }
+    }
+
+    pub struct ImportPlansResponseWrapper(pub ImportPlansResponse);
+
+    impl From<CatalogImportReport> for ImportPlansResponseWrapper {
+        fn from(value: CatalogImportReport) -> Self {
+            Self(ImportPlansResponse {
+                applied: value.applied,
+                conflicts: value
+                    .conflicts
+                    .into_iter()
+                    .map(conflict_to_server)
+                    .collect(),
+                product_families: value.product_families,
+                products: value.products,
+                plans: value.plans,
+                plan_versions: value.plan_versions,
+                skipped_plans: value.skipped_plans,
+            })
+        }
+    }
+}
+
+pub mod diff {
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use meteroid_grpc::meteroid::api::plans::v1::{
+        plan_version_field_change::Kind, price_component_diff::ChangeType,
+        DiffPlanVersionsResponse, PlanVersionFieldChange, PriceComponentDiff,
+    };
+    use meteroid_store::domain::plan_version_diff::{
+        ComponentChangeType, ComponentDiff, FieldChange, FieldChangeKind, PlanVersionDiff,
+    };
+
+    fn field_change_to_server(change: FieldChange) -> PlanVersionFieldChange {
+        let kind = match change.kind {
+            FieldChangeKind::Setting => Kind::Setting,
+            FieldChangeKind::Price => Kind::Price,
+        };
+
+        PlanVersionFieldChange {
+            field: change.field,
+            kind: kind.into(),
+            before: change.before,
+            after: change.after,
+        }
+    }
+
+    fn component_diff_to_server(diff: ComponentDiff) -> PriceComponentDiff {
+        let change_type = match diff.change_type {
+            ComponentChangeType::Added => ChangeType::Added,
+            ComponentChangeType::Removed => ChangeType::Removed,
+            ComponentChangeType::Changed => ChangeType::Changed,
+        };
+
+        PriceComponentDiff {
+            change_type: change_type.into(),
+            has_price_changes: diff.has_price_changes(),
+            name: diff.name,
+            from_component_id: diff.from_component_id.as_proto(),
+            to_component_id: diff.to_component_id.as_proto(),
+            changes: diff
+                .changes
+                .into_iter()
+                .map(field_change_to_server)
+                .collect(),
+        }
+    }
+
+    pub struct DiffPlanVersionsResponseWrapper(pub DiffPlanVersionsResponse);
+
+    impl From<PlanVersionDiff> for DiffPlanVersionsResponseWrapper {
+        fn from(value: PlanVersionDiff) -> Self {
+            Self(DiffPlanVersionsResponse {
+                changes: value
+                    .changes
+                    .into_iter()
+                    .map(field_change_to_server)
+                    .collect(),
+                components: value
+                    .components
+                    .into_iter()
+                    .map(component_diff_to_server)
+                    .collect(),
+            })
+        }
+    }
+}
+
+pub mod migrations {
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use meteroid_grpc::meteroid::api::plans::v1::{
+        subscription_migration_item::Status, SubscriptionMigration, SubscriptionMigrationItem,
+        SubscriptionMigrationStrategy,
+    };
+    use meteroid_store::domain::enums::{
+        SubscriptionMigrationItemStatusEnum, SubscriptionMigrationStrategyEnum,
+    };
+    use meteroid_store::domain::subscription_migrations as domain;
+
+    pub fn strategy_to_domain(
+        strategy: SubscriptionMigrationStrategy,
+    ) -> SubscriptionMigrationStrategyEnum {
+        match strategy {
+            SubscriptionMigrationStrategy::AtNextRenewal => {
+                SubscriptionMigrationStrategyEnum::AtNextRenewal
+            }
+            SubscriptionMigrationStrategy::ImmediateWithProration => {
+                SubscriptionMigrationStrategyEnum::ImmediateWithProration
+            }
+            SubscriptionMigrationStrategy::Grandfather => {
+                SubscriptionMigrationStrategyEnum::Grandfather
+            }
+        }
+    }
+
+    fn strategy_to_server(
+        strategy: SubscriptionMigrationStrategyEnum,
+    ) -> SubscriptionMigrationStrategy {
+        match strategy {
+            SubscriptionMigrationStrategyEnum::AtNextRenewal => {
+                SubscriptionMigrationStrategy::AtNextRenewal
+            }
+            SubscriptionMigrationStrategyEnum::ImmediateWithProration => {
+                SubscriptionMigrationStrategy::ImmediateWithProration
+            }
+            SubscriptionMigrationStrategyEnum::Grandfather => {
+                SubscriptionMigrationStrategy::Grandfather
+            }
+        }
+    }
+
+    pub fn item_status_to_domain(status: Status) -> SubscriptionMigrationItemStatusEnum {
+        match status {
+            Status::Scheduled => SubscriptionMigrationItemStatusEnum::Scheduled,
+            Status::Migrated => SubscriptionMigrationItemStatusEnum::Migrated,
+            Status::Grandfathered => SubscriptionMigrationItemStatusEnum::Grandfathered,
+            Status::Failed => SubscriptionMigrationItemStatusEnum::Failed,
+        }
+    }
+
+    fn item_status_to_server(status: SubscriptionMigrationItemStatusEnum) -> Status {
+        match status {
+            SubscriptionMigrationItemStatusEnum::Scheduled => Status::Scheduled,
+            SubscriptionMigrationItemStatusEnum::Migrated => Status::Migrated,
+            SubscriptionMigrationItemStatusEnum::Grandfathered => Status::Grandfathered,
+            SubscriptionMigrationItemStatusEnum::Failed => Status::Failed,
+        }
+    }
+
+    pub fn migration_to_server(migration: domain::SubscriptionMigration) -> SubscriptionMigration {
+        SubscriptionMigration {
+            id: migration.id.as_proto(),
+            plan_id: migration.plan_id.as_proto(),
+            to_plan_version_id: migration.to_plan_version_id.as_proto(),
+            strategy: strategy_to_server(migration.strategy).into(),
+            total_count: migration.total_count,
+            processed_count: migration.processed_count,
+            failed_count: migration.failed_count,
+            mrr_delta: migration.mrr_delta,
+            created_at: migration.created_at.as_proto(),
+            completed_at: migration.completed_at.as_proto(),
+        }
+    }
+
+    pub fn item_to_server(item: domain::SubscriptionMigrationItem) -> SubscriptionMigrationItem {
+        SubscriptionMigrationItem {
+            id: item.id.as_proto(),
+            subscription_id: item.subscription_id.as_proto(),
+            from_plan_version_id: item.from_plan_version_id.as_proto(),
+            status: item_status_to_server(item.status).into(),
+            mrr_delta: item.mrr_delta,
+            prorated_amount: item.prorated_amount,
+            error: item.error,
+        }
+    }
+}
+
+pub mod features {
+    use meteroid_grpc::meteroid::api::plans::v1::PlanFeature;
+    use meteroid_store::domain::entitlements as domain;
+
+    pub fn feature_to_domain(feature: PlanFeature) -> domain::PlanFeature {
+        domain::PlanFeature {
+            key: feature.key,
+            enabled: feature.enabled,
+            limit: feature.limit,
+        }
+    }
+
+    pub fn feature_to_server(feature: domain::PlanFeature) -> PlanFeature {
+        PlanFeature {
+            key: feature.key,
+            enabled: feature.enabled,
+            limit: feature.limit,
+        }
+    }
+}
+
+pub mod effective_config {
+    use meteroid_grpc::meteroid::api::plans::v1::{
+        plan_version_effective_config::Source, PlanVersionEffectiveConfig,
+    };
+    use meteroid_store::domain::plan_defaults::{self, ConfigSource};
+
+    fn source_to_server(source: ConfigSource) -> i32 {
+        match source {
+            ConfigSource::PlanVersion => Source::PlanVersion,
+            ConfigSource::ProductFamily => Source::ProductFamily,
+            ConfigSource::Default => Source::Default,
+        }
+        .into()
+    }
+
+    pub fn effective_config_to_server(
+        config: plan_defaults::PlanVersionEffectiveConfig,
+    ) -> PlanVersionEffectiveConfig {
+        PlanVersionEffectiveConfig {
+            currency: config.currency.value,
+            currency_source: source_to_server(config.currency.source),
+            trial_duration_days: config.trial_duration_days.value.map(|d| d as u32),
+            trial_duration_source: source_to_server(config.trial_duration_days.source),
+            net_terms: config.net_terms.value as u32,
+            net_terms_source: source_to_server(config.net_terms.source),
+            tax_category: config.tax_category.value,
+            tax_category_source: source_to_server(config.tax_category.source),
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::plans::v1::plans_service_server::PlansServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod mapping;
 mod service;
 
@@ -5,34 +5,53 @@ use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::plans::v1::{
     list_plans_request::SortBy, plans_service_server::PlansService, CopyVersionToDraftRequest,
     CopyVersionToDraftResponse, CreateDraftPlanRequest, CreateDraftPlanResponse,
-    DiscardDraftVersionRequest, DiscardDraftVersionResponse, GetLastPublishedPlanVersionRequest,
-    GetLastPublishedPlanVersionResponse, GetPlanByExternalIdRequest, GetPlanByExternalIdResponse,
-    GetPlanByIdRequest, GetPlanByIdResponse, GetPlanOverviewByExternalIdRequest,
-    GetPlanOverviewByExternalIdResponse, GetPlanParametersRequest, GetPlanParametersResponse,
-    GetPlanVersionByIdRequest, GetPlanVersionByIdResponse, ListPlanVersionByIdRequest,
-    ListPlanVersionByIdResponse, ListPlansRequest, ListPlansResponse,
+    DiffPlanVersionsRequest, DiffPlanVersionsResponse, DiscardDraftVersionRequest,
+    DiscardDraftVersionResponse, ExportPlansRequest, ExportPlansResponse,
+    GetLastPublishedPlanVersionRequest, GetLastPublishedPlanVersionResponse,
+    GetPlanByExternalIdRequest, GetPlanByExternalIdResponse, GetPlanByIdRequest,
+    GetPlanByIdResponse, GetPlanOverviewByExternalIdRequest, GetPlanOverviewByExternalIdResponse,
+    GetPlanParametersRequest, GetPlanParametersResponse, GetPlanVersionByIdRequest,
+    GetPlanVersionByIdResponse, GetPlanVersionEffectiveConfigRequest,
+    GetPlanVersionEffectiveConfigResponse, GetSubscriptionMigrationRequest,
+    GetSubscriptionMigrationResponse, ImportPlansRequest, ImportPlansResponse,
+    ListPlanVersionByIdRequest, ListPlanVersionByIdResponse, ListPlanVersionFeaturesRequest,
+    ListPlanVersionFeaturesResponse, ListPlansRequest, ListPlansResponse,
     ListSubscribablePlanVersionRequest, ListSubscribablePlanVersionResponse,
-    PublishPlanVersionRequest, PublishPlanVersionResponse, UpdateDraftPlanOverviewRequest,
-    UpdateDraftPlanOverviewResponse, UpdatePlanTrialRequest, UpdatePlanTrialResponse,
-    UpdatePublishedPlanOverviewRequest, UpdatePublishedPlanOverviewResponse,
+    ListSubscriptionMigrationItemsRequest, ListSubscriptionMigrationItemsResponse,
+    MigrateSubscriptionsRequest, MigrateSubscriptionsResponse, PublishPlanVersionRequest,
+    PublishPlanVersionResponse, SetPlanVersionFeaturesRequest, SetPlanVersionFeaturesResponse,
+    UpdateDraftPlanOverviewRequest, UpdateDraftPlanOverviewResponse, UpdatePlanTrialRequest,
+    UpdatePlanTrialResponse, UpdatePublishedPlanOverviewRequest,
+    UpdatePublishedPlanOverviewResponse,
 };
 use meteroid_grpc::meteroid::api::shared::v1::BillingPeriod;
 
 use crate::api::plans::error::PlanApiError;
 
 use crate::api::domain_mapping::billing_period;
+use crate::api::plans::mapping::catalog::{
+    conflict_strategy_to_domain, ImportPlansResponseWrapper,
+};
+use crate::api::plans::mapping::diff::DiffPlanVersionsResponseWrapper;
+use crate::api::plans::mapping::effective_config::effective_config_to_server;
 use crate::api::plans::mapping::plans::{
     ActionAfterTrialWrapper, ListPlanVersionWrapper, ListPlanWrapper,
     ListSubscribablePlanVersionWrapper, PlanDetailsWrapper, PlanOverviewWrapper, PlanStatusWrapper,
-    PlanTypeWrapper, PlanVersionWrapper,
+    PlanTypeWrapper, PlanVersionWrapper, TrialModeWrapper,
 };
+use crate::api::plans::mapping::{features, migrations};
 use crate::api::shared::conversions::{FromProtoOpt, ProtoConv};
 use crate::api::utils::PaginationExt;
 use crate::{api::utils::parse_uuid, parse_uuid};
 use meteroid_store::domain;
+use meteroid_store::domain::plan_catalog::{CatalogDocument, CatalogImport};
+use meteroid_store::domain::subscription_migrations::SubscriptionMigrationNew;
 use meteroid_store::domain::{
     OrderByRequest, PlanAndVersionPatch, PlanFilters, PlanPatch, PlanVersionPatch, TrialPatch,
 };
+use meteroid_store::repositories::entitlements::EntitlementInterface;
+use meteroid_store::repositories::plan_catalog::PlanCatalogInterface;
+use meteroid_store::repositories::subscription_migrations::SubscriptionMigrationsInterface;
 use meteroid_store::repositories::PlansInterface;
 
 use super::PlanServiceComponents;
@@ -66,10 +85,11 @@ impl PlansService for PlanServiceComponents {
                 is_draft_version: true,
                 trial: None,
                 period_start_day: None,
-                net_terms: 0,
+                net_terms: None,
                 currency: None,
                 billing_cycles: None,
                 billing_periods: vec![],
+                tax_category: None,
             },
             price_components: vec![],
         };
@@ -347,7 +367,11 @@ impl PlansService for PlanServiceComponents {
             .map(|f| {
                 BillingPeriod::try_from(*f)
                     .map_err(|_| PlanApiError::InvalidArgument("billing period".to_string()))
-                    .map(billing_period::from_proto)
+                    .and_then(|f| {
+                        // the plans only offer the named periods
+                        billing_period::from_proto(f, None)
+                            .map_err(|e| PlanApiError::InvalidArgument(e.message().to_string()))
+                    })
             })
             .collect::<Result<Vec<domain::enums::BillingPeriodEnum>, PlanApiError>>()?;
 
@@ -360,6 +384,7 @@ impl PlansService for PlanServiceComponents {
                     currency: Some(req.currency),
                     net_terms: Some(req.net_terms as i32),
                     billing_periods: Some(frequencies),
+                    tax_category: Some(req.tax_category),
                 },
                 name: Some(req.name),
                 description: Some(req.description),
@@ -455,6 +480,7 @@ impl PlansService for PlanServiceComponents {
                             trialing_plan_id: Uuid::from_proto_opt(t.trialing_plan_id)?,
                             downgrade_plan_id: Uuid::from_proto_opt(t.downgrade_plan_id)?,
                             require_pre_authorization: t.trial_is_free,
+                            mode: TrialModeWrapper(t.trial_mode()).into(),
                         })
                     })
                     .transpose()?,
@@ -489,6 +515,236 @@ impl PlansService for PlanServiceComponents {
         }))
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn diff_plan_versions(
+        &self,
+        request: Request<DiffPlanVersionsRequest>,
+    ) -> Result<Response<DiffPlanVersionsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let from_plan_version_id = parse_uuid!(&req.from_plan_version_id)?;
+        let to_plan_version_id = parse_uuid!(&req.to_plan_version_id)?;
+
+        let diff = self
+            .store
+            .diff_plan_versions(from_plan_version_id, to_plan_version_id, tenant_id)
+            .await
+            .map_err(Into::<PlanApiError>::into)
+            .map(|x| DiffPlanVersionsResponseWrapper::from(x).0)?;
+
+        Ok(Response::new(diff))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn export_plans(
+        &self,
+        request: Request<ExportPlansRequest>,
+    ) -> Result<Response<ExportPlansResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let document = self
+            .store
+            .export_plan_catalog(tenant_id)
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        let document = serde_json::to_string_pretty(&document).map_err(|e| {
+            PlanApiError::StoreError("Failed to serialize the catalog".to_string(), Box::new(e))
+        })?;
+
+        Ok(Response::new(ExportPlansResponse { document }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn import_plans(
+        &self,
+        request: Request<ImportPlansRequest>,
+    ) -> Result<Response<ImportPlansResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let document: CatalogDocument = serde_json::from_str(&req.document).map_err(|e| {
+            PlanApiError::InvalidArgument(format!("invalid catalog document: {}", e))
+        })?;
+
+        let report = self
+            .store
+            .import_plan_catalog(
+                tenant_id,
+                actor,
+                CatalogImport {
+                    document,
+                    on_conflict: conflict_strategy_to_domain(req.on_conflict()),
+                    dry_run: req.dry_run,
+                },
+            )
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(ImportPlansResponseWrapper::from(report).0))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn migrate_subscriptions(
+        &self,
+        request: Request<MigrateSubscriptionsRequest>,
+    ) -> Result<Response<MigrateSubscriptionsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let strategy = migrations::strategy_to_domain(req.strategy());
+        let to_plan_version_id = parse_uuid!(&req.to_plan_version_id)?;
+        let from_plan_version_id = match &req.from_plan_version_id {
+            Some(id) => Some(parse_uuid!(id)?),
+            None => None,
+        };
+
+        let migration = self
+            .store
+            .migrate_subscriptions(SubscriptionMigrationNew {
+                tenant_id,
+                to_plan_version_id,
+                from_plan_version_id,
+                strategy,
+                include_grandfathered: req.include_grandfathered,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(MigrateSubscriptionsResponse {
+            migration: Some(migrations::migration_to_server(migration)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_subscription_migration(
+        &self,
+        request: Request<GetSubscriptionMigrationRequest>,
+    ) -> Result<Response<GetSubscriptionMigrationResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let migration_id = parse_uuid!(&req.migration_id)?;
+
+        let migration = self
+            .store
+            .get_subscription_migration(tenant_id, migration_id)
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(GetSubscriptionMigrationResponse {
+            migration: Some(migrations::migration_to_server(migration)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_subscription_migration_items(
+        &self,
+        request: Request<ListSubscriptionMigrationItemsRequest>,
+    ) -> Result<Response<ListSubscriptionMigrationItemsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let migration_id = parse_uuid!(&req.migration_id)?;
+        let status = req
+            .status
+            .map(|_| migrations::item_status_to_domain(req.status()));
+
+        let pagination_req = domain::PaginationRequest {
+            page: req.pagination.as_ref().map(|p| p.offset).unwrap_or(0),
+            per_page: req.pagination.as_ref().map(|p| p.limit),
+        };
+
+        let res = self
+            .store
+            .list_subscription_migration_items(tenant_id, migration_id, status, pagination_req)
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(ListSubscriptionMigrationItemsResponse {
+            pagination_meta: req.pagination.into_response(res.total_results as u32),
+            items: res
+                .items
+                .into_iter()
+                .map(migrations::item_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_plan_version_features(
+        &self,
+        request: Request<SetPlanVersionFeaturesRequest>,
+    ) -> Result<Response<SetPlanVersionFeaturesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let plan_version_id = parse_uuid!(&req.plan_version_id)?;
+
+        let res = self
+            .store
+            .set_plan_version_features(
+                tenant_id,
+                plan_version_id,
+                req.features
+                    .into_iter()
+                    .map(features::feature_to_domain)
+                    .collect(),
+            )
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(SetPlanVersionFeaturesResponse {
+            features: res.into_iter().map(features::feature_to_server).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_plan_version_features(
+        &self,
+        request: Request<ListPlanVersionFeaturesRequest>,
+    ) -> Result<Response<ListPlanVersionFeaturesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let plan_version_id = parse_uuid!(&req.plan_version_id)?;
+
+        let res = self
+            .store
+            .list_plan_version_features(tenant_id, plan_version_id)
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(ListPlanVersionFeaturesResponse {
+            features: res.into_iter().map(features::feature_to_server).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_plan_version_effective_config(
+        &self,
+        request: Request<GetPlanVersionEffectiveConfigRequest>,
+    ) -> Result<Response<GetPlanVersionEffectiveConfigResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let plan_version_id = parse_uuid!(&req.plan_version_id)?;
+
+        let config = self
+            .store
+            .get_plan_version_effective_config(plan_version_id, tenant_id)
+            .await
+            .map_err(Into::<PlanApiError>::into)?;
+
+        Ok(Response::new(GetPlanVersionEffectiveConfigResponse {
+            config: Some(effective_config_to_server(config)),
+        }))
+    }
+
     //
     // #[tracing::instrument(skip_all)]
     // async fn get_plan_parameters(
@@ -32,7 +32,15 @@ pub enum PriceComponentApiError {
 
 impl From<Report<StoreError>> for PriceComponentApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        Self::StoreError("Error in api price component service".to_string(), err)
+        match value.current_context() {
+            StoreError::InvalidMatrixDimensions(_) => {
+                Self::InvalidArgument(value.current_context().to_string())
+            }
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            _ => {
+                let err = Box::new(value.into_error());
+                Self::StoreError("Error in api price component service".to_string(), err)
+            }
+        }
     }
 }
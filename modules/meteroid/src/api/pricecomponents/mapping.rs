@@ -10,7 +10,9 @@ pub mod components {
 
     use meteroid_grpc::meteroid::api::shared::v1 as api_shared;
 
+    use meteroid_store::domain::price_component_validation::PriceComponentIssue;
     use meteroid_store::domain::price_components as domain;
+    use meteroid_store::domain::SubLineAttributes;
     use rust_decimal::Decimal;
     use tonic::Status;
     use uuid::Uuid;
@@ -24,6 +26,7 @@ pub mod components {
             fee: map_fee_to_domain(comp.fee)?,
             product_item_id: Uuid::from_proto_opt(comp.product_item_id)?,
             plan_version_id: Uuid::from_proto_ref(&comp.plan_version_id)?,
+            rate_card_id: Uuid::from_proto_opt(comp.rate_card_id)?,
         })
     }
 
@@ -39,6 +42,7 @@ pub mod components {
             fee: map_fee_to_domain(component.fee)?,
             product_item_id: Uuid::from_proto_opt(component.product_item_id)?,
             id: Uuid::from_proto_ref(&component.id)?,
+            rate_card_id: Uuid::from_proto_opt(component.rate_card_id)?,
         })
     }
 
@@ -51,7 +55,10 @@ pub mod components {
                         .iter()
                         .map(|rate| {
                             Ok::<_, Status>(domain::TermRate {
-                                term: billing_period::from_proto(rate.term()),
+                                term: billing_period::from_proto(
+                                    rate.term(),
+                                    rate.billing_period_months,
+                                )?,
                                 price: Decimal::from_proto_ref(&rate.price)?,
                             })
                         })
@@ -68,7 +75,10 @@ pub mod components {
                         .iter()
                         .map(|rate| {
                             Ok::<_, Status>(domain::TermRate {
-                                term: billing_period::from_proto(rate.term()),
+                                term: billing_period::from_proto(
+                                    rate.term(),
+                                    rate.billing_period_months,
+                                )?,
                                 price: Decimal::from_proto_ref(&rate.price)?,
                             })
                         })
@@ -89,6 +99,7 @@ pub mod components {
                             })
                         })
                         .collect::<Result<Vec<_>, _>>()?,
+                    cap: Decimal::from_proto_opt(fee.cap.clone())?,
                 }),
                 api::fee::FeeType::ExtraRecurring(fee) => {
                     let cadence = fee
@@ -101,7 +112,7 @@ pub mod components {
                         unit_price: Decimal::from_proto_ref(&fee.unit_price)?,
                         quantity: fee.quantity,
                         billing_type: billing_type_from_grpc(fee.billing_type())?,
-                        cadence: billing_period::from_proto(cadence),
+                        cadence: billing_period::from_proto(cadence, fee.billing_period_months)?,
                     })
                 }
                 api::fee::FeeType::OneTime(fee) => Ok(domain::FeeType::OneTime {
@@ -114,8 +125,16 @@ pub mod components {
                     Ok(domain::FeeType::Usage {
                         metric_id: Uuid::from_proto_ref(&fee.metric_id)?,
                         pricing: mapped,
+                        cap: Decimal::from_proto_opt(fee.cap.clone())?,
+                        included: fee.included,
                     })
                 }
+                api::fee::FeeType::Percent(fee) => Ok(domain::FeeType::Percent {
+                    metric_id: Uuid::from_proto_ref(&fee.metric_id)?,
+                    percentage: Decimal::from_proto_ref(&fee.percentage)?,
+                    minimum: Decimal::from_proto_opt(fee.minimum.clone())?,
+                    maximum: Decimal::from_proto_opt(fee.maximum.clone())?,
+                }),
             },
             None => Err(Status::invalid_argument("fee is missing")),
         }
@@ -127,6 +146,7 @@ pub mod components {
             name: comp.name.to_string(),
             fee: Some(map_fee_domain_to_api(comp.fee)),
             product_item_id: comp.product_item_id.as_proto(),
+            rate_card_id: comp.rate_card_id.as_proto(),
         }
     }
 
@@ -136,6 +156,7 @@ pub mod components {
                 let rates = rates
                     .into_iter()
                     .map(|rate| api::fee::TermRate {
+                        billing_period_months: billing_period::months_to_proto(&rate.term),
                         term: billing_period::to_proto(rate.term).into(),
                         price: rate.price.as_proto(),
                     })
@@ -154,6 +175,7 @@ pub mod components {
                 let rates = rates
                     .into_iter()
                     .map(|rate| api::fee::TermRate {
+                        billing_period_months: billing_period::months_to_proto(&rate.term),
                         term: billing_period::to_proto(rate.term).into(),
                         price: rate.price.as_proto(),
                     })
@@ -171,6 +193,7 @@ pub mod components {
             domain::FeeType::Capacity {
                 metric_id,
                 thresholds,
+                cap,
             } => {
                 let thresholds = thresholds
                     .into_iter()
@@ -184,6 +207,7 @@ pub mod components {
                 api::fee::FeeType::Capacity(api::fee::CapacityFee {
                     metric_id: metric_id.as_proto(),
                     thresholds,
+                    cap: cap.map(|cap| cap.as_proto()),
                 })
             }
             domain::FeeType::ExtraRecurring {
@@ -196,6 +220,7 @@ pub mod components {
                     unit_price: unit_price.as_proto(),
                     quantity,
                     billing_type: billing_type_to_grpc(billing_type).into(),
+                    billing_period_months: billing_period::months_to_proto(&cadence),
                     term: Some(billing_period::to_proto(cadence).into()), // TODO when is that optional ??
                 })
             }
@@ -206,15 +231,87 @@ pub mod components {
                 quantity,
                 unit_price: unit_price.as_proto(),
             }),
-            domain::FeeType::Usage { metric_id, pricing } => {
+            domain::FeeType::Usage {
+                metric_id,
+                pricing,
+                cap,
+                included,
+            } => {
                 let model = usage_pricing_model_to_grpc(&metric_id, &pricing);
 
-                api::fee::FeeType::Usage(model)
+                api::fee::FeeType::Usage(api::UsageFee {
+                    cap: cap.map(|cap| cap.as_proto()),
+                    included,
+                    ..model
+                })
             }
+            domain::FeeType::Percent {
+                metric_id,
+                percentage,
+                minimum,
+                maximum,
+            } => api::fee::FeeType::Percent(api::PercentFee {
+                metric_id: metric_id.as_proto(),
+                percentage: percentage.as_proto(),
+                minimum: minimum.map(|minimum| minimum.as_proto()),
+                maximum: maximum.map(|maximum| maximum.as_proto()),
+            }),
         };
 
         api::Fee {
             fee_type: Some(fee_type),
         }
     }
+
+    pub fn issue_domain_to_api(issue: PriceComponentIssue) -> api::PriceComponentIssue {
+        api::PriceComponentIssue {
+            field: issue.field,
+            message: issue.message,
+        }
+    }
+
+    pub fn tiered_pricing_preview_to_api(
+        preview: domain::TieredPricingPreview,
+    ) -> api::TieredPricingPreview {
+        api::TieredPricingPreview {
+            usage: preview.usage.as_proto(),
+            graduated: Some(tiered_price_to_api(preview.graduated)),
+            volume: Some(tiered_price_to_api(preview.volume)),
+        }
+    }
+
+    fn tiered_price_to_api(price: domain::TieredPrice) -> api::TieredPrice {
+        api::TieredPrice {
+            quantity: price.quantity.as_proto(),
+            total: price.total,
+            tiers: price
+                .sublines
+                .into_iter()
+                .filter_map(|subline| {
+                    let (first_unit, last_unit) = match subline.attributes? {
+                        SubLineAttributes::Tiered {
+                            first_unit,
+                            last_unit,
+                            ..
+                        } => (first_unit, last_unit),
+                        // the invoice displays the last unit of a volume tier, included in it
+                        SubLineAttributes::Volume {
+                            first_unit,
+                            last_unit,
+                            ..
+                        } => (first_unit, last_unit.map(|u| u + 1)),
+                        _ => return None,
+                    };
+
+                    Some(api::tiered_price::PricedTier {
+                        first_unit,
+                        last_unit,
+                        quantity: subline.quantity.as_proto(),
+                        unit_price: subline.unit_price.as_proto(),
+                        total: subline.total,
+                    })
+                })
+                .collect(),
+        }
+    }
 }
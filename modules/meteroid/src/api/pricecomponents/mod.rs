@@ -2,7 +2,7 @@ use meteroid_grpc::meteroid::api::components::v1::price_components_service_serve
 
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub(crate) mod ext;
 pub mod mapping;
 mod service;
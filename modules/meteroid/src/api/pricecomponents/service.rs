@@ -1,3 +1,4 @@
+use rust_decimal::Decimal;
 use tonic::{Request, Response, Status};
 use uuid::Uuid;
 
@@ -6,13 +7,17 @@ use meteroid_grpc::meteroid::api::components::v1::{
     price_components_service_server::PriceComponentsService, CreatePriceComponentRequest,
     CreatePriceComponentResponse, EditPriceComponentRequest, EditPriceComponentResponse,
     EmptyResponse, ListPriceComponentRequest, ListPriceComponentResponse,
-    RemovePriceComponentRequest,
+    PreviewTieredPricingRequest, PreviewTieredPricingResponse, RemovePriceComponentRequest,
+    ValidatePriceComponentRequest, ValidatePriceComponentResponse,
 };
 
+use meteroid_store::domain::price_component_validation::PriceComponentIssueSeverity;
+use meteroid_store::errors::StoreError;
 use meteroid_store::repositories::price_components::PriceComponentInterface;
 
 use crate::api::pricecomponents::error::PriceComponentApiError;
 use crate::api::shared::conversions::ProtoConv;
+use crate::api::subscriptions::ext::tier_row_from_grpc;
 use crate::{api::utils::parse_uuid, parse_uuid};
 use common_eventbus::Event;
 
@@ -63,13 +68,14 @@ impl PriceComponentsService for PriceComponentServiceComponents {
 
         let component = self
             .store
-            .create_price_component(mapped)
+            .create_price_component(mapped, tenant_id)
             .await
-            .map_err(|err| {
-                PriceComponentApiError::StoreError(
+            .map_err(|err| match err.current_context() {
+                StoreError::InvalidMatrixDimensions(_) => err.into(),
+                _ => PriceComponentApiError::StoreError(
                     "Failed to create price components".to_string(),
                     Box::new(err.into_error()),
-                )
+                ),
             })?;
         let response = mapping::components::domain_to_api(component.clone());
 
@@ -103,11 +109,12 @@ impl PriceComponentsService for PriceComponentServiceComponents {
             .store
             .update_price_component(component, tenant_id, Uuid::from_proto(req.plan_version_id)?)
             .await
-            .map_err(|err| {
-                PriceComponentApiError::StoreError(
+            .map_err(|err| match err.current_context() {
+                StoreError::InvalidMatrixDimensions(_) => err.into(),
+                _ => PriceComponentApiError::StoreError(
                     "Failed to edit price component".to_string(),
                     Box::new(err.into_error()),
-                )
+                ),
             })?;
         let component = component.ok_or(Status::internal("No element was updated"))?;
 
@@ -161,4 +168,85 @@ impl PriceComponentsService for PriceComponentServiceComponents {
 
         Ok(Response::new(EmptyResponse {}))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn validate_price_component(
+        &self,
+        request: Request<ValidatePriceComponentRequest>,
+    ) -> Result<Response<ValidatePriceComponentResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let fee = mapping::components::map_fee_to_domain(req.fee)?;
+
+        let issues = self
+            .store
+            .validate_price_component(&fee, tenant_id, parse_uuid!(&req.plan_version_id)?)
+            .await
+            .map_err(|err| {
+                PriceComponentApiError::StoreError(
+                    "Failed to validate price component".to_string(),
+                    Box::new(err.into_error()),
+                )
+            })?;
+
+        let (errors, warnings): (Vec<_>, Vec<_>) = issues
+            .into_iter()
+            .partition(|i| i.severity == PriceComponentIssueSeverity::Error);
+
+        Ok(Response::new(ValidatePriceComponentResponse {
+            errors: errors
+                .into_iter()
+                .map(mapping::components::issue_domain_to_api)
+                .collect(),
+            warnings: warnings
+                .into_iter()
+                .map(mapping::components::issue_domain_to_api)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn preview_tiered_pricing(
+        &self,
+        request: Request<PreviewTieredPricingRequest>,
+    ) -> Result<Response<PreviewTieredPricingResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let tiers = req
+            .tiers
+            .ok_or(PriceComponentApiError::MissingArgument("tiers".to_string()))?;
+
+        let rows = tiers
+            .rows
+            .iter()
+            .map(tier_row_from_grpc)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let usages = req
+            .usages
+            .iter()
+            .map(Decimal::from_proto_ref)
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let previews = self
+            .store
+            .preview_tiered_pricing(
+                tenant_id,
+                parse_uuid!(&req.plan_version_id)?,
+                &rows,
+                tiers.block_size,
+                &usages,
+            )
+            .await
+            .map_err(Into::<PriceComponentApiError>::into)?;
+
+        Ok(Response::new(PreviewTieredPricingResponse {
+            previews: previews
+                .into_iter()
+                .map(mapping::components::tiered_pricing_preview_to_api)
+                .collect(),
+        }))
+    }
 }
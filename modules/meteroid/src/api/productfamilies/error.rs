@@ -8,6 +8,10 @@ use meteroid_store::errors::StoreError;
 
 #[derive(Debug, Error, ErrorAsTonic)]
 pub enum ProductFamilyApiError {
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
     #[error("Store error: {0}")]
     #[code(Internal)]
     StoreError(String, #[source] Box<dyn Error>),
@@ -15,7 +19,12 @@ pub enum ProductFamilyApiError {
 
 impl From<Report<StoreError>> for ProductFamilyApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        Self::StoreError("Error in product_family service".to_string(), err)
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            _ => {
+                let err = Box::new(value.into_error());
+                Self::StoreError("Error in product_family service".to_string(), err)
+            }
+        }
     }
 }
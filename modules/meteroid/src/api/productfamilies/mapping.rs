@@ -1,16 +1,35 @@
 pub mod product_family {
-    use meteroid_grpc::meteroid::api::productfamilies::v1::ProductFamily;
+    use meteroid_grpc::meteroid::api::productfamilies::v1::{ProductFamily, ProductFamilyDefaults};
     use meteroid_store::domain;
 
     pub struct ProductFamilyWrapper(pub ProductFamily);
 
     impl From<domain::ProductFamily> for ProductFamilyWrapper {
         fn from(domain_family: domain::ProductFamily) -> Self {
+            let defaults = domain_family.defaults();
             ProductFamilyWrapper(ProductFamily {
                 id: domain_family.id.to_string(),
                 name: domain_family.name,
                 external_id: domain_family.external_id,
+                defaults: Some(ProductFamilyDefaults {
+                    currency: defaults.currency,
+                    trial_duration_days: defaults.trial_duration_days.map(|d| d as u32),
+                    net_terms: defaults.net_terms.map(|n| n as u32),
+                    tax_category: defaults.tax_category,
+                }),
             })
         }
     }
+
+    pub fn defaults_to_domain(
+        defaults: Option<ProductFamilyDefaults>,
+    ) -> domain::ProductFamilyDefaults {
+        let defaults = defaults.unwrap_or_default();
+        domain::ProductFamilyDefaults {
+            currency: defaults.currency,
+            trial_duration_days: defaults.trial_duration_days.map(|d| d as i32),
+            net_terms: defaults.net_terms.map(|n| n as i32),
+            tax_category: defaults.tax_category,
+        }
+    }
 }
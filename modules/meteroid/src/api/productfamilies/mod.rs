@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::productfamilies::v1::product_families_service_server::ProductFamiliesServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod mapping;
 mod service;
 
@@ -5,12 +5,15 @@ use meteroid_grpc::meteroid::api::productfamilies::v1::{
     product_families_service_server::ProductFamiliesService, CreateProductFamilyRequest,
     CreateProductFamilyResponse, GetProductFamilyByExternalIdRequest,
     GetProductFamilyByExternalIdResponse, ListProductFamiliesRequest, ListProductFamiliesResponse,
+    UpdateProductFamilyDefaultsRequest, UpdateProductFamilyDefaultsResponse,
 };
 use meteroid_store::domain;
 use meteroid_store::repositories::ProductFamilyInterface;
 
 use crate::api::productfamilies::error::ProductFamilyApiError;
-use crate::api::productfamilies::mapping::product_family::ProductFamilyWrapper;
+use crate::api::productfamilies::mapping::product_family::{
+    defaults_to_domain, ProductFamilyWrapper,
+};
 
 use super::ProductFamilyServiceComponents;
 
@@ -46,6 +49,8 @@ impl ProductFamiliesService for ProductFamilyServiceComponents {
         let tenant_id = request.tenant()?;
         let req = request.into_inner();
 
+        let defaults = defaults_to_domain(req.defaults);
+
         let rs = self
             .store
             .insert_product_family(
@@ -53,6 +58,10 @@ impl ProductFamiliesService for ProductFamilyServiceComponents {
                     name: req.name,
                     external_id: req.external_id,
                     tenant_id,
+                    default_currency: defaults.currency,
+                    default_trial_duration_days: defaults.trial_duration_days,
+                    default_net_terms: defaults.net_terms,
+                    default_tax_category: defaults.tax_category,
                 },
                 Some(actor),
             )
@@ -84,4 +93,28 @@ impl ProductFamiliesService for ProductFamilyServiceComponents {
             product_family: Some(rs),
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_product_family_defaults(
+        &self,
+        request: Request<UpdateProductFamilyDefaultsRequest>,
+    ) -> Result<Response<UpdateProductFamilyDefaultsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let rs = self
+            .store
+            .update_product_family_defaults(
+                req.external_id.as_str(),
+                defaults_to_domain(req.defaults),
+                tenant_id,
+            )
+            .await
+            .map_err(Into::<ProductFamilyApiError>::into)
+            .map(|x| ProductFamilyWrapper::from(x).0)?;
+
+        Ok(Response::new(UpdateProductFamilyDefaultsResponse {
+            product_family: Some(rs),
+        }))
+    }
 }
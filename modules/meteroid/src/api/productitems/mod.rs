@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::products::v1::products_service_server::ProductsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 mod mapping;
 mod service;
 
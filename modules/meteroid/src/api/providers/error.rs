@@ -0,0 +1,41 @@
+use error_stack::Report;
+use std::error::Error;
+use thiserror::Error;
+
+use common_grpc_error_as_tonic_macros_impl::ErrorAsTonic;
+use meteroid_store::errors::StoreError;
+
+#[derive(Debug, Error, ErrorAsTonic)]
+pub enum ProviderApiError {
+    #[error("Missing argument: {0}")]
+    #[code(InvalidArgument)]
+    MissingArgument(String),
+
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    #[code(NotFound)]
+    NotFound(String),
+
+    #[error("Provider error: {0}")]
+    #[code(Unavailable)]
+    ProviderError(String),
+
+    #[error("Store error: {0}")]
+    #[code(Internal)]
+    StoreError(String, #[source] Box<dyn Error>),
+}
+
+impl From<Report<StoreError>> for ProviderApiError {
+    fn from(value: Report<StoreError>) -> Self {
+        match value.current_context() {
+            StoreError::ValueNotFound(msg) => Self::NotFound(msg.clone()),
+            _ => Self::StoreError(
+                "Error in providers service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
+    }
+}
@@ -0,0 +1,119 @@
+pub mod providers {
+    use crate::api::providers::error::ProviderApiError;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::invoices::v1::InvoicingProvider;
+    use meteroid_grpc::meteroid::api::providers::v1::provider_credentials::Credentials;
+    use meteroid_grpc::meteroid::api::providers::v1::{
+        ProviderConfig as ProviderConfigProto, UpsertProviderConfigRequest,
+    };
+    use meteroid_store::domain::configs::{
+        ApiSecurity, ProviderConfig, ProviderConfigNew, WebhookSecurity,
+    };
+    use meteroid_store::domain::enums::InvoicingProviderEnum;
+    use uuid::Uuid;
+
+    const HINT_LENGTH: usize = 4;
+
+    pub fn provider_server_to_domain(
+        value: i32,
+    ) -> Result<InvoicingProviderEnum, ProviderApiError> {
+        let provider = InvoicingProvider::try_from(value)
+            .map_err(|_| ProviderApiError::InvalidArgument("provider".to_string()))?;
+
+        Ok(match provider {
+            InvoicingProvider::Stripe => InvoicingProviderEnum::Stripe,
+            InvoicingProvider::Manual => InvoicingProviderEnum::Manual,
+            InvoicingProvider::Adyen => InvoicingProviderEnum::Adyen,
+            InvoicingProvider::GoCardless => InvoicingProviderEnum::GoCardless,
+        })
+    }
+
+    fn provider_domain_to_server(value: InvoicingProviderEnum) -> InvoicingProvider {
+        match value {
+            InvoicingProviderEnum::Stripe => InvoicingProvider::Stripe,
+            InvoicingProviderEnum::Manual => InvoicingProvider::Manual,
+            InvoicingProviderEnum::Adyen => InvoicingProvider::Adyen,
+            InvoicingProviderEnum::GoCardless => InvoicingProvider::GoCardless,
+        }
+    }
+
+    fn secret_hint(secret: &str) -> String {
+        let chars: Vec<char> = secret.chars().collect();
+        let visible = chars.len().saturating_sub(HINT_LENGTH);
+        format!("...{}", chars[visible..].iter().collect::<String>())
+    }
+
+    pub fn domain_to_server(config: ProviderConfig) -> ProviderConfigProto {
+        ProviderConfigProto {
+            id: config.id.to_string(),
+            provider: provider_domain_to_server(config.invoicing_provider).into(),
+            is_default: config.is_default,
+            fallback_priority: config.fallback_priority,
+            api_secret_hint: secret_hint(&config.api_security.api_key),
+            created_at: Some(chrono_to_timestamp(config.created_at)),
+        }
+    }
+
+    pub fn upsert_req_server_to_domain(
+        req: UpsertProviderConfigRequest,
+        tenant_id: Uuid,
+    ) -> Result<ProviderConfigNew, ProviderApiError> {
+        let credentials = req
+            .credentials
+            .ok_or(ProviderApiError::MissingArgument("credentials".to_string()))?
+            .credentials
+            .ok_or(ProviderApiError::MissingArgument(
+                "credentials.credentials".to_string(),
+            ))?;
+
+        let cfg = match credentials {
+            Credentials::Stripe(stripe) => ProviderConfigNew {
+                tenant_id,
+                invoicing_provider: InvoicingProviderEnum::Stripe,
+                enabled: true,
+                webhook_security: WebhookSecurity {
+                    secret: stripe.webhook_secret,
+                },
+                api_security: ApiSecurity {
+                    api_key: stripe.api_secret,
+                    merchant_account: None,
+                    live_url_prefix: None,
+                },
+                is_default: req.is_default,
+                fallback_priority: req.fallback_priority,
+            },
+            Credentials::Adyen(adyen) => ProviderConfigNew {
+                tenant_id,
+                invoicing_provider: InvoicingProviderEnum::Adyen,
+                enabled: true,
+                webhook_security: WebhookSecurity {
+                    secret: adyen.hmac_key,
+                },
+                api_security: ApiSecurity {
+                    api_key: adyen.api_key,
+                    merchant_account: Some(adyen.merchant_account),
+                    live_url_prefix: adyen.live_url_prefix,
+                },
+                is_default: req.is_default,
+                fallback_priority: req.fallback_priority,
+            },
+            Credentials::GoCardless(gocardless) => ProviderConfigNew {
+                tenant_id,
+                invoicing_provider: InvoicingProviderEnum::GoCardless,
+                enabled: true,
+                webhook_security: WebhookSecurity {
+                    secret: gocardless.webhook_secret,
+                },
+                api_security: ApiSecurity {
+                    api_key: gocardless.access_token,
+                    merchant_account: None,
+                    live_url_prefix: None,
+                },
+                is_default: req.is_default,
+                fallback_priority: req.fallback_priority,
+            },
+        };
+
+        Ok(cfg)
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::providers::v1::providers_service_server::ProvidersServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct ProvidersServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> ProvidersServiceServer<ProvidersServiceComponents> {
+    let inner = ProvidersServiceComponents { store };
+    ProvidersServiceServer::new(inner)
+}
@@ -0,0 +1,156 @@
+use secrecy::SecretString;
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::providers::v1::{
+    providers_service_server::ProvidersService, DisableProviderConfigRequest,
+    DisableProviderConfigResponse, ListProviderConfigsRequest, ListProviderConfigsResponse,
+    SetDefaultProviderRequest, SetDefaultProviderResponse, SetProviderFallbackRequest,
+    SetProviderFallbackResponse, SyncStripeCustomersRequest, SyncStripeCustomersResponse,
+    UpsertProviderConfigRequest, UpsertProviderConfigResponse,
+};
+use meteroid_store::domain::enums::InvoicingProviderEnum;
+use meteroid_store::repositories::configs::ConfigsInterface;
+
+use crate::adapters::stripe::Stripe;
+use crate::api::providers::error::ProviderApiError;
+
+use super::{mapping, ProvidersServiceComponents};
+
+#[tonic::async_trait]
+impl ProvidersService for ProvidersServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn list_provider_configs(
+        &self,
+        request: Request<ListProviderConfigsRequest>,
+    ) -> Result<Response<ListProviderConfigsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let configs = self
+            .store
+            .list_provider_configs(tenant_id)
+            .await
+            .map_err(Into::<ProviderApiError>::into)?
+            .into_iter()
+            .map(mapping::providers::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListProviderConfigsResponse { configs }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn upsert_provider_config(
+        &self,
+        request: Request<UpsertProviderConfigRequest>,
+    ) -> Result<Response<UpsertProviderConfigResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let cfg = mapping::providers::upsert_req_server_to_domain(req, tenant_id)?;
+
+        let config = self
+            .store
+            .insert_provider_config(cfg)
+            .await
+            .map(mapping::providers::domain_to_server)
+            .map_err(Into::<ProviderApiError>::into)?;
+
+        Ok(Response::new(UpsertProviderConfigResponse {
+            config: Some(config),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_default_provider(
+        &self,
+        request: Request<SetDefaultProviderRequest>,
+    ) -> Result<Response<SetDefaultProviderResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let provider = mapping::providers::provider_server_to_domain(req.provider)?;
+
+        let config = self
+            .store
+            .set_default_provider_config(provider, tenant_id)
+            .await
+            .map(mapping::providers::domain_to_server)
+            .map_err(Into::<ProviderApiError>::into)?;
+
+        Ok(Response::new(SetDefaultProviderResponse {
+            config: Some(config),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_provider_fallback(
+        &self,
+        request: Request<SetProviderFallbackRequest>,
+    ) -> Result<Response<SetProviderFallbackResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let provider = mapping::providers::provider_server_to_domain(req.provider)?;
+
+        let config = self
+            .store
+            .set_provider_config_fallback_priority(provider, tenant_id, req.fallback_priority)
+            .await
+            .map(mapping::providers::domain_to_server)
+            .map_err(Into::<ProviderApiError>::into)?;
+
+        Ok(Response::new(SetProviderFallbackResponse {
+            config: Some(config),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn disable_provider_config(
+        &self,
+        request: Request<DisableProviderConfigRequest>,
+    ) -> Result<Response<DisableProviderConfigResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let provider = mapping::providers::provider_server_to_domain(req.provider)?;
+
+        self.store
+            .disable_provider_config(provider, tenant_id)
+            .await
+            .map_err(Into::<ProviderApiError>::into)?;
+
+        Ok(Response::new(DisableProviderConfigResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn sync_stripe_customers(
+        &self,
+        request: Request<SyncStripeCustomersRequest>,
+    ) -> Result<Response<SyncStripeCustomersResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let config = self
+            .store
+            .find_provider_config(InvoicingProviderEnum::Stripe, tenant_id)
+            .await
+            .map_err(Into::<ProviderApiError>::into)?;
+
+        let report = Stripe::get()
+            .sync_customers(
+                &self.store,
+                tenant_id,
+                actor,
+                SecretString::new(config.api_security.api_key),
+            )
+            .await
+            .map_err(|e| ProviderApiError::ProviderError(e.to_string()))?;
+
+        Ok(Response::new(SyncStripeCustomersResponse {
+            created: report.created,
+            linked: report.linked,
+            updated: report.updated,
+            failed: report.failed,
+        }))
+    }
+}
@@ -0,0 +1,47 @@
+use error_stack::Report;
+use std::error::Error;
+use thiserror::Error;
+
+use common_grpc_error_as_tonic_macros_impl::ErrorAsTonic;
+use meteroid_store::errors::StoreError;
+
+#[derive(Debug, Error, ErrorAsTonic)]
+pub enum RateCardApiError {
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    #[code(NotFound)]
+    NotFound(String),
+
+    #[error("Rate card already exists")]
+    #[code(AlreadyExists)]
+    AlreadyExists,
+
+    #[error("Store error: {0}")]
+    #[code(Internal)]
+    StoreError(String, #[source] Box<dyn Error>),
+}
+
+impl From<Report<StoreError>> for RateCardApiError {
+    fn from(value: Report<StoreError>) -> Self {
+        let mut err = value.current_context();
+
+        loop {
+            if let StoreError::TransactionStoreError(inner_report) = err {
+                err = inner_report.current_context();
+                continue;
+            }
+            return match err {
+                StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+                StoreError::ValueNotFound(msg) => Self::NotFound(msg.clone()),
+                StoreError::DuplicateValue { .. } => Self::AlreadyExists,
+                _ => Self::StoreError(
+                    "Error in rate cards service".to_string(),
+                    Box::new(value.into_error()),
+                ),
+            };
+        }
+    }
+}
@@ -0,0 +1,57 @@
+pub mod ratecards {
+    use meteroid_grpc::meteroid::api::components::v1 as api_components;
+    use meteroid_grpc::meteroid::api::ratecards::v1 as server;
+    use meteroid_store::domain::rate_cards as domain;
+    use tonic::Status;
+    use uuid::Uuid;
+
+    use crate::api::shared::conversions::*;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use crate::api::subscriptions::ext::{
+        usage_pricing_model_from_grpc, usage_pricing_model_to_grpc,
+    };
+
+    pub fn rate_card_to_server(rate_card: domain::RateCard) -> server::RateCard {
+        server::RateCard {
+            id: rate_card.id.as_proto(),
+            name: rate_card.name,
+            description: rate_card.description,
+            currency: rate_card.currency,
+            created_at: Some(chrono_to_timestamp(rate_card.created_at)),
+        }
+    }
+
+    pub fn version_to_server(version: domain::RateCardVersion) -> server::RateCardVersion {
+        server::RateCardVersion {
+            id: version.id.as_proto(),
+            version: version.version,
+            rates: version
+                .rates
+                .iter()
+                .map(|rate| usage_pricing_model_to_grpc(&rate.metric_id, &rate.pricing))
+                .collect(),
+            created_at: Some(chrono_to_timestamp(version.created_at)),
+        }
+    }
+
+    pub fn details_to_server(details: domain::RateCardDetails) -> server::RateCardDetails {
+        server::RateCardDetails {
+            rate_card: Some(rate_card_to_server(details.rate_card)),
+            current_version: Some(version_to_server(details.current_version)),
+        }
+    }
+
+    pub fn rates_from_server(
+        rates: &[api_components::UsageFee],
+    ) -> Result<Vec<domain::RateCardRate>, Status> {
+        rates
+            .iter()
+            .map(|rate| {
+                Ok::<_, Status>(domain::RateCardRate {
+                    metric_id: Uuid::from_proto_ref(&rate.metric_id)?,
+                    pricing: usage_pricing_model_from_grpc(rate)?,
+                })
+            })
+            .collect()
+    }
+}
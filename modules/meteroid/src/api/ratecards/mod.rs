@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::ratecards::v1::rate_cards_service_server::RateCardsServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct RateCardsServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> RateCardsServiceServer<RateCardsServiceComponents> {
+    let inner = RateCardsServiceComponents { store };
+    RateCardsServiceServer::new(inner)
+}
@@ -0,0 +1,130 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::ratecards::v1::rate_cards_service_server::RateCardsService;
+use meteroid_grpc::meteroid::api::ratecards::v1::{
+    CreateRateCardRequest, CreateRateCardResponse, GetRateCardRequest, GetRateCardResponse,
+    ListRateCardVersionsRequest, ListRateCardVersionsResponse, ListRateCardsRequest,
+    ListRateCardsResponse, PublishRateCardVersionRequest, PublishRateCardVersionResponse,
+};
+use meteroid_store::domain::rate_cards::{RateCardNew, RateCardVersionNew};
+use meteroid_store::repositories::rate_cards::RateCardInterface;
+
+use crate::api::ratecards::error::RateCardApiError;
+use crate::api::ratecards::mapping::ratecards;
+use crate::api::ratecards::RateCardsServiceComponents;
+use crate::{api::utils::parse_uuid, parse_uuid};
+
+#[tonic::async_trait]
+impl RateCardsService for RateCardsServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn list_rate_cards(
+        &self,
+        request: Request<ListRateCardsRequest>,
+    ) -> Result<Response<ListRateCardsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let rate_cards = self
+            .store
+            .list_rate_cards(tenant_id)
+            .await
+            .map_err(Into::<RateCardApiError>::into)?
+            .into_iter()
+            .map(ratecards::rate_card_to_server)
+            .collect();
+
+        Ok(Response::new(ListRateCardsResponse { rate_cards }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_rate_card(
+        &self,
+        request: Request<GetRateCardRequest>,
+    ) -> Result<Response<GetRateCardResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let rate_card = self
+            .store
+            .get_rate_card(tenant_id, parse_uuid!(&req.rate_card_id)?)
+            .await
+            .map_err(Into::<RateCardApiError>::into)?;
+
+        Ok(Response::new(GetRateCardResponse {
+            rate_card: Some(ratecards::details_to_server(rate_card)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_rate_card_versions(
+        &self,
+        request: Request<ListRateCardVersionsRequest>,
+    ) -> Result<Response<ListRateCardVersionsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let versions = self
+            .store
+            .list_rate_card_versions(tenant_id, parse_uuid!(&req.rate_card_id)?)
+            .await
+            .map_err(Into::<RateCardApiError>::into)?
+            .into_iter()
+            .map(ratecards::version_to_server)
+            .collect();
+
+        Ok(Response::new(ListRateCardVersionsResponse { versions }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_rate_card(
+        &self,
+        request: Request<CreateRateCardRequest>,
+    ) -> Result<Response<CreateRateCardResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let rate_card = self
+            .store
+            .create_rate_card(RateCardNew {
+                tenant_id,
+                name: req.name,
+                description: req.description,
+                currency: req.currency,
+                rates: ratecards::rates_from_server(&req.rates)?,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<RateCardApiError>::into)?;
+
+        Ok(Response::new(CreateRateCardResponse {
+            rate_card: Some(ratecards::details_to_server(rate_card)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn publish_rate_card_version(
+        &self,
+        request: Request<PublishRateCardVersionRequest>,
+    ) -> Result<Response<PublishRateCardVersionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let publication = self
+            .store
+            .publish_rate_card_version(RateCardVersionNew {
+                tenant_id,
+                rate_card_id: parse_uuid!(&req.rate_card_id)?,
+                rates: ratecards::rates_from_server(&req.rates)?,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<RateCardApiError>::into)?;
+
+        Ok(Response::new(PublishRateCardVersionResponse {
+            rate_card: Some(ratecards::details_to_server(publication.rate_card)),
+            updated_price_components: publication.updated_price_components as u32,
+        }))
+    }
+}
@@ -0,0 +1,140 @@
+// masking of the sensitive fields of the api responses, depending on the role of the caller
+
+use common_grpc::middleware::server::auth::TenantRole;
+use meteroid_grpc::meteroid::api::customers::v1 as api_customers;
+use meteroid_grpc::meteroid::api::invoices::v1 as api_invoices;
+
+const MASK: &str = "***";
+
+/// Whether the role sees the customer PII and the payment metadata
+pub(crate) fn sees_sensitive_data(role: TenantRole) -> bool {
+    match role {
+        TenantRole::Admin | TenantRole::Billing => true,
+        TenantRole::ReadOnly => false,
+    }
+}
+
+/// A response masking its customer PII and dropping its payment metadata for the limited roles.
+/// The customer name is kept, so that the customer can still be identified
+pub(crate) trait Redact: Sized {
+    fn redact(&mut self);
+
+    fn redacted_for(mut self, role: TenantRole) -> Self {
+        if !sees_sensitive_data(role) {
+            self.redact();
+        }
+        self
+    }
+}
+
+/// Keeps the first character and the domain, ex: j***@example.com
+fn mask_email(email: &str) -> String {
+    match email.split_once('@') {
+        Some((local, domain)) => format!(
+            "{}{}@{}",
+            local.chars().next().map(String::from).unwrap_or_default(),
+            MASK,
+            domain
+        ),
+        None => MASK.to_string(),
+    }
+}
+
+fn mask_opt(value: &mut Option<String>, mask: fn(&str) -> String) {
+    if let Some(v) = value.as_mut() {
+        *v = mask(v);
+    }
+}
+
+fn mask_all(_: &str) -> String {
+    MASK.to_string()
+}
+
+impl Redact for api_customers::Address {
+    fn redact(&mut self) {
+        mask_opt(&mut self.line1, mask_all);
+        mask_opt(&mut self.line2, mask_all);
+        mask_opt(&mut self.zip_code, mask_all);
+    }
+}
+
+impl Redact for api_customers::CustomerBillingConfig {
+    fn redact(&mut self) {
+        if let Some(api_customers::customer_billing_config::BillingConfigOneof::Stripe(stripe)) =
+            self.billing_config_oneof.as_mut()
+        {
+            stripe.customer_id = MASK.to_string();
+            stripe.default_payment_method_id = None;
+        }
+    }
+}
+
+impl Redact for api_customers::Customer {
+    fn redact(&mut self) {
+        mask_opt(&mut self.email, mask_email);
+        mask_opt(&mut self.invoicing_email, mask_email);
+        mask_opt(&mut self.phone, mask_all);
+        if let Some(address) = self.billing_address.as_mut() {
+            address.redact();
+        }
+        if let Some(address) = self
+            .shipping_address
+            .as_mut()
+            .and_then(|s| s.address.as_mut())
+        {
+            address.redact();
+        }
+        if let Some(billing_config) = self.billing_config.as_mut() {
+            billing_config.redact();
+        }
+    }
+}
+
+impl Redact for api_customers::CustomerBrief {
+    fn redact(&mut self) {
+        mask_opt(&mut self.email, mask_email);
+    }
+}
+
+impl Redact for api_invoices::DetailedInvoice {
+    fn redact(&mut self) {
+        if let Some(address) = self
+            .customer_details
+            .as_mut()
+            .and_then(|c| c.billing_address.as_mut())
+        {
+            address.redact();
+        }
+        // the invoice of the payment provider, and the keys of the public payment pages
+        self.external_invoice_id = None;
+        self.document_sharing_key = None;
+        self.hosted_invoice_url = None;
+        self.external_pdf_url = None;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mask_email() {
+        assert_eq!(mask_email("john@example.com"), "j***@example.com");
+        assert_eq!(mask_email("@example.com"), "***@example.com");
+        assert_eq!(mask_email("not-an-email"), "***");
+    }
+
+    #[test]
+    fn test_redacted_for() {
+        let customer = api_customers::CustomerBrief {
+            email: Some("john@example.com".to_string()),
+            ..Default::default()
+        };
+
+        let admin = customer.clone().redacted_for(TenantRole::Admin);
+        assert_eq!(admin.email.as_deref(), Some("john@example.com"));
+
+        let read_only = customer.redacted_for(TenantRole::ReadOnly);
+        assert_eq!(read_only.email.as_deref(), Some("j***@example.com"));
+    }
+}
@@ -0,0 +1,150 @@
+pub mod reports {
+    use chrono::Months;
+    use chrono::NaiveDate;
+    use meteroid_grpc::meteroid::api::reports::v1 as server;
+    use meteroid_store::domain::reports::{
+        MrrReportPeriod, ReportGranularity, ReportRequest, SubscriptionSegment,
+    };
+    use tonic::Status;
+    use uuid::Uuid;
+
+    use crate::api::domain_mapping::billing_period;
+    use crate::api::reports::error::ReportApiError;
+    use crate::api::shared::mapping::date::{chrono_from_proto, chrono_to_proto};
+    use crate::api::utils::parse_uuid;
+
+    fn granularity_from_server(granularity: server::ReportGranularity) -> ReportGranularity {
+        match granularity {
+            server::ReportGranularity::Day => ReportGranularity::Day,
+            server::ReportGranularity::Week => ReportGranularity::Week,
+            server::ReportGranularity::Month => ReportGranularity::Month,
+            server::ReportGranularity::Quarter => ReportGranularity::Quarter,
+            server::ReportGranularity::Year => ReportGranularity::Year,
+        }
+    }
+
+    /// Defaults to the last 12 months, by month
+    pub fn filter_server_to_domain(
+        filter: Option<server::ReportFilter>,
+        tenant_id: Uuid,
+    ) -> Result<ReportRequest, Status> {
+        let filter = filter.ok_or(ReportApiError::MissingArgument("filter".to_string()))?;
+
+        let granularity = server::ReportGranularity::try_from(filter.granularity)
+            .map_err(|_| ReportApiError::InvalidArgument("granularity".to_string()))?;
+
+        let now = chrono::Utc::now().naive_utc().date();
+        let start_date = filter
+            .start_date
+            .and_then(chrono_from_proto)
+            .unwrap_or(now.checked_sub_months(Months::new(12)).unwrap_or(now));
+        let end_date = filter.end_date.and_then(chrono_from_proto).unwrap_or(now);
+
+        let plan_ids = filter
+            .plan_ids
+            .iter()
+            .map(|plan_id| parse_uuid(plan_id, "plan_id"))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(ReportRequest {
+            tenant_id,
+            start_date,
+            end_date,
+            granularity: granularity_from_server(granularity),
+            plan_ids,
+            currency: filter.currency,
+        })
+    }
+
+    fn period_to_server(period: &MrrReportPeriod) -> server::ReportPeriod {
+        server::ReportPeriod {
+            start_date: Some(chrono_to_proto(period.start_date)),
+            end_date: Some(chrono_to_proto(period.end_date)),
+        }
+    }
+
+    pub fn mrr_to_server(period: &MrrReportPeriod) -> server::MrrDataPoint {
+        server::MrrDataPoint {
+            period: Some(period_to_server(period)),
+            starting_mrr_cents: period.starting_mrr,
+            new_business_cents: period.new_business,
+            expansion_cents: period.expansion,
+            contraction_cents: period.contraction,
+            churn_cents: period.churn,
+            reactivation_cents: period.reactivation,
+            net_new_mrr_cents: period.net_new_mrr(),
+            ending_mrr_cents: period.ending_mrr,
+        }
+    }
+
+    pub fn churn_rate_to_server(period: &MrrReportPeriod) -> server::ChurnRateDataPoint {
+        server::ChurnRateDataPoint {
+            period: Some(period_to_server(period)),
+            starting_subscriptions: period.starting_subscriptions,
+            churned_subscriptions: period.churned_subscriptions,
+            subscription_churn_rate_percent: period.subscription_churn_rate(),
+            starting_mrr_cents: period.starting_mrr,
+            lost_mrr_cents: period.churn + period.contraction,
+            revenue_churn_rate_percent: period.revenue_churn_rate(),
+        }
+    }
+
+    pub fn net_revenue_retention_to_server(
+        period: &MrrReportPeriod,
+    ) -> server::NetRevenueRetentionDataPoint {
+        server::NetRevenueRetentionDataPoint {
+            period: Some(period_to_server(period)),
+            starting_mrr_cents: period.starting_mrr,
+            retained_mrr_cents: period.starting_mrr
+                + period.expansion
+                + period.reactivation
+                + period.contraction
+                + period.churn,
+            net_revenue_retention_percent: period.net_revenue_retention(),
+        }
+    }
+
+    pub fn arpu_to_server(period: &MrrReportPeriod) -> server::ArpuDataPoint {
+        server::ArpuDataPoint {
+            period: Some(period_to_server(period)),
+            mrr_cents: period.ending_mrr,
+            subscriptions: period.ending_subscriptions,
+            arpu_cents: period.arpu(),
+        }
+    }
+
+    /// Defaults to today, compared to one month before
+    pub fn segments_filter_server_to_domain(
+        filter: Option<server::SubscriptionSegmentsFilter>,
+    ) -> (NaiveDate, NaiveDate) {
+        let filter = filter.unwrap_or_default();
+
+        let as_of = filter
+            .as_of
+            .and_then(chrono_from_proto)
+            .unwrap_or(chrono::Utc::now().naive_utc().date());
+        let compare_to = filter
+            .compare_to
+            .and_then(chrono_from_proto)
+            .unwrap_or(as_of.checked_sub_months(Months::new(1)).unwrap_or(as_of));
+
+        (as_of, compare_to)
+    }
+
+    pub fn segment_to_server(segment: &SubscriptionSegment) -> server::SubscriptionSegment {
+        server::SubscriptionSegment {
+            plan_id: segment.plan_id.to_string(),
+            plan_name: segment.plan_name.clone(),
+            billing_period: billing_period::to_proto(segment.billing_period.clone()).into(),
+            billing_period_months: billing_period::months_to_proto(&segment.billing_period),
+            currency: segment.currency.clone(),
+            country: segment.country.clone(),
+            subscriptions: segment.subscriptions,
+            previous_subscriptions: segment.previous_subscriptions,
+            subscriptions_delta: segment.subscriptions_delta(),
+            mrr_cents: segment.mrr_cents,
+            previous_mrr_cents: segment.previous_mrr_cents,
+            mrr_delta_cents: segment.mrr_delta_cents(),
+        }
+    }
+}
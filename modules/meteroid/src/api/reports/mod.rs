@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::reports::v1::reports_service_server::ReportsServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct ReportsServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> ReportsServiceServer<ReportsServiceComponents> {
+    let inner = ReportsServiceComponents { store };
+    ReportsServiceServer::new(inner)
+}
@@ -0,0 +1,171 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::reports::v1::{
+    reports_service_server::ReportsService, ArpuRequest, ArpuResponse, ChurnRateRequest,
+    ChurnRateResponse, ExportSubscriptionSegmentsRequest, ExportSubscriptionSegmentsResponse,
+    GetSubscriptionSegmentsRequest, GetSubscriptionSegmentsResponse, MrrOverTimeRequest,
+    MrrOverTimeResponse, NetRevenueRetentionRequest, NetRevenueRetentionResponse,
+};
+use meteroid_store::repositories::reports::ReportsInterface;
+
+use crate::api::reports::error::ReportApiError;
+use crate::api::shared::mapping::date::chrono_to_proto;
+
+use super::{mapping, ReportsServiceComponents};
+
+#[tonic::async_trait]
+impl ReportsService for ReportsServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn mrr_over_time(
+        &self,
+        request: Request<MrrOverTimeRequest>,
+    ) -> Result<Response<MrrOverTimeResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let report = self
+            .store
+            .mrr_report(mapping::reports::filter_server_to_domain(
+                req.filter, tenant_id,
+            )?)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(MrrOverTimeResponse {
+            currency: report.currency,
+            data: report
+                .periods
+                .iter()
+                .map(mapping::reports::mrr_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn churn_rate(
+        &self,
+        request: Request<ChurnRateRequest>,
+    ) -> Result<Response<ChurnRateResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let report = self
+            .store
+            .mrr_report(mapping::reports::filter_server_to_domain(
+                req.filter, tenant_id,
+            )?)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(ChurnRateResponse {
+            currency: report.currency,
+            data: report
+                .periods
+                .iter()
+                .map(mapping::reports::churn_rate_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn net_revenue_retention(
+        &self,
+        request: Request<NetRevenueRetentionRequest>,
+    ) -> Result<Response<NetRevenueRetentionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let report = self
+            .store
+            .mrr_report(mapping::reports::filter_server_to_domain(
+                req.filter, tenant_id,
+            )?)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(NetRevenueRetentionResponse {
+            currency: report.currency,
+            data: report
+                .periods
+                .iter()
+                .map(mapping::reports::net_revenue_retention_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn arpu(&self, request: Request<ArpuRequest>) -> Result<Response<ArpuResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let report = self
+            .store
+            .mrr_report(mapping::reports::filter_server_to_domain(
+                req.filter, tenant_id,
+            )?)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(ArpuResponse {
+            currency: report.currency,
+            data: report
+                .periods
+                .iter()
+                .map(mapping::reports::arpu_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_subscription_segments(
+        &self,
+        request: Request<GetSubscriptionSegmentsRequest>,
+    ) -> Result<Response<GetSubscriptionSegmentsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let (as_of, compare_to) = mapping::reports::segments_filter_server_to_domain(req.filter);
+
+        let report = self
+            .store
+            .subscription_segment_report(tenant_id, as_of, compare_to)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(GetSubscriptionSegmentsResponse {
+            as_of: Some(chrono_to_proto(report.as_of)),
+            compare_to: Some(chrono_to_proto(report.compare_to)),
+            segments: report
+                .segments
+                .iter()
+                .map(mapping::reports::segment_to_server)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn export_subscription_segments(
+        &self,
+        request: Request<ExportSubscriptionSegmentsRequest>,
+    ) -> Result<Response<ExportSubscriptionSegmentsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let (as_of, compare_to) = mapping::reports::segments_filter_server_to_domain(req.filter);
+
+        let report = self
+            .store
+            .subscription_segment_report(tenant_id, as_of, compare_to)
+            .await
+            .map_err(Into::<ReportApiError>::into)?;
+
+        Ok(Response::new(ExportSubscriptionSegmentsResponse {
+            csv: report.to_csv(),
+            file_name: format!(
+                "subscription-segments-{}.csv",
+                report.as_of.format("%Y-%m-%d")
+            ),
+        }))
+    }
+}
@@ -0,0 +1,60 @@
+use axum::async_trait;
+use axum::extract::FromRequestParts;
+use axum::http::request::Parts;
+use axum::http::Method;
+use common_grpc::middleware::server::auth::AuthenticatedState;
+use meteroid_middleware::server::auth::strategies::api_key_strategy::authenticate_api_key;
+use tonic::Code;
+use uuid::Uuid;
+
+use crate::api::axum_routers::AppState;
+use crate::errors::RestApiError;
+
+/// Tenant resolved from the api key of the request
+#[derive(Debug, Clone, Copy)]
+pub struct AuthorizedTenant {
+    pub tenant_id: Uuid,
+    /// id of the api key
+    pub actor: Uuid,
+}
+
+#[async_trait]
+impl FromRequestParts<AppState> for AuthorizedTenant {
+    type Rejection = RestApiError;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let resource = scope_resource(parts.uri.path());
+        let write = parts.method != Method::GET;
+
+        let authenticated = authenticate_api_key(&parts.headers, &state.store, resource, write)
+            .await
+            .map_err(|status| {
+                log::debug!("Rejected api key: {}", status.message());
+                match status.code() {
+                    // a valid key whose scopes don't allow the call
+                    Code::PermissionDenied => RestApiError::Forbidden,
+                    _ => RestApiError::Unauthorized,
+                }
+            })?;
+
+        match authenticated {
+            AuthenticatedState::ApiKey { id, tenant_id, .. } => Ok(AuthorizedTenant {
+                tenant_id,
+                actor: id,
+            }),
+            AuthenticatedState::User { .. } => Err(RestApiError::Unauthorized),
+        }
+    }
+}
+
+/// The scope resource of a rest route, from its path: /api/v1/<resource>/...
+/// The events are ingested by the metering service, and share its scope
+fn scope_resource(path: &str) -> &str {
+    match path.split('/').nth(3).unwrap_or_default() {
+        "events" => "metering",
+        resource => resource,
+    }
+}
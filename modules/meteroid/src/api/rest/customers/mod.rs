@@ -0,0 +1,4 @@
+pub use router::customer_routes;
+
+pub mod model;
+pub mod router;
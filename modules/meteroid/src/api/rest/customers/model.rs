@@ -0,0 +1,144 @@
+use chrono::NaiveDateTime;
+use meteroid_store::domain;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Serialize, Deserialize, ToSchema)]
+pub struct Address {
+    pub line1: Option<String>,
+    pub line2: Option<String>,
+    pub city: Option<String>,
+    pub country: Option<String>,
+    pub state: Option<String>,
+    pub zip_code: Option<String>,
+}
+
+impl From<domain::Address> for Address {
+    fn from(value: domain::Address) -> Self {
+        Address {
+            line1: value.line1,
+            line2: value.line2,
+            city: value.city,
+            country: value.country,
+            state: value.state,
+            zip_code: value.zip_code,
+        }
+    }
+}
+
+impl From<Address> for domain::Address {
+    fn from(value: Address) -> Self {
+        domain::Address {
+            line1: value.line1,
+            line2: value.line2,
+            city: value.city,
+            country: value.country,
+            state: value.state,
+            zip_code: value.zip_code,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingEmailStatus {
+    Unverified,
+    Verified,
+    /// invoice emails are paused until the billing email is changed
+    Bounced,
+    Complained,
+}
+
+impl From<domain::enums::BillingEmailStatusEnum> for BillingEmailStatus {
+    fn from(value: domain::enums::BillingEmailStatusEnum) -> Self {
+        match value {
+            domain::enums::BillingEmailStatusEnum::Unverified => BillingEmailStatus::Unverified,
+            domain::enums::BillingEmailStatusEnum::Verified => BillingEmailStatus::Verified,
+            domain::enums::BillingEmailStatusEnum::Bounced => BillingEmailStatus::Bounced,
+            domain::enums::BillingEmailStatusEnum::Complained => BillingEmailStatus::Complained,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Customer {
+    pub id: Uuid,
+    pub name: String,
+    /// external id of the customer in your system
+    pub alias: Option<String>,
+    pub email: Option<String>,
+    pub invoicing_email: Option<String>,
+    pub phone: Option<String>,
+    pub currency: String,
+    pub balance_value_cents: i32,
+    pub invoicing_entity_id: Uuid,
+    pub billing_address: Option<Address>,
+    pub net_terms_override: Option<u32>,
+    pub created_at: NaiveDateTime,
+    pub archived_at: Option<NaiveDateTime>,
+    /// deliverability of the invoicing email, or of the email when not set
+    pub billing_email_status: BillingEmailStatus,
+    pub billing_email_status_reason: Option<String>,
+}
+
+impl From<domain::Customer> for Customer {
+    fn from(value: domain::Customer) -> Self {
+        Customer {
+            id: value.id,
+            name: value.name,
+            alias: value.alias,
+            email: value.email,
+            invoicing_email: value.invoicing_email,
+            phone: value.phone,
+            currency: value.currency,
+            balance_value_cents: value.balance_value_cents,
+            invoicing_entity_id: value.invoicing_entity_id,
+            billing_address: value.billing_address.map(Into::into),
+            net_terms_override: value.net_terms_override,
+            created_at: value.created_at,
+            archived_at: value.archived_at,
+            billing_email_status: value.billing_email_status.into(),
+            billing_email_status_reason: value.billing_email_status_reason,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct CustomerList {
+    pub data: Vec<Customer>,
+    pub total_pages: u32,
+    pub total_results: u64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct CustomerListQuery {
+    /// matches the name or the alias
+    pub search: Option<String>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CustomerCreateRequest {
+    pub name: String,
+    pub alias: Option<String>,
+    pub email: Option<String>,
+    pub invoicing_email: Option<String>,
+    pub phone: Option<String>,
+    pub currency: String,
+    /// defaults to the default invoicing entity of the tenant
+    pub invoicing_entity_id: Option<Uuid>,
+    pub billing_address: Option<Address>,
+    pub net_terms_override: Option<u32>,
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct CustomerUpdateRequest {
+    pub name: Option<String>,
+    pub alias: Option<String>,
+    pub email: Option<String>,
+    pub invoicing_email: Option<String>,
+    pub phone: Option<String>,
+    pub currency: Option<String>,
+    pub invoicing_entity_id: Option<Uuid>,
+    pub billing_address: Option<Address>,
+}
@@ -0,0 +1,206 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::get;
+use axum::{Json, Router};
+use meteroid_store::domain;
+use meteroid_store::domain::{CustomerNew, CustomerPatch, OrderByRequest};
+use meteroid_store::repositories::CustomersInterface;
+use uuid::Uuid;
+
+use super::model::{
+    Customer, CustomerCreateRequest, CustomerList, CustomerListQuery, CustomerUpdateRequest,
+};
+use crate::api::axum_routers::AppState;
+use crate::api::rest::auth::AuthorizedTenant;
+use crate::api::rest::model::PaginationQuery;
+use crate::errors::RestApiError;
+
+pub fn customer_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/v1/customers",
+            get(list_customers).post(create_customer),
+        )
+        .route(
+            "/api/v1/customers/:id_or_alias",
+            get(get_customer).patch(update_customer),
+        )
+}
+
+#[utoipa::path(
+    get,
+    tag = "customers",
+    path = "/api/v1/customers",
+    params(PaginationQuery, CustomerListQuery),
+    responses(
+        (status = 200, description = "List of customers", body = CustomerList),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn list_customers(
+    tenant: AuthorizedTenant,
+    Query(pagination): Query<PaginationQuery>,
+    Query(query): Query<CustomerListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<CustomerList>, RestApiError> {
+    let res = app_state
+        .store
+        .list_customers(
+            tenant.tenant_id,
+            pagination.into(),
+            OrderByRequest::DateDesc,
+            query.search,
+        )
+        .await?;
+
+    Ok(Json(CustomerList {
+        data: res.items.into_iter().map(Into::into).collect(),
+        total_pages: res.total_pages,
+        total_results: res.total_results,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    tag = "customers",
+    path = "/api/v1/customers/{id_or_alias}",
+    params(("id_or_alias" = String, Path, description = "Customer id or alias")),
+    responses(
+        (status = 200, description = "Customer", body = Customer),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Customer not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_customer(
+    tenant: AuthorizedTenant,
+    Path(id_or_alias): Path<String>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Customer>, RestApiError> {
+    let id = resolve_customer_id(&app_state, tenant.tenant_id, id_or_alias).await?;
+
+    let customer = app_state
+        .store
+        .find_customer_by_id(id, tenant.tenant_id)
+        .await?;
+
+    Ok(Json(customer.into()))
+}
+
+#[utoipa::path(
+    post,
+    tag = "customers",
+    path = "/api/v1/customers",
+    request_body = CustomerCreateRequest,
+    responses(
+        (status = 201, description = "Created customer", body = Customer),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 409, description = "A customer with this alias already exists"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn create_customer(
+    tenant: AuthorizedTenant,
+    State(app_state): State<AppState>,
+    Json(req): Json<CustomerCreateRequest>,
+) -> Result<(StatusCode, Json<Customer>), RestApiError> {
+    let customer_new = CustomerNew {
+        name: req.name,
+        created_by: tenant.actor,
+        invoicing_entity_id: req.invoicing_entity_id,
+        billing_config: domain::BillingConfig::Manual,
+        alias: req.alias,
+        email: req.email,
+        invoicing_email: req.invoicing_email,
+        phone: req.phone,
+        balance_value_cents: 0,
+        currency: req.currency,
+        billing_address: req.billing_address.map(Into::into),
+        shipping_address: None,
+        force_created_date: None,
+        net_terms_override: req.net_terms_override,
+        early_payment_discount: None,
+        due_date_policy: None,
+    };
+
+    let customer = app_state
+        .store
+        .insert_customer(customer_new, tenant.tenant_id)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(customer.into())))
+}
+
+#[utoipa::path(
+    patch,
+    tag = "customers",
+    path = "/api/v1/customers/{id_or_alias}",
+    params(("id_or_alias" = String, Path, description = "Customer id or alias")),
+    request_body = CustomerUpdateRequest,
+    responses(
+        (status = 200, description = "Updated customer", body = Customer),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Customer not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn update_customer(
+    tenant: AuthorizedTenant,
+    Path(id_or_alias): Path<String>,
+    State(app_state): State<AppState>,
+    Json(req): Json<CustomerUpdateRequest>,
+) -> Result<Json<Customer>, RestApiError> {
+    let id = resolve_customer_id(&app_state, tenant.tenant_id, id_or_alias).await?;
+
+    let billing_address = req
+        .billing_address
+        .map(|a| serde_json::to_value(domain::Address::from(a)))
+        .transpose()
+        .map_err(|_| RestApiError::InvalidInput)?;
+
+    let customer = app_state
+        .store
+        .patch_customer(
+            tenant.actor,
+            tenant.tenant_id,
+            CustomerPatch {
+                id,
+                name: req.name,
+                alias: req.alias,
+                email: req.email,
+                invoicing_email: req.invoicing_email,
+                phone: req.phone,
+                balance_value_cents: None,
+                currency: req.currency,
+                billing_address,
+                shipping_address: None,
+                invoicing_entity_id: req.invoicing_entity_id,
+            },
+        )
+        .await?
+        .ok_or(RestApiError::NotFound)?;
+
+    Ok(Json(customer.into()))
+}
+
+async fn resolve_customer_id(
+    app_state: &AppState,
+    tenant_id: Uuid,
+    id_or_alias: String,
+) -> Result<Uuid, RestApiError> {
+    if let Ok(id) = Uuid::parse_str(&id_or_alias) {
+        return Ok(id);
+    }
+
+    app_state
+        .store
+        .find_customer_ids_by_aliases(tenant_id, vec![id_or_alias])
+        .await?
+        .into_iter()
+        .next()
+        .map(|c| c.id)
+        .ok_or(RestApiError::NotFound)
+}
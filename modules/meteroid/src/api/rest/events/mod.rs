@@ -0,0 +1,4 @@
+pub use router::event_routes;
+
+pub mod model;
+pub mod router;
@@ -0,0 +1,84 @@
+use std::collections::HashMap;
+
+use metering_grpc::meteroid::metering::v1 as metering;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use crate::errors::RestApiError;
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct Event {
+    /// idempotency key of the event
+    pub event_id: String,
+    pub event_name: String,
+    /// meteroid id of the customer. Either this or `external_customer_id` is required
+    pub customer_id: Option<String>,
+    /// alias of the customer
+    pub external_customer_id: Option<String>,
+    /// rfc3339 timestamp
+    pub timestamp: String,
+    #[serde(default)]
+    pub properties: HashMap<String, String>,
+}
+
+impl TryFrom<Event> for metering::Event {
+    type Error = RestApiError;
+
+    fn try_from(value: Event) -> Result<Self, Self::Error> {
+        let customer_id = match (value.customer_id, value.external_customer_id) {
+            (Some(id), None) => metering::event::CustomerId::MeteroidCustomerId(id),
+            (None, Some(alias)) => metering::event::CustomerId::ExternalCustomerId(alias),
+            _ => {
+                return Err(RestApiError::InvalidArgument(format!(
+                    "event {} must have exactly one of customer_id or external_customer_id",
+                    value.event_id
+                )))
+            }
+        };
+
+        Ok(metering::Event {
+            event_id: value.event_id,
+            event_name: value.event_name,
+            customer_id: Some(customer_id),
+            timestamp: value.timestamp,
+            properties: value.properties,
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct IngestEventsRequest {
+    /// at most 500 events per request, unless configured otherwise
+    pub events: Vec<Event>,
+    /// allows events older than the ingestion grace period
+    #[serde(default)]
+    pub allow_backfilling: bool,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SchemaViolation {
+    /// the billable metric declaring the event schema
+    pub metric_id: String,
+    pub property: String,
+    /// MISSING_PROPERTY, INVALID_TYPE or VALUE_NOT_ALLOWED
+    pub kind: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IngestFailure {
+    pub event_id: String,
+    /// ex: INVALID_EVENT, CUSTOMER_NOT_FOUND, TOO_MANY_PROPERTIES, PROPERTY_KEY_TOO_LONG, PROPERTY_VALUE_TOO_LONG, SCHEMA_VIOLATION
+    pub code: String,
+    pub reason: String,
+    /// the mismatches with the event schemas, for a SCHEMA_VIOLATION
+    pub violations: Vec<SchemaViolation>,
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct IngestEventsResponse {
+    /// events that were rejected. The other events of the request are ingested
+    pub failures: Vec<IngestFailure>,
+    /// ids of the events dropped as already ingested, ex: by a previous attempt of the request
+    pub duplicates: Vec<String>,
+}
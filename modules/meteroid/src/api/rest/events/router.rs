@@ -0,0 +1,100 @@
+use axum::extract::{DefaultBodyLimit, State};
+use axum::http::HeaderMap;
+use axum::routing::post;
+use axum::{Json, Router};
+use common_grpc::middleware::common::auth::API_KEY_HEADER;
+use metering_grpc::meteroid::metering::v1 as metering;
+use tonic::metadata::MetadataValue;
+use tonic::{Code, Request};
+
+use super::model::{IngestEventsRequest, IngestEventsResponse, IngestFailure, SchemaViolation};
+use crate::adapters::alerting::{Alert, Alerter};
+use crate::api::axum_routers::AppState;
+use crate::api::rest::auth::AuthorizedTenant;
+use crate::errors::RestApiError;
+
+// a batch of 500 events
+const INGEST_BODY_LIMIT: usize = 1024 * 1024;
+
+pub fn event_routes() -> Router<AppState> {
+    Router::new().route(
+        "/api/v1/events",
+        post(ingest_events).layer(DefaultBodyLimit::max(INGEST_BODY_LIMIT)),
+    )
+}
+
+#[utoipa::path(
+    post,
+    tag = "events",
+    path = "/api/v1/events",
+    request_body = IngestEventsRequest,
+    responses(
+        (status = 200, description = "Ingested events, and the rejected ones", body = IngestEventsResponse),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn ingest_events(
+    tenant: AuthorizedTenant,
+    headers: HeaderMap,
+    State(app_state): State<AppState>,
+    Json(req): Json<IngestEventsRequest>,
+) -> Result<Json<IngestEventsResponse>, RestApiError> {
+    let events = req
+        .events
+        .into_iter()
+        .map(metering::Event::try_from)
+        .collect::<Result<Vec<_>, _>>()?;
+
+    // the metering service resolves the tenant from the same api key
+    let api_key = headers
+        .get(API_KEY_HEADER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| MetadataValue::try_from(v).ok())
+        .ok_or(RestApiError::Unauthorized)?;
+
+    let mut request = Request::new(metering::IngestRequest {
+        events,
+        allow_backfilling: req.allow_backfilling,
+    });
+    request.metadata_mut().insert(API_KEY_HEADER, api_key);
+
+    let res = match app_state.events_client.clone().ingest(request).await {
+        Ok(res) => res.into_inner(),
+        Err(status) if status.code() == Code::InvalidArgument => {
+            return Err(RestApiError::InvalidArgument(status.message().to_string()));
+        }
+        Err(status) => {
+            log::error!("Failed to ingest events: {:?}", status);
+            // the events are lost unless the client retries, so this is escalated
+            Alerter::get()
+                .send(&Alert::ingestion_stalled(tenant.tenant_id, &status))
+                .await;
+            return Err(RestApiError::MeteringError);
+        }
+    };
+
+    Ok(Json(IngestEventsResponse {
+        failures: res
+            .failures
+            .into_iter()
+            .map(|f| IngestFailure {
+                code: f.code().as_str_name().to_string(),
+                event_id: f.idempotency_key,
+                reason: f.reason,
+                violations: f
+                    .violations
+                    .into_iter()
+                    .map(|v| SchemaViolation {
+                        kind: v.kind().as_str_name().to_string(),
+                        metric_id: v.metric_id,
+                        property: v.property,
+                        reason: v.reason,
+                    })
+                    .collect(),
+            })
+            .collect(),
+        duplicates: res.duplicates,
+    }))
+}
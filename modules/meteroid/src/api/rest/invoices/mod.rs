@@ -0,0 +1,4 @@
+pub use router::invoice_routes;
+
+pub mod model;
+pub mod router;
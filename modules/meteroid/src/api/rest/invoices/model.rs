@@ -0,0 +1,133 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use meteroid_store::domain;
+use meteroid_store::domain::enums::InvoiceStatusEnum;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum InvoiceStatus {
+    Draft,
+    Pending,
+    Finalized,
+    Void,
+}
+
+impl From<InvoiceStatusEnum> for InvoiceStatus {
+    fn from(value: InvoiceStatusEnum) -> Self {
+        match value {
+            InvoiceStatusEnum::Draft => InvoiceStatus::Draft,
+            InvoiceStatusEnum::Pending => InvoiceStatus::Pending,
+            InvoiceStatusEnum::Finalized => InvoiceStatus::Finalized,
+            InvoiceStatusEnum::Void => InvoiceStatus::Void,
+        }
+    }
+}
+
+impl From<InvoiceStatus> for InvoiceStatusEnum {
+    fn from(value: InvoiceStatus) -> Self {
+        match value {
+            InvoiceStatus::Draft => InvoiceStatusEnum::Draft,
+            InvoiceStatus::Pending => InvoiceStatusEnum::Pending,
+            InvoiceStatus::Finalized => InvoiceStatusEnum::Finalized,
+            InvoiceStatus::Void => InvoiceStatusEnum::Void,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct InvoiceLine {
+    pub name: String,
+    pub description: Option<String>,
+    pub quantity: Option<Decimal>,
+    pub unit_price: Option<Decimal>,
+    pub start_date: NaiveDate,
+    pub end_date: NaiveDate,
+    pub subtotal: i64,
+    pub total: i64,
+    pub is_prorated: bool,
+    /// decimal places to display the quantity with
+    pub quantity_display_precision: Option<u32>,
+}
+
+impl From<domain::LineItem> for InvoiceLine {
+    fn from(value: domain::LineItem) -> Self {
+        InvoiceLine {
+            name: value.name,
+            description: value.description,
+            quantity: value.quantity,
+            unit_price: value.unit_price,
+            start_date: value.start_date,
+            end_date: value.end_date,
+            subtotal: value.subtotal,
+            total: value.total,
+            is_prorated: value.is_prorated,
+            quantity_display_precision: value.quantity_display_precision,
+        }
+    }
+}
+
+/// Amounts are in the smallest unit of the currency
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Invoice {
+    pub id: Uuid,
+    pub invoice_number: String,
+    pub status: InvoiceStatus,
+    pub customer_id: Uuid,
+    pub subscription_id: Option<Uuid>,
+    pub currency: String,
+    pub invoice_date: NaiveDate,
+    pub due_at: Option<NaiveDateTime>,
+    pub finalized_at: Option<NaiveDateTime>,
+    pub subtotal: i64,
+    pub tax_amount: i64,
+    pub total: i64,
+    pub applied_credits: i64,
+    pub amount_due: i64,
+    pub memo: Option<String>,
+    pub reference: Option<String>,
+    pub line_items: Vec<InvoiceLine>,
+    pub created_at: NaiveDateTime,
+}
+
+impl From<domain::Invoice> for Invoice {
+    fn from(value: domain::Invoice) -> Self {
+        Invoice {
+            id: value.id,
+            invoice_number: value.invoice_number,
+            status: value.status.into(),
+            customer_id: value.customer_id,
+            subscription_id: value.subscription_id,
+            currency: value.currency,
+            invoice_date: value.invoice_date,
+            due_at: value.due_at,
+            finalized_at: value.finalized_at,
+            subtotal: value.subtotal,
+            tax_amount: value.tax_amount,
+            total: value.total,
+            applied_credits: value.applied_credits,
+            amount_due: value.amount_due,
+            memo: value.memo,
+            reference: value.reference,
+            line_items: value.line_items.into_iter().map(Into::into).collect(),
+            created_at: value.created_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct InvoiceList {
+    pub data: Vec<Invoice>,
+    pub total_pages: u32,
+    pub total_results: u64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct InvoiceListQuery {
+    pub customer_id: Option<Uuid>,
+    pub status: Option<InvoiceStatus>,
+    /// matches the customer name
+    pub search: Option<String>,
+}
@@ -0,0 +1,79 @@
+use axum::extract::{Path, Query, State};
+use axum::routing::get;
+use axum::{Json, Router};
+use meteroid_store::domain::OrderByRequest;
+use meteroid_store::repositories::InvoiceInterface;
+use uuid::Uuid;
+
+use super::model::{Invoice, InvoiceList, InvoiceListQuery};
+use crate::api::axum_routers::AppState;
+use crate::api::rest::auth::AuthorizedTenant;
+use crate::api::rest::model::PaginationQuery;
+use crate::errors::RestApiError;
+
+pub fn invoice_routes() -> Router<AppState> {
+    Router::new()
+        .route("/api/v1/invoices", get(list_invoices))
+        .route("/api/v1/invoices/:id", get(get_invoice))
+}
+
+#[utoipa::path(
+    get,
+    tag = "invoices",
+    path = "/api/v1/invoices",
+    params(PaginationQuery, InvoiceListQuery),
+    responses(
+        (status = 200, description = "List of invoices", body = InvoiceList),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn list_invoices(
+    tenant: AuthorizedTenant,
+    Query(pagination): Query<PaginationQuery>,
+    Query(query): Query<InvoiceListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<InvoiceList>, RestApiError> {
+    let res = app_state
+        .store
+        .list_invoices(
+            tenant.tenant_id,
+            query.customer_id,
+            query.status.map(Into::into),
+            query.search,
+            OrderByRequest::DateDesc,
+            pagination.into(),
+        )
+        .await?;
+
+    Ok(Json(InvoiceList {
+        data: res.items.into_iter().map(|i| i.invoice.into()).collect(),
+        total_pages: res.total_pages,
+        total_results: res.total_results,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    tag = "invoices",
+    path = "/api/v1/invoices/{id}",
+    params(("id" = Uuid, Path, description = "Invoice id")),
+    responses(
+        (status = 200, description = "Invoice", body = Invoice),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Invoice not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_invoice(
+    tenant: AuthorizedTenant,
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Invoice>, RestApiError> {
+    let invoice = app_state
+        .store
+        .find_invoice_by_id(tenant.tenant_id, id)
+        .await?;
+
+    Ok(Json(invoice.invoice.into()))
+}
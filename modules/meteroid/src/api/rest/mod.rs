@@ -0,0 +1,86 @@
+use axum::Router;
+use common_grpc::middleware::common::auth::API_KEY_HEADER;
+use utoipa::openapi::security::{ApiKey, ApiKeyValue, SecurityScheme};
+use utoipa::{Modify, OpenApi};
+use utoipa_swagger_ui::SwaggerUi;
+
+use crate::api::axum_routers::AppState;
+
+mod auth;
+mod customers;
+mod events;
+mod invoices;
+mod model;
+mod subscriptions;
+
+/// Public REST api, authenticated by api key. Mirrors a subset of the grpc api
+pub fn rest_routes() -> Router<AppState> {
+    Router::new()
+        .merge(customers::customer_routes())
+        .merge(subscriptions::subscription_routes())
+        .merge(invoices::invoice_routes())
+        .merge(events::event_routes())
+        .merge(SwaggerUi::new("/api/docs").url("/api/openapi.json", ApiDoc::openapi()))
+}
+
+#[derive(OpenApi)]
+#[openapi(
+    info(title = "Meteroid API"),
+    paths(
+        customers::router::list_customers,
+        customers::router::get_customer,
+        customers::router::create_customer,
+        customers::router::update_customer,
+        subscriptions::router::list_subscriptions,
+        subscriptions::router::get_subscription,
+        subscriptions::router::create_subscription,
+        subscriptions::router::cancel_subscription,
+        subscriptions::router::list_usage_caps,
+        subscriptions::router::request_seat_increase,
+        invoices::router::list_invoices,
+        invoices::router::get_invoice,
+        events::router::ingest_events,
+    ),
+    modifiers(&SecurityAddon),
+    security(("api_key" = [])),
+    tags(
+        (name = "customers"),
+        (name = "subscriptions"),
+        (name = "invoices"),
+        (name = "events", description = "Usage ingestion"),
+    )
+)]
+pub struct ApiDoc;
+
+struct SecurityAddon;
+
+impl Modify for SecurityAddon {
+    fn modify(&self, openapi: &mut utoipa::openapi::OpenApi) {
+        if let Some(components) = openapi.components.as_mut() {
+            components.add_security_scheme(
+                "api_key",
+                SecurityScheme::ApiKey(ApiKey::Header(ApiKeyValue::new(API_KEY_HEADER))),
+            )
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_openapi_spec() {
+        let spec = ApiDoc::openapi();
+
+        assert!(spec
+            .paths
+            .paths
+            .contains_key("/api/v1/customers/{id_or_alias}"));
+        assert!(spec.paths.paths.contains_key("/api/v1/events"));
+        assert!(spec
+            .components
+            .map(|c| c.security_schemes.contains_key("api_key"))
+            .unwrap_or(false));
+    }
+}
@@ -0,0 +1,28 @@
+use meteroid_store::domain;
+use serde::Deserialize;
+use utoipa::IntoParams;
+
+const DEFAULT_PER_PAGE: u32 = 20;
+const MAX_PER_PAGE: u32 = 100;
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct PaginationQuery {
+    /// zero-based page index
+    pub page: Option<u32>,
+    /// defaults to 20, at most 100
+    pub per_page: Option<u32>,
+}
+
+impl From<PaginationQuery> for domain::PaginationRequest {
+    fn from(value: PaginationQuery) -> Self {
+        domain::PaginationRequest {
+            page: value.page.unwrap_or(0),
+            per_page: Some(
+                value
+                    .per_page
+                    .unwrap_or(DEFAULT_PER_PAGE)
+                    .clamp(1, MAX_PER_PAGE),
+            ),
+        }
+    }
+}
@@ -0,0 +1,4 @@
+pub use router::subscription_routes;
+
+pub mod model;
+pub mod router;
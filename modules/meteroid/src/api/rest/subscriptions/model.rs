@@ -0,0 +1,321 @@
+use chrono::{NaiveDate, NaiveDateTime};
+use meteroid_grpc::meteroid::api::subscriptions::v1 as proto;
+use meteroid_store::domain;
+use meteroid_store::domain::usage_caps::UsageCapStatus;
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use utoipa::{IntoParams, ToSchema};
+use uuid::Uuid;
+
+use crate::errors::RestApiError;
+use crate::services::subscription::ext::DbSubscriptionExt;
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionStatus {
+    Pending,
+    Trial,
+    Active,
+    Canceled,
+    Ended,
+}
+
+impl From<proto::SubscriptionStatus> for SubscriptionStatus {
+    fn from(value: proto::SubscriptionStatus) -> Self {
+        match value {
+            proto::SubscriptionStatus::Pending => SubscriptionStatus::Pending,
+            proto::SubscriptionStatus::Trial => SubscriptionStatus::Trial,
+            proto::SubscriptionStatus::Active => SubscriptionStatus::Active,
+            proto::SubscriptionStatus::Canceled => SubscriptionStatus::Canceled,
+            proto::SubscriptionStatus::Ended => SubscriptionStatus::Ended,
+        }
+    }
+}
+
+/// Anniversary billing renews on the billing day, calendar billing on the 1st of the month with a prorated first period
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum BillingMode {
+    #[default]
+    Anniversary,
+    Calendar,
+}
+
+impl From<domain::enums::SubscriptionBillingModeEnum> for BillingMode {
+    fn from(value: domain::enums::SubscriptionBillingModeEnum) -> Self {
+        match value {
+            domain::enums::SubscriptionBillingModeEnum::Anniversary => BillingMode::Anniversary,
+            domain::enums::SubscriptionBillingModeEnum::Calendar => BillingMode::Calendar,
+        }
+    }
+}
+
+impl From<BillingMode> for domain::enums::SubscriptionBillingModeEnum {
+    fn from(value: BillingMode) -> Self {
+        match value {
+            BillingMode::Anniversary => domain::enums::SubscriptionBillingModeEnum::Anniversary,
+            BillingMode::Calendar => domain::enums::SubscriptionBillingModeEnum::Calendar,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, ToSchema)]
+pub struct Subscription {
+    pub id: Uuid,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    pub plan_id: Uuid,
+    pub plan_name: String,
+    pub plan_version_id: Uuid,
+    pub version: u32,
+    pub status: Option<SubscriptionStatus>,
+    pub currency: String,
+    pub billing_day: i16,
+    pub billing_mode: BillingMode,
+    pub trial_start_date: Option<NaiveDate>,
+    pub billing_start_date: NaiveDate,
+    pub billing_end_date: Option<NaiveDate>,
+    pub net_terms: u32,
+    pub invoice_memo: Option<String>,
+    pub invoice_threshold: Option<Decimal>,
+    pub minimum_commitment: Option<Decimal>,
+    pub mrr_cents: u64,
+    pub created_at: NaiveDateTime,
+    pub activated_at: Option<NaiveDateTime>,
+    pub canceled_at: Option<NaiveDateTime>,
+    pub cancellation_reason: Option<String>,
+}
+
+impl From<domain::Subscription> for Subscription {
+    fn from(value: domain::Subscription) -> Self {
+        Subscription {
+            status: value.status_proto().ok().map(Into::into),
+            id: value.id,
+            customer_id: value.customer_id,
+            customer_name: value.customer_name,
+            customer_alias: value.customer_alias,
+            plan_id: value.plan_id,
+            plan_name: value.plan_name,
+            plan_version_id: value.plan_version_id,
+            version: value.version,
+            currency: value.currency,
+            billing_day: value.billing_day,
+            billing_mode: value.billing_mode.into(),
+            trial_start_date: value.trial_start_date,
+            billing_start_date: value.billing_start_date,
+            billing_end_date: value.billing_end_date,
+            net_terms: value.net_terms,
+            invoice_memo: value.invoice_memo,
+            invoice_threshold: value.invoice_threshold,
+            minimum_commitment: value.minimum_commitment,
+            mrr_cents: value.mrr_cents,
+            created_at: value.created_at,
+            activated_at: value.activated_at,
+            canceled_at: value.canceled_at,
+            cancellation_reason: value.cancellation_reason,
+        }
+    }
+}
+
+impl From<domain::SubscriptionDetails> for Subscription {
+    fn from(value: domain::SubscriptionDetails) -> Self {
+        Subscription {
+            status: value.status_proto().ok().map(Into::into),
+            id: value.id,
+            customer_id: value.customer_id,
+            customer_name: value.customer_name,
+            customer_alias: value.customer_external_id,
+            plan_id: value.plan_id,
+            plan_name: value.plan_name,
+            plan_version_id: value.plan_version_id,
+            version: value.version,
+            currency: value.currency,
+            billing_day: value.billing_day,
+            billing_mode: value.billing_mode.into(),
+            trial_start_date: value.trial_start_date,
+            billing_start_date: value.billing_start_date,
+            billing_end_date: value.billing_end_date,
+            net_terms: value.net_terms,
+            invoice_memo: value.invoice_memo,
+            invoice_threshold: value.invoice_threshold,
+            minimum_commitment: value.minimum_commitment,
+            mrr_cents: value.mrr_cents,
+            created_at: value.created_at,
+            activated_at: value.activated_at,
+            canceled_at: value.canceled_at,
+            cancellation_reason: value.cancellation_reason,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SubscriptionList {
+    pub data: Vec<Subscription>,
+    pub total_pages: u32,
+    pub total_results: u64,
+}
+
+#[derive(Debug, Deserialize, IntoParams)]
+pub struct SubscriptionListQuery {
+    pub customer_id: Option<Uuid>,
+    pub plan_id: Option<Uuid>,
+}
+
+/// Subscription to a plan version, with the default parameters of its price components
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscriptionCreateRequest {
+    pub customer_id: Uuid,
+    pub plan_version_id: Uuid,
+    pub currency: String,
+    pub billing_day: i16,
+    /// ignored in calendar mode
+    #[serde(default)]
+    pub billing_mode: BillingMode,
+    pub billing_start_date: NaiveDate,
+    pub billing_end_date: Option<NaiveDate>,
+    pub trial_start_date: Option<NaiveDate>,
+    pub net_terms: u32,
+    pub invoice_memo: Option<String>,
+    pub invoice_threshold: Option<Decimal>,
+    /// minimum spend per billing period, the difference is billed as a true-up on the invoice closing the period
+    pub minimum_commitment: Option<Decimal>,
+}
+
+impl SubscriptionCreateRequest {
+    pub fn into_domain(self, actor: Uuid) -> Result<domain::CreateSubscription, RestApiError> {
+        if self.minimum_commitment.is_some_and(|c| c < Decimal::ZERO) {
+            return Err(RestApiError::InvalidArgument(
+                "minimum_commitment cannot be negative".to_string(),
+            ));
+        }
+
+        Ok(domain::CreateSubscription {
+            subscription: domain::SubscriptionNew {
+                customer_id: self.customer_id,
+                billing_day: self.billing_day,
+                billing_mode: self.billing_mode.into(),
+                currency: self.currency,
+                trial_start_date: self.trial_start_date,
+                billing_start_date: self.billing_start_date,
+                billing_end_date: self.billing_end_date,
+                plan_version_id: self.plan_version_id,
+                created_by: actor,
+                net_terms: self.net_terms as i32,
+                invoice_memo: self.invoice_memo,
+                invoice_threshold: self.invoice_threshold,
+                activated_at: None,
+                minimum_commitment: self.minimum_commitment,
+            },
+            price_components: None,
+            add_ons: None,
+            coupons: None,
+            phases: vec![],
+        })
+    }
+}
+
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SubscriptionCancelRequest {
+    pub reason: Option<String>,
+}
+
+/// A capped usage or capacity fee in its current period
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageCap {
+    /// the subscription component or add-on
+    pub fee_id: Uuid,
+    pub name: String,
+    pub metric_id: Option<Uuid>,
+    pub period_start: NaiveDate,
+    pub period_end: NaiveDate,
+    pub cap_cents: i64,
+    /// charges of the period before the cap applies
+    pub charges_cents: i64,
+    /// the charges above the cap are not billed
+    pub limit_reached: bool,
+    pub reached_at: Option<NaiveDateTime>,
+}
+
+impl From<UsageCapStatus> for UsageCap {
+    fn from(value: UsageCapStatus) -> Self {
+        UsageCap {
+            limit_reached: value.limit_reached(),
+            fee_id: value.charges.fee_id,
+            name: value.charges.name,
+            metric_id: value.charges.metric_id,
+            period_start: value.charges.period.start,
+            period_end: value.charges.period.end,
+            cap_cents: value.charges.cap,
+            charges_cents: value.charges.charges,
+            reached_at: value.reached_at,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, ToSchema)]
+pub struct UsageCapList {
+    pub data: Vec<UsageCap>,
+}
+
+/// Seats requested by an end customer, from the customer portal of the tenant
+#[derive(Debug, Deserialize, ToSchema)]
+pub struct SeatIncreaseCreateRequest {
+    /// the slot component of the subscription
+    pub price_component_id: Uuid,
+    pub seats: u32,
+    /// the end customer user, like an email
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SeatIncreaseStatus {
+    Pending,
+    Approved,
+    Rejected,
+}
+
+impl From<domain::enums::SeatIncreaseRequestStatusEnum> for SeatIncreaseStatus {
+    fn from(value: domain::enums::SeatIncreaseRequestStatusEnum) -> Self {
+        match value {
+            domain::enums::SeatIncreaseRequestStatusEnum::Pending => SeatIncreaseStatus::Pending,
+            domain::enums::SeatIncreaseRequestStatusEnum::Approved => SeatIncreaseStatus::Approved,
+            domain::enums::SeatIncreaseRequestStatusEnum::Rejected => SeatIncreaseStatus::Rejected,
+        }
+    }
+}
+
+/// Approved right away within the auto-approve limit of the tenant, pending its approval otherwise
+#[derive(Debug, Serialize, ToSchema)]
+pub struct SeatIncrease {
+    pub id: Uuid,
+    pub subscription_id: Uuid,
+    pub price_component_id: Uuid,
+    pub seats: u32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+    pub status: SeatIncreaseStatus,
+    pub created_at: NaiveDateTime,
+    pub decided_at: Option<NaiveDateTime>,
+    /// the prorated charge of the added seats
+    pub invoice_id: Option<Uuid>,
+}
+
+impl From<domain::seat_increase_requests::SeatIncreaseRequest> for SeatIncrease {
+    fn from(value: domain::seat_increase_requests::SeatIncreaseRequest) -> Self {
+        SeatIncrease {
+            id: value.id,
+            subscription_id: value.subscription_id,
+            price_component_id: value.price_component_id,
+            seats: value.seats as u32,
+            requested_by: value.requested_by,
+            note: value.note,
+            status: value.status.into(),
+            created_at: value.created_at,
+            decided_at: value.decided_at,
+            invoice_id: value.invoice_id,
+        }
+    }
+}
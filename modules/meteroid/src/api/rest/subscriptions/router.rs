@@ -0,0 +1,229 @@
+use axum::extract::{Path, Query, State};
+use axum::http::StatusCode;
+use axum::routing::{get, post};
+use axum::{Json, Router};
+use meteroid_store::domain;
+use meteroid_store::domain::seat_increase_requests::SeatIncreaseRequestNew;
+use meteroid_store::repositories::seat_increase_requests::SeatIncreaseRequestInterface;
+use meteroid_store::repositories::subscriptions::CancellationEffectiveAt;
+use meteroid_store::repositories::usage_caps::UsageCapInterface;
+use meteroid_store::repositories::SubscriptionInterface;
+use uuid::Uuid;
+
+use super::model::{
+    SeatIncrease, SeatIncreaseCreateRequest, Subscription, SubscriptionCancelRequest,
+    SubscriptionCreateRequest, SubscriptionList, SubscriptionListQuery, UsageCapList,
+};
+use crate::api::axum_routers::AppState;
+use crate::api::rest::auth::AuthorizedTenant;
+use crate::api::rest::model::PaginationQuery;
+use crate::errors::RestApiError;
+
+pub fn subscription_routes() -> Router<AppState> {
+    Router::new()
+        .route(
+            "/api/v1/subscriptions",
+            get(list_subscriptions).post(create_subscription),
+        )
+        .route("/api/v1/subscriptions/:id", get(get_subscription))
+        .route(
+            "/api/v1/subscriptions/:id/cancel",
+            post(cancel_subscription),
+        )
+        .route("/api/v1/subscriptions/:id/usage-caps", get(list_usage_caps))
+        .route(
+            "/api/v1/subscriptions/:id/seat-requests",
+            post(request_seat_increase),
+        )
+}
+
+#[utoipa::path(
+    get,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions",
+    params(PaginationQuery, SubscriptionListQuery),
+    responses(
+        (status = 200, description = "List of subscriptions", body = SubscriptionList),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn list_subscriptions(
+    tenant: AuthorizedTenant,
+    Query(pagination): Query<PaginationQuery>,
+    Query(query): Query<SubscriptionListQuery>,
+    State(app_state): State<AppState>,
+) -> Result<Json<SubscriptionList>, RestApiError> {
+    let res = app_state
+        .store
+        .list_subscriptions(
+            tenant.tenant_id,
+            query.customer_id,
+            query.plan_id,
+            pagination.into(),
+        )
+        .await?;
+
+    Ok(Json(SubscriptionList {
+        data: res.items.into_iter().map(Into::into).collect(),
+        total_pages: res.total_pages,
+        total_results: res.total_results,
+    }))
+}
+
+#[utoipa::path(
+    get,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions/{id}",
+    params(("id" = Uuid, Path, description = "Subscription id")),
+    responses(
+        (status = 200, description = "Subscription", body = Subscription),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Subscription not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn get_subscription(
+    tenant: AuthorizedTenant,
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<Json<Subscription>, RestApiError> {
+    let subscription = app_state
+        .store
+        .get_subscription_details(tenant.tenant_id, id)
+        .await?;
+
+    Ok(Json(subscription.into()))
+}
+
+#[utoipa::path(
+    post,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions",
+    request_body = SubscriptionCreateRequest,
+    responses(
+        (status = 201, description = "Created subscription", body = Subscription),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn create_subscription(
+    tenant: AuthorizedTenant,
+    State(app_state): State<AppState>,
+    Json(req): Json<SubscriptionCreateRequest>,
+) -> Result<(StatusCode, Json<Subscription>), RestApiError> {
+    let created = app_state
+        .store
+        .insert_subscription(req.into_domain(tenant.actor)?, tenant.tenant_id)
+        .await?;
+
+    let subscription = app_state
+        .store
+        .get_subscription_details(tenant.tenant_id, created.id)
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(subscription.into())))
+}
+
+#[utoipa::path(
+    post,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions/{id}/cancel",
+    params(("id" = Uuid, Path, description = "Subscription id")),
+    request_body = SubscriptionCancelRequest,
+    responses(
+        (status = 200, description = "Subscription, canceled at the end of the current billing period", body = Subscription),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Subscription not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn cancel_subscription(
+    tenant: AuthorizedTenant,
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(req): Json<SubscriptionCancelRequest>,
+) -> Result<Json<Subscription>, RestApiError> {
+    let subscription = app_state
+        .store
+        .cancel_subscription(
+            id,
+            req.reason,
+            CancellationEffectiveAt::EndOfBillingPeriod,
+            domain::TenantContext {
+                tenant_id: tenant.tenant_id,
+                actor: tenant.actor,
+            },
+        )
+        .await?;
+
+    Ok(Json(subscription.into()))
+}
+
+#[utoipa::path(
+    get,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions/{id}/usage-caps",
+    params(("id" = Uuid, Path, description = "Subscription id")),
+    responses(
+        (status = 200, description = "Capped usage fees of the subscription, with their limit reached state", body = UsageCapList),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Subscription not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn list_usage_caps(
+    tenant: AuthorizedTenant,
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+) -> Result<Json<UsageCapList>, RestApiError> {
+    let usage_caps = app_state
+        .store
+        .list_usage_caps(tenant.tenant_id, id, chrono::Utc::now().naive_utc())
+        .await?;
+
+    Ok(Json(UsageCapList {
+        data: usage_caps.into_iter().map(Into::into).collect(),
+    }))
+}
+
+#[utoipa::path(
+    post,
+    tag = "subscriptions",
+    path = "/api/v1/subscriptions/{id}/seat-requests",
+    params(("id" = Uuid, Path, description = "Subscription id")),
+    request_body = SeatIncreaseCreateRequest,
+    responses(
+        (status = 201, description = "Seat increase, approved or pending the approval of the tenant", body = SeatIncrease),
+        (status = 400, description = "Invalid request"),
+        (status = 401, description = "Unauthorized"),
+        (status = 404, description = "Subscription or slot component not found"),
+    )
+)]
+#[tracing::instrument(skip_all)]
+pub async fn request_seat_increase(
+    tenant: AuthorizedTenant,
+    Path(id): Path<Uuid>,
+    State(app_state): State<AppState>,
+    Json(req): Json<SeatIncreaseCreateRequest>,
+) -> Result<(StatusCode, Json<SeatIncrease>), RestApiError> {
+    let seats = i32::try_from(req.seats).map_err(|_| RestApiError::InvalidInput)?;
+
+    let request = app_state
+        .store
+        .request_seat_increase(
+            SeatIncreaseRequestNew {
+                tenant_id: tenant.tenant_id,
+                subscription_id: id,
+                price_component_id: req.price_component_id,
+                seats,
+                requested_by: req.requested_by,
+                note: req.note,
+            },
+            tenant.actor,
+        )
+        .await?;
+
+    Ok((StatusCode::CREATED, Json(request.into())))
+}
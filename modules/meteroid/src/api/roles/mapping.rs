@@ -0,0 +1,51 @@
+pub mod role {
+    use common_grpc::middleware::server::auth::TenantRole;
+    use meteroid_grpc::meteroid::api::roles::v1::TenantMemberRole as TenantMemberRoleProto;
+    use meteroid_store::domain::enums::TenantMemberRoleEnum;
+
+    pub fn to_proto(role: TenantMemberRoleEnum) -> TenantMemberRoleProto {
+        match role {
+            TenantMemberRoleEnum::Admin => TenantMemberRoleProto::Admin,
+            TenantMemberRoleEnum::Billing => TenantMemberRoleProto::Billing,
+            TenantMemberRoleEnum::ReadOnly => TenantMemberRoleProto::ReadOnly,
+        }
+    }
+
+    pub fn from_proto(role: TenantMemberRoleProto) -> TenantMemberRoleEnum {
+        match role {
+            TenantMemberRoleProto::Admin => TenantMemberRoleEnum::Admin,
+            TenantMemberRoleProto::Billing => TenantMemberRoleEnum::Billing,
+            TenantMemberRoleProto::ReadOnly => TenantMemberRoleEnum::ReadOnly,
+        }
+    }
+
+    pub fn extension_to_proto(role: TenantRole) -> TenantMemberRoleProto {
+        match role {
+            TenantRole::Admin => TenantMemberRoleProto::Admin,
+            TenantRole::Billing => TenantMemberRoleProto::Billing,
+            TenantRole::ReadOnly => TenantMemberRoleProto::ReadOnly,
+        }
+    }
+
+    pub fn extension_to_domain(role: TenantRole) -> TenantMemberRoleEnum {
+        match role {
+            TenantRole::Admin => TenantMemberRoleEnum::Admin,
+            TenantRole::Billing => TenantMemberRoleEnum::Billing,
+            TenantRole::ReadOnly => TenantMemberRoleEnum::ReadOnly,
+        }
+    }
+}
+
+pub mod assignment {
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::roles::v1::TenantMemberRoleAssignment;
+    use meteroid_store::domain::tenant_member_roles::TenantMemberRole;
+
+    pub fn to_proto(assignment: TenantMemberRole) -> TenantMemberRoleAssignment {
+        TenantMemberRoleAssignment {
+            user_id: assignment.user_id.to_string(),
+            role: super::role::to_proto(assignment.role).into(),
+            created_at: Some(chrono_to_timestamp(assignment.created_at)),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::roles::v1::roles_service_server::RolesServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+pub(crate) mod mapping;
+mod service;
+
+pub struct RolesServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> RolesServiceServer<RolesServiceComponents> {
+    let inner = RolesServiceComponents { store };
+    RolesServiceServer::new(inner)
+}
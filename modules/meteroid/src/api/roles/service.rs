@@ -0,0 +1,103 @@
+use tonic::{Request, Response, Status};
+use uuid::Uuid;
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::roles::v1::roles_service_server::RolesService;
+use meteroid_grpc::meteroid::api::roles::v1::{
+    AssignTenantMemberRoleRequest, AssignTenantMemberRoleResponse, GetMyTenantRoleRequest,
+    GetMyTenantRoleResponse, ListTenantMemberRolesRequest, ListTenantMemberRolesResponse,
+    RemoveTenantMemberRoleRequest, RemoveTenantMemberRoleResponse,
+};
+use meteroid_middleware::server::auth::strategies::jwt_strategy::invalidate_tenant_role_cache;
+use meteroid_store::domain::audit_logs::AuditEntity;
+use meteroid_store::repositories::tenant_member_roles::TenantMemberRolesInterface;
+
+use crate::api::roles::error::RoleApiError;
+use crate::api::roles::mapping::{assignment, role};
+use crate::api::roles::RolesServiceComponents;
+use crate::api::shared::conversions::ProtoConv;
+use crate::api::utils::audited;
+
+#[tonic::async_trait]
+impl RolesService for RolesServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn list_tenant_member_roles(
+        &self,
+        request: Request<ListTenantMemberRolesRequest>,
+    ) -> Result<Response<ListTenantMemberRolesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let res = self
+            .store
+            .list_tenant_member_roles(tenant_id)
+            .await
+            .map_err(Into::<RoleApiError>::into)?;
+
+        Ok(Response::new(ListTenantMemberRolesResponse {
+            assignments: res.into_iter().map(assignment::to_proto).collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn assign_tenant_member_role(
+        &self,
+        request: Request<AssignTenantMemberRoleRequest>,
+    ) -> Result<Response<AssignTenantMemberRoleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let organization_id = request.organization()?;
+        let req = request.into_inner();
+
+        let user_id = Uuid::from_proto(req.user_id)?;
+        let new_role = role::from_proto(req.role());
+
+        let res = self
+            .store
+            .assign_tenant_member_role(organization_id, tenant_id, user_id, new_role)
+            .await
+            .map_err(Into::<RoleApiError>::into)?;
+
+        invalidate_tenant_role_cache(&user_id, &tenant_id).await;
+
+        Ok(audited(
+            AssignTenantMemberRoleResponse {
+                assignment: Some(assignment::to_proto(res)),
+            },
+            AuditEntity::new(user_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn remove_tenant_member_role(
+        &self,
+        request: Request<RemoveTenantMemberRoleRequest>,
+    ) -> Result<Response<RemoveTenantMemberRoleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let user_id = Uuid::from_proto(req.user_id)?;
+
+        self.store
+            .remove_tenant_member_role(tenant_id, user_id)
+            .await
+            .map_err(Into::<RoleApiError>::into)?;
+
+        invalidate_tenant_role_cache(&user_id, &tenant_id).await;
+
+        Ok(audited(
+            RemoveTenantMemberRoleResponse {},
+            AuditEntity::new(user_id),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_my_tenant_role(
+        &self,
+        request: Request<GetMyTenantRoleRequest>,
+    ) -> Result<Response<GetMyTenantRoleResponse>, Status> {
+        let tenant_role = request.tenant_role()?;
+
+        Ok(Response::new(GetMyTenantRoleResponse {
+            role: role::extension_to_proto(tenant_role).into(),
+        }))
+    }
+}
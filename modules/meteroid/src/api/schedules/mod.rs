@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::schedules::v1::schedules_service_server::SchedulesServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
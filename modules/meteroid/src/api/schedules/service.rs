@@ -50,7 +50,7 @@ impl SchedulesService for ScheduleServiceComponents {
 
         let schedule_new = domain::ScheduleNew {
             plan_version_id: parse_uuid!(&req.plan_version_id)?,
-            billing_period: billing_period::from_proto(req.billing_period()),
+            billing_period: billing_period::from_proto(req.billing_period(), None)?,
             ramps: PlanRampsWrapper(
                 req.ramps
                     .ok_or_else(|| ScheduleApiError::MissingArgument("ramps".to_string()))?,
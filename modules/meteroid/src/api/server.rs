@@ -36,13 +36,17 @@ pub async fn start_api_server(
 
     Server::builder()
         .accept_http1(true)
-        .layer(cors())
+        .layer(cors(&config.cors))
         .layer(GrpcWebLayer::new())
         .layer(common_middleware::metric::create())
         .layer(
             meteroid_middleware::server::auth::create(config.jwt_secret.clone(), store.clone())
                 .filter(common_filters::only_api),
         )
+        .layer(
+            meteroid_middleware::server::audit::create(store.clone())
+                .filter(common_filters::only_api),
+        )
         .layer(
             common_middleware::auth::create_admin(&config.internal_auth)
                 .filter(common_filters::only_internal),
@@ -54,13 +58,19 @@ pub async fn start_api_server(
         .layer(common_middleware::error_logger::create())
         .add_service(health_service)
         .add_service(reflection_service)
+        .add_service(api::accountingexports::service(
+            store.clone(),
+            object_store.clone(),
+        ))
         .add_service(api::addons::service(store.clone()))
         .add_service(api::billablemetrics::service(store.clone()))
+        .add_service(api::componentrules::service(store.clone()))
         .add_service(api::organizations::service(store.clone()))
         .add_service(api::invoicingentities::service(
             store.clone(),
             object_store.clone(),
         ))
+        .add_service(api::notificationbundles::service(store.clone()))
         .add_service(api::coupons::service(store.clone()))
         .add_service(api::customers::service(
             store.clone(),
@@ -68,6 +78,11 @@ pub async fn start_api_server(
         ))
         .add_service(api::tenants::service(store.clone()))
         .add_service(api::apitokens::service(store.clone()))
+        .add_service(api::auditlogs::service(store.clone()))
+        .add_service(api::providers::service(store.clone()))
+        .add_service(api::partners::service(store.clone()))
+        .add_service(api::paymentreminders::service(store.clone()))
+        .add_service(api::collectionstrategies::service(store.clone()))
         .add_service(api::pricecomponents::service(store.clone()))
         .add_service(api::plans::service(store.clone()))
         .add_service(api::schedules::service(store.clone()))
@@ -77,10 +92,17 @@ pub async fn start_api_server(
         .add_service(api::invoices::service(
             store.clone(),
             config.jwt_secret.clone(),
+            (&config.invoice_watchdog).into(),
+            object_store.clone(),
         ))
+        .add_service(api::ratecards::service(store.clone()))
+        .add_service(api::reports::service(store.clone()))
+        .add_service(api::roles::service(store.clone()))
         .add_service(api::stats::service(store.clone()))
         .add_service(api::users::service(store.clone()))
         .add_service(api::subscriptions::service(store.clone()))
+        .add_service(api::usage::service(store.clone()))
+        .add_service(api::usagealerts::service(store.clone()))
         .add_service(api::webhooksout::service(store.clone()))
         .add_service(api::internal::service(store.clone()))
         .serve(config.grpc_listen_addr)
@@ -1,8 +1,12 @@
 use meteroid_grpc::meteroid::api::stats::v1 as proto;
 use meteroid_grpc::meteroid::api::stats::v1::{BreakdownStat, MrrBreakdown, MrrBreakdownScope};
 use meteroid_store::domain::stats::{
-    CountAndValue, MRRBreakdown, MRRBreakdownScope, MrrMovementType, Trend, TrendScope,
+    CountAndValue, FxRestatementMode, MRRBreakdown, MRRBreakdownScope, MrrMovementType, Trend,
+    TrendScope,
 };
+use tonic::Status;
+
+use crate::api::shared;
 
 pub fn trend_to_server(trend: &Trend) -> proto::Trend {
     proto::Trend {
@@ -38,6 +42,30 @@ pub fn mrr_breakdown_scope_from_server(scope: proto::MrrBreakdownScope) -> MRRBr
     }
 }
 
+pub fn fx_restatement_from_server(
+    fx: Option<proto::FxRestatement>,
+) -> Result<FxRestatementMode, Status> {
+    let Some(fx) = fx else {
+        return Ok(FxRestatementMode::HistoricalRates);
+    };
+
+    let mode = proto::fx_restatement::Mode::try_from(fx.mode)
+        .map_err(|e| Status::invalid_argument(format!("Failed to parse fx mode: {}", e)))?;
+
+    match mode {
+        proto::fx_restatement::Mode::HistoricalRates => Ok(FxRestatementMode::HistoricalRates),
+        proto::fx_restatement::Mode::ConstantCurrency => {
+            let rate_date = match fx.rate_date {
+                Some(date) => shared::mapping::date::chrono_from_proto(date).ok_or_else(|| {
+                    Status::invalid_argument("Invalid rate date for constant currency mode")
+                })?,
+                None => chrono::Utc::now().naive_utc().date(),
+            };
+            Ok(FxRestatementMode::ConstantCurrency { rate_date })
+        }
+    }
+}
+
 pub fn breakdown_stat_to_server(stat: &CountAndValue) -> BreakdownStat {
     BreakdownStat {
         count: stat.count as i64,
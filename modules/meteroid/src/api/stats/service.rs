@@ -18,6 +18,7 @@ use meteroid_grpc::meteroid::api::stats::v1::{
 use meteroid_store::repositories::stats::StatsInterface;
 
 use crate::api::shared;
+use crate::api::shared::mapping::datetime::chrono_to_timestamp;
 use crate::api::stats::mapping::trend_to_server;
 
 use common_grpc::middleware::server::auth::RequestExt;
@@ -35,44 +36,35 @@ impl StatsService for StatsServiceComponents {
         request: Request<GeneralStatsRequest>,
     ) -> Result<Response<GeneralStatsResponse>, Status> {
         let tenant_id = request.tenant()?;
+        let fx_mode = mapping::fx_restatement_from_server(request.into_inner().fx_restatement)?;
 
-        let (
-            net_revenue_res,
-            active_subscriptions,
-            pending_invoices_res,
-            signups_res,
-            trial_conversion_res,
-            total_mrr_res,
-        ) = tokio::try_join!(
-            self.store.net_revenue(tenant_id),
-            self.store.active_subscriptions(tenant_id),
-            self.store.pending_invoices(tenant_id),
-            self.store.signups(tenant_id),
-            self.store.trial_conversion_rate(tenant_id),
-            self.store.total_mrr(tenant_id)
-        )
-        .map_err(|e| Status::internal(format!("Failed to fetch stats: {}", e)))?;
+        let stats = self
+            .store
+            .general_stats(tenant_id, fx_mode)
+            .await
+            .map_err(|e| Status::internal(format!("Failed to fetch stats: {}", e)))?;
 
         Ok(Response::new(GeneralStatsResponse {
             total_net_revenue: Some(general_stats_response::TotalNetRevenue {
-                trend: Some(trend_to_server(&net_revenue_res)),
+                trend: Some(trend_to_server(&stats.net_revenue)),
             }),
             total_active_subscriptions: Some(general_stats_response::TotalActiveSubscriptions {
-                count: active_subscriptions,
+                count: stats.active_subscriptions,
             }),
             pending_invoices: Some(general_stats_response::PendingInvoices {
-                count: pending_invoices_res.count as i64,
-                value_cents: pending_invoices_res.value,
+                count: stats.pending_invoices.count as i64,
+                value_cents: stats.pending_invoices.value,
             }),
             signups: Some(general_stats_response::Signups {
-                count: signups_res.current,
+                count: stats.signups.current,
             }),
             total_mrr: Some(general_stats_response::TotalMrr {
-                value_cents: total_mrr_res,
+                value_cents: stats.total_mrr,
             }),
             trial_conversion: Some(general_stats_response::TrialConversion {
-                rate_percent: trial_conversion_res,
+                rate_percent: stats.trial_conversion_rate,
             }),
+            as_of: Some(chrono_to_timestamp(stats.as_of)),
         }))
     }
 
@@ -106,6 +98,8 @@ impl StatsService for StatsServiceComponents {
             Some(parsed)
         };
 
+        let fx_mode = mapping::fx_restatement_from_server(req.fx_restatement)?;
+
         let mrr_chart = self
             .store
             .total_mrr_chart(meteroid_store::domain::stats::MrrChartRequest {
@@ -113,6 +107,7 @@ impl StatsService for StatsServiceComponents {
                 start_date,
                 end_date,
                 plans_id,
+                fx_mode,
             })
             .await
             .map_err(|e| Status::internal(format!("Failed to fetch mrr chart: {}", e)))?;
@@ -138,7 +133,10 @@ impl StatsService for StatsServiceComponents {
             })
             .collect();
 
-        Ok(Response::new(MrrChartResponse { series }))
+        Ok(Response::new(MrrChartResponse {
+            series,
+            as_of: Some(chrono_to_timestamp(mrr_chart.as_of)),
+        }))
     }
 
     #[tracing::instrument(skip_all)]
@@ -158,12 +156,14 @@ impl StatsService for StatsServiceComponents {
                         Status::invalid_argument(format!("Failed to parse scope: {}", e))
                     })?,
                 ),
+                fx_mode: mapping::fx_restatement_from_server(req.fx_restatement)?,
             })
             .await
             .map_err(|e| Status::internal(format!("Failed to fetch mrr breakdown: {}", e)))?;
 
         Ok(Response::new(MrrBreakdownResponse {
-            mmr_breakdown: Some(mapping::mrr_breakdown_to_server(&mrr_breakdown)),
+            mmr_breakdown: Some(mapping::mrr_breakdown_to_server(&mrr_breakdown.breakdown)),
+            as_of: Some(chrono_to_timestamp(mrr_breakdown.as_of)),
         }))
     }
 
@@ -16,6 +16,14 @@ pub enum SubscriptionApiError {
     #[code(InvalidArgument)]
     MissingArgument(String),
 
+    #[error("{0}")]
+    #[code(FailedPrecondition)]
+    ComponentRulesViolated(String),
+
+    #[error("{0}")]
+    #[code(FailedPrecondition)]
+    CreditLimitExceeded(String),
+
     #[error("Calculation error: {0}")]
     #[code(Internal)]
     CalculationError(String, #[source] meteroid_store::compute::ComputeError),
@@ -35,7 +43,18 @@ pub enum SubscriptionApiError {
 
 impl From<Report<StoreError>> for SubscriptionApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        Self::StoreError("Error in subscription service".to_string(), err)
+        match value.current_context() {
+            StoreError::InvalidArgument(msg) => Self::InvalidArgument(msg.clone()),
+            StoreError::ComponentRulesViolated(_) => {
+                Self::ComponentRulesViolated(value.current_context().to_string())
+            }
+            StoreError::CreditLimitExceeded { .. } => {
+                Self::CreditLimitExceeded(value.current_context().to_string())
+            }
+            _ => Self::StoreError(
+                "Error in subscription service".to_string(),
+                Box::new(value.into_error()),
+            ),
+        }
     }
 }
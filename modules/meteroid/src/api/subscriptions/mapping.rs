@@ -2,6 +2,13 @@ pub mod subscriptions {
     use chrono::NaiveDate;
 
     use meteroid_store::domain;
+    use meteroid_store::domain::entitlements::{CustomerEntitlements, Entitlement};
+    use meteroid_store::domain::subscription_phases::{
+        line_promotion_discount, CreateSubscriptionPhase, SubscriptionPhase,
+    };
+    use meteroid_store::domain::usage_caps::UsageCapStatus;
+
+    use crate::api::domain_mapping::discount::ServerStandardDiscountWrapper;
 
     use crate::services::subscription::ext::DbSubscriptionExt;
     use tonic::Status;
@@ -39,6 +46,8 @@ pub mod subscriptions {
             activated_at: s.activated_at.as_proto(),
             mrr_cents: s.mrr_cents,
             status,
+            billing_mode: billing_mode_to_proto(s.billing_mode) as i32,
+            minimum_commitment: s.minimum_commitment.as_proto(),
         })
     }
 
@@ -46,9 +55,18 @@ pub mod subscriptions {
         param: proto2::CreateSubscription,
         actor: &Uuid,
     ) -> Result<domain::CreateSubscription, Status> {
+        let minimum_commitment = rust_decimal::Decimal::from_proto_opt(param.minimum_commitment)?;
+
+        if minimum_commitment.is_some_and(|c| c < rust_decimal::Decimal::ZERO) {
+            return Err(Status::invalid_argument(
+                "The minimum commitment cannot be negative",
+            ));
+        }
+
         let subscription_new = meteroid_store::domain::SubscriptionNew {
             customer_id: Uuid::from_proto(param.customer_id)?,
             billing_day: param.billing_day as i16,
+            billing_mode: billing_mode_from_proto(param.billing_mode()),
             currency: param.currency,
             trial_start_date: NaiveDate::from_proto_opt(param.trial_start_date)?,
             billing_start_date: NaiveDate::from_proto(param.billing_start_date)?,
@@ -59,6 +77,7 @@ pub mod subscriptions {
             invoice_memo: param.invoice_memo,
             invoice_threshold: rust_decimal::Decimal::from_proto_opt(param.invoice_threshold)?,
             activated_at: None, //NaiveDateTime::from_proto_opt(param.activated_at)?,
+            minimum_commitment,
         };
 
         let res = domain::CreateSubscription {
@@ -76,6 +95,11 @@ pub mod subscriptions {
                 .as_ref()
                 .map(super::coupons::create_subscription_coupons_from_grpc)
                 .transpose()?,
+            phases: param
+                .phases
+                .into_iter()
+                .map(create_phase_proto_to_domain)
+                .collect::<Result<Vec<_>, _>>()?,
         };
 
         Ok(res)
@@ -101,6 +125,8 @@ pub mod subscriptions {
             invoice_threshold: sub.invoice_threshold.as_proto(),
             activated_at: sub.activated_at.as_proto(),
             mrr_cents: sub.mrr_cents as u64,
+            billing_mode: billing_mode_to_proto(sub.billing_mode) as i32,
+            minimum_commitment: sub.minimum_commitment.as_proto(),
         })
     }
 
@@ -134,6 +160,8 @@ pub mod subscriptions {
                 activated_at: sub.activated_at.as_proto(),
                 mrr_cents: sub.mrr_cents,
                 status,
+                billing_mode: billing_mode_to_proto(sub.billing_mode) as i32,
+                minimum_commitment: sub.minimum_commitment.as_proto(),
             }),
             schedules: vec![], // TODO
             price_components: sub
@@ -160,8 +188,287 @@ pub mod subscriptions {
                 .into_iter()
                 .map(super::coupons::applied_coupon_detailed_to_grpc)
                 .collect(),
+            phases: sub.phases.into_iter().map(phase_domain_to_proto).collect(),
         })
     }
+
+    fn create_phase_proto_to_domain(
+        phase: proto2::CreateSubscriptionPhase,
+    ) -> Result<CreateSubscriptionPhase, Status> {
+        let discount = phase
+            .discount
+            .ok_or_else(|| Status::invalid_argument("Missing phase discount"))?;
+
+        Ok(CreateSubscriptionPhase {
+            name: phase.name,
+            start_date: NaiveDate::from_proto(phase.start_date)?,
+            end_date: NaiveDate::from_proto(phase.end_date)?,
+            price_component_id: Uuid::from_proto_opt(phase.price_component_id)?,
+            discount: ServerStandardDiscountWrapper(discount)
+                .try_into()
+                .map_err(|_| Status::invalid_argument("Invalid phase discount"))?,
+        })
+    }
+
+    fn phase_domain_to_proto(phase: SubscriptionPhase) -> proto2::SubscriptionPhase {
+        proto2::SubscriptionPhase {
+            id: phase.id.as_proto(),
+            name: phase.name,
+            start_date: phase.start_date.as_proto(),
+            end_date: phase.end_date.as_proto(),
+            price_component_id: phase.price_component_id.as_proto(),
+            discount: Some(ServerStandardDiscountWrapper::from(phase.discount).0),
+        }
+    }
+
+    pub(crate) fn billing_schedule_entry_to_proto(
+        entry: domain::BillingScheduleEntry,
+    ) -> proto2::get_billing_schedule_response::Entry {
+        proto2::get_billing_schedule_response::Entry {
+            invoice_date: entry.invoice_date.as_proto(),
+            lines: entry
+                .lines
+                .into_iter()
+                .map(|line| proto2::get_billing_schedule_response::Line {
+                    name: line.name,
+                    price_component_id: line.price_component_id.as_proto(),
+                    product_id: line.product_id.as_proto(),
+                    quantity: line.quantity.as_proto(),
+                    unit_price: line.unit_price.as_proto(),
+                    total: line.total,
+                    start_date: line.start_date.as_proto(),
+                    end_date: line.end_date.as_proto(),
+                    is_prorated: line.is_prorated,
+                    is_usage_based: line.metric_id.is_some(),
+                    promotion_discount: line_promotion_discount(&line),
+                })
+                .collect(),
+            subtotal: entry.subtotal,
+            ramp_discount: entry.ramp_discount,
+            total: entry.total,
+            is_trial_end: entry.is_trial_end,
+        }
+    }
+
+    pub(crate) fn usage_cap_status_to_proto(
+        status: UsageCapStatus,
+    ) -> proto2::list_usage_caps_response::UsageCap {
+        proto2::list_usage_caps_response::UsageCap {
+            limit_reached: status.limit_reached(),
+            fee_id: status.charges.fee_id.as_proto(),
+            name: status.charges.name,
+            metric_id: status.charges.metric_id.as_proto(),
+            period_start: status.charges.period.start.as_proto(),
+            period_end: status.charges.period.end.as_proto(),
+            cap: status.charges.cap,
+            charges: status.charges.charges,
+            reached_at: status.reached_at.as_proto(),
+        }
+    }
+
+    pub(crate) fn entitlement_to_proto(
+        entitlement: Entitlement,
+    ) -> proto2::get_entitlements_response::Entitlement {
+        proto2::get_entitlements_response::Entitlement {
+            remaining: entitlement.remaining().as_proto(),
+            fee_id: entitlement.fee_id.as_proto(),
+            name: entitlement.name,
+            metric_id: entitlement.metric_id.as_proto(),
+            period_start: entitlement.period.start.as_proto(),
+            period_end: entitlement.period.end.as_proto(),
+            included: entitlement.included,
+            consumed: entitlement.consumed.as_proto(),
+        }
+    }
+
+    pub(crate) fn customer_entitlements_to_proto(
+        entitlements: CustomerEntitlements,
+    ) -> proto2::GetCustomerEntitlementsResponse {
+        proto2::GetCustomerEntitlementsResponse {
+            customer_id: entitlements.customer_id.as_proto(),
+            features: entitlements
+                .features
+                .into_iter()
+                .map(|f| proto2::get_customer_entitlements_response::Feature {
+                    key: f.key,
+                    enabled: f.enabled,
+                    limit: f.limit,
+                })
+                .collect(),
+        }
+    }
+
+    pub(crate) fn subscription_change_from_proto(
+        change: Option<proto2::schedule_subscription_change_request::Change>,
+    ) -> Result<domain::subscription_pending_changes::SubscriptionChange, Status> {
+        use domain::subscription_pending_changes::SubscriptionChange;
+        use proto2::schedule_subscription_change_request::Change;
+
+        match change {
+            Some(Change::PlanSwitch(switch)) => Ok(SubscriptionChange::PlanSwitch {
+                plan_version_id: Uuid::from_proto(switch.plan_version_id)?,
+                components: switch
+                    .components
+                    .map(super::price_components::create_subscription_components_from_grpc)
+                    .transpose()?,
+            }),
+            Some(Change::Cancellation(cancellation)) => Ok(SubscriptionChange::Cancellation {
+                reason: cancellation.reason,
+            }),
+            Some(Change::ComponentChange(change)) => Ok(SubscriptionChange::ComponentChange {
+                components: super::price_components::create_subscription_components_from_grpc(
+                    change
+                        .components
+                        .ok_or_else(|| Status::invalid_argument("Missing components"))?,
+                )?,
+            }),
+            None => Err(Status::invalid_argument("Missing change")),
+        }
+    }
+
+    pub(crate) fn seat_increase_request_status_to_domain(
+        status: proto2::seat_increase_request::Status,
+    ) -> domain::enums::SeatIncreaseRequestStatusEnum {
+        use domain::enums::SeatIncreaseRequestStatusEnum;
+        use proto2::seat_increase_request::Status;
+
+        match status {
+            Status::Pending => SeatIncreaseRequestStatusEnum::Pending,
+            Status::Approved => SeatIncreaseRequestStatusEnum::Approved,
+            Status::Rejected => SeatIncreaseRequestStatusEnum::Rejected,
+        }
+    }
+
+    pub(crate) fn seat_increase_request_domain_to_proto(
+        request: domain::seat_increase_requests::SeatIncreaseRequest,
+    ) -> proto2::SeatIncreaseRequest {
+        use domain::enums::SeatIncreaseRequestStatusEnum;
+        use proto2::seat_increase_request::Status;
+
+        let status = match request.status {
+            SeatIncreaseRequestStatusEnum::Pending => Status::Pending,
+            SeatIncreaseRequestStatusEnum::Approved => Status::Approved,
+            SeatIncreaseRequestStatusEnum::Rejected => Status::Rejected,
+        };
+
+        proto2::SeatIncreaseRequest {
+            id: request.id.as_proto(),
+            subscription_id: request.subscription_id.as_proto(),
+            price_component_id: request.price_component_id.as_proto(),
+            seats: request.seats as u32,
+            requested_by: request.requested_by,
+            note: request.note,
+            status: status as i32,
+            created_at: request.created_at.as_proto(),
+            decided_at: request.decided_at.as_proto(),
+            decided_by: request.decided_by.as_proto(),
+            decision_note: request.decision_note,
+            invoice_id: request.invoice_id.as_proto(),
+        }
+    }
+
+    pub(crate) fn pending_change_domain_to_proto(
+        pending: domain::subscription_pending_changes::SubscriptionPendingChange,
+    ) -> proto2::SubscriptionPendingChange {
+        use domain::subscription_pending_changes::{
+            SubscriptionChange, SubscriptionPendingChangeStatus,
+        };
+        use proto2::subscription_pending_change::{ChangeType, Status as ChangeStatus};
+
+        let status = match pending.status() {
+            SubscriptionPendingChangeStatus::Scheduled => ChangeStatus::Scheduled,
+            SubscriptionPendingChangeStatus::Applied => ChangeStatus::Applied,
+            SubscriptionPendingChangeStatus::Canceled => ChangeStatus::Canceled,
+        };
+
+        let (change_type, plan_version_id, cancellation_reason) = match pending.change {
+            SubscriptionChange::PlanSwitch {
+                plan_version_id, ..
+            } => (
+                ChangeType::PlanSwitch,
+                Some(plan_version_id.as_proto()),
+                None,
+            ),
+            SubscriptionChange::Cancellation { reason } => (ChangeType::Cancellation, None, reason),
+            SubscriptionChange::ComponentChange { .. } => (ChangeType::ComponentChange, None, None),
+        };
+
+        proto2::SubscriptionPendingChange {
+            id: pending.id.as_proto(),
+            subscription_id: pending.subscription_id.as_proto(),
+            change_type: change_type as i32,
+            plan_version_id,
+            cancellation_reason,
+            effective_at: pending.effective_at.as_proto(),
+            status: status as i32,
+            created_at: pending.created_at.as_proto(),
+            applied_at: pending.applied_at.as_proto(),
+            canceled_at: pending.canceled_at.as_proto(),
+        }
+    }
+
+    fn billing_mode_to_proto(
+        mode: domain::enums::SubscriptionBillingModeEnum,
+    ) -> proto2::BillingMode {
+        match mode {
+            domain::enums::SubscriptionBillingModeEnum::Anniversary => {
+                proto2::BillingMode::Anniversary
+            }
+            domain::enums::SubscriptionBillingModeEnum::Calendar => proto2::BillingMode::Calendar,
+        }
+    }
+
+    fn billing_mode_from_proto(
+        mode: proto2::BillingMode,
+    ) -> domain::enums::SubscriptionBillingModeEnum {
+        match mode {
+            proto2::BillingMode::Anniversary => {
+                domain::enums::SubscriptionBillingModeEnum::Anniversary
+            }
+            proto2::BillingMode::Calendar => domain::enums::SubscriptionBillingModeEnum::Calendar,
+        }
+    }
+
+    pub(crate) fn component_history_domain_to_proto(
+        history: domain::subscription_component_history::SubscriptionComponentHistory,
+    ) -> proto2::SubscriptionComponentChange {
+        use domain::subscription_component_history::ComponentParameterChange;
+        use proto2::subscription_component_change::{Change, ChangeType, FeeChange, SlotsChange};
+
+        let fee_change = |previous, new| {
+            Change::Fee(FeeChange {
+                previous_fee: Some(super::price_components::subscription_fee_to_grpc(previous)),
+                new_fee: Some(super::price_components::subscription_fee_to_grpc(new)),
+            })
+        };
+
+        let (change_type, change) = match &history.change {
+            ComponentParameterChange::Slots { previous, new } => (
+                ChangeType::Slots,
+                Change::Slots(SlotsChange {
+                    previous_slots: *previous,
+                    new_slots: *new,
+                }),
+            ),
+            ComponentParameterChange::CommittedCapacity { previous, new } => {
+                (ChangeType::CommittedCapacity, fee_change(previous, new))
+            }
+            ComponentParameterChange::Fee { previous, new } => {
+                (ChangeType::Fee, fee_change(previous, new))
+            }
+        };
+
+        proto2::SubscriptionComponentChange {
+            id: history.id.as_proto(),
+            price_component_id: history.price_component_id.as_proto(),
+            component_name: history.component_name,
+            change_type: change_type as i32,
+            change: Some(change),
+            effective_at: history.effective_at.as_proto(),
+            created_at: history.created_at.as_proto(),
+            created_by: history.created_by.as_proto(),
+        }
+    }
 }
 
 mod price_components {
@@ -191,7 +498,8 @@ mod price_components {
                     .map(api_shared::BillingPeriod::try_from)
                     .transpose()
                     .map_err(|_| Status::invalid_argument("Invalid billing period".to_string()))?
-                    .map(map_billing_period_from_grpc);
+                    .map(|period| map_billing_period_from_grpc(period, c.billing_period_months))
+                    .transpose()?;
 
                 Ok::<_, Status>(domain::ComponentParameterization {
                     component_id,
@@ -261,15 +569,27 @@ mod price_components {
             domain::enums::SubscriptionFeeBillingPeriod::OneTime => {
                 api::SubscriptionFeeBillingPeriod::OneTime
             }
+            domain::enums::SubscriptionFeeBillingPeriod::Weekly => {
+                api::SubscriptionFeeBillingPeriod::Weekly
+            }
             domain::enums::SubscriptionFeeBillingPeriod::Monthly => {
                 api::SubscriptionFeeBillingPeriod::Monthly
             }
+            domain::enums::SubscriptionFeeBillingPeriod::Bimonthly => {
+                api::SubscriptionFeeBillingPeriod::Bimonthly
+            }
             domain::enums::SubscriptionFeeBillingPeriod::Quarterly => {
                 api::SubscriptionFeeBillingPeriod::Quarterly
             }
+            domain::enums::SubscriptionFeeBillingPeriod::Semiannual => {
+                api::SubscriptionFeeBillingPeriod::Semiannual
+            }
             domain::enums::SubscriptionFeeBillingPeriod::Annual => {
                 api::SubscriptionFeeBillingPeriod::Yearly
             }
+            domain::enums::SubscriptionFeeBillingPeriod::Custom { .. } => {
+                api::SubscriptionFeeBillingPeriod::Custom
+            }
         }
     }
 
@@ -277,7 +597,10 @@ mod price_components {
         component: api::SubscriptionComponentNewInternal,
     ) -> Result<domain::SubscriptionComponentNewInternal> {
         Ok(domain::SubscriptionComponentNewInternal {
-            period: subscription_fee_billing_period_from_grpc(component.period())?,
+            period: subscription_fee_billing_period_from_grpc(
+                component.period(),
+                component.period_months,
+            )?,
             price_component_id: component
                 .price_component_id
                 .map(|id| Uuid::from_proto_ref(&id))
@@ -302,6 +625,7 @@ mod price_components {
             subscription_id: component.subscription_id.to_string(),
             name: component.name.clone(),
             period: subscription_fee_billing_period_to_grpc(component.period.clone()).into(),
+            period_months: component.period.custom_months().map(|months| months as u32),
             fee: Some(subscription_fee_to_grpc(&component.fee)),
             is_override: false, // TODO: Update this based on your logic
         }
@@ -344,6 +668,7 @@ mod price_components {
                 included,
                 overage_rate,
                 metric_id,
+                cap,
             } => api::SubscriptionFee {
                 fee: Some(api::subscription_fee::Fee::Capacity(
                     api::subscription_fee::CapacitySubscriptionFee {
@@ -351,6 +676,7 @@ mod price_components {
                         included: *included,
                         overage_rate: overage_rate.to_string(),
                         metric_id: metric_id.to_string(),
+                        cap: cap.map(|cap| cap.to_string()),
                     },
                 )),
             },
@@ -360,6 +686,7 @@ mod price_components {
                 min_slots,
                 max_slots,
                 initial_slots,
+                ..
             } => api::SubscriptionFee {
                 fee: Some(api::subscription_fee::Fee::Slot(
                     api::subscription_fee::SlotSubscriptionFee {
@@ -373,9 +700,33 @@ mod price_components {
                     },
                 )),
             },
-            domain::SubscriptionFee::Usage { metric_id, model } => api::SubscriptionFee {
+            domain::SubscriptionFee::Usage {
+                metric_id,
+                model,
+                cap,
+                included,
+            } => api::SubscriptionFee {
                 fee: Some(api::subscription_fee::Fee::Usage(
-                    usage_pricing_model_to_grpc(metric_id, model),
+                    api_components::UsageFee {
+                        cap: cap.map(|cap| cap.to_string()),
+                        included: *included,
+                        ..usage_pricing_model_to_grpc(metric_id, model)
+                    },
+                )),
+            },
+            domain::SubscriptionFee::Percent {
+                metric_id,
+                percentage,
+                minimum,
+                maximum,
+            } => api::SubscriptionFee {
+                fee: Some(api::subscription_fee::Fee::Percent(
+                    api_components::PercentFee {
+                        metric_id: metric_id.as_proto(),
+                        percentage: percentage.to_string(),
+                        minimum: minimum.map(|minimum| minimum.to_string()),
+                        maximum: maximum.map(|maximum| maximum.to_string()),
+                    },
                 )),
             },
         }
@@ -388,10 +739,14 @@ mod price_components {
         match model {
             domain::UsagePricingModel::PerUnit { rate } => api_components::UsageFee {
                 metric_id: metric_id.as_proto(),
+                cap: None,
+                included: None,
                 model: Some(api_components::usage_fee::Model::PerUnit(rate.as_proto())),
             },
             domain::UsagePricingModel::Tiered { tiers, block_size } => api_components::UsageFee {
                 metric_id: metric_id.as_proto(),
+                cap: None,
+                included: None,
                 model: Some(api_components::usage_fee::Model::Tiered(
                     api_components::usage_fee::TieredAndVolume {
                         rows: tiers.iter().map(tier_row_to_grpc).collect(),
@@ -401,6 +756,8 @@ mod price_components {
             },
             domain::UsagePricingModel::Volume { tiers, block_size } => api_components::UsageFee {
                 metric_id: metric_id.as_proto(),
+                cap: None,
+                included: None,
                 model: Some(api_components::usage_fee::Model::Volume(
                     api_components::usage_fee::TieredAndVolume {
                         rows: tiers.iter().map(tier_row_to_grpc).collect(),
@@ -410,6 +767,8 @@ mod price_components {
             },
             domain::UsagePricingModel::Package { block_size, rate } => api_components::UsageFee {
                 metric_id: metric_id.as_proto(),
+                cap: None,
+                included: None,
                 model: Some(api_components::usage_fee::Model::Package(
                     api_components::usage_fee::Package {
                         block_size: *block_size,
@@ -419,6 +778,8 @@ mod price_components {
             },
             domain::UsagePricingModel::Matrix { rates } => api_components::UsageFee {
                 metric_id: metric_id.as_proto(),
+                cap: None,
+                included: None,
                 model: Some(api_components::usage_fee::Model::Matrix(
                     api_components::usage_fee::Matrix {
                         rows: rates
@@ -482,11 +843,13 @@ mod price_components {
                 let rate = rust_decimal::Decimal::from_proto_ref(&capacity.rate)?;
                 let overage_rate = rust_decimal::Decimal::from_proto_ref(&capacity.overage_rate)?;
                 let metric_id = Uuid::from_proto_ref(&capacity.metric_id)?;
+                let cap = rust_decimal::Decimal::from_proto_opt(capacity.cap.clone())?;
                 Ok(domain::SubscriptionFee::Capacity {
                     rate,
                     included: capacity.included,
                     overage_rate,
                     metric_id,
+                    cap,
                 })
             }
             Some(api::subscription_fee::Fee::Slot(slot)) => {
@@ -497,12 +860,28 @@ mod price_components {
                     min_slots: slot.min_slots,
                     max_slots: slot.max_slots,
                     initial_slots: slot.initial_slots,
+                    upgrade_policy: Default::default(),
+                    downgrade_policy: Default::default(),
                 })
             }
             Some(api::subscription_fee::Fee::Usage(usage)) => {
                 let metric_id = Uuid::from_proto_ref(&usage.metric_id)?;
                 let model = usage_pricing_model_from_grpc(usage)?;
-                Ok(domain::SubscriptionFee::Usage { metric_id, model })
+                let cap = rust_decimal::Decimal::from_proto_opt(usage.cap.clone())?;
+                Ok(domain::SubscriptionFee::Usage {
+                    metric_id,
+                    model,
+                    cap,
+                    included: usage.included,
+                })
+            }
+            Some(api::subscription_fee::Fee::Percent(percent)) => {
+                Ok(domain::SubscriptionFee::Percent {
+                    metric_id: Uuid::from_proto_ref(&percent.metric_id)?,
+                    percentage: rust_decimal::Decimal::from_proto_ref(&percent.percentage)?,
+                    minimum: rust_decimal::Decimal::from_proto_opt(percent.minimum.clone())?,
+                    maximum: rust_decimal::Decimal::from_proto_opt(percent.maximum.clone())?,
+                })
             }
             None => Err(Status::new(
                 Code::InvalidArgument,
@@ -527,7 +906,7 @@ mod price_components {
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(domain::UsagePricingModel::Tiered {
                     tiers,
-                    block_size: None, // TODO
+                    block_size: tiered.block_size,
                 })
             }
             Some(api_components::usage_fee::Model::Volume(volume)) => {
@@ -538,7 +917,7 @@ mod price_components {
                     .collect::<Result<Vec<_>, _>>()?;
                 Ok(domain::UsagePricingModel::Volume {
                     tiers,
-                    block_size: None, // TODO
+                    block_size: volume.block_size,
                 })
             }
             Some(api_components::usage_fee::Model::Package(package)) => {
@@ -602,21 +981,36 @@ mod price_components {
         })
     }
 
+    /// The months are only read for a custom period
     pub fn subscription_fee_billing_period_from_grpc(
         period: api::SubscriptionFeeBillingPeriod,
+        months: Option<u32>,
     ) -> Result<domain::enums::SubscriptionFeeBillingPeriod, Status> {
         match period {
             api::SubscriptionFeeBillingPeriod::OneTime => {
                 Ok(domain::enums::SubscriptionFeeBillingPeriod::OneTime)
             }
+            api::SubscriptionFeeBillingPeriod::Weekly => {
+                Ok(domain::enums::SubscriptionFeeBillingPeriod::Weekly)
+            }
             api::SubscriptionFeeBillingPeriod::Monthly => {
                 Ok(domain::enums::SubscriptionFeeBillingPeriod::Monthly)
             }
+            api::SubscriptionFeeBillingPeriod::Bimonthly => {
+                Ok(domain::enums::SubscriptionFeeBillingPeriod::Bimonthly)
+            }
             api::SubscriptionFeeBillingPeriod::Quarterly => {
                 Ok(domain::enums::SubscriptionFeeBillingPeriod::Quarterly)
             }
+            api::SubscriptionFeeBillingPeriod::Semiannual => {
+                Ok(domain::enums::SubscriptionFeeBillingPeriod::Semiannual)
+            }
             api::SubscriptionFeeBillingPeriod::Yearly => {
                 Ok(domain::enums::SubscriptionFeeBillingPeriod::Annual)
+            }
+            api::SubscriptionFeeBillingPeriod::Custom => {
+                map_billing_period_from_grpc(api_shared::BillingPeriod::Custom, months)
+                    .map(|period| period.as_subscription_billing_period())
             } // _ => Err(Status::new(Code::InvalidArgument, "Invalid billing period")),
         }
     }
@@ -641,16 +1035,13 @@ mod price_components {
 
     pub fn map_billing_period_from_grpc(
         period: api_shared::BillingPeriod,
-    ) -> domain::enums::BillingPeriodEnum {
-        match period {
-            api_shared::BillingPeriod::Monthly => domain::enums::BillingPeriodEnum::Monthly,
-            api_shared::BillingPeriod::Quarterly => domain::enums::BillingPeriodEnum::Quarterly,
-            api_shared::BillingPeriod::Annual => domain::enums::BillingPeriodEnum::Annual,
-        }
+        months: Option<u32>,
+    ) -> Result<domain::enums::BillingPeriodEnum, Status> {
+        crate::api::domain_mapping::billing_period::from_proto(period, months)
     }
 }
 
-mod add_ons {
+pub mod add_ons {
     use crate::api::shared::conversions::ProtoConv;
     use crate::api::subscriptions::mapping::price_components::{
         map_billing_period_from_grpc, subscription_fee_billing_period_from_grpc,
@@ -672,6 +1063,7 @@ mod add_ons {
             subscription_id: add_on.subscription_id.to_string(),
             name: add_on.name.clone(),
             period: subscription_fee_billing_period_to_grpc(add_on.period.clone()).into(),
+            period_months: add_on.period.custom_months().map(|months| months as u32),
             fee: Some(subscription_fee_to_grpc(&add_on.fee)),
         }
     }
@@ -688,7 +1080,7 @@ mod add_ons {
         Ok(domain::CreateSubscriptionAddOns { add_ons })
     }
 
-    fn create_subscription_add_on_from_grpc(
+    pub fn create_subscription_add_on_from_grpc(
         data: api::CreateSubscriptionAddOn,
     ) -> tonic::Result<domain::CreateSubscriptionAddOn> {
         let id = Uuid::from_proto_ref(&data.add_on_id)?;
@@ -700,7 +1092,10 @@ mod add_ons {
                     domain::SubscriptionAddOnCustomization::Override(
                         domain::SubscriptionAddOnOverride {
                             name: override_.name.clone(),
-                            period: subscription_fee_billing_period_from_grpc(override_.period())?,
+                            period: subscription_fee_billing_period_from_grpc(
+                                override_.period(),
+                                override_.period_months,
+                            )?,
                             fee,
                         },
                     ),
@@ -712,7 +1107,8 @@ mod add_ons {
                     .map(api_shared::BillingPeriod::try_from)
                     .transpose()
                     .map_err(|_| Status::invalid_argument("Invalid billing period".to_string()))?
-                    .map(map_billing_period_from_grpc);
+                    .map(|period| map_billing_period_from_grpc(period, param.billing_period_months))
+                    .transpose()?;
 
                 Ok(domain::SubscriptionAddOnCustomization::Parameterization(
                     domain::SubscriptionAddOnParameterization {
@@ -734,8 +1130,8 @@ mod add_ons {
 
 pub mod ext {
     pub use super::price_components::{
-        billing_type_from_grpc, billing_type_to_grpc, usage_pricing_model_from_grpc,
-        usage_pricing_model_to_grpc,
+        billing_type_from_grpc, billing_type_to_grpc, tier_row_from_grpc,
+        usage_pricing_model_from_grpc, usage_pricing_model_to_grpc,
     };
 }
 
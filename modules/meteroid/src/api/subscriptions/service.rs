@@ -1,3 +1,4 @@
+use chrono::NaiveDate;
 use tonic::{Request, Response, Status};
 
 use common_grpc::middleware::server::auth::RequestExt;
@@ -5,25 +6,47 @@ use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::subscriptions::v1::subscriptions_service_server::SubscriptionsService;
 
 use meteroid_grpc::meteroid::api::subscriptions::v1::{
+    create_subscriptions_batch_response, ApproveSeatIncreaseRequestRequest,
+    ApproveSeatIncreaseRequestResponse, AttachAddOnRequest, AttachAddOnResponse,
+    CancelSubscriptionPendingChangeRequest, CancelSubscriptionPendingChangeResponse,
     CancelSubscriptionRequest, CancelSubscriptionResponse, CreateSubscriptionRequest,
-    CreateSubscriptionResponse, CreateSubscriptionsRequest, CreateSubscriptionsResponse,
-    GetSlotsValueRequest, GetSlotsValueResponse, ListSubscriptionsRequest,
-    ListSubscriptionsResponse, PaginationResponse, SubscriptionDetails, UpdateSlotsRequest,
-    UpdateSlotsResponse,
+    CreateSubscriptionResponse, CreateSubscriptionsBatchRequest, CreateSubscriptionsBatchResponse,
+    CreateSubscriptionsRequest, CreateSubscriptionsResponse, DetachAddOnRequest,
+    DetachAddOnResponse, GetBillingScheduleRequest, GetBillingScheduleResponse,
+    GetCustomerEntitlementsRequest, GetCustomerEntitlementsResponse, GetEntitlementsRequest,
+    GetEntitlementsResponse, GetSlotsValueRequest, GetSlotsValueResponse,
+    ListSeatIncreaseRequestsRequest, ListSeatIncreaseRequestsResponse,
+    ListSubscriptionComponentHistoryRequest, ListSubscriptionComponentHistoryResponse,
+    ListSubscriptionPendingChangesRequest, ListSubscriptionPendingChangesResponse,
+    ListSubscriptionsRequest, ListSubscriptionsResponse, ListUsageCapsRequest,
+    ListUsageCapsResponse, PaginationResponse, RejectSeatIncreaseRequestRequest,
+    RejectSeatIncreaseRequestResponse, ScheduleSubscriptionChangeRequest,
+    ScheduleSubscriptionChangeResponse, SubscriptionDetails, UpdateSlotsRequest,
+    UpdateSlotsResponse, UpdateSubscriptionTermRequest, UpdateSubscriptionTermResponse,
 };
 
 use meteroid_store::domain;
+use meteroid_store::repositories::entitlements::EntitlementInterface;
+use meteroid_store::repositories::seat_increase_requests::SeatIncreaseRequestInterface;
+use meteroid_store::repositories::subscription_add_ons::SubscriptionAddOnInterface;
+use meteroid_store::repositories::subscription_component_history::SubscriptionComponentHistoryInterface;
+use meteroid_store::repositories::subscription_pending_changes::SubscriptionPendingChangeInterface;
 use meteroid_store::repositories::subscriptions::{
-    CancellationEffectiveAt, SubscriptionSlotsInterface,
+    CancellationEffectiveAt, SubscriptionBillingScheduleInterface, SubscriptionSlotsInterface,
+    SubscriptionTermInterface,
 };
+use meteroid_store::repositories::usage_caps::UsageCapInterface;
 use meteroid_store::repositories::SubscriptionInterface;
 
+use crate::api::shared::conversions::FromProtoOpt;
 use crate::api::subscriptions::error::SubscriptionApiError;
 use crate::api::subscriptions::{mapping, SubscriptionServiceComponents};
 use crate::api::utils::{parse_uuid, parse_uuid_opt};
 
 use crate::parse_uuid;
 
+const MAX_BILLING_SCHEDULE_COUNT: u32 = 36;
+
 #[tonic::async_trait]
 impl SubscriptionsService for SubscriptionServiceComponents {
     async fn create_subscription(
@@ -88,6 +111,67 @@ impl SubscriptionsService for SubscriptionServiceComponents {
         }))
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn create_subscriptions_batch(
+        &self,
+        request: Request<CreateSubscriptionsBatchRequest>,
+    ) -> Result<Response<CreateSubscriptionsBatchResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let inner = request.into_inner();
+
+        let mut subscriptions = Vec::with_capacity(inner.subscriptions.len());
+        // maps the position in the store batch to the position in the request
+        let mut request_indexes = Vec::with_capacity(inner.subscriptions.len());
+        let mut failures = vec![];
+
+        for (index, s) in inner.subscriptions.into_iter().enumerate() {
+            match mapping::subscriptions::create_proto_to_domain(s, &actor) {
+                Ok(subscription) => {
+                    subscriptions.push(subscription);
+                    request_indexes.push(index);
+                }
+                Err(e) => failures.push(create_subscriptions_batch_response::Failure {
+                    index: index as u32,
+                    reason: e.message().to_string(),
+                }),
+            }
+        }
+
+        let res = self
+            .store
+            .create_subscriptions_batch(subscriptions, tenant_id)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        failures.extend(res.failures.into_iter().map(|f| {
+            create_subscriptions_batch_response::Failure {
+                index: request_indexes[f.index] as u32,
+                reason: f.reason,
+            }
+        }));
+        failures.sort_by_key(|f| f.index);
+
+        let created = res
+            .created
+            .into_iter()
+            .map(|(index, s)| {
+                mapping::subscriptions::created_domain_to_proto(s).map(|subscription| {
+                    create_subscriptions_batch_response::Created {
+                        index: request_indexes[index] as u32,
+                        subscription: Some(subscription),
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+
+        Ok(Response::new(CreateSubscriptionsBatchResponse {
+            created,
+            failures,
+        }))
+    }
+
     #[tracing::instrument(skip_all)]
     async fn get_subscription_details(
         &self,
@@ -156,21 +240,31 @@ impl SubscriptionsService for SubscriptionServiceComponents {
         request: Request<UpdateSlotsRequest>,
     ) -> Result<Response<UpdateSlotsResponse>, Status> {
         let tenant_id = request.tenant()?;
-        let _actor = request.actor()?;
+        let actor = request.actor()?;
 
         let inner = request.into_inner();
 
         let subscription_id = parse_uuid!(inner.subscription_id)?;
         let price_component_id = parse_uuid!(inner.price_component_id)?;
 
-        let added = self
+        let update = self
             .store
-            .add_slot_transaction(tenant_id, subscription_id, price_component_id, inner.delta)
+            .add_slot_transaction(
+                tenant_id,
+                subscription_id,
+                price_component_id,
+                inner.delta,
+                actor,
+                None,
+            )
             .await
             .map_err(Into::<SubscriptionApiError>::into)?;
 
         Ok(Response::new(UpdateSlotsResponse {
-            current_value: added as u32, // TODO
+            current_value: update.current_value,
+            next_period_value: update.next_period_value,
+            prorated_amount: update.prorated_amount,
+            invoice_id: update.invoice_id.map(|id| id.to_string()),
         }))
     }
 
@@ -201,6 +295,99 @@ impl SubscriptionsService for SubscriptionServiceComponents {
         }))
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn attach_add_on(
+        &self,
+        request: Request<AttachAddOnRequest>,
+    ) -> Result<Response<AttachAddOnResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let inner = request.into_inner();
+
+        let subscription_id = parse_uuid!(inner.subscription_id)?;
+        let add_on = mapping::add_ons::create_subscription_add_on_from_grpc(
+            inner
+                .add_on
+                .ok_or_else(|| SubscriptionApiError::MissingArgument("add_on".to_string()))?,
+        )?;
+
+        let update = self
+            .store
+            .attach_subscription_add_on(tenant_id, subscription_id, add_on, actor)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(AttachAddOnResponse {
+            add_on: Some(mapping::add_ons::subscription_add_on_to_grpc(
+                &update.add_on,
+            )),
+            prorated_amount: update.prorated_amount,
+            invoice_id: update.invoice_id.map(|id| id.to_string()),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn detach_add_on(
+        &self,
+        request: Request<DetachAddOnRequest>,
+    ) -> Result<Response<DetachAddOnResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let inner = request.into_inner();
+
+        let subscription_id = parse_uuid!(inner.subscription_id)?;
+        let subscription_add_on_id = parse_uuid!(inner.subscription_add_on_id)?;
+
+        let update = self
+            .store
+            .detach_subscription_add_on(tenant_id, subscription_id, subscription_add_on_id, actor)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(DetachAddOnResponse {
+            add_on: Some(mapping::add_ons::subscription_add_on_to_grpc(
+                &update.add_on,
+            )),
+            prorated_amount: update.prorated_amount,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_billing_schedule(
+        &self,
+        request: Request<GetBillingScheduleRequest>,
+    ) -> Result<Response<GetBillingScheduleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let subscription_id = parse_uuid!(inner.subscription_id)?;
+        let from = NaiveDate::from_proto_opt(inner.from_date)?
+            .unwrap_or_else(|| chrono::Utc::now().naive_utc().date());
+
+        if inner.count == 0 || inner.count > MAX_BILLING_SCHEDULE_COUNT {
+            return Err(SubscriptionApiError::InvalidArgument(format!(
+                "count must be between 1 and {}",
+                MAX_BILLING_SCHEDULE_COUNT
+            ))
+            .into());
+        }
+
+        let entries = self
+            .store
+            .get_billing_schedule(tenant_id, subscription_id, from, inner.count)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(GetBillingScheduleResponse {
+            entries: entries
+                .into_iter()
+                .map(mapping::subscriptions::billing_schedule_entry_to_proto)
+                .collect(),
+        }))
+    }
+
     #[tracing::instrument(skip_all)]
     async fn cancel_subscription(
         &self,
@@ -234,4 +421,268 @@ impl SubscriptionsService for SubscriptionServiceComponents {
             })
             .map_err(Into::<Status>::into)
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_subscription_term(
+        &self,
+        request: Request<UpdateSubscriptionTermRequest>,
+    ) -> Result<Response<UpdateSubscriptionTermResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let inner = request.into_inner();
+
+        let billing_end_date = NaiveDate::from_proto_opt(inner.billing_end_date)?;
+
+        let subscription = self
+            .store
+            .update_subscription_term(
+                parse_uuid!(inner.subscription_id)?,
+                billing_end_date,
+                domain::TenantContext { tenant_id, actor },
+            )
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        mapping::subscriptions::domain_to_proto(subscription)
+            .map(|s| {
+                Response::new(UpdateSubscriptionTermResponse {
+                    subscription: Some(s),
+                })
+            })
+            .map_err(Into::<Status>::into)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn schedule_subscription_change(
+        &self,
+        request: Request<ScheduleSubscriptionChangeRequest>,
+    ) -> Result<Response<ScheduleSubscriptionChangeResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let inner = request.into_inner();
+
+        let change = mapping::subscriptions::subscription_change_from_proto(inner.change)?;
+
+        let pending_change = self
+            .store
+            .schedule_subscription_change(
+                parse_uuid!(inner.subscription_id)?,
+                change,
+                domain::TenantContext { tenant_id, actor },
+            )
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ScheduleSubscriptionChangeResponse {
+            pending_change: Some(mapping::subscriptions::pending_change_domain_to_proto(
+                pending_change,
+            )),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn cancel_subscription_pending_change(
+        &self,
+        request: Request<CancelSubscriptionPendingChangeRequest>,
+    ) -> Result<Response<CancelSubscriptionPendingChangeResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let pending_change = self
+            .store
+            .cancel_subscription_pending_change(parse_uuid!(inner.pending_change_id)?, tenant_id)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(CancelSubscriptionPendingChangeResponse {
+            pending_change: Some(mapping::subscriptions::pending_change_domain_to_proto(
+                pending_change,
+            )),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_subscription_pending_changes(
+        &self,
+        request: Request<ListSubscriptionPendingChangesRequest>,
+    ) -> Result<Response<ListSubscriptionPendingChangesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let pending_changes = self
+            .store
+            .list_subscription_pending_changes(tenant_id, parse_uuid!(inner.subscription_id)?)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ListSubscriptionPendingChangesResponse {
+            pending_changes: pending_changes
+                .into_iter()
+                .map(mapping::subscriptions::pending_change_domain_to_proto)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_subscription_component_history(
+        &self,
+        request: Request<ListSubscriptionComponentHistoryRequest>,
+    ) -> Result<Response<ListSubscriptionComponentHistoryResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let history = self
+            .store
+            .list_subscription_component_history(tenant_id, parse_uuid!(inner.subscription_id)?)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ListSubscriptionComponentHistoryResponse {
+            changes: history
+                .into_iter()
+                .map(mapping::subscriptions::component_history_domain_to_proto)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_usage_caps(
+        &self,
+        request: Request<ListUsageCapsRequest>,
+    ) -> Result<Response<ListUsageCapsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let usage_caps = self
+            .store
+            .list_usage_caps(
+                tenant_id,
+                parse_uuid!(inner.subscription_id)?,
+                chrono::Utc::now().naive_utc(),
+            )
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ListUsageCapsResponse {
+            usage_caps: usage_caps
+                .into_iter()
+                .map(mapping::subscriptions::usage_cap_status_to_proto)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_entitlements(
+        &self,
+        request: Request<GetEntitlementsRequest>,
+    ) -> Result<Response<GetEntitlementsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let entitlements = self
+            .store
+            .get_entitlements(
+                tenant_id,
+                parse_uuid!(inner.subscription_id)?,
+                chrono::Utc::now().naive_utc(),
+            )
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(GetEntitlementsResponse {
+            entitlements: entitlements
+                .into_iter()
+                .map(mapping::subscriptions::entitlement_to_proto)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_customer_entitlements(
+        &self,
+        request: Request<GetCustomerEntitlementsRequest>,
+    ) -> Result<Response<GetCustomerEntitlementsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let entitlements = self
+            .store
+            .get_customer_entitlements(tenant_id, parse_uuid!(inner.customer_id)?)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(
+            mapping::subscriptions::customer_entitlements_to_proto(entitlements),
+        ))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_seat_increase_requests(
+        &self,
+        request: Request<ListSeatIncreaseRequestsRequest>,
+    ) -> Result<Response<ListSeatIncreaseRequestsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let inner = request.into_inner();
+
+        let status = inner.status.is_some().then(|| {
+            mapping::subscriptions::seat_increase_request_status_to_domain(inner.status())
+        });
+
+        let requests = self
+            .store
+            .list_seat_increase_requests(
+                tenant_id,
+                parse_uuid_opt(&inner.subscription_id, "subscription_id")?,
+                status,
+            )
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ListSeatIncreaseRequestsResponse {
+            requests: requests
+                .into_iter()
+                .map(mapping::subscriptions::seat_increase_request_domain_to_proto)
+                .collect(),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn approve_seat_increase_request(
+        &self,
+        request: Request<ApproveSeatIncreaseRequestRequest>,
+    ) -> Result<Response<ApproveSeatIncreaseRequestResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let inner = request.into_inner();
+
+        let approved = self
+            .store
+            .approve_seat_increase_request(tenant_id, parse_uuid!(inner.id)?, actor, inner.note)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(ApproveSeatIncreaseRequestResponse {
+            request: Some(mapping::subscriptions::seat_increase_request_domain_to_proto(approved)),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn reject_seat_increase_request(
+        &self,
+        request: Request<RejectSeatIncreaseRequestRequest>,
+    ) -> Result<Response<RejectSeatIncreaseRequestResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let inner = request.into_inner();
+
+        let rejected = self
+            .store
+            .reject_seat_increase_request(tenant_id, parse_uuid!(inner.id)?, actor, inner.note)
+            .await
+            .map_err(Into::<SubscriptionApiError>::into)?;
+
+        Ok(Response::new(RejectSeatIncreaseRequestResponse {
+            request: Some(mapping::subscriptions::seat_increase_request_domain_to_proto(rejected)),
+        }))
+    }
 }
@@ -11,6 +11,14 @@ pub enum TenantApiError {
     #[code(InvalidArgument)]
     MissingArgument(String),
 
+    #[error("Invalid argument: {0}")]
+    #[code(InvalidArgument)]
+    InvalidArgument(String),
+
+    #[error("{0}")]
+    #[code(FailedPrecondition)]
+    FailedPrecondition(String),
+
     #[error("Downstream service error: {0}")]
     #[code(Internal)]
     DownstreamApiError(String, #[source] Box<dyn std::error::Error + Sync + Send>),
@@ -22,7 +30,27 @@ pub enum TenantApiError {
 
 impl From<Report<StoreError>> for TenantApiError {
     fn from(value: Report<StoreError>) -> Self {
-        let err = Box::new(value.into_error());
-        TenantApiError::StoreError("Error in tenant service".to_string(), err)
+        let context = match value.current_context() {
+            StoreError::TransactionStoreError(inner) => inner.current_context(),
+            context => context,
+        };
+
+        match context {
+            StoreError::OnboardingIncomplete(_) => {
+                TenantApiError::FailedPrecondition(context.to_string())
+            }
+            StoreError::InvalidArgument(msg) => TenantApiError::InvalidArgument(msg.clone()),
+            StoreError::DuplicateValue {
+                entity: "customer",
+                key,
+            } => TenantApiError::FailedPrecondition(format!(
+                "several customers have the same {}, they must be merged or archived first",
+                key.as_deref().unwrap_or("key")
+            )),
+            _ => {
+                let err = Box::new(value.into_error());
+                TenantApiError::StoreError("Error in tenant service".to_string(), err)
+            }
+        }
     }
 }
@@ -1,8 +1,14 @@
 pub mod tenants {
+    use crate::api::collectionstrategies::mapping::preset;
     use meteroid_grpc::meteroid::api::tenants::v1::CreateTenantRequest;
+    use meteroid_grpc::meteroid::api::tenants::v1::CustomerUniqueness as GrpcCustomerUniqueness;
+    use meteroid_grpc::meteroid::api::tenants::v1::DueDatePolicy as GrpcDueDatePolicy;
+    use meteroid_grpc::meteroid::api::tenants::v1::InvoiceCadenceGrouping as GrpcInvoiceCadenceGrouping;
+    use meteroid_grpc::meteroid::api::tenants::v1::ProrationRounding as GrpcProrationRounding;
     use meteroid_grpc::meteroid::api::tenants::v1::Tenant;
     use meteroid_grpc::meteroid::api::tenants::v1::TenantEnvironmentEnum as GrpcTenantEnvironmentEnum;
     use meteroid_grpc::meteroid::api::tenants::v1::TenantUpdate as GrpcTenantUpdate;
+    use meteroid_grpc::meteroid::api::tenants::v1::ZeroInvoicePolicy as GrpcZeroInvoicePolicy;
     use meteroid_store::domain;
 
     pub fn domain_to_server(tenant: domain::Tenant) -> Tenant {
@@ -12,6 +18,27 @@ pub mod tenants {
             slug: tenant.slug,
             reporting_currency: tenant.currency,
             environment: environment_to_grpc(tenant.environment).into(),
+            proration_rounding: proration_rounding_to_grpc(tenant.proration_rounding).into(),
+            zero_invoice_policy: zero_invoice_policy_to_grpc(tenant.zero_invoice_policy).into(),
+            invoice_cadence_grouping: invoice_cadence_grouping_to_grpc(
+                tenant.invoice_cadence_grouping,
+            )
+            .into(),
+            default_net_terms: tenant.default_net_terms.map(|v| v as u32),
+            due_date_policy: due_date_policy_to_grpc(tenant.due_date_policy).into(),
+            due_date_day: tenant.due_date_day.map(|v| v as u32),
+            production_tenant_id: tenant.production_tenant_id.map(|id| id.to_string()),
+            finance_email: tenant.finance_email,
+            customer_uniqueness: customer_uniqueness_to_grpc(tenant.customer_uniqueness).into(),
+            collection_strategy_preset: preset::to_proto(tenant.collection_strategy_preset).into(),
+            billing_timezone: tenant.billing_timezone,
+            finalization_grace_period_hours: tenant
+                .finalization_grace_period_hours
+                .map(|v| v as u32),
+            seat_auto_approve_limit: tenant.seat_auto_approve_limit.map(|v| v as u32),
+            stuck_draft_max_age_days: tenant.stuck_draft_max_age_days.map(|v| v as u32),
+            stuck_pending_max_age_hours: tenant.stuck_pending_max_age_hours.map(|v| v as u32),
+            stuck_max_issue_attempts: tenant.stuck_max_issue_attempts.map(|v| v as u32),
         }
     }
 
@@ -22,12 +49,51 @@ pub mod tenants {
 
         environment_grpc_to_domain(req.environment());
 
+        let proration_rounding = req
+            .proration_rounding
+            .map(|_| proration_rounding_grpc_to_domain(req.proration_rounding()));
+
+        let zero_invoice_policy = req
+            .zero_invoice_policy
+            .map(|_| zero_invoice_policy_grpc_to_domain(req.zero_invoice_policy()));
+
+        let invoice_cadence_grouping = req
+            .invoice_cadence_grouping
+            .map(|_| invoice_cadence_grouping_grpc_to_domain(req.invoice_cadence_grouping()));
+
+        let due_date_policy = req
+            .due_date_policy
+            .map(|_| due_date_policy_grpc_to_domain(req.due_date_policy()));
+
+        let customer_uniqueness = req
+            .customer_uniqueness
+            .map(|_| customer_uniqueness_grpc_to_domain(req.customer_uniqueness()));
+
+        let collection_strategy_preset = req
+            .collection_strategy_preset
+            .map(|_| preset::from_proto(req.collection_strategy_preset()));
+
         domain::TenantUpdate {
             name: req.name,
             slug: req.slug,
             trade_name: req.trade_name,
             currency: req.reporting_currency,
             environment,
+            proration_rounding,
+            zero_invoice_policy,
+            invoice_cadence_grouping,
+            default_net_terms: req.default_net_terms.map(|v| v as i32),
+            due_date_policy,
+            due_date_day: req.due_date_day.map(|v| v as i32),
+            finance_email: req.finance_email,
+            customer_uniqueness,
+            collection_strategy_preset,
+            billing_timezone: req.billing_timezone,
+            finalization_grace_period_hours: req.finalization_grace_period_hours.map(|v| v as i32),
+            seat_auto_approve_limit: req.seat_auto_approve_limit.map(|v| v as i32),
+            stuck_draft_max_age_days: req.stuck_draft_max_age_days.map(|v| v as i32),
+            stuck_pending_max_age_hours: req.stuck_pending_max_age_hours.map(|v| v as i32),
+            stuck_max_issue_attempts: req.stuck_max_issue_attempts.map(|v| v as i32),
         }
     }
 
@@ -37,6 +103,7 @@ pub mod tenants {
         domain::TenantNew {
             name: req.name,
             environment,
+            production_tenant_id: None,
         }
     }
 
@@ -73,6 +140,140 @@ pub mod tenants {
             GrpcTenantEnvironmentEnum::Demo => domain::enums::TenantEnvironmentEnum::Demo,
         }
     }
+
+    pub fn proration_rounding_to_grpc(
+        rounding: domain::enums::ProrationRoundingEnum,
+    ) -> GrpcProrationRounding {
+        match rounding {
+            domain::enums::ProrationRoundingEnum::Nearest => GrpcProrationRounding::Nearest,
+            domain::enums::ProrationRoundingEnum::NearestEven => GrpcProrationRounding::NearestEven,
+            domain::enums::ProrationRoundingEnum::Up => GrpcProrationRounding::Up,
+            domain::enums::ProrationRoundingEnum::Down => GrpcProrationRounding::Down,
+        }
+    }
+
+    pub fn proration_rounding_grpc_to_domain(
+        rounding: GrpcProrationRounding,
+    ) -> domain::enums::ProrationRoundingEnum {
+        match rounding {
+            GrpcProrationRounding::Nearest => domain::enums::ProrationRoundingEnum::Nearest,
+            GrpcProrationRounding::NearestEven => domain::enums::ProrationRoundingEnum::NearestEven,
+            GrpcProrationRounding::Up => domain::enums::ProrationRoundingEnum::Up,
+            GrpcProrationRounding::Down => domain::enums::ProrationRoundingEnum::Down,
+        }
+    }
+
+    pub fn zero_invoice_policy_to_grpc(
+        policy: domain::enums::ZeroInvoicePolicyEnum,
+    ) -> GrpcZeroInvoicePolicy {
+        match policy {
+            domain::enums::ZeroInvoicePolicyEnum::Issue => GrpcZeroInvoicePolicy::Issue,
+            domain::enums::ZeroInvoicePolicyEnum::FinalizeWithoutIssuing => {
+                GrpcZeroInvoicePolicy::FinalizeWithoutIssuing
+            }
+            domain::enums::ZeroInvoicePolicyEnum::Suppress => GrpcZeroInvoicePolicy::Suppress,
+        }
+    }
+
+    pub fn zero_invoice_policy_grpc_to_domain(
+        policy: GrpcZeroInvoicePolicy,
+    ) -> domain::enums::ZeroInvoicePolicyEnum {
+        match policy {
+            GrpcZeroInvoicePolicy::Issue => domain::enums::ZeroInvoicePolicyEnum::Issue,
+            GrpcZeroInvoicePolicy::FinalizeWithoutIssuing => {
+                domain::enums::ZeroInvoicePolicyEnum::FinalizeWithoutIssuing
+            }
+            GrpcZeroInvoicePolicy::Suppress => domain::enums::ZeroInvoicePolicyEnum::Suppress,
+        }
+    }
+
+    pub fn invoice_cadence_grouping_to_grpc(
+        grouping: domain::enums::InvoiceCadenceGroupingEnum,
+    ) -> GrpcInvoiceCadenceGrouping {
+        match grouping {
+            domain::enums::InvoiceCadenceGroupingEnum::Combined => {
+                GrpcInvoiceCadenceGrouping::Combined
+            }
+            domain::enums::InvoiceCadenceGroupingEnum::PerCadence => {
+                GrpcInvoiceCadenceGrouping::PerCadence
+            }
+        }
+    }
+
+    pub fn invoice_cadence_grouping_grpc_to_domain(
+        grouping: GrpcInvoiceCadenceGrouping,
+    ) -> domain::enums::InvoiceCadenceGroupingEnum {
+        match grouping {
+            GrpcInvoiceCadenceGrouping::Combined => {
+                domain::enums::InvoiceCadenceGroupingEnum::Combined
+            }
+            GrpcInvoiceCadenceGrouping::PerCadence => {
+                domain::enums::InvoiceCadenceGroupingEnum::PerCadence
+            }
+        }
+    }
+
+    pub fn due_date_policy_to_grpc(policy: domain::enums::DueDatePolicyEnum) -> GrpcDueDatePolicy {
+        match policy {
+            domain::enums::DueDatePolicyEnum::NetDays => GrpcDueDatePolicy::NetDays,
+            domain::enums::DueDatePolicyEnum::EndOfMonth => GrpcDueDatePolicy::EndOfMonth,
+            domain::enums::DueDatePolicyEnum::FixedDay => GrpcDueDatePolicy::FixedDay,
+        }
+    }
+
+    pub fn due_date_policy_grpc_to_domain(
+        policy: GrpcDueDatePolicy,
+    ) -> domain::enums::DueDatePolicyEnum {
+        match policy {
+            GrpcDueDatePolicy::NetDays => domain::enums::DueDatePolicyEnum::NetDays,
+            GrpcDueDatePolicy::EndOfMonth => domain::enums::DueDatePolicyEnum::EndOfMonth,
+            GrpcDueDatePolicy::FixedDay => domain::enums::DueDatePolicyEnum::FixedDay,
+        }
+    }
+
+    pub fn customer_uniqueness_to_grpc(
+        uniqueness: domain::enums::CustomerUniquenessEnum,
+    ) -> GrpcCustomerUniqueness {
+        match uniqueness {
+            domain::enums::CustomerUniquenessEnum::None => GrpcCustomerUniqueness::None,
+            domain::enums::CustomerUniquenessEnum::Email => GrpcCustomerUniqueness::Email,
+            domain::enums::CustomerUniquenessEnum::Alias => GrpcCustomerUniqueness::Alias,
+            domain::enums::CustomerUniquenessEnum::EmailAndAlias => {
+                GrpcCustomerUniqueness::EmailAndAlias
+            }
+        }
+    }
+
+    pub fn customer_uniqueness_grpc_to_domain(
+        uniqueness: GrpcCustomerUniqueness,
+    ) -> domain::enums::CustomerUniquenessEnum {
+        match uniqueness {
+            GrpcCustomerUniqueness::None => domain::enums::CustomerUniquenessEnum::None,
+            GrpcCustomerUniqueness::Email => domain::enums::CustomerUniquenessEnum::Email,
+            GrpcCustomerUniqueness::Alias => domain::enums::CustomerUniquenessEnum::Alias,
+            GrpcCustomerUniqueness::EmailAndAlias => {
+                domain::enums::CustomerUniquenessEnum::EmailAndAlias
+            }
+        }
+    }
+}
+
+pub mod tenant_environments {
+    use meteroid_grpc::meteroid::api::tenants::v1 as server;
+    use meteroid_store::domain::tenant_environments::CatalogCopyReport;
+
+    pub fn catalog_report_to_server(report: CatalogCopyReport) -> server::CatalogCopyReport {
+        server::CatalogCopyReport {
+            product_families: report.product_families,
+            billable_metrics: report.billable_metrics,
+            products: report.products,
+            rate_cards: report.rate_cards,
+            add_ons: report.add_ons,
+            plans: report.plans,
+            plan_versions: report.plan_versions,
+            skipped_plans: report.skipped_plans,
+        }
+    }
 }
 
 pub mod provider_configs {
@@ -123,10 +324,80 @@ pub mod provider_configs {
                 },
                 api_security: ApiSecurity {
                     api_key: stripe.api_secret,
+                    merchant_account: None,
+                    live_url_prefix: None,
                 },
+                is_default: true,
+                fallback_priority: None,
             },
         };
 
         Ok(cfg)
     }
 }
+
+pub mod onboarding {
+    use crate::api::shared::conversions::ProtoConv;
+    use meteroid_grpc::meteroid::api::tenants::v1 as server;
+    use meteroid_store::domain::enums::OnboardingStepEnum;
+    use meteroid_store::domain::onboarding::OnboardingStatus;
+
+    pub fn step_to_server(step: OnboardingStepEnum) -> server::OnboardingStep {
+        match step {
+            OnboardingStepEnum::InvoicingEntity => server::OnboardingStep::InvoicingEntity,
+            OnboardingStepEnum::PaymentProvider => server::OnboardingStep::PaymentProvider,
+            OnboardingStepEnum::BillableMetric => server::OnboardingStep::BillableMetric,
+            OnboardingStepEnum::Plan => server::OnboardingStep::Plan,
+            OnboardingStepEnum::Customer => server::OnboardingStep::Customer,
+        }
+    }
+
+    pub fn step_server_to_domain(step: server::OnboardingStep) -> Option<OnboardingStepEnum> {
+        match step {
+            server::OnboardingStep::Unspecified => None,
+            server::OnboardingStep::InvoicingEntity => Some(OnboardingStepEnum::InvoicingEntity),
+            server::OnboardingStep::PaymentProvider => Some(OnboardingStepEnum::PaymentProvider),
+            server::OnboardingStep::BillableMetric => Some(OnboardingStepEnum::BillableMetric),
+            server::OnboardingStep::Plan => Some(OnboardingStepEnum::Plan),
+            server::OnboardingStep::Customer => Some(OnboardingStepEnum::Customer),
+        }
+    }
+
+    pub fn status_to_server(status: OnboardingStatus) -> server::OnboardingStatus {
+        server::OnboardingStatus {
+            completed: status.is_completed(),
+            next_step: status.next_step().map(|s| step_to_server(s).into()),
+            steps: status
+                .steps
+                .into_iter()
+                .map(|s| server::OnboardingStepStatus {
+                    step: step_to_server(s.step).into(),
+                    completed: s.completed,
+                    completed_at: s.completed_at.map(|d| d.as_proto()),
+                })
+                .collect(),
+        }
+    }
+}
+
+pub mod event_retention {
+    use crate::api::shared::conversions::ProtoConv;
+    use meteroid_grpc::meteroid::api::tenants::v1 as server;
+    use meteroid_store::compute::clients::usage::EventRetentionPolicy;
+
+    pub fn to_server(policy: EventRetentionPolicy) -> server::EventRetention {
+        server::EventRetention {
+            retention_days: policy.retention_days,
+            archive: policy.archive,
+            archived_until: policy.archived_until.map(|d| d.as_proto()),
+        }
+    }
+
+    pub fn server_to_domain(retention: server::EventRetention) -> EventRetentionPolicy {
+        EventRetentionPolicy {
+            retention_days: retention.retention_days,
+            archive: retention.archive,
+            archived_until: None,
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::tenants::v1::tenants_service_server::TenantsServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub(crate) mod mapping;
 mod service;
 
@@ -3,15 +3,24 @@ use tonic::{Request, Response, Status};
 use common_grpc::middleware::server::auth::RequestExt;
 use meteroid_grpc::meteroid::api::tenants::v1::{
     tenants_service_server::TenantsService, ActiveTenantRequest, ActiveTenantResponse,
-    ConfigureTenantBillingRequest, ConfigureTenantBillingResponse, CreateTenantRequest,
-    CreateTenantResponse, GetTenantByIdRequest, GetTenantByIdResponse, ListTenantsRequest,
-    ListTenantsResponse, UpdateTenantRequest, UpdateTenantResponse,
+    CompleteOnboardingStepRequest, CompleteOnboardingStepResponse, ConfigureTenantBillingRequest,
+    ConfigureTenantBillingResponse, CreateSandboxTenantRequest, CreateSandboxTenantResponse,
+    CreateTenantRequest, CreateTenantResponse, GetEventRetentionRequest, GetEventRetentionResponse,
+    GetOnboardingStatusRequest, GetOnboardingStatusResponse, GetTenantByIdRequest,
+    GetTenantByIdResponse, ListTenantsRequest, ListTenantsResponse, PromoteSandboxCatalogRequest,
+    PromoteSandboxCatalogResponse, ResetSandboxTenantRequest, ResetSandboxTenantResponse,
+    RestoreArchivedEventsRequest, RestoreArchivedEventsResponse, SetEventRetentionRequest,
+    SetEventRetentionResponse, UpdateTenantRequest, UpdateTenantResponse,
 };
 use meteroid_middleware::server::auth::strategies::jwt_strategy::invalidate_resolve_slugs_cache;
+use meteroid_store::domain::tenant_environments::SandboxTenantNew;
 use meteroid_store::repositories::configs::ConfigsInterface;
+use meteroid_store::repositories::onboarding::OnboardingInterface;
+use meteroid_store::repositories::tenant_environments::TenantEnvironmentsInterface;
 use meteroid_store::repositories::tenants::invalidate_reporting_currency_cache;
 use meteroid_store::repositories::{OrganizationsInterface, TenantInterface};
 
+use crate::api::shared::conversions::ProtoConv;
 use crate::api::tenants::error::TenantApiError;
 use crate::{api::utils::parse_uuid, parse_uuid};
 
@@ -168,4 +177,186 @@ impl TenantsService for TenantServiceComponents {
             billing_config: Some(res),
         }))
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_onboarding_status(
+        &self,
+        request: Request<GetOnboardingStatusRequest>,
+    ) -> Result<Response<GetOnboardingStatusResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let status = self
+            .store
+            .get_onboarding_status(tenant_id)
+            .await
+            .map(mapping::onboarding::status_to_server)
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(GetOnboardingStatusResponse {
+            status: Some(status),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn complete_onboarding_step(
+        &self,
+        request: Request<CompleteOnboardingStepRequest>,
+    ) -> Result<Response<CompleteOnboardingStepResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+
+        let step = mapping::onboarding::step_server_to_domain(request.into_inner().step())
+            .ok_or_else(|| Status::invalid_argument("Missing onboarding step"))?;
+
+        let status = self
+            .store
+            .complete_onboarding_step(tenant_id, actor, step)
+            .await
+            .map(mapping::onboarding::status_to_server)
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(CompleteOnboardingStepResponse {
+            status: Some(status),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn reset_sandbox_tenant(
+        &self,
+        request: Request<ResetSandboxTenantRequest>,
+    ) -> Result<Response<ResetSandboxTenantResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let organization_id = request.organization()?;
+
+        let confirmation = request.into_inner().confirmation;
+
+        self.store
+            .reset_sandbox_tenant(tenant_id, organization_id, confirmation)
+            .await
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(ResetSandboxTenantResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_sandbox_tenant(
+        &self,
+        request: Request<CreateSandboxTenantRequest>,
+    ) -> Result<Response<CreateSandboxTenantResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let organization_id = request.organization()?;
+        let actor = request.actor()?;
+
+        let req = request.into_inner();
+
+        let res = self
+            .store
+            .create_sandbox_tenant(SandboxTenantNew {
+                production_tenant_id: tenant_id,
+                organization_id,
+                name: req.name,
+                copy_catalog: req.copy_catalog,
+                created_by: actor,
+            })
+            .await
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(CreateSandboxTenantResponse {
+            tenant: Some(mapping::tenants::domain_to_server(res.tenant)),
+            catalog: Some(mapping::tenant_environments::catalog_report_to_server(
+                res.catalog,
+            )),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn promote_sandbox_catalog(
+        &self,
+        request: Request<PromoteSandboxCatalogRequest>,
+    ) -> Result<Response<PromoteSandboxCatalogResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let organization_id = request.organization()?;
+        let actor = request.actor()?;
+
+        let report = self
+            .store
+            .promote_sandbox_catalog(tenant_id, organization_id, actor)
+            .await
+            .map(mapping::tenant_environments::catalog_report_to_server)
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(PromoteSandboxCatalogResponse {
+            catalog: Some(report),
+        }))
+    }
+    #[tracing::instrument(skip_all)]
+    async fn get_event_retention(
+        &self,
+        request: Request<GetEventRetentionRequest>,
+    ) -> Result<Response<GetEventRetentionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let retention = self
+            .store
+            .get_tenant_event_retention(tenant_id)
+            .await
+            .map(mapping::event_retention::to_server)
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(GetEventRetentionResponse {
+            retention: Some(retention),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn set_event_retention(
+        &self,
+        request: Request<SetEventRetentionRequest>,
+    ) -> Result<Response<SetEventRetentionResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let retention = request
+            .into_inner()
+            .retention
+            .ok_or(TenantApiError::MissingArgument(
+                "retention is missing".to_string(),
+            ))?;
+
+        let retention = self
+            .store
+            .set_tenant_event_retention(
+                tenant_id,
+                mapping::event_retention::server_to_domain(retention),
+            )
+            .await
+            .map(mapping::event_retention::to_server)
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(SetEventRetentionResponse {
+            retention: Some(retention),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn restore_archived_events(
+        &self,
+        request: Request<RestoreArchivedEventsRequest>,
+    ) -> Result<Response<RestoreArchivedEventsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+
+        let req = request.into_inner();
+        let from = chrono::NaiveDate::from_proto(req.from)?;
+        let to = chrono::NaiveDate::from_proto(req.to)?;
+
+        self.store
+            .restore_tenant_archived_events(
+                tenant_id,
+                from.and_time(chrono::NaiveTime::MIN),
+                to.and_time(chrono::NaiveTime::MIN),
+            )
+            .await
+            .map_err(Into::<TenantApiError>::into)?;
+
+        Ok(Response::new(RestoreArchivedEventsResponse {}))
+    }
 }
@@ -0,0 +1,79 @@
+pub mod usage {
+    use crate::api::shared::conversions::{AsProtoOpt, ProtoConv};
+    use crate::api::shared::mapping::date::chrono_to_proto;
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use meteroid_grpc::meteroid::api::usage::v1::{
+        LineItemUsageDetail as LineItemUsageDetailProto, QueryUsageResponse,
+        UsageBreakdown as UsageBreakdownProto, UsagePoint, UsageWindow as UsageWindowProto,
+    };
+    use meteroid_store::compute::clients::usage::UsageWindow;
+    use meteroid_store::domain::usage::{LineItemUsageDetail, UsageBreakdown, UsageSeries};
+
+    pub fn window_server_to_domain(window: UsageWindowProto) -> UsageWindow {
+        match window {
+            UsageWindowProto::Day => UsageWindow::Day,
+            UsageWindowProto::Hour => UsageWindow::Hour,
+        }
+    }
+
+    fn window_domain_to_server(window: UsageWindow) -> UsageWindowProto {
+        match window {
+            UsageWindow::Day => UsageWindowProto::Day,
+            UsageWindow::Hour => UsageWindowProto::Hour,
+        }
+    }
+
+    pub fn series_domain_to_server(series: UsageSeries) -> QueryUsageResponse {
+        QueryUsageResponse {
+            metric_id: series.metric_id.as_proto(),
+            window: window_domain_to_server(series.window).into(),
+            points: series
+                .points
+                .into_iter()
+                .map(|point| UsagePoint {
+                    window_start: Some(chrono_to_timestamp(point.window_start)),
+                    window_end: Some(chrono_to_timestamp(point.window_end)),
+                    value: point.value.as_proto(),
+                    dimensions: point.dimensions.into_iter().collect(),
+                })
+                .collect(),
+        }
+    }
+
+    fn breakdown_domain_to_server(breakdown: UsageBreakdown) -> UsageBreakdownProto {
+        UsageBreakdownProto {
+            window_start: breakdown.window_start.map(chrono_to_timestamp),
+            window_end: breakdown.window_end.map(chrono_to_timestamp),
+            dimensions: breakdown.dimensions.into_iter().collect(),
+            value: breakdown.value.as_proto(),
+            event_count: breakdown.event_count,
+        }
+    }
+
+    pub fn line_item_detail_domain_to_server(
+        detail: LineItemUsageDetail,
+    ) -> LineItemUsageDetailProto {
+        LineItemUsageDetailProto {
+            invoice_id: detail.invoice_id.as_proto(),
+            line_item_id: detail.line_item_id,
+            metric_id: detail.metric_id.as_proto(),
+            metric_code: detail.metric_code,
+            start_date: Some(chrono_to_proto(detail.period.start)),
+            end_date: Some(chrono_to_proto(detail.period.end)),
+            line_quantity: detail.line_quantity.as_proto(),
+            usage: detail.usage.as_proto(),
+            event_count: detail.event_count,
+            group_by: detail.group_by,
+            days: detail
+                .days
+                .into_iter()
+                .map(breakdown_domain_to_server)
+                .collect(),
+            dimensions: detail
+                .dimensions
+                .into_iter()
+                .map(breakdown_domain_to_server)
+                .collect(),
+        }
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::usage::v1::usage_service_server::UsageServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct UsageServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> UsageServiceServer<UsageServiceComponents> {
+    let inner = UsageServiceComponents { store };
+    UsageServiceServer::new(inner)
+}
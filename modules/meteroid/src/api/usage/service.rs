@@ -0,0 +1,86 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::usage::v1::{
+    usage_service_server::UsageService, GetLineItemUsageDetailRequest,
+    GetLineItemUsageDetailResponse, QueryUsageRequest, QueryUsageResponse,
+};
+use meteroid_store::domain::usage::UsageQuery;
+use meteroid_store::domain::Period;
+use meteroid_store::repositories::usage::UsageInterface;
+
+use crate::api::shared::mapping::date::chrono_from_proto;
+use crate::api::usage::error::UsageApiError;
+use crate::api::utils::parse_uuid;
+
+use super::{mapping, UsageServiceComponents};
+
+#[tonic::async_trait]
+impl UsageService for UsageServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn query_usage(
+        &self,
+        request: Request<QueryUsageRequest>,
+    ) -> Result<Response<QueryUsageResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let window = mapping::usage::window_server_to_domain(req.window());
+
+        let start = req
+            .start_date
+            .ok_or_else(|| UsageApiError::MissingArgument("start_date".to_string()))
+            .and_then(|d| {
+                chrono_from_proto(d)
+                    .ok_or_else(|| UsageApiError::InvalidArgument("start_date".to_string()))
+            })?;
+
+        let end = req
+            .end_date
+            .ok_or_else(|| UsageApiError::MissingArgument("end_date".to_string()))
+            .and_then(|d| {
+                chrono_from_proto(d)
+                    .ok_or_else(|| UsageApiError::InvalidArgument("end_date".to_string()))
+            })?;
+
+        let query = UsageQuery {
+            tenant_id,
+            customer_id: parse_uuid(&req.customer_id, "customer_id")?,
+            metric_id: parse_uuid(&req.metric_id, "metric_id")?,
+            period: Period { start, end },
+            group_by: req.group_by,
+            window,
+        };
+
+        let series = self
+            .store
+            .query_usage(query)
+            .await
+            .map_err(Into::<UsageApiError>::into)?;
+
+        Ok(Response::new(mapping::usage::series_domain_to_server(
+            series,
+        )))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_line_item_usage_detail(
+        &self,
+        request: Request<GetLineItemUsageDetailRequest>,
+    ) -> Result<Response<GetLineItemUsageDetailResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let invoice_id = parse_uuid(&req.invoice_id, "invoice_id")?;
+
+        let detail = self
+            .store
+            .get_line_item_usage_detail(tenant_id, invoice_id, &req.line_item_id, req.group_by)
+            .await
+            .map_err(Into::<UsageApiError>::into)?;
+
+        Ok(Response::new(GetLineItemUsageDetailResponse {
+            detail: Some(mapping::usage::line_item_detail_domain_to_server(detail)),
+        }))
+    }
+}
@@ -0,0 +1,244 @@
+pub mod alerts {
+    use crate::api::shared::conversions::{AsProtoOpt, FromProtoOpt, ProtoConv};
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use crate::api::usagealerts::error::UsageAlertApiError;
+    use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alert_threshold::Threshold;
+    use meteroid_grpc::meteroid::api::usagealerts::v1::{
+        CreateUsageAlertRequest, UsageAlert as UsageAlertProto, UsageAlertThreshold,
+    };
+    use meteroid_store::domain::usage_alerts::{
+        UsageAlert, UsageAlertNew, UsageAlertThreshold as DomainThreshold,
+    };
+    use rust_decimal::Decimal;
+    use tonic::Status;
+    use uuid::Uuid;
+
+    fn threshold_to_server(threshold: &DomainThreshold) -> UsageAlertThreshold {
+        let threshold = match threshold {
+            DomainThreshold::Absolute(value) => Threshold::Absolute(value.as_proto()),
+            DomainThreshold::CapacityPercent(percent) => {
+                Threshold::CapacityPercent(percent.as_proto())
+            }
+        };
+
+        UsageAlertThreshold {
+            threshold: Some(threshold),
+        }
+    }
+
+    fn threshold_from_server(
+        threshold: Option<UsageAlertThreshold>,
+    ) -> Result<DomainThreshold, Status> {
+        let threshold = threshold
+            .and_then(|t| t.threshold)
+            .ok_or(UsageAlertApiError::MissingArgument("threshold".to_string()))?;
+
+        match threshold {
+            Threshold::Absolute(value) => {
+                Ok(DomainThreshold::Absolute(Decimal::from_proto(value)?))
+            }
+            Threshold::CapacityPercent(percent) => Ok(DomainThreshold::CapacityPercent(
+                Decimal::from_proto(percent)?,
+            )),
+        }
+    }
+
+    pub fn domain_to_server(alert: UsageAlert) -> UsageAlertProto {
+        UsageAlertProto {
+            id: alert.id.as_proto(),
+            customer_id: alert.customer_id.as_proto(),
+            billable_metric_id: alert.billable_metric_id.as_proto(),
+            threshold: Some(threshold_to_server(&alert.threshold)),
+            hysteresis_percent: alert.hysteresis_percent.as_proto(),
+            notify_email: alert.notify_email,
+            triggered: alert.triggered,
+            last_value: alert.last_value.as_proto(),
+            last_triggered_at: alert.last_triggered_at.map(chrono_to_timestamp),
+            last_checked_at: alert.last_checked_at.map(chrono_to_timestamp),
+            created_at: Some(chrono_to_timestamp(alert.created_at)),
+        }
+    }
+
+    pub fn create_req_server_to_domain(
+        req: CreateUsageAlertRequest,
+        tenant_id: Uuid,
+        actor: Uuid,
+    ) -> Result<UsageAlertNew, Status> {
+        Ok(UsageAlertNew {
+            tenant_id,
+            customer_id: Uuid::from_proto(req.customer_id)?,
+            billable_metric_id: Uuid::from_proto(req.billable_metric_id)?,
+            threshold: threshold_from_server(req.threshold)?,
+            hysteresis_percent: Decimal::from_proto_opt(req.hysteresis_percent)?,
+            notify_email: req.notify_email.filter(|e| !e.trim().is_empty()),
+            created_by: actor,
+        })
+    }
+}
+
+pub mod rules {
+    use crate::api::shared::conversions::{AsProtoOpt, FromProtoOpt, ProtoConv};
+    use crate::api::shared::mapping::datetime::chrono_to_timestamp;
+    use crate::api::usagealerts::error::UsageAlertApiError;
+    use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alert_condition::Measure;
+    use meteroid_grpc::meteroid::api::usagealerts::v1::{
+        ComparisonOperator as ComparisonOperatorProto, CreateUsageAlertRuleRequest,
+        UsageAlertCondition as UsageAlertConditionProto, UsageAlertRule as UsageAlertRuleProto,
+        UsageAlertRuleDefinition as UsageAlertRuleDefinitionProto,
+        UsageAlertRuleEvent as UsageAlertRuleEventProto, UsageAlertRuleStatus,
+        UsageAlertRuleWindow,
+    };
+    use meteroid_store::domain::enums::{UsageAlertRuleStatusEnum, UsageAlertRuleWindowEnum};
+    use meteroid_store::domain::usage_alert_rules::{
+        ComparisonOperator, UsageAlertCondition, UsageAlertRule, UsageAlertRuleDefinition,
+        UsageAlertRuleEvent, UsageAlertRuleNew,
+    };
+    use rust_decimal::Decimal;
+    use tonic::Status;
+    use uuid::Uuid;
+
+    fn window_to_server(window: UsageAlertRuleWindowEnum) -> UsageAlertRuleWindow {
+        match window {
+            UsageAlertRuleWindowEnum::BillingPeriod => UsageAlertRuleWindow::BillingPeriod,
+            UsageAlertRuleWindowEnum::RollingDay => UsageAlertRuleWindow::RollingDay,
+            UsageAlertRuleWindowEnum::RollingWeek => UsageAlertRuleWindow::RollingWeek,
+            UsageAlertRuleWindowEnum::RollingMonth => UsageAlertRuleWindow::RollingMonth,
+        }
+    }
+
+    fn window_from_server(window: UsageAlertRuleWindow) -> UsageAlertRuleWindowEnum {
+        match window {
+            UsageAlertRuleWindow::BillingPeriod => UsageAlertRuleWindowEnum::BillingPeriod,
+            UsageAlertRuleWindow::RollingDay => UsageAlertRuleWindowEnum::RollingDay,
+            UsageAlertRuleWindow::RollingWeek => UsageAlertRuleWindowEnum::RollingWeek,
+            UsageAlertRuleWindow::RollingMonth => UsageAlertRuleWindowEnum::RollingMonth,
+        }
+    }
+
+    fn operator_to_server(operator: ComparisonOperator) -> ComparisonOperatorProto {
+        match operator {
+            ComparisonOperator::GreaterThan => ComparisonOperatorProto::GreaterThan,
+            ComparisonOperator::GreaterThanOrEqual => ComparisonOperatorProto::GreaterThanOrEqual,
+            ComparisonOperator::LessThan => ComparisonOperatorProto::LessThan,
+            ComparisonOperator::LessThanOrEqual => ComparisonOperatorProto::LessThanOrEqual,
+        }
+    }
+
+    fn operator_from_server(operator: ComparisonOperatorProto) -> ComparisonOperator {
+        match operator {
+            ComparisonOperatorProto::GreaterThan => ComparisonOperator::GreaterThan,
+            ComparisonOperatorProto::GreaterThanOrEqual => ComparisonOperator::GreaterThanOrEqual,
+            ComparisonOperatorProto::LessThan => ComparisonOperator::LessThan,
+            ComparisonOperatorProto::LessThanOrEqual => ComparisonOperator::LessThanOrEqual,
+        }
+    }
+
+    fn condition_to_server(condition: &UsageAlertCondition) -> UsageAlertConditionProto {
+        let (measure, operator, value) = match condition {
+            UsageAlertCondition::Usage { operator, value } => (Measure::Usage, operator, value),
+            UsageAlertCondition::GrowthPercent { operator, value } => {
+                (Measure::GrowthPercent, operator, value)
+            }
+        };
+
+        UsageAlertConditionProto {
+            measure: measure.into(),
+            operator: operator_to_server(*operator).into(),
+            value: value.as_proto(),
+        }
+    }
+
+    fn condition_from_server(
+        condition: UsageAlertConditionProto,
+    ) -> Result<UsageAlertCondition, Status> {
+        let measure = condition.measure();
+        let operator = operator_from_server(condition.operator());
+        let value = Decimal::from_proto(condition.value)?;
+
+        Ok(match measure {
+            Measure::Usage => UsageAlertCondition::Usage { operator, value },
+            Measure::GrowthPercent => UsageAlertCondition::GrowthPercent { operator, value },
+        })
+    }
+
+    fn definition_to_server(definition: UsageAlertRuleDefinition) -> UsageAlertRuleDefinitionProto {
+        UsageAlertRuleDefinitionProto {
+            name: definition.name,
+            window: window_to_server(definition.window).into(),
+            match_all: definition.match_all,
+            conditions: definition
+                .conditions
+                .iter()
+                .map(condition_to_server)
+                .collect(),
+            notify_webhook: definition.notify_webhook,
+            notify_email: definition.notify_email,
+        }
+    }
+
+    pub fn definition_from_server(
+        definition: Option<UsageAlertRuleDefinitionProto>,
+    ) -> Result<UsageAlertRuleDefinition, Status> {
+        let definition = definition.ok_or(UsageAlertApiError::MissingArgument(
+            "definition".to_string(),
+        ))?;
+
+        Ok(UsageAlertRuleDefinition {
+            window: window_from_server(definition.window()),
+            name: definition.name,
+            match_all: definition.match_all,
+            conditions: definition
+                .conditions
+                .into_iter()
+                .map(condition_from_server)
+                .collect::<Result<Vec<_>, _>>()?,
+            notify_webhook: definition.notify_webhook,
+            notify_email: definition.notify_email.filter(|e| !e.trim().is_empty()),
+        })
+    }
+
+    pub fn domain_to_server(rule: UsageAlertRule) -> UsageAlertRuleProto {
+        UsageAlertRuleProto {
+            id: rule.id.as_proto(),
+            billable_metric_id: rule.billable_metric_id.as_proto(),
+            customer_id: rule.customer_id.as_proto(),
+            definition: Some(definition_to_server(rule.definition)),
+            last_evaluated_at: rule.last_evaluated_at.map(chrono_to_timestamp),
+            created_at: Some(chrono_to_timestamp(rule.created_at)),
+            updated_at: rule.updated_at.map(chrono_to_timestamp),
+        }
+    }
+
+    pub fn event_to_server(event: UsageAlertRuleEvent) -> UsageAlertRuleEventProto {
+        let status = match event.status {
+            UsageAlertRuleStatusEnum::Firing => UsageAlertRuleStatus::Firing,
+            UsageAlertRuleStatusEnum::Resolved => UsageAlertRuleStatus::Resolved,
+        };
+
+        UsageAlertRuleEventProto {
+            id: event.id.as_proto(),
+            rule_id: event.rule_id.as_proto(),
+            customer_id: event.customer_id.as_proto(),
+            status: status.into(),
+            usage: event.usage.as_proto(),
+            growth_percent: event.growth_percent.as_proto(),
+            window_start: event.window.start.as_proto(),
+            window_end: event.window.end.as_proto(),
+            created_at: Some(chrono_to_timestamp(event.created_at)),
+        }
+    }
+
+    pub fn create_req_server_to_domain(
+        req: CreateUsageAlertRuleRequest,
+        tenant_id: Uuid,
+        actor: Uuid,
+    ) -> Result<UsageAlertRuleNew, Status> {
+        Ok(UsageAlertRuleNew {
+            tenant_id,
+            billable_metric_id: Uuid::from_proto(req.billable_metric_id)?,
+            customer_id: Uuid::from_proto_opt(req.customer_id)?,
+            definition: definition_from_server(req.definition)?,
+            created_by: actor,
+        })
+    }
+}
@@ -0,0 +1,15 @@
+use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alerts_service_server::UsageAlertsServiceServer;
+use meteroid_store::Store;
+
+pub(crate) mod error;
+mod mapping;
+mod service;
+
+pub struct UsageAlertsServiceComponents {
+    pub store: Store,
+}
+
+pub fn service(store: Store) -> UsageAlertsServiceServer<UsageAlertsServiceComponents> {
+    let inner = UsageAlertsServiceComponents { store };
+    UsageAlertsServiceServer::new(inner)
+}
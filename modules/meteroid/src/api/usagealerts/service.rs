@@ -0,0 +1,226 @@
+use tonic::{Request, Response, Status};
+
+use common_grpc::middleware::server::auth::RequestExt;
+use meteroid_grpc::meteroid::api::usagealerts::v1::{
+    usage_alerts_service_server::UsageAlertsService, CreateUsageAlertRequest,
+    CreateUsageAlertResponse, CreateUsageAlertRuleRequest, CreateUsageAlertRuleResponse,
+    DeleteUsageAlertRequest, DeleteUsageAlertResponse, DeleteUsageAlertRuleRequest,
+    DeleteUsageAlertRuleResponse, GetUsageAlertRuleRequest, GetUsageAlertRuleResponse,
+    ListUsageAlertRuleEventsRequest, ListUsageAlertRuleEventsResponse, ListUsageAlertRulesRequest,
+    ListUsageAlertRulesResponse, ListUsageAlertsRequest, ListUsageAlertsResponse,
+    UpdateUsageAlertRuleRequest, UpdateUsageAlertRuleResponse,
+};
+use meteroid_store::repositories::usage_alert_rules::UsageAlertRuleInterface;
+use meteroid_store::repositories::usage_alerts::UsageAlertInterface;
+
+use crate::api::usagealerts::error::UsageAlertApiError;
+use crate::api::utils::{parse_uuid, parse_uuid_opt};
+
+use super::{mapping, UsageAlertsServiceComponents};
+
+#[tonic::async_trait]
+impl UsageAlertsService for UsageAlertsServiceComponents {
+    #[tracing::instrument(skip_all)]
+    async fn create_usage_alert(
+        &self,
+        request: Request<CreateUsageAlertRequest>,
+    ) -> Result<Response<CreateUsageAlertResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let alert = mapping::alerts::create_req_server_to_domain(req, tenant_id, actor)?;
+
+        let alert = self
+            .store
+            .insert_usage_alert(alert)
+            .await
+            .map(mapping::alerts::domain_to_server)
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        Ok(Response::new(CreateUsageAlertResponse {
+            alert: Some(alert),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_usage_alerts(
+        &self,
+        request: Request<ListUsageAlertsRequest>,
+    ) -> Result<Response<ListUsageAlertsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let customer_id = parse_uuid_opt(&req.customer_id, "customer_id")?;
+
+        let alerts = self
+            .store
+            .list_usage_alerts(tenant_id, customer_id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?
+            .into_iter()
+            .map(mapping::alerts::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListUsageAlertsResponse { alerts }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_usage_alert(
+        &self,
+        request: Request<DeleteUsageAlertRequest>,
+    ) -> Result<Response<DeleteUsageAlertResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .archive_usage_alert(tenant_id, id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        Ok(Response::new(DeleteUsageAlertResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn create_usage_alert_rule(
+        &self,
+        request: Request<CreateUsageAlertRuleRequest>,
+    ) -> Result<Response<CreateUsageAlertRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let actor = request.actor()?;
+        let req = request.into_inner();
+
+        let rule = mapping::rules::create_req_server_to_domain(req, tenant_id, actor)?;
+
+        let rule = self
+            .store
+            .insert_usage_alert_rule(rule)
+            .await
+            .map(mapping::rules::domain_to_server)
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        Ok(Response::new(CreateUsageAlertRuleResponse {
+            rule: Some(rule),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn update_usage_alert_rule(
+        &self,
+        request: Request<UpdateUsageAlertRuleRequest>,
+    ) -> Result<Response<UpdateUsageAlertRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+        let definition = mapping::rules::definition_from_server(req.definition)?;
+
+        let rule = self
+            .store
+            .update_usage_alert_rule(tenant_id, id, definition)
+            .await
+            .map(mapping::rules::domain_to_server)
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        Ok(Response::new(UpdateUsageAlertRuleResponse {
+            rule: Some(rule),
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_usage_alert_rules(
+        &self,
+        request: Request<ListUsageAlertRulesRequest>,
+    ) -> Result<Response<ListUsageAlertRulesResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let customer_id = parse_uuid_opt(&req.customer_id, "customer_id")?;
+
+        let rules = self
+            .store
+            .list_usage_alert_rules(tenant_id, customer_id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?
+            .into_iter()
+            .map(mapping::rules::domain_to_server)
+            .collect();
+
+        Ok(Response::new(ListUsageAlertRulesResponse { rules }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn get_usage_alert_rule(
+        &self,
+        request: Request<GetUsageAlertRuleRequest>,
+    ) -> Result<Response<GetUsageAlertRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        let rule = self
+            .store
+            .find_usage_alert_rule_by_id(tenant_id, id)
+            .await
+            .map(mapping::rules::domain_to_server)
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        let states = self
+            .store
+            .list_usage_alert_rule_states(tenant_id, id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?
+            .into_iter()
+            .map(mapping::rules::event_to_server)
+            .collect();
+
+        Ok(Response::new(GetUsageAlertRuleResponse {
+            rule: Some(rule),
+            states,
+        }))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn delete_usage_alert_rule(
+        &self,
+        request: Request<DeleteUsageAlertRuleRequest>,
+    ) -> Result<Response<DeleteUsageAlertRuleResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let id = parse_uuid(&req.id, "id")?;
+
+        self.store
+            .archive_usage_alert_rule(tenant_id, id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?;
+
+        Ok(Response::new(DeleteUsageAlertRuleResponse {}))
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn list_usage_alert_rule_events(
+        &self,
+        request: Request<ListUsageAlertRuleEventsRequest>,
+    ) -> Result<Response<ListUsageAlertRuleEventsResponse>, Status> {
+        let tenant_id = request.tenant()?;
+        let req = request.into_inner();
+
+        let rule_id = parse_uuid(&req.rule_id, "rule_id")?;
+        let customer_id = parse_uuid_opt(&req.customer_id, "customer_id")?;
+
+        let events = self
+            .store
+            .list_usage_alert_rule_events(tenant_id, rule_id, customer_id)
+            .await
+            .map_err(Into::<UsageAlertApiError>::into)?
+            .into_iter()
+            .map(mapping::rules::event_to_server)
+            .collect();
+
+        Ok(Response::new(ListUsageAlertRuleEventsResponse { events }))
+    }
+}
@@ -1,7 +1,7 @@
 use meteroid_grpc::meteroid::api::users::v1::users_service_server::UsersServiceServer;
 use meteroid_store::Store;
 
-mod error;
+pub(crate) mod error;
 pub mod mapping;
 mod service;
 
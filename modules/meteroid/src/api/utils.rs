@@ -1,4 +1,6 @@
-use tonic::Status;
+use tonic::{Response, Status};
+
+use meteroid_store::domain::audit_logs::AuditEntity;
 
 use common_grpc::meteroid::common::v1::{Pagination, PaginationResponse};
 
@@ -21,6 +23,13 @@ macro_rules! parse_uuid {
     };
 }
 
+/// The response of a mutating call, with the entity it changed for the audit log
+pub fn audited<T>(message: T, entity: AuditEntity) -> Response<T> {
+    let mut response = Response::new(message);
+    response.extensions_mut().insert(entity);
+    response
+}
+
 // let's do a parse_uuid_opt
 pub fn parse_uuid_opt(
     uuid: &Option<String>,
@@ -3,10 +3,13 @@ pub mod endpoint {
     use crate::api::webhooksout::error::WebhookApiError;
     use crate::api::webhooksout::mapping::event_type;
     use meteroid_grpc::meteroid::api::webhooks::out::v1::{
-        CreateWebhookEndpointRequest, WebhookEndpoint as WebhookEndpointProto,
+        CreateWebhookEndpointRequest, WebhookBatching as WebhookBatchingProto,
+        WebhookEndpoint as WebhookEndpointProto,
     };
     use meteroid_store::domain::enums::WebhookOutEventTypeEnum;
-    use meteroid_store::domain::webhooks::{WebhookOutEndpoint, WebhookOutEndpointNew};
+    use meteroid_store::domain::webhooks::{
+        WebhookOutBatching, WebhookOutEndpoint, WebhookOutEndpointNew,
+    };
     use secrecy::ExposeSecret;
     use uuid::Uuid;
 
@@ -23,6 +26,10 @@ pub mod endpoint {
                 .collect(),
             enabled: endpoint.enabled,
             created_at: Some(chrono_to_timestamp(endpoint.created_at)),
+            batching: endpoint.batching.map(|b| WebhookBatchingProto {
+                max_events: b.max_events,
+                max_wait_seconds: b.max_wait_seconds,
+            }),
         }
     }
 
@@ -44,6 +51,10 @@ pub mod endpoint {
             description: req.description,
             events_to_listen,
             enabled: true,
+            batching: req.batching.map(|b| WebhookOutBatching {
+                max_events: b.max_events,
+                max_wait_seconds: b.max_wait_seconds,
+            }),
         })
     }
 }
@@ -60,6 +71,36 @@ pub mod event_type {
             }
             WebhookEventTypeProto::InvoiceCreated => WebhookOutEventTypeEnum::InvoiceCreated,
             WebhookEventTypeProto::InvoiceFinalized => WebhookOutEventTypeEnum::InvoiceFinalized,
+            WebhookEventTypeProto::UsageAlertTriggered => {
+                WebhookOutEventTypeEnum::UsageAlertTriggered
+            }
+            WebhookEventTypeProto::InvoiceStuck => WebhookOutEventTypeEnum::InvoiceStuck,
+            WebhookEventTypeProto::InvoicePaymentReminder => {
+                WebhookOutEventTypeEnum::InvoicePaymentReminder
+            }
+            WebhookEventTypeProto::CustomerBillingEmailUpdated => {
+                WebhookOutEventTypeEnum::CustomerBillingEmailUpdated
+            }
+            WebhookEventTypeProto::UsageCapReached => WebhookOutEventTypeEnum::UsageCapReached,
+            WebhookEventTypeProto::CreditLimitExceeded => {
+                WebhookOutEventTypeEnum::CreditLimitExceeded
+            }
+            WebhookEventTypeProto::UsageAlertRuleFiring => {
+                WebhookOutEventTypeEnum::UsageAlertRuleFiring
+            }
+            WebhookEventTypeProto::UsageAlertRuleResolved => {
+                WebhookOutEventTypeEnum::UsageAlertRuleResolved
+            }
+            WebhookEventTypeProto::SeatIncreaseRequested => {
+                WebhookOutEventTypeEnum::SeatIncreaseRequested
+            }
+            WebhookEventTypeProto::InvoiceDeliveryRequested => {
+                WebhookOutEventTypeEnum::InvoiceDeliveryRequested
+            }
+            WebhookEventTypeProto::InvoicePaid => WebhookOutEventTypeEnum::InvoicePaid,
+            WebhookEventTypeProto::InvoicePaymentRecorded => {
+                WebhookOutEventTypeEnum::InvoicePaymentRecorded
+            }
         }
     }
 
@@ -71,6 +112,36 @@ pub mod event_type {
             }
             WebhookOutEventTypeEnum::InvoiceCreated => WebhookEventTypeProto::InvoiceCreated,
             WebhookOutEventTypeEnum::InvoiceFinalized => WebhookEventTypeProto::InvoiceFinalized,
+            WebhookOutEventTypeEnum::UsageAlertTriggered => {
+                WebhookEventTypeProto::UsageAlertTriggered
+            }
+            WebhookOutEventTypeEnum::InvoiceStuck => WebhookEventTypeProto::InvoiceStuck,
+            WebhookOutEventTypeEnum::InvoicePaymentReminder => {
+                WebhookEventTypeProto::InvoicePaymentReminder
+            }
+            WebhookOutEventTypeEnum::CustomerBillingEmailUpdated => {
+                WebhookEventTypeProto::CustomerBillingEmailUpdated
+            }
+            WebhookOutEventTypeEnum::UsageCapReached => WebhookEventTypeProto::UsageCapReached,
+            WebhookOutEventTypeEnum::CreditLimitExceeded => {
+                WebhookEventTypeProto::CreditLimitExceeded
+            }
+            WebhookOutEventTypeEnum::UsageAlertRuleFiring => {
+                WebhookEventTypeProto::UsageAlertRuleFiring
+            }
+            WebhookOutEventTypeEnum::UsageAlertRuleResolved => {
+                WebhookEventTypeProto::UsageAlertRuleResolved
+            }
+            WebhookOutEventTypeEnum::SeatIncreaseRequested => {
+                WebhookEventTypeProto::SeatIncreaseRequested
+            }
+            WebhookOutEventTypeEnum::InvoiceDeliveryRequested => {
+                WebhookEventTypeProto::InvoiceDeliveryRequested
+            }
+            WebhookOutEventTypeEnum::InvoicePaid => WebhookEventTypeProto::InvoicePaid,
+            WebhookOutEventTypeEnum::InvoicePaymentRecorded => {
+                WebhookEventTypeProto::InvoicePaymentRecorded
+            }
         }
     }
 }
@@ -90,6 +161,7 @@ pub mod event {
             request_body: event.request_body.clone(),
             response_body: event.response_body.clone(),
             error_message: event.error_message.clone(),
+            batch_id: event.batch_id.map(|id| id.to_string()),
         }
     }
 }
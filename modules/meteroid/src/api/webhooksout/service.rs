@@ -8,11 +8,12 @@ use meteroid_grpc::meteroid::api::webhooks::out::v1::{
     ListWebhookEndpointsResponse, ListWebhookEventsRequest, ListWebhookEventsResponse,
 };
 use meteroid_store::domain;
+use meteroid_store::domain::audit_logs::AuditEntity;
 use meteroid_store::domain::OrderByRequest;
 use meteroid_store::repositories::webhooks::WebhooksInterface;
 
-use crate::api::utils::parse_uuid;
 use crate::api::utils::PaginationExt;
+use crate::api::utils::{audited, parse_uuid};
 use crate::api::webhooksout::error::WebhookApiError;
 use crate::api::webhooksout::mapping::{endpoint, event};
 use crate::api::webhooksout::WebhooksServiceComponents;
@@ -37,9 +38,14 @@ impl WebhooksService for WebhooksServiceComponents {
             .map(endpoint::to_proto)
             .map_err(Into::<WebhookApiError>::into)?;
 
-        Ok(Response::new(CreateWebhookEndpointResponse {
-            endpoint: Some(endpoint),
-        }))
+        let entity = AuditEntity::new(&endpoint.id);
+
+        Ok(audited(
+            CreateWebhookEndpointResponse {
+                endpoint: Some(endpoint),
+            },
+            entity,
+        ))
     }
 
     #[tracing::instrument(skip_all)]
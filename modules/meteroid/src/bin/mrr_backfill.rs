@@ -0,0 +1,61 @@
+/*
+
+Rebuilds the MRR movement logs of a tenant from its subscription events.
+Usage: mrr_backfill <tenant_id>
+
+*/
+
+use std::collections::HashMap;
+use std::env;
+use std::sync::Arc;
+
+use common_logging::init::init_regular_logging;
+use error_stack::ResultExt;
+use meteroid::eventbus::create_eventbus_noop;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::errors::StoreError;
+use meteroid_store::repositories::stats::StatsInterface;
+use meteroid_store::Store;
+use secrecy::SecretString;
+
+#[tokio::main]
+async fn main() -> error_stack::Result<(), StoreError> {
+    dotenvy::dotenv().ok();
+
+    init_regular_logging();
+
+    let tenant_id = env::args()
+        .nth(1)
+        .ok_or(StoreError::InvalidArgument(
+            "Usage: mrr_backfill <tenant_id>".to_string(),
+        ))
+        .map(|s| uuid::Uuid::parse_str(&s))?
+        .change_context(StoreError::InvalidArgument("Invalid tenant id".to_string()))?;
+
+    let store = Store::new(
+        env::var("DATABASE_URL").change_context(StoreError::InitializationError)?,
+        env::var("SECRETS_CRYPT_KEY")
+            .map(SecretString::new)
+            .change_context(StoreError::InitializationError)?,
+        env::var("JWT_SECRET")
+            .map(SecretString::new)
+            .change_context(StoreError::InitializationError)?,
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient {
+            data: HashMap::new(),
+        }),
+    )?;
+
+    log::info!("Rebuilding MRR movement logs of tenant {}", tenant_id);
+
+    let summary = store.rebuild_mrr_movement_logs(tenant_id).await?;
+
+    log::info!(
+        "Processed {} invoices, recorded {} MRR movements",
+        summary.invoices_processed,
+        summary.movements_recorded
+    );
+
+    Ok(())
+}
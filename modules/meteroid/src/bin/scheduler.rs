@@ -41,7 +41,30 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             // (Box::new(PriceWorker), LockKey::InvoicingPrice),
             // (Box::new(FinalizeWorker), LockKey::InvoicingFinalize),
             // (Box::new(IssueWorker), LockKey::InvoicingIssue),
+            // (
+            //     Box::new(UsageThresholdWorker),
+            //     LockKey::InvoicingUsageThreshold,
+            // ),
+            // (Box::new(WatchdogWorker), LockKey::InvoicingWatchdog),
+            // (
+            //     Box::new(BillingRunSummaryWorker),
+            //     LockKey::InvoicingBillingRunSummary,
+            // ),
+            // (
+            //     Box::new(PaymentRemindersWorker),
+            //     LockKey::InvoicingPaymentReminders,
+            // ),
+            // (
+            //     Box::new(ApprovalEscalationWorker),
+            //     LockKey::InvoicingApprovalEscalation,
+            // ),
             // (Box::new(CurrencyRatesWorker), LockKey::CurrencyRates),
+            // (Box::new(UsageAlertsWorker), LockKey::UsageAlerts),
+            // (Box::new(UsageAlertRulesWorker), LockKey::UsageAlertRules),
+            // (Box::new(UsageCapsWorker), LockKey::UsageCaps),
+            // (Box::new(TrialsWorker), LockKey::SubscriptionTrials),
+            // (Box::new(ApiTokenExpiryWorker), LockKey::ApiTokenExpiry),
+            // (Box::new(AccountingExportWorker), LockKey::AccountingExport),
         ],
         config,
         pool,
@@ -4,8 +4,11 @@ use tokio::signal;
 use common_build_info::BuildInfo;
 use common_grpc::middleware::client::build_layered_client_service;
 use common_logging::init::init_telemetry;
+use metering_grpc::meteroid::metering::v1::events_service_client::EventsServiceClient;
 use metering_grpc::meteroid::metering::v1::meters_service_client::MetersServiceClient;
 use metering_grpc::meteroid::metering::v1::usage_query_service_client::UsageQueryServiceClient;
+use meteroid::adapters::adyen::Adyen;
+use meteroid::adapters::gocardless::GoCardless;
 use meteroid::adapters::stripe::Stripe;
 use meteroid::clients::usage::MeteringUsageClient;
 use meteroid::config::Config;
@@ -32,10 +35,12 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         .expect("Invalid metering_endpoint")
         .connect_lazy();
     let metering_layered_channel =
-        build_layered_client_service(metering_channel, &config.internal_auth);
+        build_layered_client_service(metering_channel.clone(), &config.internal_auth);
 
     let query_service_client = UsageQueryServiceClient::new(metering_layered_channel.clone());
     let metering_service = MetersServiceClient::new(metering_layered_channel);
+    // ingestion is authenticated with the api key of the caller, not the internal auth
+    let events_client = EventsServiceClient::new(metering_channel);
 
     // this creates a new pool, as it is incompatible with the one for cornucopia.
     let store = meteroid_store::Store::new(
@@ -71,14 +76,22 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
         client: stripe_client::client::StripeClient::new(),
     });
 
+    let adyen_adapter = Arc::new(Adyen::new());
+
+    let gocardless_adapter = Arc::new(GoCardless::new());
+
     tokio::select! {
         _ = private_server => {},
         _ = meteroid::api::axum_server::serve(
             config.rest_api_addr,
             object_store_service.clone(),
             stripe_adapter.clone(),
+            adyen_adapter.clone(),
+            gocardless_adapter.clone(),
             store.clone(),
             config.jwt_secret.clone(),
+            config.email_events_webhook_secret.clone(),
+            events_client,
         ) => {},
         _ = exit => {
               log::info!("Interrupted");
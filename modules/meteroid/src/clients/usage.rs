@@ -1,16 +1,20 @@
-use chrono::{NaiveDate, Timelike};
+use chrono::{NaiveDate, NaiveDateTime, Timelike};
+use itertools::Itertools;
 use rust_decimal::Decimal;
 use tonic::Request;
 use uuid::Uuid;
 
 use crate::api::billablemetrics::mapping;
+use crate::api::shared::mapping::datetime::chrono_to_timestamp;
 use common_grpc::middleware::client::LayeredClientService;
 use metering_grpc::meteroid::metering::v1::meter::AggregationType;
 use metering_grpc::meteroid::metering::v1::meters_service_client::MetersServiceClient;
 use metering_grpc::meteroid::metering::v1::query_meter_request::QueryWindowSize;
 use metering_grpc::meteroid::metering::v1::usage_query_service_client::UsageQueryServiceClient;
 use metering_grpc::meteroid::metering::v1::{
-    Filter, QueryMeterRequest, QueryMeterResponse, RegisterMeterRequest, ResourceIdentifier,
+    CheckMeterViewsRequest, Filter, GetRetentionPolicyRequest, PurgeTenantEventsRequest,
+    QueryMeterRequest, QueryMeterResponse, RegisterMeterRequest, ResourceIdentifier,
+    RestoreArchivedEventsRequest, RetentionPolicy, SetRetentionPolicyRequest,
 };
 use meteroid_store::compute::clients::usage::*;
 use meteroid_store::compute::ComputeError;
@@ -33,116 +37,26 @@ impl MeteringUsageClient {
             meters_grpc_client,
         }
     }
-}
-
-#[async_trait::async_trait]
-impl UsageClient for MeteringUsageClient {
-    async fn register_meter(
-        &self,
-        tenant_id: &Uuid,
-        metric: &BillableMetric,
-    ) -> Result<Vec<Metadata>, ComputeError> {
-        let metering_meter = mapping::metric::domain_to_metering(metric.clone());
-
-        let response = self
-            .meters_grpc_client
-            .clone()
-            .register_meter(Request::new(RegisterMeterRequest {
-                meter: Some(metering_meter),
-                tenant_id: tenant_id.to_string(),
-            }))
-            // TODO add in db/response the register , error and allow retrying
-            .await
-            .map(|r| r.into_inner())
-            .map_err(|status| {
-                log::error!("Failed to register meter: {:?}", status);
-                ComputeError::MeteringGrpcError
-            })?;
-
-        let metadata = response
-            .metadata
-            .into_iter()
-            .map(|m| Metadata {
-                key: m.key,
-                value: m.value,
-            })
-            .collect::<Vec<Metadata>>();
-
-        Ok(metadata)
-    }
 
-    async fn fetch_usage(
+    /// The usage of the customer aggregated over the period, grouped by the customer and the given event properties
+    async fn query_usage(
         &self,
         tenant_id: &Uuid,
         customer_id: &Uuid,
         customer_external_id: &Option<String>,
         metric: &BillableMetric,
         period: Period,
+        group_by: Vec<String>,
     ) -> Result<UsageData, ComputeError> {
         if period.start >= period.end {
             return Err(ComputeError::InvalidPeriod);
         }
 
-        let aggregation_type = match metric.aggregation_type {
-            domain::enums::BillingMetricAggregateEnum::Count => AggregationType::Count,
-            domain::enums::BillingMetricAggregateEnum::Latest => AggregationType::Latest,
-            domain::enums::BillingMetricAggregateEnum::Max => AggregationType::Max,
-            domain::enums::BillingMetricAggregateEnum::Min => AggregationType::Min,
-            domain::enums::BillingMetricAggregateEnum::Mean => AggregationType::Mean,
-            domain::enums::BillingMetricAggregateEnum::Sum => AggregationType::Sum,
-            domain::enums::BillingMetricAggregateEnum::CountDistinct => {
-                AggregationType::CountDistinct
-            }
-        } as i32;
-
-        let filter_properties = match metric.segmentation_matrix.clone() {
-            Some(domain::SegmentationMatrix::Single(domain::Dimension { key, values })) => {
-                vec![Filter {
-                    property_name: key,
-                    property_value: values,
-                }]
-            }
-            Some(domain::SegmentationMatrix::Double {
-                dimension1,
-                dimension2,
-            }) => {
-                vec![
-                    Filter {
-                        property_name: dimension1.key,
-                        property_value: dimension1.values,
-                    },
-                    Filter {
-                        property_name: dimension2.key,
-                        property_value: dimension2.values,
-                    },
-                ]
-            }
-            Some(domain::SegmentationMatrix::Linked {
-                dimension1_key,
-                dimension2_key,
-                values,
-            }) => {
-                let mut filter_properties = vec![];
-                for (key, values) in values.iter() {
-                    filter_properties.push(Filter {
-                        property_name: dimension1_key.clone(),
-                        property_value: vec![key.clone()],
-                    });
-                    filter_properties.push(Filter {
-                        property_name: dimension2_key.clone(),
-                        property_value: values.clone(),
-                    });
-                }
-                filter_properties
-            }
-            None => vec![],
-        };
-
         let request = QueryMeterRequest {
             tenant_id: tenant_id.to_string(),
             meter_slug: metric.id.to_string(),
             event_name: metric.code.clone(),
-            meter_aggregation_type: aggregation_type,
+            meter_aggregation_type: aggregation_type(metric) as i32,
             customers: vec![ResourceIdentifier {
                 meteroid_id: customer_id.to_string(),
                 external_id: customer_external_id
@@ -151,10 +65,9 @@ impl UsageClient for MeteringUsageClient {
             }],
             from: Some(date_to_timestamp(period.start)),
             to: Some(date_to_timestamp(period.end)), // exclusive TODO check
-            // not used here, defaults to customer_id
-            group_by_properties: vec![],
+            group_by_properties: group_by,
             // the segmentation dimensions TODO
-            filter_properties,
+            filter_properties: segmentation_filters(metric),
             window_size: QueryWindowSize::AggregateAll.into(),
             timezone: None,
         };
@@ -191,6 +104,401 @@ impl UsageClient for MeteringUsageClient {
 
         Ok(UsageData { data, period })
     }
+
+    /// The usage of the customer per window over the period, aggregated as given
+    #[allow(clippy::too_many_arguments)]
+    async fn query_usage_series(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+        window: UsageWindow,
+        aggregation: AggregationType,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError> {
+        if period.start >= period.end {
+            return Err(ComputeError::InvalidPeriod);
+        }
+
+        let request = QueryMeterRequest {
+            tenant_id: tenant_id.to_string(),
+            meter_slug: metric.id.to_string(),
+            event_name: metric.code.clone(),
+            meter_aggregation_type: aggregation as i32,
+            customers: vec![ResourceIdentifier {
+                meteroid_id: customer_id.to_string(),
+                external_id: customer_external_id
+                    .clone()
+                    .unwrap_or(customer_id.to_string()),
+            }],
+            from: Some(date_to_timestamp(period.start)),
+            to: Some(date_to_timestamp(period.end)),
+            group_by_properties: group_by,
+            filter_properties: segmentation_filters(metric),
+            window_size: match window {
+                UsageWindow::Hour => QueryWindowSize::Hour,
+                UsageWindow::Day => QueryWindowSize::Day,
+            }
+            .into(),
+            timezone: None,
+        };
+
+        let response: QueryMeterResponse = self
+            .usage_grpc_client
+            .clone()
+            .query_meter(request)
+            .await
+            .map_err(|status| {
+                log::error!("Failed to query meter series: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?
+            .into_inner();
+
+        let points = response
+            .usage
+            .into_iter()
+            .filter_map(|usage| {
+                let window_start = usage.window_start.and_then(timestamp_to_datetime)?;
+                let window_end = usage.window_end.and_then(timestamp_to_datetime)?;
+
+                Some(UsageSeriesPoint {
+                    window_start,
+                    window_end,
+                    value: usage
+                        .value
+                        .and_then(|v| v.try_into().ok())
+                        .unwrap_or(Decimal::ZERO),
+                    dimensions: usage
+                        .dimensions
+                        .into_iter()
+                        .map(|(k, v)| (k, v.value.unwrap_or_default()))
+                        .collect(),
+                })
+            })
+            .sorted_by_key(|p| p.window_start)
+            .collect();
+
+        Ok(points)
+    }
+}
+
+#[async_trait::async_trait]
+impl UsageClient for MeteringUsageClient {
+    async fn register_meter(
+        &self,
+        tenant_id: &Uuid,
+        metric: &BillableMetric,
+    ) -> Result<Vec<Metadata>, ComputeError> {
+        let metering_meter = mapping::metric::domain_to_metering(metric.clone());
+
+        let response = self
+            .meters_grpc_client
+            .clone()
+            .register_meter(Request::new(RegisterMeterRequest {
+                meter: Some(metering_meter),
+                tenant_id: tenant_id.to_string(),
+            }))
+            // TODO add in db/response the register , error and allow retrying
+            .await
+            .map(|r| r.into_inner())
+            .map_err(|status| {
+                log::error!("Failed to register meter: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?;
+
+        let metadata = response
+            .metadata
+            .into_iter()
+            .map(|m| Metadata {
+                key: m.key,
+                value: m.value,
+            })
+            .collect::<Vec<Metadata>>();
+
+        Ok(metadata)
+    }
+
+    async fn fetch_usage(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+    ) -> Result<UsageData, ComputeError> {
+        self.query_usage(
+            tenant_id,
+            customer_id,
+            customer_external_id,
+            metric,
+            period,
+            // not used here, defaults to customer_id
+            vec![],
+        )
+        .await
+    }
+
+    async fn fetch_grouped_usage(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+    ) -> Result<UsageData, ComputeError> {
+        self.query_usage(
+            tenant_id,
+            customer_id,
+            customer_external_id,
+            metric,
+            period,
+            group_by,
+        )
+        .await
+    }
+
+    async fn fetch_usage_series(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+        window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError> {
+        self.query_usage_series(
+            tenant_id,
+            customer_id,
+            customer_external_id,
+            metric,
+            period,
+            group_by,
+            window,
+            aggregation_type(metric),
+        )
+        .await
+    }
+
+    async fn fetch_event_count_series(
+        &self,
+        tenant_id: &Uuid,
+        customer_id: &Uuid,
+        customer_external_id: &Option<String>,
+        metric: &BillableMetric,
+        period: Period,
+        group_by: Vec<String>,
+        window: UsageWindow,
+    ) -> Result<Vec<UsageSeriesPoint>, ComputeError> {
+        self.query_usage_series(
+            tenant_id,
+            customer_id,
+            customer_external_id,
+            metric,
+            period,
+            group_by,
+            window,
+            AggregationType::Count,
+        )
+        .await
+    }
+
+    async fn purge_tenant_events(
+        &self,
+        tenant_id: &Uuid,
+        metric_ids: &[Uuid],
+    ) -> Result<(), ComputeError> {
+        self.meters_grpc_client
+            .clone()
+            .purge_tenant_events(Request::new(PurgeTenantEventsRequest {
+                tenant_id: tenant_id.to_string(),
+                // the meters are registered with the metric id as slug
+                meter_slugs: metric_ids.iter().map(|id| id.to_string()).collect(),
+            }))
+            .await
+            .map_err(|status| {
+                log::error!("Failed to purge tenant events: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?;
+
+        Ok(())
+    }
+
+    async fn check_meter_views(
+        &self,
+        tenant_id: &Uuid,
+        metric_ids: &[Uuid],
+    ) -> Result<MeterViewsDrift, ComputeError> {
+        let response = self
+            .meters_grpc_client
+            .clone()
+            .check_meter_views(Request::new(CheckMeterViewsRequest {
+                tenant_id: tenant_id.to_string(),
+                meter_slugs: metric_ids.iter().map(|id| id.to_string()).collect(),
+            }))
+            .await
+            .map_err(|status| {
+                log::error!("Failed to check meter views: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?
+            .into_inner();
+
+        Ok(MeterViewsDrift {
+            missing_metric_ids: response
+                .missing_meter_slugs
+                .iter()
+                .filter_map(|slug| Uuid::parse_str(slug).ok())
+                .collect(),
+            orphaned_views: response.orphaned_views,
+        })
+    }
+
+    async fn set_event_retention(
+        &self,
+        tenant_id: &Uuid,
+        policy: EventRetentionPolicy,
+    ) -> Result<EventRetentionPolicy, ComputeError> {
+        let response = self
+            .meters_grpc_client
+            .clone()
+            .set_retention_policy(Request::new(SetRetentionPolicyRequest {
+                tenant_id: tenant_id.to_string(),
+                policy: Some(RetentionPolicy {
+                    retention_days: policy.retention_days,
+                    archive: policy.archive,
+                    archived_until: None,
+                }),
+            }))
+            .await
+            .map_err(|status| {
+                log::error!("Failed to set event retention: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?
+            .into_inner();
+
+        Ok(response
+            .policy
+            .map(retention_policy_from_grpc)
+            .unwrap_or_default())
+    }
+
+    async fn get_event_retention(
+        &self,
+        tenant_id: &Uuid,
+    ) -> Result<EventRetentionPolicy, ComputeError> {
+        let response = self
+            .meters_grpc_client
+            .clone()
+            .get_retention_policy(Request::new(GetRetentionPolicyRequest {
+                tenant_id: tenant_id.to_string(),
+            }))
+            .await
+            .map_err(|status| {
+                log::error!("Failed to get event retention: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?
+            .into_inner();
+
+        Ok(response
+            .policy
+            .map(retention_policy_from_grpc)
+            .unwrap_or_default())
+    }
+
+    async fn restore_archived_events(
+        &self,
+        tenant_id: &Uuid,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+    ) -> Result<(), ComputeError> {
+        self.meters_grpc_client
+            .clone()
+            .restore_archived_events(Request::new(RestoreArchivedEventsRequest {
+                tenant_id: tenant_id.to_string(),
+                from: Some(chrono_to_timestamp(from)),
+                to: Some(chrono_to_timestamp(to)),
+            }))
+            .await
+            .map_err(|status| {
+                log::error!("Failed to restore archived events: {:?}", status);
+                ComputeError::MeteringGrpcError
+            })?;
+
+        Ok(())
+    }
+}
+
+fn retention_policy_from_grpc(policy: RetentionPolicy) -> EventRetentionPolicy {
+    EventRetentionPolicy {
+        retention_days: policy.retention_days,
+        archive: policy.archive,
+        archived_until: policy.archived_until.and_then(timestamp_to_datetime),
+    }
+}
+
+fn aggregation_type(metric: &BillableMetric) -> AggregationType {
+    match metric.aggregation_type {
+        domain::enums::BillingMetricAggregateEnum::Count => AggregationType::Count,
+        domain::enums::BillingMetricAggregateEnum::Latest => AggregationType::Latest,
+        domain::enums::BillingMetricAggregateEnum::Max => AggregationType::Max,
+        domain::enums::BillingMetricAggregateEnum::Min => AggregationType::Min,
+        domain::enums::BillingMetricAggregateEnum::Mean => AggregationType::Mean,
+        domain::enums::BillingMetricAggregateEnum::Sum => AggregationType::Sum,
+        domain::enums::BillingMetricAggregateEnum::CountDistinct => AggregationType::CountDistinct,
+    }
+}
+
+/// Restricts the usage to the dimensions of the segmentation matrix of the metric
+fn segmentation_filters(metric: &BillableMetric) -> Vec<Filter> {
+    match metric.segmentation_matrix.clone() {
+        Some(domain::SegmentationMatrix::Single(domain::Dimension { key, values })) => {
+            vec![Filter {
+                property_name: key,
+                property_value: values,
+            }]
+        }
+        Some(domain::SegmentationMatrix::Double {
+            dimension1,
+            dimension2,
+        }) => {
+            vec![
+                Filter {
+                    property_name: dimension1.key,
+                    property_value: dimension1.values,
+                },
+                Filter {
+                    property_name: dimension2.key,
+                    property_value: dimension2.values,
+                },
+            ]
+        }
+        Some(domain::SegmentationMatrix::Linked {
+            dimension1_key,
+            dimension2_key,
+            values,
+        }) => {
+            let mut filter_properties = vec![];
+            for (key, values) in values.iter() {
+                filter_properties.push(Filter {
+                    property_name: dimension1_key.clone(),
+                    property_value: vec![key.clone()],
+                });
+                filter_properties.push(Filter {
+                    property_name: dimension2_key.clone(),
+                    property_value: values.clone(),
+                });
+            }
+            filter_properties
+        }
+        None => vec![],
+    }
+}
+
+fn timestamp_to_datetime(ts: prost_types::Timestamp) -> Option<NaiveDateTime> {
+    chrono::DateTime::from_timestamp(ts.seconds, ts.nanos as u32).map(|dt| dt.naive_utc())
 }
 
 fn date_to_timestamp(dt: NaiveDate) -> prost_types::Timestamp {
@@ -8,7 +8,11 @@ use common_config::auth::InternalAuthConfig;
 use common_config::common::CommonConfig;
 use common_config::idempotency::IdempotencyConfig;
 
+use crate::adapters::alerting::AlertingConfig;
+use crate::adapters::quickbooks::QuickBooksConfig;
+use crate::api::cors::CorsConfig;
 use crate::workers::fang::ext::FangExtConfig;
+use crate::workers::invoicing::watchdog_worker::InvoiceWatchdogConfig;
 
 static CONFIG: std::sync::OnceLock<Config> = std::sync::OnceLock::new();
 
@@ -50,6 +54,10 @@ pub struct Config {
     #[envconfig(from = "JWT_SECRET")]
     pub jwt_secret: SecretString,
 
+    /// signing secret of the email provider delivery callbacks (bounces, complaints)
+    #[envconfig(from = "EMAIL_EVENTS_WEBHOOK_SECRET")]
+    pub email_events_webhook_secret: Option<SecretString>,
+
     #[envconfig(from = "ENABLE_MULTI_ORGANIZATION", default = "false")]
     pub multi_organization_enabled: bool,
 
@@ -62,6 +70,18 @@ pub struct Config {
     #[envconfig(nested)]
     pub fang_ext: FangExtConfig,
 
+    #[envconfig(nested)]
+    pub invoice_watchdog: InvoiceWatchdogConfig,
+
+    #[envconfig(nested)]
+    pub alerting: AlertingConfig,
+
+    #[envconfig(nested)]
+    pub quickbooks: QuickBooksConfig,
+
+    #[envconfig(nested)]
+    pub cors: CorsConfig,
+
     #[envconfig(from = "GOTENBERG_URL", default = "http://localhost:3000")]
     pub gotenberg_url: String,
 }
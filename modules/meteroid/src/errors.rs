@@ -1,5 +1,7 @@
 use axum::response::{IntoResponse, Response};
+use error_stack::Report;
 use hyper::StatusCode;
+use meteroid_store::errors::StoreError;
 
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
 pub enum AdapterWebhookError {
@@ -72,6 +74,34 @@ pub enum WorkerError {
     MeteringError,
     #[error("Failed to update currency rates")]
     CurrencyRatesUpdateError,
+    #[error("Tenant onboarding is incomplete")]
+    OnboardingIncomplete,
+    #[error("No invoicing provider configured")]
+    ProviderNotConfigured,
+    #[error("Invoice delivery failed")]
+    DeliveryError,
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+pub enum AlertingError {
+    #[error("Failed to deliver alert")]
+    DeliveryFailed,
+    #[error("Alert rejected by provider with status {0}")]
+    ProviderRejected(u16),
+}
+
+#[derive(Debug, thiserror::Error, PartialEq, Clone)]
+pub enum AccountingExportError {
+    #[error("The connector is not configured")]
+    NotConfigured,
+    #[error("Failed to call the accounting system")]
+    RequestFailed,
+    #[error("Request rejected by the accounting system with status {0}")]
+    ProviderRejected(u16),
+    #[error("Failed to store the export")]
+    StorageError,
+    #[error("Failed to load the entries to export")]
+    StoreError,
 }
 
 #[derive(Debug, thiserror::Error, PartialEq, Clone)]
@@ -86,6 +116,10 @@ pub enum InvoicingAdapterError {
     GrpcError,
     #[error("Stripe call error")]
     StripeError,
+    #[error("Adyen call error")]
+    AdyenError,
+    #[error("GoCardless call error")]
+    GoCardlessError,
 }
 
 #[derive(Debug, thiserror::Error)]
@@ -118,6 +152,14 @@ pub enum RestApiError {
     Forbidden,
     #[error("Invalid input")]
     InvalidInput,
+    #[error("Invalid argument: {0}")]
+    InvalidArgument(String),
+    #[error("Not found")]
+    NotFound,
+    #[error("Already exists")]
+    Conflict,
+    #[error("Metering error")]
+    MeteringError,
 }
 
 impl IntoResponse for RestApiError {
@@ -129,6 +171,10 @@ impl IntoResponse for RestApiError {
             RestApiError::Forbidden => StatusCode::FORBIDDEN,
             RestApiError::InvalidInput => StatusCode::BAD_REQUEST,
             RestApiError::StoreError => StatusCode::INTERNAL_SERVER_ERROR,
+            RestApiError::InvalidArgument(_) => StatusCode::BAD_REQUEST,
+            RestApiError::NotFound => StatusCode::NOT_FOUND,
+            RestApiError::Conflict => StatusCode::CONFLICT,
+            RestApiError::MeteringError => StatusCode::INTERNAL_SERVER_ERROR,
         };
 
         let error_message = match status {
@@ -141,6 +187,25 @@ impl IntoResponse for RestApiError {
     }
 }
 
+impl From<Report<StoreError>> for RestApiError {
+    fn from(err: Report<StoreError>) -> Self {
+        match err.current_context() {
+            StoreError::ValueNotFound(_) => RestApiError::NotFound,
+            StoreError::DuplicateValue { .. } => RestApiError::Conflict,
+            StoreError::InvalidArgument(msg) => RestApiError::InvalidArgument(msg.clone()),
+            StoreError::ComponentRulesViolated(_)
+            | StoreError::CreditLimitExceeded { .. }
+            | StoreError::InvalidMatrixDimensions(_) => {
+                RestApiError::InvalidArgument(err.current_context().to_string())
+            }
+            _ => {
+                log::error!("Store error: {:?}", err);
+                RestApiError::StoreError
+            }
+        }
+    }
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum ObjectStoreError {
     #[error("Failed to parse url")]
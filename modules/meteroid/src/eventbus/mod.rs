@@ -23,16 +23,20 @@ pub fn create_eventbus_memory() -> Arc<dyn EventBus<Event>> {
 }
 
 pub async fn setup_eventbus_handlers(store: Store, config: Config) {
+    let webhook_handler = Arc::new(WebhookHandler::new(
+        store.clone(),
+        config.secrets_crypt_key.clone(),
+        true,
+    ));
+
     store
         .clone()
         .eventbus
-        .subscribe(Arc::new(WebhookHandler::new(
-            store.clone(),
-            config.secrets_crypt_key.clone(),
-            true,
-        )))
+        .subscribe(webhook_handler.clone())
         .await;
 
+    webhook_handler::start_batch_flusher(webhook_handler);
+
     if config.analytics.enabled {
         let country = match analytics_handler::get_geoip().await {
             Ok(geoip) => Some(geoip.country),
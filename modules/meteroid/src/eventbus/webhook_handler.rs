@@ -1,15 +1,35 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Instant;
+
 use cached::proc_macro::cached;
 use reqwest_middleware::{ClientBuilder, ClientWithMiddleware};
 use reqwest_retry::{policies::ExponentialBackoff, RetryTransientMiddleware};
 use secrecy::{ExposeSecret, SecretString};
 use serde::Serialize;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
 use uuid::Uuid;
 
 use common_eventbus::{Event, EventData, TenantEventDataDetails};
 use common_eventbus::{EventBusError, EventHandler};
-use meteroid_store::domain::enums::WebhookOutEventTypeEnum;
-use meteroid_store::domain::webhooks::WebhookOutEventNew;
+use meteroid_store::domain::enums::{
+    BillingEmailStatusEnum, CreditLimitEnforcementEnum, CreditLimitSourceEnum,
+    InvoiceStuckReasonEnum, UsageAlertRuleStatusEnum, WebhookOutEventTypeEnum,
+};
+use meteroid_store::domain::payment_reminders::PaymentReminderTiming;
+use meteroid_store::domain::usage_alerts::UsageAlertThreshold;
+use meteroid_store::domain::webhooks::{WebhookOutBatching, WebhookOutEventNew};
 use meteroid_store::domain::DetailedInvoice;
+use meteroid_store::repositories::billable_metrics::BillableMetricInterface;
+use meteroid_store::repositories::credit_limits::CreditLimitInterface;
+use meteroid_store::repositories::invoice_payments::InvoicePaymentInterface;
+use meteroid_store::repositories::invoice_watchdog::InvoiceWatchdogInterface;
+use meteroid_store::repositories::payment_reminders::PaymentRemindersInterface;
+use meteroid_store::repositories::seat_increase_requests::SeatIncreaseRequestInterface;
+use meteroid_store::repositories::usage_alert_rules::UsageAlertRuleInterface;
+use meteroid_store::repositories::usage_alerts::UsageAlertInterface;
+use meteroid_store::repositories::usage_caps::UsageCapInterface;
 use meteroid_store::repositories::webhooks::WebhooksInterface;
 use meteroid_store::repositories::{CustomersInterface, InvoiceInterface, SubscriptionInterface};
 use meteroid_store::{crypt, Store};
@@ -19,12 +39,17 @@ use crate::webhook::Webhook;
 
 const ENDPOINT_REQUEST_TIMEOUT: std::time::Duration = std::time::Duration::from_secs(30);
 const ENDPOINT_RETRIES: u32 = 3;
+const BATCH_FLUSH_INTERVAL: std::time::Duration = std::time::Duration::from_secs(1);
+/// deliveries of a batched event before it is dropped, the failed batches being sent again with the next one
+const BATCH_MAX_ATTEMPTS: u32 = 3;
 
 pub struct WebhookHandler {
     pub store: Store,
     pub crypt_key: SecretString,
     pub client: ClientWithMiddleware,
     pub cache_enabled: bool,
+    /// the pending batches of the endpoints with batched delivery, by endpoint id
+    batches: Mutex<HashMap<Uuid, PendingBatch>>,
 }
 
 impl WebhookHandler {
@@ -40,6 +65,7 @@ impl WebhookHandler {
             crypt_key,
             client,
             cache_enabled,
+            batches: Mutex::new(HashMap::new()),
         }
     }
 
@@ -47,8 +73,7 @@ impl WebhookHandler {
     async fn send_webhook_event(
         &self,
         event: &Event,
-        webhook_event: &WebhookEvent,
-        webhook_event_payload: &Vec<u8>,
+        webhook_event_payload: &[u8],
         endpoint: &Endpoint,
     ) -> Result<reqwest::Response, EventBusError> {
         log::debug!(
@@ -57,30 +82,39 @@ impl WebhookHandler {
             endpoint.url
         );
 
+        self.send_signed(
+            endpoint,
+            event.event_id.to_string().as_str(),
+            event.event_timestamp.timestamp(),
+            webhook_event_payload,
+        )
+        .await
+    }
+
+    /// Sends the payload to the endpoint, signed with the message id and timestamp
+    async fn send_signed(
+        &self,
+        endpoint: &Endpoint,
+        msg_id: &str,
+        timestamp: i64,
+        payload: &[u8],
+    ) -> Result<reqwest::Response, EventBusError> {
         let webhook = Webhook::new(endpoint.secret.as_str()).map_err(|e| {
             EventBusError::EventHandlerFailed(format!("Invalid webhook signature: {}", e))
         })?;
 
-        let signature = webhook
-            .sign(
-                event.event_id.to_string().as_str(),
-                event.event_timestamp.timestamp(),
-                webhook_event_payload.as_slice(),
-            )
-            .map_err(|e| {
-                EventBusError::EventHandlerFailed(format!("Failed to sign event: {}", e))
-            })?;
+        let signature = webhook.sign(msg_id, timestamp, payload).map_err(|e| {
+            EventBusError::EventHandlerFailed(format!("Failed to sign event: {}", e))
+        })?;
 
         self.client
             .post(&endpoint.url)
             .timeout(ENDPOINT_REQUEST_TIMEOUT)
-            .header(webhook::HEADER_WEBHOOK_ID, event.event_id.to_string())
-            .header(
-                webhook::HEADER_WEBHOOK_TIMESTAMP,
-                event.event_timestamp.timestamp(),
-            )
+            .header(reqwest::header::CONTENT_TYPE, "application/json")
+            .header(webhook::HEADER_WEBHOOK_ID, msg_id)
+            .header(webhook::HEADER_WEBHOOK_TIMESTAMP, timestamp)
             .header(webhook::HEADER_WEBHOOK_SIGNATURE, signature)
-            .json(&webhook_event)
+            .body(payload.to_vec())
             .send()
             .await
             .map_err(|e| {
@@ -91,44 +125,193 @@ impl WebhookHandler {
             })
     }
 
+    /// Logs the delivery of the events to the endpoint, one row per event.
+    /// The events of a batch share its id and the response of the endpoint
     #[tracing::instrument(skip_all)]
     async fn log_endpoint_response_to_db(
         &self,
-        event: &Event,
         endpoint: &Endpoint,
-        webhook_event_payload: &[u8],
+        events: Vec<(WebhookOutEventTypeEnum, &[u8])>,
+        batch_id: Option<Uuid>,
         endpoint_response: Result<reqwest::Response, EventBusError>,
     ) -> Result<(), EventBusError> {
-        let event_type = get_event_type(event).ok_or_else(|| {
-            EventBusError::EventHandlerFailed("Failed to get event type".to_string())
-        })?;
-
-        let request_body = String::from_utf8(webhook_event_payload.to_owned()).map_err(|e| {
-            EventBusError::EventHandlerFailed(format!("Failed to convert payload to string: {}", e))
-        })?;
-
         let (http_status_code, response_body, error_message) = match endpoint_response {
             Ok(r) => (Some(r.status().as_u16() as i16), r.text().await.ok(), None),
             Err(e) => (None, None, Some(e.to_string())),
         };
 
-        let _ = self
-            .store
-            .insert_webhook_event(WebhookOutEventNew {
-                endpoint_id: endpoint.id,
-                created_at: chrono::Utc::now().naive_utc(),
-                event_type,
-                request_body,
-                response_body,
-                http_status_code,
-                error_message,
-            })
-            .await
-            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+        for (event_type, webhook_event_payload) in events {
+            let request_body =
+                String::from_utf8(webhook_event_payload.to_owned()).map_err(|e| {
+                    EventBusError::EventHandlerFailed(format!(
+                        "Failed to convert payload to string: {}",
+                        e
+                    ))
+                })?;
+
+            let _ = self
+                .store
+                .insert_webhook_event(WebhookOutEventNew {
+                    endpoint_id: endpoint.id,
+                    created_at: chrono::Utc::now().naive_utc(),
+                    event_type,
+                    request_body,
+                    response_body: response_body.clone(),
+                    http_status_code,
+                    error_message: error_message.clone(),
+                    batch_id,
+                })
+                .await
+                .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+        }
 
         Ok(())
     }
 
+    /// Adds the event to the pending batch of the endpoint, sending it once full
+    async fn enqueue_batched_event(
+        &self,
+        endpoint: Endpoint,
+        batching: WebhookOutBatching,
+        event: BatchedEvent,
+    ) {
+        let endpoint_id = endpoint.id;
+
+        let full_batches = {
+            let mut batches = self.batches.lock().await;
+
+            let batch = batches.entry(endpoint_id).or_insert_with(|| PendingBatch {
+                endpoint: endpoint.clone(),
+                opened_at: Instant::now(),
+                events: vec![],
+            });
+            // the endpoint may have been updated since the batch was opened
+            batch.endpoint = endpoint;
+            batch.events.push(event);
+
+            if batch.events.len() >= batching.max_events as usize {
+                let (full_batches, rest) = batches
+                    .remove(&endpoint_id)
+                    .map(PendingBatch::split)
+                    .unwrap_or_default();
+
+                if let Some(rest) = rest {
+                    batches.insert(endpoint_id, rest);
+                }
+
+                full_batches
+            } else {
+                vec![]
+            }
+        };
+
+        for batch in full_batches {
+            self.send_batch(batch).await;
+        }
+    }
+
+    /// Sends the pending batches whose oldest event waited longer than the endpoint allows
+    #[tracing::instrument(skip_all)]
+    pub async fn flush_due_batches(&self) {
+        let due_batches: Vec<PendingBatch> = {
+            let mut batches = self.batches.lock().await;
+
+            let due_ids: Vec<Uuid> = batches
+                .iter()
+                .filter(|(_, batch)| batch.is_due())
+                .map(|(id, _)| *id)
+                .collect();
+
+            due_ids
+                .iter()
+                .filter_map(|id| batches.remove(id))
+                .flat_map(|batch| {
+                    let (mut full_batches, rest) = batch.split();
+                    full_batches.extend(rest);
+                    full_batches
+                })
+                .collect()
+        };
+
+        for batch in due_batches {
+            self.send_batch(batch).await;
+        }
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn send_batch(&self, batch: PendingBatch) {
+        let batch_id = Uuid::now_v7();
+        let timestamp = chrono::Utc::now();
+
+        log::debug!(
+            "Sending batch {} of {} events to endpoint {}",
+            batch_id,
+            batch.events.len(),
+            batch.endpoint.url
+        );
+
+        let payload = serde_json::to_vec(&WebhookBatch {
+            id: batch_id,
+            timestamp,
+            events: batch
+                .events
+                .iter()
+                .map(|e| BatchedWebhookEvent {
+                    id: e.event_id,
+                    event: &e.webhook_event,
+                })
+                .collect(),
+        })
+        .map_err(|e| {
+            EventBusError::EventHandlerFailed(format!("Failed to serialize batch: {}", e))
+        });
+
+        let send_result = match payload {
+            Ok(payload) => {
+                self.send_signed(
+                    &batch.endpoint,
+                    batch_id.to_string().as_str(),
+                    timestamp.timestamp(),
+                    payload.as_slice(),
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+
+        let delivered = matches!(&send_result, Ok(response) if response.status().is_success());
+
+        let log_result = self
+            .log_endpoint_response_to_db(
+                &batch.endpoint,
+                batch
+                    .events
+                    .iter()
+                    .map(|e| (e.event_type.clone(), e.payload.as_slice()))
+                    .collect(),
+                Some(batch_id),
+                send_result,
+            )
+            .await;
+
+        if let Err(e) = log_result {
+            log::error!("Failed to log webhook batch: {}", e);
+        }
+
+        if !delivered {
+            let mut batches = self.batches.lock().await;
+            let dropped = requeue_failed_batch(&mut batches, batch, Instant::now());
+
+            if dropped > 0 {
+                log::warn!(
+                    "Dropped {} webhook events after {} failed deliveries",
+                    dropped,
+                    BATCH_MAX_ATTEMPTS
+                );
+            }
+        }
+    }
+
     #[tracing::instrument(skip_all)]
     async fn get_active_endpoints(&self, event: &Event) -> Result<Vec<Endpoint>, EventBusError> {
         let event_type = get_event_type(event);
@@ -189,6 +372,42 @@ impl WebhookHandler {
         Ok(event)
     }
 
+    #[tracing::instrument(skip_all)]
+    async fn customer_billing_email_updated_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let customer = self
+            .store
+            .find_customer_by_id(event_data_details.entity_id, event_data_details.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let status = match customer.billing_email_status {
+            BillingEmailStatusEnum::Unverified => "unverified",
+            BillingEmailStatusEnum::Verified => "verified",
+            BillingEmailStatusEnum::Bounced => "bounced",
+            BillingEmailStatusEnum::Complained => "complained",
+        };
+
+        let event = WebhookEvent {
+            event_type: "customer.billing_email.updated".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(CustomerBillingEmailData {
+                customer_id: customer.id,
+                customer_name: customer.name.clone(),
+                customer_alias: customer.alias.clone(),
+                billing_email: customer.billing_email().cloned(),
+                status: status.to_string(),
+                reason: customer.billing_email_status_reason,
+                updated_at: customer.billing_email_status_updated_at,
+            })?,
+        };
+
+        Ok(event)
+    }
+
     #[tracing::instrument(skip_all)]
     async fn subscription_created_webhook(
         &self,
@@ -278,6 +497,452 @@ impl WebhookHandler {
 
         Ok(event)
     }
+
+    #[tracing::instrument(skip_all)]
+    async fn invoice_delivery_requested_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = self
+            .store
+            .find_invoice_by_id(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let event = WebhookEvent {
+            event_type: "invoice.delivery_requested".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(InvoiceDeliveryData {
+                invoice_id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                customer_id: customer.id,
+                customer_name: customer.name.clone(),
+                billing_email: customer.billing_email().cloned(),
+                currency: invoice.currency,
+                total: invoice.total,
+                amount_due: invoice.amount_due,
+                invoice_date: invoice.invoice_date,
+                due_at: invoice.due_at,
+                pdf_document_id: invoice.pdf_document_id,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn invoice_paid_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = self
+            .store
+            .find_invoice_by_id(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let event = WebhookEvent {
+            event_type: "invoice.paid".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(InvoicePaidData {
+                invoice_id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                currency: invoice.currency,
+                total: invoice.total,
+                invoice_date: invoice.invoice_date,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn invoice_payment_recorded_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let payment = self
+            .store
+            .find_invoice_payment(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = self
+            .store
+            .find_invoice_by_id(payment.tenant_id, payment.invoice_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let event = WebhookEvent {
+            event_type: "invoice.payment_recorded".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(InvoicePaymentRecordedData {
+                invoice_payment_id: payment.id,
+                invoice_id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                currency: invoice.currency,
+                amount: payment.amount,
+                amount_due: invoice.amount_due,
+                paid_at: payment.paid_at,
+                reference: payment.reference,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn invoice_stuck_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let alert = self
+            .store
+            .find_invoice_stuck_alert(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = self
+            .store
+            .find_invoice_by_id(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let reason = match alert.reason {
+            InvoiceStuckReasonEnum::StaleDraft => "stale_draft",
+            InvoiceStuckReasonEnum::PendingFinalization => "pending_finalization",
+            InvoiceStuckReasonEnum::IssueRetriesExhausted => "issue_retries_exhausted",
+        };
+
+        let event = WebhookEvent {
+            event_type: "invoice.stuck".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(InvoiceStuckData {
+                invoice_id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                reason: reason.to_string(),
+                invoice_date: invoice.invoice_date,
+                last_issue_error: invoice.last_issue_error,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn invoice_payment_reminder_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let reminder = self
+            .store
+            .find_payment_reminder_by_id(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let DetailedInvoice {
+            invoice, customer, ..
+        } = self
+            .store
+            .find_invoice_by_id(reminder.tenant_id, reminder.invoice_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let event = WebhookEvent {
+            event_type: "invoice.payment_reminder".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(InvoicePaymentReminderData {
+                payment_reminder_id: reminder.id,
+                invoice_id: invoice.id,
+                invoice_number: invoice.invoice_number,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                customer_alias: customer.alias,
+                currency: invoice.currency,
+                amount_due: invoice.amount_due,
+                due_at: reminder.due_at,
+                timing: PaymentReminderTiming::from_offset(reminder.offset_days),
+                days: reminder.offset_days.unsigned_abs(),
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn usage_alert_triggered_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let alert = self
+            .store
+            .find_usage_alert_by_id(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let customer = self
+            .store
+            .find_customer_by_id(alert.customer_id, alert.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let metric = self
+            .store
+            .find_billable_metric_by_id(alert.billable_metric_id, alert.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let (threshold_type, threshold_value) = match alert.threshold {
+            UsageAlertThreshold::Absolute(value) => ("absolute".to_string(), value),
+            UsageAlertThreshold::CapacityPercent(percent) => {
+                ("capacity_percent".to_string(), percent)
+            }
+        };
+
+        let event = WebhookEvent {
+            event_type: "usage_alert.triggered".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(UsageAlertData {
+                usage_alert_id: alert.id,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                customer_alias: customer.alias,
+                metric_id: metric.id,
+                metric_code: metric.code,
+                threshold_type,
+                threshold_value,
+                usage: alert.last_value,
+                period_start: alert.triggered_period_start,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn usage_alert_rule_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let rule_event = self
+            .store
+            .find_usage_alert_rule_event(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let rule = self
+            .store
+            .find_usage_alert_rule_by_id(rule_event.tenant_id, rule_event.rule_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let customer = self
+            .store
+            .find_customer_by_id(rule_event.customer_id, rule_event.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let metric = self
+            .store
+            .find_billable_metric_by_id(rule.billable_metric_id, rule.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let event_type = match rule_event.status {
+            UsageAlertRuleStatusEnum::Firing => "usage_alert_rule.firing",
+            UsageAlertRuleStatusEnum::Resolved => "usage_alert_rule.resolved",
+        };
+
+        let event = WebhookEvent {
+            event_type: event_type.to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(UsageAlertRuleData {
+                usage_alert_rule_id: rule.id,
+                usage_alert_rule_event_id: rule_event.id,
+                rule_name: rule.definition.name,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                customer_alias: customer.alias,
+                metric_id: metric.id,
+                metric_code: metric.code,
+                usage: rule_event.usage,
+                growth_percent: rule_event.growth_percent,
+                window_start: rule_event.window.start,
+                window_end: rule_event.window.end,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn usage_cap_reached_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let reached = self
+            .store
+            .find_usage_cap_reached(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let subscription = self
+            .store
+            .get_subscription_details(reached.tenant_id, reached.subscription_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let fee = subscription
+            .price_components
+            .iter()
+            .find(|c| c.id == reached.fee_id)
+            .map(|c| (c.name.clone(), c.metric_id()))
+            .or_else(|| {
+                subscription
+                    .add_ons
+                    .iter()
+                    .find(|a| a.id == reached.fee_id)
+                    .map(|a| (a.name.clone(), a.fee.metric_id()))
+            });
+
+        let event = WebhookEvent {
+            event_type: "usage_cap.reached".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(UsageCapData {
+                usage_cap_reached_id: reached.id,
+                subscription_id: subscription.id,
+                customer_id: subscription.customer_id,
+                customer_name: subscription.customer_name,
+                fee_id: reached.fee_id,
+                fee_name: fee.as_ref().map(|(name, _)| name.clone()),
+                metric_id: fee.and_then(|(_, metric_id)| metric_id),
+                currency: subscription.currency,
+                cap_cents: reached.cap,
+                charges_cents: reached.charges,
+                period_start: reached.period.start,
+                period_end: reached.period.end,
+                reached_at: reached.reached_at,
+            })?,
+        };
+
+        Ok(event)
+    }
+
+    #[tracing::instrument(skip_all)]
+    async fn credit_limit_exceeded_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let exceeded = self
+            .store
+            .find_credit_limit_exceeded(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let customer = self
+            .store
+            .find_customer_by_id(exceeded.customer_id, exceeded.tenant_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let source = match exceeded.source {
+            CreditLimitSourceEnum::Subscription => "subscription",
+            CreditLimitSourceEnum::UsageThresholdInvoice => "usage_threshold_invoice",
+        };
+
+        let enforcement = match exceeded.enforcement {
+            CreditLimitEnforcementEnum::Flag => "flag",
+            CreditLimitEnforcementEnum::Block => "block",
+        };
+
+        let event = WebhookEvent {
+            event_type: "customer.credit_limit.exceeded".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(CreditLimitExceededData {
+                credit_limit_exceeded_id: exceeded.id,
+                customer_id: customer.id,
+                customer_name: customer.name,
+                customer_alias: customer.alias,
+                source: source.to_string(),
+                subscription_id: exceeded.subscription_id,
+                enforcement: enforcement.to_string(),
+                currency: exceeded.currency,
+                credit_limit_cents: exceeded.credit_limit,
+                exposure_cents: exceeded.exposure.total(),
+                unpaid_invoices_cents: exceeded.exposure.unpaid_invoices,
+                unbilled_usage_cents: exceeded.exposure.unbilled_usage,
+                exceeded_at: exceeded.exceeded_at,
+            })?,
+        };
+
+        Ok(event)
+    }
+    #[tracing::instrument(skip_all)]
+    async fn seat_increase_requested_webhook(
+        &self,
+        event: &Event,
+        event_data_details: &TenantEventDataDetails,
+    ) -> Result<WebhookEvent, EventBusError> {
+        let request = self
+            .store
+            .find_seat_increase_request(event_data_details.tenant_id, event_data_details.entity_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let subscription = self
+            .store
+            .get_subscription_details(request.tenant_id, request.subscription_id)
+            .await
+            .map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))?;
+
+        let component_name = subscription
+            .price_components
+            .iter()
+            .find(|c| c.price_component_id == Some(request.price_component_id))
+            .map(|c| c.name.clone());
+
+        let event = WebhookEvent {
+            event_type: "subscription.seat_increase_requested".to_string(),
+            timestamp: event.event_timestamp,
+            data: to_json(SeatIncreaseRequestData {
+                seat_increase_request_id: request.id,
+                subscription_id: subscription.id,
+                customer_id: subscription.customer_id,
+                customer_name: subscription.customer_name,
+                price_component_id: request.price_component_id,
+                component_name,
+                seats: request.seats,
+                requested_by: request.requested_by,
+                note: request.note,
+                requested_at: request.created_at,
+            })?,
+        };
+
+        Ok(event)
+    }
 }
 
 #[async_trait::async_trait]
@@ -297,6 +962,10 @@ impl EventHandler<Event> for WebhookHandler {
             EventData::CustomerCreated(details) => {
                 self.customer_created_webhook(&event, details).await?
             }
+            EventData::CustomerBillingEmailUpdated(details) => {
+                self.customer_billing_email_updated_webhook(&event, details)
+                    .await?
+            }
             EventData::SubscriptionCreated(details) => {
                 self.subscription_created_webhook(&event, details).await?
             }
@@ -306,23 +975,77 @@ impl EventHandler<Event> for WebhookHandler {
             EventData::InvoiceFinalized(details) => {
                 self.invoice_finalized_webhook(&event, details).await?
             }
+            EventData::InvoiceDeliveryRequested(details) => {
+                self.invoice_delivery_requested_webhook(&event, details)
+                    .await?
+            }
+            EventData::InvoicePaid(details) => self.invoice_paid_webhook(&event, details).await?,
+            EventData::InvoicePaymentRecorded(details) => {
+                self.invoice_payment_recorded_webhook(&event, details)
+                    .await?
+            }
+            EventData::InvoiceStuck(details) => self.invoice_stuck_webhook(&event, details).await?,
+            EventData::InvoicePaymentReminder(details) => {
+                self.invoice_payment_reminder_webhook(&event, details)
+                    .await?
+            }
+            EventData::UsageAlertTriggered(details) => {
+                self.usage_alert_triggered_webhook(&event, details).await?
+            }
+            EventData::UsageAlertRuleFiring(details)
+            | EventData::UsageAlertRuleResolved(details) => {
+                self.usage_alert_rule_webhook(&event, details).await?
+            }
+            EventData::UsageCapReached(details) => {
+                self.usage_cap_reached_webhook(&event, details).await?
+            }
+            EventData::CreditLimitExceeded(details) => {
+                self.credit_limit_exceeded_webhook(&event, details).await?
+            }
+            EventData::SeatIncreaseRequested(details) => {
+                self.seat_increase_requested_webhook(&event, details)
+                    .await?
+            }
             _ => {
                 log::debug!("Skipping event: {:?}", &event);
                 return Ok(());
             }
         };
 
+        let event_type = get_event_type(&event).ok_or_else(|| {
+            EventBusError::EventHandlerFailed("Failed to get event type".to_string())
+        })?;
+
         let webhook_event_payload = serde_json::to_vec(&webhook_event).map_err(|e| {
             EventBusError::EventHandlerFailed(format!("Failed to serialize event: {}", e))
         })?;
 
         for endpoint in endpoints {
+            if let Some(batching) = endpoint.batching {
+                let batched_event = BatchedEvent {
+                    event_id: event.event_id,
+                    event_type: event_type.clone(),
+                    webhook_event: webhook_event.clone(),
+                    payload: webhook_event_payload.clone(),
+                    attempts: 0,
+                };
+
+                self.enqueue_batched_event(endpoint, batching, batched_event)
+                    .await;
+                continue;
+            }
+
             let send_result = self
-                .send_webhook_event(&event, &webhook_event, &webhook_event_payload, &endpoint)
+                .send_webhook_event(&event, &webhook_event_payload, &endpoint)
                 .await;
 
             let log_result = self
-                .log_endpoint_response_to_db(&event, &endpoint, &webhook_event_payload, send_result)
+                .log_endpoint_response_to_db(
+                    &endpoint,
+                    vec![(event_type.clone(), webhook_event_payload.as_slice())],
+                    None,
+                    send_result,
+                )
                 .await;
 
             if let Err(e) = log_result {
@@ -340,9 +1063,120 @@ struct Endpoint {
     pub url: String,
     pub secret: String,
     pub event_types: Vec<WebhookOutEventTypeEnum>,
+    pub batching: Option<WebhookOutBatching>,
+}
+
+struct PendingBatch {
+    pub endpoint: Endpoint,
+    pub opened_at: Instant,
+    pub events: Vec<BatchedEvent>,
+}
+
+impl PendingBatch {
+    /// Splits the batch into requests of the batch size of the endpoint, and the remaining events
+    fn split(self) -> (Vec<PendingBatch>, Option<PendingBatch>) {
+        let size = match self.endpoint.batching {
+            Some(batching) => (batching.max_events as usize).max(1),
+            None => self.events.len().max(1),
+        };
+
+        let mut full_batches = vec![];
+        let mut events = self.events;
+
+        while events.len() >= size {
+            let rest = events.split_off(size);
+            full_batches.push(PendingBatch {
+                endpoint: self.endpoint.clone(),
+                opened_at: self.opened_at,
+                events,
+            });
+            events = rest;
+        }
+
+        let rest = (!events.is_empty()).then(|| PendingBatch {
+            endpoint: self.endpoint,
+            opened_at: self.opened_at,
+            events,
+        });
+
+        (full_batches, rest)
+    }
+
+    fn is_due(&self) -> bool {
+        match self.endpoint.batching {
+            Some(batching) => {
+                self.opened_at.elapsed().as_secs() >= batching.max_wait_seconds as u64
+            }
+            // the endpoint switched back to per event delivery
+            None => true,
+        }
+    }
+}
+
+struct BatchedEvent {
+    pub event_id: Uuid,
+    pub event_type: WebhookOutEventTypeEnum,
+    pub webhook_event: WebhookEvent,
+    /// the payload of the event alone, logged for each event of the batch
+    pub payload: Vec<u8>,
+    /// failed deliveries of the event
+    pub attempts: u32,
+}
+
+/// Puts the events of a batch that failed to be delivered back in the pending batch of the endpoint,
+/// ahead of the events queued since. Returns the number of events dropped after their last attempt
+fn requeue_failed_batch(
+    batches: &mut HashMap<Uuid, PendingBatch>,
+    failed: PendingBatch,
+    now: Instant,
+) -> usize {
+    let (mut retried, dropped): (Vec<BatchedEvent>, Vec<BatchedEvent>) = failed
+        .events
+        .into_iter()
+        .map(|mut event| {
+            event.attempts += 1;
+            event
+        })
+        .partition(|event| event.attempts < BATCH_MAX_ATTEMPTS);
+
+    if !retried.is_empty() {
+        match batches.get_mut(&failed.endpoint.id) {
+            Some(pending) => {
+                retried.append(&mut pending.events);
+                pending.events = retried;
+            }
+            None => {
+                batches.insert(
+                    failed.endpoint.id,
+                    PendingBatch {
+                        endpoint: failed.endpoint,
+                        opened_at: now,
+                        events: retried,
+                    },
+                );
+            }
+        }
+    }
+
+    dropped.len()
+}
+
+/// The events of a batch, delivered in a single request signed with the batch id
+#[derive(Serialize)]
+struct WebhookBatch<'a> {
+    pub id: Uuid,
+    pub timestamp: chrono::DateTime<chrono::Utc>,
+    pub events: Vec<BatchedWebhookEvent<'a>>,
 }
 
 #[derive(Serialize)]
+struct BatchedWebhookEvent<'a> {
+    pub id: Uuid,
+    #[serde(flatten)]
+    pub event: &'a WebhookEvent,
+}
+
+#[derive(Clone, Serialize)]
 struct WebhookEvent {
     #[serde(rename = "type")]
     pub event_type: String,
@@ -359,6 +1193,18 @@ struct CustomerData {
     pub balance_value_cents: i32,
 }
 
+#[derive(Serialize)]
+struct CustomerBillingEmailData {
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    pub billing_email: Option<String>,
+    /// unverified, verified, bounced or complained
+    pub status: String,
+    pub reason: Option<String>,
+    pub updated_at: Option<chrono::NaiveDateTime>,
+}
+
 #[derive(Serialize)]
 struct SubscriptionData {
     pub customer_name: String,
@@ -379,6 +1225,160 @@ struct InvoiceData {
     pub plan_name: Option<String>,
 }
 
+#[derive(Serialize)]
+struct InvoiceDeliveryData {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub billing_email: Option<String>,
+    pub currency: String,
+    pub total: i64,
+    pub amount_due: i64,
+    pub invoice_date: chrono::NaiveDate,
+    pub due_at: Option<chrono::NaiveDateTime>,
+    pub pdf_document_id: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InvoicePaidData {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub currency: String,
+    pub total: i64,
+    pub invoice_date: chrono::NaiveDate,
+}
+
+#[derive(Serialize)]
+struct InvoicePaymentRecordedData {
+    pub invoice_payment_id: Uuid,
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub currency: String,
+    pub amount: i64,
+    /// what remains due on the invoice, as of the delivery of the event
+    pub amount_due: i64,
+    pub paid_at: chrono::NaiveDate,
+    pub reference: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InvoiceStuckData {
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub reason: String,
+    pub invoice_date: chrono::NaiveDate,
+    pub last_issue_error: Option<String>,
+}
+
+#[derive(Serialize)]
+struct InvoicePaymentReminderData {
+    pub payment_reminder_id: Uuid,
+    pub invoice_id: Uuid,
+    pub invoice_number: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    pub currency: String,
+    pub amount_due: i64,
+    pub due_at: chrono::NaiveDateTime,
+    pub timing: PaymentReminderTiming,
+    /// days before or after the due date
+    pub days: u32,
+}
+
+#[derive(Serialize)]
+struct UsageAlertData {
+    pub usage_alert_id: Uuid,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    pub metric_id: Uuid,
+    pub metric_code: String,
+    pub threshold_type: String,
+    pub threshold_value: rust_decimal::Decimal,
+    pub usage: Option<rust_decimal::Decimal>,
+    pub period_start: Option<chrono::NaiveDate>,
+}
+
+#[derive(Serialize)]
+struct UsageAlertRuleData {
+    pub usage_alert_rule_id: Uuid,
+    pub usage_alert_rule_event_id: Uuid,
+    pub rule_name: String,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    pub metric_id: Uuid,
+    pub metric_code: String,
+    pub usage: rust_decimal::Decimal,
+    /// compared with the previous window of the same length, when a condition depends on it
+    pub growth_percent: Option<rust_decimal::Decimal>,
+    pub window_start: chrono::NaiveDate,
+    /// exclusive
+    pub window_end: chrono::NaiveDate,
+}
+
+#[derive(Serialize)]
+struct UsageCapData {
+    pub usage_cap_reached_id: Uuid,
+    pub subscription_id: Uuid,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    /// the subscription component or add-on
+    pub fee_id: Uuid,
+    pub fee_name: Option<String>,
+    pub metric_id: Option<Uuid>,
+    pub currency: String,
+    pub cap_cents: i64,
+    /// charges of the period before the cap applies
+    pub charges_cents: i64,
+    pub period_start: chrono::NaiveDate,
+    pub period_end: chrono::NaiveDate,
+    pub reached_at: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize)]
+struct CreditLimitExceededData {
+    pub credit_limit_exceeded_id: Uuid,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub customer_alias: Option<String>,
+    /// subscription or usage_threshold_invoice
+    pub source: String,
+    /// none for a blocked subscription
+    pub subscription_id: Option<Uuid>,
+    /// flag (allowed) or block (refused)
+    pub enforcement: String,
+    pub currency: String,
+    pub credit_limit_cents: i64,
+    pub exposure_cents: i64,
+    pub unpaid_invoices_cents: i64,
+    pub unbilled_usage_cents: i64,
+    pub exceeded_at: chrono::NaiveDateTime,
+}
+
+#[derive(Serialize)]
+struct SeatIncreaseRequestData {
+    pub seat_increase_request_id: Uuid,
+    pub subscription_id: Uuid,
+    pub customer_id: Uuid,
+    pub customer_name: String,
+    pub price_component_id: Uuid,
+    pub component_name: Option<String>,
+    /// seats to add, pending the approval of the tenant
+    pub seats: i32,
+    pub requested_by: Option<String>,
+    pub note: Option<String>,
+    pub requested_at: chrono::NaiveDateTime,
+}
+
 fn to_json<T: Serialize>(data: T) -> Result<serde_json::Value, EventBusError> {
     serde_json::to_value(data).map_err(|e| EventBusError::EventHandlerFailed(e.to_string()))
 }
@@ -386,9 +1386,31 @@ fn to_json<T: Serialize>(data: T) -> Result<serde_json::Value, EventBusError> {
 fn get_event_type(event: &Event) -> Option<WebhookOutEventTypeEnum> {
     match &event.event_data {
         EventData::CustomerCreated(_) => Some(WebhookOutEventTypeEnum::CustomerCreated),
+        EventData::CustomerBillingEmailUpdated(_) => {
+            Some(WebhookOutEventTypeEnum::CustomerBillingEmailUpdated)
+        }
         EventData::SubscriptionCreated(_) => Some(WebhookOutEventTypeEnum::SubscriptionCreated),
         EventData::InvoiceCreated(_) => Some(WebhookOutEventTypeEnum::InvoiceCreated),
         EventData::InvoiceFinalized(_) => Some(WebhookOutEventTypeEnum::InvoiceFinalized),
+        EventData::UsageAlertTriggered(_) => Some(WebhookOutEventTypeEnum::UsageAlertTriggered),
+        EventData::InvoiceStuck(_) => Some(WebhookOutEventTypeEnum::InvoiceStuck),
+        EventData::InvoiceDeliveryRequested(_) => {
+            Some(WebhookOutEventTypeEnum::InvoiceDeliveryRequested)
+        }
+        EventData::InvoicePaid(_) => Some(WebhookOutEventTypeEnum::InvoicePaid),
+        EventData::InvoicePaymentRecorded(_) => {
+            Some(WebhookOutEventTypeEnum::InvoicePaymentRecorded)
+        }
+        EventData::InvoicePaymentReminder(_) => {
+            Some(WebhookOutEventTypeEnum::InvoicePaymentReminder)
+        }
+        EventData::UsageAlertRuleFiring(_) => Some(WebhookOutEventTypeEnum::UsageAlertRuleFiring),
+        EventData::UsageAlertRuleResolved(_) => {
+            Some(WebhookOutEventTypeEnum::UsageAlertRuleResolved)
+        }
+        EventData::UsageCapReached(_) => Some(WebhookOutEventTypeEnum::UsageCapReached),
+        EventData::CreditLimitExceeded(_) => Some(WebhookOutEventTypeEnum::CreditLimitExceeded),
+        EventData::SeatIncreaseRequested(_) => Some(WebhookOutEventTypeEnum::SeatIncreaseRequested),
         _ => None,
     }
 }
@@ -396,9 +1418,21 @@ fn get_event_type(event: &Event) -> Option<WebhookOutEventTypeEnum> {
 fn get_tenant_event_details(event: &Event) -> Option<&TenantEventDataDetails> {
     match &event.event_data {
         EventData::CustomerCreated(d) => Some(d),
+        EventData::CustomerBillingEmailUpdated(d) => Some(d),
         EventData::SubscriptionCreated(d) => Some(d),
         EventData::InvoiceCreated(d) => Some(d),
         EventData::InvoiceFinalized(d) => Some(d),
+        EventData::UsageAlertTriggered(d) => Some(d),
+        EventData::InvoiceStuck(d) => Some(d),
+        EventData::InvoiceDeliveryRequested(d) => Some(d),
+        EventData::InvoicePaid(d) => Some(d),
+        EventData::InvoicePaymentRecorded(d) => Some(d),
+        EventData::InvoicePaymentReminder(d) => Some(d),
+        EventData::UsageAlertRuleFiring(d) => Some(d),
+        EventData::UsageAlertRuleResolved(d) => Some(d),
+        EventData::UsageCapReached(d) => Some(d),
+        EventData::CreditLimitExceeded(d) => Some(d),
+        EventData::SeatIncreaseRequested(d) => Some(d),
         _ => None,
     }
 }
@@ -423,6 +1457,7 @@ async fn get_active_endpoints_by_tenant(
                     url: e.url.to_string(),
                     secret: secret.expose_secret().to_string(),
                     event_types: e.events_to_listen,
+                    batching: e.batching,
                 })
             } else {
                 None
@@ -448,3 +1483,142 @@ async fn get_active_endpoints_by_tenant_cached(
 ) -> Result<Vec<Endpoint>, EventBusError> {
     get_active_endpoints_by_tenant(store, tenant_id, crypt_key).await
 }
+
+/// Periodically sends the batches that waited long enough
+pub fn start_batch_flusher(handler: Arc<WebhookHandler>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(BATCH_FLUSH_INTERVAL);
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+
+        loop {
+            interval.tick().await;
+            handler.flush_due_batches().await;
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn endpoint(max_events: u32) -> Endpoint {
+        Endpoint {
+            id: Uuid::now_v7(),
+            url: "https://example.com/".to_string(),
+            secret: "secret".to_string(),
+            event_types: vec![WebhookOutEventTypeEnum::CustomerCreated],
+            batching: Some(WebhookOutBatching {
+                max_events,
+                max_wait_seconds: 60,
+            }),
+        }
+    }
+
+    fn event() -> BatchedEvent {
+        BatchedEvent {
+            event_id: Uuid::now_v7(),
+            event_type: WebhookOutEventTypeEnum::CustomerCreated,
+            webhook_event: WebhookEvent {
+                event_type: "customer.created".to_string(),
+                timestamp: chrono::Utc::now(),
+                data: serde_json::Value::Null,
+            },
+            payload: vec![],
+            attempts: 0,
+        }
+    }
+
+    fn batch(endpoint: &Endpoint, events: Vec<BatchedEvent>) -> PendingBatch {
+        PendingBatch {
+            endpoint: endpoint.clone(),
+            opened_at: Instant::now(),
+            events,
+        }
+    }
+
+    fn ids(batch: &PendingBatch) -> Vec<Uuid> {
+        batch.events.iter().map(|e| e.event_id).collect()
+    }
+
+    #[test]
+    fn test_split_batch() {
+        let endpoint = endpoint(2);
+        let events: Vec<BatchedEvent> = (0..5).map(|_| event()).collect();
+        let event_ids: Vec<Uuid> = events.iter().map(|e| e.event_id).collect();
+
+        let (full_batches, rest) = batch(&endpoint, events).split();
+
+        assert_eq!(full_batches.len(), 2);
+        assert_eq!(ids(&full_batches[0]), event_ids[0..2]);
+        assert_eq!(ids(&full_batches[1]), event_ids[2..4]);
+        assert_eq!(ids(&rest.unwrap()), event_ids[4..]);
+
+        // exactly full
+        let (full_batches, rest) = batch(&endpoint, vec![event(), event()]).split();
+        assert_eq!(full_batches.len(), 1);
+        assert!(rest.is_none());
+
+        let (full_batches, rest) = batch(&endpoint, vec![event()]).split();
+        assert!(full_batches.is_empty());
+        assert_eq!(rest.unwrap().events.len(), 1);
+    }
+
+    #[test]
+    fn test_requeue_failed_batch() {
+        let endpoint = endpoint(10);
+        let mut batches = HashMap::new();
+
+        let failed = batch(&endpoint, vec![event(), event()]);
+        let failed_ids = ids(&failed);
+
+        assert_eq!(
+            requeue_failed_batch(&mut batches, failed, Instant::now()),
+            0
+        );
+        assert_eq!(ids(&batches[&endpoint.id]), failed_ids);
+        assert!(batches[&endpoint.id].events.iter().all(|e| e.attempts == 1));
+
+        // the retried events go ahead of the ones queued since
+        let queued = event();
+        let queued_id = queued.event_id;
+        batches.get_mut(&endpoint.id).unwrap().events = vec![queued];
+
+        let failed = batch(&endpoint, vec![event()]);
+        let retried_id = failed.events[0].event_id;
+        requeue_failed_batch(&mut batches, failed, Instant::now());
+
+        assert_eq!(ids(&batches[&endpoint.id]), vec![retried_id, queued_id]);
+    }
+
+    #[test]
+    fn test_requeue_drops_after_max_attempts() {
+        let endpoint = endpoint(10);
+        let mut batches = HashMap::new();
+
+        let mut last_attempt = event();
+        last_attempt.attempts = BATCH_MAX_ATTEMPTS - 1;
+        let retried = event();
+        let retried_id = retried.event_id;
+
+        let dropped = requeue_failed_batch(
+            &mut batches,
+            batch(&endpoint, vec![last_attempt, retried]),
+            Instant::now(),
+        );
+
+        assert_eq!(dropped, 1);
+        assert_eq!(ids(&batches[&endpoint.id]), vec![retried_id]);
+
+        // nothing left to retry
+        let mut batches = HashMap::new();
+        let mut last_attempt = event();
+        last_attempt.attempts = BATCH_MAX_ATTEMPTS - 1;
+
+        requeue_failed_batch(
+            &mut batches,
+            batch(&endpoint, vec![last_attempt]),
+            Instant::now(),
+        );
+        assert!(batches.is_empty());
+    }
+}
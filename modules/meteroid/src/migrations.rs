@@ -1,3 +1,5 @@
+use ::diesel::migration::MigrationSource;
+use ::diesel::pg::Pg;
 use diesel_async::async_connection_wrapper::AsyncConnectionWrapper;
 use diesel_migrations::MigrationHarness;
 use meteroid_store::store::{PgConn, PgPool};
@@ -35,6 +37,76 @@ pub async fn run(pool: &PgPool) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// The migrations embedded in this build, compared to the ones applied to the database
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MigrationStatus {
+    pub applied: Vec<String>,
+    /// embedded in this build, not applied yet
+    pub pending: Vec<String>,
+    /// applied to the database but unknown to this build, ex: by a newer version
+    pub unknown: Vec<String>,
+}
+
+impl MigrationStatus {
+    fn compare(mut embedded: Vec<String>, mut applied: Vec<String>) -> Self {
+        embedded.sort();
+        applied.sort();
+
+        MigrationStatus {
+            pending: embedded
+                .iter()
+                .filter(|v| !applied.contains(v))
+                .cloned()
+                .collect(),
+            unknown: applied
+                .iter()
+                .filter(|v| !embedded.contains(v))
+                .cloned()
+                .collect(),
+            applied,
+        }
+    }
+
+    /// The schema differs from the one of this build otherwise than by the migrations to apply:
+    /// unknown migrations were applied, or pending ones are older than the last applied
+    pub fn has_drift(&self) -> bool {
+        let last_applied = self.applied.iter().max();
+
+        !self.unknown.is_empty()
+            || self
+                .pending
+                .iter()
+                .any(|v| last_applied.is_some_and(|last| v < last))
+    }
+}
+
+pub async fn status(
+    pool: &PgPool,
+) -> Result<MigrationStatus, Box<dyn std::error::Error + Send + Sync>> {
+    let conn = pool.get().await?;
+    let mut async_wrapper: AsyncConnectionWrapper<PgConn> = AsyncConnectionWrapper::from(conn);
+
+    let status = tokio::task::spawn_blocking(move || {
+        let applied = async_wrapper
+            .applied_migrations()
+            .map_err(DieselMigrationError::GetMigrationsError)?
+            .into_iter()
+            .map(|v| v.to_string())
+            .collect();
+
+        let embedded = MigrationSource::<Pg>::migrations(&diesel::MIGRATIONS)
+            .map_err(DieselMigrationError::GetMigrationsError)?
+            .iter()
+            .map(|m| m.name().version().to_string())
+            .collect();
+
+        Ok::<_, DieselMigrationError>(MigrationStatus::compare(embedded, applied))
+    })
+    .await??;
+
+    Ok(status)
+}
+
 #[derive(Debug, Error)]
 pub enum DieselMigrationError {
     #[error("ApplyError: {0}")]
@@ -42,3 +114,57 @@ pub enum DieselMigrationError {
     #[error("GetMigrationsError: {0}")]
     GetMigrationsError(#[source] Box<dyn Error + Send + Sync>),
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn versions(v: &[&str]) -> Vec<String> {
+        v.iter().map(|s| s.to_string()).collect()
+    }
+
+    #[test]
+    fn test_up_to_date() {
+        let status = MigrationStatus::compare(
+            versions(&["20241101100000", "20241102090000"]),
+            versions(&["20241102090000", "20241101100000"]),
+        );
+
+        assert!(status.pending.is_empty());
+        assert!(status.unknown.is_empty());
+        assert!(!status.has_drift());
+    }
+
+    #[test]
+    fn test_pending_upgrade() {
+        let status = MigrationStatus::compare(
+            versions(&["20241101100000", "20241102090000", "20241102100000"]),
+            versions(&["20241101100000"]),
+        );
+
+        assert_eq!(
+            status.pending,
+            versions(&["20241102090000", "20241102100000"])
+        );
+        assert!(!status.has_drift());
+    }
+
+    #[test]
+    fn test_drift() {
+        // applied by a newer build
+        let status = MigrationStatus::compare(
+            versions(&["20241101100000"]),
+            versions(&["20241101100000", "20241102090000"]),
+        );
+        assert_eq!(status.unknown, versions(&["20241102090000"]));
+        assert!(status.has_drift());
+
+        // skipped migration, older than the last applied
+        let status = MigrationStatus::compare(
+            versions(&["20241101100000", "20241102090000", "20241102100000"]),
+            versions(&["20241101100000", "20241102100000"]),
+        );
+        assert_eq!(status.pending, versions(&["20241102090000"]));
+        assert!(status.has_drift());
+    }
+}
@@ -3,7 +3,8 @@ use error_stack::ResultExt;
 use fake::Fake;
 use meteroid_store::domain::enums::{
     BillingMetricAggregateEnum, BillingPeriodEnum, InvoiceStatusEnum, InvoiceType,
-    InvoicingProviderEnum, PlanStatusEnum, PlanTypeEnum, TenantEnvironmentEnum,
+    InvoicingProviderEnum, PlanStatusEnum, PlanTypeEnum, SubscriptionBillingModeEnum,
+    TenantEnvironmentEnum,
 };
 
 use meteroid_store::domain as store_domain;
@@ -57,6 +58,7 @@ pub async fn run(
             store_domain::TenantNew {
                 name: scenario.tenant.name,
                 environment: TenantEnvironmentEnum::Sandbox,
+                production_tenant_id: None,
             },
             organization_id,
         )
@@ -94,6 +96,9 @@ pub async fn run(
                 description: None,
                 created_by: user_id,
                 family_external_id: product_family.external_id.clone(),
+                quantity_billing_precision: None,
+                quantity_display_precision: None,
+                event_schema: None,
             })
             .await
             .change_context(SeederError::TempError)?;
@@ -119,10 +124,11 @@ pub async fn run(
                     is_draft_version: false,
                     trial: plan.version_details.trial.clone(),
                     period_start_day: plan.version_details.period_start_day,
-                    net_terms: plan.version_details.net_terms,
+                    net_terms: Some(plan.version_details.net_terms),
                     currency: Some(plan.version_details.currency),
                     billing_cycles: plan.version_details.billing_cycles,
                     billing_periods: plan.version_details.billing_periods,
+                    tax_category: None,
                 },
                 price_components: plan
                     .components
@@ -186,6 +192,9 @@ pub async fn run(
                 alias: Some(alias),
                 name: company_name.to_string(),
                 shipping_address: None,
+                net_terms_override: None,
+                early_payment_discount: None,
+                due_date_policy: None,
             });
         });
     }
@@ -294,6 +303,7 @@ pub async fn run(
             customer_id: customer.id,
             currency: "EUR".to_string(), // TODO
             billing_day: version.period_start_day.unwrap_or(1),
+            billing_mode: SubscriptionBillingModeEnum::Anniversary,
             trial_start_date,
             billing_start_date,
             billing_end_date,
@@ -303,6 +313,7 @@ pub async fn run(
             invoice_memo: None,
             invoice_threshold: None,
             activated_at,
+            minimum_commitment: None,
         };
 
         let create_subscription_components = if parameterized_components.is_empty() {
@@ -321,6 +332,7 @@ pub async fn run(
             price_components: create_subscription_components,
             add_ons: None, // todo generate add-ons
             coupons: None, // todo generate coupons
+            phases: vec![],
         };
 
         subscriptions_to_create.push(params);
@@ -497,14 +509,18 @@ pub async fn run(
                     alias: customer.alias.clone(),
                     email: customer.email.clone(),
                     vat_number: None,
+                    early_payment_discount: None,
                 },
                 seller_details: InlineInvoicingEntity {
                     id: invoicing_entity.id,
                     legal_name: invoicing_entity.legal_name.clone(),
                     vat_number: invoicing_entity.vat_number.clone(),
                     address: invoicing_entity.address(),
+                    tax_registrations: vec![],
                     snapshot_at: subscription.created_at,
                 },
+                billing_cadence: None,
+                replaces_invoice_id: None,
             };
 
             invoices_to_create.push(invoice);
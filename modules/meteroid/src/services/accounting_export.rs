@@ -0,0 +1,159 @@
+use std::sync::Arc;
+
+use async_trait::async_trait;
+use bytes::Bytes;
+use error_stack::{Report, Result, ResultExt};
+
+use meteroid_store::domain::accounting_exports::{
+    journal_entries_to_csv, AccountingExportConfig, AccountingExportRun, AccountingExportRunNew,
+    AccountingPeriod, JournalEntry,
+};
+use meteroid_store::domain::enums::{AccountingConnectorEnum, AccountingExportRunStatusEnum};
+use meteroid_store::repositories::accounting_exports::AccountingExportInterface;
+use meteroid_store::Store;
+
+use crate::adapters::quickbooks::QuickBooks;
+use crate::errors::AccountingExportError;
+use crate::services::storage::{ObjectStoreService, Prefix};
+
+/// Sends the journal entries of a period to where the tenant keeps its books
+#[async_trait]
+pub trait AccountingConnector: Send + Sync {
+    /// Returns the exported file, or the last record created in the accounting system
+    async fn export(
+        &self,
+        config: &AccountingExportConfig,
+        entries: &[JournalEntry],
+    ) -> Result<Option<String>, AccountingExportError>;
+}
+
+/// Writes the entries to a CSV file in the object store, to download or to import in any accounting system
+pub struct CsvExporter {
+    object_store: Arc<dyn ObjectStoreService>,
+}
+
+#[async_trait]
+impl AccountingConnector for CsvExporter {
+    async fn export(
+        &self,
+        _config: &AccountingExportConfig,
+        entries: &[JournalEntry],
+    ) -> Result<Option<String>, AccountingExportError> {
+        let csv = journal_entries_to_csv(entries);
+
+        let document_id = self
+            .object_store
+            .store(Bytes::from(csv), Prefix::AccountingExport)
+            .await
+            .change_context(AccountingExportError::StorageError)?;
+
+        Ok(Some(document_id.to_string()))
+    }
+}
+
+/// Creates the entries in the QuickBooks Online company of the tenant
+pub struct QuickBooksExporter {
+    store: Store,
+    adapter: &'static QuickBooks,
+}
+
+#[async_trait]
+impl AccountingConnector for QuickBooksExporter {
+    async fn export(
+        &self,
+        config: &AccountingExportConfig,
+        entries: &[JournalEntry],
+    ) -> Result<Option<String>, AccountingExportError> {
+        let credentials = config
+            .quickbooks
+            .as_ref()
+            .ok_or_else(|| Report::new(AccountingExportError::NotConfigured))
+            .attach_printable("The tenant did not authorize a QuickBooks company")?;
+
+        let session = self.adapter.open_session(credentials).await?;
+
+        // the previous refresh token is revoked, the next exports depend on this one
+        self.store
+            .update_quickbooks_credentials(config.tenant_id, session.credentials.clone())
+            .await
+            .change_context(AccountingExportError::StoreError)?;
+
+        let mut last_id = None;
+        for entry in entries {
+            last_id = Some(self.adapter.create_journal_entry(&session, entry).await?);
+        }
+
+        Ok(last_id)
+    }
+}
+
+pub fn accounting_connector(
+    connector: AccountingConnectorEnum,
+    store: &Store,
+    object_store: &Arc<dyn ObjectStoreService>,
+) -> Box<dyn AccountingConnector> {
+    match connector {
+        AccountingConnectorEnum::Csv => Box::new(CsvExporter {
+            object_store: object_store.clone(),
+        }),
+        AccountingConnectorEnum::QuickbooksOnline => Box::new(QuickBooksExporter {
+            store: store.clone(),
+            adapter: QuickBooks::get(),
+        }),
+    }
+}
+
+/// Exports the journal entries of the period and records the run, failed or not.
+/// A `scheduled` run moves the schedule of the tenant past the period once exported
+pub async fn run_accounting_export(
+    store: &Store,
+    object_store: &Arc<dyn ObjectStoreService>,
+    config: &AccountingExportConfig,
+    period: AccountingPeriod,
+    scheduled: bool,
+) -> Result<AccountingExportRun, AccountingExportError> {
+    let entries = store
+        .build_journal_entries(config.tenant_id, &config.accounts, period)
+        .await
+        .change_context(AccountingExportError::StoreError)?;
+
+    let exported = accounting_connector(config.connector, store, object_store)
+        .export(config, &entries)
+        .await;
+
+    let run = match exported {
+        Ok(document_id) => AccountingExportRunNew {
+            tenant_id: config.tenant_id,
+            connector: config.connector,
+            period,
+            status: AccountingExportRunStatusEnum::Succeeded,
+            entries: entries.len() as i32,
+            document_id,
+            error: None,
+        },
+        Err(e) => {
+            log::warn!(
+                "Accounting export of tenant {} from {} to {} failed: {:?}",
+                config.tenant_id,
+                period.start,
+                period.end,
+                e
+            );
+
+            AccountingExportRunNew {
+                tenant_id: config.tenant_id,
+                connector: config.connector,
+                period,
+                status: AccountingExportRunStatusEnum::Failed,
+                entries: entries.len() as i32,
+                document_id: None,
+                error: Some(e.current_context().to_string()),
+            }
+        }
+    };
+
+    store
+        .record_accounting_export_run(run, scheduled)
+        .await
+        .change_context(AccountingExportError::StoreError)
+}
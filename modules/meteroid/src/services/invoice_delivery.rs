@@ -0,0 +1,139 @@
+use crate::adapters::invoicing::InvoicingAdapters;
+use crate::errors;
+use async_trait::async_trait;
+use error_stack::{Report, Result, ResultExt};
+use meteroid_store::domain::configs::ApiSecurity;
+use meteroid_store::domain::enums::{InvoiceDeliveryChannelEnum, InvoicingProviderEnum};
+use meteroid_store::domain::{Customer, ExternalInvoiceLinks, Invoice};
+use meteroid_store::repositories::invoice_delivery::InvoiceDeliveryInterface;
+use meteroid_store::Store;
+
+/// The invoice as sent to the invoicing provider
+pub struct IssuedInvoice {
+    pub external_invoice_id: String,
+    /// the provider it was sent to, which differs from the one of the invoice when falling back
+    pub provider: InvoicingProviderEnum,
+    pub api_security: ApiSecurity,
+}
+
+/// Sends an issued invoice to the customer
+#[async_trait]
+pub trait InvoiceDelivery: Send + Sync {
+    fn channel(&self) -> InvoiceDeliveryChannelEnum;
+
+    /// Returns the pages of the invoice at the provider, if the channel provides them
+    async fn deliver(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+        issued: Option<&IssuedInvoice>,
+    ) -> Result<ExternalInvoiceLinks, errors::WorkerError>;
+}
+
+/// Emails the invoice to the billing email of the customer, through the outbox
+pub struct EmailDelivery {
+    store: Store,
+}
+
+#[async_trait]
+impl InvoiceDelivery for EmailDelivery {
+    fn channel(&self) -> InvoiceDeliveryChannelEnum {
+        InvoiceDeliveryChannelEnum::Email
+    }
+
+    async fn deliver(
+        &self,
+        invoice: &Invoice,
+        customer: &Customer,
+        _issued: Option<&IssuedInvoice>,
+    ) -> Result<ExternalInvoiceLinks, errors::WorkerError> {
+        self.store
+            .request_invoice_delivery_email(invoice, customer)
+            .await
+            .change_context(errors::WorkerError::DeliveryError)?;
+
+        Ok(ExternalInvoiceLinks::default())
+    }
+}
+
+/// Leaves the delivery to the webhook endpoints of the tenant
+pub struct WebhookDelivery {
+    store: Store,
+}
+
+#[async_trait]
+impl InvoiceDelivery for WebhookDelivery {
+    fn channel(&self) -> InvoiceDeliveryChannelEnum {
+        InvoiceDeliveryChannelEnum::Webhook
+    }
+
+    async fn deliver(
+        &self,
+        invoice: &Invoice,
+        _customer: &Customer,
+        _issued: Option<&IssuedInvoice>,
+    ) -> Result<ExternalInvoiceLinks, errors::WorkerError> {
+        self.store
+            .request_invoice_delivery_webhook(invoice)
+            .await
+            .change_context(errors::WorkerError::DeliveryError)?;
+
+        Ok(ExternalInvoiceLinks::default())
+    }
+}
+
+/// Finalizes the invoice at the provider, which hosts the page the customer pays it on
+pub struct ProviderHostedDelivery {
+    adapters: InvoicingAdapters,
+}
+
+#[async_trait]
+impl InvoiceDelivery for ProviderHostedDelivery {
+    fn channel(&self) -> InvoiceDeliveryChannelEnum {
+        InvoiceDeliveryChannelEnum::ProviderHosted
+    }
+
+    async fn deliver(
+        &self,
+        invoice: &Invoice,
+        _customer: &Customer,
+        issued: Option<&IssuedInvoice>,
+    ) -> Result<ExternalInvoiceLinks, errors::WorkerError> {
+        let issued = issued
+            .ok_or_else(|| Report::new(errors::WorkerError::ProviderNotConfigured))
+            .attach_printable_lazy(|| {
+                format!(
+                    "Invoice {} was not sent to an invoicing provider, it cannot be hosted",
+                    invoice.id
+                )
+            })?;
+
+        let adapter = self
+            .adapters
+            .for_provider(&issued.provider)
+            .ok_or_else(|| Report::new(errors::WorkerError::ProviderNotConfigured))?;
+
+        adapter
+            .finalize_hosted_invoice(&issued.external_invoice_id, &issued.api_security)
+            .await
+            .change_context(errors::WorkerError::ProviderError)
+    }
+}
+
+pub fn invoice_delivery(
+    channel: InvoiceDeliveryChannelEnum,
+    store: &Store,
+    adapters: &InvoicingAdapters,
+) -> Box<dyn InvoiceDelivery> {
+    match channel {
+        InvoiceDeliveryChannelEnum::Email => Box::new(EmailDelivery {
+            store: store.clone(),
+        }),
+        InvoiceDeliveryChannelEnum::Webhook => Box::new(WebhookDelivery {
+            store: store.clone(),
+        }),
+        InvoiceDeliveryChannelEnum::ProviderHosted => Box::new(ProviderHostedDelivery {
+            adapters: adapters.clone(),
+        }),
+    }
+}
@@ -5,11 +5,15 @@ use base64::Engine;
 use error_stack::ResultExt;
 use image::ImageFormat::Png;
 use meteroid_invoicing::{html_render, pdf};
+use meteroid_store::constants::Countries;
+use meteroid_store::domain::enums::NotificationNamespaceEnum;
 use meteroid_store::domain::{Invoice, InvoicingEntity};
 use meteroid_store::repositories::historical_rates::HistoricalRatesInterface;
 use meteroid_store::repositories::invoicing_entities::InvoicingEntityInterface;
+use meteroid_store::repositories::notification_bundles::NotificationBundleInterface;
 use meteroid_store::repositories::InvoiceInterface;
 use meteroid_store::Store;
+use std::collections::HashMap;
 use std::io::Cursor;
 use std::sync::Arc;
 use uuid::Uuid;
@@ -53,6 +57,8 @@ impl HtmlRenderingService {
                 .change_context(InvoicingRenderError::StoreError)?;
         }
 
+        let labels = tenant_labels(&self.store, tenant_id, &invoicing_entity).await?;
+
         let mapped = mapper::map_invoice_to_invoicing(
             invoice.invoice,
             &invoicing_entity,
@@ -61,6 +67,7 @@ impl HtmlRenderingService {
                 .as_ref()
                 .map(|id| format!("/api/files/v1/logo/{}", id)),
             rate,
+            labels,
         )?;
 
         let html_string = html_render::render_invoice(&mapped)
@@ -194,8 +201,15 @@ impl PdfRenderingService {
                 .change_context(InvoicingRenderError::StoreError)?;
         }
 
-        let mapped_invoice =
-            mapper::map_invoice_to_invoicing(invoice, invoicing_entity, &organization_logo, rate)?;
+        let labels = tenant_labels(&self.store, tenant_id, invoicing_entity).await?;
+
+        let mapped_invoice = mapper::map_invoice_to_invoicing(
+            invoice,
+            invoicing_entity,
+            &organization_logo,
+            rate,
+            labels,
+        )?;
 
         let html = html_render::render_invoice(&mapped_invoice)
             .change_context(InvoicingRenderError::RenderError)?
@@ -223,6 +237,23 @@ impl PdfRenderingService {
     }
 }
 
+/// The PDF messages of the tenant for the locale of the invoicing entity
+async fn tenant_labels(
+    store: &Store,
+    tenant_id: Uuid,
+    invoicing_entity: &InvoicingEntity,
+) -> error_stack::Result<HashMap<String, String>, InvoicingRenderError> {
+    store
+        .resolve_notification_messages(
+            tenant_id,
+            NotificationNamespaceEnum::Pdf,
+            Countries::resolve_locale(&invoicing_entity.country).to_string(),
+        )
+        .await
+        .map(|messages| messages.tenant_templates())
+        .change_context(InvoicingRenderError::StoreError)
+}
+
 mod mapper {
     use crate::errors::InvoicingRenderError;
     use error_stack::Report;
@@ -233,6 +264,7 @@ mod mapper {
     use meteroid_store::domain::historical_rates::HistoricalRate;
     use rust_decimal::prelude::FromPrimitive;
     use rust_decimal::Decimal;
+    use std::collections::HashMap;
 
     pub fn map_invoice_to_invoicing(
         invoice: store_model::Invoice,
@@ -240,6 +272,7 @@ mod mapper {
         // either link for preview or base64 for pdf
         organization_logo: &Option<String>,
         accounting_rate: Option<HistoricalRate>,
+        labels: HashMap<String, String>,
     ) -> error_stack::Result<invoicing_model::Invoice, InvoicingRenderError> {
         let finalized_date = invoice
             .finalized_at
@@ -293,6 +326,15 @@ mod mapper {
             logo_url: organization_logo.clone(),
             name: invoice.seller_details.legal_name,
             tax_id: invoice.seller_details.vat_number,
+            tax_registrations: invoice
+                .seller_details
+                .tax_registrations
+                .into_iter()
+                .map(|r| invoicing_model::TaxRegistration {
+                    label: r.registration_type.label().to_string(),
+                    number: r.registration_number,
+                })
+                .collect(),
             footer_info: invoicing_entity.invoice_footer_info.clone(),
             footer_legal: invoicing_entity.invoice_footer_legal.clone(),
             accounting_currency,
@@ -334,15 +376,15 @@ mod mapper {
                         unit_price: sub_line.unit_price,
                     })
                     .collect(),
+                quantity_precision: line.quantity_display_precision,
             })
             .collect();
 
-        let lang = Countries::resolve_country(&invoicing_entity.country)
-            .map(|c| c.locale)
-            .unwrap_or_else(|| "en-US");
+        let lang = Countries::resolve_locale(&invoicing_entity.country);
 
         Ok(invoicing_model::Invoice {
             lang: lang.to_string(),
+            labels,
             customer,
             lines,
             metadata,
@@ -1,4 +1,6 @@
+pub mod accounting_export;
 pub mod currency_rates;
+pub mod invoice_delivery;
 pub mod invoice_rendering;
 pub mod outbox;
 pub mod storage;
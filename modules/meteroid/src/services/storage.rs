@@ -14,7 +14,9 @@ use uuid::Uuid;
 pub enum Prefix {
     InvoicePdf,
     InvoiceXml,
+    InvoiceAttachment,
     ImageLogo,
+    AccountingExport,
     WebhookArchive {
         provider_uid: String,
         endpoint_uid: String,
@@ -26,7 +28,9 @@ impl Prefix {
         match self {
             Prefix::InvoicePdf => "invoice_pdf".to_string(),
             Prefix::InvoiceXml => "invoice_xml".to_string(),
+            Prefix::InvoiceAttachment => "invoice_attachment".to_string(),
             Prefix::ImageLogo => "image_logo".to_string(),
+            Prefix::AccountingExport => "accounting_export".to_string(),
             Prefix::WebhookArchive {
                 provider_uid,
                 endpoint_uid,
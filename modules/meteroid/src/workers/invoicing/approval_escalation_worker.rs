@@ -0,0 +1,69 @@
+/*
+    Goal : Escalate the steps of the invoice approvals that were not decided before their timeout.
+
+    The escalation assignee (or role) of the step can decide it from then on, along with the original assignee.
+    Each step is escalated once, and the escalation is recorded in the approval history of the invoice.
+*/
+use crate::errors;
+use crate::singletons;
+use crate::workers::metrics::record_call;
+use chrono::NaiveDateTime;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::repositories::invoice_approvals::InvoiceApprovalInterface;
+use meteroid_store::Store;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct ApprovalEscalationWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for ApprovalEscalationWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        approval_escalation_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("approval_escalation", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in approval escalation worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 6/10 * * * * *"; // every 10 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn approval_escalation_worker(
+    store: &Store,
+    now: NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let escalated = store
+        .escalate_overdue_invoice_approvals(now)
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    if escalated > 0 {
+        log::info!("Escalated {} overdue invoice approval steps", escalated);
+    }
+
+    Ok(())
+}
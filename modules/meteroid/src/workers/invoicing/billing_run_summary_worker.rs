@@ -0,0 +1,81 @@
+/*
+    Goal : Summarize each billing run (the last full hour of the draft, finalize and issue workers) per tenant :
+    invoices created, finalized and issued, their amounts per currency, and the issue failures with their reason.
+
+    Summaries are stored once per tenant and period, and can be listed through the invoices api.
+    Tenants with a finance email also get the summary by email.
+*/
+use crate::workers::metrics::record_call;
+use crate::{errors, singletons};
+use chrono::NaiveDateTime;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::domain::billing_runs::billing_run_period;
+use meteroid_store::repositories::billing_runs::BillingRunsInterface;
+use meteroid_store::Store;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct BillingRunSummaryWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for BillingRunSummaryWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        billing_run_summary_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("billing_run_summary", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in billing run summary worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 5 * * * * *"; // every hour, once the last issue run of the previous hour is done
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn billing_run_summary_worker(
+    store: &Store,
+    now: NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let (period_start, period_end) = billing_run_period(now);
+
+    let summaries = store
+        .summarize_billing_run(period_start, period_end)
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    for summary in summaries {
+        log::info!(
+            "Billing run {} - {} of tenant {} : {} created, {} finalized, {} issued, {} issue failures",
+            summary.period_start,
+            summary.period_end,
+            summary.tenant_id,
+            summary.invoices_created,
+            summary.invoices_finalized,
+            summary.invoices_issued,
+            summary.issue_failures
+        );
+    }
+
+    Ok(())
+}
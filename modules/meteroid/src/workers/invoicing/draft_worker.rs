@@ -1,18 +1,24 @@
+use crate::adapters::alerting::escalate_worker_failure;
 use crate::workers::metrics::record_call;
 use crate::{errors, singletons};
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
+use chrono_tz::Tz;
 
 use common_utils::timed::*;
 
 use error_stack::{Result, ResultExt};
 use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::FutureExt;
 
 use common_eventbus::Event;
 
 use meteroid_store::domain::CursorPaginationRequest;
 use meteroid_store::repositories::invoicing_entities::InvoicingEntityInterface;
-use meteroid_store::repositories::subscriptions::subscription_to_draft;
-use meteroid_store::repositories::{CustomersInterface, InvoiceInterface, SubscriptionInterface};
+use meteroid_store::repositories::subscription_pending_changes::SubscriptionPendingChangeInterface;
+use meteroid_store::repositories::{
+    CustomersInterface, InvoiceInterface, SubscriptionInterface, TenantInterface,
+};
+use meteroid_store::utils::datetime::local_date;
 use meteroid_store::Store;
 
 const BATCH_SIZE: usize = 100;
@@ -27,11 +33,12 @@ impl AsyncRunnable for DraftWorker {
     #[tracing::instrument(skip_all)]
     async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
         log::info!("Running draft worker");
-        draft_worker(
+        draft_worker_at(
             singletons::get_store().await,
-            chrono::Utc::now().naive_utc().date(),
+            chrono::Utc::now().naive_utc(),
         )
         .timed(|res, elapsed| record_call("draft", res, elapsed))
+        .then(|res| escalate_worker_failure("draft", res))
         .await
         .map_err(|err| {
             log::error!("Error in draft worker: {:?}", err);
@@ -55,14 +62,48 @@ impl AsyncRunnable for DraftWorker {
     }
 }
 
+/// Drafts the invoices of each billing timezone of the tenants, with the date of that timezone.
+/// The periods of a tenant in Asia/Singapore are cut at the local midnight, not at the UTC one
+#[tracing::instrument(skip_all)]
+pub async fn draft_worker_at(store: &Store, now: NaiveDateTime) -> Result<(), errors::WorkerError> {
+    let timezones = store
+        .list_tenant_billing_timezones()
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    for timezone in timezones {
+        draft_worker_in(store, local_date(now, timezone), &[timezone]).await?;
+    }
+
+    Ok(())
+}
+
+/// Drafts the invoices of all the tenants as of the given date, whatever their timezone
 #[tracing::instrument(skip_all)]
 pub async fn draft_worker(store: &Store, today: NaiveDate) -> Result<(), errors::WorkerError> {
+    let timezones = store
+        .list_tenant_billing_timezones()
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    draft_worker_in(store, today, &timezones).await
+}
+
+async fn draft_worker_in(
+    store: &Store,
+    today: NaiveDate,
+    timezones: &[Tz],
+) -> Result<(), errors::WorkerError> {
+    // changes scheduled for the renewal are applied first, so that the next period is drafted from the new terms
+    apply_pending_changes(store, today, timezones).await?;
+
     let mut last_processed_id = None;
 
     loop {
         let paginated_vec = store
             .list_subscription_invoice_candidates(
                 today,
+                timezones,
                 CursorPaginationRequest {
                     limit: Some(BATCH_SIZE as u32),
                     cursor: last_processed_id,
@@ -96,26 +137,27 @@ pub async fn draft_worker(store: &Store, today: NaiveDate) -> Result<(), errors:
             .await
             .change_context(errors::WorkerError::DatabaseError)?;
 
-        let params = paginated_vec
-            .items
-            .iter()
-            .map(|x| {
-                let cust = customers
-                    .iter()
-                    .find(|c| c.id == x.customer_id)
-                    .ok_or(errors::WorkerError::DatabaseError)?;
-
-                let invoicing_entity = invoicing_entities
-                    .iter()
-                    .find(|c| c.id == cust.invoicing_entity_id)
-                    .ok_or(errors::WorkerError::DatabaseError)?;
-
-                subscription_to_draft(x, cust, invoicing_entity)
-                    .change_context(errors::WorkerError::DatabaseError)
-            })
-            .collect::<Result<Vec<_>, _>>()?
-            .into_iter()
-            .collect::<Vec<_>>();
+        let mut params = Vec::with_capacity(paginated_vec.items.len());
+
+        for x in &paginated_vec.items {
+            let cust = customers
+                .iter()
+                .find(|c| c.id == x.customer_id)
+                .ok_or(errors::WorkerError::DatabaseError)?;
+
+            let invoicing_entity = invoicing_entities
+                .iter()
+                .find(|c| c.id == cust.invoicing_entity_id)
+                .ok_or(errors::WorkerError::DatabaseError)?;
+
+            // a draft per billing cadence when the tenant invoices them separately
+            let drafts = store
+                .draft_recurring_invoices(x, cust, invoicing_entity, today)
+                .await
+                .change_context(errors::WorkerError::DatabaseError)?;
+
+            params.extend(drafts);
+        }
 
         log::debug!("Creating {} draft invoices", params.len());
 
@@ -140,3 +182,49 @@ pub async fn draft_worker(store: &Store, today: NaiveDate) -> Result<(), errors:
 
     Ok(())
 }
+
+#[tracing::instrument(skip_all)]
+async fn apply_pending_changes(
+    store: &Store,
+    today: NaiveDate,
+    timezones: &[Tz],
+) -> Result<(), errors::WorkerError> {
+    let mut last_processed_id = None;
+
+    loop {
+        let paginated_vec = store
+            .list_due_subscription_pending_changes(
+                today,
+                timezones,
+                CursorPaginationRequest {
+                    limit: Some(BATCH_SIZE as u32),
+                    cursor: last_processed_id,
+                },
+            )
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for pending_change in &paginated_vec.items {
+            // a failing change must not block the drafts of the other subscriptions
+            if let Err(err) = store
+                .apply_subscription_pending_change(pending_change)
+                .await
+            {
+                log::error!(
+                    "Failed to apply pending change {} of subscription {}: {:?}",
+                    pending_change.id,
+                    pending_change.subscription_id,
+                    err
+                );
+            }
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
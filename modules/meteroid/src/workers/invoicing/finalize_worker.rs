@@ -6,11 +6,13 @@ use common_utils::timed::TimedExt;
 use error_stack::{Result, ResultExt};
 use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
 use futures::future::join_all;
+use futures::FutureExt;
 use meteroid_store::domain::CursorPaginationRequest;
 use meteroid_store::repositories::InvoiceInterface;
 use meteroid_store::Store;
 use tokio::sync::Semaphore;
 
+use crate::adapters::alerting::escalate_worker_failure;
 use crate::workers::metrics::record_call;
 
 const BATCH_SIZE: usize = 100;
@@ -27,6 +29,7 @@ impl AsyncRunnable for FinalizeWorker {
     async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
         finalize_worker(singletons::get_store().await)
             .timed(|res, elapsed| record_call("finalize", res, elapsed))
+            .then(|res| escalate_worker_failure("finalize", res))
             .await
             .map_err(|err| {
                 log::error!("Error in finalize worker: {}", err);
@@ -81,7 +84,7 @@ pub async fn finalize_worker(store: &Store) -> Result<(), errors::WorkerError> {
                 let _permit = permit; // Moves permit into the async block
 
                 let lines_result = store
-                    .finalize_invoice(invoice.id, invoice.tenant_id)
+                    .finalize_invoice(invoice.id, invoice.tenant_id, None)
                     .await
                     .change_context(errors::WorkerError::DatabaseError);
 
@@ -1,19 +1,23 @@
-use crate::adapters::stripe::Stripe;
-use crate::adapters::types::InvoicingAdapter;
+use crate::adapters::alerting::{escalate_worker_failure, Alert, Alerter};
+use crate::adapters::invoicing::InvoicingAdapters;
+use crate::services::invoice_delivery::{invoice_delivery, IssuedInvoice};
 use crate::workers::metrics::record_call;
-use crate::{errors, singletons};
+use crate::{config, errors, singletons};
 use common_utils::timed::TimedExt;
-use error_stack::{Result, ResultExt};
+use error_stack::{Report, Result, ResultExt};
 use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
 use futures::future::join_all;
-use meteroid_store::domain::enums::InvoicingProviderEnum;
+use futures::FutureExt;
+use meteroid_store::domain::enums::{InvoicingProviderEnum, OnboardingStepEnum};
 use meteroid_store::domain::CursorPaginationRequest;
 use meteroid_store::repositories::configs::ConfigsInterface;
+use meteroid_store::repositories::onboarding::OnboardingInterface;
 use meteroid_store::repositories::{CustomersInterface, InvoiceInterface};
 use meteroid_store::{domain, Store};
-use secrecy::SecretString;
+use std::collections::HashMap;
 use std::sync::Arc;
 use tokio::sync::Semaphore;
+use uuid::Uuid;
 
 const BATCH_SIZE: usize = 100;
 const MAX_CONCURRENT_REQUESTS: usize = 10;
@@ -27,15 +31,20 @@ pub struct IssueWorker;
 impl AsyncRunnable for IssueWorker {
     #[tracing::instrument(skip(self, _queue))]
     async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
-        issue_worker(singletons::get_store().await, Stripe::get())
-            .timed(|res, elapsed| record_call("issue", res, elapsed))
-            .await
-            .map_err(|err| {
-                log::error!("Error in issue worker: {}", err);
-                FangError {
-                    description: err.to_string(),
-                }
-            })
+        issue_worker(
+            singletons::get_store().await,
+            InvoicingAdapters::get(),
+            config::Config::get().alerting.issue_error_spike_threshold,
+        )
+        .timed(|res, elapsed| record_call("issue", res, elapsed))
+        .then(|res| escalate_worker_failure("issue", res))
+        .await
+        .map_err(|err| {
+            log::error!("Error in issue worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
     }
 
     fn cron(&self) -> Option<Scheduled> {
@@ -53,8 +62,12 @@ impl AsyncRunnable for IssueWorker {
 }
 
 #[tracing::instrument(skip_all)]
-async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), errors::WorkerError> {
-    // fetch all invoices with issue=false and send to stripe
+async fn issue_worker(
+    store: &Store,
+    adapters: &InvoicingAdapters,
+    error_spike_threshold: u32,
+) -> Result<(), errors::WorkerError> {
+    // fetch all invoices with issue=false and send to their provider
 
     let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
 
@@ -83,17 +96,33 @@ async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), erro
                 .change_context(errors::WorkerError::DatabaseError)?;
 
             let store = store.clone();
-            let stripe_adapter = stripe_adapter.clone();
+            let adapters = adapters.clone();
 
             let task = tokio::spawn(async move {
                 let _permit = permit; // Moves permit into the async block
 
-                let issue_result = issue_invoice(&invoice, &stripe_adapter, &store).await;
+                let (issue_result, delivery_channel) = match store
+                    .find_customer_by_id(invoice.customer_id, invoice.tenant_id)
+                    .await
+                    .change_context(errors::WorkerError::DatabaseError)
+                {
+                    Ok(customer) => (
+                        issue_and_deliver(&invoice, &customer, &adapters, &store).await,
+                        customer.invoice_delivery_channel,
+                    ),
+                    Err(e) => (Err(e), None),
+                };
 
-                match issue_result {
-                    Ok(_) => {
+                let failed_tenant_id = match issue_result {
+                    Ok((external_invoice_id, links)) => {
                         let res = store
-                            .invoice_issue_success(invoice.id, invoice.tenant_id)
+                            .invoice_issue_success(
+                                invoice.id,
+                                invoice.tenant_id,
+                                external_invoice_id,
+                                delivery_channel,
+                                links,
+                            )
                             .await;
 
                         if let Err(e) = res {
@@ -103,6 +132,8 @@ async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), erro
                                 e
                             )
                         }
+
+                        None
                     }
                     Err(e) => {
                         let res = store
@@ -110,6 +141,7 @@ async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), erro
                                 invoice.id,
                                 invoice.tenant_id,
                                 e.to_string().as_str(),
+                                delivery_channel,
                             )
                             .await;
 
@@ -120,10 +152,13 @@ async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), erro
                                 e
                             )
                         }
+
+                        Some(invoice.tenant_id)
                     }
-                }
+                };
 
                 //  drop(_permit) should not be necessary, TODO validate
+                failed_tenant_id
             });
             tasks.push(task);
         }
@@ -135,41 +170,148 @@ async fn issue_worker(store: &Store, stripe_adapter: &Stripe) -> Result<(), erro
         }
     }
 
-    join_all(tasks).await;
+    let failed_tenant_ids = join_all(tasks)
+        .await
+        .into_iter()
+        .filter_map(|res| res.ok().flatten());
+
+    for (tenant_id, failures) in issue_error_spikes(failed_tenant_ids, error_spike_threshold) {
+        Alerter::get()
+            .send(&Alert::issue_error_spike(tenant_id, failures))
+            .await;
+    }
 
     Ok(())
 }
 
+/// The tenants with at least `threshold` invoices that failed to be issued
+fn issue_error_spikes(
+    failed_tenant_ids: impl Iterator<Item = Uuid>,
+    threshold: u32,
+) -> Vec<(Uuid, u32)> {
+    let mut failures: HashMap<Uuid, u32> = HashMap::new();
+    for tenant_id in failed_tenant_ids {
+        *failures.entry(tenant_id).or_default() += 1;
+    }
+
+    failures
+        .into_iter()
+        .filter(|(_, count)| *count >= threshold)
+        .collect()
+}
+
+/// Issues the invoice to its provider, then delivers it through the channel of the customer.
+/// Returns the id of the invoice at the provider and the pages hosting it, if any
+#[tracing::instrument(skip_all)]
+async fn issue_and_deliver(
+    invoice: &domain::Invoice,
+    customer: &domain::Customer,
+    adapters: &InvoicingAdapters,
+    store: &Store,
+) -> Result<(Option<String>, domain::ExternalInvoiceLinks), errors::WorkerError> {
+    let issued = issue_invoice(invoice, customer, adapters, store).await?;
+
+    // a failed delivery is retried with the issuance, which is idempotent at the provider
+    let links = match customer.invoice_delivery_channel {
+        Some(channel) => {
+            let delivery = invoice_delivery(channel, store, adapters);
+
+            let links = delivery.deliver(invoice, customer, issued.as_ref()).await?;
+
+            log::info!(
+                "Invoice {} delivered through {:?}",
+                invoice.id,
+                delivery.channel()
+            );
+
+            links
+        }
+        None => domain::ExternalInvoiceLinks::default(),
+    };
+
+    Ok((issued.map(|i| i.external_invoice_id), links))
+}
+
+/// Returns the invoice at the provider, if sent
 #[tracing::instrument(skip_all)]
 async fn issue_invoice(
     invoice: &domain::Invoice,
-    stripe_adapter: &Stripe,
+    customer: &domain::Customer,
+    adapters: &InvoicingAdapters,
     store: &Store,
-) -> Result<(), errors::WorkerError> {
+) -> Result<Option<IssuedInvoice>, errors::WorkerError> {
     match invoice.invoicing_provider {
-        InvoicingProviderEnum::Stripe => {
-            let customer = store
-                .find_customer_by_id(invoice.customer_id, invoice.tenant_id)
+        InvoicingProviderEnum::Stripe
+        | InvoicingProviderEnum::Adyen
+        | InvoicingProviderEnum::GoCardless => {
+            // invoices cannot be sent to the customer until the seller details are filled in,
+            // skipping the step in the setup wizard does not provide them
+            store
+                .ensure_onboarding_step_detected(
+                    invoice.tenant_id,
+                    OnboardingStepEnum::InvoicingEntity,
+                )
                 .await
-                .change_context(errors::WorkerError::DatabaseError)?;
+                .change_context(errors::WorkerError::OnboardingIncomplete)?;
 
-            let api_key = store
-                .find_provider_config(InvoicingProviderEnum::Stripe, invoice.tenant_id)
+            // the requested provider first, then the tenant default and its fallbacks
+            let candidates = store
+                .resolve_invoicing_provider_configs(
+                    invoice.invoicing_provider.clone(),
+                    invoice.tenant_id,
+                )
                 .await
-                .change_context(errors::WorkerError::DatabaseError)?
-                .api_security
-                .api_key;
+                .change_context(errors::WorkerError::DatabaseError)?;
 
-            stripe_adapter
-                .send_invoice(invoice, &customer, SecretString::new(api_key))
-                .await
-                .change_context(errors::WorkerError::ProviderError)?;
+            let mut last_error = None;
 
-            Ok(())
-        }
-        InvoicingProviderEnum::Manual => {
-            log::warn!("Invoice has Manual provider so shouldn't be picked-up by issue_worker");
-            Ok(())
+            for config in candidates {
+                let Some(adapter) = adapters.for_provider(&config.invoicing_provider) else {
+                    // falling back to Manual after a provider failed would mark the invoice issued while nothing was sent
+                    if let Some(e) = last_error {
+                        log::warn!(
+                            "Invoice {} falls back to Manual provider after a failure, keeping the provider error",
+                            invoice.id
+                        );
+                        return Err(e);
+                    }
+
+                    log::info!(
+                        "Invoice {} falls back to Manual provider, it will not be sent",
+                        invoice.id
+                    );
+                    return Ok(None);
+                };
+
+                let res = adapter
+                    .send_invoice(invoice, customer, &config.api_security)
+                    .await
+                    .change_context(errors::WorkerError::ProviderError);
+
+                match res {
+                    Ok(external_invoice_id) => {
+                        return Ok(Some(IssuedInvoice {
+                            external_invoice_id,
+                            provider: config.invoicing_provider,
+                            api_security: config.api_security,
+                        }))
+                    }
+                    Err(e) => {
+                        log::warn!(
+                            "Failed to issue invoice {} with provider config {}, trying next fallback : {}",
+                            invoice.id,
+                            config.id,
+                            e
+                        );
+                        last_error = Some(e);
+                    }
+                }
+            }
+
+            Err(last_error
+                .unwrap_or_else(|| Report::new(errors::WorkerError::ProviderNotConfigured)))
         }
+        // picked-up only when delivered through a channel of the customer
+        InvoicingProviderEnum::Manual => Ok(None),
     }
 }
@@ -1,5 +1,10 @@
+pub mod approval_escalation_worker;
+pub mod billing_run_summary_worker;
 pub mod draft_worker;
 pub mod finalize_worker;
 pub mod issue_worker;
+pub mod payment_reminders_worker;
 pub mod pending_status_worker;
 pub mod price_worker;
+pub mod usage_threshold_worker;
+pub mod watchdog_worker;
@@ -0,0 +1,90 @@
+/*
+    Goal : Remind the customers of their unpaid invoices, following the reminder schedules of their tenant
+    (days before or after the due date of the invoice, by email or webhook).
+
+    Each reminder is logged per invoice and sent once. Paid, voided or uncollectible invoices are not reminded anymore,
+    and reminders that could not be sent within a few days of their date are dropped.
+*/
+use crate::errors;
+use crate::singletons;
+use crate::workers::metrics::record_call;
+use chrono::NaiveDateTime;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::repositories::payment_reminders::PaymentRemindersInterface;
+use meteroid_store::Store;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct PaymentRemindersWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for PaymentRemindersWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        payment_reminders_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("payment_reminders", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in payment reminders worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 10 * * * * *"; // every hour
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn payment_reminders_worker(
+    store: &Store,
+    now: NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let reminders = store
+        .list_due_payment_reminders(now)
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    for reminder in reminders {
+        let invoice_id = reminder.invoice_id;
+        let offset_days = reminder.offset_days;
+
+        match store.send_payment_reminder(reminder, now).await {
+            Ok(Some(sent)) => log::info!(
+                "Payment reminder {} sent for invoice {} ({} days from due date, {:?})",
+                sent.id,
+                invoice_id,
+                offset_days,
+                sent.channel
+            ),
+            Ok(None) => {}
+            Err(e) => {
+                // sent on the next run, unless it is too late by then
+                log::error!(
+                    "Failed to send payment reminder for invoice {} : {}",
+                    invoice_id,
+                    e
+                )
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -1,7 +1,9 @@
 use crate::{errors, singletons};
 use chrono::NaiveDateTime;
 use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::FutureExt;
 
+use crate::adapters::alerting::escalate_worker_failure;
 use crate::workers::metrics::record_call;
 use common_utils::timed::TimedExt;
 use error_stack::{Result, ResultExt};
@@ -22,6 +24,7 @@ impl AsyncRunnable for PendingStatusWorker {
             chrono::Utc::now().naive_utc(),
         )
         .timed(|res, elapsed| record_call("pending", res, elapsed))
+        .then(|res| escalate_worker_failure("pending", res))
         .await
         .map_err(|err| {
             log::error!("Error in pending_status worker: {}", err);
@@ -6,13 +6,17 @@ use std::sync::Arc;
 
 use crate::{errors, singletons};
 
+use crate::adapters::alerting::escalate_worker_failure;
 use crate::workers::metrics::record_call;
 use common_utils::timed::TimedExt;
 use error_stack::{Result, ResultExt};
 use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
 use futures::future::join_all;
+use futures::FutureExt;
 
+use meteroid_store::domain::usage_rerating::UsageRerating;
 use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::usage_rerating::UsageReratingInterface;
 use meteroid_store::repositories::InvoiceInterface;
 use meteroid_store::Store;
 use tokio::sync::Semaphore;
@@ -22,6 +26,7 @@ We stream open invoices whose price has not been updated in the last hour/period
 We compute the invoice amount
 We update the invoice with the new amount (ONLY IF status is not finalized/voided) & update the date
 
+The finalized invoices with backfilled usage in their period are re-rated, the difference is invoiced or credited
 */
 // let semaphore = Semaphore::new(MAX_CONCURRENT_REQUESTS);
 
@@ -40,6 +45,7 @@ impl AsyncRunnable for PriceWorker {
     async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
         price_worker(singletons::get_store().await)
             .timed(|res, elapsed| record_call("price", res, elapsed))
+            .then(|res| escalate_worker_failure("price", res))
             .await
             .map_err(|err| {
                 log::error!("Error in price worker: {}", err);
@@ -120,5 +126,47 @@ pub async fn price_worker(store: &Store) -> Result<(), errors::WorkerError> {
 
     join_all(tasks).await;
 
+    rerate_finalized_invoices(store).await
+}
+
+async fn rerate_finalized_invoices(store: &Store) -> Result<(), errors::WorkerError> {
+    let mut last_processed_id = None;
+
+    loop {
+        let paginated_vec = store
+            .list_usage_outdated_invoices(CursorPaginationRequest {
+                limit: Some(BATCH_SIZE as u32),
+                cursor: last_processed_id,
+            })
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for invoice in paginated_vec.items.into_iter() {
+            match store
+                .rerate_finalized_invoice(invoice.tenant_id, invoice.id)
+                .await
+            {
+                Ok(UsageRerating::Invoiced(adjustment)) => log::info!(
+                    "Invoiced the backfilled usage of invoice {} in {}",
+                    &invoice.id,
+                    &adjustment.id
+                ),
+                Ok(UsageRerating::Credited(amount)) => log::info!(
+                    "Credited {} for the backfilled usage of invoice {}",
+                    amount,
+                    &invoice.id
+                ),
+                Ok(UsageRerating::Unchanged) => {}
+                Err(e) => log::error!("Failed to re-rate invoice with id {} : {}", &invoice.id, e),
+            }
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
     Ok(())
 }
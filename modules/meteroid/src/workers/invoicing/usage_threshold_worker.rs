@@ -0,0 +1,129 @@
+/*
+    Goal : Invoice the usage of a subscription early, as soon as the amount accrued in the current period crosses its invoice threshold.
+
+    The interim invoice is finalized immediately. The subscription keeps the date until which usage was invoiced,
+    so that the next threshold invoice and the end of period invoice only bill the usage after that date.
+*/
+use std::sync::Arc;
+
+use crate::{errors, singletons};
+
+use crate::adapters::alerting::escalate_worker_failure;
+use crate::workers::metrics::record_call;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::future::join_all;
+use futures::FutureExt;
+use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::subscriptions::SubscriptionUsageThresholdInterface;
+use meteroid_store::Store;
+use tokio::sync::Semaphore;
+
+const MAX_CONCURRENT_REQUESTS: usize = 10;
+
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct UsageThresholdWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for UsageThresholdWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        usage_threshold_worker(singletons::get_store().await)
+            .timed(|res, elapsed| record_call("usage_threshold", res, elapsed))
+            .then(|res| escalate_worker_failure("usage_threshold", res))
+            .await
+            .map_err(|err| {
+                log::error!("Error in usage threshold worker: {}", err);
+                FangError {
+                    description: err.to_string(),
+                }
+            })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 7/30 * * * * *"; // every 30 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn usage_threshold_worker(store: &Store) -> Result<(), errors::WorkerError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+
+    let mut tasks = Vec::new();
+
+    let mut last_processed_id = None;
+
+    let today = chrono::Utc::now().naive_utc().date();
+
+    loop {
+        let paginated_vec = store
+            .list_usage_threshold_candidates(
+                today,
+                CursorPaginationRequest {
+                    limit: Some(BATCH_SIZE as u32),
+                    cursor: last_processed_id,
+                },
+            )
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for subscription in paginated_vec.items.into_iter() {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .change_context(errors::WorkerError::DatabaseError)?;
+
+            let store = store.clone();
+
+            let task = tokio::spawn(async move {
+                let _permit = permit;
+
+                match store
+                    .invoice_usage_threshold(subscription.tenant_id, subscription.id, today)
+                    .await
+                {
+                    Ok(Some(invoice)) => log::info!(
+                        "Usage threshold invoice {} created for subscription {}",
+                        invoice.id,
+                        subscription.id
+                    ),
+                    Ok(None) => {}
+                    Err(e) => {
+                        // evaluated again on the next run
+                        log::error!(
+                            "Failed to evaluate usage threshold of subscription with id {} : {}",
+                            subscription.id,
+                            e
+                        )
+                    }
+                }
+            });
+            tasks.push(task);
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    join_all(tasks).await;
+
+    Ok(())
+}
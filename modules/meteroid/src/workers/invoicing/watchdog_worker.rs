@@ -0,0 +1,101 @@
+/*
+    Goal : Detect the invoices stuck in a status beyond their SLA (stale drafts, pending finalization, issue retries
+    exhausted), and alert the tenant once per invoice through webhooks.
+
+    The stuck invoices of a tenant can also be listed through the invoices api.
+*/
+use crate::workers::metrics::record_call;
+use crate::{config, errors, singletons};
+use chrono::NaiveDateTime;
+use common_utils::timed::TimedExt;
+use envconfig::Envconfig;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::domain::invoice_watchdog::InvoiceWatchdogSla;
+use meteroid_store::repositories::invoice_watchdog::InvoiceWatchdogInterface;
+use meteroid_store::Store;
+
+/// The default SLAs, for the tenants that don't set their own
+#[derive(Envconfig, Debug, Clone)]
+pub struct InvoiceWatchdogConfig {
+    #[envconfig(from = "INVOICE_WATCHDOG_DRAFT_MAX_AGE_DAYS", default = "7")]
+    pub draft_max_age_days: u32,
+
+    #[envconfig(from = "INVOICE_WATCHDOG_PENDING_MAX_AGE_HOURS", default = "24")]
+    pub pending_max_age_hours: u32,
+
+    #[envconfig(from = "INVOICE_WATCHDOG_MAX_ISSUE_ATTEMPTS", default = "5")]
+    pub max_issue_attempts: u32,
+}
+
+impl From<&InvoiceWatchdogConfig> for InvoiceWatchdogSla {
+    fn from(config: &InvoiceWatchdogConfig) -> Self {
+        InvoiceWatchdogSla {
+            draft_max_age_days: config.draft_max_age_days,
+            pending_max_age_hours: config.pending_max_age_hours,
+            max_issue_attempts: config.max_issue_attempts,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct WatchdogWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for WatchdogWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        watchdog_worker(
+            singletons::get_store().await,
+            &(&config::Config::get().invoice_watchdog).into(),
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("watchdog", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in watchdog worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 7/30 * * * * *"; // every 30 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn watchdog_worker(
+    store: &Store,
+    sla: &InvoiceWatchdogSla,
+    now: NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let stuck = store
+        .alert_stuck_invoices(sla, now)
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    for invoice in stuck {
+        log::warn!(
+            "Invoice {} of tenant {} is stuck ({:?}) since {}",
+            invoice.invoice_id,
+            invoice.tenant_id,
+            invoice.reason,
+            invoice.stuck_since
+        );
+    }
+
+    Ok(())
+}
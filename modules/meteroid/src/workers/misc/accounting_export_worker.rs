@@ -0,0 +1,108 @@
+/*
+    Goal : Export the journal entries of the tenants to their accounting system (a CSV file or QuickBooks Online),
+    once each period of their schedule is complete.
+
+    A failed export is recorded and retried on the next run, the schedule only moving past the exported periods.
+*/
+use std::sync::Arc;
+
+use crate::config::Config;
+use crate::services::accounting_export::run_accounting_export;
+use crate::services::storage::{ObjectStoreService, S3Storage};
+use crate::{errors, singletons};
+
+use crate::workers::metrics::record_call;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::domain::accounting_exports::due_export_period;
+use meteroid_store::repositories::accounting_exports::AccountingExportInterface;
+use meteroid_store::Store;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct AccountingExportWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for AccountingExportWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        let config = Config::get();
+
+        let object_store: Arc<dyn ObjectStoreService> = Arc::new(
+            S3Storage::try_new(&config.object_store_uri, &config.object_store_prefix).map_err(
+                |err| FangError {
+                    description: err.to_string(),
+                },
+            )?,
+        );
+
+        accounting_export_worker(
+            singletons::get_store().await,
+            &object_store,
+            chrono::Utc::now().naive_utc().date(),
+        )
+        .timed(|res, elapsed| record_call("accounting_export", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in accounting export worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 20 * * * * *"; // every hour
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn accounting_export_worker(
+    store: &Store,
+    object_store: &Arc<dyn ObjectStoreService>,
+    today: chrono::NaiveDate,
+) -> Result<(), errors::WorkerError> {
+    let configs = store
+        .list_enabled_accounting_export_configs()
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    // one tenant at a time, the accounting systems rate limit the exports of the app
+    for config in configs {
+        let Some(period) = due_export_period(config.schedule, config.exported_until, today) else {
+            continue;
+        };
+
+        match run_accounting_export(store, object_store, &config, period, true).await {
+            Ok(run) => log::info!(
+                "Accounting export of tenant {} from {} to {}: {:?}, {} entries",
+                config.tenant_id,
+                period.start,
+                period.end,
+                run.status,
+                run.entries
+            ),
+            Err(e) => {
+                // exported again on the next run
+                log::error!(
+                    "Failed to export the accounting of tenant {} : {}",
+                    config.tenant_id,
+                    e
+                )
+            }
+        }
+    }
+
+    Ok(())
+}
@@ -0,0 +1,73 @@
+/*
+    Goal : Remind the owners of the API tokens expiring within the notice period to rotate them.
+
+    Each token is notified once, by an email requested through the outbox. The expired tokens are rejected
+    by the auth middleware regardless.
+*/
+use crate::workers::metrics::record_call;
+use crate::{errors, singletons};
+use chrono::NaiveDateTime;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::repositories::api_tokens::ApiTokensInterface;
+use meteroid_store::Store;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct ApiTokenExpiryWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for ApiTokenExpiryWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        api_token_expiry_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("api_token_expiry", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in api token expiry worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 40 * * * * *"; // every hour
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn api_token_expiry_worker(
+    store: &Store,
+    now: NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let notified = store
+        .notify_expiring_api_tokens(now)
+        .await
+        .change_context(errors::WorkerError::DatabaseError)?;
+
+    for token in notified {
+        log::info!(
+            "Reminded the owner of api token {} of tenant {} to rotate it before {:?}",
+            token.id,
+            token.tenant_id,
+            token.expires_at
+        );
+    }
+
+    Ok(())
+}
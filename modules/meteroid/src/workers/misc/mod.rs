@@ -1 +1,7 @@
+pub mod accounting_export_worker;
+pub mod api_token_expiry_worker;
 pub mod currency_rates_worker;
+pub mod trials_worker;
+pub mod usage_alert_rules_worker;
+pub mod usage_alerts_worker;
+pub mod usage_caps_worker;
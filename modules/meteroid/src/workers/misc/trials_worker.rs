@@ -0,0 +1,104 @@
+/*
+    Goal : End the trials of the subscriptions that reached their billing start date without being activated,
+    following the trial mode of their plan.
+
+    Free trials (no payment method required) expire and the subscription is canceled at the end of the trial.
+    Card-required trials are converted : the subscription is activated and charged on the payment method of the customer.
+*/
+use crate::errors;
+use crate::singletons;
+use crate::workers::metrics::record_call;
+use chrono::NaiveDate;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use meteroid_store::domain::enums::TrialModeEnum;
+use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::subscription_trials::SubscriptionTrialsInterface;
+use meteroid_store::Store;
+
+const BATCH_SIZE: u32 = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct TrialsWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for TrialsWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        trials_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc().date(),
+        )
+        .timed(|res, elapsed| record_call("trials", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in trials worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 20 * * * * *"; // every hour
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn trials_worker(store: &Store, today: NaiveDate) -> Result<(), errors::WorkerError> {
+    let mut last_processed_id = None;
+
+    loop {
+        let paginated_vec = store
+            .list_ended_trials(
+                today,
+                CursorPaginationRequest {
+                    limit: Some(BATCH_SIZE),
+                    cursor: last_processed_id,
+                },
+            )
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for trial in paginated_vec.items {
+            match store.end_trial(&trial).await {
+                Ok(()) => match trial.trial_mode {
+                    TrialModeEnum::Free => {
+                        log::info!("Trial of subscription {} expired", trial.id)
+                    }
+                    TrialModeEnum::CardRequired => {
+                        log::info!("Trial of subscription {} converted", trial.id)
+                    }
+                },
+                Err(e) => {
+                    // ended on the next run
+                    log::error!(
+                        "Failed to end the trial of subscription {} : {}",
+                        trial.id,
+                        e
+                    )
+                }
+            }
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    Ok(())
+}
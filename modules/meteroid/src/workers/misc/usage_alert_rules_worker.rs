@@ -0,0 +1,131 @@
+/*
+    Goal : Notify the tenants (webhook & email) when the usage of their customers meets the conditions of an alert rule,
+    and again once it no longer does.
+
+    The rule fires once per customer and only fires again after it resolved, the transitions being recorded as its history.
+*/
+use std::sync::Arc;
+
+use crate::{errors, singletons};
+
+use crate::workers::metrics::record_call;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::future::join_all;
+use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::usage_alert_rules::UsageAlertRuleInterface;
+use meteroid_store::Store;
+use tokio::sync::Semaphore;
+
+const MAX_CONCURRENT_REQUESTS: usize = 10;
+
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct UsageAlertRulesWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for UsageAlertRulesWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        usage_alert_rules_worker(
+            singletons::get_store().await,
+            chrono::Utc::now().naive_utc(),
+        )
+        .timed(|res, elapsed| record_call("usage_alert_rules", res, elapsed))
+        .await
+        .map_err(|err| {
+            log::error!("Error in usage alert rules worker: {}", err);
+            FangError {
+                description: err.to_string(),
+            }
+        })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 10/15 * * * * *"; // every 15 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn usage_alert_rules_worker(
+    store: &Store,
+    now: chrono::NaiveDateTime,
+) -> Result<(), errors::WorkerError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+
+    let mut tasks = Vec::new();
+
+    let mut last_processed_id = None;
+
+    loop {
+        let paginated_vec = store
+            .list_active_usage_alert_rules(CursorPaginationRequest {
+                limit: Some(BATCH_SIZE as u32),
+                cursor: last_processed_id,
+            })
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for rule in paginated_vec.items.into_iter() {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .change_context(errors::WorkerError::DatabaseError)?;
+
+            let store = store.clone();
+
+            let task = tokio::spawn(async move {
+                let _permit = permit;
+
+                let rule_id = rule.id;
+
+                match store.evaluate_usage_alert_rule(rule, now).await {
+                    Ok(events) => {
+                        for event in events {
+                            log::info!(
+                                "Usage alert rule {} is {:?} for customer {} (usage {})",
+                                rule_id,
+                                event.status,
+                                event.customer_id,
+                                event.usage
+                            )
+                        }
+                    }
+                    Err(e) => {
+                        // evaluated again on the next run
+                        log::error!(
+                            "Failed to evaluate usage alert rule with id {} : {}",
+                            rule_id,
+                            e
+                        )
+                    }
+                }
+            });
+            tasks.push(task);
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    join_all(tasks).await;
+
+    Ok(())
+}
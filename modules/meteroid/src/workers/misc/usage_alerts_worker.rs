@@ -0,0 +1,124 @@
+/*
+    Goal : Notify the tenants (webhook & email) when the usage of a customer crosses a threshold during the current period.
+
+    The alert state (triggered, period, last value) is persisted, so that an alert fires once and is only re-armed
+    when the usage drops below the hysteresis band or when a new period starts.
+*/
+use std::sync::Arc;
+
+use crate::{errors, singletons};
+
+use crate::workers::metrics::record_call;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::future::join_all;
+use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::usage_alerts::UsageAlertInterface;
+use meteroid_store::Store;
+use tokio::sync::Semaphore;
+
+const MAX_CONCURRENT_REQUESTS: usize = 10;
+
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct UsageAlertsWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for UsageAlertsWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        usage_alerts_worker(singletons::get_store().await)
+            .timed(|res, elapsed| record_call("usage_alerts", res, elapsed))
+            .await
+            .map_err(|err| {
+                log::error!("Error in usage alerts worker: {}", err);
+                FangError {
+                    description: err.to_string(),
+                }
+            })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 5/15 * * * * *"; // every 15 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn usage_alerts_worker(store: &Store) -> Result<(), errors::WorkerError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+
+    let mut tasks = Vec::new();
+
+    let mut last_processed_id = None;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    loop {
+        let paginated_vec = store
+            .list_active_usage_alerts(CursorPaginationRequest {
+                limit: Some(BATCH_SIZE as u32),
+                cursor: last_processed_id,
+            })
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for alert in paginated_vec.items.into_iter() {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .change_context(errors::WorkerError::DatabaseError)?;
+
+            let store = store.clone();
+
+            let task = tokio::spawn(async move {
+                let _permit = permit;
+
+                let alert_id = alert.id;
+
+                match store.evaluate_usage_alert(alert, now).await {
+                    Ok(Some(triggered)) => log::info!(
+                        "Usage alert {} triggered for subscription {} ({} >= {})",
+                        alert_id,
+                        triggered.subscription_id,
+                        triggered.usage,
+                        triggered.threshold
+                    ),
+                    Ok(None) => {}
+                    Err(e) => {
+                        // evaluated again on the next run
+                        log::error!(
+                            "Failed to evaluate usage alert with id {} : {}",
+                            alert_id,
+                            e
+                        )
+                    }
+                }
+            });
+            tasks.push(task);
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    join_all(tasks).await;
+
+    Ok(())
+}
@@ -0,0 +1,129 @@
+/*
+    Goal : Notify the tenants (webhook) when the charges of a capped usage or capacity fee reach the cap in the current period.
+
+    A reached cap is persisted per fee and period, so that the webhook is sent once per period. The product can also poll
+    the limit reached state through the API.
+*/
+use std::sync::Arc;
+
+use crate::{errors, singletons};
+
+use crate::workers::metrics::record_call;
+use common_utils::timed::TimedExt;
+use error_stack::{Result, ResultExt};
+use fang::{AsyncQueueable, AsyncRunnable, Deserialize, FangError, Scheduled, Serialize};
+use futures::future::join_all;
+use meteroid_store::domain::CursorPaginationRequest;
+use meteroid_store::repositories::usage_caps::UsageCapInterface;
+use meteroid_store::Store;
+use tokio::sync::Semaphore;
+
+const MAX_CONCURRENT_REQUESTS: usize = 10;
+
+const BATCH_SIZE: usize = 100;
+
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "fang::serde")]
+pub struct UsageCapsWorker;
+
+#[async_trait::async_trait]
+#[typetag::serde]
+impl AsyncRunnable for UsageCapsWorker {
+    #[tracing::instrument(skip_all)]
+    async fn run(&self, _queue: &mut dyn AsyncQueueable) -> core::result::Result<(), FangError> {
+        usage_caps_worker(singletons::get_store().await)
+            .timed(|res, elapsed| record_call("usage_caps", res, elapsed))
+            .await
+            .map_err(|err| {
+                log::error!("Error in usage caps worker: {}", err);
+                FangError {
+                    description: err.to_string(),
+                }
+            })
+    }
+
+    fn uniq(&self) -> bool {
+        true
+    }
+
+    fn cron(&self) -> Option<Scheduled> {
+        let expression = "0 10/15 * * * * *"; // every 15 minutes
+        Some(Scheduled::CronPattern(expression.to_string()))
+    }
+
+    fn max_retries(&self) -> i32 {
+        0
+    }
+}
+
+#[tracing::instrument(skip_all)]
+pub async fn usage_caps_worker(store: &Store) -> Result<(), errors::WorkerError> {
+    let semaphore = Arc::new(Semaphore::new(MAX_CONCURRENT_REQUESTS));
+
+    let mut tasks = Vec::new();
+
+    let mut last_processed_id = None;
+
+    let now = chrono::Utc::now().naive_utc();
+
+    loop {
+        let paginated_vec = store
+            .list_usage_cap_candidates(
+                now.date(),
+                CursorPaginationRequest {
+                    limit: Some(BATCH_SIZE as u32),
+                    cursor: last_processed_id,
+                },
+            )
+            .await
+            .change_context(errors::WorkerError::DatabaseError)?;
+
+        for subscription in paginated_vec.items.into_iter() {
+            let permit = semaphore
+                .clone()
+                .acquire_owned()
+                .await
+                .change_context(errors::WorkerError::DatabaseError)?;
+
+            let store = store.clone();
+
+            let task = tokio::spawn(async move {
+                let _permit = permit;
+
+                match store
+                    .evaluate_usage_caps(subscription.tenant_id, subscription.id, now)
+                    .await
+                {
+                    Ok(reached) => {
+                        for reached in reached {
+                            log::info!(
+                                "Usage cap of fee {} reached for subscription {}",
+                                reached.fee_id,
+                                subscription.id
+                            )
+                        }
+                    }
+                    Err(e) => {
+                        // evaluated again on the next run
+                        log::error!(
+                            "Failed to evaluate usage caps of subscription with id {} : {}",
+                            subscription.id,
+                            e
+                        )
+                    }
+                }
+            });
+            tasks.push(task);
+        }
+
+        last_processed_id = paginated_vec.next_cursor;
+
+        if paginated_vec.next_cursor.is_none() {
+            break;
+        }
+    }
+
+    join_all(tasks).await;
+
+    Ok(())
+}
@@ -296,6 +296,8 @@ async fn test_metering_e2e() {
             }),
             usage_group_key: None,
             family_external_id: "default".to_string(),
+            quantity_precision: None,
+            event_schema: None,
         }))
         .await
         .expect("Could not create meter");
@@ -382,11 +384,13 @@ async fn test_metering_e2e() {
                                     per_unit_overage: Decimal::new(4, 2).to_string(),
                                 },
                             ],
+                            cap: None,
                         },
                     )),
                 }),
 
                 product_item_id: None,
+                rate_card_id: None,
             },
         ))
         .await
@@ -428,6 +432,7 @@ async fn test_metering_e2e() {
                                     initial_slot_count: Some(100),
                                     billing_period: None,
                                     committed_capacity: None,
+                                    billing_period_months: None,
                                 }
                             ],
                             overridden_components: vec![],
@@ -436,6 +441,8 @@ async fn test_metering_e2e() {
                         }),
                         add_ons: None,
                         coupons: None,
+                        billing_mode: api::subscriptions::v1::BillingMode::Anniversary as i32,
+                        minimum_commitment: None,
                     },
                 )
             },
@@ -490,6 +497,7 @@ async fn test_metering_e2e() {
                 email: None,
                 vat_number: None,
                 alias: None,
+                early_payment_discount: None,
                 snapshot_at: period_2_start.naive_utc(),
             },
             seller_details: InlineInvoicingEntity {
@@ -504,8 +512,11 @@ async fn test_metering_e2e() {
                     state: None,
                     zip_code: None,
                 },
+                tax_registrations: vec![],
                 snapshot_at: period_2_start.naive_utc(),
             },
+            billing_cadence: None,
+            replaces_invoice_id: None,
         })
         .await
         .unwrap();
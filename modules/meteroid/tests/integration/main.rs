@@ -9,20 +9,33 @@ mod test_auth_api_key;
 mod test_auth_jwt;
 mod test_basic;
 mod test_billable_metric;
+mod test_billing_periods;
+mod test_billing_schedule;
+mod test_collection_strategies;
+mod test_component_rules;
 mod test_coupon;
 mod test_customer;
 mod test_idempotency;
 mod test_idempotency_cache;
 mod test_instance;
 mod test_internal;
+mod test_invoice_watchdog;
+mod test_notification_bundles;
+mod test_payment_reminders;
 mod test_plan;
 mod test_product;
 mod test_product_family;
+mod test_providers;
+mod test_rest_api;
 mod test_schedule;
 mod test_slot_transaction;
 mod test_stats;
 mod test_subscription;
+mod test_subscription_batch;
+mod test_subscription_pending_changes;
+mod test_subscription_term;
 mod test_tenant;
+mod test_usage_alerts;
 mod test_user;
 mod test_webhooks_out;
 mod test_workers;
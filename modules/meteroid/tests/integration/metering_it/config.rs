@@ -4,7 +4,10 @@ use common_config::auth::InternalAuthConfig;
 use common_config::common::CommonConfig;
 use common_config::telemetry::TelemetryConfig;
 use kafka::config::KafkaConnectionConfig;
-use metering::config::{ClickhouseConfig, Config, KafkaConfig};
+use metering::config::{
+    ClickhouseConfig, Config, IngestDedupConfig, IngestLimitsConfig, KafkaConfig,
+};
+use metering::ingest::limits::TruncationPolicy;
 
 pub fn mocked_config(
     meteroid_port: u16,
@@ -19,6 +22,9 @@ pub fn mocked_config(
             address: format!("tcp://127.0.0.1:{}", clickhouse_port),
             username: "default".to_string(),
             password: "default".to_string(),
+            archive_s3_url: None,
+            archive_s3_access_key_id: None,
+            archive_s3_secret_access_key: None,
         },
         listen_addr: format!("127.0.0.1:{}", metering_port).parse().unwrap(),
         meteroid_endpoint: format!("http://127.0.0.1:{}", meteroid_port),
@@ -28,6 +34,17 @@ pub fn mocked_config(
         internal_auth: InternalAuthConfig {
             hmac_secret: "secret".to_string().into(),
         },
+        ingest_limits: IngestLimitsConfig {
+            max_batch_size: 500,
+            max_properties_per_event: 100,
+            max_property_key_length: 128,
+            max_property_value_length: 1024,
+            value_truncation: TruncationPolicy::Reject,
+        },
+        ingest_dedup: IngestDedupConfig {
+            window_seconds: 3600,
+            max_tracked_events: 10000,
+        },
         kafka: KafkaConfig {
             kafka_connection: KafkaConnectionConfig {
                 bootstrap_servers: format!("127.0.0.1:{}", kafka_port),
@@ -38,6 +55,7 @@ pub fn mocked_config(
             },
             kafka_internal_addr: format!("it_redpanda:{}", 29092),
             kafka_topic,
+            kafka_dead_letter_topic: None,
             kafka_producer_linger_ms: 20,
             kafka_producer_queue_mib: 400,
             kafka_message_timeout_ms: 20000,
@@ -9,18 +9,24 @@ use common_grpc::middleware::common::auth::INTERNAL_API_CONTEXT_HEADER;
 use meteroid_grpc::meteroid::api::addons::v1::add_ons_service_client::AddOnsServiceClient;
 use meteroid_grpc::meteroid::api::apitokens::v1::api_tokens_service_client::ApiTokensServiceClient;
 use meteroid_grpc::meteroid::api::billablemetrics::v1::billable_metrics_service_client::BillableMetricsServiceClient;
+use meteroid_grpc::meteroid::api::collectionstrategies::v1::collection_strategies_service_client::CollectionStrategiesServiceClient;
+use meteroid_grpc::meteroid::api::componentrules::v1::component_rules_service_client::ComponentRulesServiceClient;
 use meteroid_grpc::meteroid::api::components::v1::price_components_service_client::PriceComponentsServiceClient;
 use meteroid_grpc::meteroid::api::coupons::v1::coupons_service_client::CouponsServiceClient;
 use meteroid_grpc::meteroid::api::customers::v1::customers_service_client::CustomersServiceClient;
 use meteroid_grpc::meteroid::api::instance::v1::instance_service_client::InstanceServiceClient;
+use meteroid_grpc::meteroid::api::notificationbundles::v1::notification_bundles_service_client::NotificationBundlesServiceClient;
 use meteroid_grpc::meteroid::api::organizations::v1::organizations_service_client::OrganizationsServiceClient;
+use meteroid_grpc::meteroid::api::paymentreminders::v1::payment_reminders_service_client::PaymentRemindersServiceClient;
 use meteroid_grpc::meteroid::api::plans::v1::plans_service_client::PlansServiceClient;
 use meteroid_grpc::meteroid::api::productfamilies::v1::product_families_service_client::ProductFamiliesServiceClient;
 use meteroid_grpc::meteroid::api::products::v1::products_service_client::ProductsServiceClient;
+use meteroid_grpc::meteroid::api::providers::v1::providers_service_client::ProvidersServiceClient;
 use meteroid_grpc::meteroid::api::schedules::v1::schedules_service_client::SchedulesServiceClient;
 use meteroid_grpc::meteroid::api::stats::v1::stats_service_client::StatsServiceClient;
 use meteroid_grpc::meteroid::api::subscriptions::v1::subscriptions_service_client::SubscriptionsServiceClient;
 use meteroid_grpc::meteroid::api::tenants::v1::tenants_service_client::TenantsServiceClient;
+use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alerts_service_client::UsageAlertsServiceClient;
 use meteroid_grpc::meteroid::api::users::v1::users_service_client::UsersServiceClient;
 use meteroid_grpc::meteroid::api::webhooks::out::v1::webhooks_service_client::WebhooksServiceClient;
 
@@ -29,17 +35,23 @@ pub type TestLayeredClientService = AddAuthorization<SetRequestHeader<Channel, H
 pub struct AllClients {
     pub add_ons: AddOnsServiceClient<TestLayeredClientService>,
     pub api_tokens: ApiTokensServiceClient<TestLayeredClientService>,
+    pub collection_strategies: CollectionStrategiesServiceClient<TestLayeredClientService>,
+    pub component_rules: ComponentRulesServiceClient<TestLayeredClientService>,
     pub coupons: CouponsServiceClient<TestLayeredClientService>,
     pub customers: CustomersServiceClient<TestLayeredClientService>,
     pub metrics: BillableMetricsServiceClient<TestLayeredClientService>,
     pub instance: InstanceServiceClient<TestLayeredClientService>,
+    pub notification_bundles: NotificationBundlesServiceClient<TestLayeredClientService>,
+    pub payment_reminders: PaymentRemindersServiceClient<TestLayeredClientService>,
     pub plans: PlansServiceClient<TestLayeredClientService>,
     pub price_components: PriceComponentsServiceClient<TestLayeredClientService>,
     pub product_families: ProductFamiliesServiceClient<TestLayeredClientService>,
     pub products: ProductsServiceClient<TestLayeredClientService>,
+    pub providers: ProvidersServiceClient<TestLayeredClientService>,
     pub subscriptions: SubscriptionsServiceClient<TestLayeredClientService>,
     pub schedules: SchedulesServiceClient<TestLayeredClientService>,
     pub tenants: TenantsServiceClient<TestLayeredClientService>,
+    pub usage_alerts: UsageAlertsServiceClient<TestLayeredClientService>,
     pub users: UsersServiceClient<TestLayeredClientService>,
     pub webhooks_out: WebhooksServiceClient<TestLayeredClientService>,
     pub stats: StatsServiceClient<TestLayeredClientService>,
@@ -59,17 +71,23 @@ impl AllClients {
         Self {
             add_ons: AddOnsServiceClient::new(service.clone()),
             api_tokens: ApiTokensServiceClient::new(service.clone()),
+            collection_strategies: CollectionStrategiesServiceClient::new(service.clone()),
+            component_rules: ComponentRulesServiceClient::new(service.clone()),
             coupons: CouponsServiceClient::new(service.clone()),
             customers: CustomersServiceClient::new(service.clone()),
             metrics: BillableMetricsServiceClient::new(service.clone()),
             instance: InstanceServiceClient::new(service.clone()),
+            notification_bundles: NotificationBundlesServiceClient::new(service.clone()),
+            payment_reminders: PaymentRemindersServiceClient::new(service.clone()),
             plans: PlansServiceClient::new(service.clone()),
             price_components: PriceComponentsServiceClient::new(service.clone()),
             product_families: ProductFamiliesServiceClient::new(service.clone()),
             products: ProductsServiceClient::new(service.clone()),
+            providers: ProvidersServiceClient::new(service.clone()),
             schedules: SchedulesServiceClient::new(service.clone()),
             subscriptions: SubscriptionsServiceClient::new(service.clone()),
             tenants: TenantsServiceClient::new(service.clone()),
+            usage_alerts: UsageAlertsServiceClient::new(service.clone()),
             users: UsersServiceClient::new(service.clone()),
             webhooks_out: WebhooksServiceClient::new(service.clone()),
             stats: StatsServiceClient::new(service.clone()),
@@ -7,8 +7,12 @@ use common_config::auth::InternalAuthConfig;
 use common_config::common::CommonConfig;
 use common_config::idempotency::IdempotencyConfig;
 use common_config::telemetry::TelemetryConfig;
+use meteroid::adapters::alerting::AlertingConfig;
+use meteroid::adapters::quickbooks::QuickBooksConfig;
+use meteroid::api::cors::CorsConfig;
 use meteroid::config::Config;
 use meteroid::workers::fang::ext::FangExtConfig;
+use meteroid::workers::invoicing::watchdog_worker::InvoiceWatchdogConfig;
 
 pub fn mocked_config(
     postgres_connection_string: String,
@@ -41,9 +45,14 @@ pub fn mocked_config(
             api_key: "".to_string().into(),
         },
         jwt_secret: "secret".to_string().into(),
+        email_events_webhook_secret: None,
         multi_organization_enabled: false,
         secrets_crypt_key: "00000000000000000000000000000000".to_string().into(),
         fang_ext: FangExtConfig::init_from_env().unwrap(),
+        invoice_watchdog: InvoiceWatchdogConfig::init_from_env().unwrap(),
+        alerting: AlertingConfig::init_from_env().unwrap(),
+        quickbooks: QuickBooksConfig::init_from_env().unwrap(),
+        cors: CorsConfig::init_from_env().unwrap(),
         openexchangerates_api_key: None,
         gotenberg_url: "http://localhost:3000".to_owned(),
     }
@@ -10,6 +10,10 @@ use tokio_util::sync::CancellationToken;
 use tonic::transport::Channel;
 
 use crate::helpers;
+use metering_grpc::meteroid::metering::v1::events_service_client::EventsServiceClient;
+use meteroid::adapters::adyen::Adyen;
+use meteroid::adapters::gocardless::GoCardless;
+use meteroid::adapters::stripe::Stripe;
 use meteroid::config::Config;
 use meteroid::eventbus::{create_eventbus_memory, setup_eventbus_handlers};
 use meteroid::migrations;
@@ -110,6 +114,47 @@ pub async fn start_meteroid(
     .await
 }
 
+/// Serves the rest api of the setup on its configured address until the setup is terminated. Returns its base url
+pub async fn start_rest_api(setup: &MeteroidSetup) -> String {
+    let config = setup.config.clone();
+    let store = setup.store.clone();
+    let token = setup.token.clone();
+
+    // the ingestion is forwarded to the metering service, which is not started here
+    let events_client = EventsServiceClient::new(
+        Channel::from_shared(config.metering_endpoint.clone())
+            .expect("Invalid metering_endpoint")
+            .connect_lazy(),
+    );
+
+    log::info!("Starting REST api {}", config.rest_api_addr);
+
+    tokio::spawn(async move {
+        tokio::select! {
+            _ = meteroid::api::axum_server::serve(
+                config.rest_api_addr,
+                in_memory_object_store(),
+                Arc::new(Stripe {
+                    client: stripe_client::client::StripeClient::new(),
+                }),
+                Arc::new(Adyen::new()),
+                Arc::new(GoCardless::new()),
+                store,
+                config.jwt_secret.clone(),
+                config.email_events_webhook_secret.clone(),
+                events_client,
+            ) => {},
+            _ = token.cancelled() => {
+                log::info!("Interrupted REST api via token");
+            }
+        }
+    });
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+
+    format!("http://{}", setup.config.rest_api_addr)
+}
+
 // TODO check if that replaces terminate_meteroid
 // impl Drop for MeteroidSetup {
 //     fn drop(&mut self) {
@@ -1,6 +1,22 @@
 use crate::meteroid_it::container::SeedLevel;
+use crate::meteroid_it::db::seed::*;
 use crate::{helpers, meteroid_it};
+use meteroid::eventbus::create_eventbus_memory;
 use meteroid_grpc::meteroid::api;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::add_ons::AddOnNew;
+use meteroid_store::domain::enums::BillingPeriodEnum;
+use meteroid_store::domain::{
+    CreateSubscriptionAddOn, FeeType, SubscriptionAddOnCustomization, TermRate,
+};
+use meteroid_store::repositories::add_ons::AddOnInterface;
+use meteroid_store::repositories::subscription_add_ons::SubscriptionAddOnInterface;
+use meteroid_store::repositories::SubscriptionInterface;
+use meteroid_store::Store;
+use rust_decimal_macros::dec;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
 
 #[tokio::test]
 async fn test_add_ons_basic() {
@@ -102,3 +118,94 @@ async fn test_add_ons_basic() {
 
     assert_eq!(add_ons.len(), 0);
 }
+
+const ADD_ON_ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+
+#[tokio::test]
+async fn test_attach_detach_subscription_add_on() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(&store.pool, SeedLevel::SUBSCRIPTIONS).await;
+
+    let add_on = store
+        .create_add_on(AddOnNew {
+            name: "Premium support".to_string(),
+            fee: FeeType::Rate {
+                rates: vec![TermRate {
+                    term: BillingPeriodEnum::Monthly,
+                    price: dec!(49),
+                }],
+            },
+            tenant_id: TENANT_ID,
+        })
+        .await
+        .unwrap();
+
+    let attach = || {
+        store.attach_subscription_add_on(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            CreateSubscriptionAddOn {
+                add_on_id: add_on.id,
+                customization: SubscriptionAddOnCustomization::None,
+            },
+            ADD_ON_ACTOR_ID,
+        )
+    };
+
+    let attached = attach().await.unwrap();
+    assert_eq!(attached.add_on.add_on_id, add_on.id);
+    assert_eq!(attached.add_on.name, "Premium support");
+    // the seeded subscription is not activated yet, the add-on is billed from its first invoice
+    assert_eq!(attached.prorated_amount, 0);
+    assert!(attached.invoice_id.is_none());
+
+    let details = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+    assert!(details.add_ons.iter().any(|a| a.id == attached.add_on.id));
+
+    // already attached
+    assert!(attach().await.is_err());
+
+    let detached = store
+        .detach_subscription_add_on(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            attached.add_on.id,
+            ADD_ON_ACTOR_ID,
+        )
+        .await
+        .unwrap();
+    assert_eq!(detached.add_on.id, attached.add_on.id);
+    assert_eq!(detached.prorated_amount, 0);
+
+    let details = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+    assert!(details.add_ons.iter().all(|a| a.id != attached.add_on.id));
+
+    // no longer attached
+    assert!(store
+        .detach_subscription_add_on(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            attached.add_on.id,
+            ADD_ON_ACTOR_ID,
+        )
+        .await
+        .is_err());
+}
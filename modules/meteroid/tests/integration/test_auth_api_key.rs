@@ -50,7 +50,7 @@ async fn test_api_key() {
     );
 
     // generate API Key
-    let api_token_response = generate_api_key(&setup.channel).await;
+    let (clients, api_token_response) = generate_api_key(&setup.channel).await;
 
     // access with valid API Key
     let svc = build_tower_svc(&setup.channel, api_token_response.api_key.as_str());
@@ -61,11 +61,129 @@ async fn test_api_key() {
     assert!(customers_response.is_ok());
     assert_eq!(customers_response.unwrap().into_inner().customers.len(), 0);
 
+    let api_token_id = api_token_response.details.unwrap().id;
+
+    // rotate the API Key, the previous one is rejected
+    let rotated = clients
+        .api_tokens
+        .clone()
+        .rotate_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::RotateApiTokenRequest {
+                id: api_token_id.clone(),
+                grace_period_hours: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    // the rotation creates a new token
+    let rotated_id = rotated.details.unwrap().id;
+    assert_ne!(rotated_id, api_token_id);
+
+    let svc = build_tower_svc(&setup.channel, api_token_response.api_key.as_str());
+    let customers_response = list_customers(CustomersServiceClient::new(svc)).await;
+    assert_eq!(
+        customers_response.map_err(|e| e.code()).unwrap_err(),
+        Code::Unauthenticated
+    );
+
+    let svc = build_tower_svc(&setup.channel, rotated.api_key.as_str());
+    let customers_response = list_customers(CustomersServiceClient::new(svc)).await;
+    assert!(customers_response.is_ok());
+
+    // disable the API Key
+    let disabled = clients
+        .api_tokens
+        .clone()
+        .disable_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::DisableApiTokenRequest {
+                id: rotated_id.clone(),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(disabled.api_token.unwrap().disabled_at.is_some());
+
+    let svc = build_tower_svc(&setup.channel, rotated.api_key.as_str());
+    let customers_response = list_customers(CustomersServiceClient::new(svc)).await;
+    assert_eq!(
+        customers_response.map_err(|e| e.code()).unwrap_err(),
+        Code::Unauthenticated
+    );
+
+    // a token scoped to the invoices cannot list the customers
+    let scoped = clients
+        .api_tokens
+        .clone()
+        .create_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::CreateApiTokenRequest {
+                name: "invoices-api-key".to_string(),
+                daily_request_soft_limit: None,
+                partner_id: None,
+                scopes: vec!["invoices:read".to_string()],
+                expires_at: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let svc = build_tower_svc(&setup.channel, scoped.api_key.as_str());
+    let status = list_customers(CustomersServiceClient::new(svc))
+        .await
+        .unwrap_err();
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert_eq!(
+        status.message(),
+        "The scopes of this API key do not allow this call"
+    );
+
+    // a token rotated with a grace period stays valid alongside the new one
+    let graced = clients
+        .api_tokens
+        .clone()
+        .create_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::CreateApiTokenRequest {
+                name: "graced-api-key".to_string(),
+                daily_request_soft_limit: None,
+                partner_id: None,
+                scopes: vec![],
+                expires_at: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    let rotated_with_grace = clients
+        .api_tokens
+        .clone()
+        .rotate_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::RotateApiTokenRequest {
+                id: graced.details.unwrap().id,
+                grace_period_hours: Some(1),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    for api_key in [&graced.api_key, &rotated_with_grace.api_key] {
+        let svc = build_tower_svc(&setup.channel, api_key.as_str());
+        let customers_response = list_customers(CustomersServiceClient::new(svc)).await;
+        assert!(customers_response.is_ok());
+    }
+
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await;
 }
 
-async fn generate_api_key(channel: &Channel) -> CreateApiTokenResponse {
+async fn generate_api_key(
+    channel: &Channel,
+) -> (meteroid_it::clients::AllClients, CreateApiTokenResponse) {
     let svc = tower::ServiceBuilder::new().service(channel.clone());
     let users_svc = UsersServiceClient::new(svc);
 
@@ -111,17 +229,23 @@ async fn generate_api_key(channel: &Channel) -> CreateApiTokenResponse {
         tenant_response.slug.as_str(),
     );
 
-    clients
+    let api_token = clients
         .api_tokens
         .clone()
         .create_api_token(tonic::Request::new(
             meteroid_grpc::meteroid::api::apitokens::v1::CreateApiTokenRequest {
                 name: "test-api-key".to_string(),
+                daily_request_soft_limit: None,
+                partner_id: None,
+                scopes: vec![],
+                expires_at: None,
             },
         ))
         .await
         .unwrap()
-        .into_inner()
+        .into_inner();
+
+    (clients, api_token)
 }
 
 fn build_tower_svc(
@@ -3,6 +3,9 @@ use tonic::Code;
 use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api::customers::v1::customers_service_client::CustomersServiceClient;
+use meteroid_grpc::meteroid::api::customers::v1::list_customer_request::SortBy;
+use meteroid_grpc::meteroid::api::customers::v1::ListCustomerRequest;
 use meteroid_grpc::meteroid::api::users::v1::users_service_client::UsersServiceClient;
 
 #[tokio::test]
@@ -111,3 +114,74 @@ async fn test_jwt() {
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await;
 }
+
+#[tokio::test]
+async fn test_auth_statuses() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    // # no credentials
+    let status = CustomersServiceClient::new(setup.channel.clone())
+        .list_customers(list_customers_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(status.message(), "No authentication provided");
+
+    // # invalid token
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        "faketoken",
+        "TESTORG",
+        "testslug",
+    );
+
+    let status = clients
+        .customers
+        .clone()
+        .list_customers(list_customers_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::Unauthenticated);
+    assert_eq!(status.message(), "Invalid JWT");
+
+    // # valid token, on a tenant the user cannot access
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.as_str(),
+        "TESTORG",
+        "unknown-tenant",
+    );
+
+    let status = clients
+        .customers
+        .clone()
+        .list_customers(list_customers_request())
+        .await
+        .unwrap_err();
+
+    assert_eq!(status.code(), Code::PermissionDenied);
+    assert!(status
+        .message()
+        .starts_with("Failed to retrieve tenant for slug unknown-tenant"));
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await;
+}
+
+fn list_customers_request() -> ListCustomerRequest {
+    ListCustomerRequest {
+        search: None,
+        sort_by: SortBy::NameAsc as i32,
+        pagination: None,
+    }
+}
@@ -58,6 +58,7 @@ async fn test_main() {
             api::productfamilies::v1::CreateProductFamilyRequest {
                 name: "Test - usage".to_string(),
                 external_id: "test-usage".to_string(),
+                defaults: None,
             },
         ))
         .await
@@ -102,6 +103,7 @@ async fn test_main() {
                     )),
                 }),
                 product_item_id: None,
+                rate_card_id: None,
             },
         ))
         .await
@@ -140,6 +142,7 @@ async fn test_main() {
                                 api::customers::v1::customer_billing_config::Stripe {
                                     customer_id: "customer_id".to_string(),
                                     collection_method: 0,
+                                    default_payment_method_id: None,
                                 },
                             ),
                         ),
@@ -150,6 +153,7 @@ async fn test_main() {
                     billing_address: None,
                     shipping_address: None,
                     invoicing_entity_id: None,
+                    payment_terms: None,
                 }),
             },
         ))
@@ -33,6 +33,7 @@ async fn test_billable_metrics_basic() {
         .create_product_family(api::productfamilies::v1::CreateProductFamilyRequest {
             name: "product_family_name".into(),
             external_id: "product_family_external_id".into(),
+            defaults: None,
         })
         .await
         .unwrap()
@@ -40,6 +41,23 @@ async fn test_billable_metrics_basic() {
         .product_family
         .unwrap();
 
+    let event_schema = api::billablemetrics::v1::EventSchema {
+        properties: vec![
+            api::billablemetrics::v1::event_schema::Property {
+                key: "aggregation_key".to_string(),
+                property_type: api::billablemetrics::v1::event_schema::PropertyType::Number as i32,
+                required: true,
+                allowed_values: vec![],
+            },
+            api::billablemetrics::v1::event_schema::Property {
+                key: "region".to_string(),
+                property_type: api::billablemetrics::v1::event_schema::PropertyType::String as i32,
+                required: false,
+                allowed_values: vec!["eu".to_string(), "us".to_string()],
+            },
+        ],
+    };
+
     // create metric
     let created = clients
         .metrics
@@ -59,6 +77,11 @@ async fn test_billable_metrics_basic() {
             segmentation_matrix: None, // todo add
             usage_group_key: Some("usage".to_string()),
             family_external_id: "product_family_external_id".to_string(),
+            quantity_precision: Some(api::billablemetrics::v1::QuantityPrecision {
+                billing: Some(6),
+                display: None,
+            }),
+            event_schema: Some(event_schema.clone()),
         })
         .await
         .unwrap()
@@ -84,6 +107,73 @@ async fn test_billable_metrics_basic() {
 
     assert_eq!(get_by_id.id, created.id.clone());
     assert_eq!(get_by_id.name, metric_name.clone());
+    assert_eq!(
+        get_by_id.quantity_precision,
+        Some(api::billablemetrics::v1::QuantityPrecision {
+            billing: Some(6),
+            display: None,
+        })
+    );
+    assert_eq!(get_by_id.event_schema, Some(event_schema));
+
+    // precision beyond the supported decimal places
+    let invalid = clients
+        .metrics
+        .clone()
+        .create_billable_metric(api::billablemetrics::v1::CreateBillableMetricRequest {
+            name: "invalid".to_string(),
+            code: "invalid".to_string(),
+            description: None,
+            aggregation: Some(api::billablemetrics::v1::Aggregation {
+                aggregation_type: api::billablemetrics::v1::aggregation::AggregationType::Sum
+                    as i32,
+                aggregation_key: Some("aggregation_key".to_string()),
+                unit_conversion: None,
+            }),
+            segmentation_matrix: None,
+            usage_group_key: None,
+            family_external_id: "product_family_external_id".to_string(),
+            quantity_precision: Some(api::billablemetrics::v1::QuantityPrecision {
+                billing: None,
+                display: Some(20),
+            }),
+            event_schema: None,
+        })
+        .await;
+
+    assert!(invalid.is_err());
+
+    // aggregated property that is not a number
+    let invalid = clients
+        .metrics
+        .clone()
+        .create_billable_metric(api::billablemetrics::v1::CreateBillableMetricRequest {
+            name: "invalid".to_string(),
+            code: "invalid".to_string(),
+            description: None,
+            aggregation: Some(api::billablemetrics::v1::Aggregation {
+                aggregation_type: api::billablemetrics::v1::aggregation::AggregationType::Sum
+                    as i32,
+                aggregation_key: Some("aggregation_key".to_string()),
+                unit_conversion: None,
+            }),
+            segmentation_matrix: None,
+            usage_group_key: None,
+            family_external_id: "product_family_external_id".to_string(),
+            quantity_precision: None,
+            event_schema: Some(api::billablemetrics::v1::EventSchema {
+                properties: vec![api::billablemetrics::v1::event_schema::Property {
+                    key: "aggregation_key".to_string(),
+                    property_type: api::billablemetrics::v1::event_schema::PropertyType::Boolean
+                        as i32,
+                    required: true,
+                    allowed_values: vec![],
+                }],
+            }),
+        })
+        .await;
+
+    assert!(invalid.is_err());
 
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
@@ -0,0 +1,248 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+use chrono::{Datelike, NaiveDate};
+use diesel_async::SimpleAsyncConnection;
+use meteroid::eventbus::create_eventbus_memory;
+use meteroid::workers::invoicing::draft_worker::draft_worker;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::compute::InvoiceLineInterface;
+use meteroid_store::domain::enums::{
+    BillingPeriodEnum, SubscriptionBillingModeEnum, SubscriptionFeeBillingPeriod,
+};
+use meteroid_store::domain::subscription_components::{
+    CreateSubscriptionComponents, ExtraComponent, SubscriptionComponentNewInternal, SubscriptionFee,
+};
+use meteroid_store::domain::{
+    CreateSubscription, Invoice, OrderByRequest, PaginationRequest, SubscriptionNew,
+};
+use meteroid_store::repositories::{InvoiceInterface, SubscriptionInterface};
+use meteroid_store::Store;
+use rust_decimal_macros::dec;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
+
+const PERIODS_PLAN_VERSION_ID: Uuid = uuid!("018c344b-da87-7392-bbae-c5c8780adb1b");
+const PERIODS_SEATS_COMPONENT_ID: Uuid = uuid!("018c344c-9ec9-7608-b115-1537b6985e73");
+const PERIODS_ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+
+#[tokio::test]
+async fn test_weekly_and_custom_billing_periods() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    // one invoice per cadence, so that the drafts carry their cadence
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update tenant set invoice_cadence_grouping = 'PER_CADENCE' where id = '{TENANT_ID}';"
+    ))
+    .await
+    .unwrap();
+
+    let weekly = store
+        .insert_subscription(
+            new_subscription(
+                CUSTOMER_SPORTIFY_ID,
+                SubscriptionBillingModeEnum::Anniversary,
+                date("2024-01-03"),
+                SubscriptionFeeBillingPeriod::Weekly,
+            ),
+            TENANT_ID,
+        )
+        .await
+        .unwrap();
+
+    // calendar billing, so that the first period is partial
+    let custom = store
+        .insert_subscription(
+            new_subscription(
+                CUSTOMER_UBER_ID,
+                SubscriptionBillingModeEnum::Calendar,
+                date("2024-01-10"),
+                SubscriptionFeeBillingPeriod::Custom { months: 4 },
+            ),
+            TENANT_ID,
+        )
+        .await
+        .unwrap();
+
+    // the periods and their months are stored
+    let weekly_details = store
+        .get_subscription_details(TENANT_ID, weekly.id)
+        .await
+        .unwrap();
+    assert_eq!(weekly_details.period, BillingPeriodEnum::Weekly);
+    assert_eq!(
+        weekly_details.price_components[0].period,
+        SubscriptionFeeBillingPeriod::Weekly
+    );
+
+    let custom_details = store
+        .get_subscription_details(TENANT_ID, custom.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        custom_details.period,
+        BillingPeriodEnum::Custom { months: 4 }
+    );
+    assert_eq!(
+        custom_details.price_components[0].period,
+        SubscriptionFeeBillingPeriod::Custom { months: 4 }
+    );
+
+    // the weekly fee is billed in full, a week ahead
+    let lines = store
+        .compute_dated_invoice_lines(&date("2024-01-10"), &weekly_details)
+        .await
+        .unwrap();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].start_date, date("2024-01-10"));
+    assert_eq!(lines[0].end_date, date("2024-01-17"));
+    assert!(!lines[0].is_prorated);
+    assert_eq!(lines[0].total, 12100);
+
+    // the first custom period runs until the next 4-month boundary, prorated over the full period
+    let lines = store
+        .compute_dated_invoice_lines(&date("2024-01-10"), &custom_details)
+        .await
+        .unwrap();
+    assert_eq!(lines.len(), 1);
+    assert_eq!(lines[0].start_date, date("2024-01-10"));
+    assert_eq!(lines[0].end_date, date("2024-05-01"));
+    assert!(lines[0].is_prorated);
+    // 111 of the 121 days from 2024-01-01 to 2024-05-01
+    assert_eq!(lines[0].total, 11100);
+
+    let lines = store
+        .compute_dated_invoice_lines(&date("2024-05-01"), &custom_details)
+        .await
+        .unwrap();
+    assert_eq!(lines[0].start_date, date("2024-05-01"));
+    assert_eq!(lines[0].end_date, date("2024-09-01"));
+    assert!(!lines[0].is_prorated);
+    assert_eq!(lines[0].total, 12100);
+
+    // the drafts are dated at the end of the current period, with their cadence
+    draft_worker(&store, date("2024-01-15")).await.unwrap();
+
+    let invoices = list_invoices(&store).await;
+
+    let weekly_invoices = invoices
+        .iter()
+        .filter(|i| i.subscription_id == Some(weekly.id))
+        .collect::<Vec<_>>();
+    assert_eq!(weekly_invoices.len(), 1);
+    assert_eq!(weekly_invoices[0].invoice_date, date("2024-01-17"));
+    assert_eq!(
+        weekly_invoices[0].billing_cadence,
+        Some(BillingPeriodEnum::Weekly)
+    );
+
+    let custom_invoices = invoices
+        .iter()
+        .filter(|i| i.subscription_id == Some(custom.id))
+        .collect::<Vec<_>>();
+    assert_eq!(custom_invoices.len(), 1);
+    assert_eq!(custom_invoices[0].invoice_date, date("2024-05-01"));
+    assert_eq!(
+        custom_invoices[0].billing_cadence,
+        Some(BillingPeriodEnum::Custom { months: 4 })
+    );
+
+    // a drafted cadence is not drafted again
+    draft_worker(&store, date("2024-01-16")).await.unwrap();
+    assert_eq!(
+        list_invoices(&store)
+            .await
+            .iter()
+            .filter(|i| i.subscription_id == Some(custom.id))
+            .count(),
+        1
+    );
+}
+
+async fn list_invoices(store: &Store) -> Vec<Invoice> {
+    store
+        .list_invoices(
+            TENANT_ID,
+            None,
+            None,
+            None,
+            OrderByRequest::DateAsc,
+            PaginationRequest {
+                per_page: Some(100),
+                page: 0,
+            },
+        )
+        .await
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|i| i.invoice)
+        .collect()
+}
+
+fn new_subscription(
+    customer_id: Uuid,
+    billing_mode: SubscriptionBillingModeEnum,
+    billing_start_date: NaiveDate,
+    period: SubscriptionFeeBillingPeriod,
+) -> CreateSubscription {
+    CreateSubscription {
+        subscription: SubscriptionNew {
+            customer_id,
+            billing_day: billing_start_date.day() as i16,
+            billing_mode,
+            currency: "EUR".to_string(),
+            trial_start_date: None,
+            billing_start_date,
+            billing_end_date: None,
+            plan_version_id: PERIODS_PLAN_VERSION_ID,
+            created_by: PERIODS_ACTOR_ID,
+            net_terms: 0,
+            invoice_memo: None,
+            invoice_threshold: None,
+            activated_at: None,
+            minimum_commitment: None,
+        },
+        price_components: Some(CreateSubscriptionComponents {
+            parameterized_components: vec![],
+            overridden_components: vec![],
+            extra_components: vec![ExtraComponent {
+                component: SubscriptionComponentNewInternal {
+                    price_component_id: None,
+                    product_item_id: None,
+                    name: "Platform fee".to_string(),
+                    period,
+                    fee: SubscriptionFee::Rate { rate: dec!(121) },
+                    is_override: false,
+                },
+            }],
+            remove_components: vec![PERIODS_SEATS_COMPONENT_ID],
+        }),
+        add_ons: None,
+        coupons: None,
+        phases: vec![],
+    }
+}
+
+fn date(date_str: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d").expect("Invalid date format")
+}
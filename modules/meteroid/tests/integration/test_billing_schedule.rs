@@ -0,0 +1,209 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+use chrono::NaiveDate;
+use diesel_async::SimpleAsyncConnection;
+use meteroid::eventbus::create_eventbus_memory;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::subscription_pending_changes::SubscriptionChange;
+use meteroid_store::domain::TenantContext;
+use meteroid_store::repositories::subscription_pending_changes::SubscriptionPendingChangeInterface;
+use meteroid_store::repositories::subscriptions::SubscriptionBillingScheduleInterface;
+use meteroid_store::Store;
+use secrecy::SecretString;
+use std::sync::Arc;
+use testcontainers::ContainerAsync;
+use testcontainers_modules::postgres::Postgres;
+use uuid::{uuid, Uuid};
+
+const ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+
+async fn setup_store() -> (ContainerAsync<Postgres>, Store) {
+    let (pg_container, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    (pg_container, store)
+}
+
+#[tokio::test]
+async fn test_billing_schedule_annual_subscription() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    // annual subscription started on 2023-11-04, billed on the 1st
+    let schedule = store
+        .get_billing_schedule(
+            TENANT_ID,
+            SUBSCRIPTION_UBER_ID1,
+            NaiveDate::from_ymd_opt(2023, 11, 4).unwrap(),
+            2,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 2);
+
+    // the annual seats are only billed once a year
+    assert_eq!(
+        schedule[0].invoice_date,
+        NaiveDate::from_ymd_opt(2023, 11, 4).unwrap()
+    );
+    assert_eq!(
+        schedule[1].invoice_date,
+        NaiveDate::from_ymd_opt(2024, 11, 1).unwrap()
+    );
+
+    for entry in &schedule {
+        assert!(!entry.is_trial_end);
+        assert!(entry.lines.iter().any(|l| l.name == "Seats"));
+        assert_eq!(
+            entry.subtotal,
+            entry.lines.iter().map(|l| l.total).sum::<i64>()
+        );
+    }
+
+    // dates before the requested start are skipped
+    let schedule = store
+        .get_billing_schedule(
+            TENANT_ID,
+            SUBSCRIPTION_UBER_ID1,
+            NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            1,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 1);
+    assert_eq!(
+        schedule[0].invoice_date,
+        NaiveDate::from_ymd_opt(2024, 11, 1).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_billing_schedule_trial_end() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    // monthly subscription started on 2023-11-04, billed on the 1st, after a trial from 2023-10-20
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update subscription set trial_start_date = '2023-10-20' where id = '{SUBSCRIPTION_SPORTIFY_ID1}';"
+    ))
+    .await
+    .unwrap();
+
+    let schedule = store
+        .get_billing_schedule(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            NaiveDate::from_ymd_opt(2023, 11, 1).unwrap(),
+            3,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 3);
+    assert_eq!(
+        schedule[0].invoice_date,
+        NaiveDate::from_ymd_opt(2023, 11, 4).unwrap()
+    );
+    assert!(schedule[0].is_trial_end);
+    assert!(!schedule[1].is_trial_end);
+    assert!(!schedule[2].is_trial_end);
+
+    // the trial ended before the requested start
+    let schedule = store
+        .get_billing_schedule(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            NaiveDate::from_ymd_opt(2023, 12, 1).unwrap(),
+            1,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 1);
+    assert!(!schedule[0].is_trial_end);
+
+    // a trial starting on the billing start date is no trial
+    conn.batch_execute(&format!(
+        "update subscription set trial_start_date = billing_start_date where id = '{SUBSCRIPTION_SPORTIFY_ID1}';"
+    ))
+    .await
+    .unwrap();
+
+    let schedule = store
+        .get_billing_schedule(
+            TENANT_ID,
+            SUBSCRIPTION_SPORTIFY_ID1,
+            NaiveDate::from_ymd_opt(2023, 11, 1).unwrap(),
+            1,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 1);
+    assert!(!schedule[0].is_trial_end);
+}
+
+#[tokio::test]
+async fn test_billing_schedule_pending_cancellation() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    let pending = store
+        .schedule_subscription_change(
+            SUBSCRIPTION_SPORTIFY_ID1,
+            SubscriptionChange::Cancellation { reason: None },
+            TenantContext {
+                actor: ACTOR_ID,
+                tenant_id: TENANT_ID,
+            },
+        )
+        .await
+        .unwrap();
+
+    let from = pending.effective_at - chrono::Months::new(2);
+
+    let schedule = store
+        .get_billing_schedule(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1, from, 6)
+        .await
+        .unwrap();
+
+    // billed until the cancellation takes effect, then nothing
+    assert!(!schedule.is_empty());
+    assert!(schedule.len() < 6);
+    assert!(schedule
+        .iter()
+        .all(|entry| entry.invoice_date <= pending.effective_at));
+
+    // without the change, the subscription keeps renewing
+    store
+        .cancel_subscription_pending_change(pending.id, TENANT_ID)
+        .await
+        .unwrap();
+
+    let schedule = store
+        .get_billing_schedule(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1, from, 6)
+        .await
+        .unwrap();
+
+    assert_eq!(schedule.len(), 6);
+    assert!(schedule[5].invoice_date > pending.effective_at);
+}
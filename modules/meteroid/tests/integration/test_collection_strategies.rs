@@ -0,0 +1,187 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api;
+use meteroid_grpc::meteroid::api::collectionstrategies::v1::{
+    CollectionRulesOverride, CollectionStrategy, CollectionStrategyPreset,
+    DeleteCollectionStrategyOverrideRequest, GetCustomerCollectionStrategyRequest, LateFee,
+    ListCollectionStrategyOverridesRequest, ListCollectionStrategyPresetsRequest, RetrySchedule,
+    SetCustomerCollectionSegmentRequest, UpsertCollectionStrategyOverrideRequest,
+};
+
+#[tokio::test]
+async fn test_collection_strategies() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let presets = clients
+        .collection_strategies
+        .clone()
+        .list_collection_strategy_presets(ListCollectionStrategyPresetsRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .presets;
+
+    assert_eq!(presets.len(), 3);
+
+    let customer = clients
+        .customers
+        .clone()
+        .create_customer(api::customers::v1::CreateCustomerRequest {
+            data: Some(api::customers::v1::CustomerNew {
+                name: "Dunning Corp".to_string(),
+                alias: None,
+                email: None,
+                billing_config: None,
+                invoicing_email: None,
+                phone: None,
+                currency: "EUR".to_string(),
+                billing_address: None,
+                shipping_address: None,
+                invoicing_entity_id: None,
+                payment_terms: None,
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    // the default preset of the tenant
+    let strategy = customer_strategy(&clients, &customer.id).await;
+    assert_eq!(strategy.preset(), CollectionStrategyPreset::Standard);
+    assert_eq!(strategy.segment, None);
+
+    // the tenant selects another preset
+    clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                collection_strategy_preset: Some(CollectionStrategyPreset::Strict.into()),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap();
+
+    let strategy = customer_strategy(&clients, &customer.id).await;
+    assert_eq!(strategy.preset(), CollectionStrategyPreset::Strict);
+    assert_eq!(strategy.rules.unwrap().suspend_after_days, Some(14));
+
+    // a segment overrides it
+    let created = clients
+        .collection_strategies
+        .clone()
+        .upsert_collection_strategy_override(UpsertCollectionStrategyOverrideRequest {
+            segment: " enterprise ".to_string(),
+            preset: CollectionStrategyPreset::Lenient.into(),
+            rules: Some(CollectionRulesOverride {
+                retry_schedule: Some(RetrySchedule { days: vec![5, 10] }),
+                reminder_cadence: None,
+                suspend_after_days: Some(60),
+                late_fee: Some(LateFee {
+                    percent: "1.5".to_string(),
+                    grace_days: 15,
+                }),
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .strategy
+        .unwrap();
+
+    assert_eq!(created.segment, "enterprise");
+
+    let invalid = clients
+        .collection_strategies
+        .clone()
+        .upsert_collection_strategy_override(UpsertCollectionStrategyOverrideRequest {
+            segment: "smb".to_string(),
+            preset: CollectionStrategyPreset::Strict.into(),
+            rules: Some(CollectionRulesOverride {
+                retry_schedule: Some(RetrySchedule { days: vec![10, 5] }),
+                ..Default::default()
+            }),
+        })
+        .await;
+
+    assert_eq!(invalid.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    clients
+        .collection_strategies
+        .clone()
+        .set_customer_collection_segment(SetCustomerCollectionSegmentRequest {
+            customer_id: customer.id.clone(),
+            segment: Some("enterprise".to_string()),
+        })
+        .await
+        .unwrap();
+
+    let strategy = customer_strategy(&clients, &customer.id).await;
+    assert_eq!(strategy.preset(), CollectionStrategyPreset::Lenient);
+    assert_eq!(strategy.segment.as_deref(), Some("enterprise"));
+    let rules = strategy.rules.unwrap();
+    assert_eq!(rules.retry_schedule_days, vec![5, 10]);
+    assert_eq!(rules.suspend_after_days, Some(60));
+    assert_eq!(rules.late_fee.unwrap().percent, "1.5");
+
+    // without override, the customers of the segment fall back to the tenant preset
+    clients
+        .collection_strategies
+        .clone()
+        .delete_collection_strategy_override(DeleteCollectionStrategyOverrideRequest {
+            segment: "enterprise".to_string(),
+        })
+        .await
+        .unwrap();
+
+    let overrides = clients
+        .collection_strategies
+        .clone()
+        .list_collection_strategy_overrides(ListCollectionStrategyOverridesRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .overrides;
+
+    assert!(overrides.is_empty());
+
+    let strategy = customer_strategy(&clients, &customer.id).await;
+    assert_eq!(strategy.preset(), CollectionStrategyPreset::Strict);
+    assert_eq!(strategy.segment, None);
+}
+
+async fn customer_strategy(
+    clients: &meteroid_it::clients::AllClients,
+    customer_id: &str,
+) -> CollectionStrategy {
+    clients
+        .collection_strategies
+        .clone()
+        .get_customer_collection_strategy(GetCustomerCollectionStrategyRequest {
+            customer_id: customer_id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .strategy
+        .unwrap()
+}
@@ -0,0 +1,175 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api;
+use meteroid_grpc::meteroid::api::componentrules::v1::component_rule_item::Item;
+use meteroid_grpc::meteroid::api::componentrules::v1::{
+    ComponentRuleItem, ComponentRuleType, CreateComponentRuleRequest, DeleteComponentRuleRequest,
+    ListComponentRulesRequest, ValidateComponentRulesRequest,
+};
+
+#[tokio::test]
+async fn test_component_rules() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let fee = api::components::v1::Fee {
+        fee_type: Some(api::components::v1::fee::FeeType::OneTime(
+            api::components::v1::fee::OneTimeFee {
+                unit_price: "10".into(),
+                quantity: 1,
+            },
+        )),
+    };
+
+    let mut add_on_items = vec![];
+    for name in ["support", "premium-support", "basic-support"] {
+        let add_on = clients
+            .add_ons
+            .clone()
+            .create_add_on(api::addons::v1::CreateAddOnRequest {
+                name: name.into(),
+                fee: Some(fee.clone()),
+            })
+            .await
+            .unwrap()
+            .into_inner()
+            .add_on
+            .unwrap();
+
+        add_on_items.push(ComponentRuleItem {
+            item: Some(Item::AddOnId(add_on.id)),
+        });
+    }
+
+    let [support, premium, basic] = add_on_items.try_into().unwrap();
+
+    // premium requires support, premium excludes basic
+    let requires = clients
+        .component_rules
+        .clone()
+        .create_component_rule(CreateComponentRuleRequest {
+            rule_type: ComponentRuleType::Requires.into(),
+            subject: Some(premium.clone()),
+            target: Some(support.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .rule
+        .unwrap();
+
+    assert_eq!(requires.rule_type(), ComponentRuleType::Requires);
+    assert_eq!(requires.subject.as_ref(), Some(&premium));
+
+    clients
+        .component_rules
+        .clone()
+        .create_component_rule(CreateComponentRuleRequest {
+            rule_type: ComponentRuleType::Excludes.into(),
+            subject: Some(premium.clone()),
+            target: Some(basic.clone()),
+        })
+        .await
+        .unwrap();
+
+    // same subject and target
+    let res = clients
+        .component_rules
+        .clone()
+        .create_component_rule(CreateComponentRuleRequest {
+            rule_type: ComponentRuleType::Excludes.into(),
+            subject: Some(basic.clone()),
+            target: Some(basic.clone()),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // unknown add-on
+    let res = clients
+        .component_rules
+        .clone()
+        .create_component_rule(CreateComponentRuleRequest {
+            rule_type: ComponentRuleType::Requires.into(),
+            subject: Some(ComponentRuleItem {
+                item: Some(Item::AddOnId(uuid::Uuid::now_v7().to_string())),
+            }),
+            target: Some(support.clone()),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let rules = clients
+        .component_rules
+        .clone()
+        .list_component_rules(ListComponentRulesRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .rules;
+
+    assert_eq!(rules.len(), 2);
+
+    // validate
+    let validate = |items: Vec<ComponentRuleItem>| {
+        let mut client = clients.component_rules.clone();
+        async move {
+            client
+                .validate_component_rules(ValidateComponentRulesRequest { items })
+                .await
+                .unwrap()
+                .into_inner()
+                .violations
+        }
+    };
+
+    assert!(validate(vec![support.clone(), premium.clone()])
+        .await
+        .is_empty());
+    assert!(validate(vec![support.clone(), basic.clone()])
+        .await
+        .is_empty());
+
+    let violations = validate(vec![premium.clone()]).await;
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].rule_id, requires.id);
+
+    let violations = validate(vec![premium.clone(), basic.clone()]).await;
+    assert_eq!(violations.len(), 2);
+
+    // delete
+    clients
+        .component_rules
+        .clone()
+        .delete_component_rule(DeleteComponentRuleRequest {
+            id: requires.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    assert!(validate(vec![premium.clone()]).await.is_empty());
+
+    let res = clients
+        .component_rules
+        .clone()
+        .delete_component_rule(DeleteComponentRuleRequest { id: requires.id })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+}
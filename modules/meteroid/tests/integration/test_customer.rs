@@ -1,10 +1,21 @@
+use std::sync::Arc;
+
+use meteroid::eventbus::create_eventbus_noop;
 use meteroid_grpc::meteroid::api;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::billing_emails::{EmailDeliveryEvent, EmailDeliveryEventType};
+use meteroid_store::domain::{BillingConfig, StripeCustomerSync, StripeCustomerSyncOutcome};
+use meteroid_store::repositories::billing_emails::BillingEmailsInterface;
+use meteroid_store::repositories::CustomersInterface;
+use meteroid_store::Store;
+use uuid::Uuid;
 
 use tonic::Code;
 
 use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::container::SeedLevel;
+use crate::meteroid_it::db::seed::*;
 
 #[tokio::test]
 async fn test_customers_basic() {
@@ -44,6 +55,7 @@ async fn test_customers_basic() {
                             api::customers::v1::customer_billing_config::Stripe {
                                 customer_id: "customer_id".to_string(),
                                 collection_method: 0,
+                                default_payment_method_id: None,
                             },
                         ),
                     ),
@@ -54,6 +66,7 @@ async fn test_customers_basic() {
                 billing_address: None,
                 shipping_address: None,
                 invoicing_entity_id: None,
+                payment_terms: None,
             }),
         })
         .await
@@ -83,6 +96,7 @@ async fn test_customers_basic() {
                 billing_address: None,
                 shipping_address: None,
                 invoicing_entity_id: None,
+                payment_terms: None,
             }),
         })
         .await
@@ -186,6 +200,7 @@ async fn test_customers_basic() {
                 billing_address: None,
                 shipping_address: None,
                 invoicing_entity_id: None,
+                payment_terms: None,
             }),
         })
         .await
@@ -280,6 +295,724 @@ async fn test_customers_basic() {
     assert_eq!(credits_invoice.customer_id, created_manual.id);
     // bue credits end
 
+    // payment terms start
+    let updated_terms = clients
+        .customers
+        .clone()
+        .update_customer_payment_terms(api::customers::v1::UpdateCustomerPaymentTermsRequest {
+            customer_id: created.id.clone(),
+            payment_terms: Some(api::customers::v1::PaymentTerms {
+                net_terms_override: Some(30),
+                early_payment_discount: Some(api::customers::v1::EarlyPaymentDiscount {
+                    percent: "2".to_string(),
+                    days: 10,
+                }),
+                due_date_policy: Some(api::customers::v1::DueDatePolicy {
+                    r#type: api::customers::v1::due_date_policy::Type::FixedDay.into(),
+                    day: Some(15),
+                }),
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap()
+        .payment_terms
+        .unwrap();
+
+    assert_eq!(updated_terms.net_terms_override, Some(30));
+    let discount = updated_terms.early_payment_discount.unwrap();
+    assert_eq!(discount.percent, "2");
+    assert_eq!(discount.days, 10);
+    let policy = updated_terms.due_date_policy.unwrap();
+    assert_eq!(
+        policy.r#type(),
+        api::customers::v1::due_date_policy::Type::FixedDay
+    );
+    assert_eq!(policy.day, Some(15));
+
+    let invalid_terms = clients
+        .customers
+        .clone()
+        .update_customer_payment_terms(api::customers::v1::UpdateCustomerPaymentTermsRequest {
+            customer_id: created.id.clone(),
+            payment_terms: Some(api::customers::v1::PaymentTerms {
+                net_terms_override: None,
+                early_payment_discount: Some(api::customers::v1::EarlyPaymentDiscount {
+                    percent: "120".to_string(),
+                    days: 10,
+                }),
+                due_date_policy: None,
+            }),
+        })
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(invalid_terms.code(), Code::InvalidArgument);
+
+    let invalid_policy = clients
+        .customers
+        .clone()
+        .update_customer_payment_terms(api::customers::v1::UpdateCustomerPaymentTermsRequest {
+            customer_id: created.id.clone(),
+            payment_terms: Some(api::customers::v1::PaymentTerms {
+                net_terms_override: None,
+                early_payment_discount: None,
+                due_date_policy: Some(api::customers::v1::DueDatePolicy {
+                    r#type: api::customers::v1::due_date_policy::Type::FixedDay.into(),
+                    day: None,
+                }),
+            }),
+        })
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(invalid_policy.code(), Code::InvalidArgument);
+    // payment terms end
+
+    // credit limit start
+    let credit_limit = clients
+        .customers
+        .clone()
+        .update_customer_credit_limit(api::customers::v1::UpdateCustomerCreditLimitRequest {
+            customer_id: created.id.clone(),
+            credit_limit: Some(api::customers::v1::CreditLimit {
+                limit_cents: 50000,
+                enforcement: api::customers::v1::credit_limit::Enforcement::Block.into(),
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap()
+        .credit_limit
+        .unwrap();
+
+    assert_eq!(credit_limit.limit_cents, 50000);
+    assert_eq!(
+        credit_limit.enforcement(),
+        api::customers::v1::credit_limit::Enforcement::Block
+    );
+
+    let exposure = clients
+        .customers
+        .clone()
+        .get_customer_credit_exposure(api::customers::v1::GetCustomerCreditExposureRequest {
+            customer_id: created.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .exposure
+        .unwrap();
+
+    assert_eq!(exposure.total_cents, 0);
+    assert_eq!(exposure.available_cents, Some(50000));
+    assert!(!exposure.limit_exceeded);
+
+    let invalid_limit = clients
+        .customers
+        .clone()
+        .update_customer_credit_limit(api::customers::v1::UpdateCustomerCreditLimitRequest {
+            customer_id: created.id.clone(),
+            credit_limit: Some(api::customers::v1::CreditLimit {
+                limit_cents: -1,
+                enforcement: api::customers::v1::credit_limit::Enforcement::Flag.into(),
+            }),
+        })
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(invalid_limit.code(), Code::InvalidArgument);
+
+    let removed = clients
+        .customers
+        .clone()
+        .update_customer_credit_limit(api::customers::v1::UpdateCustomerCreditLimitRequest {
+            customer_id: created.id.clone(),
+            credit_limit: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(removed.credit_limit, None);
+    // credit limit end
+
+    // invoice delivery start
+    let hosted = clients
+        .customers
+        .clone()
+        .update_customer_invoice_delivery(
+            api::customers::v1::UpdateCustomerInvoiceDeliveryRequest {
+                customer_id: created.id.clone(),
+                channel: Some(api::customers::v1::InvoiceDeliveryChannel::ProviderHosted.into()),
+            },
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(
+        hosted.invoice_delivery_channel(),
+        api::customers::v1::InvoiceDeliveryChannel::ProviderHosted
+    );
+
+    // not billed through Stripe
+    let invalid_channel = clients
+        .customers
+        .clone()
+        .update_customer_invoice_delivery(
+            api::customers::v1::UpdateCustomerInvoiceDeliveryRequest {
+                customer_id: created_manual.id.clone(),
+                channel: Some(api::customers::v1::InvoiceDeliveryChannel::ProviderHosted.into()),
+            },
+        )
+        .await
+        .err()
+        .unwrap();
+
+    assert_eq!(invalid_channel.code(), Code::InvalidArgument);
+
+    let emailed = clients
+        .customers
+        .clone()
+        .update_customer_invoice_delivery(
+            api::customers::v1::UpdateCustomerInvoiceDeliveryRequest {
+                customer_id: created_manual.id.clone(),
+                channel: Some(api::customers::v1::InvoiceDeliveryChannel::Email.into()),
+            },
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(
+        emailed.invoice_delivery_channel(),
+        api::customers::v1::InvoiceDeliveryChannel::Email
+    );
+
+    let unset = clients
+        .customers
+        .clone()
+        .update_customer_invoice_delivery(
+            api::customers::v1::UpdateCustomerInvoiceDeliveryRequest {
+                customer_id: created.id.clone(),
+                channel: None,
+            },
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(unset.invoice_delivery_channel, None);
+    // invoice delivery end
+
+    // billing email status start
+    assert_eq!(
+        created.billing_email_status(),
+        api::customers::v1::BillingEmailStatus::Unverified
+    );
+
+    let updated = setup
+        .store
+        .record_email_delivery_event(EmailDeliveryEvent {
+            event_type: EmailDeliveryEventType::HardBounced,
+            recipient: "FAKE@fake.com".to_string(),
+            tenant_id: None,
+            reason: Some("550 mailbox unavailable".to_string()),
+            occurred_at: chrono::Utc::now().naive_utc(),
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(updated.len(), 1);
+
+    let bounced = clients
+        .customers
+        .clone()
+        .get_customer_by_id(api::customers::v1::GetCustomerByIdRequest {
+            id: created.id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(
+        bounced.billing_email_status(),
+        api::customers::v1::BillingEmailStatus::Bounced
+    );
+    assert_eq!(
+        bounced.billing_email_status_reason,
+        Some("550 mailbox unavailable".to_string())
+    );
+
+    // a delivery does not clear the bounce
+    let updated = setup
+        .store
+        .record_email_delivery_event(EmailDeliveryEvent {
+            event_type: EmailDeliveryEventType::Delivered,
+            recipient: "fake@fake.com".to_string(),
+            tenant_id: None,
+            reason: None,
+            occurred_at: chrono::Utc::now().naive_utc(),
+        })
+        .await
+        .unwrap();
+
+    assert!(updated.is_empty());
+
+    // a new billing email has to be verified again
+    clients
+        .customers
+        .clone()
+        .patch_customer(api::customers::v1::PatchCustomerRequest {
+            customer: Some(api::customers::v1::PatchCustomer {
+                id: created.id.clone(),
+                name: None,
+                email: None,
+                alias: None,
+                invoicing_email: Some("billing@fake.com".to_string()),
+                phone: None,
+                balance_value_cents: None,
+                currency: None,
+                billing_address: None,
+                shipping_address: None,
+                invoicing_entity_id: None,
+                payment_terms: None,
+            }),
+        })
+        .await
+        .unwrap();
+
+    let patched = clients
+        .customers
+        .clone()
+        .get_customer_by_id(api::customers::v1::GetCustomerByIdRequest {
+            id: created.id.to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(
+        patched.billing_email_status(),
+        api::customers::v1::BillingEmailStatus::Unverified
+    );
+    assert_eq!(patched.billing_email_status_reason, None);
+    // billing email status end
+
+    // gocardless mandate start
+    let collected = clients
+        .customers
+        .clone()
+        .create_customer(api::customers::v1::CreateCustomerRequest {
+            data: Some(api::customers::v1::CustomerNew {
+                name: "direct debit".to_string(),
+                alias: Some("direct-debit".to_string()),
+                email: Some("direct-debit@meteroid.com".to_string()),
+                billing_config: Some(api::customers::v1::CustomerBillingConfig {
+                    billing_config_oneof: Some(
+                        api::customers::v1::customer_billing_config::BillingConfigOneof::GoCardless(
+                            api::customers::v1::customer_billing_config::GoCardless {
+                                customer_id: "CU123".to_string(),
+                                mandate_id: Some("MD123".to_string()),
+                            },
+                        ),
+                    ),
+                }),
+                invoicing_email: None,
+                phone: None,
+                currency: "EUR".to_string(),
+                billing_address: None,
+                shipping_address: None,
+                invoicing_entity_id: None,
+                payment_terms: None,
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    let collected = clients
+        .customers
+        .clone()
+        .get_customer_by_id(api::customers::v1::GetCustomerByIdRequest {
+            id: collected.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    let gocardless_mandate = |customer: api::customers::v1::Customer| match customer
+        .billing_config
+        .and_then(|c| c.billing_config_oneof)
+    {
+        Some(api::customers::v1::customer_billing_config::BillingConfigOneof::GoCardless(g)) => {
+            g.mandate_id
+        }
+        other => panic!("unexpected billing config {:?}", other),
+    };
+
+    assert_eq!(
+        gocardless_mandate(collected.clone()),
+        Some("MD123".to_string())
+    );
+
+    // replaced by GoCardless
+    let replaced = setup
+        .store
+        .replace_gocardless_mandate(
+            uuid::Uuid::nil(),
+            meteroid_it::db::seed::TENANT_ID,
+            "MD123".to_string(),
+            Some("MD456".to_string()),
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(replaced.id.to_string(), collected.id);
+
+    // then failed, the customer cannot be collected anymore
+    setup
+        .store
+        .replace_gocardless_mandate(
+            uuid::Uuid::nil(),
+            meteroid_it::db::seed::TENANT_ID,
+            "MD456".to_string(),
+            None,
+        )
+        .await
+        .unwrap()
+        .unwrap();
+
+    let failed = clients
+        .customers
+        .clone()
+        .get_customer_by_id(api::customers::v1::GetCustomerByIdRequest {
+            id: collected.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    assert_eq!(gocardless_mandate(failed), None);
+
+    // unknown mandate
+    let unknown = setup
+        .store
+        .replace_gocardless_mandate(
+            uuid::Uuid::nil(),
+            meteroid_it::db::seed::TENANT_ID,
+            "MD456".to_string(),
+            None,
+        )
+        .await
+        .unwrap();
+
+    assert!(unknown.is_none());
+    // gocardless mandate end
+
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
+
+#[tokio::test]
+async fn test_customers_uniqueness() {
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let customer = |name: &str, alias: Option<&str>, email: &str| api::customers::v1::CustomerNew {
+        name: name.to_string(),
+        alias: alias.map(|a| a.to_string()),
+        email: Some(email.to_string()),
+        billing_config: None,
+        invoicing_email: None,
+        phone: None,
+        currency: "EUR".to_string(),
+        billing_address: None,
+        shipping_address: None,
+        invoicing_entity_id: None,
+        payment_terms: None,
+    };
+
+    // not enforced by default
+    for name in ["first", "second"] {
+        clients
+            .customers
+            .clone()
+            .create_customer(api::customers::v1::CreateCustomerRequest {
+                data: Some(customer(name, None, "duplicate@acme.com")),
+            })
+            .await
+            .unwrap();
+    }
+
+    // the existing customers are not unique
+    let rejected = set_customer_uniqueness(&clients, api::tenants::v1::CustomerUniqueness::Email)
+        .await
+        .unwrap_err();
+    assert_eq!(rejected.code(), Code::FailedPrecondition);
+
+    let created = clients
+        .customers
+        .clone()
+        .create_customer(api::customers::v1::CreateCustomerRequest {
+            data: Some(customer("unique", Some("unique"), "unique@acme.com")),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .customer
+        .unwrap();
+
+    let tenant = set_customer_uniqueness(&clients, api::tenants::v1::CustomerUniqueness::Alias)
+        .await
+        .unwrap()
+        .into_inner()
+        .tenant
+        .unwrap();
+    assert_eq!(
+        tenant.customer_uniqueness(),
+        api::tenants::v1::CustomerUniqueness::Alias
+    );
+
+    let duplicate_alias = clients
+        .customers
+        .clone()
+        .create_customer(api::customers::v1::CreateCustomerRequest {
+            data: Some(customer("other", Some("UNIQUE "), "other@acme.com")),
+        })
+        .await
+        .unwrap_err();
+    assert_eq!(duplicate_alias.code(), Code::AlreadyExists);
+
+    // find or create returns the existing customer
+    let found = clients
+        .customers
+        .clone()
+        .find_or_create_customer(api::customers::v1::FindOrCreateCustomerRequest {
+            data: Some(customer("retry", Some("Unique"), "unique@acme.com")),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(!found.created);
+    assert_eq!(found.customer.unwrap().id, created.id);
+
+    let new = clients
+        .customers
+        .clone()
+        .find_or_create_customer(api::customers::v1::FindOrCreateCustomerRequest {
+            data: Some(customer("new", Some("new"), "new@acme.com")),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+    assert!(new.created);
+    assert_ne!(new.customer.unwrap().id, created.id);
+}
+
+#[tokio::test]
+async fn test_sync_stripe_customer() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(&store.pool, SeedLevel::PRODUCT).await;
+
+    let actor = Uuid::nil();
+
+    let stripe_customer = |stripe_customer_id: &str| StripeCustomerSync {
+        stripe_customer_id: stripe_customer_id.to_string(),
+        meteroid_customer_id: None,
+        name: Some("Acme".to_string()),
+        email: Some("billing@acme.com".to_string()),
+        phone: None,
+        currency: Some("usd".to_string()),
+        billing_address: None,
+        default_payment_method_id: None,
+    };
+
+    // no match, created
+    let (created, outcome) = store
+        .sync_stripe_customer(actor, TENANT_ID, stripe_customer("cus_acme"))
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, StripeCustomerSyncOutcome::Created);
+    assert_eq!(created.name, "Acme");
+    assert_eq!(created.currency, "USD");
+    match &created.billing_config {
+        BillingConfig::Stripe(stripe) => {
+            assert_eq!(stripe.customer_id, "cus_acme");
+            assert_eq!(stripe.collection_method, 0);
+        }
+        _ => panic!("expected a Stripe billing config"),
+    }
+
+    // replayed (webhook redelivery, repeated import), the linked customer is updated in place
+    let (updated, outcome) = store
+        .sync_stripe_customer(
+            actor,
+            TENANT_ID,
+            StripeCustomerSync {
+                name: Some("Acme Inc".to_string()),
+                default_payment_method_id: Some("pm_1".to_string()),
+                ..stripe_customer("cus_acme")
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, StripeCustomerSyncOutcome::Updated);
+    assert_eq!(updated.id, created.id);
+    assert_eq!(updated.name, "Acme Inc");
+    match &updated.billing_config {
+        BillingConfig::Stripe(stripe) => {
+            // the collection method of a linked customer is kept
+            assert_eq!(stripe.collection_method, 0);
+            assert_eq!(stripe.default_payment_method_id.as_deref(), Some("pm_1"));
+        }
+        _ => panic!("expected a Stripe billing config"),
+    }
+
+    // deleted in Stripe, falls back to manual billing
+    let unlinked = store
+        .unlink_stripe_customer(actor, TENANT_ID, "cus_acme".to_string())
+        .await
+        .unwrap()
+        .unwrap();
+
+    assert_eq!(unlinked.id, created.id);
+    assert!(matches!(unlinked.billing_config, BillingConfig::Manual));
+    assert!(store
+        .unlink_stripe_customer(actor, TENANT_ID, "cus_acme".to_string())
+        .await
+        .unwrap()
+        .is_none());
+
+    // matched by email, case-insensitive
+    let (linked, outcome) = store
+        .sync_stripe_customer(
+            actor,
+            TENANT_ID,
+            StripeCustomerSync {
+                email: Some("Billing@Acme.com".to_string()),
+                default_payment_method_id: Some("pm_2".to_string()),
+                ..stripe_customer("cus_acme_2")
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, StripeCustomerSyncOutcome::Linked);
+    assert_eq!(linked.id, created.id);
+    match &linked.billing_config {
+        BillingConfig::Stripe(stripe) => {
+            assert_eq!(stripe.customer_id, "cus_acme_2");
+            // a payment method to charge
+            assert_eq!(stripe.collection_method, 1);
+        }
+        _ => panic!("expected a Stripe billing config"),
+    }
+
+    // matched by the metadata, but already linked to another Stripe customer
+    assert!(store
+        .sync_stripe_customer(
+            actor,
+            TENANT_ID,
+            StripeCustomerSync {
+                meteroid_customer_id: Some(CUSTOMER_UBER_ID),
+                email: None,
+                ..stripe_customer("cus_uber_2")
+            },
+        )
+        .await
+        .is_err());
+
+    // matched by the metadata once unlinked
+    store
+        .unlink_stripe_customer(actor, TENANT_ID, "uber".to_string())
+        .await
+        .unwrap();
+
+    let (linked, outcome) = store
+        .sync_stripe_customer(
+            actor,
+            TENANT_ID,
+            StripeCustomerSync {
+                meteroid_customer_id: Some(CUSTOMER_UBER_ID),
+                email: None,
+                ..stripe_customer("cus_uber_2")
+            },
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(outcome, StripeCustomerSyncOutcome::Linked);
+    assert_eq!(linked.id, CUSTOMER_UBER_ID);
+}
+
+async fn set_customer_uniqueness(
+    clients: &meteroid_it::clients::AllClients,
+    uniqueness: api::tenants::v1::CustomerUniqueness,
+) -> Result<tonic::Response<api::tenants::v1::UpdateTenantResponse>, tonic::Status> {
+    clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                customer_uniqueness: Some(uniqueness.into()),
+                ..Default::default()
+            }),
+        })
+        .await
+}
@@ -1,4 +1,5 @@
 use meteroid_grpc::meteroid::api;
+use tonic_types::StatusExt;
 
 use crate::helpers;
 use crate::meteroid_it;
@@ -55,7 +56,20 @@ async fn test_instance() {
 
     log::error!("{:?}", new_org_res);
 
-    assert!(new_org_res.is_err());
+    let status = new_org_res.err().unwrap();
+
+    // the error code is listed in the catalog
+    let error_codes = clients
+        .instance
+        .clone()
+        .list_error_codes(api::instance::v1::ListErrorCodesRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .error_codes;
+
+    let error_info = status.get_details_error_info().unwrap();
+    assert!(error_codes.iter().any(|c| c.code == error_info.reason));
 
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
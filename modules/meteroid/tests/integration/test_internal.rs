@@ -32,6 +32,10 @@ async fn test_internal_basic() {
         .create_api_token(tonic::Request::new(
             meteroid_grpc::meteroid::api::apitokens::v1::CreateApiTokenRequest {
                 name: "some-api-key".to_string(),
+                daily_request_soft_limit: None,
+                partner_id: None,
+                scopes: vec![],
+                expires_at: None,
             },
         ))
         .await
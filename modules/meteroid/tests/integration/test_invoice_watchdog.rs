@@ -0,0 +1,190 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+use chrono::{NaiveDate, NaiveDateTime};
+use diesel_async::SimpleAsyncConnection;
+use meteroid::eventbus::create_eventbus_memory;
+use meteroid::workers::invoicing::draft_worker::draft_worker;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::enums::InvoiceStuckReasonEnum;
+use meteroid_store::domain::invoice_watchdog::{InvoiceWatchdogSla, StuckInvoice};
+use meteroid_store::domain::{OrderByRequest, PaginationRequest};
+use meteroid_store::repositories::invoice_watchdog::InvoiceWatchdogInterface;
+use meteroid_store::repositories::InvoiceInterface;
+use meteroid_store::Store;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
+
+const STALE_DRAFT_ID: Uuid = uuid!("0193a0c2-4e1f-7d3a-8b52-6c9e1f2a3b01");
+const PENDING_ID: Uuid = uuid!("0193a0c2-4e1f-7d3a-8b52-6c9e1f2a3b02");
+const EXHAUSTED_ID: Uuid = uuid!("0193a0c2-4e1f-7d3a-8b52-6c9e1f2a3b03");
+
+#[tokio::test]
+async fn test_invoice_watchdog_alerts_once() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let drafted = store
+        .list_invoices(
+            TENANT_ID,
+            None,
+            None,
+            None,
+            OrderByRequest::DateAsc,
+            PaginationRequest {
+                per_page: Some(100),
+                page: 0,
+            },
+        )
+        .await
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_SPORTIFY_ID1))
+        .unwrap();
+
+    // one invoice stuck in each status
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "
+        create temp table stuck_invoice as select * from invoice where id = '{drafted_id}';
+
+        update stuck_invoice
+        set id = '{STALE_DRAFT_ID}', status = 'DRAFT', invoice_number = 'STUCK-DRAFT',
+            invoice_date = '2024-01-01';
+        insert into invoice select * from stuck_invoice;
+
+        update stuck_invoice
+        set id = '{PENDING_ID}', status = 'PENDING', invoice_number = 'STUCK-PENDING',
+            invoice_date = '2024-01-02';
+        insert into invoice select * from stuck_invoice;
+
+        update stuck_invoice
+        set id = '{EXHAUSTED_ID}', status = 'FINALIZED', invoice_number = 'STUCK-FINALIZED',
+            invoice_date = '2024-01-03', finalized_at = '2024-01-03', invoicing_provider = 'STRIPE',
+            issued = false, issue_attempts = 5, suppressed_at = null, last_issue_error = 'card declined';
+        insert into invoice select * from stuck_invoice;
+
+        drop table stuck_invoice;
+        ",
+        drafted_id = drafted.id,
+    ))
+    .await
+    .unwrap();
+
+    let sla = InvoiceWatchdogSla::default();
+    let now = datetime("2024-02-01T00:00:00");
+
+    let alerted = store.alert_stuck_invoices(&sla, now).await.unwrap();
+    assert_eq!(
+        reasons(&alerted),
+        vec![
+            (STALE_DRAFT_ID, InvoiceStuckReasonEnum::StaleDraft),
+            (PENDING_ID, InvoiceStuckReasonEnum::PendingFinalization),
+            (EXHAUSTED_ID, InvoiceStuckReasonEnum::IssueRetriesExhausted),
+        ]
+    );
+
+    // the invoices still stuck are not alerted again
+    let alerted = store
+        .alert_stuck_invoices(&sla, datetime("2024-02-01T00:30:00"))
+        .await
+        .unwrap();
+    assert!(alerted.is_empty());
+
+    // the alert of an invoice that moved on is resolved
+    conn.batch_execute(&format!(
+        "update invoice set status = 'FINALIZED', issued = true where id = '{PENDING_ID}';"
+    ))
+    .await
+    .unwrap();
+
+    let alerted = store
+        .alert_stuck_invoices(&sla, datetime("2024-02-01T01:00:00"))
+        .await
+        .unwrap();
+    assert!(alerted.is_empty());
+
+    let alert = store
+        .find_invoice_stuck_alert(TENANT_ID, PENDING_ID)
+        .await
+        .unwrap();
+    assert_eq!(alert.resolved_at, Some(datetime("2024-02-01T01:00:00")));
+
+    // the SLAs of the tenant override the defaults
+    conn.batch_execute(&format!(
+        "update tenant set stuck_draft_max_age_days = 60, stuck_max_issue_attempts = 10 where id = '{TENANT_ID}';"
+    ))
+    .await
+    .unwrap();
+
+    let stuck = store
+        .list_stuck_invoices(TENANT_ID, &sla, datetime("2024-02-01T01:30:00"))
+        .await
+        .unwrap();
+    assert!(!stuck
+        .iter()
+        .any(|i| i.invoice_id == STALE_DRAFT_ID || i.invoice_id == EXHAUSTED_ID));
+
+    // an invoice stuck again is alerted again
+    conn.batch_execute(&format!(
+        "update invoice set status = 'PENDING', issued = false where id = '{PENDING_ID}';"
+    ))
+    .await
+    .unwrap();
+
+    let alerted = store
+        .alert_stuck_invoices(&sla, datetime("2024-02-01T01:30:00"))
+        .await
+        .unwrap();
+    assert_eq!(
+        reasons(&alerted),
+        vec![(PENDING_ID, InvoiceStuckReasonEnum::PendingFinalization)]
+    );
+
+    let alert = store
+        .find_invoice_stuck_alert(TENANT_ID, PENDING_ID)
+        .await
+        .unwrap();
+    assert_eq!(alert.resolved_at, None);
+}
+
+/// The reasons of the invoices created by the test, the seeded drafts are stale as well
+fn reasons(stuck: &[StuckInvoice]) -> Vec<(Uuid, InvoiceStuckReasonEnum)> {
+    let mut reasons = stuck
+        .iter()
+        .filter(|i| [STALE_DRAFT_ID, PENDING_ID, EXHAUSTED_ID].contains(&i.invoice_id))
+        .map(|i| (i.invoice_id, i.reason))
+        .collect::<Vec<_>>();
+    reasons.sort_by_key(|(id, _)| *id);
+    reasons
+}
+
+fn date(date_str: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d").expect("Invalid date format")
+}
+
+fn datetime(datetime_str: &str) -> NaiveDateTime {
+    NaiveDateTime::parse_from_str(datetime_str, "%Y-%m-%dT%H:%M:%S")
+        .expect("Invalid datetime format")
+}
@@ -0,0 +1,170 @@
+use std::collections::HashMap;
+
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api::notificationbundles::v1::localized_message::Source;
+use meteroid_grpc::meteroid::api::notificationbundles::v1::{
+    DeleteNotificationBundleRequest, ListNotificationBundlesRequest, NotificationNamespace,
+    ResolveNotificationMessagesRequest, UpsertNotificationBundleRequest,
+};
+
+#[tokio::test]
+async fn test_notification_bundles() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let messages = |entries: &[(&str, &str)]| -> HashMap<String, String> {
+        entries
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    };
+
+    let german = clients
+        .notification_bundles
+        .clone()
+        .upsert_notification_bundle(UpsertNotificationBundleRequest {
+            namespace: NotificationNamespace::Pdf.into(),
+            locale: "de".to_string(),
+            messages: messages(&[
+                ("invoice-title", "Rechnung"),
+                ("invoice-number", "Rechnung Nr. {$invoice_number}"),
+            ]),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .bundle
+        .unwrap();
+
+    assert_eq!(german.locale, "de");
+    assert_eq!(german.messages.len(), 2);
+
+    // replaces the messages of the same locale
+    let replaced = clients
+        .notification_bundles
+        .clone()
+        .upsert_notification_bundle(UpsertNotificationBundleRequest {
+            namespace: NotificationNamespace::Pdf.into(),
+            locale: "de".to_string(),
+            messages: messages(&[("invoice-title", "Rechnung"), ("tax", "MwSt.")]),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .bundle
+        .unwrap();
+
+    assert_eq!(replaced.id, german.id);
+    assert_eq!(
+        replaced.messages.get("tax").map(String::as_str),
+        Some("MwSt.")
+    );
+    assert!(!replaced.messages.contains_key("invoice-number"));
+
+    // unknown variable, unknown message, invalid locale
+    for (locale, key, template) in [
+        ("de", "invoice-number", "Rechnung {$number}"),
+        ("de", "invoice-footer", "Danke"),
+        ("german", "invoice-title", "Rechnung"),
+    ] {
+        let res = clients
+            .notification_bundles
+            .clone()
+            .upsert_notification_bundle(UpsertNotificationBundleRequest {
+                namespace: NotificationNamespace::Pdf.into(),
+                locale: locale.to_string(),
+                messages: messages(&[(key, template)]),
+            })
+            .await;
+
+        assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+    }
+
+    clients
+        .notification_bundles
+        .clone()
+        .upsert_notification_bundle(UpsertNotificationBundleRequest {
+            namespace: NotificationNamespace::Email.into(),
+            locale: "de-DE".to_string(),
+            messages: messages(&[("payment-reminder-subject", "Zahlungserinnerung")]),
+        })
+        .await
+        .unwrap();
+
+    let bundles = clients
+        .notification_bundles
+        .clone()
+        .list_notification_bundles(ListNotificationBundlesRequest {
+            namespace: Some(NotificationNamespace::Pdf.into()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .bundles;
+
+    assert_eq!(bundles.len(), 1);
+
+    // de-DE falls back to the de bundle, then to the built-in messages
+    let resolved = clients
+        .notification_bundles
+        .clone()
+        .resolve_notification_messages(ResolveNotificationMessagesRequest {
+            namespace: NotificationNamespace::Pdf.into(),
+            locale: "de-DE".to_string(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .messages;
+
+    let source = |key: &str| {
+        resolved
+            .iter()
+            .find(|m| m.key == key)
+            .and_then(|m| m.source.clone())
+            .unwrap()
+    };
+
+    assert_eq!(source("tax"), Source::TenantLocale("de".to_string()));
+    assert_eq!(
+        source("subtotal"),
+        Source::BuiltInLocale("en-US".to_string())
+    );
+
+    clients
+        .notification_bundles
+        .clone()
+        .delete_notification_bundle(DeleteNotificationBundleRequest {
+            id: german.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    let bundles = clients
+        .notification_bundles
+        .clone()
+        .list_notification_bundles(ListNotificationBundlesRequest { namespace: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .bundles;
+
+    assert_eq!(bundles.len(), 1);
+    assert_eq!(bundles[0].namespace(), NotificationNamespace::Email);
+}
@@ -0,0 +1,133 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api::paymentreminders::v1::{
+    CreatePaymentReminderScheduleRequest, DeletePaymentReminderScheduleRequest,
+    ListPaymentReminderSchedulesRequest, PaymentReminderChannel,
+    UpdatePaymentReminderScheduleRequest,
+};
+
+#[tokio::test]
+async fn test_payment_reminder_schedules() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    // create
+    let before_due = clients
+        .payment_reminders
+        .clone()
+        .create_payment_reminder_schedule(CreatePaymentReminderScheduleRequest {
+            offset_days: -3,
+            channel: PaymentReminderChannel::Email.into(),
+            enabled: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .schedule
+        .unwrap();
+
+    assert_eq!(before_due.offset_days, -3);
+    assert!(before_due.enabled);
+
+    clients
+        .payment_reminders
+        .clone()
+        .create_payment_reminder_schedule(CreatePaymentReminderScheduleRequest {
+            offset_days: 7,
+            channel: PaymentReminderChannel::Webhook.into(),
+            enabled: Some(true),
+        })
+        .await
+        .unwrap();
+
+    // same day and channel
+    let res = clients
+        .payment_reminders
+        .clone()
+        .create_payment_reminder_schedule(CreatePaymentReminderScheduleRequest {
+            offset_days: -3,
+            channel: PaymentReminderChannel::Email.into(),
+            enabled: None,
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // out of range
+    let res = clients
+        .payment_reminders
+        .clone()
+        .create_payment_reminder_schedule(CreatePaymentReminderScheduleRequest {
+            offset_days: 365,
+            channel: PaymentReminderChannel::Email.into(),
+            enabled: None,
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // disable
+    let disabled = clients
+        .payment_reminders
+        .clone()
+        .update_payment_reminder_schedule(UpdatePaymentReminderScheduleRequest {
+            id: before_due.id.clone(),
+            enabled: false,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .schedule
+        .unwrap();
+
+    assert!(!disabled.enabled);
+
+    // list, earliest first
+    let schedules = clients
+        .payment_reminders
+        .clone()
+        .list_payment_reminder_schedules(ListPaymentReminderSchedulesRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .schedules;
+
+    assert_eq!(schedules.len(), 2);
+    assert_eq!(schedules[0].offset_days, -3);
+    assert_eq!(schedules[1].offset_days, 7);
+
+    // delete
+    clients
+        .payment_reminders
+        .clone()
+        .delete_payment_reminder_schedule(DeletePaymentReminderScheduleRequest {
+            id: before_due.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    let res = clients
+        .payment_reminders
+        .clone()
+        .delete_payment_reminder_schedule(DeletePaymentReminderScheduleRequest {
+            id: before_due.id,
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+}
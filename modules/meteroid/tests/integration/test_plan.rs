@@ -2,12 +2,15 @@ use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::container::SeedLevel;
 use meteroid_grpc::meteroid::api;
+use meteroid_grpc::meteroid::api::plans::v1::catalog_import_conflict::Kind;
+use meteroid_grpc::meteroid::api::plans::v1::import_plans_request::ConflictStrategy;
 use meteroid_grpc::meteroid::api::plans::v1::plan_billing_configuration::BillingCycles;
 use meteroid_grpc::meteroid::api::plans::v1::plan_billing_configuration::SubscriptionAnniversary;
 use meteroid_grpc::meteroid::api::plans::v1::plan_billing_configuration::{
     Forever, ServicePeriodStart,
 };
 use meteroid_grpc::meteroid::api::plans::v1::PlanBillingConfiguration;
+use meteroid_store::domain::plan_catalog::{CatalogDocument, CATALOG_DOCUMENT_VERSION};
 
 #[tokio::test]
 async fn test_plans_basic() {
@@ -238,6 +241,7 @@ async fn test_plans_basic() {
             currency: "AUD".to_string(),
             net_terms: 5,
             billing_periods: vec![api::shared::v1::BillingPeriod::Quarterly as i32],
+            tax_category: None,
         })
         .await
         .unwrap()
@@ -259,6 +263,27 @@ async fn test_plans_basic() {
         &vec![api::shared::v1::BillingPeriod::Quarterly as i32]
     );
 
+    // diff the draft with the published version
+    let diff = clients
+        .plans
+        .clone()
+        .diff_plan_versions(api::plans::v1::DiffPlanVersionsRequest {
+            from_plan_version_id: published_version.id.clone(),
+            to_plan_version_id: copied_draft_version.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let changed_fields: Vec<&str> = diff.changes.iter().map(|c| c.field.as_str()).collect();
+    assert_eq!(
+        changed_fields,
+        vec!["billing_periods", "currency", "net_terms"]
+    );
+    assert_eq!(diff.changes[1].before, Some("EUR".to_string()));
+    assert_eq!(diff.changes[1].after, Some("AUD".to_string()));
+    assert!(diff.components.is_empty());
+
     // discard plan version
     clients
         .plans
@@ -328,3 +353,346 @@ async fn test_plans_basic() {
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
+
+#[tokio::test]
+async fn test_plans_export_import() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::PLANS).await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let exported = clients
+        .plans
+        .clone()
+        .export_plans(api::plans::v1::ExportPlansRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .document;
+
+    let document: CatalogDocument = serde_json::from_str(&exported).unwrap();
+    let plans_count = document.plans.len() as u32;
+
+    assert_eq!(document.version, CATALOG_DOCUMENT_VERSION);
+    assert!(plans_count > 0);
+
+    // the plans already exist
+    let conflicting = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: exported.clone(),
+            on_conflict: ConflictStrategy::Fail.into(),
+            dry_run: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!conflicting.applied);
+    assert_eq!(conflicting.conflicts.len() as u32, plans_count);
+    assert!(conflicting
+        .conflicts
+        .iter()
+        .all(|c| c.kind() == Kind::PlanExists));
+
+    // new plans are created as drafts, the families and products are matched
+    let mut renamed = document.clone();
+    for plan in renamed.plans.iter_mut() {
+        plan.external_id = format!("{}_imported", plan.external_id);
+        if let Some(trial) = plan.version.trial.as_mut() {
+            trial.trialing_plan_external_id = trial
+                .trialing_plan_external_id
+                .take()
+                .map(|id| format!("{}_imported", id));
+            trial.downgrade_plan_external_id = trial
+                .downgrade_plan_external_id
+                .take()
+                .map(|id| format!("{}_imported", id));
+        }
+    }
+
+    let dry_run = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: serde_json::to_string(&renamed).unwrap(),
+            on_conflict: ConflictStrategy::Fail.into(),
+            dry_run: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(!dry_run.applied);
+    assert!(dry_run.conflicts.is_empty());
+    assert_eq!(dry_run.plans, plans_count);
+
+    let imported = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: serde_json::to_string(&renamed).unwrap(),
+            on_conflict: ConflictStrategy::Fail.into(),
+            dry_run: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(imported.applied);
+    assert_eq!(imported.plans, plans_count);
+    assert_eq!(imported.product_families, 0);
+    assert_eq!(imported.products, 0);
+
+    let imported_plan = clients
+        .plans
+        .clone()
+        .get_plan_by_external_id(api::plans::v1::GetPlanByExternalIdRequest {
+            external_id: renamed.plans[0].external_id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .plan_details
+        .unwrap();
+
+    assert_eq!(
+        imported_plan.plan.unwrap().plan_status(),
+        api::plans::v1::PlanStatus::Draft
+    );
+
+    // the existing plans get a draft version
+    let new_versions = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: exported.clone(),
+            on_conflict: ConflictStrategy::NewDraftVersion.into(),
+            dry_run: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(new_versions.applied);
+    assert_eq!(new_versions.plans, 0);
+    assert_eq!(new_versions.plan_versions, plans_count);
+
+    let skipped = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: exported.clone(),
+            on_conflict: ConflictStrategy::Skip.into(),
+            dry_run: false,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(skipped.applied);
+    assert_eq!(skipped.skipped_plans.len() as u32, plans_count);
+
+    // unknown document version
+    let mut unsupported = document.clone();
+    unsupported.version = CATALOG_DOCUMENT_VERSION + 1;
+
+    let res = clients
+        .plans
+        .clone()
+        .import_plans(api::plans::v1::ImportPlansRequest {
+            document: serde_json::to_string(&unsupported).unwrap(),
+            on_conflict: ConflictStrategy::Fail.into(),
+            dry_run: false,
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
+
+#[tokio::test]
+async fn test_migrate_subscriptions() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup = meteroid_it::container::start_meteroid(
+        postgres_connection_string,
+        SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    // notion, with 2 seeded subscriptions
+    let plan_id = "018c344b-da85-70dc-ae6f-5b919847dbbf".to_string();
+    let plan_version_id = "018c344b-da87-7392-bbae-c5c8780adb1b".to_string();
+
+    let draft_version = clients
+        .plans
+        .clone()
+        .copy_version_to_draft(api::plans::v1::CopyVersionToDraftRequest {
+            plan_id: plan_id.clone(),
+            plan_version_id: plan_version_id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .plan_version
+        .unwrap();
+
+    let migrate = |strategy: api::plans::v1::SubscriptionMigrationStrategy,
+                   include_grandfathered: bool| {
+        let mut client = clients.plans.clone();
+        let to_plan_version_id = draft_version.id.clone();
+        async move {
+            client
+                .migrate_subscriptions(api::plans::v1::MigrateSubscriptionsRequest {
+                    to_plan_version_id,
+                    strategy: strategy.into(),
+                    from_plan_version_id: None,
+                    include_grandfathered,
+                })
+                .await
+        }
+    };
+
+    // the target must be published
+    let res = migrate(
+        api::plans::v1::SubscriptionMigrationStrategy::AtNextRenewal,
+        false,
+    )
+    .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    clients
+        .plans
+        .clone()
+        .publish_plan_version(api::plans::v1::PublishPlanVersionRequest {
+            plan_id: plan_id.clone(),
+            plan_version_id: draft_version.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    // grandfather
+    let migration = migrate(
+        api::plans::v1::SubscriptionMigrationStrategy::Grandfather,
+        false,
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .migration
+    .unwrap();
+
+    assert_eq!(migration.total_count, 2);
+    assert_eq!(migration.processed_count, 2);
+    assert_eq!(migration.failed_count, 0);
+    assert_eq!(migration.mrr_delta, 0);
+    assert!(migration.completed_at.is_some());
+
+    let fetched = clients
+        .plans
+        .clone()
+        .get_subscription_migration(api::plans::v1::GetSubscriptionMigrationRequest {
+            migration_id: migration.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .migration
+        .unwrap();
+
+    assert_eq!(fetched, migration);
+
+    let items = clients
+        .plans
+        .clone()
+        .list_subscription_migration_items(api::plans::v1::ListSubscriptionMigrationItemsRequest {
+            migration_id: migration.id.clone(),
+            status: None,
+            pagination: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .items;
+
+    assert_eq!(items.len(), 2);
+    assert!(items
+        .iter()
+        .all(|i| i.status() == api::plans::v1::subscription_migration_item::Status::Grandfathered));
+    assert!(items
+        .iter()
+        .all(|i| i.from_plan_version_id == plan_version_id));
+
+    // the grandfathered subscriptions are left out
+    let migration = migrate(
+        api::plans::v1::SubscriptionMigrationStrategy::AtNextRenewal,
+        false,
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .migration
+    .unwrap();
+
+    assert_eq!(migration.total_count, 0);
+    assert!(migration.completed_at.is_some());
+
+    // unless included
+    let migration = migrate(
+        api::plans::v1::SubscriptionMigrationStrategy::AtNextRenewal,
+        true,
+    )
+    .await
+    .unwrap()
+    .into_inner()
+    .migration
+    .unwrap();
+
+    assert_eq!(migration.total_count, 2);
+    assert_eq!(migration.processed_count, 2);
+
+    let scheduled = clients
+        .plans
+        .clone()
+        .list_subscription_migration_items(api::plans::v1::ListSubscriptionMigrationItemsRequest {
+            migration_id: migration.id.clone(),
+            status: Some(api::plans::v1::subscription_migration_item::Status::Scheduled.into()),
+            pagination: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .items;
+
+    assert_eq!(
+        scheduled.len() as u32,
+        migration.processed_count - migration.failed_count
+    );
+}
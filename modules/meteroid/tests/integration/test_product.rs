@@ -28,6 +28,7 @@ async fn test_products_basic() {
         .create_product_family(api::productfamilies::v1::CreateProductFamilyRequest {
             name: "product_family_name".into(),
             external_id: "product_family_external_id".into(),
+            defaults: None,
         })
         .await
         .unwrap()
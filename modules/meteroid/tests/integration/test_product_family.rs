@@ -29,6 +29,7 @@ async fn test_product_families_basic() {
         .create_product_family(api::productfamilies::v1::CreateProductFamilyRequest {
             name: "product_family_name".into(),
             external_id: "product_family_external_id".into(),
+            defaults: None,
         })
         .await
         .unwrap()
@@ -72,3 +73,124 @@ async fn test_product_families_basic() {
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
+
+#[tokio::test]
+async fn test_product_family_defaults() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    clients
+        .product_families
+        .clone()
+        .create_product_family(api::productfamilies::v1::CreateProductFamilyRequest {
+            name: "product_family_name".into(),
+            external_id: "product_family_external_id".into(),
+            defaults: Some(api::productfamilies::v1::ProductFamilyDefaults {
+                currency: Some("USD".into()),
+                trial_duration_days: None,
+                net_terms: Some(30),
+                tax_category: None,
+            }),
+        })
+        .await
+        .unwrap();
+
+    let version = clients
+        .plans
+        .clone()
+        .create_draft_plan(api::plans::v1::CreateDraftPlanRequest {
+            name: "plan_name".into(),
+            external_id: "plan_external_id".into(),
+            product_family_external_id: "product_family_external_id".into(),
+            description: None,
+            plan_type: api::plans::v1::PlanType::Standard as i32,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .plan
+        .unwrap()
+        .current_version
+        .unwrap();
+
+    assert_eq!(version.currency.as_str(), "USD");
+
+    // the draft follows the updated defaults
+    let updated = clients
+        .product_families
+        .clone()
+        .update_product_family_defaults(
+            api::productfamilies::v1::UpdateProductFamilyDefaultsRequest {
+                external_id: "product_family_external_id".into(),
+                defaults: Some(api::productfamilies::v1::ProductFamilyDefaults {
+                    currency: Some("USD".into()),
+                    trial_duration_days: None,
+                    net_terms: Some(15),
+                    tax_category: Some("saas".into()),
+                }),
+            },
+        )
+        .await
+        .unwrap()
+        .into_inner()
+        .product_family
+        .unwrap();
+
+    assert_eq!(updated.defaults.unwrap().net_terms, Some(15));
+
+    let config = clients
+        .plans
+        .clone()
+        .get_plan_version_effective_config(api::plans::v1::GetPlanVersionEffectiveConfigRequest {
+            plan_version_id: version.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .config
+        .unwrap();
+
+    use api::plans::v1::plan_version_effective_config::Source;
+
+    assert_eq!(config.currency.as_str(), "USD");
+    assert_eq!(config.currency_source(), Source::ProductFamily);
+    assert_eq!(config.net_terms, 15);
+    assert_eq!(config.net_terms_source(), Source::ProductFamily);
+    assert_eq!(config.tax_category, Some("saas".to_string()));
+    assert_eq!(config.trial_duration_days, None);
+    assert_eq!(config.trial_duration_source(), Source::Default);
+
+    // an unknown currency is refused
+    let res = clients
+        .product_families
+        .clone()
+        .update_product_family_defaults(
+            api::productfamilies::v1::UpdateProductFamilyDefaultsRequest {
+                external_id: "product_family_external_id".into(),
+                defaults: Some(api::productfamilies::v1::ProductFamilyDefaults {
+                    currency: Some("XXX".into()),
+                    ..Default::default()
+                }),
+            },
+        )
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
@@ -0,0 +1,203 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use meteroid_grpc::meteroid::api::invoices::v1::InvoicingProvider;
+use meteroid_grpc::meteroid::api::providers::v1::provider_credentials::{
+    Adyen, Credentials, Stripe,
+};
+use meteroid_grpc::meteroid::api::providers::v1::{
+    DisableProviderConfigRequest, ListProviderConfigsRequest, ProviderCredentials,
+    SetDefaultProviderRequest, SetProviderFallbackRequest, UpsertProviderConfigRequest,
+};
+
+#[tokio::test]
+async fn test_providers_basic() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    // configure stripe as default
+    let created = clients
+        .providers
+        .clone()
+        .upsert_provider_config(UpsertProviderConfigRequest {
+            credentials: Some(ProviderCredentials {
+                credentials: Some(Credentials::Stripe(Stripe {
+                    api_secret: "sk_test_secret1234".into(),
+                    webhook_secret: "whsec_secret".into(),
+                })),
+            }),
+            is_default: true,
+            fallback_priority: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .config
+        .unwrap();
+
+    assert_eq!(created.provider(), InvoicingProvider::Stripe);
+    assert!(created.is_default);
+    assert_eq!(created.api_secret_hint, "...1234");
+
+    // set as fallback
+    let updated = clients
+        .providers
+        .clone()
+        .set_provider_fallback(SetProviderFallbackRequest {
+            provider: InvoicingProvider::Stripe.into(),
+            fallback_priority: Some(1),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .config
+        .unwrap();
+
+    assert_eq!(updated.fallback_priority, Some(1));
+
+    // list
+    let listed = clients
+        .providers
+        .clone()
+        .list_provider_configs(ListProviderConfigsRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .configs;
+
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, created.id);
+
+    // disable
+    clients
+        .providers
+        .clone()
+        .disable_provider_config(DisableProviderConfigRequest {
+            provider: InvoicingProvider::Stripe.into(),
+        })
+        .await
+        .unwrap();
+
+    let listed = clients
+        .providers
+        .clone()
+        .list_provider_configs(ListProviderConfigsRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .configs;
+
+    assert!(listed.is_empty());
+
+    // disabling twice is not found
+    let res = clients
+        .providers
+        .clone()
+        .disable_provider_config(DisableProviderConfigRequest {
+            provider: InvoicingProvider::Stripe.into(),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    // configure adyen
+    let adyen = clients
+        .providers
+        .clone()
+        .upsert_provider_config(UpsertProviderConfigRequest {
+            credentials: Some(ProviderCredentials {
+                credentials: Some(Credentials::Adyen(Adyen {
+                    api_key: "AQE_test_key5678".into(),
+                    hmac_key: "44782DEF547AAA06C910C43932B1EB0C".into(),
+                    merchant_account: "TestMerchant".into(),
+                    live_url_prefix: None,
+                })),
+            }),
+            is_default: false,
+            fallback_priority: Some(2),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .config
+        .unwrap();
+
+    assert_eq!(adyen.provider(), InvoicingProvider::Adyen);
+    assert!(!adyen.is_default);
+    assert_eq!(adyen.fallback_priority, Some(2));
+    assert_eq!(adyen.api_secret_hint, "...5678");
+
+    // the default and fallback of a disabled provider cannot be set
+    let res = clients
+        .providers
+        .clone()
+        .set_default_provider(SetDefaultProviderRequest {
+            provider: InvoicingProvider::Stripe.into(),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    let res = clients
+        .providers
+        .clone()
+        .set_provider_fallback(SetProviderFallbackRequest {
+            provider: InvoicingProvider::Stripe.into(),
+            fallback_priority: Some(1),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::NotFound);
+
+    // updating the credentials of the default provider keeps it as default
+    clients
+        .providers
+        .clone()
+        .set_default_provider(SetDefaultProviderRequest {
+            provider: InvoicingProvider::Adyen.into(),
+        })
+        .await
+        .unwrap();
+
+    let updated = clients
+        .providers
+        .clone()
+        .upsert_provider_config(UpsertProviderConfigRequest {
+            credentials: Some(ProviderCredentials {
+                credentials: Some(Credentials::Adyen(Adyen {
+                    api_key: "AQE_test_key9012".into(),
+                    hmac_key: "44782DEF547AAA06C910C43932B1EB0C".into(),
+                    merchant_account: "TestMerchant".into(),
+                    live_url_prefix: None,
+                })),
+            }),
+            is_default: false,
+            fallback_priority: Some(2),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .config
+        .unwrap();
+
+    assert_eq!(updated.id, adyen.id);
+    assert!(updated.is_default);
+    assert_eq!(updated.api_secret_hint, "...9012");
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
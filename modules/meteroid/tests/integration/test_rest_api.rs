@@ -0,0 +1,248 @@
+use reqwest::StatusCode;
+use serde_json::{json, Value};
+use tonic::transport::Channel;
+use uuid::Uuid;
+
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use common_grpc::middleware::common::auth::API_KEY_HEADER;
+use meteroid_grpc::meteroid::api::users::v1::users_service_client::UsersServiceClient;
+
+#[tokio::test]
+async fn test_rest_api() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::MINIMAL)
+            .await;
+    let base_url = meteroid_it::container::start_rest_api(&setup).await;
+
+    let clients = tenant_clients(&setup.channel, "Rest Tenant").await;
+    let other_clients = tenant_clients(&setup.channel, "Other Rest Tenant").await;
+
+    let api_key = create_api_key(&clients, vec![]).await;
+    let other_api_key = create_api_key(&other_clients, vec![]).await;
+    let invoices_api_key = create_api_key(&clients, vec!["invoices:read".to_string()]).await;
+
+    let http = reqwest::Client::new();
+    let customers_url = format!("{}/api/v1/customers", base_url);
+
+    // the api key is required, and must be valid
+    let res = http.get(&customers_url).send().await.unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    let res = http
+        .get(&customers_url)
+        .header(API_KEY_HEADER, "fake-api-key")
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::UNAUTHORIZED);
+
+    // a valid key whose scopes don't cover the customers is forbidden
+    let res = http
+        .get(&customers_url)
+        .header(API_KEY_HEADER, invoices_api_key.as_str())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::FORBIDDEN);
+
+    let list = get_json(&http, &customers_url, &api_key).await;
+    assert_eq!(list["data"].as_array().unwrap().len(), 0);
+
+    let mut created_ids = Vec::new();
+    for idx in 1..=3 {
+        let res = http
+            .post(&customers_url)
+            .header(API_KEY_HEADER, api_key.as_str())
+            .json(&json!({
+                "name": format!("Customer {}", idx),
+                "alias": format!("rest-customer-{}", idx),
+                "currency": "EUR",
+            }))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::CREATED);
+
+        let customer: Value = res.json().await.unwrap();
+        created_ids.push(customer["id"].as_str().unwrap().to_string());
+    }
+
+    // the store errors map to their http status
+    let res = http
+        .post(&customers_url)
+        .header(API_KEY_HEADER, api_key.as_str())
+        .json(&json!({
+            "name": "Duplicate",
+            "alias": "rest-customer-1",
+            "currency": "EUR",
+        }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::CONFLICT);
+
+    let res = http
+        .get(format!("{}/{}", customers_url, Uuid::now_v7()))
+        .header(API_KEY_HEADER, api_key.as_str())
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    // the pages split the customers
+    let first_page = get_json(
+        &http,
+        &format!("{}?per_page=2&page=0", customers_url),
+        &api_key,
+    )
+    .await;
+    assert_eq!(first_page["total_results"], 3);
+    assert_eq!(first_page["total_pages"], 2);
+
+    let second_page = get_json(
+        &http,
+        &format!("{}?per_page=2&page=1", customers_url),
+        &api_key,
+    )
+    .await;
+
+    let mut listed_ids = [&first_page, &second_page]
+        .iter()
+        .flat_map(|page| page["data"].as_array().unwrap())
+        .map(|c| c["id"].as_str().unwrap().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(first_page["data"].as_array().unwrap().len(), 2);
+    assert_eq!(second_page["data"].as_array().unwrap().len(), 1);
+
+    listed_ids.sort();
+    created_ids.sort();
+    assert_eq!(listed_ids, created_ids);
+
+    // the customers are resolved by alias
+    let customer = get_json(
+        &http,
+        &format!("{}/rest-customer-2", customers_url),
+        &api_key,
+    )
+    .await;
+    assert_eq!(customer["name"], "Customer 2");
+
+    // the key of another tenant sees none of them
+    let list = get_json(&http, &customers_url, &other_api_key).await;
+    assert_eq!(list["total_results"], 0);
+
+    for id_or_alias in [customer["id"].as_str().unwrap(), "rest-customer-2"] {
+        let res = http
+            .get(format!("{}/{}", customers_url, id_or_alias))
+            .header(API_KEY_HEADER, other_api_key.as_str())
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(res.status(), StatusCode::NOT_FOUND);
+    }
+
+    let res = http
+        .patch(format!(
+            "{}/{}",
+            customers_url,
+            customer["id"].as_str().unwrap()
+        ))
+        .header(API_KEY_HEADER, other_api_key.as_str())
+        .json(&json!({ "name": "Renamed" }))
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::NOT_FOUND);
+
+    let customer = get_json(
+        &http,
+        &format!("{}/rest-customer-2", customers_url),
+        &api_key,
+    )
+    .await;
+    assert_eq!(customer["name"], "Customer 2");
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await;
+}
+
+async fn get_json(http: &reqwest::Client, url: &str, api_key: &str) -> Value {
+    let res = http
+        .get(url)
+        .header(API_KEY_HEADER, api_key)
+        .send()
+        .await
+        .unwrap();
+    assert_eq!(res.status(), StatusCode::OK);
+
+    res.json().await.unwrap()
+}
+
+/// Clients of a new tenant of the seeded organization
+async fn tenant_clients(channel: &Channel, name: &str) -> meteroid_it::clients::AllClients {
+    let auth_token = UsersServiceClient::new(channel.clone())
+        .login(tonic::Request::new(
+            meteroid_grpc::meteroid::api::users::v1::LoginRequest {
+                email: meteroid_it::svc_auth::SEED_USERNAME.to_string(),
+                password: meteroid_it::svc_auth::SEED_PASSWORD.to_string(),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .token;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        channel.clone(),
+        auth_token.as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let tenant = clients
+        .tenants
+        .clone()
+        .create_tenant(tonic::Request::new(
+            meteroid_grpc::meteroid::api::tenants::v1::CreateTenantRequest {
+                name: name.to_string(),
+                environment: 0,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .tenant
+        .unwrap();
+
+    meteroid_it::clients::AllClients::from_channel(
+        channel.clone(),
+        auth_token.as_str(),
+        "TESTORG",
+        tenant.slug.as_str(),
+    )
+}
+
+async fn create_api_key(clients: &meteroid_it::clients::AllClients, scopes: Vec<String>) -> String {
+    clients
+        .api_tokens
+        .clone()
+        .create_api_token(tonic::Request::new(
+            meteroid_grpc::meteroid::api::apitokens::v1::CreateApiTokenRequest {
+                name: "rest-api-key".to_string(),
+                daily_request_soft_limit: None,
+                partner_id: None,
+                scopes,
+                expires_at: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .api_key
+}
@@ -2,8 +2,15 @@ use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::db::seed::*;
 use chrono::NaiveDateTime;
+use diesel_async::SimpleAsyncConnection;
 use meteroid::eventbus::create_eventbus_memory;
 use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::enums::SeatIncreaseRequestStatusEnum;
+use meteroid_store::domain::seat_increase_requests::SeatIncreaseRequestNew;
+use meteroid_store::domain::subscription_component_history::ComponentParameterChange;
+use meteroid_store::domain::SlotUpdate;
+use meteroid_store::repositories::seat_increase_requests::SeatIncreaseRequestInterface;
+use meteroid_store::repositories::subscription_component_history::SubscriptionComponentHistoryInterface;
 use meteroid_store::repositories::subscriptions::SubscriptionSlotsInterface;
 use meteroid_store::Store;
 use secrecy::SecretString;
@@ -13,9 +20,9 @@ use uuid::{uuid, Uuid};
 
 const SLOT_SUBSCRIPTION_ID: Uuid = SUBSCRIPTION_UBER_ID1;
 const SLOT_PRICE_COMPONENT_ID: Uuid = uuid!("018c344c-9ec9-7608-b115-1537b6985e73");
+const SLOT_ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
 
 #[tokio::test]
-#[ignore] // add_slot_transaction is not implemented for Store yet
 async fn test_slot_transaction_active_slots() {
     helpers::init::logging();
     let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
@@ -36,79 +43,205 @@ async fn test_slot_transaction_active_slots() {
     )
     .await;
 
-    // no active slots before first transaction
+    // initial slots before first transaction
     let active = get_active_slots(&store, datetime("2024-01-01T00:00:00")).await;
-    assert_eq!(active, 0);
+    assert_eq!(active, 25);
 
-    create_slot_transaction(
-        &store,
-        15,
-        datetime("2024-01-01T00:00:00"),
-        datetime("2024-01-01T00:00:00"),
-    )
-    .await;
+    let update = create_slot_transaction(&store, 15, datetime("2024-01-01T00:00:00")).await;
+    assert_eq!(update.current_value, 40);
+    assert_eq!(update.next_period_value, 40);
+    assert!(update.prorated_amount > 0);
+    assert!(update.invoice_id.is_some());
 
-    // 15 active slots after first transaction as it is upgrade
+    // 40 active slots after first transaction as it is upgrade
     let active = get_active_slots(&store, datetime("2024-01-01T00:00:00")).await;
-    assert_eq!(active, 15);
+    assert_eq!(active, 40);
 
-    create_slot_transaction(
-        &store,
-        1,
-        datetime("2024-01-01T02:00:00"),
-        datetime("2024-01-01T02:00:00"),
-    )
-    .await;
+    create_slot_transaction(&store, 1, datetime("2024-01-01T02:00:00")).await;
 
-    // 16 active slots after second transaction as it is upgrade
+    // 41 active slots after second transaction as it is upgrade
     let active = get_active_slots(&store, datetime("2024-01-01T02:00:00")).await;
-    assert_eq!(active, 16);
+    assert_eq!(active, 41);
 
-    create_slot_transaction(
-        &store,
-        -3,
-        datetime("2024-01-01T04:00:00"),
-        datetime("2024-02-01T00:00:00"),
-    )
-    .await;
+    let update = create_slot_transaction(&store, -3, datetime("2024-01-01T04:00:00")).await;
+    assert_eq!(update.current_value, 41);
+    assert_eq!(update.next_period_value, 38);
+    assert_eq!(update.prorated_amount, 0);
+    assert!(update.invoice_id.is_none());
 
-    // still 16 active slots after third transaction as it is downgrade so it is effective after billing period ends
+    // still 41 active slots after third transaction as it is downgrade so it is effective after billing period ends
     let active = get_active_slots(&store, datetime("2024-01-01T04:00:00")).await;
-    assert_eq!(active, 16);
+    assert_eq!(active, 41);
+
+    create_slot_transaction(&store, 4, datetime("2024-01-01T06:00:00")).await;
+
+    // 45 active slots after fourth transaction as it is upgrade
+    let active = get_active_slots(&store, datetime("2024-01-01T06:00:00")).await;
+    assert_eq!(active, 45);
+
+    // 42 active slots once the downgrade is effective, after billing period end
+    let active = get_active_slots(&store, datetime("2025-01-01T00:00:00")).await;
+    assert_eq!(active, 42);
 
-    create_slot_transaction(
-        &store,
-        4,
-        datetime("2024-01-01T06:00:00"),
-        datetime("2024-01-01T06:00:00"),
+    // cannot go below the minimum slots
+    let res = store
+        .add_slot_transaction(
+            TENANT_ID,
+            SLOT_SUBSCRIPTION_ID,
+            SLOT_PRICE_COMPONENT_ID,
+            -42,
+            SLOT_ACTOR_ID,
+            Some(datetime("2024-01-01T08:00:00")),
+        )
+        .await;
+    assert!(res.is_err());
+
+    // every accepted change is in the component history, the downgrade from the next period value
+    let history = store
+        .list_subscription_component_history(TENANT_ID, SLOT_SUBSCRIPTION_ID)
+        .await
+        .unwrap();
+    assert_eq!(history.len(), 4);
+    assert!(history.iter().all(|h| h.created_by == SLOT_ACTOR_ID));
+    assert!(history.iter().any(|h| matches!(
+        h.change,
+        ComponentParameterChange::Slots {
+            previous: 41,
+            new: 38
+        }
+    )));
+}
+
+#[tokio::test]
+async fn test_seat_increase_requests() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
     )
     .await;
 
-    // 20 active slots after fourth transaction as it is upgrade
-    let active = get_active_slots(&store, datetime("2024-01-01T06:00:00")).await;
-    assert_eq!(active, 20);
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update tenant set seat_auto_approve_limit = 5 where id = '{TENANT_ID}';"
+    ))
+    .await
+    .unwrap();
+
+    let new_request = |seats: i32| SeatIncreaseRequestNew {
+        tenant_id: TENANT_ID,
+        subscription_id: SLOT_SUBSCRIPTION_ID,
+        price_component_id: SLOT_PRICE_COMPONENT_ID,
+        seats,
+        requested_by: Some("jane@uber.com".to_string()),
+        note: None,
+    };
+
+    // within the limit, the seats are added right away
+    let approved = store
+        .request_seat_increase(new_request(3), SLOT_ACTOR_ID)
+        .await
+        .unwrap();
+    assert_eq!(approved.status, SeatIncreaseRequestStatusEnum::Approved);
+    assert!(approved.invoice_id.is_some());
+    assert_eq!(approved.decided_by, None);
+    assert_eq!(get_current_slots(&store).await, 28);
+
+    // beyond the limit, the request waits for the tenant
+    let pending = store
+        .request_seat_increase(new_request(10), SLOT_ACTOR_ID)
+        .await
+        .unwrap();
+    assert_eq!(pending.status, SeatIncreaseRequestStatusEnum::Pending);
+    assert_eq!(get_current_slots(&store).await, 28);
+
+    let listed = store
+        .list_seat_increase_requests(
+            TENANT_ID,
+            Some(SLOT_SUBSCRIPTION_ID),
+            Some(SeatIncreaseRequestStatusEnum::Pending),
+        )
+        .await
+        .unwrap();
+    assert_eq!(listed, vec![pending.clone()]);
+
+    let approved = store
+        .approve_seat_increase_request(TENANT_ID, pending.id, SLOT_ACTOR_ID, None)
+        .await
+        .unwrap();
+    assert_eq!(approved.status, SeatIncreaseRequestStatusEnum::Approved);
+    assert_eq!(approved.decided_by, Some(SLOT_ACTOR_ID));
+    assert!(approved.invoice_id.is_some());
+    assert_eq!(get_current_slots(&store).await, 38);
+
+    // a decided request cannot be decided again
+    assert!(store
+        .approve_seat_increase_request(TENANT_ID, pending.id, SLOT_ACTOR_ID, None)
+        .await
+        .is_err());
+    assert!(store
+        .reject_seat_increase_request(TENANT_ID, pending.id, SLOT_ACTOR_ID, None)
+        .await
+        .is_err());
 
-    // 17 active slots after fifth transaction as it is after billing period end
-    let active = get_active_slots(&store, datetime("2024-02-01T02:00:00")).await;
-    assert_eq!(active, 17);
+    let pending = store
+        .request_seat_increase(new_request(20), SLOT_ACTOR_ID)
+        .await
+        .unwrap();
+    let rejected = store
+        .reject_seat_increase_request(
+            TENANT_ID,
+            pending.id,
+            SLOT_ACTOR_ID,
+            Some("Please contact your account manager".to_string()),
+        )
+        .await
+        .unwrap();
+    assert_eq!(rejected.status, SeatIncreaseRequestStatusEnum::Rejected);
+    assert_eq!(rejected.invoice_id, None);
+    assert_eq!(get_current_slots(&store).await, 38);
+}
+
+async fn get_current_slots(store: &Store) -> u32 {
+    store
+        .get_current_slots_value(
+            TENANT_ID,
+            SLOT_SUBSCRIPTION_ID,
+            SLOT_PRICE_COMPONENT_ID,
+            None,
+        )
+        .await
+        .unwrap()
 }
 
 async fn create_slot_transaction(
     store: &Store,
     delta: i32,
-    _transaction_at: NaiveDateTime,
-    _effective_at: NaiveDateTime,
-) {
-    // store.add_slot_transaction is not implemented yet
+    transaction_at: NaiveDateTime,
+) -> SlotUpdate {
     store
         .add_slot_transaction(
             TENANT_ID,
             SLOT_SUBSCRIPTION_ID,
             SLOT_PRICE_COMPONENT_ID,
             delta,
+            SLOT_ACTOR_ID,
+            Some(transaction_at),
         )
         .await
-        .unwrap();
+        .unwrap()
 }
 
 async fn get_active_slots(store: &Store, timestamp: NaiveDateTime) -> u32 {
@@ -1,8 +1,21 @@
 use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::container::SeedLevel;
+use crate::meteroid_it::db::seed::*;
+use chrono::NaiveDate;
+use diesel_async::{RunQueryDsl, SimpleAsyncConnection};
+use diesel_models::stats::{ReportSnapshotRow, TotalMrrRow};
+use meteroid::eventbus::create_eventbus_noop;
 use meteroid_grpc::meteroid::api;
 use meteroid_grpc::meteroid::api::stats::v1::general_stats_response;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::stats::{FxRestatementMode, MrrChartRequest};
+use meteroid_store::repositories::stats::StatsInterface;
+use meteroid_store::store::PgConn;
+use meteroid_store::Store;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
 
 #[tokio::test]
 async fn test_stats_basic() {
@@ -123,3 +136,192 @@ async fn test_stats_basic() {
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
+
+#[tokio::test]
+async fn test_stats_fx_restatement() {
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let store = create_store(postgres_connection_string).await;
+
+    // the tenant reports in EUR. The february rates do not quote EUR
+    let mut conn = store.pool.get().await.unwrap();
+    insert_rates(&mut conn).await;
+    insert_mrr_delta(&mut conn, "2024-01-10", "EUR", 1000, 1111, JAN_RATE_ID).await;
+    insert_mrr_delta(&mut conn, "2024-01-15", "GBP", 800, 1000, JAN_RATE_ID).await;
+    insert_mrr_delta(&mut conn, "2024-02-10", "USD", 1000, 1000, FEB_RATE_ID).await;
+
+    // EUR is passed through, GBP is converted from its usd amount at the january rates,
+    // and the february USD movement falls back to the january EUR rate
+    let total = total_mrr(&store, FxRestatementMode::HistoricalRates).await;
+    assert_eq!(total, 1000 + 900 + 900);
+
+    let stats = store
+        .general_stats(TENANT_ID, FxRestatementMode::HistoricalRates)
+        .await
+        .unwrap();
+    assert_eq!(stats.total_mrr, 1000 + 900 + 900);
+
+    // at constant rates, only the january rates quote both EUR and the movement currency
+    let total = total_mrr(
+        &store,
+        FxRestatementMode::ConstantCurrency {
+            rate_date: NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(),
+        },
+    )
+    .await;
+    assert_eq!(total, 1000 + 900 + 900);
+
+    // a currency that no rates quote fails the report instead of being dropped from the total
+    insert_mrr_delta(&mut conn, "2024-02-12", "XTS", 500, 500, FEB_RATE_ID).await;
+
+    let res = store
+        .total_mrr_chart(mrr_chart_request(FxRestatementMode::ConstantCurrency {
+            rate_date: NaiveDate::from_ymd_opt(2024, 2, 15).unwrap(),
+        }))
+        .await;
+    assert!(res.is_err());
+}
+
+#[tokio::test]
+async fn test_stats_report_snapshot() {
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let store = create_store(postgres_connection_string).await;
+
+    let mut conn = store.pool.get().await.unwrap();
+    insert_rates(&mut conn).await;
+    insert_mrr_delta(&mut conn, "2024-01-10", "EUR", 1000, 1111, JAN_RATE_ID).await;
+
+    let today = chrono::Utc::now().date_naive();
+
+    conn.batch_execute("BEGIN").await.unwrap();
+    let snapshot = ReportSnapshotRow::begin(&mut conn)
+        .await
+        .map_err(|e| e.error)
+        .unwrap();
+
+    let total = TotalMrrRow::get(&mut conn, TENANT_ID, today)
+        .await
+        .map_err(|e| e.error)
+        .unwrap();
+    assert_eq!(total.total_net_mrr_cents, 1000);
+
+    // written by a worker while the report is computed
+    let mut writer = store.pool.get().await.unwrap();
+    insert_mrr_delta(&mut writer, "2024-01-11", "EUR", 500, 555, JAN_RATE_ID).await;
+
+    let total = TotalMrrRow::get(&mut conn, TENANT_ID, today)
+        .await
+        .map_err(|e| e.error)
+        .unwrap();
+    assert_eq!(total.total_net_mrr_cents, 1000);
+
+    // the clock is frozen as well, so that all the figures share the as_of of the snapshot
+    tokio::time::sleep(std::time::Duration::from_millis(50)).await;
+    let clock = diesel::sql_query("SELECT (now() AT TIME ZONE 'UTC')::timestamp AS as_of")
+        .get_result::<ReportSnapshotRow>(&mut conn)
+        .await
+        .unwrap();
+    assert_eq!(clock.as_of, snapshot.as_of);
+
+    // the snapshot is read only
+    let res = conn
+        .batch_execute(&format!(
+            "update tenant set name = 'renamed' where id = '{TENANT_ID}'"
+        ))
+        .await;
+    assert!(res.is_err());
+
+    conn.batch_execute("ROLLBACK").await.unwrap();
+
+    // the next snapshot sees the write
+    let stats = store
+        .general_stats(TENANT_ID, FxRestatementMode::HistoricalRates)
+        .await
+        .unwrap();
+    assert_eq!(stats.total_mrr, 1500);
+    assert!(stats.as_of > snapshot.as_of);
+}
+
+const JAN_RATE_ID: Uuid = uuid!("018df083-46df-7326-a3ca-fb98888e1201");
+const FEB_RATE_ID: Uuid = uuid!("018df083-46df-7326-a3ca-fb98888e1202");
+const PLAN_VERSION_ID: Uuid = uuid!("018df083-46df-7326-a3ca-fb98888e1203");
+
+async fn create_store(postgres_connection_string: String) -> Store {
+    let store = Store::new(
+        postgres_connection_string,
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(&store.pool, SeedLevel::MINIMAL).await;
+
+    store
+}
+
+async fn insert_rates(conn: &mut PgConn) {
+    conn.batch_execute(&format!(
+        r#"
+        insert into historical_rates_from_usd (id, date, rates) values
+            ('{JAN_RATE_ID}', '2024-01-01', '{{"USD": 1, "EUR": 0.9, "GBP": 0.8}}'),
+            ('{FEB_RATE_ID}', '2024-02-01', '{{"USD": 1, "GBP": 0.5}}');
+        "#
+    ))
+    .await
+    .unwrap();
+}
+
+/// A new business movement of the tenant
+async fn insert_mrr_delta(
+    conn: &mut PgConn,
+    date: &str,
+    currency: &str,
+    cents: i64,
+    cents_usd: i64,
+    rate_id: Uuid,
+) {
+    conn.batch_execute(&format!(
+        r#"
+        insert into bi_delta_mrr_daily (tenant_id, plan_version_id, date, currency,
+                                        net_mrr_cents, new_business_cents, new_business_count,
+                                        expansion_cents, expansion_count, contraction_cents, contraction_count,
+                                        churn_cents, churn_count, reactivation_cents, reactivation_count,
+                                        historical_rate_id, net_mrr_cents_usd, new_business_cents_usd,
+                                        expansion_cents_usd, contraction_cents_usd, churn_cents_usd,
+                                        reactivation_cents_usd)
+        values ('{TENANT_ID}', '{PLAN_VERSION_ID}', '{date}', '{currency}', {cents}, {cents}, 1, 0, 0, 0, 0, 0, 0, 0, 0,
+                '{rate_id}', {cents_usd}, {cents_usd}, 0, 0, 0, 0);
+        "#
+    ))
+    .await
+    .unwrap();
+}
+
+fn mrr_chart_request(fx_mode: FxRestatementMode) -> MrrChartRequest {
+    MrrChartRequest {
+        tenant_id: TENANT_ID,
+        start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        end_date: NaiveDate::from_ymd_opt(2024, 2, 28).unwrap(),
+        plans_id: None,
+        fx_mode,
+    }
+}
+
+async fn total_mrr(store: &Store, fx_mode: FxRestatementMode) -> i64 {
+    let res = store
+        .total_mrr_chart(mrr_chart_request(fx_mode))
+        .await
+        .unwrap();
+
+    res.series
+        .into_iter()
+        .find(|s| s.code == "total_mrr")
+        .and_then(|s| s.data.last().map(|d| d.data.total_net_mrr))
+        .unwrap()
+}
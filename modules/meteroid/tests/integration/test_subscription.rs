@@ -244,6 +244,7 @@ async fn test_subscription_cancel() {
                                 initial_slot_count: Some(10),
                                 billing_period: Some(BillingPeriod::Monthly.into()),
                                 committed_capacity: None,
+                                billing_period_months: None,
                             }
                         ],
                         ..Default::default()
@@ -280,6 +281,174 @@ async fn test_subscription_cancel() {
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
 
+#[tokio::test]
+#[ignore] // subscription seed is broken
+async fn test_subscription_update_term() {
+    let TestContext {
+        setup,
+        clients,
+        _container,
+    } = setup_test(SeedLevel::PLANS).await.unwrap();
+    let customer_id = "018c345f-7324-7cd2-a692-78e5ab9158e0".to_string();
+    let plan_version_id = "018c344b-da87-7392-bbae-c5c8780adb1b".to_string();
+    let component_id = "018c344c-9ec9-7608-b115-1537b6985e73".to_string();
+
+    let now = chrono::offset::Local::now().date_naive();
+
+    let subscription = clients
+        .subscriptions
+        .clone()
+        .create_subscription(tonic::Request::new(
+            api::subscriptions::v1::CreateSubscriptionRequest {
+                subscription: Some(api::subscriptions::v1::CreateSubscription {
+                    plan_version_id: plan_version_id.clone(),
+                    billing_start_date: now.as_proto(),
+                    billing_day: 1,
+                    customer_id: customer_id.clone(),
+                    currency: "USD".to_string(),
+                    components: Some(api::subscriptions::v1::CreateSubscriptionComponents {
+                        parameterized_components: vec![
+                            api::subscriptions::v1::create_subscription_components::ComponentParameterization {
+                                component_id: component_id.clone(),
+                                initial_slot_count: Some(10),
+                                billing_period: Some(BillingPeriod::Monthly.into()),
+                                committed_capacity: None,
+                                billing_period_months: None,
+                            }
+                        ],
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                })
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .subscription
+        .unwrap();
+
+    let end_date = now.checked_add_months(Months::new(12)).unwrap();
+
+    let extended = clients
+        .subscriptions
+        .clone()
+        .update_subscription_term(tonic::Request::new(
+            api::subscriptions::v1::UpdateSubscriptionTermRequest {
+                subscription_id: subscription.id.clone(),
+                billing_end_date: Some(end_date.as_proto()),
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .subscription
+        .unwrap();
+
+    assert_eq!(extended.billing_end_date, Some(end_date.as_proto()));
+
+    // cannot end before the start of the subscription
+    let res = clients
+        .subscriptions
+        .clone()
+        .update_subscription_term(tonic::Request::new(
+            api::subscriptions::v1::UpdateSubscriptionTermRequest {
+                subscription_id: subscription.id.clone(),
+                billing_end_date: Some(now.pred_opt().unwrap().as_proto()),
+            },
+        ))
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), Code::InvalidArgument);
+
+    let open_ended = clients
+        .subscriptions
+        .clone()
+        .update_subscription_term(tonic::Request::new(
+            api::subscriptions::v1::UpdateSubscriptionTermRequest {
+                subscription_id: subscription.id.clone(),
+                billing_end_date: None,
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner()
+        .subscription
+        .unwrap();
+
+    assert_eq!(open_ended.billing_end_date, None);
+    assert!(open_ended.canceled_at.is_none());
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
+
+#[tokio::test]
+#[ignore] // subscription seed is broken
+async fn test_subscription_create_batch_partial_failure() {
+    let TestContext {
+        setup,
+        clients,
+        _container,
+    } = setup_test(SeedLevel::PLANS).await.unwrap();
+    let customer_id = "018c345f-7324-7cd2-a692-78e5ab9158e0".to_string();
+    let plan_version_id = "018c344b-da87-7392-bbae-c5c8780adb1b".to_string();
+    let component_id = "018c344c-9ec9-7608-b115-1537b6985e73".to_string();
+
+    let now = chrono::offset::Local::now().date_naive();
+
+    let valid = api::subscriptions::v1::CreateSubscription {
+        plan_version_id: plan_version_id.clone(),
+        billing_start_date: now.as_proto(),
+        billing_day: 1,
+        customer_id: customer_id.clone(),
+        currency: "USD".to_string(),
+        components: Some(api::subscriptions::v1::CreateSubscriptionComponents {
+            parameterized_components: vec![
+                api::subscriptions::v1::create_subscription_components::ComponentParameterization {
+                    component_id: component_id.clone(),
+                    initial_slot_count: Some(10),
+                    billing_period: Some(BillingPeriod::Monthly.into()),
+                    committed_capacity: None,
+                    billing_period_months: None,
+                },
+            ],
+            ..Default::default()
+        }),
+        ..Default::default()
+    };
+
+    let invalid = api::subscriptions::v1::CreateSubscription {
+        customer_id: "not-a-uuid".to_string(),
+        ..valid.clone()
+    };
+
+    let res = clients
+        .subscriptions
+        .clone()
+        .create_subscriptions_batch(tonic::Request::new(
+            api::subscriptions::v1::CreateSubscriptionsBatchRequest {
+                subscriptions: vec![invalid, valid],
+            },
+        ))
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert_eq!(res.created.len(), 1);
+    assert_eq!(res.created[0].index, 1);
+    assert_eq!(
+        res.created[0].subscription.as_ref().unwrap().customer_id,
+        customer_id
+    );
+
+    assert_eq!(res.failures.len(), 1);
+    assert_eq!(res.failures[0].index, 0);
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
+
 // TODO Commenting this test while we complete the slot flow (cf sequence diagram in the store impl)
 // #[tokio::test]
 // async fn test_slot_subscription_upgrade_downgrade() {
@@ -483,6 +652,7 @@ async fn test_subscription_create_invoice_seats() {
                                 initial_slot_count: Some(seats_quantity),
                                 billing_period: Some(BillingPeriod::Monthly.into()),
                                 committed_capacity: None,
+                                billing_period_months: None,
                             }
                         ],
                         ..Default::default()
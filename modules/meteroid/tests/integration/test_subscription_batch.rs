@@ -0,0 +1,141 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+use chrono::NaiveDate;
+use diesel_async::SimpleAsyncConnection;
+use meteroid::eventbus::create_eventbus_memory;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::enums::{BillingPeriodEnum, SubscriptionBillingModeEnum};
+use meteroid_store::domain::subscription_components::{
+    ComponentParameterization, ComponentParameters, CreateSubscriptionComponents,
+};
+use meteroid_store::domain::{CreateSubscription, SubscriptionNew};
+use meteroid_store::repositories::SubscriptionInterface;
+use meteroid_store::Store;
+use rust_decimal_macros::dec;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
+
+const BATCH_PLAN_VERSION_ID: Uuid = uuid!("018c344b-da87-7392-bbae-c5c8780adb1b");
+const BATCH_SEATS_COMPONENT_ID: Uuid = uuid!("018c344c-9ec9-7608-b115-1537b6985e73");
+const BATCH_ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+const REJECTED_MEMO: &str = "rejected by the database";
+
+#[tokio::test]
+async fn test_subscription_create_batch_partial_failure() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    // an item that passes the validation but fails on insert
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "alter table subscription add constraint subscription_test_memo_check check (invoice_memo is distinct from '{REJECTED_MEMO}');"
+    ))
+    .await
+    .unwrap();
+
+    let mut invalid = new_subscription(CUSTOMER_UBER_ID);
+    invalid.subscription.minimum_commitment = Some(dec!(-10));
+
+    let mut rejected = new_subscription(CUSTOMER_COMODO_ID);
+    rejected.subscription.invoice_memo = Some(REJECTED_MEMO.to_string());
+
+    let res = store
+        .create_subscriptions_batch(
+            vec![
+                new_subscription(CUSTOMER_SPORTIFY_ID),
+                invalid,
+                rejected,
+                new_subscription(CUSTOMER_UBER_ID),
+            ],
+            TENANT_ID,
+        )
+        .await
+        .unwrap();
+
+    // the rejected item does not roll back the other items of its chunk
+    let mut created = res
+        .created
+        .iter()
+        .map(|(index, s)| (*index, s.customer_id))
+        .collect::<Vec<_>>();
+    created.sort();
+    assert_eq!(
+        created,
+        vec![(0, CUSTOMER_SPORTIFY_ID), (3, CUSTOMER_UBER_ID)]
+    );
+
+    for (_, subscription) in &res.created {
+        let details = store
+            .get_subscription_details(TENANT_ID, subscription.id)
+            .await
+            .unwrap();
+        assert_eq!(details.price_components.len(), 1);
+    }
+
+    // the failures carry the validation or database details
+    assert_eq!(
+        res.failures.iter().map(|f| f.index).collect::<Vec<_>>(),
+        vec![1, 2]
+    );
+    assert!(res.failures[0]
+        .reason
+        .contains("the minimum commitment cannot be negative"));
+    assert!(res.failures[1]
+        .reason
+        .contains("subscription_test_memo_check"));
+}
+
+fn new_subscription(customer_id: Uuid) -> CreateSubscription {
+    CreateSubscription {
+        subscription: SubscriptionNew {
+            customer_id,
+            billing_day: 1,
+            billing_mode: SubscriptionBillingModeEnum::Anniversary,
+            currency: "EUR".to_string(),
+            trial_start_date: None,
+            billing_start_date: NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+            billing_end_date: None,
+            plan_version_id: BATCH_PLAN_VERSION_ID,
+            created_by: BATCH_ACTOR_ID,
+            net_terms: 0,
+            invoice_memo: None,
+            invoice_threshold: None,
+            activated_at: None,
+            minimum_commitment: None,
+        },
+        price_components: Some(CreateSubscriptionComponents {
+            parameterized_components: vec![ComponentParameterization {
+                component_id: BATCH_SEATS_COMPONENT_ID,
+                parameters: ComponentParameters {
+                    initial_slot_count: Some(10),
+                    billing_period: Some(BillingPeriodEnum::Monthly),
+                    committed_capacity: None,
+                },
+            }],
+            overridden_components: vec![],
+            extra_components: vec![],
+            remove_components: vec![],
+        }),
+        add_ons: None,
+        coupons: None,
+        phases: vec![],
+    }
+}
@@ -0,0 +1,282 @@
+use std::sync::Arc;
+
+use diesel_async::SimpleAsyncConnection;
+use meteroid::eventbus::create_eventbus_noop;
+use meteroid::workers::invoicing::draft_worker::draft_worker;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::enums::BillingPeriodEnum;
+use meteroid_store::domain::subscription_pending_changes::{
+    SubscriptionChange, SubscriptionPendingChangeStatus,
+};
+use meteroid_store::domain::{
+    ComponentParameterization, ComponentParameters, CreateSubscriptionComponents, SubscriptionFee,
+    TenantContext,
+};
+use meteroid_store::errors::StoreError;
+use meteroid_store::repositories::subscription_pending_changes::SubscriptionPendingChangeInterface;
+use meteroid_store::repositories::SubscriptionInterface;
+use meteroid_store::Store;
+use testcontainers::ContainerAsync;
+use testcontainers_modules::postgres::Postgres;
+use uuid::{uuid, Uuid};
+
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+
+const ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+const NOTION_SEATS_COMPONENT_ID: Uuid = uuid!("018c344c-9ec9-7608-b115-1537b6985e73");
+
+const CONTEXT: TenantContext = TenantContext {
+    actor: ACTOR_ID,
+    tenant_id: TENANT_ID,
+};
+
+#[tokio::test]
+async fn test_schedule_and_cancel_pending_change() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    let today = chrono::Utc::now().naive_utc().date();
+
+    let pending = store
+        .schedule_subscription_change(SUBSCRIPTION_SPORTIFY_ID1, seats_change(20), CONTEXT)
+        .await
+        .unwrap();
+
+    assert_eq!(pending.subscription_id, SUBSCRIPTION_SPORTIFY_ID1);
+    assert_eq!(pending.status(), SubscriptionPendingChangeStatus::Scheduled);
+    // takes effect at the next renewal, on the billing day
+    assert!(pending.effective_at > today);
+    assert_eq!(pending.effective_at.format("%d").to_string(), "01");
+
+    let listed = store
+        .list_subscription_pending_changes(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, pending.id);
+
+    // a single change can be scheduled at a time
+    let err = store
+        .schedule_subscription_change(
+            SUBSCRIPTION_SPORTIFY_ID1,
+            SubscriptionChange::Cancellation { reason: None },
+            CONTEXT,
+        )
+        .await
+        .unwrap_err();
+
+    assert!(matches!(
+        err.current_context(),
+        StoreError::InvalidArgument(_)
+    ));
+
+    let canceled = store
+        .cancel_subscription_pending_change(pending.id, TENANT_ID)
+        .await
+        .unwrap();
+
+    assert_eq!(canceled.status(), SubscriptionPendingChangeStatus::Canceled);
+
+    // only scheduled changes can be canceled
+    assert!(store
+        .cancel_subscription_pending_change(pending.id, TENANT_ID)
+        .await
+        .is_err());
+
+    // the canceled change no longer blocks a new one
+    let rescheduled = store
+        .schedule_subscription_change(
+            SUBSCRIPTION_SPORTIFY_ID1,
+            SubscriptionChange::Cancellation { reason: None },
+            CONTEXT,
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        rescheduled.status(),
+        SubscriptionPendingChangeStatus::Scheduled
+    );
+
+    let listed = store
+        .list_subscription_pending_changes(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+
+    assert_eq!(listed.len(), 2);
+}
+
+#[tokio::test]
+async fn test_single_scheduled_change_constraint() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    let pending = store
+        .schedule_subscription_change(SUBSCRIPTION_SPORTIFY_ID1, seats_change(20), CONTEXT)
+        .await
+        .unwrap();
+
+    // enforced by the database as well, for the concurrent requests passing the check
+    let insert_scheduled = format!(
+        "insert into subscription_pending_change (id, tenant_id, subscription_id, change_type, details, effective_at, created_by)
+         values ('{}', '{TENANT_ID}', '{SUBSCRIPTION_SPORTIFY_ID1}', 'CANCELLATION', '{{\"Cancellation\": {{\"reason\": null}}}}', '{}', '{ACTOR_ID}');",
+        Uuid::now_v7(),
+        pending.effective_at,
+    );
+
+    let mut conn = store.pool.get().await.unwrap();
+
+    assert!(conn.batch_execute(&insert_scheduled).await.is_err());
+
+    store
+        .cancel_subscription_pending_change(pending.id, TENANT_ID)
+        .await
+        .unwrap();
+
+    conn.batch_execute(&insert_scheduled).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_pending_change_applied_by_draft_worker() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    let pending = store
+        .schedule_subscription_change(SUBSCRIPTION_SPORTIFY_ID1, seats_change(20), CONTEXT)
+        .await
+        .unwrap();
+
+    // not due yet
+    draft_worker(&store, pending.effective_at.pred_opt().unwrap())
+        .await
+        .unwrap();
+
+    assert_eq!(
+        pending_status(&store, SUBSCRIPTION_SPORTIFY_ID1).await,
+        SubscriptionPendingChangeStatus::Scheduled
+    );
+    assert_eq!(seats(&store, SUBSCRIPTION_SPORTIFY_ID1).await, 12);
+
+    draft_worker(&store, pending.effective_at).await.unwrap();
+
+    assert_eq!(
+        pending_status(&store, SUBSCRIPTION_SPORTIFY_ID1).await,
+        SubscriptionPendingChangeStatus::Applied
+    );
+    assert_eq!(seats(&store, SUBSCRIPTION_SPORTIFY_ID1).await, 20);
+
+    // applied once
+    draft_worker(&store, pending.effective_at).await.unwrap();
+    assert_eq!(seats(&store, SUBSCRIPTION_SPORTIFY_ID1).await, 20);
+}
+
+#[tokio::test]
+async fn test_pending_cancellation_applied_by_draft_worker() {
+    helpers::init::logging();
+    let (_pg_container, store) = setup_store().await;
+
+    let pending = store
+        .schedule_subscription_change(
+            SUBSCRIPTION_SPORTIFY_ID1,
+            SubscriptionChange::Cancellation {
+                reason: Some("too expensive".to_string()),
+            },
+            CONTEXT,
+        )
+        .await
+        .unwrap();
+
+    draft_worker(&store, pending.effective_at).await.unwrap();
+
+    assert_eq!(
+        pending_status(&store, SUBSCRIPTION_SPORTIFY_ID1).await,
+        SubscriptionPendingChangeStatus::Applied
+    );
+
+    let subscription = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+
+    assert!(subscription.canceled_at.is_some());
+    assert_eq!(subscription.billing_end_date, Some(pending.effective_at));
+    assert_eq!(
+        subscription.cancellation_reason,
+        Some("too expensive".to_string())
+    );
+
+    // a canceled subscription cannot be changed anymore
+    assert!(store
+        .schedule_subscription_change(SUBSCRIPTION_SPORTIFY_ID1, seats_change(20), CONTEXT)
+        .await
+        .is_err());
+}
+
+fn seats_change(initial_slot_count: u32) -> SubscriptionChange {
+    SubscriptionChange::ComponentChange {
+        components: CreateSubscriptionComponents {
+            parameterized_components: vec![ComponentParameterization {
+                component_id: NOTION_SEATS_COMPONENT_ID,
+                parameters: ComponentParameters {
+                    initial_slot_count: Some(initial_slot_count),
+                    billing_period: Some(BillingPeriodEnum::Monthly),
+                    committed_capacity: None,
+                },
+            }],
+            overridden_components: vec![],
+            extra_components: vec![],
+            remove_components: vec![],
+        },
+    }
+}
+
+async fn pending_status(store: &Store, subscription_id: Uuid) -> SubscriptionPendingChangeStatus {
+    store
+        .list_subscription_pending_changes(TENANT_ID, subscription_id)
+        .await
+        .unwrap()
+        .first()
+        .expect("a pending change")
+        .status()
+}
+
+async fn seats(store: &Store, subscription_id: Uuid) -> u32 {
+    let subscription = store
+        .get_subscription_details(TENANT_ID, subscription_id)
+        .await
+        .unwrap();
+
+    subscription
+        .price_components
+        .iter()
+        .find_map(|c| match &c.fee {
+            SubscriptionFee::Slot { initial_slots, .. } => Some(*initial_slots),
+            _ => None,
+        })
+        .expect("a seats component")
+}
+
+async fn setup_store() -> (ContainerAsync<Postgres>, Store) {
+    let (pg_container, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    (pg_container, store)
+}
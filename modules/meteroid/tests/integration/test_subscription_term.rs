@@ -0,0 +1,198 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::db::seed::*;
+use chrono::{Days, NaiveDate, Utc};
+use diesel_async::SimpleAsyncConnection;
+use diesel_models::enums::SubscriptionEventType;
+use diesel_models::subscription_events::SubscriptionEventRow;
+use meteroid::eventbus::create_eventbus_memory;
+use meteroid::workers::invoicing::draft_worker::draft_worker;
+use meteroid_store::compute::clients::usage::MockUsageClient;
+use meteroid_store::domain::enums::InvoiceStatusEnum;
+use meteroid_store::domain::{OrderByRequest, PaginationRequest, TenantContext};
+use meteroid_store::errors::StoreError;
+use meteroid_store::repositories::subscriptions::SubscriptionTermInterface;
+use meteroid_store::repositories::InvoiceInterface;
+use meteroid_store::Store;
+use secrecy::SecretString;
+use std::sync::Arc;
+use uuid::{uuid, Uuid};
+
+const TERM_SUBSCRIPTION_ID: Uuid = SUBSCRIPTION_SPORTIFY_ID1;
+const TERM_DRAFT_ID: Uuid = uuid!("0192f3a1-5c2e-7b41-9d6a-1f4e8c2b7a10");
+const TERM_ACTOR_ID: Uuid = uuid!("ae35bbb9-65da-477d-b856-7dbd87546441");
+const TERM_MRR_CENTS: i64 = 12000;
+
+#[tokio::test]
+async fn test_subscription_term_update() {
+    helpers::init::logging();
+    let (_, postgres_connection_string) = meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string.clone(),
+        SecretString::new("00000000000000000000000000000000".into()),
+        SecretString::new("secret".into()),
+        false,
+        create_eventbus_memory(),
+        Arc::new(MockUsageClient::noop()),
+    )
+    .expect("Could not create store");
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let invoiced = store
+        .list_invoices(
+            TENANT_ID,
+            None,
+            None,
+            None,
+            OrderByRequest::DateAsc,
+            PaginationRequest {
+                per_page: Some(100),
+                page: 0,
+            },
+        )
+        .await
+        .unwrap()
+        .items
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(TERM_SUBSCRIPTION_ID))
+        .unwrap();
+
+    store
+        .finalize_invoice(invoiced.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    // the validation is relative to today, so the finalized invoice covers the coming month
+    // and the next draft of the subscription is dated after it
+    let today = Utc::now().date_naive();
+    let invoiced_until = today + Days::new(30);
+    let draft_date = today + Days::new(60);
+
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "
+        update invoice
+        set line_items = (select jsonb_agg(l || jsonb_build_object('end_date', '{invoiced_until}'))
+                          from jsonb_array_elements(line_items) l)
+        where id = '{invoiced_id}';
+
+        create temp table term_draft as select * from invoice where id = '{invoiced_id}';
+        update term_draft
+        set id = '{TERM_DRAFT_ID}', status = 'DRAFT', invoice_number = 'TERM-DRAFT',
+            invoice_date = '{draft_date}', finalized_at = null;
+        insert into invoice select * from term_draft;
+        drop table term_draft;
+
+        update subscription set mrr_cents = {TERM_MRR_CENTS} where id = '{TERM_SUBSCRIPTION_ID}';
+        ",
+        invoiced_id = invoiced.id,
+    ))
+    .await
+    .unwrap();
+
+    // the term cannot end within a period that was already invoiced
+    let res = update_term(&store, Some(today + Days::new(10))).await;
+    let err = res.unwrap_err();
+    assert!(matches!(
+        err.current_context(),
+        StoreError::InvalidArgument(msg) if msg.contains("already invoiced")
+    ));
+    assert_eq!(
+        get_status(&store, TERM_DRAFT_ID).await,
+        InvoiceStatusEnum::Draft
+    );
+
+    let end_date = invoiced_until + Days::new(15);
+    let subscription = update_term(&store, Some(end_date)).await.unwrap();
+    assert_eq!(subscription.billing_end_date, Some(end_date));
+
+    // the draft after the new end is voided, the finalized invoice is kept
+    assert_eq!(
+        get_status(&store, TERM_DRAFT_ID).await,
+        InvoiceStatusEnum::Void
+    );
+    assert_eq!(
+        get_status(&store, invoiced.id).await,
+        InvoiceStatusEnum::Finalized
+    );
+
+    // the churn is planned at the new end
+    let events = SubscriptionEventRow::fetch_by_subscription_id_and_date(
+        &mut conn,
+        TERM_SUBSCRIPTION_ID,
+        end_date,
+    )
+    .await
+    .map_err(|e| e.error)
+    .unwrap();
+    assert_eq!(events.len(), 1);
+    assert!(matches!(
+        events[0].event_type,
+        SubscriptionEventType::Cancelled
+    ));
+    assert_eq!(events[0].mrr_delta, Some(-TERM_MRR_CENTS));
+
+    // an unchanged term is rejected
+    assert!(update_term(&store, Some(end_date)).await.is_err());
+
+    // removing the end reverts the planned churn
+    let subscription = update_term(&store, None).await.unwrap();
+    assert_eq!(subscription.billing_end_date, None);
+
+    let events = SubscriptionEventRow::fetch_by_subscription_id_and_date(
+        &mut conn,
+        TERM_SUBSCRIPTION_ID,
+        end_date,
+    )
+    .await
+    .map_err(|e| e.error)
+    .unwrap();
+    assert_eq!(events.len(), 2);
+    assert_eq!(
+        events
+            .iter()
+            .filter(|e| matches!(e.event_type, SubscriptionEventType::Reactivated))
+            .map(|e| e.mrr_delta)
+            .collect::<Vec<_>>(),
+        vec![Some(TERM_MRR_CENTS)]
+    );
+    assert_eq!(events.iter().map(|e| e.mrr_delta.unwrap()).sum::<i64>(), 0);
+}
+
+async fn update_term(
+    store: &Store,
+    billing_end_date: Option<NaiveDate>,
+) -> meteroid_store::StoreResult<meteroid_store::domain::Subscription> {
+    store
+        .update_subscription_term(
+            TERM_SUBSCRIPTION_ID,
+            billing_end_date,
+            TenantContext {
+                actor: TERM_ACTOR_ID,
+                tenant_id: TENANT_ID,
+            },
+        )
+        .await
+}
+
+async fn get_status(store: &Store, invoice_id: Uuid) -> InvoiceStatusEnum {
+    store
+        .find_invoice_by_id(TENANT_ID, invoice_id)
+        .await
+        .unwrap()
+        .invoice
+        .status
+}
+
+fn date(date_str: &str) -> NaiveDate {
+    NaiveDate::parse_from_str(date_str, "%Y-%m-%d").expect("Invalid date format")
+}
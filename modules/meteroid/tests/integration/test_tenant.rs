@@ -120,3 +120,166 @@ async fn test_tenants_basic() {
     // teardown
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
+
+#[tokio::test]
+async fn test_sandbox_tenant_catalog() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::PLANS).await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    // sandboxes are created from production tenants only
+    let not_production = clients
+        .tenants
+        .clone()
+        .create_sandbox_tenant(api::tenants::v1::CreateSandboxTenantRequest {
+            name: "Sandbox".to_string(),
+            copy_catalog: true,
+        })
+        .await;
+
+    assert!(not_production.is_err());
+
+    let production = clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                environment: Some(api::tenants::v1::TenantEnvironmentEnum::Production.into()),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .tenant
+        .unwrap();
+
+    assert_eq!(production.billing_timezone, "UTC");
+
+    let unknown_timezone = clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                billing_timezone: Some("Asia/Atlantis".to_string()),
+                ..Default::default()
+            }),
+        })
+        .await;
+
+    assert!(unknown_timezone.is_err());
+
+    let singapore = clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                billing_timezone: Some("Asia/Singapore".to_string()),
+                ..Default::default()
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .tenant
+        .unwrap();
+
+    assert_eq!(singapore.billing_timezone, "Asia/Singapore");
+
+    let grace_too_long = clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                finalization_grace_period_hours: Some(24 * 90),
+                ..Default::default()
+            }),
+        })
+        .await;
+
+    assert!(grace_too_long.is_err());
+
+    let created = clients
+        .tenants
+        .clone()
+        .create_sandbox_tenant(api::tenants::v1::CreateSandboxTenantRequest {
+            name: "Sandbox".to_string(),
+            copy_catalog: true,
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    let sandbox = created.tenant.unwrap();
+    let catalog = created.catalog.unwrap();
+
+    assert_eq!(
+        sandbox.environment(),
+        api::tenants::v1::TenantEnvironmentEnum::Sandbox
+    );
+    assert_eq!(sandbox.production_tenant_id, Some(production.id.clone()));
+    assert_eq!(sandbox.slug, "sandbox");
+    assert!(catalog.billable_metrics > 0);
+    assert!(catalog.plans > 0);
+    assert_eq!(catalog.plan_versions, 0);
+
+    let sandbox_clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        sandbox.slug.as_str(),
+    );
+
+    // the linked sandbox stays a sandbox
+    let env_update = sandbox_clients
+        .tenants
+        .clone()
+        .update_tenant(api::tenants::v1::UpdateTenantRequest {
+            data: Some(api::tenants::v1::TenantUpdate {
+                environment: Some(api::tenants::v1::TenantEnvironmentEnum::Production.into()),
+                ..Default::default()
+            }),
+        })
+        .await;
+
+    assert!(env_update.is_err());
+
+    // the catalog is already in production, the plans get a draft version
+    let promoted = sandbox_clients
+        .tenants
+        .clone()
+        .promote_sandbox_catalog(api::tenants::v1::PromoteSandboxCatalogRequest {})
+        .await
+        .unwrap()
+        .into_inner()
+        .catalog
+        .unwrap();
+
+    assert_eq!(promoted.billable_metrics, 0);
+    assert_eq!(promoted.plans, 0);
+    assert_eq!(promoted.plan_versions, catalog.plans);
+
+    // production tenants cannot be promoted
+    let not_sandbox = clients
+        .tenants
+        .clone()
+        .promote_sandbox_catalog(api::tenants::v1::PromoteSandboxCatalogRequest {})
+        .await;
+
+    assert!(not_sandbox.is_err());
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
@@ -0,0 +1,256 @@
+use crate::helpers;
+use crate::meteroid_it;
+use crate::meteroid_it::container::SeedLevel;
+use crate::meteroid_it::db::seed::CUSTOMER_UBER_ID;
+use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alert_condition::Measure;
+use meteroid_grpc::meteroid::api::usagealerts::v1::usage_alert_threshold::Threshold;
+use meteroid_grpc::meteroid::api::usagealerts::v1::{
+    ComparisonOperator, CreateUsageAlertRequest, CreateUsageAlertRuleRequest,
+    DeleteUsageAlertRequest, DeleteUsageAlertRuleRequest, GetUsageAlertRuleRequest,
+    ListUsageAlertRulesRequest, ListUsageAlertsRequest, UpdateUsageAlertRuleRequest,
+    UsageAlertCondition, UsageAlertRuleDefinition, UsageAlertRuleWindow, UsageAlertThreshold,
+};
+
+const METRIC_BANDWIDTH_ID: &str = "018c3453-1f11-76a8-8d69-f74921b2646d";
+
+#[tokio::test]
+async fn test_usage_alerts_basic() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::PLANS).await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    // create
+    let created = clients
+        .usage_alerts
+        .clone()
+        .create_usage_alert(CreateUsageAlertRequest {
+            customer_id: CUSTOMER_UBER_ID.to_string(),
+            billable_metric_id: METRIC_BANDWIDTH_ID.to_string(),
+            threshold: Some(UsageAlertThreshold {
+                threshold: Some(Threshold::CapacityPercent("80".to_string())),
+            }),
+            hysteresis_percent: None,
+            notify_email: Some("billing@uber.com".to_string()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .alert
+        .unwrap();
+
+    assert_eq!(created.customer_id, CUSTOMER_UBER_ID.to_string());
+    assert_eq!(created.hysteresis_percent, "10");
+    assert!(!created.triggered);
+
+    // invalid threshold
+    let res = clients
+        .usage_alerts
+        .clone()
+        .create_usage_alert(CreateUsageAlertRequest {
+            customer_id: CUSTOMER_UBER_ID.to_string(),
+            billable_metric_id: METRIC_BANDWIDTH_ID.to_string(),
+            threshold: Some(UsageAlertThreshold {
+                threshold: Some(Threshold::Absolute("-1".to_string())),
+            }),
+            hysteresis_percent: None,
+            notify_email: None,
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // list
+    let listed = clients
+        .usage_alerts
+        .clone()
+        .list_usage_alerts(ListUsageAlertsRequest {
+            customer_id: Some(CUSTOMER_UBER_ID.to_string()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .alerts;
+
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, created.id);
+
+    // delete
+    clients
+        .usage_alerts
+        .clone()
+        .delete_usage_alert(DeleteUsageAlertRequest {
+            id: created.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    let listed = clients
+        .usage_alerts
+        .clone()
+        .list_usage_alerts(ListUsageAlertsRequest { customer_id: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .alerts;
+
+    assert!(listed.is_empty());
+}
+
+#[tokio::test]
+async fn test_usage_alert_rules() {
+    // Generic setup
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::PLANS).await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let definition = UsageAlertRuleDefinition {
+        name: "Bandwidth spike".to_string(),
+        window: UsageAlertRuleWindow::RollingWeek.into(),
+        match_all: true,
+        conditions: vec![
+            UsageAlertCondition {
+                measure: Measure::Usage.into(),
+                operator: ComparisonOperator::GreaterThan.into(),
+                value: "1000".to_string(),
+            },
+            UsageAlertCondition {
+                measure: Measure::GrowthPercent.into(),
+                operator: ComparisonOperator::GreaterThanOrEqual.into(),
+                value: "50".to_string(),
+            },
+        ],
+        notify_webhook: true,
+        notify_email: None,
+    };
+
+    // create, for all the customers
+    let created = clients
+        .usage_alerts
+        .clone()
+        .create_usage_alert_rule(CreateUsageAlertRuleRequest {
+            billable_metric_id: METRIC_BANDWIDTH_ID.to_string(),
+            customer_id: None,
+            definition: Some(definition.clone()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .rule
+        .unwrap();
+
+    assert_eq!(created.customer_id, None);
+    assert_eq!(created.definition.as_ref().unwrap().conditions.len(), 2);
+
+    // invalid definition
+    let res = clients
+        .usage_alerts
+        .clone()
+        .create_usage_alert_rule(CreateUsageAlertRuleRequest {
+            billable_metric_id: METRIC_BANDWIDTH_ID.to_string(),
+            customer_id: Some(CUSTOMER_UBER_ID.to_string()),
+            definition: Some(UsageAlertRuleDefinition {
+                conditions: vec![],
+                ..definition.clone()
+            }),
+        })
+        .await;
+
+    assert_eq!(res.unwrap_err().code(), tonic::Code::InvalidArgument);
+
+    // update
+    let updated = clients
+        .usage_alerts
+        .clone()
+        .update_usage_alert_rule(UpdateUsageAlertRuleRequest {
+            id: created.id.clone(),
+            definition: Some(UsageAlertRuleDefinition {
+                match_all: false,
+                notify_email: Some("billing@acme.com".to_string()),
+                ..definition.clone()
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .rule
+        .unwrap();
+
+    let updated_definition = updated.definition.unwrap();
+    assert!(!updated_definition.match_all);
+    assert_eq!(
+        updated_definition.notify_email,
+        Some("billing@acme.com".to_string())
+    );
+    assert!(updated.updated_at.is_some());
+
+    // the rules of all customers apply to the customer
+    let listed = clients
+        .usage_alerts
+        .clone()
+        .list_usage_alert_rules(ListUsageAlertRulesRequest {
+            customer_id: Some(CUSTOMER_UBER_ID.to_string()),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .rules;
+
+    assert_eq!(listed.len(), 1);
+    assert_eq!(listed[0].id, created.id);
+
+    let fetched = clients
+        .usage_alerts
+        .clone()
+        .get_usage_alert_rule(GetUsageAlertRuleRequest {
+            id: created.id.clone(),
+        })
+        .await
+        .unwrap()
+        .into_inner();
+
+    assert!(fetched.states.is_empty());
+
+    // delete
+    clients
+        .usage_alerts
+        .clone()
+        .delete_usage_alert_rule(DeleteUsageAlertRuleRequest {
+            id: created.id.clone(),
+        })
+        .await
+        .unwrap();
+
+    let listed = clients
+        .usage_alerts
+        .clone()
+        .list_usage_alert_rules(ListUsageAlertRulesRequest { customer_id: None })
+        .await
+        .unwrap()
+        .into_inner()
+        .rules;
+
+    assert!(listed.is_empty());
+}
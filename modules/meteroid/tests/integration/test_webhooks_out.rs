@@ -7,7 +7,10 @@ use std::str::FromStr;
 use crate::helpers;
 use crate::meteroid_it;
 use crate::meteroid_it::container::SeedLevel;
-use crate::meteroid_it::db::seed::{CUSTOMER_UBER_ID, SUBSCRIPTION_SPORTIFY_ID1, TENANT_ID};
+use crate::meteroid_it::db::seed::{
+    CUSTOMER_COMODO_ID, CUSTOMER_SPORTIFY_ID, CUSTOMER_UBER_ID, SUBSCRIPTION_SPORTIFY_ID1,
+    TENANT_ID,
+};
 use meteroid_grpc::meteroid::api;
 
 use meteroid_grpc::meteroid::api::webhooks::out::v1::WebhookEventType;
@@ -44,6 +47,7 @@ async fn test_webhook_endpoint_out() {
             url: "https://example.com/".to_string(),
             description: Some("Test".to_string()),
             events_to_listen: events_to_listen.clone(),
+            batching: None,
         })
         .await
         .unwrap()
@@ -128,6 +132,7 @@ async fn test_webhook_out_handler() {
                 WebhookEventType::CustomerCreated as i32,
                 WebhookEventType::SubscriptionCreated as i32,
             ],
+            batching: None,
         })
         .await
         .unwrap()
@@ -146,6 +151,7 @@ async fn test_webhook_out_handler() {
                 WebhookEventType::CustomerCreated as i32,
                 WebhookEventType::SubscriptionCreated as i32,
             ],
+            batching: None,
         })
         .await
         .unwrap()
@@ -229,6 +235,134 @@ async fn test_webhook_out_handler() {
     meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
 }
 
+#[tokio::test]
+async fn test_webhook_out_batching() {
+    helpers::init::logging();
+    let (_postgres_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+    let setup =
+        meteroid_it::container::start_meteroid(postgres_connection_string, SeedLevel::PRODUCT)
+            .await;
+
+    let mut ok_server = mockito::Server::new_async().await;
+    let mut failing_server = mockito::Server::new_async().await;
+
+    let auth = meteroid_it::svc_auth::login(setup.channel.clone()).await;
+
+    let clients = meteroid_it::clients::AllClients::from_channel(
+        setup.channel.clone(),
+        auth.token.clone().as_str(),
+        "TESTORG",
+        "testslug",
+    );
+
+    let ok_endpoint = create_batched_endpoint(&clients, ok_server.url()).await;
+    let failing_endpoint = create_batched_endpoint(&clients, failing_server.url()).await;
+
+    let handler = WebhookHandler::new(
+        setup.store.clone(),
+        setup.config.secrets_crypt_key.clone(),
+        false,
+    );
+
+    let customer_created = |event_id: &str, customer_id| Event {
+        event_id: uuid::Uuid::from_str(event_id).unwrap(),
+        event_timestamp: DateTime::parse_from_rfc3339("2024-02-01T23:22:15Z")
+            .unwrap()
+            .to_utc(),
+        event_data: common_eventbus::EventData::CustomerCreated(
+            common_eventbus::TenantEventDataDetails {
+                tenant_id: TENANT_ID,
+                entity_id: customer_id,
+            },
+        ),
+        actor: None,
+    };
+
+    let event1 = customer_created("de88623b-a85b-48a6-8720-bc656a9107d1", CUSTOMER_UBER_ID);
+    let event2 = customer_created("de88623b-a85b-48a6-8720-bc656a9107d2", CUSTOMER_SPORTIFY_ID);
+    let event3 = customer_created("de88623b-a85b-48a6-8720-bc656a9107d3", CUSTOMER_COMODO_ID);
+
+    fn batch_mock(server: &mut mockito::Server, events: &[&Event], status: usize) -> mockito::Mock {
+        server
+            .mock("POST", "/")
+            .match_header("content-type", "application/json")
+            .match_header(
+                "webhook-signature",
+                mockito::Matcher::Regex(r"v1,.*".to_string()),
+            )
+            .match_body(mockito::Matcher::AllOf(
+                events
+                    .iter()
+                    .map(|e| mockito::Matcher::Regex(e.event_id.to_string()))
+                    .collect(),
+            ))
+            .with_status(status)
+            .expect(1)
+            .create()
+    }
+
+    // sent once the batch is full, the second endpoint fails
+    let ok_mock = batch_mock(&mut ok_server, &[&event1, &event2], 200);
+    let failing_mock = batch_mock(&mut failing_server, &[&event1, &event2], 400);
+
+    handler.handle(event1.clone()).await.unwrap();
+    handler.handle(event2.clone()).await.unwrap();
+
+    ok_mock.assert();
+    ok_mock.remove();
+    failing_mock.assert();
+    failing_mock.remove();
+
+    // the failed batch is sent again as soon as it is full, only to the failing endpoint
+    let retry_mock = batch_mock(&mut failing_server, &[&event1, &event2], 200);
+
+    handler.handle(event3.clone()).await.unwrap();
+
+    retry_mock.assert();
+    retry_mock.remove();
+
+    // the last event waits for the batch to be full or old enough
+    let ok_mock = batch_mock(&mut ok_server, &[&event3], 200);
+    let failing_mock = batch_mock(&mut failing_server, &[&event3], 200);
+
+    handler.flush_due_batches().await;
+    assert!(!ok_mock.matched());
+    assert!(!failing_mock.matched());
+
+    tokio::time::sleep(std::time::Duration::from_millis(1100)).await;
+    handler.flush_due_batches().await;
+
+    ok_mock.assert();
+    failing_mock.assert();
+
+    let ok_events = list_endpoint_events(&clients, ok_endpoint.id).await;
+    assert_eq!(
+        ok_events
+            .iter()
+            .map(|e| e.http_status_code)
+            .collect::<Vec<_>>(),
+        vec![Some(200); 3]
+    );
+    // one batch of 2 events then one of 1
+    assert_eq!(ok_events[0].batch_id, ok_events[1].batch_id);
+    assert_ne!(ok_events[1].batch_id, ok_events[2].batch_id);
+
+    let failing_events = list_endpoint_events(&clients, failing_endpoint.id).await;
+    assert_eq!(
+        failing_events
+            .iter()
+            .map(|e| e.http_status_code)
+            .collect::<Vec<_>>(),
+        vec![Some(400), Some(400), Some(200), Some(200), Some(200)]
+    );
+    // each attempt is logged
+    assert_ne!(failing_events[0].batch_id, failing_events[2].batch_id);
+
+    // teardown
+    meteroid_it::container::terminate_meteroid(setup.token, setup.join_handle).await
+}
+
 async fn test_webhook_handler(
     clients: &meteroid_it::clients::AllClients,
     endpoint_server1: &mut mockito::Server,
@@ -320,3 +454,44 @@ async fn test_webhook_handler(
     )
     .await;
 }
+
+async fn create_batched_endpoint(
+    clients: &meteroid_it::clients::AllClients,
+    url: String,
+) -> api::webhooks::out::v1::WebhookEndpoint {
+    clients
+        .webhooks_out
+        .clone()
+        .create_webhook_endpoint(api::webhooks::out::v1::CreateWebhookEndpointRequest {
+            url,
+            description: None,
+            events_to_listen: vec![WebhookEventType::CustomerCreated as i32],
+            batching: Some(api::webhooks::out::v1::WebhookBatching {
+                max_events: 2,
+                max_wait_seconds: 1,
+            }),
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .endpoint
+        .unwrap()
+}
+
+async fn list_endpoint_events(
+    clients: &meteroid_it::clients::AllClients,
+    endpoint_id: String,
+) -> Vec<api::webhooks::out::v1::WebhookEvent> {
+    clients
+        .webhooks_out
+        .clone()
+        .list_webhook_events(api::webhooks::out::v1::ListWebhookEventsRequest {
+            sort_by: api::webhooks::out::v1::list_webhook_events_request::SortBy::DateAsc as i32,
+            endpoint_id,
+            pagination: None,
+        })
+        .await
+        .unwrap()
+        .into_inner()
+        .events
+}
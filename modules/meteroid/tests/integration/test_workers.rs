@@ -1,15 +1,29 @@
-use chrono::NaiveDate;
+use chrono::{NaiveDate, NaiveDateTime};
 use std::collections::HashSet;
 use std::sync::Arc;
 
+use diesel_async::SimpleAsyncConnection;
 use meteroid::eventbus::create_eventbus_noop;
+use rust_decimal_macros::dec;
 use uuid::Uuid;
 
+use meteroid::workers::invoicing::billing_run_summary_worker::billing_run_summary_worker;
 use meteroid::workers::invoicing::draft_worker::draft_worker;
+use meteroid::workers::invoicing::pending_status_worker::pending_worker;
+use meteroid::workers::misc::trials_worker::trials_worker;
 use meteroid_store::compute::clients::usage::MockUsageClient;
-use meteroid_store::domain::enums::InvoiceStatusEnum;
+use meteroid_store::domain::billing_runs::BillingRunSummary;
+use meteroid_store::domain::enums::{
+    BillingPeriodEnum, InvoiceExternalStatusEnum, InvoiceStatusEnum, InvoiceType,
+};
+use meteroid_store::domain::invoice_edits::{
+    DraftInvoiceLine, DraftInvoiceUpdate, OneOffInvoiceNew,
+};
+use meteroid_store::domain::invoice_payments::InvoicePaymentNew;
 use meteroid_store::domain::{InvoiceWithCustomer, OrderByRequest, PaginationRequest};
-use meteroid_store::repositories::InvoiceInterface;
+use meteroid_store::repositories::billing_runs::BillingRunsInterface;
+use meteroid_store::repositories::invoice_payments::InvoicePaymentInterface;
+use meteroid_store::repositories::{InvoiceInterface, SubscriptionInterface};
 use meteroid_store::Store;
 
 use crate::helpers;
@@ -102,10 +116,806 @@ async fn test_draft_worker() {
     assert_eq!(invoices2, invoices);
 }
 
+#[tokio::test]
+async fn test_draft_worker_per_cadence() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let worker_run_date = date("2023-11-06");
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    // an annual platform fee on top of the monthly seats
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        r#"
+        update tenant set invoice_cadence_grouping = 'PER_CADENCE' where id = '{TENANT_ID}';
+
+        insert into subscription_component (id, name, subscription_id, price_component_id, product_item_id, period, fee)
+        values ('0192d7a4-3f5e-7c41-9d0e-1a2b3c4d5e6f', 'Platform fee', '{SUBSCRIPTION_SPORTIFY_ID1}', null, null,
+                'ANNUAL', '{{"Rate": {{"rate": "1200.00"}}}}');
+        "#
+    ))
+    .await
+    .unwrap();
+    drop(conn);
+
+    draft_worker(&store, worker_run_date).await.unwrap();
+
+    let invoices = list_invoices(&store).await;
+
+    // a monthly and an annual invoice for the mixed subscription, a single one for the others
+    assert_eq!(invoices.len(), 7);
+
+    let mut mixed: Vec<_> = invoices
+        .iter()
+        .map(|x| &x.invoice)
+        .filter(|i| i.subscription_id == Some(SUBSCRIPTION_SPORTIFY_ID1))
+        .map(|i| (i.billing_cadence.clone(), i.invoice_date))
+        .collect();
+    mixed.sort_by_key(|(_, invoice_date)| *invoice_date);
+
+    assert_eq!(
+        mixed,
+        vec![
+            (Some(BillingPeriodEnum::Monthly), date("2023-12-01")),
+            (Some(BillingPeriodEnum::Annual), date("2024-11-01")),
+        ]
+    );
+
+    let uber_annual = invoices
+        .iter()
+        .map(|x| &x.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_UBER_ID1))
+        .unwrap();
+    assert_eq!(uber_annual.billing_cadence, Some(BillingPeriodEnum::Annual));
+
+    // second run should not create new invoices
+    draft_worker(
+        &store,
+        worker_run_date
+            .checked_add_days(chrono::Days::new(1))
+            .unwrap(),
+    )
+    .await
+    .unwrap();
+
+    let invoices2 = list_invoices(&store).await;
+
+    assert_eq!(invoices2, invoices);
+}
+
+#[tokio::test]
+async fn test_void_and_reissue_invoice() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    // drafts cannot be voided
+    assert!(store
+        .void_invoice(draft.id, TENANT_ID, "duplicate".to_string(), false)
+        .await
+        .is_err());
+
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    let voided = store
+        .void_invoice(draft.id, TENANT_ID, "wrong quantity".to_string(), true)
+        .await
+        .unwrap();
+
+    assert_eq!(voided.invoice.invoice.status, InvoiceStatusEnum::Void);
+    assert_eq!(
+        voided.invoice.invoice.void_reason.as_deref(),
+        Some("wrong quantity")
+    );
+    assert!(voided.invoice.invoice.voided_at.is_some());
+
+    let replacement = voided.replacement.unwrap().invoice;
+    assert_eq!(replacement.status, InvoiceStatusEnum::Draft);
+    assert_eq!(replacement.replaces_invoice_id, Some(draft.id));
+    assert_eq!(replacement.subscription_id, draft.subscription_id);
+    assert_eq!(replacement.invoice_date, draft.invoice_date);
+    assert_ne!(replacement.local_id, draft.local_id);
+
+    // already voided
+    assert!(store
+        .void_invoice(draft.id, TENANT_ID, "wrong quantity".to_string(), false)
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_record_invoice_payments() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    let payment = |amount: i64| InvoicePaymentNew {
+        tenant_id: TENANT_ID,
+        invoice_id: draft.id,
+        amount,
+        paid_at: date("2023-12-05"),
+        reference: Some("TRF-1205".to_string()),
+        note: None,
+        created_by: Uuid::nil(),
+    };
+
+    // drafts cannot be paid
+    assert!(store.record_invoice_payment(payment(100)).await.is_err());
+
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    // collected outside of any provider
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update invoice set invoicing_provider = 'MANUAL', amount_due = 10000 where id = '{}';",
+        draft.id
+    ))
+    .await
+    .unwrap();
+
+    let partial = store.record_invoice_payment(payment(4000)).await.unwrap();
+    assert_eq!(partial.amount_due, 6000);
+    assert!(!partial.fully_paid());
+
+    let invoice = store
+        .find_invoice_by_id(TENANT_ID, draft.id)
+        .await
+        .unwrap()
+        .invoice;
+    assert_eq!(invoice.amount_due, 6000);
+    assert_ne!(
+        invoice.external_status,
+        Some(InvoiceExternalStatusEnum::Paid)
+    );
+
+    // beyond the amount due
+    assert!(store.record_invoice_payment(payment(6001)).await.is_err());
+
+    let settled = store.record_invoice_payment(payment(6000)).await.unwrap();
+    assert_eq!(settled.amount_due, 0);
+    assert!(settled.fully_paid());
+
+    let invoice = store
+        .find_invoice_by_id(TENANT_ID, draft.id)
+        .await
+        .unwrap()
+        .invoice;
+    assert_eq!(invoice.amount_due, 0);
+    assert_eq!(
+        invoice.external_status,
+        Some(InvoiceExternalStatusEnum::Paid)
+    );
+
+    let payments = store
+        .list_invoice_payments(TENANT_ID, draft.id)
+        .await
+        .unwrap();
+    assert_eq!(
+        payments.iter().map(|p| p.amount).collect::<Vec<_>>(),
+        vec![4000, 6000]
+    );
+
+    // nothing remains due
+    assert!(store.record_invoice_payment(payment(1)).await.is_err());
+}
+
+#[tokio::test]
+async fn test_update_draft_invoice() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    let actor = Uuid::now_v7();
+
+    // keeps the existing lines and adds one
+    let mut lines = draft
+        .line_items
+        .iter()
+        .map(|l| DraftInvoiceLine {
+            local_id: Some(l.local_id.clone()),
+            name: l.name.clone(),
+            description: l.description.clone(),
+            quantity: None,
+            unit_price: None,
+            start_date: None,
+            end_date: None,
+        })
+        .collect::<Vec<_>>();
+    lines.push(DraftInvoiceLine {
+        local_id: None,
+        name: "Onboarding".to_string(),
+        description: None,
+        quantity: Some(dec!(1)),
+        unit_price: Some(dec!(500)),
+        start_date: None,
+        end_date: None,
+    });
+
+    let edited = store
+        .update_draft_invoice(
+            TENANT_ID,
+            draft.id,
+            actor,
+            DraftInvoiceUpdate {
+                lines: Some(lines),
+                memo: Some("PO 1234".to_string()),
+                due_date: Some(draft.invoice_date + chrono::Duration::days(45)),
+            },
+        )
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(edited.line_items.len(), draft.line_items.len() + 1);
+    assert_eq!(edited.subtotal, draft.subtotal + 50000);
+    assert_eq!(edited.memo.as_deref(), Some("PO 1234"));
+    assert_eq!(edited.manually_edited_by, Some(actor));
+    assert!(edited.manually_edited_at.is_some());
+
+    // the refresh of the prices keeps the manual edits
+    let refreshed = store
+        .refresh_invoice_data(draft.id, TENANT_ID)
+        .await
+        .unwrap()
+        .invoice;
+    assert_eq!(refreshed.line_items, edited.line_items);
+    assert_eq!(refreshed.subtotal, edited.subtotal);
+
+    // a due date before the invoice date
+    assert!(store
+        .update_draft_invoice(
+            TENANT_ID,
+            draft.id,
+            actor,
+            DraftInvoiceUpdate {
+                due_date: Some(draft.invoice_date - chrono::Duration::days(1)),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err());
+
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    // no longer a draft
+    assert!(store
+        .update_draft_invoice(
+            TENANT_ID,
+            draft.id,
+            actor,
+            DraftInvoiceUpdate {
+                memo: Some("".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_create_one_off_invoice() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    let line = DraftInvoiceLine {
+        local_id: None,
+        name: "Implementation services".to_string(),
+        description: Some("10 days".to_string()),
+        quantity: Some(dec!(10)),
+        unit_price: Some(dec!(800)),
+        start_date: None,
+        end_date: None,
+    };
+
+    let new = OneOffInvoiceNew {
+        tenant_id: TENANT_ID,
+        customer_id: CUSTOMER_UBER_ID,
+        lines: vec![line.clone()],
+        invoice_date: None,
+        reference: Some("SOW-42".to_string()),
+        memo: None,
+        finalize: false,
+    };
+
+    // kept as a draft
+    let draft = store
+        .create_one_off_invoice(new.clone())
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(draft.status, InvoiceStatusEnum::Draft);
+    assert_eq!(draft.invoice_type, InvoiceType::OneOff);
+    assert_eq!(draft.subscription_id, None);
+    assert_eq!(draft.total, 800000);
+    assert_eq!(draft.invoice_number, "draft");
+
+    // the finalization keeps its lines
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    let finalized = store
+        .find_invoice_by_id(TENANT_ID, draft.id)
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(finalized.status, InvoiceStatusEnum::Finalized);
+    assert_eq!(finalized.line_items, draft.line_items);
+    assert_ne!(finalized.invoice_number, "draft");
+
+    // finalized right away, next in the numbering sequence
+    let finalized_now = store
+        .create_one_off_invoice(OneOffInvoiceNew {
+            finalize: true,
+            ..new.clone()
+        })
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(finalized_now.status, InvoiceStatusEnum::Finalized);
+    assert_ne!(finalized_now.invoice_number, finalized.invoice_number);
+
+    // a new line requires a price
+    assert!(store
+        .create_one_off_invoice(OneOffInvoiceNew {
+            lines: vec![DraftInvoiceLine {
+                quantity: None,
+                unit_price: None,
+                ..line
+            }],
+            ..new
+        })
+        .await
+        .is_err());
+}
+
+#[tokio::test]
+async fn test_zero_invoice_policy_suppress() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    apply_full_discount(&store, SUBSCRIPTION_COMODO_ID2, "SUPPRESS").await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    let suppressed = store
+        .find_invoice_by_id(TENANT_ID, draft.id)
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(suppressed.total, 0);
+    assert_eq!(suppressed.status, InvoiceStatusEnum::Void);
+    assert!(suppressed.suppressed_at.is_some());
+    assert_eq!(suppressed.invoice_number, "draft");
+
+    // the redemption is kept for the next invoice
+    assert!(coupon_last_applied_at(&store, SUBSCRIPTION_COMODO_ID2)
+        .await
+        .is_none());
+}
+
+#[tokio::test]
+async fn test_zero_invoice_policy_finalize_without_issuing() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    apply_full_discount(&store, SUBSCRIPTION_COMODO_ID2, "FINALIZE_WITHOUT_ISSUING").await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    store
+        .finalize_invoice(draft.id, TENANT_ID, None)
+        .await
+        .unwrap();
+
+    let finalized = store
+        .find_invoice_by_id(TENANT_ID, draft.id)
+        .await
+        .unwrap()
+        .invoice;
+
+    assert_eq!(finalized.total, 0);
+    assert_eq!(finalized.status, InvoiceStatusEnum::Finalized);
+    assert!(finalized.suppressed_at.is_none());
+    assert_ne!(finalized.invoice_number, "draft");
+    // not sent to the invoicing provider
+    assert!(finalized.issued);
+
+    assert!(coupon_last_applied_at(&store, SUBSCRIPTION_COMODO_ID2)
+        .await
+        .is_some());
+}
+
+#[tokio::test]
+async fn test_pending_worker_tenant_grace_period() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let draft = list_invoices(&store)
+        .await
+        .into_iter()
+        .map(|i| i.invoice)
+        .find(|i| i.subscription_id == Some(SUBSCRIPTION_COMODO_ID2))
+        .unwrap();
+
+    // beyond the 23 hours of the invoicing entity
+    let now = draft.invoice_date.and_hms_opt(6, 0, 0).unwrap() + chrono::Duration::days(1);
+    pending_worker(&store, now).await.unwrap();
+    let invoice = store.find_invoice_by_id(TENANT_ID, draft.id).await.unwrap();
+    assert_eq!(invoice.invoice.status, InvoiceStatusEnum::Draft);
+
+    // still within the 48 hours of the tenant
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update tenant set finalization_grace_period_hours = 48 where id = '{TENANT_ID}';"
+    ))
+    .await
+    .unwrap();
+    drop(conn);
+
+    pending_worker(&store, now).await.unwrap();
+    let invoice = store.find_invoice_by_id(TENANT_ID, draft.id).await.unwrap();
+    assert_eq!(invoice.invoice.status, InvoiceStatusEnum::Pending);
+}
+
+#[tokio::test]
+async fn test_trials_worker() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    // a free trial on the notion plan, a card required trial on the leetcode plan
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        r#"
+        update subscription set trial_start_date = '2023-10-21'
+        where id in ('{SUBSCRIPTION_SPORTIFY_ID1}', '{SUBSCRIPTION_COMODO_ID2}');
+
+        update plan_version set trial_mode = 'CARD_REQUIRED'
+        where id = (select plan_version_id from subscription where id = '{SUBSCRIPTION_COMODO_ID2}');
+        "#
+    ))
+    .await
+    .unwrap();
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    // not ended yet
+    trials_worker(&store, date("2023-11-03")).await.unwrap();
+
+    let free = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+    assert!(free.canceled_at.is_none());
+
+    trials_worker(&store, date("2023-11-06")).await.unwrap();
+
+    let free = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_SPORTIFY_ID1)
+        .await
+        .unwrap();
+    assert!(free.canceled_at.is_some());
+    assert!(free.activated_at.is_none());
+    assert_eq!(free.billing_end_date, Some(date("2023-11-04")));
+    assert_eq!(free.cancellation_reason.as_deref(), Some("trial expired"));
+
+    let free_invoices = list_invoices(&store)
+        .await
+        .into_iter()
+        .filter(|i| i.invoice.subscription_id == Some(SUBSCRIPTION_SPORTIFY_ID1))
+        .collect::<Vec<_>>();
+    assert!(!free_invoices.is_empty());
+    assert!(free_invoices
+        .iter()
+        .all(|i| i.invoice.status == InvoiceStatusEnum::Void));
+
+    let card_required = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_COMODO_ID2)
+        .await
+        .unwrap();
+    assert!(card_required.canceled_at.is_none());
+    assert!(card_required.activated_at.is_some());
+
+    // without trial, untouched
+    let no_trial = store
+        .get_subscription_details(TENANT_ID, SUBSCRIPTION_UBER_ID2)
+        .await
+        .unwrap();
+    assert!(no_trial.canceled_at.is_none());
+    assert!(no_trial.activated_at.is_none());
+}
+
+#[tokio::test]
+async fn test_billing_run_summary_worker() {
+    helpers::init::logging();
+    let (_pg_container, postgres_connection_string) =
+        meteroid_it::container::start_postgres().await;
+
+    let store = Store::new(
+        postgres_connection_string,
+        secrecy::SecretString::new("test-key".into()),
+        secrecy::SecretString::new("test-jwt-key".into()),
+        false,
+        create_eventbus_noop().await,
+        Arc::new(MockUsageClient::noop()),
+    )
+    .unwrap();
+
+    meteroid_it::container::populate_postgres(
+        &store.pool,
+        meteroid_it::container::SeedLevel::SUBSCRIPTIONS,
+    )
+    .await;
+
+    draft_worker(&store, date("2023-11-06")).await.unwrap();
+
+    // one of the drafts failed to be issued
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        r#"
+        update tenant set finance_email = 'finance@example.com' where id = '{TENANT_ID}';
+
+        update invoice set last_issue_attempt_at = now(), last_issue_error = 'card declined'
+        where subscription_id = '{SUBSCRIPTION_UBER_ID1}';
+        "#
+    ))
+    .await
+    .unwrap();
+    drop(conn);
+
+    // the run of the current hour, summarized during the next one
+    let next_hour = chrono::Utc::now().naive_utc() + chrono::TimeDelta::hours(1);
+
+    billing_run_summary_worker(&store, next_hour).await.unwrap();
+
+    let summaries = list_billing_run_summaries(&store).await;
+
+    assert_eq!(summaries.len(), 1);
+
+    let summary = &summaries[0];
+    assert_eq!(summary.invoices_created, 6);
+    assert_eq!(summary.invoices_finalized, 0);
+    assert_eq!(summary.invoices_issued, 0);
+    assert_eq!(summary.issue_failures, 1);
+    assert_eq!(summary.failures[0].reason, "card declined");
+    assert!(!summary.totals.is_empty());
+
+    // the period is only summarized once
+    billing_run_summary_worker(&store, next_hour).await.unwrap();
+
+    assert_eq!(list_billing_run_summaries(&store).await.len(), 1);
+}
+
 fn date(date_str: &str) -> NaiveDate {
     NaiveDate::parse_from_str(date_str, "%Y-%m-%d").expect("Invalid date format")
 }
 
+async fn list_billing_run_summaries(store: &Store) -> Vec<BillingRunSummary> {
+    store
+        .list_billing_run_summaries(
+            TENANT_ID,
+            PaginationRequest {
+                per_page: Some(100),
+                page: 0,
+            },
+        )
+        .await
+        .unwrap()
+        .items
+}
+
 async fn list_invoices(store: &Store) -> Vec<InvoiceWithCustomer> {
     store
         .list_invoices(
@@ -123,3 +933,32 @@ async fn list_invoices(store: &Store) -> Vec<InvoiceWithCustomer> {
         .unwrap()
         .items
 }
+
+// a 100% discount on every invoice of the subscription, under the given zero-amount invoice policy
+async fn apply_full_discount(store: &Store, subscription_id: Uuid, zero_invoice_policy: &str) {
+    let coupon_id = Uuid::now_v7();
+
+    let mut conn = store.pool.get().await.unwrap();
+    conn.batch_execute(&format!(
+        "update tenant set zero_invoice_policy = '{zero_invoice_policy}' where id = '{TENANT_ID}';
+         insert into coupon (id, code, description, tenant_id, discount, reusable, created_at, updated_at)
+         values ('{coupon_id}', 'FREE', 'free of charge', '{TENANT_ID}', '{{\"Percentage\": \"100\"}}', false, now(), now());
+         insert into applied_coupon (id, coupon_id, customer_id, subscription_id, is_active, created_at)
+         select '{}', '{coupon_id}', customer_id, id, true, now() from subscription where id = '{subscription_id}';",
+        Uuid::now_v7(),
+    ))
+    .await
+    .unwrap();
+}
+
+async fn coupon_last_applied_at(store: &Store, subscription_id: Uuid) -> Option<NaiveDateTime> {
+    store
+        .get_subscription_details(TENANT_ID, subscription_id)
+        .await
+        .unwrap()
+        .applied_coupons
+        .first()
+        .expect("an applied coupon")
+        .applied_coupon
+        .last_applied_at
+}